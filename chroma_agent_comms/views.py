@@ -503,7 +503,9 @@ def register(request, key):
     )
 
     with transaction.atomic():
-        ClientCertificate.objects.create(host=host, serial=certificate_serial)
+        ClientCertificate.objects.create(
+            host=host, serial=certificate_serial, not_after=Crypto().get_not_after(certificate_str)
+        )
 
     # TODO: document this return format
     return HttpResponse(
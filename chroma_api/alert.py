@@ -7,6 +7,7 @@ from collections import defaultdict
 from chroma_api.utils import SeverityResource, DateSerializer
 
 from django.contrib.contenttypes.models import ContentType
+from django.db import connection
 from chroma_core.models.alert import AlertState
 from chroma_core.models.alert import AlertStateBase
 from chroma_core.models.alert import AlertSubscription
@@ -191,6 +192,10 @@ class AlertResource(SeverityResource):
         enumerations=STR_TO_SEVERITY.keys(),
     )
 
+    escalations = fields.ListField(
+        readonly=True, help_text="Escalations sent for the alert by escalation policies, oldest first"
+    )
+
     def prepend_urls(self):
         return [
             url(
@@ -219,6 +224,19 @@ class AlertResource(SeverityResource):
     def dehydrate_message(self, bundle):
         return bundle.obj.message()
 
+    def dehydrate_escalations(self, bundle):
+        with connection.cursor() as cursor:
+            cursor.execute(
+                "SELECT policy_name, emails, escalated_at FROM alert_escalation "
+                "WHERE alert_id = %s ORDER BY escalated_at",
+                [bundle.obj.id],
+            )
+
+            return [
+                {"policy_name": name, "emails": emails, "escalated_at": escalated_at.isoformat()}
+                for name, emails, escalated_at in cursor.fetchall()
+            ]
+
     def dehydrate_affected(self, bundle):
         from chroma_api.urls import api
 
@@ -20,6 +20,7 @@ from chroma_api.chroma_model_resource import ChromaModelResource
 from chroma_core.models import Command
 from chroma_core.models import SchedulingError
 from chroma_core.models import StepResult
+from chroma_core.services.rpc import RpcError
 from chroma_api.validation_utils import validate
 
 
@@ -130,9 +131,16 @@ class CommandResource(ChromaModelResource):
         from chroma_core.services.job_scheduler.job_scheduler_client import JobSchedulerClient
 
         try:
-            command_id = JobSchedulerClient.command_run_jobs(bundle.data["jobs"], bundle.data["message"])
+            command_id = JobSchedulerClient.command_run_jobs(
+                bundle.data["jobs"], bundle.data["message"], priority="high"
+            )
         except SchedulingError as e:
             raise custom_response(self, request, http.HttpBadRequest, {"state": e.message})
+        except RpcError as e:
+            # Such as jobs of a class that needs approval
+            if e.remote_exception_type != "SchedulingError":
+                raise
+            raise custom_response(self, request, http.HttpBadRequest, {"state": e.description})
 
         bundle.obj = Command.objects.get(pk=command_id)
         return bundle
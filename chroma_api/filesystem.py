@@ -105,6 +105,23 @@ class FilesystemResource(ConfParamResource):
         validation = FilesystemValidation()
         always_return_data = True
 
+    def obj_update(self, bundle, **kwargs):
+        new_state = bundle.data.get("state")
+
+        # Starting and stopping orders the targets, which only the GraphQL mutations do
+        if new_state in ["available", "stopped"]:
+            fs = self.cached_obj_get(bundle, **self.remove_api_resource_names(kwargs))
+
+            if fs.state != new_state:
+                raise custom_response(
+                    self,
+                    bundle.request,
+                    http.HttpBadRequest,
+                    {"state": "Use the filesystem.start and filesystem.stop GraphQL mutations"},
+                )
+
+        return super(FilesystemResource, self).obj_update(bundle, **kwargs)
+
 
 class OstPoolResource(ChromaModelResource):
     osts = fields.ToManyField(
@@ -0,0 +1,64 @@
+# Copyright (c) 2020 DDN. All rights reserved.
+# Use of this source code is governed by a MIT-style
+# license that can be found in the LICENSE file.
+
+
+import time
+
+import settings
+
+import django.contrib.auth as auth
+
+LAST_ACTIVITY_KEY = "last_activity"
+
+# Requests with these methods are treated as background traffic
+# (polling, nginx auth subrequests) and do not count as user activity.
+PASSIVE_METHODS = ("GET", "HEAD", "OPTIONS")
+
+
+def now():
+    return int(time.time())
+
+
+def touch_session(request):
+    """Record user activity on the current session"""
+    request.session[LAST_ACTIVITY_KEY] = now()
+
+
+def idle_expires_in(request):
+    """
+    Seconds until the current session expires due to inactivity,
+    or None if the session is anonymous or the idle timeout is disabled.
+    """
+    if not settings.SESSION_IDLE_TIMEOUT or not request.user.is_authenticated():
+        return None
+
+    t = now()
+    last_activity = request.session.get(LAST_ACTIVITY_KEY, t)
+
+    return max(0, last_activity + settings.SESSION_IDLE_TIMEOUT - t)
+
+
+class SessionIdleTimeoutMiddleware(object):
+    """
+    Logs out sessions that have seen no user activity for
+    ``settings.SESSION_IDLE_TIMEOUT`` seconds.
+
+    Only state changing requests count as activity, so
+    a client that merely polls does not keep a session alive.
+    Requests authenticated with an API key are not session based and are left alone.
+    """
+
+    def __init__(self, get_response):
+        self.get_response = get_response
+
+    def __call__(self, request):
+        if settings.SESSION_IDLE_TIMEOUT and request.user.is_authenticated():
+            if LAST_ACTIVITY_KEY not in request.session:
+                touch_session(request)
+            elif idle_expires_in(request) == 0:
+                auth.logout(request)
+            elif request.method not in PASSIVE_METHODS:
+                touch_session(request)
+
+        return self.get_response(request)
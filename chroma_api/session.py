@@ -10,6 +10,7 @@ from collections import defaultdict
 import django.contrib.auth as auth
 
 from chroma_api.authentication import CsrfAuthentication, AnonymousAuthentication
+from chroma_api.middleware import idle_expires_in, touch_session
 from chroma_api.validation_utils import validate
 from tastypie.authorization import Authorization, ReadOnlyAuthorization
 from tastypie.resources import Resource
@@ -20,8 +21,9 @@ from tastypie.validation import Validation
 
 
 class Session:
-    def __init__(self, user=None):
+    def __init__(self, user=None, idle_expires_in=None):
         self.user = user
+        self.idle_expires_in = idle_expires_in
         if settings.ALLOW_ANONYMOUS_READ:
             self.read_enabled = True
         else:
@@ -66,6 +68,7 @@ class SessionResource(Resource):
     values (see `Access control <#access-control>`_)
 
     Authenticate a session by using POST to send credentials. Use DELETE to log out from a session.
+    Use PUT to mark the session as active and reset its idle timeout.
     """
 
     user = fields.ToOneField("chroma_api.user.UserResource", "user", full=True, null=True, help_text="A user object")
@@ -75,6 +78,12 @@ class SessionResource(Resource):
         on other API resources.  Always true for authenticated users, depends on \
         settings for anonymous users.",
     )
+    idle_expires_in = fields.IntegerField(
+        attribute="idle_expires_in",
+        null=True,
+        help_text="Seconds until the session is logged out due to inactivity. \
+        ``null`` for anonymous sessions or if the idle timeout is disabled.",
+    )
 
     class Meta:
         object_class = Session
@@ -87,7 +96,7 @@ class SessionResource(Resource):
         # (and access to DELETE is harmless because it implicitly refers
         # only to the session of the caller)
         authorization = Authorization()
-        list_allowed_methods = ["get", "post", "delete"]
+        list_allowed_methods = ["get", "post", "put", "delete"]
         detail_allowed_methods = []
         resource_name = "session"
         validation = SessionValidation()
@@ -109,11 +118,20 @@ class SessionResource(Resource):
             raise ImmediateHttpResponse(response=resp)
 
         auth.login(request, user)
+        touch_session(request)
 
     def delete_list(self, request=None, **kwargs):
         """Log out this session"""
         auth.logout(request)
 
+    def put_list(self, request=None, **kwargs):
+        """Keep this session alive.
+
+        The activity itself is recorded by ``SessionIdleTimeoutMiddleware``,
+        respond with the refreshed session.
+        """
+        return self.get_list(request, **kwargs)
+
     def get_list(self, request=None, **kwargs):
         """Dictionary of session objects (esp. any logged in user).
 
@@ -134,7 +152,7 @@ class SessionResource(Resource):
         if not user.is_authenticated():
             # Anonymous user
             user = None
-        bundle = self.build_bundle(obj=Session(user), request=request)
+        bundle = self.build_bundle(obj=Session(user, idle_expires_in(request)), request=request)
         bundle = self.full_dehydrate(bundle)
         return self.create_response(request, bundle)
 
@@ -209,4 +227,9 @@ class AuthResource(Resource):
 
         bundle = self.build_bundle(obj=Auth(user), request=request)
         bundle = self.full_dehydrate(bundle)
-        return self.create_response(request, bundle)
+        response = self.create_response(request, bundle)
+
+        # Forwarded by the proxy so backend services know which user made the request
+        response["X-User-Id"] = str(user.id)
+
+        return response
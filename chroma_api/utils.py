@@ -209,7 +209,7 @@ class StatefulModelResource(CustomModelResource):
                 raise custom_response(self, request, http.HttpResponse, report)
             else:
                 try:
-                    command = Command.set_state([(stateful_object, new_state)])
+                    command = Command.set_state([(stateful_object, new_state)], priority="high")
                 except SchedulingError as e:
                     raise custom_response(self, request, http.HttpBadRequest, {"state": e.message})
 
@@ -224,9 +224,9 @@ class StatefulModelResource(CustomModelResource):
         obj = self.obj_get(bundle, **kwargs)
         try:
             if obj.immutable_state and "forgotten" in obj.states:
-                command = Command.set_state([(obj, "forgotten")])
+                command = Command.set_state([(obj, "forgotten")], priority="high")
             else:
-                command = Command.set_state([(obj, "removed")])
+                command = Command.set_state([(obj, "removed")], priority="high")
         except SchedulingError as e:
             raise custom_response(self, bundle.request, http.HttpBadRequest, {"__all__": e.message})
         raise custom_response(self, bundle.request, http.HttpAccepted, {"command": dehydrate_command(command)})
@@ -283,7 +283,9 @@ class ConfParamResource(StatefulModelResource):
             # kick off a command to apply the changes to the filesystem
             if mgs_id:
                 command_id = JobSchedulerClient.command_run_jobs(
-                    [{"class_name": "ApplyConfParams", "args": {"mgs_id": mgs_id}}], "Updating configuration parameters"
+                    [{"class_name": "ApplyConfParams", "args": {"mgs_id": mgs_id}}],
+                    "Updating configuration parameters",
+                    priority="high",
                 )
 
                 raise custom_response(
@@ -46,23 +46,46 @@ def graphql_query(query, variables={}):
 
 
 def get_targets(**kwargs):
+    """
+    Fetch all targets matching kwargs, a page at a time as the api caps how many are returned at once
+    """
     query = """
         query Targets($limit: Int, $offset: Int, $dir: SortDir, $fsname: String, $exclude_unmounted: Boolean) {
           targets(limit: $limit, offset: $offset, dir: $dir, fsName: $fsname, excludeUnmounted: $exclude_unmounted) {
-            id
-            state
-            name
-            dev_path: devPath
-            active_host_id: activeHostId
-            host_ids: hostIds
-            filesystems
-            uuid
-            mount_path: mountPath
+            data {
+              id
+              state
+              name
+              dev_path: devPath
+              active_host_id: activeHostId
+              host_ids: hostIds
+              filesystems
+              uuid
+              mount_path: mountPath
+            }
+            meta {
+              limit
+              total_count: totalCount
+              is_estimate: isEstimate
+            }
           }
         }
     """
 
-    return graphql_query(query, variables=kwargs)["targets"]
+    xs = []
+
+    while True:
+        page = graphql_query(query, variables=dict(kwargs, offset=len(xs)))["targets"]
+        meta = page["meta"]
+
+        xs.extend(page["data"])
+
+        # An estimated total may be short of the real one, so read until a page comes back short
+        if len(page["data"]) < meta["limit"]:
+            return xs
+
+        if not meta["is_estimate"] and len(xs) >= meta["total_count"]:
+            return xs
 
 
 def get_host_targets(host_id):
@@ -89,3 +112,31 @@ def get_client_mount_source(**kwargs):
     """
 
     return graphql_query(query, variables=kwargs)["clientMountSource"]
+
+
+def get_compatibility_report(**kwargs):
+    query = """
+        query CompatibilityReport($fs_name: String!) {
+          compatibilityReport(fsName: $fs_name) {
+            supported
+            issues
+          }
+        }
+    """
+
+    return graphql_query(query, variables=kwargs)["compatibilityReport"]
+
+
+def get_capacity_forecast(**kwargs):
+    query = """
+        query CapacityForecast($fs_name: String!) {
+          capacityForecast(fsName: $fs_name) {
+            bytes_total: bytesTotal
+            bytes_used: bytesUsed
+            growth_bytes_per_day: growthBytesPerDay
+            fill_date: fillDate
+          }
+        }
+    """
+
+    return graphql_query(query, variables=kwargs)["capacityForecast"]
@@ -93,12 +93,42 @@ def parse_stratagem_results_to_influx(measurement, fs_name, stratagem_results_js
     return pipe(
         [],
         partial(reduce, lambda out, cur: out + [(cur.get("name"), cur.get("counters"))], group_counters),
-        partial(filter, lambda xs: xs[0] not in ["warn_fids", "purge_fids", "filesync", "cloudsync"]),
+        partial(filter, lambda xs: xs[0] not in ["warn_fids", "purge_fids", "filesync", "cloudsync", "rebalance"]),
         partial(map, lambda xs, parse_fns=parse_fns: parse_fns[xs[0]](xs[1])),
         partial(flatten),
     )
 
 
+def count_scanned_entries(stratagem_results_json):
+    """
+    Each entry on the device is counted once by every rule group, falling through
+    to the group's "Other" counter when no rule matches, so the largest group total
+    is the number of entries scanned.
+    """
+    group_counters = stratagem_results_json.get("group_counters") or []
+
+    totals = [sum(counter.get("count", 0) for counter in group.get("counters", [])) for group in group_counters]
+
+    return max(totals or [0])
+
+
+def record_scan_progress(job_id, phase, entries_scanned=None):
+    from django.db import connection
+
+    with connection.cursor() as cursor:
+        cursor.execute(
+            """
+            INSERT INTO stratagem_scan_progress (job_id, phase, entries_scanned)
+            VALUES (%s, %s, %s)
+            ON CONFLICT (job_id) DO UPDATE
+            SET phase = EXCLUDED.phase,
+                entries_scanned = COALESCE(EXCLUDED.entries_scanned, stratagem_scan_progress.entries_scanned),
+                updated_at = now()
+            """,
+            [job_id, phase, entries_scanned],
+        )
+
+
 def clear_scan_results(clear_measurement_query):
     response = requests.post(
         "{}/query".format(settings.INFLUXDB_PROXY_PASS),
@@ -52,10 +52,24 @@ class Command(BaseCommand):
             "TIMER_SERVER_FQDN": settings.TIMER_SERVER_FQDN,
             "TIMER_PROXY_PASS": settings.TIMER_PROXY_PASS,
             "ALLOW_ANONYMOUS_READ": json.dumps(settings.ALLOW_ANONYMOUS_READ),
+            "SESSION_IDLE_TIMEOUT": settings.SESSION_IDLE_TIMEOUT,
+            "CAPACITY_ALERT_DAYS": settings.CAPACITY_ALERT_DAYS,
+            "CERTIFICATE_ALERT_DAYS": settings.CERTIFICATE_ALERT_DAYS,
+            "HEARTBEAT_ALERT_MISSED": settings.HEARTBEAT_ALERT_MISSED,
+            "STONITH_TEST_INTERVAL_HOURS": settings.STONITH_TEST_INTERVAL_HOURS,
+            "STANDBY_ALERT_LAG_MB": settings.STANDBY_ALERT_LAG_MB,
+            "GRAPHQL_MAX_PAGE_SIZE": settings.GRAPHQL_MAX_PAGE_SIZE,
+            "GRAPHQL_FAN_OUT_LIMIT": settings.GRAPHQL_FAN_OUT_LIMIT,
+            "GRAPHQL_EXACT_COUNT_THRESHOLD": settings.GRAPHQL_EXACT_COUNT_THRESHOLD,
+            "DEFERRED_QUERY_TTL_HOURS": settings.DEFERRED_QUERY_TTL_HOURS,
+            "SNAPSHOT_MIN_MDT_FREE_PERCENT": settings.SNAPSHOT_MIN_MDT_FREE_PERCENT,
+            "OTLP_ENDPOINT": settings.OTLP_ENDPOINT,
+            "OTLP_SAMPLE_RATIO": settings.OTLP_SAMPLE_RATIO,
             "BUILD": settings.BUILD,
             "IS_RELEASE": json.dumps(settings.IS_RELEASE),
             "LOG_PATH": settings.LOG_PATH,
             "SERVER_HTTP_URL": settings.SERVER_HTTP_URL,
+            "FEDERATION_SITE_NAME": settings.FEDERATION_SITE_NAME,
             "SITE_ROOT": settings.SITE_ROOT,
             "VERSION": settings.VERSION,
             "API_USER": API_USER,
@@ -0,0 +1,40 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2020-12-23 14:02
+from __future__ import unicode_literals
+
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0032_forgetlustreclientjob"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="DistributeSskKeyJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                (
+                    "filesystem",
+                    models.ForeignKey(on_delete=django.db.models.deletion.CASCADE, to="chroma_core.ManagedFilesystem"),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+    ]
@@ -0,0 +1,24 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2020-12-29 10:12
+from __future__ import unicode_literals
+
+from django.db import migrations
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0033_distributesskkeyjob"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="ManualSnapshotsCrowdingAlert",
+            fields=[],
+            options={
+                "proxy": True,
+                "indexes": [],
+            },
+            bases=("chroma_core.alertstatebase",),
+        ),
+    ]
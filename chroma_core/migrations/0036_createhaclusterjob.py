@@ -0,0 +1,61 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-04 10:12
+from __future__ import unicode_literals
+
+import django.contrib.postgres.fields.jsonb
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0035_filesystemcheckfailedalert"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="CreateHaClusterJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("cluster_name", models.CharField(max_length=64)),
+                ("mcast_port", models.IntegerField()),
+                (
+                    "nodes",
+                    django.contrib.postgres.fields.jsonb.JSONField(
+                        help_text="Ring addresses and fence device of each host, keyed by host_id"
+                    ),
+                ),
+                (
+                    "primary_host",
+                    models.ForeignKey(
+                        on_delete=django.db.models.deletion.CASCADE,
+                        related_name="+",
+                        to="chroma_core.ManagedHost",
+                    ),
+                ),
+                (
+                    "secondary_host",
+                    models.ForeignKey(
+                        on_delete=django.db.models.deletion.CASCADE,
+                        related_name="+",
+                        to="chroma_core.ManagedHost",
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+    ]
@@ -0,0 +1,51 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-05 14:10
+from __future__ import unicode_literals
+
+import django.contrib.postgres.fields.jsonb
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0036_createhaclusterjob"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="ApplyTuningProfileJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("profile_name", models.CharField(max_length=64)),
+                (
+                    "settings",
+                    django.contrib.postgres.fields.jsonb.JSONField(
+                        help_text="The settings of the profile at the time it was applied"
+                    ),
+                ),
+                (
+                    "host",
+                    models.ForeignKey(
+                        on_delete=django.db.models.deletion.CASCADE,
+                        to="chroma_core.ManagedHost",
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+    ]
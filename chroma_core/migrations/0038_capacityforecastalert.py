@@ -0,0 +1,24 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-06 09:20
+from __future__ import unicode_literals
+
+from django.db import migrations
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0037_applytuningprofilejob"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="CapacityForecastAlert",
+            fields=[],
+            options={
+                "proxy": True,
+                "indexes": [],
+            },
+            bases=("chroma_core.alertstatebase",),
+        ),
+    ]
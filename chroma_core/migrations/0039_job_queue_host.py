@@ -0,0 +1,28 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-08 11:42
+from __future__ import unicode_literals
+
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0038_capacityforecastalert"),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name="job",
+            name="queue_host",
+            field=models.ForeignKey(
+                blank=True,
+                help_text=b"The host whose queue this job is in. Jobs in a host queue run one at a time in id order            and are resumed, rather than cancelled, when the job scheduler restarts",
+                null=True,
+                on_delete=django.db.models.deletion.CASCADE,
+                related_name="+",
+                to="chroma_core.ManagedHost",
+            ),
+        ),
+    ]
@@ -0,0 +1,60 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-11 09:52
+from __future__ import unicode_literals
+
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0039_job_queue_host"),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name="clientcertificate",
+            name="not_after",
+            field=models.DateTimeField(
+                help_text=b"When the certificate expires, null for certificates issued before this was recorded",
+                null=True,
+            ),
+        ),
+        migrations.CreateModel(
+            name="RotateAgentCertificateJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                (
+                    "host",
+                    models.ForeignKey(
+                        on_delete=django.db.models.deletion.CASCADE,
+                        to="chroma_core.ManagedHost",
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="CertificateExpiryAlert",
+            fields=[],
+            options={
+                "proxy": True,
+                "indexes": [],
+            },
+            bases=("chroma_core.alertstatebase",),
+        ),
+    ]
@@ -0,0 +1,24 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-13 09:45
+from __future__ import unicode_literals
+
+from django.db import migrations
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0040_certificate_rotation"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="AgentHeartbeatAlert",
+            fields=[],
+            options={
+                "proxy": True,
+                "indexes": [],
+            },
+            bases=("chroma_core.alertstatebase",),
+        ),
+    ]
@@ -0,0 +1,47 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-18 10:24
+from __future__ import unicode_literals
+
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0042_multipath_degraded_alert"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="SetDefaultDirStripeJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("filesystem", models.CharField(max_length=8)),
+                ("stripe_count", models.IntegerField()),
+                ("stripe_offset", models.IntegerField()),
+                ("hash_type", models.CharField(blank=True, max_length=32, null=True)),
+                (
+                    "host",
+                    models.ForeignKey(
+                        on_delete=django.db.models.deletion.CASCADE,
+                        to="chroma_core.ManagedHost",
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+    ]
@@ -0,0 +1,68 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-25 10:45
+from __future__ import unicode_literals
+
+import django.contrib.postgres.fields.jsonb
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0045_setdefaultstripejob"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="SetStonithDeviceJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("device_id", models.CharField(max_length=64)),
+                ("agent", models.CharField(max_length=64)),
+                (
+                    "host_list",
+                    django.contrib.postgres.fields.jsonb.JSONField(
+                        help_text="The corosync node names the device can fence"
+                    ),
+                ),
+                (
+                    "params",
+                    django.contrib.postgres.fields.jsonb.JSONField(
+                        help_text="Instance attributes of the device as key=value"
+                    ),
+                ),
+                (
+                    "host",
+                    models.ForeignKey(
+                        on_delete=django.db.models.deletion.CASCADE,
+                        related_name="+",
+                        to="chroma_core.ManagedHost",
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="StonithTestFailedAlert",
+            fields=[],
+            options={
+                "proxy": True,
+                "indexes": [],
+            },
+            bases=("chroma_core.alertstatebase",),
+        ),
+    ]
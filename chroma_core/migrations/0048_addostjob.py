@@ -0,0 +1,60 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-29 10:41
+from __future__ import unicode_literals
+
+import django.contrib.postgres.fields.jsonb
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0047_addmdtjob"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="AddOstJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("filesystem", models.CharField(max_length=8)),
+                ("index", models.IntegerField()),
+                ("device", models.CharField(max_length=512)),
+                (
+                    "mgs_nids",
+                    django.contrib.postgres.fields.jsonb.JSONField(
+                        help_text="The NIDs of each server of the MGS, comma separated"
+                    ),
+                ),
+                ("node", models.CharField(help_text="The pacemaker node name of the host", max_length=255)),
+                (
+                    "failover_fqdns",
+                    django.contrib.postgres.fields.jsonb.JSONField(
+                        help_text="The other servers of the cluster of the host"
+                    ),
+                ),
+                (
+                    "host",
+                    models.ForeignKey(
+                        on_delete=django.db.models.deletion.CASCADE,
+                        to="chroma_core.ManagedHost",
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+    ]
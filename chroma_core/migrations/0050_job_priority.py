@@ -0,0 +1,23 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-02-08 10:12
+from __future__ import unicode_literals
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0049_clusterconfigdriftalert"),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name="job",
+            name="priority",
+            field=models.SmallIntegerField(
+                default=1,
+                help_text=b"0 (low), 1 (normal) or 2 (high). Ready jobs start highest priority first, and jobs in a            host queue run ahead of queued jobs of a lower priority",
+            ),
+        ),
+    ]
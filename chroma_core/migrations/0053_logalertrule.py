@@ -0,0 +1,43 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-02-17 10:02
+from __future__ import unicode_literals
+
+import datetime
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0052_resynchostjob"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="LogAlertRule",
+            fields=[
+                ("id", models.AutoField(auto_created=True, primary_key=True, serialize=False, verbose_name="ID")),
+                ("name", models.CharField(max_length=64, unique=True)),
+                ("enabled", models.BooleanField(default=True)),
+                ("message_pattern", models.CharField(max_length=1024)),
+                ("fqdn_pattern", models.CharField(blank=True, max_length=255, null=True)),
+                ("tag_pattern", models.CharField(blank=True, max_length=255, null=True)),
+                ("severity", models.SmallIntegerField(default=7)),
+                ("min_matches", models.IntegerField(default=1)),
+                ("within", models.DurationField(default=datetime.timedelta(0, 300))),
+                ("alert_severity", models.IntegerField(default=30)),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+        ),
+        migrations.CreateModel(
+            name="LogPatternAlert",
+            fields=[],
+            options={
+                "proxy": True,
+                "indexes": [],
+            },
+            bases=("chroma_core.alertstatebase",),
+        ),
+    ]
@@ -38,3 +38,8 @@ from .ticket import *
 from .devices import *
 from .task import *
 from .sfa import *
+from .security import *
+from .tuning import *
+from .certificate import *
+from .standby import *
+from .log_alert_rule import *
@@ -0,0 +1,110 @@
+# Copyright (c) 2020 DDN. All rights reserved.
+# Use of this source code is governed by a MIT-style
+# license that can be found in the LICENSE file.
+
+import logging
+import time
+
+from django.db import models
+from django.db.models import CASCADE
+from chroma_core.lib.job import Step
+from chroma_core.models.alert import AlertStateBase
+from chroma_core.models.client_certificate import ClientCertificate
+from chroma_core.models.jobs import Job, StateLock
+
+
+class RotateAgentCertificateStep(Step):
+    """Issue a host a new certificate from the manager CA and swap it in.
+
+    Both certificates are accepted until the agent is seen using the new one,
+    only then is the old one revoked.
+    """
+
+    # How long the agent has to come back after restarting with the new certificate
+    VERIFY_TIMEOUT = 120
+    VERIFY_INTERVAL = 5
+
+    def run(self, kwargs):
+        from chroma_core.services.crypto import Crypto
+        from chroma_core.services.http_agent import HttpAgentRpc
+        from chroma_core.services.job_scheduler.agent_rpc import AgentException
+
+        host = kwargs["host"]
+        crypto = Crypto()
+
+        csr = self.invoke_rust_agent_expect_result(host.fqdn, "certificate_csr", host.fqdn)
+
+        # Never sign a certificate for anyone but the host itself
+        common_name = crypto.get_common_name(csr)
+        if common_name != host.fqdn:
+            raise RuntimeError("%s requested a certificate for %s" % (host.fqdn, common_name))
+
+        certificate = crypto.sign(csr)
+        serial = crypto.get_serial(certificate)
+
+        ClientCertificate.objects.create(host=host, serial=serial, not_after=crypto.get_not_after(certificate))
+        HttpAgentRpc().allow_certificate(host.fqdn, serial)
+
+        self.log("Issued certificate %s to %s" % (serial, host.fqdn))
+
+        self.invoke_rust_agent_expect_result(host.fqdn, "certificate_install", certificate)
+
+        # The agent restarts to load the new certificate, wait for it to answer with it
+        deadline = time.time() + self.VERIFY_TIMEOUT
+        while True:
+            self._cancel_event.wait(self.VERIFY_INTERVAL)
+
+            try:
+                in_use = self.invoke_rust_agent_expect_result(host.fqdn, "certificate_serial", None)
+            except AgentException:
+                in_use = None
+
+            if in_use is not None and in_use.upper() == serial.upper():
+                break
+
+            if time.time() > deadline:
+                raise RuntimeError(
+                    "%s is not using certificate %s after %ss, its previous certificate is still valid"
+                    % (host.fqdn, serial, self.VERIFY_TIMEOUT)
+                )
+
+        for old in ClientCertificate.objects.filter(host=host, revoked=False).exclude(serial=serial):
+            HttpAgentRpc().revoke_certificate(old.serial)
+            old.revoked = True
+            old.save()
+
+            self.log("Revoked certificate %s of %s" % (old.serial, host.fqdn))
+
+
+class RotateAgentCertificateJob(Job):
+    host = models.ForeignKey("ManagedHost", on_delete=CASCADE)
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls):
+        return "Issue a server a new agent certificate from the manager CA and revoke its old one."
+
+    def description(self):
+        return "Rotate the agent certificate of {}".format(self.host.fqdn)
+
+    def create_locks(self):
+        return [StateLock(job=self, locked_item=self.host, write=False)]
+
+    def get_steps(self):
+        return [(RotateAgentCertificateStep, {"host": self.host})]
+
+
+class CertificateExpiryAlert(AlertStateBase):
+    # Raised by iml-api when the agent certificate of a host expires within
+    # CERTIFICATE_ALERT_DAYS, and lowered once it has been rotated.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "The agent certificate of %s expires soon" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
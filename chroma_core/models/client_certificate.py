@@ -12,6 +12,9 @@ class ClientCertificate(models.Model):
     host = models.ForeignKey("ManagedHost", on_delete=CASCADE)
     serial = models.CharField(max_length=40)
     revoked = models.BooleanField(default=False)
+    not_after = models.DateTimeField(
+        null=True, help_text="When the certificate expires, null for certificates issued before this was recorded"
+    )
 
     class Meta:
         app_label = "chroma_core"
@@ -11,11 +11,13 @@ from collections import defaultdict
 from django.utils.timezone import now
 from django.db import models
 from django.db.models import CASCADE
+from django.contrib.postgres import fields
 from toolz import dicttoolz
 
 from chroma_core.lib.job import Step, DependAll
 from chroma_core.models import corosync_common
 from chroma_core.models import CorosyncConfiguration
+from chroma_core.models.jobs import Job, StateLock
 from chroma_core.services import log_register
 from chroma_core.services.job_scheduler import job_scheduler_notify
 
@@ -406,3 +408,196 @@ class ConfigureCorosync2Job(corosync_common.ConfigureCorosyncJob):
 
         for h in HaCluster.host_peers(self.corosync_configuration.host):
             job_scheduler_notify.notify(h.corosync_configuration, now(), {"mcast_port": self.mcast_port})
+
+
+class ConfigureCorosyncClusterStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"], "configure_corosync_cluster", [kwargs["config"], kwargs["node"]]
+        )
+
+
+class StartClusterStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        for unit in ["corosync.service", "pacemaker.service"]:
+            self.invoke_rust_agent_expect_result(kwargs["fqdn"], "enable_unit", unit)
+
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "pcs", ["cluster", "start", "--wait=300"])
+
+
+class ConfigureStonithStep(Step):
+    def run(self, kwargs):
+        fqdn = kwargs["fqdn"]
+
+        for node in kwargs["nodes"]:
+            name = "st-{}".format(node["name"])
+
+            self.invoke_rust_agent_expect_result(
+                fqdn,
+                "pcs",
+                ["stonith", "create", name, node["fence_agent"], "pcmk_host_list={}".format(node["name"])]
+                + node["fence_params"],
+            )
+
+            # A node is never fenced by a device running on itself.
+            self.invoke_rust_agent_expect_result(fqdn, "pcs", ["constraint", "location", name, "avoids", node["name"]])
+
+            self.log(u"Created fence device {} for {}".format(name, node["name"]))
+
+        self.invoke_rust_agent_expect_result(fqdn, "pcs", ["property", "set", "stonith-enabled=true"])
+
+
+class VerifyRingsStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        fqdn = kwargs["fqdn"]
+
+        rings = self.invoke_rust_agent_expect_result(fqdn, "corosync_ring_status", None)
+
+        if not rings:
+            raise RuntimeError("No corosync rings found on {}".format(fqdn))
+
+        faulty = [r for r in rings if r["faulty"]]
+
+        if faulty:
+            raise RuntimeError(
+                "Faulty corosync rings on {}: {}".format(
+                    fqdn, ", ".join("ring {} ({})".format(r["ring"], r["status"]) for r in faulty)
+                )
+            )
+
+        self.log(u"All {} corosync rings of {} are healthy".format(len(rings), fqdn))
+
+        return rings
+
+
+class CreateHaClusterJob(Job):
+    primary_host = models.ForeignKey("ManagedHost", related_name="+", on_delete=CASCADE)
+    secondary_host = models.ForeignKey("ManagedHost", related_name="+", on_delete=CASCADE)
+    cluster_name = models.CharField(max_length=64)
+    mcast_port = models.IntegerField()
+    nodes = fields.JSONField(help_text="Ring addresses and fence device of each host, keyed by host_id")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls):
+        return "Build a corosync and pacemaker cluster from two servers, with fencing configured."
+
+    def description(self):
+        return "Create HA cluster {} from {} and {}".format(
+            self.cluster_name, self.primary_host.fqdn, self.secondary_host.fqdn
+        )
+
+    def create_locks(self):
+        return [
+            StateLock(job=self, locked_item=self.primary_host, write=False),
+            StateLock(job=self, locked_item=self.secondary_host, write=False),
+        ]
+
+    def get_steps(self):
+        hosts = [self.primary_host, self.secondary_host]
+
+        nodes = []
+
+        for h in hosts:
+            node = self.nodes[str(h.id)]
+
+            nodes.append(
+                {
+                    "name": h.nodename,
+                    "ring0_addr": node["ring0_addr"],
+                    "ring1_addr": node.get("ring1_addr"),
+                    "fence_agent": node["fence_agent"],
+                    "fence_params": node.get("fence_params", []),
+                }
+            )
+
+        config = {
+            "cluster_name": self.cluster_name,
+            "mcast_port": self.mcast_port,
+            "nodes": [{k: n[k] for k in ["name", "ring0_addr", "ring1_addr"]} for n in nodes],
+        }
+
+        steps = []
+
+        for h in hosts:
+            steps += [
+                (ConfigureCorosyncClusterStep, {"fqdn": h.fqdn, "config": config, "node": h.nodename}),
+                (AddFirewallPortStep, {"fqdn": h.fqdn, "proto": "udp", "port": self.mcast_port}),
+            ]
+
+        steps += [(StartClusterStep, {"fqdn": h.fqdn}) for h in hosts]
+        steps += [(ConfigureStonithStep, {"fqdn": self.primary_host.fqdn, "nodes": nodes})]
+        steps += [(VerifyRingsStep, {"fqdn": h.fqdn}) for h in hosts]
+
+        return steps
+
+
+class SetStonithDeviceStep(Step):
+    def run(self, kwargs):
+        fqdn = kwargs["fqdn"]
+        device_id = kwargs["device_id"]
+        host_list = kwargs["host_list"]
+
+        config = self.invoke_rust_agent_expect_result(fqdn, "get_stonith_config", None)
+
+        params = ["pcmk_host_list={}".format(",".join(host_list))] + kwargs["params"]
+
+        if any(d["id"] == device_id for d in config["devices"]):
+            self.invoke_rust_agent_expect_result(fqdn, "pcs", ["stonith", "update", device_id] + params)
+
+            self.log(u"Updated fence device {}".format(device_id))
+        else:
+            self.invoke_rust_agent_expect_result(
+                fqdn, "pcs", ["stonith", "create", device_id, kwargs["agent"]] + params
+            )
+
+            # A node is never fenced by a device running on itself.
+            if len(host_list) == 1:
+                self.invoke_rust_agent_expect_result(
+                    fqdn, "pcs", ["constraint", "location", device_id, "avoids", host_list[0]]
+                )
+
+            self.log(u"Created fence device {} for {}".format(device_id, ", ".join(host_list)))
+
+
+class SetStonithDeviceJob(Job):
+    host = models.ForeignKey("ManagedHost", related_name="+", on_delete=CASCADE)
+    device_id = models.CharField(max_length=64)
+    agent = models.CharField(max_length=64)
+    host_list = fields.JSONField(help_text="The corosync node names the device can fence")
+    params = fields.JSONField(help_text="Instance attributes of the device as key=value")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls):
+        return "Create a stonith device of a pacemaker cluster, or update the parameters of an existing one."
+
+    def description(self):
+        return "Set stonith device {} on {}".format(self.device_id, self.host.fqdn)
+
+    def create_locks(self):
+        return [StateLock(job=self, locked_item=self.host, write=False)]
+
+    def get_steps(self):
+        return [
+            (
+                SetStonithDeviceStep,
+                {
+                    "fqdn": self.host.fqdn,
+                    "device_id": self.device_id,
+                    "agent": self.agent,
+                    "host_list": self.host_list,
+                    "params": self.params,
+                },
+            )
+        ]
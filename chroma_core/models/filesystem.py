@@ -3,13 +3,17 @@
 # license that can be found in the LICENSE file.
 
 import functools
+import logging
 import operator
+from django.contrib.postgres import fields
 from django.db import models
 from django.db.models import CASCADE
 from chroma_core.lib.job import DependOn, DependAll, Step
 from chroma_core.models import ManagedMgs, ManagedMdt, ManagedOst, FilesystemMember, ManagedTarget, ManagedHost
-from chroma_core.models import StatefulObject, StateChangeJob, Job, AdvertisedJob
+from chroma_core.models import StatefulObject, StateChangeJob, Job, AdvertisedJob, StateLock
 from chroma_core.models import DeletableDowncastableMetaclass
+from chroma_core.models import AlertStateBase
+from chroma_core.models.target import MountStep
 from chroma_core.lib.cache import ObjectCache
 from chroma_core.lib.util import target_label_split
 from django.db.models import Q
@@ -587,3 +591,292 @@ class RemoveOstPoolStep(Step):
         self.invoke_rust_agent_expect_result(
             host, "ostpool_remove", {"filesystem": fs_name, "name": pool_name, "ost": ost_label}
         )
+
+
+class SetDefaultDirStripeStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"],
+            "set_default_dir_stripe",
+            {
+                "filesystem": kwargs["filesystem"],
+                "stripe_count": kwargs["stripe_count"],
+                "stripe_offset": kwargs["stripe_offset"],
+                "hash_type": kwargs["hash_type"],
+            },
+        )
+
+
+class SetDefaultDirStripeJob(Job):
+    """
+    Set the default striping of new directories over the MDTs of a filesystem.
+    Runs on a client that has the filesystem mounted.
+    """
+
+    host = models.ForeignKey("ManagedHost", on_delete=CASCADE)
+    filesystem = models.CharField(max_length=8)
+    stripe_count = models.IntegerField()
+    stripe_offset = models.IntegerField()
+    hash_type = models.CharField(max_length=32, null=True, blank=True)
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls):
+        return "Set the default striping of new directories over the MDTs of a filesystem."
+
+    def description(self):
+        return "Set default directory striping of {} to {} MDT(s)".format(self.filesystem, self.stripe_count)
+
+    def create_locks(self):
+        return [StateLock(job=self, locked_item=self.host, write=False)]
+
+    def get_steps(self):
+        return [
+            (
+                SetDefaultDirStripeStep,
+                {
+                    "fqdn": self.host.fqdn,
+                    "filesystem": self.filesystem,
+                    "stripe_count": self.stripe_count,
+                    "stripe_offset": self.stripe_offset,
+                    "hash_type": self.hash_type,
+                },
+            )
+        ]
+
+
+class FormatTargetStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"],
+            "format_target",
+            {
+                "kind": kwargs["kind"],
+                "filesystem": kwargs["filesystem"],
+                "index": kwargs["index"],
+                "device": kwargs["device"],
+                "mgs_nids": kwargs["mgs_nids"],
+                "mountpoint": kwargs["mountpoint"],
+            },
+        )
+
+
+class CreateMountpointStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "create_mountpoint", kwargs["mountpoint"])
+
+
+class CreateTargetResourceStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        label = kwargs["ha_label"]
+
+        agent = {
+            "agent": {"standard": "ocf", "provider": "lustre", "ocftype": "Lustre"},
+            "id": label,
+            "args": {"target": kwargs["device"], "mountpoint": kwargs["mountpoint"]},
+            "ops": {"start": "300s", "monitor": "20s", "stop": "300s"},
+        }
+
+        # The target prefers the server it was formatted on, and fails over to the rest of the cluster
+        constraints = [
+            {
+                "LOCATION": {
+                    "id": "{}-primary".format(label),
+                    "rsc": label,
+                    "node": kwargs["node"],
+                    "score": {"VALUE": 20},
+                }
+            }
+        ]
+
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "ha_resource_create", [agent, constraints])
+
+
+class NewTargetJob(Job):
+    """
+    Add a target to an existing filesystem: format it, create its pacemaker resource and start it.
+    The target registers with the MGS the first time it is started.
+    """
+
+    # "MDT" or "OST", as the agent names the kinds of targets
+    kind = None
+
+    host = models.ForeignKey("ManagedHost", on_delete=CASCADE)
+    filesystem = models.CharField(max_length=8)
+    index = models.IntegerField()
+    device = models.CharField(max_length=512)
+    mgs_nids = fields.JSONField(help_text="The NIDs of each server of the MGS, comma separated")
+    node = models.CharField(max_length=255, help_text="The pacemaker node name of the host")
+    failover_fqdns = fields.JSONField(help_text="The other servers of the cluster of the host")
+
+    class Meta:
+        abstract = True
+
+    def label(self):
+        return "{}-{}{:04x}".format(self.filesystem, self.kind, self.index)
+
+    def description(self):
+        return "Add {} on {}".format(self.label(), self.host.fqdn)
+
+    def create_locks(self):
+        return [StateLock(job=self, locked_item=self.host, write=False)]
+
+    def get_steps(self):
+        label = self.label()
+        mountpoint = "/mnt/{}".format(label)
+
+        steps = [
+            (
+                FormatTargetStep,
+                {
+                    "fqdn": self.host.fqdn,
+                    "kind": self.kind,
+                    "filesystem": self.filesystem,
+                    "index": self.index,
+                    "device": self.device,
+                    "mgs_nids": self.mgs_nids,
+                    "mountpoint": mountpoint,
+                },
+            )
+        ]
+
+        steps.extend(
+            (CreateMountpointStep, {"fqdn": fqdn, "mountpoint": mountpoint}) for fqdn in self.failover_fqdns
+        )
+
+        steps.append(
+            (
+                CreateTargetResourceStep,
+                {
+                    "fqdn": self.host.fqdn,
+                    "ha_label": label,
+                    "device": self.device,
+                    "mountpoint": mountpoint,
+                    "node": self.node,
+                },
+            )
+        )
+
+        steps.append((MountStep, {"fqdn": self.host.fqdn, "ha_label": label}))
+
+        return steps
+
+
+class AddMdtJob(NewTargetJob):
+    kind = "MDT"
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls):
+        return "Format a new MDT of an existing filesystem, add it to the pacemaker cluster of its server and start it."
+
+
+class AddOstJob(NewTargetJob):
+    kind = "OST"
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls):
+        return "Format a new OST of an existing filesystem, add it to the pacemaker cluster of its server and start it."
+
+
+class SetDefaultStripeStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"],
+            "set_default_stripe",
+            {"filesystem": kwargs["filesystem"], "components": kwargs["components"]},
+        )
+
+
+class SetDefaultStripeJob(Job):
+    """
+    Set the default layout of new files in a filesystem, the layout of its root.
+    Runs on a client that has the filesystem mounted.
+    """
+
+    host = models.ForeignKey("ManagedHost", on_delete=CASCADE)
+    filesystem = models.CharField(max_length=8)
+    components = fields.JSONField(help_text="The components of the layout, none to remove the default")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls):
+        return "Set the default stripe count and size, or progressive file layout, of new files in a filesystem."
+
+    def description(self):
+        if not self.components:
+            return "Remove default file layout of {}".format(self.filesystem)
+
+        return "Set default file layout of {} to {} component(s)".format(self.filesystem, len(self.components))
+
+    def create_locks(self):
+        return [StateLock(job=self, locked_item=self.host, write=False)]
+
+    def get_steps(self):
+        return [
+            (
+                SetDefaultStripeStep,
+                {"fqdn": self.host.fqdn, "filesystem": self.filesystem, "components": self.components},
+            )
+        ]
+
+
+class ManualSnapshotsCrowdingAlert(AlertStateBase):
+    # Raised by the snapshot retention service when manual snapshots take up much of
+    # a retention policy, so interval snapshots get deleted sooner than expected.
+    # Nothing is broken yet, so it's a WARNING.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "Manual snapshots of %s crowd out its snapshot retention policy" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+
+class FilesystemCheckFailedAlert(AlertStateBase):
+    # Raised by the scheduled filesystem health checks in iml-api and lowered
+    # by the next run that passes. The failed checks are in the check history.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "Health checks of %s failed" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+
+class CapacityForecastAlert(AlertStateBase):
+    # Raised by iml-api when a filesystem is projected to fill up within
+    # CAPACITY_ALERT_DAYS, and lowered once it no longer is.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "%s is projected to fill up soon" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
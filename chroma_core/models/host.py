@@ -1299,6 +1299,34 @@ class UpdateYumFileJob(Job):
         ]
 
 
+class CheckCompatibilityStep(Step):
+    """
+    Warn about Lustre and agent versions the manager does not support on the filesystems of a host.
+    Only logs, an update is often what brings a host back to a supported combination.
+    """
+
+    idempotent = True
+    database = True
+
+    def run(self, kwargs):
+        from chroma_core.lib.graphql import get_compatibility_report, get_host_targets
+
+        host = kwargs["host"]
+
+        fs_names = set(fs for t in get_host_targets(host.id) for fs in t["filesystems"])
+        fs_names.update(m.filesystem for m in host.client_mounts.all())
+
+        for fs_name in sorted(fs_names):
+            try:
+                report = get_compatibility_report(fs_name=fs_name)
+            except Exception as e:
+                self.log("Warning: could not check the versions of {}: {}".format(fs_name, e))
+                continue
+
+            for issue in report["issues"]:
+                self.log("Warning: {}: {}".format(fs_name, issue))
+
+
 class UpdateJob(Job):
     host = models.ForeignKey(ManagedHost, on_delete=CASCADE)
 
@@ -1317,6 +1345,7 @@ class UpdateJob(Job):
         repo_file_contents = self.host.server_profile.repo_contents
 
         return [
+            (CheckCompatibilityStep, {"host": self.host}),
             (UpdateYumFileStep, {"host": self.host, "filename": REPO_FILENAME, "file_contents": repo_file_contents}),
             (
                 UpdatePackagesStep,
@@ -1348,6 +1377,94 @@ class UpdateJob(Job):
         app_label = "chroma_core"
 
 
+class CheckProfileComplianceStep(Step):
+    """
+    Check a host against its server profile, logging each discrepancy found.
+    Only reports, the steps before it in a resync have already pushed what they can.
+    """
+
+    idempotent = True
+    database = True
+
+    def run(self, kwargs):
+        from chroma_core.services.job_scheduler.agent_rpc import AgentException
+
+        host = kwargs["host"]
+        profile = host.server_profile
+
+        discrepancies = []
+
+        for package in list(profile.base_packages) + list(profile.packages):
+            try:
+                installed = self.invoke_rust_agent_expect_result(host.fqdn, "package_installed", package)
+            except AgentException as e:
+                discrepancies.append("could not check package {}: {}".format(package, e))
+                continue
+
+            if not installed:
+                discrepancies.append("package {} of profile {} is not installed".format(package, profile.name))
+
+        units = []
+        if profile.ntp:
+            units.append("ntpd.service")
+        if profile.corosync or profile.corosync2:
+            units.append("corosync.service")
+        if profile.pacemaker:
+            units.append("pacemaker.service")
+
+        for unit in units:
+            try:
+                state = self.invoke_rust_agent_expect_result(host.fqdn, "get_unit_run_state", unit)
+            except AgentException as e:
+                discrepancies.append("could not check {}: {}".format(unit, e))
+                continue
+
+            if state != "Setup":
+                discrepancies.append("{} is {}, not enabled and started".format(unit, state.lower()))
+
+        for d in discrepancies:
+            self.log("Discrepancy on {}: {}".format(host.fqdn, d))
+
+        self.log("Found {} discrepancies on {}".format(len(discrepancies), host.fqdn))
+
+        return discrepancies
+
+
+class ResyncHostJob(Job):
+    host = models.ForeignKey(ManagedHost, on_delete=CASCADE)
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["resync_host"]
+
+    def description(self):
+        return "Resync the agent state of {}".format(self.host.fqdn)
+
+    def get_steps(self):
+        steps = [
+            (UpdateProfileStep, {"host": self.host, "profile": self.host.server_profile}),
+            (
+                UpdateYumFileStep,
+                {"host": self.host, "filename": REPO_FILENAME, "file_contents": self.host.server_profile.repo_contents},
+            ),
+        ]
+
+        if self.host.is_lustre_server:
+            steps.append((LearnDevicesStep, {"host": self.host}))
+
+        steps.append((CheckProfileComplianceStep, {"host": self.host}))
+        steps.append((CheckCompatibilityStep, {"host": self.host}))
+
+        return steps
+
+    def create_locks(self):
+        return [StateLock(job=self, locked_item=self.host, write=False)]
+
+
 class ResetConfParamsStep(Step):
     database = True
 
@@ -1391,6 +1508,19 @@ class HostContactAlert(AlertStateBase):
         )
 
 
+class AgentHeartbeatAlert(AlertStateBase):
+    # Raised by iml-api when the agent on a host misses
+    # HEARTBEAT_ALERT_MISSED heartbeats, and lowered once it is back.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "The agent on %s has stopped sending heartbeats" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+
 class HostOfflineAlert(AlertStateBase):
     """Alert should be raised when a Host is known to be down.
 
@@ -351,6 +351,33 @@ class Job(models.Model):
     wait_for_json = models.TextField()
     locks_json = models.TextField()
 
+    queue_host = models.ForeignKey(
+        "ManagedHost",
+        null=True,
+        blank=True,
+        related_name="+",
+        on_delete=models.CASCADE,
+        help_text="The host whose queue this job is in. Jobs in a host queue run one at a time in id order\
+            and are resumed, rather than cancelled, when the job scheduler restarts",
+    )
+
+    # Priority classes, see JobCollection.ready_jobs.  The names are what callers of
+    # JobSchedulerClient.command_run_jobs and command_set_state pass as `priority`.
+    PRIORITY_LOW = 0
+    PRIORITY_NORMAL = 1
+    PRIORITY_HIGH = 2
+    PRIORITIES = {"low": PRIORITY_LOW, "normal": PRIORITY_NORMAL, "high": PRIORITY_HIGH}
+
+    priority = models.SmallIntegerField(
+        default=PRIORITY_NORMAL,
+        help_text="0 (low), 1 (normal) or 2 (high). Ready jobs start highest priority first, and jobs in a\
+            host queue run ahead of queued jobs of a lower priority",
+    )
+
+    #: The priority of jobs of this class when the command they are in does not set one.
+    #: Long running background work is low, so it does not hold up what users are waiting on.
+    default_priority = PRIORITY_NORMAL
+
     @classmethod
     def long_description(cls, stateful_object):
         raise NotImplementedError("long_description needs to be implemented for each job.")
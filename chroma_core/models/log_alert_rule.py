@@ -0,0 +1,45 @@
+# Copyright (c) 2020 DDN. All rights reserved.
+# Use of this source code is governed by a MIT-style
+# license that can be found in the LICENSE file.
+
+import logging
+from datetime import timedelta
+
+from django.db import models
+from chroma_core.models.alert import AlertStateBase
+
+
+class LogAlertRule(models.Model):
+    # A user-defined rule matching incoming log messages. Rules are managed
+    # and evaluated by the Rust services, as messages are ingested; the model
+    # only exists so the alerts they raise have an item.
+    name = models.CharField(max_length=64, unique=True)
+    enabled = models.BooleanField(default=True)
+    message_pattern = models.CharField(max_length=1024)
+    fqdn_pattern = models.CharField(max_length=255, null=True, blank=True)
+    tag_pattern = models.CharField(max_length=255, null=True, blank=True)
+    # The least severe syslog severity matched, 0 (emergency) to 7 (debug)
+    severity = models.SmallIntegerField(default=7)
+    min_matches = models.IntegerField(default=1)
+    within = models.DurationField(default=timedelta(minutes=5))
+    alert_severity = models.IntegerField(default=logging.WARNING)
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    def __str__(self):
+        return "log alert rule %s" % self.name
+
+
+class LogPatternAlert(AlertStateBase):
+    # Raised when a log alert rule matched at least min_matches messages
+    # within its window, and lowered once it no longer does.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "Log messages matched %s" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
@@ -90,6 +90,32 @@ class StonithNotEnabledAlert(AlertStateBase):
         return [self.alert_item.host]
 
 
+class StonithTestFailedAlert(AlertStateBase):
+    # Raised by iml-api when the periodic stonith test finds no working
+    # device that can fence a host, and lowered by the next test that does.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "Stonith test failed for %s" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+
+class ClusterConfigDriftAlert(AlertStateBase):
+    # Raised by iml-corosync when the CIB or corosync.conf a host reports no
+    # longer matches the configuration IML last applied, and lowered once it does again.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "Cluster configuration of %s changed outside of IML" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+
 class PacemakerStoppedAlert(AlertStateBase):
     # Pacemaker being down is never solely responsible for a filesystem
     # being unavailable: if a target is offline we will get a separate
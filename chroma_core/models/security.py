@@ -0,0 +1,64 @@
+# Copyright (c) 2020 DDN. All rights reserved.
+# Use of this source code is governed by a MIT-style
+# license that can be found in the LICENSE file.
+
+from django.db import models
+from django.db.models import CASCADE
+from chroma_core.lib.job import Step
+from chroma_core.models.jobs import Job, StateLock
+from chroma_core.models.filesystem import ManagedFilesystem
+
+
+class DistributeSskKeyStep(Step):
+    def run(self, args):
+        hosts = args["hosts"]
+
+        if not hosts:
+            raise RuntimeError("No servers found for {}".format(args["fs_name"]))
+
+        # The key is generated on one server and copied to the rest.
+        # It is passed between agents directly so it never ends up in the step args or result.
+        key = self.invoke_rust_agent_expect_result(
+            hosts[0], "ssk_generate", {"fsname": args["fs_name"], "path": args["path"]}
+        )
+
+        self.log(u"Generated shared key for {} on {}".format(args["fs_name"], hosts[0]))
+
+        for host in hosts[1:]:
+            self.invoke_rust_agent_expect_result(host, "ssk_install", {"path": args["path"], "key": key})
+
+            self.log(u"Installed shared key for {} on {}".format(args["fs_name"], host))
+
+        return hosts
+
+
+class DistributeSskKeyJob(Job):
+    filesystem = models.ForeignKey("ManagedFilesystem", on_delete=CASCADE)
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls):
+        return "Generate a shared secret key for a filesystem and install it on every server."
+
+    def description(self):
+        return "Distribute shared key for {}".format(self.filesystem.name)
+
+    def create_locks(self):
+        return [StateLock(job=self, locked_item=self.filesystem, write=False)]
+
+    def get_steps(self):
+        fs_name = self.filesystem.name
+
+        return [
+            (
+                DistributeSskKeyStep,
+                {
+                    "fs_name": fs_name,
+                    "hosts": [h.fqdn for h in self.filesystem.get_servers()],
+                    "path": "/etc/lustre/{}.key".format(fs_name),
+                },
+            )
+        ]
@@ -0,0 +1,38 @@
+# Copyright (c) 2020 DDN. All rights reserved.
+# Use of this source code is governed by a MIT-style
+# license that can be found in the LICENSE file.
+
+import logging
+
+from django.db import models
+from chroma_core.models.alert import AlertStateBase
+
+
+class StandbyManager(models.Model):
+    # A warm standby manager, known by the application_name it streams the
+    # database with. Rows are added by iml-api as standbys connect, and the
+    # configuration sync is reported by the standby through the API.
+    name = models.CharField(max_length=255, unique=True)
+    config_hash = models.CharField(max_length=128, null=True, blank=True)
+    config_synced_at = models.DateTimeField(null=True, blank=True)
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    def __str__(self):
+        return "standby manager %s" % self.name
+
+
+class StandbyBehindAlert(AlertStateBase):
+    # Raised by iml-api when a standby manager is disconnected, lags too far
+    # behind the primary database or has not synced its configuration lately,
+    # and lowered once it could take over again.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "%s is not ready to take over" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
@@ -21,6 +21,8 @@ from chroma_core.lib.stratagem import (
     stratagem_measurement,
     aggregate_points,
     submit_aggregated_data,
+    count_scanned_entries,
+    record_scan_progress,
 )
 from chroma_core.lib.util import CommandLine, runningInDocker
 from chroma_core.models.jobs import Job, StateChangeJob, StateLock
@@ -333,6 +335,8 @@ class StreamFidlistStep(Step):
 
         self.log(u"\u2713 Scan results sent to client under:\n{}".format("\n".join(xs[1] for xs in mailbox_files)))
 
+        record_scan_progress(self.job_id, "complete")
+
         return result
 
 
@@ -363,6 +367,8 @@ class FastFileScanMdtJob(Job):
     fsname = models.CharField(max_length=8, null=False)
     config = fields.JSONField(null=False)
 
+    default_priority = Job.PRIORITY_LOW
+
     @classmethod
     def long_description(self):
         return "Scanning MDT"
@@ -387,6 +393,8 @@ class ScanMdtJob(Job):
     fsname = models.CharField(max_length=8, null=False)
     config = fields.JSONField(null=False)
 
+    default_priority = Job.PRIORITY_LOW
+
     @classmethod
     def long_description(self):
         return "Scanning MDT"
@@ -406,7 +414,15 @@ class ScanMdtJob(Job):
 
 class ScanMdtStep(Step):
     def run(self, args):
-        return self.invoke_rust_agent_expect_result(args["host"], "start_scan_stratagem", args["config"])
+        record_scan_progress(self.job_id, "scanning")
+
+        result = self.invoke_rust_agent_expect_result(args["host"], "start_scan_stratagem", args["config"])
+
+        _, stratagem_result, _ = result
+
+        record_scan_progress(self.job_id, "streaming", count_scanned_entries(stratagem_result))
+
+        return result
 
 
 class RunStratagemJob(Job):
@@ -423,6 +439,8 @@ class RunStratagemJob(Job):
     target_mount_point = models.CharField(max_length=512, null=False, default="")
     device_path = models.CharField(max_length=512, null=False, default="")
 
+    default_priority = Job.PRIORITY_LOW
+
     class Meta:
         app_label = "chroma_core"
         ordering = ["id"]
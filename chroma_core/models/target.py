@@ -756,6 +756,88 @@ class TargetRecoveryAlert(AlertStateBase):
         affect_target(self.alert_item)
 
 
+class MultipathDegradedAlert(AlertStateBase):
+    # Raised by iml-api when a path to the multipath device of a target has
+    # failed on one of its hosts, and lowered once every path is back.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "Multipath device of %s has failed paths" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def affected_targets(self, affect_target):
+        affect_target(self.alert_item)
+
+
+class TargetRemountFailedAlert(AlertStateBase):
+    # Raised by iml-api when a target that went offline on its own could not be
+    # remounted within the attempts its filesystem's remount policy allows.
+    # Lowered once the target is back, or no longer remounted automatically.
+    default_severity = logging.ERROR
+
+    def alert_message(self):
+        return "Target %s could not be remounted" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def affected_targets(self, affect_target):
+        affect_target(self.alert_item)
+
+
+class DuplicateTargetUuidAlert(AlertStateBase):
+    # Raised by iml-api's integrity check when a target shares its uuid with
+    # another one, and lowered once the uuid is unique again.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "Target %s shares its uuid with another target" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def affected_targets(self, affect_target):
+        affect_target(self.alert_item)
+
+
+class TargetDevPathChangedAlert(AlertStateBase):
+    # Raised by iml-api's integrity check when the device path of a target
+    # changed while it stayed on the same host. Lowered once the change is
+    # acknowledged, or the previous device path is back.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "Device path of %s changed" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def affected_targets(self, affect_target):
+        affect_target(self.alert_item)
+
+
+class TargetHostMissingAlert(AlertStateBase):
+    # Raised by iml-api's integrity check when a target is registered on a
+    # host that was removed, and lowered once it no longer is.
+    default_severity = logging.WARNING
+
+    def alert_message(self):
+        return "Target %s is registered on a removed host" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def affected_targets(self, affect_target):
+        affect_target(self.alert_item)
+
+
 def get_host_targets(host_id):
     from chroma_core.lib.graphql import get_host_targets
 
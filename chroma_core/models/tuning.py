@@ -0,0 +1,46 @@
+# Copyright (c) 2020 DDN. All rights reserved.
+# Use of this source code is governed by a MIT-style
+# license that can be found in the LICENSE file.
+
+from django.db import models
+from django.db.models import CASCADE
+from django.contrib.postgres import fields
+from chroma_core.lib.job import Step
+from chroma_core.models.jobs import Job, StateLock
+
+
+class ApplyTuningStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "apply_tuning", kwargs["settings"])
+
+        self.log(u"Applied tuning profile {} to {}".format(kwargs["profile_name"], kwargs["fqdn"]))
+
+
+class ApplyTuningProfileJob(Job):
+    host = models.ForeignKey("ManagedHost", on_delete=CASCADE)
+    profile_name = models.CharField(max_length=64)
+    settings = fields.JSONField(help_text="The settings of the profile at the time it was applied")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls):
+        return "Apply the sysctl, lctl and kernel module settings of a tuning profile to a server."
+
+    def description(self):
+        return "Apply tuning profile {} to {}".format(self.profile_name, self.host.fqdn)
+
+    def create_locks(self):
+        return [StateLock(job=self, locked_item=self.host, write=False)]
+
+    def get_steps(self):
+        return [
+            (
+                ApplyTuningStep,
+                {"fqdn": self.host.fqdn, "profile_name": self.profile_name, "settings": self.settings},
+            )
+        ]
@@ -3,11 +3,14 @@
 # license that can be found in the LICENSE file.
 
 
+import datetime
 import urlparse
 import os
 import re
 import tempfile
 
+from django.utils.timezone import utc
+
 from chroma_core.lib.util import CommandLine
 from chroma_core.services import log_register
 
@@ -31,6 +34,9 @@ class Crypto(CommandLine):
     # this is set to a 'forever' value.
     CERTIFICATE_DAYS = "36500"
 
+    # Duration of certificates issued to agents, which can be rotated
+    AGENT_CERTIFICATE_DAYS = str(settings.AGENT_CERTIFICATE_DAYS)
+
     log = log_register("crypto")
 
     def _get_or_create_private_key(self, filename):
@@ -161,7 +167,7 @@ subjectAltName = DNS:%s
                 "x509",
                 "-req",
                 "-days",
-                self.CERTIFICATE_DAYS,
+                self.AGENT_CERTIFICATE_DAYS,
                 "-CAkey",
                 self.authority_key,
                 "-CA",
@@ -177,3 +183,9 @@ subjectAltName = DNS:%s
         rc, out, err = self.try_shell(["openssl", "x509", "-serial", "-noout", "-sha256"], stdin_text=cert_str)
         # Output like "serial=foo"
         return out.strip().split("=")[1]
+
+    def get_not_after(self, cert_str):
+        rc, out, err = self.try_shell(["openssl", "x509", "-enddate", "-noout"], stdin_text=cert_str)
+        # Output like "notAfter=Jan  6 10:00:00 2022 GMT"
+        not_after = datetime.datetime.strptime(out.strip().split("=")[1], "%b %d %H:%M:%S %Y GMT")
+        return not_after.replace(tzinfo=utc)
@@ -26,7 +26,7 @@ log = log_register(__name__)
 
 
 class HttpAgentRpc(ServiceRpcInterface):
-    methods = ["reset_session", "remove_host", "reset_plugin_sessions"]
+    methods = ["reset_session", "remove_host", "reset_plugin_sessions", "allow_certificate", "revoke_certificate"]
 
 
 # TODO: interesting tests:
@@ -60,6 +60,16 @@ class Service(ChromaService):
         # TODO: ensure there are no GETs left in progress after this completes
         # TODO: drain plugin_rx_queue so that anything we will send to AMQP has been sent before this returns
 
+    def allow_certificate(self, fqdn, serial):
+        log.info("allow_certificate: %s:%s" % (fqdn, serial))
+
+        self.valid_certs[serial] = fqdn
+
+    def revoke_certificate(self, serial):
+        log.info("revoke_certificate: %s" % serial)
+
+        self.valid_certs.pop(serial, None)
+
     def __init__(self):
         super(Service, self).__init__()
 
@@ -7,6 +7,8 @@ import logging
 import threading
 import datetime
 
+from django.db import connection
+
 from chroma_agent_comms.views import MessageView
 from chroma_core.models import ManagedHost, HostContactAlert, HostRebootEvent
 from chroma_core.services import log_register
@@ -46,6 +48,8 @@ class HostState(object):
                 whether a fresh client run (different start time) is seen.
         """
         self.last_contact = IMLDateTime.utcnow()
+        self._record_heartbeat()
+
         if boot_time is not None and boot_time != self._boot_time:
             if self._boot_time is not None:
                 HostRebootEvent.register_event(alert_item=self._host, boot_time=boot_time, severity=logging.WARNING)
@@ -66,6 +70,17 @@ class HostState(object):
 
         return require_reset
 
+    def _record_heartbeat(self):
+        # iml-api reads this to report and alert on agents that have gone quiet
+        with connection.cursor() as cursor:
+            cursor.execute(
+                """
+                INSERT INTO host_heartbeat (host_id, last_seen) VALUES (%s, %s)
+                ON CONFLICT (host_id) DO UPDATE SET last_seen = EXCLUDED.last_seen
+                """,
+                [self._host.id, self.last_contact],
+            )
+
     def poll(self):
         if self._healthy:
             time_since_contact = IMLDateTime.utcnow() - self.last_contact
@@ -11,6 +11,7 @@ from django.db.models import DateTimeField
 from django.db.models.query_utils import Q
 
 from chroma_core.services.job_scheduler.mail_alerts import MailAlerts
+from chroma_core.services.job_scheduler.mail_digest import MailDigest
 from chroma_core.services.job_scheduler import job_scheduler_notify
 from chroma_core.services import ChromaService, ServiceThread, log_register
 from chroma_core.models.jobs import Job
@@ -83,10 +84,16 @@ class Service(ChromaService):
 
         super(Service, self).run()
 
-        # Cancel anything that's left behind from a previous run
-        for command in Command.objects.filter(complete=False):
+        # Cancel anything that's left behind from a previous run, apart from commands whose
+        # jobs were all still waiting in host queues: those are resumed once we are running.
+        # A job which was running may have been part way through its steps, so it is not
+        # safe to resume the command it belongs to.
+        interrupted_jobs = Job.objects.filter(Q(state="tasked") | Q(state="pending", queue_host=None))
+        for command in Command.objects.filter(complete=False, jobs__in=interrupted_jobs).distinct():
             command.completed(True, True)
-        Job.objects.filter(~Q(state="complete")).update(state="complete", cancelled=True)
+        Job.objects.filter(~Q(state="complete")).exclude(command__complete=False).update(
+            state="complete", cancelled=True
+        )
 
         self._job_scheduler = JobScheduler()
         self._queue_thread = ServiceThread(QueueHandler(self._job_scheduler))
@@ -97,15 +104,23 @@ class Service(ChromaService):
         self._rpc_thread.start()
         self._progress_thread.start()
 
+        self._job_scheduler.resume_host_queues()
+
         self._children_started.set()
         self._mail_alerts_thread = MailAlerts(settings.EMAIL_SENDER, settings.EMAIL_SUBJECT_PREFIX, settings.EMAIL_HOST)
         self._mail_alerts_thread.start()
+        self._mail_digest_thread = MailDigest(
+            settings.EMAIL_SENDER, settings.EMAIL_SUBJECT_PREFIX, settings.EMAIL_HOST, settings.DIGEST_HOUR
+        )
+        self._mail_digest_thread.start()
 
         self._complete.wait()
 
         self.log.info("Cancelling outstanding jobs...")
 
-        for job in Job.objects.filter(~Q(state="complete")).order_by("-id"):
+        # Jobs waiting in host queues are left for the next run to resume
+        jobs = Job.objects.filter(~Q(state="complete")).exclude(state="pending", queue_host__isnull=False)
+        for job in jobs.order_by("-id"):
             self._job_scheduler.cancel_job(job.id)
 
     def stop(self):
@@ -123,6 +138,7 @@ class Service(ChromaService):
         self._queue_thread.stop()
         self._progress_thread.stop()
         self._mail_alerts_thread.stop()
+        self._mail_digest_thread.stop()
 
         self.log.info("Joining...")
         self._rpc_thread.join()
@@ -130,6 +146,7 @@ class Service(ChromaService):
         self._job_scheduler.join_run_threads()
         self._progress_thread.join()
         self._mail_alerts_thread.join()
+        self._mail_digest_thread.join()
 
         self.log.info("Complete.")
 
@@ -8,11 +8,11 @@ from collections import defaultdict
 import django.db.models
 
 from django.contrib.contenttypes.models import ContentType
-from django.db import transaction
+from django.db import connection, transaction
 
 from chroma_core.services.log import log_register
 from chroma_core.services.job_scheduler.dep_cache import DepCache
-from chroma_core.models.jobs import StateChangeJob, SchedulingError, StateLock
+from chroma_core.models.jobs import Job, StateChangeJob, SchedulingError, StateLock
 from chroma_core.models.command import Command
 
 
@@ -111,7 +111,34 @@ class CommandPlan(object):
 
         return locks
 
-    def add_jobs(self, jobs, command, job_deps_map):
+    def _queue(self, job):
+        """Jobs acting on a host go in that host's queue, see JobCollection.ready_jobs"""
+        job.queue_host_id = getattr(job, "host_id", None)
+
+    def _prioritise(self, job, priority):
+        """Give `job` the priority of its command, or that of its class when the command has none"""
+        job.priority = job.default_priority if priority is None else priority
+
+        self._raise_priority(json.loads(job.wait_for_json), job.priority)
+
+    def _raise_priority(self, job_ids, priority):
+        """Raise the incomplete jobs in `job_ids`, and the jobs they wait for in turn, to at least `priority`.
+
+        A job then never waits for a job of a lower priority, so the head of a host
+        queue cannot be waiting for a job that is queued behind it.
+        """
+        for job_id in job_ids:
+            try:
+                job = self._job_collection.get(job_id)
+            except KeyError:
+                continue
+            if job.state != "complete" and job.priority < priority:
+                log.info("raise_priority: raising Job %s to priority %s" % (job_id, priority))
+                job.priority = priority
+                Job.objects.filter(id=job_id).update(priority=priority)
+                self._raise_priority(json.loads(job.wait_for_json), priority)
+
+    def add_jobs(self, jobs, command, job_deps_map, priority=None):
         """Add a job, and any others which are required in order to reach its prerequisite state"""
         # Important: the Job must not be committed until all
         # its dependencies and locks are in.
@@ -124,11 +151,13 @@ class CommandPlan(object):
                         "add_jobs: setting required dependency %s %s"
                         % (dependency.stateful_object, dependency.preferred_state)
                     )
-                    self._set_state(dependency.get_stateful_object(), dependency.preferred_state, command)
+                    self._set_state(dependency.get_stateful_object(), dependency.preferred_state, command, priority)
             log.info("add_jobs: done checking dependencies")
             locks = self._create_locks(job)
             job.locks_json = json.dumps([l.to_dict() for l in locks])
             self._create_dependencies(job, locks, job_deps_map)
+            self._queue(job)
+            self._prioritise(job, priority)
             job.save()
 
             log.info("add_jobs: created Job %s (%s)" % (job.pk, job.description()))
@@ -140,6 +169,37 @@ class CommandPlan(object):
 
         self._job_collection.add_command(command, jobs)
 
+    def check_approval(self, class_names):
+        """Refuse a command with a job of a class that has an approval rule (see `approval.addRule`
+        in iml-api). Only the GraphQL API can hold such a command back until a second user approves it."""
+        with connection.cursor() as cursor:
+            cursor.execute(
+                "SELECT class_name FROM approval_rule WHERE class_name = ANY(%s::TEXT[]) ORDER BY class_name",
+                [sorted(set(class_names))],
+            )
+            held = [x[0] for x in cursor.fetchall()]
+
+        if held:
+            raise SchedulingError(
+                "%s needs the approval of a second user, submit it through the GraphQL API or the iml CLI"
+                % ", ".join(held)
+            )
+
+    def transition_classes(self, object_ids):
+        """The job classes of the state changes `object_ids` asks for, from the current states of the
+        objects. Like the jobs passed to `command_run_jobs`, the jobs of their dependencies are left out."""
+        class_names = []
+        for ct_nk, o_pk, state in object_ids:
+            model_klass = ContentType.objects.get_by_natural_key(*ct_nk).model_class()
+            instance = model_klass.objects.get(pk=o_pk)
+            if instance.state == state:
+                continue
+
+            route = instance.get_route(instance.state, state)
+            class_names.extend(instance.get_job_class(a, b).__name__ for a, b in zip(route, route[1:]))
+
+        return class_names
+
     def get_transition_consequences(self, instance, new_state):
         """For use in the UI, for warning the user when an
         action is going to have some consequences which
@@ -278,7 +338,7 @@ class CommandPlan(object):
         object_leaf_distances.sort(lambda x, y: cmp(x[1], y[1]))
         return [obj for obj, ld in object_leaf_distances]
 
-    def _set_state(self, instance, new_state, command):
+    def _set_state(self, instance, new_state, command, priority=None):
         """Return a Job or None if the object is already in new_state.
         command_id should refer to a command instance or be None."""
 
@@ -323,6 +383,8 @@ class CommandPlan(object):
                 log.info("set_state: state %s to be reached by job %s" % (new_state, job.id))
                 command.jobs.add(job)
                 self._job_collection.add_command(command, [job])
+                if priority is not None:
+                    self._raise_priority([job.id], priority)
 
             # Pick out whichever job made it so, and attach that to the Command
             return None
@@ -344,6 +406,8 @@ class CommandPlan(object):
             locks = self._create_locks(job)
             job.locks_json = json.dumps([l.to_dict() for l in locks])
             self._create_dependencies(job, locks, {})
+            self._queue(job)
+            self._prioritise(job, priority)
             job.save()
             jobs.append(job)
             for l in locks:
@@ -495,7 +559,28 @@ class CommandPlan(object):
                     )
                     self.edges.add((root_transition, dep_transition))
 
-    def command_run_jobs(self, job_dicts, message):
+    def _job_args(self, job_klass, args):
+        """StateChangeJobs name their stateful object by id, e.g. {"target_id": 1, "old_state": "unmounted"}.
+        Swap the id for the cached object, as the job expects."""
+        if not issubclass(job_klass, StateChangeJob):
+            return args
+
+        key = "%s_id" % job_klass.stateful_object
+
+        if key not in args:
+            return args
+
+        from chroma_core.lib.cache import ObjectCache
+
+        args = dict(args)
+        model_klass = job_klass._meta.get_field(job_klass.stateful_object).related_model
+        instance = ObjectCache.get_by_id(model_klass, args.pop(key), fill_on_miss=True)
+        args[job_klass.stateful_object] = instance
+        args.setdefault("old_state", instance.state)
+
+        return args
+
+    def command_run_jobs(self, job_dicts, message, priority=None):
         assert len(job_dicts) > 0
 
         jobs = []
@@ -507,7 +592,7 @@ class CommandPlan(object):
                 depends_on_job_range = job["args"].get("depends_on_job_range")
                 del job["args"]["depends_on_job_range"]
 
-            job_instance = job_klass(**job["args"])
+            job_instance = job_klass(**self._job_args(job_klass, job["args"]))
             job_deps_map[job_instance] = depends_on_job_range
             jobs.append(job_instance)
 
@@ -518,17 +603,17 @@ class CommandPlan(object):
             log.debug("command_run_jobs: command %s" % command.id)
             for job in jobs:
                 log.debug("command_run_jobs:  job %s" % job)
-            self.add_jobs(jobs, command, job_deps_map)
+            self.add_jobs(jobs, command, job_deps_map, priority)
 
         return command.id
 
-    def command_set_state(self, object_ids, message, command=None):
+    def command_set_state(self, object_ids, message, command=None, priority=None):
         if not command:
             command = Command.objects.create(message=message)
         for ct_nk, o_pk, state in object_ids:
             model_klass = ContentType.objects.get_by_natural_key(*ct_nk).model_class()
             instance = model_klass.objects.get(pk=o_pk)
-            self._set_state(instance, state, command)
+            self._set_state(instance, state, command, priority)
 
         log.info("Created command %s (%s) with %s jobs" % (command.id, command.message, command.jobs.count()))
         if command.jobs.count() == 0:
@@ -35,6 +35,7 @@ from chroma_core.models import ManagedHost
 from chroma_core.models import FilesystemMember
 from chroma_core.models import ConfigureLNetJob
 from chroma_core.models import ManagedTarget, ApplyConfParams, ManagedOst, Job, DeletableStatefulObject
+from chroma_core.models.jobs import SchedulingError
 from chroma_core.models import StepResult
 from chroma_core.models import (
     ManagedFilesystem,
@@ -368,6 +369,31 @@ class RunJobThread(threading.Thread):
         self._job_progress.complete_job(self.job.id, errored=False)
 
 
+# Leaves room within MAX_STEP_DB_CONNECTIONS for jobs of a higher priority
+MAX_LOW_PRIORITY_JOBS = 4
+
+
+def _run_order(job):
+    """Sort key for jobs that could start: highest priority first, then oldest first"""
+    return (-job.priority, job.id)
+
+
+# Passed as `approval` by callers that applied the approval rules themselves, as iml-api does.
+# Commands from anywhere else, such as the REST API, are refused when a job class has a rule.
+APPROVAL_CHECKED = "checked"
+
+
+def _priority(name):
+    """The Job priority for a priority name passed over RPC, or None to use the defaults of the job classes"""
+    if name is None:
+        return None
+
+    try:
+        return Job.PRIORITIES[name]
+    except KeyError:
+        raise SchedulingError("Unknown priority '%s', must be one of %s" % (name, sorted(Job.PRIORITIES.keys())))
+
+
 class JobCollection(object):
     def __init__(self):
         self.flush()
@@ -443,15 +469,53 @@ class JobCollection(object):
 
         Job.objects.filter(id__in=[j.id for j in jobs]).update(state=new_state)
 
+    def queue_heads(self):
+        """The running job in each host queue, or else the job to run next, by host id.
+
+        Only the head of a host queue may run, so jobs against one host run one at
+        a time: highest priority first, and in the order they were created within
+        a priority.  A job that is running is not interrupted by a higher priority
+        one being queued.
+        """
+        heads = {}
+        for job in self.tasked_jobs:
+            if job.queue_host_id is not None:
+                heads[job.queue_host_id] = job
+
+        for job in self.pending_jobs:
+            if job.queue_host_id is None:
+                continue
+            head = heads.get(job.queue_host_id)
+            if head is None or (head.state == "pending" and _run_order(job) < _run_order(head)):
+                heads[job.queue_host_id] = job
+
+        return heads
+
     @property
     def ready_jobs(self):
+        """The pending jobs which may start now, in the order to start them.
+
+        Low priority jobs only start while fewer than MAX_LOW_PRIORITY_JOBS of them
+        are running, so background work leaves room for what users are waiting on.
+        """
         result = []
+        queue_heads = self.queue_heads()
+        complete_job_ids = set(self._state_jobs["complete"].keys())
         for job in self._state_jobs["pending"].values():
+            if job.queue_host_id is not None and queue_heads[job.queue_host_id] is not job:
+                continue
             wait_for_ids = json.loads(job.wait_for_json)
-            complete_job_ids = [j.id for j in self._state_jobs["complete"].values()]
-            if not set(wait_for_ids) - set(complete_job_ids):
+            if not set(wait_for_ids) - complete_job_ids:
                 result.append(job)
 
+        result.sort(key=_run_order)
+
+        low_running = len([j for j in self.tasked_jobs if j.priority == Job.PRIORITY_LOW])
+        low_slots = max(MAX_LOW_PRIORITY_JOBS - low_running, 0)
+        low_ready = [j for j in result if j.priority == Job.PRIORITY_LOW]
+        for job in low_ready[low_slots:]:
+            result.remove(job)
+
         if len(result) == 0 and len(self.pending_jobs) == 0 and len(self.tasked_jobs) == 0:
             # A quiescent state, flush the collection (avoid building up an indefinitely
             # large collection of complete jobs)
@@ -501,6 +565,30 @@ class JobScheduler(object):
         # but maybe improved in the future.
         self.completion_hooks = []
 
+    def resume_host_queues(self):
+        """Pick up the commands left waiting in host queues by a previous run.
+
+        The service cancels every other incomplete command at startup, so this
+        resumes all commands which are still incomplete, in job id order.
+        """
+        with self._lock:
+            resumed = {}
+            for command in Command.objects.filter(complete=False):
+                jobs = list(command.jobs.all())
+                self._job_collection.add_command(command, jobs)
+                resumed.update((job.id, job) for job in jobs)
+
+            # Anything else these jobs wait for has completed or been cancelled
+            wait_for_ids = set()
+            for job in resumed.values():
+                wait_for_ids |= set(json.loads(job.wait_for_json))
+            for job in Job.objects.filter(id__in=wait_for_ids - set(resumed.keys()), state="complete"):
+                self._job_collection.add(job)
+
+            log.info("resume_host_queues: resuming %s queued jobs" % len(self._job_collection.pending_jobs))
+
+            self._run_next()
+
     def join_run_threads(self):
         for job_id, thread in self._run_threads.items():
             log.info("Joining thread for job %s" % job_id)
@@ -722,10 +810,12 @@ class JobScheduler(object):
                 log.debug("Replaying buffered notification: %s" % (notification,))
                 self._notify(*notification)
 
-    def set_state(self, object_ids, message, run):
+    def set_state(self, object_ids, message, run, priority=None, approval=None):
         with self._lock:
             with transaction.atomic():
-                command = self.CommandPlan.command_set_state(object_ids, message)
+                if approval != APPROVAL_CHECKED:
+                    self.CommandPlan.check_approval(self.CommandPlan.transition_classes(object_ids))
+                command = self.CommandPlan.command_set_state(object_ids, message, priority=_priority(priority))
             if run:
                 self.progress.advance()
         return command.id
@@ -826,9 +916,11 @@ class JobScheduler(object):
 
             self._run_next()
 
-    def run_jobs(self, job_dicts, message):
+    def run_jobs(self, job_dicts, message, priority=None, approval=None):
         with self._lock:
-            result = self.CommandPlan.command_run_jobs(job_dicts, message)
+            if approval != APPROVAL_CHECKED:
+                self.CommandPlan.check_approval([x["class_name"] for x in job_dicts])
+            result = self.CommandPlan.command_run_jobs(job_dicts, message, _priority(priority))
 
         self.progress.advance()
 
@@ -870,6 +962,15 @@ class JobScheduler(object):
         return CommandPlan(LockCache(), None).get_transition_consequences(stateful_object, new_state)
 
     def cancel_job(self, job_id):
+        self._stop_job(job_id, cancelled=True)
+
+    def fail_job(self, job_id):
+        """Fail a job that is stuck, in the same way as cancelling it but
+        leaving it errored. Anything waiting on it is cancelled.
+        """
+        self._stop_job(job_id, errored=True)
+
+    def _stop_job(self, job_id, **kwargs):
         cancelled_thread = None
 
         with self._lock:
@@ -881,7 +982,7 @@ class JobScheduler(object):
                 # let the following check for completeness happen
                 job = Job.objects.get(pk=job_id)
 
-            log.info("cancel_job: Cancelling job %s (%s)" % (job.id, job.state))
+            log.info("_stop_job: Stopping job %s (%s) %s" % (job.id, job.state, kwargs))
             if job.state == "complete":
                 return
             elif job.state == "tasked":
@@ -891,11 +992,13 @@ class JobScheduler(object):
                 except KeyError:
                     pass
                 with transaction.atomic():
-                    self._job_collection.update(job, "complete", cancelled=True)
+                    if kwargs.get("errored"):
+                        job.on_error()
+                    self._job_collection.update(job, "complete", **kwargs)
                     self._job_collection.update_commands(job)
             elif job.state == "pending":
                 with transaction.atomic():
-                    self._job_collection.update(job, "complete", cancelled=True)
+                    self._job_collection.update(job, "complete", **kwargs)
                     self._job_collection.update_commands(job)
             self._lock_cache.remove_job(job)
 
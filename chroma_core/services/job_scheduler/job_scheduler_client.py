@@ -26,6 +26,7 @@ class JobSchedulerRpc(ServiceRpcInterface):
         "set_state",
         "run_jobs",
         "cancel_job",
+        "fail_job",
         "create_host_ssh",
         "test_host_contact",
         "create_ostpool",
@@ -57,18 +58,20 @@ class JobSchedulerClient(object):
     """
 
     @classmethod
-    def command_run_jobs(cls, job_dicts, message):
+    def command_run_jobs(cls, job_dicts, message, priority=None):
         """Create and run some Jobs, within a single Command.
 
         :param job_dicts: List of 1 or more dicts like {'class_name': 'MyJobClass', 'args': {<dict of arguments to Job constructor>}}
         :param message: User-visible string describing the operation, e.g. "Detecting filesystems"
+        :param priority: One of the names in Job.PRIORITIES.  Commands users are waiting on are "high".
+                         Defaults to the default_priority of each job's class.
         :return: The ID of a new Command
 
         """
-        return JobSchedulerRpc().run_jobs(job_dicts, message)
+        return JobSchedulerRpc().run_jobs(job_dicts, message, priority=priority)
 
     @classmethod
-    def command_set_state(cls, object_ids, message, run=True):
+    def command_set_state(cls, object_ids, message, run=True, priority=None):
         """Modify the system in whatever way is necessary to reach the state
         specified in `object_ids`.  Creates Jobs under a single Command.  May create
         no Jobs if the system is already in the state, or already scheduled to be
@@ -80,10 +83,11 @@ class JobSchedulerClient(object):
         :param object_ids: List of three-tuples (natural_key, object_id, new_state)
         :param message: User-visible string describing the operation, e.g. "Starting filesystem X"
         :param run: Test only.  Schedule jobs without starting them.
+        :param priority: As for command_run_jobs
         :return: The ID of a new Command
 
         """
-        return JobSchedulerRpc().set_state(object_ids, message, run)
+        return JobSchedulerRpc().set_state(object_ids, message, run, priority=priority)
 
     @classmethod
     def available_transitions(cls, object_list):
@@ -3,7 +3,9 @@
 # license that can be found in the LICENSE file.
 
 import threading
+from collections import defaultdict
 
+from django.db import connection
 from django.db.models.signals import post_save
 from django.contrib.auth.models import User
 from django.core.mail import send_mail
@@ -15,6 +17,20 @@ from chroma_core.models.alert import AlertState, AlertEmail
 
 logging = log_register("email_alerts")
 
+# How often escalation policies are checked, in seconds
+ESCALATION_INTERVAL = 60
+
+
+def format_escalation(policy_name, after_minutes, alerts):
+    """Lay out an escalation email. `alerts` is a list of (begin, message)"""
+    lines = [
+        "These alerts have been active and unacknowledged for more than %d minutes (policy %s):"
+        % (after_minutes, policy_name)
+    ]
+    lines.extend("%s %s" % x for x in alerts)
+
+    return "\n".join(lines)
+
 
 class MailAlerts(threading.Thread):
     def __init__(self, sender, subject_prefix, host):
@@ -31,24 +47,27 @@ class MailAlerts(threading.Thread):
     def run(self):
         while self.exit is False:
             try:
-                self.change_event.wait()
+                changed = self.change_event.wait(ESCALATION_INTERVAL)
                 self.change_event.clear()
 
                 if self.exit:
                     break
 
-                alerts = AlertState.objects.filter(alertemail=None, dismissed=False)
+                if changed:
+                    alerts = AlertState.objects.filter(alertemail=None, dismissed=False)
+
+                    # Now filter the types that send mail alerts.
+                    alerts = [alert for alert in alerts if alert.require_mail_alert]
 
-                # Now filter the types that send mail alerts.
-                alerts = [alert for alert in alerts if alert.require_mail_alert]
+                    if alerts:
+                        alert_email = AlertEmail()
+                        alert_email.save()
+                        alert_email.alerts.add(*alerts)
+                        alert_email.save()
 
-                if alerts:
-                    alert_email = AlertEmail()
-                    alert_email.save()
-                    alert_email.alerts.add(*alerts)
-                    alert_email.save()
+                        self._send_alerts_email(alert_email)
 
-                    self._send_alerts_email(alert_email)
+                self._escalate()
             except Exception as exception:
                 logging.warning(str(exception))
 
@@ -77,3 +96,51 @@ class MailAlerts(threading.Thread):
             if self.host and len(alert_messages) > 0:
                 message = "New Chroma Alerts:\n" + "\n".join(alert_messages)
                 send_mail(self.subject_prefix, message, self.sender, [user.email])
+
+    def _escalate(self):
+        """Email the alerts that each enabled escalation policy has not escalated yet, once they
+        have been active and undismissed for longer than the policy allows"""
+        if not self.host:
+            return
+
+        with connection.cursor() as cursor:
+            cursor.execute(
+                """
+                SELECT p.id, p.name, p.after_minutes, p.emails, a.id
+                FROM alert_escalation_policy p
+                INNER JOIN chroma_core_alertstate a
+                    ON a.active = 't'
+                    AND a.dismissed = 'f'
+                    AND a.severity >= p.severity
+                    AND a.begin <= now() - make_interval(mins => p.after_minutes)
+                WHERE p.enabled = 't'
+                AND NOT EXISTS (SELECT 1 FROM alert_escalation e WHERE e.alert_id = a.id AND e.policy_id = p.id)
+                ORDER BY p.id, a.begin
+                """
+            )
+            rows = cursor.fetchall()
+
+        due = defaultdict(list)
+        for policy_id, name, after_minutes, emails, alert_id in rows:
+            due[(policy_id, name, after_minutes, tuple(emails))].append(alert_id)
+
+        for (policy_id, name, after_minutes, emails), alert_ids in due.items():
+            alerts = AlertState.objects.filter(id__in=alert_ids).order_by("begin")
+
+            send_mail(
+                "%s Escalated: %s" % (self.subject_prefix, name),
+                format_escalation(name, after_minutes, [(x.begin, x.message()) for x in alerts]),
+                self.sender,
+                list(emails),
+            )
+
+            with connection.cursor() as cursor:
+                for alert_id in alert_ids:
+                    cursor.execute(
+                        """
+                        INSERT INTO alert_escalation (alert_id, policy_id, policy_name, emails)
+                        VALUES (%s, %s, %s, %s)
+                        ON CONFLICT (alert_id, policy_id) DO NOTHING
+                        """,
+                        [alert_id, policy_id, name, list(emails)],
+                    )
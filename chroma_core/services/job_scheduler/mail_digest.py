@@ -0,0 +1,208 @@
+# Copyright (c) 2020 DDN. All rights reserved.
+# Use of this source code is governed by a MIT-style
+# license that can be found in the LICENSE file.
+
+import datetime
+import threading
+
+from django.contrib.auth.models import User
+from django.core.mail import send_mail
+from django.db import connection
+from django.db.models import Q
+from django.utils import timezone
+
+from chroma_core.lib.graphql import get_capacity_forecast
+from chroma_core.models.alert import AlertState
+from chroma_core.models.command import Command
+from chroma_core.models.filesystem import ManagedFilesystem
+from chroma_core.models.jobs import Job
+from chroma_core.services import log_register
+
+
+logging = log_register("email_digest")
+
+# How often to check for digests that are due, in seconds
+CHECK_INTERVAL = 60
+
+# A digest covers at most this much, however long ago the last one was sent
+MAX_PERIOD = datetime.timedelta(days=7)
+
+# Job classes whose failures are listed as snapshot failures
+SNAPSHOT_JOBS = ["createsnapshotjob", "destroysnapshotjob", "mountsnapshotjob", "unmountsnapshotjob"]
+
+# The sections of the digest of each recipient group, most privileged group first.
+# A user gets the digest of the first group they are in.
+GROUP_SECTIONS = [
+    ("superusers", ["commands", "alerts", "capacity", "snapshots"]),
+    ("filesystem_administrators", ["commands", "alerts", "capacity", "snapshots"]),
+    ("filesystem_users", ["alerts", "capacity"]),
+]
+
+
+def last_due(now, hour):
+    """The last time at or before `now` that digests were due"""
+    due = now.replace(hour=hour, minute=0, second=0, microsecond=0)
+
+    if due > now:
+        due -= datetime.timedelta(days=1)
+
+    return due
+
+
+def group_sections(group_names):
+    for name, sections in GROUP_SECTIONS:
+        if name in group_names:
+            return sections
+
+    return []
+
+
+def format_time(x):
+    return timezone.localtime(x).strftime("%Y-%m-%d %H:%M")
+
+
+def format_bytes(x):
+    for unit in ["B", "KiB", "MiB", "GiB", "TiB"]:
+        if abs(x) < 1024:
+            return "%.1f %s" % (x, unit)
+        x /= 1024.0
+
+    return "%.1f PiB" % x
+
+
+def format_capacity(fs_name, forecast):
+    if forecast is None:
+        return "%s: no capacity stats" % fs_name
+
+    used = forecast["bytes_used"]
+    total = forecast["bytes_total"]
+
+    line = "%s: %s of %s used (%.0f%%)" % (
+        fs_name,
+        format_bytes(used),
+        format_bytes(total),
+        100.0 * used / total if total else 0,
+    )
+
+    growth = forecast["growth_bytes_per_day"]
+    if growth is not None:
+        line += ", %s%s a day" % ("-" if growth < 0 else "+", format_bytes(abs(growth)))
+
+    if forecast["fill_date"]:
+        line += ", full by %s" % forecast["fill_date"][:10]
+
+    return line
+
+
+def format_digest(since, sections):
+    """Lay out the digest text. `sections` is a list of (title, lines)"""
+    body = ["Changes since %s" % format_time(since)]
+
+    for title, lines in sections:
+        body.extend(["", title])
+        body.extend(["  %s" % x for x in lines] or ["  None"])
+
+    return "\n".join(body)
+
+
+def command_lines(since):
+    commands = Command.objects.filter(created_at__gte=since)
+    failed = commands.filter(Q(errored=True) | Q(cancelled=True)).order_by("created_at")
+
+    lines = ["%d run, %d failed or cancelled" % (commands.count(), failed.count())]
+    lines.extend(
+        "%s %s (%s)" % (format_time(x.created_at), x.message, "cancelled" if x.cancelled else "failed") for x in failed
+    )
+
+    return lines
+
+
+def alert_lines(since):
+    return [
+        "%s %s%s" % (format_time(x.begin), x.message(), " (active)" if x.active else "")
+        for x in AlertState.objects.filter(begin__gte=since).order_by("begin")
+    ]
+
+
+def capacity_lines(since):
+    lines = []
+
+    for fs in ManagedFilesystem.objects.all().order_by("name"):
+        try:
+            forecast = get_capacity_forecast(fs_name=fs.name)
+        except Exception as exception:
+            logging.warning("Could not get the capacity of %s: %s" % (fs.name, exception))
+            forecast = None
+
+        lines.append(format_capacity(fs.name, forecast))
+
+    return lines
+
+
+def snapshot_lines(since):
+    jobs = Job.objects.filter(content_type__model__in=SNAPSHOT_JOBS, errored=True, modified_at__gte=since)
+
+    return ["%s %s" % (format_time(x.modified_at), x.downcast().description()) for x in jobs.order_by("modified_at")]
+
+
+SECTIONS = {
+    "commands": ("Commands", command_lines),
+    "alerts": ("New alerts", alert_lines),
+    "capacity": ("Capacity", capacity_lines),
+    "snapshots": ("Snapshot failures", snapshot_lines),
+}
+
+
+class MailDigest(threading.Thread):
+    """Emails a daily summary to each user subscribed in the digest_subscription table"""
+
+    def __init__(self, sender, subject_prefix, host, hour):
+        super(MailDigest, self).__init__()
+
+        self.sender = sender
+        self.subject_prefix = subject_prefix
+        self.host = host
+        self.hour = hour
+        self.exit_event = threading.Event()
+
+    def run(self):
+        while not self.exit_event.wait(CHECK_INTERVAL):
+            try:
+                self._send_due_digests()
+            except Exception as exception:
+                logging.warning(str(exception))
+
+    def stop(self):
+        self.exit_event.set()
+
+    def _send_due_digests(self):
+        if not self.host:
+            return
+
+        now = timezone.now()
+        due = last_due(timezone.localtime(now), self.hour)
+
+        with connection.cursor() as cursor:
+            cursor.execute(
+                "SELECT user_id, last_sent FROM digest_subscription WHERE last_sent IS NULL OR last_sent < %s", [due]
+            )
+            subscriptions = cursor.fetchall()
+
+        # Users of the same group subscribed at the same time get the same digest
+        digests = {}
+
+        for user_id, last_sent in subscriptions:
+            user = User.objects.get(id=user_id)
+            sections = group_sections(set(user.groups.values_list("name", flat=True)))
+            since = max(last_sent or now - datetime.timedelta(days=1), now - MAX_PERIOD)
+
+            if user.email and sections:
+                key = (tuple(sections), since)
+
+                if key not in digests:
+                    digests[key] = format_digest(since, [(SECTIONS[x][0], SECTIONS[x][1](since)) for x in sections])
+
+                send_mail("%s Daily digest" % self.subject_prefix, digests[key], self.sender, [user.email])
+
+            with connection.cursor() as cursor:
+                cursor.execute("UPDATE digest_subscription SET last_sent = %s WHERE user_id = %s", [now, user_id])
@@ -173,6 +173,7 @@ help_text = {
     "discovered_filesystem_with_n_MDTs_and_n_OSTs": "Discovered filesystem %s with %s MDTs and %s OSTs",
     "update_devices": "Update device info.",
     "update_packages": "Update packages.",
+    "resync_host": "Push the server profile and repo file to a server again, re-register its devices, and report how it differs from its profile.",
     "Trigger plugin poll for %s plugins": "Trigger plugin poll for %s plugins",
     "update_nids": "Update NIDs.",
     "configure_target": "Configure target mount points.",
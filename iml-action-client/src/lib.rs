@@ -9,6 +9,7 @@ use iml_manager_env::{get_action_runner_http, get_action_runner_uds, running_in_
 use iml_wire_types::{Action, ActionId, ActionName, ActionType, AgentResult, Fqdn};
 use std::{ops::Deref, sync::Arc};
 use thiserror::Error;
+use tracing::Instrument as _;
 use uuid::Uuid;
 
 #[derive(Error, Debug)]
@@ -131,8 +132,35 @@ async fn build_invoke_rust_agent(
     host: impl Into<Fqdn>,
     action: Action,
 ) -> Result<serde_json::Value, ImlActionClientError> {
-    let action = ActionType::Remote((host.into(), action));
+    let host = host.into();
+
+    let span = match &action {
+        Action::ActionStart { action, id, .. } => tracing::info_span!(
+            "action_runner_rpc",
+            otel.kind = "client",
+            fqdn = %host,
+            action = %action,
+            id = %id.0
+        ),
+        Action::ActionCancel { id } => tracing::info_span!(
+            "action_runner_rpc",
+            otel.kind = "client",
+            fqdn = %host,
+            action = "cancel",
+            id = %id.0
+        ),
+    };
+
+    send(client, uri, ActionType::Remote((host, action)))
+        .instrument(span)
+        .await
+}
 
+async fn send(
+    client: Arc<ClientInner>,
+    uri: Arc<hyper::Uri>,
+    action: ActionType,
+) -> Result<serde_json::Value, ImlActionClientError> {
     let req = Request::builder()
         .method(hyper::Method::POST)
         .header(hyper::header::ACCEPT, "application/json")
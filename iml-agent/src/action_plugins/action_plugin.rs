@@ -4,13 +4,15 @@
 
 use crate::{
     action_plugins::{
-        check_kernel, check_stonith, firewall_cmd, high_availability, kernel_module, lamigo, ldev,
-        lpurge, lustre,
+        certificate, check_kernel, check_stonith, dne, firewall_cmd, high_availability,
+        kernel_module, lamigo, ldev, lnet_ping, lpurge, lustre, multipath,
         ntp::{action_configure, is_ntp_configured},
-        ostpool, package, postoffice,
+        ostpool, package, postoffice, ssk, stonith,
         stratagem::{
-            action_cloudsync, action_filesync, action_mirror, action_purge, action_warning, server,
+            action_cloudsync, action_filesync, action_heatmap, action_migrate, action_mirror,
+            action_project_usage, action_purge, action_purge_candidates, action_warning, server,
         },
+        stripe, tuning,
     },
     lustre::lctl,
 };
@@ -72,6 +74,19 @@ pub fn create_registry() -> action_plugins::Actions {
             "change_mcast_port",
             high_availability::corosync_conf::change_mcast_port,
         )
+        .add_plugin(
+            "configure_corosync_cluster",
+            high_availability::corosync_conf::configure_cluster,
+        )
+        .add_plugin(
+            "corosync_ring_status",
+            high_availability::corosync_conf::ring_status,
+        )
+        .add_plugin(
+            "restore_corosync_conf",
+            high_availability::corosync_conf::restore_corosync_conf,
+        )
+        .add_plugin("restore_cib", high_availability::restore_cib)
         .add_plugin("add_firewall_port", firewall_cmd::add_port)
         .add_plugin("remove_firewall_port", firewall_cmd::remove_port)
         .add_plugin("pcs", high_availability::pcs)
@@ -85,6 +100,8 @@ pub fn create_registry() -> action_plugins::Actions {
         .add_plugin("snapshot_destroy", lustre::snapshot::destroy)
         .add_plugin("snapshot_mount", lustre::snapshot::mount)
         .add_plugin("snapshot_unmount", lustre::snapshot::unmount)
+        .add_plugin("snapshot_barrier_stat", lustre::snapshot::barrier_stat)
+        .add_plugin("recovery_status", lustre::recovery::recovery_status)
         .add_plugin("postoffice_add", postoffice::route_add)
         .add_plugin("postoffice_remove", postoffice::route_remove)
         .add_plugin(
@@ -93,6 +110,23 @@ pub fn create_registry() -> action_plugins::Actions {
         )
         .add_plugin("is_ntp_configured", is_ntp_configured::is_ntp_configured)
         .add_plugin("create_ldev_conf", ldev::create)
+        .add_plugin("ssk_generate", ssk::generate_key)
+        .add_plugin("ssk_install", ssk::install_key)
+        .add_plugin("apply_tuning", tuning::apply)
+        .add_plugin("read_tuning", tuning::read)
+        .add_plugin("certificate_csr", certificate::create_csr)
+        .add_plugin("certificate_install", certificate::install)
+        .add_plugin("certificate_serial", certificate::serial)
+        .add_plugin("multipath_paths", multipath::paths)
+        .add_plugin("lnet_ping", lnet_ping::ping)
+        .add_plugin("get_default_dir_stripe", dne::get_default_dir_stripe)
+        .add_plugin("set_default_dir_stripe", dne::set_default_dir_stripe)
+        .add_plugin("format_target", lustre::format::format_target)
+        .add_plugin("create_mountpoint", lustre::format::create_mountpoint)
+        .add_plugin("get_default_stripe", stripe::get_default_stripe)
+        .add_plugin("set_default_stripe", stripe::set_default_stripe)
+        .add_plugin("get_stonith_config", stonith::get_stonith_config)
+        .add_plugin("stonith_test", stonith::stonith_test)
         // HotPools
         .add_plugin("create_lpurge_conf", lpurge::create_lpurge_conf)
         .add_plugin("create_lamigo_conf", lamigo::create_lamigo_conf)
@@ -103,7 +137,17 @@ pub fn create_registry() -> action_plugins::Actions {
         .add_plugin("action.stratagem.warning", action_warning::process_fids)
         .add_plugin("action.stratagem.purge", action_purge::process_fids)
         .add_plugin("action.stratagem.filesync", action_filesync::process_fids)
-        .add_plugin("action.stratagem.cloudsync", action_cloudsync::process_fids);
+        .add_plugin("action.stratagem.cloudsync", action_cloudsync::process_fids)
+        .add_plugin("action.stratagem.migrate", action_migrate::process_fids)
+        .add_plugin(
+            "action.stratagem.project_usage",
+            action_project_usage::process_fids,
+        )
+        .add_plugin("action.stratagem.heatmap", action_heatmap::process_fids)
+        .add_plugin(
+            "action.stratagem.purge_candidates",
+            action_purge_candidates::process_fids,
+        );
     info!("Loaded the following ActionPlugins:");
 
     for ActionName(key) in map.keys() {
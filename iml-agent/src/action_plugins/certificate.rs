@@ -0,0 +1,144 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Rotation of the certificate the agent identifies itself to the manager with.
+//!
+//! The manager asks for a CSR for a new key, signs it with its CA and hands back the certificate
+//! to install. The agent daemons are restarted to pick up the new pair, after which the manager
+//! checks the serial in use before revoking the old certificate.
+
+use crate::{agent_error::ImlAgentError, env};
+use futures::TryFutureExt;
+use iml_cmd::{CheckedChildExt, CheckedCommandExt, Command};
+use std::{io, os::unix::fs::PermissionsExt, process::Stdio};
+use tokio::{fs, io::AsyncWriteExt};
+
+/// How long after `install` answers the agent daemons are restarted.
+/// This leaves time for the answer to reach the manager.
+static RESTART_DELAY: &str = "--on-active=5";
+
+async fn openssl(args: &[&str]) -> Result<String, ImlAgentError> {
+    Command::new("/usr/bin/openssl")
+        .args(args)
+        .kill_on_drop(true)
+        .checked_output()
+        .err_into()
+        .await
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+}
+
+fn pending(path: &str) -> String {
+    format!("{}.new", path)
+}
+
+/// Creates a new private key next to the current one and returns a CSR for it.
+pub async fn create_csr(fqdn: String) -> Result<String, ImlAgentError> {
+    let key = pending(&env::get_private_pem_path());
+
+    openssl(&["genrsa", "-out", &key, "2048"]).await?;
+
+    fs::set_permissions(&key, PermissionsExt::from_mode(0o600)).await?;
+
+    openssl(&[
+        "req",
+        "-new",
+        "-sha256",
+        "-subj",
+        &format!("/C=AA/ST=AA/L=Location/O=Org/CN={}", fqdn),
+        "-key",
+        &key,
+    ])
+    .await
+}
+
+/// Swaps in the key created by `create_csr` along with its signed `certificate`,
+/// then schedules a restart of the agent daemons so they use the new pair.
+pub async fn install(certificate: String) -> Result<(), ImlAgentError> {
+    let key = env::get_private_pem_path();
+    let crt = env::get_cert_path();
+
+    let new_key = pending(&key);
+    let new_crt = pending(&crt);
+
+    fs::write(&new_crt, &certificate).await?;
+
+    let key_modulus = openssl(&["rsa", "-noout", "-modulus", "-in", &new_key]).await?;
+    let crt_modulus = openssl(&["x509", "-noout", "-modulus", "-in", &new_crt]).await?;
+
+    if key_modulus != crt_modulus {
+        fs::remove_file(&new_crt).await?;
+
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The certificate does not match the pending key",
+        )
+        .into());
+    }
+
+    fs::rename(&new_key, &key).await?;
+    fs::rename(&new_crt, &crt).await?;
+
+    Command::new("/usr/bin/systemd-run")
+        .args(&[
+            RESTART_DELAY,
+            "/usr/bin/systemctl",
+            "restart",
+            "iml-storage-server.target",
+        ])
+        .kill_on_drop(true)
+        .checked_status()
+        .err_into()
+        .await
+}
+
+/// The serial of the certificate this daemon loaded at startup.
+pub async fn serial(_: ()) -> Result<String, ImlAgentError> {
+    let mut child = Command::new("/usr/bin/openssl")
+        .args(&["x509", "-noout", "-serial"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // `PEM` holds the key followed by the certificate, openssl skips to the certificate.
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&env::PEM).await?;
+    }
+
+    let output = child.wait_with_checked_output().await?;
+
+    parse_serial(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Could not read the certificate serial",
+        )
+        .into()
+    })
+}
+
+/// Parses `serial=0A1B` as printed by `openssl x509 -serial`.
+fn parse_serial(x: &str) -> Option<String> {
+    let x = x.trim().strip_prefix("serial=")?;
+
+    if x.is_empty() {
+        None
+    } else {
+        Some(x.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_serial() {
+        assert_eq!(
+            parse_serial("serial=D8E8FCA2DC0F896F\n"),
+            Some("D8E8FCA2DC0F896F".to_string())
+        );
+        assert_eq!(parse_serial("serial=\n"), None);
+        assert_eq!(parse_serial("unable to load certificate\n"), None);
+    }
+}
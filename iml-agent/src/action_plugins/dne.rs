@@ -0,0 +1,105 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Reads and sets the default striping of new directories over the MDTs of a filesystem.
+//! Both run on a client that has the filesystem mounted.
+
+use crate::{agent_error::ImlAgentError, lustre::search_rootpath};
+use iml_cmd::{CheckedCommandExt, Command};
+use iml_wire_types::dne::{DirStripe, SetDirStripe};
+
+pub(crate) async fn lfs(args: Vec<String>) -> Result<String, ImlAgentError> {
+    let x = Command::new("/usr/bin/lfs")
+        .args(args)
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    Ok(String::from_utf8_lossy(&x.stdout).to_string())
+}
+
+/// The default striping of directories created in the root of `filesystem`.
+pub async fn get_default_dir_stripe(filesystem: String) -> Result<DirStripe, ImlAgentError> {
+    let mntpt = search_rootpath(filesystem).await?.mntpt();
+
+    let x = lfs(vec!["getdirstripe".into(), "-D".into(), mntpt]).await?;
+
+    Ok(parse_dir_stripe(&x))
+}
+
+/// Sets the default striping of directories created in the root of a filesystem.
+/// It is inherited by the directories created below them.
+pub async fn set_default_dir_stripe(x: SetDirStripe) -> Result<(), ImlAgentError> {
+    let mntpt = search_rootpath(x.filesystem).await?.mntpt();
+
+    let mut args = vec![
+        "setdirstripe".to_string(),
+        "-D".into(),
+        "-c".into(),
+        x.stripe_count.to_string(),
+        "-i".into(),
+        x.stripe_offset.to_string(),
+    ];
+
+    if let Some(hash_type) = x.hash_type {
+        args.push("-H".into());
+        args.push(hash_type);
+    }
+
+    args.push(mntpt);
+
+    lfs(args).await.map(drop)
+}
+
+/// Parses `lfs getdirstripe -D`, e.g.
+/// `lmv_stripe_count: 2 lmv_stripe_offset: -1 lmv_hash_type: fnv_1a_64 lmv_max_inherit: 3`.
+/// Values that are missing are the Lustre defaults, a single MDT chosen by Lustre.
+fn parse_dir_stripe(output: &str) -> DirStripe {
+    let mut x = DirStripe {
+        stripe_count: 0,
+        stripe_offset: -1,
+        hash_type: "none".into(),
+    };
+
+    let tokens: Vec<_> = output.split_whitespace().collect();
+
+    for kv in tokens.windows(2) {
+        match (kv[0], kv[1]) {
+            ("lmv_stripe_count:", v) => x.stripe_count = v.parse().unwrap_or(x.stripe_count),
+            ("lmv_stripe_offset:", v) => x.stripe_offset = v.parse().unwrap_or(x.stripe_offset),
+            ("lmv_hash_type:", v) => x.hash_type = v.to_string(),
+            _ => {}
+        }
+    }
+
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dir_stripe() {
+        assert_eq!(
+            parse_dir_stripe(
+                "lmv_stripe_count: 2 lmv_stripe_offset: 1 lmv_hash_type: fnv_1a_64 lmv_max_inherit: 3 lmv_max_inherit_rr: 0\n"
+            ),
+            DirStripe {
+                stripe_count: 2,
+                stripe_offset: 1,
+                hash_type: "fnv_1a_64".into(),
+            }
+        );
+
+        assert_eq!(
+            parse_dir_stripe(""),
+            DirStripe {
+                stripe_count: 0,
+                stripe_offset: -1,
+                hash_type: "none".into(),
+            }
+        );
+    }
+}
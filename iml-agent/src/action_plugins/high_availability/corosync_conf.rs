@@ -3,7 +3,8 @@
 // license that can be found in the LICENSE file.
 
 use crate::agent_error::ImlAgentError;
-use iml_cmd::{CheckedCommandExt, Command};
+use iml_cmd::{CheckedCommandExt, CmdError, Command};
+use iml_wire_types::CorosyncRing;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::{collections::HashMap, convert::TryFrom, io};
@@ -12,7 +13,7 @@ use tokio::{fs::OpenOptions, io::AsyncWriteExt};
 static COROSYNC_CONF: &str = "/etc/corosync/corosync.conf";
 
 /// Reads the corosync configuration.
-async fn read_corosync_conf() -> Result<Vec<u8>, io::Error> {
+pub(crate) async fn read_corosync_conf() -> Result<Vec<u8>, io::Error> {
     iml_fs::read_file_to_end(COROSYNC_CONF).await
 }
 
@@ -20,6 +21,7 @@ async fn read_corosync_conf() -> Result<Vec<u8>, io::Error> {
 async fn write_corosync_conf(xs: &[u8]) -> Result<(), ImlAgentError> {
     OpenOptions::new()
         .write(true)
+        .create(true)
         .truncate(true)
         .open(COROSYNC_CONF)
         .await?
@@ -85,6 +87,200 @@ pub(crate) async fn change_mcast_port(new_port: u16) -> Result<(), ImlAgentError
     Ok(())
 }
 
+/// A member of a new cluster.
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct ClusterNode {
+    name: String,
+    ring0_addr: String,
+    ring1_addr: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct ClusterConfig {
+    cluster_name: String,
+    mcast_port: u16,
+    nodes: Vec<ClusterNode>,
+}
+
+/// Renders the corosync.conf of a new cluster, as seen by the node `local`.
+///
+/// Nodes talk unicast (`udpu`), so nothing depends on multicast routing between them.
+/// Like the confs written by autoconfiguration, each interface binds to the local address of its ring.
+fn render_corosync_conf(config: &ClusterConfig, local: &str) -> Result<String, ImlAgentError> {
+    let node = config
+        .nodes
+        .iter()
+        .find(|x| x.name == local)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not a member of {}", local, config.cluster_name),
+            )
+        })?;
+
+    let redundant = config.nodes.iter().all(|x| x.ring1_addr.is_some());
+
+    let mut xs = vec![
+        "totem {".to_string(),
+        "    version: 2".into(),
+        format!("    cluster_name: {}", config.cluster_name),
+        "    secauth: off".into(),
+        "    transport: udpu".into(),
+        "    config_version: 1".into(),
+    ];
+
+    if redundant {
+        xs.push("    rrp_mode: passive".into());
+    }
+
+    xs.push("    token: 17000".into());
+    xs.push("    fail_recv_const: 10".into());
+
+    let rings = std::iter::once(Some(&node.ring0_addr))
+        .chain(std::iter::once(
+            node.ring1_addr.as_ref().filter(|_| redundant),
+        ))
+        .flatten();
+
+    for (ringnumber, addr) in rings.enumerate() {
+        xs.extend(vec![
+            "".into(),
+            "    interface {".into(),
+            format!("        ringnumber: {}", ringnumber),
+            format!("        bindnetaddr: {}", addr),
+            format!("        mcastport: {}", config.mcast_port),
+            "    }".into(),
+        ]);
+    }
+
+    xs.extend(vec!["}".into(), "".into(), "nodelist {".into()]);
+
+    for (idx, x) in config.nodes.iter().enumerate() {
+        if idx > 0 {
+            xs.push("".into());
+        }
+
+        xs.push("    node {".into());
+        xs.push(format!("        ring0_addr: {}", x.ring0_addr));
+
+        if let Some(addr) = x.ring1_addr.as_ref().filter(|_| redundant) {
+            xs.push(format!("        ring1_addr: {}", addr));
+        }
+
+        xs.push(format!("        name: {}", x.name));
+        xs.push(format!("        nodeid: {}", idx + 1));
+        xs.push("    }".into());
+    }
+
+    xs.extend(vec![
+        "}".into(),
+        "".into(),
+        "quorum {".into(),
+        "    provider: corosync_votequorum".into(),
+    ]);
+
+    if config.nodes.len() == 2 {
+        xs.push("    two_node: 1".into());
+    }
+
+    xs.extend(vec![
+        "}".into(),
+        "".into(),
+        "logging {".into(),
+        "    to_logfile: yes".into(),
+        "    logfile: /var/log/cluster/corosync.log".into(),
+        "    to_syslog: yes".into(),
+        "}".into(),
+    ]);
+
+    Ok(xs.join("\n"))
+}
+
+/// Writes the corosync.conf of a new cluster, replacing any existing one.
+/// `local` is the name of this node within the cluster.
+pub(crate) async fn configure_cluster(
+    (config, local): (ClusterConfig, String),
+) -> Result<(), ImlAgentError> {
+    let conf = render_corosync_conf(&config, &local)?;
+
+    write_corosync_conf(conf.as_bytes()).await
+}
+
+/// Puts back a corosync.conf changed outside of IML and has corosync reload it.
+pub(crate) async fn restore_corosync_conf(conf: String) -> Result<(), ImlAgentError> {
+    write_corosync_conf(conf.as_bytes()).await?;
+
+    Command::new("corosync-cfgtool")
+        .arg("-R")
+        .kill_on_drop(true)
+        .checked_status()
+        .await?;
+
+    Ok(())
+}
+
+/// The state of the local corosync rings, from `corosync-cfgtool -s`.
+pub(crate) async fn ring_status(_: ()) -> Result<Vec<CorosyncRing>, ImlAgentError> {
+    // Some corosync versions exit non-zero when a ring is faulty,
+    // so only fail if nothing could be parsed.
+    let x = Command::new("corosync-cfgtool")
+        .arg("-s")
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    let xs = parse_ring_status(&String::from_utf8_lossy(&x.stdout));
+
+    if xs.is_empty() && !x.status.success() {
+        return Err(CmdError::from(x).into());
+    }
+
+    Ok(xs)
+}
+
+/// Parses the output of `corosync-cfgtool -s`, e.g.
+///
+/// ```text
+/// Printing ring status.
+/// Local node ID 1
+/// RING ID 0
+///         id      = 10.128.0.21
+///         status  = ring 0 active with no faults
+/// ```
+fn parse_ring_status(x: &str) -> Vec<CorosyncRing> {
+    x.lines()
+        .map(str::trim)
+        .fold(vec![], |mut xs: Vec<CorosyncRing>, l| {
+            if let Some(ring) = l.strip_prefix("RING ID ") {
+                if let Ok(ring) = ring.trim().parse() {
+                    xs.push(CorosyncRing {
+                        ring,
+                        address: String::new(),
+                        status: String::new(),
+                        faulty: true,
+                    });
+                }
+
+                return xs;
+            }
+
+            let mut kv = l.splitn(2, '=').map(str::trim);
+
+            match (xs.last_mut(), kv.next(), kv.next()) {
+                (Some(x), Some("id"), Some(v)) => {
+                    x.address = v.to_string();
+                }
+                (Some(x), Some("status"), Some(v)) => {
+                    x.status = v.to_string();
+                    x.faulty = !v.contains("no faults");
+                }
+                _ => {}
+            }
+
+            xs
+        })
+}
+
 type StringMap<'a> = HashMap<&'a str, &'a str>;
 
 #[derive(Debug)]
@@ -203,7 +399,11 @@ fn try_get<'a>(hm: &'a StringMap<'a>, k: &str) -> Result<&'a &'a str, ImlAgentEr
 
 #[cfg(test)]
 mod tests {
-    use super::{corosync_cmapctl_parser, update_config_version, update_mcast_port};
+    use super::{
+        corosync_cmapctl_parser, parse_ring_status, render_corosync_conf, update_config_version,
+        update_mcast_port, ClusterConfig,
+    };
+    use iml_wire_types::CorosyncRing;
     use std::iter::FromIterator;
 
     fn corosync_cmapctl_fixture() -> &'static [u8] {
@@ -214,6 +414,10 @@ mod tests {
         include_bytes!("../fixtures/corosync.conf")
     }
 
+    fn corosync_new_cluster_fixture() -> &'static [u8] {
+        include_bytes!("../fixtures/corosync-new-cluster.conf")
+    }
+
     #[test]
     fn test_corosync_cmapctl_output() -> Result<(), Box<dyn std::error::Error>> {
         let x = corosync_cmapctl_fixture();
@@ -258,4 +462,81 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_render_corosync_conf() -> Result<(), Box<dyn std::error::Error>> {
+        let config: ClusterConfig = serde_json::from_value(serde_json::json!({
+            "cluster_name": "lustre-ha-cluster",
+            "mcast_port": 40015,
+            "nodes": [
+                { "name": "oss1", "ring0_addr": "10.128.0.21", "ring1_addr": "10.73.10.21" },
+                { "name": "oss2", "ring0_addr": "10.128.0.22", "ring1_addr": "10.73.10.22" },
+            ]
+        }))?;
+
+        let conf = render_corosync_conf(&config, "oss2")?;
+
+        assert_eq!(
+            conf,
+            std::str::from_utf8(corosync_new_cluster_fixture())?.trim()
+        );
+
+        assert!(render_corosync_conf(&config, "oss3").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_corosync_conf_single_ring() -> Result<(), Box<dyn std::error::Error>> {
+        let config: ClusterConfig = serde_json::from_value(serde_json::json!({
+            "cluster_name": "lustre-ha-cluster",
+            "mcast_port": 40015,
+            "nodes": [
+                { "name": "oss1", "ring0_addr": "10.128.0.21", "ring1_addr": "10.73.10.21" },
+                { "name": "oss2", "ring0_addr": "10.128.0.22" },
+            ]
+        }))?;
+
+        let conf = render_corosync_conf(&config, "oss1")?;
+
+        assert!(!conf.contains("rrp_mode"));
+        assert!(!conf.contains("ring1_addr"));
+        assert!(!conf.contains("ringnumber: 1"));
+        assert!(conf.contains("two_node: 1"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_ring_status() {
+        let x = "Printing ring status.
+Local node ID 1
+RING ID 0
+\tid\t= 10.128.0.21
+\tstatus\t= ring 0 active with no faults
+RING ID 1
+\tid\t= 10.73.10.21
+\tstatus\t= Marking ringid 1 interface 10.73.10.21 FAULTY
+";
+
+        assert_eq!(
+            parse_ring_status(x),
+            vec![
+                CorosyncRing {
+                    ring: 0,
+                    address: "10.128.0.21".into(),
+                    status: "ring 0 active with no faults".into(),
+                    faulty: false,
+                },
+                CorosyncRing {
+                    ring: 1,
+                    address: "10.73.10.21".into(),
+                    status: "Marking ringid 1 interface 10.73.10.21 FAULTY".into(),
+                    faulty: true,
+                }
+            ]
+        );
+
+        assert!(parse_ring_status("Could not initialize corosync configuration API").is_empty());
+    }
 }
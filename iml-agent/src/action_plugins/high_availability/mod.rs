@@ -8,7 +8,8 @@ pub(crate) mod pacemaker;
 use crate::{
     agent_error::{ImlAgentError, RequiredError},
     high_availability::{
-        cibcreate, cibxpath, crm_mon_cmd, crm_resource, read_crm_output, set_resource_role,
+        cibcreate, cibxpath, crm_mon_cmd, crm_resource, get_cib_configuration, merge_transient,
+        read_crm_output, replace_cib_configuration, set_resource_role,
     },
 };
 use elementtree::Element;
@@ -654,6 +655,14 @@ pub async fn move_resource((resource, dest_host): (String, String)) -> Result<()
     rc
 }
 
+/// Replaces the CIB configuration with `cib`, as last reported to IML.
+/// Bans and moves in effect are kept, and pacemaker adds back the options it maintains itself.
+pub async fn restore_cib(cib: String) -> Result<(), ImlAgentError> {
+    let current = get_cib_configuration().await?;
+
+    replace_cib_configuration(&merge_transient(&cib, &current)).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::{process_resource, PacemakerOperations, ResourceAgentInfo, ResourceAgentType};
@@ -0,0 +1,98 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Pings NIDs over LNet for a ping mesh, timing each round trip.
+
+use crate::{agent_error::ImlAgentError, lustre::lctl};
+use futures::future::join_all;
+use iml_wire_types::lnet_ping::{LnetPingArgs, LnetPingResult};
+use std::time::{Duration, Instant};
+
+/// How long `lctl ping` waits for an answer, in seconds.
+const TIMEOUT_SECS: &str = "5";
+
+async fn ping_once(nid: &str) -> Result<Duration, String> {
+    let start = Instant::now();
+
+    lctl(vec!["ping", nid, TIMEOUT_SECS])
+        .await
+        .map(|_| start.elapsed())
+        .map_err(|e| e.to_string())
+}
+
+/// Sums up the round trips of the pings of `nid`, `Err` for the ones that went unanswered.
+fn summarize(nid: String, samples: &[Result<Duration, String>]) -> LnetPingResult {
+    let answered: Vec<f64> = samples
+        .iter()
+        .filter_map(|x| x.as_ref().ok())
+        .map(|x| x.as_secs_f64() * 1000.)
+        .collect();
+
+    let avg_latency_ms = if answered.is_empty() {
+        None
+    } else {
+        Some(answered.iter().sum::<f64>() / answered.len() as f64)
+    };
+
+    let max_latency_ms = answered.iter().copied().fold(None, |acc: Option<f64>, x| {
+        Some(acc.map_or(x, |acc| acc.max(x)))
+    });
+
+    LnetPingResult {
+        nid,
+        sent: samples.len() as u32,
+        received: answered.len() as u32,
+        avg_latency_ms,
+        max_latency_ms,
+        error: samples.iter().rev().find_map(|x| x.clone().err()),
+    }
+}
+
+/// Pings each NID of `args` `count` times.
+/// NIDs are pinged at the same time, the pings of a single NID one after the other.
+pub async fn ping(args: LnetPingArgs) -> Result<Vec<LnetPingResult>, ImlAgentError> {
+    let count = args.count;
+
+    let xs = args.nids.into_iter().map(|nid| async move {
+        let mut samples = vec![];
+
+        for _ in 0..count {
+            samples.push(ping_once(&nid).await);
+        }
+
+        summarize(nid, &samples)
+    });
+
+    Ok(join_all(xs).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let x = summarize(
+            "10.0.0.1@tcp".into(),
+            &[
+                Ok(Duration::from_millis(2)),
+                Err("timed out".into()),
+                Ok(Duration::from_millis(4)),
+            ],
+        );
+
+        assert_eq!(x.sent, 3);
+        assert_eq!(x.received, 2);
+        assert_eq!(x.avg_latency_ms, Some(3.));
+        assert_eq!(x.max_latency_ms, Some(4.));
+        assert_eq!(x.error.as_deref(), Some("timed out"));
+
+        let x = summarize("10.0.0.1@tcp".into(), &[Err("a".into()), Err("b".into())]);
+
+        assert_eq!(x.received, 0);
+        assert_eq!(x.avg_latency_ms, None);
+        assert_eq!(x.max_latency_ms, None);
+        assert_eq!(x.error.as_deref(), Some("b"));
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Formats the targets that are added to existing filesystems.
+
+use crate::agent_error::ImlAgentError;
+use iml_cmd::{CheckedCommandExt, Command};
+use iml_wire_types::{db::TargetKind, new_target::FormatTarget};
+use tokio::fs;
+
+fn mkfs_args(x: &FormatTarget) -> Vec<String> {
+    let kind = match x.kind {
+        TargetKind::Mgt => "--mgs",
+        TargetKind::Mdt => "--mdt",
+        TargetKind::Ost => "--ost",
+    };
+
+    let mut args = vec![
+        kind.to_string(),
+        format!("--fsname={}", x.filesystem),
+        format!("--index={}", x.index),
+    ];
+
+    args.extend(x.mgs_nids.iter().map(|nids| format!("--mgsnode={}", nids)));
+
+    args.push(x.device.clone());
+
+    args
+}
+
+/// Formats a new target of an existing filesystem, and creates the directory it is mounted on.
+/// The target registers with the MGS the first time it is mounted.
+pub async fn format_target(x: FormatTarget) -> Result<(), ImlAgentError> {
+    Command::new("/usr/sbin/mkfs.lustre")
+        .args(mkfs_args(&x))
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    create_mountpoint(x.mountpoint).await
+}
+
+/// Creates the directory a target is mounted on, on a server it can fail over to.
+pub async fn create_mountpoint(mountpoint: String) -> Result<(), ImlAgentError> {
+    fs::create_dir_all(mountpoint).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mkfs_args() {
+        let x = FormatTarget {
+            kind: TargetKind::Mdt,
+            filesystem: "fs".into(),
+            index: 2,
+            device: "/dev/mapper/mpathd".into(),
+            mgs_nids: vec!["10.0.0.1@tcp".into(), "10.0.0.2@tcp,10.1.0.2@o2ib".into()],
+            mountpoint: "/mnt/fs-MDT0002".into(),
+        };
+
+        assert_eq!(
+            mkfs_args(&x),
+            vec![
+                "--mdt",
+                "--fsname=fs",
+                "--index=2",
+                "--mgsnode=10.0.0.1@tcp",
+                "--mgsnode=10.0.0.2@tcp,10.1.0.2@o2ib",
+                "/dev/mapper/mpathd",
+            ]
+        );
+
+        let x = FormatTarget {
+            kind: TargetKind::Ost,
+            index: 10,
+            device: "/dev/mapper/mpathe".into(),
+            mgs_nids: vec!["10.0.0.1@tcp".into()],
+            mountpoint: "/mnt/fs-OST000a".into(),
+            ..x
+        };
+
+        assert_eq!(
+            mkfs_args(&x),
+            vec![
+                "--ost",
+                "--fsname=fs",
+                "--index=10",
+                "--mgsnode=10.0.0.1@tcp",
+                "/dev/mapper/mpathe",
+            ]
+        );
+    }
+}
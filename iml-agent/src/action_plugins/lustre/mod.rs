@@ -3,4 +3,6 @@
 // license that can be found in the LICENSE file.
 
 pub mod client;
+pub mod format;
+pub mod recovery;
 pub mod snapshot;
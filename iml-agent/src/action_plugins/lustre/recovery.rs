@@ -0,0 +1,137 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Reports the recovery state of the targets mounted on this server.
+
+use crate::{agent_error::ImlAgentError, lustre::lctl};
+use iml_wire_types::recovery::TargetRecovery;
+
+/// The `recovery_status` of every MDT and OST mounted here.
+/// The MGS has no recovery and is not listed.
+pub async fn recovery_status(_: ()) -> Result<Vec<TargetRecovery>, ImlAgentError> {
+    let mut xs = vec![];
+
+    for param in &["mdt.*.recovery_status", "obdfilter.*.recovery_status"] {
+        // lctl fails when no target of the kind is mounted
+        if let Ok(x) = lctl(vec!["get_param", param]).await {
+            xs.extend(parse_recovery_status(&x));
+        }
+    }
+
+    Ok(xs)
+}
+
+/// Parses `lctl get_param <type>.*.recovery_status`. Each target starts with a
+/// `<type>.<target>.recovery_status=` line, followed by `key: value` lines.
+fn parse_recovery_status(output: &str) -> Vec<TargetRecovery> {
+    let mut xs: Vec<TargetRecovery> = vec![];
+
+    for l in output.lines() {
+        if let Some(param) = l.trim().strip_suffix(".recovery_status=") {
+            if let Some(target) = param.splitn(2, '.').nth(1) {
+                xs.push(TargetRecovery {
+                    target: target.to_string(),
+                    status: String::new(),
+                    time_remaining: None,
+                    connected_clients: None,
+                    total_clients: None,
+                    completed_clients: None,
+                    evicted_clients: None,
+                });
+            }
+
+            continue;
+        }
+
+        let x = match xs.last_mut() {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let mut kv = l.splitn(2, ':');
+
+        let (key, value) = match (kv.next(), kv.next()) {
+            (Some(k), Some(v)) => (k.trim(), v.trim()),
+            _ => continue,
+        };
+
+        // Counts are printed as `done/total` while recovering
+        let mut parts = value.splitn(2, '/');
+
+        let count = parts.next().and_then(|x| x.parse().ok());
+        let total = parts.next().and_then(|x| x.parse().ok());
+
+        match key {
+            "status" => x.status = value.to_string(),
+            "time_remaining" => x.time_remaining = count,
+            "connected_clients" => {
+                x.connected_clients = count;
+                x.total_clients = total.or(x.total_clients);
+            }
+            "completed_clients" => {
+                x.completed_clients = count;
+                x.total_clients = total.or(x.total_clients);
+            }
+            "evicted_clients" => x.evicted_clients = count,
+            _ => {}
+        }
+    }
+
+    xs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recovery_status() {
+        let output = r#"obdfilter.fs-OST0000.recovery_status=
+status: RECOVERING
+recovery_start: 1611660000
+time_remaining: 120
+connected_clients: 1/3
+req_replay_clients: 0
+lock_repay_clients: 0
+completed_clients: 1
+evicted_clients: 0
+replayed_requests: 0
+queued_requests: 0
+next_transno: 4294967298
+obdfilter.fs-OST0001.recovery_status=
+status: COMPLETE
+recovery_start: 1611650000
+recovery_duration: 65
+completed_clients: 3/3
+replayed_requests: 0
+last_transno: 4294967296
+VBR: DISABLED
+IR: DISABLED
+"#;
+
+        assert_eq!(
+            parse_recovery_status(output),
+            vec![
+                TargetRecovery {
+                    target: "fs-OST0000".into(),
+                    status: "RECOVERING".into(),
+                    time_remaining: Some(120),
+                    connected_clients: Some(1),
+                    total_clients: Some(3),
+                    completed_clients: Some(1),
+                    evicted_clients: Some(0),
+                },
+                TargetRecovery {
+                    target: "fs-OST0001".into(),
+                    status: "COMPLETE".into(),
+                    time_remaining: None,
+                    connected_clients: None,
+                    total_clients: Some(3),
+                    completed_clients: Some(3),
+                    evicted_clients: None,
+                },
+            ]
+        );
+    }
+}
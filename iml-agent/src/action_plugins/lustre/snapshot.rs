@@ -2,7 +2,11 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::{agent_error::ImlAgentError, device_scanner_client, lustre::lctl};
+use crate::{
+    agent_error::{ImlAgentError, RequiredError},
+    device_scanner_client,
+    lustre::lctl,
+};
 use chrono::{DateTime, TimeZone, Utc};
 use futures::future::try_join_all;
 use iml_wire_types::snapshot::{Create, Destroy, List, Mount, Snapshot, Unmount};
@@ -87,6 +91,26 @@ pub async fn unmount(u: Unmount) -> Result<(), ImlAgentError> {
     lctl(args).await.map(drop)
 }
 
+/// The state of the write barrier of `fsname`, e.g. `init`, `frozen` or `thawed`.
+/// Fails when the MGS does not support barriers.
+pub async fn barrier_stat(fsname: String) -> Result<String, ImlAgentError> {
+    let stdout = lctl(&["barrier_stat", fsname.as_str()]).await?;
+
+    parse_barrier_state(&stdout).ok_or_else(|| {
+        RequiredError(format!("Unexpected barrier_stat output: {}", stdout.trim())).into()
+    })
+}
+
+/// Parses `lctl barrier_stat`, e.g.
+/// `state: frozen` followed by `timeout: 17 seconds`.
+fn parse_barrier_state(output: &str) -> Option<String> {
+    output.lines().find_map(|x| {
+        let state = x.trim().strip_prefix("state:")?.trim();
+
+        Some(state.to_string()).filter(|x| !x.is_empty())
+    })
+}
+
 async fn build_snapshot(
     target: &str,
     snapshot_name: String,
@@ -132,6 +156,9 @@ async fn build_snapshot(
         comment,
         snapshot_fsname,
         mounted,
+        // Holds and policy runs are only known to the manager
+        held: false,
+        policy_run_id: None,
     }))
 }
 
@@ -173,6 +200,19 @@ fn parse_device_snapshots(x: &str) -> Vec<DeviceSnapshots> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_barrier_state() {
+        assert_eq!(
+            parse_barrier_state("state: frozen\ntimeout: 17 seconds\n"),
+            Some("frozen".to_string())
+        );
+        assert_eq!(
+            parse_barrier_state("state: thawed\n"),
+            Some("thawed".to_string())
+        );
+        assert_eq!(parse_barrier_state(""), None);
+    }
+
     #[test]
     fn test_parse_device_list() {
         let fixture = r#"
@@ -3,18 +3,26 @@
 // license that can be found in the LICENSE file.
 
 pub mod action_plugin;
+pub mod certificate;
 pub mod check_kernel;
 pub mod check_stonith;
+pub mod dne;
 pub mod high_availability;
 pub mod kernel_module;
 pub mod lamigo;
 pub mod ldev;
+pub mod lnet_ping;
 pub mod lpurge;
 pub mod lustre;
+pub mod multipath;
 pub mod ntp;
 pub mod ostpool;
 pub mod package;
 pub mod postoffice;
+pub mod ssk;
+pub mod stonith;
 pub mod stratagem;
+pub mod stripe;
+pub mod tuning;
 pub use action_plugin::create_registry;
 pub(crate) mod firewall_cmd;
@@ -0,0 +1,70 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Reports the paths of the device-mapper multipath maps on this server.
+
+use crate::agent_error::ImlAgentError;
+use iml_cmd::{CheckedCommandExt, Command};
+use iml_wire_types::multipath::MultipathPath;
+
+/// Lists every path `multipathd` knows of that belongs to a map.
+pub async fn paths(_: ()) -> Result<Vec<MultipathPath>, ImlAgentError> {
+    let x = Command::new("multipathd")
+        .args(&["show", "paths", "raw", "format", "%m %d %t %T"])
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    Ok(parse_paths(&String::from_utf8_lossy(&x.stdout)))
+}
+
+/// Parses `multipathd show paths raw format "%m %d %t %T"`.
+/// Paths that are not part of a map show up as `[orphan]` and are skipped.
+fn parse_paths(output: &str) -> Vec<MultipathPath> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let mut xs = l.split_whitespace();
+
+            let map = xs.next()?;
+            let dev = xs.next()?;
+            let dm_state = xs.next()?;
+            let checker_state = xs.next()?;
+
+            if map == "[orphan]" {
+                return None;
+            }
+
+            Some(MultipathPath {
+                map: map.to_string(),
+                dev: dev.to_string(),
+                dm_state: dm_state.to_string(),
+                checker_state: checker_state.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_paths() {
+        let output = "mpatha sdb active ready\nmpatha sdc failed faulty\n[orphan] sda undef ready\nmpathb sdd active ready\n\n";
+
+        let xs = parse_paths(output);
+
+        assert_eq!(
+            xs.iter()
+                .map(|x| (x.map.as_str(), x.dev.as_str(), x.is_failed()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("mpatha", "sdb", false),
+                ("mpatha", "sdc", true),
+                ("mpathb", "sdd", false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::agent_error::ImlAgentError;
+use futures::TryFutureExt;
+use iml_cmd::{CheckedCommandExt, Command};
+use std::{os::unix::fs::PermissionsExt, path::Path};
+use tokio::fs;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct GenerateKey {
+    fsname: String,
+    path: String,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct InstallKey {
+    path: String,
+    key: Vec<u8>,
+}
+
+async fn lgss_sk(args: &[&str]) -> Result<(), ImlAgentError> {
+    Command::new("/usr/sbin/lgss_sk")
+        .args(args)
+        .kill_on_drop(true)
+        .checked_output()
+        .err_into()
+        .await
+        .map(drop)
+}
+
+async fn create_parent(path: &str) -> Result<(), ImlAgentError> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    Ok(())
+}
+
+/// Generate a shared secret key usable by the MGS and servers of `fsname`.
+/// Returns the raw key file so it can be installed on the other servers.
+pub async fn generate_key(x: GenerateKey) -> Result<Vec<u8>, ImlAgentError> {
+    create_parent(&x.path).await?;
+
+    lgss_sk(&["-t", "mgs", "-t", "server", "-f", &x.fsname, "-w", &x.path]).await?;
+
+    fs::set_permissions(&x.path, PermissionsExt::from_mode(0o600)).await?;
+
+    let key = fs::read(&x.path).await?;
+
+    Ok(key)
+}
+
+/// Write a key created by `generate_key` and check it can be read back.
+pub async fn install_key(x: InstallKey) -> Result<(), ImlAgentError> {
+    create_parent(&x.path).await?;
+
+    fs::write(&x.path, &x.key).await?;
+
+    fs::set_permissions(&x.path, PermissionsExt::from_mode(0o600)).await?;
+
+    lgss_sk(&["-r", &x.path]).await
+}
@@ -0,0 +1,255 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Reads the stonith devices of the pacemaker cluster this node is part of, and tests them.
+
+use crate::{
+    agent_error::{CibError, ImlAgentError},
+    high_availability::crm_attribute,
+};
+use elementtree::Element;
+use iml_cmd::{CheckedCommandExt, Command};
+use iml_wire_types::stonith::{
+    StonithConfig, StonithDevice, StonithDeviceTest, StonithNodeTest, StonithParam,
+    StonithTestResult,
+};
+use std::collections::BTreeSet;
+
+static STONITH_ADMIN_PATH: &str = "/usr/sbin/stonith_admin";
+
+fn stonith_admin_cmd() -> Command {
+    let mut cmd = Command::new(STONITH_ADMIN_PATH);
+
+    cmd.kill_on_drop(true);
+
+    cmd
+}
+
+/// Credentials are never sent back to the manager
+fn is_secret(name: &str) -> bool {
+    (name.contains("passwd") || name.contains("password")) && !name.ends_with("_script")
+}
+
+fn parse_device(elem: &Element) -> Result<StonithDevice, ImlAgentError> {
+    let id = elem
+        .get_attr("id")
+        .ok_or_else(|| CibError(format!("{} is missing id attribute", elem.tag())))?;
+
+    let agent = elem
+        .get_attr("type")
+        .ok_or_else(|| CibError(format!("{} is missing type attribute", id)))?;
+
+    let mut host_list = vec![];
+    let mut params = vec![];
+
+    for e in elem
+        .find("instance_attributes")
+        .into_iter()
+        .flat_map(|x| x.children())
+    {
+        match (e.get_attr("name"), e.get_attr("value")) {
+            (Some("pcmk_host_list"), Some(v)) => {
+                host_list = v
+                    .split(|c| c == ',' || c == ' ')
+                    .filter(|x| !x.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            (Some(name), value) => params.push(StonithParam {
+                name: name.to_string(),
+                value: value.filter(|_| !is_secret(name)).map(String::from),
+            }),
+            (None, _) => {}
+        }
+    }
+
+    let enabled = elem
+        .find("meta_attributes")
+        .into_iter()
+        .flat_map(|x| x.children())
+        .find(|e| e.get_attr("name") == Some("target-role"))
+        .and_then(|e| e.get_attr("value"))
+        .map_or(true, |v| v == "Started");
+
+    Ok(StonithDevice {
+        id: id.to_string(),
+        agent: agent.to_string(),
+        host_list,
+        enabled,
+        params,
+    })
+}
+
+/// Parses the output of `cibadmin --query --xpath //primitive[@class='stonith']`, which is
+/// the `<primitive>` itself when there is a single device.
+fn parse_devices(xml: &[u8]) -> Result<Vec<StonithDevice>, ImlAgentError> {
+    let elem = Element::from_reader(xml)?;
+
+    match elem.tag().name() {
+        "primitive" => Ok(vec![parse_device(&elem)?]),
+        "xpath-query" => elem.find_all("primitive").map(parse_device).collect(),
+        tag => Err(CibError(format!("Unknown first tag {}", tag)).into()),
+    }
+}
+
+/// The stonith devices of the cluster and whether stonith is enabled.
+pub async fn get_stonith_config(_: ()) -> Result<StonithConfig, ImlAgentError> {
+    let x = Command::new("cibadmin")
+        .kill_on_drop(true)
+        .args(&["--query", "--xpath", "//primitive[@class='stonith']"])
+        .output()
+        .await?;
+
+    // cibadmin exits with an error when nothing matches the xpath
+    let devices = if x.status.success() {
+        parse_devices(&x.stdout)?
+    } else {
+        vec![]
+    };
+
+    let enabled = crm_attribute(vec![
+        "--type".into(),
+        "crm_config".into(),
+        "--name".into(),
+        "stonith-enabled".into(),
+        "--query".into(),
+        "--quiet".into(),
+    ])
+    .await
+    .map_or(true, |x| x.trim() != "false");
+
+    Ok(StonithConfig { enabled, devices })
+}
+
+/// Parses `stonith_admin --list <node>`, one device per line followed by a count.
+fn parse_device_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty() && !x.ends_with("found"))
+        .map(String::from)
+        .collect()
+}
+
+/// Checks every node in `nodes` can be fenced, without fencing any of them.
+///
+/// Each device that can fence a node is monitored once, which checks the agent can reach
+/// what it controls.
+pub async fn stonith_test(nodes: Vec<String>) -> Result<StonithTestResult, ImlAgentError> {
+    let mut node_devices = vec![];
+
+    for node in nodes {
+        let o = stonith_admin_cmd()
+            .args(&["--list", &node])
+            .checked_output()
+            .await?;
+
+        let devices = parse_device_list(&String::from_utf8_lossy(&o.stdout));
+
+        node_devices.push((node, devices));
+    }
+
+    let ids: BTreeSet<_> = node_devices.iter().flat_map(|(_, xs)| xs).collect();
+
+    let mut devices = vec![];
+
+    for id in ids {
+        let o = stonith_admin_cmd()
+            .args(&["--monitor", id])
+            .output()
+            .await?;
+
+        let error = if o.status.success() {
+            None
+        } else {
+            let e = String::from_utf8_lossy(&o.stderr).trim().to_string();
+
+            Some(if e.is_empty() {
+                format!("Monitor exited with {}", o.status)
+            } else {
+                e
+            })
+        };
+
+        devices.push(StonithDeviceTest {
+            id: id.to_string(),
+            passed: error.is_none(),
+            error,
+        });
+    }
+
+    let nodes = node_devices
+        .into_iter()
+        .map(|(node, xs)| StonithNodeTest {
+            passed: devices.iter().any(|d| d.passed && xs.contains(&d.id)),
+            node,
+            devices: xs,
+        })
+        .collect();
+
+    Ok(StonithTestResult { devices, nodes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_devices() {
+        let xs = parse_devices(include_bytes!(
+            "fixtures/check_stonith_test_multihost_static.xml"
+        ))
+        .unwrap();
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].id, "stonith-host0");
+        assert_eq!(xs[0].agent, "fence_ipmilan");
+        assert_eq!(xs[0].host_list, vec!["host0"]);
+        assert_eq!(xs[1].host_list, vec!["host2", "host1"]);
+        assert!(xs[0].enabled);
+        assert_eq!(
+            xs[0].params.iter().find(|x| x.name == "ipaddr"),
+            Some(&StonithParam {
+                name: "ipaddr".into(),
+                value: Some("10.0.1.10".into())
+            })
+        );
+        assert_eq!(
+            xs[0].params.iter().find(|x| x.name == "passwd"),
+            Some(&StonithParam {
+                name: "passwd".into(),
+                value: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_single_stopped_device() {
+        let testxml = r#"<primitive class="stonith" id="st-oss1" type="fence_ipmilan">
+    <meta_attributes id="st-oss1-meta_attributes">
+      <nvpair id="st-oss1-meta_attributes-target-role" name="target-role" value="Stopped"/>
+    </meta_attributes>
+  </primitive>
+"#;
+
+        assert_eq!(
+            parse_devices(testxml.as_bytes()).unwrap(),
+            vec![StonithDevice {
+                id: "st-oss1".into(),
+                agent: "fence_ipmilan".into(),
+                host_list: vec![],
+                enabled: false,
+                params: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_device_list() {
+        let output = "st-oss1\nst-oss2\n2 devices found\n";
+
+        assert_eq!(parse_device_list(output), vec!["st-oss1", "st-oss2"]);
+        assert_eq!(parse_device_list("0 devices found\n"), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    action_plugins::stratagem::action_warning::item2path,
+    agent_error::{ImlAgentError, RequiredError},
+    http_comms::streaming_client::send,
+    lustre::search_rootpath,
+};
+use futures::{channel::mpsc, future::join_all, stream, StreamExt};
+use iml_wire_types::{stratagem::HeatmapEntry, FidError, FidItem};
+use std::{
+    collections::{BTreeMap, HashMap},
+    os::unix::fs::MetadataExt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// How many levels of directories below the root are added up when the task doesn't say.
+const DEFAULT_MAX_DEPTH: usize = 3;
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// The directories a file is in, from `/` down to at most `max_depth` levels below it.
+/// `path` is relative to the mount point.
+fn directories_of(path: &str, max_depth: usize) -> Vec<String> {
+    let xs: Vec<_> = path.split('/').filter(|x| !x.is_empty()).collect();
+
+    let dirs = xs.len().saturating_sub(1).min(max_depth);
+
+    (0..=dirs)
+        .map(|depth| format!("/{}", xs[..depth].join("/")))
+        .collect()
+}
+
+/// A single file of `bytes`, last accessed `age` seconds ago.
+fn file_entry(bytes: u64, age: i64) -> HeatmapEntry {
+    let mut x = HeatmapEntry {
+        files: 1,
+        bytes,
+        ..HeatmapEntry::default()
+    };
+
+    if age < 7 * DAY {
+        x.recent_files = 1;
+        x.bytes_week = bytes;
+    } else if age < 30 * DAY {
+        x.bytes_month = bytes;
+    } else if age < 90 * DAY {
+        x.bytes_quarter = bytes;
+    } else if age < 365 * DAY {
+        x.bytes_year = bytes;
+    } else {
+        x.bytes_older = bytes;
+    }
+
+    x
+}
+
+/// Adds up `(path, bytes, atime)` of each file into every directory it is in.
+fn add_up(
+    xs: impl IntoIterator<Item = (String, u64, i64)>,
+    max_depth: usize,
+    now: i64,
+) -> Vec<HeatmapEntry> {
+    let mut dirs: BTreeMap<String, HeatmapEntry> = BTreeMap::new();
+
+    for (path, bytes, atime) in xs {
+        let file = file_entry(bytes, now - atime);
+
+        for (depth, dir) in directories_of(&path, max_depth).into_iter().enumerate() {
+            dirs.entry(dir.clone())
+                .or_insert_with(|| HeatmapEntry {
+                    path: dir,
+                    depth: depth as u32,
+                    ..HeatmapEntry::default()
+                })
+                .add(&file);
+        }
+    }
+
+    dirs.into_iter().map(|(_, x)| x).collect()
+}
+
+/// Add up FIDs by the directories they are in and when they were last accessed
+/// Task Args:
+/// * report_name - report the totals are appended to, one JSON line per directory
+/// * max_depth - (optional) how many levels of directories below the root to add up
+/// Fid Args:
+/// * pfid list - (optional) Array of LinkEA info (specifically "pfid" - parent fid)
+pub async fn process_fids(
+    (fsname_or_mntpath, mut task_args, fid_list): (String, HashMap<String, String>, Vec<FidItem>),
+) -> Result<Vec<FidError>, ImlAgentError> {
+    let report_name = task_args
+        .remove("report_name")
+        .ok_or_else(|| RequiredError("Task missing 'report_name' argument".to_string()))?;
+
+    let max_depth = task_args
+        .get("max_depth")
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(DEFAULT_MAX_DEPTH);
+
+    let llapi = search_rootpath(fsname_or_mntpath).await?;
+
+    let mntpt = llapi.mntpt();
+
+    let (tx, rx) = mpsc::unbounded::<FidError>();
+
+    let paths = join_all(
+        fid_list
+            .into_iter()
+            .map(|x| item2path(llapi.clone(), x, tx.clone())),
+    )
+    .await;
+
+    drop(tx);
+
+    let mut xs = vec![];
+
+    for path in paths.into_iter().filter_map(std::convert::identity) {
+        match tokio::fs::symlink_metadata(format!("{}/{}", mntpt, path)).await {
+            Ok(m) => xs.push((path, m.len(), m.atime())),
+            Err(e) => warn!("Could not stat {}: {}", path, e),
+        }
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs() as i64)
+        .unwrap_or_default();
+
+    let lines = add_up(xs, max_depth, now)
+        .into_iter()
+        .map(|x| serde_json::to_string(&x).map(|x| format!("{}\n", x)))
+        .collect::<Result<String, _>>()?;
+
+    if !lines.is_empty() {
+        let s = stream::iter(vec![Ok::<_, ImlAgentError>(bytes::Bytes::from(lines))]);
+
+        send("report", report_name, s).await?;
+    }
+
+    Ok(rx.collect().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directories_of() {
+        assert_eq!(
+            directories_of("proj1/data/a.dat", 3),
+            vec!["/", "/proj1", "/proj1/data"]
+        );
+        assert_eq!(directories_of("/proj1/a.dat", 3), vec!["/", "/proj1"]);
+        assert_eq!(directories_of("a.dat", 3), vec!["/"]);
+        assert_eq!(directories_of("a/b/c/d/e.dat", 2), vec!["/", "/a", "/a/b"]);
+    }
+
+    #[test]
+    fn test_add_up() {
+        let now = 1000 * DAY;
+
+        let xs = add_up(
+            vec![
+                ("proj1/a".to_string(), 10, now - DAY),
+                ("proj1/old/b".to_string(), 5, now - 400 * DAY),
+                ("c".to_string(), 7, now - 40 * DAY),
+            ],
+            1,
+            now,
+        );
+
+        assert_eq!(
+            xs,
+            vec![
+                HeatmapEntry {
+                    path: "/".into(),
+                    depth: 0,
+                    files: 3,
+                    bytes: 22,
+                    recent_files: 1,
+                    bytes_week: 10,
+                    bytes_quarter: 7,
+                    bytes_older: 5,
+                    ..HeatmapEntry::default()
+                },
+                HeatmapEntry {
+                    path: "/proj1".into(),
+                    depth: 1,
+                    files: 2,
+                    bytes: 15,
+                    recent_files: 1,
+                    bytes_week: 10,
+                    bytes_older: 5,
+                    ..HeatmapEntry::default()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{agent_error::ImlAgentError, lustre::search_rootpath};
+use futures::future::join_all;
+use iml_cmd::{CheckedCommandExt, CmdError, Command};
+use iml_wire_types::{FidError, FidItem};
+use std::collections::HashMap;
+
+async fn lfs_migrate_fiderror(ost: &str, fi: &FidItem, mntpt: &str) -> Option<FidError> {
+    let path = format!("{}/.lustre/fid/{}", mntpt, fi.fid);
+
+    let r = Command::new("/usr/bin/lfs")
+        .args(&["migrate", "-o", ost, &path])
+        .kill_on_drop(true)
+        .checked_output()
+        .await;
+
+    match r {
+        Ok(output) => {
+            if output.status.success() {
+                None
+            } else {
+                Some(FidError {
+                    fid: fi.fid.clone(),
+                    data: fi.data.clone(),
+                    errno: output.status.code().unwrap_or(0) as i16,
+                })
+            }
+        }
+        Err(CmdError::Io(err)) => Some(FidError {
+            fid: fi.fid.clone(),
+            data: fi.data.clone(),
+            errno: err.raw_os_error().unwrap_or(-1) as i16,
+        }),
+        Err(CmdError::Output(err)) => {
+            tracing::warn!("Fid {} returned CmdError({:?})", &fi.fid, err);
+            None
+        }
+    }
+}
+
+/// Task Args:
+/// * osts - comma seperated OST indexes to migrate to.
+///   Each file is moved to a single OST, spread round-robin over the list.
+/// Fid Args: NONE
+pub async fn process_fids(
+    (fsname_or_mntpath, task_args, fid_list): (String, HashMap<String, String>, Vec<FidItem>),
+) -> Result<Vec<FidError>, ImlAgentError> {
+    let llapi = search_rootpath(fsname_or_mntpath).await?;
+    let mntpt = llapi.mntpt();
+
+    let osts: Vec<&str> = match task_args.get("osts") {
+        Some(x) => x
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .collect(),
+        None => return Err(ImlAgentError::MissingArgument("task::osts".into())),
+    };
+
+    if osts.is_empty() {
+        return Err(ImlAgentError::MissingArgument("task::osts".into()));
+    }
+
+    let xs = fid_list.into_iter().enumerate().map(|(idx, fi)| {
+        let mntpt = &mntpt;
+        let ost = osts[idx % osts.len()];
+
+        async move { lfs_migrate_fiderror(ost, &fi, mntpt).await }
+    });
+
+    Ok(join_all(xs).await.into_iter().filter_map(|x| x).collect())
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    action_plugins::stratagem::action_warning::item2path,
+    agent_error::{ImlAgentError, RequiredError},
+    http_comms::streaming_client::send,
+    lustre::search_rootpath,
+};
+use futures::{channel::mpsc, future::join_all, stream, StreamExt};
+use iml_wire_types::{stratagem::ProjectUsageEntry, FidError, FidItem};
+use std::collections::{BTreeMap, HashMap};
+use tracing::warn;
+
+/// The top-level directory `path` is under. `path` is relative to the mount point.
+/// Files directly under the root belong to `/`.
+fn project_of(path: &str) -> &str {
+    let mut xs = path.trim_start_matches('/').splitn(2, '/');
+
+    match (xs.next(), xs.next()) {
+        (Some(project), Some(_)) if !project.is_empty() => project,
+        _ => "/",
+    }
+}
+
+fn add_up(xs: impl IntoIterator<Item = (String, u64)>) -> Vec<ProjectUsageEntry> {
+    let mut projects: BTreeMap<String, ProjectUsageEntry> = BTreeMap::new();
+
+    for (path, bytes) in xs {
+        let project = project_of(&path);
+
+        let x = projects
+            .entry(project.to_string())
+            .or_insert_with(|| ProjectUsageEntry {
+                project: project.to_string(),
+                ..ProjectUsageEntry::default()
+            });
+
+        x.files += 1;
+        x.bytes += bytes;
+    }
+
+    projects.into_iter().map(|(_, x)| x).collect()
+}
+
+/// Add up FIDs by the top-level directory they are in
+/// Task Args:
+/// * report_name - report the totals are appended to, one JSON line per directory
+/// Fid Args:
+/// * pfid list - (optional) Array of LinkEA info (specifically "pfid" - parent fid)
+pub async fn process_fids(
+    (fsname_or_mntpath, mut task_args, fid_list): (String, HashMap<String, String>, Vec<FidItem>),
+) -> Result<Vec<FidError>, ImlAgentError> {
+    let report_name = task_args
+        .remove("report_name")
+        .ok_or_else(|| RequiredError("Task missing 'report_name' argument".to_string()))?;
+
+    let llapi = search_rootpath(fsname_or_mntpath).await?;
+
+    let mntpt = llapi.mntpt();
+
+    let (tx, rx) = mpsc::unbounded::<FidError>();
+
+    let paths = join_all(
+        fid_list
+            .into_iter()
+            .map(|x| item2path(llapi.clone(), x, tx.clone())),
+    )
+    .await;
+
+    drop(tx);
+
+    let mut xs = vec![];
+
+    for path in paths.into_iter().filter_map(std::convert::identity) {
+        match tokio::fs::symlink_metadata(format!("{}/{}", mntpt, path)).await {
+            Ok(m) => xs.push((path, m.len())),
+            Err(e) => warn!("Could not stat {}: {}", path, e),
+        }
+    }
+
+    let lines = add_up(xs)
+        .into_iter()
+        .map(|x| serde_json::to_string(&x).map(|x| format!("{}\n", x)))
+        .collect::<Result<String, _>>()?;
+
+    if !lines.is_empty() {
+        let s = stream::iter(vec![Ok::<_, ImlAgentError>(bytes::Bytes::from(lines))]);
+
+        send("report", report_name, s).await?;
+    }
+
+    Ok(rx.collect().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_of() {
+        assert_eq!(project_of("proj1/data/a.dat"), "proj1");
+        assert_eq!(project_of("/proj1/a.dat"), "proj1");
+        assert_eq!(project_of("a.dat"), "/");
+        assert_eq!(project_of("/a.dat"), "/");
+    }
+
+    #[test]
+    fn test_add_up() {
+        let xs = add_up(vec![
+            ("proj2/a".to_string(), 10),
+            ("proj1/a".to_string(), 1),
+            ("proj2/b/c".to_string(), 5),
+            ("a".to_string(), 7),
+        ]);
+
+        assert_eq!(
+            xs,
+            vec![
+                ProjectUsageEntry {
+                    project: "/".into(),
+                    files: 1,
+                    bytes: 7,
+                },
+                ProjectUsageEntry {
+                    project: "proj1".into(),
+                    files: 1,
+                    bytes: 1,
+                },
+                ProjectUsageEntry {
+                    project: "proj2".into(),
+                    files: 2,
+                    bytes: 15,
+                },
+            ]
+        );
+    }
+}
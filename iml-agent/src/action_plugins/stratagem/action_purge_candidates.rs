@@ -0,0 +1,68 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    action_plugins::stratagem::action_warning::item2path,
+    agent_error::{ImlAgentError, RequiredError},
+    http_comms::streaming_client::send,
+    lustre::search_rootpath,
+};
+use futures::{channel::mpsc, future::join_all, stream, FutureExt, StreamExt};
+use iml_wire_types::{stratagem::PurgeCandidate, FidError, FidItem};
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Report the path and size of FIDs a purge policy may purge, without purging them
+/// Task Args:
+/// * report_name - report the candidates are appended to, one JSON line per file
+/// Fid Args:
+/// * pfid list - (optional) Array of LinkEA info (specifically "pfid" - parent fid)
+pub async fn process_fids(
+    (fsname_or_mntpath, mut task_args, fid_list): (String, HashMap<String, String>, Vec<FidItem>),
+) -> Result<Vec<FidError>, ImlAgentError> {
+    let report_name = task_args
+        .remove("report_name")
+        .ok_or_else(|| RequiredError("Task missing 'report_name' argument".to_string()))?;
+
+    let llapi = search_rootpath(fsname_or_mntpath).await?;
+
+    let mntpt = llapi.mntpt();
+
+    let (tx, rx) = mpsc::unbounded::<FidError>();
+
+    let paths = join_all(fid_list.into_iter().map(|x| {
+        let fid = x.fid.clone();
+
+        item2path(llapi.clone(), x, tx.clone()).map(move |path| path.map(|path| (fid, path)))
+    }))
+    .await;
+
+    drop(tx);
+
+    let mut lines = String::new();
+
+    for (fid, path) in paths.into_iter().filter_map(std::convert::identity) {
+        let bytes = match tokio::fs::symlink_metadata(format!("{}/{}", mntpt, path)).await {
+            Ok(m) => m.len(),
+            Err(e) => {
+                warn!("Could not stat {}: {}", path, e);
+
+                continue;
+            }
+        };
+
+        let x = PurgeCandidate { fid, path, bytes };
+
+        lines.push_str(&serde_json::to_string(&x)?);
+        lines.push('\n');
+    }
+
+    if !lines.is_empty() {
+        let s = stream::iter(vec![Ok::<_, ImlAgentError>(bytes::Bytes::from(lines))]);
+
+        send("report", report_name, s).await?;
+    }
+
+    Ok(rx.collect().await)
+}
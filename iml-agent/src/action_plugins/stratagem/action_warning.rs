@@ -34,7 +34,7 @@ async fn fid2path(llapi: LlapiFid, fid: String) -> Option<String> {
     }
 }
 
-async fn item2path(
+pub(crate) async fn item2path(
     llapi: LlapiFid,
     fi: FidItem,
     mut tx: mpsc::UnboundedSender<FidError>,
@@ -4,7 +4,11 @@
 
 pub mod action_cloudsync;
 pub mod action_filesync;
+pub mod action_heatmap;
+pub mod action_migrate;
 pub mod action_mirror;
+pub mod action_project_usage;
 pub mod action_purge;
+pub mod action_purge_candidates;
 pub mod action_warning;
 pub mod server;
@@ -0,0 +1,205 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Reads and sets the default layout of new files in a filesystem, the layout of its root.
+//! Both run on a client that has the filesystem mounted.
+
+use crate::{action_plugins::dne::lfs, agent_error::ImlAgentError, lustre::search_rootpath};
+use iml_wire_types::stripe::{SetDefaultStripe, StripeComponent};
+
+/// The default layout of files created in the root of `filesystem`.
+pub async fn get_default_stripe(filesystem: String) -> Result<Vec<StripeComponent>, ImlAgentError> {
+    let mntpt = search_rootpath(filesystem).await?.mntpt();
+
+    let x = lfs(vec!["getstripe".into(), "-d".into(), mntpt]).await?;
+
+    Ok(parse_stripe(&x))
+}
+
+/// Sets the default layout of files created in the root of a filesystem.
+/// It is inherited by the directories created below it that have no layout of their own.
+pub async fn set_default_stripe(x: SetDefaultStripe) -> Result<(), ImlAgentError> {
+    let mntpt = search_rootpath(x.filesystem).await?.mntpt();
+
+    lfs(setstripe_args(&x.components, mntpt)).await.map(drop)
+}
+
+fn setstripe_args(components: &[StripeComponent], mntpt: String) -> Vec<String> {
+    let mut args = vec!["setstripe".to_string()];
+
+    if components.is_empty() {
+        args.push("-d".into());
+    }
+
+    // A single component covering the whole file is set as a plain layout
+    let plain = matches!(components, [x] if x.extent_end.is_none());
+
+    for x in components {
+        if !plain {
+            args.push("-E".into());
+            args.push(
+                x.extent_end
+                    .map_or_else(|| "-1".to_string(), |x| (x as u64).to_string()),
+            );
+        }
+
+        args.push("-c".into());
+        args.push(x.stripe_count.to_string());
+        args.push("-S".into());
+        args.push((x.stripe_size as u64).to_string());
+
+        if let Some(pool) = &x.pool {
+            args.push("-p".into());
+            args.push(pool.to_string());
+        }
+    }
+
+    args.push(mntpt);
+
+    args
+}
+
+/// Parses `lfs getstripe -d`. A plain layout is printed as e.g.
+/// `stripe_count:  1 stripe_size:   1048576 pattern:       0 stripe_offset: -1`,
+/// a PFL as a `lcme_extent.e_start:` and `lcme_extent.e_end:` followed by the same values
+/// for each component.
+fn parse_stripe(output: &str) -> Vec<StripeComponent> {
+    let tokens: Vec<_> = output.split_whitespace().collect();
+
+    let mut xs: Vec<StripeComponent> = vec![];
+
+    for kv in tokens.windows(2) {
+        if kv[0] == "lcme_extent.e_start:" || (xs.is_empty() && kv[0] == "stripe_count:") {
+            xs.push(StripeComponent {
+                extent_end: None,
+                stripe_count: 0,
+                stripe_size: 0.,
+                pool: None,
+            });
+        }
+
+        let x = match xs.last_mut() {
+            Some(x) => x,
+            None => continue,
+        };
+
+        match (kv[0], kv[1]) {
+            ("lcme_extent.e_end:", "EOF") => x.extent_end = None,
+            ("lcme_extent.e_end:", v) => x.extent_end = v.parse().ok(),
+            ("stripe_count:", v) => x.stripe_count = v.parse().unwrap_or(x.stripe_count),
+            ("stripe_size:", v) => x.stripe_size = v.parse().unwrap_or(x.stripe_size),
+            ("pool:", v) => x.pool = Some(v.to_string()),
+            _ => {}
+        }
+    }
+
+    xs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(
+        extent_end: Option<f64>,
+        stripe_count: i32,
+        stripe_size: f64,
+        pool: Option<&str>,
+    ) -> StripeComponent {
+        StripeComponent {
+            extent_end,
+            stripe_count,
+            stripe_size,
+            pool: pool.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_parse_stripe() {
+        assert_eq!(
+            parse_stripe(
+                "stripe_count:  2 stripe_size:   4194304 pattern:       0 stripe_offset: -1\n"
+            ),
+            vec![component(None, 2, 4_194_304., None)]
+        );
+
+        assert_eq!(
+            parse_stripe(
+                r#"  lcm_layout_gen:    0
+  lcm_mirror_count:  1
+  lcm_entry_count:   2
+    lcme_id:             N/A
+    lcme_mirror_id:      N/A
+    lcme_flags:          0
+    lcme_extent.e_start: 0
+    lcme_extent.e_end:   67108864
+      stripe_count:  1       stripe_size:   1048576       pattern:       raid0       stripe_offset: -1
+
+    lcme_id:             N/A
+    lcme_mirror_id:      N/A
+    lcme_flags:          0
+    lcme_extent.e_start: 67108864
+    lcme_extent.e_end:   EOF
+      stripe_count:  -1       stripe_size:   4194304       pattern:       raid0       stripe_offset: -1       pool:          flash
+"#
+            ),
+            vec![
+                component(Some(67_108_864.), 1, 1_048_576., None),
+                component(None, -1, 4_194_304., Some("flash")),
+            ]
+        );
+
+        assert_eq!(parse_stripe(""), vec![]);
+    }
+
+    #[test]
+    fn test_setstripe_args() {
+        let mntpt = || "/mnt/fs".to_string();
+
+        assert_eq!(
+            setstripe_args(&[], mntpt()),
+            vec!["setstripe", "-d", "/mnt/fs"]
+        );
+
+        assert_eq!(
+            setstripe_args(&[component(None, 4, 1_048_576., Some("flash"))], mntpt()),
+            vec![
+                "setstripe",
+                "-c",
+                "4",
+                "-S",
+                "1048576",
+                "-p",
+                "flash",
+                "/mnt/fs"
+            ]
+        );
+
+        assert_eq!(
+            setstripe_args(
+                &[
+                    component(Some(67_108_864.), 1, 0., None),
+                    component(None, -1, 4_194_304., None)
+                ],
+                mntpt()
+            ),
+            vec![
+                "setstripe",
+                "-E",
+                "67108864",
+                "-c",
+                "1",
+                "-S",
+                "0",
+                "-E",
+                "-1",
+                "-c",
+                "-1",
+                "-S",
+                "4194304",
+                "/mnt/fs"
+            ]
+        );
+    }
+}
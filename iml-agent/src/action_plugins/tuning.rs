@@ -0,0 +1,161 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{agent_error::ImlAgentError, lustre::lctl};
+use futures::TryFutureExt;
+use iml_cmd::{CheckedCommandExt, Command};
+use iml_wire_types::{TuningSetting, TuningSettingKind};
+use std::collections::BTreeMap;
+use tokio::fs;
+
+static MODPROBE_CONF: &str = "/etc/modprobe.d/iml-tuning.conf";
+
+async fn sysctl(args: &[&str]) -> Result<String, ImlAgentError> {
+    Command::new("/usr/sbin/sysctl")
+        .args(args)
+        .kill_on_drop(true)
+        .checked_output()
+        .err_into()
+        .await
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+}
+
+/// Applies the settings of a tuning profile.
+///
+/// `sysctl` and `lctl` settings are applied to the running system.
+/// Module options replace the contents of `MODPROBE_CONF`,
+/// so options of a previously applied profile do not linger.
+pub async fn apply(xs: Vec<TuningSetting>) -> Result<(), ImlAgentError> {
+    for x in &xs {
+        let param = format!("{}={}", x.key, x.value);
+
+        match x.kind {
+            TuningSettingKind::Sysctl => {
+                sysctl(&["-w", &param]).await?;
+            }
+            TuningSettingKind::Lctl => {
+                lctl(vec!["set_param", &param]).await?;
+            }
+            TuningSettingKind::ModuleParam => {}
+        }
+    }
+
+    fs::write(MODPROBE_CONF, modprobe_conf(&xs)).await?;
+
+    Ok(())
+}
+
+/// Reads the live value of each setting, in order.
+/// A value is `None` when it could not be read, e.g. because the module is not loaded.
+pub async fn read(xs: Vec<TuningSetting>) -> Result<Vec<Option<String>>, ImlAgentError> {
+    let mut ys = vec![];
+
+    for x in xs {
+        let y = match x.kind {
+            TuningSettingKind::Sysctl => sysctl(&["-n", &x.key]).await.ok(),
+            TuningSettingKind::Lctl => lctl(vec!["get_param", "-n", &x.key]).await.ok(),
+            TuningSettingKind::ModuleParam => match split_module_param(&x.key) {
+                Some((module, param)) => {
+                    fs::read_to_string(format!("/sys/module/{}/parameters/{}", module, param))
+                        .await
+                        .ok()
+                }
+                None => None,
+            },
+        };
+
+        ys.push(y.map(|y| y.trim().to_string()));
+    }
+
+    Ok(ys)
+}
+
+/// Splits `module.option` into its parts.
+fn split_module_param(x: &str) -> Option<(&str, &str)> {
+    let mut xs = x.splitn(2, '.');
+
+    Some((xs.next()?, xs.next()?))
+}
+
+/// Renders the module options of `xs` as one `options` line per module.
+fn modprobe_conf(xs: &[TuningSetting]) -> String {
+    let modules = xs
+        .iter()
+        .filter(|x| x.kind == TuningSettingKind::ModuleParam)
+        .filter_map(|x| {
+            let (module, param) = split_module_param(&x.key)?;
+
+            Some((module, format!("{}={}", param, x.value)))
+        })
+        .fold(
+            BTreeMap::new(),
+            |mut acc: BTreeMap<_, Vec<_>>, (module, option)| {
+                acc.entry(module).or_default().push(option);
+
+                acc
+            },
+        );
+
+    let mut s =
+        "# Written by IML tuning profiles. Manual changes will be overwritten.\n".to_string();
+
+    for (module, options) in modules {
+        s.push_str(&format!("options {} {}\n", module, options.join(" ")));
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(kind: TuningSettingKind, key: &str, value: &str) -> TuningSetting {
+        TuningSetting {
+            kind,
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn test_modprobe_conf() {
+        let xs = vec![
+            setting(
+                TuningSettingKind::ModuleParam,
+                "ko2iblnd.peer_credits",
+                "128",
+            ),
+            setting(TuningSettingKind::Sysctl, "vm.min_free_kbytes", "2097152"),
+            setting(
+                TuningSettingKind::ModuleParam,
+                "libcfs.cpu_npartitions",
+                "2",
+            ),
+            setting(
+                TuningSettingKind::ModuleParam,
+                "ko2iblnd.concurrent_sends",
+                "256",
+            ),
+        ];
+
+        assert_eq!(
+            modprobe_conf(&xs),
+            "# Written by IML tuning profiles. Manual changes will be overwritten.\n\
+             options ko2iblnd peer_credits=128 concurrent_sends=256\n\
+             options libcfs cpu_npartitions=2\n"
+        );
+    }
+
+    #[test]
+    fn test_modprobe_conf_empty() {
+        let xs = vec![setting(
+            TuningSettingKind::Lctl,
+            "osc.*.max_dirty_mb",
+            "512",
+        )];
+
+        assert_eq!(modprobe_conf(&xs).lines().count(), 1);
+    }
+}
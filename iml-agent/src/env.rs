@@ -24,11 +24,11 @@ lazy_static! {
         Url::parse(&get_var("IML_MANAGER_URL")).expect("Could not parse manager url");
 }
 
-fn get_private_pem_path() -> String {
+pub fn get_private_pem_path() -> String {
     get_var("PRIVATE_PEM_PATH")
 }
 
-fn get_cert_path() -> String {
+pub fn get_cert_path() -> String {
     get_var("CRT_PATH")
 }
 
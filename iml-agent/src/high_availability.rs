@@ -2,16 +2,19 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::agent_error::ImlAgentError;
+use crate::{
+    action_plugins::high_availability::corosync_conf::read_corosync_conf,
+    agent_error::ImlAgentError,
+};
 use futures::TryFutureExt;
 use iml_cmd::{CheckedCommandExt, Command};
 use iml_fs::file_exists;
-use iml_wire_types::high_availability::{Ban, Cluster, Node, Resource};
+use iml_wire_types::high_availability::{Ban, Cluster, HaConfig, Node, Resource};
 use quick_xml::{
     events::{attributes::Attributes, Event},
     Reader,
 };
-use std::{collections::HashMap, convert::TryInto, ffi::OsStr, process::Output};
+use std::{collections::HashMap, convert::TryInto, ffi::OsStr, io, process::Output};
 
 static CIBADMIN_PATH: &str = "/usr/sbin/cibadmin";
 
@@ -23,6 +26,12 @@ fn cibadmin_cmd() -> Command {
     cmd
 }
 
+/// Constraints `crm_resource --ban` and `--move` add, which come and go with bans
+const TRANSIENT_CONSTRAINTS: [&str; 2] = [r#"id="cli-ban-"#, r#"id="cli-prefer-"#];
+
+/// Cluster options pacemaker updates on its own
+const VOLATILE_OPTIONS: [&str; 2] = [r#"name="last-lrm-refresh""#, r#"name="dc-version""#];
+
 static CRM_MON_PATH: &str = "/usr/sbin/crm_mon";
 
 pub(crate) fn crm_mon_cmd() -> Command {
@@ -225,9 +234,99 @@ pub async fn get_crm_mon() -> Result<Option<Cluster>, ImlAgentError> {
 
     read_banned_output(&ban_output.stdout, &mut x)?;
 
+    x.config = get_ha_config().await.unwrap_or_else(|e| {
+        tracing::warn!("Could not read the cluster configuration: {}", e);
+
+        None
+    });
+
     Ok(Some(x))
 }
 
+pub(crate) fn is_transient_constraint(x: &str) -> bool {
+    TRANSIENT_CONSTRAINTS.iter().any(|id| x.contains(id))
+}
+
+/// Drops the lines of a `cibadmin --query` output that change without anyone
+/// changing the configuration, so configurations can be compared line by line.
+pub(crate) fn normalize_cib(x: &str) -> String {
+    x.lines()
+        .filter(|x| !is_transient_constraint(x))
+        .filter(|x| !VOLATILE_OPTIONS.iter().any(|name| x.contains(name)))
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The `configuration` section of the CIB and the corosync.conf of this node
+pub async fn get_ha_config() -> Result<Option<HaConfig>, ImlAgentError> {
+    if !file_exists(CIBADMIN_PATH).await {
+        return Ok(None);
+    }
+
+    let corosync_conf = match read_corosync_conf().await {
+        Ok(x) => x,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let cib = get_cib_configuration().await?;
+
+    Ok(Some(HaConfig {
+        cib: normalize_cib(&cib),
+        corosync_conf: String::from_utf8_lossy(&corosync_conf).to_string(),
+    }))
+}
+
+pub async fn get_cib_configuration() -> Result<String, ImlAgentError> {
+    let o = cibadmin_cmd()
+        .args(&["--query", "--scope", "configuration"])
+        .checked_output()
+        .await?;
+
+    Ok(String::from_utf8_lossy(&o.stdout).to_string())
+}
+
+/// Carries the ban and move constraints of `current` over to `baseline`,
+/// so replacing the configuration with `baseline` leaves them in place.
+pub(crate) fn merge_transient(baseline: &str, current: &str) -> String {
+    let xs: Vec<_> = current
+        .lines()
+        .filter(|x| is_transient_constraint(x))
+        .collect();
+
+    if xs.is_empty() {
+        return baseline.to_string();
+    }
+
+    baseline
+        .lines()
+        .flat_map(|l| {
+            let t = l.trim();
+
+            if t == "</constraints>" {
+                xs.iter().copied().chain(std::iter::once(l)).collect()
+            } else if t == "<constraints/>" {
+                std::iter::once("<constraints>")
+                    .chain(xs.iter().copied())
+                    .chain(std::iter::once("</constraints>"))
+                    .collect()
+            } else {
+                vec![l]
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub async fn replace_cib_configuration(xml: &str) -> Result<(), ImlAgentError> {
+    cibadmin_cmd()
+        .args(&["--replace", "--scope", "configuration", "--xml-text", xml])
+        .checked_status()
+        .err_into()
+        .await
+}
+
 fn required_arg<'a>(arg: &str, x: &'a HashMap<&str, String>) -> Result<&'a str, ImlAgentError> {
     x.get(arg)
         .map(|x| x.as_str())
@@ -588,6 +687,52 @@ mod tests {
         insta::assert_debug_snapshot!(cluster);
     }
 
+    static CIB: &str = r#"<configuration>
+  <crm_config>
+    <cluster_property_set id="cib-bootstrap-options">
+      <nvpair id="cib-bootstrap-options-dc-version" name="dc-version" value="1.1.23"/>
+      <nvpair id="cib-bootstrap-options-no-quorum-policy" name="no-quorum-policy" value="stop"/>
+    </cluster_property_set>
+  </crm_config>
+  <constraints>
+    <rsc_location id="fs-OST0000-loc" rsc="fs-OST0000" node="oss1" score="20"/>
+    <rsc_location id="cli-ban-fs-OST0000-on-oss1" rsc="fs-OST0000" role="Started" node="oss1" score="-INFINITY"/>
+  </constraints>
+</configuration>"#;
+
+    #[test]
+    fn test_normalize_cib() {
+        let x = normalize_cib(CIB);
+
+        assert!(!x.contains("dc-version"));
+        assert!(!x.contains("cli-ban-"));
+        assert!(x.contains("no-quorum-policy"));
+        assert!(x.contains("fs-OST0000-loc"));
+    }
+
+    #[test]
+    fn test_merge_transient() {
+        let baseline = normalize_cib(CIB);
+
+        let x = merge_transient(&baseline, CIB);
+
+        assert!(x.contains("cli-ban-fs-OST0000-on-oss1"));
+        assert_eq!(normalize_cib(&x), baseline);
+
+        let x = merge_transient("<configuration>\n<constraints/>\n</configuration>", CIB);
+
+        assert_eq!(
+            x.lines().map(str::trim).collect::<Vec<_>>(),
+            vec![
+                "<configuration>",
+                "<constraints>",
+                r#"<rsc_location id="cli-ban-fs-OST0000-on-oss1" rsc="fs-OST0000" role="Started" node="oss1" score="-INFINITY"/>"#,
+                "</constraints>",
+                "</configuration>",
+            ]
+        );
+    }
+
     #[test]
     fn test_lustre_resource_mounts_output() {
         let x = parse_resource_xml(ES_LUSTRE_RESOURCE_MOUNTS_FIXTURE).unwrap();
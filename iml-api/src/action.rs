@@ -55,9 +55,11 @@ async fn get_actions(
 
 pub(crate) fn endpoint(
     client_filter: impl Filter<Extract = (Connection,), Error = warp::Rejection> + Clone + Send,
+    auth_filter: impl Filter<Extract = (), Error = warp::Rejection> + Clone + Send,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::path("action")
         .and(warp::get())
+        .and(auth_filter)
         .and(composite_ids())
         .and(client_filter)
         .and_then(get_actions)
@@ -0,0 +1,358 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Authentication for `iml-api` routes.
+//!
+//! Each route group is guarded by [`authenticate`] with its own list of [`Authenticator`]s.
+//! The first authenticator that recognizes the request decides who made it.
+//! A request that no authenticator recognizes is answered with `401 Unauthorized`.
+
+use crate::error::ImlApiError;
+use async_trait::async_trait;
+use iml_postgres::{sqlx, PgPool};
+use std::{sync::Arc, time::SystemTime};
+use warp::{
+    http::{header, HeaderMap, StatusCode},
+    Filter,
+};
+
+/// Who made a request.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Principal {
+    /// A user, authenticated by session or API key.
+    User(i32),
+    /// A managed server, authenticated by a client certificate signed by the manager CA.
+    Host(String),
+}
+
+impl Principal {
+    pub(crate) fn user_id(&self) -> Option<i32> {
+        match self {
+            Self::User(id) => Some(*id),
+            Self::Host(_) => None,
+        }
+    }
+}
+
+#[async_trait]
+pub(crate) trait Authenticator: Send + Sync {
+    /// Returns `None` when the request does not carry credentials this authenticator
+    /// accepts, so the next one can be tried.
+    async fn authenticate(
+        &self,
+        pool: &PgPool,
+        headers: &HeaderMap,
+    ) -> Result<Option<Principal>, ImlApiError>;
+}
+
+pub(crate) type Policy = Arc<Vec<Box<dyn Authenticator>>>;
+
+/// Authenticates with the Django session in the `sessionid` cookie.
+/// This is what the GUI uses.
+pub(crate) struct SessionCookie {
+    /// Sessions without user activity for this many seconds are rejected,
+    /// mirroring `SessionIdleTimeoutMiddleware`.
+    pub(crate) idle_timeout: Option<u64>,
+}
+
+#[async_trait]
+impl Authenticator for SessionCookie {
+    async fn authenticate(
+        &self,
+        pool: &PgPool,
+        headers: &HeaderMap,
+    ) -> Result<Option<Principal>, ImlApiError> {
+        let key = match get_cookie(headers, "sessionid") {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let x = sqlx::query!(
+            "SELECT session_data FROM django_session WHERE session_key = $1 AND expire_date > now()",
+            key
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        let session = match x.and_then(|x| decode_session(&x.session_data)) {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        if let (Some(timeout), Some(last_activity)) = (self.idle_timeout, session.last_activity) {
+            if last_activity + timeout < now() {
+                return Ok(None);
+            }
+        }
+
+        active_user(pool, session.user_id).await
+    }
+}
+
+/// Authenticates with a tastypie API key, sent as either
+/// `Authorization: Bearer <key>` or `Authorization: ApiKey <username>:<key>`.
+/// This is what manager services and scripts use.
+pub(crate) struct BearerToken;
+
+#[async_trait]
+impl Authenticator for BearerToken {
+    async fn authenticate(
+        &self,
+        pool: &PgPool,
+        headers: &HeaderMap,
+    ) -> Result<Option<Principal>, ImlApiError> {
+        let token = match headers
+            .get(header::AUTHORIZATION)
+            .and_then(|x| x.to_str().ok())
+            .and_then(parse_authorization)
+        {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let x = sqlx::query!(
+            r#"
+                SELECT u.id
+                FROM tastypie_apikey k
+                INNER JOIN auth_user u ON u.id = k.user_id
+                WHERE k.key = $1 AND ($2::text IS NULL OR u.username = $2) AND u.is_active
+            "#,
+            token.key,
+            token.username
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(x.map(|x| Principal::User(x.id)))
+    }
+}
+
+/// Authenticates a managed server by its client certificate.
+///
+/// TLS is terminated by nginx, which verifies the certificate against the manager CA
+/// and forwards the result in the `X-SSL-Client-*` headers.
+/// The certificate must also be known and not revoked.
+pub(crate) struct ClientCertificate;
+
+#[async_trait]
+impl Authenticator for ClientCertificate {
+    async fn authenticate(
+        &self,
+        pool: &PgPool,
+        headers: &HeaderMap,
+    ) -> Result<Option<Principal>, ImlApiError> {
+        let get = |name| headers.get(name).and_then(|x| x.to_str().ok());
+
+        let (name, serial) = match (
+            get("x-ssl-client-on"),
+            get("x-ssl-client-name"),
+            get("x-ssl-client-serial"),
+        ) {
+            (Some("SUCCESS"), Some(name), Some(serial)) => (name, serial),
+            _ => return Ok(None),
+        };
+
+        let x = sqlx::query!(
+            r#"
+                SELECT h.fqdn
+                FROM chroma_core_clientcertificate c
+                INNER JOIN chroma_core_managedhost h ON h.id = c.host_id
+                WHERE c.serial = $1 AND NOT c.revoked AND h.fqdn = $2 AND h.not_deleted = 't'
+            "#,
+            serial,
+            name
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(x.map(|x| Principal::Host(x.fqdn)))
+    }
+}
+
+/// Extracts the `Principal` of a request, using the first authenticator in `policy`
+/// that recognizes it.
+pub(crate) fn authenticate(
+    pool: PgPool,
+    policy: Policy,
+) -> impl Filter<Extract = (Principal,), Error = warp::Rejection> + Clone {
+    warp::header::headers_cloned().and_then(move |headers: HeaderMap| {
+        let pool = pool.clone();
+        let policy = Arc::clone(&policy);
+
+        async move {
+            for x in policy.iter() {
+                if let Some(p) = x.authenticate(&pool, &headers).await? {
+                    return Ok(p);
+                }
+            }
+
+            Err(warp::reject::custom(ImlApiError::Unauthorized))
+        }
+    })
+}
+
+/// Drops the `Principal`, for routes that only need to know the request is authenticated.
+pub(crate) fn require(
+    filter: impl Filter<Extract = (Principal,), Error = warp::Rejection> + Clone,
+) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    filter.map(|_| ()).untuple_one()
+}
+
+/// Turns failed authentication into a `401`. Other rejections are passed on.
+pub(crate) async fn handle_rejection(
+    err: warp::Rejection,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match err.find::<ImlApiError>() {
+        Some(ImlApiError::Unauthorized) => Ok(warp::reply::with_status(
+            "Unauthorized",
+            StatusCode::UNAUTHORIZED,
+        )),
+        _ => Err(err),
+    }
+}
+
+async fn active_user(pool: &PgPool, user_id: i32) -> Result<Option<Principal>, ImlApiError> {
+    let x = sqlx::query!(
+        "SELECT id FROM auth_user WHERE id = $1 AND is_active",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(x.map(|x| Principal::User(x.id)))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default()
+}
+
+fn get_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .flat_map(|x| x.split(';'))
+        .filter_map(|x| {
+            let mut xs = x.trim().splitn(2, '=');
+
+            Some((xs.next()?, xs.next()?))
+        })
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| v)
+}
+
+#[derive(Debug, PartialEq)]
+struct ApiToken<'a> {
+    username: Option<&'a str>,
+    key: &'a str,
+}
+
+fn parse_authorization(x: &str) -> Option<ApiToken> {
+    let mut xs = x.trim().splitn(2, ' ');
+
+    let scheme = xs.next()?;
+    let credentials = xs.next()?.trim();
+
+    if scheme.eq_ignore_ascii_case("bearer") && !credentials.is_empty() {
+        Some(ApiToken {
+            username: None,
+            key: credentials,
+        })
+    } else if scheme.eq_ignore_ascii_case("apikey") {
+        let mut xs = credentials.splitn(2, ':');
+
+        let username = xs.next().filter(|x| !x.is_empty())?;
+        let key = xs.next().filter(|x| !x.is_empty())?;
+
+        Some(ApiToken {
+            username: Some(username),
+            key,
+        })
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Session {
+    user_id: i32,
+    last_activity: Option<u64>,
+}
+
+/// Decodes Django's `session_data`.
+///
+/// Django 1.11 stores it as base64 of `<hash>:<json>`. The hash only guards
+/// against tampering with the stored data, so it is not checked here.
+fn decode_session(x: &str) -> Option<Session> {
+    let x = base64::decode(x.trim()).ok()?;
+
+    let idx = x.iter().position(|c| *c == b':')?;
+
+    let data: serde_json::Value = serde_json::from_slice(&x[idx + 1..]).ok()?;
+
+    let user_id = data.get("_auth_user_id")?.as_str()?.parse().ok()?;
+    let last_activity = data.get("last_activity").and_then(|x| x.as_u64());
+
+    Some(Session {
+        user_id,
+        last_activity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::http::HeaderValue;
+
+    #[test]
+    fn test_get_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("csrftoken=abc; sessionid=k3y"),
+        );
+
+        assert_eq!(get_cookie(&headers, "sessionid"), Some("k3y"));
+        assert_eq!(get_cookie(&headers, "other"), None);
+    }
+
+    #[test]
+    fn test_parse_authorization() {
+        assert_eq!(
+            parse_authorization("Bearer abc123"),
+            Some(ApiToken {
+                username: None,
+                key: "abc123"
+            })
+        );
+        assert_eq!(
+            parse_authorization("ApiKey api:abc123"),
+            Some(ApiToken {
+                username: Some("api"),
+                key: "abc123"
+            })
+        );
+        assert_eq!(parse_authorization("ApiKey abc123"), None);
+        assert_eq!(parse_authorization("Basic YWRtaW46"), None);
+    }
+
+    #[test]
+    fn test_decode_session() {
+        let x = base64::encode(
+            r#"ab12cd:{"_auth_user_id":"7","_auth_user_backend":"django.contrib.auth.backends.ModelBackend","last_activity":1609236000}"#,
+        );
+
+        assert_eq!(
+            decode_session(&x),
+            Some(Session {
+                user_id: 7,
+                last_activity: Some(1_609_236_000)
+            })
+        );
+        assert_eq!(decode_session(&base64::encode("ab12cd:{}")), None);
+    }
+}
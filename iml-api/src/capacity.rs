@@ -0,0 +1,326 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, CAPACITY_FORECAST_LOCK},
+};
+use chrono::{DateTime, Utc};
+use iml_influx::{capacity, Client, Precision};
+use iml_manager_env::{get_influxdb_addr, get_influxdb_metrics_db};
+use iml_postgres::{alert, sqlx, PgPool};
+use iml_wire_types::{graphql::CapacityForecast, AlertRecordType, AlertSeverity};
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+use url::Url;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// How much usage history a forecast is fit to.
+const HISTORY_DAYS: u32 = 30;
+
+/// Fewer daily samples than this are not enough to tell growth from noise.
+const MIN_SAMPLES: usize = 3;
+
+/// Two sided 95% interval of a normal distribution.
+const Z_95: f64 = 1.96;
+
+const SECS_PER_DAY: f64 = 86_400.0;
+
+/// The usage of a filesystem on one day
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    /// Seconds since the epoch
+    time: i64,
+    bytes_total: f64,
+    bytes_used: f64,
+}
+
+/// Periodically forecasts the capacity of every filesystem.
+///
+/// A filesystem projected to fill up within `alert_days` has a `CapacityForecastAlert` raised,
+/// which is lowered again once it no longer is.
+pub async fn run(pg_pool: PgPool, alert_days: u32) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, CAPACITY_FORECAST_LOCK, || {
+            check_filesystems(&pg_pool, alert_days)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error forecasting filesystem capacity: {}", e);
+        }
+    }
+}
+
+async fn check_filesystems(pool: &PgPool, alert_days: u32) -> Result<(), ImlApiError> {
+    let xs =
+        sqlx::query!("SELECT id, name FROM chroma_core_managedfilesystem WHERE not_deleted = 't'")
+            .fetch_all(pool)
+            .await?;
+
+    if xs.is_empty() {
+        return Ok(());
+    }
+
+    let content_type_id = sqlx::query!(
+        "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'managedfilesystem'"
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    for x in xs {
+        let fill_date = forecast(&x.name, alert_days)
+            .await?
+            .and_then(|f| f.fill_date);
+
+        match fill_date {
+            Some(fill_date) => {
+                tracing::info!("{} is projected to fill up by {}", x.name, fill_date);
+
+                alert::raise(
+                    pool,
+                    AlertRecordType::CapacityForecastAlert,
+                    format!(
+                        "{} is projected to fill up by {}",
+                        x.name,
+                        fill_date.format("%Y-%m-%d")
+                    ),
+                    content_type_id,
+                    None,
+                    AlertSeverity::WARNING,
+                    x.id,
+                )
+                .await?;
+            }
+            None => {
+                alert::lower(pool, vec![AlertRecordType::CapacityForecastAlert], x.id).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Forecasts the capacity of `fs_name` over the next `horizon_days`,
+/// from its OST usage over the last `HISTORY_DAYS`.
+///
+/// `None` when there are no stats for the filesystem.
+pub(crate) async fn forecast(
+    fs_name: &str,
+    horizon_days: u32,
+) -> Result<Option<CapacityForecast>, ImlApiError> {
+    let url = Url::parse(&format!("http://{}", get_influxdb_addr()))?;
+    let client = Client::new(url, get_influxdb_metrics_db());
+
+    let nodes = client
+        .query(
+            &capacity::query(fs_name, HISTORY_DAYS),
+            Some(Precision::Seconds),
+        )
+        .await
+        .map_err(iml_influx::Error::from)?
+        .unwrap_or_default();
+
+    // Columns are `time`, `bytes_total` and `bytes_free`.
+    let samples: Vec<_> = nodes
+        .into_iter()
+        .filter_map(|x| x.series)
+        .flatten()
+        .flat_map(|x| x.values)
+        .filter_map(|v| {
+            let bytes_total = v.get(1)?.as_f64()?;
+
+            Some(Sample {
+                time: v.get(0)?.as_i64()?,
+                bytes_total,
+                bytes_used: bytes_total - v.get(2)?.as_f64()?,
+            })
+        })
+        .collect();
+
+    Ok(project(fs_name, &samples, Utc::now(), horizon_days))
+}
+
+/// Projects the latest usage in `samples` forward from `now` at the fitted growth rate.
+fn project(
+    fs_name: &str,
+    samples: &[Sample],
+    now: DateTime<Utc>,
+    horizon_days: u32,
+) -> Option<CapacityForecast> {
+    let last = samples.iter().max_by_key(|x| x.time)?;
+
+    let horizon = f64::from(horizon_days);
+    let remaining = (last.bytes_total - last.bytes_used).max(0.0);
+
+    let fill_date = |bytes_per_day: f64| {
+        if bytes_per_day <= 0.0 {
+            return None;
+        }
+
+        let days = remaining / bytes_per_day;
+
+        if days > horizon {
+            None
+        } else {
+            Some(now + chrono::Duration::seconds((days * SECS_PER_DAY).round() as i64))
+        }
+    };
+
+    let growth = fit(samples);
+
+    Some(CapacityForecast {
+        fs_name: fs_name.to_string(),
+        horizon_days: horizon_days as i32,
+        samples: samples.len() as i32,
+        bytes_total: last.bytes_total,
+        bytes_used: last.bytes_used,
+        growth_bytes_per_day: growth.map(|x| x.bytes_per_day),
+        bytes_used_at_horizon: growth.map(|x| {
+            (last.bytes_used + x.bytes_per_day * horizon)
+                .max(0.0)
+                .min(last.bytes_total)
+        }),
+        fill_date: growth.and_then(|x| fill_date(x.bytes_per_day)),
+        fill_date_earliest: growth.and_then(|x| fill_date(x.bytes_per_day + Z_95 * x.std_error)),
+        fill_date_latest: growth.and_then(|x| fill_date(x.bytes_per_day - Z_95 * x.std_error)),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Growth {
+    bytes_per_day: f64,
+    /// The standard error of `bytes_per_day`
+    std_error: f64,
+}
+
+/// Least squares fit of used bytes over time.
+fn fit(samples: &[Sample]) -> Option<Growth> {
+    if samples.len() < MIN_SAMPLES {
+        return None;
+    }
+
+    let n = samples.len() as f64;
+    let t0 = samples.iter().map(|x| x.time).min()?;
+
+    let xs: Vec<_> = samples
+        .iter()
+        .map(|s| ((s.time - t0) as f64 / SECS_PER_DAY, s.bytes_used))
+        .collect();
+
+    let mean_x = xs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = xs.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let sxx: f64 = xs.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let sxy: f64 = xs.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+
+    let rss: f64 = xs
+        .iter()
+        .map(|(x, y)| (y - (intercept + slope * x)).powi(2))
+        .sum();
+
+    Some(Growth {
+        bytes_per_day: slope,
+        std_error: (rss / (n - 2.0) / sxx).sqrt(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const TB: f64 = 1_000_000_000_000.0;
+
+    fn samples(used: &[f64]) -> Vec<Sample> {
+        used.iter()
+            .enumerate()
+            .map(|(i, x)| Sample {
+                time: 1_609_459_200 + i as i64 * 86_400,
+                bytes_total: 100.0 * TB,
+                bytes_used: x * TB,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fit() {
+        let x = fit(&samples(&[10.0, 12.0, 14.0, 16.0])).unwrap();
+
+        assert!((x.bytes_per_day - 2.0 * TB).abs() < 1.0);
+        assert!(x.std_error < 1.0);
+
+        let x = fit(&samples(&[10.0, 13.0, 14.0, 17.0])).unwrap();
+
+        assert!(x.std_error > 0.0);
+
+        assert_eq!(fit(&samples(&[10.0, 12.0])), None);
+    }
+
+    #[test]
+    fn test_project() {
+        let now = Utc.ymd(2021, 1, 5).and_hms(0, 0, 0);
+
+        let x = project(
+            "scratch",
+            &samples(&[70.0, 72.0, 74.0, 76.0, 78.0]),
+            now,
+            30,
+        )
+        .unwrap();
+
+        assert_eq!(x.bytes_used, 78.0 * TB);
+        assert_eq!(x.fill_date, Some(Utc.ymd(2021, 1, 16).and_hms(0, 0, 0)));
+        assert_eq!(x.bytes_used_at_horizon, Some(100.0 * TB));
+
+        // Not full within the horizon
+        let x = project("scratch", &samples(&[70.0, 72.0, 74.0, 76.0, 78.0]), now, 7).unwrap();
+
+        assert_eq!(x.fill_date, None);
+        assert_eq!(x.bytes_used_at_horizon, Some(92.0 * TB));
+    }
+
+    #[test]
+    fn test_project_bounds() {
+        let now = Utc.ymd(2021, 1, 5).and_hms(0, 0, 0);
+
+        let x = project(
+            "scratch",
+            &samples(&[70.0, 73.0, 74.0, 77.0, 78.0]),
+            now,
+            365,
+        )
+        .unwrap();
+
+        let fill_date = x.fill_date.unwrap();
+
+        assert!(x.fill_date_earliest.unwrap() < fill_date);
+        assert!(x.fill_date_latest.unwrap() > fill_date);
+    }
+
+    #[test]
+    fn test_project_shrinking() {
+        let now = Utc.ymd(2021, 1, 5).and_hms(0, 0, 0);
+
+        let x = project("scratch", &samples(&[78.0, 76.0, 74.0]), now, 365).unwrap();
+
+        assert_eq!(x.fill_date, None);
+        assert_eq!(x.fill_date_earliest, None);
+        assert_eq!(x.bytes_used_at_horizon, Some(0.0));
+
+        assert_eq!(project("scratch", &[], now, 365), None);
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, CERTIFICATE_EXPIRY_LOCK},
+};
+use chrono::{DateTime, Utc};
+use iml_postgres::{alert, sqlx, PgPool};
+use iml_wire_types::{AlertRecordType, AlertSeverity};
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Periodically checks when the agent certificate of every host expires.
+///
+/// A host whose certificate expires within `alert_days` has a `CertificateExpiryAlert` raised,
+/// which is lowered again once the certificate has been rotated.
+pub async fn run(pg_pool: PgPool, alert_days: u32) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, CERTIFICATE_EXPIRY_LOCK, || {
+            check_certificates(&pg_pool, alert_days)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error checking agent certificate expiry: {}", e);
+        }
+    }
+}
+
+async fn check_certificates(pool: &PgPool, alert_days: u32) -> Result<(), ImlApiError> {
+    // While a certificate is being rotated the newest one is the one that counts.
+    let xs = sqlx::query!(
+        r#"
+            SELECT DISTINCT ON (c.host_id) c.host_id, h.fqdn, c.not_after
+            FROM chroma_core_clientcertificate c
+            INNER JOIN chroma_core_managedhost h ON h.id = c.host_id
+            WHERE NOT c.revoked AND h.not_deleted = 't'
+            ORDER BY c.host_id, c.id DESC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if xs.is_empty() {
+        return Ok(());
+    }
+
+    let content_type_id = sqlx::query!(
+        "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'managedhost'"
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    let now = Utc::now();
+
+    for x in xs {
+        match x.not_after {
+            Some(not_after) if days_remaining(not_after, now) < alert_days as i32 => {
+                tracing::info!(
+                    "The agent certificate of {} expires on {}",
+                    x.fqdn,
+                    not_after
+                );
+
+                alert::raise(
+                    pool,
+                    AlertRecordType::CertificateExpiryAlert,
+                    format!(
+                        "The agent certificate of {} expires on {}",
+                        x.fqdn,
+                        not_after.format("%Y-%m-%d")
+                    ),
+                    content_type_id,
+                    None,
+                    AlertSeverity::WARNING,
+                    x.host_id,
+                )
+                .await?;
+            }
+            _ => {
+                alert::lower(
+                    pool,
+                    vec![AlertRecordType::CertificateExpiryAlert],
+                    x.host_id,
+                )
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whole days from `now` until `not_after`, negative once it has passed.
+pub(crate) fn days_remaining(not_after: DateTime<Utc>, now: DateTime<Utc>) -> i32 {
+    // Floored, so a certificate that has expired is never at 0 days
+    (not_after - now).num_seconds().div_euclid(86_400) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_days_remaining() {
+        let now = Utc.ymd(2021, 1, 11).and_hms(12, 0, 0);
+
+        assert_eq!(
+            days_remaining(Utc.ymd(2021, 2, 10).and_hms(12, 0, 0), now),
+            30
+        );
+        assert_eq!(
+            days_remaining(Utc.ymd(2021, 1, 12).and_hms(11, 0, 0), now),
+            0
+        );
+        assert_eq!(
+            days_remaining(Utc.ymd(2021, 1, 11).and_hms(11, 0, 0), now),
+            -1
+        );
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, CHANGE_JOURNAL_LOCK},
+};
+use iml_postgres::{sqlx, PgPool};
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long entries are kept in `change_journal`.
+/// Consumers that fall further behind than this need to resync.
+const RETENTION_DAYS: i32 = 7;
+
+/// Periodically removes old entries from `change_journal`.
+pub async fn run(pg_pool: PgPool) {
+    let mut interval = interval(PRUNE_INTERVAL);
+
+    while interval.next().await.is_some() {
+        match run_exclusive(&pg_pool, CHANGE_JOURNAL_LOCK, || prune(&pg_pool)).await {
+            Ok(None) | Ok(Some(0)) => {}
+            Ok(Some(n)) => tracing::debug!("Pruned {} change journal entries", n),
+            Err(e) => tracing::error!("Error pruning change journal: {}", e),
+        }
+    }
+}
+
+async fn prune(pg_pool: &PgPool) -> Result<u64, ImlApiError> {
+    let n = sqlx::query!(
+        "DELETE FROM change_journal WHERE changed_at < now() - make_interval(days => $1)",
+        RETENTION_DAYS
+    )
+    .execute(pg_pool)
+    .await?
+    .rows_affected();
+
+    Ok(n)
+}
@@ -0,0 +1,382 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Files that set up a client to mount a filesystem.
+//!
+//! Each is built from the NIDs of the MGS, the same way as the client mount command:
+//! a systemd mount unit, an `/etc/fstab` line, and an `/etc/lnet.conf` for the client network.
+//! They are downloaded from `/api/client-mount/{fs_name}/{kind}`, so a new client is set up
+//! by copying files instead of editing them.
+
+use crate::error::ImlApiError;
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::graphql::ClientMountConfigKind;
+use warp::{
+    http::{header, HeaderValue, StatusCode},
+    reply::Response,
+    Filter, Reply as _,
+};
+
+/// Options for the generated files, all of them optional.
+#[derive(Debug, Default, serde::Deserialize)]
+pub(crate) struct Params {
+    /// The LNet network the client mounts over, e.g. `o2ib0`.
+    /// Defaults to the network of the first MGS NID.
+    pub(crate) network: Option<String>,
+    /// The interface of the client on `network`, e.g. `ib0`
+    pub(crate) interface: Option<String>,
+    /// Defaults to `/mnt/{fs_name}`
+    pub(crate) mountpoint: Option<String>,
+}
+
+/// The NIDs of the MGS of `fs_name`, leaving out hosts the MGS is banned from.
+pub(crate) async fn mgs_nids(pool: &PgPool, fs_name: &str) -> Result<Vec<String>, ImlApiError> {
+    let nids = sqlx::query!(
+        r#"
+            SELECT n.nid FROM target AS t
+            INNER JOIN lnet as l ON l.host_id = ANY(t.host_ids)
+            INNER JOIN nid as n ON n.id = ANY(l.nids)
+            WHERE t.name='MGS' AND $1 = ANY(t.filesystems)
+            AND n.host_id NOT IN (
+                SELECT nh.host_id
+                FROM corosync_resource_bans b
+                INNER JOIN corosync_node_managed_host nh ON (nh.corosync_node_id).name = b.node
+                AND nh.cluster_id = b.cluster_id
+                INNER JOIN corosync_resource r ON r.name = b.resource AND b.cluster_id = r.cluster_id
+                WHERE r.mount_point is not NULL AND r.mount_point = t.mount_path
+            )
+            GROUP BY l.host_id, n.nid ORDER BY l.host_id, n.nid
+            "#,
+        fs_name
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| x.nid)
+    .collect();
+
+    Ok(nids)
+}
+
+/// Drops a network number of `0`, as LNet does, so `tcp` and `tcp0` compare equal.
+fn normalize_network(network: &str) -> &str {
+    let name = network.trim_end_matches(|c: char| c.is_ascii_digit());
+
+    match network[name.len()..].parse::<u32>() {
+        Ok(0) => name,
+        _ => network,
+    }
+}
+
+/// The network of `nid`, e.g. `tcp` for `10.0.0.1@tcp`
+fn nid_network(nid: &str) -> Option<&str> {
+    nid.find('@').map(|i| &nid[i + 1..])
+}
+
+/// The mount source of `fs_name`, using only the `nids` on `network` when given.
+pub(crate) fn mount_source(nids: &[String], fs_name: &str, network: Option<&str>) -> String {
+    let nids: Vec<&str> = nids
+        .iter()
+        .map(|x| x.as_str())
+        .filter(|x| match network {
+            Some(network) => nid_network(x)
+                .map(|x| normalize_network(x) == normalize_network(network))
+                .unwrap_or(false),
+            None => true,
+        })
+        .collect();
+
+    format!("{}:/{}", nids.join(":"), fs_name)
+}
+
+pub(crate) fn default_mountpoint(fs_name: &str) -> String {
+    format!("/mnt/{}", fs_name)
+}
+
+/// The name systemd requires of the mount unit for `mountpoint`, as given by `systemd-escape --path`.
+fn unit_name(mountpoint: &str) -> String {
+    let path = mountpoint
+        .split('/')
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if path.is_empty() {
+        return "-.mount".into();
+    }
+
+    let escaped: String = path
+        .bytes()
+        .enumerate()
+        .map(|(i, c)| match c {
+            b'/' => "-".to_string(),
+            b'.' if i == 0 => format!("\\x{:02x}", c),
+            c if c.is_ascii_alphanumeric() || c == b':' || c == b'_' || c == b'.' => {
+                (c as char).to_string()
+            }
+            c => format!("\\x{:02x}", c),
+        })
+        .collect();
+
+    format!("{}.mount", escaped)
+}
+
+fn systemd_unit(source: &str, fs_name: &str, mountpoint: &str) -> String {
+    format!(
+        r#"# Save as /etc/systemd/system/{unit}
+# then run: systemctl daemon-reload && systemctl enable --now {unit}
+[Unit]
+Description=Lustre filesystem {fs_name}
+Requires=lnet.service
+After=lnet.service network-online.target
+Wants=network-online.target
+
+[Mount]
+What={source}
+Where={mountpoint}
+Type=lustre
+Options=_netdev,flock
+
+[Install]
+WantedBy=remote-fs.target
+"#,
+        unit = unit_name(mountpoint),
+        fs_name = fs_name,
+        source = source,
+        mountpoint = mountpoint,
+    )
+}
+
+fn fstab_line(source: &str, mountpoint: &str) -> String {
+    format!(
+        "{} {} lustre defaults,_netdev,flock 0 0\n",
+        source, mountpoint
+    )
+}
+
+/// The interface a client most likely has on `network`, when none is given.
+fn default_interface(network: &str) -> &'static str {
+    if network.starts_with("o2ib") {
+        "ib0"
+    } else {
+        "eth0"
+    }
+}
+
+fn lnet_conf(network: &str, interface: &str) -> String {
+    format!(
+        r#"# Save as /etc/lnet.conf, changing the interface to the one the client has on {network}
+# then run: systemctl enable --now lnet
+net:
+    - net type: {network}
+      local NI(s):
+        - interfaces:
+              0: {interface}
+"#,
+        network = network,
+        interface = interface,
+    )
+}
+
+/// The name a file of `kind` is downloaded as.
+fn file_name(kind: ClientMountConfigKind, fs_name: &str, mountpoint: &str) -> String {
+    match kind {
+        ClientMountConfigKind::Systemd => unit_name(mountpoint),
+        ClientMountConfigKind::Fstab => format!("{}.fstab", fs_name),
+        ClientMountConfigKind::Lnet => "lnet.conf".into(),
+    }
+}
+
+/// Renders the file of `kind` for mounting `fs_name` from `nids`.
+fn render(
+    nids: &[String],
+    fs_name: &str,
+    kind: ClientMountConfigKind,
+    params: &Params,
+) -> Result<String, ImlApiError> {
+    let network = match params
+        .network
+        .as_deref()
+        .or_else(|| nids.iter().find_map(|x| nid_network(x)))
+    {
+        Some(x) => x,
+        None => return Err(ImlApiError::MgsNotFound),
+    };
+
+    let mountpoint = params
+        .mountpoint
+        .clone()
+        .unwrap_or_else(|| default_mountpoint(fs_name));
+
+    let source = || {
+        let source = mount_source(nids, fs_name, Some(network));
+
+        if source.starts_with(":/") {
+            Err(ImlApiError::NetworkNotFound(network.to_string()))
+        } else {
+            Ok(source)
+        }
+    };
+
+    let x = match kind {
+        ClientMountConfigKind::Systemd => systemd_unit(&source()?, fs_name, &mountpoint),
+        ClientMountConfigKind::Fstab => fstab_line(&source()?, &mountpoint),
+        ClientMountConfigKind::Lnet => lnet_conf(
+            network,
+            params
+                .interface
+                .as_deref()
+                .unwrap_or_else(|| default_interface(network)),
+        ),
+    };
+
+    Ok(x)
+}
+
+/// The file of `kind` for mounting `fs_name`.
+pub(crate) async fn client_mount_config(
+    pool: &PgPool,
+    fs_name: &str,
+    kind: ClientMountConfigKind,
+    params: &Params,
+) -> Result<String, ImlApiError> {
+    let nids = mgs_nids(pool, fs_name).await?;
+
+    render(&nids, fs_name, kind, params)
+}
+
+async fn download(
+    fs_name: String,
+    kind: ClientMountConfigKind,
+    pool: PgPool,
+    params: Params,
+) -> Result<Response, warp::Rejection> {
+    let x = match client_mount_config(&pool, &fs_name, kind, &params).await {
+        Ok(x) => x,
+        Err(e @ ImlApiError::MgsNotFound) | Err(e @ ImlApiError::NetworkNotFound(_)) => {
+            return Ok(
+                warp::reply::with_status(e.to_string(), StatusCode::NOT_FOUND).into_response(),
+            )
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let mountpoint = params
+        .mountpoint
+        .unwrap_or_else(|| default_mountpoint(&fs_name));
+
+    let mut resp = Response::new(x.into());
+
+    let headers = resp.headers_mut();
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+
+    if let Ok(x) = HeaderValue::from_str(&format!(
+        "attachment; filename=\"{}\"",
+        file_name(kind, &fs_name, &mountpoint)
+    )) {
+        headers.insert(header::CONTENT_DISPOSITION, x);
+    }
+
+    Ok(resp)
+}
+
+pub(crate) fn endpoint(
+    pool: PgPool,
+    auth_filter: impl Filter<Extract = (), Error = warp::Rejection> + Clone + Send,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("client-mount" / String / ClientMountConfigKind)
+        .and(warp::get())
+        .and(auth_filter)
+        .and(warp::any().map(move || pool.clone()))
+        .and(warp::query::<Params>())
+        .and_then(download)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nids() -> Vec<String> {
+        vec![
+            "10.0.0.1@tcp".into(),
+            "192.168.0.1@o2ib".into(),
+            "10.0.0.2@tcp".into(),
+            "192.168.0.2@o2ib".into(),
+        ]
+    }
+
+    #[test]
+    fn test_mount_source() {
+        assert_eq!(
+            mount_source(&nids(), "fs", None),
+            "10.0.0.1@tcp:192.168.0.1@o2ib:10.0.0.2@tcp:192.168.0.2@o2ib:/fs"
+        );
+        assert_eq!(
+            mount_source(&nids(), "fs", Some("o2ib0")),
+            "192.168.0.1@o2ib:192.168.0.2@o2ib:/fs"
+        );
+        assert_eq!(mount_source(&nids(), "fs", Some("tcp1")), ":/fs");
+    }
+
+    #[test]
+    fn test_normalize_network() {
+        assert_eq!(normalize_network("tcp0"), "tcp");
+        assert_eq!(normalize_network("tcp"), "tcp");
+        assert_eq!(normalize_network("o2ib10"), "o2ib10");
+    }
+
+    #[test]
+    fn test_unit_name() {
+        assert_eq!(unit_name("/mnt/fs"), "mnt-fs.mount");
+        assert_eq!(unit_name("/mnt//lustre/"), "mnt-lustre.mount");
+        assert_eq!(unit_name("/mnt/my-fs"), "mnt-my\\x2dfs.mount");
+        assert_eq!(unit_name("/.fs"), "\\x2efs.mount");
+        assert_eq!(unit_name("/"), "-.mount");
+    }
+
+    #[test]
+    fn test_render() {
+        let params = Params {
+            network: Some("o2ib".into()),
+            ..Params::default()
+        };
+
+        let x = render(&nids(), "fs", ClientMountConfigKind::Systemd, &params).unwrap();
+
+        assert!(x.contains("What=192.168.0.1@o2ib:192.168.0.2@o2ib:/fs\n"));
+        assert!(x.contains("Where=/mnt/fs\n"));
+        assert!(x.contains("enable --now mnt-fs.mount"));
+
+        let x = render(
+            &nids(),
+            "fs",
+            ClientMountConfigKind::Fstab,
+            &Params::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            x,
+            "10.0.0.1@tcp:10.0.0.2@tcp:/fs /mnt/fs lustre defaults,_netdev,flock 0 0\n"
+        );
+
+        let x = render(&nids(), "fs", ClientMountConfigKind::Lnet, &params).unwrap();
+
+        assert!(x.contains("    - net type: o2ib\n"));
+        assert!(x.contains("              0: ib0\n"));
+
+        assert!(render(&[], "fs", ClientMountConfigKind::Fstab, &Params::default()).is_err());
+
+        let params = Params {
+            network: Some("tcp1".into()),
+            ..Params::default()
+        };
+
+        assert!(render(&nids(), "fs", ClientMountConfigKind::Fstab, &params).is_err());
+    }
+}
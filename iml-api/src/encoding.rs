@@ -0,0 +1,239 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Response compression and ETags for the GraphQL routes.
+//!
+//! Bodies are compressed with brotli or gzip, whichever the client prefers.
+//! Responses to `GET` queries also carry a weak ETag of the uncompressed body,
+//! so polling clients get a bodyless `304` when nothing changed.
+
+use crate::error::ImlApiError;
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io::Write,
+};
+use warp::{
+    http::{header, HeaderValue, StatusCode},
+    reply::Response,
+};
+
+/// Bodies smaller than this are sent as they are.
+const MIN_COMPRESS_LEN: usize = 1024;
+
+/// Brotli quality, 0 - 11. Higher levels cost too much CPU for per-request compression.
+const BROTLI_QUALITY: u32 = 5;
+
+/// Brotli window size, as a power of 2.
+const BROTLI_LG_WINDOW: u32 = 22;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Brotli,
+    Gzip,
+    Identity,
+}
+
+impl Encoding {
+    fn name(self) -> Option<&'static str> {
+        match self {
+            Self::Brotli => Some("br"),
+            Self::Gzip => Some("gzip"),
+            Self::Identity => None,
+        }
+    }
+}
+
+/// The quality value an `Accept-Encoding` header gives `coding`.
+/// A coding that is not listed takes the quality of `*`, or `0` without one.
+fn quality(accept_encoding: &str, coding: &str) -> f32 {
+    let mut wildcard = None;
+
+    for x in accept_encoding.split(',') {
+        let mut parts = x.split(';');
+
+        let name = parts.next().unwrap_or_default().trim();
+
+        let q = parts
+            .filter_map(|x| x.trim().strip_prefix("q="))
+            .find_map(|x| x.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        if name.eq_ignore_ascii_case(coding) {
+            return q;
+        }
+
+        if name == "*" {
+            wildcard = Some(q);
+        }
+    }
+
+    wildcard.unwrap_or(0.0)
+}
+
+/// Picks the encoding of a response. Brotli wins a tie with gzip.
+fn negotiate(accept_encoding: Option<&str>) -> Encoding {
+    let accept_encoding = match accept_encoding {
+        Some(x) => x,
+        None => return Encoding::Identity,
+    };
+
+    let br = quality(accept_encoding, "br");
+    let gzip = quality(accept_encoding, "gzip");
+
+    if br > 0.0 && br >= gzip {
+        Encoding::Brotli
+    } else if gzip > 0.0 {
+        Encoding::Gzip
+    } else {
+        Encoding::Identity
+    }
+}
+
+fn compress(encoding: Encoding, body: &[u8]) -> Result<Vec<u8>, ImlApiError> {
+    let xs = match encoding {
+        Encoding::Brotli => {
+            let mut w =
+                brotli::CompressorWriter::new(vec![], 4096, BROTLI_QUALITY, BROTLI_LG_WINDOW);
+            w.write_all(body)?;
+
+            w.into_inner()
+        }
+        Encoding::Gzip => {
+            let mut w = GzEncoder::new(vec![], Compression::default());
+            w.write_all(body)?;
+
+            w.finish()?
+        }
+        Encoding::Identity => body.to_vec(),
+    };
+
+    Ok(xs)
+}
+
+/// A weak ETag, as the same body is sent with different encodings.
+fn etag(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+
+    format!(r#"W/"{:016x}""#, hasher.finish())
+}
+
+/// Whether an `If-None-Match` header matches `etag`, using the weak comparison.
+fn matches_etag(if_none_match: &str, etag: &str) -> bool {
+    let strip = |x: &str| x.trim().trim_start_matches("W/").to_string();
+
+    let etag = strip(etag);
+
+    if_none_match
+        .split(',')
+        .any(|x| x.trim() == "*" || strip(x) == etag)
+}
+
+/// Builds a JSON response, compressed as the client accepts.
+pub(crate) fn reply(body: String, accept_encoding: Option<&str>) -> Result<Response, ImlApiError> {
+    let encoding = if body.len() < MIN_COMPRESS_LEN {
+        Encoding::Identity
+    } else {
+        negotiate(accept_encoding)
+    };
+
+    let mut res = Response::new(compress(encoding, body.as_bytes())?.into());
+
+    let headers = res.headers_mut();
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+
+    if let Some(x) = encoding.name() {
+        headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(x));
+    }
+
+    Ok(res)
+}
+
+/// Like `reply`, with an ETag. Returns a `304` when `if_none_match` already has the body.
+pub(crate) fn cached_reply(
+    body: String,
+    accept_encoding: Option<&str>,
+    if_none_match: Option<&str>,
+) -> Result<Response, ImlApiError> {
+    let tag = etag(body.as_bytes());
+
+    let mut res = if if_none_match.map(|x| matches_etag(x, &tag)) == Some(true) {
+        let mut res = Response::default();
+        *res.status_mut() = StatusCode::NOT_MODIFIED;
+
+        res
+    } else {
+        reply(body, accept_encoding)?
+    };
+
+    let headers = res.headers_mut();
+
+    // Clients may keep the response, but must check it is current before using it.
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+    if let Ok(x) = HeaderValue::from_str(&tag) {
+        headers.insert(header::ETAG, x);
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate(None), Encoding::Identity);
+        assert_eq!(negotiate(Some("gzip, deflate, br")), Encoding::Brotli);
+        assert_eq!(negotiate(Some("gzip, br;q=0.5")), Encoding::Gzip);
+        assert_eq!(negotiate(Some("br;q=0, *")), Encoding::Gzip);
+        assert_eq!(negotiate(Some("identity")), Encoding::Identity);
+        assert_eq!(negotiate(Some("gzip;q=0")), Encoding::Identity);
+    }
+
+    #[test]
+    fn test_matches_etag() {
+        let tag = etag(b"{}");
+
+        assert!(matches_etag(&tag, &tag));
+        assert!(matches_etag(tag.trim_start_matches("W/"), &tag));
+        assert!(matches_etag(&format!(r#""abc", {}"#, tag), &tag));
+        assert!(matches_etag("*", &tag));
+        assert!(!matches_etag(&etag(b"[]"), &tag));
+    }
+
+    #[test]
+    fn test_cached_reply() {
+        let body = r#"{"data":{}}"#.to_string();
+        let tag = etag(body.as_bytes());
+
+        let res = cached_reply(body.clone(), None, None).unwrap();
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::ETAG], tag.as_str());
+
+        let res = cached_reply(body, None, Some(&tag)).unwrap();
+
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn test_small_bodies_are_not_compressed() {
+        let res = reply("{}".into(), Some("br")).unwrap();
+
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+
+        let res = reply("x".repeat(MIN_COMPRESS_LEN), Some("gzip")).unwrap();
+
+        assert_eq!(res.headers()[header::CONTENT_ENCODING], "gzip");
+    }
+}
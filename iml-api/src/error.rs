@@ -3,6 +3,7 @@
 // license that can be found in the LICENSE file.
 
 use futures::channel::oneshot;
+use iml_action_client::ImlActionClientError;
 use iml_job_scheduler_rpc::ImlJobSchedulerRpcError;
 use iml_manager_client::ImlManagerClientError;
 use iml_postgres::sqlx;
@@ -12,24 +13,38 @@ use warp::reject;
 
 #[derive(Debug, Error)]
 pub enum ImlApiError {
+    #[error(transparent)]
+    ImlActionClientError(#[from] ImlActionClientError),
     #[error(transparent)]
     ImlJobSchedulerRpcError(#[from] ImlJobSchedulerRpcError),
     #[error(transparent)]
     ImlRabbitError(#[from] ImlRabbitError),
     #[error(transparent)]
     ImlManagerClientError(#[from] ImlManagerClientError),
+    #[error(transparent)]
+    InfluxError(#[from] iml_influx::Error),
     #[error("Not Found")]
     NoneError,
     #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
     OneshotCanceled(#[from] oneshot::Canceled),
     #[error(transparent)]
     SerdeJsonError(#[from] serde_json::error::Error),
     #[error(transparent)]
     SqlxError(#[from] sqlx::Error),
+    #[error(transparent)]
+    SqlxMigrateError(#[from] sqlx::migrate::MigrateError),
+    #[error(transparent)]
+    UrlParseError(#[from] url::ParseError),
     #[error("Filesystem Not Found")]
     FilesystemNotFound,
     #[error("Filesystem Not Found")]
     MgsNotFound,
+    #[error("No MGS NID on LNet network {0}")]
+    NetworkNotFound(String),
+    #[error("Unauthorized")]
+    Unauthorized,
 }
 
 impl reject::Reject for ImlApiError {}
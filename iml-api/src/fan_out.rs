@@ -0,0 +1,134 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Bounded replacements for `join_all` and `try_join_all`.
+//!
+//! Resolvers fan out over hosts, targets or clusters, and each future takes a database
+//! connection or an agent call. Polling them all at once lets a large cluster drain the pool,
+//! so at most `GRAPHQL_FAN_OUT_LIMIT` run at a time here.
+//! Outputs come back in the order of the futures, as with `join_all`.
+
+use futures::{
+    stream, Future, FutureExt as _, StreamExt as _, TryFuture, TryFutureExt as _, TryStreamExt as _,
+};
+use iml_manager_env::get_graphql_fan_out_limit;
+
+/// Awaits every future of `xs`, at most `GRAPHQL_FAN_OUT_LIMIT` at a time.
+pub(crate) async fn join_all<I>(xs: I) -> Vec<<I::Item as Future>::Output>
+where
+    I: IntoIterator,
+    I::Item: Future,
+{
+    join_all_limit(xs, get_graphql_fan_out_limit()).await
+}
+
+/// Awaits every future of `xs`, at most `GRAPHQL_FAN_OUT_LIMIT` at a time,
+/// stopping at the first error.
+pub(crate) async fn try_join_all<I>(
+    xs: I,
+) -> Result<Vec<<I::Item as TryFuture>::Ok>, <I::Item as TryFuture>::Error>
+where
+    I: IntoIterator,
+    I::Item: TryFuture,
+{
+    try_join_all_limit(xs, get_graphql_fan_out_limit()).await
+}
+
+async fn join_all_limit<I>(xs: I, limit: usize) -> Vec<<I::Item as Future>::Output>
+where
+    I: IntoIterator,
+    I::Item: Future,
+{
+    // Unordered, so a slow future does not hold up the slots of the ones after it
+    let xs = stream::iter(xs.into_iter().enumerate())
+        .map(|(i, x)| x.map(move |y| (i, y)))
+        .buffer_unordered(limit.max(1))
+        .collect()
+        .await;
+
+    in_order(xs)
+}
+
+async fn try_join_all_limit<I>(
+    xs: I,
+    limit: usize,
+) -> Result<Vec<<I::Item as TryFuture>::Ok>, <I::Item as TryFuture>::Error>
+where
+    I: IntoIterator,
+    I::Item: TryFuture,
+{
+    let xs = stream::iter(xs.into_iter().enumerate())
+        .map(|(i, x)| x.map_ok(move |y| (i, y)))
+        .buffer_unordered(limit.max(1))
+        .try_collect()
+        .await?;
+
+    Ok(in_order(xs))
+}
+
+fn in_order<T>(mut xs: Vec<(usize, T)>) -> Vec<T> {
+    xs.sort_by_key(|(i, _)| *i);
+
+    xs.into_iter().map(|(_, x)| x).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_join_all_limit() {
+        let running = AtomicUsize::new(0);
+        let max = AtomicUsize::new(0);
+
+        let xs = join_all_limit(
+            (0..10u64).map(|x| {
+                let running = &running;
+                let max = &max;
+
+                async move {
+                    let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max.fetch_max(n, Ordering::SeqCst);
+
+                    // Later futures finish first
+                    tokio::time::delay_for(Duration::from_millis(20 - x)).await;
+
+                    running.fetch_sub(1, Ordering::SeqCst);
+
+                    x * 2
+                }
+            }),
+            3,
+        )
+        .await;
+
+        assert_eq!(xs, (0..10).map(|x| x * 2).collect::<Vec<_>>());
+        assert_eq!(max.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_try_join_all_limit() {
+        let xs = try_join_all_limit((0..5).map(|x| async move { Ok::<_, String>(x) }), 2).await;
+
+        assert_eq!(xs, Ok(vec![0, 1, 2, 3, 4]));
+
+        let xs = try_join_all_limit(
+            (0..5).map(|x| async move {
+                if x == 3 {
+                    Err(format!("{} failed", x))
+                } else {
+                    Ok(x)
+                }
+            }),
+            2,
+        )
+        .await;
+
+        assert_eq!(xs, Err("3 failed".to_string()));
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Federation: peer managers are recorded in `federation_peer`
+//! and asked for their own site with the read-only `federation.site` query.
+//! Peers are never asked for their own peers, so managers can register each other.
+
+use crate::error::ImlApiError;
+use futures::future::join_all;
+use iml_graphql_queries::{federation::site, Response};
+use iml_manager_client::{Client, ImlManagerClientError, Url};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::federation::{AlertCounts, FederatedSite, FederationPeer, SiteOverview};
+use std::time::Duration;
+
+/// How long a peer has to answer before it is reported as unreachable.
+const PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A peer along with what it is queried with
+struct Peer {
+    id: i32,
+    name: String,
+    url: String,
+    api_key: String,
+    verify_tls: bool,
+}
+
+/// Checks that `x` is the `http` or `https` address of a manager.
+pub(crate) fn parse_peer_url(x: &str) -> Result<Url, String> {
+    let url = Url::parse(x.trim()).map_err(|e| format!("Invalid peer url {}: {}", x, e))?;
+
+    if url.scheme() != "https" && url.scheme() != "http" {
+        return Err(format!("The peer url {} is not http or https", x));
+    }
+
+    if url.host_str().is_none() {
+        return Err(format!("The peer url {} has no host", x));
+    }
+
+    Ok(url)
+}
+
+/// The active alerts of this manager, by severity.
+pub(crate) async fn alert_counts(pool: &PgPool) -> Result<AlertCounts, ImlApiError> {
+    let x = sqlx::query!(
+        r#"
+            SELECT
+                COUNT(*) FILTER (WHERE severity >= 50) AS "critical!",
+                COUNT(*) FILTER (WHERE severity >= 40 AND severity < 50) AS "error!",
+                COUNT(*) FILTER (WHERE severity >= 30 AND severity < 40) AS "warning!",
+                COUNT(*) FILTER (WHERE severity < 30) AS "info!"
+            FROM chroma_core_alertstate
+            WHERE active = 't'
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(AlertCounts {
+        critical: x.critical as i32,
+        error: x.error as i32,
+        warning: x.warning as i32,
+        info: x.info as i32,
+    })
+}
+
+/// The registered peers, by name.
+pub(crate) async fn peers(pool: &PgPool) -> Result<Vec<FederationPeer>, ImlApiError> {
+    let xs = sqlx::query_as!(
+        FederationPeer,
+        "SELECT id, name, url, verify_tls, created_at FROM federation_peer ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(xs)
+}
+
+pub(crate) async fn add_peer(
+    pool: &PgPool,
+    name: &str,
+    url: &str,
+    api_key: &str,
+    verify_tls: bool,
+) -> Result<FederationPeer, ImlApiError> {
+    let x = sqlx::query_as!(
+        FederationPeer,
+        r#"
+            INSERT INTO federation_peer (name, url, api_key, verify_tls)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (name) DO UPDATE
+            SET url = excluded.url, api_key = excluded.api_key, verify_tls = excluded.verify_tls
+            RETURNING id, name, url, verify_tls, created_at
+        "#,
+        name,
+        url,
+        api_key,
+        verify_tls
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(x)
+}
+
+/// Removes the peer `name`. Returns whether there was one.
+pub(crate) async fn remove_peer(pool: &PgPool, name: &str) -> Result<bool, ImlApiError> {
+    let x = sqlx::query!("DELETE FROM federation_peer WHERE name = $1", name)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+    Ok(x > 0)
+}
+
+async fn query_peer(peer: &Peer) -> Result<Response<site::Resp>, ImlManagerClientError> {
+    let url = Url::parse(&peer.url)?.join("/graphql")?;
+
+    let client = Client::builder()
+        .timeout(PEER_TIMEOUT)
+        .danger_accept_invalid_certs(!peer.verify_tls)
+        .build()?;
+
+    let x = client
+        .post(url)
+        .bearer_auth(&peer.api_key)
+        .json(&site::build())
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(x)
+}
+
+async fn peer_site(peer: Peer) -> FederatedSite {
+    let r = match query_peer(&peer).await {
+        Ok(Response::Data(x)) => Ok(x.data.federation.site),
+        Ok(Response::Errors(e)) => Err(e.messages().collect::<Vec<_>>().join(", ")),
+        Err(e) => Err(e.to_string()),
+    };
+
+    if let Err(e) = r.as_ref() {
+        tracing::warn!("Could not query federation peer {}: {}", peer.name, e);
+    }
+
+    let (overview, error) = match r {
+        Ok(x) => (Some(x), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    FederatedSite {
+        peer_id: Some(peer.id),
+        name: peer.name,
+        url: Some(peer.url),
+        overview,
+        error,
+    }
+}
+
+/// `local` as the site named `name`, followed by the site of each peer by name.
+/// All peers are queried at once, one that can't be reached is listed with its `error`.
+pub(crate) async fn sites(
+    pool: &PgPool,
+    name: String,
+    local: SiteOverview,
+) -> Result<Vec<FederatedSite>, ImlApiError> {
+    let peers = sqlx::query_as!(
+        Peer,
+        "SELECT id, name, url, api_key, verify_tls FROM federation_peer ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let local = FederatedSite {
+        peer_id: None,
+        name,
+        url: None,
+        overview: Some(local),
+        error: None,
+    };
+
+    let xs = join_all(peers.into_iter().map(peer_site)).await;
+
+    Ok(std::iter::once(local).chain(xs).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_peer_url() {
+        assert_eq!(
+            parse_peer_url("https://site-b.example.com")
+                .unwrap()
+                .as_str(),
+            "https://site-b.example.com/"
+        );
+        assert!(parse_peer_url(" http://10.0.0.2:8443 ").is_ok());
+        assert!(parse_peer_url("ftp://site-b.example.com").is_err());
+        assert!(parse_peer_url("site-b.example.com").is_err());
+        assert!(parse_peer_url("unix:/var/run/iml.sock").is_err());
+    }
+}
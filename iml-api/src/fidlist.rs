@@ -0,0 +1,328 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Downloads of the FIDs queued for a Stratagem task.
+//!
+//! Purge candidate lists can hold tens of millions of FIDs, so they are never loaded whole.
+//! FIDs are read from `chroma_core_fidtaskqueue` a page at a time and written out as they
+//! are read, one per line. A single byte `Range` is supported so downloads can be resumed.
+
+use crate::error::ImlApiError;
+use futures::{stream, Stream};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::stratagem::FidList;
+use warp::{
+    http::{header, HeaderValue, StatusCode},
+    hyper::Body,
+    reply::Response,
+    Filter, Reply as _,
+};
+
+/// How many FIDs are read from the database at a time.
+const PAGE_SIZE: i64 = 10_000;
+
+/// Where the FIDs of `task_id` are downloaded from.
+pub(crate) fn url(task_id: i32) -> String {
+    format!("/api/fidlist/{}", task_id)
+}
+
+/// The number of FIDs queued for a task and the size of their download.
+/// `None` if there is no such task.
+pub(crate) async fn get_fid_list(
+    pool: &PgPool,
+    task_id: i32,
+) -> Result<Option<FidList>, ImlApiError> {
+    let x = sqlx::query!(
+        r#"
+            SELECT
+                t.name,
+                count(q.id) AS "fids!",
+                coalesce(
+                    sum(length(format('[0x%s:0x%s:0x%s]', to_hex((q.fid).seq), to_hex((q.fid).oid), to_hex((q.fid).ver))) + 1)
+                        FILTER (WHERE q.id IS NOT NULL),
+                    0
+                )::bigint AS "size!"
+            FROM chroma_core_task t
+            LEFT JOIN chroma_core_fidtaskqueue q ON q.task_id = t.id
+            WHERE t.id = $1
+            GROUP BY t.name
+        "#,
+        task_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(x.map(|x| FidList {
+        task_id,
+        task_name: x.name,
+        fids: x.fids as f64,
+        size: x.size as f64,
+        url: url(task_id),
+    }))
+}
+
+/// A page of the FIDs queued for `task_id`, in the order they were queued.
+pub(crate) async fn get_fid_page(
+    pool: &PgPool,
+    task_id: i32,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<String>, ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT format('[0x%s:0x%s:0x%s]', to_hex((q.fid).seq), to_hex((q.fid).oid), to_hex((q.fid).ver)) AS "fid!"
+            FROM chroma_core_fidtaskqueue q
+            WHERE q.task_id = $1
+            ORDER BY q.id
+            OFFSET $2 LIMIT $3
+        "#,
+        task_id,
+        offset,
+        limit
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| x.fid)
+    .collect();
+
+    Ok(xs)
+}
+
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    Full,
+    /// First and last byte, inclusive
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Reads a `Range` header against a body of `size` bytes.
+/// Ranges that can't be parsed, and requests for several ranges, get the whole body.
+fn parse_range(range: &str, size: u64) -> ByteRange {
+    let spec = match range.trim().strip_prefix("bytes=") {
+        Some(x) if !x.contains(',') => x.trim(),
+        _ => return ByteRange::Full,
+    };
+
+    let (start, end) = match spec.find('-') {
+        Some(i) => (&spec[..i], &spec[i + 1..]),
+        None => return ByteRange::Full,
+    };
+
+    if start.is_empty() {
+        return match end.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if size == 0 => ByteRange::Unsatisfiable,
+            Ok(n) => ByteRange::Partial(size.saturating_sub(n), size - 1),
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let start = match start.parse::<u64>() {
+        Ok(x) => x,
+        Err(_) => return ByteRange::Full,
+    };
+
+    let end = match end {
+        "" => None,
+        x => match x.parse::<u64>() {
+            Ok(x) if x >= start => Some(x),
+            _ => return ByteRange::Full,
+        },
+    };
+
+    if start >= size {
+        return ByteRange::Unsatisfiable;
+    }
+
+    ByteRange::Partial(start, end.map(|x| x.min(size - 1)).unwrap_or(size - 1))
+}
+
+/// Drops the first `skip` bytes of `page` and keeps at most `remaining` of the rest.
+/// Returns what is left to skip and to send after this page.
+fn slice(mut page: Vec<u8>, skip: u64, remaining: u64) -> (Vec<u8>, u64, u64) {
+    let skipped = skip.min(page.len() as u64);
+
+    page.drain(..skipped as usize);
+
+    let kept = remaining.min(page.len() as u64);
+
+    page.truncate(kept as usize);
+
+    (page, skip - skipped, remaining - kept)
+}
+
+/// Streams `len` bytes of the FID list of `task_id`, starting at byte `start`.
+fn fids(
+    pool: PgPool,
+    task_id: i32,
+    start: u64,
+    len: u64,
+) -> impl Stream<Item = Result<Vec<u8>, ImlApiError>> {
+    stream::try_unfold((0, start, len), move |(after, skip, remaining)| {
+        let pool = pool.clone();
+
+        async move {
+            if remaining == 0 {
+                return Ok(None);
+            }
+
+            let xs = sqlx::query!(
+                r#"
+                    SELECT q.id, format('[0x%s:0x%s:0x%s]', to_hex((q.fid).seq), to_hex((q.fid).oid), to_hex((q.fid).ver)) AS "fid!"
+                    FROM chroma_core_fidtaskqueue q
+                    WHERE q.task_id = $1 AND q.id > $2
+                    ORDER BY q.id
+                    LIMIT $3
+                "#,
+                task_id,
+                after,
+                PAGE_SIZE
+            )
+            .fetch_all(&pool)
+            .await?;
+
+            let last = match xs.last() {
+                Some(x) => x.id,
+                None => return Ok(None),
+            };
+
+            let page: String = xs.into_iter().map(|x| format!("{}\n", x.fid)).collect();
+
+            let (chunk, skip, remaining) = slice(page.into_bytes(), skip, remaining);
+
+            Ok(Some((chunk, (last, skip, remaining))))
+        }
+    })
+}
+
+async fn download(
+    task_id: i32,
+    pool: PgPool,
+    range: Option<String>,
+) -> Result<Response, warp::Rejection> {
+    let x = match get_fid_list(&pool, task_id).await? {
+        Some(x) => x,
+        None => {
+            return Ok(warp::reply::with_status("Not Found", StatusCode::NOT_FOUND).into_response())
+        }
+    };
+
+    let size = x.size as u64;
+
+    let range = range
+        .as_deref()
+        .map_or(ByteRange::Full, |r| parse_range(r, size));
+
+    let (status, start, end) = match range {
+        ByteRange::Full => (StatusCode::OK, 0, size),
+        ByteRange::Partial(start, end) => (StatusCode::PARTIAL_CONTENT, start, end + 1),
+        ByteRange::Unsatisfiable => {
+            let mut resp = Response::new(Body::empty());
+
+            *resp.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+
+            if let Ok(x) = HeaderValue::from_str(&format!("bytes */{}", size)) {
+                resp.headers_mut().insert(header::CONTENT_RANGE, x);
+            }
+
+            return Ok(resp);
+        }
+    };
+
+    tracing::debug!(
+        "Sending bytes {}-{} of the {} FIDs of task {}",
+        start,
+        end,
+        x.fids,
+        task_id
+    );
+
+    let mut resp = Response::new(Body::wrap_stream(fids(pool, task_id, start, end - start)));
+
+    *resp.status_mut() = status;
+
+    let headers = resp.headers_mut();
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(end - start));
+
+    if let Ok(x) = HeaderValue::from_str(&format!(
+        "attachment; filename=\"task-{}-fids.txt\"",
+        task_id
+    )) {
+        headers.insert(header::CONTENT_DISPOSITION, x);
+    }
+
+    if status == StatusCode::PARTIAL_CONTENT {
+        if let Ok(x) = HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end - 1, size)) {
+            headers.insert(header::CONTENT_RANGE, x);
+        }
+    }
+
+    Ok(resp)
+}
+
+pub(crate) fn endpoint(
+    pool: PgPool,
+    auth_filter: impl Filter<Extract = (), Error = warp::Rejection> + Clone + Send,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("fidlist" / i32)
+        .and(warp::get())
+        .and(auth_filter)
+        .and(warp::any().map(move || pool.clone()))
+        .and(warp::header::optional("range"))
+        .and_then(download)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+        assert_eq!(
+            parse_range("bytes=500-", 1000),
+            ByteRange::Partial(500, 999)
+        );
+        assert_eq!(
+            parse_range("bytes=900-2000", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(parse_range("bytes=-2000", 1000), ByteRange::Partial(0, 999));
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-9,20-29", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=9-0", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-9", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=a-", 1000), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_slice() {
+        let page = b"[0x1:0x2:0x0]\n[0x1:0x3:0x0]\n".to_vec();
+
+        assert_eq!(
+            slice(page.clone(), 0, 100),
+            (page.clone(), 0, 100 - page.len() as u64)
+        );
+        assert_eq!(
+            slice(page.clone(), 14, 100),
+            (b"[0x1:0x3:0x0]\n".to_vec(), 0, 86)
+        );
+        assert_eq!(slice(page.clone(), 100, 10), (vec![], 72, 10));
+        assert_eq!(slice(page, 1, 4), (b"0x1:".to_vec(), 0, 0));
+    }
+}
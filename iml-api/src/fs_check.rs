@@ -0,0 +1,303 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, FS_CHECK_LOCK},
+};
+use iml_action_client::Client;
+use iml_postgres::{active_mgs_host_fqdn, alert, sqlx, PgPool};
+use iml_wire_types::{
+    graphql::{FilesystemCheck, FilesystemCheckKind},
+    AlertRecordType, AlertSeverity,
+};
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How long runs are kept in `filesystem_check`.
+const RETENTION_DAYS: i32 = 30;
+
+/// Periodically runs read-only health checks against every filesystem
+/// and records the results in `filesystem_check`.
+///
+/// A filesystem that fails any check has a `FilesystemCheckFailedAlert` raised,
+/// which is lowered again by the next run that passes.
+pub async fn run(pg_pool: PgPool, action_client: Client) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, FS_CHECK_LOCK, || {
+            check_filesystems(&pg_pool, &action_client)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error running filesystem checks: {}", e);
+        }
+    }
+}
+
+async fn check_filesystems(pool: &PgPool, action_client: &Client) -> Result<(), ImlApiError> {
+    sqlx::query!(
+        "DELETE FROM filesystem_check WHERE ran_at < now() - make_interval(days => $1)",
+        RETENTION_DAYS
+    )
+    .execute(pool)
+    .await?;
+
+    // Another replica may have just finished a run while this one waited for its tick.
+    let xs = sqlx::query!(
+        r#"
+            SELECT f.id, f.name
+            FROM chroma_core_managedfilesystem f
+            WHERE f.not_deleted = 't'
+            AND NOT EXISTS (
+                SELECT 1 FROM filesystem_check c
+                WHERE c.fs_name = f.name
+                AND c.ran_at > now() - make_interval(secs => $1)
+            )
+        "#,
+        CHECK_INTERVAL.as_secs_f64() / 2.0
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if xs.is_empty() {
+        return Ok(());
+    }
+
+    let content_type_id = sqlx::query!(
+        "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'managedfilesystem'"
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    for x in xs {
+        let checks = vec![
+            targets_on_primary(pool, &x.name).await?,
+            no_banned_resources(pool, &x.name).await?,
+            quorum(pool, &x.name).await?,
+            mgs_reachable(pool, action_client, &x.name).await?,
+        ];
+
+        let passed = checks.iter().all(|c| c.passed);
+
+        sqlx::query!(
+            "INSERT INTO filesystem_check (fs_name, passed, checks) VALUES ($1, $2, $3)",
+            x.name,
+            passed,
+            serde_json::to_value(&checks)?
+        )
+        .execute(pool)
+        .await?;
+
+        if passed {
+            alert::lower(
+                pool,
+                vec![AlertRecordType::FilesystemCheckFailedAlert],
+                x.id,
+            )
+            .await?;
+        } else {
+            tracing::info!("Health checks of {} failed: {:?}", x.name, checks);
+
+            alert::raise(
+                pool,
+                AlertRecordType::FilesystemCheckFailedAlert,
+                failed_message(&x.name, &checks),
+                content_type_id,
+                None,
+                AlertSeverity::WARNING,
+                x.id,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn targets_on_primary(pool: &PgPool, fs_name: &str) -> Result<FilesystemCheck, ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT name, state, active_host_id, host_ids
+            FROM target
+            WHERE filesystems @> $1
+            ORDER BY name
+        "#,
+        &[fs_name.to_string()][..]
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let details = xs
+        .into_iter()
+        .filter_map(|x| {
+            if x.state != "mounted" {
+                Some(format!("{} is {}", x.name, x.state))
+            } else if x.active_host_id != x.host_ids.first().copied() {
+                Some(format!("{} is not mounted on its primary server", x.name))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    Ok(check(FilesystemCheckKind::TargetsOnPrimary, details))
+}
+
+async fn no_banned_resources(pool: &PgPool, fs_name: &str) -> Result<FilesystemCheck, ImlApiError> {
+    let details = sqlx::query!(
+        r#"
+            SELECT DISTINCT b.resource, b.node
+            FROM corosync_resource_bans b
+            INNER JOIN corosync_resource r ON r.name = b.resource AND r.cluster_id = b.cluster_id
+            INNER JOIN target t ON t.mount_path = r.mount_point
+            WHERE t.filesystems @> $1
+            ORDER BY b.resource, b.node
+        "#,
+        &[fs_name.to_string()][..]
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| format!("{} is banned from {}", x.resource, x.node))
+    .collect();
+
+    Ok(check(FilesystemCheckKind::NoBannedResources, details))
+}
+
+async fn quorum(pool: &PgPool, fs_name: &str) -> Result<FilesystemCheck, ImlApiError> {
+    let details = sqlx::query!(
+        r#"
+            SELECT
+                n.cluster_id,
+                COUNT(*) FILTER (WHERE n.online) AS "online!",
+                COUNT(*) AS "total!"
+            FROM corosync_node n
+            WHERE n.cluster_id IN (
+                SELECT r.cluster_id
+                FROM corosync_resource r
+                INNER JOIN target t ON t.mount_path = r.mount_point
+                WHERE t.filesystems @> $1
+            )
+            GROUP BY n.cluster_id
+            ORDER BY n.cluster_id
+        "#,
+        &[fs_name.to_string()][..]
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .filter(|x| !has_quorum(x.online, x.total))
+    .map(|x| {
+        format!(
+            "Cluster {} has {} of {} nodes online",
+            x.cluster_id, x.online, x.total
+        )
+    })
+    .collect();
+
+    Ok(check(FilesystemCheckKind::Quorum, details))
+}
+
+async fn mgs_reachable(
+    pool: &PgPool,
+    action_client: &Client,
+    fs_name: &str,
+) -> Result<FilesystemCheck, ImlApiError> {
+    let fqdn = match active_mgs_host_fqdn(fs_name, pool).await? {
+        Some(x) => x,
+        None => {
+            return Ok(check(
+                FilesystemCheckKind::MgsReachable,
+                vec!["The MGS is not mounted".into()],
+            ))
+        }
+    };
+
+    let r = action_client
+        .invoke_rust_agent_expect_result(
+            fqdn.clone(),
+            "lctl",
+            vec!["get_param", "-n", "version"],
+            None,
+        )
+        .await;
+
+    let details = match r {
+        Ok(Ok(_)) => vec![],
+        Ok(Err(e)) => vec![format!("{} could not query Lustre: {}", fqdn, e)],
+        Err(e) => vec![format!("{} did not answer: {}", fqdn, e)],
+    };
+
+    Ok(check(FilesystemCheckKind::MgsReachable, details))
+}
+
+fn check(kind: FilesystemCheckKind, details: Vec<String>) -> FilesystemCheck {
+    FilesystemCheck {
+        kind,
+        passed: details.is_empty(),
+        details,
+    }
+}
+
+/// Corosync clusters with two nodes run with `two_node` set,
+/// so they stay quorate with a single node online.
+fn has_quorum(online: i64, total: i64) -> bool {
+    if total == 2 {
+        online >= 1
+    } else {
+        online * 2 > total
+    }
+}
+
+fn failed_message(fs_name: &str, checks: &[FilesystemCheck]) -> String {
+    let details: Vec<_> = checks
+        .iter()
+        .flat_map(|x| x.details.iter().map(String::as_str))
+        .collect();
+
+    format!(
+        "Health checks of {} failed: {}",
+        fs_name,
+        details.join("; ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_quorum() {
+        assert!(has_quorum(1, 2));
+        assert!(!has_quorum(0, 2));
+        assert!(has_quorum(2, 3));
+        assert!(!has_quorum(2, 4));
+    }
+
+    #[test]
+    fn test_failed_message() {
+        let checks = vec![
+            check(FilesystemCheckKind::TargetsOnPrimary, vec![]),
+            check(
+                FilesystemCheckKind::NoBannedResources,
+                vec!["ost0 is banned from oss1".into()],
+            ),
+            check(
+                FilesystemCheckKind::MgsReachable,
+                vec!["The MGS is not mounted".into()],
+            ),
+        ];
+
+        assert_eq!(
+            failed_message("fs", &checks),
+            "Health checks of fs failed: ost0 is banned from oss1; The MGS is not mounted"
+        );
+    }
+}
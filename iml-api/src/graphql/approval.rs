@@ -0,0 +1,481 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! A two-person rule for destructive commands.
+//!
+//! Job classes with an approval rule, e.g. `RemoveTaskJob`, are not dispatched when a user
+//! submits them. The mutation records a pending `command_approval` with the jobs instead and
+//! fails with `APPROVAL_REQUIRED`. The command is dispatched once a second user with the
+//! `ApproveCommands` permission approves it.
+
+use crate::{
+    command::get_command,
+    error::ImlApiError,
+    graphql::{audit, not_found, role, Context},
+};
+use futures::{TryFutureExt, TryStreamExt};
+use iml_postgres::{sqlx, PgPool};
+use iml_rabbit::ImlRabbitError;
+use iml_wire_types::{
+    approval::{ApprovalRule, ApprovalState, CommandApproval},
+    role::Permission,
+    Command,
+};
+use juniper::{FieldError, Value};
+use std::collections::{BTreeSet, HashMap};
+
+const DEFAULT_LIMIT: i32 = 100;
+
+pub(crate) struct ApprovalQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl ApprovalQuery {
+    /// The job classes whose commands need approval
+    async fn rules(context: &Context) -> juniper::FieldResult<Vec<ApprovalRule>> {
+        let xs = sqlx::query_as!(
+            ApprovalRule,
+            r#"
+                SELECT r.class_name, u.username AS "created_by?", r.created_at
+                FROM approval_rule r
+                LEFT JOIN auth_user u ON u.id = r.user_id
+                ORDER BY r.class_name
+            "#
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(
+        state(description = "Only list requests in this state"),
+        limit(description = "paging limit, defaults to 100"),
+    ))]
+    /// The commands held back for approval, newest first
+    async fn requests(
+        context: &Context,
+        state: Option<ApprovalState>,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<CommandApproval>> {
+        let xs = list(
+            &context.pg_pool,
+            None,
+            state,
+            limit.unwrap_or(DEFAULT_LIMIT),
+        )
+        .await?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct ApprovalMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl ApprovalMutation {
+    #[graphql(arguments(class_name(description = "The job class name, i.e. `RemoveTaskJob`")))]
+    /// Hold back the commands with a job of `class_name` until a second user approves them
+    async fn add_rule(context: &Context, class_name: String) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::ManageRoles).await?;
+
+        let known = sqlx::query!(
+            r#"
+                SELECT id FROM django_content_type
+                WHERE app_label = 'chroma_core' AND model = lower($1)
+            "#,
+            &class_name
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .is_some();
+
+        if !known || !class_name.ends_with("Job") {
+            return Err(FieldError::new(
+                format!("Unknown job class {}", class_name),
+                Value::null(),
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+                INSERT INTO approval_rule (class_name, user_id)
+                VALUES ($1, $2)
+                ON CONFLICT (class_name) DO NOTHING
+            "#,
+            &class_name,
+            context.user_id
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "approval.addRule",
+            serde_json::json!({ "className": class_name }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+    #[graphql(arguments(class_name(description = "The job class name")))]
+    /// Dispatch the commands with a job of `class_name` right away again.
+    /// Commands already held back still need approval
+    async fn remove_rule(context: &Context, class_name: String) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::ManageRoles).await?;
+
+        sqlx::query!(
+            "DELETE FROM approval_rule WHERE class_name = $1",
+            &class_name
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "approval.removeRule",
+            serde_json::json!({ "className": class_name }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+    #[graphql(arguments(id(description = "The approval request")))]
+    /// Approve a command another user submitted, and dispatch it
+    async fn approve(context: &Context, id: i32) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::ApproveCommands).await?;
+
+        let user_id = context.user_id.ok_or_else(|| {
+            FieldError::new(
+                "A command can only be approved by a signed in user",
+                Value::null(),
+            )
+        })?;
+
+        let requested_by = get_pending(&context.pg_pool, id).await?;
+
+        if requested_by == Some(user_id) {
+            return Err(FieldError::new(
+                "A command has to be approved by another user than the one that submitted it",
+                Value::null(),
+            ));
+        }
+
+        // Claiming the request first, so it is dispatched once when approved concurrently
+        let x = sqlx::query!(
+            r#"
+                UPDATE command_approval
+                SET state = 'approved', decided_by = $2, decided_at = now()
+                WHERE id = $1 AND state = 'pending'
+                RETURNING jobs, kwargs
+            "#,
+            id,
+            user_id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| not_pending(id))?;
+
+        let command_id = match dispatch(context, x.jobs, x.kwargs).await {
+            Ok(x) => x,
+            Err(e) => {
+                sqlx::query!(
+                    r#"
+                        UPDATE command_approval
+                        SET state = 'pending', decided_by = NULL, decided_at = NULL
+                        WHERE id = $1
+                    "#,
+                    id
+                )
+                .execute(&context.pg_pool)
+                .await?;
+
+                return Err(e.into());
+            }
+        };
+
+        sqlx::query!(
+            "UPDATE command_approval SET command_id = $1 WHERE id = $2",
+            command_id,
+            id
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        // The request is what submitted the command
+        sqlx::query!(
+            r#"
+                UPDATE api_audit SET command_id = $1
+                WHERE mutation = 'approval.request' AND (args->>'approvalId')::INT = $2
+            "#,
+            command_id,
+            id
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        audit::record(
+            context,
+            "approval.approve",
+            serde_json::json!({ "approvalId": id }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+    #[graphql(arguments(
+        id(description = "The approval request"),
+        reason(description = "Why the command is rejected"),
+    ))]
+    /// Reject a command, so it is never dispatched.
+    /// The user that submitted the command can withdraw it this way
+    async fn reject(
+        context: &Context,
+        id: i32,
+        reason: Option<String>,
+    ) -> juniper::FieldResult<CommandApproval> {
+        let requested_by = get_pending(&context.pg_pool, id).await?;
+
+        if context.user_id.is_some() && requested_by != context.user_id {
+            role::require(context, Permission::ApproveCommands).await?;
+        }
+
+        sqlx::query!(
+            r#"
+                UPDATE command_approval
+                SET state = 'rejected', decided_by = $2, decided_at = now(), reason = $3
+                WHERE id = $1 AND state = 'pending'
+                RETURNING id
+            "#,
+            id,
+            context.user_id,
+            reason
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| not_pending(id))?;
+
+        audit::record(
+            context,
+            "approval.reject",
+            serde_json::json!({ "approvalId": id, "reason": reason }),
+            None,
+        )
+        .await;
+
+        list(&context.pg_pool, Some(id), None, 1)
+            .await?
+            .pop()
+            .ok_or_else(|| not_found("Approval request", id))
+    }
+}
+
+/// Holds back the command of `jobs` when one of their classes has an approval rule.
+///
+/// A pending `command_approval` is recorded in its place, and the error has code
+/// `APPROVAL_REQUIRED` and the id of the request under `approval_id`.
+/// Commands IML submits itself, without a user, are never held back.
+///
+/// Otherwise returns `kwargs` marked for the job scheduler as having been checked.
+pub(crate) async fn check(
+    context: &Context,
+    jobs: &impl serde::Serialize,
+    kwargs: HashMap<String, String>,
+) -> juniper::FieldResult<HashMap<String, String>> {
+    let user_id = match context.user_id {
+        Some(x) => x,
+        None => return Ok(internal(kwargs)),
+    };
+
+    let jobs = serde_json::to_value(jobs)?;
+
+    let held: Vec<String> = sqlx::query!(
+        "SELECT class_name FROM approval_rule WHERE class_name = ANY($1) ORDER BY class_name",
+        &class_names(&jobs)
+    )
+    .fetch(&context.pg_pool)
+    .map_ok(|x| x.class_name)
+    .try_collect()
+    .await?;
+
+    if held.is_empty() {
+        return Ok(mark_checked(kwargs));
+    }
+
+    let message = kwargs.get("message").cloned().unwrap_or_default();
+
+    let id = sqlx::query!(
+        r#"
+            INSERT INTO command_approval (message, jobs, kwargs, class_names, requested_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+        "#,
+        &message,
+        jobs,
+        serde_json::to_value(&kwargs)?,
+        &held,
+        user_id
+    )
+    .fetch_one(&context.pg_pool)
+    .await?
+    .id;
+
+    audit::record(
+        context,
+        "approval.request",
+        serde_json::json!({ "approvalId": id, "message": message, "classNames": held }),
+        None,
+    )
+    .await;
+
+    let mut ext = juniper::Object::with_capacity(2);
+    ext.add_field("code", Value::scalar("APPROVAL_REQUIRED"));
+    ext.add_field("approval_id", Value::scalar(id));
+
+    Err(FieldError::new(
+        format!(
+            "{} needs the approval of a second user, it is held back as approval request {}",
+            held.join(", "),
+            id
+        ),
+        Value::Object(ext),
+    ))
+}
+
+/// Marks the `kwargs` of a command IML dispatches itself, without a user,
+/// so the job scheduler runs it without applying the approval rules.
+pub(crate) fn internal(kwargs: HashMap<String, String>) -> HashMap<String, String> {
+    mark_checked(kwargs)
+}
+
+/// Adds the kwarg telling the job scheduler that the approval rules were applied.
+/// Without it, jobs of a class with a rule are refused.
+fn mark_checked(mut kwargs: HashMap<String, String>) -> HashMap<String, String> {
+    kwargs.insert("approval".into(), "checked".into());
+
+    kwargs
+}
+
+/// The distinct class names of the jobs of a `run_jobs` call, in name order.
+fn class_names(jobs: &serde_json::Value) -> Vec<String> {
+    jobs.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|x| x.get("class_name")?.as_str())
+        .map(|x| x.to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+async fn dispatch(
+    context: &Context,
+    jobs: serde_json::Value,
+    kwargs: serde_json::Value,
+) -> Result<i32, ImlApiError> {
+    let kwargs = mark_checked(serde_json::from_value(kwargs)?);
+
+    let id: i32 = iml_job_scheduler_rpc::call(
+        &context
+            .rabbit_pool
+            .get()
+            .await
+            .map_err(ImlRabbitError::PoolError)?,
+        "run_jobs",
+        vec![jobs],
+        Some(kwargs),
+    )
+    .map_err(ImlApiError::ImlJobSchedulerRpcError)
+    .await?;
+
+    Ok(id)
+}
+
+/// The user that submitted the pending request `id`.
+async fn get_pending(pool: &PgPool, id: i32) -> juniper::FieldResult<Option<i32>> {
+    let x = sqlx::query!(
+        r#"
+            SELECT state as "state: ApprovalState", requested_by
+            FROM command_approval
+            WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| not_found("Approval request", id))?;
+
+    if x.state != ApprovalState::Pending {
+        return Err(not_pending(id));
+    }
+
+    Ok(x.requested_by)
+}
+
+/// Approval requests, newest first, all of them or the one with `id`.
+async fn list(
+    pool: &PgPool,
+    id: Option<i32>,
+    state: Option<ApprovalState>,
+    limit: i32,
+) -> Result<Vec<CommandApproval>, sqlx::Error> {
+    sqlx::query_as!(
+        CommandApproval,
+        r#"
+            SELECT
+                a.id,
+                a.message,
+                a.class_names,
+                a.state as "state: ApprovalState",
+                r.username AS "requested_by?",
+                a.requested_at,
+                d.username AS "decided_by?",
+                a.decided_at,
+                a.reason,
+                a.command_id
+            FROM command_approval a
+            LEFT JOIN auth_user r ON r.id = a.requested_by
+            LEFT JOIN auth_user d ON d.id = a.decided_by
+            WHERE ($1::INT IS NULL OR a.id = $1)
+            AND ($2::approval_state IS NULL OR a.state = $2)
+            ORDER BY a.id DESC
+            LIMIT $3
+        "#,
+        id,
+        state as Option<ApprovalState>,
+        limit as i64
+    )
+    .fetch_all(pool)
+    .await
+}
+
+fn not_pending(id: i32) -> FieldError {
+    FieldError::new(
+        format!("Approval request {} was already decided", id),
+        Value::null(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_names() {
+        let jobs = serde_json::json!([
+            { "class_name": "RemoveTaskJob", "args": { "task_id": 3 } },
+            { "class_name": "CreateTaskJob", "args": { "task_id": 4 } },
+            { "class_name": "RemoveTaskJob", "args": { "task_id": 4 } },
+        ]);
+
+        assert_eq!(
+            class_names(&jobs),
+            vec!["CreateTaskJob".to_string(), "RemoveTaskJob".to_string()]
+        );
+        assert!(class_names(&serde_json::json!({})).is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::Context;
+use iml_postgres::sqlx;
+use iml_wire_types::graphql::AuditEntry;
+
+const DEFAULT_LIMIT: i32 = 100;
+
+pub(crate) struct AuditQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl AuditQuery {
+    #[graphql(arguments(
+        limit(description = "paging limit, defaults to 100"),
+        offset(description = "Offset into items, defaults to 0"),
+        username(description = "Only list mutations made by this user"),
+        mutation(description = "Only list this mutation, e.g. `createSnapshot`"),
+        command_id(description = "Only list the mutation that started this command"),
+    ))]
+    /// Mutations made through the API, newest first.
+    async fn entries(
+        context: &Context,
+        limit: Option<i32>,
+        offset: Option<i32>,
+        username: Option<String>,
+        mutation: Option<String>,
+        command_id: Option<i32>,
+    ) -> juniper::FieldResult<Vec<AuditEntry>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT id, username, mutation, args, command_id, created_at
+                FROM api_audit
+                WHERE ($1::TEXT IS NULL OR username = $1)
+                AND ($2::TEXT IS NULL OR mutation = $2)
+                AND ($3::INT IS NULL OR command_id = $3)
+                ORDER BY id DESC
+                OFFSET $4 LIMIT $5
+            "#,
+            username,
+            mutation,
+            command_id,
+            offset.unwrap_or(0) as i64,
+            limit.unwrap_or(DEFAULT_LIMIT) as i64,
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| AuditEntry {
+            id: x.id,
+            username: x.username,
+            mutation: x.mutation,
+            args: x.args.to_string(),
+            command_id: x.command_id,
+            created_at: x.created_at,
+        })
+        .collect();
+
+        Ok(xs)
+    }
+}
+
+/// Records a mutation of the current user in `api_audit`.
+///
+/// `args` should only identify what was acted on, it is readable by every user.
+/// User preferences only affect the user setting them, so they are not recorded.
+/// The mutation has already been carried out when this is called, so failing to record it is only logged.
+pub(crate) async fn record(
+    context: &Context,
+    mutation: &str,
+    args: serde_json::Value,
+    command_id: Option<i32>,
+) {
+    let r = sqlx::query!(
+        r#"
+            INSERT INTO api_audit (user_id, username, mutation, args, command_id)
+            SELECT $1, (SELECT username FROM auth_user WHERE id = $1), $2, $3, $4
+        "#,
+        context.user_id,
+        mutation,
+        args,
+        command_id
+    )
+    .execute(&context.pg_pool)
+    .await;
+
+    if let Err(e) = r {
+        tracing::error!(
+            "Could not record {} by user {:?} in the audit trail: {}",
+            mutation,
+            context.user_id,
+            e
+        );
+    }
+}
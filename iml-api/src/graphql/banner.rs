@@ -0,0 +1,207 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Banners shown at the top of every page of the GUI, e.g. to announce planned maintenance.
+
+use crate::graphql::{audit, invalid, not_found, role, Context};
+use chrono::{DateTime, Utc};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::{
+    banner::{Banner, BannerInput, BannerSeverity},
+    role::Permission,
+};
+
+const MAX_TEXT_LEN: usize = 4096;
+
+pub(crate) struct BannerQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl BannerQuery {
+    /// The banners that did not expire, newest first
+    async fn active(context: &Context) -> juniper::FieldResult<Vec<Banner>> {
+        let xs = list(&context.pg_pool, None, true).await?;
+
+        Ok(xs)
+    }
+    /// Every banner, expired ones included, newest first
+    async fn all(context: &Context) -> juniper::FieldResult<Vec<Banner>> {
+        let xs = list(&context.pg_pool, None, false).await?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct BannerMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl BannerMutation {
+    #[graphql(arguments(
+        id(description = "The banner to change. A new banner is set when omitted"),
+        banner(description = "The banner"),
+    ))]
+    /// Set a banner for every user of the GUI, or change an existing one
+    async fn save(
+        context: &Context,
+        id: Option<i32>,
+        banner: BannerInput,
+    ) -> juniper::FieldResult<Banner> {
+        role::require(context, Permission::ManageBanners).await?;
+
+        let text = banner.text.trim().to_string();
+
+        validate(&text, banner.expires_at, id.is_none(), Utc::now()).map_err(invalid)?;
+
+        let id = match id {
+            Some(id) => {
+                sqlx::query!(
+                    r#"
+                        UPDATE banner
+                        SET severity = $2, text = $3, expires_at = $4
+                        WHERE id = $1
+                        RETURNING id
+                    "#,
+                    id,
+                    banner.severity as BannerSeverity,
+                    &text,
+                    banner.expires_at
+                )
+                .fetch_optional(&context.pg_pool)
+                .await?
+                .ok_or_else(|| not_found("Banner", id))?
+                .id
+            }
+            None => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO banner (severity, text, expires_at, user_id)
+                        VALUES ($1, $2, $3, $4)
+                        RETURNING id
+                    "#,
+                    banner.severity as BannerSeverity,
+                    &text,
+                    banner.expires_at,
+                    context.user_id
+                )
+                .fetch_one(&context.pg_pool)
+                .await?
+                .id
+            }
+        };
+
+        audit::record(
+            context,
+            "banner.save",
+            serde_json::json!({
+                "id": id,
+                "severity": banner.severity,
+                "text": text,
+                "expiresAt": banner.expires_at,
+            }),
+            None,
+        )
+        .await;
+
+        list(&context.pg_pool, Some(id), false)
+            .await?
+            .pop()
+            .ok_or_else(|| not_found("Banner", id))
+    }
+    #[graphql(arguments(id(description = "The banner to remove")))]
+    /// Remove a banner
+    async fn remove(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::ManageBanners).await?;
+
+        sqlx::query!("DELETE FROM banner WHERE id = $1 RETURNING id", id)
+            .fetch_optional(&context.pg_pool)
+            .await?
+            .ok_or_else(|| not_found("Banner", id))?;
+
+        audit::record(
+            context,
+            "banner.remove",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+}
+
+/// Banners, newest first, all of them or the one with `id`.
+/// When `active` is set, only those that did not expire.
+async fn list(pool: &PgPool, id: Option<i32>, active: bool) -> Result<Vec<Banner>, sqlx::Error> {
+    sqlx::query_as!(
+        Banner,
+        r#"
+            SELECT
+                b.id,
+                b.severity as "severity: BannerSeverity",
+                b.text,
+                b.expires_at,
+                u.username AS "created_by?",
+                b.created_at
+            FROM banner b
+            LEFT JOIN auth_user u ON u.id = b.user_id
+            WHERE ($1::INT IS NULL OR b.id = $1)
+            AND (NOT $2::BOOL OR b.expires_at IS NULL OR b.expires_at > now())
+            ORDER BY b.created_at DESC, b.id DESC
+        "#,
+        id,
+        active
+    )
+    .fetch_all(pool)
+    .await
+}
+
+fn validate(
+    text: &str,
+    expires_at: Option<DateTime<Utc>>,
+    new: bool,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    if text.is_empty() || text.chars().count() > MAX_TEXT_LEN {
+        return Err(format!(
+            "Invalid banner text, banners are 1 to {} characters",
+            MAX_TEXT_LEN
+        ));
+    }
+
+    // The GUI renders the markdown as HTML, links must not run scripts
+    if text.to_lowercase().contains("javascript:") {
+        return Err("Banner text cannot contain javascript: links".into());
+    }
+
+    if new && expires_at.map_or(false, |x| x <= now) {
+        return Err("A new banner cannot expire in the past".into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_validate() {
+        let now = Utc.ymd(2021, 2, 22).and_hms(9, 0, 0);
+        let hour = Duration::hours(1);
+
+        assert_eq!(validate("**Upgrade** tonight", None, true, now), Ok(()));
+        assert_eq!(
+            validate("Upgrade tonight", Some(now + hour), true, now),
+            Ok(())
+        );
+        assert!(validate("", None, true, now).is_err());
+        assert!(validate(&"x".repeat(MAX_TEXT_LEN + 1), None, true, now).is_err());
+        assert!(validate("[here](JavaScript:alert(1))", None, true, now).is_err());
+        assert!(validate("Upgrade tonight", Some(now - hour), true, now).is_err());
+        assert_eq!(
+            validate("Upgrade tonight", Some(now - hour), false, now),
+            Ok(())
+        );
+    }
+}
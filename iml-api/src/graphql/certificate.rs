@@ -0,0 +1,135 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    certificate::days_remaining,
+    command::get_command,
+    error::ImlApiError,
+    graphql::{approval, audit, not_found, run_jobs_kwargs, Context, SendJob},
+};
+use chrono::Utc;
+use futures::TryFutureExt;
+use iml_postgres::sqlx;
+use iml_wire_types::{
+    graphql::{HostCertificate, JobPriority},
+    Command,
+};
+use juniper::{FieldError, Value};
+use std::collections::HashMap;
+
+pub(crate) struct CertificateQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl CertificateQuery {
+    #[graphql(arguments(host_ids(description = "Only list the certificates of these hosts")))]
+    /// List the valid agent certificates of managed hosts, and when each expires.
+    async fn list(
+        context: &Context,
+        host_ids: Option<Vec<i32>>,
+    ) -> juniper::FieldResult<Vec<HostCertificate>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT c.host_id, h.fqdn, c.serial, c.not_after
+                FROM chroma_core_clientcertificate c
+                INNER JOIN chroma_core_managedhost h ON h.id = c.host_id
+                WHERE NOT c.revoked
+                AND h.not_deleted = 't'
+                AND ($1::INT[] IS NULL OR h.id = ANY($1))
+                ORDER BY h.fqdn, c.id
+            "#,
+            host_ids.as_deref(),
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let now = Utc::now();
+
+        let xs = xs
+            .into_iter()
+            .map(|x| HostCertificate {
+                host_id: x.host_id,
+                fqdn: x.fqdn,
+                serial: x.serial,
+                not_after: x.not_after,
+                days_remaining: x.not_after.map(|t| days_remaining(t, now)),
+            })
+            .collect();
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct CertificateMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl CertificateMutation {
+    #[graphql(arguments(host_ids(description = "The hosts to rotate the agent certificate of")))]
+    /// Issue hosts a new agent certificate from the manager CA.
+    /// The old certificate of a host is revoked once its agent is seen using the new one.
+    async fn rotate(context: &Context, host_ids: Vec<i32>) -> juniper::FieldResult<Command> {
+        if host_ids.is_empty() {
+            return Err(FieldError::new("No hosts given", Value::null()));
+        }
+
+        let hosts = sqlx::query!(
+            r#"
+                SELECT id, fqdn, state
+                FROM chroma_core_managedhost
+                WHERE not_deleted = 't' AND id = ANY($1)
+            "#,
+            &host_ids
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        for id in &host_ids {
+            let x = hosts
+                .iter()
+                .find(|x| x.id == *id)
+                .ok_or_else(|| not_found("Host", id))?;
+
+            if x.state != "managed" {
+                return Err(FieldError::new(
+                    format!("{} is {}, not managed", x.fqdn, x.state),
+                    Value::null(),
+                ));
+            }
+        }
+
+        let jobs: Vec<_> = host_ids
+            .iter()
+            .map(|id| SendJob {
+                class_name: "RotateAgentCertificateJob",
+                args: vec![("host_id".to_string(), serde_json::json!(id))]
+                    .into_iter()
+                    .collect::<HashMap<_, _>>(),
+            })
+            .collect();
+
+        let kwargs = run_jobs_kwargs("Rotate agent certificates", JobPriority::High);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
+
+        let command_id: i32 = iml_job_scheduler_rpc::call(
+            &context.rabbit_pool.get().await?,
+            "run_jobs",
+            vec![jobs],
+            Some(kwargs),
+        )
+        .map_err(ImlApiError::ImlJobSchedulerRpcError)
+        .await?;
+
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        audit::record(
+            context,
+            "certificate.rotate",
+            serde_json::json!({ "hostIds": host_ids }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+}
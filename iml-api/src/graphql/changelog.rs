@@ -0,0 +1,294 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    fan_out::try_join_all,
+    graphql::{audit, fs_id_by_name, Context},
+};
+use iml_postgres::sqlx;
+use iml_wire_types::graphql::{ChangelogUser, MdtChangelog};
+use juniper::{FieldError, Value};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref CURRENT_INDEX: Regex = Regex::new(r"^current[ _]index:\s*(\d+)").unwrap();
+    static ref USER: Regex = Regex::new(r"^(cl\d+\S*)\s+(\d+)(?:\s+\((\d+)\))?").unwrap();
+    static ref USER_ID: Regex = Regex::new(r"^cl\d+(?:-[\w.-]+)?$").unwrap();
+}
+
+pub(crate) struct ChangelogQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl ChangelogQuery {
+    #[graphql(arguments(fs_name(description = "The filesystem to list changelog consumers for")))]
+    /// The registered changelog consumers of each mounted MDT of `fs_name`.
+    /// Consumers that are never cleared keep every record on the MDT, until it fills up.
+    async fn users(context: &Context, fs_name: String) -> juniper::FieldResult<Vec<MdtChangelog>> {
+        let _ = fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        let mdts = get_mounted_mdts(context, &fs_name).await?;
+
+        let xs = try_join_all(mdts.into_iter().map(|(target, fqdn)| async move {
+            let param = format!("mdd.{}.changelog_users", target);
+
+            let x = context
+                .action_client
+                .invoke_rust_agent_expect_result(
+                    fqdn,
+                    "lctl",
+                    vec!["get_param", "-n", &param],
+                    None,
+                )
+                .await
+                .map_err(ImlApiError::from)?
+                .map_err(|e| {
+                    FieldError::new(format!("Could not read {}: {}", param, e), Value::null())
+                })?;
+
+            let x: String = serde_json::from_value(x)?;
+
+            parse_changelog_users(&target, &x)
+                .ok_or_else(|| FieldError::new(format!("Could not parse {}", param), Value::null()))
+        }))
+        .await?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct ChangelogMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl ChangelogMutation {
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the MDT"),
+        target(description = "The MDT to register with, e.g. `fs-MDT0000`"),
+    ))]
+    /// Register a new changelog consumer on an MDT. Returns the id assigned by Lustre.
+    /// From then on the MDT keeps changelog records until the consumer clears them.
+    async fn register(
+        context: &Context,
+        fs_name: String,
+        target: String,
+    ) -> juniper::FieldResult<String> {
+        let fqdn = get_mdt_fqdn(context, &fs_name, &target).await?;
+
+        let x = context
+            .action_client
+            .invoke_rust_agent_expect_result(
+                fqdn,
+                "lctl",
+                vec!["--device", &target, "changelog_register", "-n"],
+                None,
+            )
+            .await
+            .map_err(ImlApiError::from)?
+            .map_err(|e| {
+                FieldError::new(
+                    format!("Could not register a changelog user on {}: {}", target, e),
+                    Value::null(),
+                )
+            })?;
+
+        let x: String = serde_json::from_value(x)?;
+        let x = x.trim().to_string();
+
+        audit::record(
+            context,
+            "changelog.register",
+            serde_json::json!({ "fsName": fs_name, "target": target, "id": x }),
+            None,
+        )
+        .await;
+
+        Ok(x)
+    }
+
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the MDT"),
+        target(description = "The MDT the consumer is registered with, e.g. `fs-MDT0000`"),
+        id(description = "The id of the consumer, e.g. `cl1`"),
+    ))]
+    /// Deregister a changelog consumer from an MDT.
+    /// Records no other consumer still needs are purged.
+    async fn deregister(
+        context: &Context,
+        fs_name: String,
+        target: String,
+        id: String,
+    ) -> juniper::FieldResult<bool> {
+        if !USER_ID.is_match(&id) {
+            return Err(FieldError::new(
+                format!("Invalid changelog user {}", id),
+                Value::null(),
+            ));
+        }
+
+        let fqdn = get_mdt_fqdn(context, &fs_name, &target).await?;
+
+        context
+            .action_client
+            .invoke_rust_agent_expect_result(
+                fqdn,
+                "lctl",
+                vec!["--device", &target, "changelog_deregister", &id],
+                None,
+            )
+            .await
+            .map_err(ImlApiError::from)?
+            .map_err(|e| {
+                FieldError::new(
+                    format!("Could not deregister {} from {}: {}", id, target, e),
+                    Value::null(),
+                )
+            })?;
+
+        audit::record(
+            context,
+            "changelog.deregister",
+            serde_json::json!({ "fsName": fs_name, "target": target, "id": id }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+}
+
+/// The name and active host of each mounted MDT of `fs_name`.
+async fn get_mounted_mdts(
+    context: &Context,
+    fs_name: &str,
+) -> Result<Vec<(String, String)>, FieldError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT t.name, h.fqdn
+            FROM target t
+            INNER JOIN chroma_core_managedhost h ON h.id = t.active_host_id
+            WHERE $1 = ANY(t.filesystems) AND t.name LIKE '%-MDT%' AND t.state = 'mounted'
+            ORDER BY t.name
+        "#,
+        fs_name
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.name, x.fqdn))
+    .collect();
+
+    Ok(xs)
+}
+
+async fn get_mdt_fqdn(
+    context: &Context,
+    fs_name: &str,
+    target: &str,
+) -> Result<String, FieldError> {
+    let _ = fs_id_by_name(&context.pg_pool, fs_name).await?;
+
+    get_mounted_mdts(context, fs_name)
+        .await?
+        .into_iter()
+        .find(|(name, _)| name == target)
+        .map(|(_, fqdn)| fqdn)
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("{} is not a mounted MDT of {}", target, fs_name),
+                Value::null(),
+            )
+        })
+}
+
+/// Parses `mdd.<target>.changelog_users`, e.g.
+///
+/// ```text
+/// current index: 8
+/// ID    index (idle seconds)
+/// cl1   3 (120)
+/// ```
+///
+/// Older Lustre versions do not report idle seconds, newer ones append the record mask.
+fn parse_changelog_users(target: &str, x: &str) -> Option<MdtChangelog> {
+    let mut lines = x.lines().map(str::trim);
+
+    let current_index: u64 = lines
+        .next()
+        .and_then(|l| CURRENT_INDEX.captures(l))
+        .and_then(|c| c[1].parse().ok())?;
+
+    let users = lines
+        .filter_map(|l| USER.captures(l))
+        .filter_map(|c| {
+            let index: u64 = c[2].parse().ok()?;
+
+            Some(ChangelogUser {
+                id: c[1].to_string(),
+                index: index as f64,
+                lag: current_index.saturating_sub(index) as f64,
+                idle_secs: c.get(3).and_then(|x| x.as_str().parse().ok()),
+            })
+        })
+        .collect();
+
+    Some(MdtChangelog {
+        target: target.to_string(),
+        current_index: current_index as f64,
+        users,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_changelog_users() {
+        let x = parse_changelog_users(
+            "fs-MDT0000",
+            "current index: 1042\nID    index (idle seconds)\ncl1   1000 (35)\ncl2-robinhood   12 (86400) mask=-ATIME\n",
+        )
+        .unwrap();
+
+        assert_eq!(x.current_index, 1042.0);
+        assert_eq!(
+            x.users,
+            vec![
+                ChangelogUser {
+                    id: "cl1".into(),
+                    index: 1000.0,
+                    lag: 42.0,
+                    idle_secs: Some(35),
+                },
+                ChangelogUser {
+                    id: "cl2-robinhood".into(),
+                    index: 12.0,
+                    lag: 1030.0,
+                    idle_secs: Some(86400),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_changelog_users_without_idle() {
+        let x = parse_changelog_users("fs-MDT0000", "current index: 0\nID    index\n").unwrap();
+
+        assert!(x.users.is_empty());
+
+        let x = parse_changelog_users("fs-MDT0000", "current_index: 5\nID index\ncl1 5\n").unwrap();
+
+        assert_eq!(x.users[0].idle_secs, None);
+        assert_eq!(x.users[0].lag, 0.0);
+    }
+
+    #[test]
+    fn test_user_id() {
+        assert!(USER_ID.is_match("cl1"));
+        assert!(USER_ID.is_match("cl12-robinhood"));
+        assert!(!USER_ID.is_match("cl1 cl2"));
+        assert!(!USER_ID.is_match("-n"));
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Structured filters of the `commands` query.
+//!
+//! What a command touched is read from the locks its jobs took: the servers and filesystems it
+//! locked directly, and those of the targets it locked. Who submitted it is read from the API
+//! audit log, so commands started outside of the API have no user.
+//!
+//! The date range and outcome every match has in common are applied in the query, and the rest
+//! is matched against what the query read. Without a `created_after`, only the commands of the
+//! last `DEFAULT_WINDOW_DAYS` days are read.
+
+use crate::error::ImlApiError;
+use chrono::{DateTime, Duration, Utc};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::graphql::CommandFilter;
+
+/// How deep `and` and `or` may nest
+const MAX_DEPTH: usize = 8;
+
+/// How far back a filter without `created_after` looks
+const DEFAULT_WINDOW_DAYS: i64 = 30;
+
+/// What a filter is matched against
+#[derive(Debug)]
+struct CommandFacts {
+    id: i32,
+    created_at: DateTime<Utc>,
+    errored: bool,
+    cancelled: bool,
+    message: String,
+    users: Vec<String>,
+    hosts: Vec<String>,
+    filesystems: Vec<String>,
+}
+
+/// The conditions every command matching a filter meets
+#[derive(Debug, Default, PartialEq)]
+struct Bounds {
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    errored: Option<bool>,
+    cancelled: Option<bool>,
+}
+
+/// Combines the fields of `f` with those of its `and` filters, which every match has to meet.
+/// `or` filters only narrow it down when one of them matches, so they are left out.
+/// `None` when the filter requires both values of `errored` or `cancelled` and matches nothing.
+fn bounds(f: &CommandFilter) -> Option<Bounds> {
+    fn flag(a: Option<bool>, b: Option<bool>) -> Option<Option<bool>> {
+        match (a, b) {
+            (Some(a), Some(b)) if a != b => None,
+            (a, b) => Some(a.or(b)),
+        }
+    }
+
+    f.and.iter().flatten().try_fold(
+        Bounds {
+            created_after: f.created_after,
+            created_before: f.created_before,
+            errored: f.errored,
+            cancelled: f.cancelled,
+        },
+        |acc, x| {
+            let x = bounds(x)?;
+
+            Some(Bounds {
+                created_after: acc.created_after.max(x.created_after),
+                created_before: match (acc.created_before, x.created_before) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                },
+                errored: flag(acc.errored, x.errored)?,
+                cancelled: flag(acc.cancelled, x.cancelled)?,
+            })
+        },
+    )
+}
+
+fn depth(f: &CommandFilter) -> usize {
+    let children = f.and.iter().chain(f.or.iter()).flatten();
+
+    1 + children.map(depth).max().unwrap_or(0)
+}
+
+/// Checks a filter before it is run
+pub(super) fn validate(f: &CommandFilter) -> Result<(), String> {
+    if depth(f) > MAX_DEPTH {
+        return Err(format!(
+            "Command filters may nest at most {} levels deep",
+            MAX_DEPTH
+        ));
+    }
+
+    if let (Some(a), Some(b)) = (f.created_after, f.created_before) {
+        if a >= b {
+            return Err("createdAfter has to be before createdBefore".into());
+        }
+    }
+
+    Ok(())
+}
+
+fn matches(f: &CommandFilter, x: &CommandFacts) -> bool {
+    let fields = f.created_after.map_or(true, |t| x.created_at >= t)
+        && f.created_before.map_or(true, |t| x.created_at < t)
+        && f.errored.map_or(true, |e| x.errored == e)
+        && f.cancelled.map_or(true, |c| x.cancelled == c)
+        && f.user.as_ref().map_or(true, |u| x.users.contains(u))
+        && f.host.as_ref().map_or(true, |h| x.hosts.contains(h))
+        && f.fs_name
+            .as_ref()
+            .map_or(true, |n| x.filesystems.contains(n))
+        && f.msg.as_ref().map_or(true, |m| {
+            x.message.to_lowercase().contains(&m.to_lowercase())
+        });
+
+    let and = f.and.iter().flatten().all(|f| matches(f, x));
+
+    let or =
+        f.or.as_ref()
+            .filter(|xs| !xs.is_empty())
+            .map_or(true, |xs| xs.iter().any(|f| matches(f, x)));
+
+    fields && and && or
+}
+
+/// The ids of the commands that match `f`.
+pub(super) async fn matching_ids(
+    pool: &PgPool,
+    f: &CommandFilter,
+) -> Result<Vec<i32>, ImlApiError> {
+    let b = match bounds(f) {
+        Some(b) => b,
+        None => return Ok(vec![]),
+    };
+
+    let created_after = b
+        .created_after
+        .unwrap_or_else(|| Utc::now() - Duration::days(DEFAULT_WINDOW_DAYS));
+
+    let xs = sqlx::query_as!(
+        CommandFacts,
+        r#"
+            WITH cmd AS (
+                SELECT c.id, c.created_at, c.errored, c.cancelled, c.message
+                FROM chroma_core_command c
+                WHERE c.created_at >= $1
+                AND ($2::TIMESTAMPTZ IS NULL OR c.created_at < $2)
+                AND ($3::BOOL IS NULL OR c.errored = $3)
+                AND ($4::BOOL IS NULL OR c.cancelled = $4)
+            ),
+            locked AS (
+                SELECT DISTINCT cj.command_id, ct.model, (l->>'locked_item_id')::INT AS item_id
+                FROM cmd
+                INNER JOIN chroma_core_command_jobs cj ON cj.command_id = cmd.id
+                INNER JOIN chroma_core_job j ON j.id = cj.job_id
+                CROSS JOIN LATERAL jsonb_array_elements(
+                    COALESCE(NULLIF(j.locks_json, '')::JSONB, '[]'::JSONB)
+                ) l
+                INNER JOIN django_content_type ct ON ct.id = (l->>'locked_item_type_id')::INT
+            ),
+            locked_target AS (
+                SELECT l.command_id, t.host_ids, t.filesystems
+                FROM locked l
+                INNER JOIN chroma_core_managedtarget mt ON mt.id = l.item_id
+                INNER JOIN target t ON t.uuid = mt.uuid
+                WHERE l.model IN ('managedtarget', 'managedmgs', 'managedmdt', 'managedost')
+            )
+            SELECT
+                cmd.id AS "id!",
+                cmd.created_at AS "created_at!",
+                cmd.errored AS "errored!",
+                cmd.cancelled AS "cancelled!",
+                cmd.message AS "message!",
+                ARRAY(
+                    SELECT DISTINCT a.username FROM api_audit a
+                    WHERE a.command_id = cmd.id AND a.username IS NOT NULL
+                ) AS "users!",
+                ARRAY(
+                    SELECT h.fqdn FROM chroma_core_managedhost h
+                    WHERE h.id IN (
+                        SELECT item_id FROM locked
+                        WHERE command_id = cmd.id AND model = 'managedhost'
+                    )
+                    OR h.id IN (SELECT unnest(host_ids) FROM locked_target WHERE command_id = cmd.id)
+                ) AS "hosts!",
+                ARRAY(
+                    SELECT f.name FROM chroma_core_managedfilesystem f
+                    WHERE f.id IN (
+                        SELECT item_id FROM locked
+                        WHERE command_id = cmd.id AND model = 'managedfilesystem'
+                    )
+                    UNION
+                    SELECT unnest(filesystems) FROM locked_target WHERE command_id = cmd.id
+                ) AS "filesystems!"
+            FROM cmd
+        "#,
+        created_after,
+        b.created_before,
+        b.errored,
+        b.cancelled
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(xs
+        .into_iter()
+        .filter(|x| matches(f, x))
+        .map(|x| x.id)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn facts() -> CommandFacts {
+        CommandFacts {
+            id: 1,
+            created_at: Utc.ymd(2021, 1, 20).and_hms(10, 0, 0),
+            errored: true,
+            cancelled: false,
+            message: "Stop target fs-OST0003".into(),
+            users: vec!["admin".into()],
+            hosts: vec!["oss11".into(), "oss12".into()],
+            filesystems: vec!["fs".into()],
+        }
+    }
+
+    #[test]
+    fn test_matches_fields() {
+        let x = facts();
+
+        assert!(matches(&CommandFilter::default(), &x));
+
+        let f = CommandFilter {
+            created_after: Some(Utc.ymd(2021, 1, 18).and_hms(0, 0, 0)),
+            errored: Some(true),
+            host: Some("oss12".into()),
+            msg: Some("stop".into()),
+            ..Default::default()
+        };
+        assert!(matches(&f, &x));
+
+        let f = CommandFilter {
+            user: Some("operator".into()),
+            ..Default::default()
+        };
+        assert!(!matches(&f, &x));
+    }
+
+    #[test]
+    fn test_matches_and_or() {
+        let x = facts();
+
+        let f = CommandFilter {
+            or: Some(vec![
+                CommandFilter {
+                    host: Some("oss1".into()),
+                    ..Default::default()
+                },
+                CommandFilter {
+                    fs_name: Some("fs".into()),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+        assert!(matches(&f, &x));
+
+        let f = CommandFilter {
+            and: Some(vec![
+                CommandFilter {
+                    errored: Some(true),
+                    ..Default::default()
+                },
+                CommandFilter {
+                    cancelled: Some(true),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+        assert!(!matches(&f, &x));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let day = |d| Some(Utc.ymd(2021, 1, d).and_hms(0, 0, 0));
+
+        let f = CommandFilter {
+            created_after: day(18),
+            errored: Some(true),
+            and: Some(vec![
+                CommandFilter {
+                    created_after: day(19),
+                    created_before: day(25),
+                    ..Default::default()
+                },
+                CommandFilter {
+                    created_before: day(22),
+                    ..Default::default()
+                },
+            ]),
+            or: Some(vec![CommandFilter {
+                cancelled: Some(true),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        assert_eq!(
+            bounds(&f),
+            Some(Bounds {
+                created_after: day(19),
+                created_before: day(22),
+                errored: Some(true),
+                cancelled: None,
+            })
+        );
+
+        let f = CommandFilter {
+            errored: Some(true),
+            and: Some(vec![CommandFilter {
+                errored: Some(false),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        assert_eq!(bounds(&f), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate(&CommandFilter::default()), Ok(()));
+
+        let f = CommandFilter {
+            created_after: Some(Utc.ymd(2021, 1, 20).and_hms(0, 0, 0)),
+            created_before: Some(Utc.ymd(2021, 1, 19).and_hms(0, 0, 0)),
+            ..Default::default()
+        };
+        assert!(validate(&f).is_err());
+
+        let f = (0..MAX_DEPTH).fold(CommandFilter::default(), |f, _| CommandFilter {
+            and: Some(vec![f]),
+            ..Default::default()
+        });
+        assert!(validate(&f).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! The log messages of the hosts a command ran on, sliced per step.
+//!
+//! Steps do not record the host they ran on in a readable form, so each job is
+//! correlated with the host it was queued on and the hosts it locked. The steps of
+//! a job bound to no host get the messages of every host the command touched.
+
+use crate::graphql::{
+    locks::{parse_json, Lock},
+    Context,
+};
+use chrono::Duration;
+use iml_postgres::sqlx;
+use iml_wire_types::{db::LogMessageRecord, graphql::CommandStepLogs, LogMessage, LogSeverity};
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::TryInto,
+};
+
+/// How far around a step its messages are looked for,
+/// as steps and hosts do not share a clock.
+const WINDOW_MARGIN_SECS: i64 = 30;
+const DEFAULT_LIMIT: i32 = 200;
+const MAX_LIMIT: i32 = 1000;
+
+/// The managed hosts `locks` and the queue host of a job point at, in id order.
+fn job_hosts(queue_host_id: Option<i32>, locks: &[Lock], host_type_id: i32) -> Vec<i32> {
+    locks
+        .iter()
+        .filter(|l| l.locked_item_type_id == host_type_id)
+        .map(|l| l.locked_item_id)
+        .chain(queue_host_id)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+pub(super) async fn get_command_related_logs(
+    context: &Context,
+    command_id: i32,
+    severity: Option<LogSeverity>,
+    limit: Option<i32>,
+) -> juniper::FieldResult<Vec<CommandStepLogs>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1).min(MAX_LIMIT);
+    let severity = severity.unwrap_or(LogSeverity::Informational) as i16;
+
+    let steps = sqlx::query!(
+        r#"
+            SELECT
+                s.job_id,
+                ct.model AS class_name,
+                s.step_index,
+                s.step_count,
+                s.state,
+                s.log,
+                s.created_at,
+                s.modified_at,
+                j.queue_host_id,
+                j.locks_json
+            FROM chroma_core_stepresult s
+            INNER JOIN chroma_core_job j ON j.id = s.job_id
+            INNER JOIN django_content_type ct ON ct.id = j.content_type_id
+            WHERE s.job_id IN (SELECT job_id FROM chroma_core_command_jobs WHERE command_id = $1)
+            ORDER BY s.created_at, s.id
+        "#,
+        command_id
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    if steps.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let host_type_id = sqlx::query!(
+        "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'managedhost'"
+    )
+    .fetch_one(&context.pg_pool)
+    .await?
+    .id;
+
+    let hosts: Vec<_> = steps
+        .iter()
+        .map(|x| {
+            job_hosts(
+                x.queue_host_id,
+                &parse_json(x.job_id, &x.locks_json),
+                host_type_id,
+            )
+        })
+        .collect();
+
+    let host_ids: Vec<i32> = hosts
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let fqdns: HashMap<i32, String> = sqlx::query!(
+        "SELECT id, fqdn FROM chroma_core_managedhost WHERE id = ANY($1)",
+        &host_ids
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.id, x.fqdn))
+    .collect();
+
+    let all: Vec<String> = fqdns
+        .values()
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let margin = Duration::seconds(WINDOW_MARGIN_SECS);
+
+    let mut xs = vec![];
+
+    for (step, hosts) in steps.into_iter().zip(hosts) {
+        let step_fqdns: Vec<String> = if hosts.is_empty() {
+            all.clone()
+        } else {
+            hosts.iter().filter_map(|x| fqdns.get(x)).cloned().collect()
+        };
+
+        // One more than the limit, to tell whether the slice was cut
+        let mut messages = sqlx::query_as!(
+            LogMessageRecord,
+            r#"
+                SELECT * FROM chroma_core_logmessage
+                WHERE fqdn = ANY($1)
+                AND datetime BETWEEN $2 AND $3
+                AND severity <= $4
+                ORDER BY datetime
+                LIMIT $5
+            "#,
+            &step_fqdns,
+            step.created_at - margin,
+            step.modified_at + margin,
+            severity,
+            limit as i64 + 1
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| x.try_into())
+        .collect::<Result<Vec<LogMessage>, _>>()?;
+
+        let truncated = messages.len() > limit as usize;
+
+        messages.truncate(limit as usize);
+
+        xs.push(CommandStepLogs {
+            job_id: step.job_id,
+            class_name: step.class_name,
+            step_index: step.step_index,
+            step_count: step.step_count,
+            state: step.state,
+            log: step.log,
+            started_at: step.created_at,
+            finished_at: step.modified_at,
+            fqdns: step_fqdns,
+            messages,
+            truncated,
+        });
+    }
+
+    Ok(xs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(locked_item_type_id: i32, locked_item_id: i32) -> Lock {
+        Lock {
+            write: true,
+            begin_state: None,
+            end_state: None,
+            locked_item_id,
+            locked_item_type_id,
+        }
+    }
+
+    #[test]
+    fn test_job_hosts() {
+        let locks = vec![lock(7, 3), lock(12, 40), lock(7, 1), lock(7, 3)];
+
+        assert_eq!(job_hosts(Some(2), &locks, 7), vec![1, 2, 3]);
+        assert_eq!(job_hosts(Some(3), &locks, 7), vec![1, 3]);
+        assert_eq!(job_hosts(None, &[lock(12, 40)], 7), Vec::<i32>::new());
+    }
+}
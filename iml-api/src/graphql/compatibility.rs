@@ -0,0 +1,335 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{error::ImlApiError, fan_out::join_all, graphql::Context};
+use iml_manager_env::get_version;
+use iml_postgres::sqlx;
+use iml_wire_types::graphql::{CompatibilityReport, HostRole, HostVersions};
+use std::collections::BTreeSet;
+use version_utils::Version;
+
+/// The Lustre server releases each IML release can manage.
+const IML_SERVERS: &[(&str, &[&str])] = &[
+    ("5.1", &["2.12"]),
+    ("6.0", &["2.12"]),
+    ("6.1", &["2.12"]),
+    ("6.2", &["2.12", "2.14"]),
+    ("6.3", &["2.12", "2.14"]),
+];
+
+/// The Lustre client releases supported with each server release.
+const SERVER_CLIENTS: &[(&str, &[&str])] = &[
+    ("2.12", &["2.10", "2.12", "2.13", "2.14"]),
+    ("2.14", &["2.12", "2.13", "2.14"]),
+];
+
+const SERVER_PACKAGE: &str = "lustre";
+const CLIENT_PACKAGE: &str = "lustre-client";
+const AGENT_PACKAGE: &str = "rust-iml-agent";
+
+/// Reads the package versions of the servers and clients of `fs_name`
+/// and checks them against the supported combinations.
+pub(crate) async fn get_compatibility_report(
+    context: &Context,
+    fs_name: String,
+) -> juniper::FieldResult<CompatibilityReport> {
+    let hosts = fs_host_versions(context, &fs_name).await?;
+
+    let manager_version = get_version();
+    let issues = check(&manager_version, &hosts);
+
+    Ok(CompatibilityReport {
+        fs_name,
+        manager_version,
+        hosts,
+        supported: issues.is_empty(),
+        issues,
+    })
+}
+
+/// The unsupported combinations `fs_name` would have with `fqdn` as one more of its servers.
+pub(crate) async fn check_new_server(
+    context: &Context,
+    fs_name: &str,
+    host_id: i32,
+    fqdn: String,
+) -> Result<Vec<String>, ImlApiError> {
+    let mut hosts = fs_host_versions(context, fs_name).await?;
+
+    if !hosts
+        .iter()
+        .any(|x| x.host_id == host_id && x.role == HostRole::Server)
+    {
+        hosts.push(host_versions(context, host_id, fqdn, HostRole::Server).await);
+    }
+
+    Ok(check(&get_version(), &hosts))
+}
+
+async fn fs_host_versions(
+    context: &Context,
+    fs_name: &str,
+) -> Result<Vec<HostVersions>, ImlApiError> {
+    let hosts = sqlx::query!(
+        r#"
+            SELECT h.id, h.fqdn, 'server' AS "role!"
+            FROM chroma_core_managedhost h
+            WHERE h.not_deleted = 't'
+            AND EXISTS (
+                SELECT 1 FROM target t WHERE $1 = ANY(t.filesystems) AND h.id = ANY(t.host_ids)
+            )
+            UNION
+            SELECT h.id, h.fqdn, 'client' AS "role!"
+            FROM chroma_core_managedhost h
+            INNER JOIN chroma_core_lustreclientmount m ON m.host_id = h.id
+            WHERE h.not_deleted = 't' AND m.not_deleted = 't' AND m.filesystem = $1
+            ORDER BY 3 DESC, 2
+        "#,
+        fs_name
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    let xs = hosts.into_iter().map(|x| {
+        let role = if x.role == "server" {
+            HostRole::Server
+        } else {
+            HostRole::Client
+        };
+
+        host_versions(context, x.id, x.fqdn, role)
+    });
+
+    Ok(join_all(xs).await)
+}
+
+async fn host_versions(
+    context: &Context,
+    host_id: i32,
+    fqdn: String,
+    role: HostRole,
+) -> HostVersions {
+    let lustre_package = match role {
+        HostRole::Server => SERVER_PACKAGE,
+        HostRole::Client => CLIENT_PACKAGE,
+    };
+
+    let (lustre, agent) = futures::join!(
+        package_version(context, &fqdn, lustre_package),
+        package_version(context, &fqdn, AGENT_PACKAGE),
+    );
+
+    let (lustre, agent, error) = match (lustre, agent) {
+        (Ok(lustre), Ok(agent)) => (lustre, agent, None),
+        (Err(e), _) | (_, Err(e)) => (None, None, Some(e)),
+    };
+
+    HostVersions {
+        host_id,
+        fqdn,
+        role,
+        lustre,
+        agent,
+        error,
+    }
+}
+
+async fn package_version(
+    context: &Context,
+    fqdn: &str,
+    package: &str,
+) -> Result<Option<String>, String> {
+    let r = context
+        .action_client
+        .invoke_rust_agent_expect_result(fqdn, "package_version", package, None)
+        .await;
+
+    let x = match r {
+        Ok(Ok(x)) => serde_json::from_value::<Option<String>>(x).map_err(|e| e.to_string())?,
+        Ok(Err(e)) => return Err(e),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    Ok(x.map(|x| x.trim().to_string()))
+}
+
+/// The `major.minor` release of a version, e.g. `2.12` for `2.12.6`.
+fn release(version: &str) -> String {
+    version.split('.').take(2).collect::<Vec<_>>().join(".")
+}
+
+fn lookup<'a>(table: &[(&str, &'a [&'a str])], key: &str) -> Option<&'a [&'a str]> {
+    table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+/// Lists the unsupported combinations in `hosts`, managed by IML `manager_version`.
+fn check(manager_version: &str, hosts: &[HostVersions]) -> Vec<String> {
+    let mut issues = vec![];
+
+    let iml_release = release(manager_version);
+    let supported_servers = lookup(IML_SERVERS, &iml_release);
+
+    if supported_servers.is_none() {
+        issues.push(format!(
+            "IML {} is not in the compatibility matrix",
+            manager_version
+        ));
+    }
+
+    for x in hosts.iter().filter(|x| x.error.is_some()) {
+        issues.push(format!(
+            "Could not read the package versions of {}: {}",
+            x.fqdn,
+            x.error.as_deref().unwrap_or_default()
+        ));
+    }
+
+    let hosts: Vec<_> = hosts.iter().filter(|x| x.error.is_none()).collect();
+
+    let servers: Vec<_> = hosts
+        .iter()
+        .filter(|x| x.role == HostRole::Server)
+        .collect();
+
+    let server_releases: BTreeSet<_> = servers
+        .iter()
+        .filter_map(|x| x.lustre.as_deref())
+        .map(release)
+        .collect();
+
+    if server_releases.len() > 1 {
+        issues.push(format!(
+            "Servers run mixed Lustre releases: {}",
+            server_releases
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    for x in &servers {
+        match (x.lustre.as_deref(), supported_servers) {
+            (None, _) => issues.push(format!("{} has no Lustre server installed", x.fqdn)),
+            (Some(v), Some(supported)) if !supported.contains(&release(v).as_str()) => {
+                issues.push(format!(
+                    "{} runs Lustre server {}, which IML {} does not support",
+                    x.fqdn, v, manager_version
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    for x in hosts.iter().filter(|x| x.role == HostRole::Client) {
+        let v = match x.lustre.as_deref() {
+            Some(v) => v,
+            None => {
+                issues.push(format!("{} has no Lustre client installed", x.fqdn));
+                continue;
+            }
+        };
+
+        for server in &server_releases {
+            let supported = lookup(SERVER_CLIENTS, server).unwrap_or_default();
+
+            if !supported.contains(&release(v).as_str()) {
+                issues.push(format!(
+                    "{} runs Lustre client {}, which is not supported with {} servers",
+                    x.fqdn, v, server
+                ));
+            }
+        }
+    }
+
+    // An agent behind the others is a leftover of an update that did not finish.
+    let newest_agent = hosts
+        .iter()
+        .filter_map(|x| x.agent.as_deref())
+        .max_by_key(|x| Version::from(*x));
+
+    if let Some(newest) = newest_agent {
+        for x in &hosts {
+            if let Some(v) = x
+                .agent
+                .as_deref()
+                .filter(|v| Version::from(*v) < Version::from(newest))
+            {
+                issues.push(format!(
+                    "{} runs agent {}, older than {} on other hosts",
+                    x.fqdn, v, newest
+                ));
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(fqdn: &str, role: HostRole, lustre: Option<&str>, agent: Option<&str>) -> HostVersions {
+        HostVersions {
+            host_id: 1,
+            fqdn: fqdn.to_string(),
+            role,
+            lustre: lustre.map(|x| x.to_string()),
+            agent: agent.map(|x| x.to_string()),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_release() {
+        assert_eq!(release("2.12.6"), "2.12");
+        assert_eq!(release("2.14"), "2.14");
+        assert_eq!(release("2"), "2");
+    }
+
+    #[test]
+    fn test_check_supported() {
+        let xs = vec![
+            host("mds1", HostRole::Server, Some("2.12.6"), Some("0.5.0")),
+            host("oss1", HostRole::Server, Some("2.12.6"), Some("0.5.0")),
+            host("c1", HostRole::Client, Some("2.10.8"), None),
+        ];
+
+        assert_eq!(check("6.2.0", &xs), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_check_unsupported() {
+        let xs = vec![
+            host("mds1", HostRole::Server, Some("2.14.0"), Some("0.5.0")),
+            host("oss1", HostRole::Server, Some("2.12.6"), Some("0.4.0")),
+            host("c1", HostRole::Client, Some("2.10.8"), None),
+        ];
+
+        assert_eq!(
+            check("6.1.0", &xs),
+            vec![
+                "Servers run mixed Lustre releases: 2.12, 2.14",
+                "mds1 runs Lustre server 2.14.0, which IML 6.1.0 does not support",
+                "c1 runs Lustre client 2.10.8, which is not supported with 2.14 servers",
+                "oss1 runs agent 0.4.0, older than 0.5.0 on other hosts",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_unknown() {
+        let mut x = host("oss1", HostRole::Server, None, None);
+        x.error = Some("Not connected".to_string());
+
+        assert_eq!(
+            check("7.0.0", &[x]),
+            vec![
+                "IML 7.0.0 is not in the compatibility matrix",
+                "Could not read the package versions of oss1: Not connected",
+            ]
+        );
+    }
+}
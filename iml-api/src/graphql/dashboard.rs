@@ -0,0 +1,202 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{error::ImlApiError, graphql::Context};
+use iml_influx::{filesystems, Client};
+use iml_manager_env::{get_influxdb_addr, get_influxdb_metrics_db};
+use iml_postgres::sqlx;
+use iml_wire_types::graphql::{FilesystemOverview, TargetStateCounts};
+use std::collections::HashMap;
+use url::Url;
+
+/// The latest OST and MDT stats of a filesystem
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Stats {
+    bytes_total: Option<f64>,
+    bytes_free: Option<f64>,
+    bytes_avail: Option<f64>,
+    files_total: Option<f64>,
+    files_free: Option<f64>,
+    clients: Option<i32>,
+}
+
+/// Builds the overview of every filesystem with one database query and one influx query,
+/// however many filesystems there are.
+///
+/// Filesystems are listed even when influx can't be reached, with their stats left `null`.
+pub(crate) async fn get_dashboard_overview(
+    context: &Context,
+) -> juniper::FieldResult<Vec<FilesystemOverview>> {
+    let xs = sqlx::query!(
+        r#"
+            WITH target_models AS (
+                SELECT id FROM django_content_type
+                WHERE app_label = 'chroma_core' AND model IN ('managedmgs', 'managedmdt', 'managedost')
+            ),
+            fs_model AS (
+                SELECT id FROM django_content_type
+                WHERE app_label = 'chroma_core' AND model = 'managedfilesystem'
+            ),
+            alerts AS (
+                SELECT alert_item_type_id, alert_item_id FROM chroma_core_alertstate WHERE active = 't'
+            ),
+            fs_targets AS (
+                SELECT
+                    f.id AS fs_id,
+                    t.state,
+                    EXISTS (
+                        SELECT 1 FROM alerts a
+                        WHERE a.alert_item_id = mt.id
+                        AND a.alert_item_type_id IN (SELECT id FROM target_models)
+                    ) AS alerting,
+                    mt.id AS managed_target_id
+                FROM chroma_core_managedfilesystem f
+                INNER JOIN target t ON f.name = ANY(t.filesystems)
+                LEFT JOIN chroma_core_managedtarget mt ON mt.uuid = t.uuid AND mt.not_deleted = 't'
+                WHERE f.not_deleted = 't'
+            )
+            SELECT
+                f.id,
+                f.name,
+                COUNT(ft.fs_id) FILTER (WHERE ft.state = 'mounted') AS "mounted!",
+                COUNT(ft.fs_id) FILTER (WHERE ft.state <> 'mounted') AS "unmounted!",
+                COUNT(ft.fs_id) FILTER (WHERE ft.alerting) AS "alerting!",
+                (
+                    SELECT COUNT(*) FROM alerts a
+                    WHERE (a.alert_item_type_id IN (SELECT id FROM fs_model) AND a.alert_item_id = f.id)
+                    OR (
+                        a.alert_item_type_id IN (SELECT id FROM target_models)
+                        AND a.alert_item_id IN (
+                            SELECT managed_target_id FROM fs_targets WHERE fs_id = f.id
+                        )
+                    )
+                ) AS "active_alerts!"
+            FROM chroma_core_managedfilesystem f
+            LEFT JOIN fs_targets ft ON ft.fs_id = f.id
+            WHERE f.not_deleted = 't'
+            GROUP BY f.id, f.name
+            ORDER BY f.name
+        "#
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    if xs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let stats = match get_stats().await {
+        Ok(x) => x,
+        Err(e) => {
+            tracing::warn!("Could not fetch filesystem stats: {}", e);
+
+            HashMap::new()
+        }
+    };
+
+    let xs = xs
+        .into_iter()
+        .map(|x| {
+            let s = stats.get(&x.name).copied().unwrap_or_default();
+
+            FilesystemOverview {
+                id: x.id,
+                name: x.name,
+                bytes_total: s.bytes_total,
+                bytes_free: s.bytes_free,
+                bytes_avail: s.bytes_avail,
+                files_total: s.files_total,
+                files_free: s.files_free,
+                clients: s.clients,
+                targets: TargetStateCounts {
+                    mounted: x.mounted as i32,
+                    unmounted: x.unmounted as i32,
+                    alerting: x.alerting as i32,
+                },
+                active_alerts: x.active_alerts as i32,
+            }
+        })
+        .collect();
+
+    Ok(xs)
+}
+
+/// Fetches the stats of all filesystems, keyed by filesystem name.
+async fn get_stats() -> Result<HashMap<String, Stats>, ImlApiError> {
+    let url = Url::parse(&format!("http://{}", get_influxdb_addr()))?;
+    let client = Client::new(url, get_influxdb_metrics_db());
+
+    let nodes = client
+        .query(&filesystems::query(), None)
+        .await
+        .map_err(iml_influx::Error::from)?
+        .unwrap_or_default();
+
+    let xs = nodes
+        .into_iter()
+        .filter_map(|x| x.series)
+        .flatten()
+        .filter_map(|x| {
+            let fs = x.tags.as_ref()?.get("fs")?.as_str()?.to_string();
+            let values = x.values.into_iter().next()?;
+
+            Some((fs, to_stats(&values)))
+        })
+        .collect();
+
+    Ok(xs)
+}
+
+/// Columns are `time`, then the sums of `bytes_total`, `bytes_free`, `bytes_avail`,
+/// `files_total`, `files_free` and `connected_clients`.
+fn to_stats(values: &[serde_json::Value]) -> Stats {
+    let col = |i: usize| values.get(i).and_then(|v| v.as_f64());
+
+    Stats {
+        bytes_total: col(1),
+        bytes_free: col(2),
+        bytes_avail: col(3),
+        files_total: col(4),
+        files_free: col(5),
+        clients: col(6).map(|x| x as i32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_stats() {
+        let x = to_stats(&[
+            json!(1_609_459_200),
+            json!(1000),
+            json!(400),
+            json!(350),
+            json!(2048),
+            json!(1024),
+            json!(12),
+        ]);
+
+        assert_eq!(
+            x,
+            Stats {
+                bytes_total: Some(1000.0),
+                bytes_free: Some(400.0),
+                bytes_avail: Some(350.0),
+                files_total: Some(2048.0),
+                files_free: Some(1024.0),
+                clients: Some(12),
+            }
+        );
+
+        // A filesystem with no MDT stats yet
+        let x = to_stats(&[json!(1_609_459_200), json!(1000), json!(400)]);
+
+        assert_eq!(x.bytes_free, Some(400.0));
+        assert_eq!(x.files_total, None);
+        assert_eq!(x.clients, None);
+    }
+}
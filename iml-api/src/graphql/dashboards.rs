@@ -0,0 +1,352 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::{
+    audit, invalid, not_found, user_preferences::current_user, validate_name, Context,
+};
+use iml_postgres::sqlx;
+use iml_wire_types::{
+    graphql::{Dashboard, DashboardPanelInput},
+    DashboardMetric, DashboardPanel, DashboardPanelKind,
+};
+use juniper::{FieldError, Value};
+
+const MAX_NAME_LEN: usize = 64;
+const MAX_PANELS: usize = 24;
+
+/// What a panel covers
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    All,
+    Filesystem,
+    Target,
+}
+
+pub(crate) struct DashboardsQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl DashboardsQuery {
+    /// The dashboards of the current user, and the dashboards other users shared
+    async fn list(context: &Context) -> juniper::FieldResult<Vec<Dashboard>> {
+        let user_id = current_user(context)?;
+
+        let xs = sqlx::query!(
+            r#"
+                SELECT d.id, d.name, u.username, d.shared, d.panels, d.updated_at
+                FROM dashboard d
+                INNER JOIN auth_user u ON u.id = d.owner_id
+                WHERE d.owner_id = $1 OR d.shared = 't'
+                ORDER BY d.name, u.username
+            "#,
+            user_id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| {
+            Ok(Dashboard {
+                id: x.id,
+                name: x.name,
+                owner: x.username,
+                shared: x.shared,
+                panels: serde_json::from_value(x.panels)?,
+                updated_at: x.updated_at,
+            })
+        })
+        .collect::<Result<_, serde_json::Error>>()?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct DashboardsMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl DashboardsMutation {
+    #[graphql(arguments(
+        id(description = "The dashboard to change. A new dashboard is created when omitted"),
+        name(description = "The name of the dashboard"),
+        shared(description = "List the dashboard for all users. Defaults to `false`"),
+        panels(description = "The panels in display order, replacing any existing ones"),
+    ))]
+    /// Create a dashboard, or change one the current user owns.
+    async fn save(
+        context: &Context,
+        id: Option<i32>,
+        name: String,
+        shared: Option<bool>,
+        panels: Vec<DashboardPanelInput>,
+    ) -> juniper::FieldResult<Dashboard> {
+        let user_id = current_user(context)?;
+
+        validate(&name, &panels).map_err(invalid)?;
+
+        let shared = shared.unwrap_or(false);
+
+        let panels: Vec<DashboardPanel> = panels
+            .into_iter()
+            .map(|x| DashboardPanel {
+                title: x.title.trim().to_string(),
+                kind: x.kind,
+                metric: x.metric,
+                fs_name: x.fs_name,
+                target_name: x.target_name,
+                wide: x.wide.unwrap_or(false),
+            })
+            .collect();
+
+        let duplicate = sqlx::query!(
+            r#"
+                SELECT id FROM dashboard
+                WHERE owner_id = $1 AND name = $2 AND ($3::INT IS NULL OR id != $3)
+            "#,
+            user_id,
+            &name,
+            id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?;
+
+        if duplicate.is_some() {
+            return Err(FieldError::new(
+                format!("Dashboard {} already exists", name),
+                Value::null(),
+            ));
+        }
+
+        let x = match id {
+            Some(id) => sqlx::query!(
+                r#"
+                    UPDATE dashboard
+                    SET name = $3, shared = $4, panels = $5, updated_at = now()
+                    WHERE id = $1 AND owner_id = $2
+                    RETURNING id, updated_at
+                "#,
+                id,
+                user_id,
+                &name,
+                shared,
+                serde_json::to_value(&panels)?
+            )
+            .fetch_optional(&context.pg_pool)
+            .await?
+            .map(|x| (x.id, x.updated_at))
+            .ok_or_else(|| not_found("Dashboard", id))?,
+            None => sqlx::query!(
+                r#"
+                    INSERT INTO dashboard (name, owner_id, shared, panels)
+                    VALUES ($1, $2, $3, $4)
+                    RETURNING id, updated_at
+                "#,
+                &name,
+                user_id,
+                shared,
+                serde_json::to_value(&panels)?
+            )
+            .fetch_one(&context.pg_pool)
+            .await
+            .map(|x| (x.id, x.updated_at))?,
+        };
+
+        let owner = sqlx::query!("SELECT username FROM auth_user WHERE id = $1", user_id)
+            .fetch_one(&context.pg_pool)
+            .await?
+            .username;
+
+        audit::record(
+            context,
+            "dashboards.save",
+            serde_json::json!({ "id": x.0, "name": name }),
+            None,
+        )
+        .await;
+
+        Ok(Dashboard {
+            id: x.0,
+            name,
+            owner,
+            shared,
+            panels,
+            updated_at: x.1,
+        })
+    }
+    #[graphql(arguments(id(description = "The dashboard to remove")))]
+    /// Remove a dashboard the current user owns.
+    async fn remove(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        let user_id = current_user(context)?;
+
+        sqlx::query!(
+            "DELETE FROM dashboard WHERE id = $1 AND owner_id = $2 RETURNING name",
+            id,
+            user_id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| not_found("Dashboard", id))?;
+
+        audit::record(
+            context,
+            "dashboards.remove",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+}
+
+/// The scopes a chart of `metric` can be drawn for.
+fn metric_scopes(metric: DashboardMetric) -> &'static [Scope] {
+    match metric {
+        DashboardMetric::Io => &[Scope::All, Scope::Target],
+        DashboardMetric::Lnet => &[Scope::All],
+        DashboardMetric::OstBalance => &[Scope::All, Scope::Filesystem],
+        DashboardMetric::FilesystemUsage | DashboardMetric::MdtUsage => &[Scope::Filesystem],
+        DashboardMetric::MetadataOperations
+        | DashboardMetric::SpaceUsage
+        | DashboardMetric::FileUsage => &[Scope::Target],
+    }
+}
+
+fn validate(name: &str, panels: &[DashboardPanelInput]) -> Result<(), String> {
+    validate_name("dashboard", name, MAX_NAME_LEN)?;
+
+    if panels.len() > MAX_PANELS {
+        return Err(format!("Dashboards are limited to {} panels", MAX_PANELS));
+    }
+
+    for (i, x) in panels.iter().enumerate() {
+        let n = i + 1;
+
+        if x.title.trim().is_empty() || x.title.len() > MAX_NAME_LEN {
+            return Err(format!(
+                "Invalid title for panel {}, titles are 1 to {} characters",
+                n, MAX_NAME_LEN
+            ));
+        }
+
+        let scope = match (x.fs_name.as_deref(), x.target_name.as_deref()) {
+            (None, None) => Scope::All,
+            (Some(_), None) => Scope::Filesystem,
+            (None, Some(_)) => Scope::Target,
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "Panel {} is scoped to both a filesystem and a target",
+                    n
+                ))
+            }
+        };
+
+        match (x.kind, x.metric) {
+            (DashboardPanelKind::Chart, None) => {
+                return Err(format!("Chart panel {} has no metric", n));
+            }
+            (DashboardPanelKind::Chart, Some(metric)) => {
+                if !metric_scopes(metric).contains(&scope) {
+                    return Err(format!(
+                        "Panel {} can not chart {:?} for {}",
+                        n,
+                        metric,
+                        match scope {
+                            Scope::All => "all filesystems",
+                            Scope::Filesystem => "a filesystem",
+                            Scope::Target => "a target",
+                        }
+                    ));
+                }
+            }
+            (DashboardPanelKind::Overview, Some(_)) => {
+                return Err(format!("Overview panel {} can not have a metric", n));
+            }
+            (DashboardPanelKind::Overview, None) => {
+                if scope == Scope::Target {
+                    return Err(format!(
+                        "Overview panel {} can not be scoped to a target",
+                        n
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(
+        metric: DashboardMetric,
+        fs_name: Option<&str>,
+        target_name: Option<&str>,
+    ) -> DashboardPanelInput {
+        DashboardPanelInput {
+            title: "Panel".into(),
+            kind: DashboardPanelKind::Chart,
+            metric: Some(metric),
+            fs_name: fs_name.map(|x| x.into()),
+            target_name: target_name.map(|x| x.into()),
+            wide: None,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let panels = vec![
+            chart(DashboardMetric::Io, None, None),
+            chart(DashboardMetric::OstBalance, Some("fs1"), None),
+            chart(DashboardMetric::SpaceUsage, None, Some("fs1-OST0000")),
+            DashboardPanelInput {
+                title: "fs1".into(),
+                kind: DashboardPanelKind::Overview,
+                metric: None,
+                fs_name: Some("fs1".into()),
+                target_name: None,
+                wide: Some(true),
+            },
+        ];
+
+        assert_eq!(validate("NOC", &panels), Ok(()));
+        assert_eq!(validate("NOC", &[]), Ok(()));
+
+        assert_eq!(
+            validate(" ", &panels),
+            Err(r#"Invalid dashboard name " ", names are 1 to 64 characters"#.into())
+        );
+
+        assert_eq!(
+            validate("NOC", &[chart(DashboardMetric::Lnet, Some("fs1"), None)]),
+            Err("Panel 1 can not chart Lnet for a filesystem".into())
+        );
+
+        assert_eq!(
+            validate(
+                "NOC",
+                &[chart(
+                    DashboardMetric::FileUsage,
+                    Some("fs1"),
+                    Some("fs1-MDT0000")
+                )]
+            ),
+            Err("Panel 1 is scoped to both a filesystem and a target".into())
+        );
+
+        let mut x = chart(DashboardMetric::Io, None, None);
+        x.metric = None;
+
+        assert_eq!(
+            validate("NOC", &[x]),
+            Err("Chart panel 1 has no metric".into())
+        );
+
+        let xs = vec![chart(DashboardMetric::Io, None, None); MAX_PANELS + 1];
+
+        assert_eq!(
+            validate("NOC", &xs),
+            Err("Dashboards are limited to 24 panels".into())
+        );
+    }
+}
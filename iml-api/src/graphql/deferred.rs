@@ -0,0 +1,408 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Queries that take too long to answer within a request.
+//!
+//! `submitQuery` stores the query and returns a handle straight away. Every replica is told of
+//! submitted queries through `table_invalidate`, runs them as the user that submitted them,
+//! and stores the result split into rows, so `queryResult` can page through it. Queries and
+//! results are removed once `DEFERRED_QUERY_TTL_HOURS` have passed since they were submitted.
+
+use crate::{
+    error::ImlApiError,
+    graphql::{audit, invalid, operation_span, page_limit, Context, Loaders, Schema, MUTATION},
+    invalidate::{Invalidations, DEFERRED_QUERY_TABLE},
+};
+use iml_manager_env::{get_deferred_query_ttl_hours, get_graphql_max_page_size};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::graphql::{DeferredQuery, DeferredQueryResult, DeferredQueryState};
+use juniper::{http::GraphQLRequest, FieldError, Value};
+use serde_json::Value as JsonValue;
+use std::{str::FromStr as _, sync::Arc, time::Duration};
+use tokio::{stream::StreamExt, time::interval};
+use tracing::Instrument as _;
+use uuid::Uuid;
+
+/// How often submitted queries are looked for without being told of them,
+/// in case a notification was missed. Expired queries are removed as often.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How many rows of a result are inserted at a time.
+const ROW_BATCH_SIZE: usize = 1000;
+
+fn state(x: &str) -> Result<DeferredQueryState, FieldError> {
+    DeferredQueryState::from_str(x).map_err(invalid)
+}
+
+pub(crate) async fn submit(
+    context: &Context,
+    query: String,
+    operation_name: Option<String>,
+    variables: Option<String>,
+) -> juniper::FieldResult<DeferredQuery> {
+    if MUTATION.is_match(&query) {
+        return Err(FieldError::new(
+            "Only queries can be run in the background",
+            Value::null(),
+        ));
+    }
+
+    let variables: Option<JsonValue> =
+        variables
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| FieldError::new(format!("Invalid variables: {}", e), Value::null()))?;
+
+    let handle = Uuid::new_v4().to_hyphenated().to_string();
+
+    let x = sqlx::query!(
+        r#"
+            INSERT INTO deferred_query (handle, user_id, query, operation_name, variables, expires_at)
+            VALUES ($1, $2, $3, $4, $5, now() + make_interval(hours => $6))
+            RETURNING submitted_at, expires_at
+        "#,
+        handle,
+        context.user_id,
+        query,
+        operation_name,
+        variables,
+        get_deferred_query_ttl_hours() as i32
+    )
+    .fetch_one(&context.pg_pool)
+    .await?;
+
+    audit::record(
+        context,
+        "submitQuery",
+        serde_json::json!({ "handle": handle, "operationName": operation_name }),
+        None,
+    )
+    .await;
+
+    Ok(DeferredQuery {
+        handle,
+        state: DeferredQueryState::Pending,
+        submitted_at: x.submitted_at,
+        completed_at: None,
+        expires_at: x.expires_at,
+        error: None,
+    })
+}
+
+pub(crate) async fn result(
+    context: &Context,
+    handle: String,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> juniper::FieldResult<Option<DeferredQueryResult>> {
+    let x = sqlx::query!(
+        r#"
+            SELECT handle, state, path, error, submitted_at, completed_at, expires_at
+            FROM deferred_query
+            WHERE handle = $1 AND user_id IS NOT DISTINCT FROM $2
+        "#,
+        handle,
+        context.user_id
+    )
+    .fetch_optional(&context.pg_pool)
+    .await?;
+
+    let x = match x {
+        Some(x) => x,
+        None => return Ok(None),
+    };
+
+    let query = DeferredQuery {
+        handle: x.handle,
+        state: state(&x.state)?,
+        submitted_at: x.submitted_at,
+        completed_at: x.completed_at,
+        expires_at: x.expires_at,
+        error: x.error,
+    };
+
+    if query.state != DeferredQueryState::Complete {
+        return Ok(Some(DeferredQueryResult {
+            query,
+            path: vec![],
+            total_rows: 0,
+            rows: vec![],
+        }));
+    }
+
+    let total_rows = sqlx::query!(
+        r#"SELECT count(*) AS "count!" FROM deferred_query_row WHERE handle = $1"#,
+        handle
+    )
+    .fetch_one(&context.pg_pool)
+    .await?
+    .count;
+
+    let rows = sqlx::query!(
+        r#"
+            SELECT value
+            FROM deferred_query_row
+            WHERE handle = $1
+            ORDER BY idx
+            OFFSET $2 LIMIT $3
+        "#,
+        handle,
+        offset.unwrap_or(0).max(0) as i64,
+        page_limit(limit, get_graphql_max_page_size()) as i64
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| x.value.to_string())
+    .collect();
+
+    Ok(Some(DeferredQueryResult {
+        query,
+        path: x.path,
+        total_rows: total_rows as i32,
+        rows,
+    }))
+}
+
+/// Runs submitted queries as they come in, and removes expired ones.
+///
+/// Replicas claim queries with `SKIP LOCKED`, so each one is run once.
+pub(crate) async fn run(schema: Arc<Schema>, ctx: Arc<Context>, invalidations: Arc<Invalidations>) {
+    let ticks = interval(POLL_INTERVAL).map(|_| true);
+
+    let submitted = invalidations
+        .subscribe()
+        .filter(|x| x.affects(DEFERRED_QUERY_TABLE))
+        .map(|_| false);
+
+    let mut wakeups = ticks.merge(submitted);
+
+    while let Some(tick) = wakeups.next().await {
+        if tick {
+            if let Err(e) = remove_expired(&ctx.pg_pool).await {
+                tracing::error!("Error removing expired deferred queries: {}", e);
+            }
+        }
+
+        loop {
+            match run_next(&schema, &ctx).await {
+                Ok(true) => continue,
+                Ok(false) => break,
+                Err(e) => {
+                    tracing::error!("Error running deferred query: {}", e);
+
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn remove_expired(pool: &PgPool) -> Result<(), ImlApiError> {
+    sqlx::query!("DELETE FROM deferred_query WHERE expires_at < now()")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Runs the oldest pending query, if there is one.
+async fn run_next(schema: &Schema, ctx: &Context) -> Result<bool, ImlApiError> {
+    let x = sqlx::query!(
+        r#"
+            UPDATE deferred_query
+            SET state = 'running', started_at = now()
+            WHERE handle = (
+                SELECT handle
+                FROM deferred_query
+                WHERE state = 'pending'
+                ORDER BY submitted_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING handle, user_id, query, operation_name, variables
+        "#
+    )
+    .fetch_optional(&ctx.pg_pool)
+    .await?;
+
+    let x = match x {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+
+    tracing::debug!("Running deferred query {}", x.handle);
+
+    let variables = x.variables.map(serde_json::from_value).transpose()?;
+
+    let req = GraphQLRequest::new(x.query, x.operation_name, variables);
+
+    let ctx = Context {
+        user_id: x.user_id,
+        loaders: Arc::new(Loaders::new(&ctx.pg_pool)),
+        ..ctx.clone()
+    };
+
+    let span = operation_span(&req, ctx.user_id);
+
+    let res = serde_json::to_value(&req.execute(schema, &ctx).instrument(span).await)?;
+
+    let (data, error) = split_response(res);
+
+    let data = match data {
+        Some(x) => x,
+        None => {
+            sqlx::query!(
+                r#"
+                    UPDATE deferred_query
+                    SET state = 'failed', error = $2, completed_at = now()
+                    WHERE handle = $1
+                "#,
+                x.handle,
+                error.unwrap_or_else(|| "The query returned no data".into())
+            )
+            .execute(&ctx.pg_pool)
+            .await?;
+
+            return Ok(true);
+        }
+    };
+
+    let (path, rows) = into_rows(data);
+
+    let mut transaction = ctx.pg_pool.begin().await?;
+
+    for (i, chunk) in rows.chunks(ROW_BATCH_SIZE).enumerate() {
+        let start = (i * ROW_BATCH_SIZE) as i32;
+
+        let idxs: Vec<i32> = (start..start + chunk.len() as i32).collect();
+        let values: Vec<String> = chunk.iter().map(|x| x.to_string()).collect();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO deferred_query_row (handle, idx, value)
+                SELECT $1, idx, value::jsonb
+                FROM UNNEST($2::int[], $3::text[]) AS t(idx, value)
+            "#,
+            x.handle,
+            &idxs,
+            &values
+        )
+        .execute(&mut transaction)
+        .await?;
+    }
+
+    sqlx::query!(
+        r#"
+            UPDATE deferred_query
+            SET state = 'complete', path = $2, error = $3, completed_at = now()
+            WHERE handle = $1
+        "#,
+        x.handle,
+        &path,
+        error
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    tracing::debug!("Stored {} rows of deferred query {}", rows.len(), x.handle);
+
+    Ok(true)
+}
+
+/// The data of a GraphQL response, and its error messages joined together.
+fn split_response(mut res: JsonValue) -> (Option<JsonValue>, Option<String>) {
+    let error = res
+        .get("errors")
+        .and_then(JsonValue::as_array)
+        .map(|xs| {
+            xs.iter()
+                .map(|x| {
+                    x.get("message")
+                        .and_then(JsonValue::as_str)
+                        .unwrap_or("Unknown error")
+                })
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+        .filter(|x| !x.is_empty());
+
+    let data = res
+        .get_mut("data")
+        .map(JsonValue::take)
+        .filter(|x| !x.is_null());
+
+    (data, error)
+}
+
+/// The fields leading down to a list, following objects with a single field.
+fn list_path(x: &JsonValue) -> Option<Vec<String>> {
+    match x {
+        JsonValue::Array(_) => Some(vec![]),
+        JsonValue::Object(m) if m.len() == 1 => {
+            let (k, v) = m.iter().next()?;
+
+            let mut path = list_path(v)?;
+
+            path.insert(0, k.to_string());
+
+            Some(path)
+        }
+        _ => None,
+    }
+}
+
+/// Splits the data of a response into rows that can be paged.
+/// If following single field objects leads to a list, its items are the rows.
+/// Otherwise the data is a single row.
+fn into_rows(mut data: JsonValue) -> (Vec<String>, Vec<JsonValue>) {
+    let path = match list_path(&data) {
+        Some(x) => x,
+        None => return (vec![], vec![data]),
+    };
+
+    let pointer: String = path.iter().map(|x| format!("/{}", x)).collect();
+
+    match data.pointer_mut(&pointer).map(JsonValue::take) {
+        Some(JsonValue::Array(xs)) => (path, xs),
+        _ => (vec![], vec![data]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_into_rows() {
+        let (path, rows) = into_rows(json!({ "logs": { "data": [{ "id": 1 }, { "id": 2 }] } }));
+
+        assert_eq!(path, vec!["logs".to_string(), "data".to_string()]);
+        assert_eq!(rows, vec![json!({ "id": 1 }), json!({ "id": 2 })]);
+
+        let data = json!({ "logs": { "data": [], "meta": { "totalCount": 0 } } });
+
+        assert_eq!(into_rows(data.clone()), (vec![], vec![data]));
+    }
+
+    #[test]
+    fn test_split_response() {
+        let (data, error) = split_response(json!({ "data": { "x": 1 } }));
+
+        assert_eq!(data, Some(json!({ "x": 1 })));
+        assert_eq!(error, None);
+
+        let (data, error) = split_response(json!({
+            "data": null,
+            "errors": [{ "message": "a" }, { "message": "b" }]
+        }));
+
+        assert_eq!(data, None);
+        assert_eq!(error, Some("a; b".into()));
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::{user_preferences::current_user, Context};
+use iml_postgres::sqlx;
+use juniper::{FieldError, Value};
+
+pub(crate) struct DigestQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl DigestQuery {
+    /// Whether the current user gets the daily digest email.
+    async fn subscribed(context: &Context) -> juniper::FieldResult<bool> {
+        let user_id = current_user(context)?;
+
+        let x = sqlx::query!(
+            "SELECT user_id FROM digest_subscription WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?;
+
+        Ok(x.is_some())
+    }
+}
+
+pub(crate) struct DigestMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl DigestMutation {
+    #[graphql(arguments(subscribed(
+        description = "`true` to get the daily digest email, `false` to stop getting it"
+    )))]
+    /// Opt the current user in or out of the daily digest email.
+    /// The first digest is sent at the next `DIGEST_HOUR`, to the email address of the user.
+    async fn subscribe(context: &Context, subscribed: bool) -> juniper::FieldResult<bool> {
+        let user_id = current_user(context)?;
+
+        if !subscribed {
+            sqlx::query!(
+                "DELETE FROM digest_subscription WHERE user_id = $1",
+                user_id
+            )
+            .execute(&context.pg_pool)
+            .await?;
+
+            return Ok(false);
+        }
+
+        let email = sqlx::query!("SELECT email FROM auth_user WHERE id = $1", user_id)
+            .fetch_one(&context.pg_pool)
+            .await?
+            .email;
+
+        if email.trim().is_empty() {
+            return Err(FieldError::new(
+                "Set an email address for this user before subscribing to the digest",
+                Value::null(),
+            ));
+        }
+
+        // Starting from now, so the first digest does not cover everything that ever happened
+        sqlx::query!(
+            r#"
+                INSERT INTO digest_subscription (user_id, last_sent)
+                VALUES ($1, now())
+                ON CONFLICT (user_id) DO NOTHING
+            "#,
+            user_id
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        Ok(true)
+    }
+}
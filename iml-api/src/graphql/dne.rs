@@ -0,0 +1,272 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Distributed namespace (DNE): the MDTs of a filesystem, their inode usage,
+//! and the default striping of new directories over them.
+
+use crate::{
+    command::get_command,
+    error::ImlApiError,
+    graphql::{audit, filesystem::get_target_idx, invalid, run_jobs, Context, SendJob},
+};
+use iml_influx::{mdts, Client};
+use iml_manager_env::{get_influxdb_addr, get_influxdb_metrics_db};
+use iml_postgres::sqlx;
+use iml_wire_types::{
+    dne::{DirStripe, FilesystemDne, MdtLoad},
+    Command,
+};
+use juniper::{FieldError, Value};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+use url::Url;
+
+lazy_static! {
+    static ref HASH_TYPE: Regex = Regex::new(r"^[a-z0-9_]{1,32}$").unwrap();
+}
+
+/// Inode usage, keyed by MDT name
+type MdtStats = HashMap<String, (Option<f64>, Option<f64>)>;
+
+/// The MDTs of `fs_name` with their inode usage, and the default striping of its directories.
+///
+/// Inode usage is left `null` when influx can't be reached. The default striping is read from
+/// a client that has the filesystem mounted, `dirStripeError` says why when it could not be.
+pub(crate) async fn get_filesystem_dne(
+    context: &Context,
+    fs_name: String,
+) -> juniper::FieldResult<FilesystemDne> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT t.name, h.fqdn AS "fqdn?"
+            FROM target t
+            LEFT OUTER JOIN chroma_core_managedhost h
+            ON h.id = t.active_host_id AND h.not_deleted = 't'
+            WHERE $1 = ANY(t.filesystems) AND t.name LIKE '%-MDT%'
+        "#,
+        fs_name
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    let stats = match get_mdt_stats(&fs_name).await {
+        Ok(x) => x,
+        Err(e) => {
+            tracing::warn!("Could not read the MDT stats of {}: {}", fs_name, e);
+
+            HashMap::new()
+        }
+    };
+
+    let mut mdts: Vec<_> = xs
+        .into_iter()
+        .map(|x| {
+            let (files_total, files_free) = stats.get(&x.name).copied().unwrap_or_default();
+
+            MdtLoad {
+                index: get_target_idx(&x.name).unwrap_or_default(),
+                name: x.name,
+                active_host: x.fqdn,
+                files_total,
+                files_free,
+            }
+        })
+        .collect();
+
+    mdts.sort_by_key(|x| x.index);
+
+    let (default_dir_stripe, dir_stripe_error) =
+        match get_default_dir_stripe(context, &fs_name).await {
+            Ok(x) => (Some(x), None),
+            Err(e) => (None, Some(e)),
+        };
+
+    Ok(FilesystemDne {
+        fs_name,
+        mdts,
+        default_dir_stripe,
+        dir_stripe_error,
+    })
+}
+
+async fn get_mdt_stats(fs_name: &str) -> Result<MdtStats, ImlApiError> {
+    let url = Url::parse(&format!("http://{}", get_influxdb_addr()))?;
+    let client = Client::new(url, get_influxdb_metrics_db());
+
+    let nodes = client
+        .query(&mdts::query(fs_name), None)
+        .await
+        .map_err(iml_influx::Error::from)?
+        .unwrap_or_default();
+
+    // Columns are `time`, `files_total` and `files_free`.
+    let xs = nodes
+        .into_iter()
+        .filter_map(|x| x.series)
+        .flatten()
+        .filter_map(|x| {
+            let target = x.tags.as_ref()?.get("target")?.as_str()?.to_string();
+            let values = x.values.into_iter().next()?;
+
+            let col = |i: usize| values.get(i).and_then(|v| v.as_f64());
+
+            Some((target, (col(1), col(2))))
+        })
+        .collect();
+
+    Ok(xs)
+}
+
+/// A client host that has `fs_name` mounted, preferring the most recently mounted.
+pub(super) async fn mounted_client(
+    context: &Context,
+    fs_name: &str,
+) -> Result<Option<(i32, String)>, ImlApiError> {
+    let x = sqlx::query!(
+        r#"
+            SELECT h.id, h.fqdn
+            FROM chroma_core_lustreclientmount m
+            INNER JOIN chroma_core_managedhost h ON h.id = m.host_id AND h.not_deleted = 't'
+            WHERE m.filesystem = $1 AND m.state = 'mounted' AND m.not_deleted = 't'
+            ORDER BY m.id DESC
+            LIMIT 1
+        "#,
+        fs_name
+    )
+    .fetch_optional(&context.pg_pool)
+    .await?;
+
+    Ok(x.map(|x| (x.id, x.fqdn)))
+}
+
+async fn get_default_dir_stripe(context: &Context, fs_name: &str) -> Result<DirStripe, String> {
+    let (_, fqdn) = mounted_client(context, fs_name)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No client has {} mounted", fs_name))?;
+
+    let x = context
+        .action_client
+        .invoke_rust_agent_expect_result(fqdn.clone(), "get_default_dir_stripe", fs_name, None)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|x| x.map_err(|e| e.to_string()))
+        .map_err(|e| format!("Could not read the directory striping on {}: {}", fqdn, e))?;
+
+    serde_json::from_value(x).map_err(|e| e.to_string())
+}
+
+/// Checks a default directory striping against the MDTs of a filesystem.
+fn validate(
+    mdt_count: i32,
+    stripe_count: i32,
+    stripe_offset: i32,
+    hash_type: Option<&str>,
+) -> Result<(), String> {
+    if stripe_count < 0 || stripe_count > mdt_count {
+        return Err(format!(
+            "stripe_count must be between 0 and the number of MDTs, {}",
+            mdt_count
+        ));
+    }
+
+    if stripe_offset < -1 || stripe_offset >= mdt_count {
+        return Err(format!(
+            "stripe_offset must be -1 or the index of an MDT, below {}",
+            mdt_count
+        ));
+    }
+
+    match hash_type {
+        Some(x) if !HASH_TYPE.is_match(x) => Err(format!("Invalid hash_type {}", x)),
+        _ => Ok(()),
+    }
+}
+
+/// Sets the default striping of new directories in the root of `fs_name` with a
+/// `SetDefaultDirStripeJob`, run on a client that has the filesystem mounted.
+pub(crate) async fn set_default_dir_stripe(
+    context: &Context,
+    fs_name: String,
+    stripe_count: i32,
+    stripe_offset: i32,
+    hash_type: Option<String>,
+) -> juniper::FieldResult<Command> {
+    let mdt_count = sqlx::query!(
+        r#"
+            SELECT count(*) AS "count!"
+            FROM target
+            WHERE $1 = ANY(filesystems) AND name LIKE '%-MDT%'
+        "#,
+        fs_name
+    )
+    .fetch_one(&context.pg_pool)
+    .await?
+    .count as i32;
+
+    validate(mdt_count, stripe_count, stripe_offset, hash_type.as_deref()).map_err(invalid)?;
+
+    let (host_id, _) = mounted_client(context, &fs_name).await?.ok_or_else(|| {
+        FieldError::new(format!("No client has {} mounted", fs_name), Value::null())
+    })?;
+
+    let job = SendJob {
+        class_name: "SetDefaultDirStripeJob",
+        args: vec![
+            ("host_id".to_string(), serde_json::json!(host_id)),
+            ("filesystem".to_string(), serde_json::json!(fs_name)),
+            ("stripe_count".to_string(), serde_json::json!(stripe_count)),
+            (
+                "stripe_offset".to_string(),
+                serde_json::json!(stripe_offset),
+            ),
+            ("hash_type".to_string(), serde_json::json!(hash_type)),
+        ]
+        .into_iter()
+        .collect::<HashMap<_, _>>(),
+    };
+
+    let command_id = run_jobs(
+        context,
+        format!("Set default directory striping of {}", fs_name),
+        vec![job],
+    )
+    .await?;
+
+    let command = get_command(&context.pg_pool, command_id).await?;
+
+    audit::record(
+        context,
+        "filesystem.setDefaultDirStripe",
+        serde_json::json!({
+            "fsName": fs_name,
+            "stripeCount": stripe_count,
+            "stripeOffset": stripe_offset,
+            "hashType": hash_type,
+        }),
+        Some(command.id),
+    )
+    .await;
+
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate(4, 2, -1, None), Ok(()));
+        assert_eq!(validate(4, 4, 3, Some("fnv_1a_64")), Ok(()));
+        assert_eq!(validate(4, 0, 0, Some("all_char")), Ok(()));
+        assert!(validate(4, 5, -1, None).is_err());
+        assert!(validate(4, -1, -1, None).is_err());
+        assert!(validate(4, 2, 4, None).is_err());
+        assert!(validate(4, 2, -2, None).is_err());
+        assert!(validate(4, 2, -1, Some("fnv; rm")).is_err());
+        assert!(validate(0, 1, -1, None).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Alert escalation policies.
+//!
+//! Policies are only stored here. The job scheduler, which emails alerts to their subscribers,
+//! checks them every minute and emails each alert that was neither resolved nor dismissed in
+//! time once per policy, recording it in `alert_escalation`.
+
+use crate::graphql::{audit, invalid, not_found, validate_name, Context};
+use iml_postgres::sqlx;
+use iml_wire_types::{
+    escalation::{AlertEscalation, EscalationPolicy, EscalationPolicyInput},
+    AlertSeverity,
+};
+use juniper::{FieldError, Value};
+
+const MAX_NAME_LEN: usize = 64;
+const MAX_EMAILS: usize = 16;
+/// A week
+const MAX_AFTER_MINUTES: i32 = 7 * 24 * 60;
+
+pub(crate) struct EscalationQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl EscalationQuery {
+    /// Every escalation policy, by name
+    async fn policies(context: &Context) -> juniper::FieldResult<Vec<EscalationPolicy>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT id, name, severity, after_minutes, emails, enabled
+                FROM alert_escalation_policy
+                ORDER BY name
+            "#
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| EscalationPolicy {
+            id: x.id,
+            name: x.name,
+            severity: AlertSeverity::from(x.severity),
+            after_minutes: x.after_minutes,
+            emails: x.emails,
+            enabled: x.enabled,
+        })
+        .collect();
+
+        Ok(xs)
+    }
+    #[graphql(arguments(alert_id(description = "The id of the alert")))]
+    /// The escalations sent for an alert, oldest first
+    async fn history(
+        context: &Context,
+        alert_id: i32,
+    ) -> juniper::FieldResult<Vec<AlertEscalation>> {
+        let xs = sqlx::query_as!(
+            AlertEscalation,
+            r#"
+                SELECT alert_id, policy_id, policy_name, emails, escalated_at
+                FROM alert_escalation
+                WHERE alert_id = $1
+                ORDER BY escalated_at
+            "#,
+            alert_id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct EscalationMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl EscalationMutation {
+    #[graphql(arguments(
+        id(description = "The policy to change. A new policy is created when omitted"),
+        policy(description = "The policy"),
+    ))]
+    /// Create an escalation policy, or replace an existing one.
+    /// Alerts a policy already escalated are not escalated again by it.
+    async fn save(
+        context: &Context,
+        id: Option<i32>,
+        policy: EscalationPolicyInput,
+    ) -> juniper::FieldResult<EscalationPolicy> {
+        let name = policy.name.trim().to_string();
+        let emails: Vec<String> = policy.emails.iter().map(|x| x.trim().to_string()).collect();
+        let enabled = policy.enabled.unwrap_or(true);
+
+        validate(&name, policy.after_minutes, &emails).map_err(invalid)?;
+
+        let duplicate = sqlx::query!(
+            r#"
+                SELECT id FROM alert_escalation_policy
+                WHERE name = $1 AND ($2::INT IS NULL OR id != $2)
+            "#,
+            &name,
+            id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?;
+
+        if duplicate.is_some() {
+            return Err(FieldError::new(
+                format!("Escalation policy {} already exists", name),
+                Value::null(),
+            ));
+        }
+
+        let severity = i32::from(policy.severity);
+
+        let id = match id {
+            Some(id) => sqlx::query!(
+                r#"
+                    UPDATE alert_escalation_policy
+                    SET name = $2, severity = $3, after_minutes = $4, emails = $5, enabled = $6
+                    WHERE id = $1
+                    RETURNING id
+                "#,
+                id,
+                &name,
+                severity,
+                policy.after_minutes,
+                &emails,
+                enabled
+            )
+            .fetch_optional(&context.pg_pool)
+            .await?
+            .ok_or_else(|| not_found("Escalation policy", id))?
+            .id,
+            None => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO alert_escalation_policy (name, severity, after_minutes, emails, enabled)
+                        VALUES ($1, $2, $3, $4, $5)
+                        RETURNING id
+                    "#,
+                    &name,
+                    severity,
+                    policy.after_minutes,
+                    &emails,
+                    enabled
+                )
+                .fetch_one(&context.pg_pool)
+                .await?
+                .id
+            }
+        };
+
+        audit::record(
+            context,
+            "escalation.save",
+            serde_json::json!({
+                "id": id,
+                "name": name,
+                "severity": policy.severity,
+                "afterMinutes": policy.after_minutes,
+                "emails": emails,
+                "enabled": enabled,
+            }),
+            None,
+        )
+        .await;
+
+        Ok(EscalationPolicy {
+            id,
+            name,
+            severity: policy.severity,
+            after_minutes: policy.after_minutes,
+            emails,
+            enabled,
+        })
+    }
+    #[graphql(arguments(id(description = "The policy to remove")))]
+    /// Remove an escalation policy. The escalations it sent stay in the history of their alerts.
+    async fn remove(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        sqlx::query!(
+            "DELETE FROM alert_escalation_policy WHERE id = $1 RETURNING id",
+            id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| not_found("Escalation policy", id))?;
+
+        audit::record(
+            context,
+            "escalation.remove",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+}
+
+fn validate(name: &str, after_minutes: i32, emails: &[String]) -> Result<(), String> {
+    validate_name("escalation policy", name, MAX_NAME_LEN)?;
+
+    if !(1..=MAX_AFTER_MINUTES).contains(&after_minutes) {
+        return Err(format!(
+            "afterMinutes has to be between 1 and {}",
+            MAX_AFTER_MINUTES
+        ));
+    }
+
+    if emails.is_empty() || emails.len() > MAX_EMAILS {
+        return Err(format!(
+            "An escalation policy needs 1 to {} email addresses",
+            MAX_EMAILS
+        ));
+    }
+
+    if let Some(x) = emails.iter().find(|x| !is_email(x)) {
+        return Err(format!("Invalid email address {:?}", x));
+    }
+
+    Ok(())
+}
+
+/// Just enough to catch typos, the mail server has the last word
+fn is_email(x: &str) -> bool {
+    let mut parts = x.splitn(2, '@');
+
+    match (parts.next(), parts.next()) {
+        (Some(local), Some(domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !domain.contains('@')
+                && !x.chars().any(|c| c.is_whitespace() || c == ',')
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_email() {
+        assert!(is_email("oncall@example.com"));
+        assert!(!is_email("oncall"));
+        assert!(!is_email("@example.com"));
+        assert!(!is_email("oncall@localhost"));
+        assert!(!is_email("a@example.com,b@example.com"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let emails = vec!["oncall@example.com".to_string()];
+
+        assert_eq!(validate("critical", 30, &emails), Ok(()));
+        assert!(validate("", 30, &emails).is_err());
+        assert!(validate("critical", 0, &emails).is_err());
+        assert!(validate("critical", MAX_AFTER_MINUTES + 1, &emails).is_err());
+        assert!(validate("critical", 30, &[]).is_err());
+    }
+}
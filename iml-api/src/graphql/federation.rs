@@ -0,0 +1,131 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Federation of several managers: the peers this manager shows in `federatedOverview`,
+//! and the site overview each manager reports to the others.
+
+use crate::{
+    federation::{add_peer, alert_counts, parse_peer_url, peers, remove_peer, sites},
+    graphql::{audit, dashboard, get_system_health, invalid, Context},
+};
+use chrono::Utc;
+use iml_manager_env::get_federation_site_name;
+use iml_wire_types::federation::{FederatedOverview, FederationPeer, SiteOverview};
+use juniper::{FieldError, Value};
+
+/// The filesystems, health and active alerts of this manager.
+async fn site_overview(context: &Context) -> juniper::FieldResult<SiteOverview> {
+    let filesystems = dashboard::get_dashboard_overview(context).await?;
+    let health = get_system_health(context).await?;
+    let alerts = alert_counts(&context.pg_pool).await?;
+
+    Ok(SiteOverview {
+        filesystems,
+        health,
+        alerts,
+    })
+}
+
+/// The overview of this manager's site and of each peer's.
+pub(crate) async fn federated_overview(
+    context: &Context,
+) -> juniper::FieldResult<FederatedOverview> {
+    let local = site_overview(context).await?;
+
+    let sites = sites(&context.pg_pool, get_federation_site_name(), local).await?;
+
+    Ok(FederatedOverview {
+        sites,
+        checked_at: Utc::now(),
+    })
+}
+
+pub(crate) struct FederationQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl FederationQuery {
+    /// The peer managers included in `federatedOverview`, by name.
+    async fn peers(context: &Context) -> juniper::FieldResult<Vec<FederationPeer>> {
+        let xs = peers(&context.pg_pool).await?;
+
+        Ok(xs)
+    }
+    /// The filesystems, health and active alerts of this manager.
+    /// This is what other managers ask for when this one is their peer.
+    async fn site(context: &Context) -> juniper::FieldResult<SiteOverview> {
+        site_overview(context).await
+    }
+}
+
+pub(crate) struct FederationMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl FederationMutation {
+    #[graphql(arguments(
+        name(description = "The name the peer's site is shown under"),
+        url(description = "The address of the peer manager, e.g. `https://site-b.example.com`"),
+        api_key(
+            description = "An API key of a user of the peer, preferably one that may only read"
+        ),
+        verify_tls(description = "Verify the certificate of the peer, defaults to true"),
+    ))]
+    /// Add a peer manager to the federated overview, or update the peer `name`.
+    /// The peer is only ever sent the read-only `federation.site` query.
+    async fn add_peer(
+        context: &Context,
+        name: String,
+        url: String,
+        api_key: String,
+        verify_tls: Option<bool>,
+    ) -> juniper::FieldResult<FederationPeer> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(FieldError::new("The peer name is empty", Value::null()));
+        }
+
+        if api_key.trim().is_empty() {
+            return Err(FieldError::new("The peer API key is empty", Value::null()));
+        }
+
+        let url = parse_peer_url(&url).map_err(invalid)?;
+
+        let x = add_peer(
+            &context.pg_pool,
+            name,
+            url.as_str(),
+            api_key.trim(),
+            verify_tls.unwrap_or(true),
+        )
+        .await?;
+
+        audit::record(
+            context,
+            "federation.addPeer",
+            serde_json::json!({ "name": x.name, "url": x.url, "verifyTls": x.verify_tls }),
+            None,
+        )
+        .await;
+
+        Ok(x)
+    }
+    #[graphql(arguments(name(description = "The name of the peer")))]
+    /// Remove a peer manager from the federated overview.
+    /// Returns whether there was such a peer.
+    async fn remove_peer(context: &Context, name: String) -> juniper::FieldResult<bool> {
+        let removed = remove_peer(&context.pg_pool, &name).await?;
+
+        if removed {
+            audit::record(
+                context,
+                "federation.removePeer",
+                serde_json::json!({ "name": name }),
+                None,
+            )
+            .await;
+        }
+
+        Ok(removed)
+    }
+}
@@ -3,15 +3,40 @@
 // license that can be found in the LICENSE file.
 
 use crate::{
+    command::get_command,
     error::ImlApiError,
-    graphql::{get_fs_target_resources, Context, TargetResource},
+    fan_out::join_all,
+    graphql::{
+        audit, dne, fs_id_by_name, get_fs_target_resources, invalid, new_target, not_found,
+        param_prefix, run_jobs, stripe, Context, SendJob,
+    },
+    target_remount,
 };
 use futures::TryStreamExt;
-use iml_postgres::sqlx::{self, Postgres, Transaction};
+use iml_postgres::{
+    alert,
+    sqlx::{self, postgres::types::PgInterval, Postgres, Transaction},
+    PgPool,
+};
+use iml_wire_types::{
+    db::TargetKind,
+    graphql::{
+        DetectScan, DetectedFilesystem, DetectedTarget, FilesystemTransition, TargetAction,
+        TargetResource, TargetTransition,
+    },
+    graphql_duration::GraphQLDuration,
+    new_target::{AddOstResult, OstSpecInput},
+    stripe::StripeComponentInput,
+    target_remount::TargetRemountPolicy,
+    AlertRecordType, AlertSeverity, Command,
+};
 use juniper::{FieldError, Value};
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    convert::TryFrom as _,
+};
 
 #[derive(Default)]
 struct FsParts<'a> {
@@ -32,258 +57,1196 @@ pub(crate) struct FilesystemMutation;
 
 #[juniper::graphql_object(Context = Context)]
 impl FilesystemMutation {
+    /// Registers every filesystem found on any host in one step.
+    ///
+    /// Use `detectFilesystems` and `importDetected` to review what is registered first.
     async fn detect(context: &Context) -> juniper::FieldResult<bool> {
-        let mut xs = get_fs_target_resources(&context.pg_pool, None).await?;
+        let xs = detectable_targets(&context.pg_pool).await?;
 
-        // If HA is not present, we will just use the targets directly
-        if xs.is_empty() {
-            xs = sqlx::query!(
-                r#"
-                SELECT
-                    name,
-                    mount_path,
-                    filesystems,
-                    uuid,
-                    state
-                FROM target
-                WHERE CARDINALITY(filesystems) > 0"#
-            )
-            .fetch(&context.pg_pool)
-            .map_ok(|x| TargetResource {
-                cluster_id: 0,
-                fs_names: x.filesystems,
-                uuid: x.uuid,
-                name: x.name,
-                resource_id: "".to_string(),
-                state: x.state,
-                cluster_hosts: vec![],
-            })
-            .try_collect()
+        let mut transaction = context.pg_pool.begin().await?;
+
+        register(&mut transaction, &xs).await?;
+
+        transaction.commit().await?;
+
+        audit::record(context, "filesystem.detect", serde_json::json!({}), None).await;
+
+        Ok(true)
+    }
+    #[graphql(arguments(host_ids(
+        description = "The hosts to scan. Include every server of a filesystem for it to be complete"
+    )))]
+    /// Scans `host_ids` for existing Lustre targets and previews the filesystems they make up.
+    ///
+    /// Nothing is registered until the returned scan is imported with `importDetected`.
+    async fn detect_filesystems(
+        context: &Context,
+        host_ids: Vec<i32>,
+    ) -> juniper::FieldResult<DetectScan> {
+        if host_ids.is_empty() {
+            return Err(FieldError::new("No hosts to scan", Value::null()));
+        }
+
+        let scanned = context
+            .loaders
+            .hosts
+            .load_many(host_ids.iter().copied())
             .await?;
+
+        if let Some(id) = host_ids.iter().find(|x| !scanned.contains_key(x)) {
+            return Err(FieldError::new(
+                format!("Host {} not found", id),
+                Value::null(),
+            ));
         }
 
-        let content_types = sqlx::query!(
-            r#"
-                SELECT id, model FROM django_content_type
-                WHERE app_label = 'chroma_core'
-                AND model IN ('managedfilesystem','managedmdt','managedmgs','managedost', 'filesystemticket', 'masterticket')
-            "#
+        let xs: Vec<_> = detectable_targets(&context.pg_pool)
+            .await?
+            .into_iter()
+            .filter(|x| x.cluster_hosts.iter().any(|id| host_ids.contains(id)))
+            .collect();
+
+        let all_host_ids: Vec<i32> = xs
+            .iter()
+            .flat_map(|x| x.cluster_hosts.iter().copied())
+            .collect();
+
+        // The scanned hosts are cached by the first lookup, only their peers are fetched here.
+        let fqdns: HashMap<i32, String> = context
+            .loaders
+            .hosts
+            .load_many(all_host_ids)
+            .await?
+            .into_iter()
+            .map(|(id, x)| (id, x.fqdn))
+            .collect();
+
+        let uuids: Vec<_> = xs.iter().map(|x| x.uuid.to_string()).collect();
+
+        let registered_targets: HashSet<String> = sqlx::query!(
+            "SELECT uuid FROM chroma_core_managedtarget WHERE uuid = ANY($1) AND not_deleted = 't'",
+            &uuids
         )
         .fetch(&context.pg_pool)
-        .try_fold(HashMap::new(), |mut acc, x| async {
-            acc.insert(x.model, x.id);
+        .try_filter_map(|x| async { Ok(x.uuid) })
+        .try_collect()
+        .await?;
+
+        let registered_filesystems: HashSet<String> =
+            sqlx::query!("SELECT name FROM chroma_core_managedfilesystem WHERE not_deleted = 't'")
+                .fetch(&context.pg_pool)
+                .map_ok(|x| x.name)
+                .try_collect()
+                .await?;
+
+        let filesystems = preview(&xs, &fqdns, &registered_targets, &registered_filesystems);
+
+        let target_uuids: Vec<_> = filesystems
+            .iter()
+            .flat_map(|x| &x.targets)
+            .filter(|x| x.import)
+            .map(|x| x.uuid.to_string())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        let x = sqlx::query!(
+            r#"
+                INSERT INTO detect_scan (host_ids, target_uuids, filesystems)
+                VALUES ($1, $2, $3)
+                RETURNING id, created_at
+            "#,
+            &host_ids,
+            &target_uuids,
+            serde_json::to_value(&filesystems)?
+        )
+        .fetch_one(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "filesystem.detectFilesystems",
+            serde_json::json!({ "hostIds": host_ids, "scanId": x.id }),
+            None,
+        )
+        .await;
+
+        Ok(DetectScan {
+            id: x.id,
+            host_ids,
+            filesystems,
+            created_at: x.created_at,
+            imported_at: None,
+        })
+    }
+    #[graphql(arguments(scan_id(description = "The id of a scan from `detectFilesystems`")))]
+    /// Registers the targets previewed by a scan.
+    ///
+    /// Fails without registering anything if a previewed target went away
+    /// or was unmounted since the scan, in which case the hosts should be scanned again.
+    /// A scan can only be imported once.
+    async fn import_detected(context: &Context, scan_id: i32) -> juniper::FieldResult<DetectScan> {
+        let mut transaction = context.pg_pool.begin().await?;
+
+        let scan = sqlx::query!(
+            r#"
+                SELECT host_ids, target_uuids, filesystems, created_at, imported_at
+                FROM detect_scan
+                WHERE id = $1
+                FOR UPDATE
+            "#,
+            scan_id
+        )
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or_else(|| not_found("Detect scan", scan_id))?;
+
+        if scan.imported_at.is_some() {
+            return Err(FieldError::new(
+                format!("Detect scan {} was already imported", scan_id),
+                Value::null(),
+            ));
+        }
+
+        let filesystems: Vec<DetectedFilesystem> = serde_json::from_value(scan.filesystems)?;
+
+        let xs: Vec<_> = detectable_targets(&context.pg_pool)
+            .await?
+            .into_iter()
+            .filter(|x| scan.target_uuids.contains(&x.uuid) && x.state == "mounted")
+            .collect();
+
+        let changed: Vec<_> = filesystems
+            .iter()
+            .flat_map(|x| &x.targets)
+            .filter(|x| x.import && !xs.iter().any(|y| y.uuid == x.uuid))
+            .map(|x| x.name.as_str())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        if !changed.is_empty() {
+            return Err(FieldError::new(
+                format!(
+                    "{} changed since scan {}, scan the hosts again",
+                    changed.join(", "),
+                    scan_id
+                ),
+                Value::null(),
+            ));
+        }
 
-            Ok(acc)
+        register(&mut transaction, &xs).await?;
+
+        let imported_at = sqlx::query!(
+            r#"
+                UPDATE detect_scan SET imported_at = now()
+                WHERE id = $1
+                RETURNING imported_at AS "imported_at!"
+            "#,
+            scan_id
+        )
+        .fetch_one(&mut transaction)
+        .await?
+        .imported_at;
+
+        transaction.commit().await?;
+
+        audit::record(
+            context,
+            "filesystem.importDetected",
+            serde_json::json!({
+                "scanId": scan_id,
+                "filesystems": filesystems.iter().map(|x| &x.name).collect::<Vec<_>>(),
+            }),
+            None,
+        )
+        .await;
+
+        Ok(DetectScan {
+            id: scan_id,
+            host_ids: scan.host_ids,
+            filesystems,
+            created_at: scan.created_at,
+            imported_at: Some(imported_at),
         })
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to evict the client from"),
+        nid(description = "The NID of the client, e.g. `10.0.2.15@tcp`"),
+    ))]
+    /// Evicts a client from every mounted MDT and OST of `fs_name`.
+    /// The eviction is recorded as an event against the filesystem.
+    /// Returns the names of the targets the client was evicted from.
+    async fn evict_client(
+        context: &Context,
+        fs_name: String,
+        nid: String,
+    ) -> juniper::FieldResult<Vec<String>> {
+        if !is_valid_nid(&nid) {
+            return Err(FieldError::new(
+                format!("Invalid NID {}", nid),
+                Value::null(),
+            ));
+        }
+
+        let fs_id = fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        let xs = sqlx::query!(
+            r#"
+                SELECT t.name, h.fqdn
+                FROM target t
+                INNER JOIN chroma_core_managedhost h
+                ON h.id = t.active_host_id AND h.not_deleted = 't'
+                WHERE $1 = ANY(t.filesystems) AND t.name <> 'MGS'
+                ORDER BY t.name
+            "#,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        if xs.is_empty() {
+            return Err(FieldError::new(
+                format!("Filesystem {} has no mounted targets", fs_name),
+                Value::null(),
+            ));
+        }
+
+        let results = join_all(xs.iter().map(|x| {
+            let param = format!(
+                "{}.{}.evict_client=nid:{}",
+                param_prefix(&x.name),
+                x.name,
+                nid
+            );
+
+            async move {
+                context
+                    .action_client
+                    .invoke_rust_agent_expect_result(
+                        x.fqdn.clone(),
+                        "lctl",
+                        vec!["set_param", &param],
+                        None,
+                    )
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|x| x.map(drop))
+            }
+        }))
+        .await;
+
+        let (evicted, failed): (Vec<_>, Vec<_>) =
+            xs.into_iter().zip(results).partition(|(_, r)| r.is_ok());
+
+        let evicted: Vec<_> = evicted.into_iter().map(|(x, _)| x.name).collect();
+
+        if !evicted.is_empty() {
+            let content_type_id = sqlx::query!(
+                "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'managedfilesystem'"
+            )
+            .fetch_one(&context.pg_pool)
+            .await?
+            .id;
+
+            alert::event(
+                &context.pg_pool,
+                AlertRecordType::ClientConnectEvent,
+                format!(
+                    "Client {} evicted from {} target(s) of {}",
+                    nid,
+                    evicted.len(),
+                    fs_name
+                ),
+                content_type_id,
+                AlertSeverity::WARNING,
+                fs_id,
+            )
+            .await
+            .map_err(ImlApiError::from)?;
+
+            audit::record(
+                context,
+                "filesystem.evictClient",
+                serde_json::json!({ "fsName": fs_name, "nid": nid, "targets": evicted }),
+                None,
+            )
+            .await;
+        }
+
+        if failed.is_empty() {
+            Ok(evicted)
+        } else {
+            let msg = failed
+                .into_iter()
+                .map(|(x, r)| format!("{} on {}: {}", x.name, x.fqdn, r.unwrap_err()))
+                .collect::<Vec<_>>()
+                .join("; ");
+
+            Err(FieldError::new(
+                format!("Could not evict client {} from {}", nid, msg),
+                Value::null(),
+            ))
+        }
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to add the MDT to"),
+        host_id(description = "The server to format the MDT on, which it prefers to run on"),
+        device(description = "The device to format, e.g. `/dev/mapper/mpathd`"),
+    ))]
+    /// Adds an MDT to `fs_name`, with the index after its highest MDT.
+    /// The MDT is formatted, added as a resource to the pacemaker cluster of the host, and started.
+    /// The MGS and MDT0000 of the filesystem have to be started.
+    async fn add_mdt(
+        context: &Context,
+        fs_name: String,
+        host_id: i32,
+        device: String,
+    ) -> juniper::FieldResult<Command> {
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        new_target::add_mdt(context, fs_name, host_id, device).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to add the OSTs to"),
+        specs(description = "The server and device of each new OST"),
+        rebalance(
+            description = "Move files of over-full OSTs onto the new ones once they are started. Defaults to false"
+        ),
+    ))]
+    /// Adds OSTs to `fs_name` in a single command, with consecutive indexes after its highest OST.
+    /// Each OST is formatted, added as a resource to the pacemaker cluster of its host, and started.
+    /// The MGS of the filesystem has to be started.
+    ///
+    /// The new OSTs are listed as pending targets until they show up as targets.
+    async fn add_ost(
+        context: &Context,
+        fs_name: String,
+        specs: Vec<OstSpecInput>,
+        rebalance: Option<bool>,
+    ) -> juniper::FieldResult<AddOstResult> {
+        let fs_id = fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        new_target::add_ost(context, fs_id, fs_name, specs, rebalance.unwrap_or(false)).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to set the default striping of"),
+        stripe_count(description = "How many MDTs each new directory is striped over"),
+        stripe_offset(description = "The index of the MDT to start on, `-1` to let Lustre choose"),
+        hash_type(description = "How names are hashed to stripes, e.g. `fnv_1a_64`"),
+    ))]
+    /// Sets the default striping of new directories in the root of `fs_name`,
+    /// which the directories created below them inherit.
+    /// Runs on a client that has the filesystem mounted.
+    async fn set_default_dir_stripe(
+        context: &Context,
+        fs_name: String,
+        stripe_count: i32,
+        stripe_offset: i32,
+        hash_type: Option<String>,
+    ) -> juniper::FieldResult<Command> {
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        dne::set_default_dir_stripe(context, fs_name, stripe_count, stripe_offset, hash_type).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to set the default file layout of"),
+        components(
+            description = "The components of the layout in file order, one ending at `null` for a plain layout. Empty to remove the default"
+        ),
+    ))]
+    /// Sets the default layout of new files in the root of `fs_name`, which the directories
+    /// created below it inherit. The layout is checked against the OSTs of the filesystem.
+    /// Runs on a client that has the filesystem mounted.
+    async fn set_default_stripe(
+        context: &Context,
+        fs_name: String,
+        components: Vec<StripeComponentInput>,
+    ) -> juniper::FieldResult<Command> {
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        let components = components.into_iter().map(From::from).collect();
+
+        stripe::set_default_stripe(context, fs_name, components).await
+    }
+    #[graphql(arguments(fs_name(description = "The filesystem to start")))]
+    /// Starts the MGS, then the MDTs, then the OSTs of `fs_name`.
+    /// Targets that are already started are skipped.
+    async fn start(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<FilesystemTransition> {
+        transition(context, fs_name, TargetAction::Start).await
+    }
+    #[graphql(arguments(fs_name(description = "The filesystem to stop")))]
+    /// Stops the OSTs, then the MDTs, then the MGS of `fs_name`.
+    /// An MGS that other running filesystems use is left started.
+    async fn stop(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<FilesystemTransition> {
+        transition(context, fs_name, TargetAction::Stop).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem whose targets to remount"),
+        enabled(description = "Whether targets are remounted, defaults to `true`"),
+        max_attempts(description = "How many times a target is remounted before giving up"),
+        cooldown(
+            description = "How long to wait after a target went offline, and between attempts"
+        ),
+    ))]
+    /// Remount the targets of `fs_name` that go offline outside of any command.
+    /// Unset arguments keep their current value, or the default of 3 attempts, 5 minutes apart.
+    async fn set_remount_policy(
+        context: &Context,
+        fs_name: String,
+        enabled: Option<bool>,
+        max_attempts: Option<i32>,
+        cooldown: Option<GraphQLDuration>,
+    ) -> juniper::FieldResult<TargetRemountPolicy> {
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        if max_attempts.map_or(false, |x| x < 1) {
+            return Err(FieldError::new(
+                "max_attempts must be at least 1",
+                Value::null(),
+            ));
+        }
+
+        let pg_cooldown = cooldown
+            .as_ref()
+            .map(|x| PgInterval::try_from(x.0))
+            .transpose()?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO target_remount_policy (filesystem_name, enabled, max_attempts, cooldown)
+                VALUES ($1, COALESCE($2, true), COALESCE($3, 3), COALESCE($4, '5 minutes'))
+                ON CONFLICT (filesystem_name) DO UPDATE
+                SET enabled = COALESCE($2, target_remount_policy.enabled),
+                    max_attempts = COALESCE($3, target_remount_policy.max_attempts),
+                    cooldown = COALESCE($4, target_remount_policy.cooldown)
+            "#,
+            fs_name,
+            enabled,
+            max_attempts,
+            pg_cooldown
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "filesystem.setRemountPolicy",
+            serde_json::json!({
+                "fsName": fs_name,
+                "enabled": enabled,
+                "maxAttempts": max_attempts,
+                "cooldown": cooldown,
+            }),
+            None,
+        )
+        .await;
+
+        target_remount::get_policy(&context.pg_pool, &fs_name)
+            .await?
+            .ok_or_else(|| {
+                FieldError::new(
+                    format!("Remount policy of {} not found", fs_name),
+                    Value::null(),
+                )
+            })
+    }
+    #[graphql(arguments(fs_name(
+        description = "The filesystem whose targets to stop remounting"
+    )))]
+    /// Stop remounting the targets of `fs_name`.
+    /// The record of past attempts is kept.
+    async fn remove_remount_policy(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<bool> {
+        sqlx::query!(
+            "DELETE FROM target_remount_policy WHERE filesystem_name = $1",
+            fs_name
+        )
+        .execute(&context.pg_pool)
         .await?;
 
-        let fs_content_type = get_content_type(&content_types, "managedfilesystem")?;
+        audit::record(
+            context,
+            "filesystem.removeRemountPolicy",
+            serde_json::json!({ "fsName": fs_name }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+}
+
+/// A target of a filesystem being started or stopped
+#[derive(Debug, Clone)]
+struct FsTarget {
+    /// The `chroma_core_managedtarget` id
+    id: i32,
+    name: String,
+    kind: TargetKind,
+    /// The managed state, `mounted` or `unmounted` once the target is set up
+    state: String,
+    /// The other filesystems the target belongs to
+    shared_with: Vec<String>,
+}
+
+type TransitionJob = SendJob<'static, HashMap<String, serde_json::Value>>;
+
+async fn transition(
+    context: &Context,
+    fs_name: String,
+    action: TargetAction,
+) -> juniper::FieldResult<FilesystemTransition> {
+    let fs = sqlx::query!(
+        "SELECT id, state FROM chroma_core_managedfilesystem WHERE name = $1 AND not_deleted = 't'",
+        &fs_name
+    )
+    .fetch_optional(&context.pg_pool)
+    .await?
+    .ok_or_else(|| not_found("Filesystem", fs_name))?;
+
+    let xs: Vec<_> = sqlx::query!(
+        r#"
+            SELECT mt.id, mt.name, mt.state, t.filesystems
+            FROM chroma_core_managedtarget mt
+            INNER JOIN target t ON t.uuid = mt.uuid
+            WHERE mt.not_deleted = 't' AND $1 = ANY(t.filesystems)
+        "#,
+        &fs_name
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .filter_map(|x| {
+        Some(FsTarget {
+            id: x.id,
+            kind: target_kind(&x.name)?,
+            name: x.name,
+            state: x.state,
+            shared_with: x
+                .filesystems
+                .into_iter()
+                .filter(|x| x != &fs_name)
+                .collect(),
+        })
+    })
+    .collect();
+
+    // Filesystems with targets mounted that are not shared with `fs_name`
+    let running: HashSet<String> = sqlx::query!(
+        r#"
+            SELECT DISTINCT UNNEST(t.filesystems) AS "name!"
+            FROM target t
+            INNER JOIN chroma_core_managedtarget mt ON mt.uuid = t.uuid AND mt.not_deleted = 't'
+            WHERE mt.state = 'mounted' AND NOT $1 = ANY(t.filesystems)
+        "#,
+        &fs_name
+    )
+    .fetch(&context.pg_pool)
+    .map_ok(|x| x.name)
+    .try_collect()
+    .await?;
+
+    let plan = plan_transition(action, xs, &running).map_err(invalid)?;
+
+    let jobs = transition_jobs(action, fs.id, &fs.state, &plan);
+
+    if jobs.is_empty() {
+        return Err(FieldError::new(
+            format!("Filesystem {} is already {}", fs_name, past_tense(action)),
+            Value::null(),
+        ));
+    }
+
+    let verb = match action {
+        TargetAction::Start => "Start",
+        _ => "Stop",
+    };
 
-        let mgs_content_type = get_content_type(&content_types, "managedmgs")?;
+    let command_id = run_jobs(context, format!("{} filesystem {}", verb, fs_name), jobs).await?;
 
-        let mdt_content_type = get_content_type(&content_types, "managedmdt")?;
+    let command = get_command(&context.pg_pool, command_id).await?;
 
-        let ost_content_type = get_content_type(&content_types, "managedost")?;
+    audit::record(
+        context,
+        match action {
+            TargetAction::Start => "filesystem.start",
+            _ => "filesystem.stop",
+        },
+        serde_json::json!({ "fsName": fs_name }),
+        Some(command.id),
+    )
+    .await;
+
+    let targets = plan
+        .into_iter()
+        .map(|(x, reason)| TargetTransition {
+            name: x.name,
+            kind: x.kind,
+            action: if reason.is_some() {
+                TargetAction::Skip
+            } else {
+                action
+            },
+            job_state: if reason.is_some() {
+                None
+            } else {
+                Some("pending".to_string())
+            },
+            reason,
+            errored: false,
+            cancelled: false,
+        })
+        .collect();
 
-        let fs_ticket_content_type = get_content_type(&content_types, "filesystemticket")?;
+    Ok(FilesystemTransition { command, targets })
+}
 
-        let master_ticket_content_type = get_content_type(&content_types, "masterticket")?;
+/// The targets started or stopped by `command_id`, with the state of their jobs.
+pub(crate) async fn get_target_transitions(
+    pool: &PgPool,
+    command_id: i32,
+) -> Result<Vec<TargetTransition>, ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT j.id, mt.name, 'start' AS "action!", j.state, j.errored, j.cancelled
+            FROM chroma_core_command_jobs cj
+            INNER JOIN chroma_core_job j ON j.id = cj.job_id
+            INNER JOIN chroma_core_starttargetjob x ON x.job_ptr_id = j.id
+            INNER JOIN chroma_core_managedtarget mt ON mt.id = x.target_id
+            WHERE cj.command_id = $1
+            UNION ALL
+            SELECT j.id, mt.name, 'stop' AS "action!", j.state, j.errored, j.cancelled
+            FROM chroma_core_command_jobs cj
+            INNER JOIN chroma_core_job j ON j.id = cj.job_id
+            INNER JOIN chroma_core_stoptargetjob x ON x.job_ptr_id = j.id
+            INNER JOIN chroma_core_managedtarget mt ON mt.id = x.target_id
+            WHERE cj.command_id = $1
+            ORDER BY 1
+        "#,
+        command_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .filter_map(|x| {
+        Some(TargetTransition {
+            kind: target_kind(&x.name)?,
+            name: x.name,
+            action: if x.action == "start" {
+                TargetAction::Start
+            } else {
+                TargetAction::Stop
+            },
+            reason: None,
+            job_state: Some(x.state),
+            errored: x.errored,
+            cancelled: x.cancelled,
+        })
+    })
+    .collect();
+
+    Ok(xs)
+}
+
+fn past_tense(action: TargetAction) -> &'static str {
+    match action {
+        TargetAction::Start => "started",
+        _ => "stopped",
+    }
+}
 
-        let fss: Filesystems =
-            xs.iter()
-                .filter(|x| x.state == "mounted")
-                .fold(HashMap::new(), |mut acc, x| {
-                    for f in x.fs_names.as_slice() {
-                        let mut parts = acc.entry(f.to_string()).or_insert_with(FsParts::default);
+/// Where targets of `kind` come in the order of `action`, first to last.
+fn tier(action: TargetAction, kind: TargetKind) -> u8 {
+    match action {
+        TargetAction::Stop => 2 - kind_order(kind),
+        _ => kind_order(kind),
+    }
+}
 
-                        match x.name.as_str() {
-                            "MGS" => parts.mgs = Some(x),
-                            name if name.contains("-MDT") => {
-                                parts.mdts.insert(x);
-                            }
-                            name if name.contains("-OST") => {
-                                parts.osts.insert(x);
-                            }
-                            name => {
-                                tracing::debug!("detect miss on name: {}", name);
-                            }
-                        }
+/// Orders the targets of a filesystem for `action`: MGS, MDTs then OSTs to start, the reverse to stop.
+///
+/// Targets already in the wanted state are skipped, with the reason.
+/// So are targets shared with a filesystem in `running` when stopping, which in practice is an MGS.
+fn plan_transition(
+    action: TargetAction,
+    mut xs: Vec<FsTarget>,
+    running: &HashSet<String>,
+) -> Result<Vec<(FsTarget, Option<String>)>, String> {
+    xs.sort_by(|a, b| {
+        tier(action, a.kind)
+            .cmp(&tier(action, b.kind))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    xs.into_iter()
+        .map(|x| {
+            let reason = match (action, x.state.as_str()) {
+                (TargetAction::Start, "unmounted") => None,
+                (TargetAction::Start, "mounted") => Some("Already started".to_string()),
+                (TargetAction::Stop, "unmounted") => Some("Already stopped".to_string()),
+                (TargetAction::Stop, "mounted") => {
+                    let used_by: Vec<_> = x
+                        .shared_with
+                        .iter()
+                        .filter(|x| running.contains(*x))
+                        .map(|x| x.as_str())
+                        .collect();
+
+                    if used_by.is_empty() {
+                        None
+                    } else {
+                        Some(format!("Still used by {}", used_by.join(", ")))
                     }
+                }
+                (_, state) => {
+                    return Err(format!(
+                        "Target {} is {} and can not be {}",
+                        x.name,
+                        state,
+                        past_tense(action)
+                    ))
+                }
+            };
+
+            Ok((x, reason))
+        })
+        .collect()
+}
+
+/// The jobs carrying out `plan`. Each tier of targets waits for the tiers before it,
+/// and the filesystem state follows once every target is done.
+fn transition_jobs(
+    action: TargetAction,
+    fs_id: i32,
+    fs_state: &str,
+    plan: &[(FsTarget, Option<String>)],
+) -> Vec<TransitionJob> {
+    fn job(
+        class_name: &'static str,
+        key: &str,
+        id: i32,
+        old_state: &str,
+        depends_on: &[usize],
+    ) -> TransitionJob {
+        SendJob {
+            class_name,
+            args: vec![
+                (key.to_string(), serde_json::json!(id)),
+                ("old_state".to_string(), serde_json::json!(old_state)),
+                (
+                    "depends_on_job_range".to_string(),
+                    serde_json::json!(depends_on),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    let mut jobs = vec![];
+    // The jobs of earlier tiers
+    let mut prior: Vec<usize> = vec![];
+    let mut current: Vec<usize> = vec![];
+    let mut current_tier = None;
+
+    if action == TargetAction::Stop && fs_state == "available" {
+        jobs.push(job(
+            "MakeAvailableFilesystemUnavailable",
+            "filesystem_id",
+            fs_id,
+            "available",
+            &[],
+        ));
+        prior.push(0);
+    }
+
+    let class_name = match action {
+        TargetAction::Start => "StartTargetJob",
+        _ => "StopTargetJob",
+    };
+
+    for (x, _) in plan.iter().filter(|(_, reason)| reason.is_none()) {
+        let t = tier(action, x.kind);
+
+        if current_tier != Some(t) {
+            prior.append(&mut current);
+            current_tier = Some(t);
+        }
+
+        current.push(jobs.len());
+        jobs.push(job(class_name, "target_id", x.id, &x.state, &prior));
+    }
 
-                    acc
-                });
+    prior.append(&mut current);
 
-        let tickets = sqlx::query!(
+    let fs_job = match (action, fs_state) {
+        (TargetAction::Start, "stopped") => Some(("StartStoppedFilesystemJob", "stopped")),
+        (TargetAction::Start, "unavailable") => {
+            Some(("StartUnavailableFilesystemJob", "unavailable"))
+        }
+        (TargetAction::Stop, "available") | (TargetAction::Stop, "unavailable") => {
+            Some(("StopUnavailableFilesystemJob", "unavailable"))
+        }
+        _ => None,
+    };
+
+    if let Some((class_name, old_state)) = fs_job {
+        jobs.push(job(class_name, "filesystem_id", fs_id, old_state, &prior));
+    }
+
+    jobs
+}
+
+/// The targets on any host that belong to a filesystem.
+///
+/// When HA is configured, only targets with a corosync resource are included,
+/// and `cluster_hosts` are the hosts of the resource. Otherwise it's the hosts the target was seen on.
+async fn detectable_targets(pool: &PgPool) -> Result<Vec<TargetResource>, ImlApiError> {
+    let mut xs = get_fs_target_resources(pool, None).await?;
+
+    // If HA is not present, we will just use the targets directly
+    if xs.is_empty() {
+        xs = sqlx::query!(
             r#"
-            SELECT cluster_id, name, active
-            FROM corosync_resource
-            WHERE resource_agent = 'ocf::ddn:Ticketer';
-            "#
+            SELECT
+                name,
+                mount_path,
+                filesystems,
+                uuid,
+                state,
+                host_ids
+            FROM target
+            WHERE CARDINALITY(filesystems) > 0"#
         )
-        .fetch(&context.pg_pool)
-        .try_fold(HashMap::new(), |mut acc, x| async {
-            let xs = acc.entry(x.cluster_id).or_insert_with(HashSet::new);
+        .fetch(pool)
+        .map_ok(|x| TargetResource {
+            cluster_id: 0,
+            fs_names: x.filesystems,
+            uuid: x.uuid,
+            name: x.name,
+            resource_id: "".to_string(),
+            state: x.state,
+            cluster_hosts: x.host_ids,
+        })
+        .try_collect()
+        .await?;
+    }
+
+    Ok(xs)
+}
+
+fn target_kind(name: &str) -> Option<TargetKind> {
+    match name {
+        "MGS" => Some(TargetKind::Mgt),
+        name if name.contains("-MDT") => Some(TargetKind::Mdt),
+        name if name.contains("-OST") => Some(TargetKind::Ost),
+        _ => None,
+    }
+}
 
-            xs.insert((x.name, x.active));
+/// Groups the mounted targets in `xs` by filesystem.
+fn group_filesystems(xs: &[TargetResource]) -> Filesystems {
+    xs.iter()
+        .filter(|x| x.state == "mounted")
+        .fold(HashMap::new(), |mut acc, x| {
+            for f in x.fs_names.as_slice() {
+                let mut parts = acc.entry(f.to_string()).or_insert_with(FsParts::default);
+
+                match target_kind(&x.name) {
+                    Some(TargetKind::Mgt) => parts.mgs = Some(x),
+                    Some(TargetKind::Mdt) => {
+                        parts.mdts.insert(x);
+                    }
+                    Some(TargetKind::Ost) => {
+                        parts.osts.insert(x);
+                    }
+                    None => {
+                        tracing::debug!("detect miss on name: {}", x.name);
+                    }
+                }
+            }
 
-            Ok(acc)
+            acc
         })
+}
+
+/// Registers the filesystems made up by the mounted targets in `xs`.
+///
+/// The MGS of a filesystem is always registered. Its MDTs and OSTs only if the filesystem is complete.
+async fn register(
+    t: &mut Transaction<'_, Postgres>,
+    xs: &[TargetResource],
+) -> juniper::FieldResult<()> {
+    let content_types = sqlx::query!(
+        r#"
+            SELECT id, model FROM django_content_type
+            WHERE app_label = 'chroma_core'
+            AND model IN ('managedfilesystem','managedmdt','managedmgs','managedost', 'filesystemticket', 'masterticket')
+        "#
+    )
+    .fetch(&mut *t)
+    .try_fold(HashMap::new(), |mut acc, x| async {
+        acc.insert(x.model, x.id);
+
+        Ok(acc)
+    })
+    .await?;
+
+    let fs_content_type = get_content_type(&content_types, "managedfilesystem")?;
+
+    let mgs_content_type = get_content_type(&content_types, "managedmgs")?;
+
+    let mdt_content_type = get_content_type(&content_types, "managedmdt")?;
+
+    let ost_content_type = get_content_type(&content_types, "managedost")?;
+
+    let fs_ticket_content_type = get_content_type(&content_types, "filesystemticket")?;
+
+    let master_ticket_content_type = get_content_type(&content_types, "masterticket")?;
+
+    let fss = group_filesystems(xs);
+
+    let tickets = sqlx::query!(
+        r#"
+        SELECT cluster_id, name, active
+        FROM corosync_resource
+        WHERE resource_agent = 'ocf::ddn:Ticketer';
+        "#
+    )
+    .fetch(&mut *t)
+    .try_fold(HashMap::new(), |mut acc, x| async {
+        let xs = acc.entry(x.cluster_id).or_insert_with(HashSet::new);
+
+        xs.insert((x.name, x.active));
+
+        Ok(acc)
+    })
+    .await?;
+
+    for (fs, parts) in fss {
+        let mgs = match parts.mgs {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let mgs_id = upsert_managed_target(&mut *t, mgs, mgs_content_type).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO chroma_core_managedmgs
+                VALUES ($1, 0, 0)
+                ON CONFLICT (managedtarget_ptr_id) DO NOTHING
+            "#,
+            mgs_id
+        )
+        .execute(&mut *t)
         .await?;
 
-        let mut transaction = context.pg_pool.begin().await?;
+        if !parts.is_fs() {
+            continue;
+        }
 
-        for (fs, parts) in fss {
-            let mgs = match parts.mgs {
-                Some(x) => x,
-                None => continue,
-            };
+        let fs_id = upsert_managed_filesystem(&mut *t, &fs, fs_content_type, mgs_id).await?;
 
-            let mgs_id = upsert_managed_target(&mut transaction, mgs, mgs_content_type).await?;
+        for mdt in parts.mdts {
+            let idx = get_target_idx(&mdt.name).ok_or_else(|| {
+                FieldError::new(
+                    format!("Detect Failed, could not find index for MDT {}", &mdt.name),
+                    Value::null(),
+                )
+            })?;
+
+            let id = upsert_managed_target(&mut *t, mdt, mdt_content_type).await?;
 
             sqlx::query!(
                 r#"
-                    INSERT INTO chroma_core_managedmgs
-                    VALUES ($1, 0, 0)
+                    INSERT INTO chroma_core_managedmdt VALUES ($1, $2, $3)
                     ON CONFLICT (managedtarget_ptr_id) DO NOTHING
                 "#,
-                mgs_id
+                id,
+                idx,
+                fs_id
             )
-            .execute(&mut transaction)
+            .execute(&mut *t)
             .await?;
+        }
 
-            if !parts.is_fs() {
-                continue;
-            }
-
-            let fs_id =
-                upsert_managed_filesystem(&mut transaction, &fs, fs_content_type, mgs_id).await?;
-
-            for mdt in parts.mdts {
-                let idx = get_target_idx(&mdt.name).ok_or_else(|| {
-                    FieldError::new(
-                        format!("Detect Failed, could not find index for MDT {}", &mdt.name),
-                        Value::null(),
-                    )
-                })?;
-
-                let id = upsert_managed_target(&mut transaction, mdt, mdt_content_type).await?;
-
-                sqlx::query!(
-                    r#"
-                        INSERT INTO chroma_core_managedmdt VALUES ($1, $2, $3)
-                        ON CONFLICT (managedtarget_ptr_id) DO NOTHING
-                    "#,
-                    id,
-                    idx,
-                    fs_id
+        for ost in parts.osts {
+            let idx = get_target_idx(&ost.name).ok_or_else(|| {
+                FieldError::new(
+                    format!("Detect Failed, could not find index for OST {}", &ost.name),
+                    Value::null(),
                 )
-                .execute(&mut transaction)
-                .await?;
-            }
+            })?;
 
-            for ost in parts.osts {
-                let idx = get_target_idx(&ost.name).ok_or_else(|| {
-                    FieldError::new(
-                        format!("Detect Failed, could not find index for OST {}", &ost.name),
-                        Value::null(),
-                    )
-                })?;
-
-                let id = upsert_managed_target(&mut transaction, ost, ost_content_type).await?;
-
-                sqlx::query!(
-                    r#"
-                        INSERT INTO chroma_core_managedost VALUES ($1, $2, $3)
-                        ON CONFLICT (managedtarget_ptr_id) DO NOTHING
-                    "#,
-                    id,
-                    idx,
-                    fs_id
-                )
-                .execute(&mut transaction)
-                .await?;
-            }
+            let id = upsert_managed_target(&mut *t, ost, ost_content_type).await?;
 
             sqlx::query!(
                 r#"
-                    UPDATE chroma_core_managedfilesystem f
-                    SET mdt_next_index = (SELECT MAX(index) + 1 FROM chroma_core_managedmdt WHERE filesystem_id = $1),
-                    ost_next_index = (SELECT MAX(index) + 1 FROM chroma_core_managedost WHERE filesystem_id = $1)
-                    where id = $1"#,
+                    INSERT INTO chroma_core_managedost VALUES ($1, $2, $3)
+                    ON CONFLICT (managedtarget_ptr_id) DO NOTHING
+                "#,
+                id,
+                idx,
                 fs_id
             )
-            .execute(&mut transaction)
+            .execute(&mut *t)
             .await?;
+        }
 
-            let tickets = tickets.get(&mgs.cluster_id);
+        sqlx::query!(
+            r#"
+                UPDATE chroma_core_managedfilesystem f
+                SET mdt_next_index = (SELECT MAX(index) + 1 FROM chroma_core_managedmdt WHERE filesystem_id = $1),
+                ost_next_index = (SELECT MAX(index) + 1 FROM chroma_core_managedost WHERE filesystem_id = $1)
+                where id = $1"#,
+            fs_id
+        )
+        .execute(&mut *t)
+        .await?;
 
-            let tickets = match tickets {
-                Some(x) => x,
-                None => continue,
-            };
+        let tickets = tickets.get(&mgs.cluster_id);
 
-            let fs_ticket = tickets.iter().find(|(x, _)| &fs == x);
+        let tickets = match tickets {
+            Some(x) => x,
+            None => continue,
+        };
 
-            if let Some((_, active)) = fs_ticket {
-                let id = upsert_ticket(
-                    &mut transaction,
-                    &fs,
-                    *active,
-                    mgs.cluster_id,
-                    fs_ticket_content_type,
-                )
-                .await?;
+        let fs_ticket = tickets.iter().find(|(x, _)| &fs == x);
 
-                sqlx::query!(
-                    r#"
-                        INSERT INTO chroma_core_filesystemticket
-                            (ticket_ptr_id, filesystem_id)
-                            VALUES
-                            ($1, $2)
-                            ON CONFLICT (ticket_ptr_id)
-                            DO UPDATE SET
-                            filesystem_id = EXCLUDED.filesystem_id
-                    "#,
-                    id,
-                    fs_id
-                )
-                .execute(&mut transaction)
-                .await?;
-            }
+        if let Some((_, active)) = fs_ticket {
+            let id = upsert_ticket(
+                &mut *t,
+                &fs,
+                *active,
+                mgs.cluster_id,
+                fs_ticket_content_type,
+            )
+            .await?;
 
-            let lustre_ticket = tickets.iter().find(|(x, _)| &"lustre" == x);
+            sqlx::query!(
+                r#"
+                    INSERT INTO chroma_core_filesystemticket
+                        (ticket_ptr_id, filesystem_id)
+                        VALUES
+                        ($1, $2)
+                        ON CONFLICT (ticket_ptr_id)
+                        DO UPDATE SET
+                        filesystem_id = EXCLUDED.filesystem_id
+                "#,
+                id,
+                fs_id
+            )
+            .execute(&mut *t)
+            .await?;
+        }
 
-            if let Some((_, active)) = lustre_ticket {
-                let id = upsert_ticket(
-                    &mut transaction,
-                    "lustre",
-                    *active,
-                    mgs.cluster_id,
-                    master_ticket_content_type,
-                )
-                .await?;
+        let lustre_ticket = tickets.iter().find(|(x, _)| &"lustre" == x);
 
-                sqlx::query!(
-                    r#"
-                    INSERT INTO chroma_core_masterticket
-                    (ticket_ptr_id, mgs_id) 
-                    VALUES
-                    ($1, $2)
-                    ON CONFLICT (ticket_ptr_id)
-                    DO UPDATE SET
-                    mgs_id = EXCLUDED.mgs_id
-                "#,
-                    id,
-                    mgs_id
-                )
-                .execute(&mut transaction)
-                .await?;
-            }
+        if let Some((_, active)) = lustre_ticket {
+            let id = upsert_ticket(
+                &mut *t,
+                "lustre",
+                *active,
+                mgs.cluster_id,
+                master_ticket_content_type,
+            )
+            .await?;
+
+            sqlx::query!(
+                r#"
+                INSERT INTO chroma_core_masterticket
+                (ticket_ptr_id, mgs_id) 
+                VALUES
+                ($1, $2)
+                ON CONFLICT (ticket_ptr_id)
+                DO UPDATE SET
+                mgs_id = EXCLUDED.mgs_id
+            "#,
+                id,
+                mgs_id
+            )
+            .execute(&mut *t)
+            .await?;
         }
+    }
 
-        transaction.commit().await?;
+    Ok(())
+}
 
-        Ok(true)
+/// Describes what registering the targets in `xs` would do, one filesystem at a time.
+fn preview(
+    xs: &[TargetResource],
+    fqdns: &HashMap<i32, String>,
+    registered_targets: &HashSet<String>,
+    registered_filesystems: &HashSet<String>,
+) -> Vec<DetectedFilesystem> {
+    let fss = group_filesystems(xs);
+
+    let names: BTreeSet<_> = xs.iter().flat_map(|x| &x.fs_names).collect();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let complete = fss.get(name).map(|x| x.is_fs()).unwrap_or(false);
+
+            let mut targets: Vec<_> = xs
+                .iter()
+                .filter(|x| x.fs_names.contains(name))
+                .filter_map(|x| {
+                    let kind = target_kind(&x.name)?;
+
+                    let mut hosts: Vec<_> = x
+                        .cluster_hosts
+                        .iter()
+                        .filter_map(|id| fqdns.get(id).cloned())
+                        .collect();
+
+                    hosts.sort();
+
+                    Some(DetectedTarget {
+                        name: x.name.to_string(),
+                        uuid: x.uuid.to_string(),
+                        kind,
+                        state: x.state.to_string(),
+                        hosts,
+                        registered: registered_targets.contains(&x.uuid),
+                        import: x.state == "mounted" && (kind == TargetKind::Mgt || complete),
+                    })
+                })
+                .collect();
+
+            targets.sort_by(|a, b| {
+                kind_order(a.kind)
+                    .cmp(&kind_order(b.kind))
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+
+            DetectedFilesystem {
+                name: name.to_string(),
+                complete,
+                registered: registered_filesystems.contains(name),
+                targets,
+            }
+        })
+        .collect()
+}
+
+fn kind_order(x: TargetKind) -> u8 {
+    match x {
+        TargetKind::Mgt => 0,
+        TargetKind::Mdt => 1,
+        TargetKind::Ost => 2,
     }
 }
 
@@ -522,7 +1485,7 @@ async fn upsert_ticket(
     }
 }
 
-fn get_target_idx(name: &str) -> Option<i32> {
+pub(super) fn get_target_idx(name: &str) -> Option<i32> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"[\w\-]+-\w+([0-9a-f]{4})$").unwrap();
     }
@@ -536,6 +1499,15 @@ fn get_target_idx(name: &str) -> Option<i32> {
     Some(idx)
 }
 
+/// Checks `x` looks like a Lustre NID, i.e. `<address>@<net>`
+fn is_valid_nid(x: &str) -> bool {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"^[\w.:\-]+@[a-z]+[0-9]*$").unwrap();
+    }
+
+    RE.is_match(x)
+}
+
 fn get_content_type(x: &HashMap<String, i32>, name: &str) -> juniper::FieldResult<i32> {
     let x = x.get(name).ok_or_else(|| {
         FieldError::new(
@@ -546,3 +1518,212 @@ fn get_content_type(x: &HashMap<String, i32>, name: &str) -> juniper::FieldResul
 
     Ok(*x)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(name: &str, state: &str, cluster_hosts: Vec<i32>) -> TargetResource {
+        TargetResource {
+            cluster_id: 1,
+            fs_names: vec!["fs".to_string()],
+            uuid: format!("{}-uuid", name),
+            name: name.to_string(),
+            resource_id: "".to_string(),
+            state: state.to_string(),
+            cluster_hosts,
+        }
+    }
+
+    fn fqdns() -> HashMap<i32, String> {
+        vec![(1, "mds1.local".to_string()), (2, "oss1.local".to_string())]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_preview() {
+        let xs = vec![
+            target("fs-OST0000", "mounted", vec![2]),
+            target("MGS", "mounted", vec![1]),
+            target("fs-MDT0000", "mounted", vec![1]),
+            target("fs-OST0001", "unmounted", vec![2]),
+        ];
+
+        let registered: HashSet<_> = vec!["MGS-uuid".to_string()].into_iter().collect();
+
+        let fss = preview(&xs, &fqdns(), &registered, &HashSet::new());
+
+        assert_eq!(fss.len(), 1);
+        assert!(fss[0].complete);
+        assert!(!fss[0].registered);
+
+        let targets: Vec<_> = fss[0]
+            .targets
+            .iter()
+            .map(|x| (x.name.as_str(), x.kind, x.registered, x.import))
+            .collect();
+
+        assert_eq!(
+            targets,
+            vec![
+                ("MGS", TargetKind::Mgt, true, true),
+                ("fs-MDT0000", TargetKind::Mdt, false, true),
+                ("fs-OST0000", TargetKind::Ost, false, true),
+                ("fs-OST0001", TargetKind::Ost, false, false),
+            ]
+        );
+
+        assert_eq!(fss[0].targets[2].hosts, vec!["oss1.local".to_string()]);
+    }
+
+    #[test]
+    fn test_preview_incomplete() {
+        let xs = vec![
+            target("MGS", "mounted", vec![1]),
+            target("fs-MDT0000", "mounted", vec![1]),
+        ];
+
+        let fss = preview(&xs, &fqdns(), &HashSet::new(), &HashSet::new());
+
+        assert!(!fss[0].complete);
+
+        let imported: Vec<_> = fss[0]
+            .targets
+            .iter()
+            .filter(|x| x.import)
+            .map(|x| x.name.as_str())
+            .collect();
+
+        assert_eq!(imported, vec!["MGS"]);
+    }
+
+    #[test]
+    fn test_is_valid_nid() {
+        assert!(is_valid_nid("10.0.2.15@tcp"));
+        assert!(is_valid_nid("192.168.0.2@o2ib1"));
+        assert!(is_valid_nid("client-1@tcp0"));
+        assert!(!is_valid_nid("10.0.2.15"));
+        assert!(!is_valid_nid("10.0.2.15@tcp evict"));
+        assert!(!is_valid_nid("10.0.2.15@tcp,10.0.2.16@tcp"));
+    }
+
+    fn fs_target(id: i32, name: &str, state: &str, shared_with: &[&str]) -> FsTarget {
+        FsTarget {
+            id,
+            name: name.to_string(),
+            kind: target_kind(name).unwrap(),
+            state: state.to_string(),
+            shared_with: shared_with.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    fn fs_targets(state: &str) -> Vec<FsTarget> {
+        vec![
+            fs_target(4, "fs-OST0001", state, &[]),
+            fs_target(2, "fs-MDT0000", state, &[]),
+            fs_target(3, "fs-OST0000", state, &[]),
+            fs_target(1, "MGS", state, &["fs2"]),
+        ]
+    }
+
+    fn summary(plan: &[(FsTarget, Option<String>)]) -> Vec<(&str, Option<&str>)> {
+        plan.iter()
+            .map(|(x, reason)| (x.name.as_str(), reason.as_deref()))
+            .collect()
+    }
+
+    fn job_summary(jobs: &[TransitionJob]) -> Vec<(&str, serde_json::Value)> {
+        jobs.iter()
+            .map(|x| (x.class_name, x.args["depends_on_job_range"].clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_start() {
+        let mut xs = fs_targets("unmounted");
+        xs[2].state = "mounted".to_string();
+
+        let plan = plan_transition(TargetAction::Start, xs, &HashSet::new()).unwrap();
+
+        assert_eq!(
+            summary(&plan),
+            vec![
+                ("MGS", None),
+                ("fs-MDT0000", None),
+                ("fs-OST0000", Some("Already started")),
+                ("fs-OST0001", None),
+            ]
+        );
+
+        assert_eq!(
+            job_summary(&transition_jobs(TargetAction::Start, 1, "stopped", &plan)),
+            vec![
+                ("StartTargetJob", serde_json::json!([])),
+                ("StartTargetJob", serde_json::json!([0])),
+                ("StartTargetJob", serde_json::json!([0, 1])),
+                ("StartStoppedFilesystemJob", serde_json::json!([0, 1, 2])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_stop() {
+        let running = vec!["fs2".to_string()].into_iter().collect();
+
+        let plan = plan_transition(TargetAction::Stop, fs_targets("mounted"), &running).unwrap();
+
+        assert_eq!(
+            summary(&plan),
+            vec![
+                ("fs-OST0000", None),
+                ("fs-OST0001", None),
+                ("fs-MDT0000", None),
+                ("MGS", Some("Still used by fs2")),
+            ]
+        );
+
+        let jobs = transition_jobs(TargetAction::Stop, 1, "available", &plan);
+
+        assert_eq!(
+            job_summary(&jobs),
+            vec![
+                ("MakeAvailableFilesystemUnavailable", serde_json::json!([])),
+                ("StopTargetJob", serde_json::json!([0])),
+                ("StopTargetJob", serde_json::json!([0])),
+                ("StopTargetJob", serde_json::json!([0, 1, 2])),
+                (
+                    "StopUnavailableFilesystemJob",
+                    serde_json::json!([0, 1, 2, 3])
+                ),
+            ]
+        );
+
+        assert_eq!(jobs[1].args["target_id"], serde_json::json!(3));
+        assert_eq!(jobs[1].args["old_state"], serde_json::json!("mounted"));
+        assert_eq!(jobs[4].args["old_state"], serde_json::json!("unavailable"));
+
+        let plan =
+            plan_transition(TargetAction::Stop, fs_targets("mounted"), &HashSet::new()).unwrap();
+
+        assert_eq!(plan[3].1, None);
+    }
+
+    #[test]
+    fn test_plan_nothing_to_do() {
+        let plan =
+            plan_transition(TargetAction::Start, fs_targets("mounted"), &HashSet::new()).unwrap();
+
+        assert!(transition_jobs(TargetAction::Start, 1, "available", &plan).is_empty());
+
+        assert_eq!(
+            plan_transition(
+                TargetAction::Start,
+                fs_targets("unformatted"),
+                &HashSet::new()
+            )
+            .unwrap_err(),
+            "Target MGS is unformatted and can not be started"
+        );
+    }
+}
@@ -0,0 +1,27 @@
+INSERT INTO chroma_core_serverprofile
+(name, ui_name, ui_description, managed, worker, user_selectable, initial_state, ntp, corosync, corosync2, pacemaker, "default")
+VALUES
+('test_profile', 'test_profile', 'test_profile', 't', 'f', 't', 'managed', 'f', 'f', 't', 't', 'f')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO chroma_core_managedhost
+(
+    id,
+    state_modified_at,
+    state,
+    immutable_state,
+    not_deleted,
+    address,
+    fqdn,
+    nodename,
+    boot_time,
+    needs_update,
+    corosync_ring0,
+    install_method,
+    content_type_id,
+    server_profile_id
+)
+VALUES
+(9001, now(), 'managed', 'f', 't', 'oss1.test', 'oss1.test', 'oss1', NULL, 'f', '', '', NULL, 'test_profile'),
+(9002, now(), 'managed', 'f', 't', 'oss2.test', 'oss2.test', 'oss2', NULL, 'f', '', '', NULL, 'test_profile'),
+(9003, now(), 'unconfigured', 'f', 't', 'oss3.test', 'oss3.test', 'oss3', NULL, 'f', '', '', NULL, 'test_profile');
@@ -0,0 +1,519 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    command::get_command,
+    error::ImlApiError,
+    fan_out::join_all,
+    graphql::{approval, audit, invalid, not_found, run_jobs_kwargs, Context, SendJob},
+};
+use futures::TryFutureExt;
+use iml_postgres::sqlx;
+use iml_wire_types::{
+    graphql::{ClusterConfigDiff, HaNodeInput, HostRingStatus, JobPriority},
+    Command, CorosyncRing,
+};
+use juniper::{FieldError, Value};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{collections::HashMap, net::IpAddr};
+
+/// The corosync default
+const DEFAULT_MCAST_PORT: i32 = 5405;
+
+/// Unchanged lines shown around each change of a config diff
+const DIFF_CONTEXT: usize = 3;
+
+lazy_static! {
+    static ref CLUSTER_NAME: Regex = Regex::new(r"^[\w-]{1,64}$").unwrap();
+    pub(super) static ref FENCE_AGENT: Regex = Regex::new(r"^fence_\w+$").unwrap();
+    pub(super) static ref FENCE_PARAM: Regex = Regex::new(r"^\w+=\S.*$").unwrap();
+}
+
+pub(crate) struct HaClusterQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl HaClusterQuery {
+    #[graphql(arguments(host_id(description = "A host of the cluster")))]
+    /// The corosync ring status of `host_id` and of every other node of its cluster.
+    /// Hosts that can not be reached are listed with an `error` instead of failing the query.
+    async fn ring_status(
+        context: &Context,
+        host_id: i32,
+    ) -> juniper::FieldResult<Vec<HostRingStatus>> {
+        let hosts = sqlx::query!(
+            r#"
+                SELECT h.id, h.fqdn
+                FROM chroma_core_managedhost h
+                WHERE h.not_deleted = 't'
+                AND (
+                    h.id = $1
+                    OR h.id IN (
+                        SELECT nmh.host_id
+                        FROM corosync_node_managed_host nmh
+                        WHERE nmh.cluster_id IN (
+                            SELECT cluster_id FROM corosync_node_managed_host WHERE host_id = $1
+                        )
+                    )
+                )
+                ORDER BY h.fqdn
+            "#,
+            host_id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        if hosts.is_empty() {
+            return Err(FieldError::new(
+                format!("Host {} not found", host_id),
+                Value::null(),
+            ));
+        }
+
+        let xs = join_all(hosts.into_iter().map(|x| async move {
+            let r = context
+                .action_client
+                .invoke_rust_agent_expect_result(x.fqdn.clone(), "corosync_ring_status", (), None)
+                .await;
+
+            let (rings, error) = match r {
+                Ok(Ok(rings)) => match serde_json::from_value::<Vec<CorosyncRing>>(rings) {
+                    Ok(rings) => (rings, None),
+                    Err(e) => (vec![], Some(e.to_string())),
+                },
+                Ok(Err(e)) => (vec![], Some(e)),
+                Err(e) => (vec![], Some(e.to_string())),
+            };
+
+            HostRingStatus {
+                host_id: x.id,
+                fqdn: x.fqdn,
+                rings,
+                error,
+            }
+        }))
+        .await;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(cluster_id(description = "The corosync cluster")))]
+    /// How the CIB and corosync.conf each node of `cluster_id` last reported differ from
+    /// the configuration IML last applied. Changes made while IML jobs run are IML's own.
+    async fn cluster_config_diff(
+        context: &Context,
+        cluster_id: i32,
+    ) -> juniper::FieldResult<Vec<ClusterConfigDiff>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT
+                    c.host_id,
+                    h.fqdn,
+                    c.cib,
+                    c.corosync_conf,
+                    c.baseline_cib,
+                    c.baseline_corosync_conf,
+                    c.hash != c.baseline_hash AS "drifted!",
+                    c.changed_at,
+                    c.baselined_at
+                FROM cluster_config c
+                INNER JOIN chroma_core_managedhost h ON h.id = c.host_id
+                WHERE c.cluster_id = $1 AND h.not_deleted = 't'
+                ORDER BY h.fqdn
+            "#,
+            cluster_id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| ClusterConfigDiff {
+            host_id: x.host_id,
+            fqdn: x.fqdn,
+            drifted: x.drifted,
+            changed_at: x.changed_at,
+            baselined_at: x.baselined_at,
+            cib_diff: line_diff(&x.baseline_cib, &x.cib),
+            corosync_conf_diff: line_diff(&x.baseline_corosync_conf, &x.corosync_conf),
+        })
+        .collect();
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct HaClusterMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl HaClusterMutation {
+    #[graphql(arguments(
+        cluster_name(description = "The corosync cluster name"),
+        nodes(
+            description = "The two hosts of the cluster. The first one is where fencing is configured from"
+        ),
+        mcast_port(description = "The port corosync talks on. Defaults to 5405"),
+    ))]
+    /// Build a corosync and pacemaker cluster from two managed hosts.
+    /// Writes the corosync.conf of each host, starts the cluster, creates a fence device
+    /// for each host and finally checks every ring is free of faults.
+    async fn create(
+        context: &Context,
+        cluster_name: String,
+        nodes: Vec<HaNodeInput>,
+        mcast_port: Option<i32>,
+    ) -> juniper::FieldResult<Command> {
+        let mcast_port = mcast_port.unwrap_or(DEFAULT_MCAST_PORT);
+
+        validate(&cluster_name, &nodes, mcast_port).map_err(invalid)?;
+
+        let host_ids: Vec<_> = nodes.iter().map(|x| x.host_id).collect();
+
+        let hosts = sqlx::query!(
+            r#"
+                SELECT h.id, h.fqdn, h.state, nmh.cluster_id AS "cluster_id?"
+                FROM chroma_core_managedhost h
+                LEFT OUTER JOIN corosync_node_managed_host nmh ON nmh.host_id = h.id
+                WHERE h.not_deleted = 't' AND h.id = ANY($1)
+            "#,
+            &host_ids
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        for id in &host_ids {
+            let x = hosts
+                .iter()
+                .find(|x| x.id == *id)
+                .ok_or_else(|| not_found("Host", id))?;
+
+            if x.state != "managed" {
+                return Err(FieldError::new(
+                    format!("{} is {}, not managed", x.fqdn, x.state),
+                    Value::null(),
+                ));
+            }
+
+            if let Some(cluster_id) = x.cluster_id {
+                return Err(FieldError::new(
+                    format!(
+                        "{} is already a member of corosync cluster {}",
+                        x.fqdn, cluster_id
+                    ),
+                    Value::null(),
+                ));
+            }
+        }
+
+        let node_args: HashMap<String, serde_json::Value> = nodes
+            .iter()
+            .map(|x| {
+                (
+                    x.host_id.to_string(),
+                    serde_json::json!({
+                        "ring0_addr": x.ring0_addr,
+                        "ring1_addr": x.ring1_addr,
+                        "fence_agent": x.fence_agent,
+                        "fence_params": x.fence_params,
+                    }),
+                )
+            })
+            .collect();
+
+        let jobs = vec![SendJob {
+            class_name: "CreateHaClusterJob",
+            args: vec![
+                (
+                    "primary_host_id".to_string(),
+                    serde_json::json!(host_ids[0]),
+                ),
+                (
+                    "secondary_host_id".to_string(),
+                    serde_json::json!(host_ids[1]),
+                ),
+                ("cluster_name".to_string(), serde_json::json!(cluster_name)),
+                ("mcast_port".to_string(), serde_json::json!(mcast_port)),
+                ("nodes".to_string(), serde_json::json!(node_args)),
+            ]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+        }];
+
+        let kwargs = run_jobs_kwargs(
+            format!("Create HA cluster {}", cluster_name),
+            JobPriority::High,
+        );
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
+
+        let command_id: i32 = iml_job_scheduler_rpc::call(
+            &context.rabbit_pool.get().await?,
+            "run_jobs",
+            vec![jobs],
+            Some(kwargs),
+        )
+        .map_err(ImlApiError::ImlJobSchedulerRpcError)
+        .await?;
+
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        // Fence params may hold credentials, so they are left out.
+        audit::record(
+            context,
+            "haCluster.create",
+            serde_json::json!({ "clusterName": cluster_name, "hostIds": host_ids }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+    #[graphql(arguments(cluster_id(description = "The corosync cluster")))]
+    /// Put back the configuration IML last applied on the nodes of `cluster_id` that drifted from it.
+    /// The CIB is replaced once for the whole cluster, keeping the bans and moves in effect,
+    /// and each drifted corosync.conf is rewritten and reloaded.
+    /// The drift alerts are lowered once the nodes report the restored configuration.
+    async fn reapply_config(context: &Context, cluster_id: i32) -> juniper::FieldResult<bool> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT
+                    h.fqdn,
+                    c.baseline_cib,
+                    c.baseline_corosync_conf,
+                    c.cib != c.baseline_cib AS "cib_drifted!",
+                    c.corosync_conf != c.baseline_corosync_conf AS "corosync_conf_drifted!"
+                FROM cluster_config c
+                INNER JOIN chroma_core_managedhost h ON h.id = c.host_id
+                WHERE c.cluster_id = $1 AND h.not_deleted = 't'
+                ORDER BY h.fqdn
+            "#,
+            cluster_id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        if xs.is_empty() {
+            return Err(FieldError::new(
+                format!("No configuration was reported for cluster {}", cluster_id),
+                Value::null(),
+            ));
+        }
+
+        let mut fqdns = vec![];
+
+        if let Some(x) = xs.iter().find(|x| x.cib_drifted) {
+            invoke(context, &x.fqdn, "restore_cib", &x.baseline_cib).await?;
+        }
+
+        for x in xs.iter().filter(|x| x.corosync_conf_drifted) {
+            invoke(
+                context,
+                &x.fqdn,
+                "restore_corosync_conf",
+                &x.baseline_corosync_conf,
+            )
+            .await?;
+
+            fqdns.push(&x.fqdn);
+        }
+
+        audit::record(
+            context,
+            "haCluster.reapplyConfig",
+            serde_json::json!({
+                "clusterId": cluster_id,
+                "cib": xs.iter().any(|x| x.cib_drifted),
+                "corosyncConf": fqdns,
+            }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+}
+
+async fn invoke(
+    context: &Context,
+    fqdn: &str,
+    plugin: &str,
+    arg: &str,
+) -> juniper::FieldResult<()> {
+    context
+        .action_client
+        .invoke_rust_agent_expect_result(fqdn, plugin, arg, None)
+        .await?
+        .map_err(|e| {
+            FieldError::new(
+                format!("{} failed on {}: {}", plugin, fqdn, e),
+                Value::null(),
+            )
+        })?;
+
+    Ok(())
+}
+
+/// A line diff of `old` and `new` in the style of `diff -u`,
+/// with `@@` between hunks. Empty when nothing changed.
+fn line_diff(old: &str, new: &str) -> String {
+    let a: Vec<_> = old.lines().collect();
+    let b: Vec<_> = new.lines().collect();
+
+    // Longest common subsequence of the lines following each pair of positions
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut xs = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            xs.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            xs.push(('-', a[i]));
+            i += 1;
+        } else {
+            xs.push(('+', b[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<_> = xs
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != ' ')
+        .map(|(idx, _)| idx)
+        .collect();
+
+    let mut out = vec![];
+    let mut last = None;
+
+    for (idx, (op, l)) in xs.iter().enumerate() {
+        let near = changed
+            .iter()
+            .any(|c| idx + DIFF_CONTEXT >= *c && idx <= c + DIFF_CONTEXT);
+
+        if !near {
+            continue;
+        }
+
+        if last.map(|x| x + 1 != idx).unwrap_or(true) {
+            out.push("@@".to_string());
+        }
+
+        out.push(format!("{}{}", op, l));
+        last = Some(idx);
+    }
+
+    out.join("\n")
+}
+
+/// Checks the arguments of a new cluster before anything is sent to the hosts.
+/// Fence params end up on the `pcs` command line, so they may not look like options.
+fn validate(cluster_name: &str, nodes: &[HaNodeInput], mcast_port: i32) -> Result<(), String> {
+    if !CLUSTER_NAME.is_match(cluster_name) {
+        return Err(format!("Invalid cluster name {}", cluster_name));
+    }
+
+    if !(1025..=65535).contains(&mcast_port) {
+        return Err(format!("Invalid mcast port {}", mcast_port));
+    }
+
+    match nodes {
+        [a, b] if a.host_id != b.host_id => {}
+        _ => return Err("A cluster needs exactly two different hosts".into()),
+    };
+
+    let redundant = nodes.iter().filter(|x| x.ring1_addr.is_some()).count();
+
+    if redundant == 1 {
+        return Err("Either all or none of the hosts need a ring1 address".into());
+    }
+
+    for x in nodes {
+        for addr in std::iter::once(&x.ring0_addr).chain(x.ring1_addr.as_ref()) {
+            if addr.parse::<IpAddr>().is_err() {
+                return Err(format!("Invalid ring address {}", addr));
+            }
+        }
+
+        if !FENCE_AGENT.is_match(&x.fence_agent) {
+            return Err(format!("Invalid fence agent {}", x.fence_agent));
+        }
+
+        if let Some(p) = x.fence_params.iter().find(|p| !FENCE_PARAM.is_match(p)) {
+            return Err(format!("Invalid fence param {}, expected key=value", p));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(host_id: i32, ring0_addr: &str, ring1_addr: Option<&str>) -> HaNodeInput {
+        HaNodeInput {
+            host_id,
+            ring0_addr: ring0_addr.into(),
+            ring1_addr: ring1_addr.map(Into::into),
+            fence_agent: "fence_ipmilan".into(),
+            fence_params: vec!["ip=10.0.0.1".into(), "username=admin".into()],
+        }
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert_eq!(line_diff("a\nb\nc", "a\nb\nc"), "");
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13";
+
+        assert_eq!(
+            line_diff(old, new),
+            "@@\n 1\n 2\n-3\n+three\n 4\n 5\n 6\n@@\n 10\n 11\n 12\n+13"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let nodes = vec![
+            node(1, "10.128.0.21", Some("10.73.10.21")),
+            node(2, "10.128.0.22", Some("10.73.10.22")),
+        ];
+
+        assert_eq!(validate("lustre-ha", &nodes, 5405), Ok(()));
+        assert!(validate("lustre ha", &nodes, 5405).is_err());
+        assert!(validate("lustre-ha", &nodes, 80).is_err());
+        assert!(validate("lustre-ha", &nodes[..1], 5405).is_err());
+    }
+
+    #[test]
+    fn test_validate_nodes() {
+        let same = vec![node(1, "10.128.0.21", None), node(1, "10.128.0.22", None)];
+        assert!(validate("lustre-ha", &same, 5405).is_err());
+
+        let mixed = vec![
+            node(1, "10.128.0.21", Some("10.73.10.21")),
+            node(2, "10.128.0.22", None),
+        ];
+        assert!(validate("lustre-ha", &mixed, 5405).is_err());
+
+        let bad_addr = vec![node(1, "oss1", None), node(2, "10.128.0.22", None)];
+        assert!(validate("lustre-ha", &bad_addr, 5405).is_err());
+
+        let mut bad_param = vec![node(1, "10.128.0.21", None), node(2, "10.128.0.22", None)];
+        bad_param[1].fence_params.push("--force".into());
+        assert!(validate("lustre-ha", &bad_param, 5405).is_err());
+    }
+}
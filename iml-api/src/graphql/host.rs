@@ -0,0 +1,411 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    command::get_command,
+    error::ImlApiError,
+    graphql::{approval, audit, not_found, run_jobs_kwargs, Context, SendJob},
+    heartbeat::{needs_attention, seconds_since, DEFAULT_MISSED},
+};
+use chrono::Utc;
+use futures::TryFutureExt;
+use iml_manager_env::get_heartbeat_alert_missed;
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::{
+    graphql::{HostHeartbeat, HostTag, JobPriority, TaggedHost},
+    Command,
+};
+use juniper::{FieldError, Value};
+use std::collections::HashMap;
+
+const MAX_TAG_LEN: usize = 64;
+
+pub(crate) struct HostQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl HostQuery {
+    #[graphql(arguments(tags(
+        description = "Only return hosts matching all of these tags. Each is either `key` or `key=value`"
+    ),))]
+    /// List managed hosts along with their tags.
+    async fn list(
+        context: &Context,
+        tags: Option<Vec<String>>,
+    ) -> juniper::FieldResult<Vec<TaggedHost>> {
+        let ids = match tags {
+            Some(tags) => Some(host_ids_by_tags(&context.pg_pool, &tags).await?),
+            None => None,
+        };
+
+        let xs = sqlx::query!(
+            r#"
+                SELECT id, fqdn, nodename, state
+                FROM chroma_core_managedhost
+                WHERE not_deleted = 't'
+                AND ($1::INT[] IS NULL OR id = ANY($1))
+                ORDER BY fqdn
+            "#,
+            ids.as_deref(),
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let mut tags = get_tags(
+            &context.pg_pool,
+            &xs.iter().map(|x| x.id).collect::<Vec<_>>(),
+        )
+        .await?;
+
+        let xs = xs
+            .into_iter()
+            .map(|x| TaggedHost {
+                id: x.id,
+                fqdn: x.fqdn,
+                nodename: x.nodename,
+                state: x.state,
+                tags: tags.remove(&x.id).unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(xs)
+    }
+
+    /// When each host's agent was last heard from, and whether it has missed
+    /// too many heartbeats. Hosts that need attention are listed first.
+    async fn heartbeats(context: &Context) -> juniper::FieldResult<Vec<HostHeartbeat>> {
+        let missed = get_heartbeat_alert_missed().unwrap_or(DEFAULT_MISSED);
+
+        let now = Utc::now();
+
+        let mut xs: Vec<_> = sqlx::query!(
+            r#"
+                SELECT h.id, h.fqdn, h.boot_time, hb.last_seen AS "last_seen?"
+                FROM chroma_core_managedhost h
+                LEFT OUTER JOIN host_heartbeat hb ON hb.host_id = h.id
+                WHERE h.not_deleted = 't'
+                ORDER BY h.fqdn
+            "#
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| HostHeartbeat {
+            host_id: x.id,
+            fqdn: x.fqdn,
+            last_seen: x.last_seen,
+            boot_time: x.boot_time,
+            seconds_since_contact: x.last_seen.map(|t| seconds_since(t, now)),
+            needs_attention: x
+                .last_seen
+                .map(|t| needs_attention(t, now, missed))
+                .unwrap_or(false),
+        })
+        .collect();
+
+        xs.sort_by_key(|x| !x.needs_attention);
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct HostMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl HostMutation {
+    #[graphql(arguments(
+        host_ids(description = "Hosts to tag"),
+        tags(description = "Also tag every host matching all of these tag filters"),
+        key(description = "The tag key, e.g. `rack`"),
+        value(description = "The tag value, e.g. `12`"),
+    ))]
+    /// Set a tag on a group of hosts, replacing any existing value for `key`.
+    /// Returns the ids of the hosts that were tagged.
+    async fn set_tag(
+        context: &Context,
+        host_ids: Option<Vec<i32>>,
+        tags: Option<Vec<String>>,
+        key: String,
+        value: String,
+    ) -> juniper::FieldResult<Vec<i32>> {
+        validate_tag_part(&key)?;
+        validate_tag_part(&value)?;
+
+        let ids = resolve_hosts(&context.pg_pool, host_ids, tags).await?;
+
+        let xs: Vec<i32> = sqlx::query!(
+            r#"
+                INSERT INTO host_tag (host_id, key, value)
+                SELECT h.id, $2, $3
+                FROM chroma_core_managedhost h
+                WHERE h.id = ANY($1) AND h.not_deleted = 't'
+                ON CONFLICT (host_id, key) DO UPDATE SET value = EXCLUDED.value
+                RETURNING host_id
+            "#,
+            &ids,
+            key,
+            value,
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| x.host_id)
+        .collect();
+
+        audit::record(
+            context,
+            "host.setTag",
+            serde_json::json!({ "hostIds": xs, "key": key, "value": value }),
+            None,
+        )
+        .await;
+
+        Ok(xs)
+    }
+
+    #[graphql(arguments(
+        host_ids(description = "Hosts to remove the tag from"),
+        tags(description = "Also untag every host matching all of these tag filters"),
+        key(description = "The tag key to remove"),
+    ))]
+    /// Remove a tag from a group of hosts.
+    /// Returns the ids of the hosts the tag was removed from.
+    async fn remove_tag(
+        context: &Context,
+        host_ids: Option<Vec<i32>>,
+        tags: Option<Vec<String>>,
+        key: String,
+    ) -> juniper::FieldResult<Vec<i32>> {
+        let ids = resolve_hosts(&context.pg_pool, host_ids, tags).await?;
+
+        let xs: Vec<i32> = sqlx::query!(
+            "DELETE FROM host_tag WHERE host_id = ANY($1) AND key = $2 RETURNING host_id",
+            &ids,
+            key,
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| x.host_id)
+        .collect();
+
+        audit::record(
+            context,
+            "host.removeTag",
+            serde_json::json!({ "hostIds": xs, "key": key }),
+            None,
+        )
+        .await;
+
+        Ok(xs)
+    }
+
+    #[graphql(arguments(id(description = "The host to resync")))]
+    /// Bring the agent state of a host back in line with the manager, after it was changed by hand.
+    /// Pushes the server profile and repo file again, re-registers the devices of Lustre servers,
+    /// then checks the host against its profile. Each discrepancy found is logged by the command.
+    async fn resync(context: &Context, id: i32) -> juniper::FieldResult<Command> {
+        let host = sqlx::query!(
+            r#"
+                SELECT fqdn, state
+                FROM chroma_core_managedhost
+                WHERE not_deleted = 't' AND id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| not_found("Host", id))?;
+
+        if !["managed", "monitored", "working"].contains(&host.state.as_str()) {
+            return Err(FieldError::new(
+                format!(
+                    "{} is {}, it can only be resynced once set up",
+                    host.fqdn, host.state
+                ),
+                Value::null(),
+            ));
+        }
+
+        let jobs = vec![SendJob {
+            class_name: "ResyncHostJob",
+            args: vec![("host_id".to_string(), serde_json::json!(id))]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+        }];
+
+        let kwargs = run_jobs_kwargs(format!("Resync {}", host.fqdn), JobPriority::High);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
+
+        let command_id: i32 = iml_job_scheduler_rpc::call(
+            &context.rabbit_pool.get().await?,
+            "run_jobs",
+            vec![jobs],
+            Some(kwargs),
+        )
+        .map_err(ImlApiError::ImlJobSchedulerRpcError)
+        .await?;
+
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        audit::record(
+            context,
+            "host.resync",
+            serde_json::json!({ "id": id, "fqdn": host.fqdn }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+}
+
+/// Combines explicit host ids with the hosts matching `tags`.
+async fn resolve_hosts(
+    pool: &PgPool,
+    host_ids: Option<Vec<i32>>,
+    tags: Option<Vec<String>>,
+) -> Result<Vec<i32>, FieldError> {
+    if host_ids.is_none() && tags.is_none() {
+        return Err(FieldError::new(
+            "Either hostIds or tags must be given",
+            Value::null(),
+        ));
+    }
+
+    let mut ids = host_ids.unwrap_or_default();
+
+    if let Some(tags) = tags {
+        ids.extend(host_ids_by_tags(pool, &tags).await?);
+    }
+
+    ids.sort_unstable();
+    ids.dedup();
+
+    Ok(ids)
+}
+
+/// Ids of the hosts that match every filter in `tags`.
+///
+/// A filter of `key=value` matches hosts with that exact tag,
+/// a bare `key` matches hosts with that tag set to any value.
+pub(crate) async fn host_ids_by_tags(
+    pool: &PgPool,
+    tags: &[String],
+) -> Result<Vec<i32>, FieldError> {
+    let (keys, values): (Vec<_>, Vec<_>) = tags
+        .iter()
+        .map(|x| parse_tag_filter(x))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .map(|(k, v)| (k, v.unwrap_or_default()))
+        .unzip();
+
+    let xs = sqlx::query!(
+        r#"
+            SELECT h.id
+            FROM chroma_core_managedhost h
+            WHERE h.not_deleted = 't'
+            AND NOT EXISTS (
+                SELECT 1 FROM unnest($1::TEXT[], $2::TEXT[]) AS f(key, value)
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM host_tag t
+                    WHERE t.host_id = h.id
+                    AND t.key = f.key
+                    AND (f.value = '' OR t.value = f.value)
+                )
+            )
+        "#,
+        &keys,
+        &values,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| x.id)
+    .collect();
+
+    Ok(xs)
+}
+
+async fn get_tags(
+    pool: &PgPool,
+    host_ids: &[i32],
+) -> Result<HashMap<i32, Vec<HostTag>>, FieldError> {
+    let xs = sqlx::query!(
+        "SELECT host_id, key, value FROM host_tag WHERE host_id = ANY($1) ORDER BY key",
+        host_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut map: HashMap<i32, Vec<HostTag>> = HashMap::new();
+
+    for x in xs {
+        map.entry(x.host_id).or_default().push(HostTag {
+            key: x.key,
+            value: x.value,
+        });
+    }
+
+    Ok(map)
+}
+
+/// Splits a `key` or `key=value` tag filter.
+fn parse_tag_filter(x: &str) -> Result<(String, Option<String>), FieldError> {
+    let mut parts = x.splitn(2, '=');
+
+    let key = parts.next().unwrap_or_default().trim();
+    validate_tag_part(key)?;
+
+    let value = match parts.next().map(str::trim) {
+        Some(v) => {
+            validate_tag_part(v)?;
+
+            Some(v.to_string())
+        }
+        None => None,
+    };
+
+    Ok((key.to_string(), value))
+}
+
+fn validate_tag_part(x: &str) -> Result<(), FieldError> {
+    let valid = !x.is_empty()
+        && x.len() <= MAX_TAG_LEN
+        && x.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' || c == '/');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(FieldError::new(
+            format!(
+                "Invalid tag {:?}. Tags must be 1 to {} characters of letters, digits, '_', '-', '.' or '/'",
+                x, MAX_TAG_LEN
+            ),
+            Value::null(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag_filter() {
+        assert_eq!(
+            parse_tag_filter("rack=12").unwrap(),
+            ("rack".to_string(), Some("12".to_string()))
+        );
+        assert_eq!(
+            parse_tag_filter("tier").unwrap(),
+            ("tier".to_string(), None)
+        );
+        assert!(parse_tag_filter("rack=").is_err());
+        assert!(parse_tag_filter("=12").is_err());
+        assert!(parse_tag_filter("rack=1 2").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! LNet ping meshes among managed hosts, for finding pairs of hosts that can't reach each other.
+
+use crate::{
+    error::ImlApiError,
+    fan_out::join_all,
+    graphql::{audit, Context},
+    lnet_ping::{mesh_pairs, start as start_mesh},
+};
+use iml_postgres::sqlx;
+use iml_wire_types::lnet_ping::LnetPingMesh;
+use juniper::{FieldError, Value};
+
+/// How many times each NID is pinged when no count is given.
+const DEFAULT_COUNT: i32 = 3;
+
+const MAX_COUNT: i32 = 20;
+
+/// How many meshes are listed when no limit is given.
+const DEFAULT_LIMIT: i32 = 10;
+
+pub(crate) struct LnetPingQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl LnetPingQuery {
+    #[graphql(arguments(limit(description = "How many meshes to list, defaults to 10")))]
+    /// The latest ping meshes, newest first, along with the pairs recorded so far.
+    async fn meshes(
+        context: &Context,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<LnetPingMesh>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT id, host_ids, count, started_at, finished_at
+                FROM lnet_ping_mesh
+                ORDER BY started_at DESC, id DESC
+                LIMIT $1
+            "#,
+            i64::from(limit.unwrap_or(DEFAULT_LIMIT))
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let xs = join_all(xs.into_iter().map(|x| async move {
+            Ok::<_, ImlApiError>(LnetPingMesh {
+                pairs: mesh_pairs(&context.pg_pool, x.id).await?,
+                id: x.id,
+                host_ids: x.host_ids,
+                count: x.count,
+                started_at: x.started_at,
+                finished_at: x.finished_at,
+            })
+        }))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(id(description = "The id of the mesh")))]
+    /// A ping mesh and the pairs recorded so far
+    async fn mesh(context: &Context, id: i32) -> juniper::FieldResult<Option<LnetPingMesh>> {
+        let x = sqlx::query!(
+            r#"
+                SELECT id, host_ids, count, started_at, finished_at
+                FROM lnet_ping_mesh
+                WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?;
+
+        let x = match x {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        Ok(Some(LnetPingMesh {
+            pairs: mesh_pairs(&context.pg_pool, x.id).await?,
+            id: x.id,
+            host_ids: x.host_ids,
+            count: x.count,
+            started_at: x.started_at,
+            finished_at: x.finished_at,
+        }))
+    }
+}
+
+pub(crate) struct LnetPingMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl LnetPingMutation {
+    #[graphql(arguments(
+        host_ids(description = "The hosts that ping each other, at least two"),
+        count(description = "How many times each NID is pinged, defaults to 3"),
+    ))]
+    /// Start a ping mesh: each host pings every NID of the other hosts over LNet.
+    /// Returns as soon as the mesh is recorded; pairs are added as each host finishes,
+    /// and `finishedAt` is set once they all have.
+    async fn start(
+        context: &Context,
+        host_ids: Vec<i32>,
+        count: Option<i32>,
+    ) -> juniper::FieldResult<LnetPingMesh> {
+        let count = count.unwrap_or(DEFAULT_COUNT);
+
+        if !(1..=MAX_COUNT).contains(&count) {
+            return Err(FieldError::new(
+                format!("count must be between 1 and {}", MAX_COUNT),
+                Value::null(),
+            ));
+        }
+
+        let mesh = start_mesh(&context.pg_pool, &context.action_client, &host_ids, count)
+            .await?
+            .ok_or_else(|| {
+                FieldError::new(
+                    "A ping mesh needs at least two managed hosts",
+                    Value::null(),
+                )
+            })?;
+
+        audit::record(
+            context,
+            "lnetPing.start",
+            serde_json::json!({ "hostIds": mesh.host_ids, "count": count, "meshId": mesh.id }),
+            None,
+        )
+        .await;
+
+        Ok(mesh)
+    }
+}
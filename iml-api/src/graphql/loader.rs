@@ -0,0 +1,350 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::error::ImlApiError;
+use chrono::{DateTime, Utc};
+use futures::future::{BoxFuture, FutureExt as _};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::db::{FsType, TargetRecord};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    hash::Hash,
+    sync::Mutex,
+};
+use tokio::task;
+
+/// Batches lookups by key into a single call to `fetch`,
+/// so resolving many related records costs one query instead of one per record.
+///
+/// Keys asked for concurrently, e.g. by the resolvers of the items of a list,
+/// are fetched in the same batch. Values are cached for the life of the loader.
+pub(crate) struct Loader<K, V, F> {
+    fetch: F,
+    state: Mutex<State<K, V>>,
+    /// Held while a batch is fetched, so other callers wait for it
+    /// instead of fetching the same keys again.
+    fetching: futures::lock::Mutex<()>,
+}
+
+struct State<K, V> {
+    /// `None` for keys `fetch` returned no value for
+    cache: HashMap<K, Option<V>>,
+    /// Keys asked for that are not fetched yet
+    queue: HashSet<K>,
+}
+
+impl<K, V, F, Fut, E> Loader<K, V, F>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+    F: Fn(Vec<K>) -> Fut,
+    Fut: Future<Output = Result<HashMap<K, V>, E>>,
+{
+    pub(crate) fn new(fetch: F) -> Self {
+        Self {
+            fetch,
+            state: Mutex::new(State {
+                cache: HashMap::new(),
+                queue: HashSet::new(),
+            }),
+            fetching: futures::lock::Mutex::new(()),
+        }
+    }
+
+    /// Resolve `key`, batched with any other keys asked for at the same time.
+    pub(crate) async fn load(&self, key: K) -> Result<Option<V>, E> {
+        let mut xs = self.load_many(Some(key.clone())).await?;
+
+        Ok(xs.remove(&key))
+    }
+
+    /// Resolve `keys`, fetching any that are not cached in one batch.
+    /// Keys `fetch` returns no value for are left out of the result.
+    pub(crate) async fn load_many(
+        &self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Result<HashMap<K, V>, E> {
+        let keys: HashSet<K> = keys.into_iter().collect();
+
+        let queued = {
+            let mut state = self.state.lock().unwrap();
+
+            let missing: Vec<K> = keys
+                .iter()
+                .filter(|k| !state.cache.contains_key(k))
+                .cloned()
+                .collect();
+
+            let queued = !missing.is_empty();
+
+            state.queue.extend(missing);
+
+            queued
+        };
+
+        if queued {
+            // Sibling resolvers run concurrently within the same task,
+            // so giving way once lets them queue their keys into this batch.
+            task::yield_now().await;
+
+            let _fetching = self.fetching.lock().await;
+
+            let batch: Vec<K> = {
+                let mut state = self.state.lock().unwrap();
+
+                let mut queue = std::mem::take(&mut state.queue);
+
+                // When the batch that took these keys failed, they are fetched again here.
+                queue.extend(keys.iter().cloned());
+
+                queue
+                    .into_iter()
+                    .filter(|k| !state.cache.contains_key(k))
+                    .collect()
+            };
+
+            if !batch.is_empty() {
+                let mut xs = (self.fetch)(batch.clone()).await?;
+
+                let mut state = self.state.lock().unwrap();
+
+                for k in batch {
+                    let v = xs.remove(&k);
+
+                    state.cache.insert(k, v);
+                }
+            }
+        }
+
+        let state = self.state.lock().unwrap();
+
+        let xs = keys
+            .into_iter()
+            .filter_map(|k| state.cache.get(&k).cloned().flatten().map(|v| (k, v)))
+            .collect();
+
+        Ok(xs)
+    }
+}
+
+type BatchFn<V> =
+    Box<dyn Fn(Vec<i32>) -> BoxFuture<'static, Result<HashMap<i32, V>, ImlApiError>> + Send + Sync>;
+
+/// A loader of records by id
+pub(crate) type BatchLoader<V> = Loader<i32, V, BatchFn<V>>;
+
+fn batch_loader<V, F, Fut>(pool: &PgPool, f: F) -> BatchLoader<V>
+where
+    F: Fn(PgPool, Vec<i32>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<HashMap<i32, V>, ImlApiError>> + Send + 'static,
+    V: Clone,
+{
+    let pool = pool.clone();
+
+    let fetch: BatchFn<V> = Box::new(move |ids: Vec<i32>| f(pool.clone(), ids).boxed());
+
+    Loader::new(fetch)
+}
+
+/// A managed host, as loaded for nested resolvers
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HostRow {
+    pub(crate) id: i32,
+    pub(crate) fqdn: String,
+    pub(crate) nodename: String,
+    pub(crate) state: String,
+}
+
+/// A job, as loaded for nested resolvers
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct JobRow {
+    pub(crate) id: i32,
+    pub(crate) class_name: String,
+    pub(crate) state: String,
+    pub(crate) errored: bool,
+    pub(crate) cancelled: bool,
+    pub(crate) created_at: DateTime<Utc>,
+    pub(crate) modified_at: DateTime<Utc>,
+}
+
+/// The loaders of a single GraphQL request.
+///
+/// Nested resolvers go through these instead of querying on their own, so a query costs
+/// a round trip per level rather than one per record. Each request gets new loaders,
+/// so nothing is cached past it.
+pub(crate) struct Loaders {
+    pub(crate) hosts: BatchLoader<HostRow>,
+    pub(crate) targets: BatchLoader<TargetRecord>,
+    pub(crate) jobs: BatchLoader<JobRow>,
+}
+
+impl Loaders {
+    pub(crate) fn new(pool: &PgPool) -> Self {
+        Self {
+            hosts: batch_loader(pool, hosts_by_ids),
+            targets: batch_loader(pool, targets_by_ids),
+            jobs: batch_loader(pool, jobs_by_ids),
+        }
+    }
+}
+
+async fn hosts_by_ids(pool: PgPool, ids: Vec<i32>) -> Result<HashMap<i32, HostRow>, ImlApiError> {
+    let xs = sqlx::query_as!(
+        HostRow,
+        r#"
+            SELECT id, fqdn, nodename, state
+            FROM chroma_core_managedhost
+            WHERE id = ANY($1) AND not_deleted = 't'
+        "#,
+        &ids
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.id, x))
+    .collect();
+
+    Ok(xs)
+}
+
+async fn targets_by_ids(
+    pool: PgPool,
+    ids: Vec<i32>,
+) -> Result<HashMap<i32, TargetRecord>, ImlApiError> {
+    let xs = sqlx::query_as!(
+        TargetRecord,
+        r#"
+            SELECT
+                id,
+                state,
+                name,
+                active_host_id,
+                host_ids,
+                filesystems,
+                uuid,
+                mount_path,
+                dev_path,
+                fs_type as "fs_type: FsType"
+            FROM target
+            WHERE id = ANY($1)
+        "#,
+        &ids
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.id, x))
+    .collect();
+
+    Ok(xs)
+}
+
+async fn jobs_by_ids(pool: PgPool, ids: Vec<i32>) -> Result<HashMap<i32, JobRow>, ImlApiError> {
+    let xs = sqlx::query_as!(
+        JobRow,
+        r#"
+            SELECT
+                j.id,
+                ct.model AS class_name,
+                j.state,
+                j.errored,
+                j.cancelled,
+                j.created_at,
+                j.modified_at
+            FROM chroma_core_job j
+            INNER JOIN django_content_type ct ON ct.id = j.content_type_id
+            WHERE j.id = ANY($1)
+        "#,
+        &ids
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.id, x))
+    .collect();
+
+    Ok(xs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_load_many_batches_and_caches() {
+        let calls = AtomicUsize::new(0);
+
+        let loader = Loader::new(|ids: Vec<i32>| {
+            calls.fetch_add(1, Ordering::SeqCst);
+
+            async move {
+                Ok::<_, ()>(
+                    ids.into_iter()
+                        .filter(|x| *x != 3)
+                        .map(|x| (x, format!("host{}", x)))
+                        .collect::<HashMap<_, _>>(),
+                )
+            }
+        });
+
+        let xs = loader.load_many(vec![1, 2, 2, 3]).await.unwrap();
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[&1], "host1");
+        assert_eq!(xs[&2], "host2");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let xs = loader.load_many(vec![1, 2, 3]).await.unwrap();
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_loads_share_a_batch() {
+        let batches = Mutex::new(vec![]);
+
+        let loader = Loader::new(|mut ids: Vec<i32>| {
+            ids.sort();
+            batches.lock().unwrap().push(ids.clone());
+
+            async move {
+                Ok::<_, ()>(
+                    ids.into_iter()
+                        .map(|x| (x, x * 10))
+                        .collect::<HashMap<_, _>>(),
+                )
+            }
+        });
+
+        let (a, b, c) = futures::join!(loader.load(1), loader.load(2), loader.load(1));
+
+        assert_eq!(a, Ok(Some(10)));
+        assert_eq!(b, Ok(Some(20)));
+        assert_eq!(c, Ok(Some(10)));
+        assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2]]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_is_retried() {
+        let calls = AtomicUsize::new(0);
+
+        let loader = Loader::new(|ids: Vec<i32>| {
+            let n = calls.fetch_add(1, Ordering::SeqCst);
+
+            async move {
+                if n == 0 {
+                    Err("down")
+                } else {
+                    Ok(ids.into_iter().map(|x| (x, x)).collect::<HashMap<_, _>>())
+                }
+            }
+        });
+
+        assert_eq!(loader.load(1).await, Err("down"));
+        assert_eq!(loader.load(1).await, Ok(Some(1)));
+    }
+}
@@ -0,0 +1,352 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! The lock table of the job scheduler, read back from the jobs that are not complete.
+//!
+//! The job scheduler keeps its locks in memory, and rebuilds them from the
+//! `locks_json` of each unfinished job when it starts. Reading the same columns
+//! gives the same view without asking the job scheduler.
+
+use crate::graphql::Context;
+use iml_postgres::sqlx;
+use iml_wire_types::graphql::{CommandBlocker, LockEntry, LockedRecord, RecordLocks};
+use juniper::{FieldError, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// A lock, as the job scheduler serializes it.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub(super) struct Lock {
+    pub(super) write: bool,
+    pub(super) begin_state: Option<String>,
+    pub(super) end_state: Option<String>,
+    pub(super) locked_item_id: i32,
+    pub(super) locked_item_type_id: i32,
+}
+
+#[derive(Debug, Clone)]
+struct OpenJob {
+    id: i32,
+    state: String,
+    class_name: String,
+    command_ids: Vec<i32>,
+    locks: Vec<Lock>,
+    wait_for: Vec<i32>,
+}
+
+pub(super) fn parse_json<T: serde::de::DeserializeOwned>(job_id: i32, s: &str) -> Vec<T> {
+    if s.trim().is_empty() {
+        return vec![];
+    }
+
+    serde_json::from_str(s).unwrap_or_else(|e| {
+        tracing::warn!("Could not parse the lock columns of job {}: {}", job_id, e);
+
+        vec![]
+    })
+}
+
+async fn open_jobs(context: &Context) -> Result<Vec<OpenJob>, sqlx::Error> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT
+                j.id,
+                j.state,
+                ct.model AS class_name,
+                j.locks_json,
+                j.wait_for_json,
+                array_remove(array_agg(cj.command_id ORDER BY cj.command_id), NULL) AS "command_ids!"
+            FROM chroma_core_job j
+            INNER JOIN django_content_type ct ON ct.id = j.content_type_id
+            LEFT OUTER JOIN chroma_core_command_jobs cj ON cj.job_id = j.id
+            WHERE j.state <> 'complete'
+            GROUP BY j.id, ct.model
+            ORDER BY j.id
+        "#
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    let xs = xs
+        .into_iter()
+        .map(|x| OpenJob {
+            locks: parse_json(x.id, &x.locks_json),
+            wait_for: parse_json(x.id, &x.wait_for_json),
+            id: x.id,
+            state: x.state,
+            class_name: x.class_name,
+            command_ids: x.command_ids,
+        })
+        .collect();
+
+    Ok(xs)
+}
+
+/// The jobs of `job` it still waits for. Completed jobs no longer hold anything up.
+fn waiting_for(job: &OpenJob, open: &HashSet<i32>) -> Vec<i32> {
+    job.wait_for
+        .iter()
+        .filter(|x| open.contains(x))
+        .copied()
+        .collect()
+}
+
+fn record_locks(jobs: &[OpenJob], content_type_id: i32, record_id: i32) -> RecordLocks {
+    let open: HashSet<_> = jobs.iter().map(|x| x.id).collect();
+
+    let (holders, waiters) = jobs
+        .iter()
+        .flat_map(|job| {
+            job.locks
+                .iter()
+                .filter(|l| {
+                    l.locked_item_type_id == content_type_id && l.locked_item_id == record_id
+                })
+                .map(move |l| LockEntry {
+                    job_id: job.id,
+                    job_state: job.state.clone(),
+                    class_name: job.class_name.clone(),
+                    command_ids: job.command_ids.clone(),
+                    write: l.write,
+                    begin_state: l.begin_state.clone(),
+                    end_state: l.end_state.clone(),
+                    waiting_for: waiting_for(job, &open),
+                })
+        })
+        .partition(|x| x.job_state == "tasked" || x.waiting_for.is_empty());
+
+    RecordLocks {
+        content_type_id,
+        record_id,
+        holders,
+        waiters,
+    }
+}
+
+fn command_blockers(
+    jobs: &[OpenJob],
+    command_id: i32,
+    models: &HashMap<i32, String>,
+    messages: &HashMap<i32, String>,
+) -> Vec<CommandBlocker> {
+    let by_id: BTreeMap<_, _> = jobs.iter().map(|x| (x.id, x)).collect();
+
+    jobs.iter()
+        .filter(|job| job.command_ids.contains(&command_id))
+        .flat_map(|job| {
+            job.wait_for
+                .iter()
+                .filter_map(|x| by_id.get(x))
+                .filter(|blocking| !blocking.command_ids.contains(&command_id))
+                .map(move |blocking| {
+                    let records = blocking
+                        .locks
+                        .iter()
+                        .filter(|l| {
+                            job.locks.iter().any(|x| {
+                                x.locked_item_type_id == l.locked_item_type_id
+                                    && x.locked_item_id == l.locked_item_id
+                            })
+                        })
+                        .map(|l| LockedRecord {
+                            content_type_id: l.locked_item_type_id,
+                            record_id: l.locked_item_id,
+                            record_type: models
+                                .get(&l.locked_item_type_id)
+                                .cloned()
+                                .unwrap_or_default(),
+                            write: l.write,
+                        })
+                        .collect();
+
+                    let blocking_command_id = blocking.command_ids.first().copied();
+
+                    CommandBlocker {
+                        job_id: job.id,
+                        class_name: job.class_name.clone(),
+                        blocking_job_id: blocking.id,
+                        blocking_class_name: blocking.class_name.clone(),
+                        blocking_state: blocking.state.clone(),
+                        blocking_command_id,
+                        blocking_command_message: blocking_command_id
+                            .and_then(|x| messages.get(&x))
+                            .cloned(),
+                        records,
+                    }
+                })
+        })
+        .collect()
+}
+
+/// The locks held and waited for on the `record_type` record with id `record_id`.
+/// `record_type` is the lowercased model name, e.g. `managedtarget`.
+pub(crate) async fn get_locks(
+    context: &Context,
+    record_type: String,
+    record_id: i32,
+) -> juniper::FieldResult<RecordLocks> {
+    let content_type_id = sqlx::query!(
+        "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = $1",
+        record_type.to_lowercase()
+    )
+    .fetch_optional(&context.pg_pool)
+    .await?
+    .map(|x| x.id)
+    .ok_or_else(|| {
+        FieldError::new(
+            format!("Unknown record type {}", record_type),
+            Value::null(),
+        )
+    })?;
+
+    let jobs = open_jobs(context).await?;
+
+    Ok(record_locks(&jobs, content_type_id, record_id))
+}
+
+/// The jobs of other commands that jobs of `command_id` are waiting for.
+pub(crate) async fn get_command_blockers(
+    context: &Context,
+    command_id: i32,
+) -> juniper::FieldResult<Vec<CommandBlocker>> {
+    let jobs = open_jobs(context).await?;
+
+    let models = sqlx::query!("SELECT id, model FROM django_content_type")
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x.model))
+        .collect();
+
+    let command_ids: Vec<i32> = jobs
+        .iter()
+        .filter_map(|x| x.command_ids.first().copied())
+        .collect();
+
+    let messages = sqlx::query!(
+        "SELECT id, message FROM chroma_core_command WHERE id = ANY($1)",
+        &command_ids
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.id, x.message))
+    .collect();
+
+    Ok(command_blockers(&jobs, command_id, &models, &messages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(write: bool, locked_item_id: i32) -> Lock {
+        Lock {
+            write,
+            begin_state: if write { Some("mounted".into()) } else { None },
+            end_state: if write {
+                Some("unmounted".into())
+            } else {
+                None
+            },
+            locked_item_id,
+            locked_item_type_id: 7,
+        }
+    }
+
+    fn job(id: i32, state: &str, command_id: i32, locks: Vec<Lock>, wait_for: Vec<i32>) -> OpenJob {
+        OpenJob {
+            id,
+            state: state.into(),
+            class_name: format!("job{}", id),
+            command_ids: vec![command_id],
+            locks,
+            wait_for,
+        }
+    }
+
+    fn jobs() -> Vec<OpenJob> {
+        vec![
+            job(1, "tasked", 10, vec![lock(true, 100)], vec![]),
+            job(
+                2,
+                "pending",
+                11,
+                vec![lock(false, 100), lock(true, 200)],
+                vec![1],
+            ),
+            job(3, "pending", 11, vec![lock(true, 200)], vec![2, 99]),
+            job(4, "pending", 12, vec![lock(false, 300)], vec![99]),
+        ]
+    }
+
+    #[test]
+    fn test_parse_json() {
+        assert!(parse_json::<Lock>(1, "").is_empty());
+        assert!(parse_json::<Lock>(1, "not json").is_empty());
+
+        let xs: Vec<Lock> = parse_json(
+            1,
+            r#"[{"uuid": "a", "write": true, "begin_state": null, "end_state": "mounted", "locked_item_id": 3, "locked_item_type_id": 7}]"#,
+        );
+
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].end_state.as_deref(), Some("mounted"));
+    }
+
+    #[test]
+    fn test_record_locks() {
+        let xs = record_locks(&jobs(), 7, 100);
+
+        assert_eq!(
+            xs.holders.iter().map(|x| x.job_id).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(
+            xs.waiters.iter().map(|x| x.job_id).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(xs.waiters[0].waiting_for, vec![1]);
+        assert!(!xs.waiters[0].write);
+
+        // Job 99 completed, so job 4 waits for nothing
+        let xs = record_locks(&jobs(), 7, 300);
+
+        assert_eq!(xs.holders[0].job_id, 4);
+        assert!(xs.waiters.is_empty());
+
+        let xs = record_locks(&jobs(), 8, 100);
+
+        assert!(xs.holders.is_empty());
+        assert!(xs.waiters.is_empty());
+    }
+
+    #[test]
+    fn test_command_blockers() {
+        let models = vec![(7, "managedtarget".to_string())].into_iter().collect();
+        let messages = vec![(10, "Stop target".to_string())].into_iter().collect();
+
+        let xs = command_blockers(&jobs(), 11, &models, &messages);
+
+        // Job 3 waits for job 2, but that is part of the same command
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].job_id, 2);
+        assert_eq!(xs[0].blocking_job_id, 1);
+        assert_eq!(xs[0].blocking_command_id, Some(10));
+        assert_eq!(
+            xs[0].blocking_command_message.as_deref(),
+            Some("Stop target")
+        );
+        assert_eq!(
+            xs[0].records,
+            vec![LockedRecord {
+                content_type_id: 7,
+                record_id: 100,
+                record_type: "managedtarget".into(),
+                write: true,
+            }]
+        );
+
+        assert!(command_blockers(&jobs(), 10, &models, &messages).is_empty());
+    }
+}
@@ -0,0 +1,390 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Alert rules on log message patterns.
+//!
+//! Rules are evaluated by `iml_journal::log_alert` as messages are ingested,
+//! and their alerts are lowered by `crate::log_alert`.
+
+use crate::graphql::{audit, invalid, not_found, validate_name, Context};
+use chrono::{Duration as ChronoDuration, Utc};
+use iml_journal::log_alert::{self, max_within, LogLine, Rule};
+use iml_postgres::sqlx::{self, postgres::types::PgInterval};
+use iml_wire_types::{
+    graphql_duration::GraphQLDuration,
+    log_alert::{LogAlertMatch, LogAlertRule, LogAlertRuleInput, LogAlertRuleTest},
+    AlertRecordType, AlertSeverity, LogSeverity,
+};
+use juniper::{FieldError, Value};
+use std::{
+    convert::{TryFrom, TryInto},
+    time::Duration,
+};
+
+const MAX_NAME_LEN: usize = 64;
+const MAX_MESSAGE_PATTERN_LEN: usize = 1024;
+const MAX_PATTERN_LEN: usize = 255;
+const MAX_MIN_MATCHES: i32 = 10_000;
+const DEFAULT_WITHIN: Duration = Duration::from_secs(5 * 60);
+/// A day
+const MAX_WITHIN: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_REPLAY: Duration = Duration::from_secs(60 * 60);
+/// A week
+const MAX_REPLAY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// The most messages a test replays.
+const MAX_REPLAYED: i64 = 100_000;
+/// How many matches a test returns.
+const SAMPLES: usize = 20;
+
+pub(crate) struct LogAlertQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl LogAlertQuery {
+    /// Every log alert rule, by name
+    async fn rules(context: &Context) -> juniper::FieldResult<Vec<LogAlertRule>> {
+        let xs = log_alert::rules(&context.pg_pool).await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(
+        rule(description = "The rule to test, which does not have to be saved"),
+        since(description = "How far back messages are replayed. Defaults to 1h, at most a week"),
+    ))]
+    /// Replay recent log messages through a rule, to see what it would match
+    /// and whether it would have raised an alert
+    async fn test(
+        context: &Context,
+        rule: LogAlertRuleInput,
+        since: Option<GraphQLDuration>,
+    ) -> juniper::FieldResult<LogAlertRuleTest> {
+        let rule = to_rule(0, rule).map_err(invalid)?;
+
+        let since = since.map(|x| x.0).unwrap_or(DEFAULT_REPLAY);
+
+        if since > MAX_REPLAY {
+            return Err(FieldError::new(
+                format!("since can be at most {}", GraphQLDuration(MAX_REPLAY)),
+                Value::null(),
+            ));
+        }
+
+        let xs = sqlx::query!(
+            r#"
+                SELECT datetime, fqdn, tag, severity, message
+                FROM chroma_core_logmessage
+                WHERE datetime >= $1
+                AND severity <= $2
+                ORDER BY datetime
+                LIMIT $3
+            "#,
+            Utc::now() - ChronoDuration::from_std(since)?,
+            rule.rule.severity as i16,
+            MAX_REPLAYED
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let scanned = xs.len();
+
+        let matched: Vec<_> = xs
+            .into_iter()
+            .filter(|x| {
+                rule.matches(&LogLine {
+                    fqdn: &x.fqdn,
+                    tag: &x.tag,
+                    severity: x.severity,
+                    message: &x.message,
+                })
+            })
+            .collect();
+
+        let times: Vec<_> = matched.iter().map(|x| x.datetime).collect();
+        let max = max_within(&times, rule.rule.within.0);
+
+        let samples = matched
+            .into_iter()
+            .rev()
+            .take(SAMPLES)
+            .map(|x| LogAlertMatch {
+                datetime: x.datetime,
+                fqdn: x.fqdn,
+                tag: x.tag,
+                severity: LogSeverity::try_from(x.severity).unwrap_or(LogSeverity::Debug),
+                message: x.message,
+            })
+            .collect();
+
+        Ok(LogAlertRuleTest {
+            scanned: scanned.try_into()?,
+            matched: times.len().try_into()?,
+            max_within: max.try_into()?,
+            would_alert: max >= rule.rule.min_matches as usize,
+            samples,
+        })
+    }
+}
+
+pub(crate) struct LogAlertMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl LogAlertMutation {
+    #[graphql(arguments(
+        id(description = "The rule to change. A new rule is created when omitted"),
+        rule(description = "The rule"),
+    ))]
+    /// Create a log alert rule, or replace an existing one.
+    /// Messages matched before the change still count towards the new rule.
+    async fn save(
+        context: &Context,
+        id: Option<i32>,
+        rule: LogAlertRuleInput,
+    ) -> juniper::FieldResult<LogAlertRule> {
+        let x = to_rule(id.unwrap_or_default(), rule).map_err(invalid)?.rule;
+
+        let duplicate = sqlx::query!(
+            r#"
+                SELECT id FROM chroma_core_logalertrule
+                WHERE name = $1 AND ($2::INT IS NULL OR id != $2)
+            "#,
+            &x.name,
+            id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?;
+
+        if duplicate.is_some() {
+            return Err(FieldError::new(
+                format!("Log alert rule {} already exists", x.name),
+                Value::null(),
+            ));
+        }
+
+        let within = PgInterval::try_from(x.within.0)?;
+        let alert_severity = i32::from(x.alert_severity);
+
+        let id = match id {
+            Some(id) => sqlx::query!(
+                r#"
+                    UPDATE chroma_core_logalertrule
+                    SET name = $2, enabled = $3, message_pattern = $4, fqdn_pattern = $5,
+                        tag_pattern = $6, severity = $7, min_matches = $8, within = $9,
+                        alert_severity = $10
+                    WHERE id = $1
+                    RETURNING id
+                "#,
+                id,
+                &x.name,
+                x.enabled,
+                &x.message_pattern,
+                x.fqdn_pattern.as_deref(),
+                x.tag_pattern.as_deref(),
+                x.severity as i16,
+                x.min_matches,
+                within,
+                alert_severity
+            )
+            .fetch_optional(&context.pg_pool)
+            .await?
+            .ok_or_else(|| not_found("Log alert rule", id))?
+            .id,
+            None => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO chroma_core_logalertrule
+                        (name, enabled, message_pattern, fqdn_pattern, tag_pattern, severity, min_matches, within, alert_severity)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                        RETURNING id
+                    "#,
+                    &x.name,
+                    x.enabled,
+                    &x.message_pattern,
+                    x.fqdn_pattern.as_deref(),
+                    x.tag_pattern.as_deref(),
+                    x.severity as i16,
+                    x.min_matches,
+                    within,
+                    alert_severity
+                )
+                .fetch_one(&context.pg_pool)
+                .await?
+                .id
+            }
+        };
+
+        audit::record(
+            context,
+            "log_alert.save",
+            serde_json::json!({
+                "id": id,
+                "name": x.name,
+                "enabled": x.enabled,
+                "messagePattern": x.message_pattern,
+                "fqdnPattern": x.fqdn_pattern,
+                "tagPattern": x.tag_pattern,
+                "severity": x.severity,
+                "minMatches": x.min_matches,
+                "within": x.within,
+                "alertSeverity": x.alert_severity,
+            }),
+            None,
+        )
+        .await;
+
+        let x = log_alert::rules(&context.pg_pool)
+            .await?
+            .into_iter()
+            .find(|x| x.id == id)
+            .ok_or_else(|| not_found("Log alert rule", id))?;
+
+        Ok(x)
+    }
+    #[graphql(arguments(id(description = "The rule to remove")))]
+    /// Remove a log alert rule, lowering its alert
+    async fn remove(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        iml_postgres::alert::lower(&context.pg_pool, vec![AlertRecordType::LogPatternAlert], id)
+            .await?;
+
+        sqlx::query!(
+            "DELETE FROM chroma_core_logalertrule WHERE id = $1 RETURNING id",
+            id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| not_found("Log alert rule", id))?;
+
+        audit::record(
+            context,
+            "log_alert.remove",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+}
+
+/// Validates `x`, filling in the defaults, and compiles its patterns.
+fn to_rule(id: i32, x: LogAlertRuleInput) -> Result<Rule, String> {
+    let name = x.name.trim().to_string();
+
+    validate_name("log alert rule", &name, MAX_NAME_LEN)?;
+
+    if x.message_pattern.is_empty() || x.message_pattern.len() > MAX_MESSAGE_PATTERN_LEN {
+        return Err(format!(
+            "messagePattern has to be 1 to {} characters",
+            MAX_MESSAGE_PATTERN_LEN
+        ));
+    }
+
+    let pattern = |x: Option<String>| x.filter(|x| !x.is_empty());
+
+    let fqdn_pattern = pattern(x.fqdn_pattern);
+    let tag_pattern = pattern(x.tag_pattern);
+
+    if fqdn_pattern
+        .iter()
+        .chain(tag_pattern.iter())
+        .any(|x| x.len() > MAX_PATTERN_LEN)
+    {
+        return Err(format!(
+            "fqdnPattern and tagPattern can be at most {} characters",
+            MAX_PATTERN_LEN
+        ));
+    }
+
+    let min_matches = x.min_matches.unwrap_or(1);
+
+    if !(1..=MAX_MIN_MATCHES).contains(&min_matches) {
+        return Err(format!(
+            "minMatches has to be between 1 and {}",
+            MAX_MIN_MATCHES
+        ));
+    }
+
+    let within = x.within.map(|x| x.0).unwrap_or(DEFAULT_WITHIN);
+
+    if within < Duration::from_secs(1) || within > MAX_WITHIN {
+        return Err(format!(
+            "within has to be between 1s and {}",
+            GraphQLDuration(MAX_WITHIN)
+        ));
+    }
+
+    Rule::new(LogAlertRule {
+        id,
+        name,
+        enabled: x.enabled.unwrap_or(true),
+        message_pattern: x.message_pattern,
+        fqdn_pattern,
+        tag_pattern,
+        severity: x.severity.unwrap_or(LogSeverity::Debug),
+        min_matches,
+        within: GraphQLDuration(within),
+        alert_severity: x.alert_severity.unwrap_or(AlertSeverity::WARNING),
+        active: false,
+    })
+    .map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input() -> LogAlertRuleInput {
+        LogAlertRuleInput {
+            name: " evictions ".into(),
+            message_pattern: "evicting client at".into(),
+            fqdn_pattern: Some("".into()),
+            tag_pattern: None,
+            severity: None,
+            min_matches: Some(3),
+            within: None,
+            alert_severity: None,
+            enabled: None,
+        }
+    }
+
+    #[test]
+    fn test_to_rule() {
+        let x = to_rule(1, input()).unwrap().rule;
+
+        assert_eq!(x.name, "evictions");
+        assert_eq!(x.fqdn_pattern, None);
+        assert_eq!(x.severity, LogSeverity::Debug);
+        assert_eq!(x.within, GraphQLDuration(DEFAULT_WITHIN));
+        assert_eq!(x.alert_severity, AlertSeverity::WARNING);
+        assert!(x.enabled);
+    }
+
+    #[test]
+    fn test_to_rule_invalid() {
+        let invalid = vec![
+            LogAlertRuleInput {
+                name: "".into(),
+                ..input()
+            },
+            LogAlertRuleInput {
+                message_pattern: "".into(),
+                ..input()
+            },
+            LogAlertRuleInput {
+                message_pattern: "(evicting".into(),
+                ..input()
+            },
+            LogAlertRuleInput {
+                min_matches: Some(0),
+                ..input()
+            },
+            LogAlertRuleInput {
+                within: Some(GraphQLDuration(Duration::from_secs(2 * 24 * 60 * 60))),
+                ..input()
+            },
+        ];
+
+        for x in invalid {
+            assert!(to_rule(1, x).is_err());
+        }
+    }
+}
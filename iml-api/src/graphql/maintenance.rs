@@ -0,0 +1,302 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Maintenance windows.
+//!
+//! Alerts raised while a window that suppresses alerts is active are dismissed as they are
+//! raised by a trigger on `chroma_core_alertstate`, so they are neither emailed nor escalated.
+//! Alerts that were already active when the window started are left alone.
+
+use crate::graphql::{audit, invalid, not_found, validate_name, Context};
+use chrono::{DateTime, Duration, Utc};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::maintenance::{MaintenanceScope, MaintenanceWindow, MaintenanceWindowInput};
+use juniper::{FieldError, Value};
+
+const MAX_NAME_LEN: usize = 64;
+/// Longer windows are better expressed as several windows
+const MAX_DAYS: i64 = 30;
+const DEFAULT_UPCOMING: i32 = 10;
+
+pub(crate) struct MaintenanceQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl MaintenanceQuery {
+    /// Every maintenance window, most recent first
+    async fn windows(context: &Context) -> juniper::FieldResult<Vec<MaintenanceWindow>> {
+        let mut xs = list(&context.pg_pool, None, false, None).await?;
+
+        xs.reverse();
+
+        Ok(xs)
+    }
+    #[graphql(arguments(limit(description = "At most this many windows. Defaults to 10")))]
+    /// The active windows and the windows that did not start yet, soonest first
+    async fn upcoming(
+        context: &Context,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<MaintenanceWindow>> {
+        let limit = limit.unwrap_or(DEFAULT_UPCOMING).max(0);
+
+        let xs = list(&context.pg_pool, None, true, Some(limit as i64)).await?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct MaintenanceMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl MaintenanceMutation {
+    #[graphql(arguments(
+        id(description = "The window to change. A new window is scheduled when omitted"),
+        window(description = "The window"),
+    ))]
+    /// Schedule a maintenance window, or change an existing one.
+    /// Alerts dismissed while a window was active stay dismissed when it is changed.
+    async fn save(
+        context: &Context,
+        id: Option<i32>,
+        window: MaintenanceWindowInput,
+    ) -> juniper::FieldResult<MaintenanceWindow> {
+        let name = window.name.trim().to_string();
+        let suppress_alerts = window.suppress_alerts.unwrap_or(true);
+
+        validate(
+            &name,
+            window.starts_at,
+            window.ends_at,
+            id.is_none(),
+            Utc::now(),
+        )
+        .map_err(invalid)?;
+
+        check_scope(&context.pg_pool, window.scope, window.scope_id).await?;
+
+        let id = match id {
+            Some(id) => sqlx::query!(
+                r#"
+                    UPDATE maintenance_window
+                    SET name = $2, scope = $3, scope_id = $4, starts_at = $5, ends_at = $6, suppress_alerts = $7
+                    WHERE id = $1
+                    RETURNING id
+                "#,
+                id,
+                &name,
+                window.scope as MaintenanceScope,
+                window.scope_id,
+                window.starts_at,
+                window.ends_at,
+                suppress_alerts
+            )
+            .fetch_optional(&context.pg_pool)
+            .await?
+            .ok_or_else(|| not_found("Maintenance window", id))?
+            .id,
+            None => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO maintenance_window
+                        (name, scope, scope_id, starts_at, ends_at, suppress_alerts, user_id)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7)
+                        RETURNING id
+                    "#,
+                    &name,
+                    window.scope as MaintenanceScope,
+                    window.scope_id,
+                    window.starts_at,
+                    window.ends_at,
+                    suppress_alerts,
+                    context.user_id
+                )
+                .fetch_one(&context.pg_pool)
+                .await?
+                .id
+            }
+        };
+
+        audit::record(
+            context,
+            "maintenance.save",
+            serde_json::json!({
+                "id": id,
+                "name": name,
+                "scope": window.scope,
+                "scopeId": window.scope_id,
+                "startsAt": window.starts_at,
+                "endsAt": window.ends_at,
+                "suppressAlerts": suppress_alerts,
+            }),
+            None,
+        )
+        .await;
+
+        list(&context.pg_pool, Some(id), false, None)
+            .await?
+            .pop()
+            .ok_or_else(|| not_found("Maintenance window", id))
+    }
+    #[graphql(arguments(id(description = "The window to remove")))]
+    /// Remove a maintenance window. Alerts it dismissed stay dismissed.
+    async fn remove(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        sqlx::query!(
+            "DELETE FROM maintenance_window WHERE id = $1 RETURNING id",
+            id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| not_found("Maintenance window", id))?;
+
+        audit::record(
+            context,
+            "maintenance.remove",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+}
+
+/// Windows by start, all of them or the one with `id`.
+/// When `upcoming` is set, only those that did not end yet.
+async fn list(
+    pool: &PgPool,
+    id: Option<i32>,
+    upcoming: bool,
+    limit: Option<i64>,
+) -> Result<Vec<MaintenanceWindow>, sqlx::Error> {
+    sqlx::query_as!(
+        MaintenanceWindow,
+        r#"
+            SELECT
+                w.id,
+                w.name,
+                w.scope as "scope: MaintenanceScope",
+                w.scope_id,
+                CASE w.scope
+                    WHEN 'host' THEN (
+                        SELECT fqdn FROM chroma_core_managedhost
+                        WHERE id = w.scope_id AND not_deleted = 't'
+                    )
+                    WHEN 'filesystem' THEN (
+                        SELECT name FROM chroma_core_managedfilesystem
+                        WHERE id = w.scope_id AND not_deleted = 't'
+                    )
+                    ELSE (
+                        SELECT string_agg(DISTINCT h.fqdn, ', ')
+                        FROM corosync_node_managed_host c
+                        INNER JOIN chroma_core_managedhost h ON h.id = c.host_id
+                        WHERE c.cluster_id = w.scope_id
+                    )
+                END AS "scope_name?",
+                w.starts_at,
+                w.ends_at,
+                w.suppress_alerts,
+                u.username AS "created_by?"
+            FROM maintenance_window w
+            LEFT JOIN auth_user u ON u.id = w.user_id
+            WHERE ($1::INT IS NULL OR w.id = $1)
+            AND (NOT $2::BOOL OR w.ends_at > now())
+            ORDER BY w.starts_at, w.id
+            LIMIT $3
+        "#,
+        id,
+        upcoming,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+async fn check_scope(pool: &PgPool, scope: MaintenanceScope, id: i32) -> juniper::FieldResult<()> {
+    let found = match scope {
+        MaintenanceScope::Host => sqlx::query!(
+            "SELECT id FROM chroma_core_managedhost WHERE id = $1 AND not_deleted = 't'",
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .is_some(),
+        MaintenanceScope::Filesystem => sqlx::query!(
+            "SELECT id FROM chroma_core_managedfilesystem WHERE id = $1 AND not_deleted = 't'",
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .is_some(),
+        MaintenanceScope::Cluster => {
+            sqlx::query!("SELECT id FROM corosync_cluster WHERE id = $1", id)
+                .fetch_optional(pool)
+                .await?
+                .is_some()
+        }
+    };
+
+    if found {
+        Ok(())
+    } else {
+        Err(FieldError::new(
+            format!("{:?} {} not found", scope, id),
+            Value::null(),
+        ))
+    }
+}
+
+fn validate(
+    name: &str,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    new: bool,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    validate_name("maintenance window", name, MAX_NAME_LEN)?;
+
+    if ends_at <= starts_at {
+        return Err("A maintenance window has to end after it starts".into());
+    }
+
+    if ends_at - starts_at > Duration::days(MAX_DAYS) {
+        return Err(format!(
+            "A maintenance window lasts at most {} days",
+            MAX_DAYS
+        ));
+    }
+
+    if new && ends_at <= now {
+        return Err("A new maintenance window cannot end in the past".into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_validate() {
+        let now = Utc.ymd(2021, 2, 1).and_hms(9, 0, 0);
+        let hour = Duration::hours(1);
+
+        assert_eq!(validate("upgrade", now, now + hour, true, now), Ok(()));
+        assert!(validate("", now, now + hour, true, now).is_err());
+        assert!(validate("upgrade", now, now, true, now).is_err());
+        assert!(validate(
+            "upgrade",
+            now,
+            now + Duration::days(MAX_DAYS + 1),
+            true,
+            now
+        )
+        .is_err());
+        assert!(validate("upgrade", now - hour * 2, now - hour, true, now).is_err());
+        assert_eq!(
+            validate("upgrade", now - hour * 2, now - hour, false, now),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{error::ImlApiError, graphql::Context};
+use chrono::Utc;
+use iml_influx::Client;
+use iml_manager_env::{get_device_aggregator_addr, get_influxdb_addr, get_influxdb_metrics_db};
+use iml_postgres::{sqlx, PgPool};
+use iml_rabbit::{Pool, QueueDeclareOptions};
+use iml_wire_types::graphql::{HealthSeverity, ManagerStatus, ServiceStatus};
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+use tokio::{net::TcpStream, time::timeout};
+use url::Url;
+
+/// How long a service has to answer before it is considered down.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Messages waiting in a service queue beyond this are a warning.
+const QUEUE_BACKLOG: u32 = 1000;
+
+/// Replay lag of a standby beyond this is a warning.
+const REPLICATION_LAG_SECS: f64 = 60.0;
+
+/// WAL a replica has yet to replay beyond this is a warning, one WAL segment.
+const REPLICATION_LAG_BYTES: i64 = 16 * 1024 * 1024;
+
+type Findings = Vec<(HealthSeverity, String)>;
+
+/// Checks the services the manager is made of, concurrently.
+pub(crate) async fn get_manager_status(context: &Context) -> juniper::FieldResult<ManagerStatus> {
+    let (postgres, rabbitmq, influxdb, device_aggregator, action_runner) = futures::join!(
+        check("postgres", postgres(&context.pg_pool)),
+        check("rabbitmq", rabbitmq(&context.rabbit_pool)),
+        check("influxdb", influxdb()),
+        check("device-aggregator", device_aggregator(&context.rabbit_pool)),
+        check("action-runner", action_runner(&context.rabbit_pool)),
+    );
+
+    Ok(ManagerStatus::new(
+        vec![
+            postgres,
+            rabbitmq,
+            influxdb,
+            device_aggregator,
+            action_runner,
+        ],
+        Utc::now(),
+    ))
+}
+
+/// Runs `f` within `CHECK_TIMEOUT`, timing how long it took.
+///
+/// The severity is the worst of the findings, or an error if `f` failed or timed out.
+async fn check(
+    name: &str,
+    f: impl Future<Output = Result<Findings, ImlApiError>>,
+) -> ServiceStatus {
+    let start = Instant::now();
+
+    let (response_ms, findings) = match timeout(CHECK_TIMEOUT, f).await {
+        Ok(Ok(xs)) => (Some(start.elapsed().as_millis() as i32), xs),
+        Ok(Err(e)) => (None, vec![(HealthSeverity::Error, e.to_string())]),
+        Err(_) => (
+            None,
+            vec![(
+                HealthSeverity::Error,
+                format!("Did not answer within {}s", CHECK_TIMEOUT.as_secs()),
+            )],
+        ),
+    };
+
+    ServiceStatus {
+        name: name.to_string(),
+        severity: findings.iter().map(|(x, _)| *x).max().unwrap_or_default(),
+        response_ms,
+        details: findings.into_iter().map(|(_, x)| x).collect(),
+    }
+}
+
+async fn postgres(pool: &PgPool) -> Result<Findings, ImlApiError> {
+    let in_recovery = sqlx::query!(r#"SELECT pg_is_in_recovery() AS "in_recovery!""#)
+        .fetch_one(pool)
+        .await?
+        .in_recovery;
+
+    if in_recovery {
+        let lag = sqlx::query!(
+            r#"SELECT EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp())::float8 AS lag"#
+        )
+        .fetch_one(pool)
+        .await?
+        .lag;
+
+        let x = match lag {
+            Some(lag) if lag > REPLICATION_LAG_SECS => (
+                HealthSeverity::Warning,
+                format!("Standby, replaying {:.0}s behind the primary", lag),
+            ),
+            Some(lag) => (
+                HealthSeverity::Good,
+                format!("Standby, replaying {:.0}s behind the primary", lag),
+            ),
+            None => (
+                HealthSeverity::Warning,
+                "Standby, nothing replayed from the primary yet".to_string(),
+            ),
+        };
+
+        return Ok(vec![x]);
+    }
+
+    let replicas = sqlx::query!(
+        r#"
+            SELECT
+                application_name AS "name!",
+                state AS "state!",
+                pg_xlog_location_diff(pg_current_xlog_location(), replay_location)::bigint AS lag
+            FROM pg_stat_replication
+            ORDER BY application_name
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut xs = vec![(
+        HealthSeverity::Good,
+        format!("Primary with {} replica(s)", replicas.len()),
+    )];
+
+    for x in replicas {
+        let lag = x.lag.unwrap_or_default();
+
+        let severity = if x.state != "streaming" || lag > REPLICATION_LAG_BYTES {
+            HealthSeverity::Warning
+        } else {
+            HealthSeverity::Good
+        };
+
+        xs.push((
+            severity,
+            format!("Replica {} is {}, {} bytes behind", x.name, x.state, lag),
+        ));
+    }
+
+    Ok(xs)
+}
+
+async fn rabbitmq(pool: &Pool) -> Result<Findings, ImlApiError> {
+    let conn = pool.get().await.map_err(iml_rabbit::ImlRabbitError::from)?;
+
+    let _ = iml_rabbit::create_channel(&conn)
+        .await?
+        .close(0, "done")
+        .await;
+
+    Ok(vec![(HealthSeverity::Good, "Connected".to_string())])
+}
+
+async fn influxdb() -> Result<Findings, ImlApiError> {
+    let url = Url::parse(&format!("http://{}", get_influxdb_addr()))?;
+    let client = Client::new(url, get_influxdb_metrics_db());
+
+    let x = if client.ping().await {
+        (HealthSeverity::Good, "Answered ping".to_string())
+    } else {
+        (HealthSeverity::Error, "Did not answer ping".to_string())
+    };
+
+    Ok(vec![x])
+}
+
+async fn device_aggregator(pool: &Pool) -> Result<Findings, ImlApiError> {
+    let addr = get_device_aggregator_addr();
+
+    let x = match TcpStream::connect(addr).await {
+        Ok(_) => (HealthSeverity::Good, format!("Listening on {}", addr)),
+        Err(e) => (
+            HealthSeverity::Error,
+            format!("Could not connect to {}: {}", addr, e),
+        ),
+    };
+
+    Ok(vec![x, queue(pool, "rust_agent_device_rx").await?])
+}
+
+async fn action_runner(pool: &Pool) -> Result<Findings, ImlApiError> {
+    Ok(vec![queue(pool, "rust_agent_action_runner_rx").await?])
+}
+
+/// Inspects a service queue without declaring it.
+///
+/// A queue nobody consumes is an error, a queue with a backlog is a warning.
+async fn queue(pool: &Pool, name: &str) -> Result<(HealthSeverity, String), ImlApiError> {
+    let conn = pool.get().await.map_err(iml_rabbit::ImlRabbitError::from)?;
+
+    // A passive declare of a missing queue closes the channel, so each queue gets its own.
+    let ch = iml_rabbit::create_channel(&conn).await?;
+
+    let q = iml_rabbit::declare_queue(
+        &ch,
+        name,
+        QueueDeclareOptions {
+            passive: true,
+            ..QueueDeclareOptions::default()
+        },
+        None,
+    )
+    .await;
+
+    let _ = ch.close(0, "done").await;
+
+    let q = match q {
+        Ok(q) => q,
+        Err(e) => return Ok((HealthSeverity::Error, format!("Queue {}: {}", name, e))),
+    };
+
+    Ok(queue_finding(name, q.message_count(), q.consumer_count()))
+}
+
+fn queue_finding(name: &str, messages: u32, consumers: u32) -> (HealthSeverity, String) {
+    let severity = if consumers == 0 {
+        HealthSeverity::Error
+    } else if messages > QUEUE_BACKLOG {
+        HealthSeverity::Warning
+    } else {
+        HealthSeverity::Good
+    };
+
+    (
+        severity,
+        format!(
+            "Queue {} has {} message(s) and {} consumer(s)",
+            name, messages, consumers
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_finding() {
+        assert_eq!(
+            queue_finding("rust_agent_device_rx", 0, 0).0,
+            HealthSeverity::Error
+        );
+        assert_eq!(
+            queue_finding("rust_agent_device_rx", 5000, 1).0,
+            HealthSeverity::Warning
+        );
+        assert_eq!(
+            queue_finding("rust_agent_device_rx", 3, 1),
+            (
+                HealthSeverity::Good,
+                "Queue rust_agent_device_rx has 3 message(s) and 1 consumer(s)".to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_failed() {
+        let x = check("postgres", async { Err(ImlApiError::NoneError) }).await;
+
+        assert_eq!(x.severity, HealthSeverity::Error);
+        assert_eq!(x.details, vec!["Not Found".to_string()]);
+    }
+}
@@ -2,87 +2,120 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+pub(crate) mod approval;
+mod audit;
+mod banner;
+mod certificate;
+mod changelog;
+mod command_filter;
+mod command_logs;
+mod compatibility;
+mod dashboard;
+mod dashboards;
+pub(crate) mod deferred;
+mod digest;
+mod dne;
+mod escalation;
+mod federation;
 mod filesystem;
+mod ha_cluster;
+mod host;
+mod lnet_ping;
+mod loader;
+mod locks;
+mod log_alert;
+mod maintenance;
+mod manager;
+mod new_target;
+mod role;
+mod security;
+mod snapshot_policies;
+mod snapshot_preflight;
+mod standby;
+mod stats;
+mod stonith;
 mod stratagem;
+mod stripe;
 mod task;
+#[cfg(all(test, feature = "test"))]
+mod test_harness;
+mod tuning;
+mod user_preferences;
 
 use crate::{
+    auth::Principal,
+    capacity, client_mount,
     command::get_command,
+    encoding,
     error::ImlApiError,
+    integrity, migrate, row_count, snapshot_policy, target_remount,
     timer::{configure_snapshot_timer, remove_snapshot_timer},
 };
 use chrono::{DateTime, Utc};
-use futures::{
-    future::{self, join_all},
-    TryFutureExt, TryStreamExt,
-};
-use iml_postgres::{
-    active_mgs_host_fqdn, fqdn_by_host_id, sqlx, sqlx::postgres::types::PgInterval, PgPool,
-};
+use futures::{future, TryFutureExt, TryStreamExt};
+use iml_manager_env::get_graphql_max_page_size;
+use iml_postgres::{active_mgs_host_fqdn, sqlx, sqlx::postgres::types::PgInterval, PgPool};
 use iml_rabbit::{ImlRabbitError, Pool};
 use iml_wire_types::{
-    db::{LogMessageRecord, LustreFid, ServerProfileRecord, TargetRecord},
-    graphql::{ServerProfile, ServerProfileInput},
+    changes::{Change, ChangeFeed, ChangeRecordType},
+    db::{LogMessageRecord, LustreFid, ServerProfileRecord, TargetRecord, UnmanagedResource},
+    dne::FilesystemDne,
+    federation::FederatedOverview,
+    graphql::{
+        BannedResource, CapacityForecast, ClientMountConfigKind, CommandAnnotations,
+        CommandBlocker, CommandFilter, CommandList, CommandNote, CommandStepLogs,
+        CompatibilityReport, CorosyncNode, CorosyncNodeList, DeferredQuery, DeferredQueryResult,
+        DegradedFilesystem, DownHost, FilesystemCheckRun, FilesystemOverview, HaEvent,
+        HostQueueEntry, HostQueueSummary, JobPriority, JobTimeout, ManagerStatus, PageMeta,
+        ProfileListDiff, ProfileSettingDiff, RecordLocks, SchemaMigration, ServerProfile,
+        ServerProfileComparison, ServerProfileInput, SystemHealth, TargetList, TargetMiniStats,
+        TargetParam, TargetResource, TargetStateChange, TargetTransition,
+    },
     graphql_duration::GraphQLDuration,
+    integrity::IntegrityReport,
     logs::{LogResponse, Meta},
-    snapshot::{ReserveUnit, Snapshot, SnapshotInterval, SnapshotRetention},
+    multipath::{MultipathEvent, TargetMultipath, TargetMultipathStatus},
+    recovery::{FilesystemRecoveryStatus, TargetRecoveryStatus},
+    role::Permission,
+    snapshot::{
+        default_time_zone, parse_snapshot_name, ReserveUnit, Snapshot, SnapshotComparison,
+        SnapshotHistoryEntry, SnapshotInterval, SnapshotIntervalRun, SnapshotPolicyImport,
+        SnapshotPolicyRun, SnapshotPolicySettings, SnapshotPreflight, SnapshotRetention,
+        SnapshotSchedule,
+    },
+    stripe::DefaultStripe,
+    target_remount::TargetRemountStatus,
     task::Task,
-    Command, EndpointName, FsType, Job, LogMessage, LogSeverity, MessageClass, SortDir,
+    AlertRecordType, Command, EndpointName, FsType, Job, LogMessage, LogSeverity, MessageClass,
+    SortDir,
 };
 use itertools::Itertools;
 use juniper::{
     http::{graphiql::graphiql_source, GraphQLRequest},
     EmptySubscription, FieldError, RootNode, Value,
 };
+use lazy_static::lazy_static;
+pub(crate) use loader::Loaders;
+use regex::Regex;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     convert::{Infallible, TryFrom as _, TryInto},
     ops::Deref,
     str::FromStr,
     sync::Arc,
+    time::Duration,
 };
-use warp::Filter;
-
-#[derive(juniper::GraphQLObject)]
-/// A Corosync Node found in `crm_mon`
-struct CorosyncNode {
-    /// The name of the node
-    name: String,
-    /// The id of the node as reported by `crm_mon`
-    id: String,
-    /// Id of the cluster this node belongs to
-    cluster_id: i32,
-    online: bool,
-    standby: bool,
-    standby_onfail: bool,
-    maintenance: bool,
-    pending: bool,
-    unclean: bool,
-    shutdown: bool,
-    expected_up: bool,
-    is_dc: bool,
-    resources_running: i32,
-    r#type: String,
-}
+use tracing::Instrument as _;
+use warp::{http::StatusCode, Filter, Reply as _};
 
-#[derive(juniper::GraphQLObject, Hash, Eq, PartialEq)]
-/// A Lustre Target and it's corresponding resource
-struct TargetResource {
-    /// The id of the cluster
-    cluster_id: i32,
-    /// The filesystems associated with this target
-    fs_names: Vec<String>,
-    /// The uuid of this target
-    uuid: String,
-    /// The name of this target
-    name: String,
-    /// The corosync resource id associated with this target
-    resource_id: String,
-    /// The current state of this target
-    state: String,
-    /// The list of host ids this target could possibly run on
-    cluster_hosts: Vec<i32>,
-}
+/// How long a `target_params` value is served from cache.
+const TARGET_PARAMS_TTL: Duration = Duration::from_secs(5);
+
+const CHANGES_DEFAULT_LIMIT: i32 = 1000;
+const CHANGES_MAX_LIMIT: i32 = 10_000;
+
+const DEFAULT_FORECAST_HORIZON_DAYS: i32 = 90;
+const MAX_FORECAST_HORIZON_DAYS: i32 = 3650;
 
 struct BannedTargetResource {
     resource: String,
@@ -91,25 +124,6 @@ struct BannedTargetResource {
     mount_point: Option<String>,
 }
 
-#[derive(juniper::GraphQLObject)]
-/// A Corosync banned resource
-struct BannedResource {
-    // The primary id
-    id: i32,
-    /// The resource name
-    name: String,
-    /// The id of the cluster in which the resource lives
-    cluster_id: i32,
-    /// The resource name
-    resource: String,
-    /// The node in which the resource lives
-    node: String,
-    /// The assigned weight of the resource
-    weight: i32,
-    /// Is master only
-    master_only: bool,
-}
-
 #[derive(Debug, serde::Serialize)]
 pub struct SendJob<'a, T> {
     pub class_name: &'a str,
@@ -120,12 +134,89 @@ pub(crate) struct QueryRoot;
 
 #[juniper::graphql_object(Context = Context)]
 impl QueryRoot {
+    fn approval(&self) -> approval::ApprovalQuery {
+        approval::ApprovalQuery
+    }
+    fn audit(&self) -> audit::AuditQuery {
+        audit::AuditQuery
+    }
+    fn banner(&self) -> banner::BannerQuery {
+        banner::BannerQuery
+    }
+    fn certificate(&self) -> certificate::CertificateQuery {
+        certificate::CertificateQuery
+    }
+    fn changelog(&self) -> changelog::ChangelogQuery {
+        changelog::ChangelogQuery
+    }
+    fn dashboards(&self) -> dashboards::DashboardsQuery {
+        dashboards::DashboardsQuery
+    }
+    fn digest(&self) -> digest::DigestQuery {
+        digest::DigestQuery
+    }
+    fn escalation(&self) -> escalation::EscalationQuery {
+        escalation::EscalationQuery
+    }
+    fn federation(&self) -> federation::FederationQuery {
+        federation::FederationQuery
+    }
+    fn ha_cluster(&self) -> ha_cluster::HaClusterQuery {
+        ha_cluster::HaClusterQuery
+    }
+    fn host(&self) -> host::HostQuery {
+        host::HostQuery
+    }
+    fn lnet_ping(&self) -> lnet_ping::LnetPingQuery {
+        lnet_ping::LnetPingQuery
+    }
+    fn log_alert(&self) -> log_alert::LogAlertQuery {
+        log_alert::LogAlertQuery
+    }
+    fn maintenance(&self) -> maintenance::MaintenanceQuery {
+        maintenance::MaintenanceQuery
+    }
+    fn role(&self) -> role::RoleQuery {
+        role::RoleQuery
+    }
+    fn security(&self) -> security::SecurityQuery {
+        security::SecurityQuery
+    }
+    fn standby(&self) -> standby::StandbyQuery {
+        standby::StandbyQuery
+    }
+    fn stonith(&self) -> stonith::StonithQuery {
+        stonith::StonithQuery
+    }
     fn stratagem(&self) -> stratagem::StratagemQuery {
         stratagem::StratagemQuery
     }
     fn task(&self) -> task::TaskQuery {
         task::TaskQuery
     }
+    fn tuning(&self) -> tuning::TuningQuery {
+        tuning::TuningQuery
+    }
+    fn user_preferences(&self) -> user_preferences::UserPreferencesQuery {
+        user_preferences::UserPreferencesQuery
+    }
+    #[graphql(arguments(
+        handle(description = "The handle returned by `submitQuery`"),
+        limit(
+            description = "optional paging limit, defaults to and is capped at the maximum page size"
+        ),
+        offset(description = "Offset into the rows of the result, defaults to 0"),
+    ))]
+    /// The state of a query submitted with `submitQuery`, and a page of its result once complete.
+    /// `null` if there is no such query, it was submitted by another user, or it has expired.
+    async fn query_result(
+        context: &Context,
+        handle: String,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> juniper::FieldResult<Option<DeferredQueryResult>> {
+        deferred::result(context, handle, limit, offset).await
+    }
     /// Given a host id, try to find the matching corosync node name
     #[graphql(arguments(host_id(description = "The id to search on")))]
     async fn corosync_node_name_by_host(
@@ -146,7 +237,9 @@ impl QueryRoot {
         Ok(x)
     }
     #[graphql(arguments(
-        limit(description = "optional paging limit, defaults to all rows"),
+        limit(
+            description = "optional paging limit, defaults to and is capped at the maximum page size"
+        ),
         offset(description = "Offset into items, defaults to 0"),
         dir(description = "Sort direction, defaults to asc")
     ))]
@@ -155,8 +248,10 @@ impl QueryRoot {
         limit: Option<i32>,
         offset: Option<i32>,
         dir: Option<SortDir>,
-    ) -> juniper::FieldResult<Vec<CorosyncNode>> {
+    ) -> juniper::FieldResult<CorosyncNodeList> {
         let dir = dir.unwrap_or_default();
+        let limit = page_limit(limit, get_graphql_max_page_size());
+        let offset = offset.unwrap_or(0).max(0);
 
         let xs = sqlx::query_as!(
             CorosyncNode,
@@ -182,8 +277,65 @@ impl QueryRoot {
                     CASE WHEN $1 = 'DESC' THEN n.id END DESC
                 OFFSET $2 LIMIT $3"#,
             dir.deref(),
-            offset.unwrap_or(0) as i64,
-            limit.map(|x| x as i64),
+            offset as i64,
+            limit as i64,
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let count = row_count::count(&context.pg_pool, "corosync_node", false, |_| async {
+            sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM corosync_node"#)
+                .fetch_one(&context.pg_pool)
+                .await
+                .map(|x| x.count)
+        })
+        .await?;
+
+        Ok(CorosyncNodeList {
+            data: xs,
+            meta: PageMeta {
+                limit,
+                offset,
+                total_count: count.total as i32,
+                is_estimate: count.is_estimate,
+            },
+        })
+    }
+    #[graphql(arguments(host_id(
+        description = "Only list resources in the cluster this host is a member of"
+    )))]
+    /// List corosync resources that do not correspond to any known target.
+    /// These point to configuration drift or leftovers from manual changes.
+    async fn unmanaged_resources(
+        context: &Context,
+        host_id: Option<i32>,
+    ) -> juniper::FieldResult<Vec<UnmanagedResource>> {
+        let xs = sqlx::query_as!(
+            UnmanagedResource,
+            r#"
+                SELECT
+                    r.id,
+                    r.name,
+                    r.cluster_id,
+                    r.resource_agent,
+                    r.role,
+                    r.active,
+                    r.failed,
+                    r.active_node_name,
+                    r.mount_point
+                FROM corosync_resource r
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM target t WHERE t.mount_path = r.mount_point
+                )
+                AND (
+                    $1::INT IS NULL
+                    OR r.cluster_id IN (
+                        SELECT nmh.cluster_id FROM corosync_node_managed_host nmh WHERE nmh.host_id = $1
+                    )
+                )
+                ORDER BY r.cluster_id, r.name
+            "#,
+            host_id
         )
         .fetch_all(&context.pg_pool)
         .await?;
@@ -192,11 +344,19 @@ impl QueryRoot {
     }
 
     #[graphql(arguments(
-        limit(description = "optional paging limit, defaults to all rows"),
+        limit(
+            description = "optional paging limit, defaults to and is capped at the maximum page size"
+        ),
         offset(description = "Offset into items, defaults to 0"),
         dir(description = "Sort direction, defaults to asc"),
         fs_name(description = "Targets associated with the specified filesystem"),
         exclude_unmounted(description = "Exclude unmounted targets, defaults to false"),
+        dev_path(description = "Targets created on the specified device path"),
+        serial(description = "Targets created on a device with the specified serial"),
+        fqdn(description = "Targets currently running on the host with the specified fqdn"),
+        host_tags(
+            description = "Targets that can run on a host matching all of these tags. Each is either `key` or `key=value`"
+        ),
     ))]
     /// Fetch the list of known targets
     async fn targets(
@@ -206,37 +366,87 @@ impl QueryRoot {
         dir: Option<SortDir>,
         fs_name: Option<String>,
         exclude_unmounted: Option<bool>,
-    ) -> juniper::FieldResult<Vec<TargetRecord>> {
+        dev_path: Option<String>,
+        serial: Option<String>,
+        fqdn: Option<String>,
+        host_tags: Option<Vec<String>>,
+    ) -> juniper::FieldResult<TargetList> {
         let dir = dir.unwrap_or_default();
+        let limit = page_limit(limit, get_graphql_max_page_size());
+        let offset = offset.unwrap_or(0).max(0);
 
         if let Some(ref fs_name) = fs_name {
             let _ = fs_id_by_name(&context.pg_pool, &fs_name).await?;
         }
 
+        let tagged_hosts = match host_tags {
+            Some(tags) => Some(host::host_ids_by_tags(&context.pg_pool, &tags).await?),
+            None => None,
+        };
+
+        // The filters are applied in the query, so pages and `total_count` only cover matching targets.
+        // Both go through `filtered_targets`, so they can't disagree on what matches.
         let xs: Vec<TargetRecord> = sqlx::query_as!(
             TargetRecord,
             r#"
-                SELECT id, state, name, active_host_id, host_ids, filesystems, uuid, mount_path, dev_path, fs_type as "fs_type: FsType" from target t
+                SELECT
+                    t.id AS "id!",
+                    t.state AS "state!",
+                    t.name AS "name!",
+                    t.active_host_id,
+                    t.host_ids AS "host_ids!",
+                    t.filesystems AS "filesystems!",
+                    t.uuid AS "uuid!",
+                    t.mount_path,
+                    t.dev_path,
+                    t.fs_type as "fs_type: FsType"
+                FROM filtered_targets($4, $5, $6, $7, $8, $9) t
                 ORDER BY
                     CASE WHEN $3 = 'ASC' THEN t.name END ASC,
                     CASE WHEN $3 = 'DESC' THEN t.name END DESC
                 OFFSET $1 LIMIT $2"#,
-            offset.unwrap_or(0) as i64,
-            limit.map(|x| x as i64),
-            dir.deref()
+            offset as i64,
+            limit as i64,
+            dir.deref(),
+            dev_path.as_deref(),
+            fqdn.as_deref(),
+            serial.as_deref(),
+            fs_name.as_deref(),
+            exclude_unmounted.unwrap_or(false),
+            tagged_hosts.as_deref(),
         )
         .fetch_all(&context.pg_pool)
-        .await?
-        .into_iter()
-        .filter(|x| match &fs_name {
-            Some(fs) => x.filesystems.contains(&fs),
-            None => true,
-        })
-        .filter(|x| match exclude_unmounted {
-            Some(true) => x.state != "unmounted",
-            Some(false) | None => true,
+        .await?;
+
+        let filtered = dev_path.is_some()
+            || fqdn.is_some()
+            || serial.is_some()
+            || fs_name.is_some()
+            || exclude_unmounted.unwrap_or(false)
+            || tagged_hosts.is_some();
+
+        let count = row_count::count(&context.pg_pool, "target", filtered, |cap| async move {
+            sqlx::query!(
+                r#"
+                    SELECT COUNT(*) AS "count!" FROM (
+                        SELECT 1
+                        FROM filtered_targets($1, $2, $3, $4, $5, $6)
+                        LIMIT $7
+                    ) x
+                "#,
+                dev_path.as_deref(),
+                fqdn.as_deref(),
+                serial.as_deref(),
+                fs_name.as_deref(),
+                exclude_unmounted.unwrap_or(false),
+                tagged_hosts.as_deref(),
+                cap,
+            )
+            .fetch_one(&context.pg_pool)
+            .await
+            .map(|x| x.count)
         })
-        .collect();
+        .await?;
 
         let target_resources = get_fs_target_resources(&context.pg_pool, None).await?;
 
@@ -255,6 +465,559 @@ impl QueryRoot {
             })
             .collect();
 
+        let degraded_multipath = sqlx::query!(
+            r#"
+                SELECT DISTINCT target_uuid
+                FROM target_multipath
+                WHERE target_uuid = ANY($1) AND cardinality(failed_paths) > 0
+            "#,
+            &xs.iter().map(|x| x.uuid.clone()).collect::<Vec<_>>(),
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| x.target_uuid)
+        .collect();
+
+        Ok(TargetList {
+            data: xs,
+            meta: PageMeta {
+                limit,
+                offset,
+                total_count: count.total as i32,
+                is_estimate: count.is_estimate,
+            },
+            degraded_multipath,
+        })
+    }
+
+    #[graphql(arguments(
+        uuid(description = "The uuid of the target"),
+        limit(description = "The most path events to return, defaults to 50"),
+    ))]
+    /// Multipath health of a target: the paths each of its hosts has to its multipath device,
+    /// and the most recent path failures and recoveries.
+    /// Paths are checked every minute.
+    async fn target_multipath(
+        context: &Context,
+        uuid: String,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<TargetMultipathStatus> {
+        let hosts = sqlx::query!(
+            r#"
+                SELECT m.host_id, h.fqdn, m.map_name, m.path_count, m.failed_paths, m.checked_at
+                FROM target_multipath m
+                INNER JOIN chroma_core_managedhost h ON h.id = m.host_id
+                WHERE m.target_uuid = $1
+                ORDER BY h.fqdn
+            "#,
+            uuid
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| TargetMultipath {
+            host_id: x.host_id,
+            fqdn: x.fqdn,
+            map_name: x.map_name,
+            path_count: x.path_count,
+            failed_paths: x.failed_paths,
+            checked_at: x.checked_at,
+        })
+        .collect();
+
+        let events = sqlx::query!(
+            r#"
+                SELECT e.host_id, h.fqdn, e.path, e.failed, e.at
+                FROM target_multipath_event e
+                INNER JOIN chroma_core_managedhost h ON h.id = e.host_id
+                WHERE e.target_uuid = $1
+                ORDER BY e.at DESC, e.id DESC
+                LIMIT $2
+            "#,
+            uuid,
+            limit.unwrap_or(50).max(0) as i64
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| MultipathEvent {
+            host_id: x.host_id,
+            fqdn: x.fqdn,
+            path: x.path,
+            failed: x.failed,
+            at: x.at,
+        })
+        .collect();
+
+        Ok(TargetMultipathStatus { hosts, events })
+    }
+
+    /// Inconsistencies in the targets: uuids shared by several targets, device paths that
+    /// changed while a target stayed on the same host, and targets registered on removed hosts.
+    /// Device paths are compared every 10 minutes, a change is reported until acknowledged.
+    async fn integrity_report(context: &Context) -> juniper::FieldResult<IntegrityReport> {
+        let x = integrity::report(&context.pg_pool).await?;
+
+        Ok(x)
+    }
+
+    #[graphql(arguments(fs_name(description = "The filesystem to describe")))]
+    /// The recovery state of each mounted MDT and OST of `fs_name`, e.g. during a failover.
+    /// Read from the server each target is mounted on every 10 seconds.
+    async fn recovery_status(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<FilesystemRecoveryStatus> {
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        let targets: Vec<_> = sqlx::query!(
+            r#"
+                SELECT
+                    t.name,
+                    h.fqdn,
+                    r.status,
+                    r.time_remaining,
+                    r.connected_clients,
+                    r.total_clients,
+                    r.completed_clients,
+                    r.evicted_clients,
+                    r.updated_at
+                FROM target_recovery r
+                INNER JOIN target t ON t.uuid = r.target_uuid
+                INNER JOIN chroma_core_managedhost h ON h.id = r.host_id
+                WHERE $1 = ANY(t.filesystems)
+                ORDER BY t.name
+            "#,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| TargetRecoveryStatus {
+            target_name: x.name,
+            fqdn: x.fqdn,
+            status: x.status,
+            time_remaining: x.time_remaining,
+            connected_clients: x.connected_clients,
+            total_clients: x.total_clients,
+            completed_clients: x.completed_clients,
+            evicted_clients: x.evicted_clients,
+            updated_at: x.updated_at,
+        })
+        .collect();
+
+        let recovering: Vec<_> = targets
+            .iter()
+            .filter(|x| x.status == "RECOVERING")
+            .collect();
+
+        Ok(FilesystemRecoveryStatus {
+            recovering: !recovering.is_empty(),
+            time_remaining: recovering.iter().filter_map(|x| x.time_remaining).max(),
+            fs_name,
+            targets,
+        })
+    }
+
+    #[graphql(arguments(
+        uuid(description = "The uuid of the target"),
+        keys(description = "Parameter names relative to the target, e.g. `recovery_status`"),
+    ))]
+    /// Read runtime parameters of a target from the host it is currently mounted on.
+    /// Values are cached for a few seconds, so polling this is cheap.
+    /// A key that cannot be read on the target resolves to a `null` value.
+    async fn target_params(
+        context: &Context,
+        uuid: String,
+        keys: Vec<String>,
+    ) -> juniper::FieldResult<Vec<TargetParam>> {
+        if let Some(k) = keys.iter().find(|k| !valid_param_key(k)) {
+            return Err(FieldError::new(
+                format!("Invalid parameter name {}", k),
+                Value::null(),
+            ));
+        }
+
+        let target = sqlx::query!(
+            r#"
+                SELECT t.name, h.fqdn
+                FROM target t
+                INNER JOIN chroma_core_managedhost h
+                ON h.id = t.active_host_id AND h.not_deleted = 't'
+                WHERE t.uuid = $1
+            "#,
+            uuid
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("Target {} not found or not mounted", uuid),
+                Value::null(),
+            )
+        })?;
+
+        let prefix = param_prefix(&target.name);
+
+        let mut xs = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let cached = sqlx::query!(
+                r#"
+                    SELECT value FROM target_param_cache
+                    WHERE target_uuid = $1 AND key = $2
+                    AND fetched_at > now() - make_interval(secs => $3)
+                "#,
+                uuid,
+                key,
+                TARGET_PARAMS_TTL.as_secs_f64()
+            )
+            .fetch_optional(&context.pg_pool)
+            .await?
+            .map(|x| x.value);
+
+            let value = match cached {
+                Some(v) => v,
+                None => {
+                    let param = format!("{}.{}.{}", prefix, target.name, key);
+
+                    let v = context
+                        .action_client
+                        .invoke_rust_agent_expect_result(
+                            target.fqdn.clone(),
+                            "lctl",
+                            vec!["get_param", "-n", &param],
+                            None,
+                        )
+                        .await
+                        .map_err(ImlApiError::from)?;
+
+                    let v = match v.and_then(|x| {
+                        serde_json::from_value::<String>(x).map_err(|e| e.to_string())
+                    }) {
+                        Ok(x) => Some(x.trim_end().to_string()),
+                        Err(e) => {
+                            tracing::debug!("Could not read {} on {}: {}", param, target.fqdn, e);
+
+                            None
+                        }
+                    };
+
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO target_param_cache (target_uuid, key, value, fetched_at)
+                            VALUES ($1, $2, $3, now())
+                            ON CONFLICT (target_uuid, key)
+                            DO UPDATE SET value = EXCLUDED.value, fetched_at = EXCLUDED.fetched_at
+                        "#,
+                        uuid,
+                        key,
+                        v
+                    )
+                    .execute(&context.pg_pool)
+                    .await?;
+
+                    v
+                }
+            };
+
+            xs.push(TargetParam { key, value });
+        }
+
+        Ok(xs)
+    }
+
+    #[graphql(arguments(
+        uuid(description = "The uuid of the target"),
+        from(description = "Only return transitions at or after this time"),
+        to(description = "Only return transitions before this time"),
+    ))]
+    /// Mounts, unmounts, failovers and recoveries of a target, newest first.
+    async fn target_state_history(
+        context: &Context,
+        uuid: String,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> juniper::FieldResult<Vec<TargetStateChange>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT
+                    t.id,
+                    t.target_name,
+                    t.event,
+                    t.state,
+                    t.reason,
+                    t.changed_at,
+                    h.fqdn AS "active_host?",
+                    p.fqdn AS "previous_host?"
+                FROM target_state_history t
+                LEFT JOIN chroma_core_managedhost h ON h.id = t.active_host_id
+                LEFT JOIN chroma_core_managedhost p ON p.id = t.previous_host_id
+                WHERE t.target_uuid = $1
+                  AND ($2::TIMESTAMPTZ IS NULL OR t.changed_at >= $2)
+                  AND ($3::TIMESTAMPTZ IS NULL OR t.changed_at < $3)
+                ORDER BY t.changed_at DESC, t.id DESC
+            "#,
+            uuid,
+            from,
+            to
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| {
+            Ok(TargetStateChange {
+                id: x.id,
+                target_name: x.target_name,
+                event: x.event.parse()?,
+                state: x.state,
+                active_host: x.active_host,
+                previous_host: x.previous_host,
+                reason: x.reason,
+                changed_at: x.changed_at,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(invalid)?;
+
+        Ok(xs)
+    }
+
+    #[graphql(arguments(
+        cluster_id(description = "Only return events of this corosync cluster"),
+        from(description = "Only return events at or after this time"),
+        to(description = "Only return events before this time"),
+    ))]
+    /// Bans, unbans, failovers and failbacks of corosync resources, newest first.
+    /// Events are kept after their cluster is removed.
+    async fn ha_events(
+        context: &Context,
+        cluster_id: Option<i32>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> juniper::FieldResult<Vec<HaEvent>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT id, cluster_id, resource, event, node, previous_node, reason, happened_at
+                FROM ha_event
+                WHERE ($1::INT IS NULL OR cluster_id = $1)
+                  AND ($2::TIMESTAMPTZ IS NULL OR happened_at >= $2)
+                  AND ($3::TIMESTAMPTZ IS NULL OR happened_at < $3)
+                ORDER BY happened_at DESC, id DESC
+            "#,
+            cluster_id,
+            from,
+            to
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| {
+            Ok(HaEvent {
+                id: x.id,
+                cluster_id: x.cluster_id,
+                resource: x.resource,
+                event: x.event.parse()?,
+                node: x.node,
+                previous_node: x.previous_node,
+                reason: x.reason,
+                happened_at: x.happened_at,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(invalid)?;
+
+        Ok(xs)
+    }
+
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to fetch check runs for"),
+        limit(description = "The maximum number of runs to return, defaults to 20"),
+    ))]
+    /// Runs of the scheduled filesystem health checks, newest first.
+    async fn filesystem_check_history(
+        context: &Context,
+        fs_name: String,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<FilesystemCheckRun>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT id, fs_name, ran_at, passed, checks
+                FROM filesystem_check
+                WHERE fs_name = $1
+                ORDER BY ran_at DESC, id DESC
+                LIMIT $2
+            "#,
+            fs_name,
+            i64::from(limit.unwrap_or(20))
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| {
+            Ok(FilesystemCheckRun {
+                id: x.id,
+                fs_name: x.fs_name,
+                ran_at: x.ran_at,
+                passed: x.passed,
+                checks: serde_json::from_value(x.checks)?,
+            })
+        })
+        .collect::<Result<Vec<_>, serde_json::Error>>()?;
+
+        Ok(xs)
+    }
+
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to fetch the remount policy and attempts for"),
+        limit(description = "The most attempts to return, defaults to 20"),
+    ))]
+    /// Whether targets of a filesystem that go offline on their own are remounted,
+    /// and the latest attempts at doing so, newest first.
+    async fn target_remount(
+        context: &Context,
+        fs_name: String,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<TargetRemountStatus> {
+        let policy = target_remount::get_policy(&context.pg_pool, &fs_name).await?;
+
+        let attempts = target_remount::get_attempts(
+            &context.pg_pool,
+            &fs_name,
+            i64::from(limit.unwrap_or(20).max(0)),
+        )
+        .await?;
+
+        Ok(TargetRemountStatus { policy, attempts })
+    }
+
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to forecast"),
+        horizon_days(description = "How many days ahead to look, defaults to 90"),
+    ))]
+    /// When a filesystem is projected to fill up, from a linear fit of its daily OST usage over the last 30 days.
+    async fn capacity_forecast(
+        context: &Context,
+        fs_name: String,
+        horizon_days: Option<i32>,
+    ) -> juniper::FieldResult<CapacityForecast> {
+        let horizon_days = horizon_days.unwrap_or(DEFAULT_FORECAST_HORIZON_DAYS);
+
+        if !(1..=MAX_FORECAST_HORIZON_DAYS).contains(&horizon_days) {
+            return Err(FieldError::new(
+                format!(
+                    "horizon_days must be between 1 and {}",
+                    MAX_FORECAST_HORIZON_DAYS
+                ),
+                Value::null(),
+            ));
+        }
+
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        capacity::forecast(&fs_name, horizon_days as u32)
+            .await?
+            .ok_or_else(|| {
+                FieldError::new(
+                    format!("No capacity stats found for {}", fs_name),
+                    Value::null(),
+                )
+            })
+    }
+
+    #[graphql(arguments(target_uuids(description = "The targets to fetch stats for")))]
+    /// Read and write bandwidth of each target over the last 10 minutes, in 30 second buckets.
+    /// All targets are fetched together, so tables can show a sparkline per row with a single request.
+    async fn mini_stats(
+        context: &Context,
+        target_uuids: Vec<String>,
+    ) -> juniper::FieldResult<Vec<TargetMiniStats>> {
+        stats::get_mini_stats(context, target_uuids).await
+    }
+
+    /// Capacity, inode usage, client count, target states and active alert count of every filesystem.
+    /// Computed together, so the dashboard needs a single request however many filesystems there are.
+    async fn dashboard_overview(
+        context: &Context,
+    ) -> juniper::FieldResult<Vec<FilesystemOverview>> {
+        dashboard::get_dashboard_overview(context).await
+    }
+
+    /// The filesystems, health and active alerts of this manager and of every federation peer.
+    /// Peers are queried at once, and one that can't be reached is listed with its `error`.
+    async fn federated_overview(context: &Context) -> juniper::FieldResult<FederatedOverview> {
+        federation::federated_overview(context).await
+    }
+
+    #[graphql(arguments(fs_name(description = "The filesystem to check")))]
+    /// The Lustre, Lustre client and agent versions installed on each server and client of `fs_name`,
+    /// checked against the combinations this IML release supports.
+    /// Run this before an upgrade, unsupported mixes are listed in `issues`.
+    async fn compatibility_report(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<CompatibilityReport> {
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        compatibility::get_compatibility_report(context, fs_name).await
+    }
+
+    #[graphql(arguments(fs_name(description = "The filesystem to describe")))]
+    /// The MDTs of `fs_name` with their inode usage, and the default striping of new directories
+    /// over them. The striping is read from a client that has the filesystem mounted.
+    async fn filesystem_dne(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<FilesystemDne> {
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        dne::get_filesystem_dne(context, fs_name).await
+    }
+
+    #[graphql(arguments(fs_name(description = "The filesystem to describe")))]
+    /// The default layout of new files in `fs_name`, a plain stripe count and size
+    /// or the components of a PFL. Read from a client that has the filesystem mounted.
+    async fn default_stripe(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<DefaultStripe> {
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        stripe::get_default_stripe(context, fs_name).await
+    }
+
+    #[graphql(arguments(command_id(
+        description = "The command returned by `filesystem.start` or `filesystem.stop`"
+    )))]
+    /// The targets a filesystem start or stop acts on, in order, with the state of their jobs.
+    async fn target_transitions(
+        context: &Context,
+        command_id: i32,
+    ) -> juniper::FieldResult<Vec<TargetTransition>> {
+        let xs = filesystem::get_target_transitions(&context.pg_pool, command_id).await?;
+
+        Ok(xs)
+    }
+
+    /// An aggregate of everything that currently needs attention:
+    /// degraded filesystems, unreachable hosts, recently failed commands
+    /// and problems with the services the manager relies on.
+    async fn system_health(context: &Context) -> juniper::FieldResult<SystemHealth> {
+        get_system_health(context).await
+    }
+
+    /// The health of the manager's own services: postgres and its replication,
+    /// rabbitmq, influxdb, the device aggregator and the action runner.
+    async fn manager_status(context: &Context) -> juniper::FieldResult<ManagerStatus> {
+        manager::get_manager_status(context).await
+    }
+
+    /// The schema migrations of the manager database, oldest first.
+    /// `iml-api` applies the migrations it embeds as it starts.
+    async fn schema_migrations(context: &Context) -> juniper::FieldResult<Vec<SchemaMigration>> {
+        let xs = migrate::status(&context.pg_pool).await?;
+
         Ok(xs)
     }
 
@@ -290,7 +1053,7 @@ impl QueryRoot {
         fs_name: String,
     ) -> juniper::FieldResult<Vec<Vec<String>>> {
         let _ = fs_id_by_name(&context.pg_pool, &fs_name).await?;
-        let xs = get_fs_cluster_hosts(&context.pg_pool, fs_name).await?;
+        let xs = get_fs_cluster_hosts(context, fs_name).await?;
 
         Ok(xs)
     }
@@ -318,7 +1081,7 @@ impl QueryRoot {
         let snapshots = sqlx::query_as!(
             Snapshot,
                 r#"
-                    SELECT filesystem_name, snapshot_name, create_time, modify_time, snapshot_fsname, mounted, comment FROM snapshot s
+                    SELECT filesystem_name, snapshot_name, create_time, modify_time, snapshot_fsname, mounted, comment, held, policy_run_id FROM snapshot s
                     WHERE filesystem_name = $4 AND ($5::text IS NULL OR snapshot_name = $5)
                     ORDER BY
                         CASE WHEN $3 = 'ASC' THEN s.create_time END ASC,
@@ -333,16 +1096,175 @@ impl QueryRoot {
             .fetch_all(&context.pg_pool)
             .await?;
 
-        Ok(snapshots)
+        Ok(snapshots)
+    }
+    #[graphql(arguments(
+        fsname(description = "Filesystem the snapshots were taken from"),
+        name(description = "Only list the snapshots with this name"),
+        limit(description = "How many entries to return, defaults to `GRAPHQL_MAX_PAGE_SIZE`"),
+    ))]
+    /// The snapshots of a filesystem destroyed in the last 90 days, latest first.
+    /// Their data is gone, but who destroyed them and when is kept.
+    async fn snapshot_history(
+        context: &Context,
+        fsname: String,
+        name: Option<String>,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<SnapshotHistoryEntry>> {
+        let _ = fs_id_by_name(&context.pg_pool, &fsname).await?;
+
+        let xs = sqlx::query_as!(
+            SnapshotHistoryEntry,
+            r#"
+                SELECT id, filesystem_name, snapshot_name, snapshot_fsname, create_time, comment,
+                    taken_by_policy, destroyed_at, destroyed_by, command_id, reason
+                FROM snapshot_history
+                WHERE filesystem_name = $1 AND ($2::TEXT IS NULL OR snapshot_name = $2)
+                ORDER BY destroyed_at DESC, id DESC
+                LIMIT $3
+            "#,
+            fsname,
+            name,
+            page_limit(limit, get_graphql_max_page_size()) as i64
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(
+        fsname(description = "Filesystem to snapshot"),
+        name(description = "Name of the snapshot"),
+        use_barrier(
+            description = "Whether the write barrier will be set. The default value is `false`"
+        )
+    ))]
+    /// Runs the checks `createSnapshot` runs before submitting its jobs: write barrier support,
+    /// free MDT space, name collisions and other snapshot operations on the filesystem.
+    async fn snapshot_preflight(
+        context: &Context,
+        fsname: String,
+        name: String,
+        use_barrier: Option<bool>,
+    ) -> juniper::FieldResult<SnapshotPreflight> {
+        let _ = fs_id_by_name(&context.pg_pool, &fsname).await?;
+        let name = name.trim();
+        validate_snapshot_name(name)?;
+
+        let x =
+            snapshot_preflight::run(context, &fsname, name, use_barrier.unwrap_or(false)).await?;
+
+        Ok(x)
+    }
+    #[graphql(arguments(
+        fsname(description = "Filesystem the snapshots were taken from"),
+        a(description = "Name of the first snapshot"),
+        b(description = "Name of the second snapshot"),
+    ))]
+    /// Compare two snapshots of the same filesystem.
+    /// The snapshots are returned oldest first, along with the automatic
+    /// interval runs that were started between them.
+    async fn snapshot_compare(
+        context: &Context,
+        fsname: String,
+        a: String,
+        b: String,
+    ) -> juniper::FieldResult<SnapshotComparison> {
+        let _ = fs_id_by_name(&context.pg_pool, &fsname).await?;
+
+        let mut xs = sqlx::query_as!(
+            Snapshot,
+            r#"
+                SELECT filesystem_name, snapshot_name, create_time, modify_time, snapshot_fsname, mounted, comment, held, policy_run_id FROM snapshot
+                WHERE filesystem_name = $1 AND snapshot_name = ANY($2)
+                ORDER BY create_time
+            "#,
+            fsname,
+            &[a.clone(), b.clone()][..],
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        for name in &[&a, &b] {
+            if !xs.iter().any(|x| &&x.snapshot_name == name) {
+                return Err(FieldError::new(
+                    format!("Snapshot {} of {} not found", name, fsname),
+                    Value::null(),
+                ));
+            }
+        }
+
+        let (from, to) = match (xs.pop(), xs.pop()) {
+            (Some(to), Some(from)) => (from, to),
+            _ => {
+                return Err(FieldError::new(
+                    "Two different snapshots are needed to compare",
+                    Value::null(),
+                ))
+            }
+        };
+
+        let runs = sqlx::query!(
+            r#"
+                SELECT csj.name, j.created_at,
+                    CASE
+                        WHEN j.errored THEN 'failed'
+                        WHEN j.cancelled THEN 'cancelled'
+                        ELSE j.state
+                    END AS "state!"
+                FROM chroma_core_createsnapshotjob csj
+                INNER JOIN chroma_core_job j ON j.id = csj.job_ptr_id
+                WHERE csj.fsname = $1
+                AND j.created_at > $2 AND j.created_at < $3
+                AND csj.name <> ALL($4)
+                ORDER BY j.created_at
+            "#,
+            fsname,
+            from.create_time,
+            to.create_time,
+            &[from.snapshot_name.clone(), to.snapshot_name.clone()][..],
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let policy_runs = runs
+            .into_iter()
+            .filter_map(|x| {
+                let interval = parse_snapshot_name(&x.name)?;
+
+                if interval.fs_name != fsname {
+                    return None;
+                }
+
+                Some(SnapshotPolicyRun {
+                    interval_id: interval.id,
+                    snapshot_name: x.name,
+                    started_at: x.created_at,
+                    state: x.state,
+                })
+            })
+            .collect();
+
+        Ok(SnapshotComparison {
+            from,
+            to,
+            policy_runs,
+        })
     }
 
     /// Fetch the list of commands
     #[graphql(arguments(
-        limit(description = "optional paging limit, defaults to all rows"),
+        limit(
+            description = "optional paging limit, defaults to and is capped at the maximum page size"
+        ),
         offset(description = "Offset into items, defaults to 0"),
         dir(description = "Sort direction, defaults to ASC"),
         is_active(description = "Command status, active means not completed, default is true"),
         msg(description = "Substring of the command's message, null or empty matches all"),
+        tag(description = "Only return commands with this tag"),
+        filter(
+            description = "Date range, outcome, user, server and filesystem conditions, combined with `and` and `or`"
+        ),
     ))]
     async fn commands(
         context: &Context,
@@ -351,9 +1273,24 @@ impl QueryRoot {
         dir: Option<SortDir>,
         is_active: Option<bool>,
         msg: Option<String>,
-    ) -> juniper::FieldResult<Vec<Command>> {
+        tag: Option<String>,
+        filter: Option<CommandFilter>,
+    ) -> juniper::FieldResult<CommandList> {
         let dir = dir.unwrap_or_default();
+        let limit = page_limit(limit, get_graphql_max_page_size());
+        let offset = offset.unwrap_or(0).max(0);
+        let tag = tag.map(|x| normalize_tag(&x));
         let is_completed = !is_active.unwrap_or(true);
+
+        let ids = match &filter {
+            Some(f) => {
+                command_filter::validate(f).map_err(invalid)?;
+
+                Some(command_filter::matching_ids(&context.pg_pool, f).await?)
+            }
+            None => None,
+        };
+
         let commands: Vec<Command> = sqlx::query_as!(
             CommandTmpRecord,
             r#"
@@ -369,24 +1306,74 @@ impl QueryRoot {
                 JOIN chroma_core_command_jobs cj ON c.id = cj.command_id
                 WHERE ($4::BOOL IS NULL OR complete = $4)
                   AND ($5::TEXT IS NULL OR c.message ILIKE '%' || $5 || '%')
+                  AND ($6::TEXT IS NULL OR EXISTS (
+                    SELECT 1 FROM command_tag t WHERE t.command_id = c.id AND t.tag = $6
+                  ))
+                  AND ($7::INT[] IS NULL OR c.id = ANY($7))
                 GROUP BY c.id
                 ORDER BY
                     CASE WHEN $3 = 'ASC' THEN c.id END ASC,
                     CASE WHEN $3 = 'DESC' THEN c.id END DESC
                 OFFSET $1 LIMIT $2
             "#,
-            offset.unwrap_or(0) as i64,
-            limit.map(|x| x as i64),
+            offset as i64,
+            limit as i64,
             dir.deref(),
             is_completed,
-            msg,
+            msg.as_deref(),
+            tag.as_deref(),
+            ids.as_deref(),
         )
         .fetch_all(&context.pg_pool)
         .map_ok(|xs: Vec<CommandTmpRecord>| {
             xs.into_iter().map(to_command).collect::<Vec<Command>>()
         })
         .await?;
-        Ok(commands)
+
+        let count = row_count::count(
+            &context.pg_pool,
+            "chroma_core_command",
+            true,
+            |cap| async move {
+                sqlx::query!(
+                    r#"
+                        SELECT COUNT(*) AS "count!" FROM (
+                            SELECT 1
+                            FROM chroma_core_command c
+                            WHERE complete = $1
+                              AND ($2::TEXT IS NULL OR c.message ILIKE '%' || $2 || '%')
+                              AND ($3::TEXT IS NULL OR EXISTS (
+                                SELECT 1 FROM command_tag t WHERE t.command_id = c.id AND t.tag = $3
+                              ))
+                              AND ($4::INT[] IS NULL OR c.id = ANY($4))
+                              AND EXISTS (
+                                SELECT 1 FROM chroma_core_command_jobs cj WHERE cj.command_id = c.id
+                              )
+                            LIMIT $5
+                        ) x
+                    "#,
+                    is_completed,
+                    msg.as_deref(),
+                    tag.as_deref(),
+                    ids.as_deref(),
+                    cap,
+                )
+                .fetch_one(&context.pg_pool)
+                .await
+                .map(|x| x.count)
+            },
+        )
+        .await?;
+
+        Ok(CommandList {
+            data: commands,
+            meta: PageMeta {
+                limit,
+                offset,
+                total_count: count.total as i32,
+                is_estimate: count.is_estimate,
+            },
+        })
     }
 
     /// Fetch the list of commands by ids, the returned
@@ -442,20 +1429,137 @@ impl QueryRoot {
         Ok(commands)
     }
 
+    #[graphql(arguments(id(description = "The id of the command")))]
+    /// The notes and tags operators have attached to a command
+    async fn command_annotations(
+        context: &Context,
+        id: i32,
+    ) -> juniper::FieldResult<CommandAnnotations> {
+        let xs = get_command_annotations(&context.pg_pool, id).await?;
+
+        Ok(xs)
+    }
+
     /// List all snapshot intervals
     async fn snapshot_intervals(context: &Context) -> juniper::FieldResult<Vec<SnapshotInterval>> {
-        let xs: Vec<SnapshotInterval> = sqlx::query!("SELECT * FROM snapshot_interval")
-            .fetch(&context.pg_pool)
-            .map_ok(|x| SnapshotInterval {
-                id: x.id,
-                filesystem_name: x.filesystem_name,
-                use_barrier: x.use_barrier,
-                interval: x.interval.into(),
-                last_run: x.last_run,
-            })
-            .try_collect()
+        let xs: Vec<SnapshotInterval> = sqlx::query!(
+            r#"
+                SELECT id, filesystem_name, use_barrier, interval, last_run, automount,
+                    to_char(start_at, 'HH24:MI') AS start_time,
+                    time_zone,
+                    snapshot_interval_next_run(start_at, time_zone, interval, now()) AS next_run
+                FROM snapshot_interval
+            "#
+        )
+        .fetch(&context.pg_pool)
+        .map_ok(|x| SnapshotInterval {
+            id: x.id,
+            filesystem_name: x.filesystem_name,
+            use_barrier: x.use_barrier,
+            interval: x.interval.into(),
+            last_run: x.last_run,
+            automount: x.automount,
+            start_time: x.start_time,
+            time_zone: x.time_zone,
+            next_run: x.next_run,
+        })
+        .try_collect()
+        .await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(
+        interval(description = "How often a snapshot is taken"),
+        start_time(description = "The time of day snapshots are taken at, as HH:MM in `timeZone`"),
+        time_zone(description = "The IANA time zone of `startTime`. The default value is `UTC`"),
+        limit(description = "How many runs to list. The default value is 5, at most 100"),
+    ))]
+    /// When an interval created now with a start time would take its next snapshots,
+    /// so the schedule can be checked in local time before creating it
+    async fn snapshot_schedule(
+        context: &Context,
+        interval: GraphQLDuration,
+        start_time: String,
+        time_zone: Option<String>,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<SnapshotSchedule> {
+        let start = snapshot_policy::parse_start_time(&start_time).map_err(invalid)?;
+        let time_zone = time_zone.unwrap_or_else(default_time_zone);
+
+        if !snapshot_policy::is_time_zone(&context.pg_pool, &time_zone).await? {
+            return Err(FieldError::new(
+                format!("Unknown time zone {}", time_zone),
+                Value::null(),
+            ));
+        }
+
+        if interval.0.as_secs() == 0 {
+            return Err(FieldError::new(
+                "The interval has to be at least a second",
+                Value::null(),
+            ));
+        }
+
+        let runs = sqlx::query!(
+            r#"
+                SELECT (snapshot_interval_start_at($1, $2, $3) + n * $3) AT TIME ZONE $2 AS "run_at!"
+                FROM generate_series(0, $4::INT - 1) AS n
+                ORDER BY n
+            "#,
+            start,
+            time_zone,
+            PgInterval::try_from(interval.0)?,
+            limit.unwrap_or(5).max(1).min(100)
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| x.run_at)
+        .collect();
+
+        Ok(SnapshotSchedule { time_zone, runs })
+    }
+    /// The settings shared by every snapshot policy
+    async fn snapshot_policy_settings(
+        context: &Context,
+    ) -> juniper::FieldResult<SnapshotPolicySettings> {
+        let x = sqlx::query!("SELECT max_concurrent, jitter FROM snapshot_policy_settings")
+            .fetch_one(&context.pg_pool)
             .await?;
 
+        Ok(SnapshotPolicySettings {
+            max_concurrent: x.max_concurrent,
+            jitter: x.jitter.into(),
+        })
+    }
+    #[graphql(arguments(
+        fsname(description = "Only list the runs of this filesystem"),
+        limit(description = "How many runs to return, defaults to `GRAPHQL_MAX_PAGE_SIZE`"),
+    ))]
+    /// The times snapshot intervals were due, newest first,
+    /// with the jitter picked for each run and whether it was held back by `maxConcurrent`.
+    /// The snapshots the thresholds of retention policies took or deleted are listed too.
+    async fn snapshot_interval_runs(
+        context: &Context,
+        fsname: Option<String>,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<SnapshotIntervalRun>> {
+        let xs = sqlx::query_as!(
+            SnapshotIntervalRun,
+            r#"
+                SELECT id, interval_id, filesystem_name, snapshot_name, due_at, start_after,
+                    started_at, command_id, state, throttled, error, reason, action
+                FROM snapshot_policy_run
+                WHERE $1::TEXT IS NULL OR filesystem_name = $1
+                ORDER BY due_at DESC, id DESC
+                LIMIT $2
+            "#,
+            fsname,
+            page_limit(limit, get_graphql_max_page_size()) as i64
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
         Ok(xs)
     }
     /// List all snapshot retention policies. Snapshots will automatically be deleted (starting with the oldest)
@@ -463,26 +1567,180 @@ impl QueryRoot {
     async fn snapshot_retention_policies(
         context: &Context,
     ) -> juniper::FieldResult<Vec<SnapshotRetention>> {
-        let xs: Vec<SnapshotRetention> = sqlx::query_as!(
-            SnapshotRetention,
+        let xs = get_snapshot_retentions(&context.pg_pool, None).await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(fsnames(
+        description = "Only export the policies of these filesystems, defaults to every filesystem"
+    )))]
+    /// Export the snapshot intervals and retention policies of filesystems as YAML,
+    /// along with the settings shared by every policy. `importSnapshotPolicies` reads it back.
+    async fn export_snapshot_policies(
+        context: &Context,
+        fsnames: Option<Vec<String>>,
+    ) -> juniper::FieldResult<String> {
+        let x = snapshot_policies::export(&context.pg_pool, fsnames.as_deref()).await?;
+
+        Ok(serde_yaml::to_string(&x)?)
+    }
+    /// List the configured job timeouts. Jobs of a class without a timeout may run indefinitely.
+    async fn job_timeouts(context: &Context) -> juniper::FieldResult<Vec<JobTimeout>> {
+        let xs: Vec<JobTimeout> =
+            sqlx::query!("SELECT class_name, timeout FROM job_timeout ORDER BY class_name")
+                .fetch(&context.pg_pool)
+                .map_ok(|x| JobTimeout {
+                    class_name: x.class_name,
+                    timeout: x.timeout.into(),
+                })
+                .try_collect()
+                .await?;
+
+        Ok(xs)
+    }
+
+    #[graphql(arguments(host_id(description = "The id of the host")))]
+    /// List the jobs waiting in the queue of a host, in the order they will run.
+    /// Jobs are queued on the host they act on, and are resumed when the job scheduler restarts.
+    /// Higher priority jobs run ahead of queued jobs of a lower priority,
+    /// but do not interrupt a running job.
+    async fn host_queue(
+        context: &Context,
+        host_id: i32,
+    ) -> juniper::FieldResult<Vec<HostQueueEntry>> {
+        let xs = sqlx::query!(
             r#"
                 SELECT
-                    id,
-                    filesystem_name,
-                    reserve_value,
-                    reserve_unit as "reserve_unit:ReserveUnit",
-                    last_run,
-                    keep_num
-                FROM snapshot_retention
+                    q.id AS "id!",
+                    q.state AS "state!",
+                    q.priority AS "priority!",
+                    q.class_name AS "class_name!",
+                    q.created_at AS "created_at!",
+                    q.command_id AS "command_id!",
+                    q.message AS "message!"
+                FROM (
+                    SELECT DISTINCT ON (j.id)
+                        j.id,
+                        j.state,
+                        j.priority,
+                        ct.model AS class_name,
+                        j.created_at,
+                        c.id AS command_id,
+                        c.message
+                    FROM chroma_core_job j
+                    INNER JOIN django_content_type ct ON ct.id = j.content_type_id
+                    INNER JOIN chroma_core_command_jobs cj ON cj.job_id = j.id
+                    INNER JOIN chroma_core_command c ON c.id = cj.command_id
+                    WHERE j.queue_host_id = $1
+                    AND j.state <> 'complete'
+                    ORDER BY j.id, c.id
+                ) q
+                ORDER BY q.state = 'tasked' DESC, q.priority DESC, q.id
+            "#,
+            host_id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let xs = xs
+            .into_iter()
+            .enumerate()
+            .map(|(i, x)| HostQueueEntry {
+                position: i as i32,
+                job_id: x.id,
+                state: x.state,
+                priority: JobPriority::from(x.priority),
+                class_name: x.class_name,
+                command_id: x.command_id,
+                command_message: x.message,
+                created_at: x.created_at,
+            })
+            .collect();
+
+        Ok(xs)
+    }
+
+    /// What the queue of each host with unfinished jobs holds, by fqdn.
+    /// Shows why a job is waiting, e.g. behind a long running job.
+    async fn host_queues(context: &Context) -> juniper::FieldResult<Vec<HostQueueSummary>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT
+                    h.id,
+                    h.fqdn,
+                    (array_agg(ct.model) FILTER (WHERE j.state = 'tasked'))[1] AS running,
+                    COUNT(*) FILTER (WHERE j.state = 'pending' AND j.priority >= 2) AS "high!",
+                    COUNT(*) FILTER (WHERE j.state = 'pending' AND j.priority = 1) AS "normal!",
+                    COUNT(*) FILTER (WHERE j.state = 'pending' AND j.priority <= 0) AS "low!"
+                FROM chroma_core_job j
+                INNER JOIN chroma_core_managedhost h ON h.id = j.queue_host_id
+                INNER JOIN django_content_type ct ON ct.id = j.content_type_id
+                WHERE j.state <> 'complete'
+                GROUP BY h.id, h.fqdn
+                ORDER BY h.fqdn
             "#
         )
-        .fetch(&context.pg_pool)
-        .try_collect()
+        .fetch_all(&context.pg_pool)
         .await?;
 
+        let xs = xs
+            .into_iter()
+            .map(|x| HostQueueSummary {
+                host_id: x.id,
+                fqdn: x.fqdn,
+                running: x.running,
+                high: x.high as i32,
+                normal: x.normal as i32,
+                low: x.low as i32,
+            })
+            .collect();
+
         Ok(xs)
     }
 
+    #[graphql(arguments(
+        record_type(
+            description = "The lowercased model name of the record, e.g. `managedtarget` or `managedhost`"
+        ),
+        record_id(description = "The id of the record"),
+    ))]
+    /// List the locks unfinished jobs take on a record.
+    /// `holders` are running, or run as soon as the job scheduler gets to them. `waiters` wait for other jobs first.
+    async fn locks(
+        context: &Context,
+        record_type: String,
+        record_id: i32,
+    ) -> juniper::FieldResult<RecordLocks> {
+        locks::get_locks(context, record_type, record_id).await
+    }
+
+    #[graphql(arguments(command_id(description = "The id of the command")))]
+    /// List the jobs of other commands that the jobs of a command are waiting for,
+    /// with the records both of them lock.
+    async fn command_blockers(
+        context: &Context,
+        command_id: i32,
+    ) -> juniper::FieldResult<Vec<CommandBlocker>> {
+        locks::get_command_blockers(context, command_id).await
+    }
+
+    #[graphql(arguments(
+        command_id(description = "The id of the command"),
+        severity(description = "Upper bound of log severity, defaults to informational"),
+        limit(description = "The most messages returned per step, defaults to 200, at most 1000"),
+    ))]
+    /// List the steps of a command, each with the log messages of the hosts its job ran on
+    /// or locked while the step ran. Steps of jobs bound to no host get the messages of
+    /// every host of the command.
+    async fn command_related_logs(
+        context: &Context,
+        command_id: i32,
+        severity: Option<LogSeverity>,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<CommandStepLogs>> {
+        command_logs::get_command_related_logs(context, command_id, severity, limit).await
+    }
+
     #[graphql(arguments(
         limit(description = "optional paging limit, defaults to 100",),
         offset(description = "Offset into items, defaults to 0"),
@@ -496,10 +1754,14 @@ impl QueryRoot {
         tag(
             description = "Pattern to search for in tag. Uses Postgres pattern matching  (https://www.postgresql.org/docs/9.6/functions-matching.html)"
         ),
+        source(
+            description = "Only return messages from this source, e.g. `agent` for managed servers"
+        ),
         start_datetime(description = "Start of the time period of logs"),
         end_datetime(description = "End of the time period of logs"),
         message_class(description = "Array of log message classes"),
         severity(description = "Upper bound of log severity"),
+        fs_name(description = "Only return messages from the servers of this filesystem"),
     ))]
     /// Returns aggregated journal entries for all nodes the agent runs on.
     async fn logs(
@@ -510,10 +1772,12 @@ impl QueryRoot {
         message: Option<String>,
         fqdn: Option<String>,
         tag: Option<String>,
+        source: Option<String>,
         start_datetime: Option<chrono::DateTime<Utc>>,
         end_datetime: Option<chrono::DateTime<Utc>>,
         message_class: Option<Vec<MessageClass>>,
         severity: Option<LogSeverity>,
+        fs_name: Option<String>,
     ) -> juniper::FieldResult<LogResponse> {
         let dir = dir.unwrap_or_default();
 
@@ -537,6 +1801,12 @@ impl QueryRoot {
                       AND ($8::TIMESTAMPTZ IS NULL OR t.datetime < $8)
                       AND ARRAY[t.message_class] <@ $9
                       AND t.severity <= $10
+                      AND ($11::TEXT IS NULL OR t.source = $11)
+                      AND ($12::TEXT IS NULL OR t.fqdn IN (
+                        SELECT h.fqdn FROM chroma_core_managedhost h
+                        INNER JOIN target tg ON h.id = ANY(tg.host_ids)
+                        WHERE $12 = ANY(tg.filesystems) AND h.not_deleted = 't'
+                      ))
                     ORDER BY
                         CASE WHEN $3 = 'ASC' THEN t.datetime END ASC,
                         CASE WHEN $3 = 'DESC' THEN t.datetime END DESC
@@ -551,6 +1821,8 @@ impl QueryRoot {
             end_datetime,
             &message_class,
             severity,
+            source,
+            fs_name,
         )
         .fetch_all(&context.pg_pool)
         .await?;
@@ -559,61 +1831,152 @@ impl QueryRoot {
             .map(|x| x.try_into())
             .collect::<Result<_, _>>()?;
 
-        let total_count = sqlx::query!(
-            "SELECT total_rows FROM rowcount WHERE table_name = 'chroma_core_logmessage';"
+        // The count covers every message, not only the ones matching the filters
+        let count = row_count::count(
+            &context.pg_pool,
+            "chroma_core_logmessage",
+            false,
+            |_| async {
+                sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM chroma_core_logmessage"#)
+                    .fetch_one(&context.pg_pool)
+                    .await
+                    .map(|x| x.count)
+            },
         )
-        .fetch_one(&context.pg_pool)
-        .await?
-        .total_rows
-        .ok_or_else(|| FieldError::new("Number of rows doesn't fit in i32", Value::null()))?;
+        .await?;
 
         Ok(LogResponse {
             data: xs,
             meta: Meta {
-                total_count: total_count.try_into()?,
+                total_count: count.total.try_into()?,
+                is_estimate: count.is_estimate,
             },
         })
     }
 
-    async fn server_profiles(context: &Context) -> juniper::FieldResult<Vec<ServerProfile>> {
-        let server_profile_records = sqlx::query!(
+    #[graphql(arguments(
+        since_cursor(
+            description = "Cursor returned by a previous call. Omit to read from the start of the journal"
+        ),
+        record_types(description = "Only return changes to these record types. Defaults to all"),
+        limit(description = "Maximum number of changes to return. Defaults to 1000"),
+    ))]
+    /// Ordered feed of create, update and delete events for hosts, targets,
+    /// filesystems and alerts, for keeping an external system in sync.
+    ///
+    /// Changes are only returned once the transaction that made them, and every
+    /// transaction that started before it, has finished, so a cursor never skips
+    /// a change that commits later. Journal entries are pruned after a week;
+    /// resuming from a cursor that has been pruned is an error and the consumer must resync.
+    async fn changes(
+        context: &Context,
+        since_cursor: Option<String>,
+        record_types: Option<Vec<ChangeRecordType>>,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<ChangeFeed> {
+        let since = since_cursor.as_deref().map(parse_cursor).transpose()?;
+
+        let limit = limit
+            .unwrap_or(CHANGES_DEFAULT_LIMIT)
+            .max(1)
+            .min(CHANGES_MAX_LIMIT);
+
+        let record_types: Option<Vec<String>> =
+            record_types.map(|xs| xs.iter().map(|x| x.as_str().to_string()).collect());
+
+        // A cursor taken from an empty journal has nothing to look up
+        if let Some((_, id)) = since.filter(|(_, id)| *id > 0) {
+            let found = sqlx::query!(
+                r#"SELECT EXISTS(SELECT 1 FROM change_journal WHERE id = $1) AS "found!""#,
+                id
+            )
+            .fetch_one(&context.pg_pool)
+            .await?
+            .found;
+
+            if !found {
+                return Err(FieldError::new(
+                    format!(
+                        "Cursor {} has expired, the changes after it are no longer available",
+                        since_cursor.unwrap_or_default()
+                    ),
+                    Value::null(),
+                ));
+            }
+        }
+
+        let (since_txid, since_id) = since.unwrap_or((0, 0));
+
+        let mut xs = sqlx::query!(
             r#"
-                SELECT jsonb_agg((r.repo_name, r.location))
-                    AS repos, sp.*
-                    FROM chroma_core_repo AS r
-                    INNER JOIN chroma_core_serverprofile_repolist AS rl ON r.repo_name = rl.repo_id
-                    INNER JOIN chroma_core_serverprofile AS sp ON rl.serverprofile_id = sp.name
-                    GROUP BY sp.name;
+                SELECT id, txid, record_type, record_id, op, changed_at, record::TEXT AS "record!"
+                FROM change_journal
+                WHERE (txid, id) > ($1, $2)
+                  AND ($3::TEXT[] IS NULL OR record_type = ANY($3))
+                  AND txid < txid_snapshot_xmin(txid_current_snapshot())
+                ORDER BY txid, id
+                LIMIT $4
             "#,
+            since_txid,
+            since_id,
+            record_types.as_deref(),
+            limit as i64 + 1
         )
         .fetch_all(&context.pg_pool)
         .await?;
 
-        let server_profiles: Vec<_> = server_profile_records
+        let has_more = xs.len() > limit as usize;
+
+        xs.truncate(limit as usize);
+
+        let cursor = xs
+            .last()
+            .map(|x| format_cursor(x.txid, x.id))
+            .unwrap_or_else(|| format_cursor(since_txid, since_id));
+
+        let changes = xs
             .into_iter()
-            .filter_map(|spr| {
-                // TODO: Try to derive this somehow
-                let record = ServerProfileRecord {
-                    corosync: spr.corosync,
-                    corosync2: spr.corosync2,
-                    default: spr.default,
-                    initial_state: spr.initial_state,
-                    managed: spr.managed,
-                    name: spr.name,
-                    ntp: spr.ntp,
-                    pacemaker: spr.pacemaker,
-                    ui_description: spr.ui_description,
-                    ui_name: spr.ui_name,
-                    user_selectable: spr.user_selectable,
-                    worker: spr.worker,
-                };
-                let repos = spr.repos?;
-                ServerProfile::new(record, &repos).ok()
+            .map(|x| {
+                Ok(Change {
+                    cursor: format_cursor(x.txid, x.id),
+                    record_type: x.record_type.parse()?,
+                    record_id: x.record_id,
+                    op: x.op.parse()?,
+                    changed_at: x.changed_at,
+                    record: x.record,
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(invalid)?;
+
+        Ok(ChangeFeed {
+            changes,
+            cursor,
+            has_more,
+        })
+    }
+
+    async fn server_profiles(context: &Context) -> juniper::FieldResult<Vec<ServerProfile>> {
+        let server_profiles = get_server_profiles(&context.pg_pool, None).await?;
 
         Ok(server_profiles)
     }
+    #[graphql(arguments(
+        a(description = "Name of the first profile"),
+        b(description = "Name of the profile to compare it with"),
+    ))]
+    /// Compare the settings, packages and repositories of two server profiles,
+    /// e.g. a custom profile and the stock profile it was made from.
+    async fn compare_profiles(
+        context: &Context,
+        a: String,
+        b: String,
+    ) -> juniper::FieldResult<ServerProfileComparison> {
+        let a = get_profile_details(&context.pg_pool, &a).await?;
+        let b = get_profile_details(&context.pg_pool, &b).await?;
+
+        Ok(diff_profiles(&a, &b))
+    }
     /// List the client mount source.
     /// This will build up the source using known mgs locations
     /// for the given filesystem.
@@ -622,9 +1985,9 @@ impl QueryRoot {
         context: &Context,
         fs_name: String,
     ) -> juniper::FieldResult<String> {
-        let x = client_mount_source(&context.pg_pool, &fs_name).await?;
+        let nids = client_mount::mgs_nids(&context.pg_pool, &fs_name).await?;
 
-        Ok(x)
+        Ok(client_mount::mount_source(&nids, &fs_name, None))
     }
     /// List the full client mount command.
     /// This will build up the source using known mgs locations
@@ -636,33 +1999,49 @@ impl QueryRoot {
         context: &Context,
         fs_name: String,
     ) -> juniper::FieldResult<String> {
-        let x = client_mount_source(&context.pg_pool, &fs_name).await?;
+        let nids = client_mount::mgs_nids(&context.pg_pool, &fs_name).await?;
 
-        let mount_command = format!("mount -t lustre {} /mnt/{}", x, fs_name);
+        let mount_command = format!(
+            "mount -t lustre {} {}",
+            client_mount::mount_source(&nids, &fs_name, None),
+            client_mount::default_mountpoint(&fs_name)
+        );
 
         Ok(mount_command)
     }
-}
-
-struct SnapshotIntervalName {
-    id: i32,
-    fs_name: String,
-    timestamp: DateTime<Utc>,
-}
+    /// Render a file that sets up a client to mount the given filesystem:
+    /// a systemd mount unit, an `/etc/fstab` line or an `/etc/lnet.conf`.
+    /// The same files are downloaded from `/api/client-mount/{fsName}/{kind}`,
+    /// taking the other arguments as query parameters.
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to generate the file for"),
+        kind(description = "Which file to generate"),
+        network(
+            description = "The LNet network the client mounts over. Defaults to that of the first MGS NID"
+        ),
+        interface(description = "The interface of the client on the network, e.g. `ib0`"),
+        mountpoint(
+            description = "Where the client mounts the filesystem. Defaults to `/mnt/{fsName}`"
+        ),
+    ))]
+    async fn client_mount_config(
+        context: &Context,
+        fs_name: String,
+        kind: ClientMountConfigKind,
+        network: Option<String>,
+        interface: Option<String>,
+        mountpoint: Option<String>,
+    ) -> juniper::FieldResult<String> {
+        let params = client_mount::Params {
+            network,
+            interface,
+            mountpoint,
+        };
 
-fn parse_snapshot_name(name: &str) -> Option<SnapshotIntervalName> {
-    match name.trim().splitn(3, '-').collect::<Vec<&str>>().as_slice() {
-        [id, fs, ts] => {
-            let ts = ts.parse::<DateTime<Utc>>().ok()?;
-            let id = id.parse::<i32>().ok()?;
+        let x =
+            client_mount::client_mount_config(&context.pg_pool, &fs_name, kind, &params).await?;
 
-            Some(SnapshotIntervalName {
-                id,
-                fs_name: fs.to_string(),
-                timestamp: ts,
-            })
-        }
-        _ => None,
+        Ok(x)
     }
 }
 
@@ -670,15 +2049,88 @@ pub(crate) struct MutationRoot;
 
 #[juniper::graphql_object(Context = Context)]
 impl MutationRoot {
+    fn approval(&self) -> approval::ApprovalMutation {
+        approval::ApprovalMutation
+    }
+    fn banner(&self) -> banner::BannerMutation {
+        banner::BannerMutation
+    }
+    fn certificate(&self) -> certificate::CertificateMutation {
+        certificate::CertificateMutation
+    }
+    fn changelog(&self) -> changelog::ChangelogMutation {
+        changelog::ChangelogMutation
+    }
+    fn dashboards(&self) -> dashboards::DashboardsMutation {
+        dashboards::DashboardsMutation
+    }
+    fn digest(&self) -> digest::DigestMutation {
+        digest::DigestMutation
+    }
+    fn escalation(&self) -> escalation::EscalationMutation {
+        escalation::EscalationMutation
+    }
+    fn federation(&self) -> federation::FederationMutation {
+        federation::FederationMutation
+    }
     fn filesystem(&self) -> filesystem::FilesystemMutation {
         filesystem::FilesystemMutation
     }
+    fn ha_cluster(&self) -> ha_cluster::HaClusterMutation {
+        ha_cluster::HaClusterMutation
+    }
+    fn host(&self) -> host::HostMutation {
+        host::HostMutation
+    }
+    fn lnet_ping(&self) -> lnet_ping::LnetPingMutation {
+        lnet_ping::LnetPingMutation
+    }
+    fn log_alert(&self) -> log_alert::LogAlertMutation {
+        log_alert::LogAlertMutation
+    }
+    fn maintenance(&self) -> maintenance::MaintenanceMutation {
+        maintenance::MaintenanceMutation
+    }
+    fn role(&self) -> role::RoleMutation {
+        role::RoleMutation
+    }
+    fn security(&self) -> security::SecurityMutation {
+        security::SecurityMutation
+    }
+    fn standby(&self) -> standby::StandbyMutation {
+        standby::StandbyMutation
+    }
+    fn stonith(&self) -> stonith::StonithMutation {
+        stonith::StonithMutation
+    }
     fn stratagem(&self) -> stratagem::StratagemMutation {
         stratagem::StratagemMutation
     }
     fn task(&self) -> task::TaskMutation {
         task::TaskMutation
     }
+    fn tuning(&self) -> tuning::TuningMutation {
+        tuning::TuningMutation
+    }
+    fn user_preferences(&self) -> user_preferences::UserPreferencesMutation {
+        user_preferences::UserPreferencesMutation
+    }
+    #[graphql(arguments(
+        query(description = "The query to run. Mutations are not accepted"),
+        operation_name(description = "The operation to run, if the query has several"),
+        variables(description = "JSON encoded variables of the query"),
+    ))]
+    /// Runs a query in the background, for reports that take too long to answer within a request.
+    /// Returns a handle to read the result with `queryResult`.
+    /// The query and its result are removed after `DEFERRED_QUERY_TTL_HOURS`.
+    async fn submit_query(
+        context: &Context,
+        query: String,
+        operation_name: Option<String>,
+        variables: Option<String>,
+    ) -> juniper::FieldResult<DeferredQuery> {
+        deferred::submit(context, query, operation_name, variables).await
+    }
     #[graphql(arguments(
         fsname(description = "Filesystem to snapshot"),
         name(description = "Name of the snapshot"),
@@ -689,6 +2141,8 @@ impl MutationRoot {
     ))]
     /// Creates a snapshot of an existing Lustre filesystem. Returns a `Command` to track progress.
     /// For the `Command` to succeed, the filesystem being snapshoted must be available.
+    /// The checks of `snapshotPreflight` are run first, failed ones are listed in the error.
+    /// Snapshots taken by an interval with `automount` set are mounted in the same `Command`.
     async fn create_snapshot(
         context: &Context,
         fsname: String,
@@ -696,86 +2150,120 @@ impl MutationRoot {
         comment: Option<String>,
         use_barrier: Option<bool>,
     ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::CreateSnapshots).await?;
+
         let _ = fs_id_by_name(&context.pg_pool, &fsname).await?;
         let name = name.trim();
         validate_snapshot_name(name)?;
 
+        let active_mgs_host_fqdn = active_mgs_host_fqdn(&fsname, &context.pg_pool)
+            .await?
+            .ok_or_else(|| {
+                FieldError::new("Filesystem not found or MGS is not mounted", Value::null())
+            })?;
+
+        let use_barrier = use_barrier.unwrap_or(false);
+
+        let preflight = snapshot_preflight::run(context, &fsname, name, use_barrier).await?;
+
+        if !preflight.passed {
+            return Err(snapshot_preflight::failed_error(&preflight));
+        }
+
         let snapshot_interval_name = parse_snapshot_name(name);
-        if let Some(data) = snapshot_interval_name {
+        let automount = if let Some(data) = snapshot_interval_name {
             sqlx::query!(
                 r#"
                 UPDATE snapshot_interval
                 SET last_run=$1
                 WHERE id=$2 AND filesystem_name=$3
+                RETURNING automount
             "#,
                 data.timestamp,
                 data.id,
                 data.fs_name,
             )
-            .execute(&context.pg_pool)
-            .await?;
-        }
-
-        let active_mgs_host_fqdn = active_mgs_host_fqdn(&fsname, &context.pg_pool)
+            .fetch_optional(&context.pg_pool)
             .await?
-            .ok_or_else(|| {
-                FieldError::new("Filesystem not found or MGS is not mounted", Value::null())
-            })?;
+            .map(|x| x.automount)
+            .unwrap_or_default()
+        } else {
+            false
+        };
+
+        let jobs = snapshot_policy::snapshot_jobs(
+            &fsname,
+            name,
+            comment.as_deref(),
+            &active_mgs_host_fqdn,
+            use_barrier,
+            automount,
+        );
+
+        let kwargs = approval::check(context, &jobs, snapshot_policy::snapshot_kwargs()).await?;
+
+        let command_id =
+            snapshot_policy::create_snapshot_jobs(&context.rabbit_pool, jobs, kwargs).await?;
 
-        let kwargs: HashMap<String, String> = vec![("message".into(), "Creating snapshot".into())]
-            .into_iter()
-            .collect();
+        let command = get_command(&context.pg_pool, command_id).await?;
 
-        let jobs = serde_json::json!([{
-            "class_name": "CreateSnapshotJob",
-            "args": {
-                "fsname": fsname,
-                "name": name,
-                "comment": comment,
-                "fqdn": active_mgs_host_fqdn,
-                "use_barrier": use_barrier.unwrap_or(false),
-            }
-        }]);
-        let command_id: i32 = iml_job_scheduler_rpc::call(
-            &context.rabbit_pool.get().await?,
-            "run_jobs",
-            vec![jobs],
-            Some(kwargs),
+        audit::record(
+            context,
+            "createSnapshot",
+            serde_json::json!({ "fsname": fsname, "name": name }),
+            Some(command.id),
         )
-        .map_err(ImlApiError::ImlJobSchedulerRpcError)
-        .await?;
-
-        let command = get_command(&context.pg_pool, command_id).await?;
+        .await;
 
         Ok(command)
     }
     #[graphql(arguments(
         fsname(description = "Filesystem snapshot was taken from"),
         name(description = "Name of the snapshot"),
-        force(description = "Destroy the snapshot by force"),
+        force(description = "Destroy the snapshot by force, even if it is held"),
     ))]
     /// Destroys an existing snapshot of an existing Lustre filesystem. Returns a `Command` to track progress.
     /// For the `Command` to succeed, the filesystem must be available.
+    /// Held snapshots are only destroyed with `force`.
     async fn destroy_snapshot(
         context: &Context,
         fsname: String,
         name: String,
         force: bool,
     ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::DeleteSnapshots).await?;
+
         let _ = fs_id_by_name(&context.pg_pool, &fsname).await?;
         let name = name.trim();
         validate_snapshot_name(name)?;
 
+        let held = sqlx::query!(
+            "SELECT held FROM snapshot WHERE filesystem_name = $1 AND snapshot_name = $2",
+            fsname,
+            name
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .map(|x| x.held)
+        .unwrap_or(false);
+
+        if held && !force {
+            return Err(FieldError::new(
+                format!(
+                    "Snapshot {} of {} is held. Release it first, or destroy it with force",
+                    name, fsname
+                ),
+                Value::null(),
+            ));
+        }
+
         let active_mgs_host_fqdn = active_mgs_host_fqdn(&fsname, &context.pg_pool)
             .await?
             .ok_or_else(|| {
                 FieldError::new("Filesystem not found or MGS is not mounted", Value::null())
             })?;
 
-        let kwargs: HashMap<String, String> =
-            vec![("message".into(), "Destroying snapshot".into())]
-                .into_iter()
-                .collect();
+        let kwargs = run_jobs_kwargs("Destroying snapshot", JobPriority::High);
 
         let jobs = serde_json::json!([{
             "class_name": "DestroySnapshotJob",
@@ -786,6 +2274,9 @@ impl MutationRoot {
                 "fqdn": active_mgs_host_fqdn,
             }
         }]);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
+
         let command_id: i32 = iml_job_scheduler_rpc::call(
             &context.rabbit_pool.get().await?,
             "run_jobs",
@@ -797,12 +2288,71 @@ impl MutationRoot {
 
         let command = get_command(&context.pg_pool, command_id).await?;
 
+        audit::record(
+            context,
+            "destroySnapshot",
+            serde_json::json!({ "fsname": fsname, "name": name, "force": force }),
+            Some(command.id),
+        )
+        .await;
+
         Ok(command)
     }
     #[graphql(arguments(
         fsname(description = "Filesystem snapshot was taken from"),
         name(description = "Name of the snapshot"),
     ))]
+    /// Holds a snapshot, protecting it from deletion.
+    /// Held snapshots are kept by the retention policy and can only be destroyed with `force`.
+    async fn hold_snapshot(
+        context: &Context,
+        fsname: String,
+        name: String,
+    ) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::DeleteSnapshots).await?;
+
+        let name = name.trim();
+        set_snapshot_held(&context.pg_pool, &fsname, name, true).await?;
+
+        audit::record(
+            context,
+            "holdSnapshot",
+            serde_json::json!({ "fsname": fsname, "name": name }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        fsname(description = "Filesystem snapshot was taken from"),
+        name(description = "Name of the snapshot"),
+    ))]
+    /// Releases a held snapshot, so it can be deleted again.
+    async fn release_snapshot(
+        context: &Context,
+        fsname: String,
+        name: String,
+    ) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::DeleteSnapshots).await?;
+
+        let name = name.trim();
+        set_snapshot_held(&context.pg_pool, &fsname, name, false).await?;
+
+        audit::record(
+            context,
+            "releaseSnapshot",
+            serde_json::json!({ "fsname": fsname, "name": name }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        fsname(description = "Filesystem snapshot was taken from"),
+        name(description = "Name of the snapshot"),
+    ))]
     /// Mounts an existing snapshot of an existing Lustre filesystem. Returns a `Command` to track progress.
     /// For the `Command` to succeed, the filesystem must be available.
     async fn mount_snapshot(
@@ -810,6 +2360,8 @@ impl MutationRoot {
         fsname: String,
         name: String,
     ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::CreateSnapshots).await?;
+
         let name = name.trim();
         validate_snapshot_name(name)?;
 
@@ -819,9 +2371,7 @@ impl MutationRoot {
                 FieldError::new("Filesystem not found or MGS is not mounted", Value::null())
             })?;
 
-        let kwargs: HashMap<String, String> = vec![("message".into(), "Mounting snapshot".into())]
-            .into_iter()
-            .collect();
+        let kwargs = run_jobs_kwargs("Mounting snapshot", JobPriority::High);
 
         let jobs = serde_json::json!([{
             "class_name": "MountSnapshotJob",
@@ -831,6 +2381,9 @@ impl MutationRoot {
                 "fqdn": active_mgs_host_fqdn,
             }
         }]);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
+
         let command_id: i32 = iml_job_scheduler_rpc::call(
             &context.rabbit_pool.get().await?,
             "run_jobs",
@@ -840,9 +2393,17 @@ impl MutationRoot {
         .map_err(ImlApiError::ImlJobSchedulerRpcError)
         .await?;
 
-        get_command(&context.pg_pool, command_id)
-            .await
-            .map_err(|e| e.into())
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        audit::record(
+            context,
+            "mountSnapshot",
+            serde_json::json!({ "fsname": fsname, "name": name }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
     }
     #[graphql(arguments(
         fsname(description = "Filesystem snapshot was taken from"),
@@ -855,6 +2416,8 @@ impl MutationRoot {
         fsname: String,
         name: String,
     ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::CreateSnapshots).await?;
+
         let _ = fs_id_by_name(&context.pg_pool, &fsname).await?;
         let name = name.trim();
         validate_snapshot_name(name)?;
@@ -865,10 +2428,7 @@ impl MutationRoot {
                 FieldError::new("Filesystem not found or MGS is not mounted", Value::null())
             })?;
 
-        let kwargs: HashMap<String, String> =
-            vec![("message".into(), "Unmounting snapshot".into())]
-                .into_iter()
-                .collect();
+        let kwargs = run_jobs_kwargs("Unmounting snapshot", JobPriority::High);
 
         let jobs = serde_json::json!([{
             "class_name": "UnmountSnapshotJob",
@@ -878,6 +2438,9 @@ impl MutationRoot {
                 "fqdn": active_mgs_host_fqdn,
             }
         }]);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
+
         let command_id: i32 = iml_job_scheduler_rpc::call(
             &context.rabbit_pool.get().await?,
             "run_jobs",
@@ -887,9 +2450,17 @@ impl MutationRoot {
         .map_err(ImlApiError::ImlJobSchedulerRpcError)
         .await?;
 
-        get_command(&context.pg_pool, command_id)
-            .await
-            .map_err(|e| e.into())
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        audit::record(
+            context,
+            "unmountSnapshot",
+            serde_json::json!({ "fsname": fsname, "name": name }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
     }
     #[graphql(arguments(
         fsname(description = "The filesystem to create snapshots with"),
@@ -897,25 +2468,59 @@ impl MutationRoot {
         use_barrier(
             description = "Set write barrier before creating snapshot. The default value is `false`"
         ),
+        automount(
+            description = "Mount each snapshot as soon as it is taken. The default value is `false`"
+        ),
+        start_time(
+            description = "Take the snapshots at this time of day, as HH:MM in `timeZone`, and every `interval` after it. Snapshots are taken every `interval` from now when not given"
+        ),
+        time_zone(
+            description = "The IANA time zone of `startTime`, e. g. `Europe/Paris`. The default value is `UTC`"
+        ),
     ))]
     /// Creates a new snapshot interval.
     /// A recurring snapshot will be taken once the given `interval` expires for the given `fsname`.
+    /// With a `startTime`, snapshots are taken at that wall-clock time in `timeZone`,
+    /// which stays the same across daylight saving time changes.
     /// In order for the snapshot to be successful, the filesystem must be available.
     async fn create_snapshot_interval(
         context: &Context,
         fsname: String,
         interval: GraphQLDuration,
         use_barrier: Option<bool>,
+        automount: Option<bool>,
+        start_time: Option<String>,
+        time_zone: Option<String>,
     ) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::ManageSnapshotPolicies).await?;
+
         let _ = fs_id_by_name(&context.pg_pool, &fsname).await?;
+
+        let start = start_time
+            .as_deref()
+            .map(snapshot_policy::parse_start_time)
+            .transpose()
+            .map_err(invalid)?;
+        let time_zone = time_zone.unwrap_or_else(default_time_zone);
+
+        if !snapshot_policy::is_time_zone(&context.pg_pool, &time_zone).await? {
+            return Err(FieldError::new(
+                format!("Unknown time zone {}", time_zone),
+                Value::null(),
+            ));
+        }
+
         let maybe_id = sqlx::query!(
             r#"
                 INSERT INTO snapshot_interval (
                     filesystem_name,
                     use_barrier,
-                    interval
+                    interval,
+                    automount,
+                    start_at,
+                    time_zone
                 )
-                VALUES ($1, $2, $3)
+                VALUES ($1, $2, $3, $4, snapshot_interval_start_at($5, $6, $3), $6)
                 ON CONFLICT (filesystem_name, interval)
                 DO NOTHING
                 RETURNING id
@@ -923,27 +2528,303 @@ impl MutationRoot {
             fsname,
             use_barrier.unwrap_or_default(),
             PgInterval::try_from(interval.0)?,
+            automount.unwrap_or_default(),
+            start,
+            time_zone
         )
         .fetch_optional(&context.pg_pool)
         .await?
         .map(|x| x.id);
 
-        if let Some(id) = maybe_id {
-            configure_snapshot_timer(id, fsname, interval.0, use_barrier.unwrap_or_default())
-                .await?;
+        // Intervals with a start time are queued by `snapshot_policy` instead of a timer
+        if let (Some(id), None) = (maybe_id, start) {
+            configure_snapshot_timer(id, fsname.clone(), interval.0).await?;
         }
 
+        audit::record(
+            context,
+            "createSnapshotInterval",
+            serde_json::json!({
+                "fsname": fsname,
+                "interval": interval,
+                "useBarrier": use_barrier,
+                "automount": automount,
+                "startTime": start_time,
+                "timeZone": time_zone,
+            }),
+            None,
+        )
+        .await;
+
         Ok(true)
     }
     /// Removes an existing snapshot interval.
     /// This will also cancel any outstanding intervals scheduled by this rule.
     #[graphql(arguments(id(description = "The snapshot interval id"),))]
     async fn remove_snapshot_interval(context: &Context, id: i32) -> juniper::FieldResult<bool> {
-        sqlx::query!("DELETE FROM snapshot_interval WHERE id=$1", id)
+        role::require(context, Permission::ManageSnapshotPolicies).await?;
+
+        let has_timer = sqlx::query!(
+            r#"DELETE FROM snapshot_interval WHERE id=$1 RETURNING start_at IS NULL AS "has_timer!""#,
+            id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .map(|x| x.has_timer);
+
+        if has_timer != Some(false) {
+            remove_snapshot_timer(id).await?;
+        }
+
+        audit::record(
+            context,
+            "removeSnapshotInterval",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+    #[graphql(arguments(id(description = "The snapshot interval id")))]
+    /// Queues a snapshot of interval `id`, which is what its timer does when the interval is due.
+    /// The snapshot is started after a random delay of up to the configured jitter,
+    /// once fewer than `maxConcurrent` snapshots are being taken.
+    /// If a run of the interval is already queued, it is returned instead.
+    async fn run_snapshot_interval(
+        context: &Context,
+        id: i32,
+    ) -> juniper::FieldResult<SnapshotIntervalRun> {
+        role::require(context, Permission::ManageSnapshotPolicies).await?;
+
+        let x = snapshot_policy::queue_run(&context.pg_pool, id)
+            .await?
+            .ok_or_else(|| not_found("Snapshot interval", id))?;
+
+        Ok(x)
+    }
+    #[graphql(arguments(
+        max_concurrent(
+            description = "How many snapshots intervals may be taking at once, `0` for no cap"
+        ),
+        jitter(description = "Delay each interval run by a random time up to this long"),
+    ))]
+    /// Updates the settings shared by every snapshot policy. Settings that are not given are left as they are.
+    async fn set_snapshot_policy_settings(
+        context: &Context,
+        max_concurrent: Option<i32>,
+        jitter: Option<GraphQLDuration>,
+    ) -> juniper::FieldResult<SnapshotPolicySettings> {
+        role::require(context, Permission::ManageSnapshotPolicies).await?;
+
+        if max_concurrent.map_or(false, |x| x < 0) {
+            return Err(FieldError::new(
+                "max_concurrent can't be negative",
+                Value::null(),
+            ));
+        }
+
+        let pg_jitter = jitter
+            .as_ref()
+            .map(|x| PgInterval::try_from(x.0))
+            .transpose()?;
+
+        let x = sqlx::query!(
+            r#"
+                UPDATE snapshot_policy_settings
+                SET max_concurrent = COALESCE($1, max_concurrent),
+                    jitter = COALESCE($2, jitter)
+                RETURNING max_concurrent, jitter
+            "#,
+            max_concurrent,
+            pg_jitter
+        )
+        .fetch_one(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "setSnapshotPolicySettings",
+            serde_json::json!({ "maxConcurrent": max_concurrent, "jitter": jitter }),
+            None,
+        )
+        .await;
+
+        Ok(SnapshotPolicySettings {
+            max_concurrent: x.max_concurrent,
+            jitter: x.jitter.into(),
+        })
+    }
+    #[graphql(arguments(
+        class_name(description = "The job class name, i.e. `MountLustreFilesystemsJob`"),
+        timeout(description = "How long a job of this class may run before it is failed"),
+    ))]
+    /// Sets the timeout for a job class.
+    /// Running jobs of this class that exceed the timeout are failed and their dependents cancelled.
+    async fn set_job_timeout(
+        context: &Context,
+        class_name: String,
+        timeout: GraphQLDuration,
+    ) -> juniper::FieldResult<bool> {
+        sqlx::query!(
+            r#"
+                INSERT INTO job_timeout (class_name, timeout)
+                VALUES ($1, $2)
+                ON CONFLICT (class_name)
+                DO UPDATE SET timeout = EXCLUDED.timeout
+            "#,
+            class_name,
+            PgInterval::try_from(timeout.0)?,
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "setJobTimeout",
+            serde_json::json!({ "className": class_name, "timeout": timeout }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+    /// Removes the timeout for a job class.
+    #[graphql(arguments(class_name(description = "The job class name"),))]
+    async fn remove_job_timeout(
+        context: &Context,
+        class_name: String,
+    ) -> juniper::FieldResult<bool> {
+        sqlx::query!("DELETE FROM job_timeout WHERE class_name = $1", class_name)
             .execute(&context.pg_pool)
             .await?;
 
-        remove_snapshot_timer(id).await?;
+        audit::record(
+            context,
+            "removeJobTimeout",
+            serde_json::json!({ "className": class_name }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+
+    #[graphql(arguments(
+        id(description = "The id of the command"),
+        note(description = "A note to add to the command"),
+        tags(description = "Tags to add to the command. Tags are lowercased"),
+    ))]
+    /// Add a note and / or tags to a command, e.g. to record why a failover was run.
+    /// Existing notes and tags are kept.
+    async fn annotate_command(
+        context: &Context,
+        id: i32,
+        note: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> juniper::FieldResult<CommandAnnotations> {
+        let note = note.map(|x| x.trim().to_string()).filter(|x| !x.is_empty());
+
+        let tags = tags
+            .unwrap_or_default()
+            .iter()
+            .map(|x| normalize_tag(x))
+            .collect::<Vec<_>>();
+
+        if note.is_none() && tags.is_empty() {
+            return Err(FieldError::new(
+                "A note or at least one tag is required",
+                Value::null(),
+            ));
+        }
+
+        if let Some(x) = tags.iter().find(|x| !valid_tag(x)) {
+            return Err(FieldError::new(
+                format!(
+                    "Invalid tag {:?}. Tags may only contain letters, digits, '-', '_' and '.'",
+                    x
+                ),
+                Value::null(),
+            ));
+        }
+
+        let exists = sqlx::query!("SELECT id FROM chroma_core_command WHERE id = $1", id)
+            .fetch_optional(&context.pg_pool)
+            .await?
+            .is_some();
+
+        if !exists {
+            return Err(FieldError::new(
+                format!("Command {} not found", id),
+                Value::null(),
+            ));
+        }
+
+        let mut transaction = context.pg_pool.begin().await?;
+
+        if let Some(note) = note {
+            sqlx::query!(
+                "INSERT INTO command_note (command_id, note, user_id) VALUES ($1, $2, $3)",
+                id,
+                note,
+                context.user_id
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+
+        sqlx::query!(
+            r#"
+                INSERT INTO command_tag (command_id, tag)
+                SELECT $1, unnest($2::TEXT[])
+                ON CONFLICT DO NOTHING
+            "#,
+            id,
+            &tags
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        let xs = get_command_annotations(&context.pg_pool, id).await?;
+
+        audit::record(
+            context,
+            "annotateCommand",
+            serde_json::json!({ "id": id, "tags": tags }),
+            Some(id),
+        )
+        .await;
+
+        Ok(xs)
+    }
+
+    #[graphql(arguments(
+        id(description = "The id of the command"),
+        tag(description = "The tag to remove"),
+    ))]
+    /// Remove a tag from a command
+    async fn remove_command_tag(
+        context: &Context,
+        id: i32,
+        tag: String,
+    ) -> juniper::FieldResult<bool> {
+        sqlx::query!(
+            "DELETE FROM command_tag WHERE command_id = $1 AND tag = $2",
+            id,
+            normalize_tag(&tag)
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "removeCommandTag",
+            serde_json::json!({ "id": id, "tag": tag }),
+            Some(id),
+        )
+        .await;
 
         Ok(true)
     }
@@ -955,55 +2836,189 @@ impl MutationRoot {
         reserve_unit(description = "The unit of measurement associated with the reserve_value"),
         keep_num(
             description = "The minimum number of snapshots to keep. This is to avoid deleting all snapshots while pursuiting the reserve goal"
-        )
+        ),
+        version(
+            description = "The expected current version of the policy. Required when updating an existing policy"
+        ),
+        mdt_reserve_percent(
+            description = "Also delete the oldest snapshot while the MDTs have less than this percent of space free"
+        ),
+        change_percent(
+            description = "Take a snapshot once the used space changed by this percent of the filesystem size since the last one taken for it"
+        ),
     ))]
     /// Creates a new snapshot retention policy for the given `fsname`.
     /// Snapshots will automatically be deleted (starting with the oldest)
     /// when free space falls below the defined reserve value and its associated unit.
+    ///
+    /// Optionally, snapshots are also deleted as MDT free space falls below `mdtReservePercent`,
+    /// and taken as the filesystem changes by `changePercent`. Both are recorded in `snapshotIntervalRuns`.
+    ///
+    /// If a policy already exists for `fsname` it is updated, as long as `version`
+    /// matches the current version of the policy. Otherwise a `CONFLICT` error is returned.
     async fn create_snapshot_retention(
         context: &Context,
         fsname: String,
         reserve_value: i32,
         reserve_unit: ReserveUnit,
         keep_num: Option<i32>,
+        version: Option<i32>,
+        mdt_reserve_percent: Option<i32>,
+        change_percent: Option<i32>,
     ) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::ManageSnapshotPolicies).await?;
+
+        validate_thresholds(mdt_reserve_percent, change_percent).map_err(invalid)?;
+
         let _ = fs_id_by_name(&context.pg_pool, &fsname).await?;
-        sqlx::query!(
+
+        let mut transaction = context.pg_pool.begin().await?;
+
+        // The version is checked by the upsert itself, so concurrent updates can't both pass it
+        let x = sqlx::query!(
             r#"
                 INSERT INTO snapshot_retention (
                     filesystem_name,
                     reserve_value,
                     reserve_unit,
-                    keep_num
+                    keep_num,
+                    mdt_reserve_percent,
+                    change_percent
                 )
-                VALUES ($1, $2, $3, $4)
+                VALUES ($1, $2, $3, $4, $5, $6)
                 ON CONFLICT (filesystem_name)
                 DO UPDATE SET
                 reserve_value = EXCLUDED.reserve_value,
                 reserve_unit = EXCLUDED.reserve_unit,
-                keep_num = EXCLUDED.keep_num
+                keep_num = EXCLUDED.keep_num,
+                mdt_reserve_percent = EXCLUDED.mdt_reserve_percent,
+                change_percent = EXCLUDED.change_percent,
+                change_baseline = CASE
+                    WHEN EXCLUDED.change_percent IS NULL THEN NULL
+                    ELSE snapshot_retention.change_baseline
+                END,
+                version = snapshot_retention.version + 1,
+                updated_at = now()
+                WHERE snapshot_retention.version = $7
+                RETURNING (xmax = 0) AS "inserted!"
             "#,
             fsname,
             reserve_value,
             reserve_unit as ReserveUnit,
-            keep_num.unwrap_or(0)
+            keep_num.unwrap_or(0),
+            mdt_reserve_percent,
+            change_percent,
+            version
         )
-        .execute(&context.pg_pool)
+        .fetch_optional(&mut transaction)
         .await?;
 
+        if !upserted(x.map(|x| x.inserted), version) {
+            transaction.rollback().await?;
+
+            let current = match get_snapshot_retentions(&context.pg_pool, Some(&fsname))
+                .await?
+                .pop()
+            {
+                Some(x) => Some((x.version, serde_json::to_value(x)?)),
+                None => None,
+            };
+
+            return Err(version_conflict(
+                &format!("Snapshot retention policy for {}", fsname),
+                version,
+                current,
+            ));
+        }
+
+        transaction.commit().await?;
+
+        audit::record(
+            context,
+            "createSnapshotRetention",
+            serde_json::json!({
+                "fsname": fsname,
+                "reserveValue": reserve_value,
+                "keepNum": keep_num,
+                "mdtReservePercent": mdt_reserve_percent,
+                "changePercent": change_percent,
+            }),
+            None,
+        )
+        .await;
+
         Ok(true)
     }
+    #[graphql(arguments(
+        yaml(description = "Snapshot policies as written by `exportSnapshotPolicies`"),
+        fsname(
+            description = "Apply the policies of the file's only filesystem to this filesystem instead"
+        ),
+        replace(
+            description = "Also remove the intervals and retention policies of the listed filesystems that are not in the file. The default value is `false`"
+        ),
+    ))]
+    /// Apply snapshot policies written by `exportSnapshotPolicies`, in a single transaction.
+    /// Intervals that already exist have their barrier and automount updated,
+    /// retention policies are replaced whatever their version, and the shared settings
+    /// are changed when the file has them. Every filesystem listed must exist.
+    async fn import_snapshot_policies(
+        context: &Context,
+        yaml: String,
+        fsname: Option<String>,
+        replace: Option<bool>,
+    ) -> juniper::FieldResult<SnapshotPolicyImport> {
+        role::require(context, Permission::ManageSnapshotPolicies).await?;
+
+        let file = snapshot_policies::parse(&yaml, fsname.as_deref()).map_err(invalid)?;
+
+        for x in &file.filesystems {
+            let _ = fs_id_by_name(&context.pg_pool, &x.filesystem).await?;
+        }
+
+        let replace = replace.unwrap_or_default();
+
+        let x = snapshot_policies::import(&context.pg_pool, file, replace).await?;
+
+        audit::record(
+            context,
+            "importSnapshotPolicies",
+            serde_json::json!({
+                "filesystems": x.filesystems,
+                "replace": replace,
+                "intervalsAdded": x.intervals_added,
+                "intervalsRemoved": x.intervals_removed,
+            }),
+            None,
+        )
+        .await;
+
+        Ok(x)
+    }
     /// Remove an existing snapshot retention policy.
     #[graphql(arguments(id(description = "The snapshot retention policy id")))]
     async fn remove_snapshot_retention(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::ManageSnapshotPolicies).await?;
+
         sqlx::query!("DELETE FROM snapshot_retention WHERE id=$1", id)
             .execute(&context.pg_pool)
             .await?;
 
+        audit::record(
+            context,
+            "removeSnapshotRetention",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
         Ok(true)
     }
 
     /// Create a server profile.
+    /// If a profile with the same name already exists it is updated,
+    /// as long as `profile.version` matches the current version of the profile.
+    /// Otherwise a `CONFLICT` error is returned.
     #[graphql(arguments(profile(description = "The server profile to add")))]
     async fn create_server_profile(
         context: &Context,
@@ -1029,7 +3044,8 @@ impl MutationRoot {
 
         let mut transaction = context.pg_pool.begin().await?;
 
-        sqlx::query!(
+        // The version is checked by the upsert itself, so concurrent updates can't both pass it
+        let x = sqlx::query!(
             r#"
             INSERT INTO chroma_core_serverprofile
             (
@@ -1061,7 +3077,11 @@ impl MutationRoot {
             corosync = excluded.corosync,
             corosync2 = excluded.corosync2,
             pacemaker = excluded.pacemaker,
-            "default" = excluded.default
+            "default" = excluded.default,
+            version = chroma_core_serverprofile.version + 1,
+            updated_at = now()
+            WHERE chroma_core_serverprofile.version = $11
+            RETURNING (xmax = 0) AS "inserted!"
         "#,
             &profile.name,
             &profile.ui_name,
@@ -1072,11 +3092,30 @@ impl MutationRoot {
             &profile.ntp,
             &profile.corosync,
             &profile.corosync2,
-            &profile.pacemaker
+            &profile.pacemaker,
+            profile.version
         )
-        .execute(&mut transaction)
+        .fetch_optional(&mut transaction)
         .await?;
 
+        if !upserted(x.map(|x| x.inserted), profile.version) {
+            transaction.rollback().await?;
+
+            let current = match get_server_profiles(&context.pg_pool, Some(&profile.name))
+                .await?
+                .pop()
+            {
+                Some(x) => Some((x.version, serde_json::to_value(x)?)),
+                None => None,
+            };
+
+            return Err(version_conflict(
+                &format!("Server profile {}", profile.name),
+                profile.version,
+                current,
+            ));
+        }
+
         sqlx::query!(
             r#"
             INSERT INTO chroma_core_serverprofile_repolist (serverprofile_id, repo_id)
@@ -1106,6 +3145,15 @@ impl MutationRoot {
         .await?;
 
         transaction.commit().await?;
+
+        audit::record(
+            context,
+            "createServerProfile",
+            serde_json::json!({ "name": profile.name }),
+            None,
+        )
+        .await;
+
         Ok(true)
     }
 
@@ -1134,10 +3182,64 @@ impl MutationRoot {
             "DELETE FROM chroma_core_serverprofile WHERE name = $1",
             &profile_name
         )
-        .execute(&mut transaction)
+        .execute(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        audit::record(
+            context,
+            "removeServerProfile",
+            serde_json::json!({ "name": profile_name }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+
+    #[graphql(arguments(uuid(description = "The uuid of the target")))]
+    /// Accept the current device path of a target as expected,
+    /// lowering its `TargetDevPathChangedAlert`.
+    async fn acknowledge_target_dev_path(
+        context: &Context,
+        uuid: String,
+    ) -> juniper::FieldResult<bool> {
+        sqlx::query!(
+            r#"
+                UPDATE target_dev_path_baseline
+                SET previous_dev_path = NULL, changed_at = NULL
+                WHERE uuid = $1
+            "#,
+            uuid
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        let ids = sqlx::query!(
+            "SELECT id FROM chroma_core_managedtarget WHERE uuid = $1 AND not_deleted = 't'",
+            uuid
+        )
+        .fetch_all(&context.pg_pool)
         .await?;
 
-        transaction.commit().await?;
+        for x in ids {
+            iml_postgres::alert::lower(
+                &context.pg_pool,
+                vec![AlertRecordType::TargetDevPathChangedAlert],
+                x.id,
+            )
+            .await?;
+        }
+
+        audit::record(
+            context,
+            "acknowledgeTargetDevPath",
+            serde_json::json!({ "uuid": uuid }),
+            None,
+        )
+        .await;
+
         Ok(true)
     }
 }
@@ -1175,45 +3277,236 @@ fn to_command(x: CommandTmpRecord) -> Command {
 
 pub(crate) type Schema = RootNode<'static, QueryRoot, MutationRoot, EmptySubscription<Context>>;
 
+#[derive(Clone)]
 pub(crate) struct Context {
     pub(crate) pg_pool: PgPool,
     pub(crate) rabbit_pool: Pool,
+    pub(crate) action_client: iml_action_client::Client,
+    /// The authenticated user making the request.
+    pub(crate) user_id: Option<i32>,
+    /// Batches the lookups of nested resolvers. Replaced for each request.
+    pub(crate) loaders: Arc<Loaders>,
 }
 
 impl juniper::Context for Context {}
 
+/// The query string of a `GET /graphql` request
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct GetRequest {
+    query: String,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+    /// JSON encoded variables
+    variables: Option<String>,
+}
+
+lazy_static! {
+    /// Matches documents that may contain a mutation.
+    /// Errs on the safe side, e.g. for a string argument containing the word.
+    static ref MUTATION: Regex = Regex::new(r"\bmutation\b").unwrap();
+}
+
+/// The span a GraphQL operation runs in. Its resolvers run concurrently within it,
+/// so their SQL queries and RPCs show up as its children.
+pub(crate) fn operation_span(req: &GraphQLRequest, user_id: Option<i32>) -> tracing::Span {
+    let name = req.operation_name().unwrap_or("anonymous");
+
+    tracing::info_span!(
+        "graphql",
+        otel.name = %format!("graphql {}", name),
+        graphql.operation = name,
+        user_id = ?user_id,
+    )
+}
+
+async fn execute(
+    schema: &Schema,
+    ctx: Arc<Context>,
+    principal: Principal,
+    req: GraphQLRequest,
+) -> Result<String, ImlApiError> {
+    let ctx = Context {
+        user_id: principal.user_id(),
+        loaders: Arc::new(Loaders::new(&ctx.pg_pool)),
+        ..(*ctx).clone()
+    };
+
+    let span = operation_span(&req, ctx.user_id);
+
+    let res = req.execute(schema, &ctx).instrument(span).await;
+    let json = serde_json::to_string(&res)?;
+
+    Ok(json)
+}
+
 pub(crate) async fn graphql(
     schema: Arc<Schema>,
     ctx: Arc<Context>,
+    principal: Principal,
+    accept_encoding: Option<String>,
     req: GraphQLRequest,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let res = req.execute(&schema, &ctx).await;
-    let json = serde_json::to_string(&res).map_err(ImlApiError::SerdeJsonError)?;
+    let json = execute(&schema, ctx, principal, req).await?;
 
-    Ok(json)
+    Ok(encoding::reply(json, accept_encoding.as_deref())?)
+}
+
+/// Runs a query sent with `GET`, so the response can be revalidated with its ETag.
+/// Mutations are only accepted with `POST`.
+pub(crate) async fn graphql_get(
+    schema: Arc<Schema>,
+    ctx: Arc<Context>,
+    principal: Principal,
+    accept_encoding: Option<String>,
+    if_none_match: Option<String>,
+    req: GetRequest,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let error = |msg: &str, status| {
+        let body = serde_json::json!({ "errors": [{ "message": msg }] });
+
+        warp::reply::with_status(warp::reply::json(&body), status).into_response()
+    };
+
+    if MUTATION.is_match(&req.query) {
+        return Ok(error(
+            "Mutations must be sent with POST",
+            StatusCode::METHOD_NOT_ALLOWED,
+        ));
+    }
+
+    let variables = match req
+        .variables
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return Ok(error(
+                &format!("Invalid variables: {}", e),
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+    };
+
+    let req = GraphQLRequest::new(req.query, req.operation_name, variables);
+
+    let json = execute(&schema, ctx, principal, req).await?;
+
+    Ok(encoding::cached_reply(
+        json,
+        accept_encoding.as_deref(),
+        if_none_match.as_deref(),
+    )?)
 }
 
 pub(crate) fn endpoint(
     schema_filter: impl Filter<Extract = (Arc<Schema>,), Error = Infallible> + Clone + Send,
     ctx_filter: impl Filter<Extract = (Arc<Context>,), Error = Infallible> + Clone + Send,
+    principal_filter: impl Filter<Extract = (Principal,), Error = warp::Rejection> + Clone + Send,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     let graphql_route = warp::path!("graphql")
         .and(warp::post())
         .and(schema_filter.clone())
-        .and(ctx_filter)
+        .and(ctx_filter.clone())
+        .and(principal_filter.clone())
+        .and(warp::header::optional("accept-encoding"))
         .and(warp::body::json())
         .and_then(graphql);
 
+    let graphql_get_route = warp::path!("graphql")
+        .and(warp::get())
+        .and(schema_filter.clone())
+        .and(ctx_filter)
+        .and(principal_filter.clone())
+        .and(warp::header::optional("accept-encoding"))
+        .and(warp::header::optional("if-none-match"))
+        .and(warp::query())
+        .and_then(graphql_get);
+
     let graphiql_route = warp::path!("graphiql")
         .and(warp::get())
-        .map(|| warp::reply::html(graphiql_source("graphql", None)));
+        .and(principal_filter.clone())
+        .map(|_| warp::reply::html(graphiql_source("graphql", None)));
 
     let graphql_schema_route = warp::path!("graphql_schema")
         .and(warp::get())
+        .and(principal_filter)
         .and(schema_filter)
-        .map(|schema: Arc<Schema>| schema.as_schema_language());
+        .map(|_, schema: Arc<Schema>| schema.as_schema_language());
+
+    graphql_route
+        .or(graphql_get_route)
+        .or(graphiql_route)
+        .or(graphql_schema_route)
+}
+
+/// Everything that currently needs attention on this manager, see `system_health`.
+async fn get_system_health(context: &Context) -> juniper::FieldResult<SystemHealth> {
+    let degraded_filesystems = sqlx::query!(
+        r#"
+            SELECT f.id, f.name, array_agg(t.name ORDER BY t.name) AS "unmounted_targets!"
+            FROM chroma_core_managedfilesystem f
+            INNER JOIN target t ON f.name = ANY(t.filesystems)
+            WHERE f.not_deleted = 't' AND t.state <> 'mounted'
+            GROUP BY f.id, f.name
+            ORDER BY f.name
+        "#
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| DegradedFilesystem {
+        id: x.id,
+        name: x.name,
+        unmounted_targets: x.unmounted_targets,
+    })
+    .collect();
+
+    let down_hosts = sqlx::query_as!(
+        DownHost,
+        r#"
+            SELECT h.id, h.fqdn
+            FROM chroma_core_managedhost h
+            WHERE h.not_deleted = 't'
+            AND EXISTS (
+                SELECT 1 FROM chroma_core_alertstate a
+                WHERE a.alert_item_id = h.id
+                AND a.record_type = 'HostOfflineAlert'
+                AND a.active = 't'
+            )
+            ORDER BY h.fqdn
+        "#
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    let failed_commands = sqlx::query!(
+        r#"
+            SELECT COUNT(*) AS "count!"
+            FROM chroma_core_command
+            WHERE errored = 't' AND created_at > now() - interval '1 hour'
+        "#
+    )
+    .fetch_one(&context.pg_pool)
+    .await?
+    .count;
+
+    let mut service_issues = vec![];
+
+    if let Err(e) = context.rabbit_pool.get().await {
+        service_issues.push(format!("Could not connect to RabbitMQ: {}", e));
+    }
+
+    let x = SystemHealth {
+        degraded_filesystems,
+        down_hosts,
+        failed_commands: failed_commands as i32,
+        service_issues,
+        ..SystemHealth::default()
+    };
 
-    graphql_route.or(graphiql_route).or(graphql_schema_route)
+    Ok(x.with_severity())
 }
 
 async fn get_fs_target_resources(
@@ -1277,10 +3570,10 @@ async fn get_fs_target_resources(
 }
 
 async fn get_fs_cluster_hosts(
-    pool: &PgPool,
+    context: &Context,
     fs_name: String,
 ) -> Result<Vec<Vec<String>>, ImlApiError> {
-    let xs = get_fs_target_resources(pool, Some(fs_name))
+    let xs = get_fs_target_resources(&context.pg_pool, Some(fs_name))
         .await?
         .into_iter()
         .group_by(|x| x.cluster_id);
@@ -1292,18 +3585,111 @@ async fn get_fs_cluster_hosts(
 
         acc
     });
-    let xs = xs.into_iter().map(|idset| async move {
-        let fqdns = idset
-            .into_iter()
-            .map(|x| async move { fqdn_by_host_id(pool, x).await });
-        join_all(fqdns)
-            .await
-            .into_iter()
-            .filter_map(|x| x.ok())
-            .collect()
-    });
 
-    Ok(join_all(xs).await)
+    let hosts = context
+        .loaders
+        .hosts
+        .load_many(xs.iter().flatten().copied())
+        .await?;
+
+    let xs = xs
+        .into_iter()
+        .map(|ids| {
+            ids.into_iter()
+                .filter_map(|x| hosts.get(&x).map(|h| h.fqdn.clone()))
+                .collect()
+        })
+        .collect();
+
+    Ok(xs)
+}
+
+struct ProfileDetails {
+    record: ServerProfileRecord,
+    packages: BTreeSet<String>,
+    repos: BTreeSet<String>,
+}
+
+async fn get_profile_details(pool: &PgPool, name: &str) -> Result<ProfileDetails, FieldError> {
+    let record = sqlx::query_as!(
+        ServerProfileRecord,
+        r#"
+            SELECT corosync, corosync2, "default", initial_state, managed, name, ntp, pacemaker,
+                ui_description, ui_name, user_selectable, worker, version, updated_at
+            FROM chroma_core_serverprofile
+            WHERE name = $1
+        "#,
+        name
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| not_found("Server profile", name))?;
+
+    let packages = sqlx::query!(
+        "SELECT package_name FROM chroma_core_serverprofilepackage WHERE server_profile_id = $1",
+        name
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| x.package_name)
+    .collect();
+
+    let repos = sqlx::query!(
+        "SELECT repo_id FROM chroma_core_serverprofile_repolist WHERE serverprofile_id = $1",
+        name
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| x.repo_id)
+    .collect();
+
+    Ok(ProfileDetails {
+        record,
+        packages,
+        repos,
+    })
+}
+
+fn diff_profiles(a: &ProfileDetails, b: &ProfileDetails) -> ServerProfileComparison {
+    let setting = |name: &str, f: &dyn Fn(&ServerProfileRecord) -> String| {
+        let x = f(&a.record);
+        let y = f(&b.record);
+
+        ProfileSettingDiff {
+            name: name.to_string(),
+            differs: x != y,
+            a: x,
+            b: y,
+        }
+    };
+
+    let diff_lists = |a: &BTreeSet<String>, b: &BTreeSet<String>| ProfileListDiff {
+        both: a.intersection(b).cloned().collect(),
+        only_a: a.difference(b).cloned().collect(),
+        only_b: b.difference(a).cloned().collect(),
+    };
+
+    ServerProfileComparison {
+        a: a.record.name.clone(),
+        b: b.record.name.clone(),
+        settings: vec![
+            setting("Name", &|x| x.ui_name.clone()),
+            setting("Description", &|x| x.ui_description.clone()),
+            setting("Managed", &|x| x.managed.to_string()),
+            setting("Worker", &|x| x.worker.to_string()),
+            setting("User selectable", &|x| x.user_selectable.to_string()),
+            setting("Initial state", &|x| x.initial_state.clone()),
+            setting("NTP", &|x| x.ntp.to_string()),
+            setting("Corosync", &|x| x.corosync.to_string()),
+            setting("Corosync 2", &|x| x.corosync2.to_string()),
+            setting("Pacemaker", &|x| x.pacemaker.to_string()),
+            setting("Default", &|x| x.default.to_string()),
+        ],
+        packages: diff_lists(&a.packages, &b.packages),
+        repos: diff_lists(&a.repos, &b.repos),
+    }
 }
 
 async fn get_banned_targets(pool: &PgPool) -> Result<Vec<BannedTargetResource>, ImlApiError> {
@@ -1343,6 +3729,124 @@ async fn get_banned_resources(pool: &PgPool) -> Result<Vec<BannedResource>, ImlA
     Ok(xs)
 }
 
+async fn get_command_annotations(
+    pool: &PgPool,
+    id: i32,
+) -> Result<CommandAnnotations, ImlApiError> {
+    let tags = sqlx::query!(
+        "SELECT tag FROM command_tag WHERE command_id = $1 ORDER BY tag",
+        id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| x.tag)
+    .collect();
+
+    let notes = sqlx::query!(
+        r#"
+            SELECT n.id, n.note, u.username AS "author?", n.created_at
+            FROM command_note n
+            LEFT JOIN auth_user u ON u.id = n.user_id
+            WHERE n.command_id = $1
+            ORDER BY n.created_at, n.id
+        "#,
+        id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| CommandNote {
+        id: x.id,
+        note: x.note,
+        author: x.author,
+        created_at: x.created_at,
+    })
+    .collect();
+
+    Ok(CommandAnnotations {
+        command_id: id,
+        tags,
+        notes,
+    })
+}
+
+/// The error for the record `id` of `what`, e.g. `Banner`, when it does not exist.
+pub(crate) fn not_found(what: &str, id: impl std::fmt::Display) -> FieldError {
+    FieldError::new(format!("{} {} not found", what, id), Value::null())
+}
+
+/// The error for arguments that failed validation, with the `reason` they are invalid.
+pub(crate) fn invalid(reason: impl std::fmt::Display) -> FieldError {
+    FieldError::new(reason, Value::null())
+}
+
+/// Checks that the `name` of a `what` is 1 to `max_len` bytes, and not only whitespace.
+pub(crate) fn validate_name(what: &str, name: &str, max_len: usize) -> Result<(), String> {
+    if name.trim().is_empty() || name.len() > max_len {
+        return Err(format!(
+            "Invalid {} name {:?}, names are 1 to {} characters",
+            what, name, max_len
+        ));
+    }
+
+    Ok(())
+}
+
+/// The limit to apply to a paged query, capped at `max` rows.
+fn page_limit(limit: Option<i32>, max: u32) -> i32 {
+    let max = i32::try_from(max).unwrap_or(i32::MAX);
+
+    limit.unwrap_or(max).max(0).min(max)
+}
+
+fn normalize_tag(x: &str) -> String {
+    x.trim().to_lowercase()
+}
+
+fn valid_tag(x: &str) -> bool {
+    !x.is_empty()
+        && x.len() <= 64
+        && x.chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Change feed cursors are the `(txid, id)` of the last change read.
+fn format_cursor(txid: i64, id: i64) -> String {
+    format!("{}:{}", txid, id)
+}
+
+fn parse_cursor(x: &str) -> Result<(i64, i64), FieldError> {
+    let mut parts = x.splitn(2, ':').map(|p| p.parse::<i64>().ok());
+
+    match (parts.next().flatten(), parts.next().flatten()) {
+        (Some(txid), Some(id)) if txid >= 0 && id >= 0 => Ok((txid, id)),
+        _ => Err(FieldError::new(
+            format!("Invalid cursor {}", x),
+            Value::null(),
+        )),
+    }
+}
+
+/// Parameter names are passed straight to `lctl get_param`,
+/// so restrict them to a single, wildcard-free path component.
+fn valid_param_key(x: &str) -> bool {
+    !x.is_empty()
+        && x.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// The `lctl` parameter namespace a target lives under.
+fn param_prefix(name: &str) -> &'static str {
+    if name == "MGS" {
+        "mgs"
+    } else if name.contains("-MDT") {
+        "mdt"
+    } else {
+        "obdfilter"
+    }
+}
+
 fn validate_snapshot_name(x: &str) -> Result<(), FieldError> {
     if x.contains(' ') {
         Err(FieldError::new(
@@ -1369,6 +3873,194 @@ fn validate_snapshot_name(x: &str) -> Result<(), FieldError> {
     }
 }
 
+/// The MDT reserve leaves some space free, and the change threshold
+/// is at most the whole filesystem.
+fn validate_thresholds(
+    mdt_reserve_percent: Option<i32>,
+    change_percent: Option<i32>,
+) -> Result<(), String> {
+    if let Some(x) = mdt_reserve_percent {
+        if x < 1 || x > 99 {
+            return Err(format!("MDT reserve must be 1 to 99 percent, got {}", x));
+        }
+    }
+
+    if let Some(x) = change_percent {
+        if x < 1 || x > 100 {
+            return Err(format!(
+                "Change threshold must be 1 to 100 percent, got {}",
+                x
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether an upsert guarded by the version the client `expected` went through.
+/// `inserted` is `None` when the guard let no row be written, and `Some(true)` when a new
+/// record was created, which only counts when the client did not expect one to exist.
+fn upserted(inserted: Option<bool>, expected: Option<i32>) -> bool {
+    match inserted {
+        Some(true) => expected.is_none(),
+        Some(false) => true,
+        None => false,
+    }
+}
+
+/// A `CONFLICT` error for a versioned record the client `expected` to be at a version
+/// it no longer is. The extensions carry the `current` version and record, if it still exists.
+fn version_conflict(
+    what: &str,
+    expected: Option<i32>,
+    current: Option<(i32, serde_json::Value)>,
+) -> FieldError {
+    let msg = match (current.as_ref().map(|x| x.0), expected) {
+        (Some(c), Some(e)) => format!(
+            "{} was modified concurrently (expected version {}, found {})",
+            what, e, c
+        ),
+        (Some(_), None) => format!(
+            "{} already exists, pass its current version to update it",
+            what
+        ),
+        (None, _) => format!("{} no longer exists", what),
+    };
+
+    let (version, record) = match current {
+        Some((v, x)) => (Value::scalar(v), json_to_value(x)),
+        None => (Value::null(), Value::null()),
+    };
+
+    let mut ext = juniper::Object::with_capacity(3);
+    ext.add_field("code", Value::scalar("CONFLICT"));
+    ext.add_field("current_version", version);
+    ext.add_field("current", record);
+
+    FieldError::new(msg, Value::Object(ext))
+}
+
+/// Converts JSON to a GraphQL value, for error extensions.
+/// Numbers that don't fit an `Int` become a `Float`.
+fn json_to_value(x: serde_json::Value) -> Value {
+    match x {
+        serde_json::Value::Null => Value::null(),
+        serde_json::Value::Bool(x) => Value::scalar(x),
+        serde_json::Value::Number(x) => match x.as_i64().map(i32::try_from) {
+            Some(Ok(x)) => Value::scalar(x),
+            _ => Value::scalar(x.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(x) => Value::scalar(x),
+        serde_json::Value::Array(xs) => Value::list(xs.into_iter().map(json_to_value).collect()),
+        serde_json::Value::Object(xs) => {
+            Value::object(xs.into_iter().map(|(k, v)| (k, json_to_value(v))).collect())
+        }
+    }
+}
+
+/// The snapshot retention policies, of every filesystem or of `fsname`.
+async fn get_snapshot_retentions(
+    pool: &PgPool,
+    fsname: Option<&str>,
+) -> Result<Vec<SnapshotRetention>, sqlx::Error> {
+    sqlx::query_as!(
+        SnapshotRetention,
+        r#"
+            SELECT
+                id,
+                filesystem_name,
+                reserve_value,
+                reserve_unit as "reserve_unit:ReserveUnit",
+                last_run,
+                keep_num,
+                version,
+                updated_at,
+                crowded_by_manual,
+                mdt_reserve_percent,
+                change_percent
+            FROM snapshot_retention
+            WHERE $1::TEXT IS NULL OR filesystem_name = $1
+        "#,
+        fsname
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// The server profiles with their repositories, all of them or the one named `name`.
+async fn get_server_profiles(
+    pool: &PgPool,
+    name: Option<&str>,
+) -> Result<Vec<ServerProfile>, sqlx::Error> {
+    let server_profile_records = sqlx::query!(
+        r#"
+            SELECT jsonb_agg((r.repo_name, r.location))
+                AS repos, sp.*
+                FROM chroma_core_repo AS r
+                INNER JOIN chroma_core_serverprofile_repolist AS rl ON r.repo_name = rl.repo_id
+                INNER JOIN chroma_core_serverprofile AS sp ON rl.serverprofile_id = sp.name
+                WHERE $1::TEXT IS NULL OR sp.name = $1
+                GROUP BY sp.name;
+        "#,
+        name
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let server_profiles = server_profile_records
+        .into_iter()
+        .filter_map(|spr| {
+            // TODO: Try to derive this somehow
+            let record = ServerProfileRecord {
+                corosync: spr.corosync,
+                corosync2: spr.corosync2,
+                default: spr.default,
+                initial_state: spr.initial_state,
+                managed: spr.managed,
+                name: spr.name,
+                ntp: spr.ntp,
+                pacemaker: spr.pacemaker,
+                ui_description: spr.ui_description,
+                ui_name: spr.ui_name,
+                user_selectable: spr.user_selectable,
+                worker: spr.worker,
+                version: spr.version,
+                updated_at: spr.updated_at,
+            };
+            let repos = spr.repos?;
+            ServerProfile::new(record, &repos).ok()
+        })
+        .collect();
+
+    Ok(server_profiles)
+}
+
+/// Sets whether the snapshot `name` of `fsname` is held.
+async fn set_snapshot_held(
+    pool: &PgPool,
+    fsname: &str,
+    name: &str,
+    held: bool,
+) -> Result<(), juniper::FieldError> {
+    let x = sqlx::query!(
+        "UPDATE snapshot SET held = $3 WHERE filesystem_name = $1 AND snapshot_name = $2",
+        fsname,
+        name,
+        held
+    )
+    .execute(pool)
+    .await?;
+
+    if x.rows_affected() == 0 {
+        return Err(FieldError::new(
+            format!("Snapshot {} of {} not found", name, fsname),
+            Value::null(),
+        ));
+    }
+
+    Ok(())
+}
+
 async fn fs_id_by_name(pool: &PgPool, name: &str) -> Result<i32, juniper::FieldError> {
     sqlx::query!(
         "SELECT id FROM chroma_core_managedfilesystem WHERE name=$1 and not_deleted = 't'",
@@ -1377,10 +4069,10 @@ async fn fs_id_by_name(pool: &PgPool, name: &str) -> Result<i32, juniper::FieldE
     .fetch_optional(pool)
     .await?
     .map(|x| x.id)
-    .ok_or_else(|| FieldError::new(format!("Filesystem {} not found", name), Value::null()))
+    .ok_or_else(|| not_found("Filesystem", name))
 }
 
-async fn insert_task(
+pub(crate) async fn insert_task(
     name: &str,
     state: &str,
     single_runner: bool,
@@ -1437,7 +4129,11 @@ async fn insert_task(
     Ok(x)
 }
 
-async fn insert_fidlist(fids: Vec<String>, task_id: i32, pool: &PgPool) -> Result<(), ImlApiError> {
+pub(crate) async fn insert_fidlist(
+    fids: Vec<String>,
+    task_id: i32,
+    pool: &PgPool,
+) -> Result<(), ImlApiError> {
     let x = fids
         .iter()
         .map(|fid| LustreFid::from_str(&fid))
@@ -1480,17 +4176,38 @@ async fn insert_fidlist(fids: Vec<String>, task_id: i32, pool: &PgPool) -> Resul
     Ok(())
 }
 
+/// The kwargs of a `run_jobs` call for a command with `message`, whose jobs run at `priority`.
+/// Commands users start are `High`, so background work does not hold them up.
+/// They have to go through `approval::check`, or `approval::internal` when IML itself
+/// dispatches the command, or the job scheduler refuses jobs of classes with an approval rule.
+pub(crate) fn run_jobs_kwargs(
+    message: impl ToString,
+    priority: JobPriority,
+) -> HashMap<String, String> {
+    vec![
+        ("message".into(), message.to_string()),
+        ("priority".into(), priority.to_string()),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Dispatches `jobs` as a command with `msg`, unless they need approval.
 async fn run_jobs<T: std::fmt::Debug + serde::Serialize>(
+    context: &Context,
     msg: impl ToString,
     jobs: Vec<SendJob<'_, T>>,
-    rabbit_pool: &Pool,
-) -> Result<i32, ImlApiError> {
-    let kwargs: HashMap<String, String> = vec![("message".into(), msg.to_string())]
-        .into_iter()
-        .collect();
+) -> juniper::FieldResult<i32> {
+    let kwargs = run_jobs_kwargs(msg, JobPriority::High);
+
+    let kwargs = approval::check(context, &jobs, kwargs).await?;
 
     let id: i32 = iml_job_scheduler_rpc::call(
-        &rabbit_pool.get().await.map_err(ImlRabbitError::PoolError)?,
+        &context
+            .rabbit_pool
+            .get()
+            .await
+            .map_err(ImlRabbitError::PoolError)?,
         "run_jobs",
         vec![jobs],
         Some(kwargs),
@@ -1510,32 +4227,175 @@ fn create_task_job<'a>(task_id: i32) -> SendJob<'a, HashMap<String, serde_json::
     }
 }
 
-async fn client_mount_source(pg_pool: &PgPool, fs_name: &str) -> Result<String, ImlApiError> {
-    let nids = sqlx::query!(
-        r#"
-            SELECT n.nid FROM target AS t
-            INNER JOIN lnet as l ON l.host_id = ANY(t.host_ids)
-            INNER JOIN nid as n ON n.id = ANY(l.nids)
-            WHERE t.name='MGS' AND $1 = ANY(t.filesystems)
-            AND n.host_id NOT IN (
-                SELECT nh.host_id
-                FROM corosync_resource_bans b
-                INNER JOIN corosync_node_managed_host nh ON (nh.corosync_node_id).name = b.node
-                AND nh.cluster_id = b.cluster_id
-                INNER JOIN corosync_resource r ON r.name = b.resource AND b.cluster_id = r.cluster_id
-                WHERE r.mount_point is not NULL AND r.mount_point = t.mount_path
-            )
-            GROUP BY l.host_id, n.nid ORDER BY l.host_id, n.nid
-            "#,
-        fs_name
-    )
-    .fetch_all(pg_pool)
-    .await?
-    .into_iter()
-    .map(|x| x.nid)
-    .collect::<Vec<String>>();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upserted() {
+        assert!(upserted(Some(true), None));
+        assert!(upserted(Some(false), Some(3)));
+        assert!(!upserted(None, Some(2)));
+        assert!(!upserted(None, None));
+        assert!(!upserted(Some(true), Some(1)));
+    }
+
+    #[test]
+    fn test_version_conflict() {
+        let x = version_conflict("x", Some(2), Some((3, serde_json::json!({ "version": 3 }))));
+
+        assert_eq!(
+            x.message(),
+            "x was modified concurrently (expected version 2, found 3)"
+        );
+
+        let ext = x.extensions().as_object_value().unwrap();
+
+        assert_eq!(
+            ext.get_field_value("code"),
+            Some(&Value::scalar("CONFLICT"))
+        );
+        assert_eq!(
+            ext.get_field_value("current_version"),
+            Some(&Value::scalar(3))
+        );
+        assert_eq!(
+            ext.get_field_value("current")
+                .and_then(|x| x.as_object_value())
+                .and_then(|x| x.get_field_value("version")),
+            Some(&Value::scalar(3))
+        );
+
+        let x = version_conflict("x", Some(1), None);
+
+        assert_eq!(x.message(), "x no longer exists");
+        assert_eq!(
+            x.extensions()
+                .as_object_value()
+                .and_then(|x| x.get_field_value("current")),
+            Some(&Value::null())
+        );
+    }
+
+    #[test]
+    fn test_diff_profiles() {
+        let record = ServerProfileRecord {
+            corosync: false,
+            corosync2: true,
+            default: false,
+            initial_state: "managed".into(),
+            managed: true,
+            name: "base_managed_rh7".into(),
+            ntp: true,
+            pacemaker: true,
+            ui_description: "A storage server".into(),
+            ui_name: "Managed Storage Server".into(),
+            user_selectable: true,
+            worker: false,
+            version: 1,
+            updated_at: Utc::now(),
+        };
+
+        let set = |xs: &[&str]| xs.iter().map(|x| x.to_string()).collect::<BTreeSet<_>>();
+
+        let a = ProfileDetails {
+            record: record.clone(),
+            packages: set(&["lustre", "python2-iml-agent"]),
+            repos: set(&["lustre-server"]),
+        };
+
+        let b = ProfileDetails {
+            record: ServerProfileRecord {
+                name: "custom".into(),
+                pacemaker: false,
+                ..record
+            },
+            packages: set(&["lustre", "kmod-zfs"]),
+            repos: set(&["lustre-server"]),
+        };
+
+        let x = diff_profiles(&a, &b);
+
+        assert_eq!(x.a, "base_managed_rh7");
+        assert_eq!(x.b, "custom");
+
+        let differs: Vec<_> = x
+            .settings
+            .iter()
+            .filter(|x| x.differs)
+            .map(|x| (x.name.as_str(), x.a.as_str(), x.b.as_str()))
+            .collect();
+
+        assert_eq!(differs, vec![("Pacemaker", "true", "false")]);
+
+        assert_eq!(
+            x.packages,
+            ProfileListDiff {
+                both: vec!["lustre".into()],
+                only_a: vec!["python2-iml-agent".into()],
+                only_b: vec!["kmod-zfs".into()],
+            }
+        );
+        assert_eq!(
+            x.repos,
+            ProfileListDiff {
+                both: vec!["lustre-server".into()],
+                ..ProfileListDiff::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_thresholds() {
+        assert_eq!(validate_thresholds(None, None), Ok(()));
+        assert_eq!(validate_thresholds(Some(10), Some(100)), Ok(()));
+        assert!(validate_thresholds(Some(0), None).is_err());
+        assert!(validate_thresholds(Some(100), None).is_err());
+        assert!(validate_thresholds(None, Some(0)).is_err());
+        assert!(validate_thresholds(None, Some(101)).is_err());
+    }
 
-    let mount_command = format!("{}:/{}", nids.join(":"), fs_name);
+    #[test]
+    fn test_valid_param_key() {
+        assert!(valid_param_key("recovery_status"));
+        assert!(valid_param_key("tot_granted"));
+        assert!(valid_param_key("job_stats.foo"));
+        assert!(!valid_param_key(""));
+        assert!(!valid_param_key("*"));
+        assert!(!valid_param_key("recovery_status; rm -rf /"));
+    }
+
+    #[test]
+    fn test_valid_tag() {
+        assert!(valid_tag(&normalize_tag(" Intentional ")));
+        assert!(valid_tag("ticket-1234"));
+        assert!(!valid_tag(""));
+        assert!(!valid_tag("two words"));
+        assert!(!valid_tag(&"x".repeat(65)));
+    }
+
+    #[test]
+    fn test_param_prefix() {
+        assert_eq!(param_prefix("MGS"), "mgs");
+        assert_eq!(param_prefix("fs-MDT0000"), "mdt");
+        assert_eq!(param_prefix("fs-OST0001"), "obdfilter");
+    }
+
+    #[test]
+    fn test_page_limit() {
+        assert_eq!(page_limit(None, 1000), 1000);
+        assert_eq!(page_limit(Some(50), 1000), 50);
+        assert_eq!(page_limit(Some(5000), 1000), 1000);
+        assert_eq!(page_limit(Some(-1), 1000), 0);
+        assert_eq!(page_limit(None, u32::MAX), i32::MAX);
+    }
 
-    Ok(mount_command)
+    #[test]
+    fn test_parse_cursor() {
+        assert_eq!(parse_cursor("5012:1234").unwrap(), (5012, 1234));
+        assert_eq!(parse_cursor(&format_cursor(7, 3)).unwrap(), (7, 3));
+        assert!(parse_cursor("1234").is_err());
+        assert!(parse_cursor("-1:5").is_err());
+        assert!(parse_cursor("abc:def").is_err());
+    }
 }
@@ -0,0 +1,658 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Adding MDTs and OSTs to existing filesystems.
+//!
+//! A new target is formatted, added as a resource to the pacemaker cluster of its server,
+//! and started. Until the manager learns of it, it is listed in `pending_target`, which also
+//! reserves its index against other targets being added at the same time.
+
+use crate::{
+    command::get_command,
+    graphql::{
+        audit,
+        compatibility::check_new_server,
+        filesystem::get_target_idx,
+        run_jobs,
+        stratagem::{classify_with_new_osts, invalid, not_found, rebalance_jobs},
+        Context, SendJob,
+    },
+};
+use iml_postgres::sqlx;
+use iml_wire_types::{
+    new_target::{AddOstResult, OstSpecInput},
+    Command,
+};
+use juniper::{FieldError, Value};
+use std::collections::{HashMap, HashSet};
+
+/// The most OSTs a single command adds
+const MAX_NEW_OSTS: usize = 32;
+/// How many percentage points an OST has to be above the filesystem usage
+/// for the filesystem to be rebalanced once new OSTs are added
+const REBALANCE_THRESHOLD: f64 = 5.0;
+/// Files smaller than this are left where they are by a rebalance
+const REBALANCE_MIN_SIZE: u64 = 1024 * 1024;
+
+type Job = SendJob<'static, HashMap<String, serde_json::Value>>;
+
+/// A server a new target is formatted on
+struct Placement {
+    host_id: i32,
+    /// The pacemaker node name of the server
+    node: String,
+    /// The other servers of its cluster
+    failover_fqdns: Vec<String>,
+}
+
+/// The target names of a filesystem, the known ones and those being added
+struct FsTargets {
+    names: Vec<String>,
+    started: HashSet<String>,
+    mgs_host_ids: Vec<i32>,
+}
+
+impl FsTargets {
+    /// The index the next target with names containing `marker`, e.g. `-OST`, gets.
+    fn next_index(&self, marker: &str) -> i32 {
+        next_index(
+            self.names
+                .iter()
+                .filter(|x| x.contains(marker))
+                .map(|x| x.as_str()),
+        )
+    }
+
+    /// Errors unless each of `names` is started
+    fn check_started(&self, fs_name: &str, names: &[&str], what: &str) -> Result<(), FieldError> {
+        match names.iter().find(|x| !self.started.contains(**x)) {
+            Some(name) => Err(FieldError::new(
+                format!("{} of {} has to be started to add {}", name, fs_name, what),
+                Value::null(),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The index a new target gets, one past the highest of `names`.
+fn next_index<'a>(names: impl IntoIterator<Item = &'a str>) -> i32 {
+    names
+        .into_iter()
+        .filter_map(get_target_idx)
+        .max()
+        .map_or(0, |x| x + 1)
+}
+
+/// Checks `device` is a path a target can be formatted on.
+fn validate_device(device: &str) -> Result<(), String> {
+    if !device.starts_with("/dev/") || device.chars().any(|c| c.is_whitespace() || c == ',') {
+        return Err(format!(
+            "Invalid device {:?}, expected a path below /dev",
+            device
+        ));
+    }
+
+    Ok(())
+}
+
+fn target_name(fs_name: &str, kind: &str, index: i32) -> String {
+    format!("{}-{}{:04x}", fs_name, kind, index)
+}
+
+async fn fs_targets(context: &Context, fs_name: &str) -> Result<FsTargets, FieldError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT name, active_host_id, host_ids
+            FROM target
+            WHERE $1 = ANY(filesystems)
+        "#,
+        fs_name
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    let pending = sqlx::query!(
+        "SELECT name FROM pending_target WHERE fs_name = $1",
+        fs_name
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    let mgs_host_ids = xs
+        .iter()
+        .find(|x| x.name == "MGS")
+        .map(|x| x.host_ids.clone())
+        .unwrap_or_default();
+
+    let started = xs
+        .iter()
+        .filter(|x| x.active_host_id.is_some())
+        .map(|x| x.name.clone())
+        .collect();
+
+    let names = xs
+        .into_iter()
+        .map(|x| x.name)
+        .chain(pending.into_iter().map(|x| x.name))
+        .collect();
+
+    Ok(FsTargets {
+        names,
+        started,
+        mgs_host_ids,
+    })
+}
+
+/// The NIDs of each server of the MGS of `fs_name`, comma separated
+async fn mgs_nids(
+    context: &Context,
+    fs_name: &str,
+    mgs_host_ids: &[i32],
+) -> Result<Vec<String>, FieldError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT
+                lc.host_id,
+                string_agg(
+                    ni.inet4_address || '@' || n.lnd_type || COALESCE(n.lnd_network::TEXT, ''),
+                    ',' ORDER BY ni.id
+                ) AS "nids!"
+            FROM chroma_core_nid n
+            INNER JOIN chroma_core_networkinterface ni ON ni.id = n.network_interface_id
+            INNER JOIN chroma_core_lnetconfiguration lc ON lc.id = n.lnet_configuration_id
+            WHERE lc.host_id = ANY($1)
+            GROUP BY lc.host_id
+            ORDER BY lc.host_id
+        "#,
+        mgs_host_ids
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    if xs.len() != mgs_host_ids.len() {
+        return Err(FieldError::new(
+            format!("The NIDs of the MGS servers of {} are not known", fs_name),
+            Value::null(),
+        ));
+    }
+
+    Ok(xs.into_iter().map(|x| x.nids).collect())
+}
+
+/// Checks a target of `fs_name` can be formatted on `device` of `host_id`: the device is not used
+/// by another target, the host is a node of a pacemaker cluster, and its Lustre release is
+/// supported alongside the other servers and clients of the filesystem.
+async fn placement(
+    context: &Context,
+    fs_name: &str,
+    host_id: i32,
+    device: &str,
+) -> Result<Placement, FieldError> {
+    validate_device(device).map_err(invalid)?;
+
+    let host = context
+        .loaders
+        .hosts
+        .load(host_id)
+        .await?
+        .ok_or_else(|| not_found("Host", host_id))?;
+
+    let used = sqlx::query!(
+        r#"
+            SELECT name FROM target WHERE dev_path = $1 AND $2 = ANY(host_ids)
+            UNION ALL
+            SELECT name FROM pending_target WHERE dev_path = $1 AND host_id = $2
+        "#,
+        device,
+        host_id
+    )
+    .fetch_optional(&context.pg_pool)
+    .await?;
+
+    if let Some(x) = used {
+        return Err(FieldError::new(
+            format!(
+                "{} on {} is already used by {}",
+                device,
+                host.fqdn,
+                x.name.unwrap_or_default()
+            ),
+            Value::null(),
+        ));
+    }
+
+    let nodes = sqlx::query!(
+        r#"
+            SELECT h.id, h.fqdn, (nmh.corosync_node_id).name AS "node!"
+            FROM corosync_node_managed_host nmh
+            INNER JOIN chroma_core_managedhost h ON h.id = nmh.host_id AND h.not_deleted = 't'
+            WHERE nmh.cluster_id = (
+                SELECT cluster_id FROM corosync_node_managed_host WHERE host_id = $1
+            )
+            ORDER BY h.fqdn
+        "#,
+        host_id
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    let node = nodes
+        .iter()
+        .find(|x| x.id == host_id)
+        .map(|x| x.node.clone())
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("{} is not a node of a pacemaker cluster", host.fqdn),
+                Value::null(),
+            )
+        })?;
+
+    let issues = check_new_server(context, fs_name, host_id, host.fqdn.clone()).await?;
+
+    if !issues.is_empty() {
+        return Err(FieldError::new(
+            format!(
+                "{} can not serve {}: {}",
+                host.fqdn,
+                fs_name,
+                issues.join("; ")
+            ),
+            Value::null(),
+        ));
+    }
+
+    Ok(Placement {
+        host_id,
+        node,
+        failover_fqdns: nodes
+            .into_iter()
+            .filter(|x| x.id != host_id)
+            .map(|x| x.fqdn)
+            .collect(),
+    })
+}
+
+fn new_target_job(
+    class_name: &'static str,
+    fs_name: &str,
+    index: i32,
+    device: &str,
+    mgs_nids: &[String],
+    placement: &Placement,
+) -> Job {
+    SendJob {
+        class_name,
+        args: vec![
+            ("host_id".to_string(), serde_json::json!(placement.host_id)),
+            ("filesystem".to_string(), serde_json::json!(fs_name)),
+            ("index".to_string(), serde_json::json!(index)),
+            ("device".to_string(), serde_json::json!(device)),
+            ("mgs_nids".to_string(), serde_json::json!(mgs_nids)),
+            ("node".to_string(), serde_json::json!(placement.node)),
+            (
+                "failover_fqdns".to_string(),
+                serde_json::json!(placement.failover_fqdns),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    }
+}
+
+/// A target being added, as listed in `pending_target`
+struct NewTarget<'a> {
+    name: String,
+    host_id: i32,
+    device: &'a str,
+}
+
+/// Reserves the names of `xs` in `pending_target`, runs `jobs` and records the command on them.
+/// Nothing is reserved when the command could not be started.
+async fn run_new_target_jobs(
+    context: &Context,
+    fs_name: &str,
+    xs: &[NewTarget<'_>],
+    msg: String,
+    jobs: Vec<Job>,
+) -> Result<Command, FieldError> {
+    let names: Vec<_> = xs.iter().map(|x| x.name.clone()).collect();
+    let host_ids: Vec<_> = xs.iter().map(|x| x.host_id).collect();
+    let devices: Vec<_> = xs.iter().map(|x| x.device.to_string()).collect();
+
+    let ids: Vec<i32> = sqlx::query!(
+        r#"
+            INSERT INTO pending_target (name, fs_name, host_id, dev_path)
+            SELECT name, $2, host_id, dev_path
+            FROM UNNEST($1::TEXT[], $3::INT[], $4::TEXT[]) AS x(name, host_id, dev_path)
+            ON CONFLICT DO NOTHING
+            RETURNING id
+        "#,
+        &names,
+        fs_name,
+        &host_ids,
+        &devices
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| x.id)
+    .collect();
+
+    let unreserve = || async {
+        sqlx::query!("DELETE FROM pending_target WHERE id = ANY($1)", &ids)
+            .execute(&context.pg_pool)
+            .await
+    };
+
+    if ids.len() != xs.len() {
+        unreserve().await?;
+
+        return Err(FieldError::new(
+            format!(
+                "Targets of {} are being added concurrently, try again",
+                fs_name
+            ),
+            Value::null(),
+        ));
+    }
+
+    let command_id = match run_jobs(context, msg, jobs).await {
+        Ok(x) => x,
+        Err(e) => {
+            unreserve().await?;
+
+            return Err(e);
+        }
+    };
+
+    sqlx::query!(
+        "UPDATE pending_target SET command_id = $1 WHERE id = ANY($2)",
+        command_id,
+        &ids
+    )
+    .execute(&context.pg_pool)
+    .await?;
+
+    // The command may have failed before it was recorded
+    sqlx::query!(
+        r#"
+            DELETE FROM pending_target p
+            USING chroma_core_command c
+            WHERE p.command_id = c.id AND c.id = $1 AND c.complete AND (c.errored OR c.cancelled)
+        "#,
+        command_id
+    )
+    .execute(&context.pg_pool)
+    .await?;
+
+    let command = get_command(&context.pg_pool, command_id).await?;
+
+    Ok(command)
+}
+
+/// Adds an MDT on `device` of `host_id` to `fs_name` with an `AddMdtJob`, which formats it,
+/// creates its pacemaker resource in the cluster of the host, and starts it. The MDT registers
+/// with the MGS the first time it is started.
+///
+/// The MGS and MDT0000 of the filesystem have to be started, as a new MDT joins the namespace
+/// through them, and the Lustre release of the host has to be supported alongside the other
+/// servers and clients of the filesystem.
+pub(crate) async fn add_mdt(
+    context: &Context,
+    fs_name: String,
+    host_id: i32,
+    device: String,
+) -> juniper::FieldResult<Command> {
+    let targets = fs_targets(context, &fs_name).await?;
+
+    let mdt0 = target_name(&fs_name, "MDT", 0);
+
+    targets.check_started(&fs_name, &["MGS", mdt0.as_str()], "an MDT")?;
+
+    let placement = placement(context, &fs_name, host_id, &device).await?;
+
+    let mgs_nids = mgs_nids(context, &fs_name, &targets.mgs_host_ids).await?;
+
+    let index = targets.next_index("-MDT");
+
+    let job = new_target_job("AddMdtJob", &fs_name, index, &device, &mgs_nids, &placement);
+
+    let new = NewTarget {
+        name: target_name(&fs_name, "MDT", index),
+        host_id,
+        device: &device,
+    };
+
+    let command = run_new_target_jobs(
+        context,
+        &fs_name,
+        &[new],
+        format!("Add MDT{:04x} to {}", index, fs_name),
+        vec![job],
+    )
+    .await?;
+
+    audit::record(
+        context,
+        "filesystem.addMdt",
+        serde_json::json!({
+            "fsName": fs_name,
+            "hostId": host_id,
+            "device": device,
+            "index": index,
+        }),
+        Some(command.id),
+    )
+    .await;
+
+    Ok(command)
+}
+
+/// Checks the new OSTs of a single command
+fn validate_ost_specs(specs: &[OstSpecInput]) -> Result<(), String> {
+    if specs.is_empty() || specs.len() > MAX_NEW_OSTS {
+        return Err(format!("Between 1 and {} OSTs can be added", MAX_NEW_OSTS));
+    }
+
+    let mut seen = HashSet::new();
+
+    if let Some(x) = specs
+        .iter()
+        .find(|x| !seen.insert((x.host_id, x.device.as_str())))
+    {
+        return Err(format!(
+            "{} of host {} is given more than once",
+            x.device, x.host_id
+        ));
+    }
+
+    Ok(())
+}
+
+fn rebalance_hint(fs_name: &str, over: &[u32]) -> Option<String> {
+    if over.is_empty() {
+        return None;
+    }
+
+    let names: Vec<_> = over
+        .iter()
+        .map(|x| target_name(fs_name, "OST", *x as i32))
+        .collect();
+
+    Some(format!(
+        "{} will be more than {}% fuller than the rest of {}. \
+         Run rebalanceOsts once the new OSTs are started to move files onto them",
+        names.join(", "),
+        REBALANCE_THRESHOLD,
+        fs_name
+    ))
+}
+
+/// Adds OSTs to `fs_name` in a single command, with an `AddOstJob` for each,
+/// which formats it, creates its pacemaker resource in the cluster of its host, and starts it.
+/// The new OSTs get consecutive indexes after the highest OST of the filesystem.
+///
+/// When `rebalance` is set and the existing OSTs are over-full compared to the filesystem with
+/// the new OSTs, a migration task moving files onto the new OSTs is created once they all started.
+/// Otherwise, the over-full OSTs are named in `rebalanceHint`.
+pub(crate) async fn add_ost(
+    context: &Context,
+    fs_id: i32,
+    fs_name: String,
+    specs: Vec<OstSpecInput>,
+    rebalance: bool,
+) -> juniper::FieldResult<AddOstResult> {
+    validate_ost_specs(&specs).map_err(invalid)?;
+
+    let targets = fs_targets(context, &fs_name).await?;
+
+    targets.check_started(&fs_name, &["MGS"], "OSTs")?;
+
+    let mgs_nids = mgs_nids(context, &fs_name, &targets.mgs_host_ids).await?;
+
+    let first = targets.next_index("-OST");
+
+    let mut jobs = vec![];
+    let mut new = vec![];
+
+    for (index, spec) in (first..).zip(specs.iter()) {
+        let placement = placement(context, &fs_name, spec.host_id, &spec.device).await?;
+
+        jobs.push(new_target_job(
+            "AddOstJob",
+            &fs_name,
+            index,
+            &spec.device,
+            &mgs_nids,
+            &placement,
+        ));
+
+        new.push(NewTarget {
+            name: target_name(&fs_name, "OST", index),
+            host_id: spec.host_id,
+            device: &spec.device,
+        });
+    }
+
+    let indexes: Vec<_> = (first..first + new.len() as i32)
+        .map(|x| x as u32)
+        .collect();
+
+    let mut rebalance_hint_msg = None;
+    let mut rebalanced = false;
+
+    if rebalance {
+        let (over, under) =
+            classify_with_new_osts(context, &fs_name, &indexes, REBALANCE_THRESHOLD).await?;
+
+        if !over.is_empty() && !under.is_empty() {
+            let offset = jobs.len();
+
+            jobs.extend(
+                rebalance_jobs(
+                    &context.pg_pool,
+                    fs_id,
+                    &fs_name,
+                    &over,
+                    &under,
+                    REBALANCE_MIN_SIZE,
+                    offset,
+                )
+                .await?,
+            );
+
+            rebalanced = true;
+        }
+    } else if let Ok((over, _)) =
+        classify_with_new_osts(context, &fs_name, &indexes, REBALANCE_THRESHOLD).await
+    {
+        rebalance_hint_msg = rebalance_hint(&fs_name, &over);
+    }
+
+    let msg = match new.as_slice() {
+        [x] => format!("Add {}", x.name),
+        xs => format!("Add {} OSTs to {}", xs.len(), fs_name),
+    };
+
+    let msg = if rebalanced {
+        format!("{} and rebalance", msg)
+    } else {
+        msg
+    };
+
+    let command = run_new_target_jobs(context, &fs_name, &new, msg, jobs).await?;
+
+    let names: Vec<_> = new.into_iter().map(|x| x.name).collect();
+
+    audit::record(
+        context,
+        "filesystem.addOst",
+        serde_json::json!({
+            "fsName": fs_name,
+            "specs": specs,
+            "targets": names,
+            "rebalance": rebalanced,
+        }),
+        Some(command.id),
+    )
+    .await;
+
+    Ok(AddOstResult {
+        command,
+        targets: names,
+        rebalance_hint: rebalance_hint_msg,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_index() {
+        assert_eq!(next_index(vec![]), 0);
+        assert_eq!(next_index(vec!["fs-MDT0000"]), 1);
+        assert_eq!(
+            next_index(vec!["fs-MDT0000", "fs-MDT000a", "fs-MDT0003"]),
+            11
+        );
+    }
+
+    #[test]
+    fn test_validate_device() {
+        assert_eq!(validate_device("/dev/mapper/mpathd"), Ok(()));
+        assert!(validate_device("mpathd").is_err());
+        assert!(validate_device("/tmp/mdt").is_err());
+        assert!(validate_device("/dev/sdb /dev/sdc").is_err());
+    }
+
+    #[test]
+    fn test_validate_ost_specs() {
+        let spec = |host_id, device: &str| OstSpecInput {
+            host_id,
+            device: device.into(),
+        };
+
+        assert_eq!(
+            validate_ost_specs(&[spec(1, "/dev/sdb"), spec(2, "/dev/sdb")]),
+            Ok(())
+        );
+        assert!(validate_ost_specs(&[]).is_err());
+        assert!(validate_ost_specs(&[spec(1, "/dev/sdb"), spec(1, "/dev/sdb")]).is_err());
+
+        let xs: Vec<_> = (0..=MAX_NEW_OSTS as i32)
+            .map(|x| spec(x, "/dev/sdb"))
+            .collect();
+        assert!(validate_ost_specs(&xs).is_err());
+    }
+
+    #[test]
+    fn test_rebalance_hint() {
+        assert_eq!(rebalance_hint("fs", &[]), None);
+
+        let hint = rebalance_hint("fs", &[0, 10]).unwrap();
+        assert!(hint.starts_with("fs-OST0000, fs-OST000a will be more than 5% fuller"));
+    }
+}
@@ -0,0 +1,402 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Custom roles.
+//!
+//! The fixed groups still decide what a user may do by default: superusers may do anything,
+//! and filesystem administrators anything but managing roles. Roles grant further permissions
+//! on top of the group, so filesystem users can be allowed to e.g. take snapshots without
+//! being made administrators. Mutations covered by a `Permission` check it with `require`.
+
+use crate::{
+    error::ImlApiError,
+    graphql::{audit, invalid, not_found, validate_name, Context},
+};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::role::{Permission, Role};
+use juniper::{FieldError, Value};
+use std::{collections::BTreeSet, str::FromStr as _};
+
+const MAX_NAME_LEN: usize = 64;
+
+/// The permissions members of a fixed group have without any role.
+fn group_permissions(group: &str) -> &'static [Permission] {
+    match group {
+        "superusers" => &Permission::ALL,
+        "filesystem_administrators" => &[
+            Permission::CreateSnapshots,
+            Permission::DeleteSnapshots,
+            Permission::ManageSnapshotPolicies,
+            Permission::RunStratagem,
+            Permission::ManageBanners,
+            Permission::ApproveCommands,
+        ],
+        _ => &[],
+    }
+}
+
+/// Stored permissions that are no longer known are ignored.
+fn parse_permissions(xs: &[String]) -> Vec<Permission> {
+    xs.iter()
+        .filter_map(|x| Permission::from_str(x).ok())
+        .collect()
+}
+
+/// The permissions granted by the groups of a user and the permissions of their roles, combined.
+fn effective_permissions(
+    is_superuser: bool,
+    groups: &[String],
+    role_permissions: &[String],
+) -> Vec<Permission> {
+    let mut xs: BTreeSet<Permission> = groups
+        .iter()
+        .flat_map(|x| group_permissions(x).iter().copied())
+        .chain(parse_permissions(role_permissions))
+        .collect();
+
+    if is_superuser {
+        xs.extend(Permission::ALL.iter().copied());
+    }
+
+    xs.into_iter().collect()
+}
+
+/// The permissions of `user_id`. Users that are inactive or don't exist have none.
+pub(crate) async fn user_permissions(
+    pool: &PgPool,
+    user_id: i32,
+) -> Result<Vec<Permission>, ImlApiError> {
+    let x = sqlx::query!(
+        r#"
+            SELECT
+                u.is_superuser,
+                array(
+                    SELECT g.name
+                    FROM auth_user_groups ug
+                    INNER JOIN auth_group g ON g.id = ug.group_id
+                    WHERE ug.user_id = u.id
+                ) AS "groups!",
+                array(
+                    SELECT DISTINCT unnest(r.permissions)
+                    FROM role r
+                    INNER JOIN role_user ru ON ru.role_id = r.id
+                    WHERE ru.user_id = u.id
+                ) AS "role_permissions!"
+            FROM auth_user u
+            WHERE u.id = $1 AND u.is_active
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(
+        x.map(|x| effective_permissions(x.is_superuser, &x.groups, &x.role_permissions))
+            .unwrap_or_default(),
+    )
+}
+
+/// Fails unless the current user has `permission`.
+///
+/// Requests without a user are made by IML itself and are always allowed.
+pub(crate) async fn require(context: &Context, permission: Permission) -> Result<(), FieldError> {
+    let user_id = match context.user_id {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    if user_permissions(&context.pg_pool, user_id)
+        .await?
+        .contains(&permission)
+    {
+        Ok(())
+    } else {
+        Err(FieldError::new(
+            format!("Permission denied, {} is required", permission),
+            Value::null(),
+        ))
+    }
+}
+
+async fn get_roles(pool: &PgPool, id: Option<i32>) -> Result<Vec<Role>, ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT r.id, r.name, r.description, r.permissions,
+                array(SELECT ru.user_id FROM role_user ru WHERE ru.role_id = r.id ORDER BY ru.user_id) AS "user_ids!"
+            FROM role r
+            WHERE $1::INT IS NULL OR r.id = $1
+            ORDER BY r.name
+        "#,
+        id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| Role {
+        id: x.id,
+        name: x.name,
+        description: x.description,
+        permissions: parse_permissions(&x.permissions),
+        user_ids: x.user_ids,
+    })
+    .collect();
+
+    Ok(xs)
+}
+
+async fn get_role(pool: &PgPool, id: i32) -> Result<Role, FieldError> {
+    get_roles(pool, Some(id))
+        .await?
+        .pop()
+        .ok_or_else(|| not_found("Role", id))
+}
+
+fn to_strings(xs: &[Permission]) -> Vec<String> {
+    xs.iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|x| x.as_str().to_string())
+        .collect()
+}
+
+pub(crate) struct RoleQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl RoleQuery {
+    /// All custom roles, with the users they are assigned to
+    async fn list(context: &Context) -> juniper::FieldResult<Vec<Role>> {
+        let xs = get_roles(&context.pg_pool, None).await?;
+
+        Ok(xs)
+    }
+    /// The permissions of the current user, from their group and their roles
+    async fn my_permissions(context: &Context) -> juniper::FieldResult<Vec<Permission>> {
+        let xs = match context.user_id {
+            Some(user_id) => user_permissions(&context.pg_pool, user_id).await?,
+            None => Permission::ALL.to_vec(),
+        };
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct RoleMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl RoleMutation {
+    #[graphql(arguments(
+        name(description = "A unique name for the role"),
+        description(description = "What the role is for"),
+        permissions(description = "The permissions the role grants"),
+    ))]
+    /// Create a role. It is not assigned to anyone yet.
+    async fn create(
+        context: &Context,
+        name: String,
+        description: Option<String>,
+        permissions: Vec<Permission>,
+    ) -> juniper::FieldResult<Role> {
+        require(context, Permission::ManageRoles).await?;
+
+        let name = name.trim();
+        validate_name("role", name, MAX_NAME_LEN).map_err(invalid)?;
+
+        let id = sqlx::query!(
+            r#"
+                INSERT INTO role (name, description, permissions)
+                VALUES ($1, $2, $3)
+                RETURNING id
+            "#,
+            name,
+            description.unwrap_or_default(),
+            &to_strings(&permissions)
+        )
+        .fetch_one(&context.pg_pool)
+        .await?
+        .id;
+
+        audit::record(
+            context,
+            "role.create",
+            serde_json::json!({ "id": id, "name": name, "permissions": permissions }),
+            None,
+        )
+        .await;
+
+        get_role(&context.pg_pool, id).await
+    }
+    #[graphql(arguments(
+        id(description = "The id of the role"),
+        name(description = "The new name, unchanged when not set"),
+        description(description = "The new description, unchanged when not set"),
+        permissions(
+            description = "The permissions the role grants instead, unchanged when not set"
+        ),
+    ))]
+    /// Update a role. Its users are granted the new permissions straight away.
+    async fn update(
+        context: &Context,
+        id: i32,
+        name: Option<String>,
+        description: Option<String>,
+        permissions: Option<Vec<Permission>>,
+    ) -> juniper::FieldResult<Role> {
+        require(context, Permission::ManageRoles).await?;
+
+        let name = name.as_deref().map(str::trim);
+
+        if let Some(name) = name {
+            validate_name("role", name, MAX_NAME_LEN).map_err(invalid)?;
+        }
+
+        let permissions = permissions.as_deref().map(to_strings);
+
+        sqlx::query!(
+            r#"
+                UPDATE role
+                SET name = COALESCE($2, name),
+                    description = COALESCE($3, description),
+                    permissions = COALESCE($4, permissions)
+                WHERE id = $1
+            "#,
+            id,
+            name,
+            description,
+            permissions.as_deref()
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        let role = get_role(&context.pg_pool, id).await?;
+
+        audit::record(
+            context,
+            "role.update",
+            serde_json::json!({ "id": id, "name": role.name, "permissions": role.permissions }),
+            None,
+        )
+        .await;
+
+        Ok(role)
+    }
+    #[graphql(arguments(id(description = "The id of the role")))]
+    /// Remove a role. Its users lose the permissions it granted.
+    async fn remove(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        require(context, Permission::ManageRoles).await?;
+
+        sqlx::query!("DELETE FROM role WHERE id = $1", id)
+            .execute(&context.pg_pool)
+            .await?;
+
+        audit::record(
+            context,
+            "role.remove",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        id(description = "The id of the role"),
+        user_ids(description = "The users to assign the role to"),
+    ))]
+    /// Assign a role to users. Users that already have it are left as is.
+    async fn assign(context: &Context, id: i32, user_ids: Vec<i32>) -> juniper::FieldResult<Role> {
+        require(context, Permission::ManageRoles).await?;
+
+        let _ = get_role(&context.pg_pool, id).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO role_user (role_id, user_id)
+                SELECT $1, unnest($2::int[])
+                ON CONFLICT (role_id, user_id) DO NOTHING
+            "#,
+            id,
+            &user_ids
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "role.assign",
+            serde_json::json!({ "id": id, "userIds": user_ids }),
+            None,
+        )
+        .await;
+
+        get_role(&context.pg_pool, id).await
+    }
+    #[graphql(arguments(
+        id(description = "The id of the role"),
+        user_ids(description = "The users to take the role from"),
+    ))]
+    /// Take a role from users.
+    async fn unassign(
+        context: &Context,
+        id: i32,
+        user_ids: Vec<i32>,
+    ) -> juniper::FieldResult<Role> {
+        require(context, Permission::ManageRoles).await?;
+
+        sqlx::query!(
+            "DELETE FROM role_user WHERE role_id = $1 AND user_id = ANY($2)",
+            id,
+            &user_ids
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "role.unassign",
+            serde_json::json!({ "id": id, "userIds": user_ids }),
+            None,
+        )
+        .await;
+
+        get_role(&context.pg_pool, id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_permissions() {
+        assert_eq!(
+            effective_permissions(false, &["superusers".into()], &[]),
+            Permission::ALL.to_vec()
+        );
+        assert_eq!(
+            effective_permissions(true, &[], &[]),
+            Permission::ALL.to_vec()
+        );
+        assert!(
+            !effective_permissions(false, &["filesystem_administrators".into()], &[])
+                .contains(&Permission::ManageRoles)
+        );
+        assert_eq!(
+            effective_permissions(false, &["filesystem_users".into()], &[]),
+            vec![]
+        );
+        assert_eq!(
+            effective_permissions(
+                false,
+                &["filesystem_users".into()],
+                &[
+                    "delete_snapshots".into(),
+                    "create_snapshots".into(),
+                    "delete_snapshots".into(),
+                    "fly".into()
+                ]
+            ),
+            vec![Permission::CreateSnapshots, Permission::DeleteSnapshots]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    command::get_command,
+    error::ImlApiError,
+    graphql::{approval, audit, fs_id_by_name, run_jobs_kwargs, Context, SendJob},
+};
+use futures::TryFutureExt;
+use iml_postgres::{active_mgs_host_fqdn, sqlx};
+use iml_wire_types::{
+    graphql::{JobPriority, SecurityFlavor, SrpcRule, TargetSecurityFlavor},
+    Command,
+};
+use juniper::{FieldError, Value};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+lazy_static! {
+    static ref SRPC_RULE: Regex =
+        Regex::new(r"^([\w-]+)\.srpc\.flavor\.(\w+)(?:\.(\w+))?=(\S+)$").unwrap();
+}
+
+pub(crate) struct SecurityQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl SecurityQuery {
+    #[graphql(arguments(fs_name(description = "The filesystem to read security flavors for")))]
+    /// The RPC security flavor in effect for each target of `fs_name`,
+    /// as configured on the MGS.
+    async fn target_flavors(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<Vec<TargetSecurityFlavor>> {
+        let _ = fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        let targets = get_target_names(context, &fs_name).await?;
+
+        let rules = get_srpc_rules(context, &fs_name).await?;
+
+        let xs = targets
+            .into_iter()
+            .map(|target| {
+                let rules: Vec<_> = rules
+                    .iter()
+                    .filter(|x| x.target == fs_name || x.target == target)
+                    .cloned()
+                    .collect();
+
+                TargetSecurityFlavor {
+                    flavor: effective_flavor(&rules, &fs_name, &target),
+                    target,
+                    rules,
+                }
+            })
+            .collect();
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct SecurityMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl SecurityMutation {
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to configure"),
+        flavor(description = "The flavor to use"),
+        target(
+            description = "Only use the flavor for this target, e.g. `fs-OST0000`. Defaults to the whole filesystem"
+        ),
+        network(
+            description = "Only use the flavor on this LNet network, e.g. `tcp`. Defaults to all networks"
+        ),
+    ))]
+    /// Set the RPC security flavor for a filesystem, or one of its targets.
+    /// The rule is stored on the MGS and picked up by servers and clients as they reconnect.
+    /// `ssk` flavors need a shared key on every server, see `distributeSskKey`.
+    async fn set_flavor(
+        context: &Context,
+        fs_name: String,
+        flavor: SecurityFlavor,
+        target: Option<String>,
+        network: Option<String>,
+    ) -> juniper::FieldResult<bool> {
+        let _ = fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        let network = network.unwrap_or_else(|| "default".into());
+
+        if !is_valid_network(&network) {
+            return Err(FieldError::new(
+                format!("Invalid network {}", network),
+                Value::null(),
+            ));
+        }
+
+        let name = match target {
+            Some(target) => {
+                let targets = get_target_names(context, &fs_name).await?;
+
+                if !targets.contains(&target) {
+                    return Err(FieldError::new(
+                        format!("Target {} is not part of {}", target, fs_name),
+                        Value::null(),
+                    ));
+                }
+
+                target
+            }
+            None => fs_name.clone(),
+        };
+
+        let mgs_fqdn = get_mgs_fqdn(context, &fs_name).await?;
+
+        let param = format!("{}.srpc.flavor.{}={}", name, network, flavor.as_str());
+
+        context
+            .action_client
+            .invoke_rust_agent_expect_result(mgs_fqdn, "lctl", vec!["conf_param", &param], None)
+            .await
+            .map_err(ImlApiError::from)?
+            .map_err(|e| {
+                FieldError::new(format!("Could not set {}: {}", param, e), Value::null())
+            })?;
+
+        audit::record(
+            context,
+            "security.setFlavor",
+            serde_json::json!({ "fsName": fs_name, "param": param }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+
+    #[graphql(arguments(fs_name(description = "The filesystem to create a shared key for")))]
+    /// Generate a new shared secret key for `fs_name` and install it on every server of the filesystem.
+    /// Any existing key for the filesystem is replaced.
+    async fn distribute_ssk_key(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<Command> {
+        let fs_id = fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        let jobs = vec![SendJob {
+            class_name: "DistributeSskKeyJob",
+            args: vec![("filesystem_id".to_string(), serde_json::json!(fs_id))]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+        }];
+
+        let kwargs = run_jobs_kwargs(
+            format!("Distribute shared key for {}", fs_name),
+            JobPriority::High,
+        );
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
+
+        let command_id: i32 = iml_job_scheduler_rpc::call(
+            &context.rabbit_pool.get().await?,
+            "run_jobs",
+            vec![jobs],
+            Some(kwargs),
+        )
+        .map_err(ImlApiError::ImlJobSchedulerRpcError)
+        .await?;
+
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        audit::record(
+            context,
+            "security.distributeSskKey",
+            serde_json::json!({ "fsName": fs_name }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+}
+
+async fn get_target_names(context: &Context, fs_name: &str) -> Result<Vec<String>, FieldError> {
+    let xs = sqlx::query!(
+        "SELECT name FROM target WHERE $1 = ANY(filesystems) AND name <> 'MGS' ORDER BY name",
+        fs_name
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| x.name)
+    .collect();
+
+    Ok(xs)
+}
+
+async fn get_mgs_fqdn(context: &Context, fs_name: &str) -> Result<String, FieldError> {
+    active_mgs_host_fqdn(fs_name, &context.pg_pool)
+        .await?
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("The MGS for {} is not mounted", fs_name),
+                Value::null(),
+            )
+        })
+}
+
+/// Reads the secure RPC rules for `fs_name` from the MGS.
+async fn get_srpc_rules(context: &Context, fs_name: &str) -> Result<Vec<SrpcRule>, FieldError> {
+    let mgs_fqdn = get_mgs_fqdn(context, fs_name).await?;
+
+    let param = format!("mgs.MGS.live.{}", fs_name);
+
+    let x = context
+        .action_client
+        .invoke_rust_agent_expect_result(mgs_fqdn, "lctl", vec!["get_param", "-n", &param], None)
+        .await
+        .map_err(ImlApiError::from)?
+        .map_err(|e| FieldError::new(format!("Could not read {}: {}", param, e), Value::null()))?;
+
+    let x: String = serde_json::from_value(x)?;
+
+    Ok(parse_srpc_rules(&x))
+}
+
+/// Parses the `Secure RPC Config Rules` of an MGS live config,
+/// e.g. `fs.srpc.flavor.tcp.cli2mdt=skpi`
+fn parse_srpc_rules(x: &str) -> Vec<SrpcRule> {
+    x.lines()
+        .filter_map(|l| SRPC_RULE.captures(l.trim()))
+        .map(|c| SrpcRule {
+            target: c[1].to_string(),
+            network: c[2].to_string(),
+            direction: c.get(3).map(|x| x.as_str().to_string()),
+            flavor: c[4].to_string(),
+        })
+        .collect()
+}
+
+/// The default flavor for `target`. A rule for the target wins over one for the filesystem.
+/// Without any rule, Lustre uses `null`.
+fn effective_flavor(rules: &[SrpcRule], fs_name: &str, target: &str) -> String {
+    let find = |name: &str| {
+        rules
+            .iter()
+            .rev()
+            .find(|x| x.target == name && x.network == "default" && x.direction.is_none())
+    };
+
+    find(target)
+        .or_else(|| find(fs_name))
+        .map(|x| x.flavor.clone())
+        .unwrap_or_else(|| SecurityFlavor::Null.as_str().to_string())
+}
+
+fn is_valid_network(x: &str) -> bool {
+    !x.is_empty()
+        && x.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIVE: &str = r#"fsname: fs
+flags: 0x20     gen: 12
+fs-MDT0000
+fs-OST0000
+fs-OST0001
+
+Secure RPC Config Rules:
+fs.srpc.flavor.default=ski
+fs.srpc.flavor.tcp.cli2mdt=skpi
+fs-OST0001.srpc.flavor.default=skpi
+
+imperative_recovery_state:
+    state: full
+"#;
+
+    #[test]
+    fn test_parse_srpc_rules() {
+        let xs = parse_srpc_rules(LIVE);
+
+        assert_eq!(xs.len(), 3);
+        assert_eq!(
+            xs[1],
+            SrpcRule {
+                target: "fs".into(),
+                network: "tcp".into(),
+                direction: Some("cli2mdt".into()),
+                flavor: "skpi".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_effective_flavor() {
+        let xs = parse_srpc_rules(LIVE);
+
+        assert_eq!(effective_flavor(&xs, "fs", "fs-OST0000"), "ski");
+        assert_eq!(effective_flavor(&xs, "fs", "fs-OST0001"), "skpi");
+        assert_eq!(effective_flavor(&[], "fs", "fs-OST0000"), "null");
+    }
+
+    #[test]
+    fn test_is_valid_network() {
+        assert!(is_valid_network("tcp"));
+        assert!(is_valid_network("o2ib0"));
+        assert!(!is_valid_network("tcp;"));
+        assert!(!is_valid_network(""));
+    }
+}
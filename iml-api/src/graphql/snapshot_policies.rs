@@ -0,0 +1,478 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Snapshot intervals, retention policies and their shared settings as YAML,
+//! so they can be kept in version control and applied to other filesystems or managers.
+
+use crate::{
+    error::ImlApiError,
+    graphql::{invalid, validate_thresholds},
+    snapshot_policy::{is_time_zone, parse_start_time},
+    timer::{configure_snapshot_timer, remove_snapshot_timer},
+};
+use iml_postgres::{sqlx, sqlx::postgres::types::PgInterval, PgPool};
+use iml_wire_types::{
+    graphql_duration::GraphQLDuration,
+    snapshot::{
+        default_time_zone, FilesystemSnapshotPolicy, ReserveUnit, SnapshotIntervalPolicy,
+        SnapshotPolicyFile, SnapshotPolicyImport, SnapshotPolicySettings, SnapshotRetentionPolicy,
+    },
+};
+use juniper::{FieldError, Value};
+use std::{
+    collections::{BTreeMap, HashSet},
+    convert::TryFrom as _,
+};
+
+/// The policies of `fsnames`, or of every filesystem that has one.
+pub(crate) async fn export(
+    pool: &PgPool,
+    fsnames: Option<&[String]>,
+) -> Result<SnapshotPolicyFile, ImlApiError> {
+    let settings = sqlx::query!("SELECT max_concurrent, jitter FROM snapshot_policy_settings")
+        .fetch_one(pool)
+        .await?;
+
+    let intervals = sqlx::query!(
+        r#"
+            SELECT filesystem_name, interval, use_barrier, automount,
+                to_char(start_at, 'HH24:MI') AS start_time, time_zone
+            FROM snapshot_interval
+            WHERE $1::TEXT[] IS NULL OR filesystem_name = ANY($1)
+            ORDER BY filesystem_name, interval
+        "#,
+        fsnames
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let retentions = sqlx::query!(
+        r#"
+            SELECT
+                filesystem_name,
+                reserve_value,
+                reserve_unit as "reserve_unit:ReserveUnit",
+                keep_num,
+                mdt_reserve_percent,
+                change_percent
+            FROM snapshot_retention
+            WHERE $1::TEXT[] IS NULL OR filesystem_name = ANY($1)
+        "#,
+        fsnames
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut xs: BTreeMap<String, FilesystemSnapshotPolicy> = BTreeMap::new();
+
+    for x in intervals {
+        xs.entry(x.filesystem_name.clone())
+            .or_insert_with(|| empty_policy(&x.filesystem_name))
+            .intervals
+            .push(SnapshotIntervalPolicy {
+                interval: x.interval.into(),
+                use_barrier: x.use_barrier,
+                automount: x.automount,
+                time_zone: x.start_time.as_ref().map(|_| x.time_zone),
+                start_time: x.start_time,
+            });
+    }
+
+    for x in retentions {
+        xs.entry(x.filesystem_name.clone())
+            .or_insert_with(|| empty_policy(&x.filesystem_name))
+            .retention = Some(SnapshotRetentionPolicy {
+            reserve_value: x.reserve_value,
+            reserve_unit: x.reserve_unit,
+            keep_num: x.keep_num,
+            mdt_reserve_percent: x.mdt_reserve_percent,
+            change_percent: x.change_percent,
+        });
+    }
+
+    Ok(SnapshotPolicyFile {
+        settings: Some(SnapshotPolicySettings {
+            max_concurrent: settings.max_concurrent,
+            jitter: settings.jitter.into(),
+        }),
+        filesystems: xs.into_iter().map(|(_, x)| x).collect(),
+    })
+}
+
+fn empty_policy(fsname: &str) -> FilesystemSnapshotPolicy {
+    FilesystemSnapshotPolicy {
+        filesystem: fsname.to_string(),
+        intervals: vec![],
+        retention: None,
+    }
+}
+
+/// Reads a policy file and checks it can be applied as a whole.
+/// With `fsname`, the file must hold a single filesystem,
+/// whose policies are applied to `fsname` instead.
+pub(crate) fn parse(yaml: &str, fsname: Option<&str>) -> Result<SnapshotPolicyFile, String> {
+    let mut x: SnapshotPolicyFile =
+        serde_yaml::from_str(yaml).map_err(|e| format!("Invalid snapshot policy file: {}", e))?;
+
+    if let Some(fsname) = fsname {
+        match x.filesystems.as_mut_slice() {
+            [policy] => policy.filesystem = fsname.to_string(),
+            xs => {
+                return Err(format!(
+                    "The policies of a single filesystem can be applied to {}, the file has {}",
+                    fsname,
+                    xs.len()
+                ))
+            }
+        }
+    }
+
+    if let Some(settings) = &x.settings {
+        if settings.max_concurrent < 0 {
+            return Err("max_concurrent can't be negative".to_string());
+        }
+    }
+
+    let mut seen = HashSet::new();
+
+    for policy in &x.filesystems {
+        if !seen.insert(policy.filesystem.as_str()) {
+            return Err(format!("{} is listed more than once", policy.filesystem));
+        }
+
+        let mut intervals = HashSet::new();
+
+        for i in &policy.intervals {
+            if i.interval.0.as_secs() == 0 {
+                return Err(format!(
+                    "An interval of {} is shorter than a second",
+                    policy.filesystem
+                ));
+            }
+
+            if !intervals.insert(i.interval.0) {
+                return Err(format!(
+                    "The interval {} of {} is listed more than once",
+                    i.interval, policy.filesystem
+                ));
+            }
+
+            if let Some(x) = &i.start_time {
+                parse_start_time(x).map_err(|e| format!("{}: {}", policy.filesystem, e))?;
+            } else if i.time_zone.is_some() {
+                return Err(format!(
+                    "The interval {} of {} has a time zone but no start time",
+                    i.interval, policy.filesystem
+                ));
+            }
+        }
+
+        if let Some(r) = &policy.retention {
+            if r.reserve_value < 0 || r.keep_num < 0 {
+                return Err(format!(
+                    "The reserve and snapshots to keep of {} can't be negative",
+                    policy.filesystem
+                ));
+            }
+
+            validate_thresholds(r.mdt_reserve_percent, r.change_percent)
+                .map_err(|e| format!("{}: {}", policy.filesystem, e))?;
+        }
+    }
+
+    Ok(x)
+}
+
+fn pg_interval(x: &GraphQLDuration) -> juniper::FieldResult<PgInterval> {
+    PgInterval::try_from(x.0)
+        .map_err(|e| FieldError::new(format!("Invalid interval {}: {}", x, e), Value::null()))
+}
+
+/// Applies the policies of `file` to the filesystems it lists, in a single transaction.
+/// Intervals are added, or have their barrier, automount and schedule updated.
+/// With `replace`, the intervals these filesystems have that are not in the file are removed,
+/// and so is the retention policy of a filesystem without one in the file.
+///
+/// Every interval of the file without a start time has its timer configured,
+/// so a rebuilt manager gets them back.
+pub(crate) async fn import(
+    pool: &PgPool,
+    file: SnapshotPolicyFile,
+    replace: bool,
+) -> juniper::FieldResult<SnapshotPolicyImport> {
+    let mut result = SnapshotPolicyImport {
+        filesystems: file
+            .filesystems
+            .iter()
+            .map(|x| x.filesystem.clone())
+            .collect(),
+        intervals_added: 0,
+        intervals_removed: 0,
+        retentions_set: 0,
+        retentions_removed: 0,
+        settings_changed: false,
+    };
+
+    let mut timers = vec![];
+    let mut removed = vec![];
+
+    for policy in &file.filesystems {
+        for i in &policy.intervals {
+            if let Some(x) = &i.time_zone {
+                if !is_time_zone(pool, x).await? {
+                    return Err(FieldError::new(
+                        format!("Unknown time zone {}", x),
+                        Value::null(),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    if let Some(x) = &file.settings {
+        sqlx::query!(
+            "UPDATE snapshot_policy_settings SET max_concurrent = $1, jitter = $2",
+            x.max_concurrent,
+            pg_interval(&x.jitter)?
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        result.settings_changed = true;
+    }
+
+    for policy in file.filesystems {
+        let fsname = policy.filesystem;
+
+        let existing = sqlx::query!(
+            r#"
+                SELECT id, interval, start_at IS NULL AS "has_timer!"
+                FROM snapshot_interval
+                WHERE filesystem_name = $1
+            "#,
+            fsname
+        )
+        .fetch_all(&mut transaction)
+        .await?;
+
+        for i in &policy.intervals {
+            let start = i
+                .start_time
+                .as_deref()
+                .map(parse_start_time)
+                .transpose()
+                .map_err(invalid)?;
+            let time_zone = i.time_zone.clone().unwrap_or_else(default_time_zone);
+
+            // An unchanged start time keeps its schedule, which may not be aligned to today
+            let x = sqlx::query!(
+                r#"
+                    INSERT INTO snapshot_interval (filesystem_name, use_barrier, interval, automount, start_at, time_zone)
+                    VALUES ($1, $2, $3, $4, snapshot_interval_start_at($5, $6, $3), $6)
+                    ON CONFLICT (filesystem_name, interval) DO UPDATE
+                    SET use_barrier = EXCLUDED.use_barrier,
+                        automount = EXCLUDED.automount,
+                        start_at = CASE
+                            WHEN snapshot_interval.time_zone = EXCLUDED.time_zone
+                            AND snapshot_interval.start_at::TIME IS NOT DISTINCT FROM EXCLUDED.start_at::TIME
+                            THEN snapshot_interval.start_at
+                            ELSE EXCLUDED.start_at
+                        END,
+                        time_zone = EXCLUDED.time_zone
+                    RETURNING id
+                "#,
+                fsname,
+                i.use_barrier,
+                pg_interval(&i.interval)?,
+                i.automount,
+                start,
+                time_zone
+            )
+            .fetch_one(&mut transaction)
+            .await?;
+
+            match existing.iter().find(|e| e.id == x.id) {
+                None => result.intervals_added += 1,
+                Some(e) if e.has_timer && start.is_some() => removed.push(e.id),
+                Some(_) => {}
+            }
+
+            if start.is_none() {
+                timers.push((x.id, fsname.clone(), i.interval.0));
+            }
+        }
+
+        if replace {
+            for e in existing {
+                let interval = GraphQLDuration::from(e.interval).0;
+
+                if policy.intervals.iter().all(|i| i.interval.0 != interval) {
+                    sqlx::query!("DELETE FROM snapshot_interval WHERE id = $1", e.id)
+                        .execute(&mut transaction)
+                        .await?;
+
+                    if e.has_timer {
+                        removed.push(e.id);
+                    }
+
+                    result.intervals_removed += 1;
+                }
+            }
+        }
+
+        match policy.retention {
+            Some(r) => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO snapshot_retention (
+                            filesystem_name,
+                            reserve_value,
+                            reserve_unit,
+                            keep_num,
+                            mdt_reserve_percent,
+                            change_percent
+                        )
+                        VALUES ($1, $2, $3, $4, $5, $6)
+                        ON CONFLICT (filesystem_name)
+                        DO UPDATE SET
+                        reserve_value = EXCLUDED.reserve_value,
+                        reserve_unit = EXCLUDED.reserve_unit,
+                        keep_num = EXCLUDED.keep_num,
+                        mdt_reserve_percent = EXCLUDED.mdt_reserve_percent,
+                        change_percent = EXCLUDED.change_percent,
+                        change_baseline = CASE
+                            WHEN EXCLUDED.change_percent IS NULL THEN NULL
+                            ELSE snapshot_retention.change_baseline
+                        END,
+                        version = snapshot_retention.version + 1,
+                        updated_at = now()
+                    "#,
+                    fsname,
+                    r.reserve_value,
+                    r.reserve_unit as ReserveUnit,
+                    r.keep_num,
+                    r.mdt_reserve_percent,
+                    r.change_percent
+                )
+                .execute(&mut transaction)
+                .await?;
+
+                result.retentions_set += 1;
+            }
+            None if replace => {
+                let x = sqlx::query!(
+                    "DELETE FROM snapshot_retention WHERE filesystem_name = $1",
+                    fsname
+                )
+                .execute(&mut transaction)
+                .await?
+                .rows_affected();
+
+                result.retentions_removed += x as i32;
+            }
+            None => {}
+        }
+    }
+
+    transaction.commit().await?;
+
+    // Also the timers of the intervals that now have a start time
+    for id in removed {
+        remove_snapshot_timer(id).await?;
+    }
+
+    for (id, fsname, interval) in timers {
+        configure_snapshot_timer(id, fsname, interval).await?;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const FILE: &str = r#"
+settings:
+  max_concurrent: 2
+  jitter: 5m
+filesystems:
+  - filesystem: fs
+    intervals:
+      - interval: 1h
+        use_barrier: true
+      - interval: 1day
+        start_time: "02:30"
+        time_zone: Europe/Paris
+    retention:
+      reserve_value: 10
+      reserve_unit: percent
+      keep_num: 5
+      mdt_reserve_percent: 20
+"#;
+
+    #[test]
+    fn test_parse() {
+        let x = parse(FILE, None).unwrap();
+
+        assert_eq!(x.settings.as_ref().unwrap().max_concurrent, 2);
+        assert_eq!(x.filesystems[0].filesystem, "fs");
+        assert_eq!(
+            x.filesystems[0].intervals[1],
+            SnapshotIntervalPolicy {
+                interval: GraphQLDuration(Duration::from_secs(24 * 60 * 60)),
+                use_barrier: false,
+                automount: false,
+                start_time: Some("02:30".into()),
+                time_zone: Some("Europe/Paris".into()),
+            }
+        );
+        assert_eq!(
+            x.filesystems[0].retention.as_ref().unwrap().change_percent,
+            None
+        );
+
+        let yaml = serde_yaml::to_string(&x).unwrap();
+
+        assert_eq!(parse(&yaml, None).unwrap(), x);
+    }
+
+    #[test]
+    fn test_parse_fsname() {
+        let x = parse(FILE, Some("fs2")).unwrap();
+
+        assert_eq!(x.filesystems[0].filesystem, "fs2");
+
+        let two = format!("{}  - filesystem: fs3\n", FILE);
+
+        assert!(parse(&two, Some("fs2")).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let invalid = [
+            "{filesystems: [{filesystem: fs, keep: 3}]}",
+            "{filesystems: [{filesystem: fs}, {filesystem: fs}]}",
+            "{filesystems: [{filesystem: fs, intervals: [{interval: 1h}, {interval: 60m}]}]}",
+            "{filesystems: [{filesystem: fs, intervals: [{interval: 0s}]}]}",
+            "{filesystems: [{filesystem: fs, intervals: [{interval: 1h, start_time: '25:00'}]}]}",
+            "{filesystems: [{filesystem: fs, intervals: [{interval: 1h, time_zone: UTC}]}]}",
+            "{settings: {max_concurrent: -1, jitter: 0s}}",
+        ];
+
+        for x in &invalid {
+            assert!(parse(x, None).is_err(), "{} should not parse", x);
+        }
+
+        let x = concat!(
+            "{filesystems: [{filesystem: fs, retention: ",
+            "{reserve_value: 1, reserve_unit: percent, change_percent: 200}}]}"
+        );
+
+        assert!(parse(x, None).is_err());
+    }
+}
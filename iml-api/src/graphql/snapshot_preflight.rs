@@ -0,0 +1,369 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Checks run before the jobs that take a snapshot are submitted, so a snapshot that can't
+//! be taken is refused up front instead of failing on the MDS minutes later.
+
+use crate::{error::ImlApiError, graphql::Context};
+use iml_influx::{mdts, Client};
+use iml_manager_env::{
+    get_influxdb_addr, get_influxdb_metrics_db, get_snapshot_min_mdt_free_percent,
+};
+use iml_postgres::{active_mgs_host_fqdn, sqlx};
+use iml_wire_types::snapshot::{SnapshotCheck, SnapshotCheckResult, SnapshotPreflight};
+use juniper::{FieldError, Value};
+use url::Url;
+
+/// Barrier states of a barrier that is being set or held
+const BUSY_BARRIER_STATES: &[&str] = &["freezing_p1", "freezing_p2", "frozen", "thawing", "rescan"];
+
+/// Runs every check for a snapshot `name` of `fsname`.
+pub(crate) async fn run(
+    context: &Context,
+    fsname: &str,
+    name: &str,
+    use_barrier: bool,
+) -> Result<SnapshotPreflight, ImlApiError> {
+    let running = running_operations(context, fsname).await?;
+
+    let checks = vec![
+        check_barrier(context, fsname, use_barrier).await?,
+        check_mdt_space(fsname).await,
+        check_name(context, fsname, name, &running).await?,
+        conflicts_result(&running),
+    ];
+
+    Ok(SnapshotPreflight {
+        passed: checks.iter().all(|x| x.passed),
+        checks,
+    })
+}
+
+/// The error returned when a check failed, listing the failed checks under `checks`.
+pub(crate) fn failed_error(x: &SnapshotPreflight) -> FieldError {
+    let failed: Vec<_> = x.checks.iter().filter(|x| !x.passed).collect();
+
+    let msg = format!(
+        "Snapshot pre-flight checks failed: {}",
+        failed
+            .iter()
+            .map(|x| x.message.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    );
+
+    let checks = failed
+        .iter()
+        .map(|x| {
+            let mut o = juniper::Object::with_capacity(2);
+            o.add_field(
+                "check",
+                Value::scalar(serde_json::json!(x.check).as_str().unwrap_or_default()),
+            );
+            o.add_field("message", Value::scalar(x.message.as_str()));
+
+            Value::Object(o)
+        })
+        .collect();
+
+    let mut ext = juniper::Object::with_capacity(2);
+    ext.add_field("code", Value::scalar("PREFLIGHT_FAILED"));
+    ext.add_field("checks", Value::list(checks));
+
+    FieldError::new(msg, Value::Object(ext))
+}
+
+fn result(check: SnapshotCheck, r: Result<String, String>) -> SnapshotCheckResult {
+    let (passed, message) = match r {
+        Ok(x) => (true, x),
+        Err(x) => (false, x),
+    };
+
+    SnapshotCheckResult {
+        check,
+        passed,
+        message,
+    }
+}
+
+/// The write barrier needs every MDT to be mounted, an MGS that supports barriers,
+/// and no other barrier in progress.
+async fn check_barrier(
+    context: &Context,
+    fsname: &str,
+    use_barrier: bool,
+) -> Result<SnapshotCheckResult, ImlApiError> {
+    if !use_barrier {
+        return Ok(result(
+            SnapshotCheck::Barrier,
+            Ok("No write barrier requested".into()),
+        ));
+    }
+
+    let unmounted = sqlx::query!(
+        r#"
+            SELECT name FROM target
+            WHERE $1 = ANY(filesystems) AND name LIKE '%-MDT%' AND active_host_id IS NULL
+            ORDER BY name
+        "#,
+        fsname
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| x.name)
+    .collect::<Vec<_>>();
+
+    let state = match active_mgs_host_fqdn(fsname, &context.pg_pool).await? {
+        Some(fqdn) => context
+            .action_client
+            .invoke_rust_agent_expect_result(fqdn, "snapshot_barrier_stat", fsname, None)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|x| x.map_err(|e| e.to_string()))
+            .and_then(|x| serde_json::from_value(x).map_err(|e| e.to_string())),
+        None => Err("the MGS is not mounted".to_string()),
+    };
+
+    Ok(barrier_result(&unmounted, state))
+}
+
+fn barrier_result(unmounted_mdts: &[String], state: Result<String, String>) -> SnapshotCheckResult {
+    let r = if !unmounted_mdts.is_empty() {
+        Err(format!(
+            "The write barrier needs every MDT mounted, {} is not",
+            unmounted_mdts.join(", ")
+        ))
+    } else {
+        match state {
+            Ok(x) if BUSY_BARRIER_STATES.contains(&x.as_str()) => {
+                Err(format!("A write barrier is already {}", x))
+            }
+            Ok(x) => Ok(format!("The write barrier can be set, its state is {}", x)),
+            Err(e) => Err(format!("Could not read the write barrier state: {}", e)),
+        }
+    };
+
+    result(SnapshotCheck::Barrier, r)
+}
+
+/// Each MDT needs `SNAPSHOT_MIN_MDT_FREE_PERCENT` of its space free.
+/// The check passes when the stats can't be read, they are not the only guard.
+async fn check_mdt_space(fsname: &str) -> SnapshotCheckResult {
+    let min = match get_snapshot_min_mdt_free_percent() {
+        Some(x) => x,
+        None => {
+            return result(
+                SnapshotCheck::MdtSpace,
+                Ok("The MDT space check is disabled".into()),
+            )
+        }
+    };
+
+    let stats = match get_mdt_space(fsname).await {
+        Ok(x) => x,
+        Err(e) => {
+            tracing::warn!("Could not read the MDT space of {}: {}", fsname, e);
+
+            return result(
+                SnapshotCheck::MdtSpace,
+                Ok("The MDT space could not be read, skipped".into()),
+            );
+        }
+    };
+
+    mdt_space_result(min, &stats)
+}
+
+/// Space of each MDT, `(name, bytes total, bytes free)`.
+async fn get_mdt_space(fs_name: &str) -> Result<Vec<(String, f64, f64)>, ImlApiError> {
+    let url = Url::parse(&format!("http://{}", get_influxdb_addr()))?;
+    let client = Client::new(url, get_influxdb_metrics_db());
+
+    let nodes = client
+        .query(&mdts::space_query(fs_name), None)
+        .await
+        .map_err(iml_influx::Error::from)?
+        .unwrap_or_default();
+
+    // Columns are `time`, `bytes_total` and `bytes_free`.
+    let xs = nodes
+        .into_iter()
+        .filter_map(|x| x.series)
+        .flatten()
+        .filter_map(|x| {
+            let target = x.tags.as_ref()?.get("target")?.as_str()?.to_string();
+            let values = x.values.into_iter().next()?;
+
+            let col = |i: usize| values.get(i).and_then(|v| v.as_f64());
+
+            Some((target, col(1)?, col(2)?))
+        })
+        .collect();
+
+    Ok(xs)
+}
+
+fn mdt_space_result(min_percent: u32, stats: &[(String, f64, f64)]) -> SnapshotCheckResult {
+    let low: Vec<_> = stats
+        .iter()
+        .filter(|(_, total, _)| *total > 0.)
+        .map(|(name, total, free)| (name, free / total * 100.))
+        .filter(|(_, pct)| *pct < f64::from(min_percent))
+        .map(|(name, pct)| format!("{} has {:.1}% free", name, pct))
+        .collect();
+
+    let r = if low.is_empty() {
+        Ok(format!("Every MDT has at least {}% free", min_percent))
+    } else {
+        Err(format!(
+            "{}, below the {}% a snapshot needs",
+            low.join(", "),
+            min_percent
+        ))
+    };
+
+    result(SnapshotCheck::MdtSpace, r)
+}
+
+/// A snapshot of the same name can't be taken twice, nor while it is being taken.
+async fn check_name(
+    context: &Context,
+    fsname: &str,
+    name: &str,
+    running: &[(String, String)],
+) -> Result<SnapshotCheckResult, ImlApiError> {
+    let exists = sqlx::query!(
+        r#"
+            SELECT EXISTS(
+                SELECT 1 FROM snapshot WHERE filesystem_name = $1 AND snapshot_name = $2
+            ) AS "exists!"
+        "#,
+        fsname,
+        name
+    )
+    .fetch_one(&context.pg_pool)
+    .await?
+    .exists;
+
+    let creating = running.iter().any(|(op, x)| op == "create" && x == name);
+
+    let r = if exists {
+        Err(format!("Snapshot {} of {} already exists", name, fsname))
+    } else if creating {
+        Err(format!(
+            "Snapshot {} of {} is already being taken",
+            name, fsname
+        ))
+    } else {
+        Ok(format!("No snapshot of {} is named {}", fsname, name))
+    };
+
+    Ok(result(SnapshotCheck::NameCollision, r))
+}
+
+/// The snapshot operations on `fsname` whose job is not complete, `(operation, snapshot name)`.
+async fn running_operations(
+    context: &Context,
+    fsname: &str,
+) -> Result<Vec<(String, String)>, ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT 'create' AS "op!", x.name AS "name!"
+            FROM chroma_core_createsnapshotjob x
+            INNER JOIN chroma_core_job j ON j.id = x.job_ptr_id
+            WHERE j.state <> 'complete' AND x.fsname = $1
+            UNION ALL
+            SELECT 'destroy', x.name
+            FROM chroma_core_destroysnapshotjob x
+            INNER JOIN chroma_core_job j ON j.id = x.job_ptr_id
+            WHERE j.state <> 'complete' AND x.fsname = $1
+            UNION ALL
+            SELECT 'mount', x.name
+            FROM chroma_core_mountsnapshotjob x
+            INNER JOIN chroma_core_job j ON j.id = x.job_ptr_id
+            WHERE j.state <> 'complete' AND x.fsname = $1
+            UNION ALL
+            SELECT 'unmount', x.name
+            FROM chroma_core_unmountsnapshotjob x
+            INNER JOIN chroma_core_job j ON j.id = x.job_ptr_id
+            WHERE j.state <> 'complete' AND x.fsname = $1
+        "#,
+        fsname
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.op, x.name))
+    .collect();
+
+    Ok(xs)
+}
+
+fn conflicts_result(running: &[(String, String)]) -> SnapshotCheckResult {
+    let r = if running.is_empty() {
+        Ok("No other snapshot operation is running".into())
+    } else {
+        Err(format!(
+            "Other snapshot operations are running: {}",
+            running
+                .iter()
+                .map(|(op, name)| format!("{} {}", op, name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    };
+
+    result(SnapshotCheck::ConflictingOperation, r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_barrier_result() {
+        assert!(barrier_result(&[], Ok("thawed".into())).passed);
+        assert!(barrier_result(&[], Ok("init".into())).passed);
+        assert!(!barrier_result(&[], Ok("frozen".into())).passed);
+        assert!(!barrier_result(&[], Err("no barrier support".into())).passed);
+
+        let x = barrier_result(&["fs-MDT0001".into()], Ok("thawed".into()));
+
+        assert!(!x.passed);
+        assert!(x.message.contains("fs-MDT0001"));
+    }
+
+    #[test]
+    fn test_mdt_space_result() {
+        let stats = vec![
+            ("fs-MDT0000".to_string(), 100., 50.),
+            ("fs-MDT0001".to_string(), 100., 3.),
+            ("fs-MDT0002".to_string(), 0., 0.),
+        ];
+
+        assert!(mdt_space_result(2, &stats).passed);
+
+        let x = mdt_space_result(5, &stats);
+
+        assert!(!x.passed);
+        assert_eq!(
+            x.message,
+            "fs-MDT0001 has 3.0% free, below the 5% a snapshot needs"
+        );
+    }
+
+    #[test]
+    fn test_conflicts_result() {
+        assert!(conflicts_result(&[]).passed);
+
+        let x = conflicts_result(&[("destroy".into(), "snap1".into())]);
+
+        assert!(!x.passed);
+        assert_eq!(
+            x.message,
+            "Other snapshot operations are running: destroy snap1"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Warm standby managers: database replication lag, configuration sync and failover readiness.
+
+use crate::{
+    graphql::{audit, Context},
+    standby::{record_config_sync, status, DEFAULT_LAG_MB},
+};
+use iml_wire_types::standby::StandbyStatus;
+use juniper::{FieldError, Value};
+
+fn lag_mb() -> u32 {
+    iml_manager_env::get_standby_alert_lag_mb().unwrap_or(DEFAULT_LAG_MB)
+}
+
+pub(crate) struct StandbyQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl StandbyQuery {
+    /// How far behind the primary database each standby manager replays,
+    /// when it last synced the manager configuration, and whether it is ready to take over.
+    async fn status(context: &Context) -> juniper::FieldResult<StandbyStatus> {
+        let x = status(&context.pg_pool, lag_mb()).await?;
+
+        Ok(x)
+    }
+}
+
+pub(crate) struct StandbyMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl StandbyMutation {
+    #[graphql(arguments(
+        name(description = "The `application_name` the standby streams the database with"),
+        hash(description = "A hash of the configuration the standby synced"),
+    ))]
+    /// Record that a standby manager synced the manager configuration.
+    /// Run by the standby after each sync, a standby that has not synced within a day
+    /// is not ready to take over.
+    async fn record_config_sync(
+        context: &Context,
+        name: String,
+        hash: String,
+    ) -> juniper::FieldResult<bool> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(FieldError::new("The standby name is empty", Value::null()));
+        }
+
+        record_config_sync(&context.pg_pool, name, &hash).await?;
+
+        audit::record(
+            context,
+            "standby.recordConfigSync",
+            serde_json::json!({ "name": name, "hash": hash }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+}
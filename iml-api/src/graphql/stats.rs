@@ -0,0 +1,90 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::Context;
+use iml_influx::{mini_stats, Client, Precision};
+use iml_manager_env::{get_influxdb_addr, get_influxdb_metrics_db};
+use iml_postgres::sqlx;
+use iml_wire_types::graphql::TargetMiniStats;
+use std::collections::HashMap;
+use url::Url;
+
+/// Fetches the mini stats of all `target_uuids` with a single influx query.
+/// Targets without any stats get empty series.
+pub(crate) async fn get_mini_stats(
+    context: &Context,
+    target_uuids: Vec<String>,
+) -> juniper::FieldResult<Vec<TargetMiniStats>> {
+    let names: HashMap<String, String> = sqlx::query!(
+        "SELECT name, uuid FROM target WHERE uuid = ANY($1)",
+        &target_uuids
+    )
+    .fetch_all(&context.pg_pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.name, x.uuid))
+    .collect();
+
+    let mut stats: HashMap<&str, TargetMiniStats> = names
+        .values()
+        .map(|uuid| {
+            (
+                uuid.as_str(),
+                TargetMiniStats {
+                    target_uuid: uuid.to_string(),
+                    ..TargetMiniStats::default()
+                },
+            )
+        })
+        .collect();
+
+    if !names.is_empty() {
+        let url = Url::parse(&format!("http://{}", get_influxdb_addr()))?;
+        let client = Client::new(url, get_influxdb_metrics_db());
+
+        let q = mini_stats::query(&names.keys().cloned().collect::<Vec<_>>());
+
+        let nodes = client
+            .query(&q, Some(Precision::Seconds))
+            .await?
+            .unwrap_or_default();
+
+        for x in nodes.into_iter().filter_map(|x| x.series).flatten() {
+            let tag = |k: &str| {
+                x.tags
+                    .as_ref()
+                    .and_then(|t| t.get(k))
+                    .and_then(|v| v.as_str())
+            };
+
+            let stat = match tag("target")
+                .and_then(|t| names.get(t))
+                .and_then(|uuid| stats.get_mut(uuid.as_str()))
+            {
+                Some(x) => x,
+                None => continue,
+            };
+
+            // Columns are `time` and `mean`.
+            let values = x
+                .values
+                .iter()
+                .map(|v| v.get(1).and_then(|v| v.as_f64()).unwrap_or(0.0))
+                .collect();
+
+            match tag("name") {
+                Some("read_bytes") => stat.read = values,
+                Some("write_bytes") => stat.write = values,
+                _ => {}
+            }
+        }
+    }
+
+    let xs = target_uuids
+        .iter()
+        .filter_map(|x| stats.remove(x.as_str()))
+        .collect();
+
+    Ok(xs)
+}
@@ -0,0 +1,367 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! The stonith devices of each corosync cluster, read from the CIB of one of its online nodes,
+//! along with the latest test of whether every node can be fenced.
+
+use crate::{
+    command::get_command,
+    error::ImlApiError,
+    fan_out::join_all,
+    graphql::{
+        audit,
+        ha_cluster::{invalid, FENCE_AGENT, FENCE_PARAM},
+        run_jobs, Context, SendJob,
+    },
+    stonith_test::test_cluster,
+};
+use iml_postgres::sqlx;
+use iml_wire_types::{
+    stonith::{ClusterStonith, StonithConfig, StonithDeviceInput, StonithTestRun},
+    Command,
+};
+use juniper::{FieldError, Value};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+lazy_static! {
+    static ref DEVICE_ID: Regex = Regex::new(r"^[\w.-]{1,64}$").unwrap();
+}
+
+/// A corosync node and the managed host it runs on, if any
+struct ClusterNode {
+    name: String,
+    online: bool,
+    host_id: Option<i32>,
+    fqdn: Option<String>,
+}
+
+async fn cluster_nodes(
+    context: &Context,
+    cluster_id: i32,
+) -> Result<Vec<ClusterNode>, ImlApiError> {
+    let xs = sqlx::query_as!(
+        ClusterNode,
+        r#"
+            SELECT
+                (n.id).name AS "name!",
+                n.online,
+                h.id AS "host_id?",
+                h.fqdn AS "fqdn?"
+            FROM corosync_node n
+            LEFT OUTER JOIN corosync_node_managed_host nmh
+                ON nmh.corosync_node_id = n.id AND nmh.cluster_id = n.cluster_id
+            LEFT OUTER JOIN chroma_core_managedhost h
+                ON h.id = nmh.host_id AND h.not_deleted = 't'
+            WHERE n.cluster_id = $1
+            ORDER BY (n.id).name
+        "#,
+        cluster_id
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    Ok(xs)
+}
+
+/// The first online node of the cluster that is a managed host
+fn online_host(nodes: &[ClusterNode]) -> Option<(i32, &str)> {
+    nodes
+        .iter()
+        .filter(|x| x.online)
+        .find_map(|x| Some((x.host_id?, x.fqdn.as_deref()?)))
+}
+
+async fn stonith_config(
+    context: &Context,
+    cluster_id: i32,
+    nodes: &[ClusterNode],
+) -> Result<StonithConfig, String> {
+    let (_, fqdn) = online_host(nodes)
+        .ok_or_else(|| format!("No managed node of cluster {} is online", cluster_id))?;
+
+    let r = context
+        .action_client
+        .invoke_rust_agent_expect_result(fqdn, "get_stonith_config", (), None)
+        .await;
+
+    match r {
+        Ok(Ok(x)) => serde_json::from_value(x).map_err(|e| e.to_string()),
+        Ok(Err(e)) => Err(format!(
+            "{} could not read the stonith devices: {}",
+            fqdn, e
+        )),
+        Err(e) => Err(format!("{} did not answer: {}", fqdn, e)),
+    }
+}
+
+async fn last_test(
+    context: &Context,
+    cluster_id: i32,
+) -> Result<Option<StonithTestRun>, ImlApiError> {
+    let x = sqlx::query!(
+        r#"
+            SELECT id, cluster_id, ran_at, passed, error, result
+            FROM stonith_test
+            WHERE cluster_id = $1
+            ORDER BY ran_at DESC
+            LIMIT 1
+        "#,
+        cluster_id
+    )
+    .fetch_optional(&context.pg_pool)
+    .await?
+    .map(|x| StonithTestRun {
+        id: x.id,
+        cluster_id: x.cluster_id,
+        ran_at: x.ran_at,
+        passed: x.passed,
+        error: x.error,
+        result: x.result.and_then(|x| serde_json::from_value(x).ok()),
+    });
+
+    Ok(x)
+}
+
+pub(crate) struct StonithQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl StonithQuery {
+    #[graphql(arguments(cluster_id(description = "Only list this cluster")))]
+    /// The stonith devices of every corosync cluster and the latest test of each.
+    /// Credentials are never read back, so their `value` is `null`.
+    /// Clusters whose devices could not be read are listed with an `error`.
+    async fn clusters(
+        context: &Context,
+        cluster_id: Option<i32>,
+    ) -> juniper::FieldResult<Vec<ClusterStonith>> {
+        let ids = sqlx::query!(
+            r#"
+                SELECT id FROM corosync_cluster
+                WHERE $1::INT IS NULL OR id = $1
+                ORDER BY id
+            "#,
+            cluster_id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let xs = join_all(ids.into_iter().map(|x| async move {
+            let nodes = cluster_nodes(context, x.id).await?;
+
+            let (config, error) = match stonith_config(context, x.id, &nodes).await {
+                Ok(config) => (config, None),
+                Err(e) => (StonithConfig::default(), Some(e)),
+            };
+
+            Ok::<_, ImlApiError>(ClusterStonith {
+                cluster_id: x.id,
+                nodes: nodes.into_iter().map(|x| x.name).collect(),
+                enabled: config.enabled,
+                devices: config.devices,
+                error,
+                last_test: last_test(context, x.id).await?,
+            })
+        }))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct StonithMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl StonithMutation {
+    #[graphql(arguments(
+        cluster_id(description = "The corosync cluster the device belongs to"),
+        device(description = "The device to create, or update when its id exists"),
+    ))]
+    /// Create a stonith device, or update the host list and parameters of an existing one,
+    /// with a `SetStonithDeviceJob` run on an online node of the cluster.
+    async fn set_device(
+        context: &Context,
+        cluster_id: i32,
+        device: StonithDeviceInput,
+    ) -> juniper::FieldResult<Command> {
+        let nodes = cluster_nodes(context, cluster_id).await?;
+
+        if nodes.is_empty() {
+            return Err(FieldError::new(
+                format!("Cluster {} not found", cluster_id),
+                Value::null(),
+            ));
+        }
+
+        let names: Vec<_> = nodes.iter().map(|x| x.name.as_str()).collect();
+
+        validate(&device, &names).map_err(invalid)?;
+
+        let config = stonith_config(context, cluster_id, &nodes)
+            .await
+            .map_err(invalid)?;
+
+        if let Some(x) = config.devices.iter().find(|x| x.id == device.id) {
+            if x.agent != device.agent {
+                return Err(FieldError::new(
+                    format!(
+                        "{} uses {}, its agent can't be changed to {}",
+                        x.id, x.agent, device.agent
+                    ),
+                    Value::null(),
+                ));
+            }
+        }
+
+        let (host_id, _) = online_host(&nodes).ok_or_else(|| {
+            FieldError::new(
+                format!("No managed node of cluster {} is online", cluster_id),
+                Value::null(),
+            )
+        })?;
+
+        let job = SendJob {
+            class_name: "SetStonithDeviceJob",
+            args: vec![
+                ("host_id".to_string(), serde_json::json!(host_id)),
+                ("device_id".to_string(), serde_json::json!(device.id)),
+                ("agent".to_string(), serde_json::json!(device.agent)),
+                ("host_list".to_string(), serde_json::json!(device.host_list)),
+                ("params".to_string(), serde_json::json!(device.params)),
+            ]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+        };
+
+        let command_id = run_jobs(
+            context,
+            format!("Set stonith device {}", device.id),
+            vec![job],
+        )
+        .await?;
+
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        // Params may hold credentials, so they are left out.
+        audit::record(
+            context,
+            "stonith.setDevice",
+            serde_json::json!({
+                "clusterId": cluster_id,
+                "id": device.id,
+                "agent": device.agent,
+                "hostList": device.host_list,
+            }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+    #[graphql(arguments(cluster_id(description = "The corosync cluster to test")))]
+    /// Test the stonith devices of a cluster now, without fencing any node.
+    /// The run is recorded like the scheduled ones.
+    async fn test(context: &Context, cluster_id: i32) -> juniper::FieldResult<StonithTestRun> {
+        let exists = sqlx::query!(
+            r#"SELECT EXISTS(SELECT 1 FROM corosync_cluster WHERE id = $1) AS "exists!""#,
+            cluster_id
+        )
+        .fetch_one(&context.pg_pool)
+        .await?
+        .exists;
+
+        if !exists {
+            return Err(FieldError::new(
+                format!("Cluster {} not found", cluster_id),
+                Value::null(),
+            ));
+        }
+
+        let run = test_cluster(&context.pg_pool, &context.action_client, cluster_id).await?;
+
+        audit::record(
+            context,
+            "stonith.test",
+            serde_json::json!({ "clusterId": cluster_id, "passed": run.passed }),
+            None,
+        )
+        .await;
+
+        Ok(run)
+    }
+}
+
+/// Checks a device before anything is sent to the cluster.
+/// The id, host list and params end up on the `pcs` command line, so they may not look like options.
+fn validate(device: &StonithDeviceInput, nodes: &[&str]) -> Result<(), String> {
+    if !DEVICE_ID.is_match(&device.id) || device.id.starts_with('-') {
+        return Err(format!("Invalid device id {}", device.id));
+    }
+
+    if !FENCE_AGENT.is_match(&device.agent) {
+        return Err(format!("Invalid fence agent {}", device.agent));
+    }
+
+    if device.host_list.is_empty() {
+        return Err("A device needs at least one node to fence".into());
+    }
+
+    if let Some(x) = device
+        .host_list
+        .iter()
+        .find(|x| !nodes.contains(&x.as_str()))
+    {
+        return Err(format!("{} is not a node of the cluster", x));
+    }
+
+    if let Some(p) = device.params.iter().find(|p| !FENCE_PARAM.is_match(p)) {
+        return Err(format!("Invalid fence param {}, expected key=value", p));
+    }
+
+    if device
+        .params
+        .iter()
+        .any(|p| p.starts_with("pcmk_host_list="))
+    {
+        return Err("Set the nodes of the device with hostList, not pcmk_host_list".into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str, host_list: &[&str], params: &[&str]) -> StonithDeviceInput {
+        StonithDeviceInput {
+            id: id.into(),
+            agent: "fence_ipmilan".into(),
+            host_list: host_list.iter().map(|x| x.to_string()).collect(),
+            params: params.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let nodes = ["oss1", "oss2"];
+
+        assert_eq!(
+            validate(&device("st-oss1", &["oss1"], &["ip=10.0.0.1"]), &nodes),
+            Ok(())
+        );
+        assert!(validate(&device("--force", &["oss1"], &[]), &nodes).is_err());
+        assert!(validate(&device("st-oss1", &[], &[]), &nodes).is_err());
+        assert!(validate(&device("st-oss3", &["oss3"], &[]), &nodes).is_err());
+        assert!(validate(&device("st-oss1", &["oss1"], &["--force"]), &nodes).is_err());
+        assert!(validate(
+            &device("st-oss1", &["oss1"], &["pcmk_host_list=oss2"]),
+            &nodes
+        )
+        .is_err());
+    }
+}
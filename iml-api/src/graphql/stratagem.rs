@@ -5,22 +5,41 @@
 use crate::{
     command::get_command,
     error::ImlApiError,
-    graphql::{fs_id_by_name, insert_fidlist, insert_task, Context, SendJob},
+    fan_out::try_join_all,
+    fidlist::{get_fid_list, get_fid_page},
+    graphql::{
+        approval, audit, fs_id_by_name, insert_fidlist, insert_task, not_found, page_limit, role,
+        run_jobs_kwargs, Context, SendJob,
+    },
+    purge_policy, row_count,
 };
-use futures::{
-    future::{self, try_join_all},
-    TryFutureExt, TryStreamExt,
-};
-use iml_manager_env::get_report_path;
-use iml_postgres::{sqlx, PgPool};
+use futures::{future, TryFutureExt, TryStreamExt};
+use iml_manager_env::{get_graphql_max_page_size, get_report_path};
+use iml_postgres::{sqlx, sqlx::postgres::types::PgInterval, PgPool};
 use iml_wire_types::{
-    graphql_duration::GraphQLDuration, stratagem, task::TaskArgs, Command, StratagemReport,
+    graphql::{JobPriority, PageMeta},
+    graphql_duration::GraphQLDuration,
+    role::Permission,
+    stratagem::{
+        self, CompiledRule, CustomRuleInput, DeviceScanProgress, DirectoryHeat, FidList,
+        FidQueuePage, FsHeatmap, ProjectUsage, ProjectUsageScan, PurgePolicy, PurgeRun, RuleAction,
+        RuleCondition, RuleField, RuleOp, ScanPhase, ScanProgress, StratagemRuleSet,
+    },
+    task::TaskArgs,
+    Command, StratagemReport,
 };
 use juniper::{FieldError, Value};
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom as _,
+    time::Duration,
+};
 use tokio::fs;
 use uuid::Uuid;
 
+/// The deepest level of directories a heat map scan adds up.
+const MAX_HEATMAP_DEPTH: i32 = 8;
+
 pub(crate) struct StratagemQuery;
 
 #[juniper::graphql_object(Context = Context)]
@@ -50,6 +69,299 @@ impl StratagemQuery {
 
         Ok(items)
     }
+    /// List saved custom rule sets, along with the scan expressions their rules compile to.
+    async fn rule_sets(context: &Context) -> juniper::FieldResult<Vec<StratagemRuleSet>> {
+        let xs = sqlx::query!("SELECT id, name, rules FROM stratagem_rule_set ORDER BY name")
+            .fetch_all(&context.pg_pool)
+            .await?;
+
+        let xs = xs
+            .into_iter()
+            .map(|x| {
+                let rules: Vec<CustomRuleInput> = serde_json::from_value(x.rules)?;
+
+                Ok(StratagemRuleSet {
+                    id: x.id,
+                    name: x.name,
+                    rules: compile_rules(&rules)?,
+                })
+            })
+            .collect::<juniper::FieldResult<_>>()?;
+
+        Ok(xs)
+    }
+
+    #[graphql(arguments(command_id(
+        description = "The command returned when the scan was started"
+    )))]
+    /// Progress of the MDT scans started by a command.
+    async fn scan_progress(
+        context: &Context,
+        command_id: i32,
+    ) -> juniper::FieldResult<ScanProgress> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT
+                    j.id,
+                    j.state,
+                    j.errored,
+                    j.cancelled,
+                    s.fqdn AS "fqdn!",
+                    s.config->'device'->>'path' AS device,
+                    p.phase AS "phase?",
+                    p.entries_scanned AS "entries_scanned?",
+                    p.started_at AS "started_at?",
+                    p.updated_at AS "updated_at?"
+                FROM chroma_core_command_jobs cj
+                INNER JOIN chroma_core_job j ON j.id = cj.job_id
+                INNER JOIN (
+                    SELECT job_ptr_id, fqdn, config FROM chroma_core_scanmdtjob
+                    UNION ALL
+                    SELECT job_ptr_id, fqdn, config FROM chroma_core_fastfilescanmdtjob
+                ) s ON s.job_ptr_id = j.id
+                LEFT JOIN stratagem_scan_progress p ON p.job_id = j.id
+                WHERE cj.command_id = $1
+                ORDER BY s.fqdn, j.id
+            "#,
+            command_id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        if xs.is_empty() {
+            return Err(FieldError::new(
+                format!("Command {} did not start any scans", command_id),
+                Value::null(),
+            ));
+        }
+
+        let devices: Vec<_> = xs
+            .into_iter()
+            .map(|x| DeviceScanProgress {
+                phase: scan_phase(&x.state, x.errored, x.cancelled, x.phase.as_deref()),
+                fqdn: x.fqdn,
+                device: x.device.unwrap_or_default(),
+                entries_scanned: x.entries_scanned.map(|x| x as f64),
+                started_at: x.started_at,
+                updated_at: x.updated_at,
+            })
+            .collect();
+
+        let devices_completed = devices
+            .iter()
+            .filter(|x| x.phase == ScanPhase::Complete)
+            .count();
+
+        let entries_scanned = devices.iter().filter_map(|x| x.entries_scanned).sum();
+
+        Ok(ScanProgress {
+            command_id,
+            devices_total: devices.len() as i32,
+            devices_completed: devices_completed as i32,
+            entries_scanned,
+            devices,
+        })
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to report on"),
+        limit(description = "How many scans to return, newest first. Defaults to 10"),
+    ))]
+    /// Usage of each top-level directory of a filesystem, from the most recent project usage scans.
+    async fn project_usage(
+        context: &Context,
+        fs_name: String,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<ProjectUsageScan>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT u.task_id, u.scanned_at, u.project, u.files, u.bytes
+                FROM project_usage u
+                WHERE u.fs_name = $1
+                AND u.task_id IN (
+                    SELECT task_id
+                    FROM project_usage
+                    WHERE fs_name = $1
+                    GROUP BY task_id
+                    ORDER BY max(scanned_at) DESC
+                    LIMIT $2
+                )
+                ORDER BY u.scanned_at DESC, u.task_id DESC, u.bytes DESC, u.project
+            "#,
+            fs_name,
+            limit.unwrap_or(10) as i64
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let mut scans: Vec<(i32, ProjectUsageScan)> = vec![];
+
+        for x in xs {
+            let project = ProjectUsage {
+                project: x.project,
+                files: x.files as f64,
+                bytes: x.bytes as f64,
+            };
+
+            match scans.last_mut() {
+                Some((task_id, scan)) if *task_id == x.task_id => scan.projects.push(project),
+                _ => scans.push((
+                    x.task_id,
+                    ProjectUsageScan {
+                        scanned_at: x.scanned_at,
+                        projects: vec![project],
+                    },
+                )),
+            }
+        }
+
+        Ok(scans.into_iter().map(|(_, x)| x).collect())
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to report on"),
+        depth(description = "How many levels of directories below the root to return"),
+    ))]
+    /// Files and bytes under each directory of a filesystem by when they were last accessed,
+    /// from the most recent heat map scan. `null` if the filesystem hasn't been scanned.
+    async fn fs_heatmap(
+        context: &Context,
+        fs_name: String,
+        depth: Option<i32>,
+    ) -> juniper::FieldResult<Option<FsHeatmap>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT h.scanned_at, h.path, h.depth, h.files, h.bytes, h.recent_files,
+                    h.bytes_week, h.bytes_month, h.bytes_quarter, h.bytes_year, h.bytes_older
+                FROM fs_heatmap h
+                WHERE h.task_id = (
+                    SELECT task_id
+                    FROM fs_heatmap
+                    WHERE fs_name = $1
+                    ORDER BY scanned_at DESC, task_id DESC
+                    LIMIT 1
+                )
+                AND h.depth <= $2
+                ORDER BY h.path
+            "#,
+            fs_name,
+            depth.unwrap_or(MAX_HEATMAP_DEPTH)
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let scanned_at = match xs.first() {
+            Some(x) => x.scanned_at,
+            None => return Ok(None),
+        };
+
+        let directories = xs
+            .into_iter()
+            .map(|x| DirectoryHeat {
+                path: x.path,
+                depth: x.depth,
+                files: x.files as f64,
+                bytes: x.bytes as f64,
+                recent_files: x.recent_files as f64,
+                bytes_week: x.bytes_week as f64,
+                bytes_month: x.bytes_month as f64,
+                bytes_quarter: x.bytes_quarter as f64,
+                bytes_year: x.bytes_year as f64,
+                bytes_older: x.bytes_older as f64,
+            })
+            .collect();
+
+        Ok(Some(FsHeatmap {
+            scanned_at,
+            directories,
+        }))
+    }
+    #[graphql(arguments(fs_name(description = "The filesystem of the policy")))]
+    /// The purge policy of `fs_name`, `null` if it has none.
+    async fn purge_policy(
+        context: &Context,
+        fs_name: String,
+    ) -> juniper::FieldResult<Option<PurgePolicy>> {
+        let x = purge_policy::get_policy(&context.pg_pool, &fs_name).await?;
+
+        Ok(x)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the policy"),
+        limit(description = "How many runs to return, newest first. Defaults to 10"),
+    ))]
+    /// The latest runs of the purge policy of `fs_name`.
+    async fn purge_runs(
+        context: &Context,
+        fs_name: String,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<PurgeRun>> {
+        let xs = purge_policy::get_runs(
+            &context.pg_pool,
+            Some(&fs_name),
+            None,
+            limit.unwrap_or(10) as i64,
+        )
+        .await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(task_id(description = "The id of the task")))]
+    /// The FIDs queued for a task, such as the purge candidates of a scan.
+    /// The list itself is not returned, only its size and the `url` to download it from.
+    async fn fid_list(context: &Context, task_id: i32) -> juniper::FieldResult<Option<FidList>> {
+        let x = get_fid_list(&context.pg_pool, task_id).await?;
+
+        Ok(x)
+    }
+    #[graphql(arguments(
+        task_id(description = "The id of the task"),
+        limit(
+            description = "optional paging limit, defaults to and is capped at the maximum page size"
+        ),
+        offset(description = "Offset into items, defaults to 0"),
+    ))]
+    /// A page of the FIDs queued for a task, in the order they were queued.
+    async fn fid_queue(
+        context: &Context,
+        task_id: i32,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> juniper::FieldResult<FidQueuePage> {
+        let limit = page_limit(limit, get_graphql_max_page_size());
+        let offset = offset.unwrap_or(0).max(0);
+
+        let xs = get_fid_page(&context.pg_pool, task_id, offset as i64, limit as i64).await?;
+
+        let count = row_count::count(
+            &context.pg_pool,
+            "chroma_core_fidtaskqueue",
+            true,
+            |cap| async move {
+                sqlx::query!(
+                    r#"
+                        SELECT COUNT(*) AS "count!" FROM (
+                            SELECT 1 FROM chroma_core_fidtaskqueue WHERE task_id = $1 LIMIT $2
+                        ) x
+                    "#,
+                    task_id,
+                    cap,
+                )
+                .fetch_one(&context.pg_pool)
+                .await
+                .map(|x| x.count)
+            },
+        )
+        .await?;
+
+        Ok(FidQueuePage {
+            data: xs,
+            meta: PageMeta {
+                limit,
+                offset,
+                total_count: count.total as i32,
+                is_estimate: count.is_estimate,
+            },
+        })
+    }
 }
 
 pub(crate) struct StratagemMutation;
@@ -64,6 +376,8 @@ impl StratagemMutation {
         arguments: String,
         fidlist: Vec<String>,
     ) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::RunStratagem).await?;
+
         let uuid = Uuid::new_v4().to_hyphenated().to_string();
 
         let fs_id = fs_id_by_name(&context.pg_pool, &fsname).await?;
@@ -83,6 +397,15 @@ impl StratagemMutation {
         .await?;
 
         insert_fidlist(fidlist, task.id, &context.pg_pool).await?;
+
+        audit::record(
+            context,
+            "stratagem.runTaskFidlist",
+            serde_json::json!({ "fsname": fsname, "jobname": jobname, "taskId": task.id }),
+            None,
+        )
+        .await;
+
         Ok(true)
     }
     async fn run_filesync(
@@ -92,6 +415,8 @@ impl StratagemMutation {
         expression: String,
         action: String,
     ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::RunStratagem).await?;
+
         let uuid = Uuid::new_v4().to_hyphenated().to_string();
 
         let fs_id = fs_id_by_name(&context.pg_pool, &fsname).await?;
@@ -164,10 +489,9 @@ impl StratagemMutation {
             })
         }
 
-        let kwargs: HashMap<String, String> =
-            vec![("message".into(), "Stratagem: Filesync".into())]
-                .into_iter()
-                .collect();
+        let kwargs = run_jobs_kwargs("Stratagem: Filesync", JobPriority::Low);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
 
         let command_id: i32 = iml_job_scheduler_rpc::call(
             &context.rabbit_pool.get().await?,
@@ -180,6 +504,14 @@ impl StratagemMutation {
 
         let command = get_command(&context.pg_pool, command_id).await?;
 
+        audit::record(
+            context,
+            "stratagem.runFilesync",
+            serde_json::json!({ "fsname": fsname, "remote": remote, "action": action }),
+            Some(command.id),
+        )
+        .await;
+
         Ok(command)
     }
     async fn run_cloudsync(
@@ -189,6 +521,8 @@ impl StratagemMutation {
         expression: String,
         action: String,
     ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::RunStratagem).await?;
+
         let uuid = Uuid::new_v4().to_hyphenated().to_string();
 
         let fs_id = fs_id_by_name(&context.pg_pool, &fsname).await?;
@@ -261,10 +595,9 @@ impl StratagemMutation {
             })
         }
 
-        let kwargs: HashMap<String, String> =
-            vec![("message".into(), "Stratagem: Cloudsync".into())]
-                .into_iter()
-                .collect();
+        let kwargs = run_jobs_kwargs("Stratagem: Cloudsync", JobPriority::Low);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
 
         let command_id: i32 = iml_job_scheduler_rpc::call(
             &context.rabbit_pool.get().await?,
@@ -277,14 +610,409 @@ impl StratagemMutation {
 
         let command = get_command(&context.pg_pool, command_id).await?;
 
+        audit::record(
+            context,
+            "stratagem.runCloudsync",
+            serde_json::json!({ "fsname": fsname, "remote": remote, "action": action }),
+            Some(command.id),
+        )
+        .await;
+
         Ok(command)
     }
+    #[graphql(arguments(
+        fsname(description = "The filesystem to rebalance"),
+        threshold(
+            description = "How many percentage points an OST must be above the filesystem usage to be considered over-full"
+        ),
+        min_size(description = "Only migrate files of at least this many MiB. Defaults to 1"),
+    ))]
+    /// Create a migration task that moves files off over-full OSTs onto under-utilized ones.
+    /// The task is populated by scanning the MDTs, and each file is migrated with `lfs migrate`
+    /// as the task is processed.
+    async fn rebalance_osts(
+        context: &Context,
+        fsname: String,
+        threshold: f64,
+        min_size: Option<i32>,
+    ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::RunStratagem).await?;
+
+        if !(0.0..100.0).contains(&threshold) {
+            return Err(FieldError::new(
+                "threshold must be between 0 and 100",
+                Value::null(),
+            ));
+        }
+
+        let min_size = min_size.unwrap_or(1);
+
+        if min_size < 0 {
+            return Err(FieldError::new(
+                "min_size must not be negative",
+                Value::null(),
+            ));
+        }
+
+        let fs_id = fs_id_by_name(&context.pg_pool, &fsname).await?;
+
+        let usage = get_ost_usage(context, &fsname).await?;
+
+        let (over, under) = classify_osts(&usage, threshold);
+
+        if over.is_empty() {
+            return Err(FieldError::new(
+                format!(
+                    "No OST of {} is more than {}% above the filesystem usage",
+                    fsname, threshold
+                ),
+                Value::null(),
+            ));
+        }
+
+        if under.is_empty() {
+            return Err(FieldError::new(
+                format!("No OST of {} is below the filesystem usage", fsname),
+                Value::null(),
+            ));
+        }
+
+        let jobs = rebalance_jobs(
+            &context.pg_pool,
+            fs_id,
+            &fsname,
+            &over,
+            &under,
+            min_size as u64 * 1024 * 1024,
+            0,
+        )
+        .await?;
+
+        let kwargs = run_jobs_kwargs("Stratagem: Rebalance OSTs", JobPriority::Low);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
+
+        let command_id: i32 = iml_job_scheduler_rpc::call(
+            &context.rabbit_pool.get().await?,
+            "run_jobs",
+            vec![jobs],
+            Some(kwargs),
+        )
+        .map_err(ImlApiError::ImlJobSchedulerRpcError)
+        .await?;
+
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        audit::record(
+            context,
+            "stratagem.rebalanceOsts",
+            serde_json::json!({ "fsname": fsname, "threshold": threshold, "minSize": min_size }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+    #[graphql(arguments(fsname(description = "The filesystem to scan")))]
+    /// Scan the MDTs for every file, and add up the files and bytes under each top-level directory.
+    /// The files are resolved and added up by a task on the clients. Once it has processed every
+    /// file, the totals are stored and can be read with the `projectUsage` query.
+    async fn run_project_usage_scan(
+        context: &Context,
+        fsname: String,
+    ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::RunStratagem).await?;
+
+        let uuid = Uuid::new_v4().to_hyphenated().to_string();
+
+        let fs_id = fs_id_by_name(&context.pg_pool, &fsname).await?;
+
+        let task = insert_task(
+            &format!("{}-project_usage-project_usage", uuid),
+            "created",
+            false,
+            false,
+            &["stratagem.project_usage".into()],
+            serde_json::json!({
+                "report_name": format!("project_usage-{}-{}.jsonl", fsname, uuid)
+            }),
+            fs_id,
+            &context.pg_pool,
+        )
+        .await?;
+
+        let command = run_task_scan(
+            context,
+            &fsname,
+            &uuid,
+            task.id,
+            "project_usage",
+            "Stratagem: Project Usage Scan",
+        )
+        .await?;
+
+        audit::record(
+            context,
+            "stratagem.runProjectUsageScan",
+            serde_json::json!({ "fsname": fsname }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+    #[graphql(arguments(
+        fsname(description = "The filesystem to scan"),
+        max_depth(
+            description = "How many levels of directories below the root to add up. Defaults to 3"
+        ),
+    ))]
+    /// Scan the MDTs for every file, and add up the files and bytes under each directory
+    /// by when they were last accessed. The files are resolved and added up by a task on the
+    /// clients. Once it has processed every file, the totals can be read with `fsHeatmap`.
+    async fn run_heatmap_scan(
+        context: &Context,
+        fsname: String,
+        max_depth: Option<i32>,
+    ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::RunStratagem).await?;
+
+        let max_depth = max_depth.unwrap_or(3);
+
+        if max_depth < 1 || max_depth > MAX_HEATMAP_DEPTH {
+            return Err(FieldError::new(
+                format!("max_depth must be between 1 and {}", MAX_HEATMAP_DEPTH),
+                Value::null(),
+            ));
+        }
+
+        let uuid = Uuid::new_v4().to_hyphenated().to_string();
+
+        let fs_id = fs_id_by_name(&context.pg_pool, &fsname).await?;
+
+        let task = insert_task(
+            &format!("{}-heatmap-heatmap", uuid),
+            "created",
+            false,
+            false,
+            &["stratagem.heatmap".into()],
+            serde_json::json!({
+                "report_name": format!("heatmap-{}-{}.jsonl", fsname, uuid),
+                "max_depth": max_depth.to_string(),
+            }),
+            fs_id,
+            &context.pg_pool,
+        )
+        .await?;
+
+        let command = run_task_scan(
+            context,
+            &fsname,
+            &uuid,
+            task.id,
+            "heatmap",
+            "Stratagem: Heat Map Scan",
+        )
+        .await?;
+
+        audit::record(
+            context,
+            "stratagem.runHeatmapScan",
+            serde_json::json!({ "fsname": fsname, "maxDepth": max_depth }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to purge"),
+        interval(description = "How often the filesystem is scanned"),
+        purge_after(description = "Purge files not accessed for this long"),
+        max_files(description = "The most files one run purges"),
+        max_bytes(description = "The most bytes one run purges"),
+        protected_paths(
+            description = "Never purge files under these directories, relative to the root of the filesystem"
+        ),
+        require_approval(
+            description = "Wait for an operator to approve each run before purging, defaults to `true`"
+        ),
+        enabled(description = "Whether the policy is scanned, defaults to `true`"),
+    ))]
+    /// Scan `fs_name` every `interval` and purge the files not accessed for `purgeAfter`,
+    /// at most `maxFiles` files and `maxBytes` bytes per run. Replaces any existing policy.
+    /// Runs are listed by `purgeRuns`, and approved with `approvePurgeRun`.
+    async fn set_purge_policy(
+        context: &Context,
+        fs_name: String,
+        interval: GraphQLDuration,
+        purge_after: GraphQLDuration,
+        max_files: f64,
+        max_bytes: f64,
+        protected_paths: Option<Vec<String>>,
+        require_approval: Option<bool>,
+        enabled: Option<bool>,
+    ) -> juniper::FieldResult<PurgePolicy> {
+        role::require(context, Permission::RunStratagem).await?;
+
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        if max_files < 1. || max_bytes < 1. {
+            return Err(FieldError::new(
+                "max_files and max_bytes must be at least 1",
+                Value::null(),
+            ));
+        }
+
+        if interval.0 < Duration::from_secs(60 * 60) {
+            return Err(FieldError::new(
+                "interval must be at least an hour",
+                Value::null(),
+            ));
+        }
+
+        let protected_paths: Vec<String> = protected_paths
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .collect();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO stratagem_purge_policy (
+                    filesystem_name, enabled, interval, purge_after, max_files, max_bytes,
+                    protected_paths, require_approval
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (filesystem_name) DO UPDATE
+                SET enabled = excluded.enabled,
+                    interval = excluded.interval,
+                    purge_after = excluded.purge_after,
+                    max_files = excluded.max_files,
+                    max_bytes = excluded.max_bytes,
+                    protected_paths = excluded.protected_paths,
+                    require_approval = excluded.require_approval
+            "#,
+            fs_name,
+            enabled.unwrap_or(true),
+            PgInterval::try_from(interval.0)?,
+            PgInterval::try_from(purge_after.0)?,
+            max_files as i64,
+            max_bytes as i64,
+            &protected_paths,
+            require_approval.unwrap_or(true)
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        audit::record(
+            context,
+            "stratagem.setPurgePolicy",
+            serde_json::json!({
+                "fsName": fs_name,
+                "interval": interval,
+                "purgeAfter": purge_after,
+                "maxFiles": max_files,
+                "maxBytes": max_bytes,
+                "protectedPaths": protected_paths,
+                "requireApproval": require_approval,
+                "enabled": enabled,
+            }),
+            None,
+        )
+        .await;
+
+        purge_policy::get_policy(&context.pg_pool, &fs_name)
+            .await?
+            .ok_or_else(|| {
+                FieldError::new(
+                    format!("Purge policy of {} not found", fs_name),
+                    Value::null(),
+                )
+            })
+    }
+    #[graphql(arguments(fs_name(description = "The filesystem of the policy")))]
+    /// Stop scanning and purging `fs_name`. Runs waiting for approval are dropped with it.
+    /// Returns whether there was a policy.
+    async fn remove_purge_policy(context: &Context, fs_name: String) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::RunStratagem).await?;
+
+        let removed = sqlx::query!(
+            "DELETE FROM stratagem_purge_policy WHERE filesystem_name = $1",
+            fs_name
+        )
+        .execute(&context.pg_pool)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if removed {
+            audit::record(
+                context,
+                "stratagem.removePurgePolicy",
+                serde_json::json!({ "fsName": fs_name }),
+                None,
+            )
+            .await;
+        }
+
+        Ok(removed)
+    }
+    #[graphql(arguments(id(description = "The id of the run")))]
+    /// Purge the files selected by a run that is waiting for approval.
+    async fn approve_purge_run(context: &Context, id: i32) -> juniper::FieldResult<PurgeRun> {
+        role::require(context, Permission::RunStratagem).await?;
+
+        decide_purge_run(context, id, "approved").await?;
+
+        purge_policy::start_purge(&context.pg_pool, id).await?;
+
+        audit::record(
+            context,
+            "stratagem.approvePurgeRun",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
+        get_purge_run(&context.pg_pool, id).await
+    }
+    #[graphql(arguments(id(description = "The id of the run")))]
+    /// Drop a run that is waiting for approval without purging anything.
+    /// The policy is scanned again at its next interval.
+    async fn reject_purge_run(context: &Context, id: i32) -> juniper::FieldResult<PurgeRun> {
+        role::require(context, Permission::RunStratagem).await?;
+
+        decide_purge_run(context, id, "rejected").await?;
+
+        audit::record(
+            context,
+            "stratagem.rejectPurgeRun",
+            serde_json::json!({ "id": id }),
+            None,
+        )
+        .await;
+
+        get_purge_run(&context.pg_pool, id).await
+    }
+    #[graphql(arguments(
+        fsname(description = "The filesystem to scan"),
+        report_duration(description = "Report files not accessed for this long"),
+        purge_duration(description = "Purge files not accessed for this long"),
+        rule_set(description = "The name of a saved rule set to apply during the scan"),
+        custom_rules(description = "Additional custom rules to apply during the scan"),
+    ))]
     async fn run_fast_file_scan(
         context: &Context,
         fsname: String,
         report_duration: Option<GraphQLDuration>,
         purge_duration: Option<GraphQLDuration>,
+        rule_set: Option<String>,
+        custom_rules: Option<Vec<CustomRuleInput>>,
     ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::RunStratagem).await?;
+
         if let Some((r, p)) = report_duration.as_ref().zip(purge_duration.as_ref()) {
             if r.0 >= p.0 {
                 return Err(FieldError::new(
@@ -294,6 +1022,23 @@ impl StratagemMutation {
             }
         }
 
+        let mut rules = vec![];
+
+        if let Some(name) = &rule_set {
+            let x = sqlx::query!("SELECT rules FROM stratagem_rule_set WHERE name = $1", name)
+                .fetch_optional(&context.pg_pool)
+                .await?
+                .ok_or_else(|| not_found("Rule set", name))?;
+
+            let xs: Vec<CustomRuleInput> = serde_json::from_value(x.rules)?;
+
+            rules.extend(xs);
+        }
+
+        rules.extend(custom_rules.unwrap_or_default());
+
+        let custom_rules = compile_rules(&rules)?;
+
         let uuid = Uuid::new_v4().to_hyphenated().to_string();
 
         let mut cleanup_tasks = vec![];
@@ -359,6 +1104,44 @@ impl StratagemMutation {
             groups.push("purge_fids".into());
         }
 
+        for rule in &custom_rules {
+            let (action, args) = match rule.action {
+                RuleAction::Count => continue,
+                RuleAction::Report => (
+                    "stratagem.warning",
+                    serde_json::json!({
+                        "report_name": format!("{}-{}-{}.txt", rule.name, fsname, uuid)
+                    }),
+                ),
+                RuleAction::Purge => ("stratagem.purge", serde_json::json!({})),
+            };
+
+            let task = insert_task(
+                &format!("{}-custom_rules-{}", uuid, rule.name),
+                &"created",
+                false,
+                false,
+                &[action.into()],
+                args,
+                fs_id,
+                &context.pg_pool,
+            )
+            .await?;
+
+            jobs.push(SendJob {
+                class_name: "CreateTaskJob",
+                args: vec![("task_id".into(), serde_json::json!(task.id))]
+                    .into_iter()
+                    .collect(),
+            });
+
+            cleanup_tasks.push(task);
+        }
+
+        if !custom_rules.is_empty() {
+            groups.push("custom_rules".into());
+        }
+
         let job_range: Vec<_> = (0..jobs.len()).collect();
 
         let xs = get_target_hosts_by_fsname(&fsname, &context.pg_pool).await?;
@@ -455,6 +1238,13 @@ impl StratagemMutation {
                 });
             }
 
+            if !custom_rules.is_empty() {
+                cfg.groups.push(stratagem::StratagemGroup {
+                    name: "custom_rules".into(),
+                    rules: custom_rules.iter().map(to_stratagem_rule).collect(),
+                });
+            }
+
             jobs.push(SendJob {
                 class_name: "FastFileScanMdtJob",
                 args: vec![
@@ -502,10 +1292,9 @@ impl StratagemMutation {
             })
         }
 
-        let kwargs: HashMap<String, String> =
-            vec![("message".into(), "Stratagem: Fast File Scan".into())]
-                .into_iter()
-                .collect();
+        let kwargs = run_jobs_kwargs("Stratagem: Fast File Scan", JobPriority::Low);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
 
         let command_id: i32 = iml_job_scheduler_rpc::call(
             &context.rabbit_pool.get().await?,
@@ -518,6 +1307,14 @@ impl StratagemMutation {
 
         let command = get_command(&context.pg_pool, command_id).await?;
 
+        audit::record(
+            context,
+            "stratagem.runFastFileScan",
+            serde_json::json!({ "fsname": fsname, "ruleSet": rule_set }),
+            Some(command.id),
+        )
+        .await;
+
         Ok(command)
     }
     async fn run_stratagem(
@@ -527,6 +1324,8 @@ impl StratagemMutation {
         tasks: Vec<TaskArgs>,
         groups: Vec<stratagem::StratagemGroup>,
     ) -> juniper::FieldResult<Command> {
+        role::require(context, Permission::RunStratagem).await?;
+
         let mut jobs: Vec<SendJob<HashMap<String, serde_json::Value>>> = vec![];
 
         let fs_id = fs_id_by_name(&context.pg_pool, &fsname).await?;
@@ -617,10 +1416,9 @@ impl StratagemMutation {
             })
         }
 
-        let kwargs: HashMap<String, String> =
-            vec![("message".into(), "Stratagem: Scanning all MDT's".into())]
-                .into_iter()
-                .collect();
+        let kwargs = run_jobs_kwargs("Stratagem: Scanning all MDT's", JobPriority::Low);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
 
         let command_id: i32 = iml_job_scheduler_rpc::call(
             &context.rabbit_pool.get().await?,
@@ -633,17 +1431,90 @@ impl StratagemMutation {
 
         let command = get_command(&context.pg_pool, command_id).await?;
 
+        audit::record(
+            context,
+            "stratagem.runStratagem",
+            serde_json::json!({ "fsname": fsname, "uuid": uuid }),
+            Some(command.id),
+        )
+        .await;
+
         Ok(command)
     }
 
+    #[graphql(arguments(
+        name(description = "The rule set name. An existing rule set with this name is replaced"),
+        rules(description = "The rules in this set"),
+    ))]
+    /// Validate and save a named set of custom rules so it can be reused across scans.
+    async fn save_rule_set(
+        context: &Context,
+        name: String,
+        rules: Vec<CustomRuleInput>,
+    ) -> juniper::FieldResult<StratagemRuleSet> {
+        role::require(context, Permission::RunStratagem).await?;
+
+        let compiled = compile_rules(&rules)?;
+
+        let id = sqlx::query!(
+            r#"
+                INSERT INTO stratagem_rule_set (name, rules)
+                VALUES ($1, $2)
+                ON CONFLICT (name)
+                DO UPDATE SET rules = EXCLUDED.rules
+                RETURNING id
+            "#,
+            name,
+            serde_json::to_value(&rules)?,
+        )
+        .fetch_one(&context.pg_pool)
+        .await?
+        .id;
+
+        audit::record(
+            context,
+            "stratagem.saveRuleSet",
+            serde_json::json!({ "name": name }),
+            None,
+        )
+        .await;
+
+        Ok(StratagemRuleSet {
+            id,
+            name,
+            rules: compiled,
+        })
+    }
+    /// Delete a saved rule set
+    #[graphql(arguments(name(description = "The rule set name")))]
+    async fn delete_rule_set(context: &Context, name: String) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::RunStratagem).await?;
+
+        sqlx::query!("DELETE FROM stratagem_rule_set WHERE name = $1", name)
+            .execute(&context.pg_pool)
+            .await?;
+
+        audit::record(
+            context,
+            "stratagem.deleteRuleSet",
+            serde_json::json!({ "name": name }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+
     /// Delete a stratagem report
     #[graphql(arguments(filename(description = "The report filename to delete")))]
     async fn delete_stratagem_report(
-        _context: &Context,
+        context: &Context,
         filename: String,
     ) -> juniper::FieldResult<bool> {
+        role::require(context, Permission::RunStratagem).await?;
+
         let report_base = get_report_path();
-        let path = tokio::fs::canonicalize(report_base.join(filename)).await?;
+        let path = tokio::fs::canonicalize(report_base.join(&filename)).await?;
 
         if !path.starts_with(report_base) {
             return Err(FieldError::new("Invalid path", Value::null()));
@@ -651,10 +1522,30 @@ impl StratagemMutation {
 
         tokio::fs::remove_file(path).await?;
 
+        audit::record(
+            context,
+            "stratagem.deleteStratagemReport",
+            serde_json::json!({ "filename": filename }),
+            None,
+        )
+        .await;
+
         Ok(true)
     }
 }
 
+/// Combines the state of a scan job with the progress its steps reported.
+fn scan_phase(state: &str, errored: bool, cancelled: bool, phase: Option<&str>) -> ScanPhase {
+    match (state, phase) {
+        ("complete", _) if cancelled => ScanPhase::Cancelled,
+        ("complete", _) if errored => ScanPhase::Failed,
+        ("complete", _) | (_, Some("complete")) => ScanPhase::Complete,
+        (_, Some("streaming")) => ScanPhase::Streaming,
+        (_, Some("scanning")) => ScanPhase::Scanning,
+        _ => ScanPhase::Pending,
+    }
+}
+
 async fn get_stratagem_files(
     (file_path, filename): (String, String),
 ) -> juniper::FieldResult<StratagemReport> {
@@ -667,6 +1558,547 @@ async fn get_stratagem_files(
     })
 }
 
+/// Validates custom rules and compiles each one into a scan expression.
+fn compile_rules(rules: &[CustomRuleInput]) -> Result<Vec<CompiledRule>, FieldError> {
+    let mut names = HashSet::new();
+
+    rules
+        .iter()
+        .map(|x| {
+            let valid_name = !x.name.is_empty()
+                && x.name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+            if !valid_name {
+                return Err(FieldError::new(
+                    format!(
+                        "Invalid rule name '{}'. Only letters, digits, '_' and '-' are allowed.",
+                        x.name
+                    ),
+                    Value::null(),
+                ));
+            }
+
+            if !names.insert(x.name.as_str()) {
+                return Err(FieldError::new(
+                    format!("Duplicate rule name '{}'", x.name),
+                    Value::null(),
+                ));
+            }
+
+            if x.conditions.is_empty() {
+                return Err(FieldError::new(
+                    format!("Rule '{}' must have at least one condition", x.name),
+                    Value::null(),
+                ));
+            }
+
+            let mut xs = vec!["!= type S_IFDIR".to_string()];
+
+            for c in &x.conditions {
+                xs.push(compile_condition(c)?);
+            }
+
+            Ok(CompiledRule {
+                name: x.name.clone(),
+                action: x.action,
+                expression: all_of(&xs),
+            })
+        })
+        .collect()
+}
+
+fn compile_condition(x: &RuleCondition) -> Result<String, FieldError> {
+    let field = match x.field {
+        RuleField::Atime => "atime",
+        RuleField::Mtime => "mtime",
+        RuleField::Ctime => "ctime",
+        RuleField::Size => "size",
+        RuleField::Uid => "uid",
+        RuleField::Gid => "gid",
+    };
+
+    match x.field {
+        RuleField::Atime | RuleField::Mtime | RuleField::Ctime => {
+            let age: std::time::Duration = x
+                .value
+                .parse::<humantime::Duration>()
+                .map_err(|e| {
+                    FieldError::new(
+                        format!("Invalid duration '{}' for {}: {}", x.value, field, e),
+                        Value::null(),
+                    )
+                })?
+                .into();
+
+            // The value is an age, while the field is a timestamp.
+            // A file older than the age has a timestamp before `sys_time - age`.
+            let op = match x.op {
+                RuleOp::Lt => RuleOp::Gt,
+                RuleOp::Le => RuleOp::Ge,
+                RuleOp::Gt => RuleOp::Lt,
+                RuleOp::Ge => RuleOp::Le,
+                op => op,
+            };
+
+            Ok(format!(
+                "{} {} - sys_time {}",
+                op_symbol(op),
+                field,
+                age.as_millis()
+            ))
+        }
+        RuleField::Size | RuleField::Uid | RuleField::Gid => {
+            let n: u64 = x.value.trim().parse().map_err(|_| {
+                FieldError::new(
+                    format!(
+                        "Invalid value '{}' for {}, expected a whole number",
+                        x.value, field
+                    ),
+                    Value::null(),
+                )
+            })?;
+
+            Ok(format!("{} {} {}", op_symbol(x.op), field, n))
+        }
+    }
+}
+
+fn op_symbol(x: RuleOp) -> &'static str {
+    match x {
+        RuleOp::Lt => "<",
+        RuleOp::Le => "<=",
+        RuleOp::Gt => ">",
+        RuleOp::Ge => ">=",
+        RuleOp::Eq => "==",
+        RuleOp::Ne => "!=",
+    }
+}
+
+/// Joins expressions with `&&`, using the prefix notation scan expressions expect.
+fn all_of(xs: &[String]) -> String {
+    match xs {
+        [] => String::new(),
+        [x] => x.clone(),
+        [x, rest @ ..] => format!("&& {} {}", x, all_of(rest)),
+    }
+}
+
+fn to_stratagem_rule(x: &CompiledRule) -> stratagem::StratagemRule {
+    match x.action {
+        RuleAction::Count => stratagem::StratagemRule {
+            action: "LAT_COUNTER_INC".into(),
+            expression: x.expression.clone(),
+            argument: x.name.clone(),
+            counter_name: None,
+        },
+        RuleAction::Report | RuleAction::Purge => stratagem::StratagemRule {
+            action: "LAT_SHELL_CMD_FID".into(),
+            expression: x.expression.clone(),
+            argument: x.name.clone(),
+            counter_name: Some(x.name.clone()),
+        },
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct OstUsage {
+    index: u32,
+    used_kb: u64,
+    total_kb: u64,
+}
+
+/// Reads the current space usage of every mounted OST of `fsname` from its active host.
+async fn get_ost_usage(context: &Context, fsname: &str) -> Result<Vec<OstUsage>, FieldError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT t.name, h.fqdn
+            FROM target t
+            INNER JOIN chroma_core_managedhost h
+            ON h.id = t.active_host_id AND h.not_deleted = 't'
+            WHERE $1 = ANY(t.filesystems) AND t.name LIKE '%-OST%'
+            ORDER BY t.name
+        "#,
+        fsname
+    )
+    .fetch_all(&context.pg_pool)
+    .await?;
+
+    if xs.is_empty() {
+        return Err(FieldError::new(
+            format!("Filesystem {} has no mounted OSTs", fsname),
+            Value::null(),
+        ));
+    }
+
+    try_join_all(xs.into_iter().map(|x| async move {
+        let index = ost_index(&x.name).ok_or_else(|| {
+            FieldError::new(format!("Invalid OST name {}", x.name), Value::null())
+        })?;
+
+        let total_kb = read_ost_kbytes(context, &x.fqdn, &x.name, "kbytestotal").await?;
+        let free_kb = read_ost_kbytes(context, &x.fqdn, &x.name, "kbytesfree").await?;
+
+        Ok::<_, FieldError>(OstUsage {
+            index,
+            used_kb: total_kb.saturating_sub(free_kb),
+            total_kb,
+        })
+    }))
+    .await
+}
+
+async fn read_ost_kbytes(
+    context: &Context,
+    fqdn: &str,
+    ost: &str,
+    key: &str,
+) -> Result<u64, FieldError> {
+    let param = format!("obdfilter.{}.{}", ost, key);
+
+    let x = context
+        .action_client
+        .invoke_rust_agent_expect_result(
+            fqdn.to_string(),
+            "lctl",
+            vec!["get_param", "-n", &param],
+            None,
+        )
+        .await
+        .map_err(ImlApiError::from)?
+        .and_then(|x| serde_json::from_value::<String>(x).map_err(|e| e.to_string()))
+        .map_err(|e| {
+            FieldError::new(
+                format!("Could not read {} on {}: {}", param, fqdn, e),
+                Value::null(),
+            )
+        })?;
+
+    x.trim().parse().map_err(|_| {
+        FieldError::new(
+            format!("Invalid value {:?} for {} on {}", x, param, fqdn),
+            Value::null(),
+        )
+    })
+}
+
+/// The jobs of a migration task that moves the files of at least `min_size` bytes on OSTs `over`
+/// onto OSTs `under`: one creating the task, then a scan of every target for the files to move.
+///
+/// The jobs are run after the first `offset` jobs of their command.
+pub(super) async fn rebalance_jobs(
+    pool: &PgPool,
+    fs_id: i32,
+    fsname: &str,
+    over: &[u32],
+    under: &[u32],
+    min_size: u64,
+    offset: usize,
+) -> Result<Vec<SendJob<'static, HashMap<String, serde_json::Value>>>, FieldError> {
+    let uuid = Uuid::new_v4().to_hyphenated().to_string();
+
+    let osts = under
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let task = insert_task(
+        &format!("{}-rebalance-rebalance", uuid),
+        "created",
+        false,
+        false,
+        &["stratagem.migrate".into()],
+        serde_json::json!({ "osts": osts }),
+        fs_id,
+        pool,
+    )
+    .await?;
+
+    let mut create_task_args: HashMap<String, serde_json::Value> =
+        vec![("task_id".into(), serde_json::json!(task.id))]
+            .into_iter()
+            .collect();
+
+    if offset > 0 {
+        create_task_args.insert(
+            "depends_on_job_range".into(),
+            serde_json::to_value((0..offset).collect::<Vec<_>>())?,
+        );
+    }
+
+    let mut jobs = vec![SendJob {
+        class_name: "CreateTaskJob",
+        args: create_task_args,
+    }];
+
+    let job_range: Vec<_> = (0..=offset).collect();
+
+    let expression = rebalance_expression(over, min_size);
+
+    let xs = get_target_hosts_by_fsname(fsname, pool).await?;
+
+    for x in xs {
+        let path = match x.dev_path {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let cfg = stratagem::StratagemConfig {
+            flist_type: "none".into(),
+            summarize_size: true,
+            device: stratagem::StratagemDevice {
+                path,
+                groups: vec!["rebalance".into()],
+            },
+            groups: vec![stratagem::StratagemGroup {
+                name: "rebalance".into(),
+                rules: vec![stratagem::StratagemRule {
+                    action: "LAT_SHELL_CMD_FID".into(),
+                    expression: expression.clone(),
+                    argument: "rebalance".into(),
+                    counter_name: Some("rebalance".into()),
+                }],
+            }],
+        };
+
+        jobs.push(SendJob {
+            class_name: "ScanMdtJob",
+            args: vec![
+                ("fqdn".into(), serde_json::to_value(&x.fqdn)?),
+                ("uuid".into(), serde_json::to_value(&uuid)?),
+                ("fsname".into(), serde_json::to_value(fsname)?),
+                ("config".into(), serde_json::to_value(cfg)?),
+                (
+                    "depends_on_job_range".into(),
+                    serde_json::to_value(&job_range)?,
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        })
+    }
+
+    Ok(jobs)
+}
+
+/// Splits the OSTs of `fsname` like `classify_osts`, as they will be once OSTs `new` are added.
+/// New OSTs start empty, and are taken to be the average size of the current ones.
+pub(super) async fn classify_with_new_osts(
+    context: &Context,
+    fsname: &str,
+    new: &[u32],
+    threshold: f64,
+) -> Result<(Vec<u32>, Vec<u32>), FieldError> {
+    let usage = get_ost_usage(context, fsname).await?;
+
+    Ok(classify_osts(&with_new_osts(usage, new), threshold))
+}
+
+fn with_new_osts(mut xs: Vec<OstUsage>, new: &[u32]) -> Vec<OstUsage> {
+    let total_kb = match xs.len() as u64 {
+        0 => 0,
+        n => xs.iter().map(|x| x.total_kb).sum::<u64>() / n,
+    };
+
+    xs.extend(new.iter().map(|&index| OstUsage {
+        index,
+        used_kb: 0,
+        total_kb,
+    }));
+
+    xs
+}
+
+/// Parses the index of an OST from its name, e.g. `fs-OST000a` is `10`.
+fn ost_index(name: &str) -> Option<u32> {
+    let x = name.rsplitn(2, '-').next()?.strip_prefix("OST")?;
+
+    u32::from_str_radix(x, 16).ok()
+}
+
+/// Splits OSTs into over-full ones, whose usage is more than `threshold` percentage points
+/// above the filesystem usage, and under-utilized ones, whose usage is below it.
+fn classify_osts(xs: &[OstUsage], threshold: f64) -> (Vec<u32>, Vec<u32>) {
+    let used: u64 = xs.iter().map(|x| x.used_kb).sum();
+    let total: u64 = xs.iter().map(|x| x.total_kb).sum();
+
+    if total == 0 {
+        return (vec![], vec![]);
+    }
+
+    let mean = used as f64 / total as f64 * 100.0;
+
+    let mut over = vec![];
+    let mut under = vec![];
+
+    for x in xs.iter().filter(|x| x.total_kb > 0) {
+        let pct = x.used_kb as f64 / x.total_kb as f64 * 100.0;
+
+        if pct > mean + threshold {
+            over.push(x.index);
+        } else if pct < mean {
+            under.push(x.index);
+        }
+    }
+
+    (over, under)
+}
+
+/// Joins expressions with `||`, using the prefix notation scan expressions expect.
+fn any_of(xs: &[String]) -> String {
+    match xs {
+        [] => String::new(),
+        [x] => x.clone(),
+        [x, rest @ ..] => format!("|| {} {}", x, any_of(rest)),
+    }
+}
+
+/// Matches regular files of at least `min_size` bytes with an object on any of `osts`.
+fn rebalance_expression(osts: &[u32], min_size: u64) -> String {
+    let osts: Vec<_> = osts.iter().map(|x| format!("ost {}", x)).collect();
+
+    all_of(&[
+        "== type S_IFREG".to_string(),
+        format!(">= size {}", min_size),
+        any_of(&osts),
+    ])
+}
+
+/// Moves purge run `id` from `awaiting_approval` to `state`, as decided by the current user.
+async fn decide_purge_run(context: &Context, id: i32, state: &str) -> juniper::FieldResult<()> {
+    let updated = sqlx::query!(
+        r#"
+            UPDATE stratagem_purge_run
+            SET state = $2,
+                decided_by = (SELECT username FROM auth_user WHERE id = $3),
+                decided_at = now(),
+                fids = CASE WHEN $2 = 'rejected' THEN '{}' ELSE fids END
+            WHERE id = $1 AND state = 'awaiting_approval'
+        "#,
+        id,
+        state,
+        context.user_id
+    )
+    .execute(&context.pg_pool)
+    .await?
+    .rows_affected();
+
+    if updated == 0 {
+        return Err(FieldError::new(
+            format!("Purge run {} is not awaiting approval", id),
+            Value::null(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn get_purge_run(pool: &PgPool, id: i32) -> juniper::FieldResult<PurgeRun> {
+    purge_policy::get_runs(pool, None, Some(id), 1)
+        .await?
+        .pop()
+        .ok_or_else(|| not_found("Purge run", id))
+}
+
+/// Scans the MDTs of `fsname` for every file and hands each one to the task `task_id`,
+/// through the Stratagem group `name`. The task is removed once the scan is over.
+async fn run_task_scan(
+    context: &Context,
+    fsname: &str,
+    uuid: &str,
+    task_id: i32,
+    name: &str,
+    description: &str,
+) -> juniper::FieldResult<Command> {
+    let mut jobs: Vec<SendJob<HashMap<String, serde_json::Value>>> = vec![SendJob {
+        class_name: "CreateTaskJob",
+        args: vec![("task_id".into(), serde_json::json!(task_id))]
+            .into_iter()
+            .collect(),
+    }];
+
+    let job_range: Vec<_> = (0..jobs.len()).collect();
+
+    let xs = get_target_hosts_by_fsname(fsname, &context.pg_pool).await?;
+
+    for x in xs {
+        let path = match x.dev_path {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let cfg = stratagem::StratagemConfig {
+            flist_type: "none".into(),
+            summarize_size: true,
+            device: stratagem::StratagemDevice {
+                path,
+                groups: vec![name.into()],
+            },
+            groups: vec![stratagem::StratagemGroup {
+                name: name.into(),
+                rules: vec![stratagem::StratagemRule {
+                    action: "LAT_SHELL_CMD_FID".into(),
+                    expression: "!= type S_IFDIR".into(),
+                    argument: name.into(),
+                    counter_name: Some(name.into()),
+                }],
+            }],
+        };
+
+        jobs.push(SendJob {
+            class_name: "ScanMdtJob",
+            args: vec![
+                ("fqdn".into(), serde_json::to_value(&x.fqdn)?),
+                ("uuid".into(), serde_json::to_value(uuid)?),
+                ("fsname".into(), serde_json::to_value(fsname)?),
+                ("config".into(), serde_json::to_value(cfg)?),
+                (
+                    "depends_on_job_range".into(),
+                    serde_json::to_value(&job_range)?,
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        })
+    }
+
+    let job_range: Vec<_> = (0..jobs.len()).collect();
+
+    // Removing the task marks the end of the scan, which tells its collector
+    // that no more files will be added to it.
+    jobs.push(SendJob {
+        class_name: "RemoveTaskJob",
+        args: vec![
+            ("task_id".into(), serde_json::json!(task_id)),
+            (
+                "depends_on_job_range".into(),
+                serde_json::to_value(&job_range)?,
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    });
+
+    let kwargs = run_jobs_kwargs(description, JobPriority::Low);
+
+    let kwargs = approval::check(context, &jobs, kwargs).await?;
+
+    let command_id: i32 = iml_job_scheduler_rpc::call(
+        &context.rabbit_pool.get().await?,
+        "run_jobs",
+        vec![jobs],
+        Some(kwargs),
+    )
+    .map_err(ImlApiError::ImlJobSchedulerRpcError)
+    .await?;
+
+    let command = get_command(&context.pg_pool, command_id).await?;
+
+    Ok(command)
+}
+
 #[derive(Debug)]
 struct TargetHost {
     name: String,
@@ -707,3 +2139,155 @@ async fn get_target_hosts_by_fsname(
 
     Ok(xs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(field: RuleField, op: RuleOp, value: &str) -> RuleCondition {
+        RuleCondition {
+            field,
+            op,
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn test_compile_rules() {
+        let rules = vec![CustomRuleInput {
+            name: "old_big_files".into(),
+            action: RuleAction::Report,
+            conditions: vec![
+                condition(RuleField::Atime, RuleOp::Gt, "1day"),
+                condition(RuleField::Size, RuleOp::Ge, "1048576"),
+                condition(RuleField::Uid, RuleOp::Eq, "1000"),
+            ],
+        }];
+
+        let xs = compile_rules(&rules).unwrap();
+
+        assert_eq!(
+            xs[0].expression,
+            "&& != type S_IFDIR && < atime - sys_time 86400000 && >= size 1048576 == uid 1000"
+        );
+        assert_eq!(
+            to_stratagem_rule(&xs[0]).counter_name.as_deref(),
+            Some("old_big_files")
+        );
+    }
+
+    #[test]
+    fn test_scan_phase() {
+        assert_eq!(
+            scan_phase("pending", false, false, None),
+            ScanPhase::Pending
+        );
+        assert_eq!(
+            scan_phase("tasked", false, false, Some("scanning")),
+            ScanPhase::Scanning
+        );
+        assert_eq!(
+            scan_phase("tasked", false, false, Some("streaming")),
+            ScanPhase::Streaming
+        );
+        assert_eq!(
+            scan_phase("complete", false, false, Some("complete")),
+            ScanPhase::Complete
+        );
+        assert_eq!(
+            scan_phase("complete", true, false, Some("scanning")),
+            ScanPhase::Failed
+        );
+        assert_eq!(
+            scan_phase("complete", false, true, None),
+            ScanPhase::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_ost_index() {
+        assert_eq!(ost_index("fs-OST0000"), Some(0));
+        assert_eq!(ost_index("my-fs-OST000a"), Some(10));
+        assert_eq!(ost_index("fs-MDT0000"), None);
+        assert_eq!(ost_index("fs-OSTzz"), None);
+    }
+
+    #[test]
+    fn test_classify_osts() {
+        let ost = |index, used_kb| OstUsage {
+            index,
+            used_kb,
+            total_kb: 100,
+        };
+
+        let xs = vec![ost(0, 90), ost(1, 50), ost(2, 20), ost(3, 40)];
+
+        // Filesystem usage is 50%
+        assert_eq!(classify_osts(&xs, 10.0), (vec![0], vec![2, 3]));
+        assert_eq!(classify_osts(&xs, 50.0), (vec![], vec![2, 3]));
+        assert_eq!(classify_osts(&[], 10.0), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_classify_with_new_osts() {
+        let ost = |index, used_kb| OstUsage {
+            index,
+            used_kb,
+            total_kb: 100,
+        };
+
+        let xs = with_new_osts(vec![ost(0, 60), ost(1, 40)], &[2, 3]);
+
+        assert_eq!(xs[3], ost(3, 0));
+
+        // Filesystem usage drops from 50% to 25%
+        assert_eq!(classify_osts(&xs, 20.0), (vec![0], vec![2, 3]));
+    }
+
+    #[test]
+    fn test_rebalance_expression() {
+        assert_eq!(
+            rebalance_expression(&[1], 1024),
+            "&& == type S_IFREG && >= size 1024 ost 1"
+        );
+        assert_eq!(
+            rebalance_expression(&[0, 3, 7], 1048576),
+            "&& == type S_IFREG && >= size 1048576 || ost 0 || ost 3 ost 7"
+        );
+    }
+
+    #[test]
+    fn test_compile_rules_invalid() {
+        let rule = CustomRuleInput {
+            name: "x".into(),
+            action: RuleAction::Count,
+            conditions: vec![condition(RuleField::Size, RuleOp::Lt, "100")],
+        };
+
+        assert!(compile_rules(&[rule.clone(), rule.clone()]).is_err());
+
+        assert!(compile_rules(&[CustomRuleInput {
+            name: "bad name".into(),
+            ..rule.clone()
+        }])
+        .is_err());
+
+        assert!(compile_rules(&[CustomRuleInput {
+            conditions: vec![],
+            ..rule.clone()
+        }])
+        .is_err());
+
+        assert!(compile_rules(&[CustomRuleInput {
+            conditions: vec![condition(RuleField::Mtime, RuleOp::Lt, "soon")],
+            ..rule.clone()
+        }])
+        .is_err());
+
+        assert!(compile_rules(&[CustomRuleInput {
+            conditions: vec![condition(RuleField::Gid, RuleOp::Eq, "-1")],
+            ..rule
+        }])
+        .is_err());
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! The default layout of new files in a filesystem, a stripe count and size or a
+//! progressive file layout (PFL), kept as the layout of its root.
+
+use crate::{
+    command::get_command,
+    error::ImlApiError,
+    graphql::{audit, dne::mounted_client, invalid, run_jobs, Context, SendJob},
+};
+use iml_postgres::sqlx;
+use iml_wire_types::{
+    stripe::{DefaultStripe, StripeComponent},
+    Command,
+};
+use juniper::{FieldError, Value};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+lazy_static! {
+    static ref POOL_NAME: Regex = Regex::new(r"^[A-Za-z0-9_\-]{1,15}$").unwrap();
+}
+
+/// Stripe sizes and component extents are multiples of this
+const STRIPE_ALIGN: u64 = 64 * 1024;
+
+/// Lustre stores stripe sizes in 32 bits
+const MAX_STRIPE_SIZE: u64 = 4 * 1024 * 1024 * 1024 - STRIPE_ALIGN;
+
+/// The default layout of new files in `fs_name`, read from a client that has it mounted.
+pub(crate) async fn get_default_stripe(
+    context: &Context,
+    fs_name: String,
+) -> juniper::FieldResult<DefaultStripe> {
+    let (_, fqdn) = mounted_client(context, &fs_name).await?.ok_or_else(|| {
+        FieldError::new(format!("No client has {} mounted", fs_name), Value::null())
+    })?;
+
+    let x = context
+        .action_client
+        .invoke_rust_agent_expect_result(fqdn.clone(), "get_default_stripe", &fs_name, None)
+        .await
+        .map_err(ImlApiError::from)?
+        .map_err(|e| {
+            FieldError::new(
+                format!("Could not read the file layout on {}: {}", fqdn, e),
+                Value::null(),
+            )
+        })?;
+
+    Ok(DefaultStripe {
+        fs_name,
+        components: serde_json::from_value(x)?,
+    })
+}
+
+/// `x` as a whole number of bytes.
+fn bytes(x: f64) -> Option<u64> {
+    if x >= 0. && x.fract() == 0. && x <= u64::MAX as f64 {
+        Some(x as u64)
+    } else {
+        None
+    }
+}
+
+/// Checks a file layout against the OSTs of a filesystem.
+/// Every component but the last ends at a growing extent, the last one at the end of the file.
+fn validate(ost_count: i32, components: &[StripeComponent]) -> Result<(), String> {
+    let mut start = 0;
+
+    for (i, x) in components.iter().enumerate() {
+        let n = i + 1;
+        let last = n == components.len();
+
+        if x.stripe_count < -1 || x.stripe_count > ost_count {
+            return Err(format!(
+                "Component {}: stripe_count must be -1, 0, or at most the number of OSTs, {}",
+                n, ost_count
+            ));
+        }
+
+        let stripe_size = bytes(x.stripe_size)
+            .filter(|x| x % STRIPE_ALIGN == 0 && *x <= MAX_STRIPE_SIZE)
+            .ok_or_else(|| {
+                format!(
+                    "Component {}: stripe_size must be 0 or a multiple of 64 KiB below 4 GiB",
+                    n
+                )
+            })?;
+
+        match (x.extent_end, last) {
+            (None, true) => {}
+            (None, false) => {
+                return Err(format!(
+                    "Component {}: only the last component can end at the end of the file",
+                    n
+                ))
+            }
+            (Some(_), true) => {
+                return Err(format!(
+                    "Component {}: the last component must end at the end of the file",
+                    n
+                ))
+            }
+            (Some(end), false) => {
+                let end = bytes(end)
+                    .filter(|x| *x > start && x % STRIPE_ALIGN == 0)
+                    .filter(|x| stripe_size == 0 || x % stripe_size == 0)
+                    .ok_or_else(|| {
+                        format!(
+                            "Component {}: extent_end must be past the previous component, \
+                             and a multiple of 64 KiB and of its stripe_size",
+                            n
+                        )
+                    })?;
+
+                start = end;
+            }
+        }
+
+        match &x.pool {
+            Some(p) if !POOL_NAME.is_match(p) => {
+                return Err(format!("Component {}: invalid pool name {}", n, p))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets the default layout of new files in `fs_name` with a `SetDefaultStripeJob`,
+/// run on a client that has the filesystem mounted. No components removes the default.
+pub(crate) async fn set_default_stripe(
+    context: &Context,
+    fs_name: String,
+    components: Vec<StripeComponent>,
+) -> juniper::FieldResult<Command> {
+    let ost_count = sqlx::query!(
+        r#"
+            SELECT count(*) AS "count!"
+            FROM target
+            WHERE $1 = ANY(filesystems) AND name LIKE '%-OST%'
+        "#,
+        fs_name
+    )
+    .fetch_one(&context.pg_pool)
+    .await?
+    .count as i32;
+
+    validate(ost_count, &components).map_err(invalid)?;
+
+    let (host_id, _) = mounted_client(context, &fs_name).await?.ok_or_else(|| {
+        FieldError::new(format!("No client has {} mounted", fs_name), Value::null())
+    })?;
+
+    let job = SendJob {
+        class_name: "SetDefaultStripeJob",
+        args: vec![
+            ("host_id".to_string(), serde_json::json!(host_id)),
+            ("filesystem".to_string(), serde_json::json!(fs_name)),
+            ("components".to_string(), serde_json::json!(components)),
+        ]
+        .into_iter()
+        .collect::<HashMap<_, _>>(),
+    };
+
+    let command_id = run_jobs(
+        context,
+        format!("Set default file layout of {}", fs_name),
+        vec![job],
+    )
+    .await?;
+
+    let command = get_command(&context.pg_pool, command_id).await?;
+
+    audit::record(
+        context,
+        "filesystem.setDefaultStripe",
+        serde_json::json!({
+            "fsName": fs_name,
+            "components": components,
+        }),
+        Some(command.id),
+    )
+    .await;
+
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: f64 = 1024. * 1024.;
+
+    fn component(extent_end: Option<f64>, stripe_count: i32, stripe_size: f64) -> StripeComponent {
+        StripeComponent {
+            extent_end,
+            stripe_count,
+            stripe_size,
+            pool: None,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate(8, &[]), Ok(()));
+        assert_eq!(validate(8, &[component(None, 4, MIB)]), Ok(()));
+        assert_eq!(validate(8, &[component(None, 0, 0.)]), Ok(()));
+        assert_eq!(
+            validate(
+                8,
+                &[
+                    component(Some(64. * MIB), 1, MIB),
+                    component(Some(1024. * MIB), 4, 4. * MIB),
+                    component(None, -1, 4. * MIB),
+                ]
+            ),
+            Ok(())
+        );
+
+        assert!(validate(8, &[component(None, 9, MIB)]).is_err());
+        assert!(validate(8, &[component(None, -2, MIB)]).is_err());
+        assert!(validate(8, &[component(None, 1, 1000.)]).is_err());
+        assert!(validate(8, &[component(None, 1, 4096. * MIB)]).is_err());
+        assert!(validate(8, &[component(Some(64. * MIB), 1, MIB)]).is_err());
+        assert!(validate(8, &[component(None, 1, MIB), component(None, 1, MIB)]).is_err());
+        assert!(validate(
+            8,
+            &[
+                component(Some(64. * MIB), 1, MIB),
+                component(Some(32. * MIB), 1, MIB),
+                component(None, 1, MIB),
+            ]
+        )
+        .is_err());
+        assert!(validate(
+            8,
+            &[
+                component(Some(6. * MIB), 1, 4. * MIB),
+                component(None, 1, MIB)
+            ]
+        )
+        .is_err());
+
+        let mut x = component(None, 1, MIB);
+        x.pool = Some("flash".into());
+        assert_eq!(validate(8, &[x.clone()]), Ok(()));
+
+        x.pool = Some("flash; rm".into());
+        assert!(validate(8, &[x]).is_err());
+    }
+}
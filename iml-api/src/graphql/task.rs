@@ -5,12 +5,12 @@
 use crate::{
     command::get_command,
     error::ImlApiError,
-    graphql::{create_task_job, fs_id_by_name, insert_task, run_jobs, Context, SendJob},
+    graphql::{audit, create_task_job, fs_id_by_name, insert_task, run_jobs, Context, SendJob},
 };
 use futures::TryStreamExt;
 use iml_postgres::sqlx;
 use iml_wire_types::{
-    task::{Task, TaskArgs, TaskOut},
+    task::{CreateTaskResult, Task, TaskArgs, TaskOut},
     Command,
 };
 use std::{collections::HashMap, convert::TryInto};
@@ -36,12 +36,6 @@ impl TaskQuery {
     }
 }
 
-#[derive(juniper::GraphQLObject)]
-pub struct CreateTaskResult {
-    task_id: i32,
-    command: Command,
-}
-
 pub(crate) struct TaskMutation;
 
 #[juniper::graphql_object(Context = Context)]
@@ -74,10 +68,18 @@ impl TaskMutation {
 
         let job = create_task_job(task.id);
 
-        let cmd_id = run_jobs("Creating Task", vec![job], &context.rabbit_pool).await?;
+        let cmd_id = run_jobs(context, "Creating Task", vec![job]).await?;
 
         let command = get_command(&context.pg_pool, cmd_id).await?;
 
+        audit::record(
+            context,
+            "task.create",
+            serde_json::json!({ "fsname": fsname, "name": task_args.name, "taskId": task.id }),
+            Some(command.id),
+        )
+        .await;
+
         Ok(CreateTaskResult {
             task_id: task.id,
             command,
@@ -92,10 +94,18 @@ impl TaskMutation {
                 .collect::<HashMap<String, serde_json::Value>>(),
         };
 
-        let cmd_id = run_jobs("Removing Task", vec![job], &context.rabbit_pool).await?;
+        let cmd_id = run_jobs(context, "Removing Task", vec![job]).await?;
 
         let command = get_command(&context.pg_pool, cmd_id).await?;
 
+        audit::record(
+            context,
+            "task.remove",
+            serde_json::json!({ "taskId": task_id }),
+            Some(command.id),
+        )
+        .await;
+
         Ok(command)
     }
 }
@@ -0,0 +1,416 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Runs the GraphQL schema against a real database in tests.
+//!
+//! The database connection comes from `iml_postgres::test_setup`, so everything a test
+//! seeds or mutates happens inside a transaction that is never committed.
+//! The rabbit pool points at a port nothing listens on, so mutations that send jobs
+//! fail with a rabbit error instead of reaching a job scheduler.
+//!
+//! Tests using this need a migrated database and are `#[ignore]`d like the other db tests:
+//!
+//! `cargo test -p iml-api --features test -- --ignored`
+
+use crate::{
+    error::ImlApiError,
+    graphql::{Context, Loaders, MutationRoot, QueryRoot, Schema},
+};
+use iml_postgres::sqlx::{self, Executor as _};
+use iml_rabbit::ConnectionProperties;
+use juniper::{http::GraphQLRequest, EmptySubscription};
+use std::sync::Arc;
+
+/// Servers `oss1.test` (9001) and `oss2.test` (9002) which are managed,
+/// and `oss3.test` (9003) which is not.
+pub(crate) static HOSTS: &str = include_str!("fixtures/hosts.sql");
+
+/// Nothing listens on the tcpmux port.
+static UNREACHABLE_AMQP: &str = "amqp://127.0.0.1:1/%2f";
+
+pub(crate) struct TestContext {
+    schema: Schema,
+    context: Context,
+}
+
+impl TestContext {
+    pub(crate) async fn new() -> Result<Self, ImlApiError> {
+        let pg_pool = iml_postgres::test_setup().await?;
+
+        let rabbit_pool = iml_rabbit::create_pool(
+            UNREACHABLE_AMQP.to_string(),
+            1,
+            ConnectionProperties::default(),
+        );
+
+        Ok(Self {
+            schema: Schema::new(QueryRoot, MutationRoot, EmptySubscription::new()),
+            context: Context {
+                loaders: Arc::new(Loaders::new(&pg_pool)),
+                pg_pool,
+                rabbit_pool,
+                action_client: iml_action_client::Client::default(),
+                user_id: None,
+            },
+        })
+    }
+    /// Runs following requests as the user with `user_id`.
+    pub(crate) fn as_user(mut self, user_id: i32) -> Self {
+        self.context.user_id = Some(user_id);
+
+        self
+    }
+    /// Runs a SQL fixture, which may hold several statements.
+    pub(crate) async fn seed(&self, fixture: &str) -> Result<(), ImlApiError> {
+        self.context.pg_pool.execute(fixture).await?;
+
+        Ok(())
+    }
+    /// Runs a raw SQL query, to check on what a mutation wrote.
+    pub(crate) async fn fetch_all(
+        &self,
+        sql: &str,
+    ) -> Result<Vec<sqlx::postgres::PgRow>, ImlApiError> {
+        let xs = sqlx::query(sql).fetch_all(&self.context.pg_pool).await?;
+
+        Ok(xs)
+    }
+    /// Executes a query or mutation and returns the whole response,
+    /// with `data` and `errors` as they would be sent to a client.
+    pub(crate) async fn execute(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<serde_json::Value, ImlApiError> {
+        let req: GraphQLRequest = serde_json::from_value(serde_json::json!({
+            "query": query,
+            "variables": variables,
+        }))?;
+
+        // Like the server, each request gets its own loaders.
+        let context = Context {
+            loaders: Arc::new(Loaders::new(&self.context.pg_pool)),
+            ..self.context.clone()
+        };
+
+        let res = req.execute(&self.schema, &context).await;
+
+        let x = serde_json::to_value(&res)?;
+
+        Ok(x)
+    }
+    /// Executes a query or mutation and returns its `data`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the response has any errors.
+    pub(crate) async fn data(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<serde_json::Value, ImlApiError> {
+        let mut x = self.execute(query, variables).await?;
+
+        if let Some(errors) = x.get("errors") {
+            panic!("{} failed with {}", query, errors);
+        }
+
+        Ok(x["data"].take())
+    }
+    /// Executes a query or mutation that is expected to fail and returns its first error message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the response has no errors.
+    pub(crate) async fn error(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<String, ImlApiError> {
+        let x = self.execute(query, variables).await?;
+
+        match x["errors"][0]["message"].as_str() {
+            Some(message) => Ok(message.to_string()),
+            None => panic!("{} did not fail, returned {}", query, x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iml_postgres::sqlx::Row as _;
+    use serde_json::json;
+
+    static SAVE_PROFILE: &str = r#"
+        mutation SaveProfile($name: String!, $settings: [TuningSettingInput!]!) {
+          tuning {
+            saveProfile(name: $name, settings: $settings) {
+              name
+            }
+          }
+        }
+    "#;
+
+    static APPLY_PROFILE: &str = r#"
+        mutation ApplyProfile($name: String!, $hostIds: [Int!]!) {
+          tuning {
+            applyProfile(name: $name, hostIds: $hostIds) {
+              id
+            }
+          }
+        }
+    "#;
+
+    fn settings() -> serde_json::Value {
+        json!([
+            { "kind": "sysctl", "key": "vm.min_free_kbytes", "value": "2097152" },
+            { "kind": "module_param", "key": "ko2iblnd.peer_credits", "value": "128" },
+        ])
+    }
+
+    #[tokio::test]
+    #[ignore = "Requires an active and populated DB"]
+    async fn test_save_profile() -> Result<(), ImlApiError> {
+        let ctx = TestContext::new().await?.as_user(1);
+
+        ctx.data(
+            SAVE_PROFILE,
+            json!({ "name": "oss-ib", "settings": settings() }),
+        )
+        .await?;
+
+        let x = ctx
+            .data(
+                r#"
+                    query {
+                      tuning {
+                        profiles {
+                          name
+                          settings {
+                            kind
+                            key
+                            value
+                          }
+                          hostIds
+                        }
+                      }
+                    }
+                "#,
+                json!({}),
+            )
+            .await?;
+
+        assert_eq!(
+            x["tuning"]["profiles"],
+            json!([{ "name": "oss-ib", "settings": settings(), "hostIds": [] }])
+        );
+
+        let audit = ctx
+            .fetch_all("SELECT user_id, mutation FROM api_audit ORDER BY id DESC LIMIT 1")
+            .await?;
+
+        assert_eq!(audit[0].get::<Option<i32>, _>("user_id"), Some(1));
+        assert_eq!(audit[0].get::<String, _>("mutation"), "tuning.saveProfile");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "Requires an active and populated DB"]
+    async fn test_save_profile_invalid() -> Result<(), ImlApiError> {
+        let ctx = TestContext::new().await?;
+
+        let e = ctx
+            .error(
+                SAVE_PROFILE,
+                json!({
+                    "name": "oss-ib",
+                    "settings": [{ "kind": "module_param", "key": "ko2iblnd", "value": "128" }],
+                }),
+            )
+            .await?;
+
+        assert_eq!(e, "Invalid ModuleParam key ko2iblnd");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "Requires an active and populated DB"]
+    async fn test_apply_profile() -> Result<(), ImlApiError> {
+        let ctx = TestContext::new().await?;
+
+        ctx.seed(HOSTS).await?;
+
+        ctx.data(
+            SAVE_PROFILE,
+            json!({ "name": "oss-ib", "settings": settings() }),
+        )
+        .await?;
+
+        let e = ctx
+            .error(
+                APPLY_PROFILE,
+                json!({ "name": "oss-ib", "hostIds": [9001, 9003] }),
+            )
+            .await?;
+
+        assert_eq!(e, "oss3.test is unconfigured, not managed");
+
+        let e = ctx
+            .error(
+                APPLY_PROFILE,
+                json!({ "name": "oss-hdd", "hostIds": [9001] }),
+            )
+            .await?;
+
+        assert_eq!(e, "Tuning profile oss-hdd not found");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "Requires an active and populated DB"]
+    async fn test_certificates() -> Result<(), ImlApiError> {
+        let ctx = TestContext::new().await?;
+
+        ctx.seed(HOSTS).await?;
+        ctx.seed(
+            r#"
+                INSERT INTO chroma_core_clientcertificate (host_id, serial, revoked, not_after)
+                VALUES
+                (9001, 'A1', 't', now() - interval '1 day'),
+                (9001, 'A2', 'f', now() + interval '10 days 1 hour'),
+                (9002, 'B1', 'f', NULL);
+            "#,
+        )
+        .await?;
+
+        let x = ctx
+            .data(
+                r#"
+                    query Certificates($hostIds: [Int!]) {
+                      certificate {
+                        list(hostIds: $hostIds) {
+                          fqdn
+                          serial
+                          daysRemaining
+                        }
+                      }
+                    }
+                "#,
+                json!({ "hostIds": [9001, 9002] }),
+            )
+            .await?;
+
+        assert_eq!(
+            x["certificate"]["list"],
+            json!([
+                { "fqdn": "oss1.test", "serial": "A2", "daysRemaining": 10 },
+                { "fqdn": "oss2.test", "serial": "B1", "daysRemaining": null },
+            ])
+        );
+
+        let e = ctx
+            .error(
+                r#"
+                    mutation Rotate($hostIds: [Int!]!) {
+                      certificate {
+                        rotate(hostIds: $hostIds) {
+                          id
+                        }
+                      }
+                    }
+                "#,
+                json!({ "hostIds": [9003] }),
+            )
+            .await?;
+
+        assert_eq!(e, "oss3.test is unconfigured, not managed");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "Requires an active and populated DB"]
+    async fn test_dashboards() -> Result<(), ImlApiError> {
+        static SAVE: &str = r#"
+            mutation Save($id: Int, $name: String!, $panels: [DashboardPanelInput!]!) {
+              dashboards {
+                save(id: $id, name: $name, panels: $panels) {
+                  id
+                  name
+                  shared
+                }
+              }
+            }
+        "#;
+
+        let ctx = TestContext::new().await?.as_user(1);
+
+        let panels = json!([
+            { "title": "Bandwidth", "kind": "chart", "metric": "io" },
+            { "title": "fs1", "kind": "overview", "fsName": "fs1", "wide": true },
+        ]);
+
+        let x = ctx
+            .data(SAVE, json!({ "name": "NOC", "panels": panels }))
+            .await?;
+
+        let id = x["dashboards"]["save"]["id"].clone();
+
+        assert_eq!(x["dashboards"]["save"]["shared"], json!(false));
+
+        let x = ctx
+            .data(
+                r#"
+                    query {
+                      dashboards {
+                        list {
+                          name
+                          panels {
+                            title
+                            kind
+                            metric
+                            fsName
+                            wide
+                          }
+                        }
+                      }
+                    }
+                "#,
+                json!({}),
+            )
+            .await?;
+
+        assert_eq!(
+            x["dashboards"]["list"],
+            json!([{
+                "name": "NOC",
+                "panels": [
+                    { "title": "Bandwidth", "kind": "chart", "metric": "io", "fsName": null, "wide": false },
+                    { "title": "fs1", "kind": "overview", "metric": null, "fsName": "fs1", "wide": true },
+                ],
+            }])
+        );
+
+        let e = ctx
+            .error(SAVE, json!({ "name": "NOC", "panels": [] }))
+            .await?;
+
+        assert_eq!(e, "Dashboard NOC already exists");
+
+        let e = ctx
+            .error(
+                SAVE,
+                json!({ "id": id, "name": "NOC", "panels": [{ "title": "LNet", "kind": "chart", "metric": "lnet", "fsName": "fs1" }] }),
+            )
+            .await?;
+
+        assert_eq!(e, "Panel 1 can not chart Lnet for a filesystem");
+
+        Ok(())
+    }
+}
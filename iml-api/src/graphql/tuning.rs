@@ -0,0 +1,477 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    command::get_command,
+    error::ImlApiError,
+    fan_out::join_all,
+    graphql::{approval, audit, invalid, not_found, run_jobs_kwargs, Context, SendJob},
+};
+use futures::TryFutureExt;
+use iml_postgres::sqlx;
+use iml_wire_types::{
+    graphql::{JobPriority, TuningDrift, TuningMismatch, TuningProfile, TuningSettingInput},
+    Command, TuningSetting, TuningSettingKind,
+};
+use juniper::{FieldError, Value};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+lazy_static! {
+    static ref PROFILE_NAME: Regex = Regex::new(r"^[\w-]{1,64}$").unwrap();
+    static ref PARAM_KEY: Regex = Regex::new(r"^[\w*-]+(\.[\w*-]+)*$").unwrap();
+    static ref MODULE_PARAM_KEY: Regex = Regex::new(r"^\w+\.\w+$").unwrap();
+}
+
+pub(crate) struct TuningQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl TuningQuery {
+    /// All tuning profiles, with the hosts each one is assigned to
+    async fn profiles(context: &Context) -> juniper::FieldResult<Vec<TuningProfile>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT p.id, p.name, p.settings,
+                    array_remove(array_agg(ph.host_id ORDER BY ph.host_id), NULL) AS "host_ids!"
+                FROM tuning_profile p
+                LEFT OUTER JOIN tuning_profile_host ph ON ph.profile_id = p.id
+                GROUP BY p.id
+                ORDER BY p.name
+            "#
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| {
+            Ok(TuningProfile {
+                id: x.id,
+                name: x.name,
+                settings: serde_json::from_value(x.settings)?,
+                host_ids: x.host_ids,
+            })
+        })
+        .collect::<Result<_, serde_json::Error>>()?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(profile(description = "Only check hosts this profile is assigned to")))]
+    /// Hosts whose live settings differ from their assigned tuning profile.
+    /// Values are read from each host, so this reflects changes made outside of IML.
+    /// Hosts that can not be reached are listed with an `error`.
+    async fn noncompliant_hosts(
+        context: &Context,
+        profile: Option<String>,
+    ) -> juniper::FieldResult<Vec<TuningDrift>> {
+        let hosts = sqlx::query!(
+            r#"
+                SELECT h.id, h.fqdn, p.name, p.settings
+                FROM tuning_profile_host ph
+                INNER JOIN tuning_profile p ON p.id = ph.profile_id
+                INNER JOIN chroma_core_managedhost h ON h.id = ph.host_id
+                WHERE h.not_deleted = 't'
+                AND ($1::TEXT IS NULL OR p.name = $1)
+                ORDER BY h.fqdn
+            "#,
+            profile
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let xs = join_all(hosts.into_iter().map(|x| async move {
+            let settings: Vec<TuningSetting> = match serde_json::from_value(x.settings) {
+                Ok(xs) => xs,
+                Err(e) => return Some(drift(x.id, x.fqdn, x.name, vec![], Some(e.to_string()))),
+            };
+
+            let r = context
+                .action_client
+                .invoke_rust_agent_expect_result(x.fqdn.clone(), "read_tuning", &settings, None)
+                .await;
+
+            let values = match r {
+                Ok(Ok(values)) => {
+                    serde_json::from_value::<Vec<Option<String>>>(values).map_err(|e| e.to_string())
+                }
+                Ok(Err(e)) => Err(e),
+                Err(e) => Err(e.to_string()),
+            };
+
+            let values = match values {
+                Ok(values) => values,
+                Err(e) => return Some(drift(x.id, x.fqdn, x.name, vec![], Some(e))),
+            };
+
+            let mismatches = mismatches(settings, values);
+
+            if mismatches.is_empty() {
+                None
+            } else {
+                Some(drift(x.id, x.fqdn, x.name, mismatches, None))
+            }
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct TuningMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl TuningMutation {
+    #[graphql(arguments(
+        name(description = "The name of the profile"),
+        settings(description = "The settings of the profile, replacing any existing ones"),
+    ))]
+    /// Create a tuning profile, or replace the settings of an existing one.
+    /// Hosts the profile is assigned to are not changed until it is applied again.
+    async fn save_profile(
+        context: &Context,
+        name: String,
+        settings: Vec<TuningSettingInput>,
+    ) -> juniper::FieldResult<TuningProfile> {
+        validate(&name, &settings).map_err(invalid)?;
+
+        let settings: Vec<TuningSetting> = settings
+            .into_iter()
+            .map(|x| TuningSetting {
+                kind: x.kind,
+                key: x.key,
+                value: x.value,
+            })
+            .collect();
+
+        let x = sqlx::query!(
+            r#"
+                INSERT INTO tuning_profile (name, settings)
+                VALUES ($1, $2)
+                ON CONFLICT (name) DO UPDATE
+                SET settings = EXCLUDED.settings, updated_at = now()
+                RETURNING id
+            "#,
+            &name,
+            serde_json::to_value(&settings)?
+        )
+        .fetch_one(&context.pg_pool)
+        .await?;
+
+        let host_ids = sqlx::query!(
+            "SELECT host_id FROM tuning_profile_host WHERE profile_id = $1 ORDER BY host_id",
+            x.id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| x.host_id)
+        .collect();
+
+        audit::record(
+            context,
+            "tuning.saveProfile",
+            serde_json::json!({ "name": name }),
+            None,
+        )
+        .await;
+
+        Ok(TuningProfile {
+            id: x.id,
+            name,
+            settings,
+            host_ids,
+        })
+    }
+    #[graphql(arguments(name(description = "The name of the profile")))]
+    /// Remove a tuning profile and unassign it from its hosts.
+    /// Settings already applied to the hosts are left as they are.
+    async fn remove_profile(context: &Context, name: String) -> juniper::FieldResult<bool> {
+        sqlx::query!(
+            "DELETE FROM tuning_profile WHERE name = $1 RETURNING id",
+            &name
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| not_found("Tuning profile", name))?;
+
+        audit::record(
+            context,
+            "tuning.removeProfile",
+            serde_json::json!({ "name": name }),
+            None,
+        )
+        .await;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        name(description = "The name of the profile"),
+        host_ids(description = "The hosts to apply the profile to"),
+    ))]
+    /// Assign a tuning profile to hosts and apply its settings.
+    /// A host has a single profile, so this replaces any profile assigned before.
+    async fn apply_profile(
+        context: &Context,
+        name: String,
+        host_ids: Vec<i32>,
+    ) -> juniper::FieldResult<Command> {
+        if host_ids.is_empty() {
+            return Err(FieldError::new("No hosts given", Value::null()));
+        }
+
+        let profile = sqlx::query!(
+            "SELECT id, settings FROM tuning_profile WHERE name = $1",
+            &name
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| not_found("Tuning profile", name))?;
+
+        let hosts = sqlx::query!(
+            r#"
+                SELECT id, fqdn, state
+                FROM chroma_core_managedhost
+                WHERE not_deleted = 't' AND id = ANY($1)
+            "#,
+            &host_ids
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        for id in &host_ids {
+            let x = hosts
+                .iter()
+                .find(|x| x.id == *id)
+                .ok_or_else(|| not_found("Host", id))?;
+
+            if x.state != "managed" {
+                return Err(FieldError::new(
+                    format!("{} is {}, not managed", x.fqdn, x.state),
+                    Value::null(),
+                ));
+            }
+        }
+
+        sqlx::query!(
+            r#"
+                INSERT INTO tuning_profile_host (host_id, profile_id)
+                SELECT host_id, $2 FROM UNNEST($1::INT[]) AS host_id
+                ON CONFLICT (host_id) DO UPDATE
+                SET profile_id = EXCLUDED.profile_id, assigned_at = now()
+            "#,
+            &host_ids,
+            profile.id
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        let jobs: Vec<_> = host_ids
+            .iter()
+            .map(|id| SendJob {
+                class_name: "ApplyTuningProfileJob",
+                args: vec![
+                    ("host_id".to_string(), serde_json::json!(id)),
+                    ("profile_name".to_string(), serde_json::json!(name)),
+                    ("settings".to_string(), profile.settings.clone()),
+                ]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+            })
+            .collect();
+
+        let kwargs = run_jobs_kwargs(format!("Apply tuning profile {}", name), JobPriority::High);
+
+        let kwargs = approval::check(context, &jobs, kwargs).await?;
+
+        let command_id: i32 = iml_job_scheduler_rpc::call(
+            &context.rabbit_pool.get().await?,
+            "run_jobs",
+            vec![jobs],
+            Some(kwargs),
+        )
+        .map_err(ImlApiError::ImlJobSchedulerRpcError)
+        .await?;
+
+        let command = get_command(&context.pg_pool, command_id).await?;
+
+        audit::record(
+            context,
+            "tuning.applyProfile",
+            serde_json::json!({ "name": name, "hostIds": host_ids }),
+            Some(command.id),
+        )
+        .await;
+
+        Ok(command)
+    }
+}
+
+fn drift(
+    host_id: i32,
+    fqdn: String,
+    profile: String,
+    mismatches: Vec<TuningMismatch>,
+    error: Option<String>,
+) -> TuningDrift {
+    TuningDrift {
+        host_id,
+        fqdn,
+        profile,
+        mismatches,
+        error,
+    }
+}
+
+/// Pairs each setting with the value read from the host and keeps the ones that differ.
+fn mismatches(settings: Vec<TuningSetting>, values: Vec<Option<String>>) -> Vec<TuningMismatch> {
+    settings
+        .into_iter()
+        .zip(values.into_iter().chain(std::iter::repeat(None)))
+        .filter(|(x, actual)| match actual {
+            Some(actual) => !values_match(&x.value, actual),
+            None => true,
+        })
+        .map(|(x, actual)| TuningMismatch {
+            kind: x.kind,
+            key: x.key,
+            expected: x.value,
+            actual,
+        })
+        .collect()
+}
+
+/// Compares a profile value with a live one, ignoring differences in whitespace.
+/// `lctl get_param` prints one line per target for wildcard keys, each of them has to match.
+fn values_match(expected: &str, actual: &str) -> bool {
+    fn normalize(x: &str) -> String {
+        x.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    let expected = normalize(expected);
+
+    let mut lines = actual.lines().filter(|x| !x.trim().is_empty()).peekable();
+
+    lines.peek().is_some() && lines.all(|x| normalize(x) == expected)
+}
+
+/// Checks a profile before it is stored.
+/// Keys and values end up on the `sysctl` and `lctl` command lines and in modprobe.d,
+/// so they are limited to what those accept.
+fn validate(name: &str, settings: &[TuningSettingInput]) -> Result<(), String> {
+    if !PROFILE_NAME.is_match(name) {
+        return Err(format!("Invalid profile name {}", name));
+    }
+
+    for x in settings {
+        let valid_key = match x.kind {
+            TuningSettingKind::Sysctl | TuningSettingKind::Lctl => PARAM_KEY.is_match(&x.key),
+            TuningSettingKind::ModuleParam => MODULE_PARAM_KEY.is_match(&x.key),
+        };
+
+        if !valid_key {
+            return Err(format!("Invalid {:?} key {}", x.kind, x.key));
+        }
+
+        if x.value.trim().is_empty() || x.value.chars().any(|c| c.is_control() && c != '\t') {
+            return Err(format!("Invalid value for {}", x.key));
+        }
+
+        if x.kind == TuningSettingKind::ModuleParam && x.value.contains(char::is_whitespace) {
+            return Err(format!(
+                "Invalid value for {}, module options can not contain whitespace",
+                x.key
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(kind: TuningSettingKind, key: &str, value: &str) -> TuningSettingInput {
+        TuningSettingInput {
+            kind,
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn test_values_match() {
+        assert!(values_match("128", "128"));
+        assert!(values_match("4096 87380 16777216", "4096\t87380\t16777216"));
+        assert!(values_match("512", "512\n512\n"));
+        assert!(!values_match("512", "512\n256\n"));
+        assert!(!values_match("512", ""));
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let settings = vec![
+            TuningSetting {
+                kind: TuningSettingKind::Lctl,
+                key: "osc.*.max_dirty_mb".into(),
+                value: "512".into(),
+            },
+            TuningSetting {
+                kind: TuningSettingKind::ModuleParam,
+                key: "ko2iblnd.peer_credits".into(),
+                value: "128".into(),
+            },
+        ];
+
+        let xs = mismatches(settings, vec![Some("512".into()), None]);
+
+        assert_eq!(
+            xs,
+            vec![TuningMismatch {
+                kind: TuningSettingKind::ModuleParam,
+                key: "ko2iblnd.peer_credits".into(),
+                expected: "128".into(),
+                actual: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let settings = vec![
+            setting(TuningSettingKind::Sysctl, "vm.min_free_kbytes", "2097152"),
+            setting(TuningSettingKind::Lctl, "osc.fs-OST*.max_dirty_mb", "512"),
+            setting(
+                TuningSettingKind::ModuleParam,
+                "ko2iblnd.peer_credits",
+                "128",
+            ),
+        ];
+
+        assert_eq!(validate("oss-ib", &settings), Ok(()));
+        assert!(validate("oss ib", &settings).is_err());
+
+        let bad_module = vec![setting(TuningSettingKind::ModuleParam, "ko2iblnd", "128")];
+        assert!(validate("oss-ib", &bad_module).is_err());
+
+        let bad_key = vec![setting(
+            TuningSettingKind::Sysctl,
+            "vm.min_free_kbytes=1",
+            "1",
+        )];
+        assert!(validate("oss-ib", &bad_key).is_err());
+
+        let bad_value = vec![setting(TuningSettingKind::Lctl, "lnet.debug", "1\n2")];
+        assert!(validate("oss-ib", &bad_value).is_err());
+
+        let spaced = vec![setting(
+            TuningSettingKind::ModuleParam,
+            "lnet.networks",
+            "o2ib0 tcp0",
+        )];
+        assert!(validate("oss-ib", &spaced).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::Context;
+use iml_postgres::sqlx;
+use juniper::{FieldError, Value};
+
+const MAX_KEY_LEN: usize = 64;
+const MAX_VALUE_LEN: usize = 16 * 1024;
+
+pub(crate) struct UserPreferencesQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl UserPreferencesQuery {
+    #[graphql(arguments(key(description = "The preference key, e.g. `table.targets`")))]
+    /// Get the preference the current user stored under `key`, as a JSON string.
+    async fn get(context: &Context, key: String) -> juniper::FieldResult<Option<String>> {
+        let user_id = current_user(context)?;
+        validate_key(&key)?;
+
+        let x = sqlx::query!(
+            "SELECT value FROM user_preference WHERE user_id = $1 AND key = $2",
+            user_id,
+            key
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .map(|x| x.value.to_string());
+
+        Ok(x)
+    }
+}
+
+pub(crate) struct UserPreferencesMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl UserPreferencesMutation {
+    #[graphql(arguments(
+        key(description = "The preference key, e.g. `table.targets`"),
+        value(description = "The preference, as a JSON string"),
+    ))]
+    /// Store a preference for the current user under `key`, replacing any existing value.
+    async fn set(context: &Context, key: String, value: String) -> juniper::FieldResult<bool> {
+        let user_id = current_user(context)?;
+        validate_key(&key)?;
+
+        if value.len() > MAX_VALUE_LEN {
+            return Err(FieldError::new(
+                format!("Preference values are limited to {} bytes", MAX_VALUE_LEN),
+                Value::null(),
+            ));
+        }
+
+        let value: serde_json::Value = serde_json::from_str(&value)?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_preference (user_id, key, value)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (user_id, key) DO UPDATE SET value = EXCLUDED.value
+            "#,
+            user_id,
+            key,
+            value
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        Ok(true)
+    }
+
+    #[graphql(arguments(key(description = "The preference key to remove")))]
+    /// Remove the preference the current user stored under `key`.
+    async fn remove(context: &Context, key: String) -> juniper::FieldResult<bool> {
+        let user_id = current_user(context)?;
+
+        sqlx::query!(
+            "DELETE FROM user_preference WHERE user_id = $1 AND key = $2",
+            user_id,
+            key
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        Ok(true)
+    }
+}
+
+pub(crate) fn current_user(context: &Context) -> Result<i32, FieldError> {
+    context
+        .user_id
+        .ok_or_else(|| FieldError::new("No user is associated with this request", Value::null()))
+}
+
+fn validate_key(x: &str) -> Result<(), FieldError> {
+    let valid = !x.is_empty()
+        && x.len() <= MAX_KEY_LEN
+        && x.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(FieldError::new(
+            format!(
+                "Invalid preference key {:?}. Keys must be 1 to {} characters of letters, digits, '_', '-' or '.'",
+                x, MAX_KEY_LEN
+            ),
+            Value::null(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_key() {
+        assert!(validate_key("table.targets").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key("table targets").is_err());
+        assert!(validate_key(&"x".repeat(MAX_KEY_LEN + 1)).is_err());
+    }
+}
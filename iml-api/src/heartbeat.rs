@@ -0,0 +1,149 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, HEARTBEAT_LOCK},
+};
+use chrono::{DateTime, Utc};
+use iml_postgres::{alert, sqlx, PgPool};
+use iml_wire_types::{AlertRecordType, AlertSeverity};
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+
+/// How often an agent is expected to poll the manager.
+/// Matches the long poll timeout of the `http_agent` service.
+pub(crate) const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How many heartbeats can be missed before a host needs attention,
+/// when `HEARTBEAT_ALERT_MISSED` does not say otherwise.
+pub(crate) const DEFAULT_MISSED: u32 = 3;
+
+/// Periodically checks when every host's agent was last heard from.
+///
+/// A host whose agent has missed `missed` heartbeats in a row has an `AgentHeartbeatAlert` raised,
+/// which is lowered again once the agent is back in contact.
+pub async fn run(pg_pool: PgPool, missed: u32) {
+    let mut interval = interval(HEARTBEAT_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, HEARTBEAT_LOCK, || {
+            check_heartbeats(&pg_pool, missed)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error checking agent heartbeats: {}", e);
+        }
+    }
+}
+
+async fn check_heartbeats(pool: &PgPool, missed: u32) -> Result<(), ImlApiError> {
+    // Hosts that have never been heard from are still being deployed
+    let xs = sqlx::query!(
+        r#"
+            SELECT h.id, h.fqdn, hb.last_seen
+            FROM chroma_core_managedhost h
+            INNER JOIN host_heartbeat hb ON hb.host_id = h.id
+            WHERE h.not_deleted = 't'
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if xs.is_empty() {
+        return Ok(());
+    }
+
+    let content_type_id = sqlx::query!(
+        "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'managedhost'"
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    let now = Utc::now();
+
+    for x in xs {
+        if needs_attention(x.last_seen, now, missed) {
+            tracing::info!("The agent on {} was last seen at {}", x.fqdn, x.last_seen);
+
+            alert::raise(
+                pool,
+                AlertRecordType::AgentHeartbeatAlert,
+                format!(
+                    "The agent on {} has missed {} heartbeats, last seen {}",
+                    x.fqdn,
+                    missed,
+                    x.last_seen.format("%Y-%m-%d %H:%M:%S UTC")
+                ),
+                content_type_id,
+                None,
+                AlertSeverity::WARNING,
+                x.id,
+            )
+            .await?;
+        } else {
+            alert::lower(pool, vec![AlertRecordType::AgentHeartbeatAlert], x.id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Whole seconds from `last_seen` until `now`.
+pub(crate) fn seconds_since(last_seen: DateTime<Utc>, now: DateTime<Utc>) -> i32 {
+    (now - last_seen).num_seconds().max(0) as i32
+}
+
+/// Whether an agent last seen at `last_seen` has missed `missed` heartbeats by `now`.
+pub(crate) fn needs_attention(last_seen: DateTime<Utc>, now: DateTime<Utc>, missed: u32) -> bool {
+    seconds_since(last_seen, now) as u64 > HEARTBEAT_INTERVAL.as_secs() * missed as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_needs_attention() {
+        let now = Utc.ymd(2021, 1, 13).and_hms(12, 0, 0);
+
+        assert!(!needs_attention(
+            Utc.ymd(2021, 1, 13).and_hms(11, 59, 0),
+            now,
+            3
+        ));
+        assert!(!needs_attention(
+            Utc.ymd(2021, 1, 13).and_hms(11, 58, 30),
+            now,
+            3
+        ));
+        assert!(needs_attention(
+            Utc.ymd(2021, 1, 13).and_hms(11, 58, 29),
+            now,
+            3
+        ));
+        assert!(!needs_attention(
+            Utc.ymd(2021, 1, 13).and_hms(12, 0, 5),
+            now,
+            1
+        ));
+    }
+
+    #[test]
+    fn test_seconds_since() {
+        let now = Utc.ymd(2021, 1, 13).and_hms(12, 0, 0);
+
+        assert_eq!(
+            seconds_since(Utc.ymd(2021, 1, 13).and_hms(11, 58, 0), now),
+            120
+        );
+        assert_eq!(
+            seconds_since(Utc.ymd(2021, 1, 13).and_hms(12, 0, 5), now),
+            0
+        );
+    }
+}
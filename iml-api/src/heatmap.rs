@@ -0,0 +1,208 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, HEATMAP_LOCK},
+};
+use iml_manager_env::get_report_path;
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::stratagem::HeatmapEntry;
+use std::{collections::BTreeMap, io, time::Duration};
+use tokio::{fs, stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many heat map scans are kept for each filesystem.
+const SCANS_KEPT: i64 = 5;
+
+/// Periodically stores the results of finished heat map scans.
+///
+/// The `stratagem.heatmap` task appends a line per directory to a report for each batch
+/// of files it processes. Once the scan is over and every file has been processed, the lines
+/// are added up into the `fs_heatmap` table, older scans of the filesystem are dropped
+/// and the task is closed.
+pub async fn run(pg_pool: PgPool) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, HEATMAP_LOCK, || collect(&pg_pool)).await;
+
+        if let Err(e) = r {
+            tracing::error!("Error collecting heat maps: {}", e);
+        }
+    }
+}
+
+async fn collect(pool: &PgPool) -> Result<(), ImlApiError> {
+    // The task is removed from the servers once the scan has finished streaming fids to it.
+    let xs = sqlx::query!(
+        r#"
+            SELECT t.id, t.args->>'report_name' AS report_name, t.start, f.name AS fs_name
+            FROM chroma_core_task t
+            INNER JOIN chroma_core_managedfilesystem f ON f.id = t.filesystem_id
+            WHERE 'stratagem.heatmap' = ANY(t.actions)
+            AND t.state = 'removed'
+            AND t.fids_completed >= t.fids_total
+            AND NOT EXISTS (SELECT 1 FROM chroma_core_fidtaskqueue q WHERE q.task_id = t.id)
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in xs {
+        let path = x.report_name.map(|name| get_report_path().join(name));
+
+        let report = match path.as_ref() {
+            Some(path) => match fs::read_to_string(path).await {
+                Ok(x) => x,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e.into()),
+            },
+            None => String::new(),
+        };
+
+        let dirs = add_up(&report);
+
+        tracing::info!(
+            "Storing the heat of {} directories of {}",
+            dirs.len(),
+            x.fs_name
+        );
+
+        let mut transaction = pool.begin().await?;
+
+        for d in dirs {
+            sqlx::query!(
+                r#"
+                    INSERT INTO fs_heatmap (
+                        task_id, fs_name, path, depth, files, bytes, recent_files,
+                        bytes_week, bytes_month, bytes_quarter, bytes_year, bytes_older, scanned_at
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                    ON CONFLICT (task_id, path) DO NOTHING
+                "#,
+                x.id,
+                x.fs_name,
+                d.path,
+                d.depth as i32,
+                d.files as i64,
+                d.bytes as i64,
+                d.recent_files as i64,
+                d.bytes_week as i64,
+                d.bytes_month as i64,
+                d.bytes_quarter as i64,
+                d.bytes_year as i64,
+                d.bytes_older as i64,
+                x.start
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+
+        sqlx::query!(
+            r#"
+                DELETE FROM fs_heatmap
+                WHERE fs_name = $1
+                AND task_id NOT IN (
+                    SELECT task_id
+                    FROM fs_heatmap
+                    WHERE fs_name = $1
+                    GROUP BY task_id
+                    ORDER BY max(scanned_at) DESC
+                    LIMIT $2
+                )
+            "#,
+            x.fs_name,
+            SCANS_KEPT
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE chroma_core_task SET state = 'closed', finish = now() WHERE id = $1",
+            x.id
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        if let Some(path) = path {
+            if let Err(e) = fs::remove_file(&path).await {
+                tracing::debug!("Could not remove {:?}: {}", path, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds up the lines of a heat map report. Lines that can't be parsed are skipped.
+fn add_up(report: &str) -> Vec<HeatmapEntry> {
+    let mut dirs: BTreeMap<String, HeatmapEntry> = BTreeMap::new();
+
+    for l in report.lines().filter(|l| !l.trim().is_empty()) {
+        let x: HeatmapEntry = match serde_json::from_str(l) {
+            Ok(x) => x,
+            Err(e) => {
+                tracing::warn!("Could not parse heat map line {:?}: {}", l, e);
+
+                continue;
+            }
+        };
+
+        dirs.entry(x.path.clone())
+            .or_insert_with(|| HeatmapEntry {
+                path: x.path.clone(),
+                depth: x.depth,
+                ..HeatmapEntry::default()
+            })
+            .add(&x);
+    }
+
+    dirs.into_iter().map(|(_, x)| x).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_up() {
+        let report = r#"{"path":"/","depth":0,"files":2,"bytes":100,"recent_files":1,"bytes_week":60,"bytes_month":0,"bytes_quarter":0,"bytes_year":40,"bytes_older":0}
+{"path":"/proj1","depth":1,"files":1,"bytes":60,"recent_files":1,"bytes_week":60,"bytes_month":0,"bytes_quarter":0,"bytes_year":0,"bytes_older":0}
+not json
+{"path":"/","depth":0,"files":1,"bytes":5,"recent_files":0,"bytes_week":0,"bytes_month":0,"bytes_quarter":0,"bytes_year":0,"bytes_older":5}
+"#;
+
+        assert_eq!(
+            add_up(report),
+            vec![
+                HeatmapEntry {
+                    path: "/".into(),
+                    depth: 0,
+                    files: 3,
+                    bytes: 105,
+                    recent_files: 1,
+                    bytes_week: 60,
+                    bytes_year: 40,
+                    bytes_older: 5,
+                    ..HeatmapEntry::default()
+                },
+                HeatmapEntry {
+                    path: "/proj1".into(),
+                    depth: 1,
+                    files: 1,
+                    bytes: 60,
+                    recent_files: 1,
+                    bytes_week: 60,
+                    ..HeatmapEntry::default()
+                },
+            ]
+        );
+
+        assert!(add_up("").is_empty());
+    }
+}
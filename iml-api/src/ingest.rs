@@ -0,0 +1,451 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Log ingestion for nodes that do not run the agent, e.g. LNet routers.
+//!
+//! Entries are posted in batches, either as raw syslog lines (RFC 5424 or RFC 3164)
+//! or as JSON objects, and are stored in `chroma_core_logmessage` tagged with the
+//! name of their source. Log alert rules are evaluated against each batch once it is stored.
+
+use crate::error::ImlApiError;
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeZone, Utc};
+use iml_journal::{
+    get_message_class,
+    log_alert::{self, LogLine},
+};
+use iml_postgres::{sqlx, PgPool};
+use lazy_static::lazy_static;
+use regex::Regex;
+use warp::{http::StatusCode, Filter};
+
+/// The most entries accepted in one batch.
+const MAX_ENTRIES: usize = 5000;
+
+/// Column sizes of `chroma_core_logmessage`.
+const MAX_FQDN_LEN: usize = 255;
+const MAX_TAG_LEN: usize = 63;
+
+/// The severity of JSON entries that do not give one.
+const DEFAULT_SEVERITY: i16 = 6;
+
+/// The `user` facility, for JSON entries that do not give one.
+const DEFAULT_FACILITY: i16 = 1;
+
+lazy_static! {
+    static ref SOURCE: Regex = Regex::new(r"^[\w.-]{1,63}$").unwrap();
+    static ref RFC5424: Regex =
+        Regex::new(r"^<(\d{1,3})>1 (\S+) (\S+) (\S+) \S+ \S+ (?:-|(?:\[(?:[^\]\\]|\\.)*\])+) ?(.*)$")
+            .unwrap();
+    static ref RFC3164: Regex = Regex::new(
+        r"^<(\d{1,3})>([A-Z][a-z]{2} +\d{1,2} \d{2}:\d{2}:\d{2}) (\S+) ([^:\[\s]+)(?:\[\d+\])?: ?(.*)$"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Batch {
+    /// Tags every entry of the batch, e.g. `lnet-routers`
+    source: String,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Syslog(String),
+    Json(JsonEntry),
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct JsonEntry {
+    host: String,
+    #[serde(default = "Utc::now")]
+    datetime: DateTime<Utc>,
+    /// RFC 5424 severity, 0 (emergency) to 7 (debug)
+    severity: Option<i16>,
+    /// RFC 5424 facility
+    facility: Option<i16>,
+    #[serde(default)]
+    tag: String,
+    message: String,
+}
+
+#[derive(Debug, PartialEq)]
+struct Row {
+    datetime: DateTime<Utc>,
+    fqdn: String,
+    severity: i16,
+    facility: i16,
+    tag: String,
+    message: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct IngestResponse {
+    accepted: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+async fn ingest(pool: PgPool, batch: Batch) -> Result<impl warp::Reply, warp::Rejection> {
+    if !SOURCE.is_match(&batch.source) {
+        return Ok(bad_request(format!("Invalid source {}", batch.source)));
+    }
+
+    let rows = match normalize(batch.entries, Utc::now()) {
+        Ok(xs) => xs,
+        Err(e) => return Ok(bad_request(e)),
+    };
+
+    let accepted = rows.len();
+
+    if accepted > 0 {
+        insert(&pool, &batch.source, rows).await?;
+    }
+
+    tracing::debug!("Ingested {} log messages from {}", accepted, batch.source);
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&IngestResponse {
+            accepted,
+            error: None,
+        }),
+        StatusCode::OK,
+    ))
+}
+
+fn bad_request(error: String) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&IngestResponse {
+            accepted: 0,
+            error: Some(error),
+        }),
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+async fn insert(pool: &PgPool, source: &str, rows: Vec<Row>) -> Result<(), ImlApiError> {
+    let x = rows.into_iter().fold(
+        (vec![], vec![], vec![], vec![], vec![], vec![], vec![]),
+        |mut acc, x| {
+            acc.0.push(x.datetime);
+            acc.1.push(x.fqdn);
+            acc.2.push(x.severity);
+            acc.3.push(x.facility);
+            acc.6.push(get_message_class(&x.message) as i16);
+            acc.4.push(x.tag);
+            acc.5.push(x.message);
+
+            acc
+        },
+    );
+
+    sqlx::query!(
+        r#"
+            INSERT INTO chroma_core_logmessage
+            (datetime, fqdn, severity, facility, tag, message, message_class, source)
+            SELECT datetime, fqdn, severity, facility, tag, message, message_class, $8
+            FROM UNNEST($1::timestamptz[], $2::text[], $3::smallint[], $4::smallint[], $5::text[], $6::text[], $7::smallint[])
+            AS t(datetime, fqdn, severity, facility, tag, message, message_class)
+        "#,
+        &x.0,
+        &x.1,
+        &x.2,
+        &x.3,
+        &x.4,
+        &x.5,
+        &x.6,
+        source
+    )
+    .execute(pool)
+    .await?;
+
+    let lines: Vec<_> = (0..x.0.len())
+        .map(|i| LogLine {
+            fqdn: &x.1[i],
+            tag: &x.4[i],
+            severity: x.2[i],
+            message: &x.5[i],
+        })
+        .collect();
+
+    if let Err(e) = log_alert::evaluate(pool, &lines).await {
+        tracing::error!("Error evaluating log alert rules: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Turns a batch into rows. A single invalid entry rejects the whole batch,
+/// so clients can resend it after fixing it without creating duplicates.
+fn normalize(entries: Vec<Entry>, now: DateTime<Utc>) -> Result<Vec<Row>, String> {
+    if entries.len() > MAX_ENTRIES {
+        return Err(format!(
+            "A batch may have at most {} entries, got {}",
+            MAX_ENTRIES,
+            entries.len()
+        ));
+    }
+
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(idx, x)| {
+            let row = match x {
+                Entry::Syslog(x) => parse_syslog(&x, now),
+                Entry::Json(x) => from_json(x),
+            };
+
+            row.map(truncate)
+                .map_err(|e| format!("Entry {}: {}", idx, e))
+        })
+        .collect()
+}
+
+fn from_json(x: JsonEntry) -> Result<Row, String> {
+    let severity = x.severity.unwrap_or(DEFAULT_SEVERITY);
+    let facility = x.facility.unwrap_or(DEFAULT_FACILITY);
+
+    if !(0..=7).contains(&severity) {
+        return Err(format!("Invalid severity {}", severity));
+    }
+
+    if !(0..=23).contains(&facility) {
+        return Err(format!("Invalid facility {}", facility));
+    }
+
+    if x.host.is_empty() {
+        return Err("Missing host".into());
+    }
+
+    Ok(Row {
+        datetime: x.datetime,
+        fqdn: x.host,
+        severity,
+        facility,
+        tag: x.tag,
+        message: x.message,
+    })
+}
+
+/// Parses a RFC 5424 or RFC 3164 syslog line.
+///
+/// RFC 3164 timestamps have neither year nor timezone. They are taken to be UTC
+/// in the current year, or the previous one if that would put them in the future.
+fn parse_syslog(x: &str, now: DateTime<Utc>) -> Result<Row, String> {
+    let x = x.trim_end();
+
+    if let Some(c) = RFC5424.captures(x) {
+        let (severity, facility) = parse_pri(&c[1])?;
+
+        let datetime = match &c[2] {
+            "-" => now,
+            ts => DateTime::parse_from_rfc3339(ts)
+                .map_err(|e| format!("Invalid timestamp {}: {}", ts, e))?
+                .with_timezone(&Utc),
+        };
+
+        let fqdn = match &c[3] {
+            "-" => return Err("Missing hostname".into()),
+            x => x.to_string(),
+        };
+
+        let tag = match &c[4] {
+            "-" => String::new(),
+            x => x.to_string(),
+        };
+
+        return Ok(Row {
+            datetime,
+            fqdn,
+            severity,
+            facility,
+            tag,
+            message: c[5].trim_start_matches('\u{feff}').to_string(),
+        });
+    }
+
+    if let Some(c) = RFC3164.captures(x) {
+        let (severity, facility) = parse_pri(&c[1])?;
+
+        let ts = c[2].split_whitespace().collect::<Vec<_>>().join(" ");
+
+        let parse = |year: i32| {
+            NaiveDateTime::parse_from_str(&format!("{} {}", year, ts), "%Y %b %d %H:%M:%S")
+                .map(|x| Utc.from_utc_datetime(&x))
+                .map_err(|e| format!("Invalid timestamp {}: {}", ts, e))
+        };
+
+        let mut datetime = parse(now.year())?;
+
+        if datetime > now + chrono::Duration::days(1) {
+            datetime = parse(now.year() - 1)?;
+        }
+
+        return Ok(Row {
+            datetime,
+            fqdn: c[3].to_string(),
+            severity,
+            facility,
+            tag: c[4].to_string(),
+            message: c[5].to_string(),
+        });
+    }
+
+    Err("Not a RFC 5424 or RFC 3164 syslog line".into())
+}
+
+/// Splits a syslog `PRI` into severity and facility.
+fn parse_pri(x: &str) -> Result<(i16, i16), String> {
+    let pri: i16 = x.parse().map_err(|_| format!("Invalid priority {}", x))?;
+
+    if pri > 191 {
+        return Err(format!("Invalid priority {}", x));
+    }
+
+    Ok((pri % 8, pri / 8))
+}
+
+fn truncate(mut x: Row) -> Row {
+    let cut = |s: &mut String, n: usize| {
+        if let Some((idx, _)) = s.char_indices().nth(n) {
+            s.truncate(idx);
+        }
+    };
+
+    cut(&mut x.fqdn, MAX_FQDN_LEN);
+    cut(&mut x.tag, MAX_TAG_LEN);
+
+    x
+}
+
+pub(crate) fn endpoint(
+    pool: PgPool,
+    auth_filter: impl Filter<Extract = (), Error = warp::Rejection> + Clone + Send,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("ingest" / "logs")
+        .and(warp::post())
+        .and(auth_filter)
+        .and(warp::any().map(move || pool.clone()))
+        .and(warp::body::content_length_limit(16 * 1024 * 1024))
+        .and(warp::body::json())
+        .and_then(ingest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.ymd(2021, 1, 2).and_hms(3, 4, 5)
+    }
+
+    #[test]
+    fn test_parse_rfc5424() {
+        let x = parse_syslog(
+            "<11>1 2020-12-31T10:00:00.5+01:00 rtr1.local kernel - - [meta x=\"\\]\"] LNetError: 1234:0:(o2iblnd_cb.c:3373:kiblnd_check_txs_locked()) Timed out tx",
+            now(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            x,
+            Row {
+                datetime: Utc.ymd(2020, 12, 31).and_hms_milli(9, 0, 0, 500),
+                fqdn: "rtr1.local".into(),
+                severity: 3,
+                facility: 1,
+                tag: "kernel".into(),
+                message:
+                    "LNetError: 1234:0:(o2iblnd_cb.c:3373:kiblnd_check_txs_locked()) Timed out tx"
+                        .into(),
+            }
+        );
+
+        let x = parse_syslog("<14>1 - rtr1 - - - - hello", now()).unwrap();
+
+        assert_eq!(x.datetime, now());
+        assert_eq!(x.tag, "");
+        assert_eq!(x.message, "hello");
+
+        assert!(parse_syslog("<14>1 - - app - - - hello", now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_rfc3164() {
+        let x = parse_syslog(
+            "<4>Dec  3 12:00:01 rtr2 kernel[42]: Lustre: routing enabled",
+            now(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            x,
+            Row {
+                datetime: Utc.ymd(2020, 12, 3).and_hms(12, 0, 1),
+                fqdn: "rtr2".into(),
+                severity: 4,
+                facility: 0,
+                tag: "kernel".into(),
+                message: "Lustre: routing enabled".into(),
+            }
+        );
+
+        let x = parse_syslog("<30>Jan 2 01:00:00 rtr2 lnetctl: up", now()).unwrap();
+
+        assert_eq!(x.datetime, Utc.ymd(2021, 1, 2).and_hms(1, 0, 0));
+        assert_eq!((x.severity, x.facility), (6, 3));
+    }
+
+    #[test]
+    fn test_parse_pri() {
+        assert_eq!(parse_pri("0"), Ok((0, 0)));
+        assert_eq!(parse_pri("191"), Ok((7, 23)));
+        assert!(parse_pri("192").is_err());
+    }
+
+    #[test]
+    fn test_normalize() {
+        let batch: Batch = serde_json::from_str(
+            r#"{
+                "source": "lnet-routers",
+                "entries": [
+                    "<11>1 2020-12-31T10:00:00Z rtr1 kernel - - - oops",
+                    {"host": "rtr3", "message": "LustreError: down", "severity": 3}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let xs = normalize(batch.entries, now()).unwrap();
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[1].fqdn, "rtr3");
+        assert_eq!(xs[1].facility, DEFAULT_FACILITY);
+
+        let batch: Batch = serde_json::from_str(
+            r#"{"source": "x", "entries": [{"host": "rtr3", "message": "m", "severity": 9}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            normalize(batch.entries, now()),
+            Err("Entry 0: Invalid severity 9".into())
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        let x = truncate(Row {
+            datetime: now(),
+            fqdn: "h".into(),
+            severity: 6,
+            facility: 1,
+            tag: "t".repeat(100),
+            message: "m".into(),
+        });
+
+        assert_eq!(x.tag.len(), MAX_TAG_LEN);
+    }
+}
@@ -0,0 +1,367 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, INTEGRITY_LOCK},
+};
+use iml_postgres::{alert, sqlx, PgPool};
+use iml_wire_types::{
+    integrity::{IntegrityFinding, IntegrityFindingKind, IntegrityReport},
+    AlertRecordType, AlertSeverity,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+use tokio::{stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Periodically checks the targets for inconsistencies.
+///
+/// The device path each target is seen on is kept per host in `target_dev_path_baseline`.
+/// A target that shares its uuid with another one, whose device path changed while it stayed on
+/// the same host, or that is registered on a removed host has a `DuplicateTargetUuidAlert`,
+/// `TargetDevPathChangedAlert` or `TargetHostMissingAlert` raised.
+/// They are lowered once the finding is gone, a device path change once it is acknowledged.
+pub async fn run(pg_pool: PgPool) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, INTEGRITY_LOCK, || check_integrity(&pg_pool)).await;
+
+        if let Err(e) = r {
+            tracing::error!("Error checking target integrity: {}", e);
+        }
+    }
+}
+
+struct Target {
+    name: String,
+    uuid: String,
+    active_host_id: Option<i32>,
+    host_ids: Vec<i32>,
+    dev_path: Option<String>,
+}
+
+async fn get_targets(pool: &PgPool) -> Result<Vec<Target>, ImlApiError> {
+    let xs = sqlx::query_as!(
+        Target,
+        "SELECT name, uuid, active_host_id, host_ids, dev_path FROM target ORDER BY name, uuid"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(xs)
+}
+
+async fn check_integrity(pool: &PgPool) -> Result<(), ImlApiError> {
+    let targets = get_targets(pool).await?;
+
+    // Only the device path a target is active on can be told
+    let seen: Vec<_> = targets
+        .iter()
+        .filter_map(|x| Some((x, x.active_host_id?, x.dev_path.as_ref()?)))
+        .collect();
+
+    let mut transaction = pool.begin().await?;
+
+    // A device path going back to the one before the change is no change after all
+    sqlx::query!(
+        r#"
+            INSERT INTO target_dev_path_baseline (target_name, uuid, host_id, dev_path)
+            SELECT * FROM UNNEST($1::text[], $2::text[], $3::int[], $4::text[])
+            ON CONFLICT (target_name, uuid, host_id) DO UPDATE
+            SET previous_dev_path = CASE
+                    WHEN target_dev_path_baseline.dev_path = EXCLUDED.dev_path
+                        THEN target_dev_path_baseline.previous_dev_path
+                    WHEN target_dev_path_baseline.previous_dev_path = EXCLUDED.dev_path
+                        THEN NULL
+                    ELSE COALESCE(target_dev_path_baseline.previous_dev_path, target_dev_path_baseline.dev_path)
+                END,
+                changed_at = CASE
+                    WHEN target_dev_path_baseline.dev_path = EXCLUDED.dev_path
+                        THEN target_dev_path_baseline.changed_at
+                    WHEN target_dev_path_baseline.previous_dev_path = EXCLUDED.dev_path
+                        THEN NULL
+                    ELSE now()
+                END,
+                dev_path = EXCLUDED.dev_path,
+                seen_at = now()
+        "#,
+        &seen.iter().map(|(x, _, _)| x.name.clone()).collect::<Vec<_>>(),
+        &seen.iter().map(|(x, _, _)| x.uuid.clone()).collect::<Vec<_>>(),
+        &seen.iter().map(|(_, x, _)| *x).collect::<Vec<_>>(),
+        &seen.iter().map(|(_, _, x)| x.to_string()).collect::<Vec<_>>(),
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+            DELETE FROM target_dev_path_baseline b
+            WHERE NOT EXISTS (SELECT 1 FROM target t WHERE t.name = b.target_name AND t.uuid = b.uuid)
+        "#
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    let findings = findings(pool, &targets).await?;
+
+    update_alerts(pool, &findings).await
+}
+
+/// The inconsistencies in the targets as of the last check.
+pub(crate) async fn report(pool: &PgPool) -> Result<IntegrityReport, ImlApiError> {
+    let targets = get_targets(pool).await?;
+
+    let findings = findings(pool, &targets).await?;
+
+    Ok(IntegrityReport {
+        target_count: targets.len() as i32,
+        findings,
+    })
+}
+
+async fn findings(pool: &PgPool, targets: &[Target]) -> Result<Vec<IntegrityFinding>, ImlApiError> {
+    let live_host_ids: BTreeSet<i32> =
+        sqlx::query!("SELECT id FROM chroma_core_managedhost WHERE not_deleted = 't'")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|x| x.id)
+            .collect();
+
+    let mut xs: Vec<_> = duplicate_uuids(targets)
+        .into_iter()
+        .map(|(uuid, target_names)| IntegrityFinding {
+            kind: IntegrityFindingKind::DuplicateUuid,
+            message: format!("Targets {} share uuid {}", target_names.join(", "), uuid),
+            uuid,
+            target_names,
+            dev_path: None,
+            previous_dev_path: None,
+            changed_at: None,
+            missing_host_ids: vec![],
+        })
+        .collect();
+
+    let changes = sqlx::query!(
+        r#"
+            SELECT
+                b.target_name,
+                b.uuid,
+                b.dev_path,
+                b.previous_dev_path AS "previous_dev_path!",
+                b.changed_at AS "changed_at!",
+                h.fqdn AS "fqdn?"
+            FROM target_dev_path_baseline b
+            LEFT OUTER JOIN chroma_core_managedhost h ON h.id = b.host_id
+            WHERE b.previous_dev_path IS NOT NULL AND b.changed_at IS NOT NULL
+            ORDER BY b.target_name, b.uuid, b.host_id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    xs.extend(changes.into_iter().map(|x| IntegrityFinding {
+        kind: IntegrityFindingKind::DevPathChanged,
+        message: format!(
+            "Device path of {} changed from {} to {} on {}",
+            x.target_name,
+            x.previous_dev_path,
+            x.dev_path,
+            x.fqdn.as_deref().unwrap_or("a removed host")
+        ),
+        uuid: x.uuid,
+        target_names: vec![x.target_name],
+        dev_path: Some(x.dev_path),
+        previous_dev_path: Some(x.previous_dev_path),
+        changed_at: Some(x.changed_at),
+        missing_host_ids: vec![],
+    }));
+
+    xs.extend(targets.iter().filter_map(|x| {
+        let missing_host_ids = missing_hosts(x, &live_host_ids);
+
+        if missing_host_ids.is_empty() {
+            return None;
+        }
+
+        Some(IntegrityFinding {
+            kind: IntegrityFindingKind::MissingHost,
+            uuid: x.uuid.clone(),
+            target_names: vec![x.name.clone()],
+            message: format!(
+                "Target {} is registered on removed hosts {}",
+                x.name,
+                missing_host_ids
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            dev_path: None,
+            previous_dev_path: None,
+            changed_at: None,
+            missing_host_ids,
+        })
+    }));
+
+    Ok(xs)
+}
+
+fn alert_type(kind: IntegrityFindingKind) -> AlertRecordType {
+    match kind {
+        IntegrityFindingKind::DuplicateUuid => AlertRecordType::DuplicateTargetUuidAlert,
+        IntegrityFindingKind::DevPathChanged => AlertRecordType::TargetDevPathChangedAlert,
+        IntegrityFindingKind::MissingHost => AlertRecordType::TargetHostMissingAlert,
+    }
+}
+
+async fn update_alerts(pool: &PgPool, findings: &[IntegrityFinding]) -> Result<(), ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT id, content_type_id, name, uuid AS "uuid!"
+            FROM chroma_core_managedtarget
+            WHERE not_deleted = 't' AND uuid IS NOT NULL
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in xs {
+        let mut raised: Vec<(AlertRecordType, &str)> = vec![];
+
+        // A finding of a target name the managed target does not have is about another target
+        for f in findings.iter().filter(|f| {
+            f.uuid == x.uuid
+                && (f.kind == IntegrityFindingKind::DuplicateUuid
+                    || x.name.as_ref().map_or(true, |n| f.target_names.contains(n)))
+        }) {
+            let record_type = alert_type(f.kind);
+
+            if raised.iter().all(|(t, _)| *t != record_type) {
+                raised.push((record_type, &f.message));
+            }
+        }
+
+        let lowered: Vec<_> = vec![
+            AlertRecordType::DuplicateTargetUuidAlert,
+            AlertRecordType::TargetDevPathChangedAlert,
+            AlertRecordType::TargetHostMissingAlert,
+        ]
+        .into_iter()
+        .filter(|t| raised.iter().all(|(x, _)| x != t))
+        .collect();
+
+        if !lowered.is_empty() {
+            alert::lower(pool, lowered, x.id).await?;
+        }
+
+        let content_type_id = match x.content_type_id {
+            Some(x) => x,
+            None => continue,
+        };
+
+        for (record_type, message) in raised {
+            alert::raise(
+                pool,
+                record_type,
+                message.to_string(),
+                content_type_id,
+                None,
+                AlertSeverity::WARNING,
+                x.id,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The uuids shared by several targets, along with their names.
+fn duplicate_uuids(targets: &[Target]) -> Vec<(String, Vec<String>)> {
+    let mut by_uuid: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+
+    for x in targets {
+        by_uuid.entry(&x.uuid).or_default().insert(&x.name);
+    }
+
+    by_uuid
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .map(|(uuid, names)| {
+            (
+                uuid.to_string(),
+                names.into_iter().map(|x| x.to_string()).collect(),
+            )
+        })
+        .collect()
+}
+
+/// The hosts `target` is registered or active on that are not in `live_host_ids`, in id order.
+fn missing_hosts(target: &Target, live_host_ids: &BTreeSet<i32>) -> Vec<i32> {
+    target
+        .host_ids
+        .iter()
+        .copied()
+        .chain(target.active_host_id)
+        .filter(|x| !live_host_ids.contains(x))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(name: &str, uuid: &str, active_host_id: Option<i32>, host_ids: &[i32]) -> Target {
+        Target {
+            name: name.into(),
+            uuid: uuid.into(),
+            active_host_id,
+            host_ids: host_ids.to_vec(),
+            dev_path: None,
+        }
+    }
+
+    #[test]
+    fn test_duplicate_uuids() {
+        let xs = vec![
+            target("fs-OST0000", "a", None, &[]),
+            target("fs-OST0001", "b", None, &[]),
+            target("fs-OST0002", "a", None, &[]),
+            target("fs-OST0001", "c", None, &[]),
+        ];
+
+        assert_eq!(
+            duplicate_uuids(&xs),
+            vec![(
+                "a".to_string(),
+                vec!["fs-OST0000".to_string(), "fs-OST0002".to_string()]
+            )]
+        );
+        assert!(duplicate_uuids(&xs[1..2]).is_empty());
+    }
+
+    #[test]
+    fn test_missing_hosts() {
+        let live: BTreeSet<_> = vec![1, 2, 3].into_iter().collect();
+
+        assert_eq!(
+            missing_hosts(&target("fs-MDT0000", "a", Some(1), &[1, 2]), &live),
+            Vec::<i32>::new()
+        );
+        assert_eq!(
+            missing_hosts(&target("fs-MDT0000", "a", Some(5), &[4, 2, 5]), &live),
+            vec![4, 5]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Bridges the `table_invalidate` channel of Postgres to the tasks of `iml-api`.
+//!
+//! Triggers send the name of a table on the channel when a statement changes it.
+//! Tasks that would otherwise poll those tables subscribe here and check again
+//! when told, with a slow poll left only as a fallback.
+
+use crate::error::ImlApiError;
+use futures::channel::mpsc;
+use iml_postgres::{sqlx::postgres::PgListener, PgPool};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::delay_for;
+
+const CHANNEL: &str = "table_invalidate";
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+pub(crate) const DEFERRED_QUERY_TABLE: &str = "deferred_query";
+
+/// What changed, according to `table_invalidate`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Invalidation {
+    Table(String),
+    /// Notifications may have been missed, so any table may have changed
+    All,
+}
+
+impl Invalidation {
+    pub(crate) fn affects(&self, table: &str) -> bool {
+        match self {
+            Self::Table(x) => x == table,
+            Self::All => true,
+        }
+    }
+}
+
+/// The subscribers to `table_invalidate`
+#[derive(Default)]
+pub(crate) struct Invalidations {
+    subscribers: Mutex<Vec<mpsc::UnboundedSender<Invalidation>>>,
+}
+
+impl Invalidations {
+    pub(crate) fn subscribe(&self) -> mpsc::UnboundedReceiver<Invalidation> {
+        let (tx, rx) = mpsc::unbounded();
+
+        self.subscribers.lock().unwrap().push(tx);
+
+        rx
+    }
+
+    /// Sends `x` to every subscriber, dropping the ones that went away.
+    fn send(&self, x: Invalidation) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|tx| tx.unbounded_send(x.clone()).is_ok());
+    }
+}
+
+/// Listens to `table_invalidate` for as long as `iml-api` runs, reconnecting when the connection fails.
+pub(crate) async fn run(pool: PgPool, invalidations: Arc<Invalidations>) {
+    loop {
+        if let Err(e) = listen(&pool, &invalidations).await {
+            tracing::warn!("Stopped listening to {}: {}", CHANNEL, e);
+        }
+
+        invalidations.send(Invalidation::All);
+
+        delay_for(RECONNECT_DELAY).await;
+    }
+}
+
+async fn listen(pool: &PgPool, invalidations: &Invalidations) -> Result<(), ImlApiError> {
+    let mut listener = PgListener::connect_with(pool).await?;
+
+    listener.listen(CHANNEL).await?;
+
+    tracing::info!("Listening to {}", CHANNEL);
+
+    // Anything that changed before this is not going to be sent
+    invalidations.send(Invalidation::All);
+
+    loop {
+        let x = listener.recv().await?;
+
+        tracing::trace!("{} changed", x.payload());
+
+        invalidations.send(Invalidation::Table(x.payload().to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send() {
+        let xs = Invalidations::default();
+
+        let mut rx = xs.subscribe();
+        let dropped = xs.subscribe();
+
+        drop(dropped);
+
+        xs.send(Invalidation::Table(DEFERRED_QUERY_TABLE.into()));
+
+        let x = rx.try_next().unwrap().unwrap();
+
+        assert!(x.affects(DEFERRED_QUERY_TABLE));
+        assert!(!x.affects("ha_event"));
+        assert!(Invalidation::All.affects("ha_event"));
+        assert_eq!(xs.subscribers.lock().unwrap().len(), 1);
+    }
+}
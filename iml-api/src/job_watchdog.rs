@@ -0,0 +1,76 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, JOB_WATCHDOG_LOCK},
+};
+use futures::TryFutureExt;
+use iml_postgres::{sqlx, PgPool};
+use iml_rabbit::Pool;
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Periodically fails jobs that have been running longer than
+/// the timeout configured for their class in `job_timeout`.
+pub async fn run(pg_pool: PgPool, rabbit_pool: Pool) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, JOB_WATCHDOG_LOCK, || {
+            fail_overdue_jobs(&pg_pool, &rabbit_pool)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error checking for overdue jobs: {}", e);
+        }
+    }
+}
+
+async fn fail_overdue_jobs(pg_pool: &PgPool, rabbit_pool: &Pool) -> Result<(), ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT j.id, ct.model AS class_name
+            FROM chroma_core_job j
+            INNER JOIN django_content_type ct ON ct.id = j.content_type_id
+            INNER JOIN job_timeout jt ON lower(jt.class_name) = ct.model
+            WHERE j.state = 'tasked'
+            AND NOT j.timed_out
+            AND j.tasked_at < now() - jt.timeout
+        "#
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    for x in xs {
+        tracing::warn!(
+            "Job {} ({}) exceeded its timeout, failing it",
+            x.id,
+            x.class_name
+        );
+
+        // Failing through the job scheduler completes the job as errored, releases its locks,
+        // cancels any jobs waiting on it and fails its command, which raises a `CommandErroredAlert`.
+        let _: Option<()> = iml_job_scheduler_rpc::call(
+            &iml_rabbit::get_conn(rabbit_pool.clone()).await?,
+            "fail_job",
+            vec![x.id],
+            None,
+        )
+        .map_err(ImlApiError::ImlJobSchedulerRpcError)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE chroma_core_job SET timed_out = true WHERE id = $1",
+            x.id
+        )
+        .execute(pg_pool)
+        .await?;
+    }
+
+    Ok(())
+}
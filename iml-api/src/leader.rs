@@ -0,0 +1,62 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::error::ImlApiError;
+use iml_postgres::{sqlx, PgPool};
+use std::future::Future;
+
+/// Advisory lock keys for the periodic tasks `iml-api` runs, see `run_exclusive`.
+pub(crate) const JOB_WATCHDOG_LOCK: i64 = 0x696d_6c01;
+pub(crate) const CHANGE_JOURNAL_LOCK: i64 = 0x696d_6c02;
+pub(crate) const FS_CHECK_LOCK: i64 = 0x696d_6c03;
+pub(crate) const CAPACITY_FORECAST_LOCK: i64 = 0x696d_6c04;
+pub(crate) const CERTIFICATE_EXPIRY_LOCK: i64 = 0x696d_6c05;
+pub(crate) const PROJECT_USAGE_LOCK: i64 = 0x696d_6c06;
+pub(crate) const HEARTBEAT_LOCK: i64 = 0x696d_6c07;
+pub(crate) const MULTIPATH_LOCK: i64 = 0x696d_6c08;
+pub(crate) const SNAPSHOT_POLICY_LOCK: i64 = 0x696d_6c09;
+pub(crate) const TARGET_REMOUNT_LOCK: i64 = 0x696d_6c0a;
+pub(crate) const STONITH_TEST_LOCK: i64 = 0x696d_6c0b;
+pub(crate) const RECOVERY_LOCK: i64 = 0x696d_6c0c;
+pub(crate) const STANDBY_LOCK: i64 = 0x696d_6c0d;
+pub(crate) const HEATMAP_LOCK: i64 = 0x696d_6c0e;
+pub(crate) const PURGE_POLICY_LOCK: i64 = 0x696d_6c0f;
+pub(crate) const LOG_ALERT_LOCK: i64 = 0x696d_6c10;
+/// Held while the schema migrations are applied at startup.
+pub(crate) const MIGRATION_LOCK: i64 = 0x696d_6c11;
+pub(crate) const INTEGRITY_LOCK: i64 = 0x696d_6c12;
+
+/// Runs `f` while holding the Postgres advisory lock `key`.
+///
+/// When another replica already holds the lock, `f` is not run and `None` is returned.
+/// The periodic tasks wrap each of their runs in this, so when several replicas are running
+/// only one of them does a task's work at a time.
+pub(crate) async fn run_exclusive<F, Fut, T>(
+    pool: &PgPool,
+    key: i64,
+    f: F,
+) -> Result<Option<T>, ImlApiError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, ImlApiError>>,
+{
+    let mut conn = pool.acquire().await?;
+
+    let locked = sqlx::query!(r#"SELECT pg_try_advisory_lock($1) AS "locked!""#, key)
+        .fetch_one(&mut conn)
+        .await?
+        .locked;
+
+    if !locked {
+        return Ok(None);
+    }
+
+    let r = f().await;
+
+    sqlx::query!("SELECT pg_advisory_unlock($1)", key)
+        .execute(&mut conn)
+        .await?;
+
+    r.map(Some)
+}
@@ -0,0 +1,272 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! LNet ping meshes: each host of a set pings the NIDs of every other host,
+//! and how each pair went is recorded in `lnet_ping_result`.
+
+use crate::error::ImlApiError;
+use futures::future::join_all;
+use iml_action_client::Client;
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::lnet_ping::{LnetPingArgs, LnetPingMesh, LnetPingPair, LnetPingResult};
+
+/// How long meshes are kept in `lnet_ping_mesh`.
+const RETENTION_DAYS: i32 = 30;
+
+/// A host of a mesh and its NIDs
+struct MeshHost {
+    id: i32,
+    fqdn: String,
+    nids: Vec<String>,
+}
+
+/// The hosts of `host_ids` that are managed, with their NIDs.
+async fn mesh_hosts(pool: &PgPool, host_ids: &[i32]) -> Result<Vec<MeshHost>, ImlApiError> {
+    let xs = sqlx::query_as!(
+        MeshHost,
+        r#"
+            SELECT
+                h.id,
+                h.fqdn,
+                COALESCE(
+                    array_agg(n.nid ORDER BY n.nid) FILTER (WHERE n.nid IS NOT NULL),
+                    '{}'
+                ) AS "nids!"
+            FROM chroma_core_managedhost h
+            LEFT OUTER JOIN lnet l ON l.host_id = h.id
+            LEFT OUTER JOIN nid n ON n.id = ANY(l.nids)
+            WHERE h.id = ANY($1) AND h.not_deleted = 't'
+            GROUP BY h.id, h.fqdn
+            ORDER BY h.fqdn
+        "#,
+        host_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(xs)
+}
+
+/// The share of `sent` pings that went unanswered, from 0 to 100.
+fn loss_percent(sent: i32, received: i32) -> f64 {
+    if sent <= 0 {
+        return 100.;
+    }
+
+    f64::from(sent - received.min(sent)) * 100. / f64::from(sent)
+}
+
+/// Records a mesh among `host_ids` and starts it in the background.
+/// Returns the mesh before any host has pinged,
+/// or `None` when fewer than two of the hosts are managed.
+pub(crate) async fn start(
+    pool: &PgPool,
+    action_client: &Client,
+    host_ids: &[i32],
+    count: i32,
+) -> Result<Option<LnetPingMesh>, ImlApiError> {
+    sqlx::query!(
+        "DELETE FROM lnet_ping_mesh WHERE started_at < now() - make_interval(days => $1)",
+        RETENTION_DAYS
+    )
+    .execute(pool)
+    .await?;
+
+    let hosts = mesh_hosts(pool, host_ids).await?;
+
+    if hosts.len() < 2 {
+        return Ok(None);
+    }
+
+    let ids: Vec<i32> = hosts.iter().map(|x| x.id).collect();
+
+    let x = sqlx::query!(
+        r#"
+            INSERT INTO lnet_ping_mesh (host_ids, count)
+            VALUES ($1, $2)
+            RETURNING id, started_at
+        "#,
+        &ids,
+        count
+    )
+    .fetch_one(pool)
+    .await?;
+
+    tokio::spawn(run(
+        pool.clone(),
+        action_client.clone(),
+        x.id,
+        hosts,
+        count as u32,
+    ));
+
+    Ok(Some(LnetPingMesh {
+        id: x.id,
+        host_ids: ids,
+        count,
+        started_at: x.started_at,
+        finished_at: None,
+        pairs: vec![],
+    }))
+}
+
+/// Has every host of the mesh ping the others at the same time, then marks the mesh finished.
+async fn run(pool: PgPool, action_client: Client, mesh_id: i32, hosts: Vec<MeshHost>, count: u32) {
+    let xs = hosts
+        .iter()
+        .map(|source| ping_from(&pool, &action_client, mesh_id, source, &hosts, count));
+
+    for r in join_all(xs).await {
+        if let Err(e) = r {
+            tracing::error!("Error recording LNet ping mesh {}: {}", mesh_id, e);
+        }
+    }
+
+    let r = sqlx::query!(
+        "UPDATE lnet_ping_mesh SET finished_at = now() WHERE id = $1",
+        mesh_id
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = r {
+        tracing::error!("Error finishing LNet ping mesh {}: {}", mesh_id, e);
+    }
+}
+
+/// Pings the NIDs of the other `hosts` from `source` and records the results.
+/// When `source` could not ping at all, every NID is recorded as unanswered with the reason.
+async fn ping_from(
+    pool: &PgPool,
+    action_client: &Client,
+    mesh_id: i32,
+    source: &MeshHost,
+    hosts: &[MeshHost],
+    count: u32,
+) -> Result<(), ImlApiError> {
+    let targets: Vec<(i32, &str)> = hosts
+        .iter()
+        .filter(|x| x.id != source.id)
+        .flat_map(|x| x.nids.iter().map(move |nid| (x.id, nid.as_str())))
+        .collect();
+
+    let args = LnetPingArgs {
+        nids: targets.iter().map(|(_, nid)| nid.to_string()).collect(),
+        count,
+    };
+
+    let r = action_client
+        .invoke_rust_agent_expect_result(source.fqdn.clone(), "lnet_ping", args, None)
+        .await;
+
+    let r = match r {
+        Ok(Ok(x)) => serde_json::from_value::<Vec<LnetPingResult>>(x).map_err(|e| e.to_string()),
+        Ok(Err(e)) => Err(format!("{} could not ping: {}", source.fqdn, e)),
+        Err(e) => Err(format!("{} did not answer: {}", source.fqdn, e)),
+    };
+
+    let results: Vec<LnetPingResult> = match r {
+        Ok(xs) => xs,
+        Err(e) => targets
+            .iter()
+            .map(|(_, nid)| LnetPingResult {
+                nid: nid.to_string(),
+                sent: 0,
+                received: 0,
+                avg_latency_ms: None,
+                max_latency_ms: None,
+                error: Some(e.clone()),
+            })
+            .collect(),
+    };
+
+    for x in results {
+        let target_host_id = match targets.iter().find(|(_, nid)| *nid == x.nid) {
+            Some((id, _)) => *id,
+            None => continue,
+        };
+
+        sqlx::query!(
+            r#"
+                INSERT INTO lnet_ping_result
+                    (mesh_id, source_host_id, target_host_id, target_nid,
+                    sent, received, avg_latency_ms, max_latency_ms, error)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            mesh_id,
+            source.id,
+            target_host_id,
+            x.nid,
+            x.sent as i32,
+            x.received as i32,
+            x.avg_latency_ms,
+            x.max_latency_ms,
+            x.error
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// The pairs of `mesh_id` recorded so far, by source and then target.
+pub(crate) async fn mesh_pairs(
+    pool: &PgPool,
+    mesh_id: i32,
+) -> Result<Vec<LnetPingPair>, ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT
+                r.source_host_id,
+                sh.fqdn AS source_fqdn,
+                r.target_host_id,
+                th.fqdn AS target_fqdn,
+                r.target_nid,
+                r.sent,
+                r.received,
+                r.avg_latency_ms,
+                r.max_latency_ms,
+                r.error
+            FROM lnet_ping_result r
+            INNER JOIN chroma_core_managedhost sh ON sh.id = r.source_host_id
+            INNER JOIN chroma_core_managedhost th ON th.id = r.target_host_id
+            WHERE r.mesh_id = $1
+            ORDER BY sh.fqdn, th.fqdn, r.target_nid
+        "#,
+        mesh_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| LnetPingPair {
+        source_host_id: x.source_host_id,
+        source_fqdn: x.source_fqdn,
+        target_host_id: x.target_host_id,
+        target_fqdn: x.target_fqdn,
+        target_nid: x.target_nid,
+        sent: x.sent,
+        received: x.received,
+        loss_percent: loss_percent(x.sent, x.received),
+        avg_latency_ms: x.avg_latency_ms,
+        max_latency_ms: x.max_latency_ms,
+        error: x.error,
+    })
+    .collect();
+
+    Ok(xs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loss_percent() {
+        assert_eq!(loss_percent(4, 4), 0.);
+        assert_eq!(loss_percent(4, 3), 25.);
+        assert_eq!(loss_percent(4, 0), 100.);
+        assert_eq!(loss_percent(0, 0), 100.);
+    }
+}
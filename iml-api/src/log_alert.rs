@@ -0,0 +1,72 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Lowers the alerts of log alert rules that no longer match often enough.
+//!
+//! Rules are evaluated and their alerts raised by `iml_journal::log_alert` as messages are
+//! ingested. Lowering can't happen there, as a rule stops matching precisely when no
+//! matching messages come in.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, LOG_ALERT_LOCK},
+};
+use iml_postgres::{alert, sqlx, PgPool};
+use iml_wire_types::AlertRecordType;
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Periodically lowers the `LogPatternAlert` of rules that were disabled or matched fewer than
+/// `min_matches` messages within their window, and drops matches older than the window.
+pub async fn run(pg_pool: PgPool) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, LOG_ALERT_LOCK, || check_rules(&pg_pool)).await;
+
+        if let Err(e) = r {
+            tracing::error!("Error checking log alert rules: {}", e);
+        }
+    }
+}
+
+async fn check_rules(pool: &PgPool) -> Result<(), ImlApiError> {
+    sqlx::query!(
+        r#"
+            DELETE FROM log_alert_match m
+            USING chroma_core_logalertrule r
+            WHERE r.id = m.rule_id
+            AND m.matched_at <= now() - r.within
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    let xs = sqlx::query!(
+        r#"
+            SELECT r.id
+            FROM chroma_core_logalertrule r
+            WHERE EXISTS (
+                SELECT 1 FROM chroma_core_alertstate a
+                WHERE a.alert_item_id = r.id
+                AND a.record_type = 'LogPatternAlert'
+                AND a.active = 't'
+            )
+            AND (
+                NOT r.enabled
+                OR (SELECT COUNT(*) FROM log_alert_match m WHERE m.rule_id = r.id) < r.min_matches
+            )
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in xs {
+        alert::lower(pool, vec![AlertRecordType::LogPatternAlert], x.id).await?;
+    }
+
+    Ok(())
+}
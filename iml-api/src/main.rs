@@ -3,16 +3,45 @@
 // license that can be found in the LICENSE file.
 
 mod action;
+mod auth;
+mod capacity;
+mod certificate;
+mod change_journal;
+mod client_mount;
 mod command;
+mod encoding;
 mod error;
+mod fan_out;
+mod federation;
+mod fidlist;
+mod fs_check;
 mod graphql;
+mod heartbeat;
+mod heatmap;
+mod ingest;
+mod integrity;
+mod invalidate;
+mod job_watchdog;
+mod leader;
+mod lnet_ping;
+mod log_alert;
+mod migrate;
+mod multipath;
+mod project_usage;
+mod purge_policy;
+mod recovery;
+mod row_count;
+mod snapshot_policy;
+mod standby;
+mod stonith_test;
+mod target_remount;
 mod timer;
 
 use iml_manager_env::get_pool_limit;
 use iml_postgres::get_db_pool;
 use iml_rabbit::{self, create_connection_filter};
 use iml_wire_types::Conf;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use warp::Filter;
 
 // Default pool limit if not overridden by POOL_LIMIT
@@ -20,7 +49,19 @@ const DEFAULT_POOL_LIMIT: u32 = 5;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    iml_tracing::init();
+    // Flushes the spans waiting to be exported when dropped
+    let _otlp = match iml_manager_env::get_otlp_endpoint() {
+        Some(endpoint) => Some(iml_tracing::otlp::init(
+            "iml-api",
+            &endpoint,
+            iml_manager_env::get_otlp_sample_ratio(),
+        )?),
+        None => {
+            iml_tracing::init();
+
+            None
+        }
+    };
 
     let addr = iml_manager_env::get_iml_api_addr();
 
@@ -42,23 +83,121 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let pg_pool = get_db_pool(get_pool_limit().unwrap_or(DEFAULT_POOL_LIMIT)).await?;
 
+    migrate::run(&pg_pool).await?;
+
+    tokio::spawn(job_watchdog::run(pg_pool.clone(), rabbit_pool.clone()));
+    tokio::spawn(change_journal::run(pg_pool.clone()));
+    tokio::spawn(fs_check::run(
+        pg_pool.clone(),
+        iml_action_client::Client::default(),
+    ));
+    tokio::spawn(project_usage::run(pg_pool.clone()));
+    tokio::spawn(heatmap::run(pg_pool.clone()));
+    tokio::spawn(snapshot_policy::run(pg_pool.clone(), rabbit_pool.clone()));
+    tokio::spawn(purge_policy::run(pg_pool.clone(), rabbit_pool.clone()));
+    tokio::spawn(log_alert::run(pg_pool.clone()));
+    tokio::spawn(integrity::run(pg_pool.clone()));
+    tokio::spawn(multipath::run(
+        pg_pool.clone(),
+        iml_action_client::Client::default(),
+    ));
+    tokio::spawn(target_remount::run(pg_pool.clone(), rabbit_pool.clone()));
+    tokio::spawn(recovery::run(
+        pg_pool.clone(),
+        iml_action_client::Client::default(),
+    ));
+
+    if let Some(days) = iml_manager_env::get_capacity_alert_days() {
+        tokio::spawn(capacity::run(pg_pool.clone(), days));
+    }
+
+    if let Some(days) = iml_manager_env::get_certificate_alert_days() {
+        tokio::spawn(certificate::run(pg_pool.clone(), days));
+    }
+
+    if let Some(missed) = iml_manager_env::get_heartbeat_alert_missed() {
+        tokio::spawn(heartbeat::run(pg_pool.clone(), missed));
+    }
+
+    if let Some(lag_mb) = iml_manager_env::get_standby_alert_lag_mb() {
+        tokio::spawn(standby::run(pg_pool.clone(), lag_mb));
+    }
+
+    if let Some(hours) = iml_manager_env::get_stonith_test_interval_hours() {
+        tokio::spawn(stonith_test::run(
+            pg_pool.clone(),
+            iml_action_client::Client::default(),
+            hours,
+        ));
+    }
+
     let schema = Arc::new(graphql::Schema::new(
         graphql::QueryRoot,
         graphql::MutationRoot,
         juniper::EmptySubscription::new(),
     ));
-    let schema_filter = warp::any().map(move || Arc::clone(&schema));
+
+    // User facing routes accept the GUI session or an API key.
+    let user_policy: auth::Policy = Arc::new(vec![
+        Box::new(auth::SessionCookie {
+            idle_timeout: iml_manager_env::get_session_idle_timeout(),
+        }),
+        Box::new(auth::BearerToken),
+    ]);
+
+    // Agent facing routes only accept servers with a certificate from the manager CA.
+    let agent_policy: auth::Policy = Arc::new(vec![Box::new(auth::ClientCertificate)]);
+
+    let user_auth = auth::authenticate(pg_pool.clone(), user_policy);
+    let agent_auth = auth::authenticate(pg_pool.clone(), agent_policy);
+
+    let ingest_route = ingest::endpoint(pg_pool.clone(), auth::require(user_auth.clone()));
+
+    let fidlist_route = fidlist::endpoint(pg_pool.clone(), auth::require(user_auth.clone()));
+
+    let client_mount_route =
+        client_mount::endpoint(pg_pool.clone(), auth::require(user_auth.clone()));
 
     let ctx = Arc::new(graphql::Context {
+        loaders: Arc::new(graphql::Loaders::new(&pg_pool)),
         pg_pool,
         rabbit_pool,
+        action_client: iml_action_client::Client::default(),
+        user_id: None,
     });
+
+    let invalidations = Arc::new(invalidate::Invalidations::default());
+
+    tokio::spawn(invalidate::run(
+        ctx.pg_pool.clone(),
+        Arc::clone(&invalidations),
+    ));
+
+    tokio::spawn(graphql::deferred::run(
+        Arc::clone(&schema),
+        Arc::clone(&ctx),
+        invalidations,
+    ));
+
+    let schema_filter = warp::any().map(move || Arc::clone(&schema));
     let ctx_filter = warp::any().map(move || Arc::clone(&ctx));
 
-    let routes = warp::path("conf")
-        .map(move || warp::reply::json(&conf))
-        .or(action::endpoint(conn_filter.clone()))
-        .or(graphql::endpoint(schema_filter, ctx_filter));
+    let agent_conf = conf.clone();
+
+    let public_routes = warp::path("conf").map(move || warp::reply::json(&conf));
+
+    let user_routes = action::endpoint(conn_filter.clone(), auth::require(user_auth.clone()))
+        .or(ingest_route)
+        .or(fidlist_route)
+        .or(client_mount_route)
+        .or(graphql::endpoint(schema_filter, ctx_filter, user_auth));
+
+    let agent_routes = warp::path!("agent" / "conf")
+        .and(warp::get())
+        .and(auth::require(agent_auth))
+        .map(move || warp::reply::json(&agent_conf));
+
+    let routes = public_routes.or(agent_routes).or(user_routes);
 
     tracing::info!("Starting on {:?}", addr);
 
@@ -76,14 +215,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         );
     });
 
+    // Every request gets a span, continuing the trace of the caller if there is one.
+    let trace = warp::trace(|info| {
+        let span = tracing::info_span!(
+            "request",
+            otel.kind = "server",
+            http.method = %info.method(),
+            http.target = %info.path(),
+        );
+
+        let headers: HashMap<String, String> = info
+            .request_headers()
+            .iter()
+            .filter_map(|(k, v)| Some((k.as_str().to_string(), v.to_str().ok()?.to_string())))
+            .collect();
+
+        iml_tracing::otlp::set_parent(&span, &headers);
+
+        span
+    });
+
     warp::serve(
         routes
+            .recover(auth::handle_rejection)
             .or_else(|e| async {
                 tracing::error!("{:?}", e);
 
                 Err(e)
             })
-            .with(log),
+            .with(log)
+            .with(trace),
     )
     .run(addr)
     .await;
@@ -0,0 +1,173 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Applies the schema migrations embedded in `iml-api` at startup.
+//!
+//! Replicas starting together take turns on `MIGRATION_LOCK`: the first applies the pending
+//! migrations, the others wait for it and then find nothing left to do.
+//! Applied migrations are recorded by sqlx in `_sqlx_migrations`.
+
+use crate::{error::ImlApiError, leader::MIGRATION_LOCK};
+use chrono::{DateTime, Utc};
+use iml_postgres::{
+    sqlx::{self, migrate::Migrator},
+    PgPool,
+};
+use iml_wire_types::graphql::{SchemaMigration, SchemaMigrationState};
+use std::collections::BTreeMap;
+
+pub(crate) static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
+/// Applies the pending migrations, waiting for any other replica doing so first.
+pub(crate) async fn run(pool: &PgPool) -> Result<(), ImlApiError> {
+    let mut conn = pool.acquire().await?;
+
+    tracing::debug!("Waiting for the schema migration lock");
+
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK)
+        .execute(&mut conn)
+        .await?;
+
+    let r = MIGRATOR.run(&mut conn).await;
+
+    sqlx::query!("SELECT pg_advisory_unlock($1)", MIGRATION_LOCK)
+        .execute(&mut conn)
+        .await?;
+
+    r?;
+
+    tracing::info!("Database schema is up to date");
+
+    Ok(())
+}
+
+/// A row of `_sqlx_migrations`.
+struct Applied {
+    version: i64,
+    description: String,
+    installed_on: DateTime<Utc>,
+    success: bool,
+    checksum: Vec<u8>,
+    /// In nanoseconds
+    execution_time: i64,
+}
+
+/// Every embedded or applied migration, oldest first.
+pub(crate) async fn status(pool: &PgPool) -> Result<Vec<SchemaMigration>, ImlApiError> {
+    let applied = sqlx::query_as!(
+        Applied,
+        r#"
+            SELECT version, description, installed_on, success, checksum, execution_time
+            FROM _sqlx_migrations
+            ORDER BY version
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let embedded: Vec<_> = MIGRATOR
+        .iter()
+        .map(|x| (x.version, x.description.as_ref(), x.checksum.as_ref()))
+        .collect();
+
+    Ok(states(&embedded, applied))
+}
+
+fn states(embedded: &[(i64, &str, &[u8])], applied: Vec<Applied>) -> Vec<SchemaMigration> {
+    let mut xs: BTreeMap<i64, SchemaMigration> = embedded
+        .iter()
+        .map(|(version, description, _)| {
+            (
+                *version,
+                SchemaMigration {
+                    version: version.to_string(),
+                    description: description.to_string(),
+                    state: SchemaMigrationState::Pending,
+                    installed_on: None,
+                    execution_ms: None,
+                },
+            )
+        })
+        .collect();
+
+    for x in applied {
+        let checksum = embedded
+            .iter()
+            .find(|(version, _, _)| *version == x.version)
+            .map(|(_, _, checksum)| *checksum);
+
+        let state = match checksum {
+            _ if !x.success => SchemaMigrationState::Failed,
+            None => SchemaMigrationState::Unknown,
+            Some(c) if c != x.checksum.as_slice() => SchemaMigrationState::Modified,
+            Some(_) => SchemaMigrationState::Applied,
+        };
+
+        xs.insert(
+            x.version,
+            SchemaMigration {
+                version: x.version.to_string(),
+                description: x.description,
+                state,
+                installed_on: Some(x.installed_on),
+                execution_ms: Some(x.execution_time as f64 / 1_000_000.0),
+            },
+        );
+    }
+
+    xs.into_iter().map(|(_, x)| x).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(version: i64, success: bool, checksum: &[u8]) -> Applied {
+        Applied {
+            version,
+            description: format!("migration {}", version),
+            installed_on: Utc::now(),
+            success,
+            checksum: checksum.to_vec(),
+            execution_time: 2_500_000,
+        }
+    }
+
+    #[test]
+    fn test_states() {
+        let embedded: Vec<(i64, &str, &[u8])> = vec![
+            (1, "one", b"a"),
+            (2, "two", b"b"),
+            (3, "three", b"c"),
+            (4, "four", b"d"),
+        ];
+
+        let xs = states(
+            &embedded,
+            vec![
+                applied(1, true, b"a"),
+                applied(2, true, b"x"),
+                applied(3, false, b"c"),
+                applied(5, true, b"e"),
+            ],
+        );
+
+        let states: Vec<_> = xs.iter().map(|x| (x.version.as_str(), x.state)).collect();
+
+        assert_eq!(
+            states,
+            vec![
+                ("1", SchemaMigrationState::Applied),
+                ("2", SchemaMigrationState::Modified),
+                ("3", SchemaMigrationState::Failed),
+                ("4", SchemaMigrationState::Pending),
+                ("5", SchemaMigrationState::Unknown),
+            ]
+        );
+
+        assert_eq!(xs[0].execution_ms, Some(2.5));
+        assert_eq!(xs[3].installed_on, None);
+    }
+}
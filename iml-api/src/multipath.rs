@@ -0,0 +1,284 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, MULTIPATH_LOCK},
+};
+use chrono::Utc;
+use iml_action_client::Client;
+use iml_postgres::{alert, sqlx, PgPool};
+use iml_wire_types::{multipath::MultipathPath, AlertRecordType, AlertSeverity};
+use std::{collections::BTreeMap, time::Duration};
+use tokio::{stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Periodically checks the paths to every target created on a multipath device.
+///
+/// Each host that can mount such a target is asked for its multipath paths. The number of paths
+/// and the failed ones are stored in `target_multipath`, and every path that fails or comes back
+/// is recorded in `target_multipath_event`.
+///
+/// A target with a failed path on any host has a `MultipathDegradedAlert` raised,
+/// which is lowered again once all of its paths are back.
+pub async fn run(pg_pool: PgPool, action_client: Client) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, MULTIPATH_LOCK, || {
+            check_multipath(&pg_pool, &action_client)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error checking target multipath health: {}", e);
+        }
+    }
+}
+
+async fn check_multipath(pool: &PgPool, action_client: &Client) -> Result<(), ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT t.uuid, t.dev_path AS "dev_path!", h.id AS host_id, h.fqdn
+            FROM target t
+            INNER JOIN chroma_core_managedhost h ON h.id = ANY(t.host_ids) AND h.not_deleted = 't'
+            WHERE t.dev_path LIKE '/dev/mapper/%'
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if xs.is_empty() {
+        return Ok(());
+    }
+
+    let mut by_host: BTreeMap<(i32, String), Vec<(String, String)>> = BTreeMap::new();
+
+    for x in xs {
+        by_host
+            .entry((x.host_id, x.fqdn))
+            .or_default()
+            .push((x.uuid, x.dev_path));
+    }
+
+    let now = Utc::now();
+
+    for ((host_id, fqdn), targets) in by_host {
+        let r = action_client
+            .invoke_rust_agent_expect_result(fqdn.clone(), "multipath_paths", (), None)
+            .await;
+
+        let paths: Vec<MultipathPath> = match r
+            .map_err(|e| e.to_string())
+            .and_then(|x| x.map_err(|e| e.to_string()))
+            .and_then(|x| serde_json::from_value(x).map_err(|e| e.to_string()))
+        {
+            Ok(x) => x,
+            Err(e) => {
+                tracing::debug!("Could not read the multipath paths of {}: {}", fqdn, e);
+
+                continue;
+            }
+        };
+
+        for (uuid, dev_path) in targets {
+            let map = match map_name(&dev_path) {
+                Some(x) => x,
+                None => continue,
+            };
+
+            // Device-mapper devices that are not multipath maps, such as LVs, have no paths
+            let (path_count, failed) = match summarize(&paths, map) {
+                Some(x) => x,
+                None => continue,
+            };
+
+            let prev = sqlx::query!(
+                "SELECT failed_paths FROM target_multipath WHERE target_uuid = $1 AND host_id = $2",
+                uuid,
+                host_id
+            )
+            .fetch_optional(pool)
+            .await?
+            .map(|x| x.failed_paths)
+            .unwrap_or_default();
+
+            let mut transaction = pool.begin().await?;
+
+            for (path, failed) in transitions(&prev, &failed) {
+                if failed {
+                    tracing::warn!("Path {} of {} on {} failed", path, map, fqdn);
+                } else {
+                    tracing::info!("Path {} of {} on {} is back", path, map, fqdn);
+                }
+
+                sqlx::query!(
+                    r#"
+                        INSERT INTO target_multipath_event (target_uuid, host_id, path, failed, at)
+                        VALUES ($1, $2, $3, $4, $5)
+                    "#,
+                    uuid,
+                    host_id,
+                    path,
+                    failed,
+                    now
+                )
+                .execute(&mut transaction)
+                .await?;
+            }
+
+            sqlx::query!(
+                r#"
+                    INSERT INTO target_multipath (target_uuid, host_id, map_name, path_count, failed_paths, checked_at)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    ON CONFLICT (target_uuid, host_id) DO UPDATE
+                    SET map_name = EXCLUDED.map_name,
+                        path_count = EXCLUDED.path_count,
+                        failed_paths = EXCLUDED.failed_paths,
+                        checked_at = EXCLUDED.checked_at
+                "#,
+                uuid,
+                host_id,
+                map,
+                path_count,
+                &failed,
+                now
+            )
+            .execute(&mut transaction)
+            .await?;
+
+            transaction.commit().await?;
+        }
+    }
+
+    update_alerts(pool).await
+}
+
+async fn update_alerts(pool: &PgPool) -> Result<(), ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT
+                mt.id,
+                mt.content_type_id,
+                t.name,
+                bool_or(cardinality(m.failed_paths) > 0) AS "degraded!"
+            FROM target_multipath m
+            INNER JOIN target t ON t.uuid = m.target_uuid
+            INNER JOIN chroma_core_managedtarget mt ON mt.uuid = t.uuid AND mt.not_deleted = 't'
+            GROUP BY mt.id, mt.content_type_id, t.name
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in xs {
+        match (x.degraded, x.content_type_id) {
+            (true, Some(content_type_id)) => {
+                alert::raise(
+                    pool,
+                    AlertRecordType::MultipathDegradedAlert,
+                    format!("Multipath device of {} has failed paths", x.name),
+                    content_type_id,
+                    None,
+                    AlertSeverity::WARNING,
+                    x.id,
+                )
+                .await?;
+            }
+            _ => {
+                alert::lower(pool, vec![AlertRecordType::MultipathDegradedAlert], x.id).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The name of the multipath map behind `dev_path`, e.g. `mpatha` for `/dev/mapper/mpatha`.
+pub(crate) fn map_name(dev_path: &str) -> Option<&str> {
+    dev_path
+        .strip_prefix("/dev/mapper/")
+        .filter(|x| !x.is_empty() && !x.contains('/'))
+}
+
+/// The number of paths of `map` along with the failed ones, `None` when `map` has no paths.
+fn summarize(paths: &[MultipathPath], map: &str) -> Option<(i32, Vec<String>)> {
+    let xs: Vec<_> = paths.iter().filter(|x| x.map == map).collect();
+
+    if xs.is_empty() {
+        return None;
+    }
+
+    let failed = xs
+        .iter()
+        .filter(|x| x.is_failed())
+        .map(|x| x.dev.clone())
+        .collect();
+
+    Some((xs.len() as i32, failed))
+}
+
+/// The paths that changed state between `prev` and `failed`.
+/// `true` for paths that failed, `false` for paths that came back.
+fn transitions<'a>(prev: &'a [String], failed: &'a [String]) -> Vec<(&'a str, bool)> {
+    let newly_failed = failed
+        .iter()
+        .filter(|x| !prev.contains(x))
+        .map(|x| (x.as_str(), true));
+
+    let restored = prev
+        .iter()
+        .filter(|x| !failed.contains(x))
+        .map(|x| (x.as_str(), false));
+
+    newly_failed.chain(restored).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(map: &str, dev: &str, dm_state: &str, checker_state: &str) -> MultipathPath {
+        MultipathPath {
+            map: map.into(),
+            dev: dev.into(),
+            dm_state: dm_state.into(),
+            checker_state: checker_state.into(),
+        }
+    }
+
+    #[test]
+    fn test_map_name() {
+        assert_eq!(map_name("/dev/mapper/mpatha"), Some("mpatha"));
+        assert_eq!(map_name("/dev/mapper/"), None);
+        assert_eq!(map_name("/dev/sda"), None);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let xs = vec![
+            path("mpatha", "sdb", "active", "ready"),
+            path("mpatha", "sdc", "failed", "faulty"),
+            path("mpathb", "sdd", "active", "ready"),
+            path("mpathb", "sde", "active", "faulty"),
+        ];
+
+        assert_eq!(summarize(&xs, "mpatha"), Some((2, vec!["sdc".into()])));
+        assert_eq!(summarize(&xs, "mpathb"), Some((2, vec!["sde".into()])));
+        assert_eq!(summarize(&xs, "mpathc"), None);
+    }
+
+    #[test]
+    fn test_transitions() {
+        let prev = vec!["sdb".to_string(), "sdc".to_string()];
+        let failed = vec!["sdc".to_string(), "sdd".to_string()];
+
+        assert_eq!(
+            transitions(&prev, &failed),
+            vec![("sdd", true), ("sdb", false)]
+        );
+        assert!(transitions(&failed, &failed).is_empty());
+    }
+}
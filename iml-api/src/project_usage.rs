@@ -0,0 +1,167 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, PROJECT_USAGE_LOCK},
+};
+use iml_manager_env::get_report_path;
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::stratagem::ProjectUsageEntry;
+use std::{collections::BTreeMap, io, time::Duration};
+use tokio::{fs, stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Periodically stores the results of finished project usage scans.
+///
+/// The `stratagem.project_usage` task appends a line per top-level directory to a report
+/// for each batch of files it processes. Once the scan is over and every file has been
+/// processed, the lines are added up into the `project_usage` table and the task is closed.
+pub async fn run(pg_pool: PgPool) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, PROJECT_USAGE_LOCK, || collect(&pg_pool)).await;
+
+        if let Err(e) = r {
+            tracing::error!("Error collecting project usage: {}", e);
+        }
+    }
+}
+
+async fn collect(pool: &PgPool) -> Result<(), ImlApiError> {
+    // The task is removed from the servers once the scan has finished streaming fids to it.
+    let xs = sqlx::query!(
+        r#"
+            SELECT t.id, t.args->>'report_name' AS report_name, t.start, f.name AS fs_name
+            FROM chroma_core_task t
+            INNER JOIN chroma_core_managedfilesystem f ON f.id = t.filesystem_id
+            WHERE 'stratagem.project_usage' = ANY(t.actions)
+            AND t.state = 'removed'
+            AND t.fids_completed >= t.fids_total
+            AND NOT EXISTS (SELECT 1 FROM chroma_core_fidtaskqueue q WHERE q.task_id = t.id)
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in xs {
+        let path = x.report_name.map(|name| get_report_path().join(name));
+
+        let report = match path.as_ref() {
+            Some(path) => match fs::read_to_string(path).await {
+                Ok(x) => x,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e.into()),
+            },
+            None => String::new(),
+        };
+
+        let projects = add_up(&report);
+
+        tracing::info!(
+            "Storing the usage of {} projects of {}",
+            projects.len(),
+            x.fs_name
+        );
+
+        let mut transaction = pool.begin().await?;
+
+        for p in projects {
+            sqlx::query!(
+                r#"
+                    INSERT INTO project_usage (task_id, fs_name, project, files, bytes, scanned_at)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    ON CONFLICT (task_id, project) DO NOTHING
+                "#,
+                x.id,
+                x.fs_name,
+                p.project,
+                p.files as i64,
+                p.bytes as i64,
+                x.start
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+
+        sqlx::query!(
+            "UPDATE chroma_core_task SET state = 'closed', finish = now() WHERE id = $1",
+            x.id
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        if let Some(path) = path {
+            if let Err(e) = fs::remove_file(&path).await {
+                tracing::debug!("Could not remove {:?}: {}", path, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds up the lines of a project usage report. Lines that can't be parsed are skipped.
+fn add_up(report: &str) -> Vec<ProjectUsageEntry> {
+    let mut projects: BTreeMap<String, ProjectUsageEntry> = BTreeMap::new();
+
+    for l in report.lines().filter(|l| !l.trim().is_empty()) {
+        let x: ProjectUsageEntry = match serde_json::from_str(l) {
+            Ok(x) => x,
+            Err(e) => {
+                tracing::warn!("Could not parse project usage line {:?}: {}", l, e);
+
+                continue;
+            }
+        };
+
+        let p = projects
+            .entry(x.project.clone())
+            .or_insert_with(|| ProjectUsageEntry {
+                project: x.project,
+                ..ProjectUsageEntry::default()
+            });
+
+        p.files += x.files;
+        p.bytes += x.bytes;
+    }
+
+    projects.into_iter().map(|(_, x)| x).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_up() {
+        let report = r#"{"project":"proj1","files":2,"bytes":100}
+{"project":"proj2","files":1,"bytes":5}
+not json
+{"project":"proj1","files":3,"bytes":50}
+"#;
+
+        assert_eq!(
+            add_up(report),
+            vec![
+                ProjectUsageEntry {
+                    project: "proj1".into(),
+                    files: 5,
+                    bytes: 150,
+                },
+                ProjectUsageEntry {
+                    project: "proj2".into(),
+                    files: 1,
+                    bytes: 5,
+                },
+            ]
+        );
+
+        assert!(add_up("").is_empty());
+    }
+}
@@ -0,0 +1,619 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Purge policies: a scheduled scan of a filesystem for files not accessed for `purge_after`,
+//! and the purge of those files, within caps on how many files and bytes one run removes.
+//!
+//! When a policy is due, a `stratagem.purge_candidates` task is created and the MDTs are
+//! scanned. Clients running the task report the path and size of each candidate without
+//! touching it. Once every candidate has been reported, those under a protected path are left
+//! out, and the rest are selected until `max_files` or `max_bytes` would be exceeded. The
+//! selected FIDs are handed to a `stratagem.purge` task right away, or once an operator
+//! approves the run when the policy requires it. A policy is not scanned again while its
+//! last run is waiting for approval or purging.
+
+use crate::{
+    error::ImlApiError,
+    graphql::{approval, insert_fidlist, insert_task, run_jobs_kwargs},
+    leader::{run_exclusive, PURGE_POLICY_LOCK},
+};
+use futures::TryFutureExt;
+use iml_manager_env::get_report_path;
+use iml_postgres::{sqlx, PgPool};
+use iml_rabbit::{ImlRabbitError, Pool};
+use iml_wire_types::{
+    graphql::JobPriority,
+    graphql_duration::GraphQLDuration,
+    stratagem::{self, PurgeCandidate, PurgePolicy, PurgeRun},
+};
+use std::{collections::HashSet, io, time::Duration};
+use tokio::{fs, stream::StreamExt, time::interval};
+use uuid::Uuid;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The candidates of a run that are purged
+#[derive(Debug, Default, PartialEq)]
+struct Selection {
+    candidate_files: i64,
+    candidate_bytes: i64,
+    protected_files: i64,
+    files: i64,
+    bytes: i64,
+    capped: bool,
+    fids: Vec<String>,
+}
+
+/// Whether `path` is one of the `protected` directories or under one of them.
+/// Both are relative to the root of the filesystem.
+fn is_protected(path: &str, protected: &[String]) -> bool {
+    let path = path.trim_matches('/');
+
+    protected.iter().map(|x| x.trim_matches('/')).any(|x| {
+        x.is_empty() || path == x || (path.starts_with(x) && path[x.len()..].starts_with('/'))
+    })
+}
+
+/// Picks the candidates that are not protected, in the order they were reported,
+/// skipping those that would take the run past `max_files` or `max_bytes`.
+fn select(
+    xs: impl IntoIterator<Item = PurgeCandidate>,
+    protected: &[String],
+    max_files: i64,
+    max_bytes: i64,
+) -> Selection {
+    let mut seen = HashSet::new();
+    let mut s = Selection::default();
+
+    for x in xs {
+        if !seen.insert(x.fid.clone()) {
+            continue;
+        }
+
+        let bytes = x.bytes as i64;
+
+        s.candidate_files += 1;
+        s.candidate_bytes += bytes;
+
+        if is_protected(&x.path, protected) {
+            s.protected_files += 1;
+        } else if s.files >= max_files || s.bytes + bytes > max_bytes {
+            s.capped = true;
+        } else {
+            s.files += 1;
+            s.bytes += bytes;
+            s.fids.push(x.fid);
+        }
+    }
+
+    s
+}
+
+pub(crate) async fn get_policy(
+    pool: &PgPool,
+    fs_name: &str,
+) -> Result<Option<PurgePolicy>, ImlApiError> {
+    let x = sqlx::query!(
+        r#"
+            SELECT filesystem_name, enabled, interval, purge_after, max_files, max_bytes,
+                protected_paths, require_approval, last_run
+            FROM stratagem_purge_policy
+            WHERE filesystem_name = $1
+        "#,
+        fs_name
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|x| PurgePolicy {
+        filesystem_name: x.filesystem_name,
+        enabled: x.enabled,
+        interval: x.interval.into(),
+        purge_after: x.purge_after.into(),
+        max_files: x.max_files as f64,
+        max_bytes: x.max_bytes as f64,
+        protected_paths: x.protected_paths,
+        require_approval: x.require_approval,
+        last_run: x.last_run,
+    });
+
+    Ok(x)
+}
+
+/// The runs of `fs_name`, newest first, or the run `id`.
+pub(crate) async fn get_runs(
+    pool: &PgPool,
+    fs_name: Option<&str>,
+    id: Option<i32>,
+    limit: i64,
+) -> Result<Vec<PurgeRun>, ImlApiError> {
+    let xs = sqlx::query_as!(
+        PurgeRun,
+        r#"
+            SELECT id, filesystem_name, state, scan_command_id, purge_task_id,
+                candidate_files::float8 AS "candidate_files!",
+                candidate_bytes::float8 AS "candidate_bytes!",
+                protected_files::float8 AS "protected_files!",
+                files::float8 AS "files!",
+                bytes::float8 AS "bytes!",
+                capped, started_at, scanned_at, decided_by, decided_at, error
+            FROM stratagem_purge_run
+            WHERE ($1::TEXT IS NULL OR filesystem_name = $1)
+            AND ($2::INT IS NULL OR id = $2)
+            ORDER BY id DESC
+            LIMIT $3
+        "#,
+        fs_name,
+        id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(xs)
+}
+
+/// Hands the FIDs selected by run `id` to a new `stratagem.purge` task, which clients pick up.
+pub(crate) async fn start_purge(pool: &PgPool, id: i32) -> Result<i32, ImlApiError> {
+    let x = sqlx::query!(
+        r#"
+            SELECT r.fids, f.id AS fs_id
+            FROM stratagem_purge_run r
+            INNER JOIN chroma_core_managedfilesystem f ON f.name = r.filesystem_name
+            WHERE r.id = $1 AND f.not_deleted = 't'
+        "#,
+        id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let uuid = Uuid::new_v4().to_hyphenated().to_string();
+
+    let task = insert_task(
+        &format!("{}-purge_policy-{}", uuid, id),
+        "created",
+        false,
+        false,
+        &["stratagem.purge".into()],
+        serde_json::json!({}),
+        x.fs_id,
+        pool,
+    )
+    .await?;
+
+    insert_fidlist(x.fids, task.id, pool).await?;
+
+    sqlx::query!(
+        r#"
+            UPDATE stratagem_purge_run
+            SET state = 'purging', purge_task_id = $2, fids = '{}'
+            WHERE id = $1
+        "#,
+        id,
+        task.id
+    )
+    .execute(pool)
+    .await?;
+
+    tracing::info!("Purge run {} started task {}", id, task.id);
+
+    Ok(task.id)
+}
+
+/// Periodically starts the scans of due policies, and moves their runs along.
+pub async fn run(pg_pool: PgPool, rabbit_pool: Pool) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, PURGE_POLICY_LOCK, || {
+            check(&pg_pool, &rabbit_pool)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error running purge policies: {}", e);
+        }
+    }
+}
+
+async fn check(pool: &PgPool, rabbit_pool: &Pool) -> Result<(), ImlApiError> {
+    finish_runs(pool).await?;
+    collect(pool).await?;
+    start_due(pool, rabbit_pool).await?;
+
+    Ok(())
+}
+
+/// Marks runs whose scan command failed, and runs whose purge task has processed every FID.
+async fn finish_runs(pool: &PgPool) -> Result<(), ImlApiError> {
+    sqlx::query!(
+        r#"
+            UPDATE stratagem_purge_run r
+            SET state = 'failed', error = 'The scan did not complete'
+            FROM chroma_core_command c
+            WHERE r.state = 'scanning'
+            AND c.id = r.scan_command_id
+            AND (c.errored = 't' OR c.cancelled = 't')
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    let task_ids: Vec<i32> = sqlx::query!(
+        r#"
+            UPDATE stratagem_purge_run r
+            SET state = 'purged'
+            FROM chroma_core_task t
+            WHERE r.state = 'purging'
+            AND t.id = r.purge_task_id
+            AND NOT EXISTS (SELECT 1 FROM chroma_core_fidtaskqueue q WHERE q.task_id = t.id)
+            RETURNING t.id
+        "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| x.id)
+    .collect();
+
+    if !task_ids.is_empty() {
+        sqlx::query!(
+            "UPDATE chroma_core_task SET state = 'closed', finish = now() WHERE id = ANY($1)",
+            &task_ids
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Selects the candidates of runs whose scan is over and whose candidates have all been reported.
+async fn collect(pool: &PgPool) -> Result<(), ImlApiError> {
+    // The task is removed from the servers once the scan has finished streaming fids to it.
+    let xs = sqlx::query!(
+        r#"
+            SELECT r.id, r.filesystem_name, t.id AS task_id, t.args->>'report_name' AS report_name,
+                p.max_files, p.max_bytes, p.protected_paths, p.require_approval
+            FROM stratagem_purge_run r
+            INNER JOIN stratagem_purge_policy p ON p.id = r.policy_id
+            INNER JOIN chroma_core_task t ON t.id = r.scan_task_id
+            WHERE r.state = 'scanning'
+            AND t.state = 'removed'
+            AND t.fids_completed >= t.fids_total
+            AND NOT EXISTS (SELECT 1 FROM chroma_core_fidtaskqueue q WHERE q.task_id = t.id)
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in xs {
+        let path = x.report_name.map(|name| get_report_path().join(name));
+
+        let report = match path.as_ref() {
+            Some(path) => match fs::read_to_string(path).await {
+                Ok(x) => x,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e.into()),
+            },
+            None => String::new(),
+        };
+
+        let candidates = report
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|l| match serde_json::from_str(l) {
+                Ok(x) => Some(x),
+                Err(e) => {
+                    tracing::warn!("Could not parse purge candidate {:?}: {}", l, e);
+
+                    None
+                }
+            });
+
+        let s = select(candidates, &x.protected_paths, x.max_files, x.max_bytes);
+
+        tracing::info!(
+            "Purge run {} of {} found {} candidates, {} selected",
+            x.id,
+            x.filesystem_name,
+            s.candidate_files,
+            s.files
+        );
+
+        let state = if s.files == 0 {
+            "nothing_to_purge"
+        } else if x.require_approval {
+            "awaiting_approval"
+        } else {
+            "approved"
+        };
+
+        let mut transaction = pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+                UPDATE stratagem_purge_run
+                SET state = $2, scanned_at = now(), candidate_files = $3, candidate_bytes = $4,
+                    protected_files = $5, files = $6, bytes = $7, capped = $8, fids = $9
+                WHERE id = $1
+            "#,
+            x.id,
+            state,
+            s.candidate_files,
+            s.candidate_bytes,
+            s.protected_files,
+            s.files,
+            s.bytes,
+            s.capped,
+            &s.fids
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE chroma_core_task SET state = 'closed', finish = now() WHERE id = $1",
+            x.task_id
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        if let Some(path) = path {
+            if let Err(e) = fs::remove_file(&path).await {
+                tracing::debug!("Could not remove {:?}: {}", path, e);
+            }
+        }
+
+        if state == "approved" {
+            start_purge(pool, x.id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Starts the scan of each enabled policy whose interval has passed since its last scan.
+async fn start_due(pool: &PgPool, rabbit_pool: &Pool) -> Result<(), ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT p.id, p.filesystem_name, p.purge_after
+            FROM stratagem_purge_policy p
+            WHERE p.enabled = 't'
+            AND (p.last_run IS NULL OR p.last_run + p.interval <= now())
+            AND NOT EXISTS (
+                SELECT 1 FROM stratagem_purge_run r
+                WHERE r.policy_id = p.id
+                AND r.state IN ('scanning', 'awaiting_approval', 'approved', 'purging')
+            )
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in xs {
+        let purge_after = GraphQLDuration::from(x.purge_after).0;
+
+        let r = start_scan(pool, rabbit_pool, &x.filesystem_name, purge_after).await;
+
+        match r {
+            Ok((task_id, command_id)) => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO stratagem_purge_run
+                            (policy_id, filesystem_name, scan_task_id, scan_command_id)
+                        VALUES ($1, $2, $3, $4)
+                    "#,
+                    x.id,
+                    x.filesystem_name,
+                    task_id,
+                    command_id
+                )
+                .execute(pool)
+                .await?;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Could not start the purge scan of {}: {}",
+                    x.filesystem_name,
+                    e
+                );
+
+                sqlx::query!(
+                    r#"
+                        INSERT INTO stratagem_purge_run (policy_id, filesystem_name, state, error)
+                        VALUES ($1, $2, 'failed', $3)
+                    "#,
+                    x.id,
+                    x.filesystem_name,
+                    e.to_string()
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+
+        sqlx::query!(
+            "UPDATE stratagem_purge_policy SET last_run = now() WHERE id = $1",
+            x.id
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Scans the MDTs of `fs_name` for files not accessed for `purge_after`, and hands each one
+/// to a new `stratagem.purge_candidates` task. Returns the ids of the task and the command.
+async fn start_scan(
+    pool: &PgPool,
+    rabbit_pool: &Pool,
+    fs_name: &str,
+    purge_after: Duration,
+) -> Result<(i32, i32), ImlApiError> {
+    let fs_id = sqlx::query!(
+        "SELECT id FROM chroma_core_managedfilesystem WHERE name = $1 AND not_deleted = 't'",
+        fs_name
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|x| x.id)
+    .ok_or(ImlApiError::FilesystemNotFound)?;
+
+    let hosts = sqlx::query!(
+        r#"
+            SELECT t.dev_path AS "dev_path!", h.fqdn
+            FROM target t
+            INNER JOIN chroma_core_managedhost h ON t.active_host_id = h.id
+            WHERE $1 = ANY(t.filesystems)
+            AND t.name LIKE '%-MDT%'
+            AND t.dev_path IS NOT NULL
+            AND h.not_deleted = 't'
+        "#,
+        fs_name
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let uuid = Uuid::new_v4().to_hyphenated().to_string();
+
+    let task = insert_task(
+        &format!("{}-purge_candidates-purge_candidates", uuid),
+        "created",
+        false,
+        false,
+        &["stratagem.purge_candidates".into()],
+        serde_json::json!({
+            "report_name": format!("purge_candidates-{}-{}.jsonl", fs_name, uuid)
+        }),
+        fs_id,
+        pool,
+    )
+    .await?;
+
+    let mut jobs = vec![serde_json::json!({
+        "class_name": "CreateTaskJob",
+        "args": { "task_id": task.id }
+    })];
+
+    for x in hosts {
+        let cfg = stratagem::StratagemConfig {
+            flist_type: "none".into(),
+            summarize_size: true,
+            device: stratagem::StratagemDevice {
+                path: x.dev_path,
+                groups: vec!["purge_candidates".into()],
+            },
+            groups: vec![stratagem::StratagemGroup {
+                name: "purge_candidates".into(),
+                rules: vec![stratagem::StratagemRule {
+                    action: "LAT_SHELL_CMD_FID".into(),
+                    expression: format!(
+                        "&& != type S_IFDIR < atime - sys_time {}",
+                        purge_after.as_millis()
+                    ),
+                    argument: "purge_candidates".into(),
+                    counter_name: Some("purge_candidates".into()),
+                }],
+            }],
+        };
+
+        jobs.push(serde_json::json!({
+            "class_name": "ScanMdtJob",
+            "args": {
+                "fqdn": x.fqdn,
+                "uuid": uuid,
+                "fsname": fs_name,
+                "config": cfg,
+                "depends_on_job_range": [0],
+            }
+        }));
+    }
+
+    // Removing the task marks the end of the scan, once every MDT has been scanned.
+    let job_range: Vec<_> = (0..jobs.len()).collect();
+
+    jobs.push(serde_json::json!({
+        "class_name": "RemoveTaskJob",
+        "args": { "task_id": task.id, "depends_on_job_range": job_range }
+    }));
+
+    let kwargs = approval::internal(run_jobs_kwargs(
+        "Stratagem: Purge Policy Scan",
+        JobPriority::Low,
+    ));
+
+    let command_id: i32 = iml_job_scheduler_rpc::call(
+        &rabbit_pool.get().await.map_err(ImlRabbitError::PoolError)?,
+        "run_jobs",
+        vec![serde_json::Value::Array(jobs)],
+        Some(kwargs),
+    )
+    .map_err(ImlApiError::ImlJobSchedulerRpcError)
+    .await?;
+
+    Ok((task.id, command_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(fid: &str, path: &str, bytes: u64) -> PurgeCandidate {
+        PurgeCandidate {
+            fid: fid.into(),
+            path: path.into(),
+            bytes,
+        }
+    }
+
+    #[test]
+    fn test_is_protected() {
+        let protected = vec!["/home".to_string(), "proj1/keep/".to_string()];
+
+        assert!(is_protected("home/a/b.dat", &protected));
+        assert!(is_protected("/home", &protected));
+        assert!(is_protected("proj1/keep/a.dat", &protected));
+        assert!(!is_protected("homework/a.dat", &protected));
+        assert!(!is_protected("proj1/a.dat", &protected));
+        assert!(is_protected("anything", &["/".to_string()]));
+        assert!(!is_protected("anything", &[]));
+    }
+
+    #[test]
+    fn test_select() {
+        let xs = vec![
+            candidate("[0x1:0x1:0x0]", "scratch/a", 40),
+            candidate("[0x1:0x2:0x0]", "home/b", 10),
+            candidate("[0x1:0x3:0x0]", "scratch/c", 70),
+            candidate("[0x1:0x1:0x0]", "scratch/a", 40),
+            candidate("[0x1:0x4:0x0]", "scratch/d", 50),
+            candidate("[0x1:0x5:0x0]", "scratch/e", 5),
+        ];
+
+        assert_eq!(
+            select(xs, &["home".to_string()], 2, 100),
+            Selection {
+                candidate_files: 5,
+                candidate_bytes: 175,
+                protected_files: 1,
+                files: 2,
+                bytes: 90,
+                capped: true,
+                fids: vec!["[0x1:0x1:0x0]".into(), "[0x1:0x4:0x0]".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_select_within_caps() {
+        let xs = vec![candidate("[0x1:0x1:0x0]", "a", 1)];
+
+        let s = select(xs, &[], 10, 10);
+
+        assert!(!s.capped);
+        assert_eq!(s.fids, vec!["[0x1:0x1:0x0]".to_string()]);
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, RECOVERY_LOCK},
+};
+use chrono::Utc;
+use futures::future::join_all;
+use iml_action_client::Client;
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::recovery::TargetRecovery;
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+
+/// Recovery is watched live during a failover, so this is kept short.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Periodically reads the `recovery_status` of every mounted MDT and OST from the server it is
+/// mounted on, and stores the latest one in `target_recovery`.
+///
+/// Servers that don't answer keep their last status, which `updated_at` tells the age of.
+pub async fn run(pg_pool: PgPool, action_client: Client) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, RECOVERY_LOCK, || {
+            check_recovery(&pg_pool, &action_client)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error reading target recovery status: {}", e);
+        }
+    }
+}
+
+async fn check_recovery(pool: &PgPool, action_client: &Client) -> Result<(), ImlApiError> {
+    let hosts = sqlx::query!(
+        r#"
+            SELECT DISTINCT h.id, h.fqdn
+            FROM target t
+            INNER JOIN chroma_core_managedhost h ON h.id = t.active_host_id AND h.not_deleted = 't'
+            WHERE t.name <> 'MGS'
+            ORDER BY h.id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Every server is asked at once, so a slow one does not hold back the others.
+    let xs = join_all(hosts.into_iter().map(|h| async move {
+        let r = action_client
+            .invoke_rust_agent_expect_result(h.fqdn.clone(), "recovery_status", (), None)
+            .await;
+
+        let xs = r
+            .map_err(|e| e.to_string())
+            .and_then(|x| x.map_err(|e| e.to_string()))
+            .and_then(|x| {
+                serde_json::from_value::<Vec<TargetRecovery>>(x).map_err(|e| e.to_string())
+            });
+
+        (h.id, h.fqdn, xs)
+    }))
+    .await;
+
+    let now = Utc::now();
+
+    for (host_id, fqdn, r) in xs {
+        let recoveries = match r {
+            Ok(x) => x,
+            Err(e) => {
+                tracing::debug!("Could not read the recovery status of {}: {}", fqdn, e);
+
+                continue;
+            }
+        };
+
+        let targets = sqlx::query!(
+            "SELECT uuid, name FROM target WHERE active_host_id = $1",
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        for x in recoveries {
+            let uuid = match targets.iter().find(|t| t.name == x.target) {
+                Some(t) => &t.uuid,
+                None => continue,
+            };
+
+            sqlx::query!(
+                r#"
+                    INSERT INTO target_recovery
+                    (target_uuid, host_id, status, time_remaining, connected_clients, total_clients, completed_clients, evicted_clients, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                    ON CONFLICT (target_uuid) DO UPDATE
+                    SET host_id = EXCLUDED.host_id,
+                        status = EXCLUDED.status,
+                        time_remaining = EXCLUDED.time_remaining,
+                        connected_clients = EXCLUDED.connected_clients,
+                        total_clients = EXCLUDED.total_clients,
+                        completed_clients = EXCLUDED.completed_clients,
+                        evicted_clients = EXCLUDED.evicted_clients,
+                        updated_at = EXCLUDED.updated_at
+                "#,
+                uuid,
+                host_id,
+                x.status,
+                x.time_remaining,
+                x.connected_clients,
+                x.total_clients,
+                x.completed_clients,
+                x.evicted_clients,
+                now
+            )
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    // Targets that were unmounted or failed over since are read again on their new server.
+    sqlx::query!(
+        r#"
+            DELETE FROM target_recovery r
+            WHERE NOT EXISTS (
+                SELECT 1 FROM target t
+                WHERE t.uuid = r.target_uuid AND t.active_host_id = r.host_id
+            )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! The `totalCount` of paged queries.
+//!
+//! Counting rows reads every one of them, which gets slow on large tables. Tables with a row
+//! count kept by triggers in `rowcount` are read from there. Otherwise, past
+//! `GRAPHQL_EXACT_COUNT_THRESHOLD` rows, an unfiltered count is the estimate the planner keeps in
+//! `pg_class.reltuples`, and a filtered count stops once it passes the threshold.
+//! Either way `is_estimate` is set.
+
+use futures::Future;
+use iml_manager_env::get_graphql_exact_count_threshold;
+use iml_postgres::{sqlx, PgPool};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct RowCount {
+    pub(crate) total: i64,
+    pub(crate) is_estimate: bool,
+}
+
+/// Counts the rows of `table` a paged query matches.
+///
+/// `exact` counts the matching rows, but at most as many as the limit it is passed, if any.
+/// `filtered` tells whether the query matches fewer rows than the whole table.
+pub(crate) async fn count<F, Fut>(
+    pool: &PgPool,
+    table: &str,
+    filtered: bool,
+    exact: F,
+) -> Result<RowCount, sqlx::Error>
+where
+    F: FnOnce(Option<i64>) -> Fut,
+    Fut: Future<Output = Result<i64, sqlx::Error>>,
+{
+    if !filtered {
+        if let Some(total) = tracked(pool, table).await? {
+            return Ok(RowCount {
+                total,
+                is_estimate: false,
+            });
+        }
+    }
+
+    let threshold = get_graphql_exact_count_threshold();
+
+    let estimate = match planner_estimate(pool, table).await? {
+        Some(x) if x > threshold => x,
+        _ => {
+            return Ok(RowCount {
+                total: exact(None).await?,
+                is_estimate: false,
+            })
+        }
+    };
+
+    if !filtered {
+        return Ok(RowCount {
+            total: estimate,
+            is_estimate: true,
+        });
+    }
+
+    let n = exact(Some(threshold + 1)).await?;
+
+    Ok(capped(n, threshold))
+}
+
+/// A count that stopped at `threshold + 1` rows, which means there are at least that many.
+fn capped(n: i64, threshold: i64) -> RowCount {
+    RowCount {
+        total: n,
+        is_estimate: n > threshold,
+    }
+}
+
+/// The rows of `table` as kept by the `count_rows` trigger, if it has one.
+async fn tracked(pool: &PgPool, table: &str) -> Result<Option<i64>, sqlx::Error> {
+    let x = sqlx::query!(
+        "SELECT total_rows FROM rowcount WHERE table_name = $1",
+        table
+    )
+    .fetch_optional(pool)
+    .await?
+    .and_then(|x| x.total_rows);
+
+    Ok(x)
+}
+
+/// The rows of `table` as of its last `VACUUM` or `ANALYZE`.
+/// `None` when it was never analyzed.
+async fn planner_estimate(pool: &PgPool, table: &str) -> Result<Option<i64>, sqlx::Error> {
+    let x = sqlx::query!(
+        r#"
+            SELECT reltuples::BIGINT AS "reltuples!"
+            FROM pg_class
+            WHERE oid = to_regclass($1)
+        "#,
+        table
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|x| x.reltuples)
+    .filter(|x| *x >= 0);
+
+    Ok(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capped() {
+        assert_eq!(
+            capped(42, 100),
+            RowCount {
+                total: 42,
+                is_estimate: false
+            }
+        );
+        assert_eq!(
+            capped(100, 100),
+            RowCount {
+                total: 100,
+                is_estimate: false
+            }
+        );
+        assert_eq!(
+            capped(101, 100),
+            RowCount {
+                total: 101,
+                is_estimate: true
+            }
+        );
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Starts the snapshots of snapshot intervals.
+//!
+//! When an interval is due, its timer queues a run in `snapshot_policy_run` with a start time
+//! jittered by up to `snapshot_policy_settings.jitter`. Queued runs are started once their start
+//! time has passed, as long as fewer than `max_concurrent` snapshots are being taken, so
+//! filesystems sharing a schedule don't all snapshot at the same moment.
+//!
+//! Intervals with a start time have no timer. Their runs are queued here, at that wall-clock time
+//! in the interval's time zone and every interval after it, DST changes included.
+//!
+//! Snapshots queued by the `change_percent` threshold of a retention policy have no interval,
+//! they are started the same way, without barrier and without being mounted.
+
+use crate::{
+    error::ImlApiError,
+    graphql::approval,
+    leader::{run_exclusive, SNAPSHOT_POLICY_LOCK},
+};
+use chrono::{DateTime, NaiveTime, Utc};
+use futures::TryFutureExt;
+use iml_postgres::{active_mgs_host_fqdn, sqlx, PgPool};
+use iml_rabbit::{ImlRabbitError, Pool};
+use iml_wire_types::snapshot::{snapshot_name, SnapshotIntervalRun};
+use std::{collections::HashMap, time::Duration};
+use tokio::{stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Parses the start time of an interval, given as HH:MM.
+pub(crate) fn parse_start_time(x: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(x.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid start time {}, expected HH:MM", x))
+}
+
+/// Whether Postgres knows time zone `x`, e. g. `Europe/Paris`.
+pub(crate) async fn is_time_zone(pool: &PgPool, x: &str) -> Result<bool, ImlApiError> {
+    let x = sqlx::query!(
+        r#"SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1) AS "exists!""#,
+        x
+    )
+    .fetch_one(pool)
+    .await?
+    .exists;
+
+    Ok(x)
+}
+
+/// Queues a run of interval `interval_id`, due now.
+///
+/// Only one run of an interval is queued at a time. If one is already waiting, it is returned
+/// instead. `None` if there is no such interval.
+pub(crate) async fn queue_run(
+    pool: &PgPool,
+    interval_id: i32,
+) -> Result<Option<SnapshotIntervalRun>, ImlApiError> {
+    queue_run_at(pool, interval_id, Utc::now()).await
+}
+
+async fn queue_run_at(
+    pool: &PgPool,
+    interval_id: i32,
+    due_at: DateTime<Utc>,
+) -> Result<Option<SnapshotIntervalRun>, ImlApiError> {
+    let fs_name = sqlx::query!(
+        "SELECT filesystem_name FROM snapshot_interval WHERE id = $1",
+        interval_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|x| x.filesystem_name);
+
+    let fs_name = match fs_name {
+        Some(x) => x,
+        None => return Ok(None),
+    };
+
+    let queued = sqlx::query_as!(
+        SnapshotIntervalRun,
+        r#"
+            SELECT id, interval_id, filesystem_name, snapshot_name, due_at, start_after,
+                started_at, command_id, state, throttled, error, reason, action
+            FROM snapshot_policy_run
+            WHERE interval_id = $1 AND state = 'queued'
+        "#,
+        interval_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(x) = queued {
+        tracing::info!(
+            "Interval {} is due while its last run is still queued, skipping",
+            interval_id
+        );
+
+        return Ok(Some(x));
+    }
+
+    let x = sqlx::query_as!(
+        SnapshotIntervalRun,
+        r#"
+            INSERT INTO snapshot_policy_run (interval_id, filesystem_name, snapshot_name, due_at, start_after)
+            SELECT $1, $2, $3, $4, $4 + random() * s.jitter
+            FROM snapshot_policy_settings s
+            RETURNING id, interval_id, filesystem_name, snapshot_name, due_at, start_after,
+                started_at, command_id, state, throttled, error, reason, action
+        "#,
+        interval_id,
+        fs_name,
+        snapshot_name(interval_id, &fs_name, due_at),
+        due_at
+    )
+    .fetch_one(pool)
+    .await?;
+
+    tracing::debug!(
+        "Queued snapshot {} to start after {}",
+        x.snapshot_name,
+        x.start_after
+    );
+
+    Ok(Some(x))
+}
+
+/// The jobs that take a snapshot, and mount it when `automount` is set.
+pub(crate) fn snapshot_jobs(
+    fsname: &str,
+    name: &str,
+    comment: Option<&str>,
+    mgs_fqdn: &str,
+    use_barrier: bool,
+    automount: bool,
+) -> serde_json::Value {
+    let mut jobs = vec![serde_json::json!({
+        "class_name": "CreateSnapshotJob",
+        "args": {
+            "fsname": fsname,
+            "name": name,
+            "comment": comment,
+            "fqdn": mgs_fqdn,
+            "use_barrier": use_barrier,
+        }
+    })];
+
+    if automount {
+        jobs.push(serde_json::json!({
+            "class_name": "MountSnapshotJob",
+            "args": {
+                "fsname": fsname,
+                "name": name,
+                "fqdn": mgs_fqdn,
+                "depends_on_job_range": [0],
+            }
+        }));
+    }
+
+    serde_json::Value::Array(jobs)
+}
+
+/// The kwargs of the `run_jobs` call that takes a snapshot, before the approval rules are applied.
+pub(crate) fn snapshot_kwargs() -> HashMap<String, String> {
+    vec![("message".into(), "Creating snapshot".into())]
+        .into_iter()
+        .collect()
+}
+
+/// Submits the `snapshot_jobs` with `kwargs`. Returns the id of the command.
+pub(crate) async fn create_snapshot_jobs(
+    rabbit_pool: &Pool,
+    jobs: serde_json::Value,
+    kwargs: HashMap<String, String>,
+) -> Result<i32, ImlApiError> {
+    let command_id: i32 = iml_job_scheduler_rpc::call(
+        &rabbit_pool.get().await.map_err(ImlRabbitError::PoolError)?,
+        "run_jobs",
+        vec![jobs],
+        Some(kwargs),
+    )
+    .map_err(ImlApiError::ImlJobSchedulerRpcError)
+    .await?;
+
+    Ok(command_id)
+}
+
+/// Queues the latest due run of each interval with a start time, unless it was already queued.
+///
+/// An interval with a run still waiting is left alone until that run starts.
+async fn queue_scheduled(pool: &PgPool) -> Result<(), ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT x.id, x.due_at AS "due_at!"
+            FROM (
+                SELECT i.id, snapshot_interval_last_due(i.start_at, i.time_zone, i.interval, now()) AS due_at
+                FROM snapshot_interval i
+                WHERE i.start_at IS NOT NULL
+            ) x
+            WHERE x.due_at IS NOT NULL
+            AND NOT EXISTS (
+                SELECT 1 FROM snapshot_policy_run r
+                WHERE r.interval_id = x.id AND (r.due_at >= x.due_at OR r.state = 'queued')
+            )
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in xs {
+        queue_run_at(pool, x.id, x.due_at).await?;
+    }
+
+    Ok(())
+}
+
+/// Periodically queues the runs of intervals with a start time, and starts the queued runs whose
+/// start time has passed, as many as `max_concurrent` allows.
+pub async fn run(pg_pool: PgPool, rabbit_pool: Pool) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, SNAPSHOT_POLICY_LOCK, || async {
+            queue_scheduled(&pg_pool).await?;
+
+            start_due(&pg_pool, &rabbit_pool).await
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error starting snapshot interval runs: {}", e);
+        }
+    }
+}
+
+async fn start_due(pool: &PgPool, rabbit_pool: &Pool) -> Result<(), ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT r.id, r.filesystem_name, r.snapshot_name, r.due_at, r.interval_id,
+                COALESCE(i.use_barrier, 'f') AS "use_barrier!",
+                COALESCE(i.automount, 'f') AS "automount!"
+            FROM snapshot_policy_run r
+            LEFT OUTER JOIN snapshot_interval i ON i.id = r.interval_id
+            WHERE r.state = 'queued' AND r.action = 'create' AND r.start_after <= now()
+            ORDER BY r.start_after
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if xs.is_empty() {
+        return Ok(());
+    }
+
+    let max_concurrent = sqlx::query!("SELECT max_concurrent FROM snapshot_policy_settings")
+        .fetch_optional(pool)
+        .await?
+        .map(|x| x.max_concurrent)
+        .unwrap_or_default();
+
+    // Manual snapshots count too, they load the servers all the same.
+    let running = sqlx::query!(
+        r#"
+            SELECT count(*) AS "count!"
+            FROM chroma_core_createsnapshotjob csj
+            INNER JOIN chroma_core_job j ON j.id = csj.job_ptr_id
+            WHERE j.state <> 'complete'
+        "#
+    )
+    .fetch_one(pool)
+    .await?
+    .count;
+
+    let n = slots(max_concurrent, running, xs.len());
+
+    let (start, held) = xs.split_at(n);
+
+    if !held.is_empty() {
+        tracing::debug!(
+            "{} snapshot(s) running, holding back {} interval run(s)",
+            running,
+            held.len()
+        );
+
+        let ids: Vec<i32> = held.iter().map(|x| x.id).collect();
+
+        sqlx::query!(
+            "UPDATE snapshot_policy_run SET throttled = 't' WHERE id = ANY($1)",
+            &ids
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    for x in start {
+        let fqdn = active_mgs_host_fqdn(&x.filesystem_name, pool).await?;
+
+        let r = match fqdn {
+            Some(fqdn) => create_snapshot_jobs(
+                rabbit_pool,
+                snapshot_jobs(
+                    &x.filesystem_name,
+                    &x.snapshot_name,
+                    Some("automatically created by IML"),
+                    &fqdn,
+                    x.use_barrier,
+                    x.automount,
+                ),
+                approval::internal(snapshot_kwargs()),
+            )
+            .await
+            .map_err(|e| e.to_string()),
+            None => Err("Filesystem not found or MGS is not mounted".to_string()),
+        };
+
+        match r {
+            Ok(command_id) => {
+                sqlx::query!(
+                    r#"
+                        UPDATE snapshot_policy_run
+                        SET state = 'started', started_at = now(), command_id = $2
+                        WHERE id = $1
+                    "#,
+                    x.id,
+                    command_id
+                )
+                .execute(pool)
+                .await?;
+
+                if let Some(interval_id) = x.interval_id {
+                    sqlx::query!(
+                        "UPDATE snapshot_interval SET last_run = $2 WHERE id = $1",
+                        interval_id,
+                        x.due_at
+                    )
+                    .execute(pool)
+                    .await?;
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Could not start snapshot {}: {}", x.snapshot_name, e);
+
+                sqlx::query!(
+                    r#"
+                        UPDATE snapshot_policy_run
+                        SET state = 'failed', started_at = now(), error = $2
+                        WHERE id = $1
+                    "#,
+                    x.id,
+                    e
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// How many of `queued` runs can be started while `running` snapshots are being taken.
+/// A `max_concurrent` of 0 means no cap.
+fn slots(max_concurrent: i32, running: i64, queued: usize) -> usize {
+    if max_concurrent <= 0 {
+        return queued;
+    }
+
+    let free = (i64::from(max_concurrent) - running).max(0) as usize;
+
+    free.min(queued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use iml_wire_types::snapshot::parse_snapshot_name;
+
+    #[test]
+    fn test_slots() {
+        assert_eq!(slots(0, 50, 30), 30);
+        assert_eq!(slots(4, 0, 30), 4);
+        assert_eq!(slots(4, 3, 30), 1);
+        assert_eq!(slots(4, 6, 30), 0);
+        assert_eq!(slots(4, 1, 2), 2);
+    }
+
+    #[test]
+    fn test_parse_start_time() {
+        assert_eq!(parse_start_time("02:30"), Ok(NaiveTime::from_hms(2, 30, 0)));
+        assert_eq!(
+            parse_start_time(" 23:05 "),
+            Ok(NaiveTime::from_hms(23, 5, 0))
+        );
+        assert!(parse_start_time("24:00").is_err());
+        assert!(parse_start_time("2pm").is_err());
+        assert!(parse_start_time("").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_name() {
+        let due_at = Utc.ymd(2021, 1, 18).and_hms(9, 30, 5);
+
+        let name = snapshot_name(3, "fs", due_at);
+
+        assert_eq!(name, "3-fs-2021-01-18T09:30:05Z");
+
+        let x = parse_snapshot_name(&name).unwrap();
+
+        assert_eq!((x.id, x.fs_name.as_str(), x.timestamp), (3, "fs", due_at));
+    }
+}
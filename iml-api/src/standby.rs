@@ -0,0 +1,314 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Warm standby managers stream the database from the primary and sync the manager
+//! configuration on their own. Standbys are known by the `application_name` they stream with,
+//! and are recorded in `chroma_core_standbymanager` as they connect,
+//! so one that drops off is still reported.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, STANDBY_LOCK},
+};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use iml_postgres::{alert, sqlx, PgPool};
+use iml_wire_types::{
+    standby::{StandbyManager, StandbyStatus},
+    AlertRecordType, AlertSeverity,
+};
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The lag threshold of `standby.status` when `STANDBY_ALERT_LAG_MB` turns the alert off.
+pub(crate) const DEFAULT_LAG_MB: u32 = 64;
+
+/// A standby that has not synced the manager configuration for this long can't take over.
+const CONFIG_SYNC_MAX_AGE_HOURS: i64 = 24;
+
+/// Periodically checks whether every standby manager could take over.
+///
+/// A standby that is disconnected, has more than `lag_mb` MiB of WAL left to replay or has not
+/// synced its configuration within a day has a `StandbyBehindAlert` raised,
+/// which is lowered again once it is ready.
+pub async fn run(pg_pool: PgPool, lag_mb: u32) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, STANDBY_LOCK, || check_standbys(&pg_pool, lag_mb)).await;
+
+        if let Err(e) = r {
+            tracing::error!("Error checking standby managers: {}", e);
+        }
+    }
+}
+
+async fn check_standbys(pool: &PgPool, lag_mb: u32) -> Result<(), ImlApiError> {
+    if in_recovery(pool).await? {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        r#"
+            INSERT INTO chroma_core_standbymanager (name)
+            SELECT DISTINCT application_name FROM pg_stat_replication
+            WHERE application_name <> '' AND application_name <> 'pg_basebackup'
+            ON CONFLICT (name) DO NOTHING
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    let xs = standbys(pool, lag_mb, Utc::now()).await?;
+
+    if xs.is_empty() {
+        return Ok(());
+    }
+
+    let content_type_id = sqlx::query!(
+        "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'standbymanager'"
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    for (id, x) in xs {
+        let id = match id {
+            Some(id) => id,
+            None => continue,
+        };
+
+        if x.failover_ready {
+            alert::lower(pool, vec![AlertRecordType::StandbyBehindAlert], id).await?;
+        } else {
+            tracing::info!("Standby manager {} is not ready: {:?}", x.name, x.issues);
+
+            alert::raise(
+                pool,
+                AlertRecordType::StandbyBehindAlert,
+                format!(
+                    "Standby manager {} is not ready to take over: {}",
+                    x.name,
+                    x.issues.join(", ")
+                ),
+                content_type_id,
+                None,
+                AlertSeverity::WARNING,
+                id,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn in_recovery(pool: &PgPool) -> Result<bool, ImlApiError> {
+    let x = sqlx::query!(r#"SELECT pg_is_in_recovery() AS "in_recovery!""#)
+        .fetch_one(pool)
+        .await?
+        .in_recovery;
+
+    Ok(x)
+}
+
+/// The standby managers that are recorded or connected, along with their id once recorded.
+/// Must be run against the primary.
+async fn standbys(
+    pool: &PgPool,
+    lag_mb: u32,
+    now: DateTime<Utc>,
+) -> Result<Vec<(Option<i32>, StandbyManager)>, ImlApiError> {
+    let threshold = lag_threshold_bytes(lag_mb);
+
+    let xs = sqlx::query!(
+        r#"
+            SELECT
+                s.id AS "id?",
+                COALESCE(s.name, r.application_name) AS "name!",
+                host(r.client_addr) AS client_addr,
+                r.state,
+                r.sync_state,
+                pg_xlog_location_diff(pg_current_xlog_location(), r.replay_location)::float8 AS lag_bytes,
+                s.config_hash,
+                s.config_synced_at
+            FROM chroma_core_standbymanager s
+            FULL OUTER JOIN (
+                SELECT * FROM pg_stat_replication
+                WHERE application_name <> '' AND application_name <> 'pg_basebackup'
+            ) r ON r.application_name = s.name
+            ORDER BY 2
+        "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| {
+        let issues = issues(
+            x.state.as_deref(),
+            x.lag_bytes,
+            x.config_synced_at,
+            threshold,
+            now,
+        );
+
+        (
+            x.id,
+            StandbyManager {
+                name: x.name,
+                client_addr: x.client_addr,
+                state: x.state,
+                sync_state: x.sync_state,
+                lag_bytes: x.lag_bytes,
+                config_hash: x.config_hash,
+                config_synced_at: x.config_synced_at,
+                failover_ready: issues.is_empty(),
+                issues,
+            },
+        )
+    })
+    .collect();
+
+    Ok(xs)
+}
+
+/// The replication to the standby managers, as seen from the database the API is connected to.
+pub(crate) async fn status(pool: &PgPool, lag_mb: u32) -> Result<StandbyStatus, ImlApiError> {
+    let now = Utc::now();
+
+    if in_recovery(pool).await? {
+        let lag = sqlx::query!(
+            r#"SELECT EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp())::float8 AS lag"#
+        )
+        .fetch_one(pool)
+        .await?
+        .lag;
+
+        return Ok(StandbyStatus {
+            in_recovery: true,
+            replay_lag_secs: lag,
+            lag_threshold_bytes: lag_threshold_bytes(lag_mb),
+            standbys: vec![],
+            checked_at: now,
+        });
+    }
+
+    let standbys = standbys(pool, lag_mb, now)
+        .await?
+        .into_iter()
+        .map(|(_, x)| x)
+        .collect();
+
+    Ok(StandbyStatus {
+        in_recovery: false,
+        replay_lag_secs: None,
+        lag_threshold_bytes: lag_threshold_bytes(lag_mb),
+        standbys,
+        checked_at: now,
+    })
+}
+
+/// Records that the standby `name` synced the manager configuration with hash `hash`.
+pub(crate) async fn record_config_sync(
+    pool: &PgPool,
+    name: &str,
+    hash: &str,
+) -> Result<(), ImlApiError> {
+    sqlx::query!(
+        r#"
+            INSERT INTO chroma_core_standbymanager (name, config_hash, config_synced_at)
+            VALUES ($1, $2, now())
+            ON CONFLICT (name) DO UPDATE
+            SET config_hash = excluded.config_hash, config_synced_at = excluded.config_synced_at
+        "#,
+        name,
+        hash
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+fn lag_threshold_bytes(lag_mb: u32) -> f64 {
+    f64::from(lag_mb) * 1024. * 1024.
+}
+
+/// Why a standby could not take over, empty when it could.
+fn issues(
+    state: Option<&str>,
+    lag_bytes: Option<f64>,
+    config_synced_at: Option<DateTime<Utc>>,
+    threshold_bytes: f64,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut xs = vec![];
+
+    match state {
+        None => xs.push("not connected".to_string()),
+        Some("streaming") => {}
+        Some(x) => xs.push(format!("{} instead of streaming", x)),
+    }
+
+    if let Some(lag) = lag_bytes.filter(|x| *x > threshold_bytes) {
+        xs.push(format!(
+            "{:.0} MiB behind the primary",
+            lag / (1024. * 1024.)
+        ));
+    }
+
+    match config_synced_at {
+        None => xs.push("configuration never synced".to_string()),
+        Some(t) if now - t > ChronoDuration::hours(CONFIG_SYNC_MAX_AGE_HOURS) => xs.push(format!(
+            "configuration last synced {}",
+            t.format("%Y-%m-%d %H:%M:%S UTC")
+        )),
+        Some(_) => {}
+    }
+
+    xs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_issues() {
+        let now = Utc.ymd(2021, 2, 9).and_hms(12, 0, 0);
+        let threshold = lag_threshold_bytes(64);
+        let synced = Some(Utc.ymd(2021, 2, 9).and_hms(2, 0, 0));
+
+        assert!(issues(Some("streaming"), Some(1024.), synced, threshold, now).is_empty());
+
+        assert_eq!(
+            issues(
+                Some("catchup"),
+                Some(128. * 1024. * 1024.),
+                synced,
+                threshold,
+                now
+            ),
+            vec!["catchup instead of streaming", "128 MiB behind the primary"]
+        );
+
+        assert_eq!(
+            issues(None, None, None, threshold, now),
+            vec!["not connected", "configuration never synced"]
+        );
+
+        assert_eq!(
+            issues(
+                Some("streaming"),
+                Some(0.),
+                Some(Utc.ymd(2021, 2, 8).and_hms(11, 0, 0)),
+                threshold,
+                now
+            ),
+            vec!["configuration last synced 2021-02-08 11:00:00 UTC"]
+        );
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    error::ImlApiError,
+    leader::{run_exclusive, STONITH_TEST_LOCK},
+};
+use iml_action_client::Client;
+use iml_postgres::{alert, sqlx, PgPool};
+use iml_wire_types::{
+    stonith::{StonithTestResult, StonithTestRun},
+    AlertRecordType, AlertSeverity,
+};
+use std::time::Duration;
+use tokio::{stream::StreamExt, time::interval};
+
+/// How long runs are kept in `stonith_test`.
+const RETENTION_DAYS: i32 = 90;
+
+/// Periodically tests the stonith devices of every corosync cluster
+/// and records the results in `stonith_test`.
+///
+/// Each managed host that no working device can fence has a `StonithTestFailedAlert` raised,
+/// which is lowered again by the next run that can fence it.
+pub async fn run(pg_pool: PgPool, action_client: Client, interval_hours: u32) {
+    let every = Duration::from_secs(u64::from(interval_hours) * 60 * 60);
+
+    let mut interval = interval(every);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, STONITH_TEST_LOCK, || {
+            test_clusters(&pg_pool, &action_client, every)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error testing stonith devices: {}", e);
+        }
+    }
+}
+
+async fn test_clusters(
+    pool: &PgPool,
+    action_client: &Client,
+    every: Duration,
+) -> Result<(), ImlApiError> {
+    sqlx::query!(
+        "DELETE FROM stonith_test WHERE ran_at < now() - make_interval(days => $1)",
+        RETENTION_DAYS
+    )
+    .execute(pool)
+    .await?;
+
+    // Another replica may have just finished a run while this one waited for its tick.
+    let xs = sqlx::query!(
+        r#"
+            SELECT c.id
+            FROM corosync_cluster c
+            WHERE NOT EXISTS (
+                SELECT 1 FROM stonith_test t
+                WHERE t.cluster_id = c.id
+                AND t.ran_at > now() - make_interval(secs => $1)
+            )
+            ORDER BY c.id
+        "#,
+        every.as_secs_f64() / 2.0
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in xs {
+        let run = test_cluster(pool, action_client, x.id).await?;
+
+        if !run.passed {
+            tracing::info!("Stonith test of cluster {} failed: {:?}", x.id, run);
+        }
+    }
+
+    Ok(())
+}
+
+/// Tests the stonith devices of `cluster_id` from one of its online nodes and records the run.
+///
+/// Alerts are only raised or lowered when the test could be run.
+pub(crate) async fn test_cluster(
+    pool: &PgPool,
+    action_client: &Client,
+    cluster_id: i32,
+) -> Result<StonithTestRun, ImlApiError> {
+    let nodes = sqlx::query!(
+        r#"
+            SELECT
+                (n.id).name AS "name!",
+                n.online,
+                h.id AS "host_id?",
+                h.fqdn AS "fqdn?"
+            FROM corosync_node n
+            LEFT OUTER JOIN corosync_node_managed_host nmh
+                ON nmh.corosync_node_id = n.id AND nmh.cluster_id = n.cluster_id
+            LEFT OUTER JOIN chroma_core_managedhost h
+                ON h.id = nmh.host_id AND h.not_deleted = 't'
+            WHERE n.cluster_id = $1
+            ORDER BY (n.id).name
+        "#,
+        cluster_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let fqdn = nodes
+        .iter()
+        .filter(|x| x.online)
+        .find_map(|x| x.fqdn.as_ref());
+
+    let r = match fqdn {
+        Some(fqdn) => {
+            let names: Vec<_> = nodes.iter().map(|x| x.name.clone()).collect();
+
+            let r = action_client
+                .invoke_rust_agent_expect_result(fqdn.clone(), "stonith_test", names, None)
+                .await;
+
+            match r {
+                Ok(Ok(x)) => {
+                    serde_json::from_value::<StonithTestResult>(x).map_err(|e| e.to_string())
+                }
+                Ok(Err(e)) => Err(format!("{} could not test stonith: {}", fqdn, e)),
+                Err(e) => Err(format!("{} did not answer: {}", fqdn, e)),
+            }
+        }
+        None => Err(format!(
+            "No managed node of cluster {} is online",
+            cluster_id
+        )),
+    };
+
+    let (passed, error, result) = match r {
+        Ok(x) => (x.passed(), None, Some(x)),
+        Err(e) => (false, Some(e), None),
+    };
+
+    let x = sqlx::query!(
+        r#"
+            INSERT INTO stonith_test (cluster_id, passed, error, result)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, ran_at
+        "#,
+        cluster_id,
+        passed,
+        error,
+        result.as_ref().map(serde_json::to_value).transpose()?
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if let Some(result) = &result {
+        let content_type_id = sqlx::query!(
+            "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'managedhost'"
+        )
+        .fetch_one(pool)
+        .await?
+        .id;
+
+        for n in &nodes {
+            let host_id = match n.host_id {
+                Some(x) => x,
+                None => continue,
+            };
+
+            match failed_message(&n.name, result) {
+                Some(msg) => {
+                    alert::raise(
+                        pool,
+                        AlertRecordType::StonithTestFailedAlert,
+                        msg,
+                        content_type_id,
+                        None,
+                        AlertSeverity::WARNING,
+                        host_id,
+                    )
+                    .await?
+                }
+                None => {
+                    alert::lower(pool, vec![AlertRecordType::StonithTestFailedAlert], host_id)
+                        .await?
+                }
+            };
+        }
+    }
+
+    Ok(StonithTestRun {
+        id: x.id,
+        cluster_id,
+        ran_at: x.ran_at,
+        passed,
+        error,
+        result,
+    })
+}
+
+/// Why `node` can't be fenced, or `None` when it can
+fn failed_message(node: &str, result: &StonithTestResult) -> Option<String> {
+    let x = result.nodes.iter().find(|x| x.node == node)?;
+
+    if x.passed {
+        return None;
+    }
+
+    if x.devices.is_empty() {
+        return Some(format!("No stonith device can fence {}", node));
+    }
+
+    let errors: Vec<_> = result
+        .devices
+        .iter()
+        .filter(|d| x.devices.contains(&d.id))
+        .map(|d| match &d.error {
+            Some(e) => format!("{}: {}", d.id, e),
+            None => d.id.to_string(),
+        })
+        .collect();
+
+    Some(format!(
+        "Stonith devices of {} failed their test: {}",
+        node,
+        errors.join("; ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iml_wire_types::stonith::{StonithDeviceTest, StonithNodeTest};
+
+    #[test]
+    fn test_failed_message() {
+        let result = StonithTestResult {
+            devices: vec![StonithDeviceTest {
+                id: "st-oss1".into(),
+                passed: false,
+                error: Some("Connection timed out".into()),
+            }],
+            nodes: vec![
+                StonithNodeTest {
+                    node: "oss1".into(),
+                    devices: vec!["st-oss1".into()],
+                    passed: false,
+                },
+                StonithNodeTest {
+                    node: "oss2".into(),
+                    devices: vec![],
+                    passed: false,
+                },
+            ],
+        };
+
+        assert_eq!(
+            failed_message("oss1", &result).as_deref(),
+            Some("Stonith devices of oss1 failed their test: st-oss1: Connection timed out")
+        );
+        assert_eq!(
+            failed_message("oss2", &result).as_deref(),
+            Some("No stonith device can fence oss2")
+        );
+        assert_eq!(failed_message("oss3", &result), None);
+    }
+}
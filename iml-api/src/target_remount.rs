@@ -0,0 +1,385 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Remounts targets that went offline on their own.
+//!
+//! A target is offline on its own when its managed state dropped to `unmounted` while no
+//! command held it, which leaves a `TargetOfflineAlert` at error severity. Targets stopped
+//! on purpose get the alert at warning severity instead, and are left alone.
+//!
+//! Filesystems opt in with a row in `target_remount_policy`. Their offline targets are started
+//! again once `cooldown` has passed since they went offline or since the last attempt, at most
+//! `max_attempts` times. After that, the target gets a `TargetRemountFailedAlert`.
+
+use crate::{
+    error::ImlApiError,
+    graphql::approval,
+    leader::{run_exclusive, TARGET_REMOUNT_LOCK},
+};
+use chrono::{DateTime, Utc};
+use futures::TryFutureExt;
+use iml_postgres::{alert, sqlx, PgPool};
+use iml_rabbit::{ImlRabbitError, Pool};
+use iml_wire_types::{
+    graphql_duration::GraphQLDuration,
+    target_remount::{TargetRemountAttempt, TargetRemountPolicy},
+    AlertRecordType, AlertSeverity,
+};
+use std::{collections::HashSet, time::Duration};
+use tokio::{stream::StreamExt, time::interval};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What to do next about an offline target
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// The cool-down has not passed yet
+    Wait,
+    /// Make the attempt with this number
+    Remount(i32),
+    /// Every attempt was made
+    GiveUp,
+}
+
+/// What to do about a target offline since `offline_since`, which was remounted `attempts` times
+/// since then, the last time at `last_attempt_at`.
+fn next_step(
+    attempts: i64,
+    max_attempts: i32,
+    offline_since: DateTime<Utc>,
+    last_attempt_at: Option<DateTime<Utc>>,
+    cooldown: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Step {
+    if attempts >= i64::from(max_attempts) {
+        return Step::GiveUp;
+    }
+
+    let since = last_attempt_at.map_or(offline_since, |x| x.max(offline_since));
+
+    if now < since + cooldown {
+        Step::Wait
+    } else {
+        Step::Remount(attempts as i32 + 1)
+    }
+}
+
+pub(crate) async fn get_policy(
+    pool: &PgPool,
+    fs_name: &str,
+) -> Result<Option<TargetRemountPolicy>, ImlApiError> {
+    let x = sqlx::query!(
+        r#"
+            SELECT filesystem_name, enabled, max_attempts, cooldown
+            FROM target_remount_policy
+            WHERE filesystem_name = $1
+        "#,
+        fs_name
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|x| TargetRemountPolicy {
+        filesystem_name: x.filesystem_name,
+        enabled: x.enabled,
+        max_attempts: x.max_attempts,
+        cooldown: x.cooldown.into(),
+    });
+
+    Ok(x)
+}
+
+/// The latest remount attempts of the targets of `fs_name`, newest first.
+pub(crate) async fn get_attempts(
+    pool: &PgPool,
+    fs_name: &str,
+    limit: i64,
+) -> Result<Vec<TargetRemountAttempt>, ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT
+                r.id,
+                r.target_id,
+                r.target_name,
+                r.filesystem_name,
+                r.attempt,
+                r.command_id,
+                CASE
+                    WHEN r.error IS NOT NULL OR c.errored THEN 'failed'
+                    WHEN c.cancelled THEN 'cancelled'
+                    WHEN c.complete THEN 'succeeded'
+                    WHEN c.id IS NULL THEN 'failed'
+                    ELSE 'running'
+                END AS "state!",
+                r.error,
+                r.started_at
+            FROM target_remount_attempt r
+            LEFT OUTER JOIN chroma_core_command c ON c.id = r.command_id
+            WHERE r.filesystem_name = $1
+            ORDER BY r.started_at DESC, r.id DESC
+            LIMIT $2
+        "#,
+        fs_name,
+        limit
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| TargetRemountAttempt {
+        id: x.id,
+        target_id: x.target_id,
+        target_name: x.target_name,
+        filesystem_name: x.filesystem_name,
+        attempt: x.attempt,
+        command_id: x.command_id,
+        state: x.state,
+        error: x.error,
+        started_at: x.started_at,
+    })
+    .collect();
+
+    Ok(xs)
+}
+
+/// Periodically remounts the offline targets of filesystems with an enabled remount policy,
+/// and raises or lowers their `TargetRemountFailedAlert`.
+pub async fn run(pg_pool: PgPool, rabbit_pool: Pool) {
+    let mut interval = interval(CHECK_INTERVAL);
+
+    while interval.next().await.is_some() {
+        let r = run_exclusive(&pg_pool, TARGET_REMOUNT_LOCK, || {
+            remount_offline(&pg_pool, &rabbit_pool)
+        })
+        .await;
+
+        if let Err(e) = r {
+            tracing::error!("Error remounting offline targets: {}", e);
+        }
+    }
+}
+
+async fn remount_offline(pool: &PgPool, rabbit_pool: &Pool) -> Result<(), ImlApiError> {
+    // A target shared by several filesystems, like an MGS, follows the first of their policies.
+    // `busy` targets are locked by a job, which includes an attempt still running.
+    let xs = sqlx::query!(
+        r#"
+            SELECT DISTINCT ON (mt.id)
+                mt.id,
+                mt.name,
+                mt.content_type_id AS "content_type_id!",
+                p.filesystem_name,
+                p.max_attempts,
+                p.cooldown,
+                a.begin AS offline_since,
+                (
+                    SELECT count(*)
+                    FROM target_remount_attempt r
+                    WHERE r.target_id = mt.id AND r.started_at >= a.begin
+                ) AS "attempts!",
+                (
+                    SELECT max(r.started_at)
+                    FROM target_remount_attempt r
+                    WHERE r.target_id = mt.id AND r.started_at >= a.begin
+                ) AS last_attempt_at,
+                EXISTS (
+                    SELECT 1
+                    FROM chroma_core_job j
+                    WHERE j.state <> 'complete'
+                    AND NULLIF(j.locks_json, '')::jsonb @> jsonb_build_array(
+                        jsonb_build_object('locked_item_id', mt.id, 'locked_item_type_id', mt.content_type_id)
+                    )
+                ) AS "busy!"
+            FROM chroma_core_managedtarget mt
+            INNER JOIN target t ON t.uuid = mt.uuid
+            INNER JOIN target_remount_policy p ON p.filesystem_name = ANY(t.filesystems) AND p.enabled
+            INNER JOIN chroma_core_alertstate a
+                ON a.alert_item_id = mt.id
+                AND a.alert_item_type_id = mt.content_type_id
+                AND a.record_type = 'TargetOfflineAlert'
+                AND a.active = 't'
+                AND a.severity = $1
+            WHERE mt.not_deleted = 't' AND mt.state = 'unmounted'
+            ORDER BY mt.id, p.filesystem_name
+        "#,
+        i32::from(AlertSeverity::ERROR)
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let now = Utc::now();
+
+    // Targets out of attempts keep their alert until they are back
+    let mut exhausted = HashSet::new();
+
+    for x in xs {
+        let cooldown = chrono::Duration::from_std(GraphQLDuration::from(x.cooldown).0)
+            .unwrap_or_else(|_| chrono::Duration::zero());
+
+        let step = next_step(
+            x.attempts,
+            x.max_attempts,
+            x.offline_since,
+            x.last_attempt_at,
+            cooldown,
+            now,
+        );
+
+        if step == Step::GiveUp {
+            exhausted.insert(x.id);
+        }
+
+        if x.busy {
+            tracing::debug!("Target {} is locked by a job, not remounting it", x.name);
+
+            continue;
+        }
+
+        match step {
+            Step::Wait => {}
+            Step::GiveUp => {
+                alert::raise(
+                    pool,
+                    AlertRecordType::TargetRemountFailedAlert,
+                    format!(
+                        "Target {} could not be remounted after {} attempts",
+                        x.name, x.attempts
+                    ),
+                    x.content_type_id,
+                    None,
+                    AlertSeverity::ERROR,
+                    x.id,
+                )
+                .await?;
+            }
+            Step::Remount(attempt) => {
+                tracing::info!(
+                    "Remounting offline target {}, attempt {} of {}",
+                    x.name,
+                    attempt,
+                    x.max_attempts
+                );
+
+                let r = start_target(
+                    rabbit_pool,
+                    x.id,
+                    format!(
+                        "Remount offline target {} (attempt {} of {})",
+                        x.name, attempt, x.max_attempts
+                    ),
+                )
+                .await;
+
+                let (command_id, error) = match r {
+                    Ok(command_id) => (Some(command_id), None),
+                    Err(e) => {
+                        tracing::warn!("Could not remount target {}: {}", x.name, e);
+
+                        (None, Some(e.to_string()))
+                    }
+                };
+
+                sqlx::query!(
+                    r#"
+                        INSERT INTO target_remount_attempt
+                            (target_id, target_name, filesystem_name, attempt, command_id, error, started_at)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    "#,
+                    x.id,
+                    x.name,
+                    x.filesystem_name,
+                    attempt,
+                    command_id,
+                    error,
+                    now
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+
+    let alerted = sqlx::query!(
+        r#"
+            SELECT alert_item_id
+            FROM chroma_core_alertstate
+            WHERE record_type = 'TargetRemountFailedAlert' AND active = 't'
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in alerted {
+        if !exhausted.contains(&x.alert_item_id) {
+            alert::lower(
+                pool,
+                vec![AlertRecordType::TargetRemountFailedAlert],
+                x.alert_item_id,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Starts target `target_id` the same way `filesystem.start` does.
+async fn start_target(
+    rabbit_pool: &Pool,
+    target_id: i32,
+    message: String,
+) -> Result<i32, ImlApiError> {
+    let jobs = serde_json::json!([{
+        "class_name": "StartTargetJob",
+        "args": {
+            "target_id": target_id,
+            "old_state": "unmounted",
+        }
+    }]);
+
+    let kwargs = approval::internal(vec![("message".to_string(), message)].into_iter().collect());
+
+    let command_id: i32 = iml_job_scheduler_rpc::call(
+        &rabbit_pool.get().await.map_err(ImlRabbitError::PoolError)?,
+        "run_jobs",
+        vec![jobs],
+        Some(kwargs),
+    )
+    .map_err(ImlApiError::ImlJobSchedulerRpcError)
+    .await?;
+
+    Ok(command_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_next_step() {
+        let offline_since = Utc.ymd(2021, 1, 20).and_hms(9, 0, 0);
+        let cooldown = chrono::Duration::minutes(5);
+        let at = |m| offline_since + chrono::Duration::minutes(m);
+
+        assert_eq!(
+            next_step(0, 3, offline_since, None, cooldown, at(1)),
+            Step::Wait
+        );
+        assert_eq!(
+            next_step(0, 3, offline_since, None, cooldown, at(5)),
+            Step::Remount(1)
+        );
+        assert_eq!(
+            next_step(1, 3, offline_since, Some(at(5)), cooldown, at(9)),
+            Step::Wait
+        );
+        assert_eq!(
+            next_step(2, 3, offline_since, Some(at(10)), cooldown, at(15)),
+            Step::Remount(3)
+        );
+        assert_eq!(
+            next_step(3, 3, offline_since, Some(at(15)), cooldown, at(16)),
+            Step::GiveUp
+        );
+    }
+}
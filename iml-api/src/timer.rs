@@ -11,19 +11,14 @@ pub struct TimerConfig {
     service_config: String,
 }
 
+/// Configures the timer of snapshot interval `config_id`.
+/// Each time it fires, it queues a run of the interval, which `snapshot_policy` starts.
 pub async fn configure_snapshot_timer(
     config_id: i32,
     fsname: String,
     interval: Duration,
-    use_barrier: bool,
 ) -> Result<(), ImlApiError> {
-    let iml_cmd = format!(
-        r#"/bin/bash -c "/usr/bin/date +\"%%Y-%%m-%%dT%%TZ\" | xargs -I %% /usr/bin/iml snapshot create {} -c 'automatically created by IML' {} {}-{}-%%""#,
-        if use_barrier { "-b" } else { "" },
-        fsname,
-        config_id,
-        fsname
-    );
+    let iml_cmd = format!("/usr/bin/iml snapshot interval run {}", config_id);
 
     let timer_config = format!(
         r#"# Automatically created by IML
@@ -0,0 +1,133 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub approval: T,
+}
+
+pub mod requests {
+    use crate::Query;
+    use iml_wire_types::approval::{ApprovalState, CommandApproval};
+
+    pub static QUERY: &str = r#"
+        query ApprovalRequests($state: ApprovalState, $limit: Int) {
+          approval {
+            requests(state: $state, limit: $limit) {
+              id
+              message
+              class_names: classNames
+              state
+              requested_by: requestedBy
+              requested_at: requestedAt
+              decided_by: decidedBy
+              decided_at: decidedAt
+              reason
+              command_id: commandId
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        state: Option<ApprovalState>,
+        limit: Option<i32>,
+    }
+
+    pub fn build(state: Option<ApprovalState>, limit: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { state, limit }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Requests {
+        pub requests: Vec<CommandApproval>,
+    }
+
+    pub type Resp = super::Resp<Requests>;
+}
+
+pub mod approve {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation ApproveCommand($id: Int!) {
+          approval {
+            approve(id: $id) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Approve {
+        pub approve: Command,
+    }
+
+    pub type Resp = super::Resp<Approve>;
+}
+
+pub mod reject {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RejectCommand($id: Int!, $reason: String) {
+          approval {
+            reject(id: $id, reason: $reason) {
+              id
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+        reason: Option<String>,
+    }
+
+    pub fn build(id: i32, reason: Option<String>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id, reason }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Rejected {
+        pub id: i32,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Reject {
+        pub reject: Rejected,
+    }
+
+    pub type Resp = super::Resp<Reject>;
+}
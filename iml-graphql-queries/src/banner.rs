@@ -0,0 +1,42 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub banner: T,
+}
+
+pub mod active {
+    use crate::Query;
+    use iml_wire_types::banner::Banner;
+
+    pub static QUERY: &str = r#"
+        query ActiveBanners {
+          banner {
+            active {
+              id
+              severity
+              text
+              expires_at: expiresAt
+              created_by: createdBy
+              created_at: createdAt
+            }
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Active {
+        pub active: Vec<Banner>,
+    }
+
+    pub type Resp = super::Resp<Active>;
+}
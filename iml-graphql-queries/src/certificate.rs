@@ -0,0 +1,90 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub certificate: T,
+}
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::graphql::HostCertificate;
+
+    pub static QUERY: &str = r#"
+        query Certificates($hostIds: [Int!]) {
+          certificate {
+            list(hostIds: $hostIds) {
+              host_id: hostId
+              fqdn
+              serial
+              not_after: notAfter
+              days_remaining: daysRemaining
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "hostIds")]
+        host_ids: Option<Vec<i32>>,
+    }
+
+    pub fn build(host_ids: Option<Vec<i32>>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { host_ids }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct List {
+        pub list: Vec<HostCertificate>,
+    }
+
+    pub type Resp = super::Resp<List>;
+}
+
+pub mod rotate {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation RotateCertificates($hostIds: [Int!]!) {
+          certificate {
+            rotate(hostIds: $hostIds) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "hostIds")]
+        host_ids: Vec<i32>,
+    }
+
+    pub fn build(host_ids: Vec<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { host_ids }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Rotate {
+        pub rotate: Command,
+    }
+
+    pub type Resp = super::Resp<Rotate>;
+}
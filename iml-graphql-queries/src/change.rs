@@ -0,0 +1,54 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod changes {
+    use crate::Query;
+    use iml_wire_types::changes::{ChangeFeed, ChangeRecordType};
+
+    pub static QUERY: &str = r#"
+            query changes($sinceCursor: String, $recordTypes: [ChangeRecordType!], $limit: Int) {
+                changes(sinceCursor: $sinceCursor, recordTypes: $recordTypes, limit: $limit) {
+                    changes {
+                        cursor
+                        record_type: recordType
+                        record_id: recordId
+                        op
+                        changed_at: changedAt
+                        record
+                    }
+                    cursor
+                    has_more: hasMore
+                }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "sinceCursor")]
+        since_cursor: Option<String>,
+        #[serde(rename = "recordTypes")]
+        record_types: Option<Vec<ChangeRecordType>>,
+        limit: Option<i32>,
+    }
+
+    pub fn build(
+        since_cursor: Option<impl ToString>,
+        record_types: Option<Vec<ChangeRecordType>>,
+        limit: Option<i32>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                since_cursor: since_cursor.map(|x| x.to_string()),
+                record_types,
+                limit,
+            }),
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+    pub struct Resp {
+        pub changes: ChangeFeed,
+    }
+}
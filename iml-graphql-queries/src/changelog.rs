@@ -0,0 +1,126 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub changelog: T,
+}
+
+pub mod users {
+    use crate::Query;
+    use iml_wire_types::graphql::MdtChangelog;
+
+    pub static QUERY: &str = r#"
+        query ChangelogUsers($fsName: String!) {
+          changelog {
+            users(fsName: $fsName) {
+              target
+              current_index: currentIndex
+              users {
+                id
+                index
+                lag
+                idle_secs: idleSecs
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Users {
+        pub users: Vec<MdtChangelog>,
+    }
+
+    pub type Resp = super::Resp<Users>;
+}
+
+pub mod register {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RegisterChangelogUser($fsName: String!, $target: String!) {
+          changelog {
+            register(fsName: $fsName, target: $target)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        target: String,
+    }
+
+    pub fn build(fs_name: impl ToString, target: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                target: target.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Register {
+        pub register: String,
+    }
+
+    pub type Resp = super::Resp<Register>;
+}
+
+pub mod deregister {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation DeregisterChangelogUser($fsName: String!, $target: String!, $id: String!) {
+          changelog {
+            deregister(fsName: $fsName, target: $target, id: $id)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        target: String,
+        id: String,
+    }
+
+    pub fn build(fs_name: impl ToString, target: impl ToString, id: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                target: target.to_string(),
+                id: id.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Deregister {
+        pub deregister: bool,
+    }
+
+    pub type Resp = super::Resp<Deregister>;
+}
@@ -63,3 +63,49 @@ pub mod list_mount_command {
         pub client_mount_command: String,
     }
 }
+
+pub mod mount_config {
+    use crate::Query;
+    use iml_wire_types::graphql::ClientMountConfigKind;
+
+    pub static QUERY: &str = r#"
+        query clientMountConfig($fsName: String!, $kind: ClientMountConfigKind!, $network: String, $interface: String, $mountpoint: String) {
+            clientMountConfig(fsName: $fsName, kind: $kind, network: $network, interface: $interface, mountpoint: $mountpoint)
+        }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        kind: ClientMountConfigKind,
+        network: Option<String>,
+        interface: Option<String>,
+        mountpoint: Option<String>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        kind: ClientMountConfigKind,
+        network: Option<String>,
+        interface: Option<String>,
+        mountpoint: Option<String>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                kind,
+                network,
+                interface,
+                mountpoint,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "clientMountConfig"))]
+        pub client_mount_config: String,
+    }
+}
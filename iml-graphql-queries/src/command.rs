@@ -0,0 +1,256 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod annotations {
+    use crate::Query;
+    use iml_wire_types::graphql::CommandAnnotations;
+
+    pub static QUERY: &str = r#"
+            query CommandAnnotations($id: Int!) {
+              commandAnnotations(id: $id) {
+                command_id: commandId
+                tags
+                notes {
+                  id
+                  note
+                  author
+                  created_at: createdAt
+                }
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "commandAnnotations"))]
+        pub command_annotations: CommandAnnotations,
+    }
+}
+
+pub mod annotate {
+    use crate::Query;
+    use iml_wire_types::graphql::CommandAnnotations;
+
+    pub static QUERY: &str = r#"
+            mutation AnnotateCommand($id: Int!, $note: String, $tags: [String!]) {
+              annotateCommand(id: $id, note: $note, tags: $tags) {
+                command_id: commandId
+                tags
+                notes {
+                  id
+                  note
+                  author
+                  created_at: createdAt
+                }
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+        note: Option<String>,
+        tags: Option<Vec<String>>,
+    }
+
+    pub fn build(id: i32, note: Option<String>, tags: Option<Vec<String>>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id, note, tags }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "annotateCommand"))]
+        pub annotate_command: CommandAnnotations,
+    }
+}
+
+pub mod blockers {
+    use crate::Query;
+    use iml_wire_types::graphql::CommandBlocker;
+
+    pub static QUERY: &str = r#"
+            query CommandBlockers($commandId: Int!) {
+              commandBlockers(commandId: $commandId) {
+                job_id: jobId
+                class_name: className
+                blocking_job_id: blockingJobId
+                blocking_class_name: blockingClassName
+                blocking_state: blockingState
+                blocking_command_id: blockingCommandId
+                blocking_command_message: blockingCommandMessage
+                records {
+                  content_type_id: contentTypeId
+                  record_id: recordId
+                  record_type: recordType
+                  write
+                }
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "commandId")]
+        command_id: i32,
+    }
+
+    pub fn build(command_id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { command_id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "commandBlockers"))]
+        pub command_blockers: Vec<CommandBlocker>,
+    }
+}
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::{
+        graphql::{CommandFilter, CommandList},
+        SortDir,
+    };
+
+    pub static QUERY: &str = r#"
+            query Commands($limit: Int, $offset: Int, $dir: SortDir, $isActive: Boolean, $msg: String, $filter: CommandFilter) {
+              commands(limit: $limit, offset: $offset, dir: $dir, isActive: $isActive, msg: $msg, filter: $filter) {
+                data {
+                  cancelled
+                  complete
+                  created_at: createdAt
+                  errored
+                  id
+                  jobs
+                  logs
+                  message
+                  resource_uri: resourceUri
+                }
+                meta {
+                  limit
+                  offset
+                  total_count: totalCount
+                  is_estimate: isEstimate
+                }
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        limit: Option<i32>,
+        offset: Option<i32>,
+        dir: Option<SortDir>,
+        #[serde(rename = "isActive")]
+        is_active: Option<bool>,
+        msg: Option<String>,
+        filter: Option<CommandFilter>,
+    }
+
+    pub fn build(
+        limit: Option<i32>,
+        offset: Option<i32>,
+        dir: Option<SortDir>,
+        is_active: Option<bool>,
+        msg: Option<&str>,
+        filter: Option<CommandFilter>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                limit,
+                offset,
+                dir,
+                is_active,
+                msg: msg.map(|x| x.to_string()),
+                filter,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub commands: CommandList,
+    }
+}
+
+pub mod related_logs {
+    use crate::Query;
+    use iml_wire_types::{graphql::CommandStepLogs, LogSeverity};
+
+    pub static QUERY: &str = r#"
+            query CommandRelatedLogs($commandId: Int!, $severity: LogSeverity, $limit: Int) {
+              commandRelatedLogs(commandId: $commandId, severity: $severity, limit: $limit) {
+                job_id: jobId
+                class_name: className
+                step_index: stepIndex
+                step_count: stepCount
+                state
+                log
+                started_at: startedAt
+                finished_at: finishedAt
+                fqdns
+                messages {
+                  id
+                  datetime
+                  facility
+                  fqdn
+                  message
+                  message_class: messageClass
+                  severity
+                  tag
+                  source
+                }
+                truncated
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "commandId")]
+        command_id: i32,
+        severity: Option<LogSeverity>,
+        limit: Option<i32>,
+    }
+
+    pub fn build(
+        command_id: i32,
+        severity: Option<LogSeverity>,
+        limit: Option<i32>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                command_id,
+                severity,
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "commandRelatedLogs"))]
+        pub command_related_logs: Vec<CommandStepLogs>,
+    }
+}
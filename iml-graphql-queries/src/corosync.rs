@@ -0,0 +1,300 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod unmanaged_resources {
+    use crate::Query;
+    use iml_wire_types::db::UnmanagedResource;
+
+    pub static QUERY: &str = r#"
+        query unmanagedResources($hostId: Int) {
+          unmanagedResources(hostId: $hostId) {
+            id
+            name
+            cluster_id: clusterId
+            resource_agent: resourceAgent
+            role
+            active
+            failed
+            active_node_name: activeNodeName
+            mount_point: mountPoint
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "hostId")]
+        host_id: Option<i32>,
+    }
+
+    pub fn build(host_id: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { host_id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "unmanagedResources"))]
+        pub unmanaged_resources: Vec<UnmanagedResource>,
+    }
+}
+
+pub mod ha_events {
+    use crate::Query;
+    use iml_wire_types::graphql::HaEvent;
+
+    pub static QUERY: &str = r#"
+        query HaEvents($clusterId: Int, $from: DateTimeUtc, $to: DateTimeUtc) {
+          haEvents(clusterId: $clusterId, from: $from, to: $to) {
+            id
+            cluster_id: clusterId
+            resource
+            event
+            node
+            previous_node: previousNode
+            reason
+            happened_at: happenedAt
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "clusterId")]
+        cluster_id: Option<i32>,
+        from: Option<String>,
+        to: Option<String>,
+    }
+
+    /// `from` and `to` are RFC 3339 timestamps
+    pub fn build(cluster_id: Option<i32>, from: Option<String>, to: Option<String>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                cluster_id,
+                from,
+                to,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "haEvents"))]
+        pub ha_events: Vec<HaEvent>,
+    }
+}
+
+pub mod nodes {
+    use crate::Query;
+    use iml_wire_types::{graphql::CorosyncNodeList, SortDir};
+
+    pub static QUERY: &str = r#"
+        query CorosyncNodes($limit: Int, $offset: Int, $dir: SortDir) {
+          corosyncNodes(limit: $limit, offset: $offset, dir: $dir) {
+            data {
+              name
+              id
+              cluster_id: clusterId
+              online
+              standby
+              standby_onfail: standbyOnfail
+              maintenance
+              pending
+              unclean
+              shutdown
+              expected_up: expectedUp
+              is_dc: isDc
+              resources_running: resourcesRunning
+              type
+            }
+            meta {
+              limit
+              offset
+              total_count: totalCount
+              is_estimate: isEstimate
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        limit: Option<i32>,
+        offset: Option<i32>,
+        dir: Option<SortDir>,
+    }
+
+    pub fn build(limit: Option<i32>, offset: Option<i32>, dir: Option<SortDir>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { limit, offset, dir }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "corosyncNodes"))]
+        pub corosync_nodes: CorosyncNodeList,
+    }
+}
+
+pub mod fs_target_resources {
+    use crate::Query;
+    use iml_wire_types::graphql::TargetResource;
+
+    pub static QUERY: &str = r#"
+        query FsTargetResources($fsName: String) {
+          getFsTargetResources(fsName: $fsName) {
+            cluster_id: clusterId
+            fs_names: fsNames
+            uuid
+            name
+            resource_id: resourceId
+            state
+            cluster_hosts: clusterHosts
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: Option<String>,
+    }
+
+    pub fn build(fs_name: Option<impl ToString>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "getFsTargetResources"))]
+        pub get_fs_target_resources: Vec<TargetResource>,
+    }
+}
+
+pub mod banned_resources {
+    use crate::Query;
+    use iml_wire_types::graphql::BannedResource;
+
+    pub static QUERY: &str = r#"
+        query BannedResources {
+          getBannedResources {
+            id
+            name
+            cluster_id: clusterId
+            resource
+            node
+            weight
+            master_only: masterOnly
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "getBannedResources"))]
+        pub get_banned_resources: Vec<BannedResource>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_selects_all;
+    use iml_wire_types::graphql::{BannedResource, CorosyncNodeList, TargetResource};
+
+    #[test]
+    fn test_nodes() {
+        let resp = serde_json::json!({
+            "corosyncNodes": {
+                "data": [{
+                    "name": "mds1.local",
+                    "id": "1",
+                    "cluster_id": 1,
+                    "online": true,
+                    "standby": false,
+                    "standby_onfail": false,
+                    "maintenance": false,
+                    "pending": false,
+                    "unclean": false,
+                    "shutdown": false,
+                    "expected_up": true,
+                    "is_dc": true,
+                    "resources_running": 3,
+                    "type": "member"
+                }],
+                "meta": { "limit": 20, "offset": 0, "total_count": 1 }
+            }
+        });
+
+        let x: super::nodes::Resp = serde_json::from_value(resp).unwrap();
+
+        assert_eq!(x.corosync_nodes.data[0].r#type, "member");
+        assert_eq!(x.corosync_nodes.meta.total_count, 1);
+        assert!(!x.corosync_nodes.meta.is_estimate);
+
+        let x: CorosyncNodeList = x.corosync_nodes;
+
+        assert_selects_all(super::nodes::QUERY, &serde_json::to_value(x).unwrap());
+    }
+
+    #[test]
+    fn test_fs_target_resources() {
+        let x = TargetResource {
+            cluster_id: 1,
+            fs_names: vec!["fs".into()],
+            uuid: "d6f7c0c6".into(),
+            name: "fs-OST0000".into(),
+            resource_id: "fs-OST0000_a".into(),
+            state: "mounted".into(),
+            cluster_hosts: vec![3, 4],
+        };
+
+        let v = serde_json::to_value(&x).unwrap();
+
+        assert_selects_all(super::fs_target_resources::QUERY, &v);
+
+        let resp: super::fs_target_resources::Resp =
+            serde_json::from_value(serde_json::json!({ "getFsTargetResources": [v] })).unwrap();
+
+        assert_eq!(resp.get_fs_target_resources, vec![x]);
+    }
+
+    #[test]
+    fn test_banned_resources() {
+        let x = BannedResource {
+            id: 1,
+            name: "cli-ban-fs-MDT0000_a-on-mds2.local".into(),
+            cluster_id: 1,
+            resource: "fs-MDT0000_a".into(),
+            node: "mds2.local".into(),
+            weight: -1_000_000,
+            master_only: false,
+        };
+
+        let v = serde_json::to_value(&x).unwrap();
+
+        assert_selects_all(super::banned_resources::QUERY, &v);
+
+        let resp: super::banned_resources::Resp =
+            serde_json::from_value(serde_json::json!({ "getBannedResources": [v] })).unwrap();
+
+        assert_eq!(resp.get_banned_resources, vec![x]);
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::graphql::Dashboard;
+
+    pub static QUERY: &str = r#"
+        query Dashboards {
+          dashboards {
+            list {
+              id
+              name
+              owner
+              shared
+              panels {
+                title
+                kind
+                metric
+                fs_name: fsName
+                target_name: targetName
+                wide
+              }
+              updated_at: updatedAt
+            }
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct List {
+        pub list: Vec<Dashboard>,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub dashboards: List,
+    }
+}
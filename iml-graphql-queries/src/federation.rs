@@ -0,0 +1,263 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub federation: T,
+}
+
+pub mod site {
+    use crate::Query;
+    use iml_wire_types::federation::SiteOverview;
+
+    pub static QUERY: &str = r#"
+        query FederationSite {
+          federation {
+            site {
+              filesystems {
+                id
+                name
+                bytes_total: bytesTotal
+                bytes_free: bytesFree
+                bytes_avail: bytesAvail
+                files_total: filesTotal
+                files_free: filesFree
+                clients
+                targets {
+                  mounted
+                  unmounted
+                  alerting
+                }
+                active_alerts: activeAlerts
+              }
+              health {
+                severity
+                degraded_filesystems: degradedFilesystems {
+                  id
+                  name
+                  unmounted_targets: unmountedTargets
+                }
+                down_hosts: downHosts {
+                  id
+                  fqdn
+                }
+                failed_commands: failedCommands
+                service_issues: serviceIssues
+              }
+              alerts {
+                critical
+                error
+                warning
+                info
+              }
+            }
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Site {
+        pub site: SiteOverview,
+    }
+
+    pub type Resp = super::Resp<Site>;
+}
+
+pub mod overview {
+    use crate::Query;
+    use iml_wire_types::federation::FederatedOverview;
+
+    pub static QUERY: &str = r#"
+        query FederatedOverview {
+          federatedOverview {
+            sites {
+              peer_id: peerId
+              name
+              url
+              overview {
+                filesystems {
+                  id
+                  name
+                  bytes_total: bytesTotal
+                  bytes_free: bytesFree
+                  bytes_avail: bytesAvail
+                  files_total: filesTotal
+                  files_free: filesFree
+                  clients
+                  targets {
+                    mounted
+                    unmounted
+                    alerting
+                  }
+                  active_alerts: activeAlerts
+                }
+                health {
+                  severity
+                  degraded_filesystems: degradedFilesystems {
+                    id
+                    name
+                    unmounted_targets: unmountedTargets
+                  }
+                  down_hosts: downHosts {
+                    id
+                    fqdn
+                  }
+                  failed_commands: failedCommands
+                  service_issues: serviceIssues
+                }
+                alerts {
+                  critical
+                  error
+                  warning
+                  info
+                }
+              }
+              error
+            }
+            checked_at: checkedAt
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "federatedOverview"))]
+        pub federated_overview: FederatedOverview,
+    }
+}
+
+pub mod peers {
+    use crate::Query;
+    use iml_wire_types::federation::FederationPeer;
+
+    pub static QUERY: &str = r#"
+        query FederationPeers {
+          federation {
+            peers {
+              id
+              name
+              url
+              verify_tls: verifyTls
+              created_at: createdAt
+            }
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Peers {
+        pub peers: Vec<FederationPeer>,
+    }
+
+    pub type Resp = super::Resp<Peers>;
+}
+
+pub mod add_peer {
+    use crate::Query;
+    use iml_wire_types::federation::FederationPeer;
+
+    pub static QUERY: &str = r#"
+        mutation AddFederationPeer($name: String!, $url: String!, $apiKey: String!, $verifyTls: Boolean) {
+          federation {
+            addPeer(name: $name, url: $url, apiKey: $apiKey, verifyTls: $verifyTls) {
+              id
+              name
+              url
+              verify_tls: verifyTls
+              created_at: createdAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        name: String,
+        url: String,
+        #[serde(rename = "apiKey")]
+        api_key: String,
+        #[serde(rename = "verifyTls")]
+        verify_tls: Option<bool>,
+    }
+
+    pub fn build(
+        name: impl ToString,
+        url: impl ToString,
+        api_key: impl ToString,
+        verify_tls: Option<bool>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                name: name.to_string(),
+                url: url.to_string(),
+                api_key: api_key.to_string(),
+                verify_tls,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct AddPeer {
+        #[serde(rename(deserialize = "addPeer"))]
+        pub add_peer: FederationPeer,
+    }
+
+    pub type Resp = super::Resp<AddPeer>;
+}
+
+pub mod remove_peer {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveFederationPeer($name: String!) {
+          federation {
+            removePeer(name: $name)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        name: String,
+    }
+
+    pub fn build(name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemovePeer {
+        #[serde(rename(deserialize = "removePeer"))]
+        pub remove_peer: bool,
+    }
+
+    pub type Resp = super::Resp<RemovePeer>;
+}
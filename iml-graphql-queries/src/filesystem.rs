@@ -32,3 +32,915 @@ pub mod detect {
 
     pub type Resp = super::Resp<Detect>;
 }
+
+pub mod detect_filesystems {
+    use crate::Query;
+    use iml_wire_types::graphql::DetectScan;
+
+    pub static QUERY: &str = r#"
+        mutation DetectFilesystems($hostIds: [Int!]!) {
+          filesystem {
+            detectFilesystems(hostIds: $hostIds) {
+              id
+              host_ids: hostIds
+              filesystems {
+                name
+                complete
+                registered
+                targets {
+                  name
+                  uuid
+                  kind
+                  state
+                  hosts
+                  registered
+                  import
+                }
+              }
+              created_at: createdAt
+              imported_at: importedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "hostIds")]
+        host_ids: Vec<i32>,
+    }
+
+    pub fn build(host_ids: Vec<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { host_ids }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct DetectFilesystems {
+        #[serde(rename(deserialize = "detectFilesystems"))]
+        pub detect_filesystems: DetectScan,
+    }
+
+    pub type Resp = super::Resp<DetectFilesystems>;
+}
+
+pub mod import_detected {
+    use crate::Query;
+    use iml_wire_types::graphql::DetectScan;
+
+    pub static QUERY: &str = r#"
+        mutation ImportDetected($scanId: Int!) {
+          filesystem {
+            importDetected(scanId: $scanId) {
+              id
+              host_ids: hostIds
+              filesystems {
+                name
+                complete
+                registered
+                targets {
+                  name
+                  uuid
+                  kind
+                  state
+                  hosts
+                  registered
+                  import
+                }
+              }
+              created_at: createdAt
+              imported_at: importedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "scanId")]
+        scan_id: i32,
+    }
+
+    pub fn build(scan_id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { scan_id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ImportDetected {
+        #[serde(rename(deserialize = "importDetected"))]
+        pub import_detected: DetectScan,
+    }
+
+    pub type Resp = super::Resp<ImportDetected>;
+}
+
+pub mod evict_client {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation EvictClient($fsName: String!, $nid: String!) {
+          filesystem {
+            evictClient(fsName: $fsName, nid: $nid)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        nid: String,
+    }
+
+    pub fn build(fs_name: impl ToString, nid: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                nid: nid.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct EvictClient {
+        #[serde(rename(deserialize = "evictClient"))]
+        pub evict_client: Vec<String>,
+    }
+
+    pub type Resp = super::Resp<EvictClient>;
+}
+
+pub mod check_history {
+    use crate::Query;
+    use iml_wire_types::graphql::FilesystemCheckRun;
+
+    pub static QUERY: &str = r#"
+        query FilesystemCheckHistory($fsName: String!, $limit: Int) {
+          filesystemCheckHistory(fsName: $fsName, limit: $limit) {
+            id
+            fs_name: fsName
+            ran_at: ranAt
+            passed
+            checks {
+              kind
+              passed
+              details
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        limit: Option<i32>,
+    }
+
+    pub fn build(fs_name: impl ToString, limit: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "filesystemCheckHistory"))]
+        pub filesystem_check_history: Vec<FilesystemCheckRun>,
+    }
+}
+
+pub mod dashboard_overview {
+    use crate::Query;
+    use iml_wire_types::graphql::FilesystemOverview;
+
+    pub static QUERY: &str = r#"
+        query DashboardOverview {
+          dashboardOverview {
+            id
+            name
+            bytes_total: bytesTotal
+            bytes_free: bytesFree
+            bytes_avail: bytesAvail
+            files_total: filesTotal
+            files_free: filesFree
+            clients
+            targets {
+              mounted
+              unmounted
+              alerting
+            }
+            active_alerts: activeAlerts
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "dashboardOverview"))]
+        pub dashboard_overview: Vec<FilesystemOverview>,
+    }
+}
+
+pub mod compatibility_report {
+    use crate::Query;
+    use iml_wire_types::graphql::CompatibilityReport;
+
+    pub static QUERY: &str = r#"
+        query CompatibilityReport($fsName: String!) {
+          compatibilityReport(fsName: $fsName) {
+            fs_name: fsName
+            manager_version: managerVersion
+            hosts {
+              host_id: hostId
+              fqdn
+              role
+              lustre
+              agent
+              error
+            }
+            issues
+            supported
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "compatibilityReport"))]
+        pub compatibility_report: CompatibilityReport,
+    }
+}
+
+pub mod dne {
+    use crate::Query;
+    use iml_wire_types::dne::FilesystemDne;
+
+    pub static QUERY: &str = r#"
+        query FilesystemDne($fsName: String!) {
+          filesystemDne(fsName: $fsName) {
+            fs_name: fsName
+            mdts {
+              name
+              index
+              active_host: activeHost
+              files_total: filesTotal
+              files_free: filesFree
+            }
+            default_dir_stripe: defaultDirStripe {
+              stripe_count: stripeCount
+              stripe_offset: stripeOffset
+              hash_type: hashType
+            }
+            dir_stripe_error: dirStripeError
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "filesystemDne"))]
+        pub filesystem_dne: FilesystemDne,
+    }
+}
+
+pub mod recovery_status {
+    use crate::Query;
+    use iml_wire_types::recovery::FilesystemRecoveryStatus;
+
+    pub static QUERY: &str = r#"
+        query RecoveryStatus($fsName: String!) {
+          recoveryStatus(fsName: $fsName) {
+            fs_name: fsName
+            recovering
+            time_remaining: timeRemaining
+            targets {
+              target_name: targetName
+              fqdn
+              status
+              time_remaining: timeRemaining
+              connected_clients: connectedClients
+              total_clients: totalClients
+              completed_clients: completedClients
+              evicted_clients: evictedClients
+              updated_at: updatedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "recoveryStatus"))]
+        pub recovery_status: FilesystemRecoveryStatus,
+    }
+}
+
+pub mod set_default_dir_stripe {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation SetDefaultDirStripe($fsName: String!, $stripeCount: Int!, $stripeOffset: Int!, $hashType: String) {
+          filesystem {
+            setDefaultDirStripe(fsName: $fsName, stripeCount: $stripeCount, stripeOffset: $stripeOffset, hashType: $hashType) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        #[serde(rename = "stripeCount")]
+        stripe_count: i32,
+        #[serde(rename = "stripeOffset")]
+        stripe_offset: i32,
+        #[serde(rename = "hashType")]
+        hash_type: Option<String>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        stripe_count: i32,
+        stripe_offset: i32,
+        hash_type: Option<String>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                stripe_count,
+                stripe_offset,
+                hash_type,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct SetDefaultDirStripe {
+        #[serde(rename(deserialize = "setDefaultDirStripe"))]
+        pub set_default_dir_stripe: Command,
+    }
+
+    pub type Resp = super::Resp<SetDefaultDirStripe>;
+}
+
+pub mod default_stripe {
+    use crate::Query;
+    use iml_wire_types::stripe::DefaultStripe;
+
+    pub static QUERY: &str = r#"
+        query DefaultStripe($fsName: String!) {
+          defaultStripe(fsName: $fsName) {
+            fs_name: fsName
+            components {
+              extent_end: extentEnd
+              stripe_count: stripeCount
+              stripe_size: stripeSize
+              pool
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "defaultStripe"))]
+        pub default_stripe: DefaultStripe,
+    }
+}
+
+pub mod set_default_stripe {
+    use crate::Query;
+    use iml_wire_types::{stripe::StripeComponentInput, Command};
+
+    pub static QUERY: &str = r#"
+        mutation SetDefaultStripe($fsName: String!, $components: [StripeComponentInput!]!) {
+          filesystem {
+            setDefaultStripe(fsName: $fsName, components: $components) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        components: Vec<StripeComponentInput>,
+    }
+
+    pub fn build(fs_name: impl ToString, components: Vec<StripeComponentInput>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                components,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct SetDefaultStripe {
+        #[serde(rename(deserialize = "setDefaultStripe"))]
+        pub set_default_stripe: Command,
+    }
+
+    pub type Resp = super::Resp<SetDefaultStripe>;
+}
+
+pub mod start {
+    use crate::Query;
+    use iml_wire_types::graphql::FilesystemTransition;
+
+    pub static QUERY: &str = r#"
+        mutation StartFilesystem($fsName: String!) {
+          filesystem {
+            start(fsName: $fsName) {
+              command {
+                cancelled
+                complete
+                created_at: createdAt
+                errored
+                id
+                jobs
+                logs
+                message
+                resource_uri: resourceUri
+              }
+              targets {
+                name
+                kind
+                action
+                reason
+                job_state: jobState
+                errored
+                cancelled
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Start {
+        pub start: FilesystemTransition,
+    }
+
+    pub type Resp = super::Resp<Start>;
+}
+
+pub mod stop {
+    use crate::Query;
+    use iml_wire_types::graphql::FilesystemTransition;
+
+    pub static QUERY: &str = r#"
+        mutation StopFilesystem($fsName: String!) {
+          filesystem {
+            stop(fsName: $fsName) {
+              command {
+                cancelled
+                complete
+                created_at: createdAt
+                errored
+                id
+                jobs
+                logs
+                message
+                resource_uri: resourceUri
+              }
+              targets {
+                name
+                kind
+                action
+                reason
+                job_state: jobState
+                errored
+                cancelled
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Stop {
+        pub stop: FilesystemTransition,
+    }
+
+    pub type Resp = super::Resp<Stop>;
+}
+
+pub mod target_transitions {
+    use crate::Query;
+    use iml_wire_types::graphql::TargetTransition;
+
+    pub static QUERY: &str = r#"
+        query TargetTransitions($commandId: Int!) {
+          targetTransitions(commandId: $commandId) {
+            name
+            kind
+            action
+            reason
+            job_state: jobState
+            errored
+            cancelled
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "commandId")]
+        command_id: i32,
+    }
+
+    pub fn build(command_id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { command_id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "targetTransitions"))]
+        pub target_transitions: Vec<TargetTransition>,
+    }
+}
+
+pub mod remount_status {
+    use crate::Query;
+    use iml_wire_types::target_remount::TargetRemountStatus;
+
+    pub static QUERY: &str = r#"
+        query TargetRemount($fsName: String!, $limit: Int) {
+          targetRemount(fsName: $fsName, limit: $limit) {
+            policy {
+              filesystem_name: filesystemName
+              enabled
+              max_attempts: maxAttempts
+              cooldown
+            }
+            attempts {
+              id
+              target_id: targetId
+              target_name: targetName
+              filesystem_name: filesystemName
+              attempt
+              command_id: commandId
+              state
+              error
+              started_at: startedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        limit: Option<i32>,
+    }
+
+    pub fn build(fs_name: impl ToString, limit: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "targetRemount"))]
+        pub target_remount: TargetRemountStatus,
+    }
+}
+
+pub mod set_remount_policy {
+    use crate::Query;
+    use iml_wire_types::target_remount::TargetRemountPolicy;
+
+    pub static QUERY: &str = r#"
+        mutation SetRemountPolicy($fsName: String!, $enabled: Boolean, $maxAttempts: Int, $cooldown: Duration) {
+          filesystem {
+            setRemountPolicy(fsName: $fsName, enabled: $enabled, maxAttempts: $maxAttempts, cooldown: $cooldown) {
+              filesystem_name: filesystemName
+              enabled
+              max_attempts: maxAttempts
+              cooldown
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        enabled: Option<bool>,
+        #[serde(rename = "maxAttempts")]
+        max_attempts: Option<i32>,
+        cooldown: Option<String>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        enabled: Option<bool>,
+        max_attempts: Option<i32>,
+        cooldown: Option<String>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                enabled,
+                max_attempts,
+                cooldown,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct SetRemountPolicy {
+        #[serde(rename(deserialize = "setRemountPolicy"))]
+        pub set_remount_policy: TargetRemountPolicy,
+    }
+
+    pub type Resp = super::Resp<SetRemountPolicy>;
+}
+
+pub mod remove_remount_policy {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveRemountPolicy($fsName: String!) {
+          filesystem {
+            removeRemountPolicy(fsName: $fsName)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveRemountPolicy {
+        #[serde(rename(deserialize = "removeRemountPolicy"))]
+        pub remove_remount_policy: bool,
+    }
+
+    pub type Resp = super::Resp<RemoveRemountPolicy>;
+}
+
+pub mod add_mdt {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation AddMdt($fsName: String!, $hostId: Int!, $device: String!) {
+          filesystem {
+            addMdt(fsName: $fsName, hostId: $hostId, device: $device) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        #[serde(rename = "hostId")]
+        host_id: i32,
+        device: String,
+    }
+
+    pub fn build(fs_name: impl ToString, host_id: i32, device: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                host_id,
+                device: device.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct AddMdt {
+        #[serde(rename(deserialize = "addMdt"))]
+        pub add_mdt: Command,
+    }
+
+    pub type Resp = super::Resp<AddMdt>;
+}
+
+pub mod add_ost {
+    use crate::Query;
+    use iml_wire_types::new_target::{AddOstResult, OstSpecInput};
+
+    pub static QUERY: &str = r#"
+        mutation AddOst($fsName: String!, $specs: [OstSpecInput!]!, $rebalance: Boolean) {
+          filesystem {
+            addOst(fsName: $fsName, specs: $specs, rebalance: $rebalance) {
+              command {
+                cancelled
+                complete
+                created_at: createdAt
+                errored
+                id
+                jobs
+                logs
+                message
+                resource_uri: resourceUri
+              }
+              targets
+              rebalance_hint: rebalanceHint
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        specs: Vec<OstSpecInput>,
+        rebalance: Option<bool>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        specs: Vec<OstSpecInput>,
+        rebalance: impl Into<Option<bool>>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                specs,
+                rebalance: rebalance.into(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct AddOst {
+        #[serde(rename(deserialize = "addOst"))]
+        pub add_ost: AddOstResult,
+    }
+
+    pub type Resp = super::Resp<AddOst>;
+}
@@ -0,0 +1,182 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    #[serde(rename(deserialize = "haCluster"))]
+    pub ha_cluster: T,
+}
+
+pub mod ring_status {
+    use crate::Query;
+    use iml_wire_types::graphql::HostRingStatus;
+
+    pub static QUERY: &str = r#"
+        query RingStatus($hostId: Int!) {
+          haCluster {
+            ringStatus(hostId: $hostId) {
+              host_id: hostId
+              fqdn
+              rings {
+                ring
+                address
+                status
+                faulty
+              }
+              error
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "hostId")]
+        host_id: i32,
+    }
+
+    pub fn build(host_id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { host_id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RingStatus {
+        #[serde(rename(deserialize = "ringStatus"))]
+        pub ring_status: Vec<HostRingStatus>,
+    }
+
+    pub type Resp = super::Resp<RingStatus>;
+}
+
+pub mod create {
+    use crate::Query;
+    use iml_wire_types::{graphql::HaNodeInput, Command};
+
+    pub static QUERY: &str = r#"
+        mutation CreateHaCluster($clusterName: String!, $nodes: [HaNodeInput!]!, $mcastPort: Int) {
+          haCluster {
+            create(clusterName: $clusterName, nodes: $nodes, mcastPort: $mcastPort) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "clusterName")]
+        cluster_name: String,
+        nodes: Vec<HaNodeInput>,
+        #[serde(rename = "mcastPort")]
+        mcast_port: Option<i32>,
+    }
+
+    pub fn build(
+        cluster_name: impl ToString,
+        nodes: Vec<HaNodeInput>,
+        mcast_port: Option<i32>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                cluster_name: cluster_name.to_string(),
+                nodes,
+                mcast_port,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Create {
+        pub create: Command,
+    }
+
+    pub type Resp = super::Resp<Create>;
+}
+
+pub mod cluster_config_diff {
+    use crate::Query;
+    use iml_wire_types::graphql::ClusterConfigDiff;
+
+    pub static QUERY: &str = r#"
+        query ClusterConfigDiff($clusterId: Int!) {
+          haCluster {
+            clusterConfigDiff(clusterId: $clusterId) {
+              host_id: hostId
+              fqdn
+              drifted
+              changed_at: changedAt
+              baselined_at: baselinedAt
+              cib_diff: cibDiff
+              corosync_conf_diff: corosyncConfDiff
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "clusterId")]
+        cluster_id: i32,
+    }
+
+    pub fn build(cluster_id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { cluster_id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ClusterConfigDiffResp {
+        #[serde(rename(deserialize = "clusterConfigDiff"))]
+        pub cluster_config_diff: Vec<ClusterConfigDiff>,
+    }
+
+    pub type Resp = super::Resp<ClusterConfigDiffResp>;
+}
+
+pub mod reapply_config {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation ReapplyConfig($clusterId: Int!) {
+          haCluster {
+            reapplyConfig(clusterId: $clusterId)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "clusterId")]
+        cluster_id: i32,
+    }
+
+    pub fn build(cluster_id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { cluster_id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ReapplyConfig {
+        #[serde(rename(deserialize = "reapplyConfig"))]
+        pub reapply_config: bool,
+    }
+
+    pub type Resp = super::Resp<ReapplyConfig>;
+}
@@ -0,0 +1,40 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod system {
+    use crate::Query;
+    use iml_wire_types::graphql::SystemHealth;
+
+    pub static QUERY: &str = r#"
+        query SystemHealth {
+          systemHealth {
+            severity
+            degraded_filesystems: degradedFilesystems {
+              id
+              name
+              unmounted_targets: unmountedTargets
+            }
+            down_hosts: downHosts {
+              id
+              fqdn
+            }
+            failed_commands: failedCommands
+            service_issues: serviceIssues
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "systemHealth"))]
+        pub system_health: SystemHealth,
+    }
+}
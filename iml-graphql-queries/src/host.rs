@@ -0,0 +1,294 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::graphql::TaggedHost;
+
+    pub static QUERY: &str = r#"
+            query Hosts($tags: [String!]) {
+              host {
+                list(tags: $tags) {
+                  id
+                  fqdn
+                  nodename
+                  state
+                  tags {
+                    key
+                    value
+                  }
+                }
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        tags: Option<Vec<String>>,
+    }
+
+    pub fn build(tags: Option<Vec<String>>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { tags }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct HostList {
+        pub list: Vec<TaggedHost>,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub host: HostList,
+    }
+}
+
+pub mod set_tag {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+            mutation SetHostTag($hostIds: [Int!], $tags: [String!], $key: String!, $value: String!) {
+              host {
+                setTag(hostIds: $hostIds, tags: $tags, key: $key, value: $value)
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "hostIds")]
+        host_ids: Option<Vec<i32>>,
+        tags: Option<Vec<String>>,
+        key: String,
+        value: String,
+    }
+
+    pub fn build(
+        host_ids: Option<Vec<i32>>,
+        tags: Option<Vec<String>>,
+        key: impl ToString,
+        value: impl ToString,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                host_ids,
+                tags,
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct SetTag {
+        #[serde(rename = "setTag")]
+        pub set_tag: Vec<i32>,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub host: SetTag,
+    }
+}
+
+pub mod remove_tag {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+            mutation RemoveHostTag($hostIds: [Int!], $tags: [String!], $key: String!) {
+              host {
+                removeTag(hostIds: $hostIds, tags: $tags, key: $key)
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "hostIds")]
+        host_ids: Option<Vec<i32>>,
+        tags: Option<Vec<String>>,
+        key: String,
+    }
+
+    pub fn build(
+        host_ids: Option<Vec<i32>>,
+        tags: Option<Vec<String>>,
+        key: impl ToString,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                host_ids,
+                tags,
+                key: key.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveTag {
+        #[serde(rename = "removeTag")]
+        pub remove_tag: Vec<i32>,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub host: RemoveTag,
+    }
+}
+
+pub mod resync {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation ResyncHost($id: Int!) {
+          host {
+            resync(id: $id) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resync {
+        pub resync: Command,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub host: Resync,
+    }
+}
+
+pub mod heartbeats {
+    use crate::Query;
+    use iml_wire_types::graphql::HostHeartbeat;
+
+    pub static QUERY: &str = r#"
+            query HostHeartbeats {
+              host {
+                heartbeats {
+                  host_id: hostId
+                  fqdn
+                  last_seen: lastSeen
+                  boot_time: bootTime
+                  seconds_since_contact: secondsSinceContact
+                  needs_attention: needsAttention
+                }
+              }
+            }
+        "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Heartbeats {
+        pub heartbeats: Vec<HostHeartbeat>,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub host: Heartbeats,
+    }
+}
+
+pub mod queue {
+    use crate::Query;
+    use iml_wire_types::graphql::HostQueueEntry;
+
+    pub static QUERY: &str = r#"
+            query HostQueue($hostId: Int!) {
+              hostQueue(hostId: $hostId) {
+                position
+                job_id: jobId
+                state
+                priority
+                class_name: className
+                command_id: commandId
+                command_message: commandMessage
+                created_at: createdAt
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "hostId")]
+        host_id: i32,
+    }
+
+    pub fn build(host_id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { host_id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "hostQueue"))]
+        pub host_queue: Vec<HostQueueEntry>,
+    }
+}
+
+pub mod queues {
+    use crate::Query;
+    use iml_wire_types::graphql::HostQueueSummary;
+
+    pub static QUERY: &str = r#"
+            query HostQueues {
+              hostQueues {
+                host_id: hostId
+                fqdn
+                running
+                high
+                normal
+                low
+              }
+            }
+        "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "hostQueues"))]
+        pub host_queues: Vec<HostQueueSummary>,
+    }
+}
@@ -2,14 +2,32 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+pub mod approval;
+pub mod banner;
+pub mod certificate;
+pub mod change;
+pub mod changelog;
 pub mod client_mount;
+pub mod command;
+pub mod corosync;
+pub mod dashboards;
+pub mod federation;
 pub mod filesystem;
+pub mod ha_cluster;
+pub mod health;
+pub mod host;
+pub mod lnet_ping;
+pub mod lock;
 pub mod log;
+pub mod maintenance;
+pub mod manager;
+pub mod role;
 pub mod server_profile;
 pub mod snapshot;
 pub mod stratagem;
 pub mod target;
 pub mod task;
+pub mod user_preferences;
 
 use std::fmt;
 
@@ -44,6 +62,13 @@ pub struct Errors {
     errors: Vec<Error>,
 }
 
+impl Errors {
+    /// The message of each returned error
+    pub fn messages(&self) -> impl Iterator<Item = &str> {
+        self.errors.iter().map(|x| x.message.as_str())
+    }
+}
+
 impl fmt::Display for Errors {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let x = self.errors.iter().fold(
@@ -76,3 +101,31 @@ impl<T> From<Response<T>> for Result<Data<T>, Errors> {
         }
     }
 }
+
+/// Asserts `query` selects every field of `x`, under the name it is deserialized from.
+/// Catches fields added to a wire type but not to the queries returning it.
+#[cfg(test)]
+pub(crate) fn assert_selects_all(query: &str, x: &serde_json::Value) {
+    let selected: std::collections::HashSet<&str> = query
+        .lines()
+        .map(|l| l.split(|c| c == '{' || c == '(').next().unwrap_or_default())
+        .map(|l| l.split(':').next().unwrap_or_default().trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    match x {
+        serde_json::Value::Object(xs) => {
+            for (k, v) in xs {
+                assert!(selected.contains(k.as_str()), "{} is not selected", k);
+
+                assert_selects_all(query, v);
+            }
+        }
+        serde_json::Value::Array(xs) => {
+            for x in xs {
+                assert_selects_all(query, x);
+            }
+        }
+        _ => {}
+    }
+}
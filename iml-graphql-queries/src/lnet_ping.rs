@@ -0,0 +1,164 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    #[serde(rename(deserialize = "lnetPing"))]
+    pub lnet_ping: T,
+}
+
+pub mod start {
+    use crate::Query;
+    use iml_wire_types::lnet_ping::LnetPingMesh;
+
+    pub static QUERY: &str = r#"
+        mutation StartLnetPingMesh($hostIds: [Int!]!, $count: Int) {
+          lnetPing {
+            start(hostIds: $hostIds, count: $count) {
+              id
+              host_ids: hostIds
+              count
+              started_at: startedAt
+              finished_at: finishedAt
+              pairs {
+                source_host_id: sourceHostId
+                source_fqdn: sourceFqdn
+                target_host_id: targetHostId
+                target_fqdn: targetFqdn
+                target_nid: targetNid
+                sent
+                received
+                loss_percent: lossPercent
+                avg_latency_ms: avgLatencyMs
+                max_latency_ms: maxLatencyMs
+                error
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "hostIds")]
+        host_ids: Vec<i32>,
+        count: Option<i32>,
+    }
+
+    pub fn build(host_ids: Vec<i32>, count: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { host_ids, count }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Start {
+        pub start: LnetPingMesh,
+    }
+
+    pub type Resp = super::Resp<Start>;
+}
+
+pub mod mesh {
+    use crate::Query;
+    use iml_wire_types::lnet_ping::LnetPingMesh;
+
+    pub static QUERY: &str = r#"
+        query LnetPingMesh($id: Int!) {
+          lnetPing {
+            mesh(id: $id) {
+              id
+              host_ids: hostIds
+              count
+              started_at: startedAt
+              finished_at: finishedAt
+              pairs {
+                source_host_id: sourceHostId
+                source_fqdn: sourceFqdn
+                target_host_id: targetHostId
+                target_fqdn: targetFqdn
+                target_nid: targetNid
+                sent
+                received
+                loss_percent: lossPercent
+                avg_latency_ms: avgLatencyMs
+                max_latency_ms: maxLatencyMs
+                error
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Mesh {
+        pub mesh: Option<LnetPingMesh>,
+    }
+
+    pub type Resp = super::Resp<Mesh>;
+}
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::lnet_ping::LnetPingMesh;
+
+    pub static QUERY: &str = r#"
+        query LnetPingMeshes($limit: Int) {
+          lnetPing {
+            meshes(limit: $limit) {
+              id
+              host_ids: hostIds
+              count
+              started_at: startedAt
+              finished_at: finishedAt
+              pairs {
+                source_host_id: sourceHostId
+                source_fqdn: sourceFqdn
+                target_host_id: targetHostId
+                target_fqdn: targetFqdn
+                target_nid: targetNid
+                sent
+                received
+                loss_percent: lossPercent
+                avg_latency_ms: avgLatencyMs
+                max_latency_ms: maxLatencyMs
+                error
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        limit: Option<i32>,
+    }
+
+    pub fn build(limit: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { limit }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Meshes {
+        pub meshes: Vec<LnetPingMesh>,
+    }
+
+    pub type Resp = super::Resp<Meshes>;
+}
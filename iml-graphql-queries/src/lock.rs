@@ -0,0 +1,60 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::graphql::RecordLocks;
+
+    pub static QUERY: &str = r#"
+            query Locks($recordType: String!, $recordId: Int!) {
+              locks(recordType: $recordType, recordId: $recordId) {
+                content_type_id: contentTypeId
+                record_id: recordId
+                holders {
+                  job_id: jobId
+                  job_state: jobState
+                  class_name: className
+                  command_ids: commandIds
+                  write
+                  begin_state: beginState
+                  end_state: endState
+                  waiting_for: waitingFor
+                }
+                waiters {
+                  job_id: jobId
+                  job_state: jobState
+                  class_name: className
+                  command_ids: commandIds
+                  write
+                  begin_state: beginState
+                  end_state: endState
+                  waiting_for: waitingFor
+                }
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "recordType")]
+        record_type: String,
+        #[serde(rename = "recordId")]
+        record_id: i32,
+    }
+
+    pub fn build(record_type: impl ToString, record_id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                record_type: record_type.to_string(),
+                record_id,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub locks: RecordLocks,
+    }
+}
@@ -7,8 +7,8 @@ pub mod logs {
     use iml_wire_types::{logs::LogResponse, LogSeverity, MessageClass, SortDir};
 
     pub static QUERY: &str = r#"
-            query logs($limit: Int, $offset: Int, $dir: SortDir, $message: String, $fqdn: String, $tag: String, $startDatetime: DateTimeUtc, $endDatetime: DateTimeUtc, $messageClass: [MessageClass!], $severity: LogSeverity) {
-                logs(limit: $limit, offset: $offset, dir: $dir, message: $message, fqdn: $fqdn, tag: $tag, startDatetime: $startDatetime, endDatetime: $endDatetime, messageClass: $messageClass, severity: $severity) {
+            query logs($limit: Int, $offset: Int, $dir: SortDir, $message: String, $fqdn: String, $tag: String, $source: String, $startDatetime: DateTimeUtc, $endDatetime: DateTimeUtc, $messageClass: [MessageClass!], $severity: LogSeverity, $fs_name: String) {
+                logs(limit: $limit, offset: $offset, dir: $dir, message: $message, fqdn: $fqdn, tag: $tag, source: $source, startDatetime: $startDatetime, endDatetime: $endDatetime, messageClass: $messageClass, severity: $severity, fsName: $fs_name) {
                     data {
                         id
                         datetime
@@ -18,9 +18,11 @@ pub mod logs {
                         message_class: messageClass
                         severity
                         tag
+                        source
                     }
                     meta {
                         total_count: totalCount
+                        is_estimate: isEstimate
                     }
                 }
             }
@@ -34,10 +36,12 @@ pub mod logs {
         message: Option<String>,
         fqdn: Option<String>,
         tag: Option<String>,
+        source: Option<String>,
         start_datetime: Option<String>,
         end_datetime: Option<String>,
         message_class: Option<Vec<MessageClass>>,
         severity: Option<LogSeverity>,
+        fs_name: Option<String>,
     }
 
     #[derive(Debug)]
@@ -61,10 +65,12 @@ pub mod logs {
                     message: None,
                     fqdn: None,
                     tag: None,
+                    source: None,
                     start_datetime: None,
                     end_datetime: None,
                     message_class: None,
                     severity: None,
+                    fs_name: None,
                 },
             }
         }
@@ -99,6 +105,11 @@ pub mod logs {
             self
         }
 
+        pub fn with_source(mut self, source: impl ToString) -> Self {
+            self.vars.source = Some(source.to_string());
+            self
+        }
+
         pub fn with_start_datetime(mut self, start_datetime: impl ToString) -> Self {
             self.vars.start_datetime = Some(start_datetime.to_string());
             self
@@ -119,6 +130,11 @@ pub mod logs {
             self
         }
 
+        pub fn with_fs_name(mut self, fs_name: impl ToString) -> Self {
+            self.vars.fs_name = Some(fs_name.to_string());
+            self
+        }
+
         pub fn build(self) -> Query<Vars> {
             Query {
                 query: QUERY.to_string(),
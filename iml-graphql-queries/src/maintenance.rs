@@ -0,0 +1,52 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub maintenance: T,
+}
+
+pub mod upcoming {
+    use crate::Query;
+    use iml_wire_types::maintenance::MaintenanceWindow;
+
+    pub static QUERY: &str = r#"
+        query UpcomingMaintenance($limit: Int) {
+          maintenance {
+            upcoming(limit: $limit) {
+              id
+              name
+              scope
+              scope_id: scopeId
+              scope_name: scopeName
+              starts_at: startsAt
+              ends_at: endsAt
+              suppress_alerts: suppressAlerts
+              created_by: createdBy
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        limit: Option<i32>,
+    }
+
+    pub fn build(limit: impl Into<Option<i32>>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                limit: limit.into(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Upcoming {
+        pub upcoming: Vec<MaintenanceWindow>,
+    }
+
+    pub type Resp = super::Resp<Upcoming>;
+}
@@ -0,0 +1,66 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod status {
+    use crate::Query;
+    use iml_wire_types::graphql::ManagerStatus;
+
+    pub static QUERY: &str = r#"
+        query ManagerStatus {
+          managerStatus {
+            severity
+            services {
+              name
+              severity
+              response_ms: responseMs
+              details
+            }
+            checked_at: checkedAt
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "managerStatus"))]
+        pub manager_status: ManagerStatus,
+    }
+}
+
+pub mod schema_migrations {
+    use crate::Query;
+    use iml_wire_types::graphql::SchemaMigration;
+
+    pub static QUERY: &str = r#"
+        query SchemaMigrations {
+          schemaMigrations {
+            version
+            description
+            state
+            installed_on: installedOn
+            execution_ms: executionMs
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "schemaMigrations"))]
+        pub schema_migrations: Vec<SchemaMigration>,
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::role::Role;
+
+    pub static QUERY: &str = r#"
+        query Roles {
+          role {
+            list {
+              id
+              name
+              description
+              permissions
+              user_ids: userIds
+            }
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct List {
+        pub list: Vec<Role>,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub role: List,
+    }
+}
+
+pub mod my_permissions {
+    use crate::Query;
+    use iml_wire_types::role::Permission;
+
+    pub static QUERY: &str = r#"
+        query MyPermissions {
+          role {
+            myPermissions
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct MyPermissions {
+        #[serde(rename(deserialize = "myPermissions"))]
+        pub my_permissions: Vec<Permission>,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub role: MyPermissions,
+    }
+}
+
+pub mod create {
+    use crate::Query;
+    use iml_wire_types::role::{Permission, Role};
+
+    pub static QUERY: &str = r#"
+        mutation CreateRole($name: String!, $description: String, $permissions: [Permission!]!) {
+          role {
+            create(name: $name, description: $description, permissions: $permissions) {
+              id
+              name
+              description
+              permissions
+              user_ids: userIds
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        name: String,
+        description: Option<String>,
+        permissions: Vec<Permission>,
+    }
+
+    pub fn build(
+        name: impl ToString,
+        description: Option<impl ToString>,
+        permissions: Vec<Permission>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                name: name.to_string(),
+                description: description.map(|x| x.to_string()),
+                permissions,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Create {
+        pub create: Role,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub role: Create,
+    }
+}
+
+pub mod update {
+    use crate::Query;
+    use iml_wire_types::role::{Permission, Role};
+
+    pub static QUERY: &str = r#"
+        mutation UpdateRole($id: Int!, $name: String, $description: String, $permissions: [Permission!]) {
+          role {
+            update(id: $id, name: $name, description: $description, permissions: $permissions) {
+              id
+              name
+              description
+              permissions
+              user_ids: userIds
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+        name: Option<String>,
+        description: Option<String>,
+        permissions: Option<Vec<Permission>>,
+    }
+
+    pub fn build(
+        id: i32,
+        name: Option<impl ToString>,
+        description: Option<impl ToString>,
+        permissions: Option<Vec<Permission>>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                id,
+                name: name.map(|x| x.to_string()),
+                description: description.map(|x| x.to_string()),
+                permissions,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Update {
+        pub update: Role,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub role: Update,
+    }
+}
+
+pub mod remove {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveRole($id: Int!) {
+          role {
+            remove(id: $id)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Remove {
+        pub remove: bool,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub role: Remove,
+    }
+}
+
+pub mod assign {
+    use crate::Query;
+    use iml_wire_types::role::Role;
+
+    pub static QUERY: &str = r#"
+        mutation AssignRole($id: Int!, $userIds: [Int!]!) {
+          role {
+            assign(id: $id, userIds: $userIds) {
+              id
+              name
+              description
+              permissions
+              user_ids: userIds
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+        #[serde(rename = "userIds")]
+        user_ids: Vec<i32>,
+    }
+
+    pub fn build(id: i32, user_ids: Vec<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id, user_ids }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Assign {
+        pub assign: Role,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub role: Assign,
+    }
+}
+
+pub mod unassign {
+    use crate::Query;
+    use iml_wire_types::role::Role;
+
+    pub static QUERY: &str = r#"
+        mutation UnassignRole($id: Int!, $userIds: [Int!]!) {
+          role {
+            unassign(id: $id, userIds: $userIds) {
+              id
+              name
+              description
+              permissions
+              user_ids: userIds
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+        #[serde(rename = "userIds")]
+        user_ids: Vec<i32>,
+    }
+
+    pub fn build(id: i32, user_ids: Vec<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id, user_ids }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Unassign {
+        pub unassign: Role,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub role: Unassign,
+    }
+}
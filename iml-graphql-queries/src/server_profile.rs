@@ -25,6 +25,8 @@ pub mod list {
               ui_name: uiName
               user_selectable: userSelectable
               worker
+              version
+              updated_at: updatedAt
             }
           }
         "#;
@@ -104,3 +106,84 @@ pub mod remove {
         pub remove_server_profile: bool,
     }
 }
+
+pub mod compare {
+    use crate::Query;
+    use iml_wire_types::graphql::ServerProfileComparison;
+
+    pub static QUERY: &str = r#"
+          query CompareProfiles($a: String!, $b: String!) {
+            compareProfiles(a: $a, b: $b) {
+              a
+              b
+              settings {
+                name
+                a
+                b
+                differs
+              }
+              packages {
+                both
+                only_a: onlyA
+                only_b: onlyB
+              }
+              repos {
+                both
+                only_a: onlyA
+                only_b: onlyB
+              }
+            }
+          }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        a: String,
+        b: String,
+    }
+
+    pub fn build(a: impl ToString, b: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                a: a.to_string(),
+                b: b.to_string(),
+            }),
+        }
+    }
+
+    #[derive(serde::Deserialize, Clone, Debug)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "compareProfiles"))]
+        pub compare_profiles: ServerProfileComparison,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_selects_all;
+
+    #[test]
+    fn test_compare() {
+        let resp = serde_json::json!({
+            "compareProfiles": {
+                "a": "base_managed_rh7",
+                "b": "custom",
+                "settings": [
+                    { "name": "Pacemaker", "a": "true", "b": "false", "differs": true }
+                ],
+                "packages": { "both": ["lustre"], "only_a": [], "only_b": ["kmod-zfs"] },
+                "repos": { "both": ["lustre-server"], "only_a": [], "only_b": [] }
+            }
+        });
+
+        let x: super::compare::Resp = serde_json::from_value(resp).unwrap();
+
+        assert_eq!(x.compare_profiles.packages.only_b, vec!["kmod-zfs"]);
+
+        assert_selects_all(
+            super::compare::QUERY,
+            &serde_json::to_value(&x.compare_profiles).unwrap(),
+        );
+    }
+}
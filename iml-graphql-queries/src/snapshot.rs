@@ -54,6 +54,52 @@ pub mod create {
     }
 }
 
+pub mod preflight {
+    use crate::Query;
+    use iml_wire_types::snapshot::SnapshotPreflight;
+
+    pub static QUERY: &str = r#"
+            query SnapshotPreflight($fsname: String!, $name: String!, $use_barrier: Boolean) {
+              snapshotPreflight(fsname: $fsname, name: $name, useBarrier: $use_barrier) {
+                passed
+                checks {
+                  check
+                  passed
+                  message
+                }
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsname: String,
+        name: String,
+        use_barrier: Option<bool>,
+    }
+
+    pub fn build(
+        fsname: impl ToString,
+        name: impl ToString,
+        use_barrier: Option<bool>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fsname: fsname.to_string(),
+                name: name.to_string(),
+                use_barrier,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "snapshotPreflight"))]
+        pub snapshot_preflight: SnapshotPreflight,
+    }
+}
+
 pub mod destroy {
     use crate::Query;
     use iml_wire_types::Command;
@@ -185,6 +231,70 @@ pub mod unmount {
     }
 }
 
+pub mod hold {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation HoldSnapshot($fsname: String!, $name: String!) {
+          holdSnapshot(fsname: $fsname, name: $name)
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsname: String,
+        name: String,
+    }
+
+    pub fn build(fsname: impl ToString, name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fsname: fsname.to_string(),
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "holdSnapshot"))]
+        pub hold_snapshot: bool,
+    }
+}
+
+pub mod release {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation ReleaseSnapshot($fsname: String!, $name: String!) {
+          releaseSnapshot(fsname: $fsname, name: $name)
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsname: String,
+        name: String,
+    }
+
+    pub fn build(fsname: impl ToString, name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fsname: fsname.to_string(),
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "releaseSnapshot"))]
+        pub release_snapshot: bool,
+    }
+}
+
 pub mod list {
     use crate::Query;
     use iml_wire_types::{snapshot::Snapshot, SortDir};
@@ -195,8 +305,10 @@ pub mod list {
             comment
             create_time: createTime
             filesystem_name: filesystemName
+            held
             modify_time: modifyTime
             mounted
+            policy_run_id: policyRunId
             snapshot_fsname: snapshotFsname
             snapshot_name: snapshotName
           }
@@ -238,12 +350,123 @@ pub mod list {
     }
 }
 
+pub mod history {
+    use crate::Query;
+    use iml_wire_types::snapshot::SnapshotHistoryEntry;
+
+    pub static QUERY: &str = r#"
+        query SnapshotHistory($fsname: String!, $name: String, $limit: Int) {
+          snapshotHistory(fsname: $fsname, name: $name, limit: $limit) {
+            id
+            filesystem_name: filesystemName
+            snapshot_name: snapshotName
+            snapshot_fsname: snapshotFsname
+            create_time: createTime
+            comment
+            taken_by_policy: takenByPolicy
+            destroyed_at: destroyedAt
+            destroyed_by: destroyedBy
+            command_id: commandId
+            reason
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsname: String,
+        name: Option<String>,
+        limit: Option<u32>,
+    }
+
+    pub fn build(fsname: impl ToString, name: Option<&str>, limit: Option<u32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fsname: fsname.to_string(),
+                name: name.map(|x| x.to_string()),
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "snapshotHistory"))]
+        pub snapshot_history: Vec<SnapshotHistoryEntry>,
+    }
+}
+
+pub mod compare {
+    use crate::Query;
+    use iml_wire_types::snapshot::SnapshotComparison;
+
+    pub static QUERY: &str = r#"
+        query SnapshotCompare($fsname: String!, $a: String!, $b: String!) {
+          snapshotCompare(fsname: $fsname, a: $a, b: $b) {
+            from {
+              comment
+              create_time: createTime
+              filesystem_name: filesystemName
+              held
+              modify_time: modifyTime
+              mounted
+              policy_run_id: policyRunId
+              snapshot_fsname: snapshotFsname
+              snapshot_name: snapshotName
+            }
+            to {
+              comment
+              create_time: createTime
+              filesystem_name: filesystemName
+              held
+              modify_time: modifyTime
+              mounted
+              policy_run_id: policyRunId
+              snapshot_fsname: snapshotFsname
+              snapshot_name: snapshotName
+            }
+            policy_runs: policyRuns {
+              interval_id: intervalId
+              snapshot_name: snapshotName
+              started_at: startedAt
+              state
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsname: String,
+        a: String,
+        b: String,
+    }
+
+    pub fn build(fsname: impl ToString, a: impl ToString, b: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fsname: fsname.to_string(),
+                a: a.to_string(),
+                b: b.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "snapshotCompare"))]
+        pub snapshot_compare: SnapshotComparison,
+    }
+}
+
 pub mod create_interval {
     use crate::Query;
 
     pub static QUERY: &str = r#"
-        mutation CreateSnapshotInterval($fsname: String!, $interval: Duration!, $use_barrier: Boolean) {
-            createSnapshotInterval(fsname: $fsname, interval: $interval, useBarrier: $use_barrier)
+        mutation CreateSnapshotInterval($fsname: String!, $interval: Duration!, $use_barrier: Boolean, $automount: Boolean, $start_time: String, $time_zone: String) {
+            createSnapshotInterval(fsname: $fsname, interval: $interval, useBarrier: $use_barrier, automount: $automount, startTime: $start_time, timeZone: $time_zone)
         }
     "#;
 
@@ -252,12 +475,18 @@ pub mod create_interval {
         fsname: String,
         interval: String,
         use_barrier: Option<bool>,
+        automount: Option<bool>,
+        start_time: Option<String>,
+        time_zone: Option<String>,
     }
 
     pub fn build(
         fsname: impl ToString,
         interval: String,
         use_barrier: Option<bool>,
+        automount: Option<bool>,
+        start_time: Option<String>,
+        time_zone: Option<String>,
     ) -> Query<Vars> {
         Query {
             query: QUERY.to_string(),
@@ -265,6 +494,9 @@ pub mod create_interval {
                 fsname: fsname.to_string(),
                 interval,
                 use_barrier,
+                automount,
+                start_time,
+                time_zone,
             }),
         }
     }
@@ -316,6 +548,10 @@ pub mod list_intervals {
             use_barrier: useBarrier
             interval
             last_run: lastRun
+            automount
+            start_time: startTime
+            time_zone: timeZone
+            next_run: nextRun
           }
         }
     "#;
@@ -334,16 +570,285 @@ pub mod list_intervals {
     }
 }
 
+pub mod schedule {
+    use crate::Query;
+    use iml_wire_types::snapshot::SnapshotSchedule;
+
+    pub static QUERY: &str = r#"
+        query SnapshotSchedule($interval: Duration!, $start_time: String!, $time_zone: String, $limit: Int) {
+          snapshotSchedule(interval: $interval, startTime: $start_time, timeZone: $time_zone, limit: $limit) {
+            time_zone: timeZone
+            runs
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        interval: String,
+        start_time: String,
+        time_zone: Option<String>,
+        limit: Option<i32>,
+    }
+
+    pub fn build(
+        interval: impl ToString,
+        start_time: impl ToString,
+        time_zone: Option<String>,
+        limit: Option<i32>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                interval: interval.to_string(),
+                start_time: start_time.to_string(),
+                time_zone,
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "snapshotSchedule"))]
+        pub snapshot_schedule: SnapshotSchedule,
+    }
+}
+
+pub mod run_interval {
+    use crate::Query;
+    use iml_wire_types::snapshot::SnapshotIntervalRun;
+
+    pub static QUERY: &str = r#"
+        mutation RunSnapshotInterval($id: Int!) {
+          runSnapshotInterval(id: $id) {
+            id
+            interval_id: intervalId
+            filesystem_name: filesystemName
+            snapshot_name: snapshotName
+            due_at: dueAt
+            start_after: startAfter
+            started_at: startedAt
+            command_id: commandId
+            state
+            throttled
+            error
+            reason
+            action
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "runSnapshotInterval"))]
+        pub run_snapshot_interval: SnapshotIntervalRun,
+    }
+}
+
+pub mod list_interval_runs {
+    use crate::Query;
+    use iml_wire_types::snapshot::SnapshotIntervalRun;
+
+    pub static QUERY: &str = r#"
+        query SnapshotIntervalRuns($fsname: String, $limit: Int) {
+          snapshotIntervalRuns(fsname: $fsname, limit: $limit) {
+            id
+            interval_id: intervalId
+            filesystem_name: filesystemName
+            snapshot_name: snapshotName
+            due_at: dueAt
+            start_after: startAfter
+            started_at: startedAt
+            command_id: commandId
+            state
+            throttled
+            error
+            reason
+            action
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsname: Option<String>,
+        limit: Option<i32>,
+    }
+
+    pub fn build(fsname: Option<String>, limit: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { fsname, limit }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "snapshotIntervalRuns"))]
+        pub snapshot_interval_runs: Vec<SnapshotIntervalRun>,
+    }
+}
+
+pub mod policy_settings {
+    use crate::Query;
+    use iml_wire_types::snapshot::SnapshotPolicySettings;
+
+    pub static QUERY: &str = r#"
+        query SnapshotPolicySettings {
+          snapshotPolicySettings {
+            max_concurrent: maxConcurrent
+            jitter
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "snapshotPolicySettings"))]
+        pub snapshot_policy_settings: SnapshotPolicySettings,
+    }
+}
+
+pub mod set_policy_settings {
+    use crate::Query;
+    use iml_wire_types::snapshot::SnapshotPolicySettings;
+
+    pub static QUERY: &str = r#"
+        mutation SetSnapshotPolicySettings($max_concurrent: Int, $jitter: Duration) {
+          setSnapshotPolicySettings(maxConcurrent: $max_concurrent, jitter: $jitter) {
+            max_concurrent: maxConcurrent
+            jitter
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        max_concurrent: Option<i32>,
+        jitter: Option<String>,
+    }
+
+    pub fn build(max_concurrent: Option<i32>, jitter: Option<String>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                max_concurrent,
+                jitter,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "setSnapshotPolicySettings"))]
+        pub set_snapshot_policy_settings: SnapshotPolicySettings,
+    }
+}
+
 /// Graphql query to create a new retention. Note that
 /// Snapshots will automatically be deleted (starting with the oldest)
 /// when free space falls below the defined reserve value and its associated unit.
+pub mod export_policies {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        query ExportSnapshotPolicies($fsnames: [String!]) {
+          exportSnapshotPolicies(fsnames: $fsnames)
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsnames: Option<Vec<String>>,
+    }
+
+    pub fn build(fsnames: Option<Vec<String>>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { fsnames }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "exportSnapshotPolicies"))]
+        pub export_snapshot_policies: String,
+    }
+}
+
+pub mod import_policies {
+    use crate::Query;
+    use iml_wire_types::snapshot::SnapshotPolicyImport;
+
+    pub static QUERY: &str = r#"
+        mutation ImportSnapshotPolicies($yaml: String!, $fsname: String, $replace: Boolean) {
+          importSnapshotPolicies(yaml: $yaml, fsname: $fsname, replace: $replace) {
+            filesystems
+            intervals_added: intervalsAdded
+            intervals_removed: intervalsRemoved
+            retentions_set: retentionsSet
+            retentions_removed: retentionsRemoved
+            settings_changed: settingsChanged
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        yaml: String,
+        fsname: Option<String>,
+        replace: Option<bool>,
+    }
+
+    pub fn build(
+        yaml: impl ToString,
+        fsname: Option<String>,
+        replace: Option<bool>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                yaml: yaml.to_string(),
+                fsname,
+                replace,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "importSnapshotPolicies"))]
+        pub import_snapshot_policies: SnapshotPolicyImport,
+    }
+}
+
 pub mod create_retention {
     use crate::Query;
     use iml_wire_types::snapshot::ReserveUnit;
 
     pub static QUERY: &str = r#"
-        mutation CreateSnapshotRetention($fsname: String!, $reserve_value: Int!, $reserve_unit: ReserveUnit!, $keep_num: Int) {
-            createSnapshotRetention(fsname: $fsname, reserveValue: $reserve_value, reserveUnit: $reserve_unit, keepNum: $keep_num)
+        mutation CreateSnapshotRetention($fsname: String!, $reserve_value: Int!, $reserve_unit: ReserveUnit!, $keep_num: Int, $version: Int, $mdt_reserve_percent: Int, $change_percent: Int) {
+            createSnapshotRetention(fsname: $fsname, reserveValue: $reserve_value, reserveUnit: $reserve_unit, keepNum: $keep_num, version: $version, mdtReservePercent: $mdt_reserve_percent, changePercent: $change_percent)
         }
     "#;
 
@@ -353,13 +858,22 @@ pub mod create_retention {
         reserve_value: u32,
         reserve_unit: ReserveUnit,
         keep_num: Option<u32>,
+        version: Option<i32>,
+        mdt_reserve_percent: Option<u32>,
+        change_percent: Option<u32>,
     }
 
+    /// `version` is the expected current version of the policy,
+    /// it must be set when updating an existing policy.
+    /// `mdt_reserve_percent` and `change_percent` are the optional free space thresholds.
     pub fn build(
         fsname: impl ToString,
         reserve_value: u32,
         reserve_unit: ReserveUnit,
         keep_num: Option<u32>,
+        version: Option<i32>,
+        mdt_reserve_percent: Option<u32>,
+        change_percent: Option<u32>,
     ) -> Query<Vars> {
         Query {
             query: QUERY.to_string(),
@@ -368,6 +882,9 @@ pub mod create_retention {
                 reserve_value,
                 reserve_unit,
                 keep_num,
+                version,
+                mdt_reserve_percent,
+                change_percent,
             }),
         }
     }
@@ -423,6 +940,11 @@ pub mod list_retentions {
             reserve_unit: reserveUnit
             keep_num: keepNum
             last_run: lastRun
+            version
+            updated_at: updatedAt
+            crowded_by_manual: crowdedByManual
+            mdt_reserve_percent: mdtReservePercent
+            change_percent: changePercent
           }
         }
     "#;
@@ -139,9 +139,9 @@ pub mod fast_file_scan {
     use crate::Query;
 
     pub static QUERY: &str = r#"
-        mutation RunFastFileScan($fsname: String!, $report_duration: Duration, $purge_duration: Duration) {
+        mutation RunFastFileScan($fsname: String!, $report_duration: Duration, $purge_duration: Duration, $rule_set: String) {
           stratagem {
-            runFastFileScan(fsname: $fsname, reportDuration: $report_duration, purgeDuration: $purge_duration) {
+            runFastFileScan(fsname: $fsname, reportDuration: $report_duration, purgeDuration: $purge_duration, ruleSet: $rule_set) {
               cancelled
               complete
               created_at: createdAt
@@ -161,12 +161,14 @@ pub mod fast_file_scan {
         fsname: String,
         report_duration: Option<String>,
         purge_duration: Option<String>,
+        rule_set: Option<String>,
     }
 
     pub fn build(
         fsname: impl ToString,
         report_duration: Option<String>,
         purge_duration: Option<String>,
+        rule_set: Option<String>,
     ) -> Query<Vars> {
         Query {
             query: QUERY.to_string(),
@@ -174,6 +176,7 @@ pub mod fast_file_scan {
                 fsname: fsname.to_string(),
                 report_duration,
                 purge_duration,
+                rule_set,
             }),
         }
     }
@@ -348,3 +351,590 @@ pub mod cloudsync {
 
     pub type Resp = super::Resp<RunCloudsync>;
 }
+
+pub mod rebalance_osts {
+    use iml_wire_types::Command;
+
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RebalanceOsts($fsname: String!, $threshold: Float!, $min_size: Int) {
+          stratagem {
+            rebalanceOsts(fsname: $fsname, threshold: $threshold, minSize: $min_size) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsname: String,
+        threshold: f64,
+        min_size: Option<i32>,
+    }
+
+    pub fn build(fsname: impl ToString, threshold: f64, min_size: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fsname: fsname.to_string(),
+                threshold,
+                min_size,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RebalanceOsts {
+        #[serde(rename(deserialize = "rebalanceOsts"))]
+        pub rebalance_osts: Command,
+    }
+
+    pub type Resp = super::Resp<RebalanceOsts>;
+}
+
+pub mod scan_progress {
+    use crate::Query;
+    use iml_wire_types::stratagem;
+
+    pub static QUERY: &str = r#"
+        query ScanProgress($command_id: Int!) {
+          stratagem {
+            scanProgress(commandId: $command_id) {
+              command_id: commandId
+              devices_total: devicesTotal
+              devices_completed: devicesCompleted
+              entries_scanned: entriesScanned
+              devices {
+                fqdn
+                device
+                phase
+                entries_scanned: entriesScanned
+                started_at: startedAt
+                updated_at: updatedAt
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        command_id: i32,
+    }
+
+    pub fn build(command_id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { command_id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ScanProgress {
+        #[serde(rename(deserialize = "scanProgress"))]
+        pub scan_progress: stratagem::ScanProgress,
+    }
+
+    pub type Resp = super::Resp<ScanProgress>;
+}
+
+pub mod project_usage_scan {
+    use iml_wire_types::Command;
+
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RunProjectUsageScan($fsname: String!) {
+          stratagem {
+            runProjectUsageScan(fsname: $fsname) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsname: String,
+    }
+
+    pub fn build(fsname: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fsname: fsname.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RunProjectUsageScan {
+        #[serde(rename(deserialize = "runProjectUsageScan"))]
+        pub run_project_usage_scan: Command,
+    }
+
+    pub type Resp = super::Resp<RunProjectUsageScan>;
+}
+
+pub mod project_usage {
+    use crate::Query;
+    use iml_wire_types::stratagem;
+
+    pub static QUERY: &str = r#"
+        query ProjectUsage($fs_name: String!, $limit: Int) {
+          stratagem {
+            projectUsage(fsName: $fs_name, limit: $limit) {
+              scanned_at: scannedAt
+              projects {
+                project
+                files
+                bytes
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fs_name: String,
+        limit: Option<i32>,
+    }
+
+    pub fn build(fs_name: impl ToString, limit: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ProjectUsage {
+        #[serde(rename(deserialize = "projectUsage"))]
+        pub project_usage: Vec<stratagem::ProjectUsageScan>,
+    }
+
+    pub type Resp = super::Resp<ProjectUsage>;
+}
+
+pub mod heatmap_scan {
+    use iml_wire_types::Command;
+
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RunHeatmapScan($fsname: String!, $max_depth: Int) {
+          stratagem {
+            runHeatmapScan(fsname: $fsname, maxDepth: $max_depth) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fsname: String,
+        max_depth: Option<i32>,
+    }
+
+    pub fn build(fsname: impl ToString, max_depth: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fsname: fsname.to_string(),
+                max_depth,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RunHeatmapScan {
+        #[serde(rename(deserialize = "runHeatmapScan"))]
+        pub run_heatmap_scan: Command,
+    }
+
+    pub type Resp = super::Resp<RunHeatmapScan>;
+}
+
+pub mod fs_heatmap {
+    use crate::Query;
+    use iml_wire_types::stratagem;
+
+    pub static QUERY: &str = r#"
+        query FsHeatmap($fs_name: String!, $depth: Int) {
+          stratagem {
+            fsHeatmap(fsName: $fs_name, depth: $depth) {
+              scanned_at: scannedAt
+              directories {
+                path
+                depth
+                files
+                bytes
+                recent_files: recentFiles
+                bytes_week: bytesWeek
+                bytes_month: bytesMonth
+                bytes_quarter: bytesQuarter
+                bytes_year: bytesYear
+                bytes_older: bytesOlder
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fs_name: String,
+        depth: Option<i32>,
+    }
+
+    pub fn build(fs_name: impl ToString, depth: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                depth,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct FsHeatmap {
+        #[serde(rename(deserialize = "fsHeatmap"))]
+        pub fs_heatmap: Option<stratagem::FsHeatmap>,
+    }
+
+    pub type Resp = super::Resp<FsHeatmap>;
+}
+
+pub mod purge_policy {
+    use crate::Query;
+    use iml_wire_types::stratagem;
+
+    pub static QUERY: &str = r#"
+        query PurgePolicy($fs_name: String!) {
+          stratagem {
+            purgePolicy(fsName: $fs_name) {
+              filesystem_name: filesystemName
+              enabled
+              interval
+              purge_after: purgeAfter
+              max_files: maxFiles
+              max_bytes: maxBytes
+              protected_paths: protectedPaths
+              require_approval: requireApproval
+              last_run: lastRun
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct PurgePolicy {
+        #[serde(rename(deserialize = "purgePolicy"))]
+        pub purge_policy: Option<stratagem::PurgePolicy>,
+    }
+
+    pub type Resp = super::Resp<PurgePolicy>;
+}
+
+pub mod set_purge_policy {
+    use crate::Query;
+    use iml_wire_types::stratagem;
+
+    pub static QUERY: &str = r#"
+        mutation SetPurgePolicy($fs_name: String!, $interval: Duration!, $purge_after: Duration!, $max_files: Float!, $max_bytes: Float!, $protected_paths: [String!], $require_approval: Boolean, $enabled: Boolean) {
+          stratagem {
+            setPurgePolicy(fsName: $fs_name, interval: $interval, purgeAfter: $purge_after, maxFiles: $max_files, maxBytes: $max_bytes, protectedPaths: $protected_paths, requireApproval: $require_approval, enabled: $enabled) {
+              filesystem_name: filesystemName
+              enabled
+              interval
+              purge_after: purgeAfter
+              max_files: maxFiles
+              max_bytes: maxBytes
+              protected_paths: protectedPaths
+              require_approval: requireApproval
+              last_run: lastRun
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fs_name: String,
+        interval: String,
+        purge_after: String,
+        max_files: f64,
+        max_bytes: f64,
+        protected_paths: Option<Vec<String>>,
+        require_approval: Option<bool>,
+        enabled: Option<bool>,
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn build(
+        fs_name: impl ToString,
+        interval: impl ToString,
+        purge_after: impl ToString,
+        max_files: f64,
+        max_bytes: f64,
+        protected_paths: Option<Vec<String>>,
+        require_approval: Option<bool>,
+        enabled: Option<bool>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                interval: interval.to_string(),
+                purge_after: purge_after.to_string(),
+                max_files,
+                max_bytes,
+                protected_paths,
+                require_approval,
+                enabled,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct SetPurgePolicy {
+        #[serde(rename(deserialize = "setPurgePolicy"))]
+        pub set_purge_policy: stratagem::PurgePolicy,
+    }
+
+    pub type Resp = super::Resp<SetPurgePolicy>;
+}
+
+pub mod remove_purge_policy {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemovePurgePolicy($fs_name: String!) {
+          stratagem {
+            removePurgePolicy(fsName: $fs_name)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemovePurgePolicy {
+        #[serde(rename(deserialize = "removePurgePolicy"))]
+        pub remove_purge_policy: bool,
+    }
+
+    pub type Resp = super::Resp<RemovePurgePolicy>;
+}
+
+pub mod purge_runs {
+    use crate::Query;
+    use iml_wire_types::stratagem;
+
+    pub static QUERY: &str = r#"
+        query PurgeRuns($fs_name: String!, $limit: Int) {
+          stratagem {
+            purgeRuns(fsName: $fs_name, limit: $limit) {
+              id
+              filesystem_name: filesystemName
+              state
+              scan_command_id: scanCommandId
+              purge_task_id: purgeTaskId
+              candidate_files: candidateFiles
+              candidate_bytes: candidateBytes
+              protected_files: protectedFiles
+              files
+              bytes
+              capped
+              started_at: startedAt
+              scanned_at: scannedAt
+              decided_by: decidedBy
+              decided_at: decidedAt
+              error
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fs_name: String,
+        limit: Option<i32>,
+    }
+
+    pub fn build(fs_name: impl ToString, limit: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct PurgeRuns {
+        #[serde(rename(deserialize = "purgeRuns"))]
+        pub purge_runs: Vec<stratagem::PurgeRun>,
+    }
+
+    pub type Resp = super::Resp<PurgeRuns>;
+}
+
+pub mod approve_purge_run {
+    use crate::Query;
+    use iml_wire_types::stratagem;
+
+    pub static QUERY: &str = r#"
+        mutation ApprovePurgeRun($id: Int!) {
+          stratagem {
+            approvePurgeRun(id: $id) {
+              id
+              filesystem_name: filesystemName
+              state
+              scan_command_id: scanCommandId
+              purge_task_id: purgeTaskId
+              candidate_files: candidateFiles
+              candidate_bytes: candidateBytes
+              protected_files: protectedFiles
+              files
+              bytes
+              capped
+              started_at: startedAt
+              scanned_at: scannedAt
+              decided_by: decidedBy
+              decided_at: decidedAt
+              error
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ApprovePurgeRun {
+        #[serde(rename(deserialize = "approvePurgeRun"))]
+        pub approve_purge_run: stratagem::PurgeRun,
+    }
+
+    pub type Resp = super::Resp<ApprovePurgeRun>;
+}
+
+pub mod reject_purge_run {
+    use crate::Query;
+    use iml_wire_types::stratagem;
+
+    pub static QUERY: &str = r#"
+        mutation RejectPurgeRun($id: Int!) {
+          stratagem {
+            rejectPurgeRun(id: $id) {
+              id
+              filesystem_name: filesystemName
+              state
+              scan_command_id: scanCommandId
+              purge_task_id: purgeTaskId
+              candidate_files: candidateFiles
+              candidate_bytes: candidateBytes
+              protected_files: protectedFiles
+              files
+              bytes
+              capped
+              started_at: startedAt
+              scanned_at: scannedAt
+              decided_by: decidedBy
+              decided_at: decidedAt
+              error
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RejectPurgeRun {
+        #[serde(rename(deserialize = "rejectPurgeRun"))]
+        pub reject_purge_run: stratagem::PurgeRun,
+    }
+
+    pub type Resp = super::Resp<RejectPurgeRun>;
+}
@@ -4,21 +4,30 @@
 
 pub mod list {
     use crate::Query;
-    use iml_wire_types::{db::TargetRecord, SortDir};
+    use iml_wire_types::{graphql::TargetList, SortDir};
 
     pub static QUERY: &str = r#"
-            query Targets($limit: Int, $offset: Int, $dir: SortDir, $fsname: String, $exclude_unmounted: Boolean) {
-              targets(limit: $limit, offset: $offset, dir: $dir, fsName: $fsname, excludeUnmounted: $exclude_unmounted) {
-                id
-                state
-                name
-                dev_path: devPath
-                active_host_id: activeHostId
-                host_ids: hostIds
-                filesystems
-                uuid
-                mount_path: mountPath
-                fs_type: fsType
+            query Targets($limit: Int, $offset: Int, $dir: SortDir, $fsname: String, $exclude_unmounted: Boolean, $dev_path: String, $serial: String, $fqdn: String, $host_tags: [String!]) {
+              targets(limit: $limit, offset: $offset, dir: $dir, fsName: $fsname, excludeUnmounted: $exclude_unmounted, devPath: $dev_path, serial: $serial, fqdn: $fqdn, hostTags: $host_tags) {
+                data {
+                  id
+                  state
+                  name
+                  dev_path: devPath
+                  active_host_id: activeHostId
+                  host_ids: hostIds
+                  filesystems
+                  uuid
+                  mount_path: mountPath
+                  fs_type: fsType
+                }
+                meta {
+                  limit
+                  offset
+                  total_count: totalCount
+                  is_estimate: isEstimate
+                }
+                degraded_multipath: degradedMultipath
               }
             }
         "#;
@@ -30,6 +39,20 @@ pub mod list {
         dir: Option<SortDir>,
         fsname: Option<String>,
         exclude_unmounted: Option<bool>,
+        dev_path: Option<String>,
+        serial: Option<String>,
+        fqdn: Option<String>,
+        host_tags: Option<Vec<String>>,
+    }
+
+    /// Optional filters to narrow down the list of targets
+    #[derive(Debug, Default, Clone)]
+    pub struct Filters {
+        pub dev_path: Option<String>,
+        pub serial: Option<String>,
+        pub fqdn: Option<String>,
+        /// `key` or `key=value` tags a host the target can run on must match
+        pub host_tags: Option<Vec<String>>,
     }
 
     pub fn build(
@@ -38,6 +61,7 @@ pub mod list {
         dir: Option<SortDir>,
         fsname: Option<impl ToString>,
         exclude_unmounted: Option<bool>,
+        filters: Filters,
     ) -> Query<Vars> {
         Query {
             query: QUERY.to_string(),
@@ -47,12 +71,245 @@ pub mod list {
                 dir,
                 fsname: fsname.map(|s| s.to_string()),
                 exclude_unmounted,
+                dev_path: filters.dev_path,
+                serial: filters.serial,
+                fqdn: filters.fqdn,
+                host_tags: filters.host_tags,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        pub targets: TargetList,
+    }
+}
+
+pub mod params {
+    use crate::Query;
+    use iml_wire_types::graphql::TargetParam;
+
+    pub static QUERY: &str = r#"
+            query TargetParams($uuid: String!, $keys: [String!]!) {
+              targetParams(uuid: $uuid, keys: $keys) {
+                key
+                value
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        uuid: String,
+        keys: Vec<String>,
+    }
+
+    pub fn build(uuid: impl ToString, keys: &[&str]) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                uuid: uuid.to_string(),
+                keys: keys.iter().map(|x| x.to_string()).collect(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "targetParams"))]
+        pub target_params: Vec<TargetParam>,
+    }
+}
+
+pub mod multipath {
+    use crate::Query;
+    use iml_wire_types::multipath::TargetMultipathStatus;
+
+    pub static QUERY: &str = r#"
+            query TargetMultipath($uuid: String!, $limit: Int) {
+              targetMultipath(uuid: $uuid, limit: $limit) {
+                hosts {
+                  host_id: hostId
+                  fqdn
+                  map_name: mapName
+                  path_count: pathCount
+                  failed_paths: failedPaths
+                  checked_at: checkedAt
+                }
+                events {
+                  host_id: hostId
+                  fqdn
+                  path
+                  failed
+                  at
+                }
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        uuid: String,
+        limit: Option<i32>,
+    }
+
+    pub fn build(uuid: impl ToString, limit: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                uuid: uuid.to_string(),
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "targetMultipath"))]
+        pub target_multipath: TargetMultipathStatus,
+    }
+}
+
+pub mod integrity_report {
+    use crate::Query;
+    use iml_wire_types::integrity::IntegrityReport;
+
+    pub static QUERY: &str = r#"
+            query IntegrityReport {
+              integrityReport {
+                target_count: targetCount
+                findings {
+                  kind
+                  uuid
+                  target_names: targetNames
+                  message
+                  dev_path: devPath
+                  previous_dev_path: previousDevPath
+                  changed_at: changedAt
+                  missing_host_ids: missingHostIds
+                }
+              }
+            }
+        "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "integrityReport"))]
+        pub integrity_report: IntegrityReport,
+    }
+}
+
+pub mod acknowledge_dev_path {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+            mutation AcknowledgeTargetDevPath($uuid: String!) {
+              acknowledgeTargetDevPath(uuid: $uuid)
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        uuid: String,
+    }
+
+    pub fn build(uuid: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                uuid: uuid.to_string(),
             }),
         }
     }
 
     #[derive(Debug, Clone, serde::Deserialize)]
     pub struct Resp {
-        pub targets: Vec<TargetRecord>,
+        #[serde(rename(deserialize = "acknowledgeTargetDevPath"))]
+        pub acknowledge_target_dev_path: bool,
+    }
+}
+
+pub mod state_history {
+    use crate::Query;
+    use iml_wire_types::graphql::TargetStateChange;
+
+    pub static QUERY: &str = r#"
+            query TargetStateHistory($uuid: String!, $from: DateTimeUtc, $to: DateTimeUtc) {
+              targetStateHistory(uuid: $uuid, from: $from, to: $to) {
+                id
+                target_name: targetName
+                event
+                state
+                active_host: activeHost
+                previous_host: previousHost
+                reason
+                changed_at: changedAt
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        uuid: String,
+        from: Option<String>,
+        to: Option<String>,
+    }
+
+    /// `from` and `to` are RFC 3339 timestamps
+    pub fn build(uuid: impl ToString, from: Option<String>, to: Option<String>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                uuid: uuid.to_string(),
+                from,
+                to,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "targetStateHistory"))]
+        pub target_state_history: Vec<TargetStateChange>,
+    }
+}
+
+pub mod mini_stats {
+    use crate::Query;
+    use iml_wire_types::graphql::TargetMiniStats;
+
+    pub static QUERY: &str = r#"
+        query MiniStats($target_uuids: [String!]!) {
+          miniStats(targetUuids: $target_uuids) {
+            target_uuid: targetUuid
+            read
+            write
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        target_uuids: Vec<String>,
+    }
+
+    pub fn build(target_uuids: Vec<String>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { target_uuids }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "miniStats"))]
+        pub mini_stats: Vec<TargetMiniStats>,
     }
 }
@@ -9,7 +9,7 @@ pub struct Resp<T> {
 
 pub mod create {
     use crate::Query;
-    use iml_wire_types::{task::TaskArgs, Command};
+    use iml_wire_types::task::{CreateTaskResult, TaskArgs};
 
     pub static QUERY: &str = r#"
             mutation CreateTask($fsname: String!, $task: TaskArgs!) {
@@ -49,15 +49,9 @@ pub mod create {
         }
     }
 
-    #[derive(Debug, Clone, serde::Deserialize)]
-    pub struct TaskCommand {
-        task_id: i32,
-        command: Command,
-    }
-
     #[derive(Debug, Clone, serde::Deserialize)]
     pub struct Create {
-        pub create: TaskCommand,
+        pub create: CreateTaskResult,
     }
 
     pub type Resp = super::Resp<Create>;
@@ -151,3 +145,40 @@ pub mod list {
 
     pub type Resp = super::Resp<TaskOut>;
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_selects_all;
+
+    #[test]
+    fn test_create() {
+        let resp = serde_json::json!({
+            "task": {
+                "create": {
+                    "task_id": 7,
+                    "command": {
+                        "cancelled": false,
+                        "complete": false,
+                        "created_at": "2021-02-16T09:00:00.000000",
+                        "errored": false,
+                        "id": 12,
+                        "jobs": ["/api/job/40/"],
+                        "logs": "",
+                        "message": "Create task",
+                        "resource_uri": "/api/command/12/"
+                    }
+                }
+            }
+        });
+
+        let x: super::create::Resp = serde_json::from_value(resp).unwrap();
+
+        assert_eq!(x.task.create.task_id, 7);
+        assert_eq!(x.task.create.command.id, 12);
+
+        assert_selects_all(
+            super::create::QUERY,
+            &serde_json::to_value(x.task.create).unwrap(),
+        );
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+pub mod get {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+            query UserPreference($key: String!) {
+              userPreferences {
+                get(key: $key)
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        key: String,
+    }
+
+    pub fn build(key: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                key: key.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Get {
+        pub get: Option<String>,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "userPreferences"))]
+        pub user_preferences: Get,
+    }
+}
+
+pub mod set {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+            mutation SetUserPreference($key: String!, $value: String!) {
+              userPreferences {
+                set(key: $key, value: $value)
+              }
+            }
+        "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        key: String,
+        value: String,
+    }
+
+    pub fn build(key: impl ToString, value: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Set {
+        pub set: bool,
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resp {
+        #[serde(rename(deserialize = "userPreferences"))]
+        pub user_preferences: Set,
+    }
+}
@@ -2,7 +2,7 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::{sleep_with_handle, FailReasonExt, GMsg, RequestExt, Route, SessionExt};
+use crate::{components::session_timeout, sleep_with_handle, FailReasonExt, GMsg, RequestExt, Route, SessionExt};
 use futures::channel::oneshot;
 use iml_wire_types::{EndpointName, Session};
 use regex::Regex;
@@ -16,12 +16,26 @@ pub struct Model {
     session: Option<Session>,
     request_controller: Option<fetch::RequestController>,
     cancel: Option<oneshot::Sender<()>>,
+    /// Did the user interact with the page since the last session poll?
+    active: bool,
+    /// When the session expires due to inactivity, in ms since the epoch
+    idle_expires_at: Option<f64>,
+    tick_cancel: Option<oneshot::Sender<()>>,
 }
 
 impl Model {
     pub(crate) fn get_session(&self) -> Option<&Session> {
         self.session.as_ref()
     }
+    /// Seconds until the session expires due to inactivity
+    fn idle_remaining(&self) -> Option<i64> {
+        self.idle_expires_at
+            .map(|x| ((x - js_sys::Date::now()) / 1000.0).ceil() as i64)
+    }
+    /// Seconds until the session expires, if it is time to warn the user about it.
+    pub(crate) fn idle_warning(&self) -> Option<i64> {
+        self.idle_remaining().filter(|x| *x <= session_timeout::WARN_SECS)
+    }
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -33,6 +47,9 @@ pub enum Msg {
     LoggedIn,
     Loop,
     Noop,
+    Activity,
+    KeepAlive,
+    Tick,
 }
 
 pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
@@ -40,7 +57,17 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
         Msg::Fetch => {
             model.cancel = None;
 
-            let request = fetch_session().controller(|controller| model.request_controller = Some(controller));
+            // Polling does not count as activity server side,
+            // so tell the server the user is still around.
+            let request = if model.active {
+                model.active = false;
+
+                fetch_session().method(fetch::Method::Put)
+            } else {
+                fetch_session()
+            };
+
+            let request = request.controller(|controller| model.request_controller = Some(controller));
 
             orders.skip().perform_cmd(request.fetch_json(Msg::Fetched));
         }
@@ -51,8 +78,17 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                     orders.skip().send_msg(Msg::Loop);
                 }
                 Ok(resp) => {
+                    model.idle_expires_at = resp
+                        .data
+                        .idle_expires_in
+                        .map(|x| js_sys::Date::now() + (x * 1000) as f64);
+
                     model.session = Some(resp.data);
 
+                    if model.tick_cancel.is_none() {
+                        orders.send_msg(Msg::Tick);
+                    }
+
                     if model.session.as_ref().unwrap().needs_login() {
                         orders.send_g_msg(GMsg::RouteChange(Route::Login.into()));
                     } else {
@@ -96,6 +132,36 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
 
             orders.perform_cmd(fut);
         }
+        Msg::Activity => {
+            model.active = true;
+
+            orders.skip();
+        }
+        Msg::KeepAlive => {
+            model.active = true;
+
+            orders.send_msg(Msg::Fetch);
+        }
+        Msg::Tick => {
+            model.tick_cancel = None;
+
+            match model.idle_remaining() {
+                Some(x) if x <= 0 => {
+                    orders.send_msg(Msg::Fetch);
+                }
+                // Count down every second while the warning may be showing
+                Some(x) if x <= session_timeout::WARN_SECS + 10 => {
+                    let (cancel, fut) = sleep_with_handle(Duration::from_secs(1), Msg::Tick, Msg::Noop);
+
+                    model.tick_cancel = Some(cancel);
+
+                    orders.perform_cmd(fut);
+                }
+                _ => {
+                    orders.skip();
+                }
+            }
+        }
         Msg::Noop => {}
     };
 }
@@ -11,7 +11,10 @@ use crate::{
     generated::css_classes::C,
     key_codes, GMsg, RequestExt,
 };
-use iml_wire_types::{warp_drive::ErasedRecord, AvailableAction, CmdWrapper, Command, EndpointName};
+use iml_graphql_queries::{filesystem as fs_queries, Response};
+use iml_wire_types::{
+    graphql::FilesystemTransition, warp_drive::ErasedRecord, AvailableAction, CmdWrapper, Command, EndpointName,
+};
 use seed::{prelude::*, *};
 use std::sync::Arc;
 
@@ -38,6 +41,8 @@ pub enum Msg {
     JobSent(Box<fetch::ResponseDataResult<Command>>),
     SendStateChange(Arc<AvailableAction>, Arc<dyn ErasedRecord>),
     StateChangeSent(Box<fetch::ResponseDataResult<CmdWrapper>>),
+    SendFsTransition(Arc<AvailableAction>, Arc<dyn ErasedRecord>),
+    FsTransitionSent(Box<Result<FilesystemTransition, String>>),
     Modal(modal::Msg),
     Noop,
 }
@@ -47,6 +52,7 @@ pub enum Action {
     Loading,
     Job(String, Arc<AvailableAction>, Arc<dyn ErasedRecord>),
     StateChange(DryRun, Arc<AvailableAction>, Arc<dyn ErasedRecord>),
+    FsTransition(Arc<AvailableAction>, Arc<dyn ErasedRecord>),
 }
 
 pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
@@ -98,6 +104,40 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                 orders.skip();
             }
         },
+        Msg::SendFsTransition(action, erased_record) => {
+            let fs_name = erased_record.label().to_string();
+
+            if action.state.as_deref() == Some("stopped") {
+                let req = fetch::Request::graphql_query(&fs_queries::stop::build(fs_name));
+
+                orders.perform_cmd(req.fetch_json_data(
+                    |x: fetch::ResponseDataResult<Response<fs_queries::stop::Resp>>| {
+                        Msg::FsTransitionSent(Box::new(transition_result(x, |x| x.filesystem.stop)))
+                    },
+                ));
+            } else {
+                let req = fetch::Request::graphql_query(&fs_queries::start::build(fs_name));
+
+                orders.perform_cmd(req.fetch_json_data(
+                    |x: fetch::ResponseDataResult<Response<fs_queries::start::Resp>>| {
+                        Msg::FsTransitionSent(Box::new(transition_result(x, |x| x.filesystem.start)))
+                    },
+                ));
+            }
+
+            orders.send_msg(Msg::Modal(modal::Msg::Close));
+        }
+        Msg::FsTransitionSent(x) => match *x {
+            Ok(x) => {
+                let x = command_modal::Input::Commands(vec![Arc::new(x.command)]);
+
+                orders.send_g_msg(GMsg::OpenCommandModal(x));
+            }
+            Err(err) => {
+                error!("An error has occurred in Msg::FsTransitionSent: {}", err);
+                orders.skip();
+            }
+        },
         Msg::Modal(msg) => {
             modal::update(msg, &mut model.modal, &mut orders.proxy(Msg::Modal));
         }
@@ -114,6 +154,9 @@ pub(crate) fn view(action: &Action) -> Node<Msg> {
         Action::StateChange(_, action, erased_record) => {
             Msg::SendStateChange(Arc::clone(action), Arc::clone(erased_record))
         }
+        Action::FsTransition(action, erased_record) => {
+            Msg::SendFsTransition(Arc::clone(action), Arc::clone(erased_record))
+        }
     };
 
     let confirm_msg2 = confirm_msg.clone();
@@ -156,6 +199,25 @@ pub(crate) fn view(action: &Action) -> Node<Msg> {
                         .merge_attrs(class![C.pt_8]),
                     ]
                 }
+                Action::FsTransition(action, erased_record) => {
+                    let title = format!("{}: {}", action.verb, erased_record.label());
+                    vec![
+                        modal::title_view(Msg::Modal, span![title]),
+                        div![
+                            El::from_html(&action.long_description),
+                            div![
+                                class![C.pt_4, C.text_gray_600],
+                                "Targets are started MGS first and stopped OSTs first. \
+                                 Targets shared with a running filesystem are left as they are."
+                            ]
+                        ],
+                        modal::footer_view(vec![
+                            confirm_button().with_listener(simple_ev(Ev::Click, confirm_msg)),
+                            cancel_button(),
+                        ])
+                        .merge_attrs(class![C.pt_8]),
+                    ]
+                }
             },
         ),
     )
@@ -167,6 +229,17 @@ pub(crate) fn view(action: &Action) -> Node<Msg> {
     .merge_attrs(class![C.text_black])
 }
 
+fn transition_result<T>(
+    x: fetch::ResponseDataResult<Response<T>>,
+    f: impl FnOnce(T) -> FilesystemTransition,
+) -> Result<FilesystemTransition, String> {
+    match x {
+        Ok(Response::Data(x)) => Ok(f(x.data)),
+        Ok(Response::Errors(e)) => Err(e.to_string()),
+        Err(e) => Err(format!("{:?}", e)),
+    }
+}
+
 fn state_change_body_view<T>(dry_run: &DryRun) -> Node<T> {
     let job = &dry_run.transition_job;
     let dep_jobs = &dry_run.dependency_jobs;
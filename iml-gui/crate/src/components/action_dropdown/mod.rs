@@ -13,7 +13,7 @@ use crate::{
 use futures::channel::oneshot;
 use iml_wire_types::{
     warp_drive::{ArcCache, ErasedRecord, Locks},
-    ApiList, AvailableAction, CompositeId, GroupType, LockChange, Session,
+    ApiList, AvailableAction, CompositeId, EndpointName, Filesystem, GroupType, LockChange, Session,
 };
 use seed::{prelude::*, *};
 use serde_json::json;
@@ -148,6 +148,13 @@ pub fn state_change(
         .send_json(&json!({ "state": action.state, "dry_run": dry_run }))
 }
 
+/// Filesystem start and stop go through the `filesystem` mutations,
+/// which order the target jobs.
+fn is_fs_transition(action: &AvailableAction, erased_record: &Arc<dyn ErasedRecord>) -> bool {
+    erased_record.endpoint_name() == Filesystem::endpoint_name()
+        && matches!(action.state.as_deref(), Some("available") | Some("stopped"))
+}
+
 pub fn update(msg: IdMsg, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<IdMsg, GMsg>) {
     let IdMsg(id, msg) = msg;
 
@@ -208,6 +215,8 @@ pub fn update(msg: IdMsg, cache: &ArcCache, model: &mut Model, orders: &mut impl
                     model.state =
                         State::Confirming(confirm_action_modal::Action::Job(x.long_description.clone(), x, y));
                 }
+            } else if is_fs_transition(&x, &y) {
+                model.state = State::Confirming(confirm_action_modal::Action::FsTransition(x, y));
             } else {
                 let req = state_change(&x, &y, true);
                 orders.perform_cmd(req.fetch_json_data(move |z| IdMsg(id, Msg::DryRunSent(Box::new(z), x, y))));
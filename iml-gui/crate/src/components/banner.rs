@@ -0,0 +1,112 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{components::font_awesome, generated::css_classes::C, sleep_with_handle, GMsg, RequestExt};
+use chrono::Utc;
+use futures::channel::oneshot;
+use iml_graphql_queries::{banner, Response};
+use iml_wire_types::banner::{Banner, BannerSeverity};
+use seed::{prelude::*, *};
+use std::time::Duration;
+
+/// How often the banners are polled.
+/// Changes are fetched as they are made, this hides expired banners as time passes.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+pub struct Model {
+    started: bool,
+    banners: Vec<Banner>,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Start,
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<banner::active::Resp>>),
+    /// A table changed, banners are fetched again right away when it is `banner`
+    Invalidate(String),
+    Noop,
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Start => {
+            if !model.started {
+                model.started = true;
+
+                orders.send_msg(Msg::Fetch);
+            }
+        }
+        Msg::Fetch => {
+            model.cancel = None;
+
+            let query = banner::active::build();
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.banners = x.data.banner.active;
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving banners", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving banners", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(POLL_INTERVAL, Msg::Fetch, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::Invalidate(table) => {
+            if model.started && table == "banner" {
+                orders.send_msg(Msg::Fetch);
+            } else {
+                orders.skip();
+            }
+        }
+        Msg::Noop => {}
+    }
+}
+
+/// A banner for each one that did not expire, newest first.
+pub fn view(model: &Model) -> Node<Msg> {
+    let now = Utc::now();
+
+    let xs: Vec<_> = model.banners.iter().filter(|x| x.is_active(now)).collect();
+
+    if xs.is_empty() {
+        return empty![];
+    }
+
+    div![xs.into_iter().map(banner_view)]
+}
+
+fn banner_view<T>(x: &Banner) -> Node<T> {
+    let (colors, icon) = match x.severity {
+        BannerSeverity::Info => (class![C.bg_blue_100, C.border_blue_400, C.text_blue_800], "info-circle"),
+        BannerSeverity::Warning => (
+            class![C.bg_yellow_100, C.border_yellow_400, C.text_yellow_800],
+            "exclamation-triangle",
+        ),
+        BannerSeverity::Error => (
+            class![C.bg_red_100, C.border_red_400, C.text_red_800],
+            "exclamation-circle",
+        ),
+    };
+
+    div![
+        colors,
+        class![C.flex, C.items_start, C.border_l_4, C.px_4, C.py_2, C.text_sm],
+        font_awesome(class![C.w_4, C.h_4, C.flex_none, C.mt_1, C.mr_2], icon),
+        // Raw HTML is shown as text, only the markdown is rendered
+        div![El::from_markdown(&x.text.replace('<', "&lt;"))],
+    ]
+}
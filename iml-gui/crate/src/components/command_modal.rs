@@ -9,8 +9,13 @@ use crate::{
     generated::css_classes::C,
     key_codes, sleep_with_handle, GMsg,
 };
+use chrono::Local;
 use futures::channel::oneshot;
-use iml_wire_types::{ApiList, AvailableTransition, Command, EndpointName, Job, Step};
+use iml_graphql_queries::{command as command_queries, Response};
+use iml_wire_types::{
+    graphql::{CommandBlocker, CommandStepLogs},
+    ApiList, AvailableTransition, Command, EndpointName, Job, Step,
+};
 use regex::{Captures, Regex};
 use seed::{prelude::*, *};
 use serde::de::DeserializeOwned;
@@ -120,6 +125,10 @@ pub struct Model {
 
     pub select: Select,
     pub cancelling_jobs: HashSet<i32>,
+    /// Jobs of other commands that jobs of a running command wait for
+    pub blockers: HashMap<CmdId, Vec<CommandBlocker>>,
+    /// The steps of a failed command, with the log messages of their hosts
+    pub related_logs: HashMap<CmdId, Vec<CommandStepLogs>>,
     pub modal: modal::Model,
 }
 
@@ -131,6 +140,14 @@ pub enum Msg {
     FetchedCommands(Box<fetch::ResponseDataResult<ApiList<Command>>>),
     FetchedJobs(Box<fetch::ResponseDataResult<ApiList<Job0>>>),
     FetchedSteps(Box<fetch::ResponseDataResult<ApiList<Step>>>),
+    FetchedBlockers(
+        CmdId,
+        Box<fetch::ResponseDataResult<Response<command_queries::blockers::Resp>>>,
+    ),
+    FetchedRelatedLogs(
+        CmdId,
+        Box<fetch::ResponseDataResult<Response<command_queries::related_logs::Resp>>>,
+    ),
     Click(TypedId),
     CancelJob(i32),
     CancelledJob(i32, Box<fetch::ResponseDataResult<Job0>>),
@@ -160,6 +177,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                     if !is_all_commands_finished(&model.commands) {
                         orders.send_msg(Msg::FetchTree);
                     }
+                    fetch_related_logs(model, orders);
                 }
                 Input::Ids(ids) => {
                     // we have ids only, so we need to populate the vector first
@@ -177,6 +195,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
             match *commands_data_result {
                 Ok(api_list) => {
                     model.update_commands(api_list.objects.into_iter().map(Arc::new).collect());
+                    fetch_related_logs(model, orders);
                 }
                 Err(e) => {
                     error!(format!("Failed to fetch commands {:#?}", e));
@@ -207,6 +226,41 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                 orders.skip();
             }
         },
+        Msg::FetchedBlockers(cmd_id, x) => match *x {
+            Ok(Response::Data(x)) => {
+                model.blockers.insert(cmd_id, x.data.command_blockers);
+            }
+            Ok(Response::Errors(e)) => {
+                error!(format!("Failed to fetch the blockers of command {}: {}", cmd_id.0, e));
+                orders.skip();
+            }
+            Err(e) => {
+                error!(format!(
+                    "Failed to fetch the blockers of command {}: {:#?}",
+                    cmd_id.0, e
+                ));
+                orders.skip();
+            }
+        },
+        Msg::FetchedRelatedLogs(cmd_id, x) => match *x {
+            Ok(Response::Data(x)) => {
+                model.related_logs.insert(cmd_id, x.data.command_related_logs);
+            }
+            Ok(Response::Errors(e)) => {
+                error!(format!(
+                    "Failed to fetch the related logs of command {}: {}",
+                    cmd_id.0, e
+                ));
+                orders.skip();
+            }
+            Err(e) => {
+                error!(format!(
+                    "Failed to fetch the related logs of command {}: {:#?}",
+                    cmd_id.0, e
+                ));
+                orders.skip();
+            }
+        },
         Msg::Click(the_id) => {
             let do_fetch = model.select.perform_click(the_id);
             if do_fetch {
@@ -266,6 +320,35 @@ fn schedule_fetch_tree(model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
     if !load_step_ids.is_empty() {
         orders.perform_cmd(fetch_the_batch(load_step_ids, |x| Msg::FetchedSteps(Box::new(x))));
     }
+
+    // a running command may be stuck behind the jobs of another one
+    for c in cmd_ids
+        .iter()
+        .filter(|c| model.commands.get(*c).filter(|x| !x.complete).is_some())
+    {
+        let cmd_id = CmdId(*c);
+        let query = command_queries::blockers::build(*c);
+        let req = fetch::Request::graphql_query(&query);
+
+        orders.perform_cmd(req.fetch_json_data(move |x| Msg::FetchedBlockers(cmd_id, Box::new(x))));
+    }
+
+    fetch_related_logs(model, orders);
+}
+
+/// Failed commands do not change anymore, so their logs are fetched once
+fn fetch_related_logs(model: &Model, orders: &mut impl Orders<Msg, GMsg>) {
+    let (cmd_ids, _, _) = model.select.split();
+
+    for c in cmd_ids.into_iter().filter(|c| {
+        model.commands.get(c).filter(|x| x.errored).is_some() && !model.related_logs.contains_key(&CmdId(*c))
+    }) {
+        let cmd_id = CmdId(c);
+        let query = command_queries::related_logs::build(c, None, None);
+        let req = fetch::Request::graphql_query(&query);
+
+        orders.perform_cmd(req.fetch_json_data(move |x| Msg::FetchedRelatedLogs(cmd_id, Box::new(x))));
+    }
 }
 
 pub(crate) fn view(model: &Model) -> Node<Msg> {
@@ -357,6 +440,22 @@ fn command_item_view(model: &Model, x: &RichCommand) -> Node<Msg> {
                 class![C.pl_8, C.hidden => !is_open],
                 li![class![C.pb_2], "Started at: ", x.created_at],
                 li![class![C.pb_2], "Status: ", status_text(x)],
+                if x.complete {
+                    empty![]
+                } else {
+                    blocked_by_view(
+                        model
+                            .blockers
+                            .get(&CmdId(x.id))
+                            .map(|xs| xs.as_slice())
+                            .unwrap_or_default(),
+                    )
+                },
+                if x.errored {
+                    related_logs_view(model.related_logs.get(&CmdId(x.id)))
+                } else {
+                    empty![]
+                },
                 li![job_tree],
             ]
         ]
@@ -539,6 +638,141 @@ fn step_item_view(step: &RichStep, is_open: bool) -> Vec<Node<Msg>> {
     vec![item_caption, item_body]
 }
 
+fn blocked_by_view(xs: &[CommandBlocker]) -> Node<Msg> {
+    if xs.is_empty() {
+        return empty![];
+    }
+
+    li![
+        class![C.pb_2],
+        "Blocked by:",
+        ul![
+            class![C.pl_4],
+            xs.iter().map(|x| {
+                let command = match (x.blocking_command_id, &x.blocking_command_message) {
+                    (Some(id), Some(msg)) => format!(" of command {} ({})", id, msg),
+                    (Some(id), None) => format!(" of command {}", id),
+                    _ => "".into(),
+                };
+
+                let records = x
+                    .records
+                    .iter()
+                    .map(|r| {
+                        format!(
+                            "{} {} ({})",
+                            r.record_type,
+                            r.record_id,
+                            if r.write { "write" } else { "read" }
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                li![
+                    class![C.py_1, C.text_sm],
+                    font_awesome(class![C.w_3, C.h_3, C.inline, C.mr_1, C.text_yellow_500], "lock"),
+                    format!(
+                        "{} waits for {} ({}){}",
+                        x.class_name, x.blocking_class_name, x.blocking_state, command
+                    ),
+                    if records.is_empty() {
+                        empty![]
+                    } else {
+                        span![class![C.text_gray_500], format!(", locking {}", records)]
+                    },
+                ]
+            })
+        ]
+    ]
+}
+
+fn related_logs_view(xs: Option<&Vec<CommandStepLogs>>) -> Node<Msg> {
+    let xs = match xs {
+        Some(xs) => xs,
+        None => {
+            return li![
+                class![C.pb_2, C.text_gray_500],
+                "Host logs: ",
+                font_awesome(class![C.w_4, C.h_4, C.inline, C.pulse], "spinner"),
+            ]
+        }
+    };
+
+    if xs.is_empty() {
+        return empty![];
+    }
+
+    let time = |x: &chrono::DateTime<chrono::Utc>| x.with_timezone(&Local).format("%m/%d/%Y %H:%M:%S").to_string();
+
+    li![
+        class![C.pb_2],
+        "Host logs:",
+        ul![
+            class![C.pl_4],
+            xs.iter().map(|x| {
+                li![
+                    class![C.py_1, C.text_sm],
+                    div![
+                        span![
+                            class![C.font_medium, C.text_red_500 => x.state == "failed"],
+                            format!(
+                                "{} step {}/{} ({})",
+                                x.class_name,
+                                x.step_index + 1,
+                                x.step_count,
+                                x.state
+                            )
+                        ],
+                        span![
+                            class![C.text_gray_500],
+                            format!(
+                                " on {}, {} to {}",
+                                if x.fqdns.is_empty() {
+                                    "no known host".to_string()
+                                } else {
+                                    x.fqdns.join(", ")
+                                },
+                                time(&x.started_at),
+                                time(&x.finished_at)
+                            )
+                        ],
+                    ],
+                    if x.messages.is_empty() {
+                        p![class![C.text_gray_500], "No log messages."]
+                    } else {
+                        pre![
+                            class![
+                                C.p_2,
+                                C.my_1,
+                                C.leading_tight,
+                                C.text_gray_100,
+                                C.bg_gray_900,
+                                C.overflow_x_hidden,
+                                C.whitespace_pre_line,
+                                C.break_all,
+                            ],
+                            x.messages
+                                .iter()
+                                .map(|m| format!("{} {} {}: {}", time(&m.datetime), m.fqdn, m.tag, m.message))
+                                .collect::<Vec<_>>()
+                                .join("\n")
+                        ]
+                    },
+                    if x.truncated {
+                        p![
+                            class![C.text_gray_500],
+                            format!("Only the first {} messages are shown.", x.messages.len())
+                        ]
+                    } else {
+                        empty![]
+                    },
+                ]
+            })
+        ]
+    ]
+}
+
 fn status_text(cmd: &RichCommand) -> &'static str {
     if cmd.cancelled {
         "Cancelled"
@@ -770,6 +1004,8 @@ impl Model {
         self.steps_view.clear();
         self.select = Default::default();
         self.cancelling_jobs.clear();
+        self.blockers.clear();
+        self.related_logs.clear();
     }
 
     fn update_commands(&mut self, cmds: Vec<Arc<Command>>) {
@@ -0,0 +1,380 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    components::{font_awesome, modal},
+    extensions::MergeAttrs as _,
+    generated::css_classes::C,
+    key_codes,
+    route::{Route, RouteId},
+    GMsg,
+};
+use iml_wire_types::{warp_drive::ArcCache, Conf};
+use seed::{prelude::*, *};
+use std::cmp::Reverse;
+
+const MAX_RESULTS: usize = 10;
+
+/// Something the palette can do once an entry is chosen.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Navigate(Route<'static>),
+    TakeSnapshot(String),
+    ScanStratagem(i32),
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub label: String,
+    pub kind: &'static str,
+    pub icon: &'static str,
+    pub action: Action,
+}
+
+#[derive(Default, Debug)]
+pub struct Model {
+    pub modal: modal::Model,
+    query: String,
+    selected: usize,
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Modal(modal::Msg),
+    Open,
+    QueryChanged(String),
+    KeyDown(u32),
+    Hover(usize),
+    Run(Action),
+    Noop,
+}
+
+pub fn update(msg: Msg, cache: &ArcCache, conf: &Conf, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Modal(msg) => {
+            modal::update(msg, &mut model.modal, &mut orders.proxy(Msg::Modal));
+        }
+        Msg::Open => {
+            model.query.clear();
+            model.selected = 0;
+            model.entries = search(&entries(cache, conf), "");
+            model.modal.open = true;
+        }
+        Msg::QueryChanged(query) => {
+            model.entries = search(&entries(cache, conf), &query);
+            model.selected = 0;
+            model.query = query;
+        }
+        Msg::KeyDown(code) => match code {
+            key_codes::ESC => {
+                orders.send_msg(Msg::Modal(modal::Msg::Close));
+            }
+            key_codes::UP => {
+                model.selected = model.selected.saturating_sub(1);
+            }
+            key_codes::DOWN => {
+                if model.selected + 1 < model.entries.len() {
+                    model.selected += 1;
+                }
+            }
+            key_codes::ENTER => {
+                if let Some(x) = model.entries.get(model.selected) {
+                    orders.send_msg(Msg::Run(x.action.clone()));
+                }
+            }
+            _ => {
+                orders.skip();
+            }
+        },
+        Msg::Hover(idx) => {
+            model.selected = idx;
+        }
+        Msg::Run(action) => {
+            model.modal.open = false;
+
+            // Actions other than plain navigation are carried out by the parent
+            if let Action::Navigate(route) = action {
+                orders.send_g_msg(GMsg::RouteChange(route.into()));
+            }
+        }
+        Msg::Noop => {
+            orders.skip();
+        }
+    }
+}
+
+/// Builds the full list of palette entries from the static pages
+/// and the filesystems and hosts currently in the cache.
+pub fn entries(cache: &ArcCache, conf: &Conf) -> Vec<Entry> {
+    let mut pages = vec![
+        ("Dashboard", Route::Dashboard),
+        ("Dashboards", Route::CustomDashboards),
+        ("On-call", Route::OnCall),
+        ("Filesystems", Route::Filesystems),
+        ("Servers", Route::Servers),
+        ("Targets", Route::Targets),
+        ("MGTs", Route::Mgt),
+        ("OST Pools", Route::OstPools),
+        ("Volumes", Route::Volumes),
+        ("Power Control", Route::PowerControl),
+        ("Jobstats", Route::Jobstats),
+        ("Users", Route::Users),
+        ("Roles", Route::Roles),
+        ("Manager Status", Route::ManagerStatus),
+        ("Sites", Route::Sites),
+        ("About", Route::About),
+    ];
+
+    if conf.use_snapshots {
+        pages.push(("Snapshots", Route::Snapshots));
+    }
+
+    if conf.use_stratagem {
+        pages.push(("Stratagem", Route::Stratagem));
+    }
+
+    let mut xs: Vec<_> = pages
+        .into_iter()
+        .map(|(label, route)| Entry {
+            label: label.into(),
+            kind: "Page",
+            icon: "columns",
+            action: Action::Navigate(route),
+        })
+        .collect();
+
+    let mut filesystems: Vec<_> = cache.filesystem.values().collect();
+    filesystems.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for fs in filesystems {
+        xs.push(Entry {
+            label: fs.name.clone(),
+            kind: "Filesystem",
+            icon: "folder",
+            action: Action::Navigate(Route::Filesystem(RouteId::from(fs.id))),
+        });
+
+        if conf.use_snapshots {
+            xs.push(Entry {
+                label: format!("Create snapshot of {}", fs.name),
+                kind: "Action",
+                icon: "camera",
+                action: Action::TakeSnapshot(fs.name.clone()),
+            });
+        }
+
+        if conf.use_stratagem {
+            xs.push(Entry {
+                label: format!("Run stratagem scan on {}", fs.name),
+                kind: "Action",
+                icon: "chart-bar",
+                action: Action::ScanStratagem(fs.id),
+            });
+        }
+    }
+
+    let mut hosts: Vec<_> = cache.host.values().collect();
+    hosts.sort_by(|a, b| a.fqdn.cmp(&b.fqdn));
+
+    for host in hosts {
+        xs.push(Entry {
+            label: host.fqdn.clone(),
+            kind: "Server",
+            icon: "server",
+            action: Action::Navigate(Route::Server(RouteId::from(host.id))),
+        });
+    }
+
+    xs
+}
+
+/// Filters and ranks `entries` against `query`, best matches first.
+fn search(entries: &[Entry], query: &str) -> Vec<Entry> {
+    let mut xs: Vec<_> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, x)| fuzzy_score(query, &x.label).map(|score| (score, idx, x)))
+        .collect();
+
+    xs.sort_by_key(|(score, idx, _)| (Reverse(*score), *idx));
+
+    xs.into_iter().take(MAX_RESULTS).map(|(_, _, x)| x.clone()).collect()
+}
+
+/// Case-insensitive subsequence match.
+///
+/// Returns `None` if `query` is not a subsequence of `candidate`,
+/// otherwise a score that favours consecutive runs and matches at word starts.
+pub(crate) fn fuzzy_score(query: &str, candidate: &str) -> Option<u32> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    let mut score = 0;
+    let mut qi = 0;
+    let mut prev_matched = false;
+    let mut prev_char = None;
+
+    for c in candidate.chars().flat_map(char::to_lowercase) {
+        if qi == query.len() {
+            break;
+        }
+
+        if c == query[qi] {
+            score += 1;
+
+            if prev_matched {
+                score += 2;
+            }
+
+            if prev_char.map(|p: char| !p.is_alphanumeric()).unwrap_or(true) {
+                score += 3;
+            }
+
+            qi += 1;
+            prev_matched = true;
+        } else {
+            prev_matched = false;
+        }
+
+        prev_char = Some(c);
+    }
+
+    if qi == query.len() {
+        Some(score)
+    } else {
+        None
+    }
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    let input_cls = class![
+        C.appearance_none,
+        C.focus__outline_none,
+        C.focus__shadow_outline,
+        C.px_3,
+        C.py_2,
+        C.rounded_sm,
+        C.text_gray_800,
+        C.bg_gray_200,
+        C.w_full,
+    ];
+
+    let results = if model.entries.is_empty() {
+        vec![p![class![C.text_gray_600, C.py_2], "No matches"]]
+    } else {
+        model
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(idx, x)| {
+                let action = x.action.clone();
+
+                div![
+                    class![
+                        C.flex,
+                        C.items_center,
+                        C.justify_between,
+                        C.px_3,
+                        C.py_2,
+                        C.rounded,
+                        C.cursor_pointer,
+                        C.bg_blue_100 => idx == model.selected,
+                    ],
+                    mouse_ev(Ev::MouseEnter, move |_| Msg::Hover(idx)),
+                    mouse_ev(Ev::Click, move |_| Msg::Run(action)),
+                    span![
+                        font_awesome(class![C.w_4, C.h_4, C.inline, C.mr_2, C.text_gray_600], x.icon),
+                        &x.label
+                    ],
+                    span![class![C.text_sm, C.text_gray_500], x.kind],
+                ]
+            })
+            .collect()
+    };
+
+    modal::bg_view(
+        model.modal.open,
+        Msg::Modal,
+        modal::content_view(
+            Msg::Modal,
+            div![
+                input![
+                    input_cls,
+                    attrs! {
+                        At::AutoFocus => true.as_at_value(),
+                        At::Placeholder => "Go to a page, filesystem, server or action",
+                        At::Type => "text",
+                        At::Value => &model.query,
+                    },
+                    input_ev(Ev::Input, Msg::QueryChanged),
+                ],
+                div![class![C.mt_3], results],
+            ],
+        )
+        .with_listener(keyboard_ev(Ev::KeyDown, |ev| match ev.key_code() {
+            key_codes::UP | key_codes::DOWN => {
+                ev.prevent_default();
+
+                Msg::KeyDown(ev.key_code())
+            }
+            key_codes::ESC | key_codes::ENTER => Msg::KeyDown(ev.key_code()),
+            _ => Msg::Noop,
+        }))
+        .merge_attrs(class![C.text_black]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score_subsequence() {
+        assert!(fuzzy_score("fs", "Filesystems").is_some());
+        assert!(fuzzy_score("srv", "Servers").is_some());
+        assert!(fuzzy_score("", "Anything").is_some());
+        assert_eq!(fuzzy_score("xyz", "Servers"), None);
+        assert_eq!(fuzzy_score("serverss", "Servers"), None);
+    }
+
+    #[test]
+    fn test_fuzzy_score_ranking() {
+        let prefix = fuzzy_score("snap", "Snapshots").unwrap();
+        let scattered = fuzzy_score("snap", "Scan new app").unwrap();
+
+        assert!(prefix > scattered);
+
+        let word_start = fuzzy_score("cs", "Create snapshot").unwrap();
+        let inner = fuzzy_score("cs", "abcds").unwrap();
+
+        assert!(word_start > inner);
+    }
+
+    #[test]
+    fn test_search_orders_by_score() {
+        let xs = vec![
+            Entry {
+                label: "Servers".into(),
+                kind: "Page",
+                icon: "columns",
+                action: Action::Navigate(Route::Servers),
+            },
+            Entry {
+                label: "Stratagem".into(),
+                kind: "Page",
+                icon: "columns",
+                action: Action::Navigate(Route::Stratagem),
+            },
+        ];
+
+        let found = search(&xs, "strat");
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].action, Action::Navigate(Route::Stratagem));
+    }
+}
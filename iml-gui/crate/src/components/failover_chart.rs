@@ -0,0 +1,156 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{components::panel, extensions::MergeAttrs as _, generated::css_classes::C, GMsg, RequestExt};
+use chrono::{Duration, Utc};
+use iml_graphql_queries::{corosync, Response};
+use iml_wire_types::graphql::{HaEvent, HaEventKind};
+use seed::{prelude::*, *};
+use std::collections::BTreeMap;
+
+/// How far back failovers are counted.
+const DAYS: i64 = 30;
+
+/// Nodes that lost resources this many times are highlighted as flapping.
+const FLAPPING: usize = 3;
+
+#[derive(Default)]
+pub struct Model {
+    events: Option<Vec<HaEvent>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<corosync::ha_events::Resp>>),
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Fetch => {
+            let from = Utc::now() - Duration::days(DAYS);
+
+            let query = corosync::ha_events::build(None, Some(from.to_rfc3339()), None);
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => match x {
+            Ok(Response::Data(x)) => {
+                model.events = Some(x.data.ha_events);
+            }
+            Ok(Response::Errors(e)) => {
+                error!("An error occurred while retrieving HA events", e);
+            }
+            Err(e) => {
+                error!("An error occurred while retrieving HA events", e);
+            }
+        },
+    }
+}
+
+/// How often each node of each cluster lost a resource to a failover or failback
+fn failovers_by_cluster(xs: &[HaEvent]) -> BTreeMap<i32, BTreeMap<&str, usize>> {
+    xs.iter()
+        .filter(|x| x.event == HaEventKind::Failover || x.event == HaEventKind::Failback)
+        .filter_map(|x| x.previous_node.as_deref().map(|node| (x.cluster_id, node)))
+        .fold(BTreeMap::new(), |mut acc, (cluster_id, node)| {
+            *acc.entry(cluster_id)
+                .or_insert_with(BTreeMap::new)
+                .entry(node)
+                .or_insert(0) += 1;
+
+            acc
+        })
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    let xs = match model.events.as_ref() {
+        Some(xs) => xs,
+        None => return empty![],
+    };
+
+    let clusters = failovers_by_cluster(xs);
+
+    let max = clusters
+        .values()
+        .flat_map(|x| x.values())
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    panel::view(
+        h3![
+            class![C.py_4, C.font_normal, C.text_lg],
+            format!("Failovers In The Last {} Days", DAYS)
+        ],
+        if clusters.is_empty() {
+            div![class![C.px_6, C.py_4, C.text_gray_600], "No failovers"]
+        } else {
+            div![
+                class![C.px_6, C.py_4],
+                clusters.into_iter().map(|(cluster_id, nodes)| {
+                    div![
+                        class![C.mb_4],
+                        div![class![C.font_semibold, C.mb_1], format!("Cluster {}", cluster_id)],
+                        nodes.into_iter().map(|(node, count)| {
+                            let color = if count >= FLAPPING { C.bg_red_500 } else { C.bg_blue_500 };
+
+                            div![
+                                class![C.flex, C.items_center, C.text_sm, C.mb_1],
+                                span![class![C.w_48, C.truncate], node],
+                                div![
+                                    class![C.flex_grow],
+                                    div![
+                                        class![C.h_4, C.rounded, color],
+                                        style! { St::Width => format!("{}%", count * 100 / max) },
+                                    ]
+                                ],
+                                span![class![C.w_12, C.text_right], count.to_string()],
+                            ]
+                        })
+                    ]
+                })
+            ]
+        },
+    )
+    .merge_attrs(class![C.mt_4])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(cluster_id: i32, event: HaEventKind, previous_node: Option<&str>) -> HaEvent {
+        HaEvent {
+            id: 1,
+            cluster_id,
+            resource: "fs-OST0000".into(),
+            event,
+            node: "oss2".into(),
+            previous_node: previous_node.map(String::from),
+            reason: "".into(),
+            happened_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_failovers_by_cluster() {
+        let xs = vec![
+            event(1, HaEventKind::Failover, Some("oss1")),
+            event(1, HaEventKind::Failback, Some("oss1")),
+            event(1, HaEventKind::Failover, Some("oss2")),
+            event(1, HaEventKind::Ban, None),
+            event(2, HaEventKind::Failover, Some("mds1")),
+        ];
+
+        let clusters = failovers_by_cluster(&xs);
+
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[&1]["oss1"], 2);
+        assert_eq!(clusters[&1]["oss2"], 1);
+        assert_eq!(clusters[&2]["mds1"], 1);
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{components::font_awesome, generated::css_classes::C, GMsg, RequestExt};
+use iml_graphql_queries::{user_preferences, Response};
+use iml_wire_types::warp_drive::ArcCache;
+use seed::{prelude::*, *};
+
+static PREF_KEY: &str = "context.filesystem";
+
+/// The filesystem the whole GUI is scoped to, persisted per user.
+/// `None` shows all filesystems.
+#[derive(Debug, Default)]
+pub struct Model {
+    selected: Option<String>,
+}
+
+impl Model {
+    pub fn selected(&self) -> Option<&str> {
+        self.selected.as_deref()
+    }
+}
+
+/// Is `fs_name` part of the filesystem context `selected`?
+pub fn in_context(selected: Option<&str>, fs_name: &str) -> bool {
+    selected.map_or(true, |x| x == fs_name)
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Fetched(fetch::ResponseDataResult<Response<user_preferences::get::Resp>>),
+    Select(String),
+    Saved(fetch::ResponseDataResult<Response<user_preferences::set::Resp>>),
+}
+
+/// Loads the saved filesystem context of the user.
+pub fn init(orders: &mut impl Orders<Msg, GMsg>) {
+    let query = user_preferences::get::build(PREF_KEY);
+    let req = fetch::Request::graphql_query(&query);
+
+    orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Fetched(x) => match x {
+            Ok(Response::Data(x)) => {
+                if let Some(x) = x.data.user_preferences.get {
+                    match serde_json::from_str(&x) {
+                        Ok(x) => model.selected = x,
+                        Err(e) => log!(format!("Ignoring invalid filesystem context: {}", e)),
+                    }
+                }
+            }
+            Ok(Response::Errors(e)) => {
+                log!("Could not load the filesystem context", e);
+            }
+            Err(e) => {
+                log!("Could not load the filesystem context", e);
+                orders.skip();
+            }
+        },
+        Msg::Select(x) => {
+            model.selected = Some(x).filter(|x| !x.is_empty());
+
+            let value = serde_json::to_string(&model.selected).unwrap_or_else(|_| "null".into());
+
+            let query = user_preferences::set::build(PREF_KEY, value);
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Saved));
+        }
+        Msg::Saved(x) => match x {
+            Ok(Response::Data(_)) => {}
+            Ok(Response::Errors(e)) => {
+                error!("An error occurred while saving the filesystem context", e);
+            }
+            Err(e) => {
+                error!("An error occurred while saving the filesystem context", e);
+                orders.skip();
+            }
+        },
+    }
+}
+
+/// A select in the navbar to choose the filesystem context, or all filesystems.
+pub fn view(cache: &ArcCache, model: &Model) -> Node<Msg> {
+    let mut names: Vec<_> = cache.filesystem.values().map(|x| x.name.as_str()).collect();
+
+    // Keep a saved filesystem that is gone selectable, so the context can be seen and cleared.
+    if let Some(x) = model.selected() {
+        if !names.contains(&x) {
+            names.push(x);
+        }
+    }
+
+    names.sort_by(|a, b| natord::compare(a, b));
+
+    let opt = |value: &str, label: &str, selected: bool| {
+        let mut x = option![class![C.text_gray_800], attrs! {At::Value => value}, label];
+
+        if selected {
+            x.add_attr(At::Selected.to_string(), "selected");
+        }
+
+        x
+    };
+
+    div![
+        class![
+            C.relative,
+            C.flex,
+            C.items_center,
+            C.px_6,
+            C.py_2,
+            C.lg__px_4,
+            C.lg__py_0
+        ],
+        font_awesome(class![C.w_4, C.h_4, C.inline, C.mr_2, C.text_gray_500], "filter"),
+        select![
+            class![
+                C.appearance_none,
+                C.bg_transparent,
+                C.border,
+                C.border_gray_600,
+                C.cursor_pointer,
+                C.focus__outline_none,
+                C.pl_2,
+                C.pr_8,
+                C.py_1,
+                C.rounded,
+                C.text_gray_300,
+            ],
+            attrs! { At::Title => "Filesystem context" },
+            opt("", "All filesystems", model.selected.is_none()),
+            names.iter().map(|x| opt(x, x, model.selected() == Some(*x))),
+            input_ev(Ev::Change, Msg::Select),
+        ],
+        font_awesome(
+            class![
+                C.pointer_events_none,
+                C.absolute,
+                C.right_0,
+                C.mr_8,
+                C.w_3,
+                C.h_3,
+                C.text_gray_500
+            ],
+            "chevron-down"
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_context() {
+        assert!(in_context(None, "fs1"));
+        assert!(in_context(Some("fs1"), "fs1"));
+        assert!(!in_context(Some("fs1"), "fs2"));
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    components::{font_awesome, resource_links},
+    generated::css_classes::C,
+    route::{Route, RouteId},
+    sleep_with_handle, GMsg, RequestExt,
+};
+use futures::channel::oneshot;
+use iml_graphql_queries::{health, Response};
+use iml_wire_types::graphql::{HealthSeverity, SystemHealth};
+use seed::{prelude::*, *};
+use std::time::Duration;
+
+/// How often the system health is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+pub struct Model {
+    started: bool,
+    health: Option<SystemHealth>,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Start,
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<health::system::Resp>>),
+    Noop,
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Start => {
+            if !model.started {
+                model.started = true;
+
+                orders.send_msg(Msg::Fetch);
+            }
+        }
+        Msg::Fetch => {
+            model.cancel = None;
+
+            let query = health::system::build();
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.health = Some(x.data.system_health);
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving system health", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving system health", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(POLL_INTERVAL, Msg::Fetch, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::Noop => {}
+    }
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    match model.health.as_ref() {
+        Some(x) if x.severity != HealthSeverity::Good => banner(x),
+        _ => empty![],
+    }
+}
+
+/// The health of the system, also when it is healthy. Used by the on-call page.
+pub(crate) fn summary_view<T>(model: &Model) -> Node<T> {
+    match model.health.as_ref() {
+        Some(x) => banner(x),
+        None => div![class![C.p_4, C.text_gray_600], "Checking system health..."],
+    }
+}
+
+fn banner<T>(x: &SystemHealth) -> Node<T> {
+    let (colors, icon) = match x.severity {
+        HealthSeverity::Good => (
+            class![C.bg_green_100, C.border_green_400, C.text_green_800],
+            "check-circle",
+        ),
+        HealthSeverity::Warning => (
+            class![C.bg_yellow_100, C.border_yellow_400, C.text_yellow_800],
+            "exclamation-triangle",
+        ),
+        HealthSeverity::Error => (
+            class![C.bg_red_100, C.border_red_400, C.text_red_800],
+            "exclamation-circle",
+        ),
+    };
+
+    let xs = items(x);
+
+    div![
+        class![C.flex, C.items_center, C.border_l_4, C.px_4, C.py_2, C.text_sm],
+        colors,
+        font_awesome(class![C.w_4, C.h_4, C.inline, C.mr_2], icon),
+        if xs.is_empty() {
+            span!["All systems healthy"]
+        } else {
+            ul![
+                class![C.flex, C.flex_wrap],
+                xs.into_iter().map(|x| li![class![C.mr_6], x])
+            ]
+        }
+    ]
+}
+
+fn items<T>(x: &SystemHealth) -> Vec<Vec<Node<T>>> {
+    let mut xs = vec![];
+
+    if !x.degraded_filesystems.is_empty() {
+        let links = x
+            .degraded_filesystems
+            .iter()
+            .map(|f| resource_links::href_view(&f.name, Route::Filesystem(RouteId::from(f.id))));
+
+        xs.push(nodes![
+            span![class![C.font_semibold, C.mr_1], "Degraded filesystems:"],
+            comma_separated(links)
+        ]);
+    }
+
+    if !x.down_hosts.is_empty() {
+        let links = x
+            .down_hosts
+            .iter()
+            .map(|h| resource_links::href_view(&h.fqdn, Route::Server(RouteId::from(h.id))));
+
+        xs.push(nodes![
+            span![class![C.font_semibold, C.mr_1], "Unreachable servers:"],
+            comma_separated(links)
+        ]);
+    }
+
+    if x.failed_commands > 0 {
+        xs.push(nodes![span![format!(
+            "{} failed {} in the last hour",
+            x.failed_commands,
+            if x.failed_commands == 1 { "command" } else { "commands" }
+        )]]);
+    }
+
+    for issue in &x.service_issues {
+        xs.push(nodes![span![issue]]);
+    }
+
+    xs
+}
+
+fn comma_separated<T>(xs: impl Iterator<Item = Node<T>>) -> Vec<Node<T>> {
+    xs.enumerate()
+        .flat_map(|(idx, x)| if idx == 0 { vec![x] } else { vec![plain![", "], x] })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iml_wire_types::graphql::DownHost;
+
+    #[test]
+    fn test_items() {
+        let x = SystemHealth {
+            down_hosts: vec![DownHost {
+                id: 1,
+                fqdn: "oss1.local".into(),
+            }],
+            failed_commands: 2,
+            ..SystemHealth::default()
+        }
+        .with_severity();
+
+        assert_eq!(x.severity, HealthSeverity::Error);
+        assert_eq!(items::<Msg>(&x).len(), 2);
+        assert!(items::<Msg>(&SystemHealth::default()).is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! A heatmap of the latest LNet ping mesh: one row per host that pinged, one column per host pinged.
+
+use crate::{
+    components::{panel, restrict},
+    extensions::MergeAttrs as _,
+    generated::css_classes::C,
+    GMsg, RequestExt,
+};
+use iml_graphql_queries::{lnet_ping, Response};
+use iml_wire_types::{
+    lnet_ping::{LnetPingMesh, LnetPingPair},
+    GroupType, Session,
+};
+use seed::{prelude::*, *};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Pairs answering slower than this on average are highlighted.
+const SLOW_MS: f64 = 50.;
+
+#[derive(Default)]
+pub struct Model {
+    mesh: Option<LnetPingMesh>,
+    starting: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<lnet_ping::list::Resp>>),
+    Start(Vec<i32>),
+    Started(fetch::ResponseDataResult<Response<lnet_ping::start::Resp>>),
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Fetch => {
+            let query = lnet_ping::list::build(Some(1));
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => match x {
+            Ok(Response::Data(x)) => {
+                model.mesh = x.data.lnet_ping.meshes.into_iter().next();
+            }
+            Ok(Response::Errors(e)) => {
+                error!("An error occurred while retrieving LNet ping meshes", e);
+            }
+            Err(e) => {
+                error!("An error occurred while retrieving LNet ping meshes", e);
+            }
+        },
+        Msg::Start(host_ids) => {
+            model.starting = true;
+
+            let query = lnet_ping::start::build(host_ids, None);
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Started));
+        }
+        Msg::Started(x) => {
+            model.starting = false;
+
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.mesh = Some(x.data.lnet_ping.start);
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while starting an LNet ping mesh", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while starting an LNet ping mesh", e);
+                }
+            }
+        }
+    }
+}
+
+/// How one host reached another, over all the NIDs of the other host
+#[derive(Debug, Default, PartialEq)]
+struct Cell<'a> {
+    loss_percent: f64,
+    avg_latency_ms: Option<f64>,
+    nids: Vec<&'a str>,
+    error: Option<&'a str>,
+}
+
+/// The worst of the pairs of each source and target host, by fqdn
+fn cells(xs: &[LnetPingPair]) -> BTreeMap<(&str, &str), Cell> {
+    xs.iter().fold(BTreeMap::new(), |mut acc, x| {
+        let cell: &mut Cell = acc.entry((x.source_fqdn.as_str(), x.target_fqdn.as_str())).or_default();
+
+        cell.loss_percent = cell.loss_percent.max(x.loss_percent);
+        cell.avg_latency_ms = match (cell.avg_latency_ms, x.avg_latency_ms) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        cell.nids.push(&x.target_nid);
+        cell.error = cell.error.or_else(|| x.error.as_deref());
+
+        acc
+    })
+}
+
+fn cell_view<T>(x: Option<&Cell>) -> Node<T> {
+    let (color, title) = match x {
+        None => (C.bg_gray_300, "Not pinged yet".to_string()),
+        Some(x) => {
+            let color = if x.loss_percent >= 100. {
+                C.bg_red_500
+            } else if x.loss_percent > 0. {
+                C.bg_orange_500
+            } else if x.avg_latency_ms.map_or(false, |x| x > SLOW_MS) {
+                C.bg_yellow_500
+            } else {
+                C.bg_green_500
+            };
+
+            let latency = x
+                .avg_latency_ms
+                .map(|x| format!("{:.2} ms", x))
+                .unwrap_or_else(|| "---".to_string());
+
+            let mut title = format!(
+                "{}\nLoss: {:.0}%\nAverage latency: {}",
+                x.nids.join(", "),
+                x.loss_percent,
+                latency
+            );
+
+            if let Some(e) = x.error {
+                title.push_str(&format!("\n{}", e));
+            }
+
+            (color, title)
+        }
+    };
+
+    td![
+        class![C.p_1],
+        div![class![C.w_8, C.h_8, C.rounded, color], attrs! { At::Title => title }]
+    ]
+}
+
+fn heatmap_view<T>(mesh: &LnetPingMesh) -> Node<T> {
+    let cells = cells(&mesh.pairs);
+
+    let hosts: BTreeSet<&str> = cells.keys().flat_map(|(a, b)| vec![*a, *b]).collect();
+
+    if hosts.is_empty() {
+        return div![class![C.px_6, C.py_4, C.text_gray_600], "Waiting for the first results"];
+    }
+
+    div![
+        class![C.px_6, C.py_4, C.overflow_x_auto],
+        table![
+            thead![tr![
+                th![],
+                hosts
+                    .iter()
+                    .map(|x| th![class![C.text_xs, C.font_normal, C.px_2, C.whitespace_no_wrap], *x])
+            ]],
+            tbody![hosts.iter().map(|source| {
+                tr![
+                    th![
+                        class![C.text_xs, C.font_normal, C.text_right, C.pr_2, C.whitespace_no_wrap],
+                        *source
+                    ],
+                    hosts.iter().map(|target| {
+                        if source == target {
+                            td![]
+                        } else {
+                            cell_view(cells.get(&(*source, *target)))
+                        }
+                    })
+                ]
+            })]
+        ],
+        if mesh.finished_at.is_none() {
+            div![class![C.text_sm, C.text_gray_600, C.mt_2], "Pinging..."]
+        } else {
+            empty![]
+        }
+    ]
+}
+
+/// `host_ids` are the hosts a new mesh is run among.
+pub fn view(model: &Model, session: Option<&Session>, host_ids: Vec<i32>) -> Node<Msg> {
+    let disabled = model.starting || host_ids.len() < 2;
+
+    panel::view(
+        div![
+            class![C.flex, C.justify_between, C.items_center],
+            h3![class![C.py_4, C.font_normal, C.text_lg], "LNet Ping Mesh"],
+            restrict::view(
+                session,
+                GroupType::FilesystemAdministrators,
+                button![
+                    class![
+                        C.bg_blue_500,
+                        C.hover__bg_blue_700,
+                        C.text_white,
+                        C.text_sm,
+                        C.px_2,
+                        C.py_1,
+                        C.rounded,
+                        C.cursor_not_allowed => disabled,
+                        C.opacity_50 => disabled,
+                    ],
+                    attrs! { At::Disabled => disabled.as_at_value() },
+                    simple_ev(Ev::Click, Msg::Start(host_ids)),
+                    "Ping all servers"
+                ]
+            )
+        ],
+        match model.mesh.as_ref() {
+            Some(x) => heatmap_view(x),
+            None => div![class![C.px_6, C.py_4, C.text_gray_600], "No ping mesh has been run"],
+        },
+    )
+    .merge_attrs(class![C.mt_4])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(source: &str, target: &str, nid: &str, loss_percent: f64, avg: Option<f64>) -> LnetPingPair {
+        LnetPingPair {
+            source_host_id: 1,
+            source_fqdn: source.into(),
+            target_host_id: 2,
+            target_fqdn: target.into(),
+            target_nid: nid.into(),
+            sent: 4,
+            received: 4,
+            loss_percent,
+            avg_latency_ms: avg,
+            max_latency_ms: avg,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_cells() {
+        let xs = vec![
+            pair("oss1", "oss2", "10.0.0.2@tcp", 0., Some(0.5)),
+            pair("oss1", "oss2", "192.168.0.2@o2ib", 25., Some(0.1)),
+            pair("oss2", "oss1", "10.0.0.1@tcp", 100., None),
+        ];
+
+        let cells = cells(&xs);
+
+        assert_eq!(
+            cells[&("oss1", "oss2")],
+            Cell {
+                loss_percent: 25.,
+                avg_latency_ms: Some(0.5),
+                nids: vec!["10.0.0.2@tcp", "192.168.0.2@o2ib"],
+                error: None,
+            }
+        );
+        assert_eq!(cells[&("oss2", "oss1")].avg_latency_ms, None);
+        assert_eq!(cells.get(&("oss1", "oss1")), None);
+    }
+}
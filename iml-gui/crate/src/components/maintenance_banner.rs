@@ -0,0 +1,172 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    components::{font_awesome, resource_links},
+    generated::css_classes::C,
+    route::{Route, RouteId},
+    sleep_with_handle, GMsg, RequestExt,
+};
+use chrono::{DateTime, Local, Utc};
+use futures::channel::oneshot;
+use iml_graphql_queries::{maintenance, Response};
+use iml_wire_types::maintenance::{MaintenanceScope, MaintenanceWindow};
+use seed::{prelude::*, *};
+use std::time::Duration;
+
+/// How often the upcoming maintenance windows are polled.
+/// Changes are fetched as they are made, this keeps the active windows current as time passes.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many upcoming windows the dashboard lists.
+const UPCOMING_LIMIT: i32 = 5;
+
+#[derive(Default)]
+pub struct Model {
+    started: bool,
+    windows: Option<Vec<MaintenanceWindow>>,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Start,
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<maintenance::upcoming::Resp>>),
+    /// A table changed, windows are fetched again right away when it is `maintenance_window`
+    Invalidate(String),
+    Noop,
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Start => {
+            if !model.started {
+                model.started = true;
+
+                orders.send_msg(Msg::Fetch);
+            }
+        }
+        Msg::Fetch => {
+            model.cancel = None;
+
+            let query = maintenance::upcoming::build(UPCOMING_LIMIT);
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.windows = Some(x.data.maintenance.upcoming);
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving maintenance windows", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving maintenance windows", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(POLL_INTERVAL, Msg::Fetch, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::Invalidate(table) => {
+            if model.started && table == "maintenance_window" {
+                orders.send_msg(Msg::Fetch);
+            } else {
+                orders.skip();
+            }
+        }
+        Msg::Noop => {}
+    }
+}
+
+/// A banner for each active window.
+pub fn view(model: &Model) -> Node<Msg> {
+    let now = Utc::now();
+
+    let xs: Vec<_> = model.windows.iter().flatten().filter(|x| x.is_active(now)).collect();
+
+    if xs.is_empty() {
+        return empty![];
+    }
+
+    div![
+        class![
+            C.bg_blue_100,
+            C.border_blue_400,
+            C.text_blue_800,
+            C.border_l_4,
+            C.px_4,
+            C.py_2,
+            C.text_sm
+        ],
+        xs.into_iter().map(|x| {
+            div![
+                class![C.flex, C.items_center],
+                font_awesome(class![C.w_4, C.h_4, C.inline, C.mr_2], "clock"),
+                span![class![C.font_semibold, C.mr_1], format!("Maintenance: {}", x.name)],
+                span![class![C.mr_1], "on"],
+                scope_view(x),
+                span![class![C.ml_1], format!("until {}.", format_time(&x.ends_at))],
+                if x.suppress_alerts {
+                    span![class![C.ml_1], "Alerts are suppressed."]
+                } else {
+                    empty![]
+                }
+            ]
+        })
+    ]
+}
+
+/// The upcoming windows, for the dashboard.
+pub(crate) fn upcoming_view<T>(model: &Model) -> Node<T> {
+    let now = Utc::now();
+
+    match model.windows.as_ref() {
+        None => div![class![C.p_4, C.text_gray_600], "Loading maintenance windows..."],
+        Some(xs) if xs.is_empty() => div![class![C.p_4, C.text_gray_600], "No maintenance is scheduled."],
+        Some(xs) => ul![
+            class![C.p_4],
+            xs.iter().map(|x| {
+                li![
+                    class![C.py_2, C.border_b],
+                    div![
+                        span![class![C.font_semibold, C.mr_2], &x.name],
+                        if x.is_active(now) {
+                            span![class![C.text_blue_600, C.text_sm], "Active"]
+                        } else {
+                            empty![]
+                        }
+                    ],
+                    div![
+                        class![C.text_sm, C.text_gray_600],
+                        scope_view(x),
+                        span![
+                            class![C.ml_2],
+                            format!("{} to {}", format_time(&x.starts_at), format_time(&x.ends_at))
+                        ],
+                    ]
+                ]
+            })
+        ],
+    }
+}
+
+fn scope_view<T>(x: &MaintenanceWindow) -> Node<T> {
+    let id = RouteId::from(x.scope_id);
+
+    match (x.scope, x.scope_name.as_ref()) {
+        (MaintenanceScope::Host, Some(name)) => resource_links::href_view(name, Route::Server(id)),
+        (MaintenanceScope::Filesystem, Some(name)) => resource_links::href_view(name, Route::Filesystem(id)),
+        (MaintenanceScope::Cluster, Some(name)) => span![format!("cluster {}", name)],
+        (scope, None) => span![format!("removed {:?} {}", scope, x.scope_id).to_lowercase()],
+    }
+}
+
+fn format_time(x: &DateTime<Utc>) -> String {
+    x.with_timezone(&Local).format("%m/%d/%Y %H:%M").to_string()
+}
@@ -19,23 +19,35 @@ pub(crate) mod activity_indicator;
 pub(crate) mod alert_indicator;
 pub(crate) mod arrow;
 pub(crate) mod attrs;
+pub(crate) mod banner;
 pub(crate) mod breadcrumbs;
 pub(crate) mod chart;
 pub(crate) mod command_modal;
+pub(crate) mod command_palette;
 pub(crate) mod dashboard;
 pub(crate) mod date;
 pub(crate) mod datepicker;
 pub(crate) mod duration_picker;
+pub(crate) mod failover_chart;
 pub(crate) mod font_awesome;
 pub(crate) mod form;
+pub(crate) mod fs_context;
 pub(crate) mod grafana_chart;
+pub(crate) mod health_banner;
+pub(crate) mod lnet_ping_mesh;
 pub(crate) mod loading;
 pub(crate) mod lock_indicator;
 pub(crate) mod logo;
+pub(crate) mod maintenance_banner;
 pub(crate) mod restrict;
+pub(crate) mod session_timeout;
 pub(crate) mod sfa_overview;
+pub(crate) mod sparkline;
 pub(crate) mod stratagem;
+pub(crate) mod stripe_editor;
+pub(crate) mod table_columns;
 pub(crate) mod tree;
+pub(crate) mod virtual_table;
 
 pub(crate) use activity_indicator::{update_activity_health, ActivityHealth};
 pub(crate) use alert_indicator::alert_indicator;
@@ -11,7 +11,7 @@ use crate::{
 use seed::{prelude::*, virtual_dom::Attrs, *};
 use std::{cmp::Eq, fmt::Debug, ops::Range};
 
-pub const ROW_OPTS: [usize; 4] = [10, 25, 50, 100];
+pub const ROW_OPTS: [usize; 6] = [10, 25, 50, 100, 500, 1000];
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct Model {
@@ -0,0 +1,66 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{components::font_awesome, generated::css_classes::C};
+use seed::{prelude::*, *};
+
+/// How long before an idle session expires the warning banner is shown, in seconds.
+pub(crate) const WARN_SECS: i64 = 120;
+
+/// Banner counting down the seconds `remaining` until the session is logged out.
+/// Clicking "Stay signed in" sends `stay_msg`.
+pub(crate) fn view<T: Clone + 'static>(remaining: i64, stay_msg: T) -> Node<T> {
+    div![
+        class![
+            C.fixed,
+            C.top_0,
+            C.inset_x_0,
+            C.z_50,
+            C.flex,
+            C.items_center,
+            C.justify_center,
+            C.py_2,
+            C.bg_yellow_200,
+            C.text_yellow_900,
+        ],
+        font_awesome(class![C.w_4, C.h_4, C.inline, C.mr_2], "clock"),
+        span![format!(
+            "You will be signed out in {} due to inactivity.",
+            format_remaining(remaining)
+        )],
+        button![
+            class![
+                C.ml_4,
+                C.px_3,
+                C.py_1,
+                C.rounded,
+                C.font_bold,
+                C.text_white,
+                C.bg_yellow_600,
+                C.hover__bg_yellow_700,
+            ],
+            simple_ev(Ev::Click, stay_msg),
+            "Stay signed in"
+        ]
+    ]
+}
+
+fn format_remaining(secs: i64) -> String {
+    let secs = secs.max(0);
+
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_remaining() {
+        assert_eq!(format_remaining(120), "2:00");
+        assert_eq!(format_remaining(61), "1:01");
+        assert_eq!(format_remaining(9), "0:09");
+        assert_eq!(format_remaining(-3), "0:00");
+    }
+}
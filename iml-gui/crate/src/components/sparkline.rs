@@ -0,0 +1,200 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Small inline bandwidth charts for table rows.
+//!
+//! The stats of every row in a table are fetched with one `miniStats` query.
+
+use crate::{generated::css_classes::C, sleep_with_handle, GMsg, RequestExt};
+use futures::channel::oneshot;
+use iml_graphql_queries::{target, Response};
+use iml_wire_types::graphql::TargetMiniStats;
+use number_formatter::format_bytes;
+use seed::{prelude::*, *};
+use std::{collections::HashMap, time::Duration};
+
+/// How often stats are refreshed. This matches the bucket size of the query.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+static WIDTH: f64 = 100.0;
+static HEIGHT: f64 = 20.0;
+
+#[derive(Default)]
+pub struct Model {
+    target_uuids: Vec<String>,
+    stats: HashMap<String, TargetMiniStats>,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    SetTargets(Vec<String>),
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<target::mini_stats::Resp>>),
+    Noop,
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::SetTargets(mut xs) => {
+            xs.sort();
+            xs.dedup();
+
+            if xs == model.target_uuids {
+                return;
+            }
+
+            model.target_uuids = xs;
+
+            orders.send_msg(Msg::Fetch);
+        }
+        Msg::Fetch => {
+            model.cancel = None;
+
+            if model.target_uuids.is_empty() {
+                model.stats.clear();
+
+                return;
+            }
+
+            let query = target::mini_stats::build(model.target_uuids.clone());
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.skip().perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.stats = x
+                        .data
+                        .mini_stats
+                        .into_iter()
+                        .map(|x| (x.target_uuid.clone(), x))
+                        .collect();
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving mini stats", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving mini stats", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(POLL_INTERVAL, Msg::Fetch, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::Noop => {}
+    }
+}
+
+/// The bandwidth of a single target.
+pub fn target_view<T>(model: &Model, target_uuid: &str) -> Node<T> {
+    match model.stats.get(target_uuid) {
+        Some(x) => view(&x.read, &x.write),
+        None => empty![],
+    }
+}
+
+/// The combined bandwidth of several targets, e.g. the ones mounted on a server.
+pub fn sum_view<'a, T>(model: &Model, target_uuids: impl IntoIterator<Item = &'a str>) -> Node<T> {
+    let xs: Vec<_> = target_uuids.into_iter().filter_map(|x| model.stats.get(x)).collect();
+
+    if xs.is_empty() {
+        return empty![];
+    }
+
+    let read = sum(xs.iter().map(|x| &x.read[..]));
+    let write = sum(xs.iter().map(|x| &x.write[..]));
+
+    view(&read, &write)
+}
+
+fn view<T>(read: &[f64], write: &[f64]) -> Node<T> {
+    let max = read.iter().chain(write).cloned().fold(0.0, f64::max);
+
+    span![
+        attrs! {
+            At::Title => format!(
+                "Read {}/s, Write {}/s",
+                format_bytes(read.last().cloned().unwrap_or_default(), 1),
+                format_bytes(write.last().cloned().unwrap_or_default(), 1)
+            )
+        },
+        svg![
+            class![C.inline, C.w_24, C.h_6, C.stroke_current],
+            attrs! {
+                At::ViewBox => format!("0 0 {} {}", WIDTH, HEIGHT),
+                At::PreserveAspectRatio => "none",
+            },
+            line_view(read, max, C.text_blue_500),
+            line_view(write, max, C.text_green_500),
+        ]
+    ]
+}
+
+fn line_view<T>(xs: &[f64], max: f64, color: &str) -> Node<T> {
+    polyline![
+        class![color],
+        attrs! {
+            At::Points => points(xs, max),
+            At::Fill => "none",
+            At::StrokeWidth => "1.5",
+            At::from("vector-effect") => "non-scaling-stroke",
+        }
+    ]
+}
+
+/// Scales `xs` to `WIDTH` x `HEIGHT` SVG coordinates. `max` is drawn at the top.
+fn points(xs: &[f64], max: f64) -> String {
+    let step = if xs.len() > 1 {
+        WIDTH / (xs.len() - 1) as f64
+    } else {
+        0.0
+    };
+
+    xs.iter()
+        .enumerate()
+        .map(|(idx, x)| {
+            let y = if max > 0.0 { HEIGHT - x / max * HEIGHT } else { HEIGHT };
+
+            format!("{:.1},{:.1}", idx as f64 * step, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Adds up series bucket by bucket. Shorter series count as zero where they run out.
+fn sum<'a>(xs: impl Iterator<Item = &'a [f64]>) -> Vec<f64> {
+    xs.fold(vec![], |mut acc, x| {
+        if acc.len() < x.len() {
+            acc.resize(x.len(), 0.0);
+        }
+
+        for (a, b) in acc.iter_mut().zip(x) {
+            *a += b;
+        }
+
+        acc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points() {
+        assert_eq!(points(&[0.0, 5.0, 10.0], 10.0), "0.0,20.0 50.0,10.0 100.0,0.0");
+        assert_eq!(points(&[0.0, 0.0], 0.0), "0.0,20.0 100.0,20.0");
+    }
+
+    #[test]
+    fn test_sum() {
+        let a = [1.0, 2.0, 3.0];
+        let b = [1.0, 1.0];
+
+        assert_eq!(sum(vec![&a[..], &b[..]].into_iter()), vec![2.0, 3.0, 3.0]);
+    }
+}
@@ -0,0 +1,286 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! A treemap of the directories of a filesystem from the latest heat map scan.
+//! Each directory is sized by its bytes, and colored by how much of them was accessed
+//! in the last 30 days, from blue when none was to red when all of it was.
+
+use crate::{generated::css_classes::C, sleep_with_handle, GMsg, RequestExt};
+use futures::channel::oneshot;
+use iml_graphql_queries::{stratagem, Response};
+use iml_wire_types::stratagem::{DirectoryHeat, FsHeatmap};
+use number_formatter::{format_bytes, format_number};
+use seed::{prelude::*, *};
+use std::time::Duration;
+
+/// How many levels of directories below the selected one are drawn.
+const LEVELS: i32 = 2;
+
+pub struct Model {
+    fs_name: String,
+    heatmap: Option<FsHeatmap>,
+    /// The directory the treemap is zoomed in on
+    root: String,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+impl Model {
+    pub fn new(fs_name: &str) -> Self {
+        Self {
+            fs_name: fs_name.to_string(),
+            heatmap: None,
+            root: "/".to_string(),
+            cancel: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<stratagem::fs_heatmap::Resp>>),
+    Select(String),
+    Noop,
+}
+
+pub(crate) fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Fetch => {
+            model.cancel = None;
+
+            let query = stratagem::fs_heatmap::build(&model.fs_name, None);
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.heatmap = x.data.stratagem.fs_heatmap;
+
+                    let found = model
+                        .heatmap
+                        .as_ref()
+                        .map_or(false, |x| x.directories.iter().any(|x| x.path == model.root));
+
+                    if !found {
+                        model.root = "/".to_string();
+                    }
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving the heat map", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving the heat map", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(Duration::from_secs(60), Msg::Fetch, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::Select(x) => {
+            model.root = x;
+        }
+        Msg::Noop => {
+            orders.skip();
+        }
+    }
+}
+
+/// The directory `path` is in, `None` for the root.
+fn parent(path: &str) -> Option<&str> {
+    match path.rfind('/') {
+        _ if path == "/" => None,
+        Some(0) => Some("/"),
+        Some(i) => Some(&path[..i]),
+        None => None,
+    }
+}
+
+fn name(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(i) if path != "/" => &path[i + 1..],
+        _ => path,
+    }
+}
+
+/// The directories directly in `path`, largest first.
+fn children<'a>(xs: &'a [DirectoryHeat], path: &str) -> Vec<&'a DirectoryHeat> {
+    let mut xs: Vec<_> = xs.iter().filter(|x| parent(&x.path) == Some(path)).collect();
+
+    xs.sort_by(|a, b| b.bytes.partial_cmp(&a.bytes).unwrap_or(std::cmp::Ordering::Equal));
+
+    xs
+}
+
+/// The share of the bytes of `x` accessed in the last 30 days.
+fn heat(x: &DirectoryHeat) -> f64 {
+    if x.bytes > 0. {
+        ((x.bytes_week + x.bytes_month) / x.bytes).min(1.)
+    } else {
+        0.
+    }
+}
+
+fn heat_color(heat: f64) -> String {
+    format!("hsl({:.0}, 70%, 55%)", 220. * (1. - heat))
+}
+
+fn title(x: &DirectoryHeat) -> String {
+    format!(
+        "{}\n{} in {} files\nAccessed in the last 7 days: {}\n7 to 30 days ago: {}\n\
+         30 to 90 days ago: {}\n90 days to a year ago: {}\nOver a year ago: {}",
+        x.path,
+        format_bytes(x.bytes, Some(1)),
+        format_number(x.files, Some(0)),
+        format_bytes(x.bytes_week, Some(1)),
+        format_bytes(x.bytes_month, Some(1)),
+        format_bytes(x.bytes_quarter, Some(1)),
+        format_bytes(x.bytes_year, Some(1)),
+        format_bytes(x.bytes_older, Some(1)),
+    )
+}
+
+/// Draws `x` and the directories in it, `levels` deep, alternating between rows and columns.
+fn tile_view(xs: &[DirectoryHeat], x: &DirectoryHeat, levels: i32, row: bool) -> Node<Msg> {
+    let children = if levels > 0 { children(xs, &x.path) } else { vec![] };
+
+    // Files directly in `x`, or in directories deeper than the scan went
+    let rest = x.bytes - children.iter().map(|x| x.bytes).sum::<f64>();
+
+    let path = x.path.clone();
+
+    div![
+        class![C.flex, C.overflow_hidden, C.border, C.border_white, C.cursor_pointer],
+        style! {
+            St::FlexGrow => x.bytes.max(1.).to_string(),
+            St::FlexBasis => px(0),
+            St::FlexDirection => if row { "row" } else { "column" },
+            St::BackgroundColor => heat_color(heat(x)),
+        },
+        attrs! { At::Title => title(x) },
+        mouse_ev(Ev::Click, move |ev| {
+            ev.stop_propagation();
+
+            Msg::Select(path)
+        }),
+        if children.is_empty() {
+            span![class![C.text_xs, C.text_white, C.p_1, C.truncate], name(&x.path)]
+        } else {
+            empty![]
+        },
+        children.iter().map(|c| tile_view(xs, c, levels - 1, !row)),
+        if !children.is_empty() && rest > 0. {
+            div![style! {
+                St::FlexGrow => rest.to_string(),
+                St::FlexBasis => px(0),
+            }]
+        } else {
+            empty![]
+        }
+    ]
+}
+
+fn breadcrumbs_view(root: &str) -> Node<Msg> {
+    let mut xs = vec![root];
+
+    while let Some(x) = xs.last().and_then(|x| parent(x)) {
+        xs.push(x);
+    }
+
+    xs.reverse();
+
+    div![
+        class![C.text_sm, C.mb_2],
+        xs.into_iter().map(|x| {
+            span![
+                class![C.mr_1],
+                a![
+                    class![C.text_blue_500, C.hover__underline, C.cursor_pointer],
+                    simple_ev(Ev::Click, Msg::Select(x.to_string())),
+                    name(x)
+                ],
+                if x == "/" || x == root { "" } else { " /" }
+            ]
+        })
+    ]
+}
+
+fn legend_view<T>() -> Node<T> {
+    div![
+        class![C.flex, C.items_center, C.text_xs, C.text_gray_600, C.mt_2],
+        span![class![C.mr_2], "Accessed in the last 30 days: none"],
+        (0..=4).map(|x| {
+            div![
+                class![C.w_8, C.h_4],
+                style! { St::BackgroundColor => heat_color(x as f64 / 4.) }
+            ]
+        }),
+        span![class![C.ml_2], "all"]
+    ]
+}
+
+pub(crate) fn view(model: &Model) -> Node<Msg> {
+    let content = match model.heatmap.as_ref() {
+        None => div![
+            class![C.px_6, C.py_4, C.text_gray_600],
+            "No heat map scan has been run for this filesystem"
+        ],
+        Some(x) => match x.directories.iter().find(|x| x.path == model.root) {
+            None => div![class![C.px_6, C.py_4, C.text_gray_600], "No files were found"],
+            Some(root) => div![
+                class![C.px_6, C.py_4],
+                breadcrumbs_view(&model.root),
+                div![class![C.flex, C.h_96], tile_view(&x.directories, root, LEVELS, true)],
+                legend_view()
+            ],
+        },
+    };
+
+    div![
+        class![
+            C.bg_white,
+            C.border,
+            C.border_b,
+            C.border_t,
+            C.mt_24,
+            C.rounded_lg,
+            C.shadow,
+        ],
+        div![
+            class![C.flex, C.justify_between, C.px_6, C._mb_px, C.bg_gray_200],
+            h3![class![C.py_4, C.font_normal, C.text_lg], "Directory Heat Map"],
+            p![
+                class![C.py_4, C.text_gray_600],
+                "Last Scanned: ",
+                model
+                    .heatmap
+                    .as_ref()
+                    .map(|x| x.scanned_at.format("%A, %B %d, %Y %H:%M:%S %Z").to_string())
+                    .unwrap_or_else(|| "---".to_string())
+            ]
+        ],
+        content
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parent() {
+        assert_eq!(parent("/"), None);
+        assert_eq!(parent("/proj1"), Some("/"));
+        assert_eq!(parent("/proj1/data"), Some("/proj1"));
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(name("/"), "/");
+        assert_eq!(name("/proj1"), "proj1");
+        assert_eq!(name("/proj1/data"), "data");
+    }
+}
@@ -17,7 +17,9 @@ use std::{collections::BTreeMap, sync::Arc};
 
 pub(crate) mod delete_stratagem_button;
 pub(crate) mod enable_stratagem_button;
+pub(crate) mod heatmap;
 pub(crate) mod inode_table;
+pub(crate) mod scan_progress;
 pub(crate) mod scan_stratagem_button;
 pub(crate) mod scan_stratagem_modal;
 pub(crate) mod update_stratagem_button;
@@ -65,6 +67,7 @@ pub struct Model {
     use_stratagem: bool,
     fs: Arc<Filesystem>,
     inode_table: inode_table::Model,
+    heatmap: heatmap::Model,
     grafana_vars: BTreeMap<String, String>,
     pub scan_duration_picker: duration_picker::Model,
     pub report_duration_picker: duration_picker::Model,
@@ -74,6 +77,7 @@ pub struct Model {
     pub disabled: bool,
     pub target_config: TargetConfig,
     pub scan_stratagem_button: scan_stratagem_button::Model,
+    scan_progress: scan_progress::Model,
     stratagem_config: Option<Arc<StratagemConfiguration>>,
 }
 
@@ -85,6 +89,7 @@ impl Model {
         Self {
             use_stratagem,
             inode_table: inode_table::Model::new(&fs.name),
+            heatmap: heatmap::Model::new(&fs.name),
             grafana_vars,
             scan_duration_picker: duration_picker::Model::default(),
             report_duration_picker: duration_picker::Model::default(),
@@ -94,6 +99,7 @@ impl Model {
             disabled: false,
             target_config: Default::default(),
             scan_stratagem_button: scan_stratagem_button::Model::new(fs.name.to_string()),
+            scan_progress: scan_progress::Model::default(),
             fs,
             stratagem_config: None,
         }
@@ -136,12 +142,14 @@ pub enum Msg {
     SetStratagemConfig(Vec<Arc<StratagemConfiguration>>),
     DeleteStratagemConfig,
     InodeTable(inode_table::Msg),
+    Heatmap(heatmap::Msg),
     ScanDurationPicker(duration_picker::Msg),
     ReportDurationPicker(duration_picker::Msg),
     PurgeDurationPicker(duration_picker::Msg),
     SendCommand(Command),
     CmdSent(Box<fetch::FetchObject<CmdWrapper>>),
     ScanStratagemButton(scan_stratagem_button::Msg),
+    ScanProgress(scan_progress::Msg),
     Noop,
 }
 
@@ -185,6 +193,7 @@ fn handle_stratagem_config_update(config: &mut Model, conf: &Arc<StratagemConfig
 pub(crate) fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
     match msg {
         Msg::InodeTable(x) => inode_table::update(x, &mut model.inode_table, &mut orders.proxy(Msg::InodeTable)),
+        Msg::Heatmap(x) => heatmap::update(x, &mut model.heatmap, &mut orders.proxy(Msg::Heatmap)),
         Msg::ScanDurationPicker(msg) => {
             duration_picker::update(msg, &mut model.scan_duration_picker);
             validation::validate(
@@ -292,12 +301,19 @@ pub(crate) fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg,
             );
         }
         Msg::ScanStratagemButton(msg) => {
+            if let Some(id) = msg.started_command() {
+                orders.proxy(Msg::ScanProgress).send_msg(scan_progress::Msg::Watch(id));
+            }
+
             scan_stratagem_button::update(
                 msg,
                 &mut model.scan_stratagem_button,
                 &mut orders.proxy(Msg::ScanStratagemButton),
             );
         }
+        Msg::ScanProgress(msg) => {
+            scan_progress::update(msg, &mut model.scan_progress, &mut orders.proxy(Msg::ScanProgress));
+        }
         Msg::Noop => {}
     }
 }
@@ -317,7 +333,9 @@ pub(crate) fn view(model: &Model, all_locks: &Locks) -> Node<Msg> {
     div![
         stratagem_config(model, locked),
         scan_stratagem_button::view(&model.scan_stratagem_button).map_msg(Msg::ScanStratagemButton),
+        scan_progress::view(&model.scan_progress).map_msg(Msg::ScanProgress),
         inode_table::view(&model.inode_table).map_msg(Msg::InodeTable),
+        heatmap::view(&model.heatmap).map_msg(Msg::Heatmap),
         caption_wrapper(
             "inode Usage Distribution",
             Some(&last_scan),
@@ -465,4 +483,5 @@ pub fn init(cache: &ArcCache, model: &Model, orders: &mut impl Orders<Msg, GMsg>
     ));
 
     orders.proxy(Msg::InodeTable).send_msg(inode_table::Msg::FetchInodes);
+    orders.proxy(Msg::Heatmap).send_msg(heatmap::Msg::Fetch);
 }
@@ -0,0 +1,200 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{generated::css_classes::C, sleep_with_handle, GMsg, RequestExt};
+use futures::channel::oneshot;
+use iml_graphql_queries::{stratagem, Response};
+use iml_wire_types::stratagem::{DeviceScanProgress, ScanPhase, ScanProgress};
+use number_formatter::format_number;
+use seed::{prelude::*, *};
+use std::time::Duration;
+
+/// How often progress is polled while a scan is running.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct Model {
+    command_id: Option<i32>,
+    progress: Option<ScanProgress>,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Watch(i32),
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<stratagem::scan_progress::Resp>>),
+    Noop,
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Watch(id) => {
+            model.command_id = Some(id);
+            model.progress = None;
+
+            orders.send_msg(Msg::Fetch);
+        }
+        Msg::Fetch => {
+            model.cancel = None;
+
+            if let Some(id) = model.command_id {
+                let query = stratagem::scan_progress::build(id);
+                let req = fetch::Request::graphql_query(&query);
+
+                orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+            }
+        }
+        Msg::Fetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    let x = x.data.stratagem.scan_progress;
+
+                    // Stale responses for a previous scan are dropped.
+                    if model.command_id != Some(x.command_id) {
+                        return;
+                    }
+
+                    let done = finished(&x);
+
+                    model.progress = Some(x);
+
+                    if done {
+                        return;
+                    }
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving scan progress", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving scan progress", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(POLL_INTERVAL, Msg::Fetch, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::Noop => {}
+    }
+}
+
+fn finished(x: &ScanProgress) -> bool {
+    x.devices.iter().all(|x| match x.phase {
+        ScanPhase::Complete | ScanPhase::Failed | ScanPhase::Cancelled => true,
+        ScanPhase::Pending | ScanPhase::Scanning | ScanPhase::Streaming => false,
+    })
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    let x = match model.progress.as_ref() {
+        Some(x) => x,
+        None => return empty![],
+    };
+
+    let pct = if x.devices_total > 0 {
+        100 * x.devices_completed / x.devices_total
+    } else {
+        0
+    };
+
+    div![
+        class![
+            C.bg_white,
+            C.border,
+            C.border_b,
+            C.border_t,
+            C.mt_24,
+            C.rounded_lg,
+            C.shadow,
+        ],
+        div![
+            class![C.flex, C.justify_between, C.px_6, C._mb_px, C.bg_gray_200],
+            h3![class![C.py_4, C.font_normal, C.text_lg], "Scan Progress"],
+            p![
+                class![C.py_4, C.text_gray_600],
+                format!(
+                    "{} of {} devices scanned, {} entries",
+                    x.devices_completed,
+                    x.devices_total,
+                    format_number(x.entries_scanned, None)
+                )
+            ]
+        ],
+        div![
+            class![C.px_6, C.py_4],
+            div![
+                class![C.w_full, C.h_2, C.bg_gray_300, C.rounded_full],
+                div![
+                    class![C.h_2, C.bg_blue_500, C.rounded_full],
+                    style! { St::Width => format!("{}%", pct) }
+                ]
+            ],
+            ul![class![C.mt_4], x.devices.iter().map(device_view)]
+        ]
+    ]
+}
+
+fn device_view(x: &DeviceScanProgress) -> Node<Msg> {
+    li![
+        class![C.flex, C.justify_between, C.py_1, C.text_sm],
+        span![format!("{} on {}", x.device, x.fqdn)],
+        span![
+            class![C.text_gray_600],
+            match x.entries_scanned {
+                Some(n) => format!("{}, {} entries", phase_label(x.phase), format_number(n, None)),
+                None => phase_label(x.phase).to_string(),
+            }
+        ]
+    ]
+}
+
+fn phase_label(x: ScanPhase) -> &'static str {
+    match x {
+        ScanPhase::Pending => "Waiting",
+        ScanPhase::Scanning => "Scanning",
+        ScanPhase::Streaming => "Sending results",
+        ScanPhase::Complete => "Complete",
+        ScanPhase::Failed => "Failed",
+        ScanPhase::Cancelled => "Cancelled",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(phase: ScanPhase) -> DeviceScanProgress {
+        DeviceScanProgress {
+            fqdn: "mds1.local".into(),
+            device: "/dev/mapper/mpatha".into(),
+            phase,
+            entries_scanned: None,
+            started_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn progress(devices: Vec<DeviceScanProgress>) -> ScanProgress {
+        ScanProgress {
+            command_id: 1,
+            devices_total: devices.len() as i32,
+            devices_completed: 0,
+            entries_scanned: 0.0,
+            devices,
+        }
+    }
+
+    #[test]
+    fn test_finished() {
+        assert!(!finished(&progress(vec![
+            device(ScanPhase::Complete),
+            device(ScanPhase::Streaming)
+        ])));
+        assert!(finished(&progress(vec![
+            device(ScanPhase::Complete),
+            device(ScanPhase::Failed)
+        ])));
+    }
+}
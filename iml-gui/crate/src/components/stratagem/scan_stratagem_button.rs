@@ -8,6 +8,7 @@ use crate::{
     generated::css_classes::C,
     GMsg,
 };
+use iml_graphql_queries::Response;
 use seed::{prelude::*, *};
 
 #[derive(Default)]
@@ -31,6 +32,20 @@ pub enum Msg {
     ScanStratagemModal(Box<scan_stratagem_modal::Msg>),
 }
 
+impl Msg {
+    /// The id of the command a scan was just started with, if this is the message carrying it.
+    pub fn started_command(&self) -> Option<i32> {
+        match self {
+            Self::ScanStratagemModal(x) => match x.as_ref() {
+                scan_stratagem_modal::Msg::Scanned(Ok(Response::Data(x))) => {
+                    Some(x.data.stratagem.run_fast_file_scan.id)
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
 pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
     match msg {
         Msg::ScanStratagemModal(msg) => {
@@ -68,6 +68,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                     .value_as_ms()
                     .map(Duration::from_millis)
                     .map(|x| humantime::format_duration(x).to_string()),
+                None,
             );
 
             let req = fetch::Request::graphql_query(&query);
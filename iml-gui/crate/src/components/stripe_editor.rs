@@ -0,0 +1,434 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Shows and edits the default layout of new files in a filesystem,
+//! a plain stripe count and size or the components of a PFL.
+
+use crate::{
+    components::{command_modal, font_awesome, toast},
+    extensions::MergeAttrs as _,
+    generated::css_classes::C,
+    sleep_with_handle, GMsg, RequestExt,
+};
+use futures::channel::oneshot;
+use iml_graphql_queries::{filesystem as fs_queries, Response};
+use iml_wire_types::stripe::{StripeComponent, StripeComponentInput};
+use number_formatter as nf;
+use seed::{prelude::*, *};
+use std::{sync::Arc, time::Duration};
+
+const KIB: f64 = 1024.;
+const MIB: f64 = 1024. * 1024.;
+
+/// A component being edited. Extents are in MiB, empty for the end of the file,
+/// stripe sizes in KiB.
+#[derive(Clone, Debug, Default)]
+struct Row {
+    extent_end: String,
+    stripe_count: String,
+    stripe_size: String,
+    pool: String,
+}
+
+impl From<&StripeComponent> for Row {
+    fn from(x: &StripeComponent) -> Self {
+        Self {
+            extent_end: x.extent_end.map(|x| (x / MIB).to_string()).unwrap_or_default(),
+            stripe_count: x.stripe_count.to_string(),
+            stripe_size: (x.stripe_size / KIB).to_string(),
+            pool: x.pool.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl Row {
+    fn parse(&self, n: usize) -> Result<StripeComponentInput, String> {
+        let number = |x: &str, name: &str| {
+            x.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("Component {}: {} is not a number", n, name))
+        };
+
+        let extent_end = match self.extent_end.trim() {
+            "" => None,
+            x => Some((number(x, "the extent end")? * MIB).round()),
+        };
+
+        let stripe_count = self
+            .stripe_count
+            .trim()
+            .parse()
+            .map_err(|_| format!("Component {}: the stripe count is not a whole number", n))?;
+
+        let pool = match self.pool.trim() {
+            "" => None,
+            x => Some(x.to_string()),
+        };
+
+        Ok(StripeComponentInput {
+            extent_end,
+            stripe_count,
+            stripe_size: (number(&self.stripe_size, "the stripe size")? * KIB).round(),
+            pool,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Field {
+    ExtentEnd,
+    StripeCount,
+    StripeSize,
+    Pool,
+}
+
+pub struct Model {
+    fs_name: String,
+    components: Option<Vec<StripeComponent>>,
+    error: Option<String>,
+    rows: Vec<Row>,
+    editing: bool,
+    submitting: bool,
+    toast: Option<toast::Model>,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+impl Model {
+    pub(crate) fn new(fs_name: impl ToString) -> Self {
+        Self {
+            fs_name: fs_name.to_string(),
+            components: None,
+            error: None,
+            rows: vec![],
+            editing: false,
+            submitting: false,
+            toast: None,
+            cancel: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Fetch,
+    Fetched(Box<fetch::ResponseDataResult<Response<fs_queries::default_stripe::Resp>>>),
+    Edit,
+    CancelEdit,
+    AddComponent,
+    RemoveComponent(usize),
+    Changed(usize, Field, String),
+    Submit,
+    Submitted(Box<fetch::ResponseDataResult<Response<fs_queries::set_default_stripe::Resp>>>),
+    Toast(toast::Msg),
+    Noop,
+}
+
+pub fn init(orders: &mut impl Orders<Msg, GMsg>) {
+    orders.send_msg(Msg::Fetch);
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Fetch => {
+            model.cancel = None;
+            let query = fs_queries::default_stripe::build(&model.fs_name);
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(|x| Msg::Fetched(Box::new(x))));
+        }
+        Msg::Fetched(x) => {
+            match *x {
+                Ok(Response::Data(x)) => {
+                    model.components = Some(x.data.default_stripe.components);
+                    model.error = None;
+                }
+                Ok(Response::Errors(e)) => {
+                    error!(
+                        "An error occurred while retrieving the default file layout of filesystem",
+                        model.fs_name, e
+                    );
+
+                    model.error = e.messages().next().map(String::from);
+                }
+                Err(err) => {
+                    error!(
+                        "An error occurred while retrieving the default file layout of filesystem",
+                        model.fs_name, err
+                    );
+                    orders.skip();
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(Duration::from_secs(60), Msg::Fetch, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::Edit => {
+            model.rows = model.components.iter().flatten().map(Row::from).collect();
+            model.editing = true;
+            model.toast = None;
+        }
+        Msg::CancelEdit => {
+            model.editing = false;
+        }
+        Msg::AddComponent => {
+            // The new component takes over the end of the file from the last one
+            if let Some(x) = model.rows.last_mut() {
+                if x.extent_end.trim().is_empty() {
+                    x.extent_end = "1024".into();
+                }
+            }
+
+            model.rows.push(Row {
+                extent_end: String::new(),
+                stripe_count: "1".into(),
+                stripe_size: "1024".into(),
+                pool: String::new(),
+            });
+        }
+        Msg::RemoveComponent(i) => {
+            if i < model.rows.len() {
+                model.rows.remove(i);
+            }
+
+            if let Some(x) = model.rows.last_mut() {
+                x.extent_end = String::new();
+            }
+        }
+        Msg::Changed(i, field, value) => {
+            if let Some(x) = model.rows.get_mut(i) {
+                match field {
+                    Field::ExtentEnd => x.extent_end = value,
+                    Field::StripeCount => x.stripe_count = value,
+                    Field::StripeSize => x.stripe_size = value,
+                    Field::Pool => x.pool = value,
+                }
+            }
+        }
+        Msg::Submit => {
+            if model.submitting {
+                return;
+            }
+
+            let components: Result<Vec<_>, _> = model.rows.iter().enumerate().map(|(i, x)| x.parse(i + 1)).collect();
+
+            let components = match components {
+                Ok(x) => x,
+                Err(e) => {
+                    model.toast = Some(toast::Model::Error(e));
+
+                    return;
+                }
+            };
+
+            model.submitting = true;
+            model.toast = None;
+
+            let query = fs_queries::set_default_stripe::build(&model.fs_name, components);
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(|x| Msg::Submitted(Box::new(x))));
+        }
+        Msg::Submitted(x) => {
+            model.submitting = false;
+
+            match *x {
+                Ok(Response::Data(x)) => {
+                    let x = command_modal::Input::Commands(vec![Arc::new(x.data.filesystem.set_default_stripe)]);
+
+                    orders.send_g_msg(GMsg::OpenCommandModal(x));
+
+                    model.editing = false;
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while setting the default file layout", e);
+
+                    let msg = e
+                        .messages()
+                        .next()
+                        .unwrap_or("Could not set the default file layout")
+                        .to_string();
+
+                    model.toast = Some(toast::Model::Error(msg));
+                }
+                Err(e) => {
+                    error!("An error occurred while setting the default file layout", e);
+
+                    model.toast = Some(toast::Model::Error("Could not set the default file layout".into()));
+                }
+            }
+        }
+        Msg::Toast(toast::Msg::Close) => {
+            model.toast = None;
+        }
+        Msg::Noop => {}
+    }
+}
+
+/// The current layout, with an edit button when `editable`.
+pub(crate) fn view(model: &Model, editable: bool) -> Node<Msg> {
+    if model.editing {
+        return edit_view(model);
+    }
+
+    let layout = match (&model.components, &model.error) {
+        (Some(xs), _) if xs.is_empty() => plain!["Not set, the defaults of the MDT apply"],
+        (Some(xs), _) => table![xs.iter().enumerate().map(|(i, x)| component_view(i, x))],
+        (None, Some(e)) => span![class![C.text_gray_600], e],
+        (None, None) => plain!["---"],
+    };
+
+    div![
+        class![C.flex, C.items_center],
+        layout,
+        if editable && model.components.is_some() {
+            button![
+                class![
+                    C.ml_4,
+                    C.px_3,
+                    C.py_1,
+                    C.rounded,
+                    C.text_white,
+                    C.bg_blue_500,
+                    C.hover__bg_blue_700
+                ],
+                simple_ev(Ev::Click, Msg::Edit),
+                "Edit"
+            ]
+        } else {
+            empty![]
+        }
+    ]
+}
+
+fn component_view<T>(i: usize, x: &StripeComponent) -> Node<T> {
+    let extent = match x.extent_end {
+        Some(x) => format!("Up to {}", nf::format_bytes(x, 0)),
+        None if i == 0 => "Whole file".to_string(),
+        None => "To the end of the file".to_string(),
+    };
+
+    let count = match x.stripe_count {
+        -1 => "all OSTs".to_string(),
+        0 => "the default number of OSTs".to_string(),
+        n => format!("{} OST(s)", n),
+    };
+
+    let size = if x.stripe_size == 0. {
+        "default".to_string()
+    } else {
+        nf::format_bytes(x.stripe_size, 0)
+    };
+
+    tr![
+        td![class![C.pr_4], extent],
+        td![class![C.pr_4], format!("Over {}, {} stripes", count, size)],
+        td![
+            class![C.text_gray_600],
+            x.pool.as_ref().map(|x| format!("pool {}", x)).unwrap_or_default()
+        ],
+    ]
+}
+
+fn edit_view(model: &Model) -> Node<Msg> {
+    let input_cls = class![
+        C.appearance_none,
+        C.focus__outline_none,
+        C.focus__shadow_outline,
+        C.px_3,
+        C.py_1,
+        C.rounded_sm,
+        C.text_gray_800,
+        C.bg_white,
+        C.w_32,
+    ];
+
+    let btn_cls = class![C.px_3, C.py_1, C.rounded, C.text_white];
+
+    let last = model.rows.len().saturating_sub(1);
+
+    div![
+        if model.rows.is_empty() {
+            p![class![C.py_1], "No default, the defaults of the MDT apply"]
+        } else {
+            table![
+                tr![
+                    th![class![C.pr_2, C.text_left, C.font_normal], "Extent End (MiB)"],
+                    th![class![C.pr_2, C.text_left, C.font_normal], "Stripe Count"],
+                    th![class![C.pr_2, C.text_left, C.font_normal], "Stripe Size (KiB)"],
+                    th![class![C.pr_2, C.text_left, C.font_normal], "OST Pool"],
+                    th![],
+                ],
+                model.rows.iter().enumerate().map(|(i, x)| {
+                    let field = |field: Field, value: &str, placeholder: &str| {
+                        td![
+                            class![C.pr_2, C.py_1],
+                            input![
+                                &input_cls,
+                                attrs! {
+                                    At::Type => "text",
+                                    At::Placeholder => placeholder,
+                                    At::Value => value,
+                                },
+                                input_ev(Ev::Input, move |v| Msg::Changed(i, field, v)),
+                            ]
+                        ]
+                    };
+
+                    tr![
+                        if i == last {
+                            td![class![C.pr_2, C.py_1, C.text_gray_600], "End of file"]
+                        } else {
+                            field(Field::ExtentEnd, &x.extent_end, "e.g. 64")
+                        },
+                        field(Field::StripeCount, &x.stripe_count, "-1 for all"),
+                        field(Field::StripeSize, &x.stripe_size, "e.g. 1024"),
+                        field(Field::Pool, &x.pool, "None"),
+                        td![
+                            class![C.py_1],
+                            a![
+                                class![C.text_red_500, C.hover__text_red_700, C.cursor_pointer],
+                                attrs! { At::Title => "Remove component" },
+                                font_awesome(class![C.w_4, C.h_4, C.inline], "times"),
+                                simple_ev(Ev::Click, Msg::RemoveComponent(i)),
+                            ]
+                        ],
+                    ]
+                })
+            ]
+        },
+        div![
+            class![C.flex, C.items_center, C.mt_2],
+            button![
+                &btn_cls,
+                class![C.bg_gray_500, C.hover__bg_gray_700],
+                simple_ev(Ev::Click, Msg::AddComponent),
+                "Add Component"
+            ],
+            button![
+                &btn_cls,
+                class![
+                    C.ml_2,
+                    C.bg_blue_500,
+                    C.hover__bg_blue_700,
+                    C.cursor_not_allowed => model.submitting,
+                    C.opacity_50 => model.submitting,
+                ],
+                attrs! { At::Disabled => model.submitting.as_at_value() },
+                simple_ev(Ev::Click, Msg::Submit),
+                if model.submitting { "Applying..." } else { "Apply" }
+            ],
+            button![
+                &btn_cls,
+                class![C.ml_2, C.bg_gray_500, C.hover__bg_gray_700],
+                simple_ev(Ev::Click, Msg::CancelEdit),
+                "Cancel"
+            ],
+            match model.toast.as_ref() {
+                Some(x) => toast::view(x).map_msg(Msg::Toast).merge_attrs(class![C.ml_4]),
+                None => empty![],
+            }
+        ]
+    ]
+}
@@ -5,8 +5,9 @@
 use crate::{components::paging, extensions::MergeAttrs, generated::css_classes::C};
 use seed::{prelude::*, Attrs, *};
 
+/// Tables stack into one card per row on phones. Give cells a heading there with `labelled`.
 pub fn wrapper_cls() -> Attrs {
-    class![C.table_auto, C.w_full]
+    class![C.table_auto, C.w_full, C.table_cards]
 }
 
 pub fn wrapper_view<T>(children: impl View<T>) -> Node<T> {
@@ -60,6 +61,11 @@ pub fn td_center<T>(children: impl View<T>) -> Node<T> {
     td_view(children).merge_attrs(class![C.text_center])
 }
 
+/// Labels a cell with its column, shown above the cell when the table is a stack of cards.
+pub fn labelled<T>(td: Node<T>, label: &str) -> Node<T> {
+    td.merge_attrs(attrs! { At::from("data-label") => label })
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct SortBy<T>(pub T);
 
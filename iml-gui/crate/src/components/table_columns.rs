@@ -0,0 +1,326 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    components::{dropdown, font_awesome, Placement},
+    generated::css_classes::C,
+    GMsg, RequestExt,
+};
+use iml_graphql_queries::{user_preferences, Response};
+use seed::{prelude::*, *};
+
+/// A table column that can be hidden or reordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub id: &'static str,
+    pub label: &'static str,
+}
+
+/// What gets persisted for a table. Unknown ids are ignored when loading,
+/// so columns can be added or removed without invalidating saved preferences.
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Prefs {
+    order: Vec<String>,
+    hidden: Vec<String>,
+}
+
+/// Column visibility and order for a table, persisted per user under `table.{key}`.
+#[derive(Debug)]
+pub struct Model {
+    key: &'static str,
+    defaults: &'static [Column],
+    order: Vec<Column>,
+    hidden: Vec<&'static str>,
+    dropdown: dropdown::Model,
+}
+
+impl Model {
+    pub fn new(key: &'static str, defaults: &'static [Column]) -> Self {
+        Self {
+            key,
+            defaults,
+            order: defaults.to_vec(),
+            hidden: vec![],
+            dropdown: dropdown::Model::default(),
+        }
+    }
+    /// Ids of the visible columns, in display order.
+    pub fn visible(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.order
+            .iter()
+            .map(|x| x.id)
+            .filter(move |x| !self.hidden.contains(x))
+    }
+    fn pref_key(&self) -> String {
+        format!("table.{}", self.key)
+    }
+    fn prefs(&self) -> Prefs {
+        Prefs {
+            order: self.order.iter().map(|x| x.id.to_string()).collect(),
+            hidden: self.hidden.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+    fn apply(&mut self, prefs: Prefs) {
+        let find = |id: &str| self.defaults.iter().find(|x| x.id == id).copied();
+
+        let mut order: Vec<Column> = vec![];
+
+        for x in prefs.order.iter().filter_map(|x| find(x)) {
+            if !order.contains(&x) {
+                order.push(x);
+            }
+        }
+
+        for x in self.defaults {
+            if !order.contains(x) {
+                order.push(*x);
+            }
+        }
+
+        let mut hidden: Vec<_> = prefs.hidden.iter().filter_map(|x| find(x)).map(|x| x.id).collect();
+
+        // Never leave a table without any columns.
+        if hidden.len() >= order.len() {
+            hidden.clear();
+        }
+
+        self.order = order;
+        self.hidden = hidden;
+    }
+    fn move_by(&mut self, id: &str, offset: isize) {
+        let idx = match self.order.iter().position(|x| x.id == id) {
+            Some(x) => x as isize,
+            None => return,
+        };
+
+        let next = idx + offset;
+
+        if next >= 0 && (next as usize) < self.order.len() {
+            self.order.swap(idx as usize, next as usize);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Dropdown(dropdown::Msg),
+    Fetched(fetch::ResponseDataResult<Response<user_preferences::get::Resp>>),
+    Toggle(&'static str),
+    MoveUp(&'static str),
+    MoveDown(&'static str),
+    Reset,
+    Saved(fetch::ResponseDataResult<Response<user_preferences::set::Resp>>),
+    Noop,
+}
+
+/// Loads the saved preferences for the table.
+pub fn init(model: &Model, orders: &mut impl Orders<Msg, GMsg>) {
+    let query = user_preferences::get::build(model.pref_key());
+    let req = fetch::Request::graphql_query(&query);
+
+    orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Dropdown(msg) => {
+            dropdown::update(msg, &mut model.dropdown);
+        }
+        Msg::Fetched(x) => match x {
+            Ok(Response::Data(x)) => {
+                if let Some(x) = x.data.user_preferences.get {
+                    match serde_json::from_str(&x) {
+                        Ok(prefs) => model.apply(prefs),
+                        Err(e) => log!(format!("Ignoring invalid column preferences for {}: {}", model.key, e)),
+                    }
+                }
+            }
+            Ok(Response::Errors(e)) => {
+                log!("Could not load column preferences", e);
+            }
+            Err(e) => {
+                log!("Could not load column preferences", e);
+                orders.skip();
+            }
+        },
+        Msg::Toggle(id) => {
+            if let Some(idx) = model.hidden.iter().position(|x| *x == id) {
+                model.hidden.remove(idx);
+            } else if model.visible().count() > 1 {
+                model.hidden.push(id);
+            }
+
+            save(model, orders);
+        }
+        Msg::MoveUp(id) => {
+            model.move_by(id, -1);
+
+            save(model, orders);
+        }
+        Msg::MoveDown(id) => {
+            model.move_by(id, 1);
+
+            save(model, orders);
+        }
+        Msg::Reset => {
+            model.order = model.defaults.to_vec();
+            model.hidden.clear();
+
+            save(model, orders);
+        }
+        Msg::Saved(x) => match x {
+            Ok(Response::Data(_)) => {}
+            Ok(Response::Errors(e)) => {
+                error!("An error occurred while saving column preferences", e);
+            }
+            Err(e) => {
+                error!("An error occurred while saving column preferences", e);
+                orders.skip();
+            }
+        },
+        Msg::Noop => {}
+    }
+}
+
+fn save(model: &Model, orders: &mut impl Orders<Msg, GMsg>) {
+    let value = match serde_json::to_string(&model.prefs()) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Could not serialize column preferences", e);
+            return;
+        }
+    };
+
+    let query = user_preferences::set::build(model.pref_key(), value);
+    let req = fetch::Request::graphql_query(&query);
+
+    orders.perform_cmd(req.fetch_json_data(Msg::Saved));
+}
+
+/// A button that opens a menu to show, hide and reorder the columns of the table.
+pub fn view(model: &Model) -> Node<Msg> {
+    let open = model.dropdown.is_open();
+    let last = model.order.len().saturating_sub(1);
+
+    let btn = button![
+        class![
+            C.bg_blue_700 => open,
+            C.bg_transparent => !open,
+            C.border_blue_500 => !open,
+            C.border_transparent => open,
+            C.border,
+            C.focus__outline_none,
+            C.hover__bg_blue_700,
+            C.hover__border_transparent,
+            C.hover__text_white,
+            C.px_3,
+            C.py_1,
+            C.rounded_full,
+            C.text_blue_500 => !open,
+            C.text_sm,
+            C.text_white => open,
+        ],
+        font_awesome(class![C.w_3, C.h_3, C.inline, C._mt_1, C.mr_1], "columns"),
+        "Columns",
+        simple_ev(Ev::Blur, Msg::Dropdown(dropdown::Msg::Close)),
+        simple_ev(Ev::Click, Msg::Dropdown(dropdown::Msg::Toggle)),
+    ];
+
+    let items = model.order.iter().enumerate().map(|(idx, x)| {
+        let visible = !model.hidden.contains(&x.id);
+        let id = x.id;
+
+        dropdown::item_view(div![
+            class![C.flex, C.items_center, C.justify_between, C.whitespace_no_wrap],
+            span![
+                class![C.flex_grow, C.text_left],
+                font_awesome(class![C.w_3, C.h_3, C.inline, C.mr_2, C.invisible => !visible], "check"),
+                x.label,
+                simple_ev(Ev::Click, Msg::Toggle(id)),
+            ],
+            span![
+                class![C.ml_2],
+                font_awesome(
+                    class![C.w_3, C.h_3, C.inline, C.ml_1, C.invisible => idx == 0],
+                    "chevron-up"
+                )
+                .with_listener(simple_ev(Ev::Click, Msg::MoveUp(id))),
+                font_awesome(
+                    class![C.w_3, C.h_3, C.inline, C.ml_1, C.invisible => idx == last],
+                    "chevron-down"
+                )
+                .with_listener(simple_ev(Ev::Click, Msg::MoveDown(id))),
+            ]
+        ])
+        .with_listener(ev(Ev::MouseDown, |ev| {
+            ev.prevent_default();
+            Msg::Noop
+        }))
+    });
+
+    let reset = dropdown::item_view(a!["Reset to defaults"])
+        .with_listener(ev(Ev::MouseDown, |ev| {
+            ev.prevent_default();
+            Msg::Noop
+        }))
+        .with_listener(simple_ev(Ev::Click, Msg::Reset));
+
+    span![
+        class![C.relative, C.z_10],
+        btn,
+        dropdown::wrapper_view(
+            Placement::Bottom,
+            open,
+            items.chain(std::iter::once(reset)).collect::<Vec<_>>()
+        )
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: &[Column] = &[
+        Column { id: "a", label: "A" },
+        Column { id: "b", label: "B" },
+        Column { id: "c", label: "C" },
+    ];
+
+    fn prefs(order: &[&str], hidden: &[&str]) -> Prefs {
+        Prefs {
+            order: order.iter().map(|x| x.to_string()).collect(),
+            hidden: hidden.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_apply_prefs() {
+        let mut model = Model::new("test", COLUMNS);
+
+        model.apply(prefs(&["c", "gone", "a", "c"], &["a", "gone"]));
+
+        assert_eq!(model.visible().collect::<Vec<_>>(), vec!["c", "b"]);
+        assert_eq!(model.prefs(), prefs(&["c", "a", "b"], &["a"]));
+    }
+
+    #[test]
+    fn test_apply_prefs_keeps_a_column() {
+        let mut model = Model::new("test", COLUMNS);
+
+        model.apply(prefs(&[], &["a", "b", "c"]));
+
+        assert_eq!(model.visible().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_move_by() {
+        let mut model = Model::new("test", COLUMNS);
+
+        model.move_by("a", -1);
+        model.move_by("c", -1);
+        model.move_by("b", 1);
+
+        assert_eq!(model.visible().collect::<Vec<_>>(), vec!["a", "c", "b"]);
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Tables that only render the rows in view.
+//!
+//! Every row has the same height, so the rows in view follow from how far the body is scrolled.
+//! Two spacer rows stand in for the rest, keeping the scrollbar true to the whole page.
+
+use crate::{extensions::NodeExt as _, generated::css_classes::C, GMsg};
+use seed::{prelude::*, *};
+use std::ops::Range;
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+/// The space between rows, as set by the `border-spacing` of the table.
+const ROW_SPACING: f64 = 10.;
+
+/// Rows rendered above and below the rows in view, so fast scrolling does not show blanks.
+const OVERSCAN: usize = 5;
+
+pub struct Model {
+    row_height: f64,
+    max_height: f64,
+    /// The first row in view
+    first: usize,
+}
+
+impl Model {
+    /// A table of rows `row_height` pixels high, scrolling once it is taller than `max_height`.
+    pub fn new(row_height: f64, max_height: f64) -> Self {
+        Self {
+            row_height,
+            max_height,
+            first: 0,
+        }
+    }
+    fn pitch(&self) -> f64 {
+        self.row_height + ROW_SPACING
+    }
+    /// The rows to render out of `total`.
+    pub fn window(&self, total: usize) -> Range<usize> {
+        let visible = (self.max_height / self.pitch()).ceil() as usize;
+
+        window(total, self.first, visible, OVERSCAN)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Scrolled(f64),
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Scrolled(top) => {
+            let first = (top.max(0.) / model.pitch()) as usize;
+
+            // Scrolling within a row changes nothing that is rendered.
+            if first == model.first {
+                orders.skip();
+            } else {
+                model.first = first;
+            }
+        }
+    }
+}
+
+/// `visible` rows from `first` on, with `overscan` rows to either side, clamped to `total`.
+fn window(total: usize, first: usize, visible: usize, overscan: usize) -> Range<usize> {
+    let start = first.saturating_sub(overscan).min(total);
+    let end = (first + visible + overscan).min(total);
+
+    start..end
+}
+
+fn spacer<T>(rows: usize, pitch: f64) -> Node<T> {
+    if rows == 0 {
+        return empty![];
+    }
+
+    // The spacer is a row itself, so it gets one spacing from the table.
+    tr![style! { St::Height => px(rows as f64 * pitch - ROW_SPACING) }]
+}
+
+/// Renders `head` and the rows of `total` that are in view.
+/// `rows` is given the range to render and returns one `tr` per row in it.
+/// Unlike `table::wrapper_view`, rows do not stack into cards on phones, as cards differ in height.
+pub fn view<T: 'static>(
+    model: &Model,
+    on_scroll: fn(Msg) -> T,
+    head: Node<T>,
+    total: usize,
+    rows: impl FnOnce(Range<usize>) -> Vec<Node<T>>,
+) -> Node<T> {
+    let range = model.window(total);
+    let pitch = model.pitch();
+
+    let (before, after) = (range.start, total - range.end);
+
+    div![
+        class![C.overflow_y_auto],
+        style! { St::MaxHeight => px(model.max_height) },
+        ev(Ev::Scroll, move |event| {
+            let top = event
+                .target()
+                .map(|x| x.unchecked_into::<Element>().scroll_top())
+                .unwrap_or(0);
+
+            on_scroll(Msg::Scrolled(top as f64))
+        }),
+        table![
+            class![C.table_auto, C.w_full],
+            style! {
+                St::BorderSpacing => px(ROW_SPACING),
+                St::BorderCollapse => "initial"
+            },
+            head,
+            tbody![
+                spacer(before, pitch),
+                rows(range)
+                    .into_iter()
+                    .map(|x| x.with_style(St::Height, px(model.row_height)))
+                    .collect::<Vec<_>>(),
+                spacer(after, pitch),
+            ]
+        ]
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window() {
+        assert_eq!(window(1000, 0, 10, 5), 0..15);
+        assert_eq!(window(1000, 100, 10, 5), 95..115);
+        assert_eq!(window(1000, 995, 10, 5), 990..1000);
+        assert_eq!(window(8, 0, 10, 5), 0..8);
+        assert_eq!(window(0, 3, 10, 5), 0..0);
+    }
+
+    #[test]
+    fn test_window_after_shrink() {
+        let mut model = Model::new(40., 500.);
+
+        model.first = 900;
+
+        assert_eq!(model.window(100), 100..100);
+    }
+}
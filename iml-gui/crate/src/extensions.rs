@@ -3,6 +3,7 @@
 // license that can be found in the LICENSE file.
 
 use crate::auth::csrf_token;
+use iml_graphql_queries::Query;
 use iml_wire_types::{GroupType, Session};
 use seed::{fetch, prelude::*, *};
 
@@ -53,6 +54,9 @@ pub(crate) trait RequestExt: Sized {
     fn api_query(path: impl ToString, args: impl serde::Serialize) -> Result<Self, serde_urlencoded::ser::Error>;
     fn api_item(path: impl ToString, item: impl ToString) -> Self;
     fn graphql_query<T: serde::Serialize>(x: &T) -> Self;
+    /// Sends a query with `GET`, so the browser can revalidate
+    /// the response it already has instead of downloading it again.
+    fn graphql_get<T: serde::Serialize>(x: &Query<T>) -> Result<Self, serde_urlencoded::ser::Error>;
     fn with_auth(self: Self) -> Self;
 }
 
@@ -74,6 +78,17 @@ impl RequestExt for fetch::Request {
             .method(fetch::Method::Post)
             .send_json(x)
     }
+    fn graphql_get<T: serde::Serialize>(x: &Query<T>) -> Result<Self, serde_urlencoded::ser::Error> {
+        let mut xs = vec![("query", x.query.clone())];
+
+        if let Some(vars) = x.variables.as_ref().and_then(|x| serde_json::to_string(x).ok()) {
+            xs.push(("variables", vars));
+        }
+
+        let qs = serde_urlencoded::to_string(xs)?;
+
+        Ok(Self::new(format!("/graphql?{}", qs)).with_auth())
+    }
     fn with_auth(self) -> Self {
         match csrf_token() {
             Some(csrf) => self.header("X-CSRFToken", &csrf),
@@ -4931,6 +4931,7 @@ pub struct CssClasses<'a> {
     pub toggle: &'a str,
     pub spin: &'a str,
     pub pulse: &'a str,
+    pub table_cards: &'a str,
     pub sm__container: &'a str,
     pub sm__space_y_0: &'a str,
     pub sm__space_x_0: &'a str,
@@ -52064,6 +52065,20 @@ pub static C: CssClasses = CssClasses {
     */
     pulse: "pulse",
 
+    /**
+        display: block;    @media (max-width: 768px)
+        width: 100%;    @media (max-width: 768px)
+        display: none;    @media (max-width: 768px)
+        margin-bottom: 1rem;    @media (max-width: 768px)
+        border-radius: 0.25rem;    @media (max-width: 768px)
+        background-color: #f7fafc;    @media (max-width: 768px)
+        text-align: left;    @media (max-width: 768px)
+        content: attr(data-label);    @media (max-width: 768px)
+        color: #4a5568;    @media (max-width: 768px)
+        font-weight: 600;    @media (max-width: 768px)
+    */
+    table_cards: "table-cards",
+
     /**
         width: 100%;    @media (min-width: 569px)
         max-width: 569px;    @media (min-width: 569px)
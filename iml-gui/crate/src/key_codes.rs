@@ -4,3 +4,6 @@
 
 pub const ENTER: u32 = 13;
 pub const ESC: u32 = 27;
+pub const UP: u32 = 38;
+pub const DOWN: u32 = 40;
+pub const K: u32 = 75;
@@ -29,7 +29,8 @@ mod watch_state;
 mod test_utils;
 
 use components::{
-    breadcrumbs, command_modal, date, font_awesome, font_awesome_outline, loading, restrict, stratagem, tree,
+    banner, breadcrumbs, command_modal, command_palette, date, font_awesome, font_awesome_outline, fs_context,
+    health_banner, loading, maintenance_banner, modal, restrict, session_timeout, stratagem, tree,
     update_activity_health, ActivityHealth,
 };
 pub(crate) use extensions::*;
@@ -153,16 +154,22 @@ impl PartialEq for BreadCrumb {
 pub struct Model {
     activity_health: ActivityHealth,
     auth: auth::Model,
+    banner: banner::Model,
     breadcrumbs: breadcrumbs::BreadCrumbs<BreadCrumb>,
     breakpoint_size: breakpoints::Size,
     command_modal: command_modal::Model,
+    command_palette: command_palette::Model,
     conf: Conf,
+    fs_context: fs_context::Model,
+    health_banner: health_banner::Model,
     loading: Loading,
     locks: warp_drive::Locks,
+    maintenance_banner: maintenance_banner::Model,
     manage_menu_state: WatchState,
     menu_visibility: Visibility,
     notification: notification::Model,
     page: Page,
+    palette_action: Option<command_palette::Action>,
     records: warp_drive::ArcCache,
     route: Route<'static>,
     side_width_percentage: f32,
@@ -212,13 +219,19 @@ fn after_mount(url: Url, orders: &mut impl Orders<Msg, GMsg>) -> AfterMount<Mode
 
     orders.perform_cmd(fut);
 
+    let breakpoint_size = breakpoints::size();
+
     AfterMount::new(Model {
         activity_health: ActivityHealth::default(),
         auth: auth::Model::default(),
+        banner: banner::Model::default(),
         breadcrumbs: breadcrumbs::BreadCrumbs::default(),
-        breakpoint_size: breakpoints::size(),
+        breakpoint_size,
         command_modal: command_modal::Model::default(),
+        command_palette: command_palette::Model::default(),
         conf: Conf::default(),
+        fs_context: fs_context::Model::default(),
+        health_banner: health_banner::Model::default(),
         loading: Loading {
             session: Some(session_tx),
             messages: Some(messages_tx),
@@ -226,10 +239,17 @@ fn after_mount(url: Url, orders: &mut impl Orders<Msg, GMsg>) -> AfterMount<Mode
             conf: Some(conf_tx),
         },
         locks: im::hashmap!(),
+        maintenance_banner: maintenance_banner::Model::default(),
         manage_menu_state: WatchState::default(),
-        menu_visibility: Visible,
+        // The nav starts collapsed on phones and tablets
+        menu_visibility: if breakpoint_size < breakpoints::Size::LG {
+            Hidden
+        } else {
+            Visible
+        },
         notification: notification::Model::default(),
         page: Page::AppLoading,
+        palette_action: None,
         records: warp_drive::ArcCache::default(),
         route: url.into(),
         side_width_percentage: 20_f32,
@@ -299,21 +319,28 @@ fn sink(g_msg: GMsg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
 #[derive(Clone, Debug)]
 pub enum Msg {
     Auth(Box<auth::Msg>),
+    Banner(banner::Msg),
     CommandModal(command_modal::Msg),
+    CommandPalette(command_palette::Msg),
     EventSourceConnect(JsValue),
     EventSourceError(JsValue),
     EventSourceMessage(MessageEvent),
     FetchConf,
     FetchedConf(fetch::ResponseDataResult<Conf>),
+    FsContext(fs_context::Msg),
+    HealthBanner(health_banner::Msg),
     HideMenu,
     LoadPage,
     Locks(warp_drive::Locks),
+    MaintenanceBanner(maintenance_banner::Msg),
     ManageMenuState,
     Notification(notification::Msg),
     RecordChange(Box<warp_drive::RecordChange>),
     Records(Box<warp_drive::Cache>),
     RemoveRecord(warp_drive::RecordId),
     RouteChanged(Url),
+    SessionChange(i32),
+    Invalidate(String),
     StatusSection(status_section::Msg),
     SliderX(i32, f64),
     StartSliderTracking,
@@ -335,6 +362,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                 model.breadcrumbs.clear();
             }
 
+            // A collapsible nav is in the way of the page it just opened
+            if model.breakpoint_size < breakpoints::Size::LG {
+                model.menu_visibility = Hidden;
+            }
+
             orders.send_msg(Msg::LoadPage);
         }
         Msg::UpdatePageTitle => {
@@ -369,7 +401,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
         }
         Msg::LoadPage => {
             if model.loading.loaded() && !model.page.is_active(&model.route) {
-                if (model.route == Route::Snapshots && !model.conf.use_snapshots)
+                if (matches!(model.route, Route::Snapshots | Route::SnapshotCompare(_)) && !model.conf.use_snapshots)
                     || (model.route == Route::Stratagem && !model.conf.use_stratagem)
                 {
                     model.route = Route::NotFound;
@@ -378,9 +410,28 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                 model.page = (&model.records, &model.conf, &model.route).into();
                 orders.send_msg(Msg::UpdatePageTitle);
                 model.page.init(&model.records, &mut orders.proxy(Msg::Page));
+                model.page.set_fs_context(
+                    model.fs_context.selected(),
+                    &model.records,
+                    &mut orders.proxy(Msg::Page),
+                );
             } else {
                 orders.skip();
             }
+
+            if model.loading.loaded() {
+                if model.auth.get_session().is_some() {
+                    orders.proxy(Msg::Banner).send_msg(banner::Msg::Start);
+                    orders.proxy(Msg::HealthBanner).send_msg(health_banner::Msg::Start);
+                    orders
+                        .proxy(Msg::MaintenanceBanner)
+                        .send_msg(maintenance_banner::Msg::Start);
+                }
+
+                if let Some(msg) = model.palette_action.take().and_then(palette_page_msg) {
+                    orders.send_msg(Msg::Page(msg));
+                }
+            }
         }
         Msg::EventSourceMessage(msg) => {
             let txt = msg.data().as_string().unwrap();
@@ -403,6 +454,8 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                     Msg::Records(Box::new(records))
                 }
                 warp_drive::Message::RecordChange(record_change) => Msg::RecordChange(Box::new(record_change)),
+                warp_drive::Message::SessionChange(user_id) => Msg::SessionChange(user_id),
+                warp_drive::Message::Invalidate(table) => Msg::Invalidate(table),
             };
 
             orders.skip().send_msg(msg);
@@ -575,16 +628,109 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
         Msg::Auth(msg) => {
             if let (Some(_), Some(session)) = (model.auth.get_session(), model.loading.session.take()) {
                 let _ = session.send(());
+
+                fs_context::init(&mut orders.proxy(Msg::FsContext));
             }
 
             auth::update(*msg, &mut model.auth, &mut orders.proxy(|x| Msg::Auth(Box::new(x))));
         }
+        Msg::SessionChange(user_id) => {
+            let user = model.auth.get_session().and_then(|x| x.user.as_ref());
+
+            // Fetch the session right away, so restricted views lock or unlock
+            // without waiting for the next poll.
+            if user.map(|x| x.id) == Some(user_id) {
+                orders.skip().send_msg(Msg::Auth(Box::new(auth::Msg::Fetch)));
+            } else {
+                orders.skip();
+            }
+        }
+        Msg::Invalidate(table) => {
+            orders
+                .skip()
+                .proxy(Msg::MaintenanceBanner)
+                .send_msg(maintenance_banner::Msg::Invalidate(table.clone()));
+
+            orders
+                .proxy(Msg::Banner)
+                .send_msg(banner::Msg::Invalidate(table.clone()));
+
+            model.page.invalidate(&table, &mut orders.proxy(Msg::Page));
+        }
         Msg::Page(msg) => {
             page::update(msg, &mut model.page, &model.records, &mut orders.proxy(Msg::Page));
         }
+        Msg::FsContext(msg) => {
+            let old = model.fs_context.selected().map(String::from);
+
+            fs_context::update(msg, &mut model.fs_context, &mut orders.proxy(Msg::FsContext));
+
+            let selected = model.fs_context.selected();
+
+            if selected != old.as_deref() {
+                model
+                    .page
+                    .set_fs_context(selected, &model.records, &mut orders.proxy(Msg::Page));
+
+                orders
+                    .proxy(Msg::StatusSection)
+                    .send_msg(status_section::Msg::SetFsContext(selected.map(String::from)));
+            }
+        }
+        Msg::Banner(msg) => {
+            banner::update(msg, &mut model.banner, &mut orders.proxy(Msg::Banner));
+        }
+        Msg::HealthBanner(msg) => {
+            health_banner::update(msg, &mut model.health_banner, &mut orders.proxy(Msg::HealthBanner));
+        }
+        Msg::MaintenanceBanner(msg) => {
+            maintenance_banner::update(
+                msg,
+                &mut model.maintenance_banner,
+                &mut orders.proxy(Msg::MaintenanceBanner),
+            );
+        }
         Msg::CommandModal(msg) => {
             command_modal::update(msg, &mut model.command_modal, &mut orders.proxy(Msg::CommandModal));
         }
+        Msg::CommandPalette(msg) => {
+            if let command_palette::Msg::Run(action) = &msg {
+                let route = match action {
+                    command_palette::Action::Navigate(_) => None,
+                    command_palette::Action::TakeSnapshot(_) => Some(Route::Snapshots),
+                    command_palette::Action::ScanStratagem(id) => Some(Route::Filesystem(id.into())),
+                };
+
+                // Actions that open something on another page are replayed once that page has loaded.
+                if let Some(route) = route {
+                    model.palette_action = Some(action.clone());
+                    orders.send_g_msg(GMsg::RouteChange(route.into()));
+                }
+            }
+
+            command_palette::update(
+                msg,
+                &model.records,
+                &model.conf,
+                &mut model.command_palette,
+                &mut orders.proxy(Msg::CommandPalette),
+            );
+        }
+    }
+}
+
+/// Maps a pending command palette action to the page message that carries it out.
+fn palette_page_msg(action: command_palette::Action) -> Option<page::Msg> {
+    match action {
+        command_palette::Action::Navigate(_) => None,
+        command_palette::Action::TakeSnapshot(fs_name) => {
+            Some(page::Msg::Snapshots(page::snapshot::take_snapshot(fs_name)))
+        }
+        command_palette::Action::ScanStratagem(_) => Some(page::Msg::Filesystem(page::filesystem::Msg::Stratagem(
+            stratagem::Msg::ScanStratagemButton(stratagem::scan_stratagem_button::Msg::ScanStratagemModal(Box::new(
+                stratagem::scan_stratagem_modal::Msg::Modal(modal::Msg::Open),
+            ))),
+        ))),
     }
 }
 
@@ -661,6 +807,9 @@ fn handle_record_change(
                             .send_msg(tree::Msg::Add(warp_drive::RecordId::OstPoolOsts(id)));
                     };
                 }
+                ArcRecord::PendingTarget(x) => {
+                    model.records.pending_target.insert(x.id, x);
+                }
                 ArcRecord::SfaDiskDrive(x) => {
                     model.records.sfa_disk_drive.insert(x.id, Arc::clone(&x));
                 }
@@ -723,6 +872,11 @@ fn handle_record_change(
                 }
                 ArcRecord::TargetRecord(x) => {
                     model.records.target_record.insert(x.id, Arc::clone(&x));
+
+                    orders
+                        .proxy(Msg::Page)
+                        .proxy(page::Msg::Target)
+                        .send_msg(page::target::Msg::UpdateTargetRecord(x));
                 }
                 ArcRecord::User(x) => {
                     model.records.user.insert(x.id, Arc::clone(&x));
@@ -822,6 +976,9 @@ pub fn main_panels(model: &Model, children: impl View<page::Msg>) -> impl View<M
             empty![]
         },
         page::partial::header::view(model).els(),
+        banner::view(&model.banner).map_msg(Msg::Banner),
+        health_banner::view(&model.health_banner).map_msg(Msg::HealthBanner),
+        maintenance_banner::view(&model.maintenance_banner).map_msg(Msg::MaintenanceBanner),
         // panel container
         div![
             class![C.flex, C.flex_wrap, C.flex_col, C.lg__flex_row, C.flex_grow],
@@ -831,6 +988,8 @@ pub fn main_panels(model: &Model, children: impl View<page::Msg>) -> impl View<M
                 GroupType::FilesystemAdministrators,
                 div![
                     class![
+                        C.hidden,
+                        C.lg__block,
                         C.flex_grow_0,
                         C.flex_shrink_0,
                         C.overflow_x_hidden,
@@ -891,7 +1050,7 @@ pub fn main_panels(model: &Model, children: impl View<page::Msg>) -> impl View<M
                 ],
                 // main content
                 div![
-                    class![C.flex_grow, C.overflow_x_auto, C.overflow_y_auto, C.p_6],
+                    class![C.flex_grow, C.overflow_x_auto, C.overflow_y_auto, C.p_2, C.md__p_6],
                     children.els().map_msg(Msg::Page)
                 ],
                 page::partial::footer::view(&model.conf).els(),
@@ -916,7 +1075,18 @@ fn view(model: &Model) -> Vec<Node<Msg>> {
     let nodes = match &model.page {
         Page::AppLoading => loading::view().els(),
         Page::About => main_panels(model, page::about::view(model).els().map_msg(page::Msg::About)).els(),
-        Page::Dashboard(page) => main_panels(model, page::dashboard::view(page).map_msg(page::Msg::Dashboard)).els(),
+        Page::Dashboard(page) => main_panels(
+            model,
+            page::dashboard::view(page, &model.maintenance_banner).map_msg(page::Msg::Dashboard),
+        )
+        .els(),
+        Page::CustomDashboard(page) => main_panels(
+            model,
+            page::custom_dashboard::view(page)
+                .els()
+                .map_msg(page::Msg::CustomDashboard),
+        )
+        .els(),
         Page::Filesystems(page) => main_panels(
             model,
             page::filesystems::view(&model.records, page, &model.locks, model.auth.get_session())
@@ -959,6 +1129,11 @@ fn view(model: &Model) -> Vec<Node<Msg>> {
             .els()
             .map_msg(|x| page::Msg::Login(Box::new(x)))
             .map_msg(Msg::Page),
+        Page::ManagerStatus(x) => main_panels(
+            model,
+            page::manager_status::view(x).els().map_msg(page::Msg::ManagerStatus),
+        )
+        .els(),
         Page::Mgts(x) => main_panels(
             model,
             page::mgts::view(&model.records, x, &model.locks, model.auth.get_session())
@@ -967,6 +1142,7 @@ fn view(model: &Model) -> Vec<Node<Msg>> {
         )
         .els(),
         Page::NotFound => page::not_found::view(model).els(),
+        Page::OnCall => main_panels(model, page::on_call::view(model).els().map_msg(page::Msg::OnCall)).els(),
         Page::OstPools => main_panels(model, page::ostpools::view(model).els().map_msg(page::Msg::OstPools)).els(),
         Page::OstPool(x) => main_panels(model, page::ostpool::view(x).els().map_msg(page::Msg::OstPool)).els(),
         Page::PowerControl => main_panels(
@@ -987,6 +1163,13 @@ fn view(model: &Model) -> Vec<Node<Msg>> {
             .map_msg(page::Msg::Servers),
         )
         .els(),
+        Page::ServerProfileCompare(x) => main_panels(
+            model,
+            page::server_profile_compare::view(x)
+                .els()
+                .map_msg(page::Msg::ServerProfileCompare),
+        )
+        .els(),
         Page::Server(x) => main_panels(
             model,
             page::server::view(
@@ -1008,6 +1191,12 @@ fn view(model: &Model) -> Vec<Node<Msg>> {
                 .map_msg(page::Msg::Target),
         )
         .els(),
+        Page::Roles(x) => main_panels(
+            model,
+            page::roles::view(x, &model.records).els().map_msg(page::Msg::Roles),
+        )
+        .els(),
+        Page::Sites(x) => main_panels(model, page::sites::view(x).els().map_msg(page::Msg::Sites)).els(),
         Page::Users => main_panels(model, page::users::view(&model.records).els().map_msg(page::Msg::Users)).els(),
         Page::User(x) => main_panels(model, page::user::view(x).els().map_msg(page::Msg::User)).els(),
         Page::Volumes(x) => main_panels(model, page::volumes::view(x).els().map_msg(page::Msg::Volumes)).els(),
@@ -1027,6 +1216,13 @@ fn view(model: &Model) -> Vec<Node<Msg>> {
                 .map_msg(page::Msg::Snapshots),
         )
         .els(),
+        Page::SnapshotCompare(x) => main_panels(
+            model,
+            page::snapshot_compare::view(&model.records, x)
+                .els()
+                .map_msg(page::Msg::SnapshotCompare),
+        )
+        .els(),
         Page::Stratagem(x) => main_panels(
             model,
             page::stratagem::view(x, model.auth.get_session())
@@ -1038,7 +1234,12 @@ fn view(model: &Model) -> Vec<Node<Msg>> {
 
     // command modal is the global singleton, therefore is being showed here
     let modal = command_modal::view(&model.command_modal).map_msg(Msg::CommandModal);
-    div![modal, nodes].els()
+    let palette = command_palette::view(&model.command_palette).map_msg(Msg::CommandPalette);
+    let idle_warning = match model.auth.idle_warning() {
+        Some(x) => session_timeout::view(x, Msg::Auth(Box::new(auth::Msg::KeepAlive))),
+        None => empty![],
+    };
+    div![idle_warning, modal, palette, nodes].els()
 }
 
 pub fn asset_path(asset: &str) -> String {
@@ -1059,6 +1260,19 @@ pub fn window_events(model: &Model) -> Vec<EventHandler<Msg>> {
         xs.push(simple_ev(Ev::MouseUp, Msg::StopSliderTracking));
     }
 
+    if model.auth.get_session().is_some() {
+        xs.push(keyboard_ev(Ev::KeyDown, |ev| {
+            if ev.key_code() == key_codes::K && (ev.ctrl_key() || ev.meta_key()) {
+                ev.prevent_default();
+
+                Msg::CommandPalette(command_palette::Msg::Open)
+            } else {
+                Msg::Auth(Box::new(auth::Msg::Activity))
+            }
+        }));
+        xs.push(simple_ev(Ev::MouseDown, Msg::Auth(Box::new(auth::Msg::Activity))));
+    }
+
     xs
 }
 
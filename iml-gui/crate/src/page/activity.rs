@@ -240,7 +240,7 @@ fn alert_icon_view(alert: &Alert) -> Node<Msg> {
     }
 }
 
-fn is_cmd(x: AlertRecordType) -> bool {
+pub(crate) fn is_cmd(x: AlertRecordType) -> bool {
     match x {
         AlertRecordType::CommandRunningAlert
         | AlertRecordType::CommandSuccessfulAlert
@@ -250,7 +250,7 @@ fn is_cmd(x: AlertRecordType) -> bool {
     }
 }
 
-fn alert_item_classes(alert: &Alert) -> (&str, &str, &str) {
+pub(crate) fn alert_item_classes(alert: &Alert) -> (&str, &str, &str) {
     match (alert.record_type, alert.severity) {
         (AlertRecordType::CommandRunningAlert, _) => (C.border_gray_500, C.text_gray_500, C.bg_gray_400),
         (AlertRecordType::CommandSuccessfulAlert, _) => (C.border_green_500, C.text_green_500, C.bg_green_400),
@@ -330,7 +330,7 @@ fn alert_item_view(
     ]
 }
 
-fn date_view<T>(sd: &date::Model, date: &str) -> Node<T> {
+pub(crate) fn date_view<T>(sd: &date::Model, date: &str) -> Node<T> {
     match chrono::DateTime::parse_from_rfc3339(date) {
         Ok(d) => date::view(sd, &d),
         Err(e) => {
@@ -0,0 +1,364 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    components::{
+        dashboard::{dashboard_container, performance_container},
+        datepicker,
+        grafana_chart::{self, create_chart_params, IML_METRICS_DASHBOARD_ID, IML_METRICS_DASHBOARD_NAME},
+        panel,
+    },
+    extensions::MergeAttrs as _,
+    generated::css_classes::C,
+    page::{filesystem, filesystems},
+    route::RouteId,
+    sleep_with_handle, GMsg, RequestExt, Route,
+};
+use futures::channel::oneshot;
+use iml_graphql_queries::{dashboards, filesystem as fs_queries, Response};
+use iml_wire_types::{
+    graphql::{Dashboard, FilesystemOverview, TargetStateCounts},
+    DashboardMetric, DashboardPanel, DashboardPanelKind,
+};
+use seed::{prelude::*, *};
+use std::{collections::HashMap, time::Duration};
+
+/// How often overview panels are refreshed.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+pub struct Model {
+    /// The dashboard to show. The first one when `None`
+    pub id: Option<i32>,
+    dashboards: Option<Vec<Dashboard>>,
+    overviews: HashMap<String, FilesystemOverview>,
+    /// The time range of every chart on the dashboard
+    date_picker: datepicker::Model,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+impl Model {
+    pub fn new(id: Option<i32>) -> Self {
+        Self {
+            id,
+            ..Default::default()
+        }
+    }
+    fn selected(&self) -> Option<&Dashboard> {
+        let xs = self.dashboards.as_ref()?;
+
+        match self.id {
+            Some(id) => xs.iter().find(|x| x.id == id),
+            None => xs.first(),
+        }
+    }
+    fn has_overview(&self) -> bool {
+        self.selected()
+            .map(|x| x.panels.iter().any(|x| x.kind == DashboardPanelKind::Overview))
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<dashboards::list::Resp>>),
+    FetchOverview,
+    OverviewFetched(Box<fetch::ResponseDataResult<Response<fs_queries::dashboard_overview::Resp>>>),
+    DatePicker(datepicker::Msg),
+    Noop,
+}
+
+pub fn init(orders: &mut impl Orders<Msg, GMsg>) {
+    orders.send_msg(Msg::Fetch);
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Fetch => {
+            let query = dashboards::list::build();
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => match x {
+            Ok(Response::Data(x)) => {
+                model.dashboards = Some(x.data.dashboards.list);
+
+                if model.has_overview() {
+                    orders.send_msg(Msg::FetchOverview);
+                }
+            }
+            Ok(Response::Errors(e)) => {
+                error!("An error occurred while retrieving dashboards", e);
+            }
+            Err(e) => {
+                error!("An error occurred while retrieving dashboards", e);
+            }
+        },
+        Msg::FetchOverview => {
+            model.cancel = None;
+
+            let query = fs_queries::dashboard_overview::build();
+            let req = fetch::Request::graphql_query(&query);
+
+            orders
+                .skip()
+                .perform_cmd(req.fetch_json_data(|x| Msg::OverviewFetched(Box::new(x))));
+        }
+        Msg::OverviewFetched(x) => {
+            match *x {
+                Ok(Response::Data(x)) => {
+                    model.overviews = x
+                        .data
+                        .dashboard_overview
+                        .into_iter()
+                        .map(|x| (x.name.clone(), x))
+                        .collect();
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving the filesystem overview", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving the filesystem overview", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(POLL_INTERVAL, Msg::FetchOverview, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::DatePicker(msg) => {
+            datepicker::update(msg, &mut model.date_picker, &mut orders.proxy(Msg::DatePicker));
+        }
+        Msg::Noop => {}
+    }
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    let xs = match model.dashboards.as_ref() {
+        Some(xs) => xs,
+        None => {
+            return panel::view(
+                h3![class![C.py_4, C.font_normal, C.text_lg], "Dashboards"],
+                div![class![C.p_4, C.text_gray_600], "Loading dashboards..."],
+            )
+        }
+    };
+
+    let selected = model.selected();
+
+    div![
+        panel::view(
+            h3![class![C.py_4, C.font_normal, C.text_lg], "Dashboards"],
+            if xs.is_empty() {
+                div![
+                    class![C.p_4, C.text_gray_600],
+                    "No dashboards have been saved yet. Dashboards are created with the ",
+                    code!["dashboards.save"],
+                    " mutation."
+                ]
+            } else {
+                div![
+                    class![C.flex, C.flex_wrap, C.p_4],
+                    xs.iter()
+                        .map(|x| dashboard_link(x, selected.map(|y| y.id) == Some(x.id)))
+                ]
+            }
+        ),
+        match selected {
+            Some(x) => div![
+                class![C.grid, C.lg__grid_cols_2, C.gap_6, C.mt_6],
+                x.panels.iter().map(|x| panel_view(x, model))
+            ],
+            None if !xs.is_empty() => div![class![C.p_4, C.text_gray_600], "Dashboard not found"],
+            None => empty![],
+        }
+    ]
+}
+
+fn dashboard_link(x: &Dashboard, active: bool) -> Node<Msg> {
+    a![
+        class![
+            C.mr_4,
+            C.text_blue_500,
+            C.hover__underline,
+            C.font_bold => active,
+        ],
+        attrs! {At::Href => Route::CustomDashboard(RouteId::from(x.id)).to_href()},
+        &x.name,
+        if x.shared {
+            span![
+                class![C.ml_1, C.text_sm, C.text_gray_600],
+                format!("(shared by {})", x.owner)
+            ]
+        } else {
+            empty![]
+        }
+    ]
+}
+
+fn panel_view(x: &DashboardPanel, model: &Model) -> Node<Msg> {
+    let body = match (x.kind, x.metric) {
+        (DashboardPanelKind::Chart, Some(metric)) => chart_view(x, metric, &model.date_picker),
+        (DashboardPanelKind::Overview, _) => overview_view(x, &model.overviews),
+        (DashboardPanelKind::Chart, None) => div![class![C.p_4, C.text_gray_600], "No metric selected"],
+    };
+
+    let node = dashboard_container::view(&x.title, body);
+
+    if x.wide {
+        node.merge_attrs(class![C.lg__col_span_2])
+    } else {
+        node
+    }
+}
+
+fn chart_view(x: &DashboardPanel, metric: DashboardMetric, date_picker: &datepicker::Model) -> Node<Msg> {
+    let fs_name = x.fs_name.clone().unwrap_or_default();
+    let target_name = x.target_name.clone().unwrap_or_default();
+    let range = vec![("from", &date_picker.from), ("to", &date_picker.to)];
+
+    let (panel_id, vars, ranged) = match metric {
+        DashboardMetric::Io => {
+            let (bw_id, iops_id, mut vars) = match x.target_name.as_ref() {
+                Some(target_name) => (39, 38, vec![("target_name", target_name)]),
+                None => (18, 20, vec![]),
+            };
+
+            vars.extend(range);
+
+            return performance_container(date_picker, bw_id, iops_id, vars).map_msg(Msg::DatePicker);
+        }
+        DashboardMetric::Lnet => (34, vec![], true),
+        DashboardMetric::OstBalance if x.fs_name.is_some() => (35, vec![("fs_name", &fs_name)], false),
+        DashboardMetric::OstBalance => (26, vec![], false),
+        DashboardMetric::FilesystemUsage => (31, vec![("fs_name", &fs_name)], true),
+        DashboardMetric::MdtUsage => (32, vec![("fs_name", &fs_name)], true),
+        DashboardMetric::MetadataOperations => (37, vec![("target_name", &target_name)], false),
+        DashboardMetric::SpaceUsage => (14, vec![("target_name", &target_name)], false),
+        DashboardMetric::FileUsage => (16, vec![("target_name", &target_name)], false),
+    };
+
+    let vars = if ranged {
+        vars.into_iter().chain(range).collect()
+    } else {
+        vars
+    };
+
+    div![
+        class![C.h_full, C.min_h_80, C.p_2],
+        grafana_chart::view(
+            IML_METRICS_DASHBOARD_ID,
+            IML_METRICS_DASHBOARD_NAME,
+            create_chart_params(panel_id, "10s", vars),
+            "90%",
+        ),
+        if ranged {
+            datepicker::view(date_picker).map_msg(Msg::DatePicker)
+        } else {
+            empty![]
+        }
+    ]
+}
+
+fn overview_view(x: &DashboardPanel, overviews: &HashMap<String, FilesystemOverview>) -> Node<Msg> {
+    let overview = match x.fs_name.as_ref() {
+        Some(fs_name) => match overviews.get(fs_name) {
+            Some(x) => x.clone(),
+            None => {
+                return div![
+                    class![C.p_4, C.text_gray_600],
+                    format!("Filesystem {} not found", fs_name)
+                ]
+            }
+        },
+        None => sum_overviews(overviews.values()),
+    };
+
+    let row = |label: &str, value: Node<Msg>| {
+        div![
+            class![C.flex, C.justify_between, C.py_2, C.border_b],
+            span![class![C.text_gray_600], label],
+            value
+        ]
+    };
+
+    div![
+        class![C.p_6],
+        row(
+            "Space Used",
+            filesystem::space_used_view(
+                overview.bytes_free.map(|x| x as u64),
+                overview.bytes_total.map(|x| x as u64),
+                overview.bytes_avail.map(|x| x as u64),
+            )
+        ),
+        row(
+            "Files",
+            plain![match (overview.files_total, overview.files_free) {
+                (Some(total), Some(free)) => format!("{} / {}", total - free, total),
+                _ => "---".to_string(),
+            }]
+        ),
+        row("Clients", filesystem::clients_view(overview.clients.map(|x| x as u64))),
+        row("Targets Mounted", filesystems::targets_view(Some(&overview))),
+        row(
+            "Active Alerts",
+            span![
+                class![if overview.active_alerts > 0 {
+                    C.text_red_500
+                } else {
+                    C.text_gray_700
+                }],
+                overview.active_alerts.to_string()
+            ]
+        ),
+    ]
+}
+
+/// Adds up the overviews of several filesystems.
+/// A stat is `None` only if it is `None` for every filesystem.
+fn sum_overviews<'a>(xs: impl IntoIterator<Item = &'a FilesystemOverview>) -> FilesystemOverview {
+    fn add<T: std::ops::Add<Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        }
+    }
+
+    xs.into_iter().fold(
+        FilesystemOverview {
+            id: 0,
+            name: "All filesystems".to_string(),
+            bytes_total: None,
+            bytes_free: None,
+            bytes_avail: None,
+            files_total: None,
+            files_free: None,
+            clients: None,
+            targets: TargetStateCounts {
+                mounted: 0,
+                unmounted: 0,
+                alerting: 0,
+            },
+            active_alerts: 0,
+        },
+        |acc, x| FilesystemOverview {
+            bytes_total: add(acc.bytes_total, x.bytes_total),
+            bytes_free: add(acc.bytes_free, x.bytes_free),
+            bytes_avail: add(acc.bytes_avail, x.bytes_avail),
+            files_total: add(acc.files_total, x.files_total),
+            files_free: add(acc.files_free, x.files_free),
+            clients: add(acc.clients, x.clients),
+            targets: TargetStateCounts {
+                mounted: acc.targets.mounted + x.targets.mounted,
+                unmounted: acc.targets.unmounted + x.targets.unmounted,
+                alerting: acc.targets.alerting + x.targets.alerting,
+            },
+            active_alerts: acc.active_alerts + x.active_alerts,
+            ..acc
+        },
+    )
+}
@@ -8,9 +8,10 @@ use crate::{
         dashboard::{dashboard_container, dashboard_fs_usage, performance_container},
         datepicker,
         grafana_chart::{self, create_chart_params, no_vars, IML_METRICS_DASHBOARD_ID, IML_METRICS_DASHBOARD_NAME},
-        sfa_overview,
+        maintenance_banner, sfa_overview,
     },
     generated::css_classes::C,
+    page::fs_dashboard,
     GMsg, RecordChange,
 };
 use iml_wire_types::warp_drive::{ArcCache, ArcRecord, RecordId};
@@ -18,6 +19,8 @@ use seed::{class, prelude::*, *};
 
 #[derive(Default)]
 pub struct Model {
+    /// The dashboard of the filesystem context, shown in place of the global one
+    pub fs_dashboard: Option<Box<fs_dashboard::Model>>,
     pub fs_usage: fs_usage::Model,
     pub io_date_picker: datepicker::Model,
     pub lnet_date_picker: datepicker::Model,
@@ -44,6 +47,7 @@ impl RecordChange<Msg> for Model {
 
 #[derive(Clone, Debug)]
 pub enum Msg {
+    FsDashboard(fs_dashboard::Msg),
     FsUsage(fs_usage::Msg),
     IoChart(datepicker::Msg),
     LNetChart(datepicker::Msg),
@@ -52,6 +56,11 @@ pub enum Msg {
 
 pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
     match msg {
+        Msg::FsDashboard(msg) => {
+            if let Some(x) = model.fs_dashboard.as_mut() {
+                fs_dashboard::update(msg, x, &mut orders.proxy(Msg::FsDashboard));
+            }
+        }
         Msg::FsUsage(msg) => {
             fs_usage::update(msg, &mut model.fs_usage, &mut orders.proxy(Msg::FsUsage));
         }
@@ -69,7 +78,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
     }
 }
 
-pub fn view(model: &Model) -> Node<Msg> {
+pub fn view(model: &Model, maintenance: &maintenance_banner::Model) -> Node<Msg> {
+    if let Some(x) = model.fs_dashboard.as_ref() {
+        return fs_dashboard::view(x).map_msg(Msg::FsDashboard);
+    }
+
     div![
         class![C.grid, C.lg__grid_cols_2, C.gap_6, C.h_full],
         vec![
@@ -120,6 +133,7 @@ pub fn view(model: &Model) -> Node<Msg> {
                     datepicker::view(&model.lnet_date_picker).map_msg(Msg::LNetChart),
                 ]
             ),
+            dashboard_container::view("Upcoming Maintenance", maintenance_banner::upcoming_view(maintenance)),
         ]
     ]
 }
@@ -133,3 +147,16 @@ pub fn init(cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, G
 
     orders.proxy(Msg::FsUsage).send_msg(fs_usage::Msg::FetchData);
 }
+
+/// Shows the dashboard of the filesystem context, or the global one for all filesystems.
+pub fn set_fs_context(fs_name: Option<&str>, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    if model.fs_dashboard.as_ref().map(|x| x.fs_name.as_str()) == fs_name {
+        return;
+    }
+
+    model.fs_dashboard = fs_name.map(|x| Box::new(fs_dashboard::Model::new(x.to_string())));
+
+    if model.fs_dashboard.is_some() {
+        fs_dashboard::init(&mut orders.proxy(Msg::FsDashboard));
+    }
+}
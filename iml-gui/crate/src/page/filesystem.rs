@@ -4,8 +4,9 @@
 
 use crate::{
     components::{
-        action_dropdown, alert_indicator, font_awesome::*, lock_indicator, paging, progress_circle, resource_links,
-        stratagem, table as t, Placement,
+        action_dropdown, alert_indicator, command_modal, font_awesome::*, lock_indicator, paging, progress_circle,
+        resource_links, restrict, sparkline, stratagem, stripe_editor, table as t, table_columns, toast, virtual_table,
+        Placement,
     },
     extensions::MergeAttrs,
     generated::css_classes::C,
@@ -15,18 +16,51 @@ use crate::{
     sleep_with_handle, GMsg, RequestExt, Route,
 };
 use futures::channel::oneshot;
-use iml_graphql_queries::{client_mount, Response};
+use iml_graphql_queries::{client_mount, filesystem as fs_queries, Response};
 use iml_wire_types::{
     db::{CorosyncResourceBanRecord, ManagedTargetRecord, TargetKind, TargetRecord},
+    dne::FilesystemDne,
+    graphql::FilesystemCheckRun,
+    new_target::{OstSpecInput, PendingTarget},
+    recovery::FilesystemRecoveryStatus,
     warp_drive::ArcRecord,
     warp_drive::RecordId,
-    warp_drive::{ArcCache, Locks},
-    Filesystem, Label, Session, ToCompositeId,
+    warp_drive::{ArcCache, ArcValuesExt, Locks},
+    Filesystem, GroupType, Label, Session, ToCompositeId,
 };
 use number_formatter as nf;
 use seed::{prelude::*, *};
 use std::{borrow::Cow, collections::HashMap, sync::Arc, time::Duration};
 
+const TARGET_COLUMNS: &[table_columns::Column] = &[
+    table_columns::Column {
+        id: "name",
+        label: "Name",
+    },
+    table_columns::Column {
+        id: "dev_path",
+        label: "Device Path",
+    },
+    table_columns::Column {
+        id: "active_server",
+        label: "Active Server",
+    },
+    table_columns::Column {
+        id: "standby_servers",
+        label: "Standby Servers",
+    },
+    table_columns::Column {
+        id: "bandwidth",
+        label: "Bandwidth",
+    },
+];
+
+/// MDT and OST rows, tall enough for the action dropdown and the bandwidth sparkline.
+const TARGET_ROW_HEIGHT: f64 = 64.;
+
+/// MDT and OST tables scroll past this, rendering only the rows in view.
+const TARGET_TABLE_HEIGHT: f64 = 740.;
+
 pub struct Row {
     dropdown: action_dropdown::Model,
 }
@@ -35,16 +69,39 @@ pub struct Model {
     pub fs: Arc<Filesystem>,
     mdts: Vec<Arc<ManagedTargetRecord>>,
     mdt_paging: paging::Model,
+    mdt_table: virtual_table::Model,
     mgt: Vec<Arc<ManagedTargetRecord>>,
     osts: Vec<Arc<ManagedTargetRecord>>,
     mount_command: Option<String>,
     ost_paging: paging::Model,
+    ost_table: virtual_table::Model,
     rows: HashMap<i32, Row>,
     stratagem: stratagem::Model,
     stats: iml_influx::filesystem::Response,
     mount_cancel: Option<oneshot::Sender<()>>,
     stats_cancel: Option<oneshot::Sender<()>>,
     stats_url: String,
+    evict_nid: String,
+    evicting: bool,
+    evict_toast: Option<toast::Model>,
+    add_mdt_host: Option<i32>,
+    add_mdt_device: String,
+    adding_mdt: bool,
+    add_mdt_toast: Option<toast::Model>,
+    /// The server and device of each OST to add
+    add_ost_rows: Vec<(Option<i32>, String)>,
+    add_ost_rebalance: bool,
+    adding_ost: bool,
+    add_ost_toast: Option<toast::Model>,
+    target_columns: table_columns::Model,
+    sparklines: sparkline::Model,
+    last_check: Option<FilesystemCheckRun>,
+    check_cancel: Option<oneshot::Sender<()>>,
+    dne: Option<FilesystemDne>,
+    dne_cancel: Option<oneshot::Sender<()>>,
+    stripe_editor: stripe_editor::Model,
+    recovery: Option<FilesystemRecoveryStatus>,
+    recovery_cancel: Option<oneshot::Sender<()>>,
 }
 
 impl Model {
@@ -53,16 +110,38 @@ impl Model {
             fs: Arc::clone(fs),
             mdts: Default::default(),
             mdt_paging: Default::default(),
+            mdt_table: virtual_table::Model::new(TARGET_ROW_HEIGHT, TARGET_TABLE_HEIGHT),
             mgt: Default::default(),
             mount_command: None,
             osts: Default::default(),
             ost_paging: Default::default(),
+            ost_table: virtual_table::Model::new(TARGET_ROW_HEIGHT, TARGET_TABLE_HEIGHT),
             rows: Default::default(),
             stratagem: stratagem::Model::new(use_stratagem, Arc::clone(fs)),
             stats: iml_influx::filesystem::Response::default(),
             mount_cancel: None,
             stats_cancel: None,
             stats_url: format!(r#"/influx?db=iml_stats&q={}"#, iml_influx::filesystem::query(&fs.name)),
+            evict_nid: String::new(),
+            evicting: false,
+            evict_toast: None,
+            add_mdt_host: None,
+            add_mdt_device: String::new(),
+            adding_mdt: false,
+            add_mdt_toast: None,
+            add_ost_rows: vec![(None, String::new())],
+            add_ost_rebalance: false,
+            adding_ost: false,
+            add_ost_toast: None,
+            target_columns: table_columns::Model::new("targets", TARGET_COLUMNS),
+            sparklines: sparkline::Model::default(),
+            last_check: None,
+            check_cancel: None,
+            dne: None,
+            dne_cancel: None,
+            stripe_editor: stripe_editor::Model::new(&fs.name),
+            recovery: None,
+            recovery_cancel: None,
         }
     }
 }
@@ -107,11 +186,45 @@ pub enum Msg {
     SetTargets(Vec<Arc<ManagedTargetRecord>>),
     OstPaging(paging::Msg),
     MdtPaging(paging::Msg),
+    OstTable(virtual_table::Msg),
+    MdtTable(virtual_table::Msg),
     UpdatePaging,
     Stratagem(stratagem::Msg),
+    EvictNidChanged(String),
+    EvictClient,
+    ClientEvicted(fetch::ResponseDataResult<Response<fs_queries::evict_client::Resp>>),
+    EvictToast(toast::Msg),
+    AddMdtHostChanged(String),
+    AddMdtDeviceChanged(String),
+    AddMdt,
+    MdtAdded(Box<fetch::ResponseDataResult<Response<fs_queries::add_mdt::Resp>>>),
+    AddMdtToast(toast::Msg),
+    AddOstHostChanged(usize, String),
+    AddOstDeviceChanged(usize, String),
+    AddOstRow,
+    RemoveOstRow(usize),
+    ToggleAddOstRebalance,
+    AddOst,
+    OstsAdded(Box<fetch::ResponseDataResult<Response<fs_queries::add_ost::Resp>>>),
+    AddOstToast(toast::Msg),
+    TargetColumns(table_columns::Msg),
+    Sparkline(sparkline::Msg),
+    FetchLastCheck,
+    LastCheckFetched(fetch::ResponseDataResult<Response<fs_queries::check_history::Resp>>),
+    FetchDne,
+    DneFetched(Box<fetch::ResponseDataResult<Response<fs_queries::dne::Resp>>>),
+    StripeEditor(stripe_editor::Msg),
+    FetchRecovery,
+    RecoveryFetched(Box<fetch::ResponseDataResult<Response<fs_queries::recovery_status::Resp>>>),
     Noop,
 }
 
+pub fn invalidate(table: &str, orders: &mut impl Orders<Msg, GMsg>) {
+    if table == "target_recovery" {
+        orders.send_msg(Msg::FetchRecovery);
+    }
+}
+
 pub fn init(cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
     orders.send_msg(Msg::SetTargets(cache.target.values().cloned().collect()));
 
@@ -120,6 +233,16 @@ pub fn init(cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, G
     orders.send_msg(Msg::FetchStats);
 
     orders.send_msg(Msg::FetchMountCommand);
+
+    orders.send_msg(Msg::FetchLastCheck);
+
+    orders.send_msg(Msg::FetchDne);
+
+    orders.send_msg(Msg::FetchRecovery);
+
+    stripe_editor::init(&mut orders.proxy(Msg::StripeEditor));
+
+    table_columns::init(&model.target_columns, &mut orders.proxy(Msg::TargetColumns));
 }
 
 pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
@@ -155,6 +278,103 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
             model.mount_cancel = Some(cancel);
             orders.perform_cmd(fut);
         }
+        Msg::FetchLastCheck => {
+            model.check_cancel = None;
+            let query = fs_queries::check_history::build(&model.fs.name, Some(1));
+            let req = seed::fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::LastCheckFetched));
+        }
+        Msg::LastCheckFetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.last_check = x.data.filesystem_check_history.into_iter().next();
+                }
+                Ok(Response::Errors(e)) => {
+                    error!(
+                        "An error occurred while retrieving the health checks for filesystem",
+                        model.fs.name, e
+                    );
+                }
+                Err(err) => {
+                    error!(
+                        "An error occurred while retrieving the health checks for filesystem",
+                        model.fs.name, err
+                    );
+                    orders.skip();
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(Duration::from_secs(60), Msg::FetchLastCheck, Msg::Noop);
+            model.check_cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::FetchDne => {
+            model.dne_cancel = None;
+            let query = fs_queries::dne::build(&model.fs.name);
+            let req = seed::fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(|x| Msg::DneFetched(Box::new(x))));
+        }
+        Msg::DneFetched(x) => {
+            match *x {
+                Ok(Response::Data(x)) => {
+                    model.dne = Some(x.data.filesystem_dne);
+                }
+                Ok(Response::Errors(e)) => {
+                    error!(
+                        "An error occurred while retrieving the MDTs of filesystem",
+                        model.fs.name, e
+                    );
+                }
+                Err(err) => {
+                    error!(
+                        "An error occurred while retrieving the MDTs of filesystem",
+                        model.fs.name, err
+                    );
+                    orders.skip();
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(Duration::from_secs(60), Msg::FetchDne, Msg::Noop);
+            model.dne_cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::FetchRecovery => {
+            model.recovery_cancel = None;
+            let query = fs_queries::recovery_status::build(&model.fs.name);
+            let req = seed::fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(|x| Msg::RecoveryFetched(Box::new(x))));
+        }
+        Msg::RecoveryFetched(x) => {
+            match *x {
+                Ok(Response::Data(x)) => {
+                    model.recovery = Some(x.data.recovery_status);
+                }
+                Ok(Response::Errors(e)) => {
+                    error!(
+                        "An error occurred while retrieving the recovery status of filesystem",
+                        model.fs.name, e
+                    );
+                }
+                Err(err) => {
+                    error!(
+                        "An error occurred while retrieving the recovery status of filesystem",
+                        model.fs.name, err
+                    );
+                    orders.skip();
+                }
+            }
+
+            // Changes are fetched as they are recorded, this only catches missed notifications
+            let (cancel, fut) = sleep_with_handle(Duration::from_secs(60), Msg::FetchRecovery, Msg::Noop);
+            model.recovery_cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::StripeEditor(msg) => {
+            stripe_editor::update(msg, &mut model.stripe_editor, &mut orders.proxy(Msg::StripeEditor));
+        }
         Msg::FetchStats => {
             model.stats_cancel = None;
             let request = seed::fetch::Request::new(model.stats_url.clone());
@@ -261,9 +481,19 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
         }
         Msg::MdtPaging(msg) => {
             paging::update(msg, &mut model.mdt_paging, &mut orders.proxy(Msg::MdtPaging));
+
+            set_sparkline_targets(model, orders);
         }
         Msg::OstPaging(msg) => {
             paging::update(msg, &mut model.ost_paging, &mut orders.proxy(Msg::OstPaging));
+
+            set_sparkline_targets(model, orders);
+        }
+        Msg::MdtTable(msg) => {
+            virtual_table::update(msg, &mut model.mdt_table, &mut orders.proxy(Msg::MdtTable));
+        }
+        Msg::OstTable(msg) => {
+            virtual_table::update(msg, &mut model.ost_table, &mut orders.proxy(Msg::OstTable));
         }
         Msg::UpdatePaging => {
             orders
@@ -274,10 +504,230 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
                 .send_msg(paging::Msg::SetTotal(model.osts.len()));
         }
         Msg::Stratagem(msg) => stratagem::update(msg, &mut model.stratagem, &mut orders.proxy(Msg::Stratagem)),
+        Msg::EvictNidChanged(x) => {
+            model.evict_nid = x.trim().to_string();
+        }
+        Msg::EvictClient => {
+            if model.evict_nid.is_empty() || model.evicting {
+                return;
+            }
+
+            let msg = format!(
+                "Evict client {} from all targets of {}? Its open files will be lost.",
+                model.evict_nid, model.fs.name
+            );
+
+            if let Ok(true) = window().confirm_with_message(&msg) {
+                model.evicting = true;
+                model.evict_toast = None;
+
+                let query = fs_queries::evict_client::build(&model.fs.name, &model.evict_nid);
+                let req = fetch::Request::graphql_query(&query);
+
+                orders.perform_cmd(req.fetch_json_data(Msg::ClientEvicted));
+            }
+        }
+        Msg::ClientEvicted(x) => {
+            model.evicting = false;
+
+            model.evict_toast = Some(match x {
+                Ok(Response::Data(x)) => {
+                    let n = x.data.filesystem.evict_client.len();
+                    let nid = std::mem::take(&mut model.evict_nid);
+
+                    toast::Model::Success(format!("Client {} evicted from {} target(s)", nid, n))
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while evicting client", e);
+
+                    toast::Model::Error(format!("Could not evict client {}", model.evict_nid))
+                }
+                Err(e) => {
+                    error!("An error occurred while evicting client", e);
+
+                    toast::Model::Error(format!("Could not evict client {}", model.evict_nid))
+                }
+            });
+        }
+        Msg::EvictToast(toast::Msg::Close) => {
+            model.evict_toast = None;
+        }
+        Msg::AddMdtHostChanged(x) => {
+            model.add_mdt_host = x.parse().ok();
+        }
+        Msg::AddMdtDeviceChanged(x) => {
+            model.add_mdt_device = x.trim().to_string();
+        }
+        Msg::AddMdt => {
+            let host_id = match model.add_mdt_host {
+                Some(x) if !model.add_mdt_device.is_empty() && !model.adding_mdt => x,
+                _ => return,
+            };
+
+            let fqdn = cache.host.get(&host_id).map(|x| x.fqdn.to_string()).unwrap_or_default();
+
+            let msg = format!(
+                "Format {} on {} as a new MDT of {}? Any data on the device will be lost.",
+                model.add_mdt_device, fqdn, model.fs.name
+            );
+
+            if let Ok(true) = window().confirm_with_message(&msg) {
+                model.adding_mdt = true;
+                model.add_mdt_toast = None;
+
+                let query = fs_queries::add_mdt::build(&model.fs.name, host_id, &model.add_mdt_device);
+                let req = fetch::Request::graphql_query(&query);
+
+                orders.perform_cmd(req.fetch_json_data(|x| Msg::MdtAdded(Box::new(x))));
+            }
+        }
+        Msg::MdtAdded(x) => {
+            model.adding_mdt = false;
+
+            match *x {
+                Ok(Response::Data(x)) => {
+                    let x = command_modal::Input::Commands(vec![Arc::new(x.data.filesystem.add_mdt)]);
+
+                    orders.send_g_msg(GMsg::OpenCommandModal(x));
+
+                    model.add_mdt_device = String::new();
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while adding an MDT", e);
+
+                    let msg = e.messages().next().unwrap_or("Could not add the MDT").to_string();
+
+                    model.add_mdt_toast = Some(toast::Model::Error(msg));
+                }
+                Err(e) => {
+                    error!("An error occurred while adding an MDT", e);
+
+                    model.add_mdt_toast = Some(toast::Model::Error("Could not add the MDT".into()));
+                }
+            }
+        }
+        Msg::AddMdtToast(toast::Msg::Close) => {
+            model.add_mdt_toast = None;
+        }
+        Msg::AddOstHostChanged(idx, x) => {
+            if let Some(row) = model.add_ost_rows.get_mut(idx) {
+                row.0 = x.parse().ok();
+            }
+        }
+        Msg::AddOstDeviceChanged(idx, x) => {
+            if let Some(row) = model.add_ost_rows.get_mut(idx) {
+                row.1 = x.trim().to_string();
+            }
+        }
+        Msg::AddOstRow => {
+            let host = model.add_ost_rows.last().and_then(|x| x.0);
+
+            model.add_ost_rows.push((host, String::new()));
+        }
+        Msg::RemoveOstRow(idx) => {
+            if model.add_ost_rows.len() > 1 && idx < model.add_ost_rows.len() {
+                model.add_ost_rows.remove(idx);
+            }
+        }
+        Msg::ToggleAddOstRebalance => {
+            model.add_ost_rebalance = !model.add_ost_rebalance;
+        }
+        Msg::AddOst => {
+            if !can_add_osts(model) {
+                return;
+            }
+
+            let specs: Vec<_> = model
+                .add_ost_rows
+                .iter()
+                .filter_map(|(host, device)| {
+                    host.map(|host_id| OstSpecInput {
+                        host_id,
+                        device: device.clone(),
+                    })
+                })
+                .collect();
+
+            let devices: Vec<_> = specs
+                .iter()
+                .map(|x| {
+                    let fqdn = cache.host.get(&x.host_id).map(|x| x.fqdn.as_str()).unwrap_or_default();
+
+                    format!("{} on {}", x.device, fqdn)
+                })
+                .collect();
+
+            let msg = format!(
+                "Format {} as new OSTs of {}? Any data on the devices will be lost.",
+                devices.join(", "),
+                model.fs.name
+            );
+
+            if let Ok(true) = window().confirm_with_message(&msg) {
+                model.adding_ost = true;
+                model.add_ost_toast = None;
+
+                let query = fs_queries::add_ost::build(&model.fs.name, specs, model.add_ost_rebalance);
+                let req = fetch::Request::graphql_query(&query);
+
+                orders.perform_cmd(req.fetch_json_data(|x| Msg::OstsAdded(Box::new(x))));
+            }
+        }
+        Msg::OstsAdded(x) => {
+            model.adding_ost = false;
+
+            match *x {
+                Ok(Response::Data(x)) => {
+                    let x = x.data.filesystem.add_ost;
+
+                    orders.send_g_msg(GMsg::OpenCommandModal(command_modal::Input::Commands(vec![Arc::new(
+                        x.command,
+                    )])));
+
+                    model.add_ost_toast = x.rebalance_hint.map(toast::Model::Warn);
+                    model.add_ost_rows = vec![(None, String::new())];
+                    model.add_ost_rebalance = false;
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while adding OSTs", e);
+
+                    let msg = e.messages().next().unwrap_or("Could not add the OSTs").to_string();
+
+                    model.add_ost_toast = Some(toast::Model::Error(msg));
+                }
+                Err(e) => {
+                    error!("An error occurred while adding OSTs", e);
+
+                    model.add_ost_toast = Some(toast::Model::Error("Could not add the OSTs".into()));
+                }
+            }
+        }
+        Msg::AddOstToast(toast::Msg::Close) => {
+            model.add_ost_toast = None;
+        }
+        Msg::TargetColumns(msg) => {
+            table_columns::update(msg, &mut model.target_columns, &mut orders.proxy(Msg::TargetColumns));
+        }
+        Msg::Sparkline(msg) => {
+            sparkline::update(msg, &mut model.sparklines, &mut orders.proxy(Msg::Sparkline));
+        }
         Msg::Noop => {}
     }
 }
 
+/// Only the targets on the current pages get sparklines.
+fn set_sparkline_targets(model: &Model, orders: &mut impl Orders<Msg, GMsg>) {
+    let uuids = model
+        .mgt
+        .iter()
+        .chain(&model.mdts[model.mdt_paging.range()])
+        .chain(&model.osts[model.ost_paging.range()])
+        .filter_map(|x| x.uuid.clone())
+        .collect();
+
+    orders.proxy(Msg::Sparkline).send_msg(sparkline::Msg::SetTargets(uuids));
+}
+
 fn paging_view(pager: &paging::Model) -> Node<paging::Msg> {
     div![
         class![C.flex, C.justify_end, C.py_1, C.pr_3],
@@ -301,39 +751,43 @@ pub(crate) fn view(
     };
 
     div![
-        details(cache, all_locks, model),
+        recovery_banner(model.recovery.as_ref()),
+        details(cache, all_locks, session, model),
         stratagem_content,
         targets(
-            "Management Target",
+            targets_heading("Management Target", Some(&model.target_columns)),
             cache,
             all_locks,
             session,
-            &model.rows,
+            model,
             &model.mgt[..],
+            None,
             None
         ),
         targets(
-            "Metadata Targets",
+            targets_heading("Metadata Targets", None),
             cache,
             all_locks,
             session,
-            &model.rows,
+            model,
             &model.mdts[model.mdt_paging.range()],
+            Some((&model.mdt_table, Msg::MdtTable)),
             paging_view(&model.mdt_paging).map_msg(Msg::MdtPaging)
         ),
         targets(
-            "Object Storage Targets",
+            targets_heading("Object Storage Targets", None),
             cache,
             all_locks,
             session,
-            &model.rows,
+            model,
             &model.osts[model.ost_paging.range()],
+            Some((&model.ost_table, Msg::OstTable)),
             paging_view(&model.ost_paging).map_msg(Msg::OstPaging)
         ),
     ]
 }
 
-fn details(cache: &ArcCache, all_locks: &Locks, model: &Model) -> Node<Msg> {
+fn details(cache: &ArcCache, all_locks: &Locks, session: Option<&Session>, model: &Model) -> Node<Msg> {
     let label_cls = class![C.col_span_2, C.p_4, C.self_center];
     let item_cls = class![
         C.bg_gray_100,
@@ -379,12 +833,41 @@ fn details(cache: &ArcCache, all_locks: &Locks, model: &Model) -> Node<Msg> {
             ],
             div![&label_cls, "Number of MDTs"],
             div![&item_cls, model.mdts.len().to_string()],
+            div![&label_cls, "MDT Inode Usage"],
+            div![&item_cls, mdt_load_view(model.dne.as_ref())],
+            div![&label_cls, "Default Directory Striping"],
+            div![&item_cls, dir_stripe_view(model.dne.as_ref())],
+            div![&label_cls, "Default File Striping"],
+            div![
+                &item_cls,
+                stripe_editor::view(
+                    &model.stripe_editor,
+                    restrict::is_allowed(session, GroupType::FilesystemAdministrators)
+                )
+                .map_msg(Msg::StripeEditor)
+            ],
             div![&label_cls, "Number of OSTs"],
             div![&item_cls, model.osts.len().to_string()],
             div![&label_cls, "Number of Connected Clients"],
             div![&item_cls, clients_view(model.stats.clients)],
+            restrict::view(
+                session,
+                GroupType::FilesystemAdministrators,
+                div![
+                    class![C.col_span_12, C.grid, C.grid_cols_12, C.gap_2],
+                    div![&label_cls, "Evict Client"],
+                    div![&item_cls, evict_client_view(model)],
+                    div![&label_cls, "Add MDT"],
+                    div![&item_cls, add_mdt_view(cache, model)],
+                    div![&label_cls, "Add OSTs"],
+                    div![&item_cls, add_ost_view(cache, model)],
+                ]
+            ),
+            pending_targets_view(cache, &label_cls, &item_cls, &model.fs.name),
             div![&label_cls, "Status"],
             div![&item_cls, status_view(cache, all_locks, &model.fs)],
+            div![&label_cls, "Health Checks"],
+            div![&item_cls, last_check_view(model.last_check.as_ref())],
             div![&label_cls, "Client mount command"],
             pre![
                 class![C.break_all, C.text_white, C.whitespace_pre_line],
@@ -403,20 +886,271 @@ fn details(cache: &ArcCache, all_locks: &Locks, model: &Model) -> Node<Msg> {
                         "Loading...",
                     ]
                 }
-            ]
+            ],
+            div![&label_cls, "Client setup files"],
+            div![&item_cls, client_mount_config_links(&model.fs.name)],
         ],
     ]
 }
 
+/// Downloads of the files that set up a client to mount `fs_name`.
+fn client_mount_config_links<T>(fs_name: &str) -> Node<T> {
+    let xs = [
+        ("systemd", "systemd mount unit"),
+        ("fstab", "/etc/fstab line"),
+        ("lnet", "/etc/lnet.conf"),
+    ];
+
+    div![xs.iter().map(|(kind, label)| {
+        a![
+            class![C.text_blue_500, C.hover__underline, C.mr_4],
+            attrs! {
+                At::Download => "",
+                At::Href => format!("/api/client-mount/{}/{}", fs_name, kind)
+            },
+            *label
+        ]
+    })]
+}
+
+/// Shown while any target of the filesystem is in recovery, e.g. after a failover.
+fn recovery_banner<T>(x: Option<&FilesystemRecoveryStatus>) -> Node<T> {
+    let x = match x {
+        Some(x) if x.recovering => x,
+        _ => return empty![],
+    };
+
+    let remaining = x
+        .time_remaining
+        .map(|t| {
+            format!(
+                ", {} remaining",
+                humantime::format_duration(Duration::from_secs(t.max(0) as u64))
+            )
+        })
+        .unwrap_or_default();
+
+    div![
+        class![
+            C.bg_yellow_100,
+            C.border,
+            C.border_yellow_400,
+            C.mb_4,
+            C.px_6,
+            C.py_4,
+            C.rounded_lg,
+            C.shadow,
+        ],
+        div![
+            class![C.font_bold, C.mb_2],
+            font_awesome(
+                class![C.w_4, C.h_4, C.inline, C.mr_2, C.text_yellow_600],
+                "exclamation-triangle"
+            ),
+            format!("Recovery in progress{}", remaining)
+        ],
+        ul![x.targets.iter().filter(|t| t.status == "RECOVERING").map(|t| {
+            let clients = match (t.connected_clients, t.total_clients) {
+                (Some(a), Some(b)) => format!("{} of {} clients reconnected", a, b),
+                _ => "waiting for clients".to_string(),
+            };
+
+            let evicted = match t.evicted_clients {
+                Some(n) if n > 0 => format!(", {} evicted", n),
+                _ => String::new(),
+            };
+
+            li![format!("{} on {}: {}{}", t.target_name, t.fqdn, clients, evicted)]
+        })]
+    ]
+}
+
+fn last_check_view<T>(x: Option<&FilesystemCheckRun>) -> Node<T> {
+    let x = match x {
+        Some(x) => x,
+        None => return plain!["Not run yet"],
+    };
+
+    let ran_at = x.ran_at.format("%m/%d/%Y %H:%M:%S");
+
+    if x.passed {
+        return span![
+            font_awesome(class![C.w_4, C.h_4, C.inline, C.mr_2, C.text_green_500], "check-circle"),
+            format!("All checks passed at {}", ran_at)
+        ];
+    }
+
+    div![
+        span![
+            font_awesome(
+                class![C.w_4, C.h_4, C.inline, C.mr_2, C.text_red_500],
+                "exclamation-circle"
+            ),
+            format!("Checks failed at {}", ran_at)
+        ],
+        ul![class![C.ml_6], x.checks.iter().flat_map(|c| &c.details).map(|d| li![d])]
+    ]
+}
+
+fn mdt_load_view<T>(x: Option<&FilesystemDne>) -> Node<T> {
+    let x = match x {
+        Some(x) => x,
+        None => return plain!["---"],
+    };
+
+    table![x.mdts.iter().map(|m| {
+        tr![
+            td![class![C.pr_4], &m.name],
+            td![
+                class![C.pr_4, C.text_gray_600],
+                m.active_host.as_deref().unwrap_or("Not mounted")
+            ],
+            td![files_created_view(
+                m.files_free.map(|x| x as u64),
+                m.files_total.map(|x| x as u64)
+            )],
+        ]
+    })]
+}
+
+fn dir_stripe_view<T>(x: Option<&FilesystemDne>) -> Node<T> {
+    let x = match x {
+        Some(x) => x,
+        None => return plain!["---"],
+    };
+
+    match (&x.default_dir_stripe, &x.dir_stripe_error) {
+        (Some(s), _) => {
+            let offset = if s.stripe_offset < 0 {
+                "any MDT".to_string()
+            } else {
+                format!("MDT index {}", s.stripe_offset)
+            };
+
+            plain![format!(
+                "Over {} MDT(s), starting on {}, {} hash",
+                s.stripe_count.max(1),
+                offset,
+                s.hash_type
+            )]
+        }
+        (None, Some(e)) => span![class![C.text_gray_600], e],
+        (None, None) => plain!["---"],
+    }
+}
+
+/// The heading of a target table. The column preferences are shared by all target tables,
+/// so only one of them should show the menu.
+fn targets_heading(title: &str, columns: Option<&table_columns::Model>) -> Node<Msg> {
+    div![
+        class![
+            C.flex,
+            C.justify_between,
+            C.items_center,
+            C.px_6,
+            C._mb_px,
+            C.bg_gray_200
+        ],
+        h3![class![C.py_4, C.font_normal, C.text_lg], title],
+        match columns {
+            Some(x) => table_columns::view(x).map_msg(Msg::TargetColumns),
+            None => empty![],
+        }
+    ]
+}
+
 fn targets(
-    title: &str,
+    heading: Node<Msg>,
     cache: &ArcCache,
     all_locks: &Locks,
     session: Option<&Session>,
-    rows: &HashMap<i32, Row>,
+    model: &Model,
     tgts: &[Arc<ManagedTargetRecord>],
+    window: Option<(&virtual_table::Model, fn(virtual_table::Msg) -> Msg)>,
     pager: impl Into<Option<Node<Msg>>>,
 ) -> Node<Msg> {
+    let columns = &model.target_columns;
+
+    let head = t::thead_view(
+        columns
+            .visible()
+            .map(|c| match c {
+                "name" => t::th_left(plain!["Name"]).merge_attrs(class![C.w_32]),
+                "dev_path" => t::th_left(plain!["Device Path"]),
+                "active_server" => {
+                    t::th_left(plain!["Active Server"]).merge_attrs(class![C.w_48, C.hidden, C.md__table_cell])
+                }
+                "standby_servers" => {
+                    t::th_left(plain!["Standby Servers"]).merge_attrs(class![C.w_48, C.hidden, C.md__table_cell])
+                }
+                "bandwidth" => t::th_left(plain!["Bandwidth"]).merge_attrs(class![C.w_32, C.hidden, C.md__table_cell]),
+                _ => empty![],
+            })
+            .chain(std::iter::once(th![class![C.w_48]]))
+            .collect::<Vec<_>>(),
+    );
+
+    let row = |x: &Arc<ManagedTargetRecord>| -> Option<Node<Msg>> {
+        let targ = get_target_from_managed_target(cache, x)?;
+        let row = model.rows.get(&x.id)?;
+
+        let dev_path = targ
+            .dev_path
+            .as_ref()
+            .map(|x| Cow::from(x.to_string()))
+            .unwrap_or_else(|| Cow::from("---"));
+
+        let active_host = targ.active_host_id.and_then(|x| cache.host.get(&x));
+
+        Some(tr![
+            columns
+                .visible()
+                .map(|c| match c {
+                    "name" => t::td_view(vec![
+                        a![
+                            class![C.text_blue_500, C.hover__underline],
+                            attrs! {At::Href => Route::Target(RouteId::from(x.id)).to_href()},
+                            &targ.name
+                        ],
+                        lock_indicator::view(all_locks, &x).merge_attrs(class![C.ml_2]),
+                        alert_indicator(&cache.active_alert, &x, true, Placement::Right).merge_attrs(class![C.ml_2]),
+                    ]),
+                    "dev_path" => t::td_view(plain![dev_path.clone()]),
+                    "active_server" => t::td_view(resource_links::server_link(
+                        active_host.map(|x| &x.resource_uri),
+                        active_host.map(|x| x.fqdn.to_string()).as_deref().unwrap_or_default(),
+                    ))
+                    .merge_attrs(class![C.hidden, C.md__table_cell]),
+                    "standby_servers" =>
+                        t::td_view(standby_hosts_view(cache, &targ)).merge_attrs(class![C.hidden, C.md__table_cell]),
+                    "bandwidth" => t::td_view(sparkline::target_view(&model.sparklines, &targ.uuid))
+                        .merge_attrs(class![C.hidden, C.md__table_cell]),
+                    _ => empty![],
+                })
+                .collect::<Vec<_>>(),
+            td![
+                class![C.p_3, C.text_center],
+                action_dropdown::view(x.id, &row.dropdown, all_locks, session)
+                    .map_msg(|x| Msg::ActionDropdown(Box::new(x)))
+            ]
+        ])
+    };
+
+    let body = match window {
+        Some((window, on_scroll)) => virtual_table::view(window, on_scroll, head, tgts.len(), |range| {
+            tgts[range].iter().filter_map(row).collect()
+        }),
+        None => table![
+            class![C.table_auto, C.w_full],
+            style! {
+                St::BorderSpacing => px(10),
+                St::BorderCollapse => "initial"
+            },
+            head,
+            tbody![tgts.iter().filter_map(row).collect::<Vec<_>>()]
+        ],
+    };
+
     div![
         class![
             C.bg_white,
@@ -427,72 +1161,8 @@ fn targets(
             C.rounded_lg,
             C.shadow,
         ],
-        div![
-            class![C.flex, C.justify_between, C.px_6, C._mb_px, C.bg_gray_200],
-            h3![class![C.py_4, C.font_normal, C.text_lg], title]
-        ],
-        table![
-            class![C.table_auto, C.w_full],
-            style! {
-                St::BorderSpacing => px(10),
-                St::BorderCollapse => "initial"
-            },
-            vec![
-                t::thead_view(vec![
-                    t::th_left(plain!["Name"]).merge_attrs(class![C.w_32]),
-                    t::th_left(plain!["Device Path"]),
-                    t::th_left(plain!["Active Server"]).merge_attrs(class![C.w_48, C.hidden, C.md__table_cell]),
-                    t::th_left(plain!["Standby Servers"]).merge_attrs(class![C.w_48, C.hidden, C.md__table_cell]),
-                    th![class![C.w_48]]
-                ]),
-                tbody![tgts
-                    .iter()
-                    .filter_map(|x| {
-                        let t = get_target_from_managed_target(cache, x)?;
-
-                        Some((x, t))
-                    })
-                    .map(move |(x, targ)| {
-                        let dev_path = targ
-                            .dev_path
-                            .as_ref()
-                            .map(|x| Cow::from(x.to_string()))
-                            .unwrap_or_else(|| Cow::from("---"));
-
-                        let active_host = targ.active_host_id.and_then(|x| cache.host.get(&x));
-
-                        match rows.get(&x.id) {
-                            None => empty![],
-                            Some(row) => tr![
-                                t::td_view(vec![
-                                    a![
-                                        class![C.text_blue_500, C.hover__underline],
-                                        attrs! {At::Href => Route::Target(RouteId::from(x.id)).to_href()},
-                                        &targ.name
-                                    ],
-                                    lock_indicator::view(all_locks, &x).merge_attrs(class![C.ml_2]),
-                                    alert_indicator(&cache.active_alert, &x, true, Placement::Right)
-                                        .merge_attrs(class![C.ml_2]),
-                                ]),
-                                t::td_view(plain![dev_path]),
-                                t::td_view(resource_links::server_link(
-                                    active_host.map(|x| &x.resource_uri),
-                                    active_host.map(|x| x.fqdn.to_string()).as_deref().unwrap_or_default(),
-                                ))
-                                .merge_attrs(class![C.hidden, C.md__table_cell]),
-                                t::td_view(standby_hosts_view(cache, &targ))
-                                    .merge_attrs(class![C.hidden, C.md__table_cell]),
-                                td![
-                                    class![C.p_3, C.text_center],
-                                    action_dropdown::view(x.id, &row.dropdown, all_locks, session)
-                                        .map_msg(|x| Msg::ActionDropdown(Box::new(x)))
-                                ]
-                            ],
-                        }
-                    })]
-            ]
-        ]
-        .merge_attrs(class![C.p_6]),
+        heading,
+        div![class![C.p_6], body],
         match pager.into() {
             Some(x) => x,
             None => empty![],
@@ -569,6 +1239,253 @@ pub(crate) fn mgs<T>(cache: &ArcCache, targets: &[Arc<TargetRecord>], f: &Filesy
     }
 }
 
+fn evict_client_view(model: &Model) -> Node<Msg> {
+    div![
+        class![C.flex, C.items_center],
+        input![
+            class![
+                C.appearance_none,
+                C.focus__outline_none,
+                C.focus__shadow_outline,
+                C.px_3,
+                C.py_1,
+                C.rounded_sm,
+                C.text_gray_800,
+                C.bg_white,
+            ],
+            attrs! {
+                At::Type => "text",
+                At::Placeholder => "Client NID, e.g. 10.0.2.15@tcp",
+                At::Value => &model.evict_nid,
+            },
+            input_ev(Ev::Input, Msg::EvictNidChanged),
+        ],
+        button![
+            class![
+                C.ml_2,
+                C.px_3,
+                C.py_1,
+                C.rounded,
+                C.text_white,
+                C.bg_red_500,
+                C.hover__bg_red_700,
+                C.cursor_not_allowed => model.evict_nid.is_empty() || model.evicting,
+                C.opacity_50 => model.evict_nid.is_empty() || model.evicting,
+            ],
+            attrs! { At::Disabled => (model.evict_nid.is_empty() || model.evicting).as_at_value() },
+            simple_ev(Ev::Click, Msg::EvictClient),
+            if model.evicting { "Evicting..." } else { "Evict" }
+        ],
+        match model.evict_toast.as_ref() {
+            Some(x) => toast::view(x).map_msg(Msg::EvictToast).merge_attrs(class![C.ml_4]),
+            None => empty![],
+        }
+    ]
+}
+
+fn add_mdt_view(cache: &ArcCache, model: &Model) -> Node<Msg> {
+    let disabled = model.add_mdt_host.is_none() || model.add_mdt_device.is_empty() || model.adding_mdt;
+
+    div![
+        class![C.flex, C.items_center],
+        host_select(cache, model.add_mdt_host).map_msg(Msg::AddMdtHostChanged),
+        input![
+            class![
+                C.appearance_none,
+                C.focus__outline_none,
+                C.focus__shadow_outline,
+                C.ml_2,
+                C.px_3,
+                C.py_1,
+                C.rounded_sm,
+                C.text_gray_800,
+                C.bg_white,
+            ],
+            attrs! {
+                At::Type => "text",
+                At::Placeholder => "Device, e.g. /dev/mapper/mpathd",
+                At::Value => &model.add_mdt_device,
+            },
+            input_ev(Ev::Input, Msg::AddMdtDeviceChanged),
+        ],
+        button![
+            class![
+                C.ml_2,
+                C.px_3,
+                C.py_1,
+                C.rounded,
+                C.text_white,
+                C.bg_blue_500,
+                C.hover__bg_blue_700,
+                C.cursor_not_allowed => disabled,
+                C.opacity_50 => disabled,
+            ],
+            attrs! { At::Disabled => disabled.as_at_value() },
+            simple_ev(Ev::Click, Msg::AddMdt),
+            if model.adding_mdt { "Adding..." } else { "Add" }
+        ],
+        match model.add_mdt_toast.as_ref() {
+            Some(x) => toast::view(x).map_msg(Msg::AddMdtToast).merge_attrs(class![C.ml_4]),
+            None => empty![],
+        }
+    ]
+}
+
+fn can_add_osts(model: &Model) -> bool {
+    !model.adding_ost
+        && model
+            .add_ost_rows
+            .iter()
+            .all(|(host, device)| host.is_some() && !device.is_empty())
+}
+
+fn host_select(cache: &ArcCache, selected: Option<i32>) -> Node<String> {
+    let mut hosts: Vec<_> = cache.host.values().collect();
+    hosts.sort_by(|a, b| natord::compare(&a.fqdn, &b.fqdn));
+
+    select![
+        class![
+            C.appearance_none,
+            C.focus__outline_none,
+            C.focus__shadow_outline,
+            C.px_3,
+            C.py_1,
+            C.rounded_sm,
+            C.text_gray_800,
+            C.bg_white,
+        ],
+        option![attrs! {At::Value => ""}, "Select a server"],
+        hosts.into_iter().map(|x| {
+            let mut opt = option![attrs! {At::Value => x.id.to_string()}, &x.fqdn];
+
+            if selected == Some(x.id) {
+                opt.add_attr(At::Selected.to_string(), "selected");
+            }
+
+            opt
+        }),
+        input_ev(Ev::Change, |x| x),
+    ]
+}
+
+fn add_ost_view(cache: &ArcCache, model: &Model) -> Node<Msg> {
+    let disabled = !can_add_osts(model);
+    let removable = model.add_ost_rows.len() > 1;
+
+    div![
+        model.add_ost_rows.iter().enumerate().map(|(idx, (host, device))| {
+            div![
+                class![C.flex, C.items_center, C.mb_1],
+                host_select(cache, *host).map_msg(move |x| Msg::AddOstHostChanged(idx, x)),
+                input![
+                    class![
+                        C.appearance_none,
+                        C.focus__outline_none,
+                        C.focus__shadow_outline,
+                        C.ml_2,
+                        C.px_3,
+                        C.py_1,
+                        C.rounded_sm,
+                        C.text_gray_800,
+                        C.bg_white,
+                    ],
+                    attrs! {
+                        At::Type => "text",
+                        At::Placeholder => "Device, e.g. /dev/mapper/mpathe",
+                        At::Value => device,
+                    },
+                    input_ev(Ev::Input, move |x| Msg::AddOstDeviceChanged(idx, x)),
+                ],
+                if removable {
+                    button![
+                        class![C.ml_2, C.text_gray_600, C.hover__text_gray_800],
+                        attrs! { At::Title => "Remove" },
+                        simple_ev(Ev::Click, Msg::RemoveOstRow(idx)),
+                        font_awesome(class![C.w_4, C.h_4, C.inline], "times"),
+                    ]
+                } else {
+                    empty![]
+                },
+            ]
+        }),
+        div![
+            class![C.flex, C.items_center],
+            button![
+                class![C.px_3, C.py_1, C.rounded, C.text_blue_500, C.hover__text_blue_700],
+                simple_ev(Ev::Click, Msg::AddOstRow),
+                font_awesome(class![C.w_4, C.h_4, C.inline, C.mr_1], "plus"),
+                "Another OST",
+            ],
+            label![
+                class![C.ml_4, C.flex, C.items_center],
+                input![
+                    class![C.mr_2],
+                    attrs! {
+                        At::Type => "checkbox",
+                        At::Checked => model.add_ost_rebalance.as_at_value(),
+                    },
+                    simple_ev(Ev::Click, Msg::ToggleAddOstRebalance),
+                ],
+                "Rebalance onto the new OSTs",
+            ],
+            button![
+                class![
+                    C.ml_4,
+                    C.px_3,
+                    C.py_1,
+                    C.rounded,
+                    C.text_white,
+                    C.bg_blue_500,
+                    C.hover__bg_blue_700,
+                    C.cursor_not_allowed => disabled,
+                    C.opacity_50 => disabled,
+                ],
+                attrs! { At::Disabled => disabled.as_at_value() },
+                simple_ev(Ev::Click, Msg::AddOst),
+                if model.adding_ost { "Adding..." } else { "Add" }
+            ],
+            match model.add_ost_toast.as_ref() {
+                Some(x) => toast::view(x).map_msg(Msg::AddOstToast).merge_attrs(class![C.ml_4]),
+                None => empty![],
+            }
+        ]
+    ]
+}
+
+/// The targets of `fs_name` that are being added, until the manager learns of them.
+fn pending_targets_view<T>(cache: &ArcCache, label_cls: &Attrs, item_cls: &Attrs, fs_name: &str) -> Node<T> {
+    let mut xs: Vec<&PendingTarget> = cache
+        .pending_target
+        .arc_values()
+        .filter(|x| x.fs_name == fs_name)
+        .collect();
+
+    if xs.is_empty() {
+        return empty![];
+    }
+
+    xs.sort_by(|a, b| natord::compare(&a.name, &b.name));
+
+    div![
+        class![C.col_span_12, C.grid, C.grid_cols_12, C.gap_2],
+        div![label_cls, "Targets Being Added"],
+        div![
+            item_cls,
+            ul![xs.into_iter().map(|x| {
+                let fqdn = cache.host.get(&x.host_id).map(|h| h.fqdn.as_str()).unwrap_or_default();
+
+                li![
+                    font_awesome(
+                        class![C.w_4, C.h_4, C.inline, C.mr_2, C.text_gray_500, C.pulse],
+                        "spinner"
+                    ),
+                    format!("{} on {} ({})", x.name, fqdn, x.dev_path)
+                ]
+            })]
+        ]
+    ]
+}
+
 pub(crate) fn clients_view<T>(cc: impl Into<Option<u64>>) -> Node<T> {
     plain![cc.into().map(|c| c.to_string()).unwrap_or_else(|| "---".to_string())]
 }
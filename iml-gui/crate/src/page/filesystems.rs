@@ -3,15 +3,17 @@
 // license that can be found in the LICENSE file.
 
 use crate::{
-    components::{action_dropdown, alert_indicator, lock_indicator, paging, table as t, Placement},
+    components::{action_dropdown, alert_indicator, fs_context, lock_indicator, paging, table as t, Placement},
     extensions::MergeAttrs,
     generated::css_classes::C,
     page::filesystem,
     route::RouteId,
-    sleep_with_handle, GMsg, Route,
+    sleep_with_handle, GMsg, RequestExt, Route,
 };
 use futures::channel::oneshot;
+use iml_graphql_queries::{filesystem as fs_queries, Response};
 use iml_wire_types::{
+    graphql::FilesystemOverview,
     warp_drive::{ArcCache, Locks},
     Filesystem, Session, ToCompositeId,
 };
@@ -26,7 +28,8 @@ struct Row {
 #[derive(Default)]
 pub struct Model {
     filesystems: Vec<Arc<Filesystem>>,
-    stats: iml_influx::filesystems::Response,
+    fs_name: Option<String>,
+    overviews: HashMap<String, FilesystemOverview>,
     pager: paging::Model,
     rows: HashMap<i32, Row>,
     stats_cancel: Option<oneshot::Sender<()>>,
@@ -35,7 +38,7 @@ pub struct Model {
 #[derive(Clone, Debug)]
 pub enum Msg {
     FetchStats,
-    StatsFetched(Box<fetch::ResponseDataResult<iml_influx::filesystems::InfluxResponse>>),
+    StatsFetched(Box<fetch::ResponseDataResult<Response<fs_queries::dashboard_overview::Resp>>>),
     ActionDropdown(Box<action_dropdown::IdMsg>),
     AddFilesystem(Arc<Filesystem>),
     Page(paging::Msg),
@@ -49,20 +52,37 @@ pub fn init(cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
     orders.send_msg(Msg::FetchStats);
 }
 
+/// Only lists the filesystem of the context.
+pub fn set_fs_context(fs_name: Option<&str>, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    model.fs_name = fs_name.map(String::from);
+
+    orders.send_msg(Msg::SetFilesystems(cache.filesystem.values().cloned().collect()));
+}
+
 pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
     match msg {
         Msg::FetchStats => {
             model.stats_cancel = None;
-            let request =
-                seed::fetch::Request::new(format!("/influx?db=iml_stats&q={}", iml_influx::filesystems::query()));
+            let query = fs_queries::dashboard_overview::build();
+            let req = fetch::Request::graphql_query(&query);
+
             orders
                 .skip()
-                .perform_cmd(request.fetch_json_data(|x| Msg::StatsFetched(Box::new(x))));
+                .perform_cmd(req.fetch_json_data(|x| Msg::StatsFetched(Box::new(x))));
         }
         Msg::StatsFetched(res) => {
             match *res {
-                Ok(response) => {
-                    model.stats = response.into();
+                Ok(Response::Data(x)) => {
+                    model.overviews = x
+                        .data
+                        .dashboard_overview
+                        .into_iter()
+                        .map(|x| (x.name.clone(), x))
+                        .collect();
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving the filesystem overview", e);
+                    orders.skip();
                 }
                 Err(e) => {
                     error!(e);
@@ -86,7 +106,12 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
             }
         }
         Msg::SetFilesystems(filesystems) => {
-            model.filesystems = filesystems;
+            let fs_name = model.fs_name.as_deref();
+
+            model.filesystems = filesystems
+                .into_iter()
+                .filter(|x| fs_context::in_context(fs_name, &x.name))
+                .collect();
 
             orders
                 .proxy(Msg::Page)
@@ -109,6 +134,10 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
             paging::update(msg, &mut model.pager, &mut orders.proxy(Msg::Page));
         }
         Msg::AddFilesystem(fs) => {
+            if !fs_context::in_context(model.fs_name.as_deref(), &fs.name) {
+                return;
+            }
+
             match model.filesystems.iter().position(|x| x.id == fs.id) {
                 Some(p) => {
                     model.filesystems.remove(p);
@@ -144,7 +173,7 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
 }
 
 pub fn view(cache: &ArcCache, model: &Model, all_locks: &Locks, session: Option<&Session>) -> impl View<Msg> {
-    if cache.filesystem.is_empty() {
+    if model.filesystems.is_empty() {
         div![
             class![C.text_3xl, C.text_center],
             h1![class![C.m_2, C.text_gray_600], "No filesystems found"],
@@ -161,6 +190,7 @@ pub fn view(cache: &ArcCache, model: &Model, all_locks: &Locks, session: Option<
                     t::th_view(plain!["Filesystem"]),
                     t::th_view(plain!["Active MGS"]),
                     t::th_view(plain!["MDT Count"]),
+                    t::th_view(plain!["Targets Mounted"]),
                     t::th_view(plain!["Connected Clients"]),
                     t::th_view(plain!["Space Used / Available"]),
                 ]),
@@ -169,7 +199,7 @@ pub fn view(cache: &ArcCache, model: &Model, all_locks: &Locks, session: Option<
                     .map(|f| match model.rows.get(&f.id) {
                         None => empty![],
                         Some(row) => {
-                            let stats = model.stats.get(&f.name).cloned().unwrap_or_default();
+                            let overview = model.overviews.get(&f.name);
                             let xs: Vec<_> = cache.target_record.values().cloned().collect();
 
                             tr![
@@ -181,11 +211,14 @@ pub fn view(cache: &ArcCache, model: &Model, all_locks: &Locks, session: Option<
                                 .merge_attrs(class![C.text_center]),
                                 t::td_center(filesystem::mgs(cache, &xs, f)),
                                 t::td_center(plain![f.mdts.len().to_string()]),
-                                t::td_center(filesystem::clients_view(stats.clients)),
+                                t::td_center(targets_view(overview)),
+                                t::td_center(filesystem::clients_view(
+                                    overview.and_then(|x| x.clients).map(|x| x as u64)
+                                )),
                                 t::td_center(filesystem::space_used_view(
-                                    stats.bytes_free,
-                                    stats.bytes_total,
-                                    stats.bytes_avail
+                                    overview.and_then(|x| x.bytes_free).map(|x| x as u64),
+                                    overview.and_then(|x| x.bytes_total).map(|x| x as u64),
+                                    overview.and_then(|x| x.bytes_avail).map(|x| x as u64),
                                 )),
                                 td![
                                     class![C.p_3, C.text_center],
@@ -207,6 +240,24 @@ pub fn view(cache: &ArcCache, model: &Model, all_locks: &Locks, session: Option<
     }
 }
 
+pub(crate) fn targets_view<T>(x: Option<&FilesystemOverview>) -> Node<T> {
+    match x {
+        Some(x) => {
+            let total = x.targets.mounted + x.targets.unmounted;
+
+            span![
+                class![if x.targets.unmounted > 0 {
+                    C.text_red_500
+                } else {
+                    C.text_gray_700
+                }],
+                format!("{} / {}", x.targets.mounted, total)
+            ]
+        }
+        None => plain!["---"],
+    }
+}
+
 fn fs_link<T>(f: &iml_wire_types::Filesystem) -> Node<T> {
     a![
         class![C.text_blue_500, C.hover__underline, C.mr_2],
@@ -3,7 +3,7 @@
 // license that can be found in the LICENSE file.
 
 use crate::{
-    components::{font_awesome, loading, paging},
+    components::{font_awesome, loading, paging, table_columns, virtual_table},
     extensions::*,
     generated::css_classes::C,
     route::{Route, RouteId},
@@ -19,11 +19,59 @@ use iml_wire_types::{warp_drive::ArcCache, Host, LogMessage, LogSeverity, SortDi
 use seed::{prelude::*, *};
 use std::{sync::Arc, time::Duration};
 
-#[derive(Default)]
+/// Every log takes one line. Long messages are cut short, with the full text in their title.
+const ROW_HEIGHT: f64 = 40.;
+
+const TABLE_HEIGHT: f64 = 720.;
+
+const COLUMNS: &[table_columns::Column] = &[
+    table_columns::Column {
+        id: "time",
+        label: "Time",
+    },
+    table_columns::Column {
+        id: "severity",
+        label: "Severity",
+    },
+    table_columns::Column {
+        id: "message",
+        label: "Message",
+    },
+    table_columns::Column {
+        id: "fqdn",
+        label: "FQDN",
+    },
+    table_columns::Column {
+        id: "service",
+        label: "Service",
+    },
+    table_columns::Column {
+        id: "source",
+        label: "Source",
+    },
+];
+
 pub struct Model {
     state: State,
     cancel: Option<oneshot::Sender<()>>,
     pager: paging::Model,
+    columns: table_columns::Model,
+    table: virtual_table::Model,
+    /// Only show the logs of the servers of this filesystem
+    pub fs_name: Option<String>,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self {
+            state: State::default(),
+            cancel: None,
+            pager: paging::Model::default(),
+            columns: table_columns::Model::new("logs", COLUMNS),
+            table: virtual_table::Model::new(ROW_HEIGHT, TABLE_HEIGHT),
+            fs_name: None,
+        }
+    }
 }
 
 pub enum State {
@@ -44,18 +92,26 @@ pub enum Msg {
     FetchOffset,
     Loop,
     Page(paging::Msg),
+    Columns(table_columns::Msg),
+    Table(virtual_table::Msg),
+    SetFsName(Option<String>),
     Noop,
 }
 
 pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
     match msg {
         Msg::FetchOffset => {
-            let builder = log::logs::Builder::new()
+            let mut builder = log::logs::Builder::new()
                 .with_limit(model.pager.limit())
                 .with_offset(model.pager.offset())
                 .with_dir(SortDir::Desc);
+
+            if let Some(x) = &model.fs_name {
+                builder = builder.with_fs_name(x);
+            }
+
             let query = builder.build();
-            let req = fetch::Request::graphql_query(&query);
+            let req = fetch::Request::graphql_get(&query).unwrap_or_else(|_| fetch::Request::graphql_query(&query));
 
             orders.perform_cmd(req.fetch_json_data(|x| Msg::LogsFetched(x)));
         }
@@ -95,6 +151,12 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                 }
             }
         },
+        Msg::Columns(msg) => {
+            table_columns::update(msg, &mut model.columns, &mut orders.proxy(Msg::Columns));
+        }
+        Msg::Table(msg) => {
+            virtual_table::update(msg, &mut model.table, &mut orders.proxy(Msg::Table));
+        }
         Msg::Loop => {
             orders.skip();
 
@@ -104,15 +166,32 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
 
             orders.perform_cmd(fut);
         }
+        Msg::SetFsName(x) => {
+            model.fs_name = x;
+
+            // Dropping the handle stops the current poll, the fetch starts a new one.
+            model.cancel = None;
+            model.state = State::Fetching;
+
+            paging::update(
+                paging::Msg::SetOffset(0),
+                &mut model.pager,
+                &mut orders.proxy(Msg::Page),
+            );
+
+            orders.send_msg(Msg::FetchOffset);
+        }
         Msg::Noop => {}
     }
 }
 
-pub(crate) fn init(orders: &mut impl Orders<Msg, GMsg>) {
+pub(crate) fn init(model: &Model, orders: &mut impl Orders<Msg, GMsg>) {
     orders
         .proxy(Msg::Page)
         .send_msg(paging::Msg::SetLimit(paging::ROW_OPTS[1]));
     orders.send_msg(Msg::FetchOffset);
+
+    table_columns::init(&model.columns, &mut orders.proxy(Msg::Columns));
 }
 
 pub fn view(model: &Model, cache: &ArcCache) -> impl View<Msg> {
@@ -130,7 +209,8 @@ pub fn view(model: &Model, cache: &ArcCache) -> impl View<Msg> {
                         paging::page_count_view(&model.pager).map_msg(Msg::Page)
                     ],
                     div![
-                        class![C.grid, C.grid_cols_2, C.justify_end],
+                        class![C.flex, C.items_center, C.justify_end],
+                        table_columns::view(&model.columns).map_msg(Msg::Columns),
                         paging::next_prev_view(&model.pager).map_msg(Msg::Page)
                     ],
                 ],
@@ -149,12 +229,27 @@ pub fn view(model: &Model, cache: &ArcCache) -> impl View<Msg> {
                         paging::page_count_view(&model.pager).map_msg(Msg::Page)
                     ],
                     div![
-                        class![C.grid, C.grid_cols_2, C.justify_end],
+                        class![C.flex, C.items_center, C.justify_end],
+                        table_columns::view(&model.columns).map_msg(Msg::Columns),
                         paging::next_prev_view(&model.pager).map_msg(Msg::Page)
                     ]
                 ],
             ],
-            response.logs.data.iter().map(|x| { log_item_view(x, cache) })
+            div![
+                class![C.px_6, C.py_4],
+                virtual_table::view(
+                    &model.table,
+                    Msg::Table,
+                    log_head_view(&model.columns),
+                    response.logs.data.len(),
+                    |range| {
+                        response.logs.data[range]
+                            .iter()
+                            .map(|x| log_row_view(x, &model.columns, cache))
+                            .collect()
+                    }
+                )
+            ]
         ],
     }]
 }
@@ -174,36 +269,54 @@ fn log_severity<T>(x: LogSeverity) -> Node<T> {
     }
 }
 
-fn log_item_view(log: &LogMessage, cache: &ArcCache) -> Node<Msg> {
-    div![
-        class![
-            C.bg_menu,
-            C.grid,
-            C.grid_cols_4,
-            C.gap_6,
-            C.rounded,
-            C.text_white,
-            C.mx_4,
-            C.my_6,
-            C.p_8
-        ],
-        div![
-            class![C.text_green_500, C.col_span_3],
-            label_view("Time: "),
-            &log.datetime.format("%H:%M:%S %Y/%m/%d").to_string()
-        ],
-        div![
-            class![C.grid, C.justify_end],
-            log_severity(LogSeverity::from(log.severity))
-        ],
-        div![class![C.col_span_4], log.message],
-        div![
-            class![C.col_span_2],
-            label_view("FQDN: "),
-            server_link(&log.fqdn, &cache.host)
-        ],
-        div![class![C.text_right, C.col_span_2], label_view("Service: "), log.tag],
-    ]
+fn log_head_view(columns: &table_columns::Model) -> Node<Msg> {
+    thead![tr![columns
+        .visible()
+        .filter_map(|x| COLUMNS.iter().find(|c| c.id == x))
+        .map(|c| {
+            th![
+                class![
+                    C.bg_menu_active,
+                    C.font_normal,
+                    C.px_3,
+                    C.sticky,
+                    C.text_gray_500,
+                    C.text_left,
+                    C.top_0
+                ],
+                c.label
+            ]
+        })]]
+}
+
+fn log_row_view(log: &LogMessage, columns: &table_columns::Model, cache: &ArcCache) -> Node<Msg> {
+    let cls = class![C.bg_menu, C.px_3, C.rounded, C.text_white, C.whitespace_no_wrap];
+
+    tr![columns
+        .visible()
+        .map(|c| match c {
+            "time" => td![
+                &cls,
+                class![C.text_green_500],
+                &log.datetime.format("%H:%M:%S %Y/%m/%d").to_string()
+            ],
+            "severity" => td![
+                &cls,
+                class![C.text_center],
+                log_severity(LogSeverity::from(log.severity))
+            ],
+            "message" => td![
+                &cls,
+                class![C.max_w_xs, C.truncate],
+                attrs! { At::Title => &log.message },
+                log.message
+            ],
+            "fqdn" => td![&cls, server_link(&log.fqdn, &cache.host)],
+            "service" => td![&cls, log.tag],
+            "source" => td![&cls, log.source],
+            _ => empty![],
+        })
+        .collect::<Vec<_>>()]
 }
 
 fn server_link<T>(fqdn: &str, hosts: &im::HashMap<i32, Arc<Host>>) -> Node<T> {
@@ -218,7 +331,3 @@ fn server_link<T>(fqdn: &str, hosts: &im::HashMap<i32, Arc<Host>>) -> Node<T> {
         None => plain![fqdn.to_string()],
     }
 }
-
-fn label_view<T>(s: &str) -> Node<T> {
-    span![class![C.text_gray_500], s]
-}
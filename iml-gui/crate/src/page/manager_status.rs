@@ -0,0 +1,126 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    components::{font_awesome, panel, table},
+    extensions::MergeAttrs as _,
+    generated::css_classes::C,
+    sleep_with_handle, GMsg, RequestExt,
+};
+use futures::channel::oneshot;
+use iml_graphql_queries::{manager, Response};
+use iml_wire_types::graphql::{HealthSeverity, ManagerStatus};
+use seed::{prelude::*, *};
+use std::time::Duration;
+
+/// How often the manager status is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+pub struct Model {
+    status: Option<ManagerStatus>,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<manager::status::Resp>>),
+    Noop,
+}
+
+pub fn init(orders: &mut impl Orders<Msg, GMsg>) {
+    orders.send_msg(Msg::Fetch);
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Fetch => {
+            model.cancel = None;
+
+            let query = manager::status::build();
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.status = Some(x.data.manager_status);
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving manager status", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving manager status", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(POLL_INTERVAL, Msg::Fetch, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::Noop => {}
+    }
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    let x = match model.status.as_ref() {
+        Some(x) => x,
+        None => {
+            return panel::view(
+                h3![class![C.py_4, C.font_normal, C.text_lg], "Manager Status"],
+                div![class![C.p_4, C.text_gray_600], "Checking manager services..."],
+            )
+        }
+    };
+
+    panel::view(
+        h3![
+            class![C.py_4, C.font_normal, C.text_lg],
+            "Manager Status",
+            span![
+                class![C.ml_2, C.text_sm, C.text_gray_600],
+                format!("checked at {}", x.checked_at.format("%m/%d/%Y %H:%M:%S"))
+            ]
+        ],
+        table::wrapper_view(vec![
+            table::thead_view(vec![
+                table::th_left(plain!["Service"]),
+                table::th_view(plain!["Status"]),
+                table::th_view(plain!["Response"]),
+                table::th_left(plain!["Details"]),
+            ]),
+            tbody![x.services.iter().map(|x| {
+                tr![
+                    table::labelled(table::td_view(plain![x.name.clone()]), "Service"),
+                    table::labelled(table::td_center(severity_view(x.severity)), "Status"),
+                    table::labelled(
+                        table::td_center(plain![match x.response_ms {
+                            Some(ms) => format!("{} ms", ms),
+                            None => "---".to_string(),
+                        }]),
+                        "Response"
+                    ),
+                    table::labelled(table::td_view(ul![x.details.iter().map(|d| li![d])]), "Details"),
+                ]
+            })],
+        ])
+        .merge_attrs(class![C.my_6]),
+    )
+}
+
+pub(crate) fn severity_view<T>(x: HealthSeverity) -> Node<T> {
+    let (color, icon, label) = match x {
+        HealthSeverity::Good => (C.text_green_500, "check-circle", "Good"),
+        HealthSeverity::Warning => (C.text_yellow_500, "exclamation-triangle", "Warning"),
+        HealthSeverity::Error => (C.text_red_500, "exclamation-circle", "Error"),
+    };
+
+    span![
+        class![color],
+        font_awesome(class![C.w_4, C.h_4, C.inline, C.mr_1], icon),
+        label
+    ]
+}
@@ -15,7 +15,7 @@ use iml_api_utils::extract_id;
 use iml_wire_types::{
     db::{ManagedTargetRecord, TargetKind},
     warp_drive::{ArcCache, ArcValuesExt, Locks},
-    Label, Session, ToCompositeId,
+    Filesystem, Label, Session, ToCompositeId,
 };
 use seed::{prelude::*, *};
 use std::{borrow::Cow, collections::HashMap, sync::Arc};
@@ -28,6 +28,7 @@ pub struct Row {
 pub struct Model {
     pub rows: HashMap<i32, Row>,
     pub mgts: Vec<Arc<ManagedTargetRecord>>,
+    pub fs_name: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -42,6 +43,28 @@ pub fn init(cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
     orders.send_msg(Msg::SetTargets(cache.target.values().cloned().collect()));
 }
 
+/// Only lists the MGT of the filesystem of the context.
+pub fn set_fs_context(fs_name: Option<&str>, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    model.fs_name = fs_name.map(String::from);
+
+    orders.send_msg(Msg::SetTargets(cache.target.values().cloned().collect()));
+}
+
+/// Does the MGT `id` serve the filesystem `fs_name`, or any when `None`?
+fn in_context(cache: &ArcCache, fs_name: Option<&str>, id: i32) -> bool {
+    fs_name.map_or(true, |fs_name| filesystems(cache, id).any(|x| x.name == fs_name))
+}
+
+/// The filesystems that use the MGT `id`.
+fn filesystems(cache: &ArcCache, id: i32) -> impl Iterator<Item = &Filesystem> {
+    cache.filesystem.arc_values().filter(move |x| {
+        extract_id(&x.mgt)
+            .and_then(|x| x.parse::<i32>().ok())
+            .filter(|x| x == &id)
+            .is_some()
+    })
+}
+
 pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
     match msg {
         Msg::ActionDropdown(x) => {
@@ -69,7 +92,13 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
                 })
                 .collect();
 
-            let mut mgts: Vec<_> = xs.into_iter().filter(|x| x.get_kind() == TargetKind::Mgt).collect();
+            let fs_name = model.fs_name.as_deref();
+
+            let mut mgts: Vec<_> = xs
+                .into_iter()
+                .filter(|x| x.get_kind() == TargetKind::Mgt)
+                .filter(|x| in_context(cache, fs_name, x.id))
+                .collect();
 
             mgts.sort_by(|a, b| natord::compare(a.label(), b.label()));
 
@@ -121,16 +150,7 @@ pub fn view(cache: &ArcCache, model: &Model, all_locks: &Locks, session: Option<
                 tbody![model.mgts.iter().map(|x| match model.rows.get(&x.id) {
                     None => empty![],
                     Some(row) => {
-                        let fs: Vec<_> = cache
-                            .filesystem
-                            .arc_values()
-                            .filter(|y| {
-                                extract_id(&y.mgt)
-                                    .and_then(|y| y.parse::<i32>().ok())
-                                    .filter(|y| y == &x.id)
-                                    .is_some()
-                            })
-                            .collect();
+                        let fs: Vec<_> = filesystems(cache, x.id).collect();
 
                         let t = get_target_from_managed_target(cache, x);
 
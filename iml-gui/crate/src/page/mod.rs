@@ -4,6 +4,7 @@
 
 pub mod about;
 pub mod activity;
+pub mod custom_dashboard;
 pub mod dashboard;
 pub mod filesystem;
 pub mod filesystems;
@@ -11,17 +12,23 @@ pub mod fs_dashboard;
 pub mod jobstats;
 pub mod login;
 pub mod logs;
+pub mod manager_status;
 pub mod mgts;
 pub mod not_found;
+pub mod on_call;
 pub mod ostpool;
 pub mod ostpools;
 pub mod partial;
 pub mod power_control;
+pub mod roles;
 pub mod server;
 pub mod server_dashboard;
+pub mod server_profile_compare;
 pub mod servers;
 pub mod sfa_enclosure;
+pub mod sites;
 pub mod snapshot;
+pub mod snapshot_compare;
 pub mod stratagem;
 pub mod target;
 pub mod target_dashboard;
@@ -59,17 +66,23 @@ pub(crate) enum Page {
     Filesystems(filesystems::Model),
     Filesystem(Box<filesystem::Model>),
     Dashboard(dashboard::Model),
+    CustomDashboard(custom_dashboard::Model),
     FsDashboard(Box<fs_dashboard::Model>),
     ServerDashboard(server_dashboard::Model),
     TargetDashboard(target_dashboard::Model),
     Jobstats,
     Login(login::Model),
+    ManagerStatus(manager_status::Model),
     Mgts(mgts::Model),
     NotFound,
+    OnCall,
     OstPools,
     OstPool(ostpool::Model),
     PowerControl,
+    Roles(roles::Model),
     Servers(servers::Model),
+    ServerProfileCompare(server_profile_compare::Model),
+    Sites(sites::Model),
     Server(Box<server::Model>),
     Targets,
     Target(Box<target::Model>),
@@ -80,6 +93,7 @@ pub(crate) enum Page {
     Volume(volume::Model),
     SfaEnclosure(sfa_enclosure::Model),
     Snapshots(snapshot::Model),
+    SnapshotCompare(Box<snapshot_compare::Model>),
     Stratagem(stratagem::Model),
 }
 
@@ -91,17 +105,23 @@ impl Page {
             Self::Filesystems(_) => "Filesystems".into(),
             Self::Filesystem(m) => format!("Filesystem: {}", &m.fs.name),
             Self::Dashboard(_) => "Dashboard".into(),
+            Self::CustomDashboard(_) => "Dashboards".into(),
             Self::FsDashboard(m) => format!("{} Filesystem Dashboard", &m.fs_name),
             Self::ServerDashboard(m) => format!("{} Server Dashboard", &m.host_name),
             Self::TargetDashboard(m) => format!("{} Target Dashboard", &m.target_name),
             Self::Jobstats => "Jobstats".into(),
             Self::Login(_) => "Login".into(),
+            Self::ManagerStatus(_) => "Manager Status".into(),
             Self::Mgts(_) => "MGTs".into(),
             Self::NotFound => "Page not found".into(),
+            Self::OnCall => "On-call".into(),
             Self::OstPools => "OST Pools".into(),
             Self::OstPool(m) => format!("OST Pool: {}", &m.id),
             Self::PowerControl => "Power Control".into(),
+            Self::Roles(_) => "Roles".into(),
             Self::Servers(_) => "Servers".into(),
+            Self::ServerProfileCompare(_) => "Compare Server Profiles".into(),
+            Self::Sites(_) => "Sites".into(),
             Self::Server(m) => format!("Server: {}", &m.server.fqdn),
             Self::Targets => "Targets".into(),
             Self::Target(m) => format!("Target: {}", m.target.label()),
@@ -112,6 +132,7 @@ impl Page {
             Self::Volume(m) => format!("Volume: {}", &m.id),
             Self::SfaEnclosure(m) => format!("Sfa Enclosure: {}", &m.id),
             Self::Snapshots(_) => "Snapshots".into(),
+            Self::SnapshotCompare(m) => format!("Compare Snapshots: {}", &m.fs.name),
             Self::Stratagem(_) => "Stratagem".into(),
         }
     }
@@ -171,6 +192,8 @@ impl<'a> From<(&ArcCache, &Conf, &Route<'a>)> for Page {
                 },
                 ..dashboard::Model::default()
             }),
+            Route::CustomDashboards => Self::CustomDashboard(custom_dashboard::Model::new(None)),
+            Route::CustomDashboard(id) => Self::CustomDashboard(custom_dashboard::Model::new(id.parse().ok())),
             Route::FsDashboard(id) => Self::FsDashboard(Box::new(fs_dashboard::Model::new(id.to_string()))),
             Route::ServerDashboard(id) => Self::ServerDashboard(server_dashboard::Model {
                 host_name: id.to_string(),
@@ -181,15 +204,20 @@ impl<'a> From<(&ArcCache, &Conf, &Route<'a>)> for Page {
             }),
             Route::Jobstats => Self::Jobstats,
             Route::Login => Self::Login(login::Model::default()),
+            Route::ManagerStatus => Self::ManagerStatus(manager_status::Model::default()),
             Route::Mgt => Self::Mgts(mgts::Model::default()),
             Route::NotFound => Self::NotFound,
+            Route::OnCall => Self::OnCall,
             Route::OstPools => Self::OstPools,
             Route::OstPool(id) => id
                 .parse()
                 .map(|id| Self::OstPool(ostpool::Model { id }))
                 .unwrap_or_default(),
             Route::PowerControl => Self::PowerControl,
+            Route::Roles => Self::Roles(roles::Model::default()),
             Route::Servers => Self::Servers(servers::Model::default()),
+            Route::ServerProfileCompare => Self::ServerProfileCompare(server_profile_compare::Model::default()),
+            Route::Sites => Self::Sites(sites::Model::default()),
             Route::Server(id) => id
                 .parse()
                 .ok()
@@ -235,6 +263,12 @@ impl<'a> From<(&ArcCache, &Conf, &Route<'a>)> for Page {
                 .map(|id| Self::SfaEnclosure(sfa_enclosure::Model { id }))
                 .unwrap_or_default(),
             Route::Snapshots => Self::Snapshots(snapshot::Model::default()),
+            Route::SnapshotCompare(id) => id
+                .parse()
+                .ok()
+                .and_then(|x| cache.filesystem.get(&x))
+                .map(|x| Self::SnapshotCompare(Box::new(snapshot_compare::Model::new(Arc::clone(x)))))
+                .unwrap_or_default(),
             Route::Stratagem => Self::Stratagem(stratagem::Model::default()),
         }
     }
@@ -249,11 +283,16 @@ impl Page {
             | (Route::Dashboard, Self::Dashboard(dashboard::Model { .. }))
             | (Route::Jobstats, Self::Jobstats)
             | (Route::Login, Self::Login(_))
+            | (Route::ManagerStatus, Self::ManagerStatus(_))
             | (Route::Mgt, Self::Mgts(_))
             | (Route::NotFound, Self::NotFound)
+            | (Route::OnCall, Self::OnCall)
             | (Route::OstPools, Self::OstPools)
             | (Route::PowerControl, Self::PowerControl)
+            | (Route::Roles, Self::Roles(_))
             | (Route::Servers, Self::Servers(_))
+            | (Route::ServerProfileCompare, Self::ServerProfileCompare(_))
+            | (Route::Sites, Self::Sites(_))
             | (Route::Targets, Self::Targets)
             | (Route::Users, Self::Users)
             | (Route::Snapshots, Self::Snapshots(_))
@@ -264,7 +303,12 @@ impl Page {
             (Route::User(route_id), Self::User(x)) => route_id == &RouteId::from(x.user.id),
             (Route::Filesystem(route_id), Self::Filesystem(x)) => route_id == &RouteId::from(x.fs.id),
             (Route::Target(route_id), Self::Target(x)) => route_id == &RouteId::from(x.target.id),
+            (Route::SnapshotCompare(route_id), Self::SnapshotCompare(x)) => route_id == &RouteId::from(x.fs.id),
             (Route::SfaEnclosure(route_id), Self::SfaEnclosure(x)) => route_id == &RouteId::from(x.id),
+            (Route::CustomDashboards, Self::CustomDashboard(x)) => x.id.is_none(),
+            (Route::CustomDashboard(route_id), Self::CustomDashboard(x)) => {
+                x.id.map(RouteId::from).as_ref() == Some(route_id)
+            }
             (Route::FsDashboard(route_id), Self::FsDashboard(x)) => {
                 let fs_dashboard::Model { fs_name, .. } = &**x;
                 &route_id.to_string() == fs_name
@@ -284,15 +328,33 @@ impl Page {
             Self::Servers(_) => {
                 servers::init(cache, &mut orders.proxy(Msg::Servers));
             }
+            Self::ServerProfileCompare(_) => {
+                server_profile_compare::init(&mut orders.proxy(Msg::ServerProfileCompare));
+            }
+            Self::Server(m) => {
+                server::init(m, &mut orders.proxy(Msg::Server));
+            }
             Self::Filesystems(_) => {
                 filesystems::init(cache, &mut orders.proxy(Msg::Filesystems));
             }
             Self::Filesystem(m) => {
                 filesystem::init(cache, m, &mut orders.proxy(Msg::Filesystem));
             }
+            Self::ManagerStatus(_) => {
+                manager_status::init(&mut orders.proxy(Msg::ManagerStatus));
+            }
             Self::Mgts(_) => {
                 mgts::init(cache, &mut orders.proxy(Msg::Mgts));
             }
+            Self::Roles(_) => {
+                roles::init(&mut orders.proxy(Msg::Roles));
+            }
+            Self::Sites(_) => {
+                sites::init(&mut orders.proxy(Msg::Sites));
+            }
+            Self::CustomDashboard(_) => {
+                custom_dashboard::init(&mut orders.proxy(Msg::CustomDashboard));
+            }
             Self::FsDashboard(_) => {
                 fs_dashboard::init(&mut orders.proxy(Msg::FsDashboard));
             }
@@ -305,25 +367,54 @@ impl Page {
             Self::Snapshots(m) => {
                 snapshot::init(cache, m, &mut orders.proxy(Msg::Snapshots));
             }
+            Self::SnapshotCompare(m) => {
+                snapshot_compare::init(cache, m, &mut orders.proxy(Msg::SnapshotCompare));
+            }
             Self::Stratagem(m) => {
                 stratagem::init(m, &mut orders.proxy(Msg::Stratagem));
             }
+            Self::Target(_) => {
+                target::init(&mut orders.proxy(Msg::Target));
+            }
             _ => {}
         };
     }
+    /// Fetches what the page shows of `table` again, as it changed.
+    pub fn invalidate(&mut self, table: &str, orders: &mut impl Orders<Msg, GMsg>) {
+        match self {
+            Self::Filesystem(_) => filesystem::invalidate(table, &mut orders.proxy(Msg::Filesystem)),
+            Self::Servers(_) => servers::invalidate(table, &mut orders.proxy(Msg::Servers)),
+            _ => {}
+        }
+    }
+    /// Scopes the page to the filesystem context, `None` for all filesystems.
+    /// Called after `init`, and whenever the context changes.
+    pub fn set_fs_context(&mut self, fs_name: Option<&str>, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
+        match self {
+            Self::Dashboard(m) => dashboard::set_fs_context(fs_name, m, &mut orders.proxy(Msg::Dashboard)),
+            Self::Filesystems(m) => filesystems::set_fs_context(fs_name, cache, m, &mut orders.proxy(Msg::Filesystems)),
+            Self::Mgts(m) => mgts::set_fs_context(fs_name, cache, m, &mut orders.proxy(Msg::Mgts)),
+            Self::Snapshots(m) => snapshot::set_fs_context(fs_name, cache, m, &mut orders.proxy(Msg::Snapshots)),
+            _ => {}
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum Msg {
     About(about::Msg),
+    CustomDashboard(custom_dashboard::Msg),
     Dashboard(dashboard::Msg),
     Filesystem(filesystem::Msg),
     Filesystems(filesystems::Msg),
     FsDashboard(fs_dashboard::Msg),
+    ManagerStatus(manager_status::Msg),
     Mgts(mgts::Msg),
+    OnCall(on_call::Msg),
     Server(server::Msg),
     ServerDashboard(server_dashboard::Msg),
     Servers(servers::Msg),
+    ServerProfileCompare(server_profile_compare::Msg),
     Target(target::Msg),
     Targets(targets::Msg),
     TargetDashboard(target_dashboard::Msg),
@@ -331,6 +422,8 @@ pub enum Msg {
     OstPools(ostpools::Msg),
     OstPool(ostpool::Msg),
     PowerControl(power_control::Msg),
+    Roles(roles::Msg),
+    Sites(sites::Msg),
     Login(Box<login::Msg>),
     User(user::Msg),
     Users(users::Msg),
@@ -338,6 +431,7 @@ pub enum Msg {
     Volumes(volumes::Msg),
     SfaEnclosure(sfa_enclosure::Msg),
     Snapshots(snapshot::Msg),
+    SnapshotCompare(snapshot_compare::Msg),
     Stratagem(stratagem::Msg),
 }
 
@@ -348,6 +442,11 @@ pub(crate) fn update(msg: Msg, page: &mut Page, cache: &ArcCache, orders: &mut i
                 servers::update(msg, cache, page, &mut orders.proxy(Msg::Servers))
             }
         }
+        Msg::ServerProfileCompare(msg) => {
+            if let Page::ServerProfileCompare(m) = page {
+                server_profile_compare::update(msg, m, &mut orders.proxy(Msg::ServerProfileCompare))
+            }
+        }
         Msg::Server(msg) => {
             if let Page::Server(page) = page {
                 server::update(msg, cache, page, &mut orders.proxy(Msg::Server))
@@ -363,11 +462,31 @@ pub(crate) fn update(msg: Msg, page: &mut Page, cache: &ArcCache, orders: &mut i
                 filesystems::update(msg, cache, page, &mut orders.proxy(Msg::Filesystems))
             }
         }
+        Msg::ManagerStatus(msg) => {
+            if let Page::ManagerStatus(page) = page {
+                manager_status::update(msg, page, &mut orders.proxy(Msg::ManagerStatus))
+            }
+        }
         Msg::Mgts(msg) => {
             if let Page::Mgts(page) = page {
                 mgts::update(msg, cache, page, &mut orders.proxy(Msg::Mgts))
             }
         }
+        Msg::OnCall(msg) => {
+            if let Page::OnCall = page {
+                on_call::update(msg, &mut orders.proxy(Msg::OnCall))
+            }
+        }
+        Msg::Roles(msg) => {
+            if let Page::Roles(page) = page {
+                roles::update(msg, page, &mut orders.proxy(Msg::Roles))
+            }
+        }
+        Msg::Sites(msg) => {
+            if let Page::Sites(page) = page {
+                sites::update(msg, page, &mut orders.proxy(Msg::Sites))
+            }
+        }
         Msg::Target(msg) => {
             if let Page::Target(page) = page {
                 target::update(msg, cache, page, &mut orders.proxy(Msg::Target))
@@ -378,6 +497,11 @@ pub(crate) fn update(msg: Msg, page: &mut Page, cache: &ArcCache, orders: &mut i
                 dashboard::update(msg, page, &mut orders.proxy(Msg::Dashboard))
             }
         }
+        Msg::CustomDashboard(msg) => {
+            if let Page::CustomDashboard(page) = page {
+                custom_dashboard::update(msg, page, &mut orders.proxy(Msg::CustomDashboard))
+            }
+        }
         Msg::FsDashboard(msg) => {
             if let Page::FsDashboard(page) = page {
                 fs_dashboard::update(msg, page, &mut orders.proxy(Msg::FsDashboard))
@@ -407,6 +531,11 @@ pub(crate) fn update(msg: Msg, page: &mut Page, cache: &ArcCache, orders: &mut i
                 snapshot::update(msg, m, &mut orders.proxy(Msg::Snapshots))
             }
         }
+        Msg::SnapshotCompare(msg) => {
+            if let Page::SnapshotCompare(m) = page {
+                snapshot_compare::update(msg, m, &mut orders.proxy(Msg::SnapshotCompare))
+            }
+        }
         Msg::Stratagem(msg) => {
             if let Page::Stratagem(m) = page {
                 stratagem::update(msg, m, &mut orders.proxy(Msg::Stratagem))
@@ -0,0 +1,204 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! A single column view of what needs attention, for checking on the system from a phone.
+
+use crate::{
+    components::{command_modal, date, font_awesome, health_banner, panel},
+    generated::css_classes::C,
+    page::activity::{alert_item_classes, date_view, is_cmd},
+    GMsg, Model,
+};
+use iml_api_utils::extract_id;
+use iml_wire_types::{Alert, AlertRecordType};
+use seed::{prelude::*, *};
+use std::{cmp::Reverse, sync::Arc};
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    OpenCommandModal(i32),
+}
+
+pub fn update(msg: Msg, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::OpenCommandModal(id) => {
+            orders.send_g_msg(GMsg::OpenCommandModal(command_modal::Input::Ids(vec![id])));
+        }
+    }
+}
+
+/// Active alerts, most severe first, then newest first.
+fn active_alerts(xs: &[&Arc<Alert>]) -> Vec<Arc<Alert>> {
+    let mut xs: Vec<_> = xs
+        .iter()
+        .filter(|x| !is_cmd(x.record_type))
+        .map(|&x| Arc::clone(x))
+        .collect();
+
+    xs.sort_by_key(|x| (Reverse(x.severity), Reverse(x.begin.clone())));
+
+    xs
+}
+
+/// Commands that are still running, newest first.
+fn running_commands(xs: &[&Arc<Alert>]) -> Vec<Arc<Alert>> {
+    let mut xs: Vec<_> = xs
+        .iter()
+        .filter(|x| x.record_type == AlertRecordType::CommandRunningAlert)
+        .map(|&x| Arc::clone(x))
+        .collect();
+
+    xs.sort_by_key(|x| Reverse(x.begin.clone()));
+
+    xs
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    let xs: Vec<_> = model.records.active_alert.values().collect();
+
+    let alerts = active_alerts(&xs);
+    let commands = running_commands(&xs);
+
+    div![
+        class![C.grid, C.gap_4],
+        health_banner::summary_view(&model.health_banner),
+        panel::view(
+            h3![
+                class![C.py_4, C.font_normal, C.text_lg],
+                format!("Active Alerts ({})", alerts.len())
+            ],
+            if alerts.is_empty() {
+                div![class![C.p_4, C.text_gray_600], "No active alerts."]
+            } else {
+                div![alerts.iter().map(|x| alert_card(&model.server_date, x))]
+            }
+        ),
+        panel::view(
+            h3![
+                class![C.py_4, C.font_normal, C.text_lg],
+                format!("Running Commands ({})", commands.len())
+            ],
+            if commands.is_empty() {
+                div![class![C.p_4, C.text_gray_600], "No commands are running."]
+            } else {
+                div![commands.iter().map(|x| command_card(&model.server_date, x))]
+            }
+        ),
+    ]
+}
+
+fn alert_card(sd: &date::Model, x: &Alert) -> Node<Msg> {
+    let (border_color, icon_color, _) = alert_item_classes(x);
+
+    div![
+        class![C.flex, C.items_start, C.border_l_4, C.border_b, C.p_3, border_color],
+        font_awesome(class![C.w_4, C.h_4, C.mt_1, C.mr_3, C.flex_none, icon_color], "bell"),
+        div![
+            p![class![C.text_gray_800], &x.message],
+            p![
+                class![C.text_xs, C.text_gray_500],
+                format!("{:?} · since ", x.severity),
+                date_view(sd, &x.begin)
+            ]
+        ]
+    ]
+}
+
+fn command_card(sd: &date::Model, x: &Alert) -> Node<Msg> {
+    let id = extract_id(&x.alert_item).and_then(|x| x.parse().ok());
+
+    div![
+        class![C.flex, C.items_start, C.border_b, C.p_3, C.cursor_pointer => id.is_some()],
+        font_awesome(
+            class![C.w_4, C.h_4, C.mt_1, C.mr_3, C.flex_none, C.text_gray_500, C.pulse],
+            "spinner"
+        ),
+        div![
+            p![class![C.text_gray_800], &x.message],
+            p![class![C.text_xs, C.text_gray_500], "Started ", date_view(sd, &x.begin)]
+        ],
+        id.map(|id| simple_ev(Ev::Click, Msg::OpenCommandModal(id)))
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iml_wire_types::AlertSeverity;
+
+    fn alert(id: i32, record_type: AlertRecordType, severity: AlertSeverity, begin: &str) -> Arc<Alert> {
+        Arc::new(Alert {
+            _message: None,
+            active: Some(true),
+            affected: None,
+            affected_composite_ids: None,
+            alert_item: format!("/api/command/{}/", id),
+            alert_item_id: Some(id),
+            alert_item_str: String::new(),
+            alert_type: String::new(),
+            begin: begin.into(),
+            dismissed: false,
+            end: None,
+            id,
+            lustre_pid: None,
+            message: format!("alert {}", id),
+            record_type,
+            resource_uri: format!("/api/alert/{}/", id),
+            severity,
+            variant: "{}".into(),
+        })
+    }
+
+    #[test]
+    fn test_active_alerts_and_running_commands() {
+        let xs = vec![
+            alert(
+                1,
+                AlertRecordType::HostOfflineAlert,
+                AlertSeverity::WARNING,
+                "2021-01-01T00:00:00Z",
+            ),
+            alert(
+                2,
+                AlertRecordType::HostOfflineAlert,
+                AlertSeverity::ERROR,
+                "2021-01-01T00:00:00Z",
+            ),
+            alert(
+                3,
+                AlertRecordType::HostOfflineAlert,
+                AlertSeverity::WARNING,
+                "2021-01-02T00:00:00Z",
+            ),
+            alert(
+                4,
+                AlertRecordType::CommandRunningAlert,
+                AlertSeverity::INFO,
+                "2021-01-01T00:00:00Z",
+            ),
+            alert(
+                5,
+                AlertRecordType::CommandRunningAlert,
+                AlertSeverity::INFO,
+                "2021-01-03T00:00:00Z",
+            ),
+            alert(
+                6,
+                AlertRecordType::CommandErroredAlert,
+                AlertSeverity::ERROR,
+                "2021-01-03T00:00:00Z",
+            ),
+        ];
+        let xs: Vec<_> = xs.iter().collect();
+
+        assert_eq!(
+            active_alerts(&xs).iter().map(|x| x.id).collect::<Vec<_>>(),
+            vec![2, 3, 1]
+        );
+        assert_eq!(
+            running_commands(&xs).iter().map(|x| x.id).collect::<Vec<_>>(),
+            vec![5, 4]
+        );
+    }
+}
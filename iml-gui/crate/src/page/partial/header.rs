@@ -5,8 +5,8 @@
 use crate::{
     auth, breakpoints,
     components::{
-        ai_200x, ai_400x, ai_7990x, breadcrumbs, ddn_logo, ddn_logo_lettering, exa5, font_awesome, restrict,
-        whamcloud_logo,
+        ai_200x, ai_400x, ai_7990x, breadcrumbs, ddn_logo, ddn_logo_lettering, exa5, font_awesome, fs_context,
+        restrict, whamcloud_logo,
     },
     generated::css_classes::C,
     MergeAttrs, Model, Msg, Route, SessionExt,
@@ -78,6 +78,12 @@ fn nav_manage_dropdown(open: bool, conf: &iml_wire_types::Conf) -> Node<Msg> {
                     At::Href => Route::Mgt.to_href(),
                 },
             ],
+            li![
+                a![&cls, "Dashboards"],
+                attrs! {
+                    At::Href => Route::CustomDashboards.to_href(),
+                },
+            ],
             if conf.use_snapshots {
                 li![
                     a![&cls, "Snapshots"],
@@ -103,6 +109,24 @@ fn nav_manage_dropdown(open: bool, conf: &iml_wire_types::Conf) -> Node<Msg> {
                 attrs! {
                     At::Href => Route::Users.to_href(),
                 },
+            ],
+            li![
+                a![&cls, "Roles"],
+                attrs! {
+                    At::Href => Route::Roles.to_href(),
+                },
+            ],
+            li![
+                a![&cls, "Manager Status"],
+                attrs! {
+                    At::Href => Route::ManagerStatus.to_href(),
+                },
+            ],
+            li![
+                a![&cls, "Sites"],
+                attrs! {
+                    At::Href => Route::Sites.to_href(),
+                },
             ]
         ]
     ]
@@ -144,6 +168,20 @@ fn main_menu_items(model: &Model) -> Node<Msg> {
                 ],
             ]
         ],
+        a![
+            &menu_class,
+            class![C.bg_menu_active => model.route == Route::OnCall],
+            attrs! {
+                At::Href => Route::OnCall.to_href()
+            },
+            span![
+                menu_icon("bell"),
+                span![
+                    class![C.group_hover__text_active, C.text_active => model.route == Route::OnCall],
+                    "On-call",
+                ],
+            ]
+        ],
         restrict::view(
             model.auth.get_session(),
             GroupType::FilesystemAdministrators,
@@ -275,6 +313,7 @@ fn nav(model: &Model) -> Node<Msg> {
                     C.lg__h_16,
                 ],
                 main_menu_items(model),
+                fs_context_view(model),
                 auth_view(&model.auth),
             ]
         } else {
@@ -283,6 +322,14 @@ fn nav(model: &Model) -> Node<Msg> {
     ]
 }
 
+/// The filesystem context is saved per user, so it's only offered once logged in.
+fn fs_context_view(model: &Model) -> Node<Msg> {
+    match model.auth.get_session() {
+        Some(x) if x.has_user() => fs_context::view(&model.records, &model.fs_context).map_msg(Msg::FsContext),
+        _ => empty![],
+    }
+}
+
 /// Show the logged in user if available.
 /// Also show the Login / Logout link
 pub fn auth_view(auth: &auth::Model) -> Node<Msg> {
@@ -0,0 +1,427 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    components::{font_awesome, form, panel, table},
+    extensions::{MergeAttrs as _, NodeExt as _},
+    generated::css_classes::C,
+    GMsg, RequestExt,
+};
+use iml_graphql_queries::{role, Response};
+use iml_wire_types::{
+    role::{Permission, Role},
+    warp_drive::ArcCache,
+};
+use seed::{prelude::*, *};
+use std::collections::HashMap;
+
+#[derive(Default, Debug)]
+pub struct Model {
+    roles: Vec<Role>,
+    /// Whether the current user may change roles
+    can_manage: bool,
+    name: String,
+    description: String,
+    /// The user picked to be assigned, by role id
+    selected_user: HashMap<i32, i32>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    FetchRoles,
+    Roles(fetch::ResponseDataResult<Response<role::list::Resp>>),
+    MyPermissions(fetch::ResponseDataResult<Response<role::my_permissions::Resp>>),
+    NameChanged(String),
+    DescriptionChanged(String),
+    Create,
+    Created(fetch::ResponseDataResult<Response<role::create::Resp>>),
+    TogglePermission(i32, Permission),
+    Updated(fetch::ResponseDataResult<Response<role::update::Resp>>),
+    Remove(i32, String),
+    Removed(fetch::ResponseDataResult<Response<role::remove::Resp>>),
+    SelectUser(i32, String),
+    Assign(i32),
+    Unassign(i32, i32),
+    Assigned(fetch::ResponseDataResult<Response<role::assign::Resp>>),
+    Unassigned(fetch::ResponseDataResult<Response<role::unassign::Resp>>),
+}
+
+fn permission_label(x: Permission) -> &'static str {
+    match x {
+        Permission::CreateSnapshots => "Create Snapshots",
+        Permission::DeleteSnapshots => "Delete Snapshots",
+        Permission::ManageSnapshotPolicies => "Snapshot Policies",
+        Permission::RunStratagem => "Run Stratagem",
+        Permission::ManageRoles => "Manage Roles",
+        Permission::ManageBanners => "Manage Banners",
+        Permission::ApproveCommands => "Approve Commands",
+    }
+}
+
+fn replace_role(model: &mut Model, role: Role) {
+    if let Some(x) = model.roles.iter_mut().find(|x| x.id == role.id) {
+        *x = role;
+    }
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::FetchRoles => {
+            let query = role::list::build();
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Roles));
+        }
+        Msg::Roles(x) => match x {
+            Ok(Response::Data(d)) => {
+                model.roles = d.data.role.list;
+            }
+            Ok(Response::Errors(e)) => {
+                error!("Roles were not fetched: ", e);
+            }
+            Err(e) => {
+                error!("Roles were not fetched: ", e);
+            }
+        },
+        Msg::MyPermissions(x) => match x {
+            Ok(Response::Data(d)) => {
+                model.can_manage = d.data.role.my_permissions.contains(&Permission::ManageRoles);
+            }
+            Ok(Response::Errors(e)) => {
+                error!("Permissions were not fetched: ", e);
+            }
+            Err(e) => {
+                error!("Permissions were not fetched: ", e);
+            }
+        },
+        Msg::NameChanged(x) => {
+            model.name = x;
+        }
+        Msg::DescriptionChanged(x) => {
+            model.description = x;
+        }
+        Msg::Create => {
+            let description = Some(model.description.trim()).filter(|x| !x.is_empty());
+
+            let query = role::create::build(model.name.trim(), description, vec![]);
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Created));
+        }
+        Msg::Created(x) => match x {
+            Ok(Response::Data(d)) => {
+                model.name = "".into();
+                model.description = "".into();
+                model.roles.push(d.data.role.create);
+                model.roles.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+            Ok(Response::Errors(e)) => {
+                error!("Error creating the role: ", e);
+            }
+            Err(e) => {
+                error!("Error creating the role: ", e);
+            }
+        },
+        Msg::TogglePermission(id, permission) => {
+            let x = match model.roles.iter().find(|x| x.id == id) {
+                Some(x) => x,
+                None => return,
+            };
+
+            let permissions = if x.permissions.contains(&permission) {
+                x.permissions.iter().copied().filter(|x| x != &permission).collect()
+            } else {
+                let mut xs = x.permissions.clone();
+                xs.push(permission);
+                xs
+            };
+
+            let query = role::update::build(id, None::<String>, None::<String>, Some(permissions));
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Updated));
+        }
+        Msg::Updated(x) => match x {
+            Ok(Response::Data(d)) => {
+                replace_role(model, d.data.role.update);
+            }
+            Ok(Response::Errors(e)) => {
+                error!("Error updating the role: ", e);
+            }
+            Err(e) => {
+                error!("Error updating the role: ", e);
+            }
+        },
+        Msg::Remove(id, name) => {
+            if let Ok(true) = window().confirm_with_message(&format!("Remove role {}?", name)) {
+                let query = role::remove::build(id);
+                let req = fetch::Request::graphql_query(&query);
+
+                orders.perform_cmd(req.fetch_json_data(Msg::Removed));
+            }
+        }
+        Msg::Removed(x) => {
+            match x {
+                Ok(Response::Data(_)) => {}
+                Ok(Response::Errors(e)) => {
+                    error!("Error removing the role: ", e);
+                }
+                Err(e) => {
+                    error!("Error removing the role: ", e);
+                }
+            }
+
+            orders.send_msg(Msg::FetchRoles);
+        }
+        Msg::SelectUser(id, x) => match x.parse() {
+            Ok(user_id) => {
+                model.selected_user.insert(id, user_id);
+            }
+            Err(_) => {
+                model.selected_user.remove(&id);
+            }
+        },
+        Msg::Assign(id) => {
+            if let Some(user_id) = model.selected_user.remove(&id) {
+                let query = role::assign::build(id, vec![user_id]);
+                let req = fetch::Request::graphql_query(&query);
+
+                orders.perform_cmd(req.fetch_json_data(Msg::Assigned));
+            }
+        }
+        Msg::Unassign(id, user_id) => {
+            let query = role::unassign::build(id, vec![user_id]);
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Unassigned));
+        }
+        Msg::Assigned(x) => match x {
+            Ok(Response::Data(d)) => {
+                replace_role(model, d.data.role.assign);
+            }
+            Ok(Response::Errors(e)) => {
+                error!("Error assigning the role: ", e);
+            }
+            Err(e) => {
+                error!("Error assigning the role: ", e);
+            }
+        },
+        Msg::Unassigned(x) => match x {
+            Ok(Response::Data(d)) => {
+                replace_role(model, d.data.role.unassign);
+            }
+            Ok(Response::Errors(e)) => {
+                error!("Error unassigning the role: ", e);
+            }
+            Err(e) => {
+                error!("Error unassigning the role: ", e);
+            }
+        },
+    }
+}
+
+fn users_view(role: &Role, cache: &ArcCache, model: &Model) -> Node<Msg> {
+    let id = role.id;
+
+    div![
+        class![C.flex, C.flex_wrap, C.items_center],
+        role.user_ids.iter().map(|user_id| {
+            let user_id = *user_id;
+
+            let name = cache
+                .user
+                .get(&user_id)
+                .map(|x| x.username.to_string())
+                .unwrap_or_else(|| user_id.to_string());
+
+            span![
+                class![C.bg_gray_200, C.mr_2, C.my_2, C.px_2, C.py_1, C.rounded_full, C.text_sm],
+                name,
+                if model.can_manage {
+                    span![
+                        class![C.cursor_pointer, C.ml_2, C.text_red_500],
+                        font_awesome(class![C.w_3, C.h_3, C.inline], "times"),
+                        simple_ev(Ev::Click, Msg::Unassign(id, user_id))
+                    ]
+                } else {
+                    empty![]
+                }
+            ]
+        }),
+        if model.can_manage {
+            div![
+                class![C.flex, C.items_center],
+                select![
+                    class![C.bg_gray_200, C.px_2, C.py_1, C.rounded_sm, C.text_sm],
+                    option![attrs! {At::Value => ""}, "Add user..."],
+                    cache.user.values().filter(|x| !role.user_ids.contains(&x.id)).map(|x| {
+                        let mut opt = option![attrs! {At::Value => x.id}, x.username.as_str()];
+
+                        if model.selected_user.get(&id) == Some(&x.id) {
+                            opt.add_attr(At::Selected.to_string(), "selected");
+                        }
+
+                        opt
+                    }),
+                    input_ev(Ev::Change, move |x| Msg::SelectUser(id, x)),
+                ],
+                button![
+                    class![C.ml_2, C.px_2, C.py_1, C.text_blue_500, C.text_sm],
+                    font_awesome(class![C.w_3, C.h_3, C.inline, C.mr_1], "plus"),
+                    "Assign",
+                    simple_ev(Ev::Click, Msg::Assign(id))
+                ]
+            ]
+        } else {
+            empty![]
+        }
+    ]
+}
+
+fn permission_view(role: &Role, permission: Permission, can_manage: bool) -> Node<Msg> {
+    let id = role.id;
+
+    form::toggle()
+        .merge_attrs(attrs! {
+            At::Checked => role.permissions.contains(&permission).as_at_value(),
+            At::Disabled => (!can_manage).as_at_value(),
+        })
+        .with_listener(input_ev(Ev::Change, move |_| Msg::TogglePermission(id, permission)))
+}
+
+fn create_view(model: &Model) -> Node<Msg> {
+    let input_cls = class![
+        C.appearance_none,
+        C.bg_gray_200,
+        C.focus__outline_none,
+        C.focus__shadow_outline,
+        C.mr_2,
+        C.px_3,
+        C.py_2,
+        C.rounded_sm,
+        C.text_gray_800,
+    ];
+
+    form![
+        class![C.flex, C.items_center, C.my_6],
+        ev(Ev::Submit, |event| {
+            event.prevent_default();
+            Msg::Create
+        }),
+        input![
+            &input_cls,
+            attrs! {
+                At::Placeholder => "Role name",
+                At::Required => true.as_at_value(),
+                At::Value => model.name,
+            },
+            input_ev(Ev::Input, Msg::NameChanged),
+        ],
+        input![
+            &input_cls,
+            attrs! {
+                At::Placeholder => "Description",
+                At::Value => model.description,
+            },
+            input_ev(Ev::Input, Msg::DescriptionChanged),
+        ],
+        button![
+            class![
+                C.bg_blue_500,
+                C.duration_300,
+                C.flex,
+                C.hover__bg_blue_400,
+                C.items_center,
+                C.px_4,
+                C.py_2,
+                C.rounded_full,
+                C.text_white,
+                C.transition_colors,
+            ],
+            font_awesome(class![C.h_3, C.w_3, C.mr_1, C.inline], "plus"),
+            "Create Role",
+        ]
+    ]
+}
+
+pub fn view(model: &Model, cache: &ArcCache) -> Node<Msg> {
+    panel::view(
+        h3![class![C.py_4, C.font_normal, C.text_lg], "Roles"],
+        div![
+            p![
+                class![C.text_sm, C.text_gray_500],
+                "Roles grant their users permissions beyond their group. \
+                 Superusers and filesystem administrators have these permissions already."
+            ],
+            if model.can_manage { create_view(model) } else { empty![] },
+            if model.roles.is_empty() {
+                p![class![C.my_6], "No roles have been created."]
+            } else {
+                table::wrapper_view(vec![
+                    table::thead_view(
+                        vec![table::th_left(plain!["Role"])]
+                            .into_iter()
+                            .chain(
+                                Permission::ALL
+                                    .iter()
+                                    .map(|x| table::th_view(plain![permission_label(*x)])),
+                            )
+                            .chain(vec![table::th_left(plain!["Users"]), th![]])
+                            .collect::<Vec<_>>(),
+                    ),
+                    tbody![model.roles.iter().map(|x| {
+                        let id = x.id;
+                        let name = x.name.clone();
+
+                        tr![
+                            table::td_view(div![
+                                div![x.name.as_str()],
+                                div![class![C.text_sm, C.text_gray_500], x.description.as_str()]
+                            ]),
+                            Permission::ALL
+                                .iter()
+                                .map(|p| table::td_center(permission_view(x, *p, model.can_manage)))
+                                .collect::<Vec<_>>(),
+                            table::td_view(users_view(x, cache, model)),
+                            td![
+                                table::td_cls(),
+                                if model.can_manage {
+                                    button![
+                                        class![
+                                            C.bg_red_500,
+                                            C.duration_300,
+                                            C.flex,
+                                            C.hover__bg_red_400,
+                                            C.items_center,
+                                            C.px_4,
+                                            C.py_2,
+                                            C.rounded_sm,
+                                            C.text_white,
+                                            C.transition_colors,
+                                        ],
+                                        font_awesome(class![C.w_3, C.h_3, C.inline, C.mr_2], "trash"),
+                                        "Remove",
+                                        simple_ev(Ev::Click, Msg::Remove(id, name))
+                                    ]
+                                } else {
+                                    empty![]
+                                }
+                            ]
+                        ]
+                    })],
+                ])
+                .merge_attrs(class![C.my_6])
+            }
+        ],
+    )
+}
+
+pub fn init(orders: &mut impl Orders<Msg, GMsg>) {
+    orders.send_msg(Msg::FetchRoles);
+
+    let query = role::my_permissions::build();
+    let req = fetch::Request::graphql_query(&query);
+
+    orders.perform_cmd(req.fetch_json_data(Msg::MyPermissions));
+}
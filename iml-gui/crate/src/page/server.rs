@@ -3,14 +3,16 @@
 // license that can be found in the LICENSE file.
 
 use crate::{
-    components::{action_dropdown, alert_indicator, date, font_awesome, lnet_status, lock_indicator, panel, Placement},
-    extensions::MergeAttrs as _,
+    components::{
+        action_dropdown, alert_indicator, date, font_awesome, lnet_status, lock_indicator, panel, table, Placement,
+    },
+    extensions::{MergeAttrs as _, RequestExt},
     generated::css_classes::C,
     GMsg,
 };
-
+use iml_graphql_queries::{corosync, Response};
 use iml_wire_types::{
-    db::{CorosyncConfigurationRecord, LnetConfigurationRecord, PacemakerConfigurationRecord},
+    db::{CorosyncConfigurationRecord, LnetConfigurationRecord, PacemakerConfigurationRecord, UnmanagedResource},
     warp_drive::{ArcCache, Locks},
     Host, Session, ToCompositeId,
 };
@@ -23,6 +25,14 @@ pub enum Msg {
     LnetActionDropdown(action_dropdown::IdMsg),
     PacemakerActionDropdown(action_dropdown::IdMsg),
     CorosyncActionDropdown(action_dropdown::IdMsg),
+    FetchUnmanagedResources,
+    UnmanagedResourcesFetched(fetch::ResponseDataResult<Response<corosync::unmanaged_resources::Resp>>),
+}
+
+pub fn init(model: &Model, orders: &mut impl Orders<Msg, GMsg>) {
+    if model.corosync_config.is_some() {
+        orders.send_msg(Msg::FetchUnmanagedResources);
+    }
 }
 
 pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
@@ -52,6 +62,23 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
                 action_dropdown::update(msg, cache, d, &mut orders.proxy(Msg::CorosyncActionDropdown));
             }
         }
+        Msg::FetchUnmanagedResources => {
+            let query = corosync::unmanaged_resources::build(Some(model.server.id));
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::UnmanagedResourcesFetched));
+        }
+        Msg::UnmanagedResourcesFetched(x) => match x {
+            Ok(Response::Data(x)) => {
+                model.unmanaged_resources = x.data.unmanaged_resources;
+            }
+            Ok(Response::Errors(e)) => {
+                error!("An error occurred while retrieving unmanaged resources", e);
+            }
+            Err(e) => {
+                error!("An error occurred while retrieving unmanaged resources", e);
+            }
+        },
     }
 }
 
@@ -61,6 +88,7 @@ pub struct Model {
     pacemaker_config: Option<(Arc<PacemakerConfigurationRecord>, action_dropdown::Model)>,
     corosync_config: Option<(Arc<CorosyncConfigurationRecord>, action_dropdown::Model)>,
     server_dropdown: action_dropdown::Model,
+    unmanaged_resources: Vec<UnmanagedResource>,
 }
 
 impl Model {
@@ -88,6 +116,7 @@ impl Model {
                 (x, action_dropdown::Model::new(vec![id]))
             }),
             server,
+            unmanaged_resources: vec![],
         }
     }
 }
@@ -149,10 +178,57 @@ pub fn view(
             corosync_section(cache, x, corosync_dropdown, all_locks, session)
         } else {
             empty![]
+        },
+        if model.unmanaged_resources.is_empty() {
+            empty![]
+        } else {
+            unmanaged_resources_section(&model.unmanaged_resources)
         }
     ]
 }
 
+fn unmanaged_resources_section(xs: &[UnmanagedResource]) -> Node<Msg> {
+    panel::view(
+        h3![
+            class![C.py_4, C.font_normal, C.text_lg],
+            "Unmanaged Resources",
+            font_awesome(
+                class![C.w_4, C.h_4, C.inline, C.ml_2, C.text_yellow_500],
+                "exclamation-triangle"
+            )
+        ],
+        table::wrapper_view(vec![
+            table::thead_view(vec![
+                table::th_view(plain!["Name"]),
+                table::th_view(plain!["Type"]),
+                table::th_view(plain!["Role"]),
+                table::th_view(plain!["State"]),
+                table::th_view(plain!["Running On"]),
+                table::th_view(plain!["Mount Point"]),
+            ]),
+            tbody![xs.iter().map(|x| {
+                let state = if x.failed {
+                    "Failed"
+                } else if x.active {
+                    "Active"
+                } else {
+                    "Inactive"
+                };
+
+                tr![
+                    table::td_center(plain![x.name.to_string()]),
+                    table::td_center(plain![x.resource_agent.to_string()]),
+                    table::td_center(plain![x.role.to_string()]),
+                    table::td_center(plain![state]),
+                    table::td_center(plain![x.active_node_name.as_deref().unwrap_or("---").to_string()]),
+                    table::td_center(plain![x.mount_point.as_deref().unwrap_or("---").to_string()]),
+                ]
+            })],
+        ]),
+    )
+    .merge_attrs(class![C.mt_4])
+}
+
 fn pacemaker_section(
     cache: &ArcCache,
     x: &PacemakerConfigurationRecord,
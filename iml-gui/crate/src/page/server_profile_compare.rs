@@ -0,0 +1,313 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Two server profiles side by side, to help craft a custom profile from a stock one.
+
+use crate::{
+    components::{font_awesome, panel, table},
+    extensions::MergeAttrs as _,
+    generated::css_classes::C,
+    GMsg, RequestExt,
+};
+use iml_graphql_queries::{server_profile, Response};
+use iml_wire_types::graphql::{ProfileListDiff, ServerProfile, ServerProfileComparison};
+use seed::{prelude::*, *};
+
+#[derive(Default)]
+pub struct Model {
+    profiles: Vec<ServerProfile>,
+    a: String,
+    b: String,
+    comparison: Option<ServerProfileComparison>,
+    loading: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    FetchProfiles,
+    ProfilesFetched(fetch::ResponseDataResult<Response<server_profile::list::Resp>>),
+    AChanged(String),
+    BChanged(String),
+    Compare,
+    Compared(fetch::ResponseDataResult<Response<server_profile::compare::Resp>>),
+}
+
+pub fn init(orders: &mut impl Orders<Msg, GMsg>) {
+    orders.send_msg(Msg::FetchProfiles);
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::FetchProfiles => {
+            let query = server_profile::list::build();
+
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::ProfilesFetched));
+        }
+        Msg::ProfilesFetched(x) => match x {
+            Ok(Response::Data(x)) => {
+                let mut xs = x.data.server_profiles;
+
+                xs.sort_by(|a, b| natord::compare(&a.ui_name, &b.ui_name));
+
+                // Start from the default profile, as custom profiles are usually made from it
+                if model.a.is_empty() {
+                    if let Some(x) = xs.iter().find(|x| x.default) {
+                        model.a = x.name.clone();
+                    }
+                }
+
+                model.profiles = xs;
+            }
+            Ok(Response::Errors(e)) => {
+                error!("An error occurred while fetching server profiles", e);
+            }
+            Err(e) => {
+                error!("An error occurred while fetching server profiles", e);
+            }
+        },
+        Msg::AChanged(x) => {
+            model.a = x;
+        }
+        Msg::BChanged(x) => {
+            model.b = x;
+        }
+        Msg::Compare => {
+            if model.a.is_empty() || model.b.is_empty() || model.a == model.b {
+                return;
+            }
+
+            model.loading = true;
+
+            let query = server_profile::compare::build(&model.a, &model.b);
+
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Compared));
+        }
+        Msg::Compared(x) => {
+            model.loading = false;
+
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.comparison = Some(x.data.compare_profiles);
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while comparing server profiles", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while comparing server profiles", e);
+                }
+            }
+        }
+    }
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    div![
+        select_view(model),
+        match &model.comparison {
+            Some(x) => div![
+                settings_view(x),
+                list_view("Packages", "packages", x, &x.packages),
+                list_view("Repositories", "repositories", x, &x.repos),
+            ],
+            None => empty![],
+        }
+    ]
+}
+
+fn select_view(model: &Model) -> Node<Msg> {
+    if model.profiles.len() < 2 {
+        return panel::view(
+            h3![class![C.py_4, C.font_normal, C.text_lg], "Compare Server Profiles"],
+            p![
+                class![C.p_4, C.text_gray_600],
+                "At least two server profiles are needed to compare."
+            ],
+        );
+    }
+
+    let can_compare = !model.a.is_empty() && !model.b.is_empty() && model.a != model.b && !model.loading;
+
+    panel::view(
+        h3![class![C.py_4, C.font_normal, C.text_lg], "Compare Server Profiles"],
+        form![
+            class![C.grid, C.grid_cols_2, C.gap_4, C.p_4, C.items_center],
+            ev(Ev::Submit, move |event| {
+                event.prevent_default();
+                Msg::Compare
+            }),
+            label![attrs! {At::For => "profile_a"}, "Profile"],
+            profile_select("profile_a", &model.profiles, &model.a, Msg::AChanged),
+            label![attrs! {At::For => "profile_b"}, "Compare With"],
+            profile_select("profile_b", &model.profiles, &model.b, Msg::BChanged),
+            div![
+                class![C.col_span_2, C.flex, C.justify_end],
+                button![
+                    class![
+                        C.bg_blue_500,
+                        C.duration_300,
+                        C.hover__bg_blue_400,
+                        C.px_6,
+                        C.py_2,
+                        C.rounded_sm,
+                        C.text_white,
+                        C.transition_colors,
+                        C.opacity_50 => !can_compare,
+                        C.cursor_not_allowed => !can_compare,
+                    ],
+                    attrs! {
+                        At::Type => "submit",
+                        At::Disabled => (!can_compare).as_at_value(),
+                    },
+                    if model.loading { "Comparing..." } else { "Compare" },
+                ],
+            ],
+        ],
+    )
+}
+
+fn profile_select(
+    id: &str,
+    xs: &[ServerProfile],
+    selected: &str,
+    f: impl FnOnce(String) -> Msg + Clone + 'static,
+) -> Node<Msg> {
+    div![
+        class![C.inline_block, C.relative, C.bg_gray_200],
+        select![
+            id![id],
+            class![
+                C.appearance_none,
+                C.block,
+                C.leading_tight,
+                C.bg_transparent,
+                C.focus__outline_none,
+                C.px_3,
+                C.py_2,
+                C.pr_8,
+                C.rounded,
+                C.text_gray_800,
+                C.w_full,
+            ],
+            option![attrs! {At::Value => ""}, "Select a profile"],
+            xs.iter().map(|x| {
+                let mut opt = option![
+                    class![C.font_sans],
+                    attrs! {At::Value => x.name},
+                    format!("{} ({})", x.ui_name, x.name)
+                ];
+
+                if x.name == selected {
+                    opt.add_attr(At::Selected.to_string(), "selected");
+                }
+
+                opt
+            }),
+            input_ev(Ev::Change, f),
+        ],
+        div![
+            class![
+                C.pointer_events_none,
+                C.absolute,
+                C.inset_y_0,
+                C.right_0,
+                C.flex,
+                C.items_center,
+                C.px_2,
+                C.text_gray_700,
+            ],
+            font_awesome(class![C.w_4, C.h_4, C.inline, C.ml_1], "chevron-down")
+        ],
+    ]
+}
+
+fn settings_view(x: &ServerProfileComparison) -> Node<Msg> {
+    panel::view(
+        h3![class![C.py_4, C.font_normal, C.text_lg], "Settings"],
+        table::wrapper_view(vec![
+            table::thead_view(vec![
+                table::th_left(plain![""]),
+                table::th_view(plain![x.a.clone()]),
+                table::th_view(plain![x.b.clone()]),
+            ]),
+            tbody![x.settings.iter().map(|s| {
+                tr![
+                    td![table::td_cls(), class![C.font_bold], &s.name],
+                    td![
+                        table::td_cls(),
+                        class![C.text_center, C.bg_yellow_100 => s.differs],
+                        &s.a
+                    ],
+                    td![
+                        table::td_cls(),
+                        class![C.text_center, C.bg_yellow_100 => s.differs],
+                        &s.b
+                    ],
+                ]
+            })],
+        ])
+        .merge_attrs(class![C.my_6]),
+    )
+}
+
+/// Entries only one profile has are highlighted in its column, entries both have are listed in both.
+fn list_view(title: &str, what: &str, x: &ServerProfileComparison, diff: &ProfileListDiff) -> Node<Msg> {
+    if diff.both.is_empty() && diff.only_a.is_empty() && diff.only_b.is_empty() {
+        return panel::view(
+            h3![class![C.py_4, C.font_normal, C.text_lg], title],
+            p![class![C.p_4, C.text_gray_600], format!("Neither profile has {}.", what)],
+        );
+    }
+
+    let rows = diff
+        .only_a
+        .iter()
+        .map(|p| (Some(p), None))
+        .chain(diff.only_b.iter().map(|p| (None, Some(p))))
+        .chain(diff.both.iter().map(|p| (Some(p), Some(p))));
+
+    let cell = |x: Option<&String>, only: bool| {
+        td![
+            table::td_cls(),
+            class![
+                C.text_center,
+                C.bg_green_100 => only && x.is_some(),
+                C.bg_red_100 => only && x.is_none(),
+            ],
+            x.map(String::as_str).unwrap_or("---")
+        ]
+    };
+
+    panel::view(
+        h3![class![C.py_4, C.font_normal, C.text_lg], title],
+        div![
+            table::wrapper_view(vec![
+                table::thead_view(vec![
+                    table::th_view(plain![x.a.clone()]),
+                    table::th_view(plain![x.b.clone()]),
+                ]),
+                tbody![rows.map(|(a, b)| {
+                    let only = a.is_none() || b.is_none();
+
+                    tr![cell(a, only), cell(b, only)]
+                })],
+            ])
+            .merge_attrs(class![C.my_6]),
+            p![
+                class![C.px_4, C.pb_4, C.text_gray_600],
+                format!(
+                    "{} in both, {} only in {}, {} only in {}.",
+                    diff.both.len(),
+                    diff.only_a.len(),
+                    x.a,
+                    diff.only_b.len(),
+                    x.b
+                )
+            ]
+        ],
+    )
+}
@@ -3,7 +3,10 @@
 // license that can be found in the LICENSE file.
 
 use crate::{
-    components::{action_dropdown, alert_indicator, date, lnet_status, lock_indicator, paging, table, Placement},
+    components::{
+        action_dropdown, alert_indicator, date, failover_chart, lnet_ping_mesh, lnet_status, lock_indicator, paging,
+        sparkline, table, Placement,
+    },
     generated::css_classes::C,
     page::server::date_view,
     GMsg, MergeAttrs, Route,
@@ -39,6 +42,9 @@ pub struct Model {
     rows: HashMap<i32, Row>,
     pager: paging::Model,
     sort: (SortField, paging::Dir),
+    sparklines: sparkline::Model,
+    failovers: failover_chart::Model,
+    ping_mesh: lnet_ping_mesh::Model,
 }
 
 #[derive(Clone, Debug)]
@@ -53,6 +59,9 @@ pub enum Msg {
     Sort,
     SortBy(table::SortBy<SortField>),
     ActionDropdown(Box<action_dropdown::IdMsg>),
+    Sparkline(sparkline::Msg),
+    FailoverChart(failover_chart::Msg),
+    PingMesh(lnet_ping_mesh::Msg),
 }
 
 pub fn init(cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
@@ -62,6 +71,21 @@ pub fn init(cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
         cache.pacemaker_configuration.clone(),
         cache.corosync_configuration.clone(),
     ));
+
+    orders.proxy(Msg::FailoverChart).send_msg(failover_chart::Msg::Fetch);
+    orders.proxy(Msg::PingMesh).send_msg(lnet_ping_mesh::Msg::Fetch);
+}
+
+pub fn invalidate(table: &str, orders: &mut impl Orders<Msg, GMsg>) {
+    match table {
+        "ha_event" => {
+            orders.proxy(Msg::FailoverChart).send_msg(failover_chart::Msg::Fetch);
+        }
+        "lnet_ping_mesh" | "lnet_ping_result" => {
+            orders.proxy(Msg::PingMesh).send_msg(lnet_ping_mesh::Msg::Fetch);
+        }
+        _ => {}
+    }
 }
 
 pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
@@ -95,6 +119,8 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
             };
 
             model.hosts.sort_by(sort_fn);
+
+            set_sparkline_targets(cache, model, orders);
         }
         Msg::SetHosts(hosts, lnet_configs, pacemaker_configs, corosync_configs) => {
             model.hosts = hosts;
@@ -144,6 +170,8 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
         }
         Msg::Page(msg) => {
             paging::update(msg, &mut model.pager, &mut orders.proxy(Msg::Page));
+
+            set_sparkline_targets(cache, model, orders);
         }
         Msg::ActionDropdown(msg) => {
             let action_dropdown::IdMsg(id, msg) = *msg;
@@ -157,9 +185,38 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
                 );
             }
         }
+        Msg::Sparkline(msg) => {
+            sparkline::update(msg, &mut model.sparklines, &mut orders.proxy(Msg::Sparkline));
+        }
+        Msg::FailoverChart(msg) => {
+            failover_chart::update(msg, &mut model.failovers, &mut orders.proxy(Msg::FailoverChart));
+        }
+        Msg::PingMesh(msg) => {
+            lnet_ping_mesh::update(msg, &mut model.ping_mesh, &mut orders.proxy(Msg::PingMesh));
+        }
     }
 }
 
+/// Only the servers on the current page get sparklines.
+fn set_sparkline_targets(cache: &ArcCache, model: &Model, orders: &mut impl Orders<Msg, GMsg>) {
+    let uuids = model.hosts[model.pager.range()]
+        .iter()
+        .flat_map(|x| active_targets(cache, x.id))
+        .map(|x| x.to_string())
+        .collect();
+
+    orders.proxy(Msg::Sparkline).send_msg(sparkline::Msg::SetTargets(uuids));
+}
+
+/// Uuids of the targets currently mounted on `host_id`.
+fn active_targets(cache: &ArcCache, host_id: i32) -> impl Iterator<Item = &str> {
+    cache
+        .target_record
+        .values()
+        .filter(move |x| x.active_host_id == Some(host_id))
+        .map(|x| x.uuid.as_str())
+}
+
 pub fn view(
     cache: &ArcCache,
     session: Option<&Session>,
@@ -171,7 +228,12 @@ pub fn view(
         class![C.bg_white],
         div![
             class![C.flex, C.justify_between, C.px_6, C._mb_px, C.bg_gray_200],
-            h3![class![C.py_4, C.font_normal, C.text_lg], "Servers"]
+            h3![class![C.py_4, C.font_normal, C.text_lg], "Servers"],
+            a![
+                class![C.py_4, C.text_blue_500, C.hover__underline],
+                attrs! {At::Href => Route::ServerProfileCompare.to_href()},
+                "Compare Server Profiles"
+            ]
         ],
         if cache.host.is_empty() {
             p!["No hosts found"]
@@ -184,27 +246,47 @@ pub fn view(
                         table::sort_header("Profile", SortField::Profile, model.sort.0, model.sort.1)
                             .map_msg(Msg::SortBy),
                         table::th_view(Node::new_text("LNet")).merge_attrs(class![C.text_center]),
+                        table::th_view(Node::new_text("Bandwidth")).merge_attrs(class![C.text_center]),
                     ]),
                     tbody![model.hosts[model.pager.range()].iter().map(|x| {
                         match model.rows.get(&x.id) {
                             None => empty![],
                             Some(row) => tr![
-                                table::td_view(vec![
-                                    a![
-                                        class![C.text_blue_500, C.hover__underline, C.mr_2],
-                                        attrs! {
-                                            At::Href => Route::Server(x.id.into()).to_href()
-                                        },
-                                        x.label()
-                                    ],
-                                    lock_indicator::view(all_locks, x).merge_attrs(class![C.mr_2]),
-                                    alert_indicator(&cache.active_alert, &x, true, Placement::Top)
-                                ])
-                                .merge_attrs(class![C.text_center]),
-                                table::td_view(date_view(sd, &x.boot_time)).merge_attrs(class![C.text_center]),
-                                table::td_view(span![x.server_profile.ui_name]).merge_attrs(class![C.text_center]),
-                                table::td_view(div![lnet_by_server_view(x, cache, all_locks).unwrap_or_else(Vec::new)])
+                                table::labelled(
+                                    table::td_view(vec![
+                                        a![
+                                            class![C.text_blue_500, C.hover__underline, C.mr_2],
+                                            attrs! {
+                                                At::Href => Route::Server(x.id.into()).to_href()
+                                            },
+                                            x.label()
+                                        ],
+                                        lock_indicator::view(all_locks, x).merge_attrs(class![C.mr_2]),
+                                        alert_indicator(&cache.active_alert, &x, true, Placement::Top)
+                                    ])
                                     .merge_attrs(class![C.text_center]),
+                                    "Host",
+                                ),
+                                table::labelled(
+                                    table::td_view(date_view(sd, &x.boot_time)).merge_attrs(class![C.text_center]),
+                                    "Boot time",
+                                ),
+                                table::labelled(
+                                    table::td_view(span![x.server_profile.ui_name]).merge_attrs(class![C.text_center]),
+                                    "Profile",
+                                ),
+                                table::labelled(
+                                    table::td_view(div![
+                                        lnet_by_server_view(x, cache, all_locks).unwrap_or_else(Vec::new)
+                                    ])
+                                    .merge_attrs(class![C.text_center]),
+                                    "LNet",
+                                ),
+                                table::labelled(
+                                    table::td_view(sparkline::sum_view(&model.sparklines, active_targets(cache, x.id)))
+                                        .merge_attrs(class![C.text_center]),
+                                    "Bandwidth",
+                                ),
                                 td![
                                     class![C.p_3, C.text_center],
                                     action_dropdown::view(x.id, &row.dropdown, all_locks, session)
@@ -222,7 +304,10 @@ pub fn view(
                     paging::next_prev_view(&model.pager).map_msg(Msg::Page)
                 ],
             ]
-        }
+        },
+        failover_chart::view(&model.failovers).map_msg(Msg::FailoverChart),
+        lnet_ping_mesh::view(&model.ping_mesh, session, model.hosts.iter().map(|x| x.id).collect())
+            .map_msg(Msg::PingMesh),
     ]
 }
 
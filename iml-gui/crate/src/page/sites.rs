@@ -0,0 +1,294 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! The sites of the federation: this manager and its peers, together or one at a time.
+
+use crate::{
+    components::{panel, table},
+    extensions::MergeAttrs as _,
+    generated::css_classes::C,
+    page::{filesystem, filesystems::targets_view, manager_status::severity_view},
+    sleep_with_handle, GMsg, RequestExt,
+};
+use futures::channel::oneshot;
+use iml_graphql_queries::{federation, Response};
+use iml_wire_types::{
+    federation::{AlertCounts, FederatedOverview, FederatedSite, SiteOverview},
+    graphql::HealthSeverity,
+};
+use seed::{prelude::*, *};
+use std::time::Duration;
+
+/// How often the sites are polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+pub struct Model {
+    overview: Option<FederatedOverview>,
+    /// The name of the site shown on its own, all of them when `None`
+    selected: Option<String>,
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Fetch,
+    Fetched(fetch::ResponseDataResult<Response<federation::overview::Resp>>),
+    Select(Option<String>),
+    Noop,
+}
+
+pub fn init(orders: &mut impl Orders<Msg, GMsg>) {
+    orders.send_msg(Msg::Fetch);
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Fetch => {
+            model.cancel = None;
+
+            let query = federation::overview::build();
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Fetched));
+        }
+        Msg::Fetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.overview = Some(x.data.federated_overview);
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving the federated overview", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving the federated overview", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(POLL_INTERVAL, Msg::Fetch, Msg::Noop);
+            model.cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::Select(x) => {
+            model.selected = x;
+        }
+        Msg::Noop => {}
+    }
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    let x = match model.overview.as_ref() {
+        Some(x) => x,
+        None => {
+            return panel::view(
+                h3![class![C.py_4, C.font_normal, C.text_lg], "Sites"],
+                div![class![C.p_4, C.text_gray_600], "Querying sites..."],
+            )
+        }
+    };
+
+    let selected = model
+        .selected
+        .as_ref()
+        .and_then(|name| x.sites.iter().find(|x| &x.name == name));
+
+    panel::view(
+        h3![
+            class![C.py_4, C.font_normal, C.text_lg],
+            "Sites",
+            span![
+                class![C.ml_2, C.text_sm, C.text_gray_600],
+                format!("checked at {}", x.checked_at.format("%m/%d/%Y %H:%M:%S"))
+            ]
+        ],
+        div![
+            switcher_view(&x.sites, selected.map(|x| x.name.as_str())),
+            match selected {
+                Some(x) => site_view(x),
+                None => sites_view(&x.sites),
+            }
+        ],
+    )
+}
+
+fn switcher_view(sites: &[FederatedSite], selected: Option<&str>) -> Node<Msg> {
+    let button = |label: &str, active: bool, msg: Msg| {
+        button![
+            class![
+                C.px_3,
+                C.py_1,
+                C.mr_2,
+                C.mb_2,
+                C.rounded,
+                C.text_sm,
+                C.bg_blue_500 => active,
+                C.text_white => active,
+                C.bg_gray_200 => !active,
+                C.text_gray_800 => !active,
+            ],
+            simple_ev(Ev::Click, msg),
+            label
+        ]
+    };
+
+    div![
+        class![C.flex, C.flex_wrap, C.px_6, C.pt_4],
+        button("All sites", selected.is_none(), Msg::Select(None)),
+        sites.iter().map(|x| button(
+            &x.name,
+            selected == Some(x.name.as_str()),
+            Msg::Select(Some(x.name.clone()))
+        ))
+    ]
+}
+
+fn alerts_view<T>(x: &AlertCounts) -> Node<T> {
+    let counts = vec![
+        (x.critical, "critical", C.text_red_700),
+        (x.error, "error", C.text_red_500),
+        (x.warning, "warning", C.text_yellow_600),
+    ];
+
+    let xs: Vec<_> = counts
+        .into_iter()
+        .filter(|(count, _, _)| *count > 0)
+        .map(|(count, label, color)| span![class![C.mr_2, color], format!("{} {}", count, label)])
+        .collect();
+
+    if xs.is_empty() {
+        span![class![C.text_gray_600], "None"]
+    } else {
+        span![xs]
+    }
+}
+
+fn sites_view(sites: &[FederatedSite]) -> Node<Msg> {
+    table::wrapper_view(vec![
+        table::thead_view(vec![
+            table::th_left(plain!["Site"]),
+            table::th_view(plain!["Health"]),
+            table::th_view(plain!["Filesystems"]),
+            table::th_view(plain!["Active Alerts"]),
+            table::th_left(plain!["Details"]),
+        ]),
+        tbody![sites.iter().map(|x| {
+            let (health, filesystems, alerts, details) = match x.overview.as_ref() {
+                Some(o) => (
+                    severity_view(o.health.severity),
+                    plain![o.filesystems.len().to_string()],
+                    alerts_view(&o.alerts),
+                    plain![x.url.clone().unwrap_or_else(|| "This manager".to_string())],
+                ),
+                None => (
+                    severity_view(HealthSeverity::Error),
+                    plain!["---"],
+                    plain!["---"],
+                    span![class![C.text_red_500], x.error.clone().unwrap_or_default()],
+                ),
+            };
+
+            tr![
+                table::labelled(
+                    table::td_view(a![
+                        class![C.text_blue_500, C.hover__underline, C.cursor_pointer],
+                        simple_ev(Ev::Click, Msg::Select(Some(x.name.clone()))),
+                        &x.name
+                    ]),
+                    "Site"
+                ),
+                table::labelled(table::td_center(health), "Health"),
+                table::labelled(table::td_center(filesystems), "Filesystems"),
+                table::labelled(table::td_center(alerts), "Active Alerts"),
+                table::labelled(table::td_view(details), "Details"),
+            ]
+        })],
+    ])
+    .merge_attrs(class![C.my_6])
+}
+
+fn site_view(x: &FederatedSite) -> Node<Msg> {
+    match x.overview.as_ref() {
+        Some(o) => div![filesystems_view(o), health_view(o)],
+        None => div![
+            class![C.px_6, C.py_4, C.text_red_500],
+            format!(
+                "{} could not be queried: {}",
+                x.name,
+                x.error.as_deref().unwrap_or("unknown error")
+            )
+        ],
+    }
+}
+
+fn filesystems_view<T>(x: &SiteOverview) -> Node<T> {
+    if x.filesystems.is_empty() {
+        return div![class![C.px_6, C.py_4, C.text_gray_600], "No filesystems"];
+    }
+
+    table::wrapper_view(vec![
+        table::thead_view(vec![
+            table::th_left(plain!["Filesystem"]),
+            table::th_view(plain!["Targets Mounted"]),
+            table::th_view(plain!["Clients"]),
+            table::th_view(plain!["Space Used / Total"]),
+            table::th_view(plain!["Active Alerts"]),
+        ]),
+        tbody![x.filesystems.iter().map(|x| {
+            tr![
+                table::labelled(table::td_view(plain![x.name.clone()]), "Filesystem"),
+                table::labelled(table::td_center(targets_view(Some(x))), "Targets Mounted"),
+                table::labelled(
+                    table::td_center(filesystem::clients_view(x.clients.map(|x| x as u64))),
+                    "Clients"
+                ),
+                table::labelled(
+                    table::td_center(filesystem::space_used_view(
+                        x.bytes_free.map(|x| x as u64),
+                        x.bytes_total.map(|x| x as u64),
+                        x.bytes_avail.map(|x| x as u64),
+                    )),
+                    "Space Used / Total"
+                ),
+                table::labelled(table::td_center(plain![x.active_alerts.to_string()]), "Active Alerts"),
+            ]
+        })],
+    ])
+    .merge_attrs(class![C.my_6])
+}
+
+fn health_view<T>(x: &SiteOverview) -> Node<T> {
+    let h = &x.health;
+
+    let mut items: Vec<String> = vec![];
+
+    items.extend(h.degraded_filesystems.iter().map(|x| {
+        format!(
+            "Filesystem {} has unmounted targets: {}",
+            x.name,
+            x.unmounted_targets.join(", ")
+        )
+    }));
+    items.extend(h.down_hosts.iter().map(|x| format!("Server {} is down", x.fqdn)));
+
+    if h.failed_commands > 0 {
+        items.push(format!("{} commands failed in the last hour", h.failed_commands));
+    }
+
+    items.extend(h.service_issues.iter().cloned());
+
+    div![
+        class![C.px_6, C.pb_4],
+        h4![
+            class![C.font_normal, C.mb_2],
+            "Health ",
+            severity_view(h.severity),
+            span![class![C.ml_4], "Active alerts: ", alerts_view(&x.alerts)]
+        ],
+        if items.is_empty() {
+            div![class![C.text_gray_600], "Nothing needs attention"]
+        } else {
+            ul![class![C.list_disc, C.pl_6], items.iter().map(|x| li![x])]
+        }
+    ]
+}
@@ -13,19 +13,29 @@ use crate::{
     },
     GMsg, RequestExt,
 };
+use chrono::Local;
 use iml_graphql_queries::{snapshot, Response};
-use iml_wire_types::{warp_drive::ArcCache, warp_drive::ArcRecord, warp_drive::RecordId, Filesystem};
+use iml_wire_types::{
+    snapshot::SnapshotSchedule, warp_drive::ArcCache, warp_drive::ArcRecord, warp_drive::RecordId, Filesystem,
+};
 use seed::{prelude::*, *};
 use std::sync::Arc;
 
+/// How many upcoming runs are previewed.
+const PREVIEW_RUNS: i32 = 3;
+
 #[derive(Debug)]
 pub struct Model {
     submitting: bool,
     filesystems: Vec<Arc<Filesystem>>,
     fs_name: String,
     barrier: bool,
+    automount: bool,
     interval_value: String,
     interval_unit: String,
+    start_time: String,
+    time_zone: String,
+    schedule: Option<Result<SnapshotSchedule, String>>,
     pub modal: modal::Model,
 }
 
@@ -36,8 +46,12 @@ impl Default for Model {
             filesystems: vec![],
             fs_name: "".into(),
             barrier: false,
+            automount: false,
             interval_value: "".into(),
             interval_unit: "d".into(),
+            start_time: "".into(),
+            time_zone: "".into(),
+            schedule: None,
             modal: modal::Model::default(),
         }
     }
@@ -72,9 +86,14 @@ pub enum Msg {
     Close,
     SetFilesystems(Vec<Arc<Filesystem>>),
     BarrierChanged(String),
+    AutomountChanged(String),
     FsNameChanged(String),
     IntervalValueChanged(String),
     IntervalUnitChanged(String),
+    StartTimeChanged(String),
+    TimeZoneChanged(String),
+    FetchSchedule,
+    ScheduleFetched(fetch::ResponseDataResult<Response<snapshot::schedule::Resp>>),
     Submit,
     SnapshotCreateIntervalResp(fetch::ResponseDataResult<Response<snapshot::create_interval::Resp>>),
     Noop,
@@ -87,6 +106,10 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
         }
         Msg::Open => {
             model.modal.open = true;
+
+            if model.time_zone.is_empty() {
+                model.time_zone = browser_time_zone().unwrap_or_else(|| "UTC".into());
+            }
         }
         Msg::Close => {
             model.modal.open = false;
@@ -100,18 +123,79 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
         Msg::BarrierChanged(_) => {
             model.barrier = !model.barrier;
         }
+        Msg::AutomountChanged(_) => {
+            model.automount = !model.automount;
+        }
         Msg::IntervalValueChanged(x) => {
             model.interval_value = x;
+
+            orders.send_msg(Msg::FetchSchedule);
         }
         Msg::IntervalUnitChanged(x) => {
             model.interval_unit = x;
+
+            orders.send_msg(Msg::FetchSchedule);
+        }
+        Msg::StartTimeChanged(x) => {
+            model.start_time = x;
+
+            orders.send_msg(Msg::FetchSchedule);
+        }
+        Msg::TimeZoneChanged(x) => {
+            model.time_zone = x;
+
+            orders.send_msg(Msg::FetchSchedule);
+        }
+        Msg::FetchSchedule => {
+            if model.start_time.is_empty() || model.interval_value.trim().is_empty() {
+                model.schedule = None;
+
+                return;
+            }
+
+            let query = snapshot::schedule::build(
+                interval(model),
+                &model.start_time,
+                Some(model.time_zone.trim().to_string()),
+                Some(PREVIEW_RUNS),
+            );
+
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::ScheduleFetched));
+        }
+        Msg::ScheduleFetched(x) => {
+            model.schedule = match x {
+                Ok(Response::Data(x)) => Some(Ok(x.data.snapshot_schedule)),
+                Ok(Response::Errors(e)) => Some(Err(e
+                    .messages()
+                    .next()
+                    .unwrap_or("Invalid start time or time zone")
+                    .to_string())),
+                Err(e) => {
+                    error!("An error has occurred while previewing the snapshot schedule: ", e);
+
+                    None
+                }
+            };
         }
         Msg::Submit => {
             model.submitting = true;
 
-            let interval = format!("{}{}", model.interval_value.trim(), model.interval_unit);
+            let (start_time, time_zone) = if model.start_time.is_empty() {
+                (None, None)
+            } else {
+                (Some(model.start_time.clone()), Some(model.time_zone.trim().to_string()))
+            };
 
-            let query = snapshot::create_interval::build(&model.fs_name, interval, Some(model.barrier));
+            let query = snapshot::create_interval::build(
+                &model.fs_name,
+                interval(model),
+                Some(model.barrier),
+                Some(model.automount),
+                start_time,
+                time_zone,
+            );
 
             let req = fetch::Request::graphql_query(&query);
 
@@ -125,6 +209,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                 Ok(Response::Data(_)) => {
                     *model = Model {
                         fs_name: model.fs_name.to_string(),
+                        time_zone: model.time_zone.to_string(),
                         ..Model::default()
                     };
                 }
@@ -140,6 +225,38 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
     };
 }
 
+fn interval(model: &Model) -> String {
+    format!("{}{}", model.interval_value.trim(), model.interval_unit)
+}
+
+/// The IANA time zone of the browser, e.g. `Europe/Paris`.
+fn browser_time_zone() -> Option<String> {
+    let x = js_sys::Intl::DateTimeFormat::new(&js_sys::Array::new(), &js_sys::Object::new()).resolved_options();
+
+    js_sys::Reflect::get(&x, &JsValue::from_str("timeZone"))
+        .ok()?
+        .as_string()
+}
+
+/// The next runs in local time, so a start time in another time zone can be checked.
+fn schedule_view(model: &Model) -> Node<Msg> {
+    match &model.schedule {
+        Some(Ok(x)) => div![
+            class![C.col_span_2, C.text_sm, C.text_gray_600],
+            p![format!("Next snapshots, from {} {}:", model.start_time, x.time_zone)],
+            ul![x.runs.iter().map(|t| {
+                li![format!(
+                    "{} local time ({} UTC)",
+                    t.with_timezone(&Local).format("%m/%d/%Y %H:%M"),
+                    t.format("%m/%d/%Y %H:%M")
+                )]
+            })]
+        ],
+        Some(Err(e)) => p![class![C.col_span_2, C.text_sm, C.text_red_500], e],
+        None => empty![],
+    }
+}
+
 pub fn view(model: &Model) -> Node<Msg> {
     let input_cls = class![
         C.appearance_none,
@@ -265,6 +382,60 @@ pub fn view(model: &Model) -> Node<Msg> {
                                At::Checked => model.barrier.as_at_value()
                             })
                             .with_listener(input_ev(Ev::Change, Msg::BarrierChanged)),
+                        label![
+                            attrs! {At::For => "interval_automount"},
+                            "Automount",
+                            help_indicator(
+                                "Mount each snapshot as soon as it is taken, e.g. for backup software",
+                                Placement::Right
+                            )
+                        ],
+                        form::toggle()
+                            .merge_attrs(id!["interval_automount"])
+                            .merge_attrs(attrs! {
+                               At::Checked => model.automount.as_at_value()
+                            })
+                            .with_listener(input_ev(Ev::Change, Msg::AutomountChanged)),
+                        label![
+                            attrs! {At::For => "interval_start_time"},
+                            "Start Time",
+                            help_indicator(
+                                "Take the snapshots at this time of day and every interval after it. \
+                                 Snapshots are taken every interval from now when empty",
+                                Placement::Right
+                            )
+                        ],
+                        input![
+                            &input_cls,
+                            class![C.bg_gray_200, C.text_gray_800],
+                            id!["interval_start_time"],
+                            attrs! {
+                                At::Type => "time",
+                                At::Value => model.start_time,
+                            },
+                            input_ev(Ev::Change, Msg::StartTimeChanged),
+                        ],
+                        label![
+                            attrs! {At::For => "interval_time_zone"},
+                            "Time Zone",
+                            help_indicator(
+                                "The time zone of the start time, e.g. Europe/Paris. \
+                                 Daylight saving time changes don't move the start time",
+                                Placement::Right
+                            )
+                        ],
+                        input![
+                            &input_cls,
+                            class![C.bg_gray_200, C.text_gray_800],
+                            id!["interval_time_zone"],
+                            attrs! {
+                                At::Type => "text",
+                                At::Value => model.time_zone,
+                                At::Disabled => model.start_time.is_empty().as_at_value(),
+                            },
+                            input_ev(Ev::Change, Msg::TimeZoneChanged),
+                        ],
+                        schedule_view(model),
                     ],
                     modal::footer_view(vec![
                         button![
@@ -18,7 +18,7 @@ use iml_wire_types::{
     snapshot::ReserveUnit, warp_drive::ArcCache, warp_drive::ArcRecord, warp_drive::RecordId, Filesystem,
 };
 use seed::{prelude::*, *};
-use std::{str::FromStr, sync::Arc};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 #[derive(Debug)]
 pub struct Model {
@@ -28,6 +28,10 @@ pub struct Model {
     reserve_value: u32,
     reserve_unit: ReserveUnit,
     keep_num: Option<u32>,
+    mdt_reserve_percent: Option<u32>,
+    change_percent: Option<u32>,
+    /// Current policy versions, keyed by filesystem name
+    versions: HashMap<String, i32>,
     pub modal: modal::Model,
 }
 
@@ -40,6 +44,9 @@ impl Default for Model {
             reserve_value: 0,
             reserve_unit: ReserveUnit::Percent,
             keep_num: None,
+            mdt_reserve_percent: None,
+            change_percent: None,
+            versions: HashMap::new(),
             modal: modal::Model::default(),
         }
     }
@@ -48,9 +55,11 @@ impl Default for Model {
 impl RecordChange<Msg> for Model {
     fn update_record(&mut self, _: ArcRecord, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
         orders.send_msg(Msg::SetFilesystems(cache.filesystem.values().cloned().collect()));
+        orders.send_msg(Msg::SetVersions(get_versions(cache)));
     }
     fn remove_record(&mut self, _: RecordId, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
         orders.send_msg(Msg::SetFilesystems(cache.filesystem.values().cloned().collect()));
+        orders.send_msg(Msg::SetVersions(get_versions(cache)));
 
         let present = cache.filesystem.values().any(|x| x.name == self.fs_name);
 
@@ -61,6 +70,7 @@ impl RecordChange<Msg> for Model {
     }
     fn set_records(&mut self, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
         orders.send_msg(Msg::SetFilesystems(cache.filesystem.values().cloned().collect()));
+        orders.send_msg(Msg::SetVersions(get_versions(cache)));
 
         let x = get_fs_names(cache).into_iter().next().unwrap_or_default();
         orders.send_msg(Msg::FsNameChanged(x));
@@ -73,7 +83,10 @@ pub enum Msg {
     Open,
     Close,
     SetFilesystems(Vec<Arc<Filesystem>>),
+    SetVersions(HashMap<String, i32>),
     KeepNumChanged(String),
+    MdtReservePercentChanged(String),
+    ChangePercentChanged(String),
     FsNameChanged(String),
     ReserveValueChanged(String),
     ReserveUnitChanged(String),
@@ -96,12 +109,21 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
         Msg::SetFilesystems(x) => {
             model.filesystems = x;
         }
+        Msg::SetVersions(x) => {
+            model.versions = x;
+        }
         Msg::FsNameChanged(x) => {
             model.fs_name = x;
         }
         Msg::KeepNumChanged(x) => {
             model.keep_num = x.parse().ok();
         }
+        Msg::MdtReservePercentChanged(x) => {
+            model.mdt_reserve_percent = x.parse().ok();
+        }
+        Msg::ChangePercentChanged(x) => {
+            model.change_percent = x.parse().ok();
+        }
         Msg::ReserveValueChanged(x) => {
             model.reserve_value = x.parse().unwrap();
         }
@@ -116,6 +138,9 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                 model.reserve_value,
                 model.reserve_unit,
                 model.keep_num,
+                model.versions.get(&model.fs_name).copied(),
+                model.mdt_reserve_percent,
+                model.change_percent,
             );
 
             let req = fetch::Request::graphql_query(&query);
@@ -130,6 +155,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
                 Ok(Response::Data(_)) => {
                     *model = Model {
                         fs_name: model.fs_name.to_string(),
+                        versions: model.versions.clone(),
                         ..Model::default()
                     };
                 }
@@ -145,6 +171,14 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
     };
 }
 
+fn get_versions(cache: &ArcCache) -> HashMap<String, i32> {
+    cache
+        .snapshot_retention
+        .values()
+        .map(|x| (x.filesystem_name.to_string(), x.version))
+        .collect()
+}
+
 pub fn view(model: &Model) -> Node<Msg> {
     let input_cls = class![
         C.appearance_none,
@@ -279,6 +313,48 @@ pub fn view(model: &Model) -> Node<Msg> {
                             },
                             input_ev(Ev::Change, Msg::KeepNumChanged),
                         ],
+                        label![
+                            attrs! {At::For => "mdt_reserve_percent"},
+                            "MDT Reserve (%)",
+                            help_indicator(
+                                "Also delete the oldest snapshot while the MDTs have less than this percent of space free.",
+                                Placement::Right
+                            )
+                        ],
+                        input![
+                            &input_cls,
+                            class![C.bg_gray_200, C.text_gray_800, C.rounded_r_none],
+                            id!["mdt_reserve_percent"],
+                            attrs! {
+                                At::Type => "number",
+                                At::Min => "1",
+                                At::Max => "99",
+                                At::Placeholder => "Optional",
+                                At::Required => false.as_at_value(),
+                            },
+                            input_ev(Ev::Change, Msg::MdtReservePercentChanged),
+                        ],
+                        label![
+                            attrs! {At::For => "change_percent"},
+                            "Change Threshold (%)",
+                            help_indicator(
+                                "Take a snapshot once used space changed by this percent of the filesystem size.",
+                                Placement::Right
+                            )
+                        ],
+                        input![
+                            &input_cls,
+                            class![C.bg_gray_200, C.text_gray_800, C.rounded_r_none],
+                            id!["change_percent"],
+                            attrs! {
+                                At::Type => "number",
+                                At::Min => "1",
+                                At::Max => "100",
+                                At::Placeholder => "Optional",
+                                At::Required => false.as_at_value(),
+                            },
+                            input_ev(Ev::Change, Msg::ChangePercentChanged),
+                        ],
                     ],
                     modal::footer_view(vec![
                         button![
@@ -0,0 +1,157 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! The snapshots destroyed in the last 90 days, folded away until opened.
+
+use super::*;
+use crate::{components::command_modal, extensions::RequestExt};
+use iml_wire_types::snapshot::SnapshotHistoryEntry;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Toggle,
+    Fetch,
+    Fetched(
+        String,
+        Box<fetch::ResponseDataResult<Response<snapshot::history::Resp>>>,
+    ),
+    OpenCommandModal(i32),
+}
+
+#[derive(Default, Debug)]
+pub struct Model {
+    pub fs_name: Option<String>,
+    fs_names: Vec<String>,
+    open: bool,
+    rows: BTreeMap<String, Vec<SnapshotHistoryEntry>>,
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::Toggle => {
+            model.open = !model.open;
+
+            if model.open {
+                orders.send_msg(Msg::Fetch);
+            }
+        }
+        Msg::Fetch => {
+            for fs_name in &model.fs_names {
+                let query = snapshot::history::build(fs_name, None, None);
+
+                let req = fetch::Request::graphql_query(&query);
+
+                let fs_name = fs_name.clone();
+
+                orders.perform_cmd(req.fetch_json_data(move |x| Msg::Fetched(fs_name, Box::new(x))));
+            }
+        }
+        Msg::Fetched(fs_name, x) => match *x {
+            Ok(Response::Data(x)) => {
+                model.rows.insert(fs_name, x.data.snapshot_history);
+            }
+            Ok(Response::Errors(e)) => {
+                error!("An error occurred while fetching destroyed snapshots", e);
+            }
+            Err(e) => {
+                error!("An error occurred while fetching destroyed snapshots", e);
+            }
+        },
+        Msg::OpenCommandModal(id) => {
+            orders.send_g_msg(GMsg::OpenCommandModal(command_modal::Input::Ids(vec![id])));
+        }
+    }
+}
+
+impl RecordChange<Msg> for Model {
+    fn update_record(&mut self, _: ArcRecord, _: &ArcCache, _: &mut impl Orders<Msg, GMsg>) {}
+    fn remove_record(&mut self, id: RecordId, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
+        self.set_records(cache, orders);
+
+        // A snapshot gone from the cache is in the history now
+        if let RecordId::Snapshot(_) = id {
+            if self.open {
+                orders.send_msg(Msg::Fetch);
+            }
+        }
+    }
+    fn set_records(&mut self, cache: &ArcCache, _: &mut impl Orders<Msg, GMsg>) {
+        self.fs_names = get_fs_names(cache)
+            .into_iter()
+            .filter(|x| fs_context::in_context(self.fs_name.as_deref(), x))
+            .collect();
+
+        let fs_names = &self.fs_names;
+
+        self.rows.retain(|k, _| fs_names.contains(k));
+    }
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    let mut rows: Vec<_> = model.rows.values().flatten().collect();
+
+    rows.sort_by(|a, b| b.destroyed_at.cmp(&a.destroyed_at));
+
+    panel::view(
+        h3![
+            class![C.py_4, C.font_normal, C.text_lg, C.cursor_pointer, C.select_none],
+            simple_ev(Ev::Click, Msg::Toggle),
+            "Destroyed Snapshots",
+            font_awesome(
+                class![C.w_4, C.h_4, C.inline, C.ml_2, C.text_blue_500],
+                if model.open { "chevron-up" } else { "chevron-down" }
+            ),
+        ],
+        if !model.open {
+            empty![]
+        } else if rows.is_empty() {
+            p![
+                class![C.p_4, C.text_gray_600],
+                "No snapshots were destroyed in the last 90 days."
+            ]
+        } else {
+            table::wrapper_view(vec![
+                table::thead_view(vec![
+                    table::th_view(plain!["Name"]),
+                    table::th_view(plain!["FS Name"]),
+                    table::th_view(plain!["Creation Time"]),
+                    table::th_view(plain!["Origin"]),
+                    table::th_view(plain!["Destroyed"]),
+                    table::th_view(plain!["Destroyed By"]),
+                    table::th_view(plain!["Comment"]),
+                ]),
+                tbody![rows.into_iter().map(|x| {
+                    tr![
+                        table::td_center(plain![x.snapshot_name.clone()]),
+                        table::td_center(plain![x.filesystem_name.clone()]),
+                        table::td_center(plain![x.create_time.format("%m/%d/%Y %H:%M:%S").to_string()]),
+                        table::td_center(plain![if x.taken_by_policy { "Policy" } else { "Manual" }]),
+                        table::td_center(plain![x.destroyed_at.format("%m/%d/%Y %H:%M:%S").to_string()]),
+                        table::td_center(destroyed_by_view(x)),
+                        table::td_center(plain![x.comment.as_deref().unwrap_or("---")]),
+                    ]
+                })],
+            ])
+            .merge_attrs(class![C.my_6])
+        },
+    )
+}
+
+fn destroyed_by_view(x: &SnapshotHistoryEntry) -> Node<Msg> {
+    let who = match (&x.destroyed_by, &x.reason) {
+        (_, Some(reason)) => format!("Retention policy ({})", reason),
+        (Some(user), None) => user.to_string(),
+        (None, None) => "Outside the manager".to_string(),
+    };
+
+    match x.command_id {
+        Some(id) => a![
+            class![C.text_blue_500, C.hover__underline, C.cursor_pointer],
+            simple_ev(Ev::Click, Msg::OpenCommandModal(id)),
+            who
+        ],
+        None => plain![who],
+    }
+}
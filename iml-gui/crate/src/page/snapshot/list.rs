@@ -3,6 +3,38 @@
 // license that can be found in the LICENSE file.
 
 use super::*;
+use crate::components::table_columns;
+
+const COLUMNS: &[table_columns::Column] = &[
+    table_columns::Column {
+        id: "name",
+        label: "Name",
+    },
+    table_columns::Column {
+        id: "fs_name",
+        label: "FS Name",
+    },
+    table_columns::Column {
+        id: "creation_time",
+        label: "Creation Time",
+    },
+    table_columns::Column {
+        id: "comment",
+        label: "Comment",
+    },
+    table_columns::Column {
+        id: "state",
+        label: "State",
+    },
+    table_columns::Column {
+        id: "origin",
+        label: "Origin",
+    },
+    table_columns::Column {
+        id: "compare",
+        label: "Compare",
+    },
+];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortField {
@@ -16,30 +48,44 @@ impl Default for SortField {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct Model {
     pager: paging::Model,
     rows: Vec<Arc<SnapshotRecord>>,
+    pub fs_name: Option<String>,
     sort: (SortField, paging::Dir),
+    columns: table_columns::Model,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self {
+            pager: paging::Model::default(),
+            rows: vec![],
+            fs_name: None,
+            sort: (SortField::default(), paging::Dir::default()),
+            columns: table_columns::Model::new("snapshots", COLUMNS),
+        }
+    }
 }
 
 impl RecordChange<Msg> for Model {
     fn update_record(&mut self, _: ArcRecord, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
-        self.rows = cache.snapshot.values().cloned().collect();
+        self.rows = in_context(cache.snapshot.values(), &self.fs_name, |x| &x.filesystem_name);
 
         orders.proxy(Msg::Page).send_msg(paging::Msg::SetTotal(self.rows.len()));
 
         orders.send_msg(Msg::Sort);
     }
     fn remove_record(&mut self, _: RecordId, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
-        self.rows = cache.snapshot.values().cloned().collect();
+        self.rows = in_context(cache.snapshot.values(), &self.fs_name, |x| &x.filesystem_name);
 
         orders.proxy(Msg::Page).send_msg(paging::Msg::SetTotal(self.rows.len()));
 
         orders.send_msg(Msg::Sort);
     }
     fn set_records(&mut self, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
-        self.rows = cache.snapshot.values().cloned().collect();
+        self.rows = in_context(cache.snapshot.values(), &self.fs_name, |x| &x.filesystem_name);
 
         orders.proxy(Msg::Page).send_msg(paging::Msg::SetTotal(self.rows.len()));
 
@@ -52,6 +98,11 @@ pub enum Msg {
     Page(paging::Msg),
     Sort,
     SortBy(table::SortBy<SortField>),
+    Columns(table_columns::Msg),
+}
+
+pub fn init(model: &Model, orders: &mut impl Orders<Msg, GMsg>) {
+    table_columns::init(&model.columns, &mut orders.proxy(Msg::Columns));
 }
 
 pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
@@ -70,6 +121,9 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
         Msg::Page(msg) => {
             paging::update(msg, &mut model.pager, &mut orders.proxy(Msg::Page));
         }
+        Msg::Columns(msg) => {
+            table_columns::update(msg, &mut model.columns, &mut orders.proxy(Msg::Columns));
+        }
         Msg::Sort => {
             let sort_fn = match model.sort {
                 (SortField::Name, paging::Dir::Asc) => Box::new(|a: &Arc<SnapshotRecord>, b: &Arc<SnapshotRecord>| {
@@ -102,43 +156,26 @@ pub fn view(model: &Model, cache: &ArcCache) -> Node<Msg> {
     }
 
     panel::view(
-        h3![class![C.py_4, C.font_normal, C.text_lg], "Snapshots"],
+        div![
+            class![C.flex, C.justify_between, C.items_center],
+            h3![class![C.py_4, C.font_normal, C.text_lg], "Snapshots"],
+            table_columns::view(&model.columns).map_msg(Msg::Columns),
+        ],
         div![
             table::wrapper_view(vec![
-                table::thead_view(vec![
-                    table::sort_header("Name", SortField::Name, model.sort.0, model.sort.1).map_msg(Msg::SortBy),
-                    table::th_view(plain!["FS Name"]),
-                    table::sort_header("Creation Time", SortField::CreationTime, model.sort.0, model.sort.1)
-                        .map_msg(Msg::SortBy),
-                    table::th_view(plain!["Comment"]),
-                    table::th_view(plain!["State"]),
-                ]),
+                table::thead_view(
+                    model
+                        .columns
+                        .visible()
+                        .map(|c| header_view(c, model))
+                        .collect::<Vec<_>>()
+                ),
                 tbody![model.rows[model.pager.range()].iter().map(|x| {
-                    tr![
-                        td![table::td_cls(), class![C.text_center], &x.snapshot_name],
-                        td![
-                            table::td_cls(),
-                            class![C.text_center],
-                            match get_fs_by_name(cache, &x.filesystem_name) {
-                                Some(x) => {
-                                    div![resource_links::fs_link(&x)]
-                                }
-                                None => {
-                                    plain![x.filesystem_name.to_string()]
-                                }
-                            }
-                        ],
-                        table::td_center(plain![x.create_time.format("%m/%d/%Y %H:%M:%S").to_string()]),
-                        td![
-                            table::td_cls(),
-                            class![C.text_center],
-                            x.comment.as_deref().unwrap_or("---")
-                        ],
-                        table::td_center(plain![match &x.mounted {
-                            true => "mounted",
-                            false => "unmounted",
-                        }]),
-                    ]
+                    tr![model
+                        .columns
+                        .visible()
+                        .map(|c| cell_view(c, x, cache))
+                        .collect::<Vec<_>>()]
                 })]
             ])
             .merge_attrs(class![C.my_6]),
@@ -151,3 +188,80 @@ pub fn view(model: &Model, cache: &ArcCache) -> Node<Msg> {
         ],
     )
 }
+
+fn header_view(column: &str, model: &Model) -> Node<Msg> {
+    match column {
+        "name" => table::sort_header("Name", SortField::Name, model.sort.0, model.sort.1).map_msg(Msg::SortBy),
+        "creation_time" => table::sort_header("Creation Time", SortField::CreationTime, model.sort.0, model.sort.1)
+            .map_msg(Msg::SortBy),
+        "fs_name" => table::th_view(plain!["FS Name"]),
+        "comment" => table::th_view(plain!["Comment"]),
+        "state" => table::th_view(plain!["State"]),
+        "origin" => table::th_view(plain!["Origin"]),
+        _ => table::th_view(plain![""]),
+    }
+}
+
+fn cell_view(column: &str, x: &SnapshotRecord, cache: &ArcCache) -> Node<Msg> {
+    match column {
+        "name" => td![
+            table::td_cls(),
+            class![C.text_center],
+            &x.snapshot_name,
+            if x.held { held_indicator() } else { empty![] }
+        ],
+        "fs_name" => td![
+            table::td_cls(),
+            class![C.text_center],
+            match get_fs_by_name(cache, &x.filesystem_name) {
+                Some(x) => {
+                    div![resource_links::fs_link(&x)]
+                }
+                None => {
+                    plain![x.filesystem_name.to_string()]
+                }
+            }
+        ],
+        "creation_time" => table::td_center(plain![x.create_time.format("%m/%d/%Y %H:%M:%S").to_string()]),
+        "comment" => td![
+            table::td_cls(),
+            class![C.text_center],
+            x.comment.as_deref().unwrap_or("---")
+        ],
+        "state" => table::td_center(plain![match &x.mounted {
+            true => "mounted",
+            false => "unmounted",
+        }]),
+        "origin" => table::td_center(plain![match x.policy_run_id {
+            Some(id) => format!("Automatic (run {})", id),
+            None => "Manual".to_string(),
+        }]),
+        "compare" => td![
+            table::td_cls(),
+            class![C.text_center],
+            match get_fs_by_name(cache, &x.filesystem_name) {
+                Some(fs) => a![
+                    class![C.text_blue_500, C.hover__underline],
+                    attrs! {At::Href => Route::SnapshotCompare(RouteId::from(fs.id)).to_href()},
+                    "Compare"
+                ],
+                None => empty![],
+            }
+        ],
+        _ => empty![],
+    }
+}
+
+fn held_indicator<T>() -> Node<T> {
+    span![
+        attrs::container(),
+        class![C.inline_block, C.h_3, C.w_3, C.ml_2, C.cursor_pointer],
+        font_awesome(class![C.text_gray_600], "lock")
+            .with_style(St::Height, "inherit")
+            .with_style(St::Width, "inherit"),
+        tooltip::view(
+            "Held. The retention policy keeps this snapshot, and it can only be destroyed by force",
+            Placement::Top
+        )
+    ]
+}
@@ -4,6 +4,7 @@
 
 use super::*;
 use crate::{extensions::RequestExt, font_awesome};
+use chrono::Local;
 use chrono_humanize::{Accuracy, HumanTime, Tense};
 use iml_wire_types::snapshot::SnapshotInterval;
 use std::time::Duration;
@@ -33,6 +34,7 @@ pub enum Msg {
 pub struct Model {
     pager: paging::Model,
     rows: Vec<Arc<SnapshotInterval>>,
+    pub fs_name: Option<String>,
     sort: (SortField, paging::Dir),
     take: take::Model,
 }
@@ -102,21 +104,21 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
 
 impl RecordChange<Msg> for Model {
     fn update_record(&mut self, _: ArcRecord, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
-        self.rows = cache.snapshot_interval.values().cloned().collect();
+        self.rows = in_context(cache.snapshot_interval.values(), &self.fs_name, |x| &x.filesystem_name);
 
         orders.proxy(Msg::Page).send_msg(paging::Msg::SetTotal(self.rows.len()));
 
         orders.send_msg(Msg::Sort);
     }
     fn remove_record(&mut self, _: RecordId, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
-        self.rows = cache.snapshot_interval.values().cloned().collect();
+        self.rows = in_context(cache.snapshot_interval.values(), &self.fs_name, |x| &x.filesystem_name);
 
         orders.proxy(Msg::Page).send_msg(paging::Msg::SetTotal(self.rows.len()));
 
         orders.send_msg(Msg::Sort);
     }
     fn set_records(&mut self, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
-        self.rows = cache.snapshot_interval.values().cloned().collect();
+        self.rows = in_context(cache.snapshot_interval.values(), &self.fs_name, |x| &x.filesystem_name);
 
         orders.proxy(Msg::Page).send_msg(paging::Msg::SetTotal(self.rows.len()));
 
@@ -135,7 +137,10 @@ pub fn view(model: &Model, cache: &ArcCache, session: Option<&Session>) -> Node<
                     table::sort_header("Interval", SortField::Interval, model.sort.0, model.sort.1)
                         .map_msg(Msg::SortBy),
                     table::th_view(plain!["Use Barrier"]),
+                    table::th_view(plain!["Automount"]),
+                    table::th_view(plain!["Start Time"]),
                     table::th_view(plain!["Last Run"]),
+                    table::th_view(plain!["Next Run"]),
                     restrict::view(session, GroupType::FilesystemAdministrators, th![]),
                 ]),
                 tbody![model.rows[model.pager.range()].iter().map(|x| {
@@ -161,10 +166,16 @@ pub fn view(model: &Model, cache: &ArcCache, session: Option<&Session>) -> Node<
                                 "no"
                             }
                         }]),
+                        table::td_center(plain![if x.automount { "yes" } else { "no" }]),
+                        table::td_center(start_time_view(x)),
                         table::td_center(plain![x
                             .last_run
                             .map(|x| x.format("%m/%d/%Y %H:%M:%S").to_string())
                             .unwrap_or_else(|| "---".to_string())]),
+                        table::td_center(plain![x
+                            .next_run
+                            .map(|x| x.with_timezone(&Local).format("%m/%d/%Y %H:%M").to_string())
+                            .unwrap_or_else(|| "---".to_string())]),
                         td![
                             class![C.flex, C.justify_center, C.p_4, C.px_3],
                             restrict::view(
@@ -203,6 +214,26 @@ pub fn view(model: &Model, cache: &ArcCache, session: Option<&Session>) -> Node<
     )
 }
 
+/// The start time in the time zone of the interval, along with its local time equivalent.
+fn start_time_view(x: &SnapshotInterval) -> Node<Msg> {
+    let start_time = match &x.start_time {
+        Some(t) => t,
+        None => return plain!["---"],
+    };
+
+    let local = x
+        .next_run
+        .map(|t| format!("{} local time", t.with_timezone(&Local).format("%H:%M")));
+
+    div![
+        div![format!("{} {}", start_time, x.time_zone)],
+        match local {
+            Some(local) => div![class![C.text_sm, C.text_gray_600], local],
+            None => empty![],
+        }
+    ]
+}
+
 fn display_interval(x: Duration) -> String {
     chrono::Duration::from_std(x)
         .map(HumanTime::from)
@@ -17,6 +17,7 @@ pub enum Msg {
 pub struct Model {
     pager: paging::Model,
     rows: Vec<Arc<SnapshotRetention>>,
+    pub fs_name: Option<String>,
     take: take::Model,
 }
 
@@ -48,17 +49,17 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
 
 impl RecordChange<Msg> for Model {
     fn update_record(&mut self, _: ArcRecord, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
-        self.rows = cache.snapshot_retention.values().cloned().collect();
+        self.rows = in_context(cache.snapshot_retention.values(), &self.fs_name, |x| &x.filesystem_name);
 
         orders.proxy(Msg::Page).send_msg(paging::Msg::SetTotal(self.rows.len()));
     }
     fn remove_record(&mut self, _: RecordId, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
-        self.rows = cache.snapshot_retention.values().cloned().collect();
+        self.rows = in_context(cache.snapshot_retention.values(), &self.fs_name, |x| &x.filesystem_name);
 
         orders.proxy(Msg::Page).send_msg(paging::Msg::SetTotal(self.rows.len()));
     }
     fn set_records(&mut self, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
-        self.rows = cache.snapshot_retention.values().cloned().collect();
+        self.rows = in_context(cache.snapshot_retention.values(), &self.fs_name, |x| &x.filesystem_name);
 
         orders.proxy(Msg::Page).send_msg(paging::Msg::SetTotal(self.rows.len()));
     }
@@ -99,7 +100,14 @@ pub fn view(model: &Model, cache: &ArcCache, session: Option<&Session>) -> Node<
                                 ReserveUnit::Tebibytes => "TiB",
                             }
                         )]),
-                        table::td_center(plain![x.keep_num.to_string()]),
+                        table::td_center(nodes![
+                            plain![x.keep_num.to_string()],
+                            if x.crowded_by_manual {
+                                crowded_indicator()
+                            } else {
+                                empty![]
+                            }
+                        ]),
                         table::td_center(plain![x
                             .last_run
                             .map(|x| x.format("%m/%d/%Y %H:%M:%S").to_string())
@@ -141,3 +149,17 @@ pub fn view(model: &Model, cache: &ArcCache, session: Option<&Session>) -> Node<
         ],
     )
 }
+
+fn crowded_indicator<T>() -> Node<T> {
+    span![
+        attrs::container(),
+        class![C.inline_block, C.h_3, C.w_3, C.ml_2, C.cursor_pointer],
+        font_awesome(class![C.text_yellow_500], "exclamation-triangle")
+            .with_style(St::Height, "inherit")
+            .with_style(St::Width, "inherit"),
+        tooltip::view(
+            "Manual snapshots take up much of this policy, so snapshots taken by intervals are deleted sooner",
+            Placement::Left
+        )
+    ]
+}
@@ -4,6 +4,7 @@
 
 mod add_interval;
 mod create_retention;
+mod history;
 mod list;
 mod list_interval;
 mod list_retention;
@@ -11,12 +12,13 @@ mod take;
 
 use crate::{
     components::{
-        attrs, font_awesome, font_awesome_outline, form, paging, panel, resource_links, restrict, table, tooltip,
-        Placement,
+        attrs, font_awesome, font_awesome_outline, form, fs_context, paging, panel, resource_links, restrict, table,
+        tooltip, Placement,
     },
     extensions::{MergeAttrs as _, NodeExt as _},
     generated::css_classes::C,
     page::RecordChange,
+    route::{Route, RouteId},
     GMsg,
 };
 use iml_graphql_queries::{snapshot, Response};
@@ -36,6 +38,7 @@ pub struct Model {
     list: list::Model,
     add_interval: add_interval::Model,
     create_retention: create_retention::Model,
+    history: history::Model,
 }
 
 impl RecordChange<Msg> for Model {
@@ -55,6 +58,9 @@ impl RecordChange<Msg> for Model {
         self.create_retention
             .update_record(record.clone(), cache, &mut orders.proxy(Msg::CreatRetention));
 
+        self.history
+            .update_record(record.clone(), cache, &mut orders.proxy(Msg::History));
+
         self.take.update_record(record, cache, &mut orders.proxy(Msg::Take));
     }
     fn remove_record(&mut self, record: RecordId, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
@@ -72,6 +78,9 @@ impl RecordChange<Msg> for Model {
         self.create_retention
             .remove_record(record, cache, &mut orders.proxy(Msg::CreatRetention));
 
+        self.history
+            .remove_record(record, cache, &mut orders.proxy(Msg::History));
+
         self.take.remove_record(record, cache, &mut orders.proxy(Msg::Take));
     }
     fn set_records(&mut self, cache: &ArcCache, orders: &mut impl Orders<Msg, GMsg>) {
@@ -89,6 +98,8 @@ impl RecordChange<Msg> for Model {
         self.create_retention
             .set_records(cache, &mut orders.proxy(Msg::CreatRetention));
 
+        self.history.set_records(cache, &mut orders.proxy(Msg::History));
+
         self.take.set_records(cache, &mut orders.proxy(Msg::Take));
     }
 }
@@ -101,6 +112,7 @@ pub enum Msg {
     List(list::Msg),
     AddInterval(add_interval::Msg),
     CreatRetention(create_retention::Msg),
+    History(history::Msg),
 }
 
 pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
@@ -123,6 +135,38 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
         Msg::CreatRetention(msg) => {
             create_retention::update(msg, &mut model.create_retention, &mut orders.proxy(Msg::CreatRetention));
         }
+        Msg::History(msg) => {
+            history::update(msg, &mut model.history, &mut orders.proxy(Msg::History));
+        }
+    }
+}
+
+/// Preselects `fs_name` in the take snapshot form, and scrolls to and focuses the form.
+pub fn take_snapshot(fs_name: String) -> Msg {
+    Msg::Take(take::Msg::Open(fs_name))
+}
+
+/// Only lists the snapshots, rules and retention policies of the filesystem context,
+/// and takes snapshots of it by default.
+pub fn set_fs_context(fs_name: Option<&str>, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    let fs_name = fs_name.map(String::from);
+
+    model.list.fs_name = fs_name.clone();
+    model.list_interval.fs_name = fs_name.clone();
+    model.list_retention.fs_name = fs_name.clone();
+    model.history.fs_name = fs_name.clone();
+
+    model.list.set_records(cache, &mut orders.proxy(Msg::List));
+    model
+        .list_interval
+        .set_records(cache, &mut orders.proxy(Msg::ListInterval));
+    model
+        .list_retention
+        .set_records(cache, &mut orders.proxy(Msg::ListRetention));
+    model.history.set_records(cache, &mut orders.proxy(Msg::History));
+
+    if let Some(x) = fs_name {
+        orders.send_msg(Msg::Take(take::Msg::FsNameChanged(x)));
     }
 }
 
@@ -130,6 +174,8 @@ pub fn init(cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, G
     model.set_records(cache, orders);
 
     take::init(cache, &mut model.take);
+
+    list::init(&model.list, &mut orders.proxy(Msg::List));
 }
 
 pub fn view(model: &Model, cache: &ArcCache, session: Option<&Session>) -> impl View<Msg> {
@@ -162,7 +208,10 @@ pub fn view(model: &Model, cache: &ArcCache, session: Option<&Session>) -> impl
         },
         create_retention_btn(session),
         create_retention::view(&model.create_retention).map_msg(Msg::CreatRetention),
-        list::view(&model.list, cache).map_msg(Msg::List)
+        list::view(&model.list, cache).map_msg(Msg::List),
+        history::view(&model.history)
+            .map_msg(Msg::History)
+            .merge_attrs(class![C.my_6]),
     ]
 }
 
@@ -231,6 +280,17 @@ fn help_indicator<T>(msg: &str, placement: Placement) -> Node<T> {
     ]
 }
 
+/// The records of `xs` in the filesystem context `fs_name`.
+fn in_context<'a, T: 'a>(
+    xs: impl Iterator<Item = &'a Arc<T>>,
+    fs_name: &Option<String>,
+    get_fs_name: impl Fn(&T) -> &String,
+) -> Vec<Arc<T>> {
+    xs.filter(|x| fs_context::in_context(fs_name.as_deref(), get_fs_name(x)))
+        .cloned()
+        .collect()
+}
+
 fn get_fs_names(cache: &ArcCache) -> Vec<String> {
     cache.filesystem.values().map(|x| x.name.to_string()).collect()
 }
@@ -4,6 +4,8 @@
 
 use super::*;
 use crate::{components::command_modal, components::font_awesome, extensions::RequestExt};
+use iml_wire_types::snapshot::SnapshotPreflight;
+use wasm_bindgen::JsCast;
 
 #[derive(Clone, Debug)]
 pub enum Msg {
@@ -13,6 +15,9 @@ pub enum Msg {
     SetFilesystems(Vec<Arc<Filesystem>>),
     FsNameChanged(String),
     BarrierChanged(String),
+    Open(String),
+    Focus,
+    PreflightResp(Box<fetch::ResponseDataResult<Response<snapshot::preflight::Resp>>>),
     SnapshotCreateResp(fetch::ResponseDataResult<Response<snapshot::create::Resp>>),
 }
 
@@ -24,6 +29,7 @@ pub struct Model {
     name: String,
     comment: Option<String>,
     submitting: bool,
+    preflight: Option<SnapshotPreflight>,
 }
 
 impl RecordChange<Msg> for Model {
@@ -52,14 +58,46 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
     match msg {
         Msg::Submit => {
             model.submitting = true;
+            model.preflight = None;
 
-            let query =
-                snapshot::create::build(&model.fs_name, &model.name, model.comment.as_ref(), Some(model.barrier));
+            let query = snapshot::preflight::build(&model.fs_name, &model.name, Some(model.barrier));
 
             let req = fetch::Request::graphql_query(&query);
 
-            orders.perform_cmd(req.fetch_json_data(|x| Msg::SnapshotCreateResp(x)));
+            orders.perform_cmd(req.fetch_json_data(|x| Msg::PreflightResp(Box::new(x))));
         }
+        Msg::PreflightResp(x) => match *x {
+            Ok(Response::Data(x)) => {
+                let x = x.data.snapshot_preflight;
+
+                if x.passed {
+                    let query = snapshot::create::build(
+                        &model.fs_name,
+                        &model.name,
+                        model.comment.as_ref(),
+                        Some(model.barrier),
+                    );
+
+                    let req = fetch::Request::graphql_query(&query);
+
+                    orders.perform_cmd(req.fetch_json_data(|x| Msg::SnapshotCreateResp(x)));
+                } else {
+                    model.submitting = false;
+                }
+
+                model.preflight = Some(x);
+            }
+            Ok(Response::Errors(e)) => {
+                error!("An error has occurred during Snapshot pre-flight checks: ", e);
+
+                model.submitting = false;
+            }
+            Err(e) => {
+                error!("An error has occurred during Snapshot pre-flight checks: ", e);
+
+                model.submitting = false;
+            }
+        },
         Msg::SnapshotCreateResp(x) => match x {
             Ok(Response::Data(x)) => {
                 let x = command_modal::Input::Commands(vec![Arc::new(x.data.create_snapshot)]);
@@ -87,15 +125,33 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>)
         }
         Msg::NameChange(x) => {
             model.name = x;
+            model.preflight = None;
         }
         Msg::CommentChange(x) => {
             model.comment = Some(x);
         }
         Msg::FsNameChanged(x) => {
             model.fs_name = x;
+            model.preflight = None;
         }
         Msg::BarrierChanged(_) => {
             model.barrier = !model.barrier;
+            model.preflight = None;
+        }
+        Msg::Open(x) => {
+            model.fs_name = x;
+            model.preflight = None;
+
+            orders.after_next_render(|_| Msg::Focus);
+        }
+        Msg::Focus => {
+            if let Some(el) = seed::document().get_element_by_id("snapshot_name") {
+                el.scroll_into_view();
+
+                if let Some(el) = el.dyn_ref::<web_sys::HtmlElement>() {
+                    let _ = el.focus();
+                }
+            }
         }
     }
 }
@@ -235,7 +291,32 @@ pub fn view(model: &Model) -> Node<Msg> {
                     font_awesome_outline(class![C.h_4, C.w_4, C.mr_1, C.inline], "check-circle"),
                     "Take Snapshot",
                 ],
+                match &model.preflight {
+                    Some(x) if !x.passed => preflight_view(x),
+                    _ => empty![],
+                },
             ]
         ],
     )
 }
+
+/// The pre-flight checks that kept the snapshot from being taken.
+fn preflight_view<T>(x: &SnapshotPreflight) -> Node<T> {
+    div![
+        class![C.col_span_2],
+        p![class![C.mb_2], "The snapshot can't be taken:"],
+        ul![x.checks.iter().map(|c| {
+            let (cls, icon) = if c.passed {
+                (C.text_green_500, "check-circle")
+            } else {
+                (C.text_red_500, "exclamation-circle")
+            };
+
+            li![
+                class![C.py_1],
+                font_awesome(class![C.w_4, C.h_4, C.inline, C.mr_2, cls], icon),
+                &c.message
+            ]
+        })]
+    ]
+}
@@ -0,0 +1,341 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    components::{font_awesome, panel, table},
+    extensions::MergeAttrs as _,
+    generated::css_classes::C,
+    GMsg, RequestExt,
+};
+use chrono_humanize::{Accuracy, HumanTime, Tense};
+use iml_graphql_queries::{snapshot, Response};
+use iml_wire_types::{
+    snapshot::{Snapshot, SnapshotComparison, SnapshotRecord, CHANGE_THRESHOLD_ID},
+    warp_drive::ArcCache,
+    Filesystem,
+};
+use seed::{prelude::*, *};
+use std::sync::Arc;
+
+pub struct Model {
+    pub fs: Arc<Filesystem>,
+    a: String,
+    b: String,
+    comparison: Option<SnapshotComparison>,
+    loading: bool,
+}
+
+impl Model {
+    pub fn new(fs: Arc<Filesystem>) -> Self {
+        Self {
+            fs,
+            a: String::new(),
+            b: String::new(),
+            comparison: None,
+            loading: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    AChanged(String),
+    BChanged(String),
+    Compare,
+    Compared(fetch::ResponseDataResult<Response<snapshot::compare::Resp>>),
+}
+
+/// Preselects the two most recent snapshots of the filesystem and compares them.
+pub fn init(cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    let xs = fs_snapshots(cache, &model.fs.name);
+
+    if let [b, a, ..] = xs.as_slice() {
+        model.a = a.snapshot_name.clone();
+        model.b = b.snapshot_name.clone();
+
+        orders.send_msg(Msg::Compare);
+    }
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+    match msg {
+        Msg::AChanged(x) => {
+            model.a = x;
+        }
+        Msg::BChanged(x) => {
+            model.b = x;
+        }
+        Msg::Compare => {
+            if model.a.is_empty() || model.b.is_empty() || model.a == model.b {
+                return;
+            }
+
+            model.loading = true;
+
+            let query = snapshot::compare::build(&model.fs.name, &model.a, &model.b);
+
+            let req = fetch::Request::graphql_query(&query);
+
+            orders.perform_cmd(req.fetch_json_data(Msg::Compared));
+        }
+        Msg::Compared(x) => {
+            model.loading = false;
+
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.comparison = Some(x.data.snapshot_compare);
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while comparing snapshots", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while comparing snapshots", e);
+                }
+            }
+        }
+    }
+}
+
+pub fn view(cache: &ArcCache, model: &Model) -> Node<Msg> {
+    div![
+        select_view(cache, model),
+        match &model.comparison {
+            Some(x) => div![metadata_view(x), policy_runs_view(cache, x)],
+            None => empty![],
+        }
+    ]
+}
+
+fn select_view(cache: &ArcCache, model: &Model) -> Node<Msg> {
+    let xs = fs_snapshots(cache, &model.fs.name);
+
+    if xs.len() < 2 {
+        return panel::view(
+            h3![class![C.py_4, C.font_normal, C.text_lg], "Compare Snapshots"],
+            p![
+                class![C.p_4, C.text_gray_600],
+                format!("{} needs at least two snapshots to compare.", model.fs.name)
+            ],
+        );
+    }
+
+    let can_compare = !model.a.is_empty() && !model.b.is_empty() && model.a != model.b && !model.loading;
+
+    panel::view(
+        h3![class![C.py_4, C.font_normal, C.text_lg], "Compare Snapshots"],
+        form![
+            class![C.grid, C.grid_cols_2, C.gap_4, C.p_4, C.items_center],
+            ev(Ev::Submit, move |event| {
+                event.prevent_default();
+                Msg::Compare
+            }),
+            label![attrs! {At::For => "snapshot_a"}, "Snapshot"],
+            snapshot_select("snapshot_a", &xs, &model.a, Msg::AChanged),
+            label![attrs! {At::For => "snapshot_b"}, "Compare With"],
+            snapshot_select("snapshot_b", &xs, &model.b, Msg::BChanged),
+            div![
+                class![C.col_span_2, C.flex, C.justify_end],
+                button![
+                    class![
+                        C.bg_blue_500,
+                        C.duration_300,
+                        C.hover__bg_blue_400,
+                        C.px_6,
+                        C.py_2,
+                        C.rounded_sm,
+                        C.text_white,
+                        C.transition_colors,
+                        C.opacity_50 => !can_compare,
+                        C.cursor_not_allowed => !can_compare,
+                    ],
+                    attrs! {
+                        At::Type => "submit",
+                        At::Disabled => (!can_compare).as_at_value(),
+                    },
+                    if model.loading { "Comparing..." } else { "Compare" },
+                ],
+            ],
+        ],
+    )
+}
+
+fn snapshot_select(
+    id: &str,
+    xs: &[Arc<SnapshotRecord>],
+    selected: &str,
+    f: impl FnOnce(String) -> Msg + Clone + 'static,
+) -> Node<Msg> {
+    div![
+        class![C.inline_block, C.relative, C.bg_gray_200],
+        select![
+            id![id],
+            class![
+                C.appearance_none,
+                C.block,
+                C.leading_tight,
+                C.bg_transparent,
+                C.focus__outline_none,
+                C.px_3,
+                C.py_2,
+                C.pr_8,
+                C.rounded,
+                C.text_gray_800,
+                C.w_full,
+            ],
+            option![attrs! {At::Value => ""}, "Select a snapshot"],
+            xs.iter().map(|x| {
+                let mut opt = option![
+                    class![C.font_sans],
+                    attrs! {At::Value => x.snapshot_name},
+                    format!("{} ({})", x.snapshot_name, format_time(&x.create_time))
+                ];
+
+                if x.snapshot_name == selected {
+                    opt.add_attr(At::Selected.to_string(), "selected");
+                }
+
+                opt
+            }),
+            input_ev(Ev::Change, f),
+        ],
+        div![
+            class![
+                C.pointer_events_none,
+                C.absolute,
+                C.inset_y_0,
+                C.right_0,
+                C.flex,
+                C.items_center,
+                C.px_2,
+                C.text_gray_700,
+            ],
+            font_awesome(class![C.w_4, C.h_4, C.inline, C.ml_1], "chevron-down")
+        ],
+    ]
+}
+
+fn metadata_view(x: &SnapshotComparison) -> Node<Msg> {
+    let row = |label: &str, f: &dyn Fn(&Snapshot) -> String| {
+        let a = f(&x.from);
+        let b = f(&x.to);
+        let changed = a != b;
+
+        tr![
+            td![table::td_cls(), class![C.font_bold], label],
+            td![table::td_cls(), class![C.text_center, C.bg_yellow_100 => changed], a],
+            td![table::td_cls(), class![C.text_center, C.bg_yellow_100 => changed], b],
+        ]
+    };
+
+    let elapsed = HumanTime::from(x.to.create_time - x.from.create_time).to_text_en(Accuracy::Rough, Tense::Present);
+
+    panel::view(
+        h3![class![C.py_4, C.font_normal, C.text_lg], "Metadata"],
+        div![
+            table::wrapper_view(vec![
+                table::thead_view(vec![
+                    table::th_left(plain![""]),
+                    table::th_view(plain!["Older"]),
+                    table::th_view(plain!["Newer"]),
+                ]),
+                tbody![
+                    row("Name", &|s| s.snapshot_name.clone()),
+                    row("Created", &|s| format_time(&s.create_time)),
+                    row("Modified", &|s| format_time(&s.modify_time)),
+                    row("Comment", &|s| s.comment.clone().unwrap_or_else(|| "---".into())),
+                    row("State", &|s| {
+                        let x = if s.mounted { "mounted" } else { "unmounted" };
+
+                        x.to_string()
+                    }),
+                    row("Snapshot FS Name", &|s| s.snapshot_fsname.clone()),
+                ]
+            ])
+            .merge_attrs(class![C.my_6]),
+            p![
+                class![C.px_4, C.pb_4, C.text_gray_600],
+                format!("Taken {} apart.", elapsed)
+            ]
+        ],
+    )
+}
+
+fn policy_runs_view(cache: &ArcCache, x: &SnapshotComparison) -> Node<Msg> {
+    let body = if x.policy_runs.is_empty() {
+        p![
+            class![C.p_4, C.text_gray_600],
+            "No automatic snapshots were started between these snapshots."
+        ]
+    } else {
+        table::wrapper_view(vec![
+            table::thead_view(vec![
+                table::th_view(plain!["Started"]),
+                table::th_view(plain!["Snapshot"]),
+                table::th_view(plain!["Rule"]),
+                table::th_view(plain!["State"]),
+            ]),
+            tbody![x.policy_runs.iter().map(|r| {
+                let rule = cache
+                    .snapshot_interval
+                    .get(&r.interval_id)
+                    .map(|i| {
+                        let every = chrono::Duration::from_std(i.interval.0)
+                            .map(|d| HumanTime::from(d).to_text_en(Accuracy::Precise, Tense::Present))
+                            .unwrap_or_else(|_| "---".into());
+
+                        format!("Every {}", every)
+                    })
+                    .unwrap_or_else(|| {
+                        if r.interval_id == CHANGE_THRESHOLD_ID {
+                            "Change threshold".into()
+                        } else {
+                            format!("Rule {} (removed)", r.interval_id)
+                        }
+                    });
+
+                tr![
+                    table::td_center(plain![format_time(&r.started_at)]),
+                    table::td_center(plain![r.snapshot_name.clone()]),
+                    table::td_center(plain![rule]),
+                    td![
+                        table::td_cls(),
+                        class![
+                            C.text_center,
+                            C.text_red_600 => r.state == "failed",
+                            C.text_gray_500 => r.state == "cancelled",
+                        ],
+                        &r.state
+                    ],
+                ]
+            })],
+        ])
+        .merge_attrs(class![C.my_6])
+    };
+
+    panel::view(
+        h3![
+            class![C.py_4, C.font_normal, C.text_lg],
+            "Automatic Snapshot Runs In Between"
+        ],
+        body,
+    )
+}
+
+fn format_time(x: &chrono::DateTime<chrono::Utc>) -> String {
+    x.format("%m/%d/%Y %H:%M:%S").to_string()
+}
+
+/// Snapshots of `fs_name` in the cache, newest first.
+fn fs_snapshots(cache: &ArcCache, fs_name: &str) -> Vec<Arc<SnapshotRecord>> {
+    let mut xs: Vec<_> = cache
+        .snapshot
+        .values()
+        .filter(|x| x.filesystem_name == fs_name)
+        .cloned()
+        .collect();
+
+    xs.sort_by(|a, b| b.create_time.cmp(&a.create_time));
+
+    xs
+}
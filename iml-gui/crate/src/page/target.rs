@@ -3,24 +3,33 @@
 // license that can be found in the LICENSE file.
 
 use crate::{
-    components::{action_dropdown, alert_indicator, lock_indicator, panel, resource_links, Placement},
+    components::{action_dropdown, alert_indicator, font_awesome, lock_indicator, panel, resource_links, Placement},
     extensions::MergeAttrs as _,
     generated::css_classes::C,
     get_target_from_managed_target,
     page::filesystem::standby_hosts_view,
-    GMsg,
+    sleep_with_handle, GMsg, RequestExt,
 };
+use futures::channel::oneshot;
+use iml_graphql_queries::{target, Response};
 use iml_wire_types::{
-    db::ManagedTargetRecord,
+    db::{ManagedTargetRecord, TargetRecord},
+    graphql::{TargetParam, TargetStateChange, TargetStateEvent},
     warp_drive::{ArcCache, Locks},
     Label, Session, ToCompositeId,
 };
 use seed::{prelude::*, *};
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, sync::Arc, time::Duration};
+
+/// Runtime parameters shown for the target, refreshed while the page is open.
+const PARAMS: [&str; 2] = ["recovery_status", "tot_granted"];
 
 pub struct Model {
     pub target: Arc<ManagedTargetRecord>,
     dropdown: action_dropdown::Model,
+    params: Vec<TargetParam>,
+    params_cancel: Option<oneshot::Sender<()>>,
+    history: Vec<TargetStateChange>,
 }
 
 impl Model {
@@ -28,6 +37,9 @@ impl Model {
         Self {
             dropdown: action_dropdown::Model::new(vec![target.composite_id()]),
             target,
+            params: vec![],
+            params_cancel: None,
+            history: vec![],
         }
     }
 }
@@ -36,6 +48,16 @@ impl Model {
 pub enum Msg {
     ActionDropdown(action_dropdown::IdMsg),
     UpdateTarget(Arc<ManagedTargetRecord>),
+    UpdateTargetRecord(Arc<TargetRecord>),
+    FetchParams,
+    ParamsFetched(fetch::ResponseDataResult<Response<target::params::Resp>>),
+    FetchHistory,
+    HistoryFetched(fetch::ResponseDataResult<Response<target::state_history::Resp>>),
+    Noop,
+}
+
+pub fn init(orders: &mut impl Orders<Msg, GMsg>) {
+    orders.send_msg(Msg::FetchParams).send_msg(Msg::FetchHistory);
 }
 
 pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
@@ -55,10 +77,77 @@ pub fn update(msg: Msg, cache: &ArcCache, model: &mut Model, orders: &mut impl O
                 model.target = x;
             }
         }
+        Msg::UpdateTargetRecord(x) => {
+            if model.target.uuid.as_ref() == Some(&x.uuid) {
+                orders.send_msg(Msg::FetchHistory);
+            }
+        }
+        Msg::FetchParams => {
+            model.params_cancel = None;
+
+            let mounted = get_target_from_managed_target(cache, &model.target)
+                .and_then(|x| x.active_host_id)
+                .is_some();
+
+            match model.target.uuid.as_ref() {
+                Some(uuid) if mounted => {
+                    let query = target::params::build(uuid, &PARAMS);
+                    let req =
+                        fetch::Request::graphql_get(&query).unwrap_or_else(|_| fetch::Request::graphql_query(&query));
+
+                    orders.perform_cmd(req.fetch_json_data(Msg::ParamsFetched));
+                }
+                _ => {
+                    model.params.clear();
+
+                    let (cancel, fut) = sleep_with_handle(Duration::from_secs(10), Msg::FetchParams, Msg::Noop);
+                    model.params_cancel = Some(cancel);
+                    orders.perform_cmd(fut);
+                }
+            }
+        }
+        Msg::ParamsFetched(x) => {
+            match x {
+                Ok(Response::Data(x)) => {
+                    model.params = x.data.target_params;
+                }
+                Ok(Response::Errors(e)) => {
+                    error!("An error occurred while retrieving target parameters", e);
+                }
+                Err(e) => {
+                    error!("An error occurred while retrieving target parameters", e);
+                }
+            }
+
+            let (cancel, fut) = sleep_with_handle(Duration::from_secs(10), Msg::FetchParams, Msg::Noop);
+            model.params_cancel = Some(cancel);
+            orders.perform_cmd(fut);
+        }
+        Msg::FetchHistory => {
+            if let Some(uuid) = model.target.uuid.as_ref() {
+                let query = target::state_history::build(uuid, None, None);
+                let req = fetch::Request::graphql_query(&query);
+
+                orders.perform_cmd(req.fetch_json_data(Msg::HistoryFetched));
+            }
+        }
+        Msg::HistoryFetched(x) => match x {
+            Ok(Response::Data(x)) => {
+                model.history = x.data.target_state_history;
+            }
+            Ok(Response::Errors(e)) => {
+                error!("An error occurred while retrieving target state history", e);
+            }
+            Err(e) => {
+                error!("An error occurred while retrieving target state history", e);
+                orders.skip();
+            }
+        },
+        Msg::Noop => {}
     }
 }
 
-pub fn view(cache: &ArcCache, model: &Model, all_locks: &Locks, session: Option<&Session>) -> Node<Msg> {
+pub fn view(cache: &ArcCache, model: &Model, all_locks: &Locks, session: Option<&Session>) -> impl View<Msg> {
     let t = get_target_from_managed_target(cache, &model.target);
 
     let active_host = t
@@ -72,40 +161,120 @@ pub fn view(cache: &ArcCache, model: &Model, all_locks: &Locks, session: Option<
         .map(|x| Cow::from(x.to_string()))
         .unwrap_or_else(|| Cow::from("---"));
 
-    panel::view(
-        h3![
-            class![C.py_4, C.font_normal, C.text_lg],
-            &format!("Target: {}", model.target.label()),
-            lock_indicator::view(all_locks, &model.target).merge_attrs(class![C.ml_2]),
-            alert_indicator(&cache.active_alert, &model.target, true, Placement::Right).merge_attrs(class![C.ml_2]),
-        ],
-        div![
-            class![C.grid, C.grid_cols_2, C.gap_4],
-            div![class![C.p_6], "Active Server"],
-            div![
-                class![C.p_6],
-                resource_links::server_link(
-                    active_host.as_ref().map(|x| &x.resource_uri),
-                    &active_host.as_ref().map(|x| x.fqdn.to_string()).unwrap_or_default()
-                )
+    nodes![
+        panel::view(
+            h3![
+                class![C.py_4, C.font_normal, C.text_lg],
+                &format!("Target: {}", model.target.label()),
+                lock_indicator::view(all_locks, &model.target).merge_attrs(class![C.ml_2]),
+                alert_indicator(&cache.active_alert, &model.target, true, Placement::Right).merge_attrs(class![C.ml_2]),
             ],
-            div![class![C.p_6], "Standby Servers"],
             div![
-                class![C.p_6],
-                match t {
-                    Some(t) => {
-                        standby_hosts_view(cache, &t)
-                    }
-                    None => {
-                        plain!["---"]
+                class![C.grid, C.grid_cols_2, C.gap_4],
+                div![class![C.p_6], "Active Server"],
+                div![
+                    class![C.p_6],
+                    resource_links::server_link(
+                        active_host.as_ref().map(|x| &x.resource_uri),
+                        &active_host.as_ref().map(|x| x.fqdn.to_string()).unwrap_or_default()
+                    )
+                ],
+                div![class![C.p_6], "Standby Servers"],
+                div![
+                    class![C.p_6],
+                    match t {
+                        Some(t) => {
+                            standby_hosts_view(cache, &t)
+                        }
+                        None => {
+                            plain!["---"]
+                        }
                     }
-                }
+                ],
+                div![class![C.p_6], "Device Path"],
+                div![class![C.p_6], dev_path],
+                params_view(&model.params),
+                action_dropdown::view(model.target.id, &model.dropdown, all_locks, session)
+                    .merge_attrs(class![C.p_6, C.grid, C.col_span_2])
+                    .map_msg(Msg::ActionDropdown)
             ],
-            div![class![C.p_6], "Device Path"],
-            div![class![C.p_6], dev_path],
-            action_dropdown::view(model.target.id, &model.dropdown, all_locks, session)
-                .merge_attrs(class![C.p_6, C.grid, C.col_span_2])
-                .map_msg(Msg::ActionDropdown)
-        ],
+        ),
+        history_view(&model.history).merge_attrs(class![C.mt_4]),
+    ]
+}
+
+/// A timeline of the target's mounts, unmounts, failovers and recoveries, newest first.
+fn history_view(xs: &[TargetStateChange]) -> Node<Msg> {
+    panel::view(
+        h3![class![C.py_4, C.font_normal, C.text_lg], "State History"],
+        if xs.is_empty() {
+            div![
+                class![C.p_6, C.text_gray_500],
+                "No state changes have been recorded for this target."
+            ]
+        } else {
+            ol![
+                class![C.px_6, C.py_4],
+                xs.iter().map(|x| {
+                    let (icon, color) = event_icon(x.event);
+
+                    li![
+                        class![C.flex, C.items_start, C.pb_4, C.border_l_2, C.border_gray_300, C.pl_4],
+                        font_awesome(class![C.w_4, C.h_4, C.inline, C.mt_1, C.mr_3, color], icon),
+                        div![
+                            div![
+                                class![C.font_medium],
+                                event_label(x.event),
+                                span![
+                                    class![C.ml_2, C.text_sm, C.text_gray_500],
+                                    x.changed_at.format("%m/%d/%Y %H:%M:%S").to_string()
+                                ],
+                            ],
+                            div![class![C.text_sm], &x.reason],
+                        ]
+                    ]
+                })
+            ]
+        },
     )
 }
+
+fn event_icon(x: TargetStateEvent) -> (&'static str, &'static str) {
+    match x {
+        TargetStateEvent::Mounted => ("check-circle", C.text_green_500),
+        TargetStateEvent::Unmounted => ("power-off", C.text_gray_500),
+        TargetStateEvent::Failover => ("exclamation-triangle", C.text_yellow_500),
+        TargetStateEvent::RecoveryStart => ("clock", C.text_blue_500),
+        TargetStateEvent::RecoveryEnd => ("check", C.text_blue_500),
+    }
+}
+
+fn event_label(x: TargetStateEvent) -> &'static str {
+    match x {
+        TargetStateEvent::Mounted => "Mounted",
+        TargetStateEvent::Unmounted => "Unmounted",
+        TargetStateEvent::Failover => "Failover",
+        TargetStateEvent::RecoveryStart => "Recovery started",
+        TargetStateEvent::RecoveryEnd => "Recovery completed",
+    }
+}
+
+fn params_view(xs: &[TargetParam]) -> Vec<Node<Msg>> {
+    xs.iter()
+        .filter_map(|x| x.value.as_ref().map(|v| (&x.key, v)))
+        .flat_map(|(k, v)| {
+            vec![
+                div![class![C.p_6], param_label(k)],
+                div![class![C.p_6], pre![class![C.text_sm, C.whitespace_pre_wrap], v]],
+            ]
+        })
+        .collect()
+}
+
+fn param_label(key: &str) -> &str {
+    match key {
+        "recovery_status" => "Recovery Status",
+        "tot_granted" => "Total Granted",
+        x => x,
+    }
+}
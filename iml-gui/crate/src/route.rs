@@ -44,6 +44,8 @@ impl<'a> Deref for RouteId<'a> {
 pub enum Route<'a> {
     About,
     Dashboard,
+    CustomDashboards,
+    CustomDashboard(RouteId<'a>),
     FsDashboard(RouteId<'a>),
     ServerDashboard(RouteId<'a>),
     TargetDashboard(RouteId<'a>),
@@ -51,10 +53,15 @@ pub enum Route<'a> {
     Filesystem(RouteId<'a>),
     Jobstats,
     Login,
+    ManagerStatus,
     Mgt,
     NotFound,
+    OnCall,
     PowerControl,
+    Roles,
     Servers,
+    ServerProfileCompare,
+    Sites,
     Server(RouteId<'a>),
     OstPools,
     OstPool(RouteId<'a>),
@@ -67,6 +74,7 @@ pub enum Route<'a> {
     Volume(RouteId<'a>),
     SfaEnclosure(RouteId<'a>),
     Snapshots,
+    SnapshotCompare(RouteId<'a>),
     Stratagem,
 }
 
@@ -75,6 +83,8 @@ impl<'a> Route<'a> {
         let mut p = match self {
             Self::About => vec!["about"],
             Self::Dashboard => vec!["dashboard"],
+            Self::CustomDashboards => vec!["dashboard", "custom"],
+            Self::CustomDashboard(id) => vec!["dashboard", "custom", id],
             Self::FsDashboard(id) => vec!["dashboard", "fs", id],
             Self::ServerDashboard(id) => vec!["dashboard", "server", id],
             Self::TargetDashboard(id) => vec!["dashboard", "target", id],
@@ -82,12 +92,17 @@ impl<'a> Route<'a> {
             Self::Filesystem(id) => vec!["filesystems", id],
             Self::Jobstats => vec!["jobstats"],
             Self::Login => vec!["login"],
+            Self::ManagerStatus => vec!["manager_status"],
             Self::Mgt => vec!["mgt"],
             Self::NotFound => vec!["404"],
+            Self::OnCall => vec!["oncall"],
             Self::OstPools => vec!["ost_pools"],
             Self::OstPool(id) => vec!["ost_pools", id],
             Self::PowerControl => vec!["power_control"],
+            Self::Roles => vec!["roles"],
             Self::Servers => vec!["servers"],
+            Self::ServerProfileCompare => vec!["server_profiles", "compare"],
+            Self::Sites => vec!["sites"],
             Self::Server(id) => vec!["servers", id],
             Self::Targets => vec!["targets"],
             Self::Target(id) => vec!["targets", id],
@@ -98,6 +113,7 @@ impl<'a> Route<'a> {
             Self::Volume(id) => vec!["volumes", id],
             Self::SfaEnclosure(id) => vec!["sfa_enclosure", id],
             Self::Snapshots => vec!["snapshots"],
+            Self::SnapshotCompare(id) => vec!["snapshots", "compare", id],
             Self::Stratagem => vec!["stratagem"],
         };
 
@@ -128,6 +144,10 @@ impl<'a> From<Url> for Route<'a> {
             Some("dashboard") => match path.next() {
                 None => Self::Dashboard,
                 Some(name) => match name.as_str() {
+                    "custom" => match path.next() {
+                        Some(id) => Self::CustomDashboard(RouteId::from(id)),
+                        None => Self::CustomDashboards,
+                    },
                     "fs" => match path.next() {
                         Some(name) => Self::FsDashboard(RouteId::from(name)),
                         None => Self::Dashboard,
@@ -150,12 +170,15 @@ impl<'a> From<Url> for Route<'a> {
             None | Some("") => Self::Dashboard,
             Some("jobstats") => Self::Jobstats,
             Some("login") => Self::Login,
+            Some("manager_status") => Self::ManagerStatus,
             Some("mgt") => Self::Mgt,
+            Some("oncall") => Self::OnCall,
             Some("ost_pools") => match path.next() {
                 None => Self::OstPools,
                 Some(id) => Self::OstPool(RouteId::from(id)),
             },
             Some("power_control") => Self::PowerControl,
+            Some("roles") => Self::Roles,
             Some("servers") => match path.next() {
                 None => Self::Servers,
                 Some(id) => match path.next().as_deref() {
@@ -164,6 +187,11 @@ impl<'a> From<Url> for Route<'a> {
                     _ => Self::NotFound,
                 },
             },
+            Some("server_profiles") => match path.next().as_deref() {
+                Some("compare") => Self::ServerProfileCompare,
+                _ => Self::NotFound,
+            },
+            Some("sites") => Self::Sites,
             Some("targets") => match path.next() {
                 None => Self::Targets,
                 Some(id) => Self::Target(RouteId::from(id)),
@@ -180,7 +208,14 @@ impl<'a> From<Url> for Route<'a> {
                 Some(id) => Self::SfaEnclosure(RouteId::from(id)),
                 None => Self::NotFound,
             },
-            Some("snapshots") => Self::Snapshots,
+            Some("snapshots") => match path.next().as_deref() {
+                None => Self::Snapshots,
+                Some("compare") => match path.next() {
+                    Some(id) => Self::SnapshotCompare(RouteId::from(id)),
+                    None => Self::NotFound,
+                },
+                _ => Self::NotFound,
+            },
             Some("stratagem") => Self::Stratagem,
             _ => Self::NotFound,
         }
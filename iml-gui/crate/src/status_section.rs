@@ -22,6 +22,7 @@ use seed::{prelude::*, *};
 #[derive(Default)]
 pub struct Model {
     section: Option<Section>,
+    fs_name: Option<String>,
 }
 
 pub enum Section {
@@ -59,6 +60,7 @@ pub enum Msg {
     LogsSection(logs::Msg),
     Open(SectionSelector),
     Close,
+    SetFsContext(Option<String>),
 }
 
 pub fn update(msg: Msg, records: &ArcCache, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
@@ -69,11 +71,14 @@ pub fn update(msg: Msg, records: &ArcCache, model: &mut Model, orders: &mut impl
                 return;
             }
 
-            let section = section.into();
+            let mut section = section.into();
 
-            match &section {
+            match &mut section {
                 Section::Activity(_) => activity::init(&mut orders.proxy(Msg::ActivitySection)),
-                Section::Logs(_) => logs::init(&mut orders.proxy(Msg::LogsSection)),
+                Section::Logs(x) => {
+                    x.fs_name = model.fs_name.clone();
+                    logs::init(x, &mut orders.proxy(Msg::LogsSection))
+                }
             }
 
             model.section = Some(section);
@@ -81,6 +86,13 @@ pub fn update(msg: Msg, records: &ArcCache, model: &mut Model, orders: &mut impl
         Msg::Close => {
             model.section = None;
         }
+        Msg::SetFsContext(x) => {
+            model.fs_name = x.clone();
+
+            if let Some(Section::Logs(_)) = &model.section {
+                orders.send_msg(Msg::LogsSection(logs::Msg::SetFsName(x)));
+            }
+        }
         Msg::ActivitySection(msg) => {
             if let Some(Section::Activity(x)) = &mut model.section {
                 activity::update(msg, records, x, &mut orders.proxy(Msg::ActivitySection))
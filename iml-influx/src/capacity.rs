@@ -0,0 +1,37 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+/// Daily OST capacity of `fs_name` over the last `days`.
+/// Columns are `time`, `bytes_total` and `bytes_free`, summed over the OSTs of the filesystem.
+/// Days without stats are left out.
+pub fn query(fs_name: &str, days: u32) -> String {
+    format!(
+        r#"SELECT SUM(b_total) AS bytes_total, SUM(b_free) AS bytes_free
+           FROM (SELECT MEAN(bytes_total) AS b_total, MEAN(bytes_free) AS b_free
+                 FROM target
+                 WHERE "kind" = 'OST' AND "fs" = '{fs_name}'
+                   AND time > now() - {days}d
+                 GROUP BY time(1d), "target")
+           WHERE time > now() - {days}d
+           GROUP BY time(1d) fill(none)"#,
+        fs_name = fs_name.replace('\'', r"\'"),
+        days = days,
+    )
+    .split_whitespace()
+    .collect::<Vec<&str>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let q = query("scratch", 30);
+
+        assert!(q.contains(r#"WHERE "kind" = 'OST' AND "fs" = 'scratch' AND time > now() - 30d"#));
+        assert!(q.ends_with("GROUP BY time(1d) fill(none)"));
+    }
+}
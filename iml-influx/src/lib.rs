@@ -2,8 +2,11 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+pub mod capacity;
 pub mod filesystem;
 pub mod filesystems;
+pub mod mdts;
+pub mod mini_stats;
 
 #[cfg(feature = "with-db-client")]
 use futures::{future::BoxFuture, FutureExt};
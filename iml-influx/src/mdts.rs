@@ -0,0 +1,54 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+/// The latest inode usage of each MDT of `fs_name`.
+/// There is a series per MDT, tagged with `target`. Columns are `time`, `files_total` and `files_free`.
+pub fn query(fs_name: &str) -> String {
+    format!(
+        r#"SELECT LAST(files_total) AS files_total, LAST(files_free) AS files_free
+           FROM target
+           WHERE "kind" = 'MDT' AND "fs" = '{fs_name}'
+           GROUP BY "target""#,
+        fs_name = fs_name.replace('\'', r"\'"),
+    )
+    .split_whitespace()
+    .collect::<Vec<&str>>()
+    .join(" ")
+}
+
+/// The latest space usage of each MDT of `fs_name`.
+/// There is a series per MDT, tagged with `target`. Columns are `time`, `bytes_total` and `bytes_free`.
+pub fn space_query(fs_name: &str) -> String {
+    format!(
+        r#"SELECT LAST(bytes_total) AS bytes_total, LAST(bytes_free) AS bytes_free
+           FROM target
+           WHERE "kind" = 'MDT' AND "fs" = '{fs_name}'
+           GROUP BY "target""#,
+        fs_name = fs_name.replace('\'', r"\'"),
+    )
+    .split_whitespace()
+    .collect::<Vec<&str>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let q = query("scratch");
+
+        assert!(q.contains(r#"WHERE "kind" = 'MDT' AND "fs" = 'scratch' GROUP BY "target""#));
+        assert!(query("a'b").contains(r#""fs" = 'a\'b'"#));
+    }
+
+    #[test]
+    fn test_space_query() {
+        let q = space_query("scratch");
+
+        assert!(q.starts_with("SELECT LAST(bytes_total) AS bytes_total"));
+        assert!(q.contains(r#"WHERE "kind" = 'MDT' AND "fs" = 'scratch' GROUP BY "target""#));
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+/// How far back mini stats go.
+pub const WINDOW_SECS: u32 = 600;
+
+/// The width of each mini stats bucket.
+pub const BUCKET_SECS: u32 = 30;
+
+/// Read and write bandwidth in bytes/sec of `targets` over the last `WINDOW_SECS`,
+/// in `BUCKET_SECS` buckets. Results are grouped by the `target` and `name` tags,
+/// where `name` is either `read_bytes` or `write_bytes`.
+pub fn query(targets: &[String]) -> String {
+    let targets = targets
+        .iter()
+        .map(|x| format!(r#""target" = '{}'"#, x.replace('\'', r"\'")))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+
+    format!(
+        r#"SELECT MEAN("diff")
+           FROM (SELECT NON_NEGATIVE_DERIVATIVE("sum", 1s) AS "diff"
+                 FROM "target"
+                 WHERE ("name" = 'read_bytes' OR "name" = 'write_bytes')
+                   AND ({targets})
+                   AND time > now() - {window}s
+                 GROUP BY "target", "name", "host")
+           WHERE time > now() - {window}s
+           GROUP BY time({bucket}s), "target", "name" fill(0)"#,
+        targets = targets,
+        window = WINDOW_SECS,
+        bucket = BUCKET_SECS,
+    )
+    .split_whitespace()
+    .collect::<Vec<&str>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let q = query(&["fs-OST0000".into(), "fs-OST0001".into()]);
+
+        assert!(q.contains(r#"AND ("target" = 'fs-OST0000' OR "target" = 'fs-OST0001')"#));
+        assert!(q.ends_with(r#"GROUP BY time(30s), "target", "name" fill(0)"#));
+    }
+}
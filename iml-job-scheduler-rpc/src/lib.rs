@@ -20,6 +20,7 @@ use iml_wire_types::CompositeId;
 use request::Request;
 use response::Response;
 use std::{collections::HashMap, fmt::Debug};
+use tracing::Instrument as _;
 use uuid::Uuid;
 
 static JOB_SCHEDULER_RPC: &str = "JobSchedulerRpc";
@@ -92,61 +93,67 @@ pub async fn call<I: Debug + serde::Serialize, T: serde::de::DeserializeOwned>(
         kwargs.into().unwrap_or_default(),
     );
 
-    tracing::debug!("--> to job scheduler {} {:?}", method, req);
-
-    let channel = create_channel(conn).await?;
-    declare_transient_exchange(&channel, RPC, ExchangeKind::Topic).await?;
-
-    let queue = declare_queue(
-        &channel,
-        &response_key,
-        QueueDeclareOptions {
-            durable: false,
-            auto_delete: true,
-            ..QueueDeclareOptions::default()
-        },
-        None,
-    )
-    .await?;
-
-    bind_queue(&channel, RPC, &response_key, &response_key).await?;
-
-    basic_publish(
-        &channel,
-        RPC,
-        format!("{}.requests", JOB_SCHEDULER_RPC),
-        req,
-    )
-    .await?;
-
-    let (channel, delivery) = basic_consume_one(
-        &channel,
-        queue,
-        &response_key,
-        Some(BasicConsumeOptions {
-            no_ack: true,
-            ..BasicConsumeOptions::default()
-        }),
-    )
-    .await?;
-
-    tracing::debug!(
-        "<- from job scheduler {} {:?}",
-        method,
-        std::str::from_utf8(&delivery.data)
-    );
-
-    close_channel(&channel).await?;
-
-    let resp: Response<T> = serde_json::from_slice(&delivery.data)?;
+    let span = tracing::info_span!("job_scheduler_rpc", otel.kind = "client", method = %method);
+
+    async move {
+        tracing::debug!("--> to job scheduler {} {:?}", method, req);
+
+        let channel = create_channel(conn).await?;
+        declare_transient_exchange(&channel, RPC, ExchangeKind::Topic).await?;
+
+        let queue = declare_queue(
+            &channel,
+            &response_key,
+            QueueDeclareOptions {
+                durable: false,
+                auto_delete: true,
+                ..QueueDeclareOptions::default()
+            },
+            None,
+        )
+        .await?;
+
+        bind_queue(&channel, RPC, &response_key, &response_key).await?;
+
+        basic_publish(
+            &channel,
+            RPC,
+            format!("{}.requests", JOB_SCHEDULER_RPC),
+            req,
+        )
+        .await?;
+
+        let (channel, delivery) = basic_consume_one(
+            &channel,
+            queue,
+            &response_key,
+            Some(BasicConsumeOptions {
+                no_ack: true,
+                ..BasicConsumeOptions::default()
+            }),
+        )
+        .await?;
+
+        tracing::debug!(
+            "<- from job scheduler {} {:?}",
+            method,
+            std::str::from_utf8(&delivery.data)
+        );
+
+        close_channel(&channel).await?;
+
+        let resp: Response<T> = serde_json::from_slice(&delivery.data)?;
+
+        if let Some(e) = resp.exception {
+            return Err(e.into());
+        }
 
-    if let Some(e) = resp.exception {
-        return Err(e.into());
+        resp.result.ok_or_else(|| {
+            ImlJobSchedulerRpcError::RpcError("RPC response was unexpectedly empty".into())
+        })
     }
-
-    resp.result.ok_or_else(|| {
-        ImlJobSchedulerRpcError::RpcError("RPC response was unexpectedly empty".into())
-    })
+    .instrument(span)
+    .await
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -0,0 +1,120 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::{graphql, wait_for_cmd_display},
+    display_utils::{display_output, display_success, generate_table, wrap_fut, DisplayType},
+    error::ImlManagerCliError,
+};
+use iml_graphql_queries::{approval as approval_queries, Response};
+use iml_wire_types::approval::ApprovalState;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum ApprovalCommand {
+    /// List the commands held back for approval, newest first
+    #[structopt(name = "list")]
+    List {
+        /// List decided requests too
+        #[structopt(short = "a", long = "all")]
+        all: bool,
+        #[structopt(short = "l", long = "limit")]
+        limit: Option<i32>,
+    },
+    /// Approve a command another user submitted, and dispatch it
+    #[structopt(name = "approve")]
+    Approve {
+        /// The approval request
+        id: i32,
+    },
+    /// Reject a command, or withdraw one you submitted
+    #[structopt(name = "reject")]
+    Reject {
+        /// The approval request
+        id: i32,
+        #[structopt(short = "r", long = "reason")]
+        reason: Option<String>,
+    },
+}
+
+pub async fn approval_cli(
+    command: ApprovalCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    match command {
+        ApprovalCommand::List { all, limit } => {
+            let state = if all {
+                None
+            } else {
+                Some(ApprovalState::Pending)
+            };
+
+            let query = approval_queries::requests::build(state, limit);
+
+            let resp: Response<approval_queries::requests::Resp> =
+                wrap_fut("Fetching approval requests...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.approval.requests;
+
+            if !output.is_tabular() {
+                display_output(&xs, output);
+
+                return Ok(());
+            }
+
+            let table = generate_table(
+                &[
+                    "Id",
+                    "Message",
+                    "Job Classes",
+                    "State",
+                    "Requested By",
+                    "Requested",
+                    "Decided By",
+                    "Command",
+                ],
+                xs.into_iter().map(|x| {
+                    vec![
+                        x.id.to_string(),
+                        x.message,
+                        x.class_names.join(", "),
+                        format!("{:?}", x.state),
+                        x.requested_by.unwrap_or_else(|| "---".to_string()),
+                        x.requested_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                        x.decided_by.unwrap_or_else(|| "---".to_string()),
+                        x.command_id
+                            .map(|x| x.to_string())
+                            .unwrap_or_else(|| "---".to_string()),
+                    ]
+                }),
+            );
+
+            table.printstd();
+        }
+        ApprovalCommand::Approve { id } => {
+            let query = approval_queries::approve::build(id);
+
+            let resp: Response<approval_queries::approve::Resp> =
+                wrap_fut("Approving command...", graphql(query)).await?;
+
+            let command = Result::from(resp)?.data.approval.approve;
+
+            let cmd = wait_for_cmd_display(command).await?;
+
+            display_output(&cmd, output);
+        }
+        ApprovalCommand::Reject { id, reason } => {
+            let query = approval_queries::reject::build(id, reason);
+
+            let resp: Response<approval_queries::reject::Resp> =
+                wrap_fut("Rejecting command...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.approval.reject;
+
+            display_success(format!("Rejected approval request {}", x.id));
+        }
+    };
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::graphql,
+    display_utils::{display_output, generate_table, wrap_fut, DisplayType},
+    error::ImlManagerCliError,
+};
+use console::Term;
+use iml_graphql_queries::changelog as changelog_queries;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum ChangelogCommand {
+    /// List the changelog users of each MDT
+    #[structopt(name = "list")]
+    List {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+    },
+    /// Register a changelog user on an MDT
+    #[structopt(name = "register")]
+    Register {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The MDT, e.g. fs-MDT0000
+        target: String,
+    },
+    /// Deregister a changelog user from an MDT
+    #[structopt(name = "deregister")]
+    Deregister {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The MDT, e.g. fs-MDT0000
+        target: String,
+        /// The changelog user, e.g. cl1
+        id: String,
+    },
+}
+
+pub async fn changelog_cli(
+    command: ChangelogCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    let term = Term::stderr();
+
+    match command {
+        ChangelogCommand::List { fsname } => {
+            let query = changelog_queries::users::build(&fsname);
+
+            let resp: iml_graphql_queries::Response<changelog_queries::users::Resp> =
+                wrap_fut("Fetching changelog users...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.changelog.users;
+
+            if !output.is_tabular() {
+                display_output(&xs, output);
+
+                return Ok(());
+            }
+
+            let table = generate_table(
+                &["Target", "Id", "Index", "Lag", "Idle (s)"],
+                xs.iter().flat_map(|mdt| {
+                    mdt.users.iter().map(move |x| {
+                        vec![
+                            mdt.target.clone(),
+                            x.id.clone(),
+                            x.index.to_string(),
+                            x.lag.to_string(),
+                            x.idle_secs
+                                .map(|x| x.to_string())
+                                .unwrap_or_else(|| "---".into()),
+                        ]
+                    })
+                }),
+            );
+
+            table.printstd();
+        }
+        ChangelogCommand::Register { fsname, target } => {
+            let query = changelog_queries::register::build(&fsname, &target);
+
+            let resp: iml_graphql_queries::Response<changelog_queries::register::Resp> =
+                wrap_fut("Registering changelog user...", graphql(query)).await?;
+
+            let id = Result::from(resp)?.data.changelog.register;
+
+            display_output(&id, output);
+
+            term.write_line(&format!("Registered {} on {}", id, target))
+                .unwrap();
+        }
+        ChangelogCommand::Deregister { fsname, target, id } => {
+            let query = changelog_queries::deregister::build(&fsname, &target, &id);
+
+            let resp: iml_graphql_queries::Response<changelog_queries::deregister::Resp> =
+                wrap_fut("Deregistering changelog user...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            term.write_line(&format!("Deregistered {} from {}", id, target))
+                .unwrap();
+        }
+    };
+
+    Ok(())
+}
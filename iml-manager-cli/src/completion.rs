@@ -0,0 +1,311 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::{get_all, get_hosts},
+    error::ImlManagerCliError,
+    target::get_all_targets,
+};
+use iml_graphql_queries::target as target_queries;
+use iml_wire_types::Filesystem;
+use std::collections::BTreeSet;
+use structopt::{
+    clap::{arg_enum, Shell},
+    StructOpt,
+};
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ValueKind {
+        Filesystem,
+        Host,
+        Target,
+    }
+}
+
+/// Which positional arguments take values known to the manager.
+/// `(command, position, kind)`, every position from `position` on when `repeats`.
+struct DynamicArg {
+    command: &'static str,
+    position: usize,
+    repeats: bool,
+    kind: ValueKind,
+}
+
+const fn arg(command: &'static str, position: usize, kind: ValueKind) -> DynamicArg {
+    DynamicArg {
+        command,
+        position,
+        repeats: false,
+        kind,
+    }
+}
+
+const fn args(command: &'static str, position: usize, kind: ValueKind) -> DynamicArg {
+    DynamicArg {
+        command,
+        position,
+        repeats: true,
+        kind,
+    }
+}
+
+const DYNAMIC_ARGS: &[DynamicArg] = &[
+    arg("filesystem show", 0, ValueKind::Filesystem),
+    arg("filesystem forget", 0, ValueKind::Filesystem),
+    arg("filesystem compatibility", 0, ValueKind::Filesystem),
+    arg("filesystem start", 0, ValueKind::Filesystem),
+    arg("filesystem stop", 0, ValueKind::Filesystem),
+    arg("filesystem remount-policy", 0, ValueKind::Filesystem),
+    arg("filesystem list-client-mount", 0, ValueKind::Filesystem),
+    arg("filesystem client-mount-config", 0, ValueKind::Filesystem),
+    args("filesystem detect", 0, ValueKind::Host),
+    arg("filesystem pool list", 0, ValueKind::Filesystem),
+    arg("filesystem pool show", 0, ValueKind::Filesystem),
+    arg("filesystem pool create", 0, ValueKind::Filesystem),
+    arg("filesystem pool grow", 0, ValueKind::Filesystem),
+    arg("filesystem pool shrink", 0, ValueKind::Filesystem),
+    arg("filesystem pool destroy", 0, ValueKind::Filesystem),
+    args("server remove", 0, ValueKind::Host),
+    args("server force-remove", 0, ValueKind::Host),
+    args("server tag", 1, ValueKind::Host),
+    args("server untag", 1, ValueKind::Host),
+    arg("server queue", 0, ValueKind::Host),
+    args("server certificates", 0, ValueKind::Host),
+    args("server rotate-certificate", 0, ValueKind::Host),
+    args("server ping-mesh", 0, ValueKind::Host),
+    arg("snapshot create", 0, ValueKind::Filesystem),
+    arg("snapshot destroy", 0, ValueKind::Filesystem),
+    arg("snapshot mount", 0, ValueKind::Filesystem),
+    arg("snapshot unmount", 0, ValueKind::Filesystem),
+    arg("snapshot hold", 0, ValueKind::Filesystem),
+    arg("snapshot release", 0, ValueKind::Filesystem),
+    arg("snapshot list", 0, ValueKind::Filesystem),
+    args("snapshot policy export", 0, ValueKind::Filesystem),
+    arg("target list", 0, ValueKind::Filesystem),
+    arg("target multipath", 0, ValueKind::Target),
+];
+
+#[derive(Debug, StructOpt)]
+pub enum CompletionCommand {
+    /// Print a completion script for `shell`.
+    /// The bash script also completes filesystem names, server names and target uuids
+    /// by asking the manager, e.g. `source <(iml completion script bash)`.
+    /// zsh can use it after `autoload bashcompinit && bashcompinit`
+    #[structopt(name = "script")]
+    Script {
+        shell: Shell,
+        #[structopt(short = "e", long = "executable", default_value = "iml")]
+        exe: String,
+    },
+    /// Print the known values of `kind` starting with `prefix`, one per line
+    #[structopt(name = "values", setting = structopt::clap::AppSettings::Hidden)]
+    Values {
+        #[structopt(possible_values = &ValueKind::variants(), case_insensitive = true)]
+        kind: ValueKind,
+        prefix: Option<String>,
+    },
+}
+
+async fn values(kind: ValueKind) -> Result<BTreeSet<String>, ImlManagerCliError> {
+    let xs = match kind {
+        ValueKind::Filesystem => get_all::<Filesystem>()
+            .await?
+            .objects
+            .into_iter()
+            .map(|x| x.name)
+            .collect(),
+        ValueKind::Host => get_hosts()
+            .await?
+            .objects
+            .into_iter()
+            .map(|x| x.fqdn)
+            .collect(),
+        ValueKind::Target => get_all_targets(None, target_queries::list::Filters::default())
+            .await?
+            .into_iter()
+            .map(|x| x.uuid)
+            .collect(),
+    };
+
+    Ok(xs)
+}
+
+/// Prints the values of `kind`. Completion runs on every tab,
+/// so failures print nothing instead of an error.
+pub async fn values_cli(kind: ValueKind, prefix: Option<String>) -> Result<(), ImlManagerCliError> {
+    let prefix = prefix.unwrap_or_default();
+
+    match values(kind).await {
+        Ok(xs) => {
+            for x in xs.into_iter().filter(|x| x.starts_with(&prefix)) {
+                println!("{}", x);
+            }
+        }
+        Err(e) => tracing::debug!("Could not fetch {} values: {}", kind, e),
+    }
+
+    Ok(())
+}
+
+/// Every command on the way to a command taking dynamic values, e.g. `filesystem pool`
+fn command_prefixes() -> BTreeSet<String> {
+    DYNAMIC_ARGS
+        .iter()
+        .flat_map(|x| {
+            let words: Vec<_> = x.command.split(' ').collect();
+
+            (1..=words.len()).map(move |n| words[..n].join(" "))
+        })
+        .collect()
+}
+
+/// A command line for each of `DYNAMIC_ARGS`, without the executable: the command followed by
+/// a value up to its position, and one more past it when it repeats.
+/// The command line has to parse for the entry to match the real command.
+pub fn dynamic_arg_lines() -> Vec<Vec<String>> {
+    DYNAMIC_ARGS
+        .iter()
+        .map(|x| {
+            let values = if x.repeats {
+                x.position + 2
+            } else {
+                x.position + 1
+            };
+
+            x.command
+                .split(' ')
+                .map(|x| x.to_string())
+                .chain(std::iter::repeat("1".to_string()).take(values))
+                .collect()
+        })
+        .collect()
+}
+
+/// A bash completion function that completes the arguments in `DYNAMIC_ARGS`
+/// with `<exe> completion values` and hands everything else to the function clap generated.
+/// Options are skipped when counting positions, along with the value of `--output`.
+pub fn dynamic_bash_script(exe: &str) -> String {
+    let commands = command_prefixes()
+        .into_iter()
+        .map(|x| format!("\"{}\"", x))
+        .collect::<Vec<_>>()
+        .join("|");
+
+    let cases = DYNAMIC_ARGS
+        .iter()
+        .map(|x| {
+            let pattern = if x.repeats {
+                format!("\"{}:\"*", x.command)
+            } else {
+                format!("\"{}:{}\"", x.command, x.position)
+            };
+
+            let guard = if x.repeats && x.position > 0 {
+                format!(" [[ $pos -ge {} ]] &&", x.position)
+            } else {
+                "".to_string()
+            };
+
+            format!(
+                "        {})\n           {} kind={} ;;",
+                pattern,
+                guard,
+                x.kind.to_string().to_lowercase()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"
+_{exe}_dynamic() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" cmd="" pos=0 skip=0 kind="" i w next
+
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        w="${{COMP_WORDS[i]}}"
+
+        if [[ $skip -eq 1 ]]; then
+            skip=0
+            continue
+        fi
+
+        case "$w" in
+            -d|--output|--display)
+                skip=1
+                ;;
+            -*)
+                ;;
+            *)
+                next="${{cmd:+$cmd }}$w"
+
+                if [[ $pos -eq 0 ]] && case "$next" in {commands}) true ;; *) false ;; esac; then
+                    cmd="$next"
+                else
+                    pos=$((pos + 1))
+                fi
+                ;;
+        esac
+    done
+
+    if [[ $cur != -* ]]; then
+        case "$cmd:$pos" in
+{cases}
+        esac
+    fi
+
+    if [[ -n $kind ]]; then
+        COMPREPLY=($({exe} completion values "$kind" "$cur" 2>/dev/null))
+        return 0
+    fi
+
+    _{exe} "$@"
+}}
+
+complete -F _{exe}_dynamic -o bashdefault -o default {exe}
+"#,
+        exe = exe,
+        commands = commands,
+        cases = cases,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_prefixes() {
+        let xs = command_prefixes();
+
+        assert!(xs.contains("filesystem"));
+        assert!(xs.contains("filesystem pool"));
+        assert!(xs.contains("filesystem pool show"));
+        assert!(!xs.contains("filesystem list"));
+    }
+
+    #[test]
+    fn test_dynamic_arg_lines() {
+        let xs = dynamic_arg_lines();
+
+        assert!(xs.contains(&vec!["filesystem".into(), "show".into(), "1".into()]));
+        assert!(xs.contains(&vec![
+            "server".into(),
+            "tag".into(),
+            "1".into(),
+            "1".into(),
+            "1".into()
+        ]));
+    }
+
+    #[test]
+    fn test_dynamic_bash_script() {
+        let x = dynamic_bash_script("iml");
+
+        assert!(x.contains(r#""filesystem show:0")"#));
+        assert!(x.contains(r#""server tag:"*)"#));
+        assert!(x.contains("[[ $pos -ge 1 ]] && kind=host"));
+        assert!(x.contains("complete -F _iml_dynamic -o bashdefault -o default iml"));
+    }
+}
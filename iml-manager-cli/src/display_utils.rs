@@ -2,21 +2,55 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+use crate::snapshot::SnapshotPolicy;
 use chrono_humanize::{Accuracy, HumanTime, Tense};
 use console::style;
 use futures::{Future, FutureExt};
 use iml_wire_types::{
     db::TargetRecord,
-    graphql::ServerProfile,
-    snapshot::{ReserveUnit, Snapshot, SnapshotInterval, SnapshotRetention},
+    graphql::{
+        CompatibilityReport, HostCertificate, HostHeartbeat, HostQueueEntry, HostQueueSummary,
+        HostRole, ServerProfile,
+    },
+    integrity::{IntegrityFindingKind, IntegrityReport},
+    lnet_ping::LnetPingPair,
+    multipath::TargetMultipathStatus,
+    snapshot::{
+        ReserveUnit, Snapshot, SnapshotHistoryEntry, SnapshotInterval, SnapshotIntervalRun,
+        SnapshotPolicySettings, SnapshotRetention,
+    },
+    stratagem::{FsHeatmap, ProjectUsageScan, PurgePolicy, PurgeRun},
+    target_remount::TargetRemountStatus,
     Command, Filesystem, Host, OstPool, StratagemConfiguration, StratagemReport,
 };
 use indicatif::ProgressBar;
 use number_formatter::{format_bytes, format_number};
 use prettytable::{Row, Table};
-use std::{fmt::Display, io, str::FromStr};
+use std::{
+    fmt::Display,
+    io,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
 use structopt::StructOpt;
 
+/// Whether status messages go to stderr, which is the case when stdout carries JSON or YAML.
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Sends status messages to stderr for `--output json|yaml`, so stdout only carries the output.
+/// With `--output table` they stay on stdout.
+pub fn set_output(display_type: DisplayType) {
+    STATUS_TO_STDERR.store(!display_type.is_tabular(), Ordering::Relaxed);
+}
+
+fn display_status(message: impl Display) {
+    if STATUS_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
 pub fn wrap_fut<T>(msg: &str, fut: impl Future<Output = T>) -> impl Future<Output = T> {
     let pb = ProgressBar::new_spinner();
     pb.enable_steady_tick(100);
@@ -36,7 +70,7 @@ pub fn format_cmd_state(cmd: &Command) -> String {
 }
 
 pub fn display_cmd_state(cmd: &Command) {
-    println!("{}", format_cmd_state(&cmd));
+    display_status(format_cmd_state(&cmd));
 }
 
 pub fn format_cancelled(message: impl Display) -> String {
@@ -44,7 +78,7 @@ pub fn format_cancelled(message: impl Display) -> String {
 }
 
 pub fn display_cancelled(message: impl Display) {
-    println!("{}", format_cancelled(&message));
+    display_status(format_cancelled(&message));
 }
 
 pub fn format_success(message: impl Display) -> String {
@@ -52,7 +86,7 @@ pub fn format_success(message: impl Display) -> String {
 }
 
 pub fn display_success(message: impl Display) {
-    println!("{}", format_success(message))
+    display_status(format_success(message))
 }
 
 pub fn format_error(message: impl Display) -> String {
@@ -60,7 +94,7 @@ pub fn format_error(message: impl Display) -> String {
 }
 
 pub fn display_error(message: impl Display) {
-    println!("{}", format_error(message))
+    display_status(format_error(message))
 }
 
 pub fn generate_table<Rows, R>(columns: &[&str], rows: Rows) -> Table
@@ -119,6 +153,8 @@ impl IntoTable for Vec<Snapshot> {
                 "Snapshot",
                 "Creation Time",
                 "State",
+                "Held",
+                "Origin",
                 "Comment",
             ],
             self.into_iter().map(|s| {
@@ -131,6 +167,11 @@ impl IntoTable for Vec<Snapshot> {
                         false => "unmounted",
                     }
                     .to_string(),
+                    if s.held { "yes" } else { "no" }.to_string(),
+                    match s.policy_run_id {
+                        Some(id) => format!("run {}", id),
+                        None => "manual".to_string(),
+                    },
                     s.comment.unwrap_or_else(|| "---".to_string()),
                 ]
             }),
@@ -138,49 +179,492 @@ impl IntoTable for Vec<Snapshot> {
     }
 }
 
+impl IntoTable for Vec<SnapshotHistoryEntry> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Filesystem",
+                "Snapshot",
+                "Creation Time",
+                "Origin",
+                "Destroyed",
+                "Destroyed By",
+                "Command",
+                "Comment",
+            ],
+            self.into_iter().map(|x| {
+                vec![
+                    x.filesystem_name,
+                    x.snapshot_name,
+                    x.create_time.to_rfc2822(),
+                    if x.taken_by_policy {
+                        "policy"
+                    } else {
+                        "manual"
+                    }
+                    .to_string(),
+                    x.destroyed_at.to_rfc2822(),
+                    match (x.destroyed_by, x.reason) {
+                        (_, Some(reason)) => format!("retention ({})", reason),
+                        (Some(user), None) => user,
+                        (None, None) => "---".to_string(),
+                    },
+                    x.command_id
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| "---".to_string()),
+                    x.comment.unwrap_or_else(|| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+fn format_interval(x: std::time::Duration) -> String {
+    chrono::Duration::from_std(x)
+        .map(HumanTime::from)
+        .map(|x| x.to_text_en(Accuracy::Precise, Tense::Present))
+        .unwrap_or_else(|_| "---".to_string())
+}
+
+fn format_reserve(value: i32, unit: ReserveUnit) -> String {
+    format!(
+        "{} {}",
+        value,
+        match unit {
+            ReserveUnit::Percent => "%",
+            ReserveUnit::Gibibytes => "GiB",
+            ReserveUnit::Tebibytes => "TiB",
+        }
+    )
+}
+
+/// The optional MDT reserve and change thresholds of a retention policy
+fn format_thresholds(x: &SnapshotRetention) -> String {
+    let xs: Vec<_> = vec![
+        x.mdt_reserve_percent.map(|x| format!("MDT {} %", x)),
+        x.change_percent.map(|x| format!("change {} %", x)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if xs.is_empty() {
+        "---".to_string()
+    } else {
+        xs.join(", ")
+    }
+}
+
 impl IntoTable for Vec<SnapshotInterval> {
     fn into_table(self) -> Table {
         generate_table(
-            &["Id", "Filesystem", "Interval", "Use Barrier", "Last Run"],
+            &[
+                "Id",
+                "Filesystem",
+                "Interval",
+                "Use Barrier",
+                "Automount",
+                "Start Time",
+                "Last Run",
+                "Next Run",
+            ],
             self.into_iter().map(|i| {
                 vec![
                     i.id.to_string(),
                     i.filesystem_name,
-                    chrono::Duration::from_std(i.interval.0)
-                        .map(HumanTime::from)
-                        .map(|x| x.to_text_en(Accuracy::Precise, Tense::Present))
-                        .unwrap_or_else(|_| "---".to_string()),
+                    format_interval(i.interval.0),
                     i.use_barrier.to_string(),
+                    i.automount.to_string(),
+                    i.start_time
+                        .map(|t| format!("{} {}", t, i.time_zone))
+                        .unwrap_or_else(|| "---".to_string()),
                     i.last_run
                         .map(|t| t.to_rfc2822())
                         .unwrap_or_else(|| "---".to_string()),
+                    i.next_run
+                        .map(|t| t.to_rfc2822())
+                        .unwrap_or_else(|| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<SnapshotIntervalRun> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Interval",
+                "Action",
+                "Snapshot",
+                "Due",
+                "Delay",
+                "State",
+                "Throttled",
+                "Command",
+            ],
+            self.into_iter().map(|x| {
+                let delay = x.started_at.unwrap_or(x.start_after) - x.due_at;
+
+                vec![
+                    x.interval_id.map(|x| x.to_string()).unwrap_or(x.reason),
+                    x.action,
+                    x.snapshot_name,
+                    x.due_at.to_rfc2822(),
+                    delay
+                        .to_std()
+                        .map(format_interval)
+                        .unwrap_or_else(|_| "---".to_string()),
+                    match x.error {
+                        Some(e) => format!("{}: {}", x.state, e),
+                        None => x.state,
+                    },
+                    if x.throttled { "yes" } else { "no" }.to_string(),
+                    x.command_id
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for SnapshotPolicySettings {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Max Concurrent", "Jitter"],
+            std::iter::once(vec![
+                if self.max_concurrent == 0 {
+                    "unlimited".to_string()
+                } else {
+                    self.max_concurrent.to_string()
+                },
+                format_interval(self.jitter.0),
+            ]),
+        )
+    }
+}
+
+impl IsEmpty for SnapshotPolicySettings {
+    fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl IntoTable for Vec<HostQueueEntry> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Position", "Job", "State", "Priority", "Class", "Command", "Created",
+            ],
+            self.into_iter().map(|x| {
+                vec![
+                    x.position.to_string(),
+                    x.job_id.to_string(),
+                    x.state,
+                    x.priority.to_string(),
+                    x.class_name,
+                    format!("{} ({})", x.command_message, x.command_id),
+                    x.created_at.to_rfc2822(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<HostQueueSummary> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Server", "Running", "High", "Normal", "Low"],
+            self.into_iter().map(|x| {
+                vec![
+                    x.fqdn,
+                    x.running.unwrap_or_else(|| "---".to_string()),
+                    x.high.to_string(),
+                    x.normal.to_string(),
+                    x.low.to_string(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<LnetPingPair> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "From",
+                "To",
+                "NID",
+                "Loss",
+                "Avg Latency",
+                "Max Latency",
+                "Error",
+            ],
+            self.into_iter().map(|x| {
+                let ms = |x: Option<f64>| {
+                    x.map(|x| format!("{:.2} ms", x))
+                        .unwrap_or_else(|| "---".to_string())
+                };
+
+                vec![
+                    x.source_fqdn,
+                    x.target_fqdn,
+                    x.target_nid,
+                    format!("{:.0}%", x.loss_percent),
+                    ms(x.avg_latency_ms),
+                    ms(x.max_latency_ms),
+                    x.error.unwrap_or_else(|| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<HostCertificate> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Server", "Serial", "Expires", "Days Remaining"],
+            self.into_iter().map(|x| {
+                vec![
+                    x.fqdn,
+                    x.serial,
+                    x.not_after
+                        .map(|t| t.to_rfc2822())
+                        .unwrap_or_else(|| "---".to_string()),
+                    x.days_remaining
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<HostHeartbeat> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Server",
+                "Last Seen",
+                "Seconds Since",
+                "Booted",
+                "Needs Attention",
+            ],
+            self.into_iter().map(|x| {
+                vec![
+                    x.fqdn,
+                    x.last_seen
+                        .map(|t| t.to_rfc2822())
+                        .unwrap_or_else(|| "---".to_string()),
+                    x.seconds_since_contact
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| "---".to_string()),
+                    x.boot_time
+                        .map(|t| t.to_rfc2822())
+                        .unwrap_or_else(|| "---".to_string()),
+                    if x.needs_attention { "yes" } else { "no" }.to_string(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for TargetMultipathStatus {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Server", "Map", "Paths", "Failed Paths", "Checked"],
+            self.hosts.into_iter().map(|x| {
+                vec![
+                    x.fqdn,
+                    x.map_name,
+                    x.path_count.to_string(),
+                    if x.failed_paths.is_empty() {
+                        "---".to_string()
+                    } else {
+                        x.failed_paths.join(", ")
+                    },
+                    x.checked_at.to_rfc2822(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IsEmpty for TargetMultipathStatus {
+    fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+}
+
+impl IntoTable for TargetRemountStatus {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Target", "Attempt", "State", "Command", "Started"],
+            self.attempts.into_iter().map(|x| {
+                vec![
+                    x.target_name,
+                    x.attempt.to_string(),
+                    match x.error {
+                        Some(e) => format!("{}: {}", x.state, e),
+                        None => x.state,
+                    },
+                    x.command_id
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| "---".to_string()),
+                    x.started_at.to_rfc2822(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IsEmpty for TargetRemountStatus {
+    fn is_empty(&self) -> bool {
+        self.policy.is_none() && self.attempts.is_empty()
+    }
+}
+
+impl IntoTable for IntegrityReport {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Kind", "Targets", "UUID", "Finding"],
+            self.findings.into_iter().map(|x| {
+                let kind = match x.kind {
+                    IntegrityFindingKind::DuplicateUuid => "duplicate uuid",
+                    IntegrityFindingKind::DevPathChanged => "device path changed",
+                    IntegrityFindingKind::MissingHost => "missing host",
+                };
+
+                vec![
+                    kind.to_string(),
+                    x.target_names.join(", "),
+                    x.uuid,
+                    x.message,
                 ]
             }),
         )
     }
 }
 
+impl IsEmpty for IntegrityReport {
+    fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+impl IsEmpty for CompatibilityReport {
+    fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+}
+
+impl IntoTable for CompatibilityReport {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Host", "Role", "Lustre", "Agent"],
+            self.hosts.into_iter().map(|x| {
+                let role = match x.role {
+                    HostRole::Server => "server",
+                    HostRole::Client => "client",
+                };
+
+                let (lustre, agent) = match x.error {
+                    Some(e) => (e, "---".to_string()),
+                    None => (
+                        x.lustre.unwrap_or_else(|| "---".to_string()),
+                        x.agent.unwrap_or_else(|| "---".to_string()),
+                    ),
+                };
+
+                vec![x.fqdn, role.to_string(), lustre, agent]
+            }),
+        )
+    }
+}
+
 impl IntoTable for Vec<SnapshotRetention> {
     fn into_table(self) -> Table {
         generate_table(
-            &["Id", "Filesystem", "Reserve", "Keep", "Last Run"],
+            &[
+                "Id",
+                "Filesystem",
+                "Reserve",
+                "Keep",
+                "Thresholds",
+                "Last Run",
+                "Version",
+            ],
             self.into_iter().map(|r| {
+                let thresholds = format_thresholds(&r);
+
                 vec![
                     r.id.to_string(),
                     r.filesystem_name,
-                    format!(
-                        "{} {}",
-                        r.reserve_value,
-                        match r.reserve_unit {
-                            ReserveUnit::Percent => "%",
-                            ReserveUnit::Gibibytes => "GiB",
-                            ReserveUnit::Tebibytes => "TiB",
-                        }
-                    ),
+                    format_reserve(r.reserve_value, r.reserve_unit),
                     r.keep_num.to_string(),
+                    thresholds,
                     r.last_run
                         .map(|t| t.to_rfc2822())
                         .unwrap_or_else(|| "---".to_string()),
+                    r.version.to_string(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<SnapshotPolicy> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Filesystem",
+                "Intervals",
+                "Use Barrier",
+                "Automount",
+                "Reserve",
+                "Keep",
+            ],
+            self.into_iter().map(|p| {
+                let none = || "---".to_string();
+
+                vec![
+                    p.filesystem_name,
+                    if p.intervals.is_empty() {
+                        none()
+                    } else {
+                        p.intervals
+                            .iter()
+                            .map(|i| match &i.start_time {
+                                Some(t) => format!(
+                                    "{} from {} {}",
+                                    format_interval(i.interval.0),
+                                    t,
+                                    i.time_zone
+                                ),
+                                None => format_interval(i.interval.0),
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    },
+                    if p.intervals.is_empty() {
+                        none()
+                    } else {
+                        p.intervals.iter().any(|i| i.use_barrier).to_string()
+                    },
+                    if p.intervals.is_empty() {
+                        none()
+                    } else {
+                        p.intervals.iter().any(|i| i.automount).to_string()
+                    },
+                    p.retention
+                        .as_ref()
+                        .map(|r| format_reserve(r.reserve_value, r.reserve_unit))
+                        .unwrap_or_else(none),
+                    p.retention
+                        .as_ref()
+                        .map(|r| r.keep_num.to_string())
+                        .unwrap_or_else(none),
                 ]
             }),
         )
@@ -197,6 +681,145 @@ impl IntoTable for Vec<StratagemReport> {
     }
 }
 
+impl IntoTable for Vec<ProjectUsageScan> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Scanned", "Project", "Files", "Size"],
+            self.into_iter().flat_map(|s| {
+                let scanned_at = s.scanned_at.to_rfc2822();
+
+                s.projects.into_iter().map(move |p| {
+                    vec![
+                        scanned_at.clone(),
+                        p.project,
+                        format_number(p.files, Some(0)),
+                        format_bytes(p.bytes, Some(1)),
+                    ]
+                })
+            }),
+        )
+    }
+}
+
+impl IsEmpty for FsHeatmap {
+    fn is_empty(&self) -> bool {
+        self.directories.is_empty()
+    }
+}
+
+impl IntoTable for FsHeatmap {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Directory",
+                "Files",
+                "Size",
+                "Accessed 7d",
+                "7-30d",
+                "30-90d",
+                "90d-1y",
+                "Over 1y",
+            ],
+            self.directories.into_iter().map(|x| {
+                vec![
+                    x.path,
+                    format_number(x.files, Some(0)),
+                    format_bytes(x.bytes, Some(1)),
+                    format_bytes(x.bytes_week, Some(1)),
+                    format_bytes(x.bytes_month, Some(1)),
+                    format_bytes(x.bytes_quarter, Some(1)),
+                    format_bytes(x.bytes_year, Some(1)),
+                    format_bytes(x.bytes_older, Some(1)),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for PurgePolicy {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Filesystem",
+                "Enabled",
+                "Interval",
+                "Purge After",
+                "Max Files",
+                "Max Size",
+                "Protected",
+                "Approval",
+                "Last Run",
+            ],
+            std::iter::once(vec![
+                self.filesystem_name,
+                self.enabled.to_string(),
+                format_interval(self.interval.0),
+                format_interval(self.purge_after.0),
+                format_number(self.max_files, Some(0)),
+                format_bytes(self.max_bytes, Some(1)),
+                if self.protected_paths.is_empty() {
+                    "---".to_string()
+                } else {
+                    self.protected_paths.join(", ")
+                },
+                if self.require_approval {
+                    "required"
+                } else {
+                    "no"
+                }
+                .to_string(),
+                self.last_run
+                    .map(|t| t.to_rfc2822())
+                    .unwrap_or_else(|| "---".to_string()),
+            ]),
+        )
+    }
+}
+
+impl IsEmpty for PurgePolicy {
+    fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl IntoTable for Vec<PurgeRun> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Id",
+                "Started",
+                "State",
+                "Candidates",
+                "Protected",
+                "Files",
+                "Size",
+                "Capped",
+                "Decided By",
+            ],
+            self.into_iter().map(|x| {
+                vec![
+                    x.id.to_string(),
+                    x.started_at.to_rfc2822(),
+                    match x.error {
+                        Some(e) => format!("{}: {}", x.state, e),
+                        None => x.state,
+                    },
+                    format!(
+                        "{} ({})",
+                        format_number(x.candidate_files, Some(0)),
+                        format_bytes(x.candidate_bytes, Some(1))
+                    ),
+                    format_number(x.protected_files, Some(0)),
+                    format_number(x.files, Some(0)),
+                    format_bytes(x.bytes, Some(1)),
+                    if x.capped { "yes" } else { "no" }.to_string(),
+                    x.decided_by.unwrap_or_else(|| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
 impl IntoTable for Vec<Host> {
     fn into_table(self) -> Table {
         generate_table(
@@ -269,10 +892,10 @@ impl IntoTable for Vec<Filesystem> {
 impl IntoTable for Vec<ServerProfile> {
     fn into_table(self) -> Table {
         generate_table(
-            &["Profile", "Name", "Description"],
+            &["Profile", "Name", "Description", "Version"],
             self.into_iter()
                 .filter(|x| x.user_selectable)
-                .map(|x| vec![x.name, x.ui_name, x.ui_description]),
+                .map(|x| vec![x.name, x.ui_name, x.ui_description, x.version.to_string()]),
         )
     }
 }
@@ -319,6 +942,26 @@ impl IsEmpty for (Vec<Host>, Vec<TargetRecord>) {
     }
 }
 
+/// Serializes `x` as JSON or YAML.
+/// `None` for `DisplayType::Tabular`.
+pub fn format_output(x: &impl serde::Serialize, display_type: DisplayType) -> Option<String> {
+    match display_type {
+        DisplayType::Json => {
+            Some(serde_json::to_string_pretty(x).expect("Cannot serialize item to JSON"))
+        }
+        DisplayType::Yaml => Some(serde_yaml::to_string(x).expect("Cannot serialize item to YAML")),
+        DisplayType::Tabular => None,
+    }
+}
+
+/// Prints `x` for `--output json|yaml`.
+/// Commands that change something print what they changed this way, and a message for `table`.
+pub fn display_output(x: &impl serde::Serialize, display_type: DisplayType) {
+    if let Some(x) = format_output(x, display_type) {
+        println!("{}", x);
+    }
+}
+
 pub trait IntoDisplayType {
     fn into_display_type(self, display_type: DisplayType) -> String;
 }
@@ -328,31 +971,28 @@ where
     T: IsEmpty + IntoTable + serde::Serialize,
 {
     fn into_display_type(self, display_type: DisplayType) -> String {
-        match display_type {
-            DisplayType::Json => {
-                serde_json::to_string_pretty(&self).expect("Cannot serialize item to JSON")
-            }
-            DisplayType::Yaml => {
-                serde_yaml::to_string(&self).expect("Cannot serialize item to YAML")
-            }
-            DisplayType::Tabular => {
-                if self.is_empty() {
-                    "".to_string()
-                } else {
-                    self.into_table().to_string()
-                }
-            }
+        match format_output(&self, display_type) {
+            Some(x) => x,
+            None if self.is_empty() => "".to_string(),
+            None => self.into_table().to_string(),
         }
     }
 }
 
-#[derive(StructOpt, Debug)]
+/// The format of the output of a command, given with `--output`
+#[derive(StructOpt, Debug, Clone, Copy, PartialEq)]
 pub enum DisplayType {
     Json,
     Yaml,
     Tabular,
 }
 
+impl DisplayType {
+    pub fn is_tabular(self) -> bool {
+        self == Self::Tabular
+    }
+}
+
 impl Default for DisplayType {
     fn default() -> Self {
         Self::Tabular
@@ -366,11 +1006,30 @@ impl FromStr for DisplayType {
         match s {
             "json" => Ok(Self::Json),
             "yaml" => Ok(Self::Yaml),
-            "tabular" => Ok(Self::Tabular),
+            "table" | "tabular" => Ok(Self::Tabular),
             _ => Err(Self::Err::new(
                 io::ErrorKind::InvalidInput,
-                "Couldn't parse display type.",
+                "Couldn't parse display type, expected table, json or yaml.",
             )),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_output_keeps_keys() {
+        let x = serde_json::json!({
+            "resource_uri": "/api/host/1/",
+            "tuning": { "max_dirty_mb": 512 },
+        });
+
+        assert_eq!(
+            format_output(&x, DisplayType::Json).unwrap(),
+            serde_json::to_string_pretty(&x).unwrap()
+        );
+        assert_eq!(format_output(&x, DisplayType::Tabular), None);
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::graphql,
+    display_utils::{display_output, display_success, generate_table, wrap_fut, DisplayType},
+    error::ImlManagerCliError,
+};
+use dialoguer::Password;
+use iml_graphql_queries::{federation as federation_queries, Response};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum FederationCommand {
+    /// Show the filesystems, health and alerts of this manager and every peer
+    #[structopt(name = "overview")]
+    Overview,
+    /// List the peer managers
+    #[structopt(name = "list")]
+    List,
+    /// Add a peer manager, or update an existing one.
+    /// Prompts for the API key of a user of the peer unless --api-key-file is given
+    #[structopt(name = "add")]
+    Add {
+        /// The name the peer's site is shown under
+        name: String,
+        /// The address of the peer manager, e.g. https://site-b.example.com
+        url: String,
+        /// Read the API key from this file
+        #[structopt(long = "api-key-file")]
+        api_key_file: Option<String>,
+        /// Don't verify the certificate of the peer
+        #[structopt(long = "insecure")]
+        insecure: bool,
+    },
+    /// Remove a peer manager
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the peer
+        name: String,
+    },
+}
+
+pub async fn federation_cli(
+    command: FederationCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    match command {
+        FederationCommand::Overview => {
+            let query = federation_queries::overview::build();
+
+            let resp: Response<federation_queries::overview::Resp> =
+                wrap_fut("Querying sites...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.federated_overview;
+
+            if !output.is_tabular() {
+                display_output(&x, output);
+
+                return Ok(());
+            }
+
+            let table = generate_table(
+                &[
+                    "Site",
+                    "Health",
+                    "Filesystems",
+                    "Critical",
+                    "Error",
+                    "Warning",
+                    "Details",
+                ],
+                x.sites.into_iter().map(|x| match x.overview {
+                    Some(o) => vec![
+                        x.name,
+                        format!("{:?}", o.health.severity),
+                        o.filesystems.len().to_string(),
+                        o.alerts.critical.to_string(),
+                        o.alerts.error.to_string(),
+                        o.alerts.warning.to_string(),
+                        x.url.unwrap_or_else(|| "This manager".to_string()),
+                    ],
+                    None => vec![
+                        x.name,
+                        "Unreachable".to_string(),
+                        "---".to_string(),
+                        "---".to_string(),
+                        "---".to_string(),
+                        "---".to_string(),
+                        x.error.unwrap_or_default(),
+                    ],
+                }),
+            );
+
+            table.printstd();
+        }
+        FederationCommand::List => {
+            let query = federation_queries::peers::build();
+
+            let resp: Response<federation_queries::peers::Resp> =
+                wrap_fut("Fetching peers...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.federation.peers;
+
+            if !output.is_tabular() {
+                display_output(&xs, output);
+
+                return Ok(());
+            }
+
+            let table = generate_table(
+                &["Name", "Url", "Verify TLS", "Added"],
+                xs.into_iter().map(|x| {
+                    vec![
+                        x.name,
+                        x.url,
+                        x.verify_tls.to_string(),
+                        x.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    ]
+                }),
+            );
+
+            table.printstd();
+        }
+        FederationCommand::Add {
+            name,
+            url,
+            api_key_file,
+            insecure,
+        } => {
+            let api_key = match api_key_file {
+                Some(path) => tokio::fs::read_to_string(path).await?,
+                None => Password::new()
+                    .with_prompt(format!("API key for {}", name))
+                    .interact()?,
+            };
+
+            let query =
+                federation_queries::add_peer::build(&name, &url, api_key.trim(), Some(!insecure));
+
+            let resp: Response<federation_queries::add_peer::Resp> =
+                wrap_fut("Adding peer...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.federation.add_peer;
+
+            display_success(format!("Added {} at {}", x.name, x.url));
+        }
+        FederationCommand::Remove { name } => {
+            let query = federation_queries::remove_peer::build(&name);
+
+            let resp: Response<federation_queries::remove_peer::Resp> =
+                wrap_fut("Removing peer...", graphql(query)).await?;
+
+            if Result::from(resp)?.data.federation.remove_peer {
+                display_success(format!("Removed {}", name));
+            } else {
+                return Err(ImlManagerCliError::DoesNotExist("peer"));
+            }
+        }
+    };
+
+    Ok(())
+}
@@ -3,15 +3,30 @@
 // license that can be found in the LICENSE file.
 
 use crate::{
-    api_utils::{get_all, get_hosts, get_influx, get_one, graphql, put, wait_for_cmds_success},
-    display_utils::{usage, wrap_fut, DisplayType, IntoDisplayType as _},
+    api_utils::{
+        get_all, get_hosts, get_influx, get_one, graphql, put, wait_for_cmd_display,
+        wait_for_cmds_success,
+    },
+    changelog::{changelog_cli, ChangelogCommand},
+    display_utils::{
+        display_cancelled, display_error, display_output, display_success, generate_table, usage,
+        wrap_fut, DisplayType, IntoDisplayType as _,
+    },
     error::ImlManagerCliError,
     ostpool::{ostpool_cli, OstPoolCommand},
+    parse_hosts,
+    server::filter_known_hosts,
+    target::get_all_targets,
 };
 use console::Term;
+use dialoguer::Confirm;
 use futures::future::{try_join, try_join5};
-use iml_graphql_queries::{client_mount, filesystem as fs_queries, target as target_queries};
-use iml_wire_types::{db::TargetKind, CmdWrapper, Filesystem};
+use iml_graphql_queries::{client_mount, filesystem as fs_queries, target::list::Filters};
+use iml_wire_types::{
+    db::TargetKind,
+    graphql::{ClientMountConfigKind, DetectedFilesystem, DetectedTarget, TargetAction},
+    CmdWrapper, Filesystem,
+};
 use number_formatter::{format_bytes, format_number};
 use prettytable::{Row, Table};
 use structopt::StructOpt;
@@ -25,11 +40,7 @@ struct StateChange {
 pub enum FilesystemCommand {
     /// List all configured filesystems
     #[structopt(name = "list")]
-    List {
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
-    },
+    List,
     /// Show filesystem
     #[structopt(name = "show")]
     Show {
@@ -42,9 +53,23 @@ pub enum FilesystemCommand {
         #[structopt(subcommand)]
         command: OstPoolCommand,
     },
-    /// Detect existing filesystem
+    /// Changelog users
+    #[structopt(name = "changelog")]
+    Changelog {
+        #[structopt(subcommand)]
+        command: ChangelogCommand,
+    },
+    /// Detect existing filesystems on servers and import them
+    /// after showing what would be registered
     #[structopt(name = "detect")]
-    Detect,
+    Detect {
+        /// Hostlist expressions of the servers to scan, e. g. oss[1-4].local.
+        /// Defaults to all servers
+        hosts: Vec<String>,
+        /// Import without asking for confirmation
+        #[structopt(short = "y", long = "yes")]
+        yes: bool,
+    },
     /// Forget existing filesystem
     /// This will remove knowledge of the filesystem
     /// from the manager, but will not effect it on storage servers
@@ -53,34 +78,343 @@ pub enum FilesystemCommand {
         #[structopt(name = "FSNAME")]
         fs_name: String,
     },
+    /// Check the Lustre and agent versions of the servers and clients
+    /// against the combinations this manager supports. Run before upgrading
+    #[structopt(name = "compatibility")]
+    Compatibility {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+    },
+    /// Start a filesystem: the MGS, then the MDTs, then the OSTs
+    #[structopt(name = "start")]
+    Start {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+    },
+    /// Stop a filesystem: the OSTs, then the MDTs, then the MGS.
+    /// An MGS other running filesystems use is left started
+    #[structopt(name = "stop")]
+    Stop {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+    },
+    /// Show or change how targets that go offline outside of any command are remounted,
+    /// along with the latest attempts
+    #[structopt(name = "remount-policy")]
+    RemountPolicy {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// Remount targets of the filesystem
+        #[structopt(long = "enable", conflicts_with_all = &["disable", "remove"])]
+        enable: bool,
+        /// Stop remounting targets, keeping the other settings
+        #[structopt(long = "disable", conflicts_with = "remove")]
+        disable: bool,
+        /// Stop remounting targets and forget the settings
+        #[structopt(long = "remove")]
+        remove: bool,
+        /// How many times a target is remounted before giving up
+        #[structopt(long = "max-attempts")]
+        max_attempts: Option<i32>,
+        /// How long to wait after a target went offline, and between attempts, e.g. 5m
+        #[structopt(long = "cooldown")]
+        cooldown: Option<String>,
+    },
     /// Client mount command
     #[structopt(name = "list-client-mount")]
     ClientMount {
         #[structopt(name = "fsname")]
         fsname: String,
     },
+    /// Print a file that sets up a client to mount the filesystem:
+    /// a systemd mount unit, an /etc/fstab line or an /etc/lnet.conf,
+    /// e.g. `iml filesystem client-mount-config fs systemd > /etc/systemd/system/mnt-fs.mount`
+    #[structopt(name = "client-mount-config")]
+    ClientMountConfig {
+        #[structopt(name = "fsname")]
+        fsname: String,
+        #[structopt(possible_values = &["systemd", "fstab", "lnet"])]
+        kind: ClientMountConfigKind,
+        /// The LNet network the client mounts over, e.g. o2ib0.
+        /// Defaults to the network of the first MGS NID
+        #[structopt(long = "network")]
+        network: Option<String>,
+        /// The interface of the client on the network, e.g. ib0
+        #[structopt(long = "interface")]
+        interface: Option<String>,
+        /// Where the client mounts the filesystem. Defaults to /mnt/<fsname>
+        #[structopt(long = "mountpoint")]
+        mountpoint: Option<String>,
+    },
 }
 
 fn option_sub(a: Option<u64>, b: Option<u64>) -> Option<u64> {
     Some(a?.saturating_sub(b?))
 }
 
-async fn detect_filesystem() -> Result<(), ImlManagerCliError> {
-    let query = fs_queries::detect::build();
+async fn detect_filesystem(
+    hosts: Vec<String>,
+    yes: bool,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    let api_hosts = wrap_fut("Fetching hosts...", get_hosts()).await?;
+
+    let host_ids: Vec<_> = if hosts.is_empty() {
+        api_hosts.objects.iter().map(|x| x.id).collect()
+    } else {
+        let (known, unknown_names) = filter_known_hosts(parse_hosts(&hosts)?, &api_hosts.objects);
+
+        if !unknown_names.is_empty() {
+            return Err(ImlManagerCliError::ApiError(format!(
+                "Unknown hosts: {}",
+                unknown_names.into_iter().collect::<Vec<_>>().join(", ")
+            )));
+        }
+
+        known.into_iter().map(|x| x.id).collect()
+    };
+
+    let query = fs_queries::detect_filesystems::build(host_ids);
+
+    let resp: iml_graphql_queries::Response<fs_queries::detect_filesystems::Resp> =
+        wrap_fut("Scanning servers...", graphql(query)).await?;
+
+    let scan = Result::from(resp)?.data.filesystem.detect_filesystems;
+
+    display_output(&scan.filesystems, output);
+
+    let term = Term::stderr();
+
+    if scan.filesystems.is_empty() {
+        term.write_line("No filesystems found").unwrap();
+
+        return Ok(());
+    }
+
+    let rows = scan.filesystems.iter().flat_map(|fs| {
+        fs.targets.iter().map(move |x| {
+            vec![
+                fs.name.to_string(),
+                x.name.to_string(),
+                x.state.to_string(),
+                x.hosts.join(", "),
+                detect_action(fs, x).to_string(),
+            ]
+        })
+    });
+
+    if output.is_tabular() {
+        let table = generate_table(&["Filesystem", "Target", "State", "Hosts", "Action"], rows);
+
+        table.printstd();
+    }
 
-    let resp: iml_graphql_queries::Response<fs_queries::detect::Resp> =
-        wrap_fut("Detecting Filesystem...", graphql(query)).await?;
+    for fs in scan.filesystems.iter().filter(|x| !x.complete) {
+        display_cancelled(format!(
+            "{} is missing a mounted MGS, MDT or OST; only its MGS will be imported.",
+            fs.name
+        ));
+    }
+
+    if !scan
+        .filesystems
+        .iter()
+        .flat_map(|x| &x.targets)
+        .any(|x| x.import)
+    {
+        term.write_line("Nothing to import").unwrap();
+
+        return Ok(());
+    }
+
+    let confirmed = yes
+        || Confirm::new()
+            .with_prompt("Import these filesystems?")
+            .default(false)
+            .show_default(true)
+            .interact()
+            .unwrap_or(false);
+
+    if !confirmed {
+        display_cancelled("Nothing was imported.");
+
+        return Ok(());
+    }
+
+    let query = fs_queries::import_detected::build(scan.id);
+
+    let resp: iml_graphql_queries::Response<fs_queries::import_detected::Resp> =
+        wrap_fut("Importing filesystems...", graphql(query)).await?;
 
     let _ = Result::from(resp)?;
 
-    let term = Term::stdout();
+    display_success("Imported detected filesystems");
 
-    term.write_line("Detected Filesystems").unwrap();
+    Ok(())
+}
+
+fn detect_action(fs: &DetectedFilesystem, x: &DetectedTarget) -> &'static str {
+    if !x.import {
+        "skip"
+    } else if x.registered && fs.registered {
+        "update"
+    } else {
+        "import"
+    }
+}
+
+async fn remount_policy(
+    fsname: String,
+    display_type: DisplayType,
+    enabled: Option<bool>,
+    remove: bool,
+    max_attempts: Option<i32>,
+    cooldown: Option<String>,
+) -> Result<(), ImlManagerCliError> {
+    if remove {
+        let query = fs_queries::remove_remount_policy::build(&fsname);
+
+        let resp: iml_graphql_queries::Response<fs_queries::remove_remount_policy::Resp> =
+            wrap_fut("Removing remount policy...", graphql(query)).await?;
+
+        Result::from(resp)?;
+    } else if enabled.is_some() || max_attempts.is_some() || cooldown.is_some() {
+        let query = fs_queries::set_remount_policy::build(&fsname, enabled, max_attempts, cooldown);
+
+        let resp: iml_graphql_queries::Response<fs_queries::set_remount_policy::Resp> =
+            wrap_fut("Setting remount policy...", graphql(query)).await?;
+
+        Result::from(resp)?;
+    }
+
+    let query = fs_queries::remount_status::build(&fsname, None);
+
+    let resp: iml_graphql_queries::Response<fs_queries::remount_status::Resp> =
+        wrap_fut("Fetching remount policy...", graphql(query)).await?;
+
+    let status = Result::from(resp)?.data.target_remount;
+
+    if display_type.is_tabular() {
+        match &status.policy {
+            Some(x) => Term::stdout()
+                .write_line(&format!(
+                    "Remounting {}: {}, up to {} attempts, {} apart",
+                    fsname,
+                    if x.enabled { "enabled" } else { "disabled" },
+                    x.max_attempts,
+                    x.cooldown
+                ))
+                .unwrap(),
+            None => Term::stdout()
+                .write_line(&format!("Targets of {} are not remounted", fsname))
+                .unwrap(),
+        }
+    }
+
+    Term::stdout()
+        .write_line(&status.into_display_type(display_type))
+        .unwrap();
 
     Ok(())
 }
 
-async fn forget_filesystem(fsname: String) -> Result<(), ImlManagerCliError> {
+async fn compatibility_report(
+    fsname: String,
+    display_type: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    let query = fs_queries::compatibility_report::build(fsname);
+
+    let resp: iml_graphql_queries::Response<fs_queries::compatibility_report::Resp> =
+        wrap_fut("Checking versions...", graphql(query)).await?;
+
+    let report = Result::from(resp)?.data.compatibility_report;
+
+    if !display_type.is_tabular() {
+        Term::stdout()
+            .write_line(&report.into_display_type(display_type))
+            .unwrap();
+
+        return Ok(());
+    }
+
+    let issues = report.issues.clone();
+    let manager_version = report.manager_version.clone();
+
+    Term::stdout()
+        .write_line(&report.into_display_type(display_type))
+        .unwrap();
+
+    if issues.is_empty() {
+        display_success(format!(
+            "All versions are supported with IML {}",
+            manager_version
+        ));
+    }
+
+    for x in issues {
+        display_error(x);
+    }
+
+    Ok(())
+}
+
+async fn transition_filesystem(
+    fsname: String,
+    start: bool,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    let x = if start {
+        let resp: iml_graphql_queries::Response<fs_queries::start::Resp> = wrap_fut(
+            "Starting filesystem...",
+            graphql(fs_queries::start::build(&fsname)),
+        )
+        .await?;
+
+        Result::from(resp)?.data.filesystem.start
+    } else {
+        let resp: iml_graphql_queries::Response<fs_queries::stop::Resp> = wrap_fut(
+            "Stopping filesystem...",
+            graphql(fs_queries::stop::build(&fsname)),
+        )
+        .await?;
+
+        Result::from(resp)?.data.filesystem.stop
+    };
+
+    for t in &x.targets {
+        if let Some(reason) = &t.reason {
+            display_cancelled(format!("Skipping {}: {}", t.name, reason));
+        }
+    }
+
+    let cmd = wait_for_cmd_display(x.command).await?;
+
+    let resp: iml_graphql_queries::Response<fs_queries::target_transitions::Resp> =
+        graphql(fs_queries::target_transitions::build(cmd.id)).await?;
+
+    let transitions = Result::from(resp)?.data.target_transitions;
+
+    display_output(&transitions, output);
+
+    for t in transitions {
+        let (action, done) = match t.action {
+            TargetAction::Start => ("start", "Started"),
+            _ => ("stop", "Stopped"),
+        };
+
+        if t.errored {
+            display_error(format!("Could not {} {}", action, t.name));
+        } else if t.cancelled {
+            display_cancelled(format!("Did not {} {}", action, t.name));
+        } else if t.job_state.as_deref() == Some("complete") {
+            display_success(format!("{} {}", done, t.name));
+        }
+    }
+
+    Ok(())
+}
+
+async fn forget_filesystem(fsname: String, output: DisplayType) -> Result<(), ImlManagerCliError> {
     let fs = wrap_fut(
         "Fetching Filesystem...",
         get_one::<Filesystem>(vec![("name", &fsname)]),
@@ -98,14 +432,19 @@ async fn forget_filesystem(fsname: String) -> Result<(), ImlManagerCliError> {
 
     let CmdWrapper { command } = r.json().await?;
 
-    wait_for_cmds_success(&[command]).await?;
+    let cmds = wait_for_cmds_success(&[command]).await?;
+
+    display_output(&cmds, output);
 
     Ok(())
 }
 
-pub async fn filesystem_cli(command: FilesystemCommand) -> Result<(), ImlManagerCliError> {
+pub async fn filesystem_cli(
+    command: FilesystemCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
     match command {
-        FilesystemCommand::List { display_type } => {
+        FilesystemCommand::List => {
             let fut_fs = get_all::<Filesystem>();
             let query = iml_influx::filesystems::query();
             let fut_st =
@@ -132,10 +471,29 @@ pub async fn filesystem_cli(command: FilesystemCommand) -> Result<(), ImlManager
 
             tracing::debug!("Filesystems: {:?}", filesystems);
 
-            let x = filesystems.objects.into_display_type(display_type);
+            let x = filesystems.objects.into_display_type(output);
 
             term.write_line(&x).unwrap();
         }
+        FilesystemCommand::Show { fsname } if !output.is_tabular() => {
+            let fut_fs = get_one::<Filesystem>(vec![("name", &fsname)]);
+
+            let query = iml_influx::filesystem::query(&fsname);
+            let fut_st =
+                get_influx::<iml_influx::filesystem::InfluxResponse>("iml_stats", query.as_str());
+
+            let (mut fs, influx_resp) =
+                wrap_fut("Fetching filesystem...", try_join(fut_fs, fut_st)).await?;
+            let st = iml_influx::filesystem::Response::from(influx_resp);
+
+            fs.bytes_free = st.bytes_free.map(|x| x as f64);
+            fs.bytes_total = st.bytes_total.map(|x| x as f64);
+            fs.files_free = st.files_free;
+            fs.files_total = st.files_total;
+            fs.client_count = st.clients;
+
+            display_output(&fs, output);
+        }
         FilesystemCommand::Show { fsname } => {
             let fut_fs = get_one::<Filesystem>(vec![("name", &fsname)]);
 
@@ -143,21 +501,9 @@ pub async fn filesystem_cli(command: FilesystemCommand) -> Result<(), ImlManager
             let fut_st =
                 get_influx::<iml_influx::filesystem::InfluxResponse>("iml_stats", query.as_str());
 
-            let targets = graphql(target_queries::list::build(
-                None,
-                None,
-                None,
-                Some(&fsname),
-                None,
-            ));
-
-            let (fs, influx_resp, hosts, targets_resp, client_mount_cmd): (
-                _,
-                _,
-                _,
-                iml_graphql_queries::Response<target_queries::list::Resp>,
-                _,
-            ) = wrap_fut(
+            let targets = get_all_targets(Some(fsname.clone()), Filters::default());
+
+            let (fs, influx_resp, hosts, targets, client_mount_cmd) = wrap_fut(
                 "Fetching filesystem...",
                 try_join5(
                     fut_fs,
@@ -172,9 +518,7 @@ pub async fn filesystem_cli(command: FilesystemCommand) -> Result<(), ImlManager
 
             tracing::debug!("FS: {:?}", fs);
             tracing::debug!("ST: {:?}", st);
-            tracing::debug!("Targets: {:?}", targets_resp);
-
-            let targets = Result::from(targets_resp)?.data.targets;
+            tracing::debug!("Targets: {:?}", targets);
 
             let (mgs, mdts, osts) =
                 targets
@@ -230,12 +574,54 @@ pub async fn filesystem_cli(command: FilesystemCommand) -> Result<(), ImlManager
         FilesystemCommand::ClientMount { fsname } => {
             let cmd = get_fs_mount_cmd(&fsname).await?;
 
-            let term = Term::stdout();
-            term.write_line(&cmd).unwrap();
+            if output.is_tabular() {
+                let term = Term::stdout();
+                term.write_line(&cmd).unwrap();
+            } else {
+                display_output(&cmd, output);
+            }
+        }
+        FilesystemCommand::ClientMountConfig {
+            fsname,
+            kind,
+            network,
+            interface,
+            mountpoint,
+        } => {
+            let query =
+                client_mount::mount_config::build(&fsname, kind, network, interface, mountpoint);
+
+            let resp: iml_graphql_queries::Response<client_mount::mount_config::Resp> =
+                wrap_fut("Fetching client mount config", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.client_mount_config;
+
+            // Printed as is, so it can be redirected straight into a file
+            print!("{}", x);
+        }
+        FilesystemCommand::Pool { command } => ostpool_cli(command, output).await?,
+        FilesystemCommand::Changelog { command } => changelog_cli(command, output).await?,
+        FilesystemCommand::Detect { hosts, yes } => detect_filesystem(hosts, yes, output).await?,
+        FilesystemCommand::Forget { fs_name } => forget_filesystem(fs_name, output).await?,
+        FilesystemCommand::Start { fsname } => transition_filesystem(fsname, true, output).await?,
+        FilesystemCommand::Stop { fsname } => transition_filesystem(fsname, false, output).await?,
+        FilesystemCommand::Compatibility { fsname } => compatibility_report(fsname, output).await?,
+        FilesystemCommand::RemountPolicy {
+            fsname,
+            enable,
+            disable,
+            remove,
+            max_attempts,
+            cooldown,
+        } => {
+            let enabled = match (enable, disable) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+
+            remount_policy(fsname, output, enabled, remove, max_attempts, cooldown).await?
         }
-        FilesystemCommand::Pool { command } => ostpool_cli(command).await?,
-        FilesystemCommand::Detect => detect_filesystem().await?,
-        FilesystemCommand::Forget { fs_name } => forget_filesystem(fs_name).await?,
     };
 
     Ok(())
@@ -4,8 +4,12 @@
 
 pub mod api;
 pub mod api_utils;
+pub mod approval;
+pub mod changelog;
+pub mod completion;
 pub mod display_utils;
 pub mod error;
+pub mod federation;
 pub mod filesystem;
 pub mod nginx;
 pub mod ostpool;
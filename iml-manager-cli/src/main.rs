@@ -4,7 +4,10 @@
 
 use iml_manager_cli::{
     api::{self, api_cli, graphql_cli},
-    display_utils::display_error,
+    approval::{self, approval_cli},
+    completion::{dynamic_bash_script, values_cli, CompletionCommand},
+    display_utils::{display_error, set_output, DisplayType},
+    federation::{self, federation_cli},
     filesystem::{self, filesystem_cli},
     selfname,
     server::{self, server_cli},
@@ -14,13 +17,33 @@ use iml_manager_cli::{
     update_repo_file::{self, update_repo_file_cli},
 };
 
-use std::process::exit;
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+    process::exit,
+};
 use structopt::clap::Shell;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
 #[structopt(setting = structopt::clap::AppSettings::ColoredHelp)]
-pub enum App {
+pub struct App {
+    /// Output format: table, json or yaml.
+    /// JSON and YAML use the field names of the GraphQL API
+    #[structopt(
+        short = "d",
+        long = "output",
+        alias = "display",
+        global = true,
+        default_value = "table"
+    )]
+    output: DisplayType,
+    #[structopt(subcommand)]
+    command: AppCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum AppCommand {
     #[structopt(name = "stratagem")]
     /// Work with Stratagem server
     Stratagem {
@@ -39,6 +62,18 @@ pub enum App {
         #[structopt(subcommand)]
         command: filesystem::FilesystemCommand,
     },
+    #[structopt(name = "approval")]
+    /// Commands held back until a second user approves them
+    Approval {
+        #[structopt(subcommand)]
+        command: approval::ApprovalCommand,
+    },
+    #[structopt(name = "federation")]
+    /// Peer managers of other sites, and an overview of all of them
+    Federation {
+        #[structopt(subcommand)]
+        command: federation::FederationCommand,
+    },
     #[structopt(name = "snapshot")]
     /// Snapshot operations
     Snapshot {
@@ -63,53 +98,114 @@ pub enum App {
     /// Direct GraphQL Access (for testing and debug)
     DebugQl(api::GraphQlCommand),
 
+    #[structopt(name = "completion")]
+    /// Shell completion, including the names of filesystems, servers and targets
+    Completion {
+        #[structopt(subcommand)]
+        command: CompletionCommand,
+    },
+
     #[structopt(name = "shell-completion", setting = structopt::clap::AppSettings::Hidden)]
     /// Generate shell completion script
     Shell {
         shell: Shell,
         #[structopt(short = "e", long = "executable", default_value = "iml")]
         exe: String,
-        #[structopt(short = "o", long = "output")]
-        output: Option<String>,
+        // `--output` is still accepted here, see `shell_args`
+        /// Write the script to this file instead of stdout
+        #[structopt(short = "o", long = "file")]
+        file: Option<String>,
     },
 }
 
+/// The completion script clap generates, which for bash
+/// is followed by the completion of filesystem, server and target names.
+fn write_completions(exe: &str, shell: Shell, out: &mut impl Write) -> io::Result<()> {
+    App::clap().gen_completions_to(exe, shell, out);
+
+    if let Shell::Bash = shell {
+        write!(out, "{}", dynamic_bash_script(exe))?;
+    }
+
+    Ok(())
+}
+
+/// `shell-completion` took the script path as `--output` before that became
+/// the global output format, so it is rewritten to `--file` for that subcommand only.
+fn shell_args(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let args: Vec<OsString> = args.into_iter().collect();
+
+    if args.get(1).and_then(|x| x.to_str()) != Some("shell-completion") {
+        return args;
+    }
+
+    args.into_iter()
+        .map(|x| match x.to_str() {
+            Some("--output") => "--file".into(),
+            Some(s) if s.starts_with("--output=") => s.replacen("--output=", "--file=", 1).into(),
+            _ => x,
+        })
+        .collect()
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     iml_tracing::init();
 
     let name = selfname(None).unwrap_or_else(|| "iml".to_string());
 
-    let matches = App::from_clap(&App::clap().bin_name(&name).name(&name).get_matches());
+    let matches = App::from_clap(
+        &App::clap()
+            .bin_name(&name)
+            .name(&name)
+            .get_matches_from(shell_args(std::env::args_os())),
+    );
 
     tracing::debug!("Matching args {:?}", matches);
 
-    match matches {
-        App::Shell { .. } => (),
+    let App { output, command } = matches;
+
+    set_output(output);
+
+    match command {
+        AppCommand::Shell { .. }
+        | AppCommand::Completion {
+            command: CompletionCommand::Script { .. },
+        } => (),
         _ => dotenv::from_path("/etc/emf/emf-settings.conf")
             .or_else(|_| dotenv::from_path("/etc/iml/iml-settings.conf"))
             .or_else(|_| dotenv::from_path("/var/lib/chroma/iml-settings.conf"))
             .expect("Could not load cli env"),
     }
 
-    let r = match matches {
-        App::DebugApi(command) => api_cli(command).await,
-        App::DebugQl(command) => graphql_cli(command).await,
-        App::Filesystem { command } => filesystem_cli(command).await,
-        App::Server { command } => server_cli(command).await,
-        App::Snapshot { command } => snapshot_cli(command).await,
-        App::Stratagem { command } => stratagem_cli(command).await,
-        App::Target { command } => target_cli(command).await,
-        App::UpdateRepoFile(config) => update_repo_file_cli(config).await,
-        App::Shell { shell, exe, output } => {
-            if let Some(out) = output {
+    let r = match command {
+        AppCommand::Approval { command } => approval_cli(command, output).await,
+        AppCommand::DebugApi(command) => api_cli(command).await,
+        AppCommand::DebugQl(command) => graphql_cli(command).await,
+        AppCommand::Federation { command } => federation_cli(command, output).await,
+        AppCommand::Filesystem { command } => filesystem_cli(command, output).await,
+        AppCommand::Server { command } => server_cli(command, output).await,
+        AppCommand::Snapshot { command } => snapshot_cli(command, output).await,
+        AppCommand::Stratagem { command } => stratagem_cli(command, output).await,
+        AppCommand::Target { command } => target_cli(command, output).await,
+        AppCommand::UpdateRepoFile(config) => update_repo_file_cli(config, output).await,
+        AppCommand::Shell { shell, exe, file } => {
+            if let Some(out) = file {
                 let mut o = std::fs::File::create(out)?;
-                App::clap().gen_completions_to(exe, shell, &mut o);
+                write_completions(&exe, shell, &mut o)?;
             } else {
-                App::clap().gen_completions_to(exe, shell, &mut std::io::stdout());
+                write_completions(&exe, shell, &mut std::io::stdout())?;
             };
             Ok(())
         }
+        AppCommand::Completion { command } => match command {
+            CompletionCommand::Script { shell, exe } => {
+                write_completions(&exe, shell, &mut std::io::stdout())?;
+
+                Ok(())
+            }
+            CompletionCommand::Values { kind, prefix } => values_cli(kind, prefix).await,
+        },
     };
 
     if let Err(e) = r {
@@ -119,3 +215,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iml_manager_cli::completion::dynamic_arg_lines;
+    use structopt::clap::ErrorKind;
+
+    /// Every command completed with dynamic values exists, and takes a value at that position.
+    /// Other required arguments may be missing.
+    #[test]
+    fn test_dynamic_args() {
+        for line in dynamic_arg_lines() {
+            let r = App::clap()
+                .get_matches_from_safe(std::iter::once("iml".into()).chain(line.clone()));
+
+            match r {
+                Ok(_) => {}
+                Err(e) if e.kind == ErrorKind::MissingRequiredArgument => {}
+                Err(e) => panic!("{:?} does not match the CLI: {}", line, e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_shell_args() {
+        let args = |xs: &[&str]| -> Vec<OsString> { xs.iter().map(OsString::from).collect() };
+
+        assert_eq!(
+            shell_args(args(&[
+                "iml",
+                "shell-completion",
+                "bash",
+                "--output",
+                "/tmp/iml"
+            ])),
+            args(&["iml", "shell-completion", "bash", "--file", "/tmp/iml"])
+        );
+        assert_eq!(
+            shell_args(args(&[
+                "iml",
+                "shell-completion",
+                "zsh",
+                "--output=/tmp/_iml"
+            ])),
+            args(&["iml", "shell-completion", "zsh", "--file=/tmp/_iml"])
+        );
+        assert_eq!(
+            shell_args(args(&["iml", "server", "list", "--output", "json"])),
+            args(&["iml", "server", "list", "--output", "json"])
+        );
+    }
+}
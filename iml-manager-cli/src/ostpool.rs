@@ -4,7 +4,7 @@
 
 use crate::{
     api_utils::{delete, get, get_all, get_one, post, put, wait_for_cmds_success},
-    display_utils::{wrap_fut, DisplayType, IntoDisplayType as _},
+    display_utils::{display_output, wrap_fut, DisplayType, IntoDisplayType as _},
     error::ImlManagerCliError,
 };
 use console::{style, Term};
@@ -31,10 +31,6 @@ pub enum OstPoolCommand {
     List {
         #[structopt(name = "FSNAME")]
         fsname: Option<String>,
-
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
     },
 
     /// Show Pool Details
@@ -181,11 +177,21 @@ async fn ostpool_list(
     Ok(())
 }
 
-async fn ostpool_show(fsname: String, poolname: String) -> Result<(), ImlManagerCliError> {
+async fn ostpool_show(
+    fsname: String,
+    poolname: String,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
     let mut pool = pool_lookup(&fsname, &poolname).await?;
 
     pool.ost.osts.sort_unstable();
 
+    if !output.is_tabular() {
+        display_output(&pool.ost, output);
+
+        return Ok(());
+    }
+
     let mut table = Table::new();
     table.add_row(Row::from(&["Filesystem".to_string(), fsname]));
     table.add_row(Row::from(&["Name".to_string(), poolname]));
@@ -199,25 +205,28 @@ async fn ostpool_destroy(
     term: &Term,
     fsname: String,
     poolname: String,
+    output: DisplayType,
 ) -> Result<(), ImlManagerCliError> {
     let pool = pool_lookup(&fsname, &poolname).await?;
     let resp = delete(&pool.resource_uri, "").await?;
     term.write_line(&format!("{} ost pool...", style("Destroying").green()))?;
     let objs: ObjCommands = resp.json().await?;
-    wait_for_cmds_success(&objs.commands).await?;
+    let cmds = wait_for_cmds_success(&objs.commands).await?;
+
+    display_output(&cmds, output);
 
     Ok(())
 }
 
-pub async fn ostpool_cli(command: OstPoolCommand) -> Result<(), ImlManagerCliError> {
-    let term = Term::stdout();
+pub async fn ostpool_cli(
+    command: OstPoolCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    let term = Term::stderr();
 
     match command {
-        OstPoolCommand::List {
-            fsname,
-            display_type,
-        } => ostpool_list(fsname, display_type).await?,
-        OstPoolCommand::Show { fsname, poolname } => ostpool_show(fsname, poolname).await?,
+        OstPoolCommand::List { fsname } => ostpool_list(fsname, output).await?,
+        OstPoolCommand::Show { fsname, poolname } => ostpool_show(fsname, poolname, output).await?,
         OstPoolCommand::Create {
             fsname,
             poolname,
@@ -235,10 +244,12 @@ pub async fn ostpool_cli(command: OstPoolCommand) -> Result<(), ImlManagerCliErr
 
             term.write_line(&format!("{} ost pool...", style("Creating").green()))?;
             let objs: ObjCommand = resp.json().await?;
-            wait_for_cmds_success(&[objs.command]).await?;
+            let cmds = wait_for_cmds_success(&[objs.command]).await?;
+
+            display_output(&cmds, output);
         }
         OstPoolCommand::Destroy { fsname, poolname } => {
-            ostpool_destroy(&term, fsname, poolname).await?;
+            ostpool_destroy(&term, fsname, poolname, output).await?;
         }
         OstPoolCommand::Grow {
             fsname,
@@ -257,7 +268,9 @@ pub async fn ostpool_cli(command: OstPoolCommand) -> Result<(), ImlManagerCliErr
             let uri = pool.resource_uri.clone();
             let resp = put(&uri, pool).await?;
             let objs: ObjCommand = resp.json().await?;
-            wait_for_cmds_success(&[objs.command]).await?;
+            let cmds = wait_for_cmds_success(&[objs.command]).await?;
+
+            display_output(&cmds, output);
         }
         OstPoolCommand::Shrink {
             fsname,
@@ -274,7 +287,9 @@ pub async fn ostpool_cli(command: OstPoolCommand) -> Result<(), ImlManagerCliErr
             let resp = put(&uri, pool).await?;
 
             let objs: ObjCommand = resp.json().await?;
-            wait_for_cmds_success(&[objs.command]).await?;
+            let cmds = wait_for_cmds_success(&[objs.command]).await?;
+
+            display_output(&cmds, output);
         }
     };
 
@@ -4,7 +4,7 @@
 
 use crate::{
     api_utils::graphql,
-    display_utils::{display_success, wrap_fut, DisplayType, IntoDisplayType as _},
+    display_utils::{display_output, display_success, wrap_fut, DisplayType, IntoDisplayType as _},
     error::ImlManagerCliError,
 };
 use console::Term;
@@ -18,14 +18,14 @@ use tokio::io::{stdin, AsyncReadExt};
 pub enum Cmd {
     /// List all profiles
     #[structopt(name = "list")]
-    List {
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
-    },
+    List,
     /// Load a new profile from stdin
     #[structopt(name = "load")]
-    Load,
+    Load {
+        /// Current version of the profile, required when replacing an existing profile
+        #[structopt(long = "version")]
+        version: Option<i32>,
+    },
     /// Remove an existing profile
     #[structopt(name = "remove")]
     Remove { name: String },
@@ -82,29 +82,32 @@ impl Into<ServerProfileInput> for UserProfile {
             ui_name: self.ui_name,
             user_selectable: true,
             worker: self.worker,
+            version: None,
         }
     }
 }
 
-pub async fn cmd(cmd: Option<Cmd>) -> Result<(), ImlManagerCliError> {
+pub async fn cmd(cmd: Option<Cmd>, output: DisplayType) -> Result<(), ImlManagerCliError> {
     match cmd {
-        None => {
-            list_profiles(DisplayType::Tabular).await?;
-        }
-        Some(Cmd::List { display_type }) => {
-            list_profiles(display_type).await?;
+        None | Some(Cmd::List) => {
+            list_profiles(output).await?;
         }
-        Some(Cmd::Load) => {
+        Some(Cmd::Load { version }) => {
             let mut buf: Vec<u8> = Vec::new();
             stdin().read_to_end(&mut buf).await?;
             let profile: UserProfile = serde_json::from_slice(&buf)?;
-            let input: ServerProfileInput = profile.into();
+            let input = ServerProfileInput {
+                version,
+                ..profile.into()
+            };
 
             let query = server_profile::create::build(input);
 
             let resp: iml_graphql_queries::Response<server_profile::create::Resp> =
                 wrap_fut("Loading profile", graphql(query)).await?;
-            let _success = Result::from(resp)?.data.create_server_profile;
+            let success = Result::from(resp)?.data.create_server_profile;
+
+            display_output(&success, output);
 
             display_success("Profile loaded");
         }
@@ -113,7 +116,9 @@ pub async fn cmd(cmd: Option<Cmd>) -> Result<(), ImlManagerCliError> {
 
             let resp: iml_graphql_queries::Response<server_profile::remove::Resp> =
                 wrap_fut("Removing profile", graphql(query)).await?;
-            let _success = Result::from(resp)?.data.remove_server_profile;
+            let success = Result::from(resp)?.data.remove_server_profile;
+
+            display_output(&success, output);
 
             display_success("Profile removed");
         }
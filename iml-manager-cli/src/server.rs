@@ -4,11 +4,12 @@
 
 use crate::{
     api_utils::{
-        get, get_all, get_hosts, post, put, wait_for_cmds, wait_for_cmds_success, SendCmd, SendJob,
+        get, get_all, get_hosts, graphql, post, put, wait_for_cmds, wait_for_cmds_success, SendCmd,
+        SendJob,
     },
     display_utils::{
-        display_cancelled, display_error, format_error, format_success, generate_table, wrap_fut,
-        DisplayType, IntoDisplayType as _,
+        display_cancelled, display_error, display_output, display_success, format_error,
+        format_success, generate_table, wrap_fut, DisplayType, IntoDisplayType as _,
     },
     error::ImlManagerCliError,
     parse_hosts, profile,
@@ -16,17 +17,24 @@ use crate::{
 use console::{style, Term};
 use dialoguer::Confirm;
 use futures::future;
+use iml_graphql_queries::{
+    certificate as certificate_queries, host as host_queries, lnet_ping as lnet_ping_queries,
+};
 use iml_wire_types::{
-    ApiList, AvailableAction, CmdWrapper, Command, EndpointName, Host, ProfileTest, ServerProfile,
-    TestHostJob, ToCompositeId,
+    graphql::TaggedHost, lnet_ping::LnetPingMesh, ApiList, AvailableAction, CmdWrapper, Command,
+    EndpointName, Host, ProfileTest, ServerProfile, TestHostJob, ToCompositeId,
 };
 use std::{
     collections::BTreeSet,
     io::{Error, ErrorKind},
     iter,
+    time::Duration,
 };
 use structopt::StructOpt;
-use tokio::io::{stdin, AsyncReadExt};
+use tokio::{
+    io::{stdin, AsyncReadExt},
+    time::delay_for,
+};
 
 #[derive(StructOpt, Debug)]
 pub struct AddHosts {
@@ -59,9 +67,9 @@ pub enum ServerCommand {
     /// List all configured storage servers (default)
     #[structopt(name = "list")]
     List {
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
+        /// Only list servers matching all of these tags, e. g. --tag rack=12 --tag nvme
+        #[structopt(long = "tag", number_of_values = 1)]
+        tags: Vec<String>,
     },
     /// Add new servers
     #[structopt(name = "add")]
@@ -70,16 +78,88 @@ pub enum ServerCommand {
     #[structopt(name = "remove")]
     Remove {
         /// Hostlist expressions, e. g. mds[1,2].local
-        #[structopt(required = true, min_values = 1)]
+        #[structopt(required_unless = "tags")]
         hosts: Vec<String>,
+        /// Also remove servers matching all of these tags
+        #[structopt(long = "tag", number_of_values = 1)]
+        tags: Vec<String>,
     },
     /// Remove servers from DB, but leave agents in place.
     #[structopt(name = "force-remove")]
     ForceRemove {
+        /// Hostlist expressions, e. g. mds[1,2].local
+        #[structopt(required_unless = "tags")]
+        hosts: Vec<String>,
+        /// Also force remove servers matching all of these tags
+        #[structopt(long = "tag", number_of_values = 1)]
+        tags: Vec<String>,
+    },
+    /// Set a tag on servers, e. g. `iml server tag rack=12 oss[1-4].local`
+    #[structopt(name = "tag")]
+    Tag {
+        /// The tag to set, as key=value
+        tag: String,
+        /// Hostlist expressions, e. g. mds[1,2].local
+        #[structopt(required_unless = "tags")]
+        hosts: Vec<String>,
+        /// Also tag servers matching all of these tags
+        #[structopt(long = "tag", number_of_values = 1)]
+        tags: Vec<String>,
+    },
+    /// Remove a tag from servers
+    #[structopt(name = "untag")]
+    Untag {
+        /// The tag key to remove
+        key: String,
+        /// Hostlist expressions, e. g. mds[1,2].local
+        #[structopt(required_unless = "tags")]
+        hosts: Vec<String>,
+        /// Also untag servers matching all of these tags
+        #[structopt(long = "tag", number_of_values = 1)]
+        tags: Vec<String>,
+    },
+    /// Show the jobs queued on a server, in the order they will run.
+    /// Jobs run highest priority first, so interactive commands go ahead of background work
+    #[structopt(name = "queue")]
+    Queue {
+        /// The server, e. g. oss1.local. A summary of the queue of every server when omitted
+        host: Option<String>,
+    },
+    /// Show the agent certificates of servers and when they expire
+    #[structopt(name = "certificates")]
+    Certificates {
+        /// Hostlist expressions, e. g. mds[1,2].local. All servers when omitted
+        hosts: Vec<String>,
+    },
+    /// Show when the agent on each server was last heard from
+    #[structopt(name = "heartbeats")]
+    Heartbeats,
+    /// Issue servers a new agent certificate, revoking the old one
+    #[structopt(name = "rotate-certificate")]
+    RotateCertificate {
         /// Hostlist expressions, e. g. mds[1,2].local
         #[structopt(required = true, min_values = 1)]
         hosts: Vec<String>,
     },
+    /// Push the configuration of servers again and re-register their devices,
+    /// then report how each differs from its server profile
+    #[structopt(name = "resync")]
+    Resync {
+        /// Hostlist expressions, e. g. mds[1,2].local
+        #[structopt(required = true, min_values = 1)]
+        hosts: Vec<String>,
+    },
+    /// Have each server ping the NIDs of the others over LNet,
+    /// and show the loss and latency of every pair
+    #[structopt(name = "ping-mesh")]
+    PingMesh {
+        /// Hostlist expressions, e. g. oss[1-4].local. At least two servers
+        #[structopt(required = true, min_values = 1)]
+        hosts: Vec<String>,
+        /// How many times each NID is pinged
+        #[structopt(short = "c", long = "count")]
+        count: Option<i32>,
+    },
     /// Work with server profiles
     #[structopt(name = "profile")]
     Profile {
@@ -182,7 +262,7 @@ pub struct Objects<T> {
 /// Given an expanded hostlist and a list of API host objects
 /// returns a tuple of hosts that match a hostlist item, and the remaining hostlist items
 /// that did not match anything.
-fn filter_known_hosts<'a>(
+pub(crate) fn filter_known_hosts<'a>(
     hostlist: BTreeSet<String>,
     api_hosts: &'a [Host],
 ) -> (Vec<&'a Host>, BTreeSet<String>) {
@@ -222,8 +302,19 @@ fn get_confirm() -> bool {
         .unwrap_or(false)
 }
 
-async fn list_server(display_type: DisplayType) -> Result<(), ImlManagerCliError> {
-    let hosts: ApiList<Host> = wrap_fut("Fetching hosts...", get_hosts()).await?;
+async fn list_server(
+    display_type: DisplayType,
+    tags: Vec<String>,
+) -> Result<(), ImlManagerCliError> {
+    let mut hosts: ApiList<Host> = wrap_fut("Fetching hosts...", get_hosts()).await?;
+
+    if !tags.is_empty() {
+        let tagged = get_tagged_hosts(tags).await?;
+
+        hosts
+            .objects
+            .retain(|x| tagged.iter().any(|y| y.id == x.id));
+    }
 
     let term = Term::stdout();
 
@@ -330,7 +421,7 @@ fn all_jobs_passed(jobs: ApiList<TestHostJob>, profile: &ServerProfile, term: &T
 
                 term.write_line("\n").unwrap();
 
-                table.printstd();
+                term.write_line(&table.to_string()).unwrap();
 
                 term.write_line("\n").unwrap();
 
@@ -411,7 +502,7 @@ fn handle_invalid_profile_tests(
                 .map(|x| vec![&x.description, &x.test, &x.error]),
         );
 
-        table.printstd();
+        term.write_line(&table.to_string()).unwrap();
 
         term.write_line("\n\n").unwrap();
     }
@@ -476,7 +567,7 @@ async fn deploy_agents(
     new_hosts: &BTreeSet<String>,
     server_profile: &ServerProfile,
     auth: &AuthType,
-) -> Result<(), ImlManagerCliError> {
+) -> Result<Vec<Command>, ImlManagerCliError> {
     let objects = get_agent_config_objects(new_hosts, server_profile, auth);
 
     let Objects { objects }: Objects<CommandAndHostWrapper> = post_command_to_host(objects).await?;
@@ -487,13 +578,11 @@ async fn deploy_agents(
 
     term.write_line(&format!("{} agents...", style("Deploying").green()))?;
 
-    wait_for_cmds_success(&commands).await?;
-
-    Ok(())
+    wait_for_cmds_success(&commands).await
 }
 
-async fn add_server(config: AddHosts) -> Result<(), ImlManagerCliError> {
-    let term = Term::stdout();
+async fn add_server(config: AddHosts, output: DisplayType) -> Result<(), ImlManagerCliError> {
+    let term = Term::stderr();
 
     let new_hosts = parse_hosts(&config.hosts)?;
 
@@ -518,24 +607,30 @@ async fn add_server(config: AddHosts) -> Result<(), ImlManagerCliError> {
 
     handle_test_host_failure(all_passed, &config)?;
 
-    deploy_agents(&term, &new_hosts, &server_profile, &auth).await?;
+    let cmds = deploy_agents(&term, &new_hosts, &server_profile, &auth).await?;
+
+    display_output(&cmds, output);
 
     Ok(())
 }
 
-pub async fn server_cli(command: Option<ServerCommand>) -> Result<(), ImlManagerCliError> {
-    server(command.unwrap_or(ServerCommand::List {
-        display_type: DisplayType::Tabular,
-    }))
+pub async fn server_cli(
+    command: Option<ServerCommand>,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    server(
+        command.unwrap_or(ServerCommand::List { tags: vec![] }),
+        output,
+    )
     .await
 }
 
-async fn server(command: ServerCommand) -> Result<(), ImlManagerCliError> {
+async fn server(command: ServerCommand, output: DisplayType) -> Result<(), ImlManagerCliError> {
     match command {
-        ServerCommand::List { display_type } => list_server(display_type).await?,
-        ServerCommand::Add(config) => add_server(config).await?,
-        ServerCommand::ForceRemove { hosts } => {
-            let remove_hosts = parse_hosts(&hosts)?;
+        ServerCommand::List { tags } => list_server(output, tags).await?,
+        ServerCommand::Add(config) => add_server(config, output).await?,
+        ServerCommand::ForceRemove { hosts, tags } => {
+            let remove_hosts = resolve_host_names(&hosts, tags).await?;
 
             tracing::debug!("Parsed hosts {:?}", remove_hosts);
 
@@ -602,10 +697,12 @@ async fn server(command: ServerCommand) -> Result<(), ImlManagerCliError> {
 
             let command: Command = wrap_fut("Removing Hosts...", cmd.json()).await?;
 
-            wait_for_cmds_success(&[command]).await?;
+            let cmds = wait_for_cmds_success(&[command]).await?;
+
+            display_output(&cmds, output);
         }
-        ServerCommand::Remove { hosts } => {
-            let remove_hosts = parse_hosts(&hosts)?;
+        ServerCommand::Remove { hosts, tags } => {
+            let remove_hosts = resolve_host_names(&hosts, tags).await?;
 
             tracing::debug!("Parsed hosts {:?}", remove_hosts);
 
@@ -674,14 +771,239 @@ async fn server(command: ServerCommand) -> Result<(), ImlManagerCliError> {
             let commands: Vec<Command> =
                 wrap_fut("Removing Servers...", future::try_join_all(xs)).await?;
 
-            wait_for_cmds_success(&commands).await?;
+            let cmds = wait_for_cmds_success(&commands).await?;
+
+            display_output(&cmds, output);
+        }
+        ServerCommand::Tag { tag, hosts, tags } => {
+            let (key, value) = parse_tag(&tag)?;
+
+            let host_ids = known_host_ids(&hosts).await?;
+            let tags = if tags.is_empty() { None } else { Some(tags) };
+
+            let query = host_queries::set_tag::build(Some(host_ids), tags, key, value);
+
+            let resp: iml_graphql_queries::Response<host_queries::set_tag::Resp> =
+                wrap_fut("Tagging servers...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.host.set_tag;
+
+            display_output(&xs, output);
+
+            display_success(format!("Tagged {} server(s) with {}", xs.len(), tag));
+        }
+        ServerCommand::Untag { key, hosts, tags } => {
+            let host_ids = known_host_ids(&hosts).await?;
+            let tags = if tags.is_empty() { None } else { Some(tags) };
+
+            let query = host_queries::remove_tag::build(Some(host_ids), tags, &key);
+
+            let resp: iml_graphql_queries::Response<host_queries::remove_tag::Resp> =
+                wrap_fut("Untagging servers...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.host.remove_tag;
+
+            display_output(&xs, output);
+
+            display_success(format!("Removed {} from {} server(s)", key, xs.len()));
+        }
+        ServerCommand::Queue { host: None } => {
+            let query = host_queries::queues::build();
+
+            let resp: iml_graphql_queries::Response<host_queries::queues::Resp> =
+                wrap_fut("Fetching host queues...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.host_queues;
+
+            let x = xs.into_display_type(output);
+
+            Term::stdout().write_line(&x).unwrap();
+        }
+        ServerCommand::Queue { host: Some(host) } => {
+            let host_id = match known_host_ids(&[host.clone()]).await?.as_slice() {
+                [x] => *x,
+                _ => return Err(not_found_err(format!("Host {} not found", host))),
+            };
+
+            let query = host_queries::queue::build(host_id);
+
+            let resp: iml_graphql_queries::Response<host_queries::queue::Resp> =
+                wrap_fut("Fetching host queue...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.host_queue;
+
+            let x = xs.into_display_type(output);
+
+            Term::stdout().write_line(&x).unwrap();
+        }
+        ServerCommand::Certificates { hosts } => {
+            let host_ids = if hosts.is_empty() {
+                None
+            } else {
+                Some(known_host_ids(&hosts).await?)
+            };
+
+            let query = certificate_queries::list::build(host_ids);
+
+            let resp: iml_graphql_queries::Response<certificate_queries::list::Resp> =
+                wrap_fut("Fetching certificates...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.certificate.list;
+
+            let x = xs.into_display_type(output);
+
+            Term::stdout().write_line(&x).unwrap();
+        }
+        ServerCommand::Heartbeats => {
+            let query = host_queries::heartbeats::build();
+
+            let resp: iml_graphql_queries::Response<host_queries::heartbeats::Resp> =
+                wrap_fut("Fetching heartbeats...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.host.heartbeats;
+
+            let x = xs.into_display_type(output);
+
+            Term::stdout().write_line(&x).unwrap();
+        }
+        ServerCommand::PingMesh { hosts, count } => {
+            let host_ids = known_host_ids(&hosts).await?;
+
+            let query = lnet_ping_queries::start::build(host_ids, count);
+
+            let resp: iml_graphql_queries::Response<lnet_ping_queries::start::Resp> =
+                wrap_fut("Starting ping mesh...", graphql(query)).await?;
+
+            let mesh = Result::from(resp)?.data.lnet_ping.start;
+
+            let mesh = wrap_fut("Pinging...", wait_for_ping_mesh(mesh.id)).await?;
+
+            let x = mesh.pairs.into_display_type(output);
+
+            Term::stdout().write_line(&x).unwrap();
         }
-        ServerCommand::Profile { cmd } => profile::cmd(cmd).await?,
+        ServerCommand::RotateCertificate { hosts } => {
+            let host_ids = known_host_ids(&hosts).await?;
+
+            if host_ids.is_empty() {
+                return Err(not_found_err(format!(
+                    "No servers found for {}",
+                    hosts.join(" ")
+                )));
+            }
+
+            let query = certificate_queries::rotate::build(host_ids);
+
+            let resp: iml_graphql_queries::Response<certificate_queries::rotate::Resp> =
+                wrap_fut("Rotating certificates...", graphql(query)).await?;
+
+            let command = Result::from(resp)?.data.certificate.rotate;
+
+            let cmds = wait_for_cmds_success(&[command]).await?;
+
+            display_output(&cmds, output);
+        }
+        ServerCommand::Resync { hosts } => {
+            let host_ids = known_host_ids(&hosts).await?;
+
+            if host_ids.is_empty() {
+                return Err(not_found_err(format!(
+                    "No servers found for {}",
+                    hosts.join(" ")
+                )));
+            }
+
+            let mut commands = vec![];
+
+            for id in host_ids {
+                let query = host_queries::resync::build(id);
+
+                let resp: iml_graphql_queries::Response<host_queries::resync::Resp> =
+                    wrap_fut("Resyncing server...", graphql(query)).await?;
+
+                commands.push(Result::from(resp)?.data.host.resync);
+            }
+
+            let cmds = wait_for_cmds_success(&commands).await?;
+
+            display_output(&cmds, output);
+        }
+        ServerCommand::Profile { cmd } => profile::cmd(cmd, output).await?,
     };
 
     Ok(())
 }
 
+async fn get_tagged_hosts(tags: Vec<String>) -> Result<Vec<TaggedHost>, ImlManagerCliError> {
+    let query = host_queries::list::build(Some(tags));
+
+    let resp: iml_graphql_queries::Response<host_queries::list::Resp> =
+        wrap_fut("Fetching tagged hosts...", graphql(query)).await?;
+
+    Ok(Result::from(resp)?.data.host.list)
+}
+
+/// Expands hostlist expressions, adding the hosts matching all of `tags`.
+async fn resolve_host_names(
+    hosts: &[String],
+    tags: Vec<String>,
+) -> Result<BTreeSet<String>, ImlManagerCliError> {
+    let mut xs = parse_hosts(hosts)?;
+
+    if !tags.is_empty() {
+        xs.extend(get_tagged_hosts(tags).await?.into_iter().map(|x| x.fqdn));
+    }
+
+    Ok(xs)
+}
+
+/// Expands hostlist expressions into host ids, warning about unknown names.
+async fn known_host_ids(hosts: &[String]) -> Result<Vec<i32>, ImlManagerCliError> {
+    if hosts.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let api_hosts = wrap_fut("Fetching hosts...", get_hosts()).await?;
+
+    let (known, unknown_names) = filter_known_hosts(parse_hosts(hosts)?, &api_hosts.objects);
+
+    for unknown_name in unknown_names {
+        display_cancelled(format!(
+            "Host {} is unknown and will be skipped.",
+            unknown_name
+        ));
+    }
+
+    Ok(known.into_iter().map(|x| x.id).collect())
+}
+
+/// Polls the ping mesh `id` until every server has finished pinging.
+async fn wait_for_ping_mesh(id: i32) -> Result<LnetPingMesh, ImlManagerCliError> {
+    loop {
+        let query = lnet_ping_queries::mesh::build(id);
+
+        let resp: iml_graphql_queries::Response<lnet_ping_queries::mesh::Resp> =
+            graphql(query).await?;
+
+        match Result::from(resp)?.data.lnet_ping.mesh {
+            Some(x) if x.finished_at.is_some() => return Ok(x),
+            Some(_) => delay_for(Duration::from_millis(1000)).await,
+            None => return Err(not_found_err(format!("Ping mesh {} not found", id))),
+        }
+    }
+}
+
+fn parse_tag(x: &str) -> Result<(&str, &str), ImlManagerCliError> {
+    match x.splitn(2, '=').collect::<Vec<_>>().as_slice() {
+        [k, v] if !k.is_empty() && !v.is_empty() => Ok((*k, *v)),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Tag {} must be given as key=value", x),
+        )
+        .into()),
+    }
+}
+
 fn not_found_err(x: impl Into<String>) -> ImlManagerCliError {
     Error::new(ErrorKind::NotFound, x.into()).into()
 }
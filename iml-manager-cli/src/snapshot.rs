@@ -5,27 +5,40 @@
 use crate::{
     api_utils::graphql,
     api_utils::wait_for_cmds_success,
-    display_utils::{DisplayType, IntoDisplayType as _},
+    display_utils::{display_output, display_success, DisplayType, IntoDisplayType as _},
     error::ImlManagerCliError,
 };
 use console::Term;
 use iml_graphql_queries::snapshot as snapshot_queries;
-use iml_wire_types::snapshot;
+use iml_wire_types::{
+    graphql_duration::GraphQLDuration,
+    snapshot::{self, SnapshotInterval, SnapshotRetention},
+};
+use std::{collections::BTreeMap, convert::TryFrom as _};
 use structopt::StructOpt;
+use tokio::{
+    fs,
+    io::{stdin, AsyncReadExt},
+};
 
 #[derive(Debug, StructOpt)]
 pub enum IntervalCommand {
     /// List snapshots intervals
-    List {
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
-    },
+    List,
     /// Add new snapshot interval
     Add {
         /// Use barrier when creating snapshots
         #[structopt(short = "b", long = "barrier")]
         barrier: bool,
+        /// Mount snapshots as soon as they are taken
+        #[structopt(short = "m", long = "automount")]
+        automount: bool,
+        /// Take the snapshots at this time of day, as HH:MM, and every interval after it
+        #[structopt(long = "start-time")]
+        start_time: Option<String>,
+        /// The time zone of the start time, e. g. Europe/Paris (default: UTC)
+        #[structopt(long = "time-zone", requires = "start-time")]
+        time_zone: Option<String>,
         /// Filesystem to add a snapshot interval for
         filesystem: String,
         /// Snapshot interval in human form, e. g. 1hour
@@ -38,16 +51,18 @@ pub enum IntervalCommand {
         #[structopt(required = true, min_values = 1)]
         ids: Vec<i32>,
     },
+    /// Queue a snapshot of an interval, as its timer does when the interval is due.
+    /// It is started after the policy jitter, once the concurrency cap allows it
+    Run {
+        /// The id of the snapshot interval
+        id: i32,
+    },
 }
 
 #[derive(Debug, StructOpt)]
 pub enum RetentionCommand {
     /// List snapshots retention rules
-    List {
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
-    },
+    List,
     /// Create snapshot retention rule
     Create {
         /// Filesystem to create a snapshot retention rule for
@@ -58,6 +73,15 @@ pub enum RetentionCommand {
         reserve_unit: snapshot::ReserveUnit,
         /// Minimum number of snapshots to keep (default: 0)
         keep_num: Option<u32>,
+        /// Current version of the rule, required when updating an existing rule
+        #[structopt(long = "version")]
+        version: Option<i32>,
+        /// Also delete the oldest snapshot while the MDTs have less than this percent free
+        #[structopt(long = "mdt-reserve")]
+        mdt_reserve_percent: Option<u32>,
+        /// Take a snapshot once used space changed by this percent of the filesystem size
+        #[structopt(long = "change")]
+        change_percent: Option<u32>,
     },
     /// Remove snapshot retention rule
     Remove {
@@ -67,6 +91,106 @@ pub enum RetentionCommand {
     },
 }
 
+#[derive(Debug, StructOpt)]
+pub struct PolicyArgs {
+    /// Filesystem the policy applies to
+    filesystem: String,
+    /// Take a snapshot every interval, in human form, e. g. 1hour. May be given multiple times
+    #[structopt(short = "i", long = "interval", number_of_values = 1)]
+    intervals: Vec<String>,
+    /// Use barrier when creating snapshots
+    #[structopt(short = "b", long = "barrier")]
+    barrier: bool,
+    /// Mount snapshots as soon as they are taken
+    #[structopt(short = "m", long = "automount")]
+    automount: bool,
+    /// Take the snapshots at this time of day, as HH:MM, and every interval after it
+    #[structopt(long = "start-time")]
+    start_time: Option<String>,
+    /// The time zone of the start time, e. g. Europe/Paris (default: UTC)
+    #[structopt(long = "time-zone", requires = "start-time")]
+    time_zone: Option<String>,
+    /// Delete the oldest snapshot when available space falls below this value
+    #[structopt(long = "reserve-value", requires = "reserve-unit")]
+    reserve_value: Option<u32>,
+    /// The unit of measurement associated with the reserve-value (%, GiB or TiB)
+    #[structopt(long = "reserve-unit", requires = "reserve-value")]
+    reserve_unit: Option<snapshot::ReserveUnit>,
+    /// Minimum number of snapshots to keep (default: 0)
+    #[structopt(long = "keep-num", requires = "reserve-value")]
+    keep_num: Option<u32>,
+    /// Also delete the oldest snapshot while the MDTs have less than this percent free
+    #[structopt(long = "mdt-reserve", requires = "reserve-value")]
+    mdt_reserve_percent: Option<u32>,
+    /// Take a snapshot once used space changed by this percent of the filesystem size
+    #[structopt(long = "change", requires = "reserve-value")]
+    change_percent: Option<u32>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum PolicyCommand {
+    /// List snapshot policies
+    List,
+    /// Create a snapshot policy for a filesystem
+    Create(PolicyArgs),
+    /// Update the snapshot policy of a filesystem.
+    /// Intervals given replace the existing ones, retention is replaced if given
+    Update(PolicyArgs),
+    /// Delete the snapshot policies of the given filesystems
+    Delete {
+        #[structopt(required = true, min_values = 1)]
+        filesystems: Vec<String>,
+    },
+    /// Show or change the settings shared by every snapshot policy
+    Settings {
+        /// How many snapshots intervals may be taking at once, 0 for no cap
+        #[structopt(long = "max-concurrent")]
+        max_concurrent: Option<i32>,
+        /// Delay each interval run by a random time up to this long, e. g. 5min
+        #[structopt(long = "jitter")]
+        jitter: Option<String>,
+    },
+    /// Export snapshot policies and their shared settings as YAML
+    Export {
+        /// Write the policies to this file instead of stdout
+        #[structopt(short = "f", long = "file")]
+        file: Option<String>,
+        /// Only export the policies of these filesystems
+        filesystems: Vec<String>,
+    },
+    /// Apply snapshot policies written by `export`.
+    /// Intervals and retention rules of other filesystems are left as they are
+    Import {
+        /// Apply the policies of the file's only filesystem to this filesystem instead
+        #[structopt(long = "fsname")]
+        fsname: Option<String>,
+        /// Also remove the intervals and retention rules of the filesystems in the file
+        /// that are not in it
+        #[structopt(long = "replace")]
+        replace: bool,
+        /// The file to read the policies from, stdin when not given
+        file: Option<String>,
+    },
+    /// List the times snapshot intervals were due, newest first
+    Runs {
+        /// How many runs to list
+        #[structopt(long = "limit", default_value = "50")]
+        limit: i32,
+        /// Only list the runs of this filesystem
+        filesystem: Option<String>,
+    },
+}
+
+/// The intervals and retention rule configured for a filesystem.
+///
+/// This is a client side grouping of `SnapshotInterval`s and `SnapshotRetention`.
+#[derive(Debug, serde::Serialize)]
+pub struct SnapshotPolicy {
+    pub filesystem_name: String,
+    pub intervals: Vec<SnapshotInterval>,
+    pub retention: Option<SnapshotRetention>,
+}
+
 #[derive(Debug, StructOpt)]
 pub enum SnapshotCommand {
     /// Create a snapshot
@@ -77,30 +201,52 @@ pub enum SnapshotCommand {
     Mount(snapshot::Mount),
     /// Unmount a snapshot
     Unmount(snapshot::Unmount),
+    /// Hold a snapshot, so it is kept by the retention policy
+    /// and can only be destroyed with --force
+    Hold {
+        /// Filesystem name
+        fsname: String,
+        /// Name of the snapshot
+        name: String,
+    },
+    /// Release a held snapshot
+    Release {
+        /// Filesystem name
+        fsname: String,
+        /// Name of the snapshot
+        name: String,
+    },
     /// List snapshots
     List {
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
         /// The filesystem to list snapshots for
         fsname: String,
     },
+    /// List the snapshots destroyed in the last 90 days, and who destroyed them
+    History {
+        /// The filesystem to list destroyed snapshots for
+        fsname: String,
+        /// Only list the snapshots with this name
+        name: Option<String>,
+    },
     /// Snapshot intervals operations
     Interval(IntervalCommand),
     /// Snapshot retention rules operations
     Retention(RetentionCommand),
+    /// Snapshot policy operations.
+    /// A policy combines the intervals and retention rule of a filesystem
+    Policy(PolicyCommand),
 }
 
-async fn interval_cli(cmd: IntervalCommand) -> Result<(), ImlManagerCliError> {
+async fn interval_cli(cmd: IntervalCommand, output: DisplayType) -> Result<(), ImlManagerCliError> {
     match cmd {
-        IntervalCommand::List { display_type } => {
+        IntervalCommand::List => {
             let query = snapshot_queries::list_intervals::build();
 
             let resp: iml_graphql_queries::Response<snapshot_queries::list_intervals::Resp> =
                 graphql(query).await?;
             let intervals = Result::from(resp)?.data.snapshot_intervals;
 
-            let x = intervals.into_display_type(display_type);
+            let x = intervals.into_display_type(output);
 
             let term = Term::stdout();
             term.write_line(&x).unwrap();
@@ -111,11 +257,17 @@ async fn interval_cli(cmd: IntervalCommand) -> Result<(), ImlManagerCliError> {
             filesystem,
             interval,
             barrier,
+            automount,
+            start_time,
+            time_zone,
         } => {
             let query = snapshot_queries::create_interval::build(
                 filesystem,
                 interval.join(" "),
                 Some(barrier),
+                Some(automount),
+                start_time,
+                time_zone,
             );
 
             let _resp: iml_graphql_queries::Response<snapshot_queries::create_interval::Resp> =
@@ -132,19 +284,39 @@ async fn interval_cli(cmd: IntervalCommand) -> Result<(), ImlManagerCliError> {
             }
             Ok(())
         }
+        IntervalCommand::Run { id } => {
+            let query = snapshot_queries::run_interval::build(id);
+
+            let resp: iml_graphql_queries::Response<snapshot_queries::run_interval::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.run_snapshot_interval;
+
+            display_output(&x, output);
+
+            display_success(format!(
+                "Snapshot {} queued to start after {}",
+                x.snapshot_name,
+                x.start_after.to_rfc2822()
+            ));
+
+            Ok(())
+        }
     }
 }
 
-async fn retention_cli(cmd: RetentionCommand) -> Result<(), ImlManagerCliError> {
+async fn retention_cli(
+    cmd: RetentionCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
     match cmd {
-        RetentionCommand::List { display_type } => {
+        RetentionCommand::List => {
             let query = snapshot_queries::list_retentions::build();
 
             let resp: iml_graphql_queries::Response<snapshot_queries::list_retentions::Resp> =
                 graphql(query).await?;
             let retentions = Result::from(resp)?.data.snapshot_retention_policies;
 
-            let x = retentions.into_display_type(display_type);
+            let x = retentions.into_display_type(output);
 
             let term = Term::stdout();
             term.write_line(&x).unwrap();
@@ -156,12 +328,18 @@ async fn retention_cli(cmd: RetentionCommand) -> Result<(), ImlManagerCliError>
             keep_num,
             reserve_value,
             reserve_unit,
+            version,
+            mdt_reserve_percent,
+            change_percent,
         } => {
             let query = snapshot_queries::create_retention::build(
                 filesystem,
                 reserve_value,
                 reserve_unit,
                 keep_num,
+                version,
+                mdt_reserve_percent,
+                change_percent,
             );
 
             let _resp: iml_graphql_queries::Response<snapshot_queries::create_retention::Resp> =
@@ -181,18 +359,348 @@ async fn retention_cli(cmd: RetentionCommand) -> Result<(), ImlManagerCliError>
     }
 }
 
-pub async fn snapshot_cli(command: SnapshotCommand) -> Result<(), ImlManagerCliError> {
-    match command {
-        SnapshotCommand::List {
-            display_type,
+fn group_policies(
+    intervals: Vec<SnapshotInterval>,
+    retentions: Vec<SnapshotRetention>,
+) -> Vec<SnapshotPolicy> {
+    let mut xs: BTreeMap<String, SnapshotPolicy> = BTreeMap::new();
+
+    for i in intervals {
+        xs.entry(i.filesystem_name.clone())
+            .or_insert_with(|| SnapshotPolicy {
+                filesystem_name: i.filesystem_name.clone(),
+                intervals: vec![],
+                retention: None,
+            })
+            .intervals
+            .push(i);
+    }
+
+    for r in retentions {
+        xs.entry(r.filesystem_name.clone())
+            .or_insert_with(|| SnapshotPolicy {
+                filesystem_name: r.filesystem_name.clone(),
+                intervals: vec![],
+                retention: None,
+            })
+            .retention = Some(r);
+    }
+
+    xs.into_iter()
+        .map(|(_, mut x)| {
+            x.intervals.sort_by_key(|i| i.interval.0);
+
+            x
+        })
+        .collect()
+}
+
+async fn get_policy(filesystem: &str) -> Result<Option<SnapshotPolicy>, ImlManagerCliError> {
+    Ok(get_policies()
+        .await?
+        .into_iter()
+        .find(|x| x.filesystem_name == filesystem))
+}
+
+async fn get_policies() -> Result<Vec<SnapshotPolicy>, ImlManagerCliError> {
+    let resp: iml_graphql_queries::Response<snapshot_queries::list_intervals::Resp> =
+        graphql(snapshot_queries::list_intervals::build()).await?;
+    let intervals = Result::from(resp)?.data.snapshot_intervals;
+
+    let resp: iml_graphql_queries::Response<snapshot_queries::list_retentions::Resp> =
+        graphql(snapshot_queries::list_retentions::build()).await?;
+    let retentions = Result::from(resp)?.data.snapshot_retention_policies;
+
+    Ok(group_policies(intervals, retentions))
+}
+
+async fn add_intervals(
+    args: &PolicyArgs,
+    intervals: Vec<String>,
+) -> Result<(), ImlManagerCliError> {
+    for interval in intervals {
+        let query = snapshot_queries::create_interval::build(
+            &args.filesystem,
+            interval,
+            Some(args.barrier),
+            Some(args.automount),
+            args.start_time.clone(),
+            args.time_zone.clone(),
+        );
+
+        let resp: iml_graphql_queries::Response<snapshot_queries::create_interval::Resp> =
+            graphql(query).await?;
+        Result::from(resp)?;
+    }
+
+    Ok(())
+}
+
+async fn remove_intervals(intervals: &[SnapshotInterval]) -> Result<(), ImlManagerCliError> {
+    for x in intervals {
+        let query = snapshot_queries::remove_interval::build(x.id);
+
+        let resp: iml_graphql_queries::Response<snapshot_queries::remove_interval::Resp> =
+            graphql(query).await?;
+        Result::from(resp)?;
+    }
+
+    Ok(())
+}
+
+async fn set_retention(args: &PolicyArgs, version: Option<i32>) -> Result<(), ImlManagerCliError> {
+    if let (Some(reserve_value), Some(reserve_unit)) = (args.reserve_value, args.reserve_unit) {
+        let query = snapshot_queries::create_retention::build(
+            &args.filesystem,
+            reserve_value,
+            reserve_unit,
+            args.keep_num,
+            version,
+            args.mdt_reserve_percent,
+            args.change_percent,
+        );
+
+        let resp: iml_graphql_queries::Response<snapshot_queries::create_retention::Resp> =
+            graphql(query).await?;
+        Result::from(resp)?;
+    }
+
+    Ok(())
+}
+
+async fn policy_cli(cmd: PolicyCommand, output: DisplayType) -> Result<(), ImlManagerCliError> {
+    match cmd {
+        PolicyCommand::List => {
+            let policies = get_policies().await?;
+
+            let x = policies.into_display_type(output);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+
+            Ok(())
+        }
+        PolicyCommand::Create(args) => {
+            if get_policy(&args.filesystem).await?.is_some() {
+                return Err(ImlManagerCliError::ApiError(format!(
+                    "A snapshot policy already exists for {}, use `update` to change it",
+                    args.filesystem
+                )));
+            }
+
+            if args.intervals.is_empty() && args.reserve_value.is_none() {
+                return Err(ImlManagerCliError::ApiError(
+                    "A snapshot policy needs at least one interval or a retention rule".into(),
+                ));
+            }
+
+            set_retention(&args, None).await?;
+            add_intervals(&args, args.intervals.clone()).await?;
+
+            Ok(())
+        }
+        PolicyCommand::Update(args) => {
+            let policy = get_policy(&args.filesystem).await?.ok_or_else(|| {
+                ImlManagerCliError::ApiError(format!(
+                    "No snapshot policy exists for {}",
+                    args.filesystem
+                ))
+            })?;
+
+            set_retention(&args, policy.retention.as_ref().map(|x| x.version)).await?;
+
+            if !args.intervals.is_empty() {
+                let wanted = args
+                    .intervals
+                    .iter()
+                    .map(|x| {
+                        GraphQLDuration::try_from(x.to_string())
+                            .map(|d| (x.to_string(), d.0))
+                            .map_err(|e| {
+                                ImlManagerCliError::ApiError(format!(
+                                    "Invalid interval {}: {}",
+                                    x, e
+                                ))
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let time_zone = args.time_zone.as_deref().unwrap_or("UTC");
+
+                let (keep, stale): (Vec<_>, Vec<_>) = policy.intervals.into_iter().partition(|i| {
+                    i.use_barrier == args.barrier
+                        && i.automount == args.automount
+                        && i.start_time == args.start_time
+                        && (i.start_time.is_none() || i.time_zone == time_zone)
+                        && wanted.iter().any(|(_, d)| *d == i.interval.0)
+                });
+
+                remove_intervals(&stale).await?;
+
+                let new = wanted
+                    .into_iter()
+                    .filter(|(_, d)| keep.iter().all(|i| i.interval.0 != *d))
+                    .map(|(x, _)| x)
+                    .collect();
+
+                add_intervals(&args, new).await?;
+            }
+
+            Ok(())
+        }
+        PolicyCommand::Delete { filesystems } => {
+            let policies = get_policies().await?;
+
+            for fs in filesystems {
+                let policy = policies
+                    .iter()
+                    .find(|x| x.filesystem_name == fs)
+                    .ok_or_else(|| {
+                        ImlManagerCliError::ApiError(format!(
+                            "No snapshot policy exists for {}",
+                            fs
+                        ))
+                    })?;
+
+                remove_intervals(&policy.intervals).await?;
+
+                if let Some(r) = &policy.retention {
+                    let query = snapshot_queries::remove_retention::build(r.id);
+
+                    let resp: iml_graphql_queries::Response<
+                        snapshot_queries::remove_retention::Resp,
+                    > = graphql(query).await?;
+                    Result::from(resp)?;
+                }
+            }
+
+            Ok(())
+        }
+        PolicyCommand::Settings {
+            max_concurrent,
+            jitter,
+        } => {
+            let x = if max_concurrent.is_none() && jitter.is_none() {
+                let query = snapshot_queries::policy_settings::build();
+
+                let resp: iml_graphql_queries::Response<snapshot_queries::policy_settings::Resp> =
+                    graphql(query).await?;
+
+                Result::from(resp)?.data.snapshot_policy_settings
+            } else {
+                let query = snapshot_queries::set_policy_settings::build(max_concurrent, jitter);
+
+                let resp: iml_graphql_queries::Response<
+                    snapshot_queries::set_policy_settings::Resp,
+                > = graphql(query).await?;
+
+                Result::from(resp)?.data.set_snapshot_policy_settings
+            };
+
+            let term = Term::stdout();
+            term.write_line(&x.into_display_type(output)).unwrap();
+
+            Ok(())
+        }
+        PolicyCommand::Export { file, filesystems } => {
+            let fsnames = if filesystems.is_empty() {
+                None
+            } else {
+                Some(filesystems)
+            };
+
+            let query = snapshot_queries::export_policies::build(fsnames);
+
+            let resp: iml_graphql_queries::Response<snapshot_queries::export_policies::Resp> =
+                graphql(query).await?;
+            let yaml = Result::from(resp)?.data.export_snapshot_policies;
+
+            if let Some(path) = file {
+                fs::write(&path, yaml).await?;
+
+                display_success(format!("Snapshot policies written to {}", path));
+            } else {
+                let term = Term::stdout();
+                term.write_str(&yaml).unwrap();
+            }
+
+            Ok(())
+        }
+        PolicyCommand::Import {
             fsname,
+            replace,
+            file,
         } => {
+            let yaml = match file {
+                Some(path) => fs::read_to_string(path).await?,
+                None => {
+                    let mut buf = String::new();
+                    stdin().read_to_string(&mut buf).await?;
+
+                    buf
+                }
+            };
+
+            let query = snapshot_queries::import_policies::build(yaml, fsname, Some(replace));
+
+            let resp: iml_graphql_queries::Response<snapshot_queries::import_policies::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.import_snapshot_policies;
+
+            display_output(&x, output);
+
+            display_success(format!(
+                "Snapshot policies applied to {}: {} interval(s) added and {} removed, \
+                 {} retention rule(s) set and {} removed",
+                x.filesystems.join(", "),
+                x.intervals_added,
+                x.intervals_removed,
+                x.retentions_set,
+                x.retentions_removed
+            ));
+
+            Ok(())
+        }
+        PolicyCommand::Runs { limit, filesystem } => {
+            let query = snapshot_queries::list_interval_runs::build(filesystem, Some(limit));
+
+            let resp: iml_graphql_queries::Response<snapshot_queries::list_interval_runs::Resp> =
+                graphql(query).await?;
+            let xs = Result::from(resp)?.data.snapshot_interval_runs;
+
+            let term = Term::stdout();
+            term.write_line(&xs.into_display_type(output)).unwrap();
+
+            Ok(())
+        }
+    }
+}
+
+pub async fn snapshot_cli(
+    command: SnapshotCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    match command {
+        SnapshotCommand::List { fsname } => {
             let query = snapshot_queries::list::build(fsname, None, None, None, Some(1_000));
             let resp: iml_graphql_queries::Response<snapshot_queries::list::Resp> =
                 graphql(query).await?;
             let snaps = Result::from(resp)?.data.snapshots;
 
-            let x = snaps.into_display_type(display_type);
+            let x = snaps.into_display_type(output);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+
+            Ok(())
+        }
+        SnapshotCommand::History { fsname, name } => {
+            let query = snapshot_queries::history::build(fsname, name.as_deref(), None);
+            let resp: iml_graphql_queries::Response<snapshot_queries::history::Resp> =
+                graphql(query).await?;
+            let xs = Result::from(resp)?.data.snapshot_history;
+
+            let x = xs.into_display_type(output);
 
             let term = Term::stdout();
             term.write_line(&x).unwrap();
@@ -206,7 +714,9 @@ pub async fn snapshot_cli(command: SnapshotCommand) -> Result<(), ImlManagerCliE
             let resp: iml_graphql_queries::Response<snapshot_queries::create::Resp> =
                 graphql(query).await?;
             let x = Result::from(resp)?.data.create_snapshot;
-            wait_for_cmds_success(&[x]).await?;
+            let cmds = wait_for_cmds_success(&[x]).await?;
+
+            display_output(&cmds, output);
 
             Ok(())
         }
@@ -216,7 +726,9 @@ pub async fn snapshot_cli(command: SnapshotCommand) -> Result<(), ImlManagerCliE
             let resp: iml_graphql_queries::Response<snapshot_queries::destroy::Resp> =
                 graphql(query).await?;
             let x = Result::from(resp)?.data.destroy_snapshot;
-            wait_for_cmds_success(&[x]).await?;
+            let cmds = wait_for_cmds_success(&[x]).await?;
+
+            display_output(&cmds, output);
 
             Ok(())
         }
@@ -225,7 +737,9 @@ pub async fn snapshot_cli(command: SnapshotCommand) -> Result<(), ImlManagerCliE
             let resp: iml_graphql_queries::Response<snapshot_queries::mount::Resp> =
                 graphql(query).await?;
             let x = Result::from(resp)?.data.mount_snapshot;
-            wait_for_cmds_success(&[x]).await?;
+            let cmds = wait_for_cmds_success(&[x]).await?;
+
+            display_output(&cmds, output);
 
             Ok(())
         }
@@ -234,11 +748,91 @@ pub async fn snapshot_cli(command: SnapshotCommand) -> Result<(), ImlManagerCliE
             let resp: iml_graphql_queries::Response<snapshot_queries::unmount::Resp> =
                 graphql(query).await?;
             let x = Result::from(resp)?.data.unmount_snapshot;
-            wait_for_cmds_success(&[x]).await?;
+            let cmds = wait_for_cmds_success(&[x]).await?;
+
+            display_output(&cmds, output);
 
             Ok(())
         }
-        SnapshotCommand::Interval(cmd) => interval_cli(cmd).await,
-        SnapshotCommand::Retention(cmd) => retention_cli(cmd).await,
+        SnapshotCommand::Hold { fsname, name } => {
+            let query = snapshot_queries::hold::build(&fsname, &name);
+            let resp: iml_graphql_queries::Response<snapshot_queries::hold::Resp> =
+                graphql(query).await?;
+            Result::from(resp)?;
+
+            display_success(format!("Held snapshot {} of {}", name, fsname));
+
+            Ok(())
+        }
+        SnapshotCommand::Release { fsname, name } => {
+            let query = snapshot_queries::release::build(&fsname, &name);
+            let resp: iml_graphql_queries::Response<snapshot_queries::release::Resp> =
+                graphql(query).await?;
+            Result::from(resp)?;
+
+            display_success(format!("Released snapshot {} of {}", name, fsname));
+
+            Ok(())
+        }
+        SnapshotCommand::Interval(cmd) => interval_cli(cmd, output).await,
+        SnapshotCommand::Retention(cmd) => retention_cli(cmd, output).await,
+        SnapshotCommand::Policy(cmd) => policy_cli(cmd, output).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use iml_wire_types::snapshot::ReserveUnit;
+    use std::time::Duration;
+
+    fn interval(id: i32, fs: &str, secs: u64) -> SnapshotInterval {
+        SnapshotInterval {
+            id,
+            filesystem_name: fs.into(),
+            use_barrier: false,
+            interval: GraphQLDuration(Duration::from_secs(secs)),
+            last_run: None,
+            automount: false,
+            start_time: None,
+            time_zone: "UTC".into(),
+            next_run: None,
+        }
+    }
+
+    #[test]
+    fn test_group_policies() {
+        let xs = group_policies(
+            vec![
+                interval(1, "fs2", 3600),
+                interval(2, "fs1", 86400),
+                interval(3, "fs1", 3600),
+            ],
+            vec![SnapshotRetention {
+                id: 1,
+                filesystem_name: "fs3".into(),
+                reserve_value: 10,
+                reserve_unit: ReserveUnit::Percent,
+                keep_num: 2,
+                last_run: None,
+                version: 1,
+                updated_at: Utc::now(),
+                crowded_by_manual: false,
+                mdt_reserve_percent: None,
+                change_percent: None,
+            }],
+        );
+
+        let names: Vec<_> = xs.iter().map(|x| x.filesystem_name.as_str()).collect();
+
+        assert_eq!(names, vec!["fs1", "fs2", "fs3"]);
+
+        let ids: Vec<_> = xs[0].intervals.iter().map(|x| x.id).collect();
+
+        assert_eq!(ids, vec![3, 2]);
+        assert!(xs[0].retention.is_none());
+        assert!(xs[2].intervals.is_empty());
+        assert_eq!(xs[2].retention.as_ref().map(|x| x.id), Some(1));
     }
 }
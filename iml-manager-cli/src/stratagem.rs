@@ -4,7 +4,7 @@
 
 use crate::{
     api_utils::{delete, first, get, graphql, post, wait_for_cmd_display},
-    display_utils::{wrap_fut, DisplayType, IntoDisplayType as _},
+    display_utils::{display_output, display_success, wrap_fut, DisplayType, IntoDisplayType as _},
     error::{
         DurationParseError, ImlManagerCliError, RunStratagemCommandResult,
         RunStratagemValidationError,
@@ -33,23 +33,146 @@ pub enum StratagemCommand {
     /// Kickoff a Stratagem Cloudsync
     #[structopt(name = "cloudsync")]
     Cloudsync(StratagemCloudsyncData),
+    /// Migrate files off over-full OSTs onto under-utilized ones
+    #[structopt(name = "rebalance")]
+    Rebalance(StratagemRebalanceData),
     /// Work with Stratagem reports
     #[structopt(name = "report")]
     Report {
         #[structopt(subcommand)]
         command: Option<ReportCommand>,
     },
+    /// Files and bytes under each top-level directory of a filesystem
+    #[structopt(name = "project-usage")]
+    ProjectUsage {
+        #[structopt(subcommand)]
+        command: ProjectUsageCommand,
+    },
+    /// Files and bytes under each directory of a filesystem by when they were last accessed
+    #[structopt(name = "heatmap")]
+    Heatmap {
+        #[structopt(subcommand)]
+        command: HeatmapCommand,
+    },
+    /// Scheduled purges of files not accessed for a while
+    #[structopt(name = "purge-policy")]
+    PurgePolicy {
+        #[structopt(subcommand)]
+        command: PurgePolicyCommand,
+    },
 }
 
 #[derive(Debug, StructOpt)]
-pub enum ReportCommand {
-    /// List all existing Stratagem reports (default)
+pub enum ProjectUsageCommand {
+    /// Scan a filesystem and add up the usage of each top-level directory
+    #[structopt(name = "scan")]
+    Scan {
+        /// The filesystem to scan
+        filesystem: String,
+    },
+    /// Show the usage found by the most recent scans
     #[structopt(name = "list")]
     List {
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
+        /// The filesystem to show
+        filesystem: String,
+        /// How many scans to show
+        #[structopt(short = "l", long = "limit", default_value = "1")]
+        limit: i32,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum HeatmapCommand {
+    /// Scan a filesystem and add up each directory by when its files were last accessed
+    #[structopt(name = "scan")]
+    Scan {
+        /// The filesystem to scan
+        filesystem: String,
+        /// How many levels of directories below the root to add up
+        #[structopt(long = "max-depth", default_value = "3")]
+        max_depth: i32,
+    },
+    /// Show the directories found by the most recent scan
+    #[structopt(name = "show")]
+    Show {
+        /// The filesystem to show
+        filesystem: String,
+        /// How many levels of directories below the root to show
+        #[structopt(long = "depth", default_value = "1")]
+        depth: i32,
     },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum PurgePolicyCommand {
+    /// Show the purge policy of a filesystem
+    #[structopt(name = "show")]
+    Show {
+        /// The filesystem of the policy
+        filesystem: String,
+    },
+    /// Create or replace the purge policy of a filesystem
+    #[structopt(name = "set")]
+    Set {
+        /// The filesystem to purge
+        filesystem: String,
+        /// How often to scan the filesystem, e.g. 1day
+        #[structopt(short = "i", long = "interval", default_value = "1day")]
+        interval: String,
+        /// Purge files not accessed for this long, e.g. 90days
+        #[structopt(short = "a", long = "purge-after")]
+        purge_after: String,
+        /// The most files one run purges
+        #[structopt(long = "max-files")]
+        max_files: u64,
+        /// The most bytes one run purges
+        #[structopt(long = "max-bytes")]
+        max_bytes: u64,
+        /// Never purge files under this directory, relative to the root of the filesystem.
+        /// Can be given several times
+        #[structopt(short = "p", long = "protect", number_of_values = 1)]
+        protected_paths: Vec<String>,
+        /// Purge without waiting for each run to be approved
+        #[structopt(long = "no-approval")]
+        no_approval: bool,
+        /// Keep the policy but stop scanning
+        #[structopt(long = "disable")]
+        disable: bool,
+    },
+    /// Remove the purge policy of a filesystem
+    #[structopt(name = "remove")]
+    Remove {
+        /// The filesystem of the policy
+        filesystem: String,
+    },
+    /// List the latest runs of the purge policy of a filesystem
+    #[structopt(name = "runs")]
+    Runs {
+        /// The filesystem of the policy
+        filesystem: String,
+        /// How many runs to show
+        #[structopt(short = "l", long = "limit", default_value = "10")]
+        limit: i32,
+    },
+    /// Purge the files selected by a run waiting for approval
+    #[structopt(name = "approve")]
+    Approve {
+        /// The id of the run
+        id: i32,
+    },
+    /// Drop a run waiting for approval without purging anything
+    #[structopt(name = "reject")]
+    Reject {
+        /// The id of the run
+        id: i32,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ReportCommand {
+    /// List all existing Stratagem reports (default)
+    #[structopt(name = "list")]
+    List,
     /// Delete Stratagem reports
     #[structopt(name = "remove")]
     Delete {
@@ -63,11 +186,7 @@ pub enum ReportCommand {
 pub enum IntervalCommand {
     /// List all existing Stratagem intervals
     #[structopt(name = "list")]
-    List {
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
-    },
+    List,
     /// Create Stratagem scan interval
     #[structopt(name = "create")]
     Create(IntervalCommandConfig),
@@ -109,6 +228,9 @@ pub struct StratagemScanData {
     /// EX: 1hour
     #[structopt(short = "p", long = "purge", min_values = 1)]
     purge_duration: Option<Vec<String>>,
+    /// The name of a saved rule set to apply during the scan
+    #[structopt(long = "rule-set")]
+    rule_set: Option<String>,
 }
 
 arg_enum! {
@@ -163,6 +285,18 @@ pub struct StratagemCloudsyncData {
     files: Vec<PathBuf>,
 }
 
+#[derive(serde::Serialize, StructOpt, Debug)]
+pub struct StratagemRebalanceData {
+    /// The name of the filesystem to rebalance
+    filesystem: String,
+    /// How many percentage points above the filesystem usage an OST must be to be rebalanced
+    #[structopt(short = "t", long = "threshold", default_value = "10")]
+    threshold: f64,
+    /// Only migrate files of at least this many MiB
+    #[structopt(short = "m", long = "min-size")]
+    min_size: Option<i32>,
+}
+
 fn parse_duration(src: &str) -> Result<u64, ImlManagerCliError> {
     if src.len() < 2 {
         return Err(DurationParseError::InvalidValue.into());
@@ -247,16 +381,16 @@ fn list_stratagem_configurations(
     term.write_line(&x).unwrap();
 }
 
-async fn report_cli(cmd: ReportCommand) -> Result<(), ImlManagerCliError> {
+async fn report_cli(cmd: ReportCommand, output: DisplayType) -> Result<(), ImlManagerCliError> {
     match cmd {
-        ReportCommand::List { display_type } => {
+        ReportCommand::List => {
             let query = stratagem_queries::list_reports::build();
 
             let resp: iml_graphql_queries::Response<stratagem_queries::list_reports::Resp> =
                 graphql(query).await?;
             let reports = Result::from(resp)?.data.stratagem.stratagem_reports;
 
-            let x = reports.into_display_type(display_type);
+            let x = reports.into_display_type(output);
 
             let term = Term::stdout();
             term.write_line(&x).unwrap();
@@ -275,9 +409,188 @@ async fn report_cli(cmd: ReportCommand) -> Result<(), ImlManagerCliError> {
     }
 }
 
-async fn interval_cli(cmd: IntervalCommand) -> Result<(), ImlManagerCliError> {
+async fn project_usage_cli(
+    cmd: ProjectUsageCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    match cmd {
+        ProjectUsageCommand::Scan { filesystem } => {
+            let query = stratagem_queries::project_usage_scan::build(filesystem);
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::project_usage_scan::Resp> =
+                graphql(query).await?;
+
+            let command = Result::from(resp)?.data.stratagem.run_project_usage_scan;
+
+            let cmd = wait_for_cmd_display(command).await?;
+
+            display_output(&cmd, output);
+
+            eprintln!("The usage is stored once the clients have processed every file.");
+        }
+        ProjectUsageCommand::List { filesystem, limit } => {
+            let query = stratagem_queries::project_usage::build(&filesystem, Some(limit));
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::project_usage::Resp> =
+                wrap_fut("Fetching project usage...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.stratagem.project_usage;
+
+            if xs.is_empty() && output.is_tabular() {
+                eprintln!("No project usage found for {}", filesystem);
+            } else {
+                let term = Term::stdout();
+                term.write_line(&xs.into_display_type(output)).unwrap();
+            }
+        }
+    };
+
+    Ok(())
+}
+
+async fn heatmap_cli(cmd: HeatmapCommand, output: DisplayType) -> Result<(), ImlManagerCliError> {
+    match cmd {
+        HeatmapCommand::Scan {
+            filesystem,
+            max_depth,
+        } => {
+            let query = stratagem_queries::heatmap_scan::build(filesystem, Some(max_depth));
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::heatmap_scan::Resp> =
+                graphql(query).await?;
+
+            let command = Result::from(resp)?.data.stratagem.run_heatmap_scan;
+
+            let cmd = wait_for_cmd_display(command).await?;
+
+            display_output(&cmd, output);
+
+            eprintln!("The heat map is stored once the clients have processed every file.");
+        }
+        HeatmapCommand::Show { filesystem, depth } => {
+            let query = stratagem_queries::fs_heatmap::build(&filesystem, Some(depth));
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::fs_heatmap::Resp> =
+                wrap_fut("Fetching heat map...", graphql(query)).await?;
+
+            match Result::from(resp)?.data.stratagem.fs_heatmap {
+                Some(x) => {
+                    let term = Term::stdout();
+                    term.write_line(&x.into_display_type(output)).unwrap();
+                }
+                None => eprintln!("No heat map found for {}", filesystem),
+            }
+        }
+    };
+
+    Ok(())
+}
+
+async fn purge_policy_cli(
+    cmd: PurgePolicyCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
+    match cmd {
+        PurgePolicyCommand::Show { filesystem } => {
+            let query = stratagem_queries::purge_policy::build(&filesystem);
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::purge_policy::Resp> =
+                wrap_fut("Fetching purge policy...", graphql(query)).await?;
+
+            match Result::from(resp)?.data.stratagem.purge_policy {
+                Some(x) => {
+                    let term = Term::stdout();
+                    term.write_line(&x.into_display_type(output)).unwrap();
+                }
+                None => eprintln!("No purge policy found for {}", filesystem),
+            }
+        }
+        PurgePolicyCommand::Set {
+            filesystem,
+            interval,
+            purge_after,
+            max_files,
+            max_bytes,
+            protected_paths,
+            no_approval,
+            disable,
+        } => {
+            let query = stratagem_queries::set_purge_policy::build(
+                filesystem,
+                interval,
+                purge_after,
+                max_files as f64,
+                max_bytes as f64,
+                Some(protected_paths),
+                Some(!no_approval),
+                Some(!disable),
+            );
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::set_purge_policy::Resp> =
+                wrap_fut("Setting purge policy...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.stratagem.set_purge_policy;
+
+            let term = Term::stdout();
+            term.write_line(&x.into_display_type(output)).unwrap();
+        }
+        PurgePolicyCommand::Remove { filesystem } => {
+            let query = stratagem_queries::remove_purge_policy::build(&filesystem);
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::remove_purge_policy::Resp> =
+                wrap_fut("Removing purge policy...", graphql(query)).await?;
+
+            if Result::from(resp)?.data.stratagem.remove_purge_policy {
+                display_success(format!("Removed the purge policy of {}", filesystem));
+            } else {
+                eprintln!("No purge policy found for {}", filesystem);
+            }
+        }
+        PurgePolicyCommand::Runs { filesystem, limit } => {
+            let query = stratagem_queries::purge_runs::build(&filesystem, Some(limit));
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::purge_runs::Resp> =
+                wrap_fut("Fetching purge runs...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.stratagem.purge_runs;
+
+            if xs.is_empty() && output.is_tabular() {
+                eprintln!("No purge runs found for {}", filesystem);
+            } else {
+                let term = Term::stdout();
+                term.write_line(&xs.into_display_type(output)).unwrap();
+            }
+        }
+        PurgePolicyCommand::Approve { id } => {
+            let query = stratagem_queries::approve_purge_run::build(id);
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::approve_purge_run::Resp> =
+                graphql(query).await?;
+
+            let x = Result::from(resp)?.data.stratagem.approve_purge_run;
+
+            let term = Term::stdout();
+            term.write_line(&vec![x].into_display_type(output)).unwrap();
+        }
+        PurgePolicyCommand::Reject { id } => {
+            let query = stratagem_queries::reject_purge_run::build(id);
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::reject_purge_run::Resp> =
+                graphql(query).await?;
+
+            let x = Result::from(resp)?.data.stratagem.reject_purge_run;
+
+            let term = Term::stdout();
+            term.write_line(&vec![x].into_display_type(output)).unwrap();
+        }
+    };
+
+    Ok(())
+}
+
+async fn interval_cli(cmd: IntervalCommand, output: DisplayType) -> Result<(), ImlManagerCliError> {
     match cmd {
-        IntervalCommand::List { display_type } => {
+        IntervalCommand::List => {
             let r: ApiList<StratagemConfiguration> = wrap_fut(
                 "Finding existing intervals...",
                 get(
@@ -287,10 +600,10 @@ async fn interval_cli(cmd: IntervalCommand) -> Result<(), ImlManagerCliError> {
             )
             .await?;
 
-            if r.objects.is_empty() {
-                println!("No Stratagem intervals found");
+            if r.objects.is_empty() && output.is_tabular() {
+                eprintln!("No Stratagem intervals found");
             } else {
-                list_stratagem_configurations(r.objects, display_type);
+                list_stratagem_configurations(r.objects, output);
             }
             Ok(())
         }
@@ -299,7 +612,9 @@ async fn interval_cli(cmd: IntervalCommand) -> Result<(), ImlManagerCliError> {
 
             let CmdWrapper { command } = handle_cmd_resp(r).await?;
 
-            wait_for_cmd_display(command).await?;
+            let cmd = wait_for_cmd_display(command).await?;
+
+            display_output(&cmd, output);
             Ok(())
         }
         IntervalCommand::Remove(StratagemRemoveData { filesystem }) => {
@@ -313,19 +628,25 @@ async fn interval_cli(cmd: IntervalCommand) -> Result<(), ImlManagerCliError> {
 
             let CmdWrapper { command } = handle_cmd_resp(r).await?;
 
-            wait_for_cmd_display(command).await?;
+            let cmd = wait_for_cmd_display(command).await?;
+
+            display_output(&cmd, output);
             Ok(())
         }
     }
 }
 
-pub async fn stratagem_cli(command: StratagemCommand) -> Result<(), ImlManagerCliError> {
+pub async fn stratagem_cli(
+    command: StratagemCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
     match command {
         StratagemCommand::Scan(data) => {
             let query = stratagem_queries::fast_file_scan::build(
                 &data.filesystem,
                 data.report_duration.map(|xs| xs.join(" ")),
                 data.purge_duration.map(|xs| xs.join(" ")),
+                data.rule_set,
             );
 
             let resp: iml_graphql_queries::Response<stratagem_queries::fast_file_scan::Resp> =
@@ -333,7 +654,9 @@ pub async fn stratagem_cli(command: StratagemCommand) -> Result<(), ImlManagerCl
 
             let command = Result::from(resp)?.data.stratagem.run_fast_file_scan;
 
-            wait_for_cmd_display(command).await?;
+            let cmd = wait_for_cmd_display(command).await?;
+
+            display_output(&cmd, output);
         }
         StratagemCommand::Filesync(data) => match data.expression {
             Some(ref exp) => {
@@ -349,7 +672,9 @@ pub async fn stratagem_cli(command: StratagemCommand) -> Result<(), ImlManagerCl
 
                 let command = Result::from(resp)?.data.stratagem.run_filesync;
 
-                wait_for_cmd_display(command).await?;
+                let cmd = wait_for_cmd_display(command).await?;
+
+                display_output(&cmd, output);
             }
             None => {
                 #[derive(Serialize, Debug)]
@@ -412,7 +737,9 @@ pub async fn stratagem_cli(command: StratagemCommand) -> Result<(), ImlManagerCl
 
                 tracing::debug!("run_cloudsync: {:?}", command);
 
-                wait_for_cmd_display(command).await?;
+                let cmd = wait_for_cmd_display(command).await?;
+
+                display_output(&cmd, output);
             }
             None => {
                 #[derive(Serialize, Debug)]
@@ -459,12 +786,28 @@ pub async fn stratagem_cli(command: StratagemCommand) -> Result<(), ImlManagerCl
                     graphql(query).await?;
             }
         },
-        StratagemCommand::Interval(cmd) => interval_cli(cmd).await?,
+        StratagemCommand::Rebalance(data) => {
+            let query = stratagem_queries::rebalance_osts::build(
+                &data.filesystem,
+                data.threshold,
+                data.min_size,
+            );
+
+            let resp: iml_graphql_queries::Response<stratagem_queries::rebalance_osts::Resp> =
+                graphql(query).await?;
+
+            let command = Result::from(resp)?.data.stratagem.rebalance_osts;
+
+            let cmd = wait_for_cmd_display(command).await?;
+
+            display_output(&cmd, output);
+        }
+        StratagemCommand::Interval(cmd) => interval_cli(cmd, output).await?,
+        StratagemCommand::ProjectUsage { command } => project_usage_cli(command, output).await?,
+        StratagemCommand::Heatmap { command } => heatmap_cli(command, output).await?,
+        StratagemCommand::PurgePolicy { command } => purge_policy_cli(command, output).await?,
         StratagemCommand::Report { command } => {
-            report_cli(command.unwrap_or(ReportCommand::List {
-                display_type: DisplayType::Tabular,
-            }))
-            .await?
+            report_cli(command.unwrap_or(ReportCommand::List), output).await?
         }
     };
 
@@ -4,46 +4,168 @@
 
 use crate::{
     api_utils::{get_hosts, graphql},
-    display_utils::{wrap_fut, DisplayType, IntoDisplayType as _},
+    display_utils::{display_output, display_success, wrap_fut, DisplayType, IntoDisplayType as _},
     error::ImlManagerCliError,
 };
 use console::Term;
 use iml_graphql_queries::target as target_queries;
-use iml_wire_types::{ApiList, Host};
+use iml_wire_types::{db::TargetRecord, ApiList, Host};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
 pub enum TargetCommand {
     /// List known targets
     List {
-        /// Display type: json, yaml, tabular
-        #[structopt(short = "d", long = "display", default_value = "tabular")]
-        display_type: DisplayType,
         /// Optionally filter by filesystem name
         fsname: Option<String>,
+        /// Optionally filter by device path (e.g. /dev/mapper/mpatha)
+        #[structopt(long = "dev-path")]
+        dev_path: Option<String>,
+        /// Optionally filter by device serial
+        #[structopt(long = "serial")]
+        serial: Option<String>,
+        /// Optionally filter by the fqdn of the host currently running the target
+        #[structopt(long = "host")]
+        fqdn: Option<String>,
+        /// Optionally filter by tags of the hosts the target can run on, e.g. --host-tag rack=12
+        #[structopt(long = "host-tag", number_of_values = 1)]
+        host_tags: Vec<String>,
+    },
+    /// Show the paths each host has to the multipath device of a target
+    Multipath {
+        /// The uuid of the target
+        uuid: String,
+    },
+    /// Show duplicate target uuids, unexpected device path changes and targets on removed hosts
+    Integrity,
+    /// Accept the current device path of a target after it changed
+    #[structopt(name = "ack-dev-path")]
+    AckDevPath {
+        /// The uuid of the target
+        uuid: String,
     },
 }
 
-pub async fn target_cli(command: TargetCommand) -> Result<(), ImlManagerCliError> {
+pub async fn target_cli(
+    command: TargetCommand,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
     match command {
         TargetCommand::List {
-            display_type,
             fsname,
+            dev_path,
+            serial,
+            fqdn,
+            host_tags,
         } => {
-            let query = target_queries::list::build(None, None, None, fsname, None);
+            let filters = target_queries::list::Filters {
+                dev_path,
+                serial,
+                fqdn,
+                host_tags: if host_tags.is_empty() {
+                    None
+                } else {
+                    Some(host_tags)
+                },
+            };
+
+            let targets = wrap_fut("Fetching targets...", get_all_targets(fsname, filters)).await?;
+
+            // The hosts only name the active host of each target in the table
+            if !output.is_tabular() {
+                display_output(&targets, output);
+
+                return Ok(());
+            }
 
             let hosts: ApiList<Host> = wrap_fut("Fetching hosts...", get_hosts()).await?;
 
-            let resp: iml_graphql_queries::Response<target_queries::list::Resp> =
-                wrap_fut("Fetching targets...", graphql(query)).await?;
+            let x = (hosts.objects, targets).into_display_type(output);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+
+            Ok(())
+        }
+        TargetCommand::Multipath { uuid } => {
+            let query = target_queries::multipath::build(uuid, None);
+
+            let resp: iml_graphql_queries::Response<target_queries::multipath::Resp> =
+                wrap_fut("Fetching multipath status...", graphql(query)).await?;
+
+            let x = Result::from(resp)?
+                .data
+                .target_multipath
+                .into_display_type(output);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+
+            Ok(())
+        }
+        TargetCommand::Integrity => {
+            let query = target_queries::integrity_report::build();
+
+            let resp: iml_graphql_queries::Response<target_queries::integrity_report::Resp> =
+                wrap_fut("Fetching integrity report...", graphql(query)).await?;
 
-            let targets = Result::from(resp)?.data.targets;
-            let x = (hosts.objects, targets).into_display_type(display_type);
+            let x = Result::from(resp)?
+                .data
+                .integrity_report
+                .into_display_type(output);
 
             let term = Term::stdout();
             term.write_line(&x).unwrap();
 
             Ok(())
         }
+        TargetCommand::AckDevPath { uuid } => {
+            let query = target_queries::acknowledge_dev_path::build(&uuid);
+
+            let resp: iml_graphql_queries::Response<target_queries::acknowledge_dev_path::Resp> =
+                graphql(query).await?;
+
+            Result::from(resp)?;
+
+            display_success(format!("Acknowledged the device path of {}", uuid));
+
+            Ok(())
+        }
+    }
+}
+
+/// Fetches every target matching `fsname` and `filters`,
+/// a page at a time as the manager caps how many are returned at once.
+pub(crate) async fn get_all_targets(
+    fsname: Option<String>,
+    filters: target_queries::list::Filters,
+) -> Result<Vec<TargetRecord>, ImlManagerCliError> {
+    let mut xs = vec![];
+
+    loop {
+        let query = target_queries::list::build(
+            None,
+            Some(xs.len() as u32),
+            None,
+            fsname.as_ref(),
+            None,
+            filters.clone(),
+        );
+
+        let resp: iml_graphql_queries::Response<target_queries::list::Resp> =
+            graphql(query).await?;
+
+        let page = Result::from(resp)?.data.targets;
+
+        // An estimated count can be short of the actual one
+        let done = page.data.is_empty()
+            || (!page.meta.is_estimate
+                && (page.meta.offset as usize) + page.data.len() >= page.meta.total_count as usize);
+
+        xs.extend(page.data);
+
+        if done {
+            return Ok(xs);
+        }
     }
 }
@@ -4,7 +4,7 @@
 
 use crate::{
     api_utils::{create_command, get_hosts, wait_for_cmds_success, SendCmd, SendJob},
-    display_utils::{display_cancelled, display_error, wrap_fut},
+    display_utils::{display_cancelled, display_error, display_output, wrap_fut, DisplayType},
     error::ImlManagerCliError,
     parse_hosts,
 };
@@ -41,7 +41,10 @@ pub struct HostId {
     host_id: i32,
 }
 
-pub async fn update_repo_file_cli(config: UpdateRepoFileHosts) -> Result<(), ImlManagerCliError> {
+pub async fn update_repo_file_cli(
+    config: UpdateRepoFileHosts,
+    output: DisplayType,
+) -> Result<(), ImlManagerCliError> {
     let r = parse_hosts(&config.hosts)?;
 
     tracing::debug!("Parsed hosts {:?}", r);
@@ -75,7 +78,9 @@ pub async fn update_repo_file_cli(config: UpdateRepoFileHosts) -> Result<(), Iml
 
     let cmd = wrap_fut("Updating Repo files...", create_command(cmd)).await?;
 
-    wait_for_cmds_success(&[cmd]).await?;
+    let cmds = wait_for_cmds_success(&[cmd]).await?;
+
+    display_output(&cmds, output);
 
     Ok(())
 }
@@ -77,6 +77,135 @@ pub fn get_allow_anonymous_read() -> bool {
     string_to_bool(get_var("ALLOW_ANONYMOUS_READ"))
 }
 
+/// Get the session idle timeout in seconds from the env.
+/// `None` when unset or disabled with 0.
+pub fn get_session_idle_timeout() -> Option<u64> {
+    env::var("SESSION_IDLE_TIMEOUT")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0)
+}
+
+/// Get how many days ahead a filesystem projected to fill up raises an alert.
+/// `None` when unset or disabled with 0.
+pub fn get_capacity_alert_days() -> Option<u32> {
+    env::var("CAPACITY_ALERT_DAYS")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0)
+}
+
+/// Get how many days ahead an expiring agent certificate raises an alert.
+/// `None` when unset or disabled with 0.
+pub fn get_certificate_alert_days() -> Option<u32> {
+    env::var("CERTIFICATE_ALERT_DAYS")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0)
+}
+
+/// Get how many heartbeats an agent can miss before an alert is raised.
+/// `None` when unset or disabled with 0.
+pub fn get_heartbeat_alert_missed() -> Option<u32> {
+    env::var("HEARTBEAT_ALERT_MISSED")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0)
+}
+
+/// Get how many hours apart the stonith devices of every cluster are tested.
+/// `None` when disabled with 0, 24 when unset.
+pub fn get_stonith_test_interval_hours() -> Option<u32> {
+    env::var("STONITH_TEST_INTERVAL_HOURS")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .or(Some(24))
+        .filter(|x| *x > 0)
+}
+
+/// Get how many MiB of WAL a standby manager can have left to replay before an alert is raised.
+/// `None` when unset or disabled with 0.
+pub fn get_standby_alert_lag_mb() -> Option<u32> {
+    env::var("STANDBY_ALERT_LAG_MB")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0)
+}
+
+/// Get the name this manager's site is shown under in the federated overview.
+/// Defaults to `local` when unset.
+pub fn get_federation_site_name() -> String {
+    env::var("FEDERATION_SITE_NAME")
+        .ok()
+        .filter(|x| !x.is_empty())
+        .unwrap_or_else(|| "local".to_string())
+}
+
+/// Get the most rows a paged GraphQL query returns at once.
+/// Defaults to 1000 when unset or 0.
+pub fn get_graphql_max_page_size() -> u32 {
+    env::var("GRAPHQL_MAX_PAGE_SIZE")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0)
+        .unwrap_or(1000)
+}
+
+/// Get how many rows a table can have before paged GraphQL queries
+/// estimate its row count rather than count it. Defaults to 100000 when unset or 0.
+pub fn get_graphql_exact_count_threshold() -> i64 {
+    env::var("GRAPHQL_EXACT_COUNT_THRESHOLD")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0)
+        .unwrap_or(100_000)
+}
+
+/// Get how many per-item futures a GraphQL resolver runs at once,
+/// e.g. one agent call per host. Defaults to 4 when unset or 0.
+pub fn get_graphql_fan_out_limit() -> usize {
+    env::var("GRAPHQL_FAN_OUT_LIMIT")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0)
+        .unwrap_or(4)
+}
+
+/// Get how many hours queries run in the background are kept along with their results.
+/// Defaults to 24 when unset or 0.
+pub fn get_deferred_query_ttl_hours() -> u32 {
+    env::var("DEFERRED_QUERY_TTL_HOURS")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0)
+        .unwrap_or(24)
+}
+
+/// Get the least free space, in percent, each MDT needs before a snapshot is taken.
+/// `None` when unset or disabled with 0.
+pub fn get_snapshot_min_mdt_free_percent() -> Option<u32> {
+    env::var("SNAPSHOT_MIN_MDT_FREE_PERCENT")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| *x > 0 && *x <= 100)
+}
+
+/// Get the OTLP collector spans are exported to, e.g. `http://localhost:4317`.
+/// Spans are not exported when unset.
+pub fn get_otlp_endpoint() -> Option<String> {
+    env::var("OTLP_ENDPOINT").ok().and_then(empty_str_to_none)
+}
+
+/// Get the ratio of traces that are exported, between 0 and 1.
+/// Defaults to 1 when unset or invalid.
+pub fn get_otlp_sample_ratio() -> f64 {
+    env::var("OTLP_SAMPLE_RATIO")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .filter(|x| (0.0..=1.0).contains(x))
+        .unwrap_or(1.0)
+}
+
 /// Get build num from the env or panic
 pub fn get_build() -> String {
     get_var("BUILD")
@@ -71,3 +71,46 @@ pub async fn raise(
 
     Ok(())
 }
+
+/// Records an event.
+/// Events are alerts that end as soon as they are raised, they only show up in the history.
+/// `msg` is also stored as the `message_str` variant used by the python event classes.
+pub async fn event(
+    pool: &PgPool,
+    record_type: AlertRecordType,
+    msg: String,
+    item_content_type_id: i32,
+    severity: AlertSeverity,
+    item_id: i32,
+) -> Result<(), sqlx::Error> {
+    let record_type = record_type.to_string();
+    let severity: i32 = severity.into();
+
+    sqlx::query!(
+        r#"INSERT INTO chroma_core_alertstate
+        (
+            record_type,
+            variant,
+            alert_item_id,
+            alert_type,
+            begin,
+            "end",
+            message,
+            active,
+            dismissed,
+            severity,
+            alert_item_type_id
+        )
+        VALUES ($1, json_build_object('message_str', $3::TEXT)::TEXT, $2, $1, now(), now(), $3, Null, false, $4, $5)
+        "#,
+        &record_type,
+        item_id,
+        msg,
+        severity,
+        item_content_type_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -13,7 +13,7 @@ use iml_manager_env::get_db_conn_string;
 use iml_wire_types::Fqdn;
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 pub use sqlx::{self, postgres::PgPool};
-use std::{pin::Pin, sync::Arc};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
 pub use tokio_postgres::{
     error::DbError,
     row::Row,
@@ -111,6 +111,25 @@ pub async fn fqdn_by_host_id(pool: &PgPool, id: i32) -> Result<String, sqlx::Err
     Ok(fqdn)
 }
 
+/// Batched version of `fqdn_by_host_id`.
+/// Ids of missing or deleted hosts are left out of the result.
+pub async fn fqdns_by_host_ids(
+    pool: &PgPool,
+    ids: &[i32],
+) -> Result<HashMap<i32, String>, sqlx::Error> {
+    let xs = sqlx::query!(
+        r#"SELECT id, fqdn FROM chroma_core_managedhost WHERE id = ANY($1) and not_deleted = 't'"#,
+        ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.id, x.fqdn))
+    .collect();
+
+    Ok(xs)
+}
+
 pub async fn host_id_by_fqdn(fqdn: &Fqdn, pool: &PgPool) -> Result<Option<i32>, sqlx::Error> {
     let id = sqlx::query!(
         "select id from chroma_core_managedhost where fqdn = $1 and not_deleted = 't'",
@@ -4,7 +4,7 @@
 
 use iml_postgres::{sqlx, PgPool};
 use iml_service_queue::service_queue::ImlServiceQueueError;
-use iml_wire_types::high_availability::{Ban, Node, Resource};
+use iml_wire_types::high_availability::{Ban, HaConfig, Node, Resource};
 use std::{collections::HashMap, fmt};
 use thiserror::Error;
 
@@ -583,3 +583,65 @@ pub async fn update_chroma_ticket(
 
     Ok(())
 }
+
+/// Stores the configuration `host_id` reported and returns whether it drifted from its baseline.
+///
+/// The baseline is taken when the host first reports, when it moves to another cluster,
+/// and while IML jobs run, since those are the changes IML makes itself.
+pub async fn upsert_cluster_config(
+    host_id: i32,
+    cluster_id: i32,
+    config: &HaConfig,
+    pool: &PgPool,
+) -> Result<bool, ImlCorosyncError> {
+    let jobs_running = sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM chroma_core_job WHERE state != 'complete') AS "running!""#
+    )
+    .fetch_one(pool)
+    .await?
+    .running;
+
+    let x = sqlx::query!(
+        r#"
+            INSERT INTO cluster_config
+            (host_id, cluster_id, cib, corosync_conf, hash, baseline_cib, baseline_corosync_conf, baseline_hash)
+            VALUES ($1, $2, $3, $4, md5($3 || $4), $3, $4, md5($3 || $4))
+            ON CONFLICT (host_id) DO UPDATE
+            SET
+                cib = EXCLUDED.cib,
+                corosync_conf = EXCLUDED.corosync_conf,
+                hash = EXCLUDED.hash,
+                changed_at = CASE
+                    WHEN cluster_config.hash = EXCLUDED.hash THEN cluster_config.changed_at
+                    ELSE now()
+                END,
+                cluster_id = EXCLUDED.cluster_id,
+                baseline_cib = CASE
+                    WHEN $5 OR cluster_config.cluster_id != EXCLUDED.cluster_id THEN EXCLUDED.cib
+                    ELSE cluster_config.baseline_cib
+                END,
+                baseline_corosync_conf = CASE
+                    WHEN $5 OR cluster_config.cluster_id != EXCLUDED.cluster_id THEN EXCLUDED.corosync_conf
+                    ELSE cluster_config.baseline_corosync_conf
+                END,
+                baseline_hash = CASE
+                    WHEN $5 OR cluster_config.cluster_id != EXCLUDED.cluster_id THEN EXCLUDED.hash
+                    ELSE cluster_config.baseline_hash
+                END,
+                baselined_at = CASE
+                    WHEN $5 OR cluster_config.cluster_id != EXCLUDED.cluster_id THEN now()
+                    ELSE cluster_config.baselined_at
+                END
+            RETURNING hash != baseline_hash AS "drifted!"
+        "#,
+        host_id,
+        cluster_id,
+        config.cib,
+        config.corosync_conf,
+        jobs_running
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(x.drifted)
+}
@@ -5,16 +5,16 @@
 use futures::TryStreamExt;
 use iml_corosync::{
     delete_cluster, delete_corosync_resource_bans, delete_nodes, delete_target_resources,
-    fetch_corosync_cluster_by_nodes, update_chroma_ticket, upsert_cluster_nodes,
-    upsert_corosync_cluster, upsert_node_managed_host, upsert_resource_bans,
+    fetch_corosync_cluster_by_nodes, update_chroma_ticket, upsert_cluster_config,
+    upsert_cluster_nodes, upsert_corosync_cluster, upsert_node_managed_host, upsert_resource_bans,
     upsert_target_resource_managed_host, upsert_target_resources, CorosyncNodeKey,
     ImlCorosyncError,
 };
 use iml_manager_env::get_pool_limit;
-use iml_postgres::{get_db_pool, host_id_by_fqdn, sqlx};
+use iml_postgres::{alert, get_db_pool, host_id_by_fqdn, sqlx};
 use iml_service_queue::service_queue::consume_data;
 use iml_tracing::tracing;
-use iml_wire_types::high_availability::Cluster;
+use iml_wire_types::{high_availability::Cluster, AlertRecordType, AlertSeverity};
 use std::collections::BTreeSet;
 
 // Default pool limit if not overridden by POOL_LIMIT
@@ -106,6 +106,40 @@ async fn main() -> Result<(), ImlCorosyncError> {
         upsert_node_managed_host(host_id, cluster_id, local_node_key, &pool).await?;
 
         upsert_target_resource_managed_host(host_id, cluster_id, &resource_ids, &pool).await?;
+
+        if let Some(config) = cluster.config {
+            let drifted = upsert_cluster_config(host_id, cluster_id, &config, &pool).await?;
+
+            if drifted {
+                let content_type_id = sqlx::query!(
+                    "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'managedhost'"
+                )
+                .fetch_one(&pool)
+                .await?
+                .id;
+
+                alert::raise(
+                    &pool,
+                    AlertRecordType::ClusterConfigDriftAlert,
+                    format!(
+                        "The cluster configuration of {} was changed outside of IML",
+                        fqdn
+                    ),
+                    content_type_id,
+                    None,
+                    AlertSeverity::WARNING,
+                    host_id,
+                )
+                .await?;
+            } else {
+                alert::lower(
+                    &pool,
+                    vec![AlertRecordType::ClusterConfigDriftAlert],
+                    host_id,
+                )
+                .await?;
+            }
+        }
     }
 
     Ok(())
@@ -15,6 +15,8 @@ use regex::Regex;
 use std::collections::HashMap;
 use thiserror::Error;
 
+pub mod log_alert;
+
 #[derive(Error, Debug)]
 pub enum ImlJournalError {
     #[error(transparent)]
@@ -0,0 +1,307 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Alert rules on log message patterns.
+//!
+//! Rules are evaluated as messages are ingested, both from the agents and through the
+//! ingestion API of `iml-api`. Each match is recorded in `log_alert_match`, and a rule that
+//! matched at least `min_matches` messages within `within` has a `LogPatternAlert` raised.
+//! Lowering the alert once the rule no longer matches that often is left to `iml-api`,
+//! as it has to happen even when no messages come in.
+
+use crate::ImlJournalError;
+use chrono::{DateTime, Utc};
+use iml_postgres::{
+    alert,
+    sqlx::{self, PgPool},
+};
+use iml_wire_types::{log_alert::LogAlertRule, AlertRecordType, AlertSeverity, LogSeverity};
+use regex::{Regex, RegexBuilder};
+use std::{convert::TryFrom, time::Duration};
+
+/// The most memory a compiled pattern may use.
+const PATTERN_SIZE_LIMIT: usize = 1 << 20;
+
+/// How much of the latest matching message is put in the alert.
+const MAX_ALERT_MESSAGE_LEN: usize = 256;
+
+/// A log message as it is ingested.
+pub struct LogLine<'a> {
+    pub fqdn: &'a str,
+    pub tag: &'a str,
+    pub severity: i16,
+    pub message: &'a str,
+}
+
+/// A rule along with its compiled patterns.
+pub struct Rule {
+    pub rule: LogAlertRule,
+    message: Regex,
+    fqdn: Option<Regex>,
+    tag: Option<Regex>,
+}
+
+impl Rule {
+    pub fn new(rule: LogAlertRule) -> Result<Self, regex::Error> {
+        let message = compile(&rule.message_pattern)?;
+        let fqdn = rule.fqdn_pattern.as_deref().map(compile).transpose()?;
+        let tag = rule.tag_pattern.as_deref().map(compile).transpose()?;
+
+        Ok(Self {
+            rule,
+            message,
+            fqdn,
+            tag,
+        })
+    }
+    pub fn matches(&self, x: &LogLine) -> bool {
+        x.severity <= self.rule.severity as i16
+            && self.fqdn.as_ref().map_or(true, |r| r.is_match(x.fqdn))
+            && self.tag.as_ref().map_or(true, |r| r.is_match(x.tag))
+            && self.message.is_match(x.message)
+    }
+}
+
+pub fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .build()
+}
+
+/// The most of `times`, sorted oldest first, that fall within `within` of each other.
+pub fn max_within(times: &[DateTime<Utc>], within: Duration) -> usize {
+    let within = match chrono::Duration::from_std(within) {
+        Ok(x) => x,
+        Err(_) => return times.len(),
+    };
+
+    let mut start = 0;
+    let mut max = 0;
+
+    for (end, t) in times.iter().enumerate() {
+        while *t - times[start] > within {
+            start += 1;
+        }
+
+        max = std::cmp::max(max, end + 1 - start);
+    }
+
+    max
+}
+
+/// Every log alert rule, by name.
+pub async fn rules(pool: &PgPool) -> Result<Vec<LogAlertRule>, ImlJournalError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT
+                r.id,
+                r.name,
+                r.enabled,
+                r.message_pattern,
+                r.fqdn_pattern,
+                r.tag_pattern,
+                r.severity,
+                r.min_matches,
+                r.within,
+                r.alert_severity,
+                EXISTS (
+                    SELECT 1 FROM chroma_core_alertstate a
+                    WHERE a.alert_item_id = r.id
+                    AND a.record_type = 'LogPatternAlert'
+                    AND a.active = 't'
+                ) AS "active!"
+            FROM chroma_core_logalertrule r
+            ORDER BY r.name
+        "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| LogAlertRule {
+        id: x.id,
+        name: x.name,
+        enabled: x.enabled,
+        message_pattern: x.message_pattern,
+        fqdn_pattern: x.fqdn_pattern,
+        tag_pattern: x.tag_pattern,
+        severity: LogSeverity::try_from(x.severity).unwrap_or(LogSeverity::Debug),
+        min_matches: x.min_matches,
+        within: x.within.into(),
+        alert_severity: AlertSeverity::from(x.alert_severity),
+        active: x.active,
+    })
+    .collect();
+
+    Ok(xs)
+}
+
+/// Evaluates the enabled rules against a batch of ingested messages.
+pub async fn evaluate(pool: &PgPool, xs: &[LogLine<'_>]) -> Result<(), ImlJournalError> {
+    if xs.is_empty() {
+        return Ok(());
+    }
+
+    let compiled: Vec<_> = rules(pool)
+        .await?
+        .into_iter()
+        .filter(|x| x.enabled)
+        .filter_map(|x| {
+            let name = x.name.clone();
+
+            Rule::new(x)
+                .map_err(|e| {
+                    iml_tracing::tracing::warn!("Skipping log alert rule {}: {}", name, e);
+                })
+                .ok()
+        })
+        .collect();
+
+    if compiled.is_empty() {
+        return Ok(());
+    }
+
+    let mut item_type_id = None;
+
+    for rule in compiled {
+        let matched: Vec<_> = xs.iter().filter(|x| rule.matches(x)).collect();
+
+        let latest = match matched.last() {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let fqdns: Vec<_> = matched.iter().map(|x| x.fqdn.to_string()).collect();
+        let messages: Vec<_> = matched.iter().map(|x| x.message.to_string()).collect();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO log_alert_match (rule_id, fqdn, message)
+                SELECT $1, fqdn, message
+                FROM UNNEST($2::text[], $3::text[])
+                AS t(fqdn, message)
+            "#,
+            rule.rule.id,
+            &fqdns,
+            &messages
+        )
+        .execute(pool)
+        .await?;
+
+        let count = sqlx::query!(
+            r#"
+                SELECT COUNT(*) AS "count!"
+                FROM log_alert_match m
+                INNER JOIN chroma_core_logalertrule r ON r.id = m.rule_id
+                WHERE m.rule_id = $1
+                AND m.matched_at > now() - r.within
+            "#,
+            rule.rule.id
+        )
+        .fetch_one(pool)
+        .await?
+        .count;
+
+        if count < rule.rule.min_matches as i64 {
+            continue;
+        }
+
+        let content_type_id = match item_type_id {
+            Some(x) => x,
+            None => {
+                let x = sqlx::query!(
+                    "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'logalertrule'"
+                )
+                .fetch_one(pool)
+                .await?
+                .id;
+
+                *item_type_id.get_or_insert(x)
+            }
+        };
+
+        let message: String = latest.message.chars().take(MAX_ALERT_MESSAGE_LEN).collect();
+
+        alert::raise(
+            pool,
+            AlertRecordType::LogPatternAlert,
+            format!(
+                "{} log messages matched {} within {}, latest from {}: {}",
+                count, rule.rule.name, rule.rule.within, latest.fqdn, message
+            ),
+            content_type_id,
+            None,
+            rule.rule.alert_severity,
+            rule.rule.id,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use iml_wire_types::graphql_duration::GraphQLDuration;
+
+    fn rule() -> LogAlertRule {
+        LogAlertRule {
+            id: 1,
+            name: "evictions".into(),
+            enabled: true,
+            message_pattern: "evicting client at".into(),
+            fqdn_pattern: Some(r"^oss\d+".into()),
+            tag_pattern: None,
+            severity: LogSeverity::Warning,
+            min_matches: 3,
+            within: GraphQLDuration(Duration::from_secs(300)),
+            alert_severity: AlertSeverity::ERROR,
+            active: false,
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let rule = Rule::new(rule()).unwrap();
+
+        let line = |fqdn, severity, message| LogLine {
+            fqdn,
+            tag: "kernel",
+            severity,
+            message,
+        };
+
+        assert!(rule.matches(&line(
+            "oss1.local",
+            3,
+            "LustreError: evicting client at 10.0.0.1@tcp"
+        )));
+        assert!(rule.matches(&line("oss1.local", 4, "evicting client at 10.0.0.1@tcp")));
+        assert!(!rule.matches(&line("oss1.local", 6, "evicting client at 10.0.0.1@tcp")));
+        assert!(!rule.matches(&line("mds1.local", 3, "evicting client at 10.0.0.1@tcp")));
+        assert!(!rule.matches(&line("oss1.local", 3, "client reconnected")));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let x = LogAlertRule {
+            tag_pattern: Some("(kernel".into()),
+            ..rule()
+        };
+
+        assert!(Rule::new(x).is_err());
+    }
+
+    #[test]
+    fn test_max_within() {
+        let t = |s| Utc.timestamp(s, 0);
+        let xs = vec![t(0), t(100), t(200), t(400), t(450), t(500), t(520)];
+
+        assert_eq!(max_within(&xs, Duration::from_secs(120)), 4);
+        assert_eq!(max_within(&xs, Duration::from_secs(600)), 7);
+        assert_eq!(max_within(&xs, Duration::from_secs(1)), 1);
+        assert_eq!(max_within(&[], Duration::from_secs(60)), 0);
+    }
+}
@@ -4,7 +4,11 @@
 
 use chrono::TimeZone;
 use futures::TryStreamExt;
-use iml_journal::{execute_handlers, get_message_class, ImlJournalError};
+use iml_journal::{
+    execute_handlers, get_message_class,
+    log_alert::{self, LogLine},
+    ImlJournalError,
+};
 use iml_manager_env::get_pool_limit;
 use iml_postgres::{
     get_db_pool,
@@ -24,9 +28,20 @@ lazy_static! {
 // Default pool limit if not overridden by POOL_LIMIT
 const DEFAULT_POOL_LIMIT: u32 = 2;
 
-async fn purge_excess(pool: &PgPool, mut num_rows: i64) -> Result<i64, ImlJournalError> {
+/// Purges the oldest messages once there are more than `DBLOG_HW`.
+///
+/// The row count is read from `rowcount` rather than tracked here,
+/// as messages are also added through the ingestion API of `iml-api`.
+async fn purge_excess(pool: &PgPool) -> Result<(), ImlJournalError> {
+    let mut num_rows =
+        sqlx::query!("SELECT total_rows FROM rowcount WHERE table_name = 'chroma_core_logmessage'")
+            .fetch_optional(pool)
+            .await?
+            .and_then(|x| x.total_rows)
+            .unwrap_or_default();
+
     if num_rows <= *DBLOG_HW {
-        return Ok(num_rows);
+        return Ok(());
     }
 
     while *DBLOG_LW < num_rows {
@@ -47,7 +62,7 @@ async fn purge_excess(pool: &PgPool, mut num_rows: i64) -> Result<i64, ImlJourna
         tracing::info!("Purged {} rows, current known row count is {}", x, num_rows);
     }
 
-    Ok(num_rows)
+    Ok(())
 }
 
 #[tokio::main]
@@ -66,14 +81,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut s = consume_data::<Vec<JournalMessage>>(&ch, "rust_agent_journal_rx");
 
-    let mut num_rows = sqlx::query!("SELECT COUNT(*) FROM chroma_core_logmessage")
-        .fetch_one(&pool)
-        .await?
-        .count
-        .expect("count returned None");
-
     while let Some((host, xs)) = s.try_next().await? {
-        num_rows = purge_excess(&pool, num_rows).await?;
+        purge_excess(&pool).await?;
 
         struct Row {
             id: i32,
@@ -108,8 +117,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             execute_handlers(&x.message, row.id, content_type_id, &pool).await?;
         }
 
-        num_rows += xs.len() as i64;
-
         let x = xs.into_iter().fold(
             (vec![], vec![], vec![], vec![], vec![], vec![]),
             |mut acc, x| {
@@ -151,6 +158,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
         .execute(&pool)
         .await?;
+
+        let fqdn = host.to_string();
+
+        let lines: Vec<_> =
+            x.1.iter()
+                .zip(x.3.iter())
+                .zip(x.4.iter())
+                .map(|((severity, tag), message)| LogLine {
+                    fqdn: &fqdn,
+                    tag,
+                    severity: *severity,
+                    message,
+                })
+                .collect();
+
+        if let Err(e) = log_alert::evaluate(&pool, &lines).await {
+            tracing::error!("Error evaluating log alert rules: {}", e);
+        }
     }
 
     Ok(())
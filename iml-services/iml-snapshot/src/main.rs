@@ -96,8 +96,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             sqlx::query!(
             r#"
-            INSERT INTO snapshot (filesystem_name, snapshot_name, create_time, modify_time, snapshot_fsname, mounted, comment)
-            SELECT * FROM
+            INSERT INTO snapshot (filesystem_name, snapshot_name, create_time, modify_time, snapshot_fsname, mounted, comment, policy_run_id)
+            SELECT s.*, (
+                SELECT r.id FROM snapshot_policy_run r
+                WHERE r.filesystem_name = s.filesystem_name
+                AND r.snapshot_name = s.snapshot_name
+                AND r.action = 'create'
+                AND r.state = 'started'
+                ORDER BY r.started_at DESC
+                LIMIT 1
+            ) FROM
             UNNEST (
                 $1::text[],
                 $2::text[],
@@ -106,14 +114,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 $5::text[],
                 $6::bool[],
                 $7::text[]
-            )
+            ) AS s (filesystem_name, snapshot_name, create_time, modify_time, snapshot_fsname, mounted, comment)
             ON CONFLICT (filesystem_name, snapshot_name) DO UPDATE
             SET
                 create_time = excluded.create_time,
                 modify_time = excluded.modify_time,
                 snapshot_fsname = excluded.snapshot_fsname,
                 mounted = excluded.mounted,
-                comment = excluded.comment
+                comment = excluded.comment,
+                policy_run_id = COALESCE(snapshot.policy_run_id, excluded.policy_run_id)
             "#,
             &snaps.0,
             &snaps.1,
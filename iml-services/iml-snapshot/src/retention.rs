@@ -2,13 +2,20 @@ use crate::{Error, FsStats};
 use iml_command_utils::wait_for_cmds_success;
 use iml_influx::{Client as InfluxClient, InfluxClientExt as _};
 use iml_manager_client::{graphql, Client};
-use iml_postgres::{sqlx, PgPool};
+use iml_postgres::{alert, sqlx, PgPool};
 use iml_tracing::tracing;
-use iml_wire_types::{snapshot, Command};
+use iml_wire_types::{snapshot, AlertRecordType, AlertSeverity, Command};
+use sqlx::types::chrono::Utc;
 use std::collections::HashMap;
 
+/// Warn once manual snapshots take up this share of a policy's `keep_num`.
+const MANUAL_SHARE_WARNING: f64 = 0.5;
+
+/// The `bytes_avail`, `bytes_free` and `bytes_used` summed over the targets
+/// of `fs_name` of `kind`, `OST` or `MDT`.
 async fn get_stats_from_influx(
     fs_name: &str,
+    kind: &str,
     client: &InfluxClient,
 ) -> Result<Option<(u64, u64, u64)>, Error> {
     let nodes: Option<Vec<FsStats>> = client
@@ -24,11 +31,11 @@ async fn get_stats_from_influx(
                     LAST("bytes_free") as bytes_free, 
                     LAST("bytes_avail") as bytes_avail 
                 FROM "target" 
-                WHERE "kind" = 'OST' AND "fs" = '{}' 
+                WHERE "kind" = '{}' AND "fs" = '{}' 
                 GROUP BY target
             )
     "#,
-                fs_name,
+                kind, fs_name,
             )
             .as_str(),
             None,
@@ -76,7 +83,12 @@ async fn get_retentions(pool: &sqlx::PgPool) -> Result<Vec<snapshot::SnapshotRet
                     reserve_value,
                     reserve_unit as "reserve_unit:snapshot::ReserveUnit",
                     last_run,
-                    keep_num
+                    keep_num,
+                    version,
+                    updated_at,
+                    crowded_by_manual,
+                    mdt_reserve_percent,
+                    change_percent
                 FROM snapshot_retention
             "#
     )
@@ -112,6 +124,272 @@ async fn destroy_snapshot(
     Ok(cmd)
 }
 
+async fn unmount_snapshot(
+    client: Client,
+    fs_name: &str,
+    snapshot_name: &str,
+) -> Result<Command, Error> {
+    let resp: iml_graphql_queries::Response<iml_graphql_queries::snapshot::unmount::Resp> =
+        graphql(
+            client,
+            iml_graphql_queries::snapshot::unmount::build(fs_name, snapshot_name),
+        )
+        .await?;
+
+    let cmd = Result::from(resp)?.data.unmount_snapshot;
+
+    Ok(cmd)
+}
+
+/// Records a snapshot deleted by a reserve of the retention policy as a run,
+/// so it shows up in the run history next to the snapshots the intervals take.
+async fn record_destroy_run(
+    pool: &PgPool,
+    fs_name: &str,
+    snapshot_name: &str,
+    reason: &str,
+    command_id: i32,
+) -> Result<i32, Error> {
+    let x = sqlx::query!(
+        r#"
+            INSERT INTO snapshot_policy_run
+            (filesystem_name, snapshot_name, due_at, start_after, started_at, command_id, state, reason, action)
+            VALUES ($1, $2, now(), now(), now(), $3, 'started', $4, 'destroy')
+            RETURNING id
+        "#,
+        fs_name,
+        snapshot_name,
+        command_id,
+        reason
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(x.id)
+}
+
+/// Unmounts `snapshot` if needed and deletes it, recording the deletion with `reason`.
+async fn delete_snapshot(
+    client: &Client,
+    pool: &PgPool,
+    snapshot: &snapshot::SnapshotRecord,
+    reason: &str,
+) -> Result<(), Error> {
+    let fs_name = &snapshot.filesystem_name;
+    let snapshot_name = &snapshot.snapshot_name;
+
+    // Snapshots taken by automounting intervals may still be mounted
+    if snapshot.mounted {
+        tracing::debug!("Unmounting {}", snapshot_name);
+        let cmd = unmount_snapshot(client.clone(), fs_name, snapshot_name).await?;
+
+        wait_for_cmds_success(&[cmd], None).await?;
+    }
+
+    tracing::debug!("Deleting {}", snapshot_name);
+    let cmd = destroy_snapshot(client.clone(), fs_name, snapshot_name).await?;
+
+    let run_id = record_destroy_run(pool, fs_name, snapshot_name, reason, cmd.id).await?;
+
+    if let Err(e) = wait_for_cmds_success(&[cmd], None).await {
+        sqlx::query!(
+            "UPDATE snapshot_policy_run SET state = 'failed', error = $2 WHERE id = $1",
+            run_id,
+            e.to_string()
+        )
+        .execute(pool)
+        .await?;
+
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+/// Whether used space moved from `baseline` by at least `change_percent` of `bytes_total`.
+fn change_exceeded(baseline: i64, bytes_used: u64, bytes_total: u64, change_percent: i32) -> bool {
+    if bytes_total == 0 {
+        return false;
+    }
+
+    let change = (bytes_used as i64 - baseline).abs() as f64;
+
+    change >= bytes_total as f64 * f64::from(change_percent) / 100.0
+}
+
+/// Queues a snapshot of `fs_name` once used space changed by `change_percent` since the last one.
+/// The first check only records the baseline. The snapshot is started by the API like an interval run,
+/// and at most one is queued at a time.
+async fn check_change(
+    pool: &PgPool,
+    retention: &snapshot::SnapshotRetention,
+    bytes_used: u64,
+    bytes_total: u64,
+) -> Result<(), Error> {
+    let change_percent = match retention.change_percent {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    let baseline = sqlx::query!(
+        "SELECT change_baseline FROM snapshot_retention WHERE id = $1",
+        retention.id
+    )
+    .fetch_one(pool)
+    .await?
+    .change_baseline;
+
+    match baseline {
+        Some(x) if !change_exceeded(x, bytes_used, bytes_total, change_percent) => return Ok(()),
+        Some(x) => {
+            let fs_name = &retention.filesystem_name;
+
+            tracing::info!(
+                "Used space of {} changed from {} to {} bytes, queueing a snapshot",
+                fs_name,
+                x,
+                bytes_used
+            );
+
+            sqlx::query!(
+                r#"
+                    INSERT INTO snapshot_policy_run
+                    (filesystem_name, snapshot_name, due_at, start_after, reason)
+                    SELECT $1, $2, now(), now(), 'change'
+                    WHERE NOT EXISTS (
+                        SELECT 1 FROM snapshot_policy_run
+                        WHERE filesystem_name = $1 AND reason = 'change' AND state = 'queued'
+                    )
+                "#,
+                fs_name,
+                snapshot::snapshot_name(snapshot::CHANGE_THRESHOLD_ID, fs_name, Utc::now())
+            )
+            .execute(pool)
+            .await?;
+        }
+        None => {}
+    }
+
+    sqlx::query!(
+        "UPDATE snapshot_retention SET change_baseline = $1 WHERE id = $2",
+        bytes_used as i64,
+        retention.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+fn percent_free(bytes_avail: u64, bytes_used: u64) -> f64 {
+    let percent_used = (bytes_used as f64 / (bytes_used as f64 + bytes_avail as f64)) * 100.0f64;
+
+    100.0f64 - percent_used
+}
+
+/// Manual snapshots count towards `keep_num` but are never replaced by the intervals.
+/// Once the policy is at its limit and manual snapshots fill a large part of it,
+/// far fewer interval snapshots are kept than `keep_num` suggests.
+fn crowded_by_manual(manual: usize, total: usize, keep_num: i32) -> bool {
+    let keep_num = keep_num.max(0) as usize;
+
+    manual > 0 && total >= keep_num && manual as f64 >= keep_num as f64 * MANUAL_SHARE_WARNING
+}
+
+/// Records whether manual snapshots crowd out the interval snapshots of `fs_name`
+/// on its retention policy, and raises or lowers a warning alert on the filesystem to match.
+async fn check_manual_snapshots(
+    pool: &PgPool,
+    retention: &snapshot::SnapshotRetention,
+    snapshots: &[snapshot::SnapshotRecord],
+) -> Result<(), Error> {
+    let fs_name = &retention.filesystem_name;
+
+    let manual = snapshots
+        .iter()
+        .filter(|x| snapshot::parse_snapshot_name(&x.snapshot_name).is_none())
+        .count();
+
+    let crowded = crowded_by_manual(manual, snapshots.len(), retention.keep_num);
+
+    if crowded == retention.crowded_by_manual {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "UPDATE snapshot_retention SET crowded_by_manual = $1 WHERE id = $2",
+        crowded,
+        retention.id
+    )
+    .execute(pool)
+    .await?;
+
+    let fs_id = sqlx::query!(
+        "SELECT id FROM chroma_core_managedfilesystem WHERE name = $1 AND not_deleted = 't'",
+        fs_name
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|x| x.id);
+
+    let fs_id = match fs_id {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    if crowded {
+        let content_type_id = sqlx::query!(
+            "SELECT id FROM django_content_type WHERE app_label = 'chroma_core' AND model = 'managedfilesystem'"
+        )
+        .fetch_one(pool)
+        .await?
+        .id;
+
+        tracing::info!(
+            "{} of the {} snapshots kept for {} are manual",
+            manual,
+            retention.keep_num,
+            fs_name
+        );
+
+        alert::raise(
+            pool,
+            AlertRecordType::ManualSnapshotsCrowdingAlert,
+            format!(
+                "Manual snapshots of {} use {} of the {} snapshots its retention policy keeps. Snapshots taken by intervals will be deleted sooner than expected",
+                fs_name, manual, retention.keep_num
+            ),
+            content_type_id,
+            None,
+            AlertSeverity::WARNING,
+            fs_id,
+        )
+        .await?;
+    } else {
+        alert::lower(
+            pool,
+            vec![AlertRecordType::ManualSnapshotsCrowdingAlert],
+            fs_id,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// The oldest snapshot that is not held, once there are more than `keep_num` snapshots.
+/// Held snapshots count towards `keep_num` but are never deleted.
+fn next_to_delete(
+    snapshots: &[snapshot::SnapshotRecord],
+    keep_num: i32,
+) -> Option<&snapshot::SnapshotRecord> {
+    if snapshots.len() <= keep_num.max(0) as usize {
+        return None;
+    }
+
+    snapshots.iter().find(|x| !x.held)
+}
+
 async fn get_retention_filesystems(pool: &PgPool) -> Result<Vec<String>, Error> {
     let xs = get_retentions(pool).await?;
 
@@ -130,7 +408,28 @@ pub async fn process_retention(
     tracing::debug!("Filesystems with retentions: {:?}", filesystems);
 
     for fs_name in filesystems {
-        let stats = get_stats_from_influx(&fs_name, &influx_client).await?;
+        let retention = match get_retention_policy(pool, &fs_name).await? {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let snapshots = get_snapshots(pool, &fs_name).await?;
+
+        check_manual_snapshots(pool, &retention, &snapshots).await?;
+
+        let stats = get_stats_from_influx(&fs_name, "OST", influx_client).await?;
+
+        // The key the last MDT usage a snapshot was deleted at is recorded under
+        let mdt_key = format!("{}:MDT", fs_name);
+
+        let mdt_reserve = match retention.mdt_reserve_percent {
+            Some(x) => get_stats_from_influx(&fs_name, "MDT", influx_client)
+                .await?
+                .map(|stats| (x, stats)),
+            None => None,
+        };
+
+        let mut reason = None;
 
         if let Some((bytes_avail, bytes_free, bytes_used)) = stats {
             tracing::debug!(
@@ -139,53 +438,64 @@ pub async fn process_retention(
                 bytes_free,
                 bytes_used
             );
-            let percent_used =
-                (bytes_used as f64 / (bytes_used as f64 + bytes_avail as f64)) as f64 * 100.0f64;
-            let percent_free = 100.0f64 - percent_used;
+            let percent_free = percent_free(bytes_avail, bytes_used);
 
             tracing::debug!(
                 "stats record: {:?} - bytes free: {}",
                 stats_record.get(&fs_name),
                 bytes_free
             );
-            let retention = get_retention_policy(pool, &fs_name).await?;
-
-            if let Some(retention) = retention {
-                let snapshots = get_snapshots(pool, &fs_name).await?;
-
-                tracing::debug!(
-                    "percent_left: {}, reserve value: {}",
-                    percent_free,
-                    retention.reserve_value
-                );
-                let should_delete_snapshot = match retention.reserve_unit {
-                    snapshot::ReserveUnit::Percent => percent_free < retention.reserve_value as f64,
-                    snapshot::ReserveUnit::Gibibytes => {
-                        let gib_free: f64 = bytes_free as f64 / 1_073_741_824_f64;
-                        gib_free < retention.reserve_value as f64
-                    }
-                    snapshot::ReserveUnit::Tebibytes => {
-                        let teb_free: f64 = bytes_free as f64 / 1_099_511_627_776_f64;
-                        teb_free < retention.reserve_value as f64
-                    }
-                };
-                tracing::debug!("Should delete snapshot?: {}", should_delete_snapshot);
-
-                if should_delete_snapshot
-                    && snapshots.len() > retention.keep_num as usize
-                    && stats_record.get(&fs_name) != Some(&bytes_used)
-                {
-                    stats_record.insert(fs_name.to_string(), bytes_used);
-                    tracing::debug!("About to delete earliest snapshot.");
-                    let snapshot_name = snapshots[0].snapshot_name.to_string();
-                    tracing::debug!("Deleting {}", snapshot_name);
-                    let cmd =
-                        destroy_snapshot(client.clone(), &fs_name, snapshot_name.as_ref()).await?;
-
-                    wait_for_cmds_success(&[cmd], None).await?;
+
+            check_change(pool, &retention, bytes_used, bytes_used + bytes_avail).await?;
+
+            tracing::debug!(
+                "percent_left: {}, reserve value: {}",
+                percent_free,
+                retention.reserve_value
+            );
+            let should_delete_snapshot = match retention.reserve_unit {
+                snapshot::ReserveUnit::Percent => percent_free < retention.reserve_value as f64,
+                snapshot::ReserveUnit::Gibibytes => {
+                    let gib_free: f64 = bytes_free as f64 / 1_073_741_824_f64;
+                    gib_free < retention.reserve_value as f64
+                }
+                snapshot::ReserveUnit::Tebibytes => {
+                    let teb_free: f64 = bytes_free as f64 / 1_099_511_627_776_f64;
+                    teb_free < retention.reserve_value as f64
                 }
+            };
+            tracing::debug!("Should delete snapshot?: {}", should_delete_snapshot);
+
+            if should_delete_snapshot && stats_record.get(&fs_name) != Some(&bytes_used) {
+                reason = Some(("reserve", fs_name.to_string(), bytes_used));
+            }
+        }
+
+        if let Some((mdt_reserve_percent, (bytes_avail, _, bytes_used))) = mdt_reserve {
+            let percent_free = percent_free(bytes_avail, bytes_used);
+
+            tracing::debug!(
+                "MDT percent_left: {}, MDT reserve value: {}",
+                percent_free,
+                mdt_reserve_percent
+            );
+
+            if reason.is_none()
+                && percent_free < f64::from(mdt_reserve_percent)
+                && stats_record.get(&mdt_key) != Some(&bytes_used)
+            {
+                reason = Some(("mdt_reserve", mdt_key, bytes_used));
             }
         }
+
+        let next = next_to_delete(&snapshots, retention.keep_num);
+
+        if let (Some((reason, key, bytes_used)), Some(next)) = (reason, next) {
+            stats_record.insert(key, bytes_used);
+            tracing::debug!("About to delete earliest snapshot, {}.", reason);
+
+            delete_snapshot(client, pool, next, reason).await?;
+        }
     }
 
     Ok(stats_record)
@@ -211,3 +521,60 @@ pub async fn handle_retention_rules(
         tokio::time::delay_for(tokio::time::Duration::from_secs(60)).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crowded_by_manual() {
+        // Under the limit, nothing is deleted yet
+        assert!(!crowded_by_manual(5, 8, 10));
+        // At the limit, half of it manual
+        assert!(crowded_by_manual(5, 10, 10));
+        // At the limit, mostly interval snapshots
+        assert!(!crowded_by_manual(2, 12, 10));
+        assert!(!crowded_by_manual(0, 12, 10));
+    }
+
+    #[test]
+    fn test_change_exceeded() {
+        assert!(!change_exceeded(100, 104, 100, 5));
+        assert!(change_exceeded(100, 105, 100, 5));
+        // Freeing space is a change too
+        assert!(change_exceeded(100, 90, 100, 5));
+        assert!(!change_exceeded(0, 10, 0, 5));
+    }
+
+    #[test]
+    fn test_next_to_delete() {
+        let snapshot = |name: &str, held: bool| snapshot::SnapshotRecord {
+            id: 1,
+            filesystem_name: "fs".into(),
+            snapshot_name: name.into(),
+            modify_time: sqlx::types::chrono::Utc::now(),
+            create_time: sqlx::types::chrono::Utc::now(),
+            snapshot_fsname: "abcd1234".into(),
+            mounted: false,
+            comment: None,
+            held,
+            policy_run_id: None,
+        };
+
+        let xs = vec![
+            snapshot("a", true),
+            snapshot("b", false),
+            snapshot("c", false),
+        ];
+
+        assert_eq!(
+            next_to_delete(&xs, 2).map(|x| x.snapshot_name.as_str()),
+            Some("b")
+        );
+        assert_eq!(next_to_delete(&xs, 3), None);
+
+        let xs = vec![snapshot("a", true), snapshot("b", true)];
+
+        assert_eq!(next_to_delete(&xs, 1), None);
+    }
+}
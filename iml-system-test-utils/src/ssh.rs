@@ -263,7 +263,7 @@ pub async fn create_iml_diagnostics<'a, 'b>(
 }
 
 pub async fn detect_fs(host: &str) -> Result<(), TestError> {
-    ssh_exec_cmd(host, "iml filesystem detect")
+    ssh_exec_cmd(host, "iml filesystem detect --yes")
         .await?
         .checked_status()
         .err_into()
@@ -271,7 +271,7 @@ pub async fn detect_fs(host: &str) -> Result<(), TestError> {
 }
 
 pub async fn list_fs_json(host: &str) -> Result<Vec<serde_json::Value>, TestError> {
-    ssh_exec_cmd(host, "iml filesystem list --display json")
+    ssh_exec_cmd(host, "iml filesystem list --output json")
         .await?
         .checked_output()
         .err_into()
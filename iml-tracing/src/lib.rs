@@ -2,6 +2,9 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+#[cfg(feature = "otlp")]
+pub mod otlp;
+
 use tokio::signal::unix::{signal, SignalKind};
 pub use tracing;
 use tracing_subscriber::{fmt::Subscriber, EnvFilter};
@@ -19,13 +22,18 @@ pub fn init() {
     let handle = builder.reload_handle();
     builder.try_init().expect("Could not init builder");
 
-    let handle2 = handle.clone();
+    reload_on_signals(move |level| handle.reload(level).unwrap());
+}
+
+/// Calls `reload` with `info` on `SIGUSR1` and with `debug` on `SIGUSR2`.
+fn reload_on_signals(reload: impl Fn(&str) + Clone + Send + 'static) {
+    let reload2 = reload.clone();
 
     tokio::spawn(async move {
         let mut stream = signal(SignalKind::user_defined1()).expect("Could not listen to SIGUSR1");
 
         while stream.recv().await.is_some() {
-            handle2.reload("info").unwrap();
+            reload2("info");
         }
     });
 
@@ -33,7 +41,7 @@ pub fn init() {
         let mut stream = signal(SignalKind::user_defined2()).expect("Could not listen to SIGUSR2");
 
         while stream.recv().await.is_some() {
-            handle.reload("debug").unwrap();
+            reload("debug");
         }
     });
 }
@@ -0,0 +1,74 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Exports spans to an OpenTelemetry collector over OTLP, e.g. the one in front of Jaeger.
+//!
+//! Trace context is read from and passed on in W3C `traceparent` headers,
+//! so spans of a request line up with the services it went through.
+
+use opentelemetry::{
+    global,
+    sdk::{
+        propagation::TraceContextPropagator,
+        trace::{self, Sampler},
+        Resource,
+    },
+    trace::TraceError,
+    KeyValue,
+};
+pub use opentelemetry_otlp::Uninstall;
+use std::collections::HashMap;
+use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt as _, reload, util::SubscriberInitExt as _, EnvFilter,
+};
+
+/// Like `crate::init`, additionally exporting spans to the collector at `endpoint`.
+///
+/// `sample_ratio` of the traces started by this service are exported.
+/// Traces started by a caller are exported when the caller exported them.
+///
+/// Spans still waiting to be exported are flushed when the returned guard is dropped.
+pub fn init(
+    service_name: &str,
+    endpoint: &str,
+    sample_ratio: f64,
+) -> Result<Uninstall, TraceError> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let (tracer, uninstall) = opentelemetry_otlp::new_pipeline()
+        .with_endpoint(endpoint)
+        .with_trace_config(
+            trace::config()
+                .with_default_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                    sample_ratio,
+                ))))
+                .with_resource(Resource::new(vec![KeyValue::new(
+                    "service.name",
+                    service_name.to_string(),
+                )])),
+        )
+        .install()?;
+
+    let (filter, handle) = reload::Layer::new(EnvFilter::from_default_env());
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()
+        .expect("Could not init subscriber");
+
+    crate::reload_on_signals(move |level| handle.reload(EnvFilter::new(level)).unwrap());
+
+    Ok(uninstall)
+}
+
+/// Makes `span` a child of the trace in the `traceparent` and `tracestate` of `headers`.
+/// Header names are expected in lowercase. Nothing changes when there is no trace.
+pub fn set_parent(span: &tracing::Span, headers: &HashMap<String, String>) {
+    let cx = global::get_text_map_propagator(|x| x.extract(headers));
+
+    span.set_parent(&cx);
+}
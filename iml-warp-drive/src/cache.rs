@@ -14,6 +14,7 @@ use iml_wire_types::{
         OstPoolOstsRecord, OstPoolRecord, PacemakerConfigurationRecord, StratagemConfiguration,
         TargetRecord, VolumeNodeRecord, VolumeRecord,
     },
+    new_target::PendingTarget,
     sfa::{
         EnclosureType, HealthState, JobState, JobType, MemberState, SfaController, SfaDiskDrive,
         SfaEnclosure, SfaJob, SfaPowerSupply, SfaStorageSystem, SubTargetType,
@@ -137,6 +138,12 @@ pub async fn db_record_to_change_record(
                 Ok(RecordChange::Update(Record::OstPoolOsts(x)))
             }
         },
+        DbRecord::PendingTarget(x) => match (msg_type, x) {
+            (MessageType::Delete, x) => Ok(RecordChange::Delete(RecordId::PendingTarget(x.id))),
+            (MessageType::Insert, x) | (MessageType::Update, x) => {
+                Ok(RecordChange::Update(Record::PendingTarget(x)))
+            }
+        },
         DbRecord::StratagemConfiguration(x) => match (msg_type, x) {
             (MessageType::Delete, x) => Ok(RecordChange::Delete(RecordId::StratagemConfig(x.id()))),
             (_, ref x) if x.deleted() => {
@@ -458,6 +465,18 @@ pub async fn populate_from_db(
             .try_collect()
             .await?;
 
+    cache.pending_target = sqlx::query_as!(
+        PendingTarget,
+        r#"
+        SELECT id, name, fs_name, host_id, dev_path, command_id, created_at
+        FROM pending_target
+    "#
+    )
+    .fetch(pool)
+    .map_ok(|x| (x.id, x))
+    .try_collect()
+    .await?;
+
     cache.sfa_disk_drive = sqlx::query_as!(
         SfaDiskDrive,
         r#"SELECT
@@ -578,22 +597,34 @@ pub async fn populate_from_db(
         .try_collect()
         .await?;
 
-    cache.snapshot_interval = sqlx::query!("SELECT * FROM snapshot_interval")
-        .fetch(pool)
-        .map_ok(|x| {
-            (
-                x.id,
-                SnapshotInterval {
-                    id: x.id,
-                    filesystem_name: x.filesystem_name,
-                    use_barrier: x.use_barrier,
-                    interval: x.interval.into(),
-                    last_run: x.last_run,
-                },
-            )
-        })
-        .try_collect()
-        .await?;
+    cache.snapshot_interval = sqlx::query!(
+        r#"
+        SELECT id, filesystem_name, use_barrier, interval, last_run, automount,
+            to_char(start_at, 'HH24:MI') AS start_time,
+            time_zone,
+            snapshot_interval_next_run(start_at, time_zone, interval, now()) AS next_run
+        FROM snapshot_interval
+        "#
+    )
+    .fetch(pool)
+    .map_ok(|x| {
+        (
+            x.id,
+            SnapshotInterval {
+                id: x.id,
+                filesystem_name: x.filesystem_name,
+                use_barrier: x.use_barrier,
+                interval: x.interval.into(),
+                last_run: x.last_run,
+                automount: x.automount,
+                start_time: x.start_time,
+                time_zone: x.time_zone,
+                next_run: x.next_run,
+            },
+        )
+    })
+    .try_collect()
+    .await?;
 
     cache.snapshot_retention = sqlx::query_as!(
         SnapshotRetention,
@@ -604,7 +635,12 @@ pub async fn populate_from_db(
             reserve_value,
             reserve_unit as "reserve_unit:ReserveUnit",
             last_run,
-            keep_num
+            keep_num,
+            version,
+            updated_at,
+            crowded_by_manual,
+            mdt_reserve_percent,
+            change_percent
         FROM snapshot_retention
     "#
     )
@@ -17,6 +17,7 @@ use iml_wire_types::{
         PACEMAKER_CONFIGURATION_TABLE_NAME, STRATAGEM_CONFIGURATION_TABLE_NAME, TARGET_TABLE_NAME,
         VOLUME_NODE_TABLE_NAME, VOLUME_TABLE_NAME,
     },
+    new_target::{PendingTarget, PENDING_TARGET_TABLE_NAME},
     sfa::{
         SfaController, SfaDiskDrive, SfaEnclosure, SfaJob, SfaPowerSupply, SfaStorageSystem,
         SFA_CONTROLLER_TABLE_NAME, SFA_DISK_DRIVE_TABLE_NAME, SFA_ENCLOSURE_TABLE_NAME,
@@ -49,6 +50,7 @@ pub enum DbRecord {
     OstPool(OstPoolRecord),
     OstPoolOsts(OstPoolOstsRecord),
     PacemakerConfiguration(PacemakerConfigurationRecord),
+    PendingTarget(PendingTarget),
     SfaDiskDrive(SfaDiskDrive),
     SfaEnclosure(SfaEnclosure),
     SfaStorageSystem(SfaStorageSystem),
@@ -83,6 +85,7 @@ impl TryFrom<(TableName<'_>, serde_json::Value)> for DbRecord {
             ALERT_STATE_TABLE_NAME => serde_json::from_value(x).map(DbRecord::AlertState),
             OSTPOOL_TABLE_NAME => serde_json::from_value(x).map(DbRecord::OstPool),
             OSTPOOL_OSTS_TABLE_NAME => serde_json::from_value(x).map(DbRecord::OstPoolOsts),
+            PENDING_TARGET_TABLE_NAME => serde_json::from_value(x).map(DbRecord::PendingTarget),
             SFA_DISK_DRIVE_TABLE_NAME => serde_json::from_value(x).map(DbRecord::SfaDiskDrive),
             SFA_ENCLOSURE_TABLE_NAME => serde_json::from_value(x).map(DbRecord::SfaEnclosure),
             SFA_STORAGE_SYSTEM_TABLE_NAME => {
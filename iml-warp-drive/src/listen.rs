@@ -27,11 +27,21 @@ fn into_db_record(s: &str) -> serde_json::error::Result<(MessageType, DbRecord)>
     Ok((msg_type, r))
 }
 
+/// The user whose permissions change along with `r`, if any.
+fn session_user_id(r: &DbRecord) -> Option<i32> {
+    match r {
+        DbRecord::AuthUserGroup(x) => Some(x.user_id),
+        _ => None,
+    }
+}
+
+/// Applies `record_change` to the cache and sends it to users.
+/// Returns `false` if the cache already matched, in which case nothing is sent.
 async fn handle_record_change(
     record_change: RecordChange,
     api_cache_state: cache::SharedCache,
     user_state: users::SharedUsers,
-) {
+) -> bool {
     match record_change.clone() {
         RecordChange::Delete(r) => {
             tracing::debug!("LISTEN / NOTIFY Delete record: {:?}", r);
@@ -45,6 +55,8 @@ async fn handle_record_change(
                 )
                 .await;
             }
+
+            removed
         }
         RecordChange::Update(r) => {
             let record_id = (&r).into();
@@ -70,8 +82,10 @@ async fn handle_record_change(
                 )
                 .await;
             }
+
+            changed
         }
-    };
+    }
 }
 
 pub async fn handle_db_notifications(
@@ -94,10 +108,24 @@ pub async fn handle_db_notifications(
                 if n.channel() == "table_update" {
                     let r = into_db_record(n.payload())?;
 
+                    let session_user = session_user_id(&r.1);
+
                     let record_change =
                         cache::db_record_to_change_record(r, api_client.clone()).await?;
 
-                    handle_record_change(record_change, api_cache_state, user_state).await;
+                    let changed = handle_record_change(
+                        record_change,
+                        api_cache_state,
+                        Arc::clone(&user_state),
+                    )
+                    .await;
+
+                    if let (true, Some(user_id)) = (changed, session_user) {
+                        users::send_message(Message::SessionChange(user_id), user_state).await;
+                    }
+                } else if n.channel() == "table_invalidate" {
+                    users::send_message(Message::Invalidate(n.payload().to_string()), user_state)
+                        .await;
                 } else {
                     tracing::warn!("unknown channel: {}", n.channel());
                 }
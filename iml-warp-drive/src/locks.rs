@@ -6,9 +6,9 @@ use crate::request::Request;
 use futures::{Stream, TryStreamExt};
 use im::{HashMap, HashSet};
 use iml_rabbit::{
-    basic_consume, basic_publish, bind_queue, declare_transient_exchange, declare_transient_queue,
-    message::Delivery, purge_queue, BasicConsumeOptions, Channel, ExchangeKind, ImlRabbitError,
-    Queue,
+    basic_consume, basic_publish, bind_queue, declare_queue, declare_transient_exchange,
+    message::Delivery, BasicConsumeOptions, Channel, ExchangeKind, ImlRabbitError, Queue,
+    QueueDeclareOptions,
 };
 use iml_wire_types::{LockAction, LockChange, ToCompositeId};
 
@@ -17,13 +17,28 @@ async fn declare_rpc_exchange(c: &Channel) -> Result<(), ImlRabbitError> {
     declare_transient_exchange(&c, "rpc", ExchangeKind::Topic).await
 }
 
-/// Declares the queue used for locks
+/// Declares a queue for this instance's lock updates.
+///
+/// The broker names the queue and removes it when the connection closes,
+/// so every running warp-drive receives its own copy of each lock change
+/// instead of competing for them on a shared queue.
 async fn declare_locks_queue(c: &Channel) -> Result<Queue, ImlRabbitError> {
-    declare_transient_queue(c, "locks").await
+    declare_queue(
+        c,
+        "",
+        QueueDeclareOptions {
+            durable: false,
+            exclusive: true,
+            auto_delete: true,
+            ..QueueDeclareOptions::default()
+        },
+        None,
+    )
+    .await
 }
 
 /// Creates a consumer for the locks queue.
-/// This fn will first purge the locks queue
+/// This fn will first declare a queue for this instance
 /// and then make a one-off request to get
 /// all locks currently held in the job-scheduler.
 ///
@@ -35,9 +50,7 @@ pub async fn create_locks_consumer(
 
     let queue = declare_locks_queue(&channel).await?;
 
-    bind_queue(channel, "rpc", "locks", "locks").await?;
-
-    purge_queue(channel, "locks").await?;
+    bind_queue(channel, "rpc", queue.name().as_str(), "locks").await?;
 
     basic_publish(
         channel,
@@ -75,6 +75,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let c = shared_client.lock().await;
 
         c.simple_query("LISTEN table_update").await?;
+        c.simple_query("LISTEN table_invalidate").await?;
     }
 
     tracing::info!("Started listening to NOTIFY events");
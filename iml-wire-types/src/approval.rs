@@ -0,0 +1,56 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Commands held back until a second user approves them.
+
+use chrono::{DateTime, Utc};
+
+/// Where a held back command is at
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[cfg_attr(feature = "postgres-interop", derive(sqlx::Type))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename = "approval_state"))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename_all = "lowercase"))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalState {
+    #[cfg_attr(feature = "graphql", graphql(name = "pending"))]
+    Pending,
+    /// The command was dispatched
+    #[cfg_attr(feature = "graphql", graphql(name = "approved"))]
+    Approved,
+    /// The command was rejected, or withdrawn by the user that submitted it
+    #[cfg_attr(feature = "graphql", graphql(name = "rejected"))]
+    Rejected,
+}
+
+/// A job class whose commands need the approval of a second user
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct ApprovalRule {
+    /// E.g. `RemoveTaskJob`
+    pub class_name: String,
+    /// The user that added the rule
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A command held back by an approval rule
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct CommandApproval {
+    pub id: i32,
+    /// The message of the command
+    pub message: String,
+    /// The job classes of the command that need approval
+    pub class_names: Vec<String>,
+    pub state: ApprovalState,
+    pub requested_by: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    /// Why the command was rejected
+    pub reason: Option<String>,
+    /// The command dispatched once approved
+    pub command_id: Option<i32>,
+}
@@ -0,0 +1,54 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Messages shown to every user of the GUI.
+
+use chrono::{DateTime, Utc};
+
+/// How prominently a banner is shown
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[cfg_attr(feature = "postgres-interop", derive(sqlx::Type))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename = "banner_severity"))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename_all = "lowercase"))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum BannerSeverity {
+    #[cfg_attr(feature = "graphql", graphql(name = "info"))]
+    Info,
+    #[cfg_attr(feature = "graphql", graphql(name = "warning"))]
+    Warning,
+    #[cfg_attr(feature = "graphql", graphql(name = "error"))]
+    Error,
+}
+
+/// A message shown at the top of every page of the GUI
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct Banner {
+    pub id: i32,
+    pub severity: BannerSeverity,
+    /// Markdown. Raw HTML is shown as text
+    pub text: String,
+    /// `null` when the banner is shown until it is removed
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The user that set the banner
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Banner {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.map_or(true, |x| now < x)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+pub struct BannerInput {
+    pub severity: BannerSeverity,
+    /// Markdown, up to 4096 characters
+    pub text: String,
+    /// Shown until removed when omitted
+    pub expires_at: Option<DateTime<Utc>>,
+}
@@ -304,6 +304,7 @@ impl ManagedTargetRecord {
     }
 }
 
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
 #[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Eq, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum TargetKind {
@@ -1007,6 +1008,28 @@ impl Label for CorosyncResourceRecord {
     }
 }
 
+/// A corosync resource that does not correspond to any known target.
+/// These are usually leftovers from manual changes to the cluster.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UnmanagedResource {
+    pub id: i32,
+    /// The resource name
+    pub name: String,
+    /// The id of the cluster in which the resource lives
+    pub cluster_id: i32,
+    /// The resource agent, i.e. `ocf::heartbeat:Filesystem`
+    pub resource_agent: String,
+    /// The current role of the resource
+    pub role: String,
+    pub active: bool,
+    pub failed: bool,
+    /// The node the resource is currently running on
+    pub active_node_name: Option<String>,
+    /// Where the resource mounts, if it is a mount resource
+    pub mount_point: Option<String>,
+}
+
 /// Record from the corosync_resource_bans table
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct CorosyncResourceBanRecord {
@@ -1050,6 +1073,7 @@ pub struct LogMessageRecord {
     pub tag: String,
     pub message: String,
     pub message_class: i16,
+    pub source: String,
 }
 
 pub const LOG_MESSAGE_TABLE_NAME: TableName = TableName("chroma_core_logmessage");
@@ -1081,6 +1105,9 @@ pub struct ServerProfileRecord {
     pub ui_name: String,
     pub user_selectable: bool,
     pub worker: bool,
+    /// Incremented on every update, used for optimistic locking
+    pub version: i32,
+    pub updated_at: DateTime<Utc>,
 }
 
 pub const SERVER_PROFILE_TABLE_NAME: TableName = TableName("chroma_core_serverprofile");
@@ -0,0 +1,54 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Distributed namespace (DNE): the MDTs of a filesystem and how new directories are striped over them.
+
+/// The default striping of new directories, as `lfs getdirstripe -D` reports it
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct DirStripe {
+    /// How many MDTs each new directory is striped over. `0` and `1` both mean a single MDT
+    pub stripe_count: i32,
+    /// The index of the MDT new directories start on, `-1` to let Lustre choose
+    pub stripe_offset: i32,
+    /// How names are hashed to stripes, e.g. `fnv_1a_64`
+    pub hash_type: String,
+}
+
+/// Sets the default striping of new directories in the root of `filesystem`
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct SetDirStripe {
+    pub filesystem: String,
+    pub stripe_count: i32,
+    pub stripe_offset: i32,
+    /// Left to the Lustre default when `None`
+    pub hash_type: Option<String>,
+}
+
+/// An MDT of a filesystem, with its inode usage
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct MdtLoad {
+    pub name: String,
+    pub index: i32,
+    /// The server the MDT is mounted on, `null` when it is not mounted
+    pub active_host: Option<String>,
+    /// `null` until stats have been received for the MDT
+    pub files_total: Option<f64>,
+    pub files_free: Option<f64>,
+}
+
+/// The MDTs of a filesystem and the default striping of its directories
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct FilesystemDne {
+    pub fs_name: String,
+    /// By index
+    pub mdts: Vec<MdtLoad>,
+    /// Read from a client that has the filesystem mounted.
+    /// `null` when no client has it mounted, or it could not be read
+    pub default_dir_stripe: Option<DirStripe>,
+    /// Why `defaultDirStripe` could not be read
+    pub dir_stripe_error: Option<String>,
+}
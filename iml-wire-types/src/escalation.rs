@@ -0,0 +1,51 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Escalation of alerts that stay active and unacknowledged, to recipients beyond
+//! the users subscribed to them.
+
+use crate::AlertSeverity;
+use chrono::{DateTime, Utc};
+
+/// A rule that escalates alerts of at least `severity` that were neither resolved
+/// nor dismissed `after_minutes` after they were raised.
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct EscalationPolicy {
+    pub id: i32,
+    pub name: String,
+    /// The lowest severity of alerts this policy escalates
+    pub severity: AlertSeverity,
+    pub after_minutes: i32,
+    /// Where the escalation is emailed to
+    pub emails: Vec<String>,
+    pub enabled: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+pub struct EscalationPolicyInput {
+    pub name: String,
+    /// The lowest severity of alerts this policy escalates
+    pub severity: AlertSeverity,
+    /// How long an alert has to stay active and undismissed before it is escalated
+    pub after_minutes: i32,
+    /// Where the escalation is emailed to
+    pub emails: Vec<String>,
+    /// Defaults to `true`
+    pub enabled: Option<bool>,
+}
+
+/// An escalation that was sent for an alert
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct AlertEscalation {
+    pub alert_id: i32,
+    /// `null` once the policy was removed
+    pub policy_id: Option<i32>,
+    /// The name of the policy when the escalation was sent
+    pub policy_name: String,
+    pub emails: Vec<String>,
+    pub escalated_at: DateTime<Utc>,
+}
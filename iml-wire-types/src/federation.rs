@@ -0,0 +1,65 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Federation: one manager registers peer managers of other sites
+//! and shows their filesystems, health and alerts next to its own.
+
+use crate::graphql::{FilesystemOverview, SystemHealth};
+use chrono::{DateTime, Utc};
+
+/// A peer manager whose site is included in the federated overview
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct FederationPeer {
+    pub id: i32,
+    pub name: String,
+    /// The address of the peer manager, e.g. `https://site-b.example.com`
+    pub url: String,
+    /// Whether the certificate of the peer is verified
+    pub verify_tls: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The number of active alerts of each severity
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct AlertCounts {
+    pub critical: i32,
+    pub error: i32,
+    pub warning: i32,
+    /// Info and debug alerts
+    pub info: i32,
+}
+
+/// What a manager reports about its own site
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct SiteOverview {
+    pub filesystems: Vec<FilesystemOverview>,
+    pub health: SystemHealth,
+    pub alerts: AlertCounts,
+}
+
+/// A site of the federation, either this manager or one of its peers
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct FederatedSite {
+    /// `null` for this manager
+    pub peer_id: Option<i32>,
+    pub name: String,
+    /// `null` for this manager
+    pub url: Option<String>,
+    /// `null` when the peer could not be queried
+    pub overview: Option<SiteOverview>,
+    /// Why the peer could not be queried
+    pub error: Option<String>,
+}
+
+/// The sites of the federation, this manager first and then its peers by name
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct FederatedOverview {
+    pub sites: Vec<FederatedSite>,
+    pub checked_at: DateTime<Utc>,
+}
@@ -94,4 +94,16 @@ pub struct Cluster {
     pub resources: Vec<Resource>,
     pub bans: Vec<Ban>,
     pub resource_mounts: HashMap<String, String>,
+    /// Missing from agents that do not report it
+    #[serde(default)]
+    pub config: Option<HaConfig>,
+}
+
+/// The configuration of a cluster, as seen by one of its nodes
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HaConfig {
+    /// The `configuration` section of the CIB, without bans and other transient settings
+    pub cib: String,
+    /// The corosync.conf of the node
+    pub corosync_conf: String,
 }
@@ -0,0 +1,48 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Consistency of the targets the manager knows about.
+
+use chrono::{DateTime, Utc};
+
+/// What is wrong with a target
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IntegrityFindingKind {
+    /// Several targets share a uuid
+    DuplicateUuid,
+    /// The device path of a target changed while it stayed on the same host
+    DevPathChanged,
+    /// A target is registered on hosts that were removed
+    MissingHost,
+}
+
+/// An inconsistency found in the targets
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct IntegrityFinding {
+    pub kind: IntegrityFindingKind,
+    pub uuid: String,
+    /// More than one for `DUPLICATE_UUID`
+    pub target_names: Vec<String>,
+    pub message: String,
+    /// The current device path, for `DEV_PATH_CHANGED`
+    pub dev_path: Option<String>,
+    /// The device path before the change, for `DEV_PATH_CHANGED`
+    pub previous_dev_path: Option<String>,
+    /// When the device path changed, for `DEV_PATH_CHANGED`
+    pub changed_at: Option<DateTime<Utc>>,
+    /// The removed hosts, for `MISSING_HOST`
+    pub missing_host_ids: Vec<i32>,
+}
+
+/// The inconsistencies found in the targets
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct IntegrityReport {
+    /// How many targets were checked
+    pub target_count: i32,
+    pub findings: Vec<IntegrityFinding>,
+}
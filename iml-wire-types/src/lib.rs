@@ -2,13 +2,30 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+pub mod approval;
+pub mod banner;
 pub mod client;
 pub mod db;
+pub mod dne;
+pub mod escalation;
+pub mod federation;
 pub mod graphql_duration;
 pub mod high_availability;
+pub mod integrity;
+pub mod lnet_ping;
+pub mod log_alert;
+pub mod maintenance;
+pub mod multipath;
+pub mod new_target;
+pub mod recovery;
+pub mod role;
 pub mod sfa;
 pub mod snapshot;
+pub mod standby;
+pub mod stonith;
 pub mod stratagem;
+pub mod stripe;
+pub mod target_remount;
 pub mod task;
 pub mod warp_drive;
 
@@ -662,7 +679,14 @@ impl EndpointName for ServerProfile {
 }
 
 pub mod graphql {
-    use crate::db::ServerProfileRecord;
+    use crate::{
+        db::{ServerProfileRecord, TargetKind, TargetRecord},
+        graphql_duration::GraphQLDuration,
+        Command, CorosyncRing, DashboardMetric, DashboardPanel, DashboardPanelKind, TuningSetting,
+        TuningSettingKind,
+    };
+    use chrono::{DateTime, Utc};
+    use std::fmt;
 
     #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
     #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
@@ -680,6 +704,11 @@ pub mod graphql {
         pub user_selectable: bool,
         pub worker: bool,
         pub repos: Vec<Repository>,
+        /// The current version of this profile.
+        /// Must be passed back when updating the profile.
+        pub version: i32,
+        /// When this profile was last updated.
+        pub updated_at: DateTime<Utc>,
     }
 
     #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
@@ -703,6 +732,41 @@ pub mod graphql {
         pub worker: bool,
         pub packages: Vec<String>,
         pub repolist: Vec<String>,
+        /// The expected current version of the profile.
+        /// Required when updating an existing profile.
+        pub version: Option<i32>,
+    }
+
+    /// A setting of two server profiles, side by side
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct ProfileSettingDiff {
+        pub name: String,
+        pub a: String,
+        pub b: String,
+        pub differs: bool,
+    }
+
+    /// The entries of a list of two server profiles, split by which of them has each one
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct ProfileListDiff {
+        pub both: Vec<String>,
+        pub only_a: Vec<String>,
+        pub only_b: Vec<String>,
+    }
+
+    /// Two server profiles, side by side
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct ServerProfileComparison {
+        pub a: String,
+        pub b: String,
+        /// Every setting, whether it differs or not
+        pub settings: Vec<ProfileSettingDiff>,
+        pub packages: ProfileListDiff,
+        /// Repositories by name
+        pub repos: ProfileListDiff,
     }
 
     #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
@@ -719,6 +783,1171 @@ pub mod graphql {
         pub location: String,
     }
 
+    /// A runtime parameter read from a target
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TargetParam {
+        /// The parameter name, relative to the target
+        pub key: String,
+        /// The parameter value, or `null` if it could not be read
+        pub value: Option<String>,
+    }
+
+    /// A key/value tag set on a host
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct HostTag {
+        pub key: String,
+        pub value: String,
+    }
+
+    /// A managed host and its tags
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TaggedHost {
+        pub id: i32,
+        pub fqdn: String,
+        pub nodename: String,
+        pub state: String,
+        pub tags: Vec<HostTag>,
+    }
+
+    /// When a host's agent was last heard from
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct HostHeartbeat {
+        pub host_id: i32,
+        pub fqdn: String,
+        /// The last time the agent polled the manager, `null` if it never has
+        pub last_seen: Option<DateTime<Utc>>,
+        /// When the host last booted, as reported by the agent
+        pub boot_time: Option<DateTime<Utc>>,
+        pub seconds_since_contact: Option<i32>,
+        /// True once the agent has missed too many heartbeats
+        pub needs_attention: bool,
+    }
+
+    /// The kinds of transition recorded in a target's state history
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "snake_case")]
+    pub enum TargetStateEvent {
+        #[cfg_attr(feature = "graphql", graphql(name = "mounted"))]
+        Mounted,
+        #[cfg_attr(feature = "graphql", graphql(name = "unmounted"))]
+        Unmounted,
+        #[cfg_attr(feature = "graphql", graphql(name = "failover"))]
+        Failover,
+        #[cfg_attr(feature = "graphql", graphql(name = "recovery_start"))]
+        RecoveryStart,
+        #[cfg_attr(feature = "graphql", graphql(name = "recovery_end"))]
+        RecoveryEnd,
+    }
+
+    impl std::str::FromStr for TargetStateEvent {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "mounted" => Ok(Self::Mounted),
+                "unmounted" => Ok(Self::Unmounted),
+                "failover" => Ok(Self::Failover),
+                "recovery_start" => Ok(Self::RecoveryStart),
+                "recovery_end" => Ok(Self::RecoveryEnd),
+                x => Err(format!("Unknown target state event {}", x)),
+            }
+        }
+    }
+
+    /// A recorded transition of a target
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TargetStateChange {
+        pub id: i32,
+        pub target_name: String,
+        pub event: TargetStateEvent,
+        /// The target state after the transition
+        pub state: String,
+        /// The fqdn of the host the target was active on after the transition
+        pub active_host: Option<String>,
+        /// The fqdn of the host the target was active on before the transition
+        pub previous_host: Option<String>,
+        /// Why the transition happened, as far as is known
+        pub reason: String,
+        pub changed_at: DateTime<Utc>,
+    }
+
+    /// The kinds of event recorded in the HA history of a cluster
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "snake_case")]
+    pub enum HaEventKind {
+        #[cfg_attr(feature = "graphql", graphql(name = "ban"))]
+        Ban,
+        #[cfg_attr(feature = "graphql", graphql(name = "unban"))]
+        Unban,
+        #[cfg_attr(feature = "graphql", graphql(name = "failover"))]
+        Failover,
+        #[cfg_attr(feature = "graphql", graphql(name = "failback"))]
+        Failback,
+    }
+
+    impl std::str::FromStr for HaEventKind {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "ban" => Ok(Self::Ban),
+                "unban" => Ok(Self::Unban),
+                "failover" => Ok(Self::Failover),
+                "failback" => Ok(Self::Failback),
+                x => Err(format!("Unknown HA event {}", x)),
+            }
+        }
+    }
+
+    /// A recorded ban, unban or move of a corosync resource
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct HaEvent {
+        pub id: i32,
+        pub cluster_id: i32,
+        pub resource: String,
+        pub event: HaEventKind,
+        /// The node the resource was banned from or unbanned from, or moved to
+        pub node: String,
+        /// The node a resource moved away from
+        pub previous_node: Option<String>,
+        /// Why the event happened, as far as is known
+        pub reason: String,
+        pub happened_at: DateTime<Utc>,
+    }
+
+    /// A Lustre RPC security flavor
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "lowercase")]
+    pub enum SecurityFlavor {
+        #[cfg_attr(feature = "graphql", graphql(name = "null"))]
+        Null,
+        #[cfg_attr(feature = "graphql", graphql(name = "plain"))]
+        Plain,
+        #[cfg_attr(feature = "graphql", graphql(name = "gssnull"))]
+        Gssnull,
+        #[cfg_attr(feature = "graphql", graphql(name = "krb5n"))]
+        Krb5n,
+        #[cfg_attr(feature = "graphql", graphql(name = "krb5a"))]
+        Krb5a,
+        #[cfg_attr(feature = "graphql", graphql(name = "krb5i"))]
+        Krb5i,
+        #[cfg_attr(feature = "graphql", graphql(name = "krb5p"))]
+        Krb5p,
+        #[cfg_attr(feature = "graphql", graphql(name = "skn"))]
+        Skn,
+        #[cfg_attr(feature = "graphql", graphql(name = "ska"))]
+        Ska,
+        #[cfg_attr(feature = "graphql", graphql(name = "ski"))]
+        Ski,
+        #[cfg_attr(feature = "graphql", graphql(name = "skpi"))]
+        Skpi,
+    }
+
+    impl SecurityFlavor {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Self::Null => "null",
+                Self::Plain => "plain",
+                Self::Gssnull => "gssnull",
+                Self::Krb5n => "krb5n",
+                Self::Krb5a => "krb5a",
+                Self::Krb5i => "krb5i",
+                Self::Krb5p => "krb5p",
+                Self::Skn => "skn",
+                Self::Ska => "ska",
+                Self::Ski => "ski",
+                Self::Skpi => "skpi",
+            }
+        }
+    }
+
+    /// A secure RPC rule configured on the MGS
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct SrpcRule {
+        /// The filesystem or target name the rule applies to
+        pub target: String,
+        /// The LNet network the rule applies to, or `default`
+        pub network: String,
+        /// The connection direction the rule applies to, e.g. `cli2mdt`. `null` for all directions
+        pub direction: Option<String>,
+        pub flavor: String,
+    }
+
+    /// The security flavor in effect for a target
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TargetSecurityFlavor {
+        pub target: String,
+        /// The default flavor used for connections to the target
+        pub flavor: String,
+        /// Every rule that applies to the target, including filesystem wide ones
+        pub rules: Vec<SrpcRule>,
+    }
+
+    /// A consumer registered to read the changelog of an MDT
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct ChangelogUser {
+        /// The id assigned by Lustre on registration, e.g. `cl1`
+        pub id: String,
+        /// The index of the last record the consumer cleared
+        pub index: f64,
+        /// The number of records the consumer is behind.
+        /// The MDT keeps every record until all consumers have cleared it
+        pub lag: f64,
+        /// Seconds since the consumer last cleared records, if reported by Lustre
+        pub idle_secs: Option<i32>,
+    }
+
+    /// The changelog consumers of an MDT
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct MdtChangelog {
+        pub target: String,
+        /// The index of the newest record
+        pub current_index: f64,
+        pub users: Vec<ChangelogUser>,
+    }
+
+    /// Overall health of the system, ordered from best to worst
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(
+        serde::Serialize, serde::Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug,
+    )]
+    #[serde(rename_all = "lowercase")]
+    pub enum HealthSeverity {
+        #[cfg_attr(feature = "graphql", graphql(name = "good"))]
+        Good,
+        #[cfg_attr(feature = "graphql", graphql(name = "warning"))]
+        Warning,
+        #[cfg_attr(feature = "graphql", graphql(name = "error"))]
+        Error,
+    }
+
+    impl Default for HealthSeverity {
+        fn default() -> Self {
+            Self::Good
+        }
+    }
+
+    /// A filesystem with one or more targets that are not mounted
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct DegradedFilesystem {
+        pub id: i32,
+        pub name: String,
+        pub unmounted_targets: Vec<String>,
+    }
+
+    /// A managed host the manager can't currently reach
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct DownHost {
+        pub id: i32,
+        pub fqdn: String,
+    }
+
+    /// An aggregate view of the things that need attention
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct SystemHealth {
+        /// The worst severity of the items below
+        pub severity: HealthSeverity,
+        pub degraded_filesystems: Vec<DegradedFilesystem>,
+        pub down_hosts: Vec<DownHost>,
+        /// The number of commands that failed in the last hour
+        pub failed_commands: i32,
+        /// Problems with the services the manager relies on
+        pub service_issues: Vec<String>,
+    }
+
+    impl SystemHealth {
+        /// Computes `severity` from the collected items.
+        /// A failed command is only a warning, anything else is an error.
+        pub fn with_severity(mut self) -> Self {
+            self.severity = if !self.degraded_filesystems.is_empty()
+                || !self.down_hosts.is_empty()
+                || !self.service_issues.is_empty()
+            {
+                HealthSeverity::Error
+            } else if self.failed_commands > 0 {
+                HealthSeverity::Warning
+            } else {
+                HealthSeverity::Good
+            };
+
+            self
+        }
+    }
+
+    /// The health of one of the services the manager is made of
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct ServiceStatus {
+        /// e.g. `postgres` or `rabbitmq`
+        pub name: String,
+        pub severity: HealthSeverity,
+        /// How long the service took to answer, if it did
+        pub response_ms: Option<i32>,
+        /// What was checked, and what was wrong
+        pub details: Vec<String>,
+    }
+
+    /// The health of the manager itself
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct ManagerStatus {
+        /// The worst severity of `services`
+        pub severity: HealthSeverity,
+        pub services: Vec<ServiceStatus>,
+        pub checked_at: DateTime<Utc>,
+    }
+
+    impl ManagerStatus {
+        pub fn new(services: Vec<ServiceStatus>, checked_at: DateTime<Utc>) -> Self {
+            Self {
+                severity: services
+                    .iter()
+                    .map(|x| x.severity)
+                    .max()
+                    .unwrap_or_default(),
+                services,
+                checked_at,
+            }
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    pub enum SchemaMigrationState {
+        /// Applied as embedded in this `iml-api`
+        Applied,
+        /// Embedded in this `iml-api` but not applied yet
+        Pending,
+        /// Started but did not complete
+        Failed,
+        /// Applied, but the embedded migration has changed since
+        Modified,
+        /// Applied by a newer version, not embedded in this `iml-api`
+        Unknown,
+    }
+
+    /// A schema migration of the manager database
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct SchemaMigration {
+        /// The version, a timestamp such as `20210218093044`
+        pub version: String,
+        pub description: String,
+        pub state: SchemaMigrationState,
+        pub installed_on: Option<DateTime<Utc>>,
+        /// How long applying the migration took
+        pub execution_ms: Option<f64>,
+    }
+
+    /// A valid agent certificate of a host.
+    /// A host has two while its certificate is being rotated.
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct HostCertificate {
+        pub host_id: i32,
+        pub fqdn: String,
+        pub serial: String,
+        /// `null` for certificates issued before expiry was recorded
+        pub not_after: Option<DateTime<Utc>>,
+        /// Whole days until `not_after`, negative once expired
+        pub days_remaining: Option<i32>,
+    }
+
+    /// The priority class of a job.
+    /// Ready jobs start highest priority first, and fewer low priority jobs run at once.
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(
+        serde::Serialize, serde::Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug,
+    )]
+    #[serde(rename_all = "snake_case")]
+    pub enum JobPriority {
+        /// Background work, e.g. Stratagem scans
+        #[cfg_attr(feature = "graphql", graphql(name = "low"))]
+        Low,
+        #[cfg_attr(feature = "graphql", graphql(name = "normal"))]
+        Normal,
+        /// Commands users started and are waiting on
+        #[cfg_attr(feature = "graphql", graphql(name = "high"))]
+        High,
+    }
+
+    impl From<i16> for JobPriority {
+        fn from(x: i16) -> Self {
+            match x {
+                x if x <= 0 => Self::Low,
+                1 => Self::Normal,
+                _ => Self::High,
+            }
+        }
+    }
+
+    impl fmt::Display for JobPriority {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let x = match self {
+                Self::Low => "low",
+                Self::Normal => "normal",
+                Self::High => "high",
+            };
+
+            write!(f, "{}", x)
+        }
+    }
+
+    /// A job waiting in the queue of a host.
+    /// Jobs acting on a host run one at a time, highest priority first
+    /// and in the order they were created within a priority.
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct HostQueueEntry {
+        /// Position in the queue, the job at 0 is running or runs next
+        pub position: i32,
+        pub job_id: i32,
+        /// `pending`, or `tasked` once the job is running
+        pub state: String,
+        pub priority: JobPriority,
+        /// The lowercased job class, e.g. `configurelnetjob`
+        pub class_name: String,
+        pub command_id: i32,
+        pub command_message: String,
+        pub created_at: DateTime<Utc>,
+    }
+
+    /// What the queue of a host holds
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct HostQueueSummary {
+        pub host_id: i32,
+        pub fqdn: String,
+        /// The running job, as a lowercased job class
+        pub running: Option<String>,
+        /// How many high priority jobs are waiting
+        pub high: i32,
+        /// How many normal priority jobs are waiting
+        pub normal: i32,
+        /// How many low priority jobs are waiting
+        pub low: i32,
+    }
+
+    /// A file setting up a client to mount a filesystem
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ClientMountConfigKind {
+        /// A systemd `.mount` unit
+        #[cfg_attr(feature = "graphql", graphql(name = "systemd"))]
+        Systemd,
+        /// A line of `/etc/fstab`
+        #[cfg_attr(feature = "graphql", graphql(name = "fstab"))]
+        Fstab,
+        /// An `/etc/lnet.conf` for `lnetctl import`, configuring the client network
+        #[cfg_attr(feature = "graphql", graphql(name = "lnet"))]
+        Lnet,
+    }
+
+    impl std::str::FromStr for ClientMountConfigKind {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "systemd" => Ok(Self::Systemd),
+                "fstab" => Ok(Self::Fstab),
+                "lnet" => Ok(Self::Lnet),
+                x => Err(format!("Unknown client mount config {}", x)),
+            }
+        }
+    }
+
+    impl fmt::Display for ClientMountConfigKind {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let x = match self {
+                Self::Systemd => "systemd",
+                Self::Fstab => "fstab",
+                Self::Lnet => "lnet",
+            };
+
+            write!(f, "{}", x)
+        }
+    }
+
+    /// A lock an unfinished job takes on a record
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct LockEntry {
+        pub job_id: i32,
+        /// `pending`, or `tasked` once the job is running
+        pub job_state: String,
+        /// The lowercased job class, e.g. `stoptargetjob`
+        pub class_name: String,
+        /// The commands the job belongs to
+        pub command_ids: Vec<i32>,
+        /// Whether this is a write lock. Write locks change the state of the record
+        pub write: bool,
+        /// The state the record is expected in before a write lock is taken
+        pub begin_state: Option<String>,
+        /// The state the record is left in once the job completes
+        pub end_state: Option<String>,
+        /// Unfinished jobs this job waits for
+        pub waiting_for: Vec<i32>,
+    }
+
+    /// The locks held and waited for on a record
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct RecordLocks {
+        pub content_type_id: i32,
+        pub record_id: i32,
+        /// Locks of running jobs, and of jobs with nothing left to wait for
+        pub holders: Vec<LockEntry>,
+        /// Locks of jobs waiting for other jobs, oldest first
+        pub waiters: Vec<LockEntry>,
+    }
+
+    /// A record two jobs both lock
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct LockedRecord {
+        pub content_type_id: i32,
+        pub record_id: i32,
+        /// The lowercased model name, e.g. `managedtarget`
+        pub record_type: String,
+        /// Whether the blocking job holds a write lock on the record
+        pub write: bool,
+    }
+
+    /// A job of a command that waits for a job of another command
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct CommandBlocker {
+        pub job_id: i32,
+        pub class_name: String,
+        pub blocking_job_id: i32,
+        pub blocking_class_name: String,
+        /// `pending`, or `tasked` once the blocking job is running
+        pub blocking_state: String,
+        pub blocking_command_id: Option<i32>,
+        pub blocking_command_message: Option<String>,
+        /// The records both jobs lock
+        pub records: Vec<LockedRecord>,
+    }
+
+    /// A step of a command, with the log messages of its hosts while it ran
+    #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct CommandStepLogs {
+        pub job_id: i32,
+        pub class_name: String,
+        pub step_index: i32,
+        pub step_count: i32,
+        /// `incomplete`, `failed` or `success`
+        pub state: String,
+        /// What the step logged itself
+        pub log: String,
+        pub started_at: DateTime<Utc>,
+        pub finished_at: DateTime<Utc>,
+        /// The hosts messages were looked for on
+        pub fqdns: Vec<String>,
+        /// Oldest first
+        pub messages: Vec<crate::LogMessage>,
+        /// Whether there were more messages than the limit
+        pub truncated: bool,
+    }
+
+    /// A Lustre target found on a scanned host
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct DetectedTarget {
+        pub name: String,
+        pub uuid: String,
+        pub kind: TargetKind,
+        pub state: String,
+        /// The hosts the target can run on
+        pub hosts: Vec<String>,
+        /// Whether the manager already knows about this target
+        pub registered: bool,
+        /// Whether importing the scan registers this target.
+        /// Targets that are not mounted, or that belong to an incomplete filesystem, are skipped.
+        pub import: bool,
+    }
+
+    /// A filesystem assembled from the targets found on scanned hosts
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct DetectedFilesystem {
+        pub name: String,
+        /// Whether a mounted MGS, MDT and OST were all found.
+        /// Only the MGS of an incomplete filesystem is imported.
+        pub complete: bool,
+        /// Whether the manager already knows about this filesystem
+        pub registered: bool,
+        pub targets: Vec<DetectedTarget>,
+    }
+
+    /// A preview of the filesystems found on a set of hosts,
+    /// which can be imported with `importDetected`
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct DetectScan {
+        pub id: i32,
+        pub host_ids: Vec<i32>,
+        pub filesystems: Vec<DetectedFilesystem>,
+        pub created_at: DateTime<Utc>,
+        pub imported_at: Option<DateTime<Utc>>,
+    }
+
+    /// A mutation made through the API
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct AuditEntry {
+        pub id: i32,
+        /// The user that made the mutation, as named at the time
+        pub username: Option<String>,
+        /// The mutation, e.g. `createSnapshot` or `stratagem.runFilesync`
+        pub mutation: String,
+        /// The identifying arguments of the mutation, as JSON
+        pub args: String,
+        /// The command the mutation started, if any
+        pub command_id: Option<i32>,
+        pub created_at: DateTime<Utc>,
+    }
+
+    /// A note an operator left on a command
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct CommandNote {
+        pub id: i32,
+        pub note: String,
+        /// The user that wrote the note, if they still exist
+        pub author: Option<String>,
+        pub created_at: DateTime<Utc>,
+    }
+
+    /// The notes and tags attached to a command
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct CommandAnnotations {
+        pub command_id: i32,
+        pub tags: Vec<String>,
+        /// Oldest first
+        pub notes: Vec<CommandNote>,
+    }
+
+    /// Narrows the `commands` query. Every field that is set has to match,
+    /// along with every filter of `and` and at least one filter of `or`.
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug, Default)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+    #[serde(rename_all(serialize = "camelCase"))]
+    pub struct CommandFilter {
+        /// Only commands created at or after this time.
+        /// Without one here or in an `and` filter, only commands of the last 30 days match.
+        pub created_after: Option<DateTime<Utc>>,
+        /// Only commands created before this time
+        pub created_before: Option<DateTime<Utc>>,
+        /// `true` for commands that failed, `false` for commands that did not
+        pub errored: Option<bool>,
+        /// `true` for commands that were cancelled, `false` for commands that were not
+        pub cancelled: Option<bool>,
+        /// The user that submitted the command through the API
+        pub user: Option<String>,
+        /// The fqdn of a server the command locked, directly or through one of its targets
+        pub host: Option<String>,
+        /// A filesystem the command locked, directly or through one of its targets
+        pub fs_name: Option<String>,
+        /// Substring of the command's message, case insensitive
+        pub msg: Option<String>,
+        /// Filters that all have to match
+        pub and: Option<Vec<CommandFilter>>,
+        /// Filters of which at least one has to match
+        pub or: Option<Vec<CommandFilter>>,
+    }
+
+    /// Recent bandwidth of a target, small enough to draw inline in a table row.
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug, Default)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TargetMiniStats {
+        pub target_uuid: String,
+        /// Read bandwidth in bytes/sec, oldest first, one value per bucket
+        pub read: Vec<f64>,
+        /// Write bandwidth in bytes/sec, oldest first, one value per bucket
+        pub write: Vec<f64>,
+    }
+
+    /// The checks that make up a scheduled filesystem health check
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "snake_case")]
+    pub enum FilesystemCheckKind {
+        /// Every target is mounted on its primary server
+        #[cfg_attr(feature = "graphql", graphql(name = "targets_on_primary"))]
+        TargetsOnPrimary,
+        /// No target resource is banned from any of its servers
+        #[cfg_attr(feature = "graphql", graphql(name = "no_banned_resources"))]
+        NoBannedResources,
+        /// Every cluster running targets has quorum
+        #[cfg_attr(feature = "graphql", graphql(name = "quorum"))]
+        Quorum,
+        /// The MGS is mounted and its server answers
+        #[cfg_attr(feature = "graphql", graphql(name = "mgs_reachable"))]
+        MgsReachable,
+    }
+
+    /// The outcome of a single check
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct FilesystemCheck {
+        pub kind: FilesystemCheckKind,
+        pub passed: bool,
+        /// What failed, e.g. the targets that are not on their primary server
+        pub details: Vec<String>,
+    }
+
+    /// One scheduled health check run of a filesystem
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct FilesystemCheckRun {
+        pub id: i32,
+        pub fs_name: String,
+        pub ran_at: DateTime<Utc>,
+        /// `true` when every check passed
+        pub passed: bool,
+        pub checks: Vec<FilesystemCheck>,
+    }
+
+    /// When a filesystem is projected to fill up, from a linear fit of its recent daily usage.
+    /// Bounds are a 95% confidence interval of the growth rate.
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct CapacityForecast {
+        pub fs_name: String,
+        /// How far ahead the forecast looks
+        pub horizon_days: i32,
+        /// The number of days of usage history the forecast is based on
+        pub samples: i32,
+        pub bytes_total: f64,
+        pub bytes_used: f64,
+        /// `null` when there is not enough history to fit
+        pub growth_bytes_per_day: Option<f64>,
+        /// Projected usage at the end of the horizon, capped at `bytes_total`
+        pub bytes_used_at_horizon: Option<f64>,
+        /// `null` when the filesystem is not projected to fill up within the horizon
+        pub fill_date: Option<DateTime<Utc>>,
+        /// The fill date at the upper bound of the growth rate
+        pub fill_date_earliest: Option<DateTime<Utc>>,
+        /// The fill date at the lower bound of the growth rate
+        pub fill_date_latest: Option<DateTime<Utc>>,
+    }
+
+    /// What a host is to a filesystem
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "lowercase")]
+    pub enum HostRole {
+        #[cfg_attr(feature = "graphql", graphql(name = "server"))]
+        Server,
+        #[cfg_attr(feature = "graphql", graphql(name = "client"))]
+        Client,
+    }
+
+    /// The installed versions of the packages that matter for compatibility on a host
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct HostVersions {
+        pub host_id: i32,
+        pub fqdn: String,
+        pub role: HostRole,
+        /// `lustre` on servers, `lustre-client` on clients. `null` when not installed
+        pub lustre: Option<String>,
+        /// `rust-iml-agent`. `null` when not installed
+        pub agent: Option<String>,
+        /// Why the versions could not be read, if they couldn't
+        pub error: Option<String>,
+    }
+
+    /// The Lustre and IML versions a filesystem runs with, checked against the supported combinations
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct CompatibilityReport {
+        pub fs_name: String,
+        pub manager_version: String,
+        pub hosts: Vec<HostVersions>,
+        /// Unsupported combinations, and hosts that could not be checked
+        pub issues: Vec<String>,
+        /// `true` when there are no issues
+        pub supported: bool,
+    }
+
+    /// What a filesystem start or stop does to a target
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "lowercase")]
+    pub enum TargetAction {
+        #[cfg_attr(feature = "graphql", graphql(name = "start"))]
+        Start,
+        #[cfg_attr(feature = "graphql", graphql(name = "stop"))]
+        Stop,
+        /// The target is left as is
+        #[cfg_attr(feature = "graphql", graphql(name = "skip"))]
+        Skip,
+    }
+
+    /// A target of a filesystem start or stop, and how far its job got
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TargetTransition {
+        pub name: String,
+        pub kind: TargetKind,
+        pub action: TargetAction,
+        /// Why the target is skipped, if it is
+        pub reason: Option<String>,
+        /// `pending`, `tasked` or `complete`. `null` for skipped targets
+        pub job_state: Option<String>,
+        pub errored: bool,
+        pub cancelled: bool,
+    }
+
+    /// The command starting or stopping a filesystem
+    #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct FilesystemTransition {
+        pub command: Command,
+        /// In the order they are started or stopped
+        pub targets: Vec<TargetTransition>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+    pub struct DashboardPanelInput {
+        pub title: String,
+        pub kind: DashboardPanelKind,
+        pub metric: Option<DashboardMetric>,
+        pub fs_name: Option<String>,
+        pub target_name: Option<String>,
+        pub wide: Option<bool>,
+    }
+
+    /// A saved layout of panels
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct Dashboard {
+        pub id: i32,
+        pub name: String,
+        /// The user who created the dashboard. Only they can change or remove it
+        pub owner: String,
+        /// Listed for every user, not just the owner
+        pub shared: bool,
+        /// In display order
+        pub panels: Vec<DashboardPanel>,
+        pub updated_at: DateTime<Utc>,
+    }
+
+    /// Where a page of a paged query starts and how long it is
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct PageMeta {
+        /// The limit that was applied, which is capped at the maximum page size of the manager
+        pub limit: i32,
+        pub offset: i32,
+        /// Rows matching the query across all pages
+        pub total_count: i32,
+        /// `total_count` is an estimate, or a lower bound for a filtered query, as the table is
+        /// too large to count
+        #[serde(default)]
+        pub is_estimate: bool,
+    }
+
+    /// A page of targets
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TargetList {
+        pub data: Vec<TargetRecord>,
+        pub meta: PageMeta,
+        /// The uuids of the targets in `data` with a failed multipath path on any of their hosts
+        #[serde(default)]
+        pub degraded_multipath: Vec<String>,
+    }
+
+    /// A Corosync Node found in `crm_mon`
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct CorosyncNode {
+        /// The name of the node
+        pub name: String,
+        /// The id of the node as reported by `crm_mon`
+        pub id: String,
+        /// Id of the cluster this node belongs to
+        pub cluster_id: i32,
+        pub online: bool,
+        pub standby: bool,
+        pub standby_onfail: bool,
+        pub maintenance: bool,
+        pub pending: bool,
+        pub unclean: bool,
+        pub shutdown: bool,
+        pub expected_up: bool,
+        pub is_dc: bool,
+        pub resources_running: i32,
+        pub r#type: String,
+    }
+
+    /// A page of commands
+    #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct CommandList {
+        pub data: Vec<Command>,
+        pub meta: PageMeta,
+    }
+
+    /// A page of corosync nodes
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct CorosyncNodeList {
+        pub data: Vec<CorosyncNode>,
+        pub meta: PageMeta,
+    }
+
+    /// A Lustre Target and it's corresponding resource
+    #[derive(serde::Serialize, serde::Deserialize, Hash, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TargetResource {
+        /// The id of the cluster
+        pub cluster_id: i32,
+        /// The filesystems associated with this target
+        pub fs_names: Vec<String>,
+        /// The uuid of this target
+        pub uuid: String,
+        /// The name of this target
+        pub name: String,
+        /// The corosync resource id associated with this target
+        pub resource_id: String,
+        /// The current state of this target
+        pub state: String,
+        /// The list of host ids this target could possibly run on
+        pub cluster_hosts: Vec<i32>,
+    }
+
+    /// A Corosync banned resource
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct BannedResource {
+        // The primary id
+        pub id: i32,
+        /// The resource name
+        pub name: String,
+        /// The id of the cluster in which the resource lives
+        pub cluster_id: i32,
+        /// The resource name
+        pub resource: String,
+        /// The node in which the resource lives
+        pub node: String,
+        /// The assigned weight of the resource
+        pub weight: i32,
+        /// Is master only
+        pub master_only: bool,
+    }
+
+    /// How long jobs of a class may run before they are failed
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct JobTimeout {
+        /// The job class name, i.e. `MountLustreFilesystemsJob`
+        pub class_name: String,
+        /// How long a job may stay running
+        pub timeout: GraphQLDuration,
+    }
+
+    /// How many targets of a filesystem are in each state
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TargetStateCounts {
+        pub mounted: i32,
+        pub unmounted: i32,
+        /// Targets that are the subject of an active alert, whatever their state
+        pub alerting: i32,
+    }
+
+    /// Everything the dashboard card of a filesystem shows
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct FilesystemOverview {
+        pub id: i32,
+        pub name: String,
+        /// Capacity of the OSTs. Stats are `null` until the filesystem has reported them
+        pub bytes_total: Option<f64>,
+        pub bytes_free: Option<f64>,
+        pub bytes_avail: Option<f64>,
+        /// Inodes of the OSTs
+        pub files_total: Option<f64>,
+        pub files_free: Option<f64>,
+        /// Clients connected to the MDTs
+        pub clients: Option<i32>,
+        pub targets: TargetStateCounts,
+        /// Active alerts on the filesystem or any of its targets
+        pub active_alerts: i32,
+    }
+
+    /// A host to add to a new HA cluster
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+    pub struct HaNodeInput {
+        #[serde(rename(serialize = "hostId"))]
+        pub host_id: i32,
+        /// The address of the first corosync ring
+        #[serde(rename(serialize = "ring0Addr"))]
+        pub ring0_addr: String,
+        /// The address of the optional second, redundant ring
+        #[serde(rename(serialize = "ring1Addr"))]
+        pub ring1_addr: Option<String>,
+        /// The fence agent that powers off this host, e.g. `fence_ipmilan`
+        #[serde(rename(serialize = "fenceAgent"))]
+        pub fence_agent: String,
+        /// Options passed to the fence agent, each `key=value`
+        #[serde(rename(serialize = "fenceParams"))]
+        pub fence_params: Vec<String>,
+    }
+
+    /// The corosync rings of a host
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct HostRingStatus {
+        pub host_id: i32,
+        pub fqdn: String,
+        pub rings: Vec<CorosyncRing>,
+        /// Set when the rings could not be read from the host
+        pub error: Option<String>,
+    }
+
+    /// How the cluster configuration of a host differs from the one IML last applied
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct ClusterConfigDiff {
+        pub host_id: i32,
+        pub fqdn: String,
+        pub drifted: bool,
+        /// When the host last reported a different configuration
+        pub changed_at: DateTime<Utc>,
+        /// When IML last applied the configuration
+        pub baselined_at: DateTime<Utc>,
+        /// Line diff of the CIB `configuration` section, empty when unchanged
+        pub cib_diff: String,
+        /// Line diff of corosync.conf, empty when unchanged
+        pub corosync_conf_diff: String,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+    pub struct TuningSettingInput {
+        pub kind: TuningSettingKind,
+        pub key: String,
+        pub value: String,
+    }
+
+    /// A named set of settings that can be applied to servers
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TuningProfile {
+        pub id: i32,
+        pub name: String,
+        pub settings: Vec<TuningSetting>,
+        /// The hosts the profile is assigned to
+        pub host_ids: Vec<i32>,
+    }
+
+    /// A setting whose live value differs from the profile
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TuningMismatch {
+        pub kind: TuningSettingKind,
+        pub key: String,
+        pub expected: String,
+        /// `null` when the value could not be read
+        pub actual: Option<String>,
+    }
+
+    /// A host that does not match its assigned tuning profile
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct TuningDrift {
+        pub host_id: i32,
+        pub fqdn: String,
+        pub profile: String,
+        pub mismatches: Vec<TuningMismatch>,
+        /// Set when the live values could not be read from the host
+        pub error: Option<String>,
+    }
+
+    /// Where a query submitted to run in the background is at
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "lowercase")]
+    pub enum DeferredQueryState {
+        #[cfg_attr(feature = "graphql", graphql(name = "pending"))]
+        Pending,
+        #[cfg_attr(feature = "graphql", graphql(name = "running"))]
+        Running,
+        #[cfg_attr(feature = "graphql", graphql(name = "complete"))]
+        Complete,
+        #[cfg_attr(feature = "graphql", graphql(name = "failed"))]
+        Failed,
+    }
+
+    impl std::str::FromStr for DeferredQueryState {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "pending" => Ok(Self::Pending),
+                "running" => Ok(Self::Running),
+                "complete" => Ok(Self::Complete),
+                "failed" => Ok(Self::Failed),
+                x => Err(format!("Unknown deferred query state {}", x)),
+            }
+        }
+    }
+
+    /// A query submitted to run in the background
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct DeferredQuery {
+        /// Passed to `queryResult` to read the result
+        pub handle: String,
+        pub state: DeferredQueryState,
+        pub submitted_at: DateTime<Utc>,
+        pub completed_at: Option<DateTime<Utc>>,
+        /// When the query and its result are removed
+        pub expires_at: DateTime<Utc>,
+        /// Set when the query failed, or completed with errors
+        pub error: Option<String>,
+    }
+
+    /// A page of the result of a query run in the background.
+    /// Lists in the result are paged by their items, anything else is a single row.
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct DeferredQueryResult {
+        pub query: DeferredQuery,
+        /// The fields leading to the list that was paged, empty if the result is a single row
+        pub path: Vec<String>,
+        pub total_rows: i32,
+        /// JSON encoded rows, empty until the query is complete
+        pub rows: Vec<String>,
+    }
+
     impl ServerProfile {
         pub fn new(
             record: ServerProfileRecord,
@@ -751,6 +1980,8 @@ pub mod graphql {
                 ui_name: record.ui_name,
                 user_selectable: record.user_selectable,
                 worker: record.worker,
+                version: record.version,
+                updated_at: record.updated_at,
             })
         }
     }
@@ -1129,6 +2360,20 @@ pub enum AlertRecordType {
     NoTimeSyncAlert,
     MultipleTimeSyncAlert,
     UnknownTimeSyncAlert,
+    ManualSnapshotsCrowdingAlert,
+    FilesystemCheckFailedAlert,
+    CapacityForecastAlert,
+    CertificateExpiryAlert,
+    AgentHeartbeatAlert,
+    MultipathDegradedAlert,
+    TargetRemountFailedAlert,
+    StonithTestFailedAlert,
+    ClusterConfigDriftAlert,
+    StandbyBehindAlert,
+    LogPatternAlert,
+    DuplicateTargetUuidAlert,
+    TargetDevPathChangedAlert,
+    TargetHostMissingAlert,
 }
 
 impl ToString for AlertRecordType {
@@ -1137,6 +2382,7 @@ impl ToString for AlertRecordType {
     }
 }
 
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
 #[derive(
     serde::Serialize, serde::Deserialize, Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq,
 )]
@@ -1160,6 +2406,19 @@ impl From<AlertSeverity> for i32 {
     }
 }
 
+/// Levels in between round down, like the `logging` levels they come from.
+impl From<i32> for AlertSeverity {
+    fn from(x: i32) -> Self {
+        match x {
+            x if x >= 50 => AlertSeverity::CRITICAL,
+            x if x >= 40 => AlertSeverity::ERROR,
+            x if x >= 30 => AlertSeverity::WARNING,
+            x if x >= 20 => AlertSeverity::INFO,
+            _ => AlertSeverity::DEBUG,
+        }
+    }
+}
+
 /// An Alert record from /api/alert/
 #[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
 pub struct Alert {
@@ -1313,6 +2572,8 @@ pub struct LogMessage {
     pub message_class: MessageClass,
     pub severity: LogSeverity,
     pub tag: String,
+    /// `agent` for messages from managed servers, otherwise the source named on ingestion
+    pub source: String,
 }
 
 impl TryFrom<LogMessageRecord> for LogMessage {
@@ -1328,6 +2589,7 @@ impl TryFrom<LogMessageRecord> for LogMessage {
             message_class: record.message_class.try_into()?,
             severity: record.severity.try_into()?,
             tag: record.tag,
+            source: record.source,
         })
     }
 }
@@ -1364,6 +2626,9 @@ pub mod logs {
     #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
     pub struct Meta {
         pub total_count: i32,
+        /// `total_count` is an estimate
+        #[serde(default)]
+        pub is_estimate: bool,
     }
 
     #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
@@ -1374,6 +2639,106 @@ pub mod logs {
     }
 }
 
+pub mod changes {
+    use chrono::{DateTime, Utc};
+    use std::fmt;
+
+    /// The kinds of record tracked in the change journal.
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "lowercase")]
+    pub enum ChangeRecordType {
+        #[cfg_attr(feature = "graphql", graphql(name = "host"))]
+        Host,
+        #[cfg_attr(feature = "graphql", graphql(name = "target"))]
+        Target,
+        #[cfg_attr(feature = "graphql", graphql(name = "filesystem"))]
+        Filesystem,
+        #[cfg_attr(feature = "graphql", graphql(name = "alert"))]
+        Alert,
+    }
+
+    impl ChangeRecordType {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Self::Host => "host",
+                Self::Target => "target",
+                Self::Filesystem => "filesystem",
+                Self::Alert => "alert",
+            }
+        }
+    }
+
+    impl fmt::Display for ChangeRecordType {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.as_str())
+        }
+    }
+
+    impl std::str::FromStr for ChangeRecordType {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "host" => Ok(Self::Host),
+                "target" => Ok(Self::Target),
+                "filesystem" => Ok(Self::Filesystem),
+                "alert" => Ok(Self::Alert),
+                x => Err(format!("Unknown record type {}", x)),
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+    #[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+    #[serde(rename_all = "lowercase")]
+    pub enum ChangeOp {
+        #[cfg_attr(feature = "graphql", graphql(name = "create"))]
+        Create,
+        #[cfg_attr(feature = "graphql", graphql(name = "update"))]
+        Update,
+        #[cfg_attr(feature = "graphql", graphql(name = "delete"))]
+        Delete,
+    }
+
+    impl std::str::FromStr for ChangeOp {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "create" => Ok(Self::Create),
+                "update" => Ok(Self::Update),
+                "delete" => Ok(Self::Delete),
+                x => Err(format!("Unknown change op {}", x)),
+            }
+        }
+    }
+
+    /// A single journaled change to a record.
+    #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct Change {
+        /// Cursor positioned just after this change
+        pub cursor: String,
+        pub record_type: ChangeRecordType,
+        pub record_id: i32,
+        pub op: ChangeOp,
+        pub changed_at: DateTime<Utc>,
+        /// The record as it was after the change (before it, for deletes), serialized as JSON
+        pub record: String,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    pub struct ChangeFeed {
+        pub changes: Vec<Change>,
+        /// Pass this back as `sinceCursor` to resume after the last change returned
+        pub cursor: String,
+        /// `true` if more changes are available past `cursor`
+        pub has_more: bool,
+    }
+}
+
 /// A `StratagemConfiguration` record from `api/stratagem_configuration`.
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct StratagemConfiguration {
@@ -1491,6 +2856,9 @@ pub struct Session {
     pub read_enabled: bool,
     pub resource_uri: String,
     pub user: Option<User>,
+    /// Seconds until the session is logged out due to inactivity
+    #[serde(default)]
+    pub idle_expires_in: Option<i64>,
 }
 
 impl EndpointName for Session {
@@ -1626,6 +2994,104 @@ pub struct ResourceAgentInfo {
     pub ops: PacemakerOperations,
 }
 
+/// The state of a corosync ring, as seen by one node
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct CorosyncRing {
+    pub ring: i32,
+    /// The local address of the ring
+    pub address: String,
+    /// The status line reported by `corosync-cfgtool`
+    pub status: String,
+    pub faulty: bool,
+}
+
+/// How a tuning setting is applied to a server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum TuningSettingKind {
+    /// A kernel parameter, set with `sysctl -w`
+    #[cfg_attr(feature = "graphql", graphql(name = "sysctl"))]
+    Sysctl,
+    /// A Lustre parameter, set with `lctl set_param`
+    #[cfg_attr(feature = "graphql", graphql(name = "lctl"))]
+    Lctl,
+    /// A kernel module option as `module.option`, e.g. `ko2iblnd.peer_credits` or `libcfs.cpu_pattern`.
+    /// These are written to modprobe.d and take effect the next time the module is loaded.
+    #[cfg_attr(feature = "graphql", graphql(name = "module_param"))]
+    ModuleParam,
+}
+
+/// One setting of a tuning profile
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct TuningSetting {
+    pub kind: TuningSettingKind,
+    pub key: String,
+    pub value: String,
+}
+
+/// How a dashboard panel is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum DashboardPanelKind {
+    /// A chart of `metric` over time
+    #[cfg_attr(feature = "graphql", graphql(name = "chart"))]
+    Chart,
+    /// Capacity, inodes, clients, targets and alerts of a filesystem, or of all filesystems
+    #[cfg_attr(feature = "graphql", graphql(name = "overview"))]
+    Overview,
+}
+
+/// What a dashboard chart shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum DashboardMetric {
+    /// Read and write bandwidth and IOPS, of all filesystems or of an OST
+    #[cfg_attr(feature = "graphql", graphql(name = "io"))]
+    Io,
+    /// LNet traffic of all servers
+    #[cfg_attr(feature = "graphql", graphql(name = "lnet"))]
+    Lnet,
+    /// Usage of each OST, of all filesystems or of a filesystem
+    #[cfg_attr(feature = "graphql", graphql(name = "ost_balance"))]
+    OstBalance,
+    /// Space usage of a filesystem
+    #[cfg_attr(feature = "graphql", graphql(name = "filesystem_usage"))]
+    FilesystemUsage,
+    /// Inode usage of the MDTs of a filesystem
+    #[cfg_attr(feature = "graphql", graphql(name = "mdt_usage"))]
+    MdtUsage,
+    /// Metadata operations of an MDT
+    #[cfg_attr(feature = "graphql", graphql(name = "metadata_operations"))]
+    MetadataOperations,
+    /// Space usage of a target
+    #[cfg_attr(feature = "graphql", graphql(name = "space_usage"))]
+    SpaceUsage,
+    /// File or object usage of a target
+    #[cfg_attr(feature = "graphql", graphql(name = "file_usage"))]
+    FileUsage,
+}
+
+/// One panel of a dashboard.
+/// A panel is scoped to at most one of `fs_name` or `target_name`, unscoped panels cover everything.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct DashboardPanel {
+    pub title: String,
+    pub kind: DashboardPanelKind,
+    /// Set for `chart` panels only
+    pub metric: Option<DashboardMetric>,
+    pub fs_name: Option<String>,
+    pub target_name: Option<String>,
+    /// Span the full width of the dashboard instead of half of it
+    #[serde(default)]
+    pub wide: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderingKind {
@@ -0,0 +1,62 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! LNet ping meshes: every host of a set pings the NIDs of every other host,
+//! showing which pairs can't reach each other or are slow to.
+
+use chrono::{DateTime, Utc};
+
+/// What a host is asked to ping
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct LnetPingArgs {
+    pub nids: Vec<String>,
+    /// How many times each NID is pinged
+    pub count: u32,
+}
+
+/// How pinging a NID went, as seen from the host that pinged it
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+pub struct LnetPingResult {
+    pub nid: String,
+    pub sent: u32,
+    pub received: u32,
+    /// The mean round trip of the pings that were answered
+    pub avg_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<f64>,
+    /// The last error of a ping that was not answered
+    pub error: Option<String>,
+}
+
+/// How one host reached one NID of another host during a mesh
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct LnetPingPair {
+    pub source_host_id: i32,
+    pub source_fqdn: String,
+    pub target_host_id: i32,
+    pub target_fqdn: String,
+    pub target_nid: String,
+    pub sent: i32,
+    pub received: i32,
+    /// The share of pings that went unanswered, from 0 to 100
+    pub loss_percent: f64,
+    pub avg_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<f64>,
+    /// Why pings went unanswered, or why the source host could not ping at all
+    pub error: Option<String>,
+}
+
+/// A run of pings among a set of hosts
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct LnetPingMesh {
+    pub id: i32,
+    pub host_ids: Vec<i32>,
+    /// How many times each NID was pinged by each host
+    pub count: i32,
+    pub started_at: DateTime<Utc>,
+    /// `null` while hosts are still pinging, pairs are added as each host finishes
+    pub finished_at: Option<DateTime<Utc>>,
+    pub pairs: Vec<LnetPingPair>,
+}
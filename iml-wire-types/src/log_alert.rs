@@ -0,0 +1,80 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! User-defined rules that raise alerts when incoming log messages match them.
+
+use crate::{graphql_duration::GraphQLDuration, AlertSeverity, LogSeverity};
+use chrono::{DateTime, Utc};
+
+/// A rule that raises a `LogPatternAlert` once `min_matches` log messages matched it
+/// within `within`.
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct LogAlertRule {
+    pub id: i32,
+    pub name: String,
+    pub enabled: bool,
+    /// Regular expression the message has to match
+    pub message_pattern: String,
+    /// Regular expression the host has to match, any host when `null`
+    pub fqdn_pattern: Option<String>,
+    /// Regular expression the tag has to match, any tag when `null`
+    pub tag_pattern: Option<String>,
+    /// The least severe messages matched
+    pub severity: LogSeverity,
+    pub min_matches: i32,
+    pub within: GraphQLDuration,
+    /// The severity of the alert raised
+    pub alert_severity: AlertSeverity,
+    /// Whether the alert of this rule is currently raised
+    pub active: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+pub struct LogAlertRuleInput {
+    pub name: String,
+    /// Regular expression the message has to match
+    pub message_pattern: String,
+    /// Regular expression the host has to match, any host when omitted
+    pub fqdn_pattern: Option<String>,
+    /// Regular expression the tag has to match, any tag when omitted
+    pub tag_pattern: Option<String>,
+    /// The least severe messages matched. Defaults to `DEBUG`, i.e. every message
+    pub severity: Option<LogSeverity>,
+    /// Defaults to 1
+    pub min_matches: Option<i32>,
+    /// Defaults to 5 minutes
+    pub within: Option<GraphQLDuration>,
+    /// Defaults to `WARNING`
+    pub alert_severity: Option<AlertSeverity>,
+    /// Defaults to `true`
+    pub enabled: Option<bool>,
+}
+
+/// What a rule would have done with recent log messages
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct LogAlertRuleTest {
+    /// How many messages were replayed
+    pub scanned: i32,
+    /// How many of them matched
+    pub matched: i32,
+    /// The most matches within the window of the rule
+    pub max_within: i32,
+    /// Whether the rule would have raised an alert
+    pub would_alert: bool,
+    /// The most recent matches, newest first
+    pub samples: Vec<LogAlertMatch>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct LogAlertMatch {
+    pub datetime: DateTime<Utc>,
+    pub fqdn: String,
+    pub tag: String,
+    pub severity: LogSeverity,
+    pub message: String,
+}
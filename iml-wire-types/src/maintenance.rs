@@ -0,0 +1,65 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Scheduled maintenance of hosts, filesystems and clusters.
+
+use chrono::{DateTime, Utc};
+
+/// What a maintenance window covers
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[cfg_attr(feature = "postgres-interop", derive(sqlx::Type))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename = "maintenance_scope"))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename_all = "lowercase"))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MaintenanceScope {
+    /// A managed host and the targets it serves
+    #[cfg_attr(feature = "graphql", graphql(name = "host"))]
+    Host,
+    /// A filesystem and its targets
+    #[cfg_attr(feature = "graphql", graphql(name = "filesystem"))]
+    Filesystem,
+    /// The hosts of a corosync cluster and the targets they serve
+    #[cfg_attr(feature = "graphql", graphql(name = "cluster"))]
+    Cluster,
+}
+
+/// A scheduled maintenance window
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct MaintenanceWindow {
+    pub id: i32,
+    pub name: String,
+    pub scope: MaintenanceScope,
+    /// The id of the host, filesystem or cluster
+    pub scope_id: i32,
+    /// The fqdn of the host, or the name of the filesystem or cluster.
+    /// `null` once it was removed
+    pub scope_name: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Whether alerts raised in scope while the window is active are dismissed
+    pub suppress_alerts: bool,
+    /// The user that scheduled the window
+    pub created_by: Option<String>,
+}
+
+impl MaintenanceWindow {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+pub struct MaintenanceWindowInput {
+    pub name: String,
+    pub scope: MaintenanceScope,
+    /// The id of the host, filesystem or cluster
+    pub scope_id: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Defaults to `true`
+    pub suppress_alerts: Option<bool>,
+}
@@ -0,0 +1,64 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Health of the device-mapper multipath devices targets are created on.
+
+use chrono::{DateTime, Utc};
+
+/// A path of a multipath map, as reported by `multipathd`
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct MultipathPath {
+    /// The name of the map the path belongs to, e.g. `mpatha`
+    pub map: String,
+    /// The block device of the path, e.g. `sdb`
+    pub dev: String,
+    /// The device-mapper state of the path, `active` or `failed`
+    pub dm_state: String,
+    /// The path checker state, e.g. `ready` or `faulty`
+    pub checker_state: String,
+}
+
+impl MultipathPath {
+    pub fn is_failed(&self) -> bool {
+        self.dm_state == "failed" || self.checker_state == "faulty"
+    }
+}
+
+/// The paths to a target's multipath device, as seen by one of its hosts
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct TargetMultipath {
+    pub host_id: i32,
+    pub fqdn: String,
+    /// The name of the multipath map, e.g. `mpatha`
+    pub map_name: String,
+    pub path_count: i32,
+    /// The block devices of the paths that have failed
+    pub failed_paths: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// A path to a target's multipath device failing or coming back
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct MultipathEvent {
+    pub host_id: i32,
+    pub fqdn: String,
+    /// The block device of the path
+    pub path: String,
+    /// `true` when the path failed, `false` when it came back
+    pub failed: bool,
+    pub at: DateTime<Utc>,
+}
+
+/// Multipath health of a target
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct TargetMultipathStatus {
+    /// One entry per host that can mount the target.
+    /// Empty when the target is not on a multipath device
+    pub hosts: Vec<TargetMultipath>,
+    /// Path failures and recoveries, newest first
+    pub events: Vec<MultipathEvent>,
+}
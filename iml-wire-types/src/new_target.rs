@@ -0,0 +1,67 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Adding MDTs and OSTs to existing filesystems.
+
+use crate::{
+    db::{Id, TableName, TargetKind},
+    Command,
+};
+use chrono::{DateTime, Utc};
+
+/// Formats `device` as target `index` of `filesystem`, and creates the directory it is mounted on
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct FormatTarget {
+    pub kind: TargetKind,
+    pub filesystem: String,
+    pub index: i32,
+    pub device: String,
+    /// The NIDs of each server of the MGS, comma separated
+    pub mgs_nids: Vec<String>,
+    pub mountpoint: String,
+}
+
+/// A new OST, on `device` of the server `host_id`
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+pub struct OstSpecInput {
+    #[serde(rename(serialize = "hostId"))]
+    pub host_id: i32,
+    pub device: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct AddOstResult {
+    pub command: Command,
+    /// The names of the new OSTs, in the order they were asked for
+    pub targets: Vec<String>,
+    /// Set when rebalancing was not asked for, but the existing OSTs
+    /// are much fuller than the filesystem will be once the new ones are added
+    pub rebalance_hint: Option<String>,
+}
+
+/// A target that is being added to a filesystem and is not known to the manager yet.
+/// Removed once the target is, or once the command adding it failed.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct PendingTarget {
+    pub id: i32,
+    /// The name the target will have, e.g. `fs-OST0004`
+    pub name: String,
+    pub fs_name: String,
+    pub host_id: i32,
+    pub dev_path: String,
+    /// The command adding the target, `null` until it started
+    pub command_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Id for PendingTarget {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+pub const PENDING_TARGET_TABLE_NAME: TableName = TableName("pending_target");
@@ -0,0 +1,62 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Recovery of targets after they are mounted, e.g. after a failover, as reported by
+//! `recovery_status`.
+
+use chrono::{DateTime, Utc};
+
+/// The `recovery_status` of a target mounted on a server
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct TargetRecovery {
+    /// The target name, e.g. `fs-OST0000`
+    pub target: String,
+    /// `RECOVERING`, `COMPLETE`, `INACTIVE` or `WAITING`
+    pub status: String,
+    /// Seconds left before recovery times out, while recovering
+    pub time_remaining: Option<i32>,
+    /// Clients that have reconnected, while recovering
+    pub connected_clients: Option<i32>,
+    /// Clients the target expects back
+    pub total_clients: Option<i32>,
+    /// Clients that are done with recovery
+    pub completed_clients: Option<i32>,
+    /// Clients evicted because they did not reconnect in time
+    pub evicted_clients: Option<i32>,
+}
+
+impl TargetRecovery {
+    pub fn is_recovering(&self) -> bool {
+        self.status == "RECOVERING"
+    }
+}
+
+/// The latest recovery state of a target
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct TargetRecoveryStatus {
+    pub target_name: String,
+    /// The server the target is mounted on
+    pub fqdn: String,
+    /// `RECOVERING`, `COMPLETE`, `INACTIVE` or `WAITING`
+    pub status: String,
+    pub time_remaining: Option<i32>,
+    pub connected_clients: Option<i32>,
+    pub total_clients: Option<i32>,
+    pub completed_clients: Option<i32>,
+    pub evicted_clients: Option<i32>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The recovery state of every mounted target of a filesystem
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct FilesystemRecoveryStatus {
+    pub fs_name: String,
+    /// `true` while any target is recovering
+    pub recovering: bool,
+    /// The longest time any target has left, in seconds
+    pub time_remaining: Option<i32>,
+    pub targets: Vec<TargetRecoveryStatus>,
+}
@@ -0,0 +1,91 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Custom roles, granting users permissions beyond their group.
+
+use std::fmt;
+
+/// Something a role allows its users to do
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[derive(
+    serde::Serialize, serde::Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Take, mount and unmount snapshots
+    #[cfg_attr(feature = "graphql", graphql(name = "create_snapshots"))]
+    CreateSnapshots,
+    /// Destroy, hold and release snapshots
+    #[cfg_attr(feature = "graphql", graphql(name = "delete_snapshots"))]
+    DeleteSnapshots,
+    /// Manage snapshot intervals, retention policies and their settings
+    #[cfg_attr(feature = "graphql", graphql(name = "manage_snapshot_policies"))]
+    ManageSnapshotPolicies,
+    /// Run Stratagem scans and tasks, and manage their rule sets and reports
+    #[cfg_attr(feature = "graphql", graphql(name = "run_stratagem"))]
+    RunStratagem,
+    /// Manage roles and who they are assigned to
+    #[cfg_attr(feature = "graphql", graphql(name = "manage_roles"))]
+    ManageRoles,
+    /// Set and remove the banners shown to every user
+    #[cfg_attr(feature = "graphql", graphql(name = "manage_banners"))]
+    ManageBanners,
+    /// Approve the commands other users submitted that need a second user's approval
+    #[cfg_attr(feature = "graphql", graphql(name = "approve_commands"))]
+    ApproveCommands,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 7] = [
+        Permission::CreateSnapshots,
+        Permission::DeleteSnapshots,
+        Permission::ManageSnapshotPolicies,
+        Permission::RunStratagem,
+        Permission::ManageRoles,
+        Permission::ManageBanners,
+        Permission::ApproveCommands,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::CreateSnapshots => "create_snapshots",
+            Self::DeleteSnapshots => "delete_snapshots",
+            Self::ManageSnapshotPolicies => "manage_snapshot_policies",
+            Self::RunStratagem => "run_stratagem",
+            Self::ManageRoles => "manage_roles",
+            Self::ManageBanners => "manage_banners",
+            Self::ApproveCommands => "approve_commands",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|x| x.as_str() == s)
+            .ok_or_else(|| format!("Unknown permission {}", s))
+    }
+}
+
+/// A named set of permissions, and the users it is assigned to
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct Role {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub permissions: Vec<Permission>,
+    /// The ids of the users the role is assigned to
+    pub user_ids: Vec<i32>,
+}
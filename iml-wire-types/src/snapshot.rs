@@ -36,6 +36,143 @@ pub struct Snapshot {
     pub mounted: bool,
     /// Optional comment for the snapshot
     pub comment: Option<String>,
+    /// Held snapshots are never deleted by a retention policy,
+    /// and can only be destroyed with `force`
+    #[serde(default)]
+    pub held: bool,
+    /// The policy run that took the snapshot, `null` for snapshots taken manually
+    #[serde(default)]
+    pub policy_run_id: Option<i32>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+/// A snapshot taken automatically by a snapshot interval
+pub struct SnapshotPolicyRun {
+    /// The id of the interval that took the snapshot
+    pub interval_id: i32,
+    pub snapshot_name: String,
+    /// When the run was started
+    pub started_at: DateTime<Utc>,
+    /// One of `pending`, `tasked`, `complete`, `failed` or `cancelled`
+    pub state: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+/// Settings shared by every snapshot policy
+pub struct SnapshotPolicySettings {
+    /// How many snapshots intervals may be taking at once, `0` for no cap.
+    /// Manually created snapshots count towards the cap, but are never held back by it
+    pub max_concurrent: i32,
+    /// Each interval run is delayed by a random time up to this long,
+    /// so intervals that are due together don't all start at once
+    pub jitter: GraphQLDuration,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+/// A time a snapshot interval was due, and when its snapshot was started.
+/// Snapshots the thresholds of a retention policy take or delete are recorded as runs too
+pub struct SnapshotIntervalRun {
+    pub id: i32,
+    /// The id of the interval that was due, `null` for runs of a retention threshold
+    pub interval_id: Option<i32>,
+    pub filesystem_name: String,
+    pub snapshot_name: String,
+    /// When the interval was due
+    pub due_at: DateTime<Utc>,
+    /// `dueAt` plus the jitter picked for the run
+    pub start_after: DateTime<Utc>,
+    /// `null` while the run is queued
+    pub started_at: Option<DateTime<Utc>>,
+    /// The command taking the snapshot
+    pub command_id: Option<i32>,
+    /// One of `queued`, `started` or `failed`
+    pub state: String,
+    /// The run waited for other snapshots to finish because of `maxConcurrent`
+    pub throttled: bool,
+    /// Why the snapshot could not be started
+    pub error: Option<String>,
+    /// What started the run: `interval`, `change` for the `changePercent` threshold,
+    /// `reserve` or `mdt_reserve` for the free space reserves of the retention policy
+    pub reason: String,
+    /// `create` or `destroy`
+    pub action: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+/// What is kept of a destroyed snapshot
+pub struct SnapshotHistoryEntry {
+    pub id: i32,
+    pub filesystem_name: String,
+    pub snapshot_name: String,
+    pub snapshot_fsname: String,
+    pub create_time: DateTime<Utc>,
+    pub comment: Option<String>,
+    /// Whether the snapshot was taken by a policy run rather than manually
+    pub taken_by_policy: bool,
+    /// When the manager saw the snapshot gone
+    pub destroyed_at: DateTime<Utc>,
+    /// The user that destroyed the snapshot, `null` when it was destroyed outside
+    /// the manager or by a service
+    pub destroyed_by: Option<String>,
+    /// The command that destroyed the snapshot
+    pub command_id: Option<i32>,
+    /// The threshold of the retention policy that destroyed the snapshot,
+    /// `reserve` or `mdt_reserve`
+    pub reason: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+/// Two snapshots of the same filesystem, and the automatic snapshot runs between them
+pub struct SnapshotComparison {
+    /// The older of the two snapshots
+    pub from: Snapshot,
+    /// The newer of the two snapshots
+    pub to: Snapshot,
+    /// Interval runs started after `from` and before `to` was created, oldest first
+    pub policy_runs: Vec<SnapshotPolicyRun>,
+}
+
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+/// What a snapshot pre-flight check looks at
+pub enum SnapshotCheck {
+    /// The write barrier can be set: every MDT is mounted and no barrier is in progress
+    #[cfg_attr(feature = "graphql", graphql(name = "barrier"))]
+    Barrier,
+    /// Every MDT has enough free space for the snapshot
+    #[cfg_attr(feature = "graphql", graphql(name = "mdt_space"))]
+    MdtSpace,
+    /// No snapshot of the filesystem has the same name
+    #[cfg_attr(feature = "graphql", graphql(name = "name_collision"))]
+    NameCollision,
+    /// No other snapshot operation is running on the filesystem
+    #[cfg_attr(feature = "graphql", graphql(name = "conflicting_operation"))]
+    ConflictingOperation,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+/// The outcome of a snapshot pre-flight check
+pub struct SnapshotCheckResult {
+    pub check: SnapshotCheck,
+    pub passed: bool,
+    /// Why the check failed, or what it found
+    pub message: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+/// The checks run before the jobs that take a snapshot are submitted
+pub struct SnapshotPreflight {
+    /// Every check passed
+    pub passed: bool,
+    pub checks: Vec<SnapshotCheckResult>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
@@ -48,6 +185,10 @@ pub struct SnapshotRecord {
     pub snapshot_fsname: String,
     pub mounted: bool,
     pub comment: Option<String>,
+    #[serde(default)]
+    pub held: bool,
+    #[serde(default)]
+    pub policy_run_id: Option<i32>,
 }
 
 impl Id for SnapshotRecord {
@@ -72,6 +213,33 @@ pub struct SnapshotInterval {
     pub interval: GraphQLDuration,
     // Last known run
     pub last_run: Option<DateTime<Utc>>,
+    /// Mount snapshots as soon as they are taken
+    #[serde(default)]
+    pub automount: bool,
+    /// The wall-clock time, as HH:MM in `time_zone`, runs are aligned to.
+    /// Runs are counted from when the interval was created when not set
+    #[serde(default)]
+    pub start_time: Option<String>,
+    /// The IANA time zone `start_time` is in
+    #[serde(default = "default_time_zone")]
+    pub time_zone: String,
+    /// When the interval next runs, if it has a `start_time`
+    #[serde(default)]
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+pub fn default_time_zone() -> String {
+    "UTC".into()
+}
+
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+/// The next runs of an interval with a start time
+pub struct SnapshotSchedule {
+    /// The IANA time zone the start time is in
+    pub time_zone: String,
+    /// When snapshots would be taken, soonest first
+    pub runs: Vec<DateTime<Utc>>,
 }
 
 impl Id for SnapshotInterval {
@@ -82,6 +250,47 @@ impl Id for SnapshotInterval {
 
 pub const SNAPSHOT_INTERVAL_TABLE_NAME: TableName = TableName("snapshot_interval");
 
+/// Snapshots taken by the `changePercent` threshold of a retention policy
+/// are named like interval snapshots, with this interval id.
+pub const CHANGE_THRESHOLD_ID: i32 = 0;
+
+/// The name of the snapshot an interval takes when it is due at `due_at`,
+/// `{interval id}-{fs name}-{timestamp}`.
+pub fn snapshot_name(interval_id: i32, fs_name: &str, due_at: DateTime<Utc>) -> String {
+    format!(
+        "{}-{}-{}",
+        interval_id,
+        fs_name,
+        due_at.format("%Y-%m-%dT%H:%M:%SZ")
+    )
+}
+
+/// The parts of the name of a snapshot taken by a `SnapshotInterval`,
+/// `{interval id}-{fs name}-{timestamp}`
+pub struct SnapshotIntervalName {
+    pub id: i32,
+    pub fs_name: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Parses the name of a snapshot taken by a `SnapshotInterval`.
+/// Returns `None` for snapshots that were taken manually.
+pub fn parse_snapshot_name(name: &str) -> Option<SnapshotIntervalName> {
+    match name.trim().splitn(3, '-').collect::<Vec<&str>>().as_slice() {
+        [id, fs, ts] => {
+            let ts = ts.parse::<DateTime<Utc>>().ok()?;
+            let id = id.parse::<i32>().ok()?;
+
+            Some(SnapshotIntervalName {
+                id,
+                fs_name: fs.to_string(),
+                timestamp: ts,
+            })
+        }
+        _ => None,
+    }
+}
+
 #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
 #[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
 pub struct SnapshotRetention {
@@ -93,6 +302,20 @@ pub struct SnapshotRetention {
     /// Minimum number of snapshots to keep
     pub keep_num: i32,
     pub last_run: Option<DateTime<Utc>>,
+    /// Incremented on every update, used for optimistic locking
+    pub version: i32,
+    pub updated_at: DateTime<Utc>,
+    /// Manual snapshots take up so many of the `keep_num` slots
+    /// that snapshots taken by intervals are being deleted early
+    #[serde(default)]
+    pub crowded_by_manual: bool,
+    /// Delete the oldest snapshots while the MDTs have less than this percent of space free
+    #[serde(default)]
+    pub mdt_reserve_percent: Option<i32>,
+    /// Take a snapshot once the used space changed by this percent of the filesystem size
+    /// since the last snapshot taken for it
+    #[serde(default)]
+    pub change_percent: Option<i32>,
 }
 
 impl Id for SnapshotRetention {
@@ -103,6 +326,69 @@ impl Id for SnapshotRetention {
 
 pub const SNAPSHOT_RETENTION_TABLE_NAME: TableName = TableName("snapshot_retention");
 
+/// Snapshot policies as `exportSnapshotPolicies` writes them and `importSnapshotPolicies` reads them,
+/// so they can be kept in version control and applied to other filesystems or managers
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotPolicyFile {
+    /// The settings shared by every policy, left as they are when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<SnapshotPolicySettings>,
+    #[serde(default)]
+    pub filesystems: Vec<FilesystemSnapshotPolicy>,
+}
+
+/// The snapshot intervals and retention policy of a filesystem
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FilesystemSnapshotPolicy {
+    pub filesystem: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub intervals: Vec<SnapshotIntervalPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<SnapshotRetentionPolicy>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotIntervalPolicy {
+    pub interval: GraphQLDuration,
+    #[serde(default)]
+    pub use_barrier: bool,
+    #[serde(default)]
+    pub automount: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotRetentionPolicy {
+    pub reserve_value: i32,
+    pub reserve_unit: ReserveUnit,
+    #[serde(default)]
+    pub keep_num: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mdt_reserve_percent: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_percent: Option<i32>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+/// What importing snapshot policies changed
+pub struct SnapshotPolicyImport {
+    /// The filesystems the policies were applied to
+    pub filesystems: Vec<String>,
+    pub intervals_added: i32,
+    pub intervals_removed: i32,
+    pub retentions_set: i32,
+    pub retentions_removed: i32,
+    pub settings_changed: bool,
+}
+
 #[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
 #[cfg_attr(feature = "postgres-interop", derive(sqlx::Type))]
 #[cfg_attr(feature = "postgres-interop", sqlx(rename = "snapshot_reserve_unit"))]
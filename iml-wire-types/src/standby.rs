@@ -0,0 +1,46 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Warm standby managers: how far behind the primary database each replays,
+//! when it last synced the manager configuration, and whether it could take over.
+
+use chrono::{DateTime, Utc};
+
+/// A standby manager, as seen from the primary
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StandbyManager {
+    /// The `application_name` the standby streams the database with
+    pub name: String,
+    /// `null` while the standby is not connected
+    pub client_addr: Option<String>,
+    /// The replication state, such as `streaming` or `catchup`, `null` while not connected
+    pub state: Option<String>,
+    /// `async`, `potential` or `sync`
+    pub sync_state: Option<String>,
+    /// WAL the standby has yet to replay, in bytes
+    pub lag_bytes: Option<f64>,
+    /// The hash of the configuration the standby last synced
+    pub config_hash: Option<String>,
+    pub config_synced_at: Option<DateTime<Utc>>,
+    /// The standby is streaming, within the lag threshold and synced its configuration lately
+    pub failover_ready: bool,
+    /// Why the standby could not take over, empty when it is ready
+    pub issues: Vec<String>,
+}
+
+/// Replication to the standby managers
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StandbyStatus {
+    /// The database the API is connected to is itself a standby.
+    /// Only the primary sees its standbys, so `standbys` is empty then
+    pub in_recovery: bool,
+    /// How long ago the last transaction replayed from the primary was committed, when `inRecovery`
+    pub replay_lag_secs: Option<f64>,
+    /// The lag beyond which a standby is not ready to take over, in bytes
+    pub lag_threshold_bytes: f64,
+    pub standbys: Vec<StandbyManager>,
+    pub checked_at: DateTime<Utc>,
+}
@@ -0,0 +1,119 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! The stonith (fencing) devices of a pacemaker cluster, and the tests that check
+//! every node of a cluster can be fenced.
+
+use chrono::{DateTime, Utc};
+
+/// An instance attribute of a stonith device, e.g. `ip` or `username` of `fence_ipmilan`
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StonithParam {
+    pub name: String,
+    /// `null` for credentials, which are never read back from the cluster
+    pub value: Option<String>,
+}
+
+/// A stonith device as configured in the CIB
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StonithDevice {
+    /// The resource id, e.g. `st-oss1`
+    pub id: String,
+    /// The fence agent, e.g. `fence_ipmilan`
+    pub agent: String,
+    /// The nodes this device can fence, from `pcmk_host_list`.
+    /// Empty when the agent is asked which nodes it can fence
+    pub host_list: Vec<String>,
+    /// `false` when the `target-role` of the device is not `Started`
+    pub enabled: bool,
+    pub params: Vec<StonithParam>,
+}
+
+/// The outcome of monitoring a stonith device, which checks the fence agent can
+/// reach what it controls (e.g. the BMC) without fencing anything
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StonithDeviceTest {
+    pub id: String,
+    pub passed: bool,
+    /// The output of the monitor when it failed
+    pub error: Option<String>,
+}
+
+/// Whether a node can be fenced: at least one device that passed its monitor can fence it
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StonithNodeTest {
+    pub node: String,
+    /// The devices that can fence the node
+    pub devices: Vec<String>,
+    pub passed: bool,
+}
+
+/// The devices and nodes checked by a stonith test
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StonithTestResult {
+    pub devices: Vec<StonithDeviceTest>,
+    pub nodes: Vec<StonithNodeTest>,
+}
+
+impl StonithTestResult {
+    /// `true` when every node can be fenced and every device passed
+    pub fn passed(&self) -> bool {
+        self.devices.iter().all(|x| x.passed) && self.nodes.iter().all(|x| x.passed)
+    }
+}
+
+/// A stonith test of a cluster
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StonithTestRun {
+    pub id: i32,
+    pub cluster_id: i32,
+    pub ran_at: DateTime<Utc>,
+    pub passed: bool,
+    /// Why the test could not be run, e.g. no node of the cluster is online
+    pub error: Option<String>,
+    pub result: Option<StonithTestResult>,
+}
+
+/// The stonith configuration of a cluster
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct ClusterStonith {
+    pub cluster_id: i32,
+    /// The corosync node names of the cluster
+    pub nodes: Vec<String>,
+    /// `false` when the `stonith-enabled` cluster property is false
+    pub enabled: bool,
+    pub devices: Vec<StonithDevice>,
+    /// Why the devices could not be read
+    pub error: Option<String>,
+    pub last_test: Option<StonithTestRun>,
+}
+
+/// The stonith configuration read from the CIB
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+pub struct StonithConfig {
+    pub enabled: bool,
+    pub devices: Vec<StonithDevice>,
+}
+
+/// A stonith device to create or update
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+pub struct StonithDeviceInput {
+    /// The resource id. The device is updated when it exists, created otherwise
+    pub id: String,
+    /// The fence agent, e.g. `fence_ipmilan`. Can't be changed on an existing device
+    pub agent: String,
+    /// The nodes this device can fence
+    pub host_list: Vec<String>,
+    /// Instance attributes as `key=value`, e.g. `ip=10.0.0.1`.
+    /// On update, attributes that are left out keep their value
+    pub params: Vec<String>,
+}
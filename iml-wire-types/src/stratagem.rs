@@ -2,6 +2,9 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+use crate::{graphql::PageMeta, graphql_duration::GraphQLDuration};
+use chrono::{DateTime, Utc};
+
 /// The device that is scanned for matching rules.
 #[derive(Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct StratagemDevice {
@@ -47,3 +50,305 @@ impl StratagemConfig {
         self.groups.iter().find(|g| g.name == name)
     }
 }
+
+/// A file attribute a custom rule can match on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum RuleField {
+    /// Time since last access. The value is a duration, i.e. `30days`
+    Atime,
+    /// Time since last modification. The value is a duration
+    Mtime,
+    /// Time since last status change. The value is a duration
+    Ctime,
+    /// File size in bytes
+    Size,
+    Uid,
+    Gid,
+}
+
+/// How a `RuleField` is compared against a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+/// What happens to files matched by a custom rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Count matching files
+    Count,
+    /// List matching files in a report
+    Report,
+    /// Purge matching files
+    Purge,
+}
+
+/// A single comparison within a custom rule.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+pub struct RuleCondition {
+    pub field: RuleField,
+    pub op: RuleOp,
+    pub value: String,
+}
+
+/// A custom rule. A file matches when all of its conditions match.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+pub struct CustomRuleInput {
+    /// Used to name the counter and any report produced by the rule
+    pub name: String,
+    pub action: RuleAction,
+    pub conditions: Vec<RuleCondition>,
+}
+
+/// A custom rule compiled into a scan expression.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct CompiledRule {
+    pub name: String,
+    pub action: RuleAction,
+    pub expression: String,
+}
+
+/// A saved, named set of custom rules that can be reused across scans.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StratagemRuleSet {
+    pub id: i32,
+    pub name: String,
+    pub rules: Vec<CompiledRule>,
+}
+
+/// Where the scan of a single device is at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum ScanPhase {
+    #[cfg_attr(feature = "graphql", graphql(name = "pending"))]
+    Pending,
+    #[cfg_attr(feature = "graphql", graphql(name = "scanning"))]
+    Scanning,
+    #[cfg_attr(feature = "graphql", graphql(name = "streaming"))]
+    Streaming,
+    #[cfg_attr(feature = "graphql", graphql(name = "complete"))]
+    Complete,
+    #[cfg_attr(feature = "graphql", graphql(name = "failed"))]
+    Failed,
+    #[cfg_attr(feature = "graphql", graphql(name = "cancelled"))]
+    Cancelled,
+}
+
+/// Progress of the scan of a single MDT.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct DeviceScanProgress {
+    pub fqdn: String,
+    pub device: String,
+    pub phase: ScanPhase,
+    /// Entries scanned on the device, once its scan has finished
+    pub entries_scanned: Option<f64>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Progress of the scans started by a command.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct ScanProgress {
+    pub command_id: i32,
+    pub devices_total: i32,
+    pub devices_completed: i32,
+    /// Entries scanned across all devices that have finished scanning
+    pub entries_scanned: f64,
+    pub devices: Vec<DeviceScanProgress>,
+}
+
+/// Files and bytes under one top-level directory, as the `stratagem.project_usage`
+/// task action reports them for each batch of files it resolves.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProjectUsageEntry {
+    pub project: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Usage of one top-level directory of a filesystem.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct ProjectUsage {
+    /// The top-level directory. Files directly under the root are counted as `/`
+    pub project: String,
+    pub files: f64,
+    pub bytes: f64,
+}
+
+/// Usage of every top-level directory of a filesystem, from one project usage scan.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct ProjectUsageScan {
+    pub scanned_at: DateTime<Utc>,
+    /// Largest first
+    pub projects: Vec<ProjectUsage>,
+}
+
+/// Files and bytes under one directory by when they were last accessed, as the
+/// `stratagem.heatmap` task action reports them for each batch of files it resolves.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HeatmapEntry {
+    /// Relative to the root of the filesystem, which is `/`
+    pub path: String,
+    pub depth: u32,
+    pub files: u64,
+    pub bytes: u64,
+    pub recent_files: u64,
+    pub bytes_week: u64,
+    pub bytes_month: u64,
+    pub bytes_quarter: u64,
+    pub bytes_year: u64,
+    pub bytes_older: u64,
+}
+
+impl HeatmapEntry {
+    /// Adds the counts of `x` to these.
+    pub fn add(&mut self, x: &HeatmapEntry) {
+        self.files += x.files;
+        self.bytes += x.bytes;
+        self.recent_files += x.recent_files;
+        self.bytes_week += x.bytes_week;
+        self.bytes_month += x.bytes_month;
+        self.bytes_quarter += x.bytes_quarter;
+        self.bytes_year += x.bytes_year;
+        self.bytes_older += x.bytes_older;
+    }
+}
+
+/// Files and bytes under one directory of a filesystem, by when they were last accessed.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct DirectoryHeat {
+    /// Relative to the root of the filesystem, which is `/`
+    pub path: String,
+    /// How many levels below the root the directory is
+    pub depth: i32,
+    pub files: f64,
+    pub bytes: f64,
+    /// Files accessed within the last 7 days
+    pub recent_files: f64,
+    /// Bytes accessed within the last 7 days
+    pub bytes_week: f64,
+    /// Bytes last accessed 7 to 30 days ago
+    pub bytes_month: f64,
+    /// Bytes last accessed 30 to 90 days ago
+    pub bytes_quarter: f64,
+    /// Bytes last accessed 90 days to a year ago
+    pub bytes_year: f64,
+    /// Bytes last accessed over a year ago
+    pub bytes_older: f64,
+}
+
+/// The directories of a filesystem, from one heat map scan.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct FsHeatmap {
+    pub scanned_at: DateTime<Utc>,
+    /// Sorted by path, so each directory comes before the ones in it
+    pub directories: Vec<DirectoryHeat>,
+}
+
+/// A file not accessed for the `purge_after` of a purge policy, as the
+/// `stratagem.purge_candidates` task action reports it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PurgeCandidate {
+    pub fid: String,
+    /// Relative to the root of the filesystem
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// A scheduled scan of a filesystem that purges the files not accessed for a while,
+/// within caps on how much one run may purge.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct PurgePolicy {
+    pub filesystem_name: String,
+    pub enabled: bool,
+    /// How often the filesystem is scanned
+    pub interval: GraphQLDuration,
+    /// Files not accessed for this long are purged
+    pub purge_after: GraphQLDuration,
+    /// The most files one run purges
+    pub max_files: f64,
+    /// The most bytes one run purges
+    pub max_bytes: f64,
+    /// Files under these directories, relative to the root of the filesystem, are never purged
+    pub protected_paths: Vec<String>,
+    /// Whether an operator approves each run before anything is purged
+    pub require_approval: bool,
+    /// When the last scan was started
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+/// One run of a purge policy.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct PurgeRun {
+    pub id: i32,
+    pub filesystem_name: String,
+    /// `scanning`, `awaiting_approval`, `purging`, `purged`, `nothing_to_purge`,
+    /// `rejected` or `failed`
+    pub state: String,
+    pub scan_command_id: Option<i32>,
+    /// The `stratagem.purge` task removing the files
+    pub purge_task_id: Option<i32>,
+    /// Files the scan found not accessed for `purgeAfter`
+    pub candidate_files: f64,
+    pub candidate_bytes: f64,
+    /// Candidates left alone because they are under a protected path
+    pub protected_files: f64,
+    /// The candidates purged, or to be purged once approved
+    pub files: f64,
+    pub bytes: f64,
+    /// Whether the caps left some candidates out. They are found again by the next run
+    pub capped: bool,
+    pub started_at: DateTime<Utc>,
+    pub scanned_at: Option<DateTime<Utc>>,
+    /// Who approved or rejected the run
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// The FIDs queued for a task, e.g. the purge candidates found by a scan.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct FidList {
+    pub task_id: i32,
+    pub task_name: String,
+    /// How many FIDs are queued
+    pub fids: f64,
+    /// The size of the download in bytes
+    pub size: f64,
+    /// Where the list can be downloaded from, one FID per line.
+    /// `Range` requests are supported, so interrupted downloads can be resumed
+    pub url: String,
+}
+
+/// A page of the FIDs queued for a task, formatted as `[0xseq:0xoid:0xver]`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct FidQueuePage {
+    pub data: Vec<String>,
+    pub meta: PageMeta,
+}
@@ -0,0 +1,66 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! The default layout of new files in a filesystem: a stripe count and size,
+//! or a progressive file layout (PFL) made of several components.
+
+/// A component of a file layout. It covers the file from the end of the previous component
+/// up to `extent_end`.
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct StripeComponent {
+    /// Where the component ends in the file, in bytes. `null` for the end of the file
+    pub extent_end: Option<f64>,
+    /// How many OSTs the component is striped over, `-1` for all of them,
+    /// `0` for the default of the MDT
+    pub stripe_count: i32,
+    /// How many bytes are written to an OST before moving on to the next one,
+    /// a multiple of 64 KiB. `0` for the default of the MDT
+    pub stripe_size: f64,
+    /// The OST pool the component is allocated from
+    pub pool: Option<String>,
+}
+
+/// A component of a file layout to set, see `StripeComponent`
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+pub struct StripeComponentInput {
+    #[serde(rename(serialize = "extentEnd"))]
+    pub extent_end: Option<f64>,
+    #[serde(rename(serialize = "stripeCount"))]
+    pub stripe_count: i32,
+    #[serde(rename(serialize = "stripeSize"))]
+    pub stripe_size: f64,
+    pub pool: Option<String>,
+}
+
+impl From<StripeComponentInput> for StripeComponent {
+    fn from(x: StripeComponentInput) -> Self {
+        Self {
+            extent_end: x.extent_end,
+            stripe_count: x.stripe_count,
+            stripe_size: x.stripe_size,
+            pool: x.pool,
+        }
+    }
+}
+
+/// The default layout of new files in a filesystem, as `lfs getstripe -d` reports it
+/// for the root of the filesystem
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct DefaultStripe {
+    pub fs_name: String,
+    /// A single component ending at `null` for a plain layout, several for a PFL.
+    /// Empty when no default is set, and the defaults of the MDT apply
+    pub components: Vec<StripeComponent>,
+}
+
+/// Sets the default layout of new files in the root of `filesystem`.
+/// No components removes the default
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+pub struct SetDefaultStripe {
+    pub filesystem: String,
+    pub components: Vec<StripeComponent>,
+}
@@ -0,0 +1,51 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Remounting targets that went offline on their own.
+
+use crate::graphql_duration::GraphQLDuration;
+use chrono::{DateTime, Utc};
+
+/// Whether and how the targets of a filesystem are remounted
+/// when they go offline outside of any command
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct TargetRemountPolicy {
+    pub filesystem_name: String,
+    pub enabled: bool,
+    /// How many times a target is remounted before giving up
+    pub max_attempts: i32,
+    /// How long to wait after a target went offline, and between attempts
+    pub cooldown: GraphQLDuration,
+}
+
+/// An attempt at remounting a target that went offline
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct TargetRemountAttempt {
+    pub id: i32,
+    /// The `chroma_core_managedtarget` id
+    pub target_id: i32,
+    pub target_name: String,
+    pub filesystem_name: String,
+    /// 1 for the first attempt after the target went offline
+    pub attempt: i32,
+    /// The command starting the target, if it could be created
+    pub command_id: Option<i32>,
+    /// `running`, `succeeded`, `failed` or `cancelled`
+    pub state: String,
+    /// Why the command could not be created
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+/// The remount policy of a filesystem and its latest attempts
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct TargetRemountStatus {
+    /// `None` when targets of the filesystem are not remounted
+    pub policy: Option<TargetRemountPolicy>,
+    /// Newest first
+    pub attempts: Vec<TargetRemountAttempt>,
+}
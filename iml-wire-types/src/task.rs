@@ -2,10 +2,11 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+use crate::Command;
 use chrono::{DateTime, Utc};
 use std::{collections::HashMap, convert::TryFrom};
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
 pub struct KeyValue {
     pub key: String,
@@ -22,6 +23,7 @@ impl ToHashMap<String, String> for Vec<KeyValue> {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
 pub struct KeyValueOut {
     pub key: String,
@@ -60,6 +62,7 @@ pub struct TaskArgs {
     pub needs_cleanup: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
 pub struct TaskOut {
     pub id: i32,
@@ -79,6 +82,14 @@ pub struct TaskOut {
     pub running_on_id: Option<i32>,
 }
 
+/// The task created by the `task.create` mutation, and the command setting it up
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+pub struct CreateTaskResult {
+    pub task_id: i32,
+    pub command: Command,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct Task {
     pub id: i32,
@@ -141,3 +152,73 @@ impl TryFrom<Task> for TaskOut {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone as _;
+
+    fn task(args: serde_json::Value) -> Task {
+        Task {
+            id: 3,
+            name: "purge".into(),
+            start: Utc.ymd(2021, 2, 16).and_hms(9, 0, 0),
+            finish: None,
+            state: "started".into(),
+            fids_total: 10,
+            fids_completed: 7,
+            fids_failed: 1,
+            data_transfered: 4096,
+            single_runner: true,
+            keep_failed: false,
+            actions: vec!["stratagem.purge".into()],
+            args,
+            filesystem_id: 1,
+            running_on_id: Some(2),
+        }
+    }
+
+    #[test]
+    fn test_task_out_from_task() {
+        let x = TaskOut::try_from(task(serde_json::json!({ "report_name": "r1" }))).unwrap();
+
+        assert_eq!(
+            x,
+            TaskOut {
+                id: 3,
+                name: "purge".into(),
+                start: Utc.ymd(2021, 2, 16).and_hms(9, 0, 0),
+                finish: None,
+                state: "started".into(),
+                fids_total: 10.,
+                fids_completed: 7.,
+                fids_failed: 1.,
+                data_transfered: 4096.,
+                single_runner: true,
+                keep_failed: false,
+                actions: vec!["stratagem.purge".into()],
+                args: vec![KeyValueOut {
+                    key: "report_name".into(),
+                    value: "r1".into()
+                }],
+                filesystem_id: 1,
+                running_on_id: Some(2),
+            }
+        );
+    }
+
+    #[test]
+    fn test_task_out_from_task_with_bad_args() {
+        assert!(TaskOut::try_from(task(serde_json::json!({ "depth": 3 }))).is_err());
+        assert!(TaskOut::try_from(task(serde_json::json!(["report_name"]))).is_err());
+    }
+
+    #[test]
+    fn test_task_out_round_trip() {
+        let x = TaskOut::try_from(task(serde_json::json!({}))).unwrap();
+
+        let s = serde_json::to_string(&x).unwrap();
+
+        assert_eq!(serde_json::from_str::<TaskOut>(&s).unwrap(), x);
+    }
+}
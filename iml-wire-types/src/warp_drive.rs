@@ -10,6 +10,7 @@ use crate::{
         ManagedTargetRecord, OstPoolOstsRecord, OstPoolRecord, PacemakerConfigurationRecord,
         StratagemConfiguration, TargetRecord, VolumeNodeRecord, VolumeRecord,
     },
+    new_target::PendingTarget,
     sfa::{SfaController, SfaDiskDrive, SfaEnclosure, SfaJob, SfaPowerSupply, SfaStorageSystem},
     snapshot::{SnapshotInterval, SnapshotRecord, SnapshotRetention},
     Alert, CompositeId, EndpointNameSelf, Filesystem, Host, Label, LockChange, ToCompositeId,
@@ -103,6 +104,7 @@ pub struct Cache {
     pub lnet_configuration: HashMap<i32, LnetConfigurationRecord>,
     pub ost_pool: HashMap<i32, OstPoolRecord>,
     pub ost_pool_osts: HashMap<i32, OstPoolOstsRecord>,
+    pub pending_target: HashMap<i32, PendingTarget>,
     pub sfa_disk_drive: HashMap<i32, SfaDiskDrive>,
     pub sfa_enclosure: HashMap<i32, SfaEnclosure>,
     pub sfa_job: HashMap<i32, SfaJob>,
@@ -135,6 +137,7 @@ pub struct ArcCache {
     pub lnet_configuration: HashMap<i32, Arc<LnetConfigurationRecord>>,
     pub ost_pool: HashMap<i32, Arc<OstPoolRecord>>,
     pub ost_pool_osts: HashMap<i32, Arc<OstPoolOstsRecord>>,
+    pub pending_target: HashMap<i32, Arc<PendingTarget>>,
     pub pacemaker_configuration: HashMap<i32, Arc<PacemakerConfigurationRecord>>,
     pub sfa_disk_drive: HashMap<i32, Arc<SfaDiskDrive>>,
     pub sfa_enclosure: HashMap<i32, Arc<SfaEnclosure>>,
@@ -181,6 +184,9 @@ impl Cache {
             RecordId::ContentType(id) => self.content_type.remove(&id).map(Record::ContentType),
             RecordId::OstPool(id) => self.ost_pool.remove(&id).map(Record::OstPool),
             RecordId::OstPoolOsts(id) => self.ost_pool_osts.remove(&id).map(Record::OstPoolOsts),
+            RecordId::PendingTarget(id) => {
+                self.pending_target.remove(&id).map(Record::PendingTarget)
+            }
             RecordId::PacemakerConfiguration(id) => self
                 .pacemaker_configuration
                 .remove(&id)
@@ -256,6 +262,9 @@ impl Cache {
             Record::OstPoolOsts(x) => {
                 self.ost_pool_osts.insert(x.id(), x);
             }
+            Record::PendingTarget(x) => {
+                self.pending_target.insert(x.id, x);
+            }
             Record::PacemakerConfiguration(x) => {
                 self.pacemaker_configuration.insert(x.id, x);
             }
@@ -338,6 +347,7 @@ impl ArcCache {
             RecordId::LnetConfiguration(id) => self.lnet_configuration.remove(&id).is_some(),
             RecordId::OstPool(id) => self.ost_pool.remove(&id).is_some(),
             RecordId::OstPoolOsts(id) => self.ost_pool_osts.remove(&id).is_some(),
+            RecordId::PendingTarget(id) => self.pending_target.remove(&id).is_some(),
             RecordId::PacemakerConfiguration(id) => {
                 self.pacemaker_configuration.remove(&id).is_some()
             }
@@ -395,6 +405,9 @@ impl ArcCache {
             Record::OstPoolOsts(x) => {
                 self.ost_pool_osts.insert(x.id(), Arc::new(x));
             }
+            Record::PendingTarget(x) => {
+                self.pending_target.insert(x.id, Arc::new(x));
+            }
             Record::PacemakerConfiguration(x) => {
                 self.pacemaker_configuration.insert(x.id, Arc::new(x));
             }
@@ -493,6 +506,7 @@ impl From<&Cache> for ArcCache {
             lnet_configuration: hashmap_to_arc_hashmap(&cache.lnet_configuration),
             ost_pool: hashmap_to_arc_hashmap(&cache.ost_pool),
             ost_pool_osts: hashmap_to_arc_hashmap(&cache.ost_pool_osts),
+            pending_target: hashmap_to_arc_hashmap(&cache.pending_target),
             pacemaker_configuration: hashmap_to_arc_hashmap(&cache.pacemaker_configuration),
             sfa_disk_drive: hashmap_to_arc_hashmap(&cache.sfa_disk_drive),
             sfa_enclosure: hashmap_to_arc_hashmap(&cache.sfa_enclosure),
@@ -528,6 +542,7 @@ impl From<&ArcCache> for Cache {
             lnet_configuration: arc_hashmap_to_hashmap(&cache.lnet_configuration),
             ost_pool: arc_hashmap_to_hashmap(&cache.ost_pool),
             ost_pool_osts: arc_hashmap_to_hashmap(&cache.ost_pool_osts),
+            pending_target: arc_hashmap_to_hashmap(&cache.pending_target),
             pacemaker_configuration: arc_hashmap_to_hashmap(&cache.pacemaker_configuration),
             sfa_disk_drive: arc_hashmap_to_hashmap(&cache.sfa_disk_drive),
             sfa_enclosure: arc_hashmap_to_hashmap(&cache.sfa_enclosure),
@@ -564,6 +579,7 @@ pub enum Record {
     LnetConfiguration(LnetConfigurationRecord),
     OstPool(OstPoolRecord),
     OstPoolOsts(OstPoolOstsRecord),
+    PendingTarget(PendingTarget),
     PacemakerConfiguration(PacemakerConfigurationRecord),
     SfaDiskDrive(SfaDiskDrive),
     SfaEnclosure(SfaEnclosure),
@@ -596,6 +612,7 @@ pub enum ArcRecord {
     LnetConfiguration(Arc<LnetConfigurationRecord>),
     OstPool(Arc<OstPoolRecord>),
     OstPoolOsts(Arc<OstPoolOstsRecord>),
+    PendingTarget(Arc<PendingTarget>),
     PacemakerConfiguration(Arc<PacemakerConfigurationRecord>),
     SfaDiskDrive(Arc<SfaDiskDrive>),
     SfaEnclosure(Arc<SfaEnclosure>),
@@ -629,6 +646,7 @@ impl From<Record> for ArcRecord {
             Record::LnetConfiguration(x) => Self::LnetConfiguration(Arc::new(x)),
             Record::OstPool(x) => Self::OstPool(Arc::new(x)),
             Record::OstPoolOsts(x) => Self::OstPoolOsts(Arc::new(x)),
+            Record::PendingTarget(x) => Self::PendingTarget(Arc::new(x)),
             Record::PacemakerConfiguration(x) => Self::PacemakerConfiguration(Arc::new(x)),
             Record::SfaDiskDrive(x) => Self::SfaDiskDrive(Arc::new(x)),
             Record::SfaEnclosure(x) => Self::SfaEnclosure(Arc::new(x)),
@@ -664,6 +682,7 @@ pub enum RecordId {
     LnetConfiguration(i32),
     OstPool(i32),
     OstPoolOsts(i32),
+    PendingTarget(i32),
     PacemakerConfiguration(i32),
     SfaDiskDrive(i32),
     SfaEnclosure(i32),
@@ -697,6 +716,7 @@ impl From<&Record> for RecordId {
             Record::LnetConfiguration(x) => RecordId::LnetConfiguration(x.id),
             Record::OstPool(x) => RecordId::OstPool(x.id),
             Record::OstPoolOsts(x) => RecordId::OstPoolOsts(x.id),
+            Record::PendingTarget(x) => RecordId::PendingTarget(x.id),
             Record::PacemakerConfiguration(x) => RecordId::PacemakerConfiguration(x.id),
             Record::SfaDiskDrive(x) => RecordId::SfaDiskDrive(x.id),
             Record::SfaEnclosure(x) => RecordId::SfaEnclosure(x.id),
@@ -733,6 +753,7 @@ impl Deref for RecordId {
             | Self::Host(x)
             | Self::OstPool(x)
             | Self::OstPoolOsts(x)
+            | Self::PendingTarget(x)
             | Self::PacemakerConfiguration(x)
             | Self::SfaDiskDrive(x)
             | Self::SfaEnclosure(x)
@@ -771,6 +792,12 @@ pub enum Message {
     Locks(Locks),
     Records(Cache),
     RecordChange(RecordChange),
+    /// The group membership of the user with this id changed.
+    /// Sessions of that user hold stale permissions until they are fetched again.
+    SessionChange(i32),
+    /// A table that is not part of `Cache` changed.
+    /// Views reading it through the API fetch it again instead of waiting for their next poll.
+    Invalidate(String),
 }
 
 #[cfg(test)]
@@ -0,0 +1,23 @@
+CREATE INDEX IF NOT EXISTS target_dev_path_idx ON target (dev_path);
+
+CREATE INDEX IF NOT EXISTS target_active_host_id_idx ON target (active_host_id);
+
+-- Flatten a stored device tree into (serial, path) pairs
+CREATE OR REPLACE FUNCTION device_serial_paths(jsonb) RETURNS TABLE (serial text, path text)
+  AS $$
+    WITH RECURSIVE nodes(node) AS (
+      SELECT x.value FROM jsonb_each($1) AS x
+      UNION ALL
+      SELECT c.value
+      FROM nodes n,
+        jsonb_array_elements(n.node -> 'children') AS ch(child),
+        jsonb_each(ch.child) AS c
+      WHERE jsonb_typeof(n.node -> 'children') = 'array'
+    )
+    SELECT n.node ->> 'serial', p.path
+    FROM nodes n, jsonb_array_elements_text(n.node -> 'paths') AS p(path)
+    WHERE n.node ->> 'serial' IS NOT NULL
+      AND jsonb_typeof(n.node -> 'paths') = 'array'
+  $$ LANGUAGE SQL
+  IMMUTABLE
+  RETURNS NULL ON NULL INPUT;
@@ -0,0 +1,9 @@
+-- Per job class timeouts. Jobs of a class without an entry never time out.
+CREATE TABLE IF NOT EXISTS job_timeout (
+    class_name TEXT PRIMARY KEY,
+    timeout INTERVAL NOT NULL CHECK (timeout > interval '0')
+);
+
+ALTER TABLE chroma_core_job ADD COLUMN IF NOT EXISTS timed_out BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS chroma_core_job_tasked_idx ON chroma_core_job (modified_at) WHERE state = 'tasked';
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS stratagem_rule_set (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    rules JSONB NOT NULL
+);
@@ -0,0 +1,70 @@
+-- Journal of changes to core records, read by the `changes` query
+CREATE TABLE IF NOT EXISTS change_journal (
+  id BIGSERIAL PRIMARY KEY,
+  record_type TEXT NOT NULL,
+  record_id INT NOT NULL,
+  op TEXT NOT NULL CHECK (op IN ('create', 'update', 'delete')),
+  changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  txid BIGINT NOT NULL DEFAULT txid_current(),
+  record JSONB NOT NULL
+);
+
+-- Entries are read in (txid, id) order. Every transaction with a txid below the
+-- current snapshot xmin has finished, so nothing can later appear before a row read from there
+CREATE INDEX IF NOT EXISTS change_journal_txid_idx ON change_journal (txid, id);
+
+CREATE INDEX IF NOT EXISTS change_journal_changed_at_idx ON change_journal (changed_at);
+
+-- Appends a row to change_journal. TG_ARGV[0] is the record type.
+-- Soft deletes (not_deleted going from true to null) are journaled as deletes.
+CREATE OR REPLACE FUNCTION change_journal_record() RETURNS TRIGGER AS $$
+DECLARE
+  op TEXT;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    INSERT INTO change_journal (record_type, record_id, op, record)
+    VALUES (TG_ARGV[0], OLD.id, 'delete', to_jsonb(OLD));
+
+    RETURN OLD;
+  END IF;
+
+  IF TG_OP = 'INSERT' THEN
+    op := 'create';
+  ELSIF OLD IS NOT DISTINCT FROM NEW THEN
+    RETURN NEW;
+  ELSIF (to_jsonb(OLD) ->> 'not_deleted') IS NOT NULL AND (to_jsonb(NEW) ->> 'not_deleted') IS NULL THEN
+    op := 'delete';
+  ELSE
+    op := 'update';
+  END IF;
+
+  INSERT INTO change_journal (record_type, record_id, op, record)
+  VALUES (TG_ARGV[0], NEW.id, op, to_jsonb(NEW));
+
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS chroma_core_managedhost_change_journal ON chroma_core_managedhost;
+
+CREATE TRIGGER chroma_core_managedhost_change_journal
+AFTER INSERT OR UPDATE OR DELETE ON chroma_core_managedhost
+FOR EACH ROW EXECUTE PROCEDURE change_journal_record('host');
+
+DROP TRIGGER IF EXISTS target_change_journal ON target;
+
+CREATE TRIGGER target_change_journal
+AFTER INSERT OR UPDATE OR DELETE ON target
+FOR EACH ROW EXECUTE PROCEDURE change_journal_record('target');
+
+DROP TRIGGER IF EXISTS chroma_core_managedfilesystem_change_journal ON chroma_core_managedfilesystem;
+
+CREATE TRIGGER chroma_core_managedfilesystem_change_journal
+AFTER INSERT OR UPDATE OR DELETE ON chroma_core_managedfilesystem
+FOR EACH ROW EXECUTE PROCEDURE change_journal_record('filesystem');
+
+DROP TRIGGER IF EXISTS chroma_core_alertstate_change_journal ON chroma_core_alertstate;
+
+CREATE TRIGGER chroma_core_alertstate_change_journal
+AFTER INSERT OR UPDATE OR DELETE ON chroma_core_alertstate
+FOR EACH ROW EXECUTE PROCEDURE change_journal_record('alert');
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS host_tag (
+    host_id INT NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (host_id, key)
+);
+
+CREATE INDEX IF NOT EXISTS host_tag_key_value_idx ON host_tag (key, value);
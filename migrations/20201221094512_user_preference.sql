@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS user_preference (
+    user_id INT NOT NULL REFERENCES auth_user (id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value JSONB NOT NULL,
+    PRIMARY KEY (user_id, key)
+);
@@ -0,0 +1,143 @@
+-- History of target state changes, read by the `target_state_history` query
+CREATE TABLE IF NOT EXISTS target_state_history (
+  id SERIAL PRIMARY KEY,
+  target_uuid TEXT NOT NULL,
+  target_name TEXT NOT NULL,
+  event TEXT NOT NULL CHECK (event IN ('mounted', 'unmounted', 'failover', 'recovery_start', 'recovery_end')),
+  state TEXT NOT NULL,
+  active_host_id INT NULL,
+  previous_host_id INT NULL,
+  reason TEXT NOT NULL,
+  changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS target_state_history_uuid_idx ON target_state_history (target_uuid, changed_at);
+
+-- Records mounts, unmounts and failovers of a target.
+-- A mount on a different host than the target was last seen on is recorded as a failover.
+-- Alerts active on the previous host at the time are kept as part of the reason.
+CREATE OR REPLACE FUNCTION target_state_history_record() RETURNS TRIGGER AS $$
+DECLARE
+  prev_host_id INT;
+  host TEXT;
+  prev_host TEXT;
+  alerts TEXT;
+  event TEXT;
+  reason TEXT;
+BEGIN
+  IF TG_OP = 'UPDATE'
+    AND OLD.state IS NOT DISTINCT FROM NEW.state
+    AND OLD.active_host_id IS NOT DISTINCT FROM NEW.active_host_id THEN
+    RETURN NEW;
+  END IF;
+
+  IF TG_OP = 'UPDATE' AND OLD.active_host_id IS NOT NULL THEN
+    prev_host_id := OLD.active_host_id;
+  ELSE
+    SELECT h.active_host_id INTO prev_host_id
+    FROM target_state_history h
+    WHERE h.target_uuid = NEW.uuid AND h.active_host_id IS NOT NULL
+    ORDER BY h.id DESC
+    LIMIT 1;
+  END IF;
+
+  SELECT COALESCE(h.fqdn, 'host ' || NEW.active_host_id) INTO host
+  FROM (SELECT NULL) AS x
+  LEFT JOIN chroma_core_managedhost h ON h.id = NEW.active_host_id;
+
+  SELECT COALESCE(h.fqdn, 'host ' || prev_host_id) INTO prev_host
+  FROM (SELECT NULL) AS x
+  LEFT JOIN chroma_core_managedhost h ON h.id = prev_host_id;
+
+  SELECT string_agg(DISTINCT a.record_type, ', ') INTO alerts
+  FROM chroma_core_alertstate a
+  INNER JOIN django_content_type ct ON ct.id = a.alert_item_type_id
+  WHERE a.active = 't'
+    AND ct.app_label = 'chroma_core'
+    AND ct.model = 'managedhost'
+    AND a.alert_item_id = prev_host_id;
+
+  IF NEW.state = 'mounted' AND NEW.active_host_id IS NOT NULL
+    AND prev_host_id IS NOT NULL AND NEW.active_host_id <> prev_host_id THEN
+    event := 'failover';
+    reason := format('Moved from %s to %s', prev_host, host);
+  ELSIF (TG_OP = 'INSERT' OR OLD.state IS DISTINCT FROM NEW.state) AND NEW.state = 'mounted' THEN
+    event := 'mounted';
+    reason := format('Mounted on %s', host);
+    alerts := NULL;
+  ELSIF (TG_OP = 'INSERT' OR OLD.state IS DISTINCT FROM NEW.state) AND NEW.state = 'unmounted' THEN
+    event := 'unmounted';
+    reason := CASE
+      WHEN prev_host_id IS NULL OR TG_OP = 'INSERT' THEN 'Unmounted'
+      ELSE format('Unmounted from %s', prev_host)
+    END;
+  ELSE
+    RETURN NEW;
+  END IF;
+
+  IF alerts IS NOT NULL THEN
+    reason := reason || format(' while %s had active alerts: %s', prev_host, alerts);
+  END IF;
+
+  INSERT INTO target_state_history
+    (target_uuid, target_name, event, state, active_host_id, previous_host_id, reason)
+  VALUES
+    (NEW.uuid, NEW.name, event, NEW.state, NEW.active_host_id, prev_host_id, reason);
+
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS target_state_history ON target;
+
+CREATE TRIGGER target_state_history
+AFTER INSERT OR UPDATE ON target
+FOR EACH ROW EXECUTE PROCEDURE target_state_history_record();
+
+-- Records when a target enters and completes recovery
+CREATE OR REPLACE FUNCTION target_recovery_history_record() RETURNS TRIGGER AS $$
+DECLARE
+  t RECORD;
+  event TEXT;
+BEGIN
+  IF TG_OP = 'INSERT' AND NEW.active = 't' THEN
+    event := 'recovery_start';
+  ELSIF TG_OP = 'UPDATE' AND OLD.active = 't' AND NEW.active IS DISTINCT FROM 't' THEN
+    event := 'recovery_end';
+  ELSE
+    RETURN NEW;
+  END IF;
+
+  SELECT tg.uuid, tg.name, tg.state, tg.active_host_id INTO t
+  FROM chroma_core_managedtarget mt
+  INNER JOIN target tg ON tg.uuid = mt.uuid
+  WHERE mt.id = NEW.alert_item_id
+  LIMIT 1;
+
+  IF NOT FOUND THEN
+    RETURN NEW;
+  END IF;
+
+  INSERT INTO target_state_history
+    (target_uuid, target_name, event, state, active_host_id, reason)
+  VALUES
+    (
+      t.uuid,
+      t.name,
+      event,
+      t.state,
+      t.active_host_id,
+      CASE WHEN event = 'recovery_start' THEN 'Entered recovery' ELSE 'Completed recovery' END
+    );
+
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS chroma_core_alertstate_target_recovery_history ON chroma_core_alertstate;
+
+CREATE TRIGGER chroma_core_alertstate_target_recovery_history
+AFTER INSERT OR UPDATE ON chroma_core_alertstate
+FOR EACH ROW
+WHEN (NEW.record_type = 'TargetRecoveryAlert')
+EXECUTE PROCEDURE target_recovery_history_record();
@@ -0,0 +1,8 @@
+-- Progress of each ScanMdtJob, written by the job steps as the scan advances
+CREATE TABLE IF NOT EXISTS stratagem_scan_progress (
+  job_id INT PRIMARY KEY REFERENCES chroma_core_job (id) ON DELETE CASCADE,
+  phase TEXT NOT NULL CHECK (phase IN ('scanning', 'streaming', 'complete')),
+  entries_scanned BIGINT NULL,
+  started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
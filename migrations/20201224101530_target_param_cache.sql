@@ -0,0 +1,10 @@
+-- Shared by every iml-api replica so a value read through one
+-- is served from cache by all of them. Nothing here needs to survive
+-- a crash, so the table is unlogged.
+CREATE UNLOGGED TABLE IF NOT EXISTS target_param_cache (
+    target_uuid TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT,
+    fetched_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (target_uuid, key)
+);
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS command_note (
+    id SERIAL PRIMARY KEY,
+    command_id INT NOT NULL REFERENCES chroma_core_command (id) ON DELETE CASCADE,
+    note TEXT NOT NULL,
+    user_id INT REFERENCES auth_user (id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS command_note_command_id_idx ON command_note (command_id);
+
+CREATE TABLE IF NOT EXISTS command_tag (
+    command_id INT NOT NULL REFERENCES chroma_core_command (id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (command_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS command_tag_tag_idx ON command_tag (tag);
@@ -0,0 +1,3 @@
+-- Set by the snapshot retention service when manual snapshots take up
+-- so much of `keep_num` that interval snapshots are deleted early.
+ALTER TABLE IF EXISTS snapshot_retention ADD COLUMN IF NOT EXISTS crowded_by_manual BOOLEAN NOT NULL DEFAULT FALSE;
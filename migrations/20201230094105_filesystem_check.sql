@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS filesystem_check (
+    id SERIAL PRIMARY KEY,
+    fs_name TEXT NOT NULL,
+    ran_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    passed BOOLEAN NOT NULL,
+    checks JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS filesystem_check_fs_name_ran_at_idx ON filesystem_check (fs_name, ran_at DESC);
@@ -0,0 +1,3 @@
+-- Where a log message came from. `agent` for managed servers,
+-- otherwise the name given by the client of the ingestion API.
+ALTER TABLE chroma_core_logmessage ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'agent';
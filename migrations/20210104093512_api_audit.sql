@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS api_audit (
+    id SERIAL PRIMARY KEY,
+    -- Not a foreign key, entries outlive the user and the command they point to.
+    user_id INT,
+    username TEXT,
+    mutation TEXT NOT NULL,
+    args JSONB NOT NULL DEFAULT '{}',
+    command_id INT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS api_audit_created_at_idx ON api_audit (created_at);
+CREATE INDEX IF NOT EXISTS api_audit_command_id_idx ON api_audit (command_id);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS tuning_profile (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    settings JSONB NOT NULL DEFAULT '[]',
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+-- A host has at most one profile assigned.
+CREATE TABLE IF NOT EXISTS tuning_profile_host (
+    host_id INT PRIMARY KEY REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    profile_id INT NOT NULL REFERENCES tuning_profile (id) ON DELETE CASCADE,
+    assigned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS detect_scan (
+    id SERIAL PRIMARY KEY,
+    host_ids INT[] NOT NULL,
+    -- The targets importing the scan registers
+    target_uuids TEXT[] NOT NULL,
+    -- The preview returned by the scan, as a list of DetectedFilesystem
+    filesystems JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    imported_at TIMESTAMP WITH TIME ZONE
+);
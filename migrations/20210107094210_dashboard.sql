@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS dashboard (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    owner_id INT NOT NULL REFERENCES auth_user (id) ON DELETE CASCADE,
+    shared BOOLEAN NOT NULL DEFAULT 'f',
+    panels JSONB NOT NULL DEFAULT '[]',
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    UNIQUE (owner_id, name)
+);
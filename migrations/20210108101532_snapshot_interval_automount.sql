@@ -0,0 +1,23 @@
+-- Mount the snapshots an interval takes as soon as they are created
+ALTER TABLE IF EXISTS snapshot_interval ADD COLUMN IF NOT EXISTS automount BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE OR REPLACE FUNCTION table_update_notify_snapshot_interval() RETURNS TRIGGER
+  AS $$
+    BEGIN
+      IF TG_OP = 'INSERT' THEN PERFORM pg_notify(
+        'table_update',
+        notify_row(TG_OP, TG_TABLE_NAME, json_build_object('id', NEW.id, 'filesystem_name', NEW.filesystem_name, 'use_barrier', NEW.use_barrier, 'last_run', NEW.last_run, 'interval', interval_to_seconds(NEW.interval), 'automount', NEW.automount))
+      );
+      ELSEIF TG_OP = 'UPDATE' AND OLD IS DISTINCT FROM NEW THEN PERFORM pg_notify(
+        'table_update',
+        notify_row(TG_OP, TG_TABLE_NAME, json_build_object('id', NEW.id, 'filesystem_name', NEW.filesystem_name, 'use_barrier', NEW.use_barrier, 'last_run', NEW.last_run, 'interval', interval_to_seconds(NEW.interval), 'automount', NEW.automount))
+      );
+      ELSE PERFORM pg_notify(
+        'table_update',
+        notify_row(TG_OP, TG_TABLE_NAME, json_build_object('id', OLD.id, 'filesystem_name', OLD.filesystem_name, 'use_barrier', OLD.use_barrier, 'last_run', OLD.last_run, 'interval', interval_to_seconds(OLD.interval), 'automount', OLD.automount))
+      );
+      END IF;
+
+      RETURN NEW;
+    END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS digest_subscription (
+    user_id INT PRIMARY KEY REFERENCES auth_user (id) ON DELETE CASCADE,
+    last_sent TIMESTAMP WITH TIME ZONE
+);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS project_usage (
+    id serial PRIMARY KEY,
+    task_id INT NOT NULL,
+    fs_name TEXT NOT NULL,
+    project TEXT NOT NULL,
+    files BIGINT NOT NULL,
+    bytes BIGINT NOT NULL,
+    scanned_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    UNIQUE (task_id, project)
+);
+
+CREATE INDEX IF NOT EXISTS project_usage_fs_name_scanned_at_idx ON project_usage (fs_name, scanned_at);
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS host_heartbeat (
+    host_id INT PRIMARY KEY REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    last_seen TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Held snapshots are kept by the retention policy, and only destroyed with force
+ALTER TABLE IF EXISTS snapshot ADD COLUMN IF NOT EXISTS held BOOLEAN NOT NULL DEFAULT FALSE;
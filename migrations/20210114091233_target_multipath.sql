@@ -0,0 +1,21 @@
+-- The paths to the multipath device of each target, as last seen by each of its hosts
+CREATE TABLE IF NOT EXISTS target_multipath (
+    target_uuid TEXT NOT NULL,
+    host_id INT NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    map_name TEXT NOT NULL,
+    path_count INT NOT NULL,
+    failed_paths TEXT[] NOT NULL,
+    checked_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (target_uuid, host_id)
+);
+
+CREATE TABLE IF NOT EXISTS target_multipath_event (
+    id serial PRIMARY KEY,
+    target_uuid TEXT NOT NULL,
+    host_id INT NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    failed BOOLEAN NOT NULL,
+    at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS target_multipath_event_target_uuid_at_idx ON target_multipath_event (target_uuid, at);
@@ -0,0 +1,24 @@
+-- Queries submitted to run in the background, and their results
+CREATE TABLE IF NOT EXISTS deferred_query (
+    handle TEXT PRIMARY KEY,
+    user_id INT,
+    query TEXT NOT NULL,
+    operation_name TEXT,
+    variables JSONB,
+    state TEXT NOT NULL DEFAULT 'pending',
+    path TEXT[] NOT NULL DEFAULT '{}',
+    error TEXT,
+    submitted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    started_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS deferred_query_state_submitted_at_idx ON deferred_query (state, submitted_at);
+
+CREATE TABLE IF NOT EXISTS deferred_query_row (
+    handle TEXT NOT NULL REFERENCES deferred_query (handle) ON DELETE CASCADE,
+    idx INT NOT NULL,
+    value JSONB NOT NULL,
+    PRIMARY KEY (handle, idx)
+);
@@ -0,0 +1,31 @@
+-- Settings shared by every snapshot policy. There is a single row.
+-- A `max_concurrent` of 0 puts no cap on the snapshots taken at once.
+CREATE TABLE IF NOT EXISTS snapshot_policy_settings (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    max_concurrent INT NOT NULL DEFAULT 0 CHECK (max_concurrent >= 0),
+    jitter INTERVAL NOT NULL DEFAULT interval '0' CHECK (jitter >= interval '0')
+);
+
+INSERT INTO snapshot_policy_settings (id) VALUES (TRUE) ON CONFLICT DO NOTHING;
+
+-- Every time an interval is due. Runs are queued, and started by `iml-api`
+-- once their jittered start time has passed and the concurrency cap allows it.
+CREATE TABLE IF NOT EXISTS snapshot_policy_run (
+    id serial PRIMARY KEY,
+    interval_id INT NOT NULL REFERENCES snapshot_interval (id) ON DELETE CASCADE,
+    filesystem_name TEXT NOT NULL,
+    snapshot_name TEXT NOT NULL,
+    due_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    start_after TIMESTAMP WITH TIME ZONE NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE,
+    command_id INT,
+    -- `queued`, `started` or `failed`
+    state TEXT NOT NULL DEFAULT 'queued',
+    -- The run was held back by `max_concurrent`
+    throttled BOOLEAN NOT NULL DEFAULT FALSE,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS snapshot_policy_run_queued_idx ON snapshot_policy_run (start_after) WHERE state = 'queued';
+
+CREATE INDEX IF NOT EXISTS snapshot_policy_run_filesystem_name_due_at_idx ON snapshot_policy_run (filesystem_name, due_at);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS role (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    permissions TEXT[] NOT NULL DEFAULT '{}'
+);
+
+CREATE TABLE IF NOT EXISTS role_user (
+    role_id INT NOT NULL REFERENCES role (id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES auth_user (id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, user_id)
+);
@@ -0,0 +1,21 @@
+-- Filesystems whose targets are remounted when they go offline outside of any command
+CREATE TABLE IF NOT EXISTS target_remount_policy (
+    filesystem_name TEXT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    max_attempts INT NOT NULL DEFAULT 3 CHECK (max_attempts > 0),
+    cooldown INTERVAL NOT NULL DEFAULT '5 minutes'
+);
+
+CREATE TABLE IF NOT EXISTS target_remount_attempt (
+    id serial PRIMARY KEY,
+    target_id INT NOT NULL REFERENCES chroma_core_managedtarget (id) ON DELETE CASCADE,
+    target_name TEXT NOT NULL,
+    filesystem_name TEXT NOT NULL,
+    attempt INT NOT NULL,
+    command_id INT NULL REFERENCES chroma_core_command (id) ON DELETE SET NULL,
+    error TEXT NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS target_remount_attempt_target_id_started_at_idx ON target_remount_attempt (target_id, started_at);
+CREATE INDEX IF NOT EXISTS target_remount_attempt_filesystem_name_started_at_idx ON target_remount_attempt (filesystem_name, started_at);
@@ -0,0 +1,11 @@
+-- Results of testing the stonith devices of a corosync cluster
+CREATE TABLE IF NOT EXISTS stonith_test (
+    id serial PRIMARY KEY,
+    cluster_id INT NOT NULL REFERENCES corosync_cluster (id) ON DELETE CASCADE,
+    ran_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    passed BOOLEAN NOT NULL,
+    error TEXT NULL,
+    result JSONB NULL
+);
+
+CREATE INDEX IF NOT EXISTS stonith_test_cluster_id_ran_at_idx ON stonith_test (cluster_id, ran_at);
@@ -0,0 +1,12 @@
+-- The latest recovery_status of each mounted MDT and OST, as read from the server it is mounted on
+CREATE TABLE IF NOT EXISTS target_recovery (
+    target_uuid TEXT PRIMARY KEY,
+    host_id INT NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    status TEXT NOT NULL,
+    time_remaining INT NULL,
+    connected_clients INT NULL,
+    total_clients INT NULL,
+    completed_clients INT NULL,
+    evicted_clients INT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,20 @@
+-- Rules that escalate alerts left active and undismissed to further recipients
+CREATE TABLE IF NOT EXISTS alert_escalation_policy (
+    id serial PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    severity INT NOT NULL,
+    after_minutes INT NOT NULL,
+    emails TEXT[] NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 't'
+);
+
+-- Escalations that were sent, at most one per alert and policy
+CREATE TABLE IF NOT EXISTS alert_escalation (
+    id serial PRIMARY KEY,
+    alert_id INT NOT NULL REFERENCES chroma_core_alertstate (id) ON DELETE CASCADE,
+    policy_id INT NULL REFERENCES alert_escalation_policy (id) ON DELETE SET NULL,
+    policy_name TEXT NOT NULL,
+    emails TEXT[] NOT NULL,
+    escalated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    UNIQUE (alert_id, policy_id)
+);
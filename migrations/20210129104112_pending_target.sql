@@ -0,0 +1,59 @@
+-- Targets being added to existing filesystems, until they show up in `target`.
+-- A row reserves the name of its target, `command_id` is set once the command adding it started.
+CREATE TABLE IF NOT EXISTS pending_target (
+    id serial PRIMARY KEY,
+    name TEXT NOT NULL,
+    fs_name TEXT NOT NULL,
+    host_id INT NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    dev_path TEXT NOT NULL,
+    command_id INT NULL REFERENCES chroma_core_command (id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    UNIQUE (fs_name, name)
+);
+
+-- A pending target is done once the target is known
+CREATE OR REPLACE FUNCTION pending_target_added() RETURNS TRIGGER AS $$
+BEGIN
+  DELETE FROM pending_target WHERE name = NEW.name AND fs_name = ANY(NEW.filesystems);
+
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS target_pending_target_added ON target;
+
+CREATE TRIGGER target_pending_target_added
+AFTER INSERT OR UPDATE ON target FOR EACH ROW EXECUTE PROCEDURE pending_target_added();
+
+-- A pending target is dropped when the command adding it failed or was cancelled
+CREATE OR REPLACE FUNCTION pending_target_failed() RETURNS TRIGGER AS $$
+BEGIN
+  IF NEW.complete AND (NEW.errored OR NEW.cancelled) THEN
+    DELETE FROM pending_target WHERE command_id = NEW.id;
+  END IF;
+
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS command_pending_target_failed ON chroma_core_command;
+
+CREATE TRIGGER command_pending_target_failed
+AFTER UPDATE ON chroma_core_command FOR EACH ROW EXECUTE PROCEDURE pending_target_failed();
+
+DROP TRIGGER IF EXISTS pending_target_notify_update ON pending_target;
+
+DROP TRIGGER IF EXISTS pending_target_notify_insert ON pending_target;
+
+DROP TRIGGER IF EXISTS pending_target_notify_delete ON pending_target;
+
+CREATE TRIGGER pending_target_notify_update
+AFTER
+UPDATE ON pending_target FOR EACH ROW EXECUTE PROCEDURE table_update_notify();
+
+CREATE TRIGGER pending_target_notify_insert
+AFTER
+INSERT ON pending_target FOR EACH ROW EXECUTE PROCEDURE table_update_notify();
+
+CREATE TRIGGER pending_target_notify_delete
+AFTER DELETE ON pending_target FOR EACH ROW EXECUTE PROCEDURE table_update_notify();
@@ -0,0 +1,85 @@
+DO $$ BEGIN
+  CREATE TYPE maintenance_scope AS ENUM ('host', 'filesystem', 'cluster');
+EXCEPTION
+  WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS maintenance_window (
+    id serial PRIMARY KEY,
+    name TEXT NOT NULL,
+    scope maintenance_scope NOT NULL,
+    scope_id INT NOT NULL,
+    starts_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    ends_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    suppress_alerts BOOLEAN NOT NULL DEFAULT 't',
+    user_id INT REFERENCES auth_user (id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS maintenance_window_ends_at_idx ON maintenance_window (ends_at);
+
+-- Whether an active window that suppresses alerts covers the alert item.
+-- Hosts and filesystems cover their targets, clusters cover their hosts and their targets.
+CREATE OR REPLACE FUNCTION in_maintenance(item_type_id INT, item_id INT) RETURNS BOOLEAN AS $$
+DECLARE
+  item_model TEXT;
+  host_ids INT[] := '{}';
+  fs_names TEXT[] := '{}';
+BEGIN
+  SELECT model INTO item_model FROM django_content_type WHERE id = item_type_id;
+
+  IF item_model = 'managedhost' THEN
+    host_ids := ARRAY[item_id];
+  ELSIF item_model = 'managedfilesystem' THEN
+    SELECT ARRAY[name] INTO fs_names FROM chroma_core_managedfilesystem WHERE id = item_id;
+  ELSIF item_model IN ('managedtarget', 'managedmgs', 'managedmdt', 'managedost') THEN
+    SELECT t.host_ids, t.filesystems INTO host_ids, fs_names
+    FROM chroma_core_managedtarget mt
+    INNER JOIN target t ON t.uuid = mt.uuid
+    WHERE mt.id = item_id;
+  ELSE
+    RETURN FALSE;
+  END IF;
+
+  RETURN EXISTS (
+    SELECT 1 FROM maintenance_window w
+    WHERE w.suppress_alerts
+    AND w.starts_at <= now() AND now() < w.ends_at
+    AND (
+      (w.scope = 'host' AND w.scope_id = ANY(host_ids))
+      OR (
+        w.scope = 'filesystem'
+        AND EXISTS (
+          SELECT 1 FROM chroma_core_managedfilesystem f
+          WHERE f.id = w.scope_id AND f.name = ANY(fs_names)
+        )
+      )
+      OR (
+        w.scope = 'cluster'
+        AND EXISTS (
+          SELECT 1 FROM corosync_node_managed_host c
+          WHERE c.cluster_id = w.scope_id AND c.host_id = ANY(host_ids)
+        )
+      )
+    )
+  );
+END;
+$$ LANGUAGE plpgsql;
+
+-- Alerts raised in scope of an active window are dismissed as they are raised,
+-- so they are neither emailed nor escalated
+CREATE OR REPLACE FUNCTION alert_maintenance_suppress() RETURNS TRIGGER AS $$
+BEGIN
+  IF NEW.active AND NOT NEW.dismissed AND in_maintenance(NEW.alert_item_type_id, NEW.alert_item_id) THEN
+    NEW.dismissed := 't';
+  END IF;
+
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS alertstate_maintenance_suppress ON chroma_core_alertstate;
+
+CREATE TRIGGER alertstate_maintenance_suppress
+BEFORE INSERT ON chroma_core_alertstate FOR EACH ROW EXECUTE PROCEDURE alert_maintenance_suppress();
@@ -0,0 +1,135 @@
+-- History of resource bans and moves, read by the `haEvents` query.
+-- Rows outlive their cluster, so `cluster_id` is not a foreign key.
+CREATE TABLE IF NOT EXISTS ha_event (
+  id SERIAL PRIMARY KEY,
+  cluster_id INT NOT NULL,
+  resource TEXT NOT NULL,
+  event TEXT NOT NULL CHECK (event IN ('ban', 'unban', 'failover', 'failback')),
+  node TEXT NOT NULL,
+  previous_node TEXT NULL,
+  reason TEXT NOT NULL,
+  happened_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS ha_event_cluster_id_idx ON ha_event (cluster_id, happened_at);
+
+-- Records bans as they appear and unbans as they are cleared.
+-- Bans dropped along with their cluster are not unbans.
+CREATE OR REPLACE FUNCTION ha_event_ban_record() RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    INSERT INTO ha_event (cluster_id, resource, event, node, reason)
+    VALUES (
+      NEW.cluster_id,
+      NEW.resource,
+      'ban',
+      NEW.node,
+      format('Banned from %s by %s with weight %s', NEW.node, NEW.name, NEW.weight)
+    );
+
+    RETURN NEW;
+  END IF;
+
+  IF NOT EXISTS (SELECT 1 FROM corosync_cluster WHERE id = OLD.cluster_id) THEN
+    RETURN OLD;
+  END IF;
+
+  INSERT INTO ha_event (cluster_id, resource, event, node, reason)
+  VALUES (OLD.cluster_id, OLD.resource, 'unban', OLD.node, format('Cleared %s', OLD.name));
+
+  RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS corosync_resource_bans_ha_event ON corosync_resource_bans;
+
+CREATE TRIGGER corosync_resource_bans_ha_event
+AFTER INSERT OR DELETE ON corosync_resource_bans
+FOR EACH ROW EXECUTE PROCEDURE ha_event_ban_record();
+
+-- Records a resource starting on a different node than it was last seen on.
+-- Moving back to the node of the last failover is a failback.
+-- The state of the previous node and its bans at the time are kept as the reason.
+CREATE OR REPLACE FUNCTION ha_event_move_record() RETURNS TRIGGER AS $$
+DECLARE
+  new_node TEXT;
+  prev_node TEXT;
+  last_event TEXT;
+  last_node TEXT;
+  last_prev_node TEXT;
+  prev_online BOOLEAN;
+  prev_unclean BOOLEAN;
+  prev_standby BOOLEAN;
+  prev_maintenance BOOLEAN;
+  ban TEXT;
+  event TEXT;
+  cause TEXT;
+BEGIN
+  new_node := (NEW.active_node).name;
+
+  IF new_node IS NULL THEN
+    RETURN NEW;
+  END IF;
+
+  SELECT e.event, e.node, e.previous_node INTO last_event, last_node, last_prev_node
+  FROM ha_event e
+  WHERE e.cluster_id = NEW.cluster_id
+    AND e.resource = NEW.name
+    AND e.event IN ('failover', 'failback')
+  ORDER BY e.id DESC
+  LIMIT 1;
+
+  IF TG_OP = 'UPDATE' AND OLD.active_node IS NOT NULL THEN
+    prev_node := (OLD.active_node).name;
+  ELSE
+    prev_node := last_node;
+  END IF;
+
+  IF prev_node IS NULL OR prev_node = new_node THEN
+    RETURN NEW;
+  END IF;
+
+  IF last_event = 'failover' AND last_prev_node = new_node THEN
+    event := 'failback';
+  ELSE
+    event := 'failover';
+  END IF;
+
+  SELECT n.online, n.unclean, n.standby, n.maintenance
+  INTO prev_online, prev_unclean, prev_standby, prev_maintenance
+  FROM corosync_node n
+  WHERE n.cluster_id = NEW.cluster_id AND (n.id).name = prev_node;
+
+  SELECT string_agg(b.name, ', ') INTO ban
+  FROM corosync_resource_bans b
+  WHERE b.cluster_id = NEW.cluster_id AND b.resource = NEW.name AND b.node = prev_node;
+
+  cause := CASE
+    WHEN prev_unclean THEN format('%s was unclean', prev_node)
+    WHEN prev_online IS FALSE THEN format('%s was offline', prev_node)
+    WHEN prev_standby THEN format('%s was in standby', prev_node)
+    WHEN prev_maintenance THEN format('%s was in maintenance', prev_node)
+    WHEN ban IS NOT NULL THEN format('banned from %s by %s', prev_node, ban)
+    WHEN NEW.failed OR (TG_OP = 'UPDATE' AND OLD.failed) THEN 'the resource failed'
+    ELSE NULL
+  END;
+
+  INSERT INTO ha_event (cluster_id, resource, event, node, previous_node, reason)
+  VALUES (
+    NEW.cluster_id,
+    NEW.name,
+    event,
+    new_node,
+    prev_node,
+    format('Moved from %s to %s', prev_node, new_node) || COALESCE(', ' || cause, '')
+  );
+
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS corosync_resource_ha_event ON corosync_resource;
+
+CREATE TRIGGER corosync_resource_ha_event
+AFTER INSERT OR UPDATE ON corosync_resource
+FOR EACH ROW EXECUTE PROCEDURE ha_event_move_record();
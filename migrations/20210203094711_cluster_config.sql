@@ -0,0 +1,17 @@
+-- The CIB configuration and corosync.conf each node last reported, and the baseline they are compared to.
+-- corosync.conf can differ between nodes, so configurations are kept per host.
+-- The baseline follows the reported configuration while IML jobs run, so only out-of-band changes drift.
+CREATE TABLE IF NOT EXISTS cluster_config (
+  host_id INT PRIMARY KEY REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+  cluster_id INT NOT NULL REFERENCES corosync_cluster (id) ON DELETE CASCADE,
+  cib TEXT NOT NULL,
+  corosync_conf TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  baseline_cib TEXT NOT NULL,
+  baseline_corosync_conf TEXT NOT NULL,
+  baseline_hash TEXT NOT NULL,
+  changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  baselined_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS cluster_config_cluster_id_idx ON cluster_config (cluster_id);
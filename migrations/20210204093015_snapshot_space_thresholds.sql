@@ -0,0 +1,17 @@
+-- Optional thresholds of a retention policy, both in percent:
+-- the oldest snapshots are deleted while the MDTs have less than `mdt_reserve_percent` free,
+-- and a snapshot is taken once used space changed by `change_percent` of the filesystem size
+-- since `change_baseline`, the bytes used when the last such snapshot was taken.
+ALTER TABLE IF EXISTS snapshot_retention
+  ADD COLUMN IF NOT EXISTS mdt_reserve_percent INT CHECK (mdt_reserve_percent BETWEEN 1 AND 99),
+  ADD COLUMN IF NOT EXISTS change_percent INT CHECK (change_percent BETWEEN 1 AND 100),
+  ADD COLUMN IF NOT EXISTS change_baseline BIGINT;
+
+-- Runs are also recorded for the snapshots thresholds take or delete, which have no interval.
+ALTER TABLE IF EXISTS snapshot_policy_run ALTER COLUMN interval_id DROP NOT NULL;
+
+ALTER TABLE IF EXISTS snapshot_policy_run
+  ADD COLUMN IF NOT EXISTS reason TEXT NOT NULL DEFAULT 'interval'
+    CHECK (reason IN ('interval', 'change', 'reserve', 'mdt_reserve')),
+  ADD COLUMN IF NOT EXISTS action TEXT NOT NULL DEFAULT 'create'
+    CHECK (action IN ('create', 'destroy'));
@@ -0,0 +1,34 @@
+-- Tables that are not mirrored by `table_update` but are read by clients that poll.
+-- Each statement that changes one of them sends its name on `table_invalidate`,
+-- once per transaction, so readers can fetch them again instead of polling.
+CREATE OR REPLACE FUNCTION table_invalidate() RETURNS TRIGGER AS $$
+BEGIN
+  PERFORM pg_notify('table_invalidate', TG_TABLE_NAME);
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS deferred_query_invalidate ON deferred_query;
+
+CREATE TRIGGER deferred_query_invalidate
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON deferred_query
+FOR EACH STATEMENT EXECUTE PROCEDURE table_invalidate();
+
+DROP TRIGGER IF EXISTS maintenance_window_invalidate ON maintenance_window;
+
+CREATE TRIGGER maintenance_window_invalidate
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON maintenance_window
+FOR EACH STATEMENT EXECUTE PROCEDURE table_invalidate();
+
+DROP TRIGGER IF EXISTS ha_event_invalidate ON ha_event;
+
+CREATE TRIGGER ha_event_invalidate
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON ha_event
+FOR EACH STATEMENT EXECUTE PROCEDURE table_invalidate();
+
+DROP TRIGGER IF EXISTS target_recovery_invalidate ON target_recovery;
+
+CREATE TRIGGER target_recovery_invalidate
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON target_recovery
+FOR EACH STATEMENT EXECUTE PROCEDURE table_invalidate();
@@ -0,0 +1,35 @@
+-- LNet ping meshes: each host of a mesh pings the NIDs of every other host
+CREATE TABLE IF NOT EXISTS lnet_ping_mesh (
+    id serial PRIMARY KEY,
+    host_ids INT[] NOT NULL,
+    count INT NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    finished_at TIMESTAMP WITH TIME ZONE NULL
+);
+
+-- How one host reached one NID of another host
+CREATE TABLE IF NOT EXISTS lnet_ping_result (
+    id serial PRIMARY KEY,
+    mesh_id INT NOT NULL REFERENCES lnet_ping_mesh (id) ON DELETE CASCADE,
+    source_host_id INT NOT NULL,
+    target_host_id INT NOT NULL,
+    target_nid TEXT NOT NULL,
+    sent INT NOT NULL,
+    received INT NOT NULL,
+    avg_latency_ms DOUBLE PRECISION NULL,
+    max_latency_ms DOUBLE PRECISION NULL,
+    error TEXT NULL,
+    UNIQUE (mesh_id, source_host_id, target_nid)
+);
+
+DROP TRIGGER IF EXISTS lnet_ping_mesh_invalidate ON lnet_ping_mesh;
+
+CREATE TRIGGER lnet_ping_mesh_invalidate
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON lnet_ping_mesh
+FOR EACH STATEMENT EXECUTE PROCEDURE table_invalidate();
+
+DROP TRIGGER IF EXISTS lnet_ping_result_invalidate ON lnet_ping_result;
+
+CREATE TRIGGER lnet_ping_result_invalidate
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON lnet_ping_result
+FOR EACH STATEMENT EXECUTE PROCEDURE table_invalidate();
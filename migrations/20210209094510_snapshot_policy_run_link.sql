@@ -0,0 +1,18 @@
+-- The policy run that took a snapshot, `NULL` for snapshots taken manually.
+ALTER TABLE IF EXISTS snapshot
+  ADD COLUMN IF NOT EXISTS policy_run_id INT REFERENCES snapshot_policy_run (id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS snapshot_policy_run_id_idx ON snapshot (policy_run_id);
+
+-- Link the snapshots already taken by a run, the latest one when a name was used twice.
+UPDATE snapshot s
+SET policy_run_id = (
+  SELECT r.id FROM snapshot_policy_run r
+  WHERE r.filesystem_name = s.filesystem_name
+  AND r.snapshot_name = s.snapshot_name
+  AND r.action = 'create'
+  AND r.state = 'started'
+  ORDER BY r.started_at DESC
+  LIMIT 1
+)
+WHERE s.policy_run_id IS NULL;
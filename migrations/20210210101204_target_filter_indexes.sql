@@ -0,0 +1,5 @@
+-- Filtering on `filesystems` uses `@>` rather than `= ANY` so it can use this index
+CREATE INDEX IF NOT EXISTS target_filesystems_idx ON target USING GIN (filesystems);
+
+-- Listing targets without the unmounted ones, in name order
+CREATE INDEX IF NOT EXISTS target_mounted_name_idx ON target (name) WHERE state <> 'unmounted';
@@ -0,0 +1,17 @@
+-- Peer managers whose sites are shown in the federated overview
+CREATE TABLE IF NOT EXISTS federation_peer (
+    id serial PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    url TEXT NOT NULL,
+    -- The API key the peer is queried with, as `Authorization: Bearer <key>`
+    api_key TEXT NOT NULL,
+    -- Peers with a self-signed certificate can turn certificate verification off
+    verify_tls BOOLEAN NOT NULL DEFAULT 't',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+DROP TRIGGER IF EXISTS federation_peer_invalidate ON federation_peer;
+
+CREATE TRIGGER federation_peer_invalidate
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON federation_peer
+FOR EACH STATEMENT EXECUTE PROCEDURE table_invalidate();
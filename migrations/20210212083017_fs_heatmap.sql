@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS fs_heatmap (
+    id serial PRIMARY KEY,
+    task_id INT NOT NULL,
+    fs_name TEXT NOT NULL,
+    path TEXT NOT NULL,
+    depth INT NOT NULL,
+    files BIGINT NOT NULL,
+    bytes BIGINT NOT NULL,
+    recent_files BIGINT NOT NULL,
+    bytes_week BIGINT NOT NULL,
+    bytes_month BIGINT NOT NULL,
+    bytes_quarter BIGINT NOT NULL,
+    bytes_year BIGINT NOT NULL,
+    bytes_older BIGINT NOT NULL,
+    scanned_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    UNIQUE (task_id, path)
+);
+
+CREATE INDEX IF NOT EXISTS fs_heatmap_fs_name_scanned_at_idx ON fs_heatmap (fs_name, scanned_at);
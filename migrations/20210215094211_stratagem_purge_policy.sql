@@ -0,0 +1,37 @@
+CREATE TABLE IF NOT EXISTS stratagem_purge_policy (
+    id serial PRIMARY KEY,
+    filesystem_name TEXT NOT NULL UNIQUE,
+    enabled BOOLEAN NOT NULL DEFAULT 't',
+    interval INTERVAL NOT NULL,
+    purge_after INTERVAL NOT NULL,
+    max_files BIGINT NOT NULL,
+    max_bytes BIGINT NOT NULL,
+    protected_paths TEXT[] NOT NULL DEFAULT '{}',
+    require_approval BOOLEAN NOT NULL DEFAULT 't',
+    last_run TIMESTAMP WITH TIME ZONE
+);
+
+CREATE TABLE IF NOT EXISTS stratagem_purge_run (
+    id serial PRIMARY KEY,
+    policy_id INT NOT NULL REFERENCES stratagem_purge_policy (id) ON DELETE CASCADE,
+    filesystem_name TEXT NOT NULL,
+    state TEXT NOT NULL DEFAULT 'scanning',
+    scan_task_id INT,
+    scan_command_id INT,
+    purge_task_id INT,
+    candidate_files BIGINT NOT NULL DEFAULT 0,
+    candidate_bytes BIGINT NOT NULL DEFAULT 0,
+    protected_files BIGINT NOT NULL DEFAULT 0,
+    files BIGINT NOT NULL DEFAULT 0,
+    bytes BIGINT NOT NULL DEFAULT 0,
+    capped BOOLEAN NOT NULL DEFAULT 'f',
+    fids TEXT[] NOT NULL DEFAULT '{}',
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    scanned_at TIMESTAMP WITH TIME ZONE,
+    decided_by TEXT,
+    decided_at TIMESTAMP WITH TIME ZONE,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS stratagem_purge_run_filesystem_name_idx ON stratagem_purge_run (filesystem_name, id);
+CREATE INDEX IF NOT EXISTS stratagem_purge_run_state_idx ON stratagem_purge_run (state);
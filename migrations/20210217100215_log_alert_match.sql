@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS log_alert_match (
+    id serial PRIMARY KEY,
+    rule_id INT NOT NULL REFERENCES chroma_core_logalertrule (id) ON DELETE CASCADE,
+    fqdn TEXT NOT NULL,
+    message TEXT NOT NULL,
+    matched_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS log_alert_match_rule_id_idx ON log_alert_match (rule_id, matched_at);
@@ -0,0 +1,50 @@
+-- Intervals with a start time run at that wall-clock time in their time zone, then every interval
+-- after it. start_at is the local time of the first run, so DST changes don't shift later runs.
+ALTER TABLE IF EXISTS snapshot_interval ADD COLUMN IF NOT EXISTS start_at TIMESTAMP WITHOUT TIME ZONE;
+ALTER TABLE IF EXISTS snapshot_interval ADD COLUMN IF NOT EXISTS time_zone TEXT NOT NULL DEFAULT 'UTC';
+
+-- The latest run of a scheduled interval due at or before `at`, NULL before its first run.
+CREATE OR REPLACE FUNCTION snapshot_interval_last_due(start_at TIMESTAMP, time_zone TEXT, step INTERVAL, at TIMESTAMPTZ) RETURNS TIMESTAMPTZ
+  AS $$
+    SELECT CASE
+      WHEN start_at IS NULL OR (at AT TIME ZONE time_zone) < start_at THEN NULL
+      ELSE (start_at + floor(extract(epoch FROM (at AT TIME ZONE time_zone) - start_at) / extract(epoch FROM step)) * step) AT TIME ZONE time_zone
+    END
+$$ LANGUAGE sql STABLE;
+
+-- The first run of a scheduled interval due after `at`.
+CREATE OR REPLACE FUNCTION snapshot_interval_next_run(start_at TIMESTAMP, time_zone TEXT, step INTERVAL, at TIMESTAMPTZ) RETURNS TIMESTAMPTZ
+  AS $$
+    SELECT CASE
+      WHEN start_at IS NULL THEN NULL
+      WHEN (at AT TIME ZONE time_zone) < start_at THEN start_at AT TIME ZONE time_zone
+      ELSE (start_at + (floor(extract(epoch FROM (at AT TIME ZONE time_zone) - start_at) / extract(epoch FROM step)) + 1) * step) AT TIME ZONE time_zone
+    END
+$$ LANGUAGE sql STABLE;
+
+-- The start_at of a new interval starting at start_time: its first run after now.
+CREATE OR REPLACE FUNCTION snapshot_interval_start_at(start_time TIME, time_zone TEXT, step INTERVAL) RETURNS TIMESTAMP
+  AS $$
+    SELECT snapshot_interval_next_run((now() AT TIME ZONE time_zone)::date + start_time, time_zone, step, now()) AT TIME ZONE time_zone
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION table_update_notify_snapshot_interval() RETURNS TRIGGER
+  AS $$
+    BEGIN
+      IF TG_OP = 'INSERT' THEN PERFORM pg_notify(
+        'table_update',
+        notify_row(TG_OP, TG_TABLE_NAME, json_build_object('id', NEW.id, 'filesystem_name', NEW.filesystem_name, 'use_barrier', NEW.use_barrier, 'last_run', NEW.last_run, 'interval', interval_to_seconds(NEW.interval), 'automount', NEW.automount, 'start_time', to_char(NEW.start_at, 'HH24:MI'), 'time_zone', NEW.time_zone, 'next_run', snapshot_interval_next_run(NEW.start_at, NEW.time_zone, NEW.interval, now())))
+      );
+      ELSEIF TG_OP = 'UPDATE' AND OLD IS DISTINCT FROM NEW THEN PERFORM pg_notify(
+        'table_update',
+        notify_row(TG_OP, TG_TABLE_NAME, json_build_object('id', NEW.id, 'filesystem_name', NEW.filesystem_name, 'use_barrier', NEW.use_barrier, 'last_run', NEW.last_run, 'interval', interval_to_seconds(NEW.interval), 'automount', NEW.automount, 'start_time', to_char(NEW.start_at, 'HH24:MI'), 'time_zone', NEW.time_zone, 'next_run', snapshot_interval_next_run(NEW.start_at, NEW.time_zone, NEW.interval, now())))
+      );
+      ELSE PERFORM pg_notify(
+        'table_update',
+        notify_row(TG_OP, TG_TABLE_NAME, json_build_object('id', OLD.id, 'filesystem_name', OLD.filesystem_name, 'use_barrier', OLD.use_barrier, 'last_run', OLD.last_run, 'interval', interval_to_seconds(OLD.interval), 'automount', OLD.automount, 'start_time', to_char(OLD.start_at, 'HH24:MI'), 'time_zone', OLD.time_zone, 'next_run', NULL))
+      );
+      END IF;
+
+      RETURN NEW;
+    END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,80 @@
+-- What is left of destroyed snapshots, to tell who destroyed one and when.
+-- Entries are kept for 90 days.
+CREATE TABLE IF NOT EXISTS snapshot_history (
+  id serial PRIMARY KEY,
+  filesystem_name TEXT NOT NULL,
+  snapshot_name TEXT NOT NULL,
+  snapshot_fsname TEXT NOT NULL,
+  create_time TIMESTAMP WITH TIME ZONE NOT NULL,
+  comment TEXT NULL,
+  -- The snapshot was taken by a policy run rather than manually.
+  taken_by_policy BOOLEAN NOT NULL,
+  -- When the manager saw the snapshot gone.
+  destroyed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  -- The user, and the command, of the `destroySnapshot` mutation. `NULL` when the snapshot
+  -- was destroyed outside the manager, or by a service without a user.
+  destroyed_by TEXT NULL,
+  command_id INT NULL,
+  -- The threshold of the retention policy that destroyed the snapshot, e.g. `reserve`.
+  reason TEXT NULL
+);
+
+CREATE INDEX IF NOT EXISTS snapshot_history_filesystem_name_destroyed_at_idx ON snapshot_history (filesystem_name, destroyed_at);
+
+CREATE OR REPLACE FUNCTION snapshot_history_record() RETURNS TRIGGER
+  AS $$
+    DECLARE
+      user_name TEXT;
+      cmd_id INT;
+      run_reason TEXT;
+    BEGIN
+      SELECT username, command_id INTO user_name, cmd_id
+      FROM api_audit
+      WHERE mutation = 'destroySnapshot'
+      AND args->>'fsname' = OLD.filesystem_name
+      AND args->>'name' = OLD.snapshot_name
+      AND created_at >= OLD.create_time
+      ORDER BY id DESC
+      LIMIT 1;
+
+      IF cmd_id IS NOT NULL THEN
+        SELECT reason INTO run_reason
+        FROM snapshot_policy_run
+        WHERE action = 'destroy' AND command_id = cmd_id
+        LIMIT 1;
+      END IF;
+
+      INSERT INTO snapshot_history
+        (filesystem_name, snapshot_name, snapshot_fsname, create_time, comment, taken_by_policy, destroyed_by, command_id, reason)
+      VALUES
+        (OLD.filesystem_name, OLD.snapshot_name, OLD.snapshot_fsname, OLD.create_time, OLD.comment, OLD.policy_run_id IS NOT NULL, user_name, cmd_id, run_reason);
+
+      DELETE FROM snapshot_history WHERE destroyed_at < now() - INTERVAL '90 days';
+
+      RETURN OLD;
+    END;
+$$ LANGUAGE plpgsql;
+
+-- A snapshot an agent stops listing for a while comes back with the same creation time,
+-- it was not destroyed after all.
+CREATE OR REPLACE FUNCTION snapshot_history_restore() RETURNS TRIGGER
+  AS $$
+    BEGIN
+      DELETE FROM snapshot_history
+      WHERE filesystem_name = NEW.filesystem_name
+      AND snapshot_name = NEW.snapshot_name
+      AND create_time = NEW.create_time;
+
+      RETURN NEW;
+    END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS snapshot_history_record ON snapshot;
+
+DROP TRIGGER IF EXISTS snapshot_history_restore ON snapshot;
+
+CREATE TRIGGER snapshot_history_record
+AFTER DELETE ON snapshot FOR EACH ROW EXECUTE PROCEDURE snapshot_history_record();
+
+CREATE TRIGGER snapshot_history_restore
+AFTER INSERT ON snapshot FOR EACH ROW EXECUTE PROCEDURE snapshot_history_restore();
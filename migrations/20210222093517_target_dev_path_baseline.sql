@@ -0,0 +1,16 @@
+-- The device path each target was last seen on, per host it was active on.
+-- A target whose device path changes on the same host has the change kept
+-- until it is acknowledged.
+CREATE TABLE IF NOT EXISTS target_dev_path_baseline (
+  target_name TEXT NOT NULL,
+  uuid TEXT NOT NULL,
+  host_id INT NOT NULL,
+  dev_path TEXT NOT NULL,
+  -- The device path before the first unacknowledged change.
+  previous_dev_path TEXT NULL,
+  changed_at TIMESTAMP WITH TIME ZONE NULL,
+  seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  PRIMARY KEY (target_name, uuid, host_id)
+);
+
+CREATE INDEX IF NOT EXISTS target_dev_path_baseline_uuid_idx ON target_dev_path_baseline (uuid);
@@ -0,0 +1,25 @@
+DO $$ BEGIN
+  CREATE TYPE banner_severity AS ENUM ('info', 'warning', 'error');
+EXCEPTION
+  WHEN duplicate_object THEN NULL;
+END $$;
+
+-- Messages shown at the top of every page of the GUI, e.g. to announce planned maintenance
+CREATE TABLE IF NOT EXISTS banner (
+    id serial PRIMARY KEY,
+    severity banner_severity NOT NULL,
+    -- Markdown
+    text TEXT NOT NULL,
+    -- The banner is shown until then, or until it is removed when `NULL`
+    expires_at TIMESTAMP WITH TIME ZONE NULL,
+    user_id INT REFERENCES auth_user (id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS banner_expires_at_idx ON banner (expires_at);
+
+DROP TRIGGER IF EXISTS banner_invalidate ON banner;
+
+CREATE TRIGGER banner_invalidate
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON banner
+FOR EACH STATEMENT EXECUTE PROCEDURE table_invalidate();
@@ -0,0 +1,32 @@
+DO $$ BEGIN
+  CREATE TYPE approval_state AS ENUM ('pending', 'approved', 'rejected');
+EXCEPTION
+  WHEN duplicate_object THEN NULL;
+END $$;
+
+-- Job classes whose commands are only dispatched once a second user approves them
+CREATE TABLE IF NOT EXISTS approval_rule (
+    class_name TEXT PRIMARY KEY,
+    user_id INT REFERENCES auth_user (id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+-- Commands held back by an approval rule, along with the `run_jobs` call that dispatches them
+CREATE TABLE IF NOT EXISTS command_approval (
+    id serial PRIMARY KEY,
+    message TEXT NOT NULL,
+    jobs JSONB NOT NULL,
+    kwargs JSONB NOT NULL,
+    -- The job classes of the command that need approval
+    class_names TEXT[] NOT NULL,
+    state approval_state NOT NULL DEFAULT 'pending',
+    requested_by INT REFERENCES auth_user (id) ON DELETE SET NULL,
+    requested_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    decided_by INT REFERENCES auth_user (id) ON DELETE SET NULL,
+    decided_at TIMESTAMP WITH TIME ZONE NULL,
+    reason TEXT NULL,
+    -- The command dispatched once approved
+    command_id INT NULL
+);
+
+CREATE INDEX IF NOT EXISTS command_approval_state_idx ON command_approval (state);
@@ -0,0 +1,22 @@
+-- When a job was last tasked, which its timeout is measured from.
+-- The job scheduler does not know about it, it is kept up to date by a trigger.
+ALTER TABLE chroma_core_job ADD COLUMN IF NOT EXISTS tasked_at TIMESTAMP WITH TIME ZONE NULL;
+
+UPDATE chroma_core_job SET tasked_at = modified_at WHERE state = 'tasked' AND tasked_at IS NULL;
+
+CREATE OR REPLACE FUNCTION job_set_tasked_at() RETURNS TRIGGER AS $$
+BEGIN
+  IF NEW.state = 'tasked' AND OLD.state IS DISTINCT FROM 'tasked' THEN
+    NEW.tasked_at := now();
+  END IF;
+
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS job_set_tasked_at ON chroma_core_job;
+CREATE TRIGGER job_set_tasked_at BEFORE
+UPDATE ON chroma_core_job FOR EACH ROW EXECUTE PROCEDURE job_set_tasked_at();
+
+DROP INDEX IF EXISTS chroma_core_job_tasked_idx;
+CREATE INDEX IF NOT EXISTS chroma_core_job_tasked_at_idx ON chroma_core_job (tasked_at) WHERE state = 'tasked';
@@ -0,0 +1,31 @@
+-- The targets matching the filters of the `targets` query, shared by its list and count.
+-- A NULL filter matches every target. Being a single STABLE SQL query, the planner inlines it,
+-- so the indexes on `target` are still used.
+CREATE OR REPLACE FUNCTION filtered_targets(
+  dev_path TEXT,
+  fqdn TEXT,
+  serial TEXT,
+  fs_name TEXT,
+  exclude_unmounted BOOLEAN,
+  host_ids INT[]
+) RETURNS SETOF target
+  AS $$
+    SELECT t.*
+    FROM target t
+    LEFT OUTER JOIN chroma_core_managedhost h
+    ON h.id = t.active_host_id AND h.not_deleted = 't'
+    WHERE ($1 IS NULL OR t.dev_path = $1)
+      AND ($2 IS NULL OR h.fqdn = $2)
+      AND ($3 IS NULL OR t.dev_path IN (
+          SELECT dp.path
+          FROM chroma_core_device d
+          INNER JOIN chroma_core_managedhost dh
+          ON dh.fqdn = d.fqdn AND dh.not_deleted = 't',
+          LATERAL device_serial_paths(d.devices) dp
+          WHERE dh.id = ANY(t.host_ids) AND dp.serial = $3
+      ))
+      AND ($4 IS NULL OR t.filesystems @> ARRAY[$4])
+      AND (NOT $5 OR t.state <> 'unmounted')
+      AND ($6 IS NULL OR t.host_ids && $6)
+  $$ LANGUAGE SQL
+  STABLE;
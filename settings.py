@@ -46,11 +46,12 @@ IML_API_PORT = 8004
 
 IML_API_HOST = os.getenv("IML_API_HOST", PROXY_HOST)
 
-IML_API_PROXY_PASS = "http://{}:{}".format(IML_API_HOST, IML_API_PORT)
+# Point this at an nginx upstream to load balance over several iml-api replicas
+IML_API_PROXY_PASS = os.getenv("IML_API_PROXY_PASS", "http://{}:{}".format(IML_API_HOST, IML_API_PORT))
 
 WARP_DRIVE_PORT = 8890
 
-WARP_DRIVE_PROXY_PASS = "http://{}:{}".format(PROXY_HOST, WARP_DRIVE_PORT)
+WARP_DRIVE_PROXY_PASS = os.getenv("WARP_DRIVE_PROXY_PASS", "http://{}:{}".format(PROXY_HOST, WARP_DRIVE_PORT))
 
 MAILBOX_PORT = 8891
 
@@ -240,6 +241,7 @@ MIDDLEWARE = [
     "django.middleware.common.CommonMiddleware",
     "django.middleware.csrf.CsrfViewMiddleware",
     "django.contrib.auth.middleware.AuthenticationMiddleware",
+    "chroma_api.middleware.SessionIdleTimeoutMiddleware",
     "django.middleware.clickjacking.XFrameOptionsMiddleware",
 ]
 
@@ -297,6 +299,8 @@ LOG_LEVEL = int(os.getenv("LOG_LEVEL", logging.INFO))
 EMAIL_HOST = None
 EMAIL_SUBJECT_PREFIX = "[Chroma Server]"
 EMAIL_SENDER = "noreply@%s" % socket.getfqdn()
+#: Local hour of the day the daily digest is sent at
+DIGEST_HOUR = int(os.getenv("DIGEST_HOUR", 6))
 
 _plugins_path = os.path.join(os.path.dirname(sys.modules["settings"].__file__), "chroma_core", "plugins")
 sys.path.append(_plugins_path)
@@ -344,6 +348,58 @@ LONG_POLL_TIMEOUT_SECONDS = 60 * 5
 # Realtime service
 SESSION_COOKIE_HTTPONLY = False
 
+# Log out sessions after this many seconds without user activity.
+# Set to 0 to disable
+SESSION_IDLE_TIMEOUT = int(os.getenv("SESSION_IDLE_TIMEOUT", 30 * 60))
+
+# Raise an alert when a filesystem is projected to fill up within this many days.
+# Set to 0 to disable
+CAPACITY_ALERT_DAYS = int(os.getenv("CAPACITY_ALERT_DAYS", 14))
+
+# How long certificates issued to agents are valid for
+AGENT_CERTIFICATE_DAYS = int(os.getenv("AGENT_CERTIFICATE_DAYS", 36500))
+
+# Raise an alert when an agent certificate expires within this many days.
+# Set to 0 to disable
+CERTIFICATE_ALERT_DAYS = int(os.getenv("CERTIFICATE_ALERT_DAYS", 30))
+
+# Raise an alert when an agent misses this many heartbeats in a row.
+# Set to 0 to disable
+HEARTBEAT_ALERT_MISSED = int(os.getenv("HEARTBEAT_ALERT_MISSED", 3))
+
+# Test the stonith devices of every HA cluster this many hours apart.
+# Set to 0 to disable
+STONITH_TEST_INTERVAL_HOURS = int(os.getenv("STONITH_TEST_INTERVAL_HOURS", 24))
+
+# Raise an alert when a standby manager replays more than this many MiB behind
+# the primary database, is disconnected or has not synced its configuration in a day.
+# Set to 0 to disable
+STANDBY_ALERT_LAG_MB = int(os.getenv("STANDBY_ALERT_LAG_MB", 64))
+
+# The most rows a paged GraphQL query such as targets returns at once
+GRAPHQL_MAX_PAGE_SIZE = int(os.getenv("GRAPHQL_MAX_PAGE_SIZE", 1000))
+
+# How many per-item queries or agent calls a GraphQL query runs at once,
+# e.g. when reading the corosync rings of every host. Keep it below the database pool size of iml-api (5 by default)
+GRAPHQL_FAN_OUT_LIMIT = int(os.getenv("GRAPHQL_FAN_OUT_LIMIT", 4))
+
+# Past this many rows in a table, the totalCount of paged GraphQL queries over it is an estimate
+GRAPHQL_EXACT_COUNT_THRESHOLD = int(os.getenv("GRAPHQL_EXACT_COUNT_THRESHOLD", 100000))
+
+# How many hours queries submitted to run in the background are kept along with their results
+DEFERRED_QUERY_TTL_HOURS = int(os.getenv("DEFERRED_QUERY_TTL_HOURS", 24))
+
+# Refuse to take a snapshot when an MDT has less than this percentage of its space free.
+# Set to 0 to disable
+SNAPSHOT_MIN_MDT_FREE_PERCENT = int(os.getenv("SNAPSHOT_MIN_MDT_FREE_PERCENT", 5))
+
+# The OpenTelemetry collector iml-api exports spans to over OTLP, e.g. http://localhost:4317.
+# Leave empty to not export spans
+OTLP_ENDPOINT = os.getenv("OTLP_ENDPOINT", "")
+
+# The ratio of traces started by iml-api that are exported, between 0 and 1
+OTLP_SAMPLE_RATIO = float(os.getenv("OTLP_SAMPLE_RATIO", 1.0))
+
 # The value at which log entries in the database will be aged out to a
 # flat text file in /var/log/chroma/db_log
 DBLOG_HW = int(os.getenv("DBLOG_HW", 1200000))
@@ -360,6 +416,9 @@ SERVER_FQDN = os.getenv("SERVER_FQDN", socket.getfqdn())
 # address or port, override this
 SERVER_HTTP_URL = "https://%s:%s" % (SERVER_FQDN, HTTPS_FRONTEND_PORT)
 
+# The name this manager's site is shown under in the federated overview
+FEDERATION_SITE_NAME = os.getenv("FEDERATION_SITE_NAME", SERVER_FQDN)
+
 # Supported power control agents
 SUPPORTED_FENCE_AGENTS = ["fence_apc", "fence_apc_snmp", "fence_ipmilan", "fence_virsh", "fence_vbox"]
 
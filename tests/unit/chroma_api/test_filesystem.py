@@ -24,7 +24,7 @@ class TestFilesystemResource(ChromaApiTestCase):
 
         fs_uri = "/api/filesystem/%s/" % self.fs.id
         with mock.patch("chroma_core.models.Command.set_state", mock.Mock(return_value=None)):
-            self.api_set_state_partial(fs_uri, "stopped")
+            self.api_set_state_partial(fs_uri, "removed")
             Command.set_state.assert_called_once()
 
     def test_set_state_full(self):
@@ -34,5 +34,15 @@ class TestFilesystemResource(ChromaApiTestCase):
 
         fs_uri = "/api/filesystem/%s/" % self.fs.id
         with mock.patch("chroma_core.models.Command.set_state", mock.Mock(return_value=None)):
-            self.api_set_state_full(fs_uri, "stopped")
+            self.api_set_state_full(fs_uri, "removed")
             Command.set_state.assert_called_once()
+
+    def test_start_stop_rejected(self):
+        """Starting and stopping is left to the GraphQL mutations"""
+        (mgt, fs, mdt, ost) = create_simple_fs()
+
+        fs_uri = "/api/filesystem/%s/" % fs.id
+        with mock.patch("chroma_core.models.Command.set_state", mock.Mock(return_value=None)):
+            response = self.api_client.put(fs_uri, data={"state": "stopped"})
+            self.assertHttpBadRequest(response)
+            self.assertFalse(Command.set_state.called)
@@ -0,0 +1,62 @@
+import mock
+
+from tests.unit.chroma_api.chroma_api_test_case import ChromaApiTestCase
+
+
+@mock.patch("settings.SESSION_IDLE_TIMEOUT", 60)
+class TestSessionIdleTimeout(ChromaApiTestCase):
+    """Test that sessions are logged out after a period of inactivity"""
+
+    def get_session(self, t):
+        with mock.patch("chroma_api.middleware.now", return_value=t):
+            response = self.api_client.get("/api/session/")
+            self.assertHttpOK(response)
+
+            return self.deserialize(response)
+
+    def put_session(self, t):
+        with mock.patch("chroma_api.middleware.now", return_value=t):
+            response = self.api_client.put("/api/session/", data={})
+            self.assertHttpOK(response)
+
+            return self.deserialize(response)
+
+    def test_polling_does_not_extend_session(self):
+        self.assertEqual(self.get_session(1000)["idle_expires_in"], 60)
+
+        session = self.get_session(1030)
+        self.assertIsNotNone(session["user"])
+        self.assertEqual(session["idle_expires_in"], 30)
+
+        session = self.get_session(1061)
+        self.assertIsNone(session["user"])
+        self.assertIsNone(session["idle_expires_in"])
+
+    def test_put_keeps_session_alive(self):
+        self.get_session(1000)
+
+        self.assertEqual(self.put_session(1050)["idle_expires_in"], 60)
+
+        session = self.get_session(1100)
+        self.assertIsNotNone(session["user"])
+        self.assertEqual(session["idle_expires_in"], 10)
+
+    def test_timeout_disabled(self):
+        with mock.patch("settings.SESSION_IDLE_TIMEOUT", 0):
+            self.get_session(1000)
+            session = self.get_session(100000)
+
+        self.assertIsNotNone(session["user"])
+        self.assertIsNone(session["idle_expires_in"])
+
+
+class TestAuthUserHeader(ChromaApiTestCase):
+    """Test that the auth endpoint tells the proxy which user made the request"""
+
+    def test_user_id_header(self):
+        from django.contrib.auth.models import User
+
+        response = self.api_client.get("/api/auth/")
+        self.assertHttpOK(response)
+
+        self.assertEqual(response["X-User-Id"], str(User.objects.get(username=self.username).id))
@@ -0,0 +1,109 @@
+import json
+
+from chroma_core.models.jobs import Job
+from chroma_core.services.job_scheduler.job_scheduler import JobCollection, MAX_LOW_PRIORITY_JOBS
+from tests.unit.chroma_core.helpers import synthetic_host, load_default_profile
+from tests.unit.lib.iml_unit_test_case import IMLUnitTestCase
+
+
+class TestHostQueue(IMLUnitTestCase):
+    """Test that jobs in a host queue only become ready one at a time, in id order"""
+
+    def setUp(self):
+        super(TestHostQueue, self).setUp()
+
+        load_default_profile()
+
+        self.host = synthetic_host("myaddress")
+        self.job_collection = JobCollection()
+
+    def _add_job(self, queue_host=None, wait_for=[], priority=Job.PRIORITY_NORMAL):
+        job = Job.objects.create(
+            queue_host=queue_host,
+            wait_for_json=json.dumps([j.id for j in wait_for]),
+            locks_json="[]",
+            priority=priority,
+        )
+        self.job_collection.add(job)
+
+        return job
+
+    def test_queue_order(self):
+        first = self._add_job(self.host)
+        second = self._add_job(self.host)
+        unqueued = self._add_job()
+
+        self.assertEqual(set(self.job_collection.ready_jobs), set([first, unqueued]))
+
+        self.job_collection.update_many([first, unqueued], "tasked")
+        self.assertEqual(self.job_collection.ready_jobs, [])
+
+        self.job_collection.update(first, "complete")
+        self.assertEqual(self.job_collection.ready_jobs, [second])
+
+    def test_queue_head_waiting(self):
+        """The head of a queue which is waiting on another job holds up the rest of the queue"""
+        unqueued = self._add_job()
+        first = self._add_job(self.host, wait_for=[unqueued])
+        self._add_job(self.host)
+
+        self.assertEqual(self.job_collection.ready_jobs, [unqueued])
+        self.assertEqual(self.job_collection.queue_heads(), {self.host.id: first})
+
+        self.job_collection.update(unqueued, "complete")
+        self.assertEqual(self.job_collection.ready_jobs, [first])
+
+    def test_queue_priority(self):
+        """A higher priority job runs ahead of queued jobs, but does not interrupt a running one"""
+        first = self._add_job(self.host, priority=Job.PRIORITY_LOW)
+        second = self._add_job(self.host, priority=Job.PRIORITY_LOW)
+
+        self.assertEqual(self.job_collection.ready_jobs, [first])
+        self.job_collection.update(first, "tasked")
+
+        high = self._add_job(self.host, priority=Job.PRIORITY_HIGH)
+        self.assertEqual(self.job_collection.queue_heads(), {self.host.id: first})
+        self.assertEqual(self.job_collection.ready_jobs, [])
+
+        self.job_collection.update(first, "complete")
+        self.assertEqual(self.job_collection.ready_jobs, [high])
+
+        self.job_collection.update(high, "complete")
+        self.assertEqual(self.job_collection.ready_jobs, [second])
+
+
+class TestPriority(IMLUnitTestCase):
+    """Test that ready jobs start in priority order, with a limit on running low priority jobs"""
+
+    def setUp(self):
+        super(TestPriority, self).setUp()
+
+        self.job_collection = JobCollection()
+
+    def _add_job(self, priority):
+        job = Job.objects.create(wait_for_json="[]", locks_json="[]", priority=priority)
+        self.job_collection.add(job)
+
+        return job
+
+    def test_order(self):
+        low = self._add_job(Job.PRIORITY_LOW)
+        normal = self._add_job(Job.PRIORITY_NORMAL)
+        high = self._add_job(Job.PRIORITY_HIGH)
+
+        self.assertEqual(self.job_collection.ready_jobs, [high, normal, low])
+
+    def test_low_priority_limit(self):
+        running = [self._add_job(Job.PRIORITY_LOW) for _ in range(MAX_LOW_PRIORITY_JOBS - 1)]
+        self.job_collection.update_many(running, "tasked")
+
+        low = [self._add_job(Job.PRIORITY_LOW) for _ in range(2)]
+        normal = self._add_job(Job.PRIORITY_NORMAL)
+
+        self.assertEqual(self.job_collection.ready_jobs, [normal, low[0]])
+
+        self.job_collection.update(low[0], "tasked")
+        self.assertEqual(self.job_collection.ready_jobs, [normal])
+
+        self.job_collection.update(running[0], "complete")
+        self.assertEqual(self.job_collection.ready_jobs, [normal, low[1]])
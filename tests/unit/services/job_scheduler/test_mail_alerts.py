@@ -0,0 +1,12 @@
+from unittest import TestCase
+
+from chroma_core.services.job_scheduler.mail_alerts import format_escalation
+
+
+class TestMailAlerts(TestCase):
+    def test_format_escalation(self):
+        self.assertEqual(
+            format_escalation("critical", 30, [("2021-01-27 09:00", "Lost contact with host oss1")]),
+            "These alerts have been active and unacknowledged for more than 30 minutes (policy critical):\n"
+            "2021-01-27 09:00 Lost contact with host oss1",
+        )
@@ -0,0 +1,42 @@
+import datetime
+from unittest import TestCase
+
+from django.utils import timezone
+
+from chroma_core.services.job_scheduler.mail_digest import format_capacity, format_digest, group_sections, last_due
+
+
+class TestMailDigest(TestCase):
+    def test_last_due(self):
+        now = datetime.datetime(2021, 1, 11, 5, 30, tzinfo=timezone.utc)
+
+        self.assertEqual(last_due(now, 6), datetime.datetime(2021, 1, 10, 6, 0, tzinfo=timezone.utc))
+        self.assertEqual(last_due(now, 5), datetime.datetime(2021, 1, 11, 5, 0, tzinfo=timezone.utc))
+
+    def test_group_sections(self):
+        self.assertEqual(
+            group_sections({"filesystem_users", "superusers"}), ["commands", "alerts", "capacity", "snapshots"]
+        )
+        self.assertEqual(group_sections({"filesystem_users"}), ["alerts", "capacity"])
+        self.assertEqual(group_sections(set()), [])
+
+    def test_format_capacity(self):
+        forecast = {
+            "bytes_used": 512 * 1024 ** 3,
+            "bytes_total": 1024 ** 4,
+            "growth_bytes_per_day": 2 * 1024 ** 3,
+            "fill_date": "2021-09-01T00:00:00Z",
+        }
+
+        self.assertEqual(
+            format_capacity("fs1", forecast), "fs1: 512.0 GiB of 1.0 TiB used (50%), +2.0 GiB a day, full by 2021-09-01"
+        )
+        self.assertEqual(format_capacity("fs1", None), "fs1: no capacity stats")
+
+    def test_format_digest(self):
+        since = datetime.datetime(2021, 1, 10, 6, 0, tzinfo=timezone.utc)
+
+        self.assertEqual(
+            format_digest(since, [("New alerts", []), ("Capacity", ["fs1: no capacity stats"])]),
+            "Changes since 2021-01-10 06:00\n\nNew alerts\n  None\n\nCapacity\n  fs1: no capacity stats",
+        )